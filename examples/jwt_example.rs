@@ -19,6 +19,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         expiration: 3600, // 1 hour
         issuer: Some("magictunnel-example".to_string()),
         audience: Some("mcp-clients".to_string()),
+        jwks_url: None,
+        trusted_issuers: vec![],
+        audiences: vec![],
+        jwks_cache_seconds: 3600,
+        jwks_min_refresh_seconds: 30,
     };
 
     println!("📋 JWT Configuration:");
@@ -148,6 +153,11 @@ mod tests {
             expiration: 3600,
             issuer: Some("test-issuer".to_string()),
             audience: Some("test-audience".to_string()),
+            jwks_url: None,
+            trusted_issuers: vec![],
+            audiences: vec![],
+            jwks_cache_seconds: 3600,
+            jwks_min_refresh_seconds: 30,
         };
 
         assert_eq!(jwt_config.algorithm, "HS256");
@@ -0,0 +1,262 @@
+//! Batching, retries and backpressure shared by the HTTP audit exporters
+//!
+//! Events are queued, up to `buffer_size`, and sent in batches of up to
+//! `batch_size` once a batch is full or `flush_interval_ms` has passed since
+//! its first event. Failed batches are retried with exponential backoff; no
+//! new batch is sent while one is being retried, so the queue absorbs slow or
+//! unavailable destinations and events arriving when it is full are dropped
+//! and counted.
+
+use crate::audit::{AuditEvent, StorageBackend};
+use crate::error::{ProxyError, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+fn default_batch_size() -> usize {
+    500
+}
+
+fn default_flush_interval_ms() -> u64 {
+    1000
+}
+
+fn default_max_retries() -> u32 {
+    5
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    500
+}
+
+fn default_buffer_size() -> usize {
+    10_000
+}
+
+/// How events are batched and retried
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchConfig {
+    /// Most events sent in one request
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// Longest an event waits for its batch to fill
+    #[serde(default = "default_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+    /// Retries of a failed batch before its events are dropped
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for each further one
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    /// Events queued while batches are sent or retried
+    #[serde(default = "default_buffer_size")]
+    pub buffer_size: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: default_batch_size(),
+            flush_interval_ms: default_flush_interval_ms(),
+            max_retries: default_max_retries(),
+            retry_backoff_ms: default_retry_backoff_ms(),
+            buffer_size: default_buffer_size(),
+        }
+    }
+}
+
+impl BatchConfig {
+    pub fn validate(&self, section: &str) -> Result<()> {
+        if self.batch_size == 0 || self.buffer_size == 0 || self.flush_interval_ms == 0 {
+            return Err(ProxyError::config(format!(
+                "{}.batch_size, buffer_size and flush_interval_ms must be at least 1", section
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// What became of a batch
+#[derive(Debug)]
+pub(crate) enum SendOutcome {
+    Sent,
+    /// These events failed in a way worth retrying
+    Retry(Vec<AuditEvent>, String),
+    /// The batch was rejected and retrying won't help
+    Failed(String),
+}
+
+/// Destination of batches
+#[async_trait]
+pub(crate) trait BatchSink: Send + Sync + 'static {
+    /// Name of the destination, for logs
+    fn name(&self) -> &str;
+
+    async fn send(&self, batch: Vec<AuditEvent>) -> SendOutcome;
+}
+
+/// Storage backend queueing events for a task sending them in batches
+pub struct BatchingBackend {
+    sender: mpsc::Sender<AuditEvent>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl BatchingBackend {
+    /// Start sending to `sink`; must be called within a Tokio runtime
+    pub(crate) fn start(config: BatchConfig, sink: impl BatchSink) -> Self {
+        let (sender, receiver) = mpsc::channel(config.buffer_size);
+        let dropped = Arc::new(AtomicU64::new(0));
+        tokio::spawn(run(config, sink, receiver, dropped.clone()));
+        Self { sender, dropped }
+    }
+
+    /// Events dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl StorageBackend for BatchingBackend {
+    fn store(&self, event: &AuditEvent) {
+        if self.sender.try_send(event.clone()).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+async fn run(config: BatchConfig, sink: impl BatchSink, mut receiver: mpsc::Receiver<AuditEvent>, dropped: Arc<AtomicU64>) {
+    let flush_interval = Duration::from_millis(config.flush_interval_ms);
+    let mut reported_dropped = 0;
+    while let Some(first) = receiver.recv().await {
+        let mut batch = vec![first];
+        let deadline = tokio::time::Instant::now() + flush_interval;
+        while batch.len() < config.batch_size {
+            match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(event)) => batch.push(event),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let mut attempt = 0;
+        loop {
+            match sink.send(batch).await {
+                SendOutcome::Sent => break,
+                SendOutcome::Retry(failed, error) if attempt < config.max_retries => {
+                    let backoff = Duration::from_millis(config.retry_backoff_ms.saturating_mul(1 << attempt.min(16)));
+                    warn!("Failed to send {} audit events to {}, retrying in {:?}: {}", failed.len(), sink.name(), backoff, error);
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                    batch = failed;
+                }
+                SendOutcome::Retry(failed, error) => {
+                    warn!("Dropped {} audit events after {} retries to {}: {}", failed.len(), attempt, sink.name(), error);
+                    break;
+                }
+                SendOutcome::Failed(error) => {
+                    warn!("{} rejected audit events: {}", sink.name(), error);
+                    break;
+                }
+            }
+        }
+
+        let total_dropped = dropped.load(Ordering::Relaxed);
+        if total_dropped > reported_dropped {
+            warn!("Dropped {} audit events for {} while its queue was full", total_dropped - reported_dropped, sink.name());
+            reported_dropped = total_dropped;
+        }
+    }
+}
+
+/// Whether a request failing with HTTP `status` may succeed later
+pub(crate) fn is_retriable(status: u16) -> bool {
+    status == 408 || status == 429 || status >= 500
+}
+
+/// Fill in the `{...}` placeholders of an index, sourcetype or similar template
+///
+/// `{date}` is the event's UTC date as `YYYY.MM.DD`, `{level}` its level, and
+/// any other name the event field of that name (`unknown` when missing).
+pub fn render_template(template: &str, event: &AuditEvent) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else { break };
+        rendered.push_str(&rest[..start]);
+        match &rest[start + 1..end] {
+            "date" => rendered.push_str(&event.timestamp.format("%Y.%m.%d").to_string()),
+            "level" => rendered.push_str(&event.level.as_str().to_ascii_lowercase()),
+            name => rendered.push_str(event.fields.get(name).map_or("unknown", String::as_str)),
+        }
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+    use tracing::Level;
+
+    pub(crate) fn event(message: &str) -> AuditEvent {
+        AuditEvent {
+            timestamp: chrono::Utc.with_ymd_and_hms(2026, 10, 15, 9, 30, 0).unwrap(),
+            level: Level::INFO,
+            message: message.to_string(),
+            fields: BTreeMap::from([("event_type".to_string(), "secret_detected".to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let event = event("Secret redacted");
+        assert_eq!(render_template("audit-{event_type}-{date}", &event), "audit-secret_detected-2026.10.15");
+        assert_eq!(render_template("{level}/{tool}", &event), "info/unknown");
+        assert_eq!(render_template("plain {unclosed", &event), "plain {unclosed");
+    }
+
+    /// Sink failing its first `failures` batches
+    struct Flaky {
+        failures: Mutex<u32>,
+        sent: Arc<Mutex<Vec<Vec<String>>>>,
+    }
+
+    #[async_trait]
+    impl BatchSink for Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        async fn send(&self, batch: Vec<AuditEvent>) -> SendOutcome {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return SendOutcome::Retry(batch, "unavailable".to_string());
+            }
+            self.sent.lock().unwrap().push(batch.into_iter().map(|event| event.message).collect());
+            SendOutcome::Sent
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batches_are_retried() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let config = BatchConfig { batch_size: 2, flush_interval_ms: 20, retry_backoff_ms: 1, ..Default::default() };
+        let backend = BatchingBackend::start(config, Flaky { failures: Mutex::new(2), sent: sent.clone() });
+        for message in ["a", "b", "c"] {
+            backend.store(&event(message));
+        }
+        while sent.lock().unwrap().len() < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(*sent.lock().unwrap(), vec![vec!["a", "b"], vec!["c"]]);
+        assert_eq!(backend.dropped(), 0);
+    }
+}
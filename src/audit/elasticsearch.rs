@@ -0,0 +1,234 @@
+//! Export of audit events to Elasticsearch through its bulk API
+
+use crate::audit::batch::{is_retriable, render_template, BatchConfig, BatchSink, BatchingBackend, SendOutcome};
+use crate::audit::AuditEvent;
+use crate::config::secrets::resolve_secret;
+use crate::error::{ProxyError, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+
+fn default_index() -> String {
+    "magictunnel-audit-{date}".to_string()
+}
+
+/// Elasticsearch cluster receiving audit events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElasticsearchConfig {
+    /// Base URL of the cluster, e.g. `https://es.example.com:9200`
+    pub url: String,
+    /// Index or data stream template; names are lowercased
+    #[serde(default = "default_index")]
+    pub index: String,
+    /// API key, as sent in `Authorization: ApiKey`; may be a secret reference
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Password of `username`; may be a secret reference
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(flatten)]
+    pub batch: BatchConfig,
+}
+
+impl ElasticsearchConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            return Err(ProxyError::config(format!("audit.elasticsearch.url '{}' must be an http(s) URL", self.url)));
+        }
+        if self.index.trim().is_empty() {
+            return Err(ProxyError::config("audit.elasticsearch.index cannot be empty"));
+        }
+        if self.api_key.is_some() && self.username.is_some() {
+            return Err(ProxyError::config("audit.elasticsearch takes either api_key or username, not both"));
+        }
+        if self.username.is_some() != self.password.is_some() {
+            return Err(ProxyError::config("audit.elasticsearch.username and password must be set together"));
+        }
+        self.batch.validate("audit.elasticsearch")
+    }
+}
+
+/// Sends batches to the cluster's `_bulk` endpoint
+struct ElasticsearchSink {
+    config: ElasticsearchConfig,
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl ElasticsearchSink {
+    /// NDJSON `create` actions, which work for both indices and data streams
+    fn body(&self, batch: &[AuditEvent]) -> String {
+        let mut body = String::new();
+        for event in batch {
+            let action = json!({ "create": { "_index": render_template(&self.config.index, event).to_lowercase() } });
+            let document = json!({
+                "@timestamp": event.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                "message": event.message,
+                "log": { "level": event.level.as_str().to_ascii_lowercase() },
+                "magictunnel": event.fields,
+            });
+            body.push_str(&action.to_string());
+            body.push('\n');
+            body.push_str(&document.to_string());
+            body.push('\n');
+        }
+        body
+    }
+
+    async fn authorize(&self, request: reqwest::RequestBuilder) -> std::result::Result<reqwest::RequestBuilder, String> {
+        if let Some(api_key) = &self.config.api_key {
+            let api_key = resolve_secret(api_key).await.map_err(|e| format!("failed to resolve the API key: {}", e))?;
+            return Ok(request.header("Authorization", format!("ApiKey {}", api_key)));
+        }
+        if let (Some(username), Some(password)) = (&self.config.username, &self.config.password) {
+            let password = resolve_secret(password).await.map_err(|e| format!("failed to resolve the password: {}", e))?;
+            return Ok(request.basic_auth(username, Some(password)));
+        }
+        Ok(request)
+    }
+}
+
+/// Indices of the bulk items that failed but may succeed when retried, and
+/// the first error of those that won't
+fn failed_items(response: &Value) -> (Vec<usize>, Option<String>) {
+    let mut retriable = Vec::new();
+    let mut rejected = None;
+    let items = response.get("items").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+    for (index, item) in items.iter().enumerate() {
+        let Some(result) = item.as_object().and_then(|item| item.values().next()) else { continue };
+        let status = result.get("status").and_then(Value::as_u64).unwrap_or(0) as u16;
+        if (200..300).contains(&status) {
+            continue;
+        }
+        if is_retriable(status) {
+            retriable.push(index);
+        } else if rejected.is_none() {
+            rejected = Some(result.get("error").map(Value::to_string).unwrap_or_else(|| format!("status {}", status)));
+        }
+    }
+    (retriable, rejected)
+}
+
+#[async_trait]
+impl BatchSink for ElasticsearchSink {
+    fn name(&self) -> &str {
+        "Elasticsearch"
+    }
+
+    async fn send(&self, batch: Vec<AuditEvent>) -> SendOutcome {
+        let request = self.client.post(&self.endpoint)
+            .header("Content-Type", "application/x-ndjson")
+            .body(self.body(&batch));
+        let request = match self.authorize(request).await {
+            Ok(request) => request,
+            Err(error) => return SendOutcome::Retry(batch, error),
+        };
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => return SendOutcome::Retry(batch, e.to_string()),
+        };
+        let status = response.status();
+        if !status.is_success() {
+            let error = format!("HTTP {} {}", status, response.text().await.unwrap_or_default());
+            return if is_retriable(status.as_u16()) {
+                SendOutcome::Retry(batch, error)
+            } else {
+                SendOutcome::Failed(error)
+            };
+        }
+        let body: Value = match response.json().await {
+            Ok(body) => body,
+            Err(e) => return SendOutcome::Failed(format!("unreadable bulk response: {}", e)),
+        };
+        if !body.get("errors").and_then(Value::as_bool).unwrap_or(false) {
+            return SendOutcome::Sent;
+        }
+
+        let (retriable, rejected) = failed_items(&body);
+        if let Some(error) = rejected {
+            tracing::warn!("Elasticsearch rejected audit events: {}", error);
+        }
+        if retriable.is_empty() {
+            return SendOutcome::Sent;
+        }
+        let failed = batch.into_iter()
+            .enumerate()
+            .filter(|(index, _)| retriable.contains(index))
+            .map(|(_, event)| event)
+            .collect();
+        SendOutcome::Retry(failed, "bulk items were rejected as busy or failing".to_string())
+    }
+}
+
+/// Start exporting to Elasticsearch; must be called within a Tokio runtime
+pub fn start(config: ElasticsearchConfig) -> Result<BatchingBackend> {
+    config.validate()?;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| ProxyError::config(format!("Failed to create the Elasticsearch client: {}", e)))?;
+    let sink = ElasticsearchSink {
+        endpoint: format!("{}/_bulk", config.url.trim_end_matches('/')),
+        client,
+        config: config.clone(),
+    };
+    tracing::info!("Exporting audit events to Elasticsearch at {}", config.url);
+    Ok(BatchingBackend::start(config.batch, sink))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::batch::tests::event;
+
+    fn config(yaml: &str) -> ElasticsearchConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_body() {
+        let sink = ElasticsearchSink {
+            config: config("url: http://localhost:9200\nindex: \"Audit-{event_type}-{date}\""),
+            endpoint: String::new(),
+            client: reqwest::Client::new(),
+        };
+        let body = sink.body(&[event("a")]);
+        let lines: Vec<Value> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["create"]["_index"], "audit-secret_detected-2026.10.15");
+        assert_eq!(lines[1]["@timestamp"], "2026-10-15T09:30:00.000Z");
+        assert_eq!(lines[1]["log"]["level"], "info");
+        assert_eq!(lines[1]["magictunnel"]["event_type"], "secret_detected");
+        assert!(body.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_failed_items() {
+        let response = json!({
+            "errors": true,
+            "items": [
+                { "create": { "status": 201 } },
+                { "create": { "status": 429, "error": { "type": "es_rejected_execution_exception" } } },
+                { "create": { "status": 400, "error": { "type": "mapper_parsing_exception" } } },
+                { "create": { "status": 503 } },
+            ]
+        });
+        let (retriable, rejected) = failed_items(&response);
+        assert_eq!(retriable, vec![1, 3]);
+        assert!(rejected.unwrap().contains("mapper_parsing_exception"));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(config("url: https://es:9200\napi_key: k").validate().is_ok());
+        assert!(config("url: es:9200").validate().is_err());
+        assert!(config("url: https://es:9200\nusername: u").validate().is_err());
+        assert!(config("url: https://es:9200\napi_key: k\nusername: u\npassword: p").validate().is_err());
+        assert!(config("url: https://es:9200\nbatch_size: 0").validate().is_err());
+    }
+}
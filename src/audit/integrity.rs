@@ -0,0 +1,402 @@
+//! Tamper-evident hash chaining of audit events
+//!
+//! With `audit.integrity` configured, every audit event gets these fields
+//! before it reaches the storage backends:
+//!
+//! - `chain_id`: random ID of the chain, one per server process
+//! - `chain_seq`: position of the event in its chain, from 1
+//! - `chain_prev`: `chain_hash` of the previous event
+//! - `chain_hash`: SHA-256 over `chain_prev` and the event itself
+//!
+//! Modifying an event breaks its hash, and removing one leaves a gap in the
+//! sequence. Since anyone can recompute plain hashes, the first event of a
+//! chain and every `anchor_every`-th one after it are marked `chain_anchor`,
+//! record the interval as `chain_anchor_every` and, given a `signing_key`,
+//! get a `chain_signature` (HMAC-SHA256 of their hash), which can't be forged
+//! without the key. [`verify`] checks a set of stored events, expecting an
+//! anchor wherever the signed interval puts one.
+
+use crate::audit::AuditEvent;
+use crate::config::secrets::resolve_secret;
+use crate::error::{ProxyError, Result};
+use chrono::{TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, RwLock};
+
+pub const CHAIN_ID: &str = "chain_id";
+pub const CHAIN_SEQ: &str = "chain_seq";
+pub const CHAIN_PREV: &str = "chain_prev";
+pub const CHAIN_HASH: &str = "chain_hash";
+pub const CHAIN_ANCHOR: &str = "chain_anchor";
+pub const CHAIN_ANCHOR_EVERY: &str = "chain_anchor_every";
+pub const CHAIN_SIGNATURE: &str = "chain_signature";
+
+/// `chain_prev` of the first event of a chain
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+fn default_anchor_every() -> u64 {
+    100
+}
+
+/// Hash chaining of audit events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityConfig {
+    /// Key signing anchor events; may be a secret reference
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_key: Option<String>,
+    /// Events between anchors
+    #[serde(default = "default_anchor_every")]
+    pub anchor_every: u64,
+}
+
+impl IntegrityConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.anchor_every == 0 {
+            return Err(ProxyError::config("audit.integrity.anchor_every must be at least 1"));
+        }
+        if self.signing_key.as_ref().is_some_and(|key| key.trim().is_empty()) {
+            return Err(ProxyError::config("audit.integrity.signing_key cannot be empty"));
+        }
+        Ok(())
+    }
+
+    /// Chain configured this way, with its signing key resolved
+    pub async fn chain(&self) -> Result<HashChain> {
+        let signing_key = match &self.signing_key {
+            Some(key) => Some(resolve_secret(key).await?.into_bytes()),
+            None => None,
+        };
+        Ok(HashChain::new(signing_key, self.anchor_every))
+    }
+}
+
+/// Chain linking each event to the previous one
+pub struct HashChain {
+    id: String,
+    sequence: u64,
+    last_hash: String,
+    signing_key: Option<Vec<u8>>,
+    anchor_every: u64,
+}
+
+impl HashChain {
+    pub fn new(signing_key: Option<Vec<u8>>, anchor_every: u64) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            sequence: 0,
+            last_hash: GENESIS.to_string(),
+            signing_key,
+            anchor_every: anchor_every.max(1),
+        }
+    }
+
+    /// Signing key, for verifying events of this chain
+    pub fn signing_key(&self) -> Option<&[u8]> {
+        self.signing_key.as_deref()
+    }
+
+    /// Events between anchors
+    pub fn anchor_every(&self) -> u64 {
+        self.anchor_every
+    }
+
+    /// Add the chain fields to `event`, the next event of the chain
+    pub fn link(&mut self, event: &mut AuditEvent) {
+        // Storage backends keep at least millisecond precision
+        if let Some(timestamp) = Utc.timestamp_millis_opt(event.timestamp.timestamp_millis()).single() {
+            event.timestamp = timestamp;
+        }
+        self.sequence += 1;
+        event.fields.insert(CHAIN_ID.to_string(), self.id.clone());
+        event.fields.insert(CHAIN_SEQ.to_string(), self.sequence.to_string());
+        event.fields.insert(CHAIN_PREV.to_string(), self.last_hash.clone());
+        let anchor = self.sequence == 1 || self.sequence % self.anchor_every == 0;
+        if anchor {
+            event.fields.insert(CHAIN_ANCHOR.to_string(), "true".to_string());
+            event.fields.insert(CHAIN_ANCHOR_EVERY.to_string(), self.anchor_every.to_string());
+        }
+        let hash = event_hash(event);
+        if let (true, Some(key)) = (anchor, &self.signing_key) {
+            event.fields.insert(CHAIN_SIGNATURE.to_string(), sign(key, &hash));
+        }
+        event.fields.insert(CHAIN_HASH.to_string(), hash.clone());
+        self.last_hash = hash;
+    }
+}
+
+/// SHA-256 of an event and all its fields but `chain_hash` and `chain_signature`
+fn event_hash(event: &AuditEvent) -> String {
+    let fields: BTreeMap<&String, &String> = event.fields.iter()
+        .filter(|(name, _)| *name != CHAIN_HASH && *name != CHAIN_SIGNATURE)
+        .collect();
+    let canonical = serde_json::json!([
+        event.timestamp.timestamp_millis(),
+        event.level.as_str(),
+        event.message,
+        fields,
+    ]);
+    hex::encode(Sha256::digest(canonical.to_string().as_bytes()))
+}
+
+fn sign(key: &[u8], hash: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(hash.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Signing key and anchor interval of the configured chain
+type Verification = (Option<Vec<u8>>, Option<u64>);
+
+fn verification() -> &'static RwLock<Verification> {
+    static VERIFICATION: OnceLock<RwLock<Verification>> = OnceLock::new();
+    VERIFICATION.get_or_init(RwLock::default)
+}
+
+/// Key [`verify_installed`] checks signatures with, and the anchor interval
+/// it expects of chains whose stored events don't record theirs
+pub fn set_verification_key(key: Option<Vec<u8>>, anchor_every: u64) {
+    if let Ok(mut installed) = verification().write() {
+        *installed = (key, Some(anchor_every));
+    }
+}
+
+/// Something wrong with stored events
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "issue", rename_all = "snake_case")]
+pub enum IntegrityIssue {
+    /// An event without the chain fields, or with unreadable ones
+    Unchained { timestamp: String, message: String },
+    /// Events `after` and `before` of a chain are stored, but none between them
+    Gap { chain_id: String, after: u64, before: u64 },
+    /// Two events have the same position in a chain
+    Duplicate { chain_id: String, sequence: u64 },
+    /// The event no longer matches its hash
+    Modified { chain_id: String, sequence: u64 },
+    /// The event doesn't follow the previous event of its chain
+    BrokenLink { chain_id: String, sequence: u64 },
+    /// The anchor's signature is missing or wrong
+    BadSignature { chain_id: String, sequence: u64 },
+}
+
+/// Events verified of a chain
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChainSummary {
+    pub chain_id: String,
+    pub first_sequence: u64,
+    pub last_sequence: u64,
+    pub events: usize,
+    /// Anchors whose signature was checked
+    pub signed_anchors: usize,
+}
+
+/// Outcome of [`verify`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VerificationReport {
+    pub events: usize,
+    pub chains: Vec<ChainSummary>,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl VerificationReport {
+    pub fn is_intact(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Check stored events for modifications and gaps
+///
+/// `events` may be in any order and cover only part of the chains, such as
+/// a time range: events missing before the first or after the last of a
+/// chain go unnoticed, except that the first event of a chain is always an
+/// anchor. Signatures are checked when `signing_key` is given.
+///
+/// Which events must be signed anchors doesn't depend on their own
+/// `chain_anchor` flag, which a forger could drop: it follows from the
+/// `chain_anchor_every` of a correctly signed anchor of the chain or, when
+/// none of the events has one, from `anchor_every`.
+pub fn verify(events: &[AuditEvent], signing_key: Option<&[u8]>, anchor_every: Option<u64>) -> VerificationReport {
+    let mut report = VerificationReport { events: events.len(), ..Default::default() };
+    let mut chains: BTreeMap<&str, Vec<(u64, &AuditEvent)>> = BTreeMap::new();
+    for event in events {
+        let chain_id = event.fields.get(CHAIN_ID);
+        let sequence = event.fields.get(CHAIN_SEQ).and_then(|sequence| sequence.parse().ok());
+        match (chain_id, sequence) {
+            (Some(chain_id), Some(sequence)) => chains.entry(chain_id).or_default().push((sequence, event)),
+            _ => report.issues.push(IntegrityIssue::Unchained {
+                timestamp: event.timestamp.to_rfc3339(),
+                message: event.message.clone(),
+            }),
+        }
+    }
+
+    for (chain_id, mut chain) in chains {
+        chain.sort_by_key(|(sequence, _)| *sequence);
+        let mut summary = ChainSummary {
+            chain_id: chain_id.to_string(),
+            first_sequence: chain[0].0,
+            last_sequence: chain.last().map_or(0, |(sequence, _)| *sequence),
+            events: chain.len(),
+            signed_anchors: 0,
+        };
+        let id = || chain_id.to_string();
+        let interval = signing_key
+            .and_then(|key| chain.iter().find_map(|&(_, event)| signed_anchor_every(event, key)))
+            .or(anchor_every.filter(|every| *every > 0));
+
+        let mut previous: Option<(u64, &AuditEvent)> = None;
+        for &(sequence, event) in &chain {
+            let hash = event.fields.get(CHAIN_HASH);
+            if hash != Some(&event_hash(event)) {
+                report.issues.push(IntegrityIssue::Modified { chain_id: id(), sequence });
+            }
+            match previous {
+                Some((previous_sequence, _)) if previous_sequence == sequence => {
+                    report.issues.push(IntegrityIssue::Duplicate { chain_id: id(), sequence });
+                }
+                Some((previous_sequence, _)) if previous_sequence + 1 < sequence => {
+                    report.issues.push(IntegrityIssue::Gap { chain_id: id(), after: previous_sequence, before: sequence });
+                }
+                Some((_, previous_event)) if event.fields.get(CHAIN_PREV) != previous_event.fields.get(CHAIN_HASH) => {
+                    report.issues.push(IntegrityIssue::BrokenLink { chain_id: id(), sequence });
+                }
+                None if sequence == 1 && event.fields.get(CHAIN_PREV).map(String::as_str) != Some(GENESIS) => {
+                    report.issues.push(IntegrityIssue::BrokenLink { chain_id: id(), sequence });
+                }
+                _ => {}
+            }
+
+            let anchor = sequence == 1 || match interval {
+                Some(every) => sequence % every == 0,
+                None => event.fields.get(CHAIN_ANCHOR).is_some_and(|anchor| anchor == "true"),
+            };
+            if let (true, Some(key)) = (anchor, signing_key) {
+                if hash.is_some_and(|hash| event.fields.get(CHAIN_SIGNATURE) == Some(&sign(key, hash))) {
+                    summary.signed_anchors += 1;
+                } else {
+                    report.issues.push(IntegrityIssue::BadSignature { chain_id: id(), sequence });
+                }
+            }
+            previous = Some((sequence, event));
+        }
+        report.chains.push(summary);
+    }
+    report
+}
+
+/// Anchor interval recorded in `event`, if it is an unmodified anchor signed with `key`
+fn signed_anchor_every(event: &AuditEvent, key: &[u8]) -> Option<u64> {
+    let hash = event.fields.get(CHAIN_HASH).filter(|hash| **hash == event_hash(event))?;
+    if event.fields.get(CHAIN_SIGNATURE) != Some(&sign(key, hash)) {
+        return None;
+    }
+    event.fields.get(CHAIN_ANCHOR_EVERY)?.parse().ok().filter(|every| *every > 0)
+}
+
+/// Verify events found with the installed query backend, checking
+/// signatures with the key of the configured chain
+pub async fn verify_installed(query: &crate::audit::AuditQuery) -> Result<VerificationReport> {
+    let events = crate::audit::query(query).await?;
+    let (key, anchor_every) = verification().read().map(|installed| installed.clone()).unwrap_or_default();
+    Ok(verify(&events, key.as_deref(), anchor_every))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::batch::tests::event;
+
+    fn chain(count: usize, key: Option<&[u8]>) -> Vec<AuditEvent> {
+        let mut chain = HashChain::new(key.map(<[u8]>::to_vec), 3);
+        (0..count)
+            .map(|i| {
+                let mut event = event(&format!("event {}", i));
+                chain.link(&mut event);
+                event
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_intact_chain_verifies() {
+        let mut events = chain(7, Some(b"key"));
+        events.reverse();
+        let report = verify(&events, Some(b"key"), None);
+        assert!(report.is_intact(), "{:?}", report.issues);
+        assert_eq!(report.chains.len(), 1);
+        assert_eq!((report.chains[0].first_sequence, report.chains[0].last_sequence), (1, 7));
+        // Events 1, 3 and 6
+        assert_eq!(report.chains[0].signed_anchors, 3);
+    }
+
+    #[test]
+    fn test_modification_is_detected() {
+        let mut events = chain(4, None);
+        events[2].message = "nothing to see".to_string();
+        let report = verify(&events, None, None);
+        assert_eq!(report.issues, vec![IntegrityIssue::Modified { chain_id: events[2].fields[CHAIN_ID].clone(), sequence: 3 }]);
+    }
+
+    #[test]
+    fn test_gap_is_detected() {
+        let mut events = chain(5, None);
+        let chain_id = events[0].fields[CHAIN_ID].clone();
+        events.remove(2);
+        events.remove(2);
+        let report = verify(&events, None, None);
+        assert_eq!(report.issues, vec![IntegrityIssue::Gap { chain_id, after: 2, before: 5 }]);
+    }
+
+    #[test]
+    fn test_rehashed_forgery_fails_signature() {
+        let mut events = chain(3, Some(b"key"));
+        // Rewrite the first event and recompute every hash without the key
+        events[0].message = "forged".to_string();
+        let mut previous = GENESIS.to_string();
+        for event in events.iter_mut() {
+            event.fields.insert(CHAIN_PREV.to_string(), previous.clone());
+            let hash = event_hash(event);
+            event.fields.insert(CHAIN_HASH.to_string(), hash.clone());
+            previous = hash;
+        }
+        let report = verify(&events, Some(b"key"), None);
+        let chain_id = events[0].fields[CHAIN_ID].clone();
+        assert_eq!(report.issues, vec![
+            IntegrityIssue::BadSignature { chain_id: chain_id.clone(), sequence: 1 },
+            IntegrityIssue::BadSignature { chain_id, sequence: 3 },
+        ]);
+    }
+
+    #[test]
+    fn test_stripped_anchors_fail_signature() {
+        let mut events = chain(7, Some(b"key"));
+        // Rewrite event 4, drop the anchor marks of the events after it and
+        // recompute their hashes without the key
+        events[3].message = "forged".to_string();
+        let mut previous = events[2].fields[CHAIN_HASH].clone();
+        for event in events.iter_mut().skip(3) {
+            for field in [CHAIN_ANCHOR, CHAIN_ANCHOR_EVERY, CHAIN_SIGNATURE] {
+                event.fields.remove(field);
+            }
+            event.fields.insert(CHAIN_PREV.to_string(), previous.clone());
+            let hash = event_hash(event);
+            event.fields.insert(CHAIN_HASH.to_string(), hash.clone());
+            previous = hash;
+        }
+        let chain_id = events[0].fields[CHAIN_ID].clone();
+        let report = verify(&events, Some(b"key"), None);
+        assert_eq!(report.issues, vec![IntegrityIssue::BadSignature { chain_id: chain_id.clone(), sequence: 6 }]);
+
+        // Without a signed anchor among the events, the configured interval applies
+        let report = verify(&events[3..], Some(b"key"), Some(3));
+        assert_eq!(report.issues, vec![IntegrityIssue::BadSignature { chain_id, sequence: 6 }]);
+    }
+
+    #[test]
+    fn test_unchained_events_are_reported() {
+        let report = verify(&[event("plain")], None, None);
+        assert!(matches!(report.issues[..], [IntegrityIssue::Unchained { .. }]));
+    }
+}
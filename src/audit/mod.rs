@@ -0,0 +1,516 @@
+//! Export of audit events
+//!
+//! Audit events are logged to the `audit` tracing target throughout the
+//! server. Besides the log, they can be shipped to storage backends such as a
+//! SIEM's syslog collector, Splunk or Elasticsearch, or archived to S3:
+//!
+//! ```yaml
+//! audit:
+//!   syslog:
+//!     address: "siem.example.com:6514"
+//!     protocol: tls          # udp (default), tcp or tls
+//!     format: cef            # rfc5424 (default) or cef
+//!   elasticsearch:
+//!     url: "https://es.example.com:9200"
+//!     index: "audit-{event_type}-{date}"
+//! ```
+//!
+//! [`layer`] captures the events; backends are installed once the
+//! configuration is loaded with [`configure`], or directly with [`install`].
+//! Stored events can be searched with an [`AuditQuery`] when a backend
+//! supporting queries, such as Postgres, is configured, and checked for
+//! tampering when [`integrity`] chaining is enabled.
+
+pub mod batch;
+pub mod elasticsearch;
+pub mod integrity;
+pub mod postgres;
+pub mod s3;
+pub mod splunk;
+pub mod syslog;
+
+use crate::error::{ProxyError, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Tracing target of audit events
+pub const AUDIT_TARGET: &str = "audit";
+
+/// Where audit events are exported to
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditConfig {
+    /// Syslog collector receiving the events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub syslog: Option<syslog::SyslogConfig>,
+    /// Splunk HTTP Event Collector receiving the events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub splunk: Option<splunk::SplunkConfig>,
+    /// Elasticsearch cluster receiving the events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elasticsearch: Option<elasticsearch::ElasticsearchConfig>,
+    /// S3-compatible bucket archiving the events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s3: Option<s3::S3Config>,
+    /// Postgres database storing the events for queries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub postgres: Option<postgres::PostgresConfig>,
+    /// Hash chaining making modified or removed events detectable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<integrity::IntegrityConfig>,
+}
+
+impl AuditConfig {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(syslog) = &self.syslog {
+            syslog.validate()?;
+        }
+        if let Some(splunk) = &self.splunk {
+            splunk.validate()?;
+        }
+        if let Some(elasticsearch) = &self.elasticsearch {
+            elasticsearch.validate()?;
+        }
+        if let Some(s3) = &self.s3 {
+            s3.validate()?;
+        }
+        if let Some(postgres) = &self.postgres {
+            postgres.validate()?;
+        }
+        if let Some(integrity) = &self.integrity {
+            integrity.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// An audit event, as handed to storage backends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub timestamp: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_level", deserialize_with = "deserialize_level")]
+    pub level: Level,
+    pub message: String,
+    /// Fields of the event, such as `tool`, `user` or `event_type`
+    pub fields: BTreeMap<String, String>,
+}
+
+fn serialize_level<S: serde::Serializer>(level: &Level, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(level.as_str())
+}
+
+fn deserialize_level<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Level, D::Error> {
+    String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+}
+
+/// Search of stored audit events; unset criteria match every event
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditQuery {
+    /// Free text matched against the message and field values; supports
+    /// `"quoted phrases"`, `or` and `-excluded` words
+    pub text: Option<String>,
+    /// The `user` (or `user_id`) field
+    pub user: Option<String>,
+    /// The `tool` field
+    pub tool: Option<String>,
+    /// The `outcome` (or `status`) field
+    pub outcome: Option<String>,
+    /// The `event_type` field
+    pub event_type: Option<String>,
+    /// Other fields with these exact values
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+    /// Events at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Events before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Most events returned, newest first (default: 100)
+    pub limit: Option<usize>,
+    /// Matching events skipped, for pagination
+    pub offset: Option<usize>,
+}
+
+impl AuditQuery {
+    /// Query from URL parameters: `q` for free text, `field.<name>` for other
+    /// fields, and the other criteria by name
+    pub fn from_params<'a>(params: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<Self> {
+        fn time(name: &str, value: &str) -> Result<DateTime<Utc>> {
+            DateTime::parse_from_rfc3339(value)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|e| ProxyError::validation(format!("{} '{}' is not an RFC 3339 time: {}", name, value, e)))
+        }
+        fn number(name: &str, value: &str) -> Result<usize> {
+            value.parse().map_err(|_| ProxyError::validation(format!("{} '{}' is not a number", name, value)))
+        }
+
+        let mut query = Self::default();
+        for (name, value) in params {
+            if value.is_empty() {
+                continue;
+            }
+            match name {
+                "q" | "text" => query.text = Some(value.to_string()),
+                "user" => query.user = Some(value.to_string()),
+                "tool" => query.tool = Some(value.to_string()),
+                "outcome" => query.outcome = Some(value.to_string()),
+                "event_type" => query.event_type = Some(value.to_string()),
+                "from" => query.from = Some(time(name, value)?),
+                "until" => query.until = Some(time(name, value)?),
+                "limit" => query.limit = Some(number(name, value)?),
+                "offset" => query.offset = Some(number(name, value)?),
+                _ => match name.strip_prefix("field.") {
+                    Some(field) => {
+                        query.fields.insert(field.to_string(), value.to_string());
+                    }
+                    None => return Err(ProxyError::validation(format!("Unknown audit search parameter '{}'", name))),
+                },
+            }
+        }
+        Ok(query)
+    }
+}
+
+/// First of the `names` fields of an event, without the `Some(..)` wrapping
+/// of optional values logged with `?`
+pub(crate) fn field(fields: &BTreeMap<String, String>, names: &[&str]) -> Option<String> {
+    let value = names.iter().find_map(|name| fields.get(*name))?;
+    match value.strip_prefix("Some(").and_then(|value| value.strip_suffix(')')) {
+        Some(inner) => Some(inner.trim_matches('"').to_string()),
+        None if value == "None" => None,
+        None => Some(value.clone()),
+    }
+}
+
+/// Events as CSV, with the fields searchable by name in their own columns
+/// and all of them as JSON in the last
+pub fn to_csv(events: &[AuditEvent]) -> String {
+    fn escape(value: &str) -> String {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+
+    let mut csv = String::from("timestamp,level,event_type,user,tool,outcome,message,fields\n");
+    for event in events {
+        let row = [
+            event.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            event.level.as_str().to_string(),
+            field(&event.fields, &["event_type"]).unwrap_or_default(),
+            field(&event.fields, &["user", "user_id"]).unwrap_or_default(),
+            field(&event.fields, &["tool"]).unwrap_or_default(),
+            field(&event.fields, &["outcome", "status"]).unwrap_or_default(),
+            event.message.clone(),
+            serde_json::to_string(&event.fields).unwrap_or_default(),
+        ];
+        csv.push_str(&row.iter().map(|value| escape(value)).collect::<Vec<_>>().join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Destination of audit events
+///
+/// `store` is called on the thread that logged the event, so backends must
+/// not block; those doing I/O hand events to a task of their own.
+pub trait StorageBackend: Send + Sync {
+    fn store(&self, event: &AuditEvent);
+}
+
+/// Storage backend that can search the events it stored
+#[async_trait]
+pub trait QueryBackend: Send + Sync {
+    /// The page of matching events `query` asks for
+    async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>>;
+
+    /// Number of events matching `query`, whatever its limit and offset
+    async fn count(&self, query: &AuditQuery) -> Result<u64>;
+}
+
+type Backends = Arc<RwLock<Vec<Arc<dyn StorageBackend>>>>;
+type Chain = Arc<Mutex<Option<integrity::HashChain>>>;
+
+fn query_backend() -> &'static RwLock<Option<Arc<dyn QueryBackend>>> {
+    static QUERY_BACKEND: OnceLock<RwLock<Option<Arc<dyn QueryBackend>>>> = OnceLock::new();
+    QUERY_BACKEND.get_or_init(RwLock::default)
+}
+
+fn global_backends() -> &'static Backends {
+    static BACKENDS: OnceLock<Backends> = OnceLock::new();
+    BACKENDS.get_or_init(Backends::default)
+}
+
+fn global_chain() -> &'static Chain {
+    static CHAIN: OnceLock<Chain> = OnceLock::new();
+    CHAIN.get_or_init(Chain::default)
+}
+
+/// Tracing layer handing audit events to storage backends
+#[derive(Clone)]
+pub struct AuditLayer {
+    backends: Backends,
+    chain: Chain,
+}
+
+impl AuditLayer {
+    /// Layer with its own backends, independent of those [`install`]ed
+    pub fn new() -> Self {
+        Self { backends: Backends::default(), chain: Chain::default() }
+    }
+
+    /// Add a backend receiving every audit event from now on
+    pub fn add_backend(&self, backend: Arc<dyn StorageBackend>) {
+        if let Ok(mut backends) = self.backends.write() {
+            backends.push(backend);
+        }
+    }
+
+    /// Link every audit event from now on into `chain`
+    pub fn set_chain(&self, chain: integrity::HashChain) {
+        if let Ok(mut installed) = self.chain.lock() {
+            *installed = Some(chain);
+        }
+    }
+}
+
+impl Default for AuditLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Layer handing audit events to the backends [`install`]ed in the process
+pub fn layer() -> AuditLayer {
+    AuditLayer { backends: global_backends().clone(), chain: global_chain().clone() }
+}
+
+/// Ship audit events captured by [`layer`] to `backend`
+pub fn install(backend: Arc<dyn StorageBackend>) {
+    if let Ok(mut backends) = global_backends().write() {
+        backends.push(backend);
+    }
+}
+
+/// Link audit events captured by [`layer`] into `chain`
+pub fn install_chain(chain: integrity::HashChain) {
+    integrity::set_verification_key(chain.signing_key().map(<[u8]>::to_vec), chain.anchor_every());
+    if let Ok(mut installed) = global_chain().lock() {
+        *installed = Some(chain);
+    }
+}
+
+/// Answer [`query`] from `backend`, replacing any previous one
+pub fn install_query_backend(backend: Arc<dyn QueryBackend>) {
+    if let Ok(mut installed) = query_backend().write() {
+        *installed = Some(backend);
+    }
+}
+
+fn installed_query_backend() -> Result<Arc<dyn QueryBackend>> {
+    query_backend().read().ok().and_then(|backend| backend.clone())
+        .ok_or_else(|| ProxyError::config("No audit backend supporting queries is configured"))
+}
+
+/// Search the stored audit events
+pub async fn query(query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+    installed_query_backend()?.query(query).await
+}
+
+/// Count the stored audit events matching `query`
+pub async fn count(query: &AuditQuery) -> Result<u64> {
+    installed_query_backend()?.count(query).await
+}
+
+/// Start the backends of `config`
+pub async fn configure(config: &AuditConfig) -> Result<()> {
+    if let Some(integrity) = &config.integrity {
+        install_chain(integrity.chain().await?);
+    }
+    if let Some(syslog) = &config.syslog {
+        install(Arc::new(syslog::SyslogBackend::start(syslog.clone())?));
+    }
+    if let Some(splunk) = &config.splunk {
+        install(Arc::new(splunk::start(splunk.clone())?));
+    }
+    if let Some(elasticsearch) = &config.elasticsearch {
+        install(Arc::new(elasticsearch::start(elasticsearch.clone())?));
+    }
+    if let Some(s3) = &config.s3 {
+        install(Arc::new(s3::start(s3.clone())?));
+    }
+    if let Some(postgres) = &config.postgres {
+        let store = postgres::PostgresStore::connect(postgres.clone()).await?;
+        install(Arc::new(store.start()));
+        install_query_backend(Arc::new(store));
+    }
+    Ok(())
+}
+
+impl<S: Subscriber> Layer<S> for AuditLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() != AUDIT_TARGET {
+            return;
+        }
+        let Ok(backends) = self.backends.read() else { return };
+        if backends.is_empty() {
+            return;
+        }
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let mut event = AuditEvent {
+            timestamp: Utc::now(),
+            level: *event.metadata().level(),
+            message: visitor.message,
+            fields: visitor.fields,
+        };
+        // Held while storing so backends receive events in chain order
+        let mut chain = self.chain.lock().ok();
+        if let Some(chain) = chain.as_mut().and_then(|chain| chain.as_mut()) {
+            chain.link(&mut event);
+        }
+        for backend in backends.iter() {
+            backend.store(&event);
+        }
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record(field, format!("{:?}", value));
+    }
+}
+
+impl FieldVisitor {
+    fn record(&mut self, field: &Field, value: String) {
+        if field.name() == "message" {
+            self.message = value;
+        } else {
+            self.fields.insert(field.name().to_string(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Default)]
+    struct Collect(Mutex<Vec<AuditEvent>>);
+
+    impl StorageBackend for Collect {
+        fn store(&self, event: &AuditEvent) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn test_layer_captures_audit_events_only() {
+        let collect = Arc::new(Collect::default());
+        let layer = AuditLayer::new();
+        layer.add_backend(collect.clone());
+
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            tracing::info!(target: "audit", tool = %"deploy", user = "alice", "Tool call waiting for approval");
+            tracing::info!("Not an audit event");
+        });
+
+        let events = collect.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].message, "Tool call waiting for approval");
+        assert_eq!(events[0].fields["tool"], "deploy");
+        assert_eq!(events[0].fields["user"], "alice");
+        assert_eq!(events[0].level, Level::INFO);
+    }
+
+    #[test]
+    fn test_layer_chains_events() {
+        let collect = Arc::new(Collect::default());
+        let layer = AuditLayer::new();
+        layer.add_backend(collect.clone());
+        layer.set_chain(integrity::HashChain::new(Some(b"key".to_vec()), 10));
+
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            tracing::info!(target: "audit", tool = %"deploy", "Tool call approved");
+            tracing::warn!(target: "audit", tool = %"deploy", "Quota exceeded");
+        });
+
+        let events = collect.0.lock().unwrap();
+        assert_eq!(events[1].fields[integrity::CHAIN_SEQ], "2");
+        assert_eq!(events[1].fields[integrity::CHAIN_PREV], events[0].fields[integrity::CHAIN_HASH]);
+        assert!(integrity::verify(&events, Some(b"key"), None).is_intact());
+    }
+
+    #[test]
+    fn test_query_from_params() {
+        let query = AuditQuery::from_params([
+            ("q", "\"rm -rf\" or drop"),
+            ("tool", "shell"),
+            ("field.stage", "arguments"),
+            ("from", "2026-10-01T00:00:00+02:00"),
+            ("offset", "200"),
+            ("until", ""),
+        ]).unwrap();
+        assert_eq!(query.text.as_deref(), Some("\"rm -rf\" or drop"));
+        assert_eq!(query.tool.as_deref(), Some("shell"));
+        assert_eq!(query.fields["stage"], "arguments");
+        assert_eq!(query.from.unwrap().to_rfc3339(), "2026-09-30T22:00:00+00:00");
+        assert_eq!((query.offset, query.until), (Some(200), None));
+
+        assert!(AuditQuery::from_params([("limit", "many")]).is_err());
+        assert!(AuditQuery::from_params([("from", "yesterday")]).is_err());
+        assert!(AuditQuery::from_params([("colour", "red")]).is_err());
+    }
+
+    #[test]
+    fn test_indexed_fields() {
+        let fields = BTreeMap::from([
+            ("user".to_string(), "Some(\"alice\")".to_string()),
+            ("api_key".to_string(), "None".to_string()),
+            ("tool".to_string(), "deploy".to_string()),
+            ("status".to_string(), "approved".to_string()),
+        ]);
+        assert_eq!(field(&fields, &["user", "user_id"]).as_deref(), Some("alice"));
+        assert_eq!(field(&fields, &["api_key"]), None);
+        assert_eq!(field(&fields, &["tool"]).as_deref(), Some("deploy"));
+        assert_eq!(field(&fields, &["outcome", "status"]).as_deref(), Some("approved"));
+        assert_eq!(field(&fields, &["event_type"]), None);
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let event = AuditEvent {
+            timestamp: DateTime::parse_from_rfc3339("2026-10-15T09:30:00Z").unwrap().with_timezone(&Utc),
+            level: Level::WARN,
+            message: "Secret redacted, \"AWS\" key".to_string(),
+            fields: BTreeMap::from([
+                ("tool".to_string(), "shell".to_string()),
+                ("user".to_string(), "Some(\"alice\")".to_string()),
+            ]),
+        };
+        let csv = to_csv(&[event]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "timestamp,level,event_type,user,tool,outcome,message,fields");
+        assert_eq!(
+            lines[1],
+            r#"2026-10-15T09:30:00.000Z,WARN,,alice,shell,,"Secret redacted, ""AWS"" key","{""tool"":""shell"",""user"":""Some(\""alice\"")""}""#
+        );
+    }
+}
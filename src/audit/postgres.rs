@@ -0,0 +1,395 @@
+//! Storage of audit events in Postgres, searchable with [`AuditQuery`]
+//!
+//! Events are inserted in batches into `magictunnel_audit_events`, with the
+//! user, tool, outcome and event type in indexed columns next to the full
+//! field set as JSONB, and a full-text index over the message and field
+//! values. The schema is created and upgraded by the migrations below when
+//! the store connects; applied versions are recorded in
+//! `magictunnel_audit_migrations`.
+
+use crate::audit::batch::{BatchConfig, BatchSink, BatchingBackend, SendOutcome};
+use crate::audit::{field, AuditEvent, AuditQuery, QueryBackend};
+use crate::config::secrets::resolve_secret;
+use crate::error::{ProxyError, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Client, NoTls};
+
+/// Schema migrations, applied in order; never edit one once released
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE magictunnel_audit_events (
+        id BIGSERIAL PRIMARY KEY,
+        timestamp TIMESTAMPTZ NOT NULL,
+        level TEXT NOT NULL,
+        message TEXT NOT NULL,
+        event_type TEXT,
+        user_id TEXT,
+        tool TEXT,
+        outcome TEXT,
+        fields JSONB NOT NULL
+    );
+    CREATE INDEX magictunnel_audit_events_timestamp ON magictunnel_audit_events (timestamp DESC);
+    CREATE INDEX magictunnel_audit_events_user ON magictunnel_audit_events (user_id, timestamp DESC);
+    CREATE INDEX magictunnel_audit_events_tool ON magictunnel_audit_events (tool, timestamp DESC);
+    CREATE INDEX magictunnel_audit_events_outcome ON magictunnel_audit_events (outcome, timestamp DESC);
+    CREATE INDEX magictunnel_audit_events_event_type ON magictunnel_audit_events (event_type, timestamp DESC);",
+    "ALTER TABLE magictunnel_audit_events ADD COLUMN search tsvector GENERATED ALWAYS AS (
+        to_tsvector('simple', message) || jsonb_to_tsvector('simple', fields, '[\"string\"]')
+    ) STORED;
+    CREATE INDEX magictunnel_audit_events_search ON magictunnel_audit_events USING GIN (search);
+    CREATE INDEX magictunnel_audit_events_fields ON magictunnel_audit_events USING GIN (fields jsonb_path_ops);",
+];
+
+/// Serializes migrations across replicas sharing the database
+const MIGRATION_LOCK: i64 = 0x6d61_6769_6361_7564;
+
+const DEFAULT_QUERY_LIMIT: usize = 100;
+const MAX_QUERY_LIMIT: usize = 10_000;
+
+fn default_max_connections() -> usize {
+    4
+}
+
+/// Postgres database storing audit events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostgresConfig {
+    /// Connection string, e.g. `postgres://audit@db/magictunnel`; may be a secret reference
+    pub url: String,
+    /// Most connections opened, shared by inserts and queries
+    #[serde(default = "default_max_connections")]
+    pub max_connections: usize,
+    #[serde(flatten)]
+    pub batch: BatchConfig,
+}
+
+impl PostgresConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.url.trim().is_empty() {
+            return Err(ProxyError::config("audit.postgres.url cannot be empty"));
+        }
+        if self.max_connections == 0 {
+            return Err(ProxyError::config("audit.postgres.max_connections must be at least 1"));
+        }
+        self.batch.validate("audit.postgres")
+    }
+}
+
+/// Connections to the audit database, opened on demand up to a maximum
+struct Pool {
+    url: String,
+    idle: Mutex<Vec<Client>>,
+    permits: Arc<Semaphore>,
+}
+
+impl Pool {
+    /// Idle connection, or a new one if none is left
+    async fn get(self: &Arc<Self>) -> Result<Pooled> {
+        let permit = self.permits.clone().acquire_owned().await
+            .map_err(|_| ProxyError::routing("Audit database pool is closed"))?;
+        let idle = self.idle.lock().ok().and_then(|mut idle| {
+            while let Some(client) = idle.pop() {
+                if !client.is_closed() {
+                    return Some(client);
+                }
+            }
+            None
+        });
+        let client = match idle {
+            Some(client) => client,
+            None => {
+                let url = resolve_secret(&self.url).await?;
+                let (client, connection) = tokio_postgres::connect(&url, NoTls).await
+                    .map_err(|e| ProxyError::routing(format!("Audit database connection failed: {}", e)))?;
+                tokio::spawn(async move {
+                    if let Err(e) = connection.await {
+                        tracing::warn!("Audit database connection error: {}", e);
+                    }
+                });
+                client
+            }
+        };
+        Ok(Pooled { client: Some(client), pool: self.clone(), _permit: permit })
+    }
+}
+
+/// Connection returned to the pool when dropped
+struct Pooled {
+    client: Option<Client>,
+    pool: Arc<Pool>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Deref for Pooled {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client.as_ref().expect("pooled connection is present until dropped")
+    }
+}
+
+impl DerefMut for Pooled {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.client.as_mut().expect("pooled connection is present until dropped")
+    }
+}
+
+impl Drop for Pooled {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take().filter(|client| !client.is_closed()) {
+            if let Ok(mut idle) = self.pool.idle.lock() {
+                idle.push(client);
+            }
+        }
+    }
+}
+
+/// Audit events in Postgres
+pub struct PostgresStore {
+    config: PostgresConfig,
+    pool: Arc<Pool>,
+}
+
+impl PostgresStore {
+    /// Connect to the database and bring its schema up to date
+    pub async fn connect(config: PostgresConfig) -> Result<Self> {
+        config.validate()?;
+        let pool = Arc::new(Pool {
+            url: config.url.clone(),
+            idle: Mutex::new(Vec::new()),
+            permits: Arc::new(Semaphore::new(config.max_connections)),
+        });
+        let store = Self { config, pool };
+        store.migrate().await?;
+        Ok(store)
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        let failed = |e: tokio_postgres::Error| ProxyError::config(format!("Audit database migration failed: {}", e));
+        let mut client = self.pool.get().await?;
+        client.batch_execute(
+            "CREATE TABLE IF NOT EXISTS magictunnel_audit_migrations (
+                version INTEGER PRIMARY KEY,
+                applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )",
+        ).await.map_err(failed)?;
+
+        let transaction = client.transaction().await.map_err(failed)?;
+        transaction.execute("SELECT pg_advisory_xact_lock($1)", &[&MIGRATION_LOCK]).await.map_err(failed)?;
+        let applied: i32 = transaction
+            .query_one("SELECT COALESCE(MAX(version), 0) FROM magictunnel_audit_migrations", &[])
+            .await.map_err(failed)?
+            .get(0);
+        for (version, migration) in (1..).zip(MIGRATIONS).skip(applied as usize) {
+            transaction.batch_execute(migration).await.map_err(failed)?;
+            transaction.execute("INSERT INTO magictunnel_audit_migrations (version) VALUES ($1)", &[&version])
+                .await.map_err(failed)?;
+            tracing::info!("Applied audit database migration {}", version);
+        }
+        transaction.commit().await.map_err(failed)
+    }
+
+    /// Start storing events, in batches, in the background; must be called
+    /// within a Tokio runtime
+    pub fn start(&self) -> BatchingBackend {
+        BatchingBackend::start(self.config.batch.clone(), PostgresSink { pool: self.pool.clone() })
+    }
+}
+
+#[async_trait]
+impl QueryBackend for PostgresStore {
+    async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        let (sql, params) = select(query);
+        let params: Vec<&(dyn ToSql + Sync)> = params.iter().map(|param| param.as_ref() as &(dyn ToSql + Sync)).collect();
+        let client = self.pool.get().await?;
+        let rows = client.query(&sql, &params).await
+            .map_err(|e| ProxyError::routing(format!("Audit query failed: {}", e)))?;
+        Ok(rows.iter()
+            .map(|row| AuditEvent {
+                timestamp: row.get("timestamp"),
+                level: row.get::<_, String>("level").parse().unwrap_or(tracing::Level::INFO),
+                message: row.get("message"),
+                fields: serde_json::from_value(row.get("fields")).unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    async fn count(&self, query: &AuditQuery) -> Result<u64> {
+        let (sql, params) = select_count(query);
+        let params: Vec<&(dyn ToSql + Sync)> = params.iter().map(|param| param.as_ref() as &(dyn ToSql + Sync)).collect();
+        let client = self.pool.get().await?;
+        let row = client.query_one(&sql, &params).await
+            .map_err(|e| ProxyError::routing(format!("Audit query failed: {}", e)))?;
+        Ok(row.get::<_, i64>(0) as u64)
+    }
+}
+
+type Params = Vec<Box<dyn ToSql + Sync + Send>>;
+
+/// `WHERE` clause of the events matching `query`, adding its parameters to `params`
+fn filter(query: &AuditQuery, params: &mut Params) -> String {
+    let mut conditions = Vec::new();
+    let columns = [
+        ("user_id", &query.user),
+        ("tool", &query.tool),
+        ("outcome", &query.outcome),
+        ("event_type", &query.event_type),
+    ];
+    for (column, value) in columns {
+        if let Some(value) = value {
+            params.push(Box::new(value.clone()));
+            conditions.push(format!("{} = ${}", column, params.len()));
+        }
+    }
+    if let Some(text) = &query.text {
+        params.push(Box::new(text.clone()));
+        conditions.push(format!("search @@ websearch_to_tsquery('simple', ${})", params.len()));
+    }
+    if !query.fields.is_empty() {
+        params.push(Box::new(serde_json::json!(query.fields)));
+        conditions.push(format!("fields @> ${}", params.len()));
+    }
+    if let Some(from) = query.from {
+        params.push(Box::new(from));
+        conditions.push(format!("timestamp >= ${}", params.len()));
+    }
+    if let Some(until) = query.until {
+        params.push(Box::new(until));
+        conditions.push(format!("timestamp < ${}", params.len()));
+    }
+    if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    }
+}
+
+/// SQL selecting the page of events `query` asks for, with its parameters
+fn select(query: &AuditQuery) -> (String, Params) {
+    let mut params = Params::new();
+    let mut sql = format!("SELECT timestamp, level, message, fields FROM magictunnel_audit_events{}", filter(query, &mut params));
+    let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT);
+    params.push(Box::new(limit as i64));
+    sql.push_str(&format!(" ORDER BY timestamp DESC LIMIT ${}", params.len()));
+    if let Some(offset) = query.offset.filter(|offset| *offset > 0) {
+        params.push(Box::new(offset as i64));
+        sql.push_str(&format!(" OFFSET ${}", params.len()));
+    }
+    (sql, params)
+}
+
+/// SQL counting the events matching `query`, with its parameters
+fn select_count(query: &AuditQuery) -> (String, Params) {
+    let mut params = Params::new();
+    let sql = format!("SELECT COUNT(*) FROM magictunnel_audit_events{}", filter(query, &mut params));
+    (sql, params)
+}
+
+/// Inserts batches of events with a single statement each
+struct PostgresSink {
+    pool: Arc<Pool>,
+}
+
+#[async_trait]
+impl BatchSink for PostgresSink {
+    fn name(&self) -> &str {
+        "Postgres audit store"
+    }
+
+    async fn send(&self, batch: Vec<AuditEvent>) -> SendOutcome {
+        let client = match self.pool.get().await {
+            Ok(client) => client,
+            Err(e) => return SendOutcome::Retry(batch, e.to_string()),
+        };
+        let timestamps: Vec<DateTime<Utc>> = batch.iter().map(|event| event.timestamp).collect();
+        let levels: Vec<&str> = batch.iter().map(|event| event.level.as_str()).collect();
+        let messages: Vec<&str> = batch.iter().map(|event| event.message.as_str()).collect();
+        let event_types: Vec<Option<String>> = batch.iter().map(|event| field(&event.fields, &["event_type"])).collect();
+        let users: Vec<Option<String>> = batch.iter().map(|event| field(&event.fields, &["user", "user_id"])).collect();
+        let tools: Vec<Option<String>> = batch.iter().map(|event| field(&event.fields, &["tool"])).collect();
+        let outcomes: Vec<Option<String>> = batch.iter().map(|event| field(&event.fields, &["outcome", "status"])).collect();
+        let fields: Vec<serde_json::Value> = batch.iter().map(|event| serde_json::json!(event.fields)).collect();
+
+        let inserted = client.execute(
+            "INSERT INTO magictunnel_audit_events (timestamp, level, message, event_type, user_id, tool, outcome, fields)
+             SELECT * FROM UNNEST($1::timestamptz[], $2::text[], $3::text[], $4::text[], $5::text[], $6::text[], $7::text[], $8::jsonb[])",
+            &[&timestamps, &levels, &messages, &event_types, &users, &tools, &outcomes, &fields],
+        ).await;
+        match inserted {
+            Ok(_) => SendOutcome::Sent,
+            // Errors reported by the database, such as a constraint
+            // violation, recur however often the batch is retried
+            Err(e) if e.as_db_error().is_some() => SendOutcome::Failed(e.to_string()),
+            Err(e) => SendOutcome::Retry(batch, e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_select_without_criteria() {
+        let (sql, params) = select(&AuditQuery::default());
+        assert_eq!(sql, "SELECT timestamp, level, message, fields FROM magictunnel_audit_events ORDER BY timestamp DESC LIMIT $1");
+        assert_eq!(params.len(), 1);
+    }
+
+    #[test]
+    fn test_select_with_criteria() {
+        let query = AuditQuery {
+            user: Some("alice".to_string()),
+            outcome: Some("denied".to_string()),
+            from: Some(Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap()),
+            limit: Some(1_000_000),
+            ..Default::default()
+        };
+        let (sql, params) = select(&query);
+        assert_eq!(
+            sql,
+            "SELECT timestamp, level, message, fields FROM magictunnel_audit_events \
+             WHERE user_id = $1 AND outcome = $2 AND timestamp >= $3 ORDER BY timestamp DESC LIMIT $4"
+        );
+        assert_eq!(params.len(), 4);
+    }
+
+    #[test]
+    fn test_search() {
+        let query = AuditQuery {
+            text: Some("secret -test".to_string()),
+            fields: BTreeMap::from([("stage".to_string(), "arguments".to_string())]),
+            limit: Some(50),
+            offset: Some(100),
+            ..Default::default()
+        };
+        let (sql, params) = select(&query);
+        assert_eq!(
+            sql,
+            "SELECT timestamp, level, message, fields FROM magictunnel_audit_events \
+             WHERE search @@ websearch_to_tsquery('simple', $1) AND fields @> $2 ORDER BY timestamp DESC LIMIT $3 OFFSET $4"
+        );
+        assert_eq!(params.len(), 4);
+
+        let (sql, params) = select_count(&query);
+        assert_eq!(
+            sql,
+            "SELECT COUNT(*) FROM magictunnel_audit_events WHERE search @@ websearch_to_tsquery('simple', $1) AND fields @> $2"
+        );
+        assert_eq!(params.len(), 2);
+    }
+
+    #[test]
+    fn test_validation() {
+        let config: PostgresConfig = serde_yaml::from_str("url: postgres://localhost/audit").unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.max_connections, 4);
+        assert!(PostgresConfig { max_connections: 0, ..config }.validate().is_err());
+    }
+}
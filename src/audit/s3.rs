@@ -0,0 +1,290 @@
+//! Archive of audit events in S3-compatible object storage
+//!
+//! Batches are written as gzipped JSON lines under keys partitioned by the
+//! hour of their events:
+//!
+//! ```text
+//! {prefix}/year=2026/month=10/day=15/hour=09/20261015T093000Z-{uuid}.jsonl.gz
+//! ```
+//!
+//! so lifecycle rules can expire or transition whole days or months by prefix,
+//! and query engines such as Athena can prune partitions.
+
+use crate::audit::batch::{is_retriable, BatchConfig, BatchSink, BatchingBackend, SendOutcome};
+use crate::audit::AuditEvent;
+use crate::error::{ProxyError, Result};
+use crate::routing::aws_sigv4::{sign_request, AwsSigV4Config};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT_SECS: u64 = 60;
+const RETRY_BACKOFF_MS: u64 = 1000;
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_prefix() -> String {
+    "magictunnel/audit".to_string()
+}
+
+fn default_batch_size() -> usize {
+    10_000
+}
+
+fn default_flush_interval_secs() -> u64 {
+    300
+}
+
+fn default_max_retries() -> u32 {
+    5
+}
+
+fn default_buffer_size() -> usize {
+    50_000
+}
+
+/// S3 bucket archiving audit events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct S3Config {
+    pub bucket: String,
+    #[serde(default = "default_region")]
+    pub region: String,
+    /// Endpoint of an S3-compatible store such as MinIO, addressed path-style
+    /// (default: AWS, addressed virtual-hosted-style)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// Key prefix of the archive
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    /// Storage class of the objects, e.g. `STANDARD_IA` (default: the bucket's)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_class: Option<String>,
+    /// Access key ID or secret reference (default: `AWS_ACCESS_KEY_ID`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_key_id: Option<String>,
+    /// Secret access key or secret reference (default: `AWS_SECRET_ACCESS_KEY`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_access_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_token: Option<String>,
+    /// Most events in one object
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// Longest an event waits before its object is written
+    #[serde(default = "default_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+    /// Retries of a failed upload before its events are dropped
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Events queued while objects are uploaded or retried
+    #[serde(default = "default_buffer_size")]
+    pub buffer_size: usize,
+}
+
+impl S3Config {
+    pub fn validate(&self) -> Result<()> {
+        if self.bucket.trim().is_empty() {
+            return Err(ProxyError::config("audit.s3.bucket cannot be empty"));
+        }
+        if let Some(endpoint) = &self.endpoint {
+            url::Url::parse(endpoint)
+                .map_err(|e| ProxyError::config(format!("audit.s3.endpoint '{}' is not a valid URL: {}", endpoint, e)))?;
+        }
+        self.batch().validate("audit.s3")
+    }
+
+    fn batch(&self) -> BatchConfig {
+        BatchConfig {
+            batch_size: self.batch_size,
+            flush_interval_ms: self.flush_interval_secs.saturating_mul(1000),
+            max_retries: self.max_retries,
+            retry_backoff_ms: RETRY_BACKOFF_MS,
+            buffer_size: self.buffer_size,
+        }
+    }
+
+    fn signing(&self) -> AwsSigV4Config {
+        let mut signing = AwsSigV4Config::new("s3", &self.region);
+        if let Some(access_key_id) = &self.access_key_id {
+            signing.access_key_id = access_key_id.clone();
+        }
+        if let Some(secret_access_key) = &self.secret_access_key {
+            signing.secret_access_key = secret_access_key.clone();
+        }
+        signing.session_token = self.session_token.clone();
+        signing
+    }
+
+    /// URL of the object at `key`
+    fn object_url(&self, key: &str) -> Result<url::Url> {
+        let key = key.split('/').map(|segment| urlencoding::encode(segment).into_owned()).collect::<Vec<_>>().join("/");
+        let url = match &self.endpoint {
+            Some(endpoint) => format!("{}/{}/{}", endpoint.trim_end_matches('/'), self.bucket, key),
+            None => format!("https://{}.s3.{}.amazonaws.com/{}", self.bucket, self.region, key),
+        };
+        url::Url::parse(&url).map_err(|e| ProxyError::config(format!("Invalid audit archive URL {}: {}", url, e)))
+    }
+}
+
+/// Hour partition of the archive an event belongs in
+fn partition(prefix: &str, timestamp: &DateTime<Utc>) -> String {
+    format!("{}/{}", prefix.trim_matches('/'), timestamp.format("year=%Y/month=%m/day=%d/hour=%H"))
+}
+
+/// Gzipped JSON lines of `events`
+fn compress(events: &[AuditEvent]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for event in events {
+        serde_json::to_writer(&mut encoder, event)?;
+        encoder.write_all(b"\n")?;
+    }
+    encoder.finish()
+}
+
+/// Uploads each hour of a batch as an object
+struct S3Sink {
+    config: S3Config,
+    signing: AwsSigV4Config,
+    client: reqwest::Client,
+}
+
+impl S3Sink {
+    /// Upload `events`, returning whether a failure is worth retrying
+    async fn put(&self, key: &str, events: &[AuditEvent]) -> std::result::Result<(), (bool, String)> {
+        let body = compress(events).map_err(|e| (false, format!("failed to compress: {}", e)))?;
+        let url = self.config.object_url(key).map_err(|e| (false, e.to_string()))?;
+        let credentials = self.signing.credentials().await.map_err(|e| (true, e.to_string()))?;
+
+        let mut headers = vec![
+            ("Content-Type".to_string(), "application/gzip".to_string()),
+            ("X-Amz-Content-Sha256".to_string(), hex::encode(Sha256::digest(&body))),
+        ];
+        if let Some(storage_class) = &self.config.storage_class {
+            headers.push(("X-Amz-Storage-Class".to_string(), storage_class.clone()));
+        }
+        let signature = sign_request("PUT", &url, &headers, &body, &self.signing, &credentials, Utc::now())
+            .map_err(|e| (false, e.to_string()))?;
+
+        let mut request = self.client.put(url);
+        for (name, value) in headers.into_iter().chain(signature) {
+            request = request.header(name, value);
+        }
+        let response = request.body(body).send().await.map_err(|e| (true, e.to_string()))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let text = response.text().await.unwrap_or_default();
+        Err((is_retriable(status.as_u16()), format!("HTTP {} {}", status, text)))
+    }
+}
+
+#[async_trait]
+impl BatchSink for S3Sink {
+    fn name(&self) -> &str {
+        "S3 audit archive"
+    }
+
+    async fn send(&self, batch: Vec<AuditEvent>) -> SendOutcome {
+        let mut partitions: BTreeMap<String, Vec<AuditEvent>> = BTreeMap::new();
+        for event in batch {
+            partitions.entry(partition(&self.config.prefix, &event.timestamp)).or_default().push(event);
+        }
+
+        let mut retry = Vec::new();
+        let mut last_error = None;
+        for (partition, events) in partitions {
+            let key = format!(
+                "{}/{}-{}.jsonl.gz",
+                partition,
+                events[0].timestamp.format("%Y%m%dT%H%M%SZ"),
+                uuid::Uuid::new_v4()
+            );
+            match self.put(&key, &events).await {
+                Ok(()) => {}
+                Err((true, error)) => {
+                    retry.extend(events);
+                    last_error = Some(error);
+                }
+                Err((false, error)) => {
+                    tracing::warn!("Failed to archive {} audit events to {}: {}", events.len(), key, error);
+                }
+            }
+        }
+        match last_error {
+            Some(error) if !retry.is_empty() => SendOutcome::Retry(retry, error),
+            _ => SendOutcome::Sent,
+        }
+    }
+}
+
+/// Start archiving to S3; must be called within a Tokio runtime
+pub fn start(config: S3Config) -> Result<BatchingBackend> {
+    config.validate()?;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| ProxyError::config(format!("Failed to create the audit archive client: {}", e)))?;
+    tracing::info!("Archiving audit events to bucket {} under {}", config.bucket, config.prefix);
+    let batch = config.batch();
+    Ok(BatchingBackend::start(batch, S3Sink { signing: config.signing(), client, config }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::batch::tests::event;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn config(yaml: &str) -> S3Config {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_key_layout() {
+        let event = event("a");
+        assert_eq!(partition("/archive/audit/", &event.timestamp), "archive/audit/year=2026/month=10/day=15/hour=09");
+    }
+
+    #[test]
+    fn test_object_urls() {
+        let aws = config("bucket: audit-archive\nregion: eu-west-1");
+        assert_eq!(
+            aws.object_url("magictunnel/audit/year=2026/a.jsonl.gz").unwrap().as_str(),
+            "https://audit-archive.s3.eu-west-1.amazonaws.com/magictunnel/audit/year%3D2026/a.jsonl.gz"
+        );
+        let minio = config("bucket: audit\nendpoint: http://minio:9000/");
+        assert_eq!(minio.object_url("p/a.jsonl.gz").unwrap().as_str(), "http://minio:9000/audit/p/a.jsonl.gz");
+    }
+
+    #[test]
+    fn test_compressed_json_lines() {
+        let compressed = compress(&[event("a"), event("b")]).unwrap();
+        let mut lines = String::new();
+        GzDecoder::new(compressed.as_slice()).read_to_string(&mut lines).unwrap();
+        let events: Vec<serde_json::Value> = lines.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1]["message"], "b");
+        assert_eq!(events[1]["level"], "INFO");
+        assert_eq!(events[1]["fields"]["event_type"], "secret_detected");
+    }
+
+    #[test]
+    fn test_validation() {
+        let archive = config("bucket: audit\nflush_interval_secs: 60");
+        assert!(archive.validate().is_ok());
+        assert_eq!(archive.batch().flush_interval_ms, 60_000);
+        assert!(config("bucket: ''").validate().is_err());
+        assert!(config("bucket: audit\nendpoint: 'not a url'").validate().is_err());
+        assert!(config("bucket: audit\nbatch_size: 0").validate().is_err());
+    }
+}
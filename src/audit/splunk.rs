@@ -0,0 +1,174 @@
+//! Export of audit events to Splunk's HTTP Event Collector
+
+use crate::audit::batch::{is_retriable, render_template, BatchConfig, BatchSink, BatchingBackend, SendOutcome};
+use crate::audit::AuditEvent;
+use crate::config::secrets::resolve_secret;
+use crate::error::{ProxyError, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+
+fn default_sourcetype() -> String {
+    "magictunnel:audit".to_string()
+}
+
+fn default_source() -> String {
+    "magictunnel".to_string()
+}
+
+/// Splunk HTTP Event Collector receiving audit events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplunkConfig {
+    /// Base URL of the collector, e.g. `https://splunk.example.com:8088`
+    pub url: String,
+    /// HEC token; may be a secret reference
+    pub token: String,
+    /// Index template, e.g. `audit_{event_type}` (default: the token's index)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<String>,
+    /// Sourcetype template
+    #[serde(default = "default_sourcetype")]
+    pub sourcetype: String,
+    #[serde(default = "default_source")]
+    pub source: String,
+    /// Host of the events (default: the `HOSTNAME` environment variable)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(flatten)]
+    pub batch: BatchConfig,
+}
+
+impl SplunkConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            return Err(ProxyError::config(format!("audit.splunk.url '{}' must be an http(s) URL", self.url)));
+        }
+        if self.token.trim().is_empty() {
+            return Err(ProxyError::config("audit.splunk.token cannot be empty"));
+        }
+        self.batch.validate("audit.splunk")
+    }
+}
+
+/// Sends batches to the collector's event endpoint
+struct SplunkSink {
+    config: SplunkConfig,
+    host: Option<String>,
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl SplunkSink {
+    /// Newline-separated HEC events
+    fn body(&self, batch: &[AuditEvent]) -> String {
+        batch.iter()
+            .map(|event| {
+                let mut hec = json!({
+                    "time": event.timestamp.timestamp_millis() as f64 / 1000.0,
+                    "source": self.config.source,
+                    "sourcetype": render_template(&self.config.sourcetype, event),
+                    "event": {
+                        "message": event.message,
+                        "level": event.level.as_str(),
+                        "fields": event.fields,
+                    },
+                });
+                if let Some(index) = &self.config.index {
+                    hec["index"] = json!(render_template(index, event));
+                }
+                if let Some(host) = &self.host {
+                    hec["host"] = json!(host);
+                }
+                hec.to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[async_trait]
+impl BatchSink for SplunkSink {
+    fn name(&self) -> &str {
+        "Splunk HEC"
+    }
+
+    async fn send(&self, batch: Vec<AuditEvent>) -> SendOutcome {
+        let token = match resolve_secret(&self.config.token).await {
+            Ok(token) => token,
+            Err(e) => return SendOutcome::Retry(batch, format!("failed to resolve the token: {}", e)),
+        };
+        let response = self.client.post(&self.endpoint)
+            .header("Authorization", format!("Splunk {}", token))
+            .body(self.body(&batch))
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status().is_success() => SendOutcome::Sent,
+            Ok(response) => {
+                let status = response.status();
+                let text = response.json::<Value>().await.ok()
+                    .and_then(|body| body.get("text").and_then(Value::as_str).map(String::from))
+                    .unwrap_or_default();
+                let error = format!("HTTP {} {}", status, text);
+                if is_retriable(status.as_u16()) {
+                    SendOutcome::Retry(batch, error)
+                } else {
+                    SendOutcome::Failed(error)
+                }
+            }
+            Err(e) => SendOutcome::Retry(batch, e.to_string()),
+        }
+    }
+}
+
+/// Start exporting to Splunk; must be called within a Tokio runtime
+pub fn start(config: SplunkConfig) -> Result<BatchingBackend> {
+    config.validate()?;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| ProxyError::config(format!("Failed to create the Splunk HEC client: {}", e)))?;
+    let sink = SplunkSink {
+        host: config.host.clone().or_else(|| std::env::var("HOSTNAME").ok()),
+        endpoint: format!("{}/services/collector/event", config.url.trim_end_matches('/')),
+        client,
+        config: config.clone(),
+    };
+    tracing::info!("Exporting audit events to Splunk HEC at {}", config.url);
+    Ok(BatchingBackend::start(config.batch, sink))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::batch::tests::event;
+
+    #[test]
+    fn test_body() {
+        let config: SplunkConfig = serde_yaml::from_str(r#"
+url: https://splunk.example.com:8088
+token: t
+index: "audit_{event_type}"
+batch_size: 100
+"#).unwrap();
+        assert_eq!(config.batch.batch_size, 100);
+        let sink = SplunkSink {
+            host: Some("tunnel-1".to_string()),
+            endpoint: String::new(),
+            client: reqwest::Client::new(),
+            config,
+        };
+        let body = sink.body(&[event("a"), event("b")]);
+        let events: Vec<Value> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["index"], "audit_secret_detected");
+        assert_eq!(events[0]["sourcetype"], "magictunnel:audit");
+        assert_eq!(events[0]["host"], "tunnel-1");
+        assert_eq!(events[0]["time"], 1792056600.0);
+        assert_eq!(events[1]["event"]["message"], "b");
+        assert_eq!(events[1]["event"]["fields"]["event_type"], "secret_detected");
+    }
+}
@@ -0,0 +1,446 @@
+//! Syslog export of audit events, for SIEMs ingesting syslog
+//!
+//! Events are sent as RFC 5424 messages with their fields as structured
+//! data, or as ArcSight CEF records in an RFC 5424 envelope, over UDP, TCP
+//! or TLS. TCP and TLS use octet-counted framing (RFC 6587).
+//!
+//! Events are queued in memory, up to `buffer_size`, while the collector is
+//! unreachable, and the connection is retried with exponential backoff.
+//! Events arriving when the queue is full are dropped and counted.
+
+use crate::audit::AuditEvent;
+use crate::error::{ProxyError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tracing::{info, warn, Level};
+
+/// Private enterprise number reserved for documentation (RFC 5612), naming
+/// the structured data element of events
+const SD_ID: &str = "magictunnel@32473";
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+fn default_facility() -> u8 {
+    // log audit
+    13
+}
+
+fn default_app_name() -> String {
+    "magictunnel".to_string()
+}
+
+fn default_buffer_size() -> usize {
+    10_000
+}
+
+/// Transport to the syslog collector
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyslogProtocol {
+    #[default]
+    Udp,
+    Tcp,
+    Tls,
+}
+
+/// Format of the messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyslogFormat {
+    /// RFC 5424 with the event's fields as structured data
+    #[default]
+    Rfc5424,
+    /// ArcSight Common Event Format
+    Cef,
+}
+
+/// Syslog collector receiving audit events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyslogConfig {
+    /// `host:port` of the collector
+    pub address: String,
+    #[serde(default)]
+    pub protocol: SyslogProtocol,
+    #[serde(default)]
+    pub format: SyslogFormat,
+    /// Syslog facility (default: 13, log audit)
+    #[serde(default = "default_facility")]
+    pub facility: u8,
+    /// APP-NAME of the messages
+    #[serde(default = "default_app_name")]
+    pub app_name: String,
+    /// HOSTNAME of the messages (default: the `HOSTNAME` environment variable)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// CA certificates the collector's certificate is checked against, for
+    /// `tls` (default: the Mozilla root certificates)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_file: Option<PathBuf>,
+    /// Events queued while the collector is unreachable
+    #[serde(default = "default_buffer_size")]
+    pub buffer_size: usize,
+}
+
+impl SyslogConfig {
+    pub fn validate(&self) -> Result<()> {
+        let port = self.address.rsplit_once(':').map(|(_, port)| port.parse::<u16>());
+        if !matches!(port, Some(Ok(_))) {
+            return Err(ProxyError::config(format!("audit.syslog.address '{}' must be host:port", self.address)));
+        }
+        if self.facility > 23 {
+            return Err(ProxyError::config("audit.syslog.facility must be between 0 and 23"));
+        }
+        if self.buffer_size == 0 {
+            return Err(ProxyError::config("audit.syslog.buffer_size must be at least 1"));
+        }
+        if self.ca_file.is_some() && self.protocol != SyslogProtocol::Tls {
+            return Err(ProxyError::config("audit.syslog.ca_file only applies to the tls protocol"));
+        }
+        Ok(())
+    }
+
+    fn host(&self) -> &str {
+        let host = self.address.rsplit_once(':').map_or(self.address.as_str(), |(host, _)| host);
+        host.trim_start_matches('[').trim_end_matches(']')
+    }
+}
+
+/// Formats audit events as syslog messages
+#[derive(Debug, Clone)]
+pub struct SyslogFormatter {
+    format: SyslogFormat,
+    facility: u8,
+    hostname: String,
+    app_name: String,
+    procid: String,
+}
+
+impl SyslogFormatter {
+    pub fn new(config: &SyslogConfig) -> Self {
+        let hostname = config.hostname.clone()
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .unwrap_or_else(|| "-".to_string());
+        Self {
+            format: config.format,
+            facility: config.facility,
+            hostname: header_field(&hostname, 255),
+            app_name: header_field(&config.app_name, 48),
+            procid: std::process::id().to_string(),
+        }
+    }
+
+    /// The event as one syslog message, without framing
+    pub fn format(&self, event: &AuditEvent) -> String {
+        let severity = match event.level {
+            Level::ERROR => 3,
+            Level::WARN => 4,
+            Level::INFO => 6,
+            _ => 7,
+        };
+        let msgid = header_field(event.fields.get("event_type").map_or("audit", String::as_str), 32);
+        let header = format!(
+            "<{}>1 {} {} {} {} {}",
+            self.facility as u16 * 8 + severity,
+            event.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            self.hostname,
+            self.app_name,
+            self.procid,
+            msgid
+        );
+        match self.format {
+            SyslogFormat::Rfc5424 => format!("{} {} {}", header, structured_data(event), event.message),
+            SyslogFormat::Cef => format!("{} - {}", header, cef(event, &msgid)),
+        }
+    }
+}
+
+/// Printable ASCII without spaces, as syslog header fields require
+fn header_field(value: &str, max_len: usize) -> String {
+    let field: String = value.chars()
+        .map(|c| if c.is_ascii_graphic() { c } else { '_' })
+        .take(max_len)
+        .collect();
+    if field.is_empty() { "-".to_string() } else { field }
+}
+
+/// The event's fields as one structured data element
+fn structured_data(event: &AuditEvent) -> String {
+    if event.fields.is_empty() {
+        return "-".to_string();
+    }
+    let params: String = event.fields.iter()
+        .map(|(name, value)| {
+            let name: String = name.chars()
+                .filter(|c| c.is_ascii_graphic() && !matches!(c, '=' | ']' | '"'))
+                .take(32)
+                .collect();
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"").replace(']', "\\]");
+            format!(" {}=\"{}\"", name, value)
+        })
+        .collect();
+    format!("[{}{}]", SD_ID, params)
+}
+
+/// The event as a CEF record
+fn cef(event: &AuditEvent, signature: &str) -> String {
+    let header = |value: &str| value.replace('\\', "\\\\").replace('|', "\\|");
+    let extension = |value: &str| value.replace('\\', "\\\\").replace('=', "\\=").replace('\r', "\\r").replace('\n', "\\n");
+    let severity = match event.level {
+        Level::ERROR => 8,
+        Level::WARN => 6,
+        _ => 3,
+    };
+    let mut extensions = vec![
+        format!("rt={}", event.timestamp.timestamp_millis()),
+        format!("msg={}", extension(&event.message)),
+    ];
+    for (name, value) in &event.fields {
+        // Standard keys where there is one, the field's own name otherwise
+        let key = match name.as_str() {
+            "user" => "suser".to_string(),
+            "client_ip" | "remote_addr" => "src".to_string(),
+            "tool" => "act".to_string(),
+            other => other.chars().filter(char::is_ascii_alphanumeric).collect(),
+        };
+        if !key.is_empty() {
+            extensions.push(format!("{}={}", key, extension(value)));
+        }
+    }
+    format!(
+        "CEF:0|MagicBeans|MagicTunnel|{}|{}|{}|{}|{}",
+        header(crate::VERSION),
+        header(signature),
+        header(&event.message),
+        severity,
+        extensions.join(" ")
+    )
+}
+
+/// Open connection to the collector
+enum Connection {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    Tls(Box<tokio_rustls::client::TlsStream<TcpStream>>),
+}
+
+impl Connection {
+    async fn open(config: &SyslogConfig, tls: Option<&tokio_rustls::TlsConnector>) -> std::io::Result<Self> {
+        match (config.protocol, tls) {
+            (SyslogProtocol::Udp, _) => {
+                let socket = UdpSocket::bind(if config.host().contains(':') { "[::]:0" } else { "0.0.0.0:0" }).await?;
+                socket.connect(&config.address).await?;
+                Ok(Connection::Udp(socket))
+            }
+            (SyslogProtocol::Tls, Some(connector)) => {
+                let stream = TcpStream::connect(&config.address).await?;
+                let server_name = rustls::ServerName::try_from(config.host())
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+                Ok(Connection::Tls(Box::new(connector.connect(server_name, stream).await?)))
+            }
+            _ => Ok(Connection::Tcp(TcpStream::connect(&config.address).await?)),
+        }
+    }
+
+    async fn send(&mut self, message: &str) -> std::io::Result<()> {
+        // Octet-counted framing over streams, one datagram per message over UDP
+        let framed = format!("{} {}", message.len(), message);
+        match self {
+            Connection::Udp(socket) => socket.send(message.as_bytes()).await.map(|_| ()),
+            Connection::Tcp(stream) => stream.write_all(framed.as_bytes()).await,
+            Connection::Tls(stream) => stream.write_all(framed.as_bytes()).await,
+        }
+    }
+}
+
+fn tls_connector(config: &SyslogConfig) -> Result<tokio_rustls::TlsConnector> {
+    let mut roots = rustls::RootCertStore::empty();
+    match &config.ca_file {
+        Some(ca_file) => {
+            let ca_file = File::open(ca_file)
+                .map_err(|e| ProxyError::config(format!("Failed to open audit.syslog.ca_file: {}", e)))?;
+            let ca_certs = rustls_pemfile::certs(&mut BufReader::new(ca_file))
+                .map_err(|e| ProxyError::config(format!("Failed to parse audit.syslog.ca_file: {}", e)))?;
+            for ca_cert in ca_certs {
+                roots.add(&rustls::Certificate(ca_cert))
+                    .map_err(|e| ProxyError::config(format!("Invalid CA certificate in audit.syslog.ca_file: {}", e)))?;
+            }
+        }
+        None => roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|anchor| {
+            rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+        })),
+    }
+    let client_config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(tokio_rustls::TlsConnector::from(Arc::new(client_config)))
+}
+
+/// Storage backend shipping audit events to a syslog collector
+pub struct SyslogBackend {
+    formatter: SyslogFormatter,
+    sender: mpsc::Sender<String>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl SyslogBackend {
+    /// Start sending to the collector; must be called within a Tokio runtime
+    pub fn start(config: SyslogConfig) -> Result<Self> {
+        config.validate()?;
+        let tls = match config.protocol {
+            SyslogProtocol::Tls => Some(tls_connector(&config)?),
+            SyslogProtocol::Udp | SyslogProtocol::Tcp => None,
+        };
+        let (sender, receiver) = mpsc::channel(config.buffer_size);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let formatter = SyslogFormatter::new(&config);
+        info!("Exporting audit events to syslog collector {} over {:?}", config.address, config.protocol);
+        tokio::spawn(run(config, tls, receiver, dropped.clone()));
+        Ok(Self { formatter, sender, dropped })
+    }
+
+    /// Events dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl super::StorageBackend for SyslogBackend {
+    fn store(&self, event: &AuditEvent) {
+        if self.sender.try_send(self.formatter.format(event)).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Send queued messages, reconnecting whenever the connection fails
+async fn run(config: SyslogConfig, tls: Option<tokio_rustls::TlsConnector>, mut receiver: mpsc::Receiver<String>, dropped: Arc<AtomicU64>) {
+    let mut connection: Option<Connection> = None;
+    let mut backoff = MIN_BACKOFF;
+    let mut unsent: Option<String> = None;
+    let mut reported_dropped = 0;
+    loop {
+        let message = match unsent.take() {
+            Some(message) => message,
+            None => match receiver.recv().await {
+                Some(message) => message,
+                None => return,
+            },
+        };
+        if connection.is_none() {
+            match Connection::open(&config, tls.as_ref()).await {
+                Ok(opened) => {
+                    backoff = MIN_BACKOFF;
+                    connection = Some(opened);
+                }
+                Err(e) => {
+                    warn!("Failed to connect to syslog collector {}, retrying in {:?}: {}", config.address, backoff, e);
+                    unsent = Some(message);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    continue;
+                }
+            }
+        }
+        let Some(open) = connection.as_mut() else { continue };
+        if let Err(e) = open.send(&message).await {
+            warn!("Lost connection to syslog collector {}: {}", config.address, e);
+            connection = None;
+            unsent = Some(message);
+        }
+        let total_dropped = dropped.load(Ordering::Relaxed);
+        if total_dropped > reported_dropped {
+            warn!("Dropped {} audit events while the syslog queue was full", total_dropped - reported_dropped);
+            reported_dropped = total_dropped;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::StorageBackend;
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+    use tokio::io::AsyncReadExt;
+
+    fn config(yaml: &str) -> SyslogConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn event() -> AuditEvent {
+        AuditEvent {
+            timestamp: chrono::Utc.with_ymd_and_hms(2026, 10, 15, 9, 30, 0).unwrap(),
+            level: Level::WARN,
+            message: "Quota exceeded".to_string(),
+            fields: BTreeMap::from([
+                ("event_type".to_string(), "quota_exceeded".to_string()),
+                ("key".to_string(), "ci=bot \"x\"]".to_string()),
+                ("user".to_string(), "alice".to_string()),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_rfc5424() {
+        let formatter = SyslogFormatter::new(&config("{ address: 'siem:514', hostname: 'tunnel-1' }"));
+        let message = formatter.format(&event());
+        let procid = std::process::id();
+        assert_eq!(message, format!(
+            "<108>1 2026-10-15T09:30:00.000Z tunnel-1 magictunnel {} quota_exceeded \
+             [magictunnel@32473 event_type=\"quota_exceeded\" key=\"ci=bot \\\"x\\\"\\]\" user=\"alice\"] Quota exceeded",
+            procid
+        ));
+    }
+
+    #[test]
+    fn test_cef() {
+        let formatter = SyslogFormatter::new(&config("{ address: 'siem:514', format: cef, hostname: 'tunnel-1' }"));
+        let message = formatter.format(&event());
+        let (_, cef) = message.split_once(" - ").unwrap();
+        assert_eq!(cef, format!(
+            "CEF:0|MagicBeans|MagicTunnel|{}|quota_exceeded|Quota exceeded|6|rt=1791970200000 msg=Quota exceeded \
+             eventtype=quota_exceeded key=ci\\=bot \"x\"] suser=alice",
+            crate::VERSION
+        ));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(config("{ address: 'siem:514' }").validate().is_ok());
+        assert!(config("{ address: 'siem' }").validate().is_err());
+        assert!(config("{ address: 'siem:514', facility: 24 }").validate().is_err());
+        assert!(config("{ address: 'siem:514', ca_file: ca.pem }").validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_events_queued_until_collector_is_reachable() {
+        // Nothing listens on the address until the event has been queued
+        let address = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let backend = SyslogBackend::start(config(&format!("{{ address: '{}', protocol: tcp }}", address))).unwrap();
+        backend.store(&event());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let listener = tokio::net::TcpListener::bind(address).await.unwrap();
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut received = String::new();
+        while !received.ends_with("Quota exceeded") {
+            let mut buffer = [0u8; 1024];
+            let read = stream.read(&mut buffer).await.unwrap();
+            assert!(read > 0);
+            received.push_str(std::str::from_utf8(&buffer[..read]).unwrap());
+        }
+        let (length, message) = received.split_once(' ').unwrap();
+        assert_eq!(length.parse::<usize>().unwrap(), message.len());
+        assert!(message.starts_with("<108>1 "));
+        assert_eq!(backend.dropped(), 0);
+    }
+}
@@ -1,20 +1,36 @@
 //! API Key authentication implementation
 
+use crate::auth::key_rotation::{ApiKeyStore, KeyAuditEvent, KeyLifecycle, KeyRotation, KeyRotationRequest};
 use crate::config::{AuthConfig, ApiKeyEntry, AuthType};
 use crate::error::{ProxyError, Result};
 use actix_web::HttpRequest;
+use std::sync::Arc;
 use tracing::{debug, warn};
 
 /// API Key authentication validator
 pub struct ApiKeyValidator {
     /// Authentication configuration
     config: AuthConfig,
+    /// Rotated secrets and key lifecycle events
+    store: Arc<ApiKeyStore>,
 }
 
 impl ApiKeyValidator {
-    /// Create a new API key validator
+    /// Create a new API key validator, keeping rotations in memory
     pub fn new(config: AuthConfig) -> Self {
-        Self { config }
+        let rotation = config.api_keys.as_ref().map(|api_keys| api_keys.rotation.clone()).unwrap_or_default();
+        Self { config, store: Arc::new(ApiKeyStore::in_memory(rotation)) }
+    }
+
+    /// Create a new API key validator whose rotations persist to `rotation.state_path`
+    pub fn open(config: AuthConfig) -> Result<Self> {
+        match config.api_keys {
+            Some(ref api_keys) if config.enabled && config.uses(&AuthType::ApiKey) => {
+                let store = ApiKeyStore::open(api_keys.rotation.clone())?;
+                Ok(Self { config, store: Arc::new(store) })
+            }
+            _ => Ok(Self::new(config)),
+        }
     }
 
     /// Check if authentication is enabled
@@ -31,7 +47,7 @@ impl ApiKeyValidator {
         }
 
         // Only validate API key auth type
-        if self.config.r#type != AuthType::ApiKey {
+        if !self.config.uses(&AuthType::ApiKey) {
             debug!("Non-API key auth type, skipping API key validation");
             return Ok(None);
         }
@@ -97,24 +113,42 @@ impl ApiKeyValidator {
         api_key: &str,
         api_key_config: &crate::config::ApiKeyConfig,
     ) -> Result<Option<ApiKeyEntry>> {
-        // Find the API key entry
-        let key_entry = api_key_config
-            .keys
-            .iter()
-            .find(|entry| entry.key == api_key)
+        // Find the API key entry, by its current secret or one still in its grace period
+        let key_entry = self.store
+            .resolve(api_key, &api_key_config.keys)
             .ok_or_else(|| {
                 warn!("Invalid API key attempted: {}", api_key);
                 ProxyError::auth("Invalid API key")
             })?;
 
         // Check if the key is valid (active and not expired)
+        self.store.check_expiry(&key_entry);
         if !key_entry.is_valid() {
             warn!("Expired or inactive API key attempted: {}", key_entry.name);
             return Err(ProxyError::auth("API key is expired or inactive"));
         }
 
         debug!("API key validation successful for: {}", key_entry.name);
-        Ok(Some(key_entry.clone()))
+        Ok(Some(key_entry))
+    }
+
+    /// Issue a new secret for the API key `name`, keeping the current one valid for a grace period
+    pub fn rotate_key(&self, name: &str, request: &KeyRotationRequest) -> Result<KeyRotation> {
+        let api_key_config = self.config.api_keys.as_ref()
+            .ok_or_else(|| ProxyError::validation("API key authentication is not configured"))?;
+        self.store.rotate(name, &api_key_config.keys, request)
+    }
+
+    /// Expiry and rotation status of the configured API keys
+    pub fn key_lifecycle(&self) -> Vec<KeyLifecycle> {
+        self.config.api_keys.as_ref()
+            .map(|api_key_config| self.store.lifecycle(&api_key_config.keys))
+            .unwrap_or_default()
+    }
+
+    /// Recent rotation and expiry audit events, newest first
+    pub fn key_events(&self, limit: usize) -> Vec<KeyAuditEvent> {
+        self.store.events(limit)
     }
 
     /// Check if an API key has a specific permission
@@ -143,11 +177,14 @@ mod tests {
                     permissions: vec!["read".to_string()],
                     expires_at: Some("2020-01-01T00:00:00Z".to_string()),
                     active: true,
+                    toolset: None,
+                    scopes: Vec::new(),
                 },
             ],
             require_header: true,
             header_name: "Authorization".to_string(),
             header_format: "Bearer {key}".to_string(),
+            rotation: Default::default(),
         });
         config
     }
@@ -0,0 +1,276 @@
+//! TLS client certificate (mTLS) authentication for MCP Proxy
+//!
+//! Machine-to-machine clients can authenticate with the certificate they
+//! present during the TLS handshake instead of an API key. The certificate
+//! comes from the connection itself in `application` TLS mode, or from a
+//! header set by a reverse proxy in `tls.trusted_proxies` that terminated TLS
+//! and verified it. Its subject common name and subject alternative names are
+//! matched against `auth.client_cert.mappings` to find the caller's
+//! permissions and toolset.
+
+use crate::config::{AuthConfig, AuthType, ClientCertConfig, ClientCertMapping, TlsConfig};
+use crate::error::{ProxyError, Result};
+use crate::tls::ProxyValidationUtils;
+use actix_web::dev::Extensions;
+use actix_web::{web, HttpRequest};
+use std::any::Any;
+use tracing::{debug, warn};
+use x509_parser::extensions::GeneralName;
+use x509_parser::prelude::X509Certificate;
+
+/// DER-encoded certificate the client presented on a TLS connection, kept as connection data
+#[derive(Debug, Clone)]
+pub struct PeerCertificate(pub Vec<u8>);
+
+/// Keep the client certificate of a new TLS connection, for `HttpServer::on_connect`
+pub fn store_peer_certificate(connection: &dyn Any, data: &mut Extensions) {
+    use actix_tls::accept::rustls_0_20::TlsStream;
+
+    if let Some(tls_stream) = connection.downcast_ref::<TlsStream<actix_web::rt::net::TcpStream>>() {
+        let (_, session) = tls_stream.get_ref();
+        if let Some(certificate) = session.peer_certificates().and_then(|certificates| certificates.first()) {
+            data.insert(PeerCertificate(certificate.0.clone()));
+        }
+    }
+}
+
+/// Caller identified by a client certificate
+#[derive(Debug, Clone)]
+pub struct ClientCertIdentity {
+    /// Name of the mapping the certificate matched
+    pub mapping: String,
+    /// Subject common name of the certificate
+    pub subject: Option<String>,
+    /// Subject alternative names of the certificate
+    pub sans: Vec<String>,
+    /// Permissions granted by the mapping
+    pub permissions: Vec<String>,
+    /// Toolset the caller is restricted to, if any
+    pub toolset: Option<String>,
+}
+
+impl ClientCertIdentity {
+    /// Get user ID: the subject common name, or else the first subject alternative name
+    pub fn get_user_id(&self) -> String {
+        self.subject.clone()
+            .or_else(|| self.sans.first().cloned())
+            .unwrap_or_else(|| self.mapping.clone())
+    }
+}
+
+/// Client certificate validator for handling mTLS authentication
+pub struct ClientCertValidator {
+    /// Authentication configuration
+    config: AuthConfig,
+}
+
+impl ClientCertValidator {
+    /// Create a new client certificate validator
+    pub fn new(config: AuthConfig) -> Self {
+        Self { config }
+    }
+
+    /// Validate an HTTP request for client certificate authentication
+    pub fn validate_request(&self, req: &HttpRequest) -> Result<Option<ClientCertIdentity>> {
+        if !self.config.enabled || !self.config.uses(&AuthType::ClientCert) {
+            debug!("Non-client certificate auth type, skipping client certificate validation");
+            return Ok(None);
+        }
+
+        let client_cert_config = match &self.config.client_cert {
+            Some(config) => config,
+            None => {
+                warn!("Client certificate authentication enabled but no client certificate configuration found");
+                return Err(ProxyError::auth("Client certificate configuration missing"));
+            }
+        };
+
+        let der = self.extract_certificate(req, client_cert_config)?;
+        let (_, certificate) = x509_parser::parse_x509_certificate(&der)
+            .map_err(|e| ProxyError::auth(format!("Invalid client certificate: {}", e)))?;
+        if !certificate.validity().is_valid() {
+            return Err(ProxyError::auth("Client certificate is expired or not yet valid"));
+        }
+
+        let subject = subject_common_name(&certificate);
+        let sans = subject_alternative_names(&certificate);
+        let mapping = client_cert_config.mappings.iter()
+            .find(|mapping| mapping_matches(mapping, subject.as_deref(), &sans))
+            .ok_or_else(|| ProxyError::auth(format!(
+                "Client certificate '{}' matches no mapping",
+                subject.as_deref().or(sans.first().map(String::as_str)).unwrap_or("unnamed")
+            )))?;
+
+        Ok(Some(ClientCertIdentity {
+            mapping: mapping.name.clone(),
+            subject,
+            sans,
+            permissions: mapping.permissions.clone(),
+            toolset: mapping.toolset.clone(),
+        }))
+    }
+
+    /// Check if an identified client has a specific permission
+    pub fn check_permission(&self, identity: &ClientCertIdentity, permission: &str) -> bool {
+        identity.permissions.contains(&permission.to_string())
+    }
+
+    /// Certificate from the TLS connection, or else from the header of a trusted proxy
+    fn extract_certificate(&self, req: &HttpRequest, client_cert_config: &ClientCertConfig) -> Result<Vec<u8>> {
+        if let Some(PeerCertificate(der)) = req.conn_data::<PeerCertificate>() {
+            return Ok(der.clone());
+        }
+
+        let Some(header) = req.headers().get(&client_cert_config.forwarded_header) else {
+            return Err(ProxyError::auth("No client certificate presented"));
+        };
+        let from_trusted_proxy = req.app_data::<web::Data<TlsConfig>>()
+            .map_or(false, |tls_config| ProxyValidationUtils::is_from_trusted_proxy(req, tls_config));
+        if !from_trusted_proxy {
+            warn!(
+                remote_addr = ?req.connection_info().peer_addr(),
+                "Ignoring {} header from an untrusted address", client_cert_config.forwarded_header
+            );
+            return Err(ProxyError::auth("No client certificate presented"));
+        }
+
+        let header = header.to_str()
+            .map_err(|_| ProxyError::auth("Invalid client certificate header encoding"))?;
+        let pem = urlencoding::decode(header)
+            .map_err(|_| ProxyError::auth("Invalid client certificate header encoding"))?;
+        let (_, pem) = x509_parser::pem::parse_x509_pem(pem.as_bytes())
+            .map_err(|e| ProxyError::auth(format!("Invalid client certificate PEM: {}", e)))?;
+        Ok(pem.contents)
+    }
+}
+
+/// Subject common name of `certificate`
+fn subject_common_name(certificate: &X509Certificate<'_>) -> Option<String> {
+    certificate.subject().iter_common_name()
+        .next()
+        .and_then(|common_name| common_name.as_str().ok())
+        .map(str::to_string)
+}
+
+/// DNS, URI, email and IP subject alternative names of `certificate`
+fn subject_alternative_names(certificate: &X509Certificate<'_>) -> Vec<String> {
+    let Ok(Some(extension)) = certificate.subject_alternative_name() else {
+        return Vec::new();
+    };
+    extension.value.general_names.iter()
+        .filter_map(|name| match name {
+            GeneralName::DNSName(name) | GeneralName::URI(name) | GeneralName::RFC822Name(name) => Some(name.to_string()),
+            GeneralName::IPAddress(bytes) => match bytes.len() {
+                4 => Some(std::net::IpAddr::from(<[u8; 4]>::try_from(*bytes).ok()?).to_string()),
+                16 => Some(std::net::IpAddr::from(<[u8; 16]>::try_from(*bytes).ok()?).to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether a certificate with `subject` and `sans` matches every pattern of `mapping`
+fn mapping_matches(mapping: &ClientCertMapping, subject: Option<&str>, sans: &[String]) -> bool {
+    let matches = |pattern: &str, value: &str| {
+        globset::GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_or(false, |glob| glob.compile_matcher().is_match(value))
+    };
+
+    mapping.subject.as_deref().map_or(true, |pattern| subject.map_or(false, |subject| matches(pattern, subject)))
+        && mapping.san.as_deref().map_or(true, |pattern| sans.iter().any(|san| matches(pattern, san)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    /// Self-signed, CN=billing-worker with DNS and SPIFFE URI SANs, valid 2025-2125
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIB+zCCAaGgAwIBAgIUBZ7IuDYneUAppFExHdQp0BdWlk0wCgYIKoZIzj0EAwIw
+KzEQMA4GA1UECgwHRXhhbXBsZTEXMBUGA1UEAwwOYmlsbGluZy13b3JrZXIwIBcN
+MjUwMTAxMDAwMDAwWhgPMjEyNTAxMDEwMDAwMDBaMCsxEDAOBgNVBAoMB0V4YW1w
+bGUxFzAVBgNVBAMMDmJpbGxpbmctd29ya2VyMFkwEwYHKoZIzj0CAQYIKoZIzj0D
+AQcDQgAErB//N/tKtFS2NmnoAlF5aYbP8SHyQapQvSUYAsHK+et8zGNCX7jT90X2
+TxzW/a/oPpraSGubcYKiENgOyy2XSKOBoDCBnTAdBgNVHQ4EFgQU4hHSIdkz8/ze
+a5qhf3xMsBuF5vgwHwYDVR0jBBgwFoAU4hHSIdkz8/zea5qhf3xMsBuF5vgwDwYD
+VR0TAQH/BAUwAwEB/zBKBgNVHREEQzBBghxiaWxsaW5nLnN2Yy5leGFtcGxlLmlu
+dGVybmFshiFzcGlmZmU6Ly9leGFtcGxlLmludGVybmFsL2JpbGxpbmcwCgYIKoZI
+zj0EAwIDSAAwRQIgCUhm/rysTrGNU5u6IGDVWdon+R2fg2UhCUXXDvxsBfUCIQDE
+PeNzuyo9WAcs684DQr/G7+hbX723DnSoxIYHK4XmuA==
+-----END CERTIFICATE-----
+";
+
+    fn mapping(name: &str, subject: Option<&str>, san: Option<&str>, permissions: &[&str]) -> ClientCertMapping {
+        ClientCertMapping {
+            name: name.to_string(),
+            subject: subject.map(str::to_string),
+            san: san.map(str::to_string),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            toolset: None,
+        }
+    }
+
+    fn create_test_config() -> AuthConfig {
+        let mut config = AuthConfig::default();
+        config.enabled = true;
+        config.r#type = AuthType::ClientCert;
+        config.client_cert = Some(ClientCertConfig {
+            forwarded_header: "X-Client-Cert".to_string(),
+            mappings: vec![
+                mapping("Payments", Some("payments-*"), None, &["read", "write"]),
+                mapping("Billing", None, Some("spiffe://example.internal/billing"), &["read"]),
+            ],
+        });
+        config
+    }
+
+    fn tls_config(trusted_proxies: &[&str]) -> TlsConfig {
+        TlsConfig {
+            trusted_proxies: trusted_proxies.iter().map(|p| p.to_string()).collect(),
+            ..TlsConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_forwarded_certificate_from_trusted_proxy() {
+        let validator = ClientCertValidator::new(create_test_config());
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.5:40000".parse().unwrap())
+            .app_data(web::Data::new(tls_config(&["10.0.0.0/8"])))
+            .insert_header(("X-Client-Cert", urlencoding::encode(CERTIFICATE).into_owned()))
+            .to_http_request();
+
+        let identity = validator.validate_request(&req).unwrap().unwrap();
+        assert_eq!(identity.mapping, "Billing");
+        assert_eq!(identity.get_user_id(), "billing-worker");
+        assert_eq!(identity.sans, vec!["billing.svc.example.internal", "spiffe://example.internal/billing"]);
+        assert!(validator.check_permission(&identity, "read"));
+        assert!(!validator.check_permission(&identity, "write"));
+    }
+
+    #[test]
+    fn test_forwarded_certificate_from_untrusted_address() {
+        let validator = ClientCertValidator::new(create_test_config());
+        let req = TestRequest::default()
+            .peer_addr("203.0.113.7:40000".parse().unwrap())
+            .app_data(web::Data::new(tls_config(&["10.0.0.0/8"])))
+            .insert_header(("X-Client-Cert", urlencoding::encode(CERTIFICATE).into_owned()))
+            .to_http_request();
+
+        assert!(validator.validate_request(&req).is_err());
+        assert!(validator.validate_request(&TestRequest::default().to_http_request()).is_err());
+    }
+
+    #[test]
+    fn test_mapping_matches() {
+        let sans = vec!["billing.svc.example.internal".to_string()];
+        assert!(mapping_matches(&mapping("a", Some("Billing-*"), None, &[]), Some("billing-worker"), &sans));
+        assert!(mapping_matches(&mapping("b", None, Some("*.svc.example.internal"), &[]), None, &sans));
+        assert!(!mapping_matches(&mapping("c", Some("billing-*"), Some("*.other.internal"), &[]), Some("billing-worker"), &sans));
+        assert!(!mapping_matches(&mapping("d", Some("billing-*"), None, &[]), None, &sans));
+    }
+}
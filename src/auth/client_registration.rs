@@ -0,0 +1,325 @@
+//! OAuth dynamic client registration (RFC 7591)
+//!
+//! MCP clients that expect to self-register against an authorization server
+//! post their metadata to `/auth/oauth/register` and get a `client_id` back.
+//! With `require_approval`, new clients are held in a queue until an operator
+//! approves them in the dashboard; until then the authorization endpoint
+//! refuses them. Clients are registered as public clients (no secret; they
+//! are expected to use PKCE) and persisted to `state_path`.
+
+use crate::config::{write_atomic, ClientRegistrationConfig};
+use crate::error::{ProxyError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tracing::info;
+
+/// Registrations awaiting review accepted at once, so the queue can't be flooded
+const MAX_PENDING: usize = 100;
+
+fn default_grant_types() -> Vec<String> { vec!["authorization_code".to_string()] }
+fn default_response_types() -> Vec<String> { vec!["code".to_string()] }
+
+/// Client metadata sent in a registration request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientMetadata {
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+    #[serde(default = "default_grant_types")]
+    pub grant_types: Vec<String>,
+    #[serde(default = "default_response_types")]
+    pub response_types: Vec<String>,
+    /// Always `none`: clients are registered as public clients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_endpoint_auth_method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contacts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub software_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub software_version: Option<String>,
+}
+
+/// Review state of a registered client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A registered client, as returned to it and shown in the dashboard
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisteredClient {
+    pub client_id: String,
+    /// Registration time, in seconds since the epoch
+    pub client_id_issued_at: i64,
+    #[serde(flatten)]
+    pub metadata: ClientMetadata,
+    pub registration_status: ClientStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+/// Rejected registration request, with its RFC 7591 error code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistrationError {
+    InvalidRedirectUri(String),
+    InvalidClientMetadata(String),
+    /// Registrations are not accepted right now
+    Unavailable(String),
+}
+
+impl RegistrationError {
+    /// `error` code of the error response
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidRedirectUri(_) => "invalid_redirect_uri",
+            Self::InvalidClientMetadata(_) => "invalid_client_metadata",
+            Self::Unavailable(_) => "temporarily_unavailable",
+        }
+    }
+
+    /// `error_description` of the error response
+    pub fn description(&self) -> &str {
+        match self {
+            Self::InvalidRedirectUri(description)
+            | Self::InvalidClientMetadata(description)
+            | Self::Unavailable(description) => description,
+        }
+    }
+}
+
+/// Registered clients, persisted as JSON
+pub struct ClientRegistry {
+    path: Option<PathBuf>,
+    config: ClientRegistrationConfig,
+    clients: RwLock<BTreeMap<String, RegisteredClient>>,
+}
+
+impl ClientRegistry {
+    /// Open the registry persisted at `config.state_path`
+    pub fn open(config: ClientRegistrationConfig) -> Result<Self> {
+        let path = PathBuf::from(&config.state_path);
+        let clients = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| ProxyError::auth(format!("Failed to read OAuth clients '{}': {}", path.display(), e)))?;
+            serde_json::from_str(&content)
+                .map_err(|e| ProxyError::auth(format!("Invalid OAuth clients '{}': {}", path.display(), e)))?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self { path: Some(path), config, clients: RwLock::new(clients) })
+    }
+
+    /// Registry that keeps clients in memory only
+    pub fn in_memory(config: ClientRegistrationConfig) -> Self {
+        Self { path: None, config, clients: RwLock::new(BTreeMap::new()) }
+    }
+
+    /// Register a client, pending review if approval is required
+    pub fn register(&self, mut metadata: ClientMetadata) -> std::result::Result<RegisteredClient, RegistrationError> {
+        validate_metadata(&metadata)?;
+        metadata.token_endpoint_auth_method = Some("none".to_string());
+
+        let status = if self.config.require_approval { ClientStatus::Pending } else { ClientStatus::Approved };
+        if status == ClientStatus::Pending && self.pending_count() >= MAX_PENDING {
+            return Err(RegistrationError::Unavailable("Too many registrations awaiting approval".to_string()));
+        }
+
+        let client = RegisteredClient {
+            client_id: format!("mtc_{}", uuid::Uuid::new_v4().simple()),
+            client_id_issued_at: Utc::now().timestamp(),
+            metadata,
+            registration_status: status,
+            reviewed_at: None,
+        };
+        self.update(|clients| {
+            clients.insert(client.client_id.clone(), client.clone());
+        })
+        .map_err(|e| RegistrationError::Unavailable(e.to_string()))?;
+
+        info!(
+            target: "audit",
+            client_id = %client.client_id,
+            client_name = ?client.metadata.client_name,
+            status = ?client.registration_status,
+            "OAuth client registered"
+        );
+        Ok(client)
+    }
+
+    /// All registered clients, oldest first
+    pub fn clients(&self) -> Vec<RegisteredClient> {
+        let mut clients: Vec<_> = self.clients.read().map(|clients| clients.values().cloned().collect()).unwrap_or_default();
+        clients.sort_by_key(|client| client.client_id_issued_at);
+        clients
+    }
+
+    /// Number of clients awaiting review
+    pub fn pending_count(&self) -> usize {
+        self.clients.read()
+            .map(|clients| clients.values().filter(|client| client.registration_status == ClientStatus::Pending).count())
+            .unwrap_or(0)
+    }
+
+    /// Approve or reject a client
+    pub fn review(&self, client_id: &str, approve: bool) -> Result<RegisteredClient> {
+        let status = if approve { ClientStatus::Approved } else { ClientStatus::Rejected };
+        let mut reviewed = None;
+        self.update(|clients| {
+            if let Some(client) = clients.get_mut(client_id) {
+                client.registration_status = status;
+                client.reviewed_at = Some(Utc::now());
+                reviewed = Some(client.clone());
+            }
+        })?;
+        let client = reviewed.ok_or_else(|| ProxyError::validation(format!("Unknown OAuth client '{}'", client_id)))?;
+
+        info!(target: "audit", client_id = %client_id, status = ?status, "OAuth client reviewed");
+        Ok(client)
+    }
+
+    /// Check that a client may start an authorization with `redirect_uri`
+    pub fn check_authorization(&self, client_id: &str, redirect_uri: &str) -> Result<()> {
+        let clients = self.clients.read()
+            .map_err(|_| ProxyError::auth("OAuth client registry lock poisoned"))?;
+        let client = clients.get(client_id)
+            .ok_or_else(|| ProxyError::auth(format!("Unknown OAuth client '{}'", client_id)))?;
+
+        match client.registration_status {
+            ClientStatus::Approved => {}
+            ClientStatus::Pending => return Err(ProxyError::auth(format!("OAuth client '{}' is awaiting approval", client_id))),
+            ClientStatus::Rejected => return Err(ProxyError::auth(format!("OAuth client '{}' was rejected", client_id))),
+        }
+        // Exact match, as OAuth 2.1 requires
+        if !client.metadata.redirect_uris.iter().any(|uri| uri == redirect_uri) {
+            return Err(ProxyError::auth(format!("Redirect URI is not registered for OAuth client '{}'", client_id)));
+        }
+        Ok(())
+    }
+
+    fn update(&self, change: impl FnOnce(&mut BTreeMap<String, RegisteredClient>)) -> Result<()> {
+        let mut clients = self.clients.write()
+            .map_err(|_| ProxyError::auth("OAuth client registry lock poisoned"))?;
+        let mut updated = clients.clone();
+        change(&mut updated);
+        self.save(&updated)?;
+        *clients = updated;
+        Ok(())
+    }
+
+    fn save(&self, clients: &BTreeMap<String, RegisteredClient>) -> Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ProxyError::auth(format!("Failed to create '{}': {}", parent.display(), e)))?;
+        }
+        let content = serde_json::to_string_pretty(clients)?;
+        write_atomic(path, content.as_bytes(), true)
+            .map_err(|e| ProxyError::auth(format!("Failed to write OAuth clients '{}': {}", path.display(), e)))
+    }
+}
+
+fn validate_metadata(metadata: &ClientMetadata) -> std::result::Result<(), RegistrationError> {
+    if let Some(grant_type) = metadata.grant_types.iter().find(|grant| !matches!(grant.as_str(), "authorization_code" | "refresh_token")) {
+        return Err(RegistrationError::InvalidClientMetadata(format!("Unsupported grant type '{}'", grant_type)));
+    }
+    if metadata.response_types.iter().any(|response_type| response_type != "code") {
+        return Err(RegistrationError::InvalidClientMetadata("Only the 'code' response type is supported".to_string()));
+    }
+
+    if metadata.redirect_uris.is_empty() {
+        return Err(RegistrationError::InvalidRedirectUri("At least one redirect URI is required".to_string()));
+    }
+    for uri in &metadata.redirect_uris {
+        validate_redirect_uri(uri).map_err(|reason| {
+            RegistrationError::InvalidRedirectUri(format!("Redirect URI '{}' {}", uri, reason))
+        })?;
+    }
+    Ok(())
+}
+
+/// HTTPS, loopback HTTP, or a private-use scheme of a native app (RFC 8252)
+fn validate_redirect_uri(uri: &str) -> std::result::Result<(), &'static str> {
+    let url = url::Url::parse(uri).map_err(|_| "is not an absolute URI")?;
+    if url.fragment().is_some() {
+        return Err("must not contain a fragment");
+    }
+    match url.scheme() {
+        "https" => Ok(()),
+        "http" => match url.host() {
+            Some(url::Host::Domain("localhost")) => Ok(()),
+            Some(url::Host::Ipv4(ip)) if ip.is_loopback() => Ok(()),
+            Some(url::Host::Ipv6(ip)) if ip.is_loopback() => Ok(()),
+            _ => Err("must use https unless it is a loopback address"),
+        },
+        "javascript" | "data" | "file" | "vbscript" => Err("uses a forbidden scheme"),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(redirect_uris: &[&str]) -> ClientMetadata {
+        serde_json::from_value(serde_json::json!({
+            "client_name": "Test Client",
+            "redirect_uris": redirect_uris,
+            "token_endpoint_auth_method": "client_secret_basic"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_registration_awaits_approval() {
+        let registry = ClientRegistry::in_memory(ClientRegistrationConfig { enabled: true, ..Default::default() });
+        let client = registry.register(metadata(&["http://127.0.0.1:33418/callback"])).unwrap();
+        assert_eq!(client.registration_status, ClientStatus::Pending);
+        assert_eq!(client.metadata.token_endpoint_auth_method.as_deref(), Some("none"));
+        assert_eq!(registry.pending_count(), 1);
+
+        let callback = "http://127.0.0.1:33418/callback";
+        assert!(registry.check_authorization(&client.client_id, callback).is_err());
+
+        registry.review(&client.client_id, true).unwrap();
+        assert!(registry.check_authorization(&client.client_id, callback).is_ok());
+        assert!(registry.check_authorization(&client.client_id, "http://127.0.0.1:33418/other").is_err());
+
+        registry.review(&client.client_id, false).unwrap();
+        assert!(registry.check_authorization(&client.client_id, callback).is_err());
+        assert!(registry.review("mtc_unknown", true).is_err());
+    }
+
+    #[test]
+    fn test_redirect_uri_validation() {
+        let registry = ClientRegistry::in_memory(ClientRegistrationConfig { enabled: true, require_approval: false, ..Default::default() });
+        assert!(registry.register(metadata(&["https://client.example.com/callback"])).is_ok());
+        assert!(registry.register(metadata(&["com.example.app:/callback"])).is_ok());
+
+        for uri in ["http://client.example.com/callback", "https://client.example.com/cb#frag", "javascript:alert(1)", "/callback"] {
+            let error = registry.register(metadata(&[uri])).unwrap_err();
+            assert_eq!(error.code(), "invalid_redirect_uri", "{}", uri);
+        }
+        assert_eq!(registry.register(metadata(&[])).unwrap_err().code(), "invalid_redirect_uri");
+
+        let mut implicit = metadata(&["https://client.example.com/callback"]);
+        implicit.grant_types = vec!["implicit".to_string()];
+        assert_eq!(registry.register(implicit).unwrap_err().code(), "invalid_client_metadata");
+    }
+}
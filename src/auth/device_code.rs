@@ -0,0 +1,451 @@
+//! OAuth 2.0 device authorization grant (RFC 8628) for stdio mode
+//!
+//! CLI-based MCP clients launch MagicTunnel with `--stdio` and have no
+//! browser redirect to complete an authorization code flow. When OAuth
+//! authentication is enabled and the provider has a device authorization
+//! endpoint, stdio mode signs the user in before serving requests:
+//!
+//! ```yaml
+//! auth:
+//!   enabled: true
+//!   type: oauth
+//!   oauth:
+//!     provider: github
+//!     client_id: your-client-id
+//!     client_secret: your-client-secret
+//!     auth_url: https://github.com/login/oauth/authorize
+//!     token_url: https://github.com/login/oauth/access_token
+//!     device_auth_url: https://github.com/login/device/code
+//! ```
+//!
+//! The verification URL and user code are printed to stderr (stdout carries
+//! the JSON-RPC stream) and the token endpoint is polled until the user
+//! approves. The resulting session is cached on disk and reused, or
+//! refreshed, on the next start. With `auth.token_encryption` configured the
+//! cached tokens are encrypted (see [`crate::auth::token_encryption`]).
+
+use crate::auth::{OAuthTokenResponse, OAuthValidator, SealedToken, TokenCipher};
+use crate::config::{write_atomic, AuthConfig, AuthType, OAuthConfig};
+use crate::error::{ProxyError, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// File name of the cached session in the user data directory
+const SESSION_FILE: &str = "device-session.json";
+
+/// Polling interval when the provider does not specify one
+const DEFAULT_INTERVAL: u64 = 5;
+/// Cached sessions are treated as expired this many seconds early
+const EXPIRY_MARGIN: u64 = 60;
+
+/// Device authorization response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAuthorization {
+    /// Code the client polls the token endpoint with
+    pub device_code: String,
+    /// Code the user enters at the verification URL
+    pub user_code: String,
+    /// URL the user opens to approve the client
+    #[serde(alias = "verification_url")]
+    pub verification_uri: String,
+    /// Verification URL with the user code filled in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification_uri_complete: Option<String>,
+    /// Lifetime of the codes in seconds
+    pub expires_in: u64,
+    /// Minimum seconds between token requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+}
+
+/// Signed-in session cached between stdio runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceSession {
+    pub access_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Unix time the access token expires at, `None` if unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl DeviceSession {
+    fn from_token(token: OAuthTokenResponse, previous_refresh_token: Option<String>) -> Self {
+        Self {
+            access_token: token.access_token,
+            // Providers may omit the refresh token on refresh, keeping the old one valid
+            refresh_token: token.refresh_token.or(previous_refresh_token),
+            expires_at: token.expires_in.map(|expires_in| now() + expires_in),
+        }
+    }
+
+    /// Whether the access token is expired or about to expire
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now() + EXPIRY_MARGIN)
+    }
+
+    /// Load a cached session, `None` if there is none or it is unreadable.
+    /// Encrypted sessions are decrypted with `cipher`.
+    pub fn load(path: &Path, cipher: Option<&TokenCipher>) -> Option<Self> {
+        match Self::read(path, cipher) {
+            Ok(session) => session.map(|(session, _)| session),
+            Err(e) => {
+                warn!("Ignoring device session '{}': {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Cache the session, readable by the current user only and encrypted with `cipher` if given
+    pub fn save(&self, path: &Path, cipher: Option<&TokenCipher>) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            let mut builder = std::fs::DirBuilder::new();
+            builder.recursive(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::DirBuilderExt;
+                builder.mode(0o700);
+            }
+            builder.create(parent)
+                .map_err(|e| ProxyError::auth(format!("Failed to create '{}': {}", parent.display(), e)))?;
+        }
+        let stored = match cipher {
+            Some(cipher) => StoredSession::Sealed { sealed: cipher.seal(&serde_json::to_vec(self)?)? },
+            None => StoredSession::Plain(self.clone()),
+        };
+        let content = serde_json::to_string_pretty(&stored)?;
+        write_atomic(path, content.as_bytes(), true)
+            .map_err(|e| ProxyError::auth(format!("Failed to write device session '{}': {}", path.display(), e)))
+    }
+
+    /// Re-encrypt the cached session with the current key of `cipher` if it is
+    /// stored in plaintext or encrypted with a previous key. Returns whether it was rewritten.
+    pub fn reseal(path: &Path, cipher: &TokenCipher) -> Result<bool> {
+        match Self::read(path, Some(cipher))? {
+            Some((session, true)) => {
+                session.save(path, Some(cipher))?;
+                info!("Re-encrypted device session '{}' with key '{}'", path.display(), cipher.key_id());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// The cached session and whether it should be re-encrypted with `cipher`
+    fn read(path: &Path, cipher: Option<&TokenCipher>) -> Result<Option<(Self, bool)>> {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Ok(None);
+        };
+        match (serde_json::from_str(&content)?, cipher) {
+            (StoredSession::Plain(session), cipher) => Ok(Some((session, cipher.is_some()))),
+            (StoredSession::Sealed { sealed }, Some(cipher)) => {
+                let session = serde_json::from_slice(&cipher.open(&sealed)?)?;
+                Ok(Some((session, cipher.needs_rotation(&sealed))))
+            }
+            (StoredSession::Sealed { .. }, None) => {
+                Err(ProxyError::auth("the session is encrypted but auth.token_encryption is not configured"))
+            }
+        }
+    }
+}
+
+/// Device session file content
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredSession {
+    Sealed { sealed: SealedToken },
+    Plain(DeviceSession),
+}
+
+/// Where stdio mode caches the signed-in session, in the current user's data directory
+pub fn default_session_path() -> PathBuf {
+    crate::config::user_data_dir().join(SESSION_FILE)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Device authorization grant against an OAuth provider
+pub struct DeviceCodeFlow {
+    config: OAuthConfig,
+    device_auth_url: String,
+    client: Client,
+}
+
+impl DeviceCodeFlow {
+    /// Create the flow, failing if the provider has no device authorization URL
+    pub fn new(config: OAuthConfig) -> Result<Self> {
+        let device_auth_url = config.device_auth_url.clone()
+            .ok_or_else(|| ProxyError::config("OAuth device authorization URL is not configured"))?;
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        Ok(Self { config, device_auth_url, client })
+    }
+
+    /// Request a device and user code
+    pub async fn start(&self) -> Result<DeviceAuthorization> {
+        let response = self.client
+            .post(&self.device_auth_url)
+            .header("Accept", "application/json")
+            .form(&[("client_id", self.config.client_id.as_str())])
+            .send()
+            .await
+            .map_err(|e| ProxyError::auth(format!("Device authorization request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body: Value = response.json().await.unwrap_or_default();
+            return Err(ProxyError::auth(format!("Device authorization rejected: {}", error_reason(&body, status))));
+        }
+
+        response.json::<DeviceAuthorization>().await
+            .map_err(|e| ProxyError::auth(format!("Invalid device authorization response: {}", e)))
+    }
+
+    /// Poll the token endpoint until the user approves, denies, or the code expires
+    pub async fn poll(&self, authorization: &DeviceAuthorization) -> Result<OAuthTokenResponse> {
+        let deadline = Instant::now() + Duration::from_secs(authorization.expires_in);
+        let mut interval = Duration::from_secs(authorization.interval.unwrap_or(DEFAULT_INTERVAL).max(1));
+
+        loop {
+            tokio::time::sleep(interval).await;
+            if Instant::now() >= deadline {
+                return Err(ProxyError::auth("Device code expired before sign-in completed"));
+            }
+
+            let mut form = vec![
+                ("grant_type", DEVICE_CODE_GRANT_TYPE),
+                ("device_code", authorization.device_code.as_str()),
+            ];
+            form.extend(self.client_credentials());
+            let body = self.token_request(&form).await?;
+
+            match body.get("error").and_then(Value::as_str) {
+                None => return parse_token(body),
+                Some("authorization_pending") => debug!("Device sign-in pending"),
+                Some("slow_down") => interval += Duration::from_secs(5),
+                Some(_) => {
+                    return Err(ProxyError::auth(format!(
+                        "Device sign-in failed: {}",
+                        error_reason(&body, reqwest::StatusCode::BAD_REQUEST)
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Obtain a new access token with a cached refresh token
+    pub async fn refresh(&self, refresh_token: &str) -> Result<OAuthTokenResponse> {
+        let mut form = vec![("grant_type", "refresh_token"), ("refresh_token", refresh_token)];
+        form.extend(self.client_credentials());
+        let body = self.token_request(&form).await?;
+        if body.get("error").is_some() {
+            return Err(ProxyError::auth(format!(
+                "Token refresh failed: {}",
+                error_reason(&body, reqwest::StatusCode::BAD_REQUEST)
+            )));
+        }
+        parse_token(body)
+    }
+
+    fn client_credentials(&self) -> Vec<(&'static str, &str)> {
+        let mut credentials = vec![("client_id", self.config.client_id.as_str())];
+        if !self.config.client_secret.is_empty() {
+            credentials.push(("client_secret", self.config.client_secret.as_str()));
+        }
+        credentials
+    }
+
+    /// POST to the token endpoint; errors are returned in the body, as some
+    /// providers (GitHub) report them with a 200 status
+    async fn token_request(&self, form: &[(&str, &str)]) -> Result<Value> {
+        let response = self.client
+            .post(&self.config.token_url)
+            .header("Accept", "application/json")
+            .form(form)
+            .send()
+            .await
+            .map_err(|e| ProxyError::auth(format!("Token request to {} failed: {}", self.config.token_url, e)))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or_default();
+        if !status.is_success() && body.get("error").is_none() {
+            return Err(ProxyError::auth(format!("Token endpoint returned {}", status)));
+        }
+        Ok(body)
+    }
+}
+
+fn parse_token(body: Value) -> Result<OAuthTokenResponse> {
+    serde_json::from_value(body).map_err(|e| ProxyError::auth(format!("Invalid token response: {}", e)))
+}
+
+fn error_reason(body: &Value, status: reqwest::StatusCode) -> String {
+    let Some(error) = body.get("error").and_then(Value::as_str) else {
+        return status.to_string();
+    };
+    match body.get("error_description").and_then(Value::as_str) {
+        Some(description) => format!("{}: {}", error, description),
+        None => error.to_string(),
+    }
+}
+
+/// User signed in to a stdio session
+#[derive(Debug, Clone)]
+pub struct StdioSession {
+    /// User identifier reported by the OAuth provider
+    pub user_id: String,
+    /// Access token the user's calls are made with
+    pub access_token: String,
+}
+
+/// Sign the stdio user in with the device flow, reusing the session cached at `session_path`.
+/// Returns `None` when OAuth authentication or the device authorization URL is not configured.
+pub async fn stdio_session(auth_config: &AuthConfig, session_path: impl Into<PathBuf>) -> Result<Option<StdioSession>> {
+    if !auth_config.enabled || !auth_config.uses(&AuthType::OAuth) {
+        return Ok(None);
+    }
+    let Some(oauth_config) = auth_config.oauth.as_ref().filter(|oauth| oauth.device_auth_url.is_some()) else {
+        warn!("OAuth authentication is enabled but no device_auth_url is configured; stdio mode runs unauthenticated");
+        return Ok(None);
+    };
+
+    let session_path = session_path.into();
+    let flow = DeviceCodeFlow::new(oauth_config.clone())?;
+    let validator = OAuthValidator::new(auth_config.clone());
+    let cipher = match &auth_config.token_encryption {
+        Some(token_encryption) => {
+            let cipher = TokenCipher::from_config(token_encryption).await?;
+            if let Err(e) = DeviceSession::reseal(&session_path, &cipher) {
+                warn!("Device session '{}' could not be re-encrypted: {}", session_path.display(), e);
+            }
+            Some(cipher)
+        }
+        None => None,
+    };
+
+    if let Some(session) = DeviceSession::load(&session_path, cipher.as_ref()) {
+        let session = if session.is_expired() {
+            match session.refresh_token.as_deref() {
+                Some(refresh_token) => match flow.refresh(refresh_token).await {
+                    Ok(token) => Some(DeviceSession::from_token(token, session.refresh_token.clone())),
+                    Err(e) => {
+                        warn!("Cached device session could not be refreshed: {}", e);
+                        None
+                    }
+                },
+                None => None,
+            }
+        } else {
+            Some(session)
+        };
+
+        if let Some(session) = session {
+            match validator.validate_token(&session.access_token).await {
+                Ok(Some(result)) => {
+                    session.save(&session_path, cipher.as_ref())?;
+                    info!("Resumed stdio session for user: {}", result.user_info.id);
+                    return Ok(Some(StdioSession { user_id: result.user_info.id, access_token: session.access_token }));
+                }
+                Ok(None) => {}
+                Err(e) => debug!("Cached device session rejected: {}", e),
+            }
+        }
+    }
+
+    let authorization = flow.start().await?;
+    eprintln!();
+    eprintln!("MagicTunnel sign-in required.");
+    match &authorization.verification_uri_complete {
+        Some(url) => eprintln!("Open {} and confirm the code {}", url, authorization.user_code),
+        None => eprintln!("Open {} and enter the code {}", authorization.verification_uri, authorization.user_code),
+    }
+    eprintln!("Waiting for approval (expires in {} minutes)...", authorization.expires_in / 60);
+
+    let session = DeviceSession::from_token(flow.poll(&authorization).await?, None);
+    let result = validator.validate_token(&session.access_token).await?
+        .ok_or_else(|| ProxyError::auth("Device sign-in returned an unusable token"))?;
+    session.save(&session_path, cipher.as_ref())?;
+    eprintln!("Signed in as {}", result.user_info.login.as_deref().unwrap_or(&result.user_info.id));
+    info!("Stdio session signed in for user: {}", result.user_info.id);
+
+    Ok(Some(StdioSession { user_id: result.user_info.id, access_token: session.access_token }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_expiry() {
+        let session = DeviceSession { access_token: "token".to_string(), refresh_token: None, expires_at: None };
+        assert!(!session.is_expired());
+
+        let expiring = DeviceSession { expires_at: Some(now() + 30), ..session.clone() };
+        assert!(expiring.is_expired());
+
+        let valid = DeviceSession { expires_at: Some(now() + 3600), ..session };
+        assert!(!valid.is_expired());
+    }
+
+    #[test]
+    fn test_session_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions/device-session.json");
+        assert!(DeviceSession::load(&path, None).is_none());
+
+        let session = DeviceSession {
+            access_token: "token".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_at: Some(now() + 3600),
+        };
+        session.save(&path, None).unwrap();
+        let loaded = DeviceSession::load(&path, None).unwrap();
+        assert_eq!(loaded.access_token, "token");
+        assert_eq!(loaded.refresh_token.as_deref(), Some("refresh"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_encrypted_session_and_key_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("device-session.json");
+        let session = DeviceSession {
+            access_token: "access-secret".to_string(),
+            refresh_token: Some("refresh-secret".to_string()),
+            expires_at: None,
+        };
+
+        // A plaintext session is encrypted once a key is configured
+        session.save(&path, None).unwrap();
+        let old_key: &[u8] = b"old-key-0123456789abcdefghijklmnop";
+        let old = TokenCipher::new(old_key, []);
+        assert!(DeviceSession::reseal(&path, &old).unwrap());
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("access-secret") && !content.contains("refresh-secret"));
+        assert!(DeviceSession::load(&path, None).is_none());
+        assert_eq!(DeviceSession::load(&path, Some(&old)).unwrap().access_token, "access-secret");
+        assert!(!DeviceSession::reseal(&path, &old).unwrap());
+
+        // After rotation the session is re-encrypted with the new key
+        let rotated = TokenCipher::new(b"new-key-0123456789abcdefghijklmnop", [old_key]);
+        assert!(DeviceSession::reseal(&path, &rotated).unwrap());
+        let new_only = TokenCipher::new(b"new-key-0123456789abcdefghijklmnop", []);
+        let loaded = DeviceSession::load(&path, Some(&new_only)).unwrap();
+        assert_eq!(loaded.refresh_token.as_deref(), Some("refresh-secret"));
+        assert!(DeviceSession::load(&path, Some(&old)).is_none());
+    }
+}
@@ -0,0 +1,132 @@
+//! Guest access for requests without credentials
+//!
+//! With `auth.guest` enabled, a request that presents no credentials at all
+//! is served as a guest instead of being rejected. Requests presenting
+//! invalid credentials are still rejected. Guests are restricted to a toolset
+//! or scope patterns, share the `rate_limiting.guest` limit, and every guest
+//! request is logged to the `audit` target:
+//!
+//! ```yaml
+//! auth:
+//!   enabled: true
+//!   type: api_key
+//!   api_keys: { ... }
+//!   guest:
+//!     enabled: true
+//!     toolset: docs          # or scopes: ["docs_*"]
+//! rate_limiting:
+//!   guest: { requests_per_minute: 10 }
+//! ```
+
+use crate::auth::PeerCertificate;
+use crate::config::{AuthConfig, GuestAccessConfig};
+use actix_web::HttpRequest;
+use tracing::info;
+
+/// Caller served with guest access
+#[derive(Debug, Clone)]
+pub struct GuestIdentity {
+    /// Address the request came from
+    pub address: String,
+    /// Permissions granted to guests
+    pub permissions: Vec<String>,
+    /// Toolset guests are restricted to, if any
+    pub toolset: Option<String>,
+    /// Tool name patterns guests are restricted to
+    pub scopes: Vec<String>,
+}
+
+impl GuestIdentity {
+    /// Get user ID, tagging the guest with their address
+    pub fn get_user_id(&self) -> String {
+        format!("guest@{}", self.address)
+    }
+}
+
+/// Serves requests without credentials as guests, when guest access is enabled
+pub struct GuestValidator {
+    guest: Option<GuestAccessConfig>,
+    /// Headers carrying credentials of the configured authentication methods
+    credential_headers: Vec<String>,
+}
+
+impl GuestValidator {
+    pub fn new(config: &AuthConfig) -> Self {
+        let mut credential_headers = vec!["Authorization".to_string()];
+        credential_headers.extend(config.api_keys.as_ref().map(|api_keys| api_keys.header_name.clone()));
+        credential_headers.extend(config.client_cert.as_ref().map(|client_cert| client_cert.forwarded_header.clone()));
+        credential_headers.push(crate::auth::SIGNATURE_HEADER.to_string());
+
+        Self {
+            guest: config.guest.clone().filter(|guest| config.enabled && guest.enabled),
+            credential_headers,
+        }
+    }
+
+    /// Guest identity for a request that presents no credentials, `None` if
+    /// guest access is disabled or the request must be authenticated
+    pub fn validate_request(&self, req: &HttpRequest) -> Option<GuestIdentity> {
+        let guest = self.guest.as_ref()?;
+        if self.presents_credentials(req) {
+            return None;
+        }
+
+        let address = req.connection_info().realip_remote_addr().unwrap_or("unknown").to_string();
+        info!(
+            target: "audit",
+            guest = true,
+            remote_addr = %address,
+            method = %req.method(),
+            path = %req.path(),
+            "Guest access"
+        );
+        Some(GuestIdentity {
+            address,
+            permissions: guest.permissions.clone(),
+            toolset: guest.toolset.clone(),
+            scopes: guest.scopes.clone(),
+        })
+    }
+
+    fn presents_credentials(&self, req: &HttpRequest) -> bool {
+        self.credential_headers.iter().any(|header| req.headers().contains_key(header.as_str()))
+            || req.query_string().split('&').any(|param| param.starts_with("token="))
+            || req.conn_data::<PeerCertificate>().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AuthType;
+    use actix_web::test::TestRequest;
+
+    fn guest_validator() -> GuestValidator {
+        let mut config = AuthConfig::default();
+        config.enabled = true;
+        config.r#type = AuthType::Jwt;
+        config.guest = Some(GuestAccessConfig {
+            enabled: true,
+            toolset: Some("docs".to_string()),
+            scopes: Vec::new(),
+            permissions: vec!["read".to_string()],
+        });
+        GuestValidator::new(&config)
+    }
+
+    #[test]
+    fn test_guest_only_without_credentials() {
+        let validator = guest_validator();
+
+        let guest = validator.validate_request(&TestRequest::default().to_http_request()).unwrap();
+        assert_eq!(guest.toolset.as_deref(), Some("docs"));
+        assert!(guest.get_user_id().starts_with("guest@"));
+
+        let req = TestRequest::default().insert_header(("Authorization", "Bearer invalid")).to_http_request();
+        assert!(validator.validate_request(&req).is_none());
+        let req = TestRequest::default().uri("/mcp?token=invalid").to_http_request();
+        assert!(validator.validate_request(&req).is_none());
+
+        assert!(GuestValidator::new(&AuthConfig::default()).validate_request(&TestRequest::default().to_http_request()).is_none());
+    }
+}
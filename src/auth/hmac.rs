@@ -0,0 +1,271 @@
+//! HMAC request signing authentication for MCP Proxy
+//!
+//! Machine callers that cannot do OAuth, such as webhook senders, sign each
+//! request with a shared secret instead of sending a static bearer key. The
+//! signature covers a timestamp, the method, the path and the body, so a
+//! captured request cannot be altered or replayed:
+//!
+//! ```text
+//! X-MagicTunnel-Client: billing-webhooks
+//! X-MagicTunnel-Timestamp: 1760529600
+//! X-MagicTunnel-Signature: sha256=<hex HMAC-SHA256 of "1760529600.POST./mcp/call.<body>">
+//! ```
+//!
+//! Signatures outside `max_age_seconds` of the server clock are rejected, and
+//! each signature is accepted only once within that window. The [`SignedBody`]
+//! middleware keeps the body of signed requests so it can be verified before
+//! handlers consume it.
+
+use crate::config::{AuthConfig, AuthType, HmacClientConfig, HmacConfig};
+use crate::error::{ProxyError, Result};
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::web::{Bytes, BytesMut};
+use actix_web::{HttpMessage, HttpRequest};
+use futures_util::future::LocalBoxFuture;
+use futures_util::StreamExt;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Header carrying the name of the signing client
+pub const CLIENT_HEADER: &str = "X-MagicTunnel-Client";
+/// Header carrying the Unix timestamp the request was signed at
+pub const TIMESTAMP_HEADER: &str = "X-MagicTunnel-Timestamp";
+/// Header carrying the request signature
+pub const SIGNATURE_HEADER: &str = "X-MagicTunnel-Signature";
+
+/// Largest signed body kept for verification
+const MAX_SIGNED_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Signature header value of a request sent at `timestamp`
+pub fn sign_request(secret: &str, timestamp: i64, method: &str, path_and_query: &str, body: &[u8]) -> String {
+    format!("sha256={}", hex::encode(mac(secret, timestamp, method, path_and_query, body).finalize().into_bytes()))
+}
+
+fn mac(secret: &str, timestamp: i64, method: &str, path_and_query: &str, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}.{}.", timestamp, method.to_uppercase(), path_and_query).as_bytes());
+    mac.update(body);
+    mac
+}
+
+/// Caller identified by a request signature
+#[derive(Debug, Clone)]
+pub struct HmacIdentity {
+    /// Name of the signing client
+    pub client: String,
+    /// Permissions of the client
+    pub permissions: Vec<String>,
+    /// Toolset the client is restricted to, if any
+    pub toolset: Option<String>,
+}
+
+impl HmacIdentity {
+    /// Get user ID, tagging the client as a signing client
+    pub fn get_user_id(&self) -> String {
+        format!("hmac:{}", self.client)
+    }
+}
+
+/// Body of a signed request, kept by [`SignedBody`] for signature verification
+#[derive(Debug, Clone)]
+pub struct SignedRequestBody(pub Bytes);
+
+/// HMAC request signature validator
+pub struct HmacValidator {
+    /// Authentication configuration
+    config: AuthConfig,
+    /// Signatures accepted within the replay window, with when they expire
+    seen: Mutex<HashMap<String, Instant>>,
+}
+
+impl HmacValidator {
+    /// Create a new HMAC validator
+    pub fn new(config: AuthConfig) -> Self {
+        Self { config, seen: Mutex::new(HashMap::new()) }
+    }
+
+    /// Validate the signature of an HTTP request
+    pub fn validate_request(&self, req: &HttpRequest) -> Result<Option<HmacIdentity>> {
+        if !self.config.enabled || !self.config.uses(&AuthType::Hmac) {
+            debug!("Non-HMAC auth type, skipping request signature validation");
+            return Ok(None);
+        }
+        let hmac_config = self.config.hmac.as_ref()
+            .ok_or_else(|| ProxyError::auth("HMAC configuration missing"))?;
+
+        let client_name = header(req, CLIENT_HEADER)?;
+        let client = hmac_config.clients.iter()
+            .find(|client| client.name == client_name)
+            .ok_or_else(|| ProxyError::auth(format!("Unknown HMAC client '{}'", client_name)))?;
+        let timestamp = header(req, TIMESTAMP_HEADER)?.parse::<i64>()
+            .map_err(|_| ProxyError::auth(format!("Invalid {} header", TIMESTAMP_HEADER)))?;
+        let signature = header(req, SIGNATURE_HEADER)?;
+
+        self.verify(hmac_config, client, req, timestamp, signature)?;
+        Ok(Some(HmacIdentity {
+            client: client.name.clone(),
+            permissions: client.permissions.clone(),
+            toolset: client.toolset.clone(),
+        }))
+    }
+
+    fn verify(&self, hmac_config: &HmacConfig, client: &HmacClientConfig, req: &HttpRequest, timestamp: i64, signature: &str) -> Result<()> {
+        let age = chrono::Utc::now().timestamp() - timestamp;
+        if age.unsigned_abs() > hmac_config.max_age_seconds {
+            return Err(ProxyError::auth("Request signature expired"));
+        }
+
+        let digest = signature.strip_prefix("sha256=")
+            .and_then(|hex_digest| hex::decode(hex_digest).ok())
+            .ok_or_else(|| ProxyError::auth(format!("Invalid {} header", SIGNATURE_HEADER)))?;
+        let body = req.extensions().get::<SignedRequestBody>().map(|body| body.0.clone()).unwrap_or_default();
+        let path_and_query = req.uri().path_and_query().map_or(req.path(), |path| path.as_str());
+        mac(&client.secret, timestamp, req.method().as_str(), path_and_query, &body)
+            .verify_slice(&digest)
+            .map_err(|_| ProxyError::auth("Invalid request signature"))?;
+
+        // A signature is only accepted once while its timestamp is within the window
+        let mut seen = self.seen.lock().map_err(|_| ProxyError::auth("HMAC replay cache lock poisoned"))?;
+        let now = Instant::now();
+        seen.retain(|_, expires_at| *expires_at > now);
+        let window = Duration::from_secs(hmac_config.max_age_seconds * 2);
+        if seen.insert(hex::encode(&digest), now + window).is_some() {
+            return Err(ProxyError::auth("Request signature already used"));
+        }
+        Ok(())
+    }
+
+    /// Check if a signing client has a specific permission
+    pub fn check_permission(&self, identity: &HmacIdentity, permission: &str) -> bool {
+        identity.permissions.iter().any(|granted| granted == permission)
+    }
+}
+
+fn header<'a>(req: &'a HttpRequest, name: &str) -> Result<&'a str> {
+    req.headers().get(name)
+        .ok_or_else(|| ProxyError::auth(format!("Missing {} header", name)))?
+        .to_str()
+        .map_err(|_| ProxyError::auth(format!("Invalid {} header encoding", name)))
+}
+
+/// Middleware keeping the body of signed requests for signature verification
+pub struct SignedBody;
+
+impl<S, B> Transform<S, ServiceRequest> for SignedBody
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = SignedBodyService<S>;
+    type InitError = ();
+    type Future = std::future::Ready<std::result::Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        std::future::ready(Ok(SignedBodyService { service: Rc::new(service) }))
+    }
+}
+
+/// Service created by [`SignedBody`]
+pub struct SignedBodyService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for SignedBodyService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, std::result::Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        Box::pin(async move {
+            if req.headers().contains_key(SIGNATURE_HEADER) {
+                let mut payload = req.take_payload();
+                let mut body = BytesMut::new();
+                while let Some(chunk) = payload.next().await {
+                    body.extend_from_slice(&chunk?);
+                    if body.len() > MAX_SIGNED_BODY_BYTES {
+                        return Err(actix_web::error::ErrorPayloadTooLarge("Signed request body too large"));
+                    }
+                }
+                let body = body.freeze();
+                req.extensions_mut().insert(SignedRequestBody(body.clone()));
+
+                // Hand the body on to the handler
+                let stream = futures_util::stream::once(async move { Ok(body) });
+                req.set_payload(Payload::Stream { payload: Box::pin(stream) });
+            }
+            service.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    const SECRET: &str = "billing_webhooks_secret_0123456789abcdef";
+
+    fn hmac_validator() -> HmacValidator {
+        let mut config = AuthConfig::default();
+        config.enabled = true;
+        config.r#type = AuthType::Hmac;
+        config.hmac = Some(HmacConfig {
+            clients: vec![HmacClientConfig {
+                name: "billing".to_string(),
+                secret: SECRET.to_string(),
+                permissions: vec!["read".to_string(), "write".to_string()],
+                toolset: None,
+            }],
+            max_age_seconds: 300,
+        });
+        HmacValidator::new(config)
+    }
+
+    fn signed_request(timestamp: i64, path: &str, body: &'static [u8], signed_body: &[u8]) -> HttpRequest {
+        let req = TestRequest::post()
+            .uri(path)
+            .insert_header((CLIENT_HEADER, "billing"))
+            .insert_header((TIMESTAMP_HEADER, timestamp.to_string()))
+            .insert_header((SIGNATURE_HEADER, sign_request(SECRET, timestamp, "POST", path, signed_body)))
+            .to_http_request();
+        req.extensions_mut().insert(SignedRequestBody(Bytes::from_static(body)));
+        req
+    }
+
+    #[test]
+    fn test_signature_verification() {
+        let validator = hmac_validator();
+        let now = chrono::Utc::now().timestamp();
+
+        let identity = validator.validate_request(&signed_request(now, "/mcp/call", b"{}", b"{}")).unwrap().unwrap();
+        assert_eq!(identity.client, "billing");
+
+        // Altered body, stale timestamp
+        assert!(validator.validate_request(&signed_request(now, "/mcp/call", b"{\"x\":1}", b"{}")).is_err());
+        assert!(validator.validate_request(&signed_request(now - 600, "/mcp/call", b"{}", b"{}")).is_err());
+    }
+
+    #[test]
+    fn test_replayed_signature_rejected() {
+        let validator = hmac_validator();
+        let now = chrono::Utc::now().timestamp();
+
+        assert!(validator.validate_request(&signed_request(now, "/mcp/tools", b"", b"")).is_ok());
+        let error = validator.validate_request(&signed_request(now, "/mcp/tools", b"", b"")).unwrap_err();
+        assert!(error.to_string().contains("already used"));
+    }
+}
@@ -0,0 +1,230 @@
+//! Remote JWKS key sets for JWT validation
+//!
+//! Identity providers publish the public keys they sign tokens with as a JSON
+//! Web Key Set. Each set is cached for `jwks_cache_seconds`; a token naming a
+//! `kid` the cached set does not contain triggers a re-fetch, so keys rotated
+//! in by the provider are picked up, but no more often than every
+//! `jwks_min_refresh_seconds` per endpoint.
+
+use crate::config::JwtConfig;
+use crate::error::{ProxyError, Result};
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey};
+use reqwest::Client;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{debug, warn};
+
+/// JWKS endpoint tokens of an issuer are verified against
+#[derive(Debug, Clone)]
+pub struct JwksSource {
+    /// Expected `iss` claim, `None` accepting any issuer
+    pub issuer: Option<String>,
+    /// JWKS endpoint URL
+    pub url: String,
+}
+
+/// Key set fetched from one endpoint
+struct CachedKeySet {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+/// Cache of the key sets of every trusted issuer
+pub struct JwksCache {
+    sources: Vec<JwksSource>,
+    client: Client,
+    cache_ttl: Duration,
+    min_refresh: Duration,
+    sets: RwLock<HashMap<String, CachedKeySet>>,
+    /// Serializes fetches so concurrent misses trigger a single request
+    fetch_lock: Mutex<()>,
+}
+
+impl JwksCache {
+    /// Create the cache for the JWKS endpoints of a JWT configuration,
+    /// `None` if it verifies tokens with a shared secret
+    pub fn from_config(config: &JwtConfig) -> Option<Self> {
+        if !config.uses_jwks() {
+            return None;
+        }
+
+        let mut sources: Vec<JwksSource> = config
+            .jwks_url
+            .iter()
+            .map(|url| JwksSource { issuer: config.issuer.clone(), url: url.clone() })
+            .collect();
+        sources.extend(config.trusted_issuers.iter().map(|trusted| JwksSource {
+            issuer: Some(trusted.issuer.clone()),
+            url: trusted.jwks_url.clone(),
+        }));
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+
+        Some(Self {
+            sources,
+            client,
+            cache_ttl: Duration::from_secs(config.jwks_cache_seconds),
+            min_refresh: Duration::from_secs(config.jwks_min_refresh_seconds),
+            sets: RwLock::new(HashMap::new()),
+            fetch_lock: Mutex::new(()),
+        })
+    }
+
+    /// Source trusted for tokens with the given `iss` claim
+    pub fn source_for(&self, issuer: Option<&str>) -> Option<&JwksSource> {
+        self.sources
+            .iter()
+            .find(|source| source.issuer.is_some() && source.issuer.as_deref() == issuer)
+            .or_else(|| self.sources.iter().find(|source| source.issuer.is_none()))
+    }
+
+    /// Key verifying a token signed with `algorithm` under key id `kid`
+    pub async fn decoding_key(&self, source: &JwksSource, kid: Option<&str>, algorithm: Algorithm) -> Result<DecodingKey> {
+        if !is_asymmetric(algorithm) {
+            return Err(ProxyError::auth(format!("JWT algorithm {:?} is not allowed with JWKS keys", algorithm)));
+        }
+
+        let jwk = match self.cached_key(source, kid, false).await {
+            Some(jwk) => jwk,
+            None => {
+                self.refresh(source).await?;
+                self.cached_key(source, kid, true).await.ok_or_else(|| {
+                    warn!(jwks_url = %source.url, kid = ?kid, "No JWKS key found for JWT");
+                    ProxyError::auth("Unknown JWT signing key")
+                })?
+            }
+        };
+
+        if let Some(key_algorithm) = jwk.common.key_algorithm {
+            if format!("{:?}", key_algorithm) != format!("{:?}", algorithm) {
+                return Err(ProxyError::auth(format!(
+                    "JWT algorithm {:?} does not match its signing key",
+                    algorithm
+                )));
+            }
+        }
+
+        DecodingKey::from_jwk(&jwk)
+            .map_err(|e| ProxyError::auth(format!("Unusable JWKS key: {}", e)))
+    }
+
+    /// Look a key up in the cached set, ignoring sets past their TTL unless `allow_stale`
+    async fn cached_key(&self, source: &JwksSource, kid: Option<&str>, allow_stale: bool) -> Option<Jwk> {
+        let sets = self.sets.read().await;
+        let cached = sets.get(&source.url)?;
+        if !allow_stale && cached.fetched_at.elapsed() >= self.cache_ttl {
+            return None;
+        }
+        find_key(&cached.keys, kid).cloned()
+    }
+
+    /// Re-fetch a source's key set unless it was fetched within the minimum refresh interval
+    async fn refresh(&self, source: &JwksSource) -> Result<()> {
+        let _guard = self.fetch_lock.lock().await;
+
+        let last_fetch = self.sets.read().await.get(&source.url).map(|cached| cached.fetched_at);
+        if last_fetch.is_some_and(|fetched_at| fetched_at.elapsed() < self.min_refresh) {
+            debug!(jwks_url = %source.url, "JWKS fetched recently, not refreshing");
+            return Ok(());
+        }
+
+        match self.fetch(&source.url).await {
+            Ok(keys) => {
+                debug!(jwks_url = %source.url, keys = keys.keys.len(), "Fetched JWKS");
+                self.sets.write().await.insert(
+                    source.url.clone(),
+                    CachedKeySet { keys, fetched_at: Instant::now() },
+                );
+                Ok(())
+            }
+            // Keep serving the stale set while the endpoint is unreachable
+            Err(e) if last_fetch.is_some() => {
+                warn!(jwks_url = %source.url, error = %e, "JWKS refresh failed, using cached keys");
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn fetch(&self, url: &str) -> Result<JwkSet> {
+        let response = self.client.get(url).send().await
+            .map_err(|e| ProxyError::auth(format!("Failed to fetch JWKS from {}: {}", url, e)))?;
+        if !response.status().is_success() {
+            return Err(ProxyError::auth(format!("JWKS endpoint {} returned {}", url, response.status())));
+        }
+        response.json::<JwkSet>().await
+            .map_err(|e| ProxyError::auth(format!("Invalid JWKS from {}: {}", url, e)))
+    }
+}
+
+/// Key named by `kid`, or the only key of the set when the token names none
+fn find_key<'a>(keys: &'a JwkSet, kid: Option<&str>) -> Option<&'a Jwk> {
+    match kid {
+        Some(kid) => keys.find(kid),
+        None => match keys.keys.as_slice() {
+            [only] => Some(only),
+            _ => None,
+        },
+    }
+}
+
+fn is_asymmetric(algorithm: Algorithm) -> bool {
+    !matches!(algorithm, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JwtIssuerConfig;
+
+    fn jwks_config() -> JwtConfig {
+        JwtConfig {
+            secret: String::new(),
+            algorithm: "RS256".to_string(),
+            expiration: 3600,
+            issuer: Some("https://primary.example.com".to_string()),
+            audience: None,
+            jwks_url: Some("https://primary.example.com/jwks.json".to_string()),
+            trusted_issuers: vec![JwtIssuerConfig {
+                issuer: "https://partner.example.com".to_string(),
+                jwks_url: "https://partner.example.com/jwks.json".to_string(),
+            }],
+            audiences: vec![],
+            jwks_cache_seconds: 3600,
+            jwks_min_refresh_seconds: 30,
+        }
+    }
+
+    #[test]
+    fn test_source_for_issuer() {
+        let cache = JwksCache::from_config(&jwks_config()).unwrap();
+        assert_eq!(
+            cache.source_for(Some("https://partner.example.com")).unwrap().url,
+            "https://partner.example.com/jwks.json"
+        );
+        assert_eq!(
+            cache.source_for(Some("https://primary.example.com")).unwrap().url,
+            "https://primary.example.com/jwks.json"
+        );
+        assert!(cache.source_for(Some("https://other.example.com")).is_none());
+        assert!(cache.source_for(None).is_none());
+
+        let mut secret_config = jwks_config();
+        secret_config.jwks_url = None;
+        secret_config.trusted_issuers.clear();
+        assert!(JwksCache::from_config(&secret_config).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_symmetric_algorithms_rejected() {
+        let config = jwks_config();
+        let cache = JwksCache::from_config(&config).unwrap();
+        let source = cache.source_for(config.issuer.as_deref()).unwrap().clone();
+        assert!(cache.decoding_key(&source, Some("key-1"), Algorithm::HS256).await.is_err());
+    }
+}
@@ -1,10 +1,12 @@
 //! JWT authentication for MCP Proxy
 
+use crate::auth::JwksCache;
 use crate::config::JwtConfig;
 use crate::error::{ProxyError, Result};
 use actix_web::HttpRequest;
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
-use serde::{Deserialize, Serialize};
+use base64::Engine;
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{debug, error, warn};
 use urlencoding;
@@ -20,12 +22,32 @@ pub struct JwtClaims {
     pub exp: u64,
     /// Issuer
     pub iss: Option<String>,
-    /// Audience
+    /// Audience (the first one, for tokens issued for several)
+    #[serde(default, deserialize_with = "deserialize_audience")]
     pub aud: Option<String>,
     /// User permissions
     pub permissions: Option<Vec<String>>,
     /// Additional user information
     pub user_info: Option<JwtUserInfo>,
+    /// Other claims, such as identity provider scopes and groups
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Accept `aud` as a single string or as an array of them
+fn deserialize_audience<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Audience {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Option::<Audience>::deserialize(deserializer)? {
+        Some(Audience::One(aud)) => Some(aud),
+        Some(Audience::Many(auds)) => auds.into_iter().next(),
+        None => None,
+    })
 }
 
 /// User information embedded in JWT
@@ -76,12 +98,19 @@ pub struct JwtValidator {
     algorithm: Option<Algorithm>,
     /// Token validation rules
     validation: Option<Validation>,
+    /// Keys fetched from the identity providers' JWKS endpoints
+    jwks: Option<JwksCache>,
 }
 
 impl JwtValidator {
     /// Create a new JWT validator
     pub fn new(config: Option<JwtConfig>) -> Result<Self> {
-        let (decoding_key, encoding_key, algorithm, validation) = if let Some(ref jwt_config) = config {
+        let jwks = config.as_ref().and_then(JwksCache::from_config);
+
+        let (decoding_key, encoding_key, algorithm, validation) = if jwks.is_some() {
+            // Keys come from the JWKS endpoints; tokens are verified per key
+            (None, None, None, None)
+        } else if let Some(ref jwt_config) = config {
             // Parse algorithm
             let algorithm = Self::parse_algorithm(&jwt_config.algorithm)?;
             
@@ -129,8 +158,9 @@ impl JwtValidator {
             if let Some(ref issuer) = jwt_config.issuer {
                 validation.set_issuer(&[issuer]);
             }
-            if let Some(ref audience) = jwt_config.audience {
-                validation.set_audience(&[audience]);
+            let audiences = jwt_config.accepted_audiences();
+            if !audiences.is_empty() {
+                validation.set_audience(&audiences);
             }
 
             (Some(decoding_key), Some(encoding_key), Some(algorithm), Some(validation))
@@ -144,6 +174,7 @@ impl JwtValidator {
             encoding_key,
             algorithm,
             validation,
+            jwks,
         })
     }
 
@@ -172,7 +203,7 @@ impl JwtValidator {
     }
 
     /// Validate a JWT token from an HTTP request
-    pub fn validate_request(&self, req: &HttpRequest) -> Result<Option<JwtValidationResult>> {
+    pub async fn validate_request(&self, req: &HttpRequest) -> Result<Option<JwtValidationResult>> {
         // If JWT is not configured, return None
         let jwt_config = match &self.config {
             Some(config) => config,
@@ -185,8 +216,24 @@ impl JwtValidator {
         // Extract token from Authorization header
         let token = self.extract_token_from_request(req)?;
 
-        // Validate the token
-        self.validate_token(&token, jwt_config)
+        self.validate_bearer_token(&token, jwt_config).await
+    }
+
+    /// Validate a JWT obtained outside an HTTP request, `None` if JWT is not configured
+    pub async fn validate_standalone_token(&self, token: &str) -> Result<Option<JwtValidationResult>> {
+        match &self.config {
+            Some(jwt_config) => self.validate_bearer_token(token, jwt_config).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Validate a token with the identity providers' keys or the configured secret
+    async fn validate_bearer_token(&self, token: &str, jwt_config: &JwtConfig) -> Result<Option<JwtValidationResult>> {
+        if let Some(jwks) = &self.jwks {
+            self.validate_jwks_token(token, jwt_config, jwks).await
+        } else {
+            self.validate_token(token, jwt_config)
+        }
     }
 
     /// Extract JWT token from HTTP request
@@ -232,8 +279,54 @@ impl JwtValidator {
                 ProxyError::auth("Invalid JWT token")
             })?;
 
-        let claims = token_data.claims;
+        Ok(Some(Self::validation_result(token_data.claims)))
+    }
+
+    /// Validate a JWT token against the keys of the issuer it names
+    async fn validate_jwks_token(&self, token: &str, jwt_config: &JwtConfig, jwks: &JwksCache) -> Result<Option<JwtValidationResult>> {
+        let header = decode_header(token).map_err(|e| {
+            warn!("JWT header invalid: {}", e);
+            ProxyError::auth("Invalid JWT token")
+        })?;
+
+        // The issuer picks the key set; the signature check below confirms it
+        let issuer = Self::unverified_issuer(token);
+        let source = jwks.source_for(issuer.as_deref()).ok_or_else(|| {
+            warn!(issuer = ?issuer, "JWT from untrusted issuer");
+            ProxyError::auth("Untrusted JWT issuer")
+        })?;
+        let decoding_key = jwks.decoding_key(source, header.kid.as_deref(), header.alg).await?;
+
+        let mut validation = Validation::new(header.alg);
+        if let Some(ref issuer) = source.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        let audiences = jwt_config.accepted_audiences();
+        if audiences.is_empty() {
+            validation.validate_aud = false;
+        } else {
+            validation.set_audience(&audiences);
+        }
+
+        let token_data = decode::<JwtClaims>(token, &decoding_key, &validation)
+            .map_err(|e| {
+                warn!("JWT validation failed: {}", e);
+                ProxyError::auth("Invalid JWT token")
+            })?;
+
+        Ok(Some(Self::validation_result(token_data.claims)))
+    }
 
+    /// Read the `iss` claim of a token before its signature is verified
+    fn unverified_issuer(token: &str) -> Option<String> {
+        let payload = token.split('.').nth(1)?;
+        let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(payload).ok()?;
+        let claims: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+        claims.get("iss")?.as_str().map(str::to_string)
+    }
+
+    /// Build the validation result for verified claims
+    fn validation_result(claims: JwtClaims) -> JwtValidationResult {
         // Extract user information
         let user_info = claims.user_info.clone().unwrap_or_else(|| JwtUserInfo {
             id: claims.sub.clone(),
@@ -247,11 +340,11 @@ impl JwtValidator {
 
         debug!("JWT validation successful for user: {}", user_info.id);
 
-        Ok(Some(JwtValidationResult {
+        JwtValidationResult {
             claims,
             user_info,
             permissions,
-        }))
+        }
     }
 
     /// Generate a JWT token
@@ -278,6 +371,7 @@ impl JwtValidator {
             aud: jwt_config.audience.clone(),
             permissions: Some(permissions),
             user_info,
+            extra: serde_json::Map::new(),
         };
 
         let header = Header::new(algorithm);
@@ -307,6 +401,11 @@ mod tests {
             expiration: 3600,
             issuer: Some("test-issuer".to_string()),
             audience: Some("test-audience".to_string()),
+            jwks_url: None,
+            trusted_issuers: vec![],
+            audiences: vec![],
+            jwks_cache_seconds: 3600,
+            jwks_min_refresh_seconds: 30,
         }
     }
 
@@ -0,0 +1,439 @@
+//! API key rotation and expiry tracking
+//!
+//! Rotating a key issues a new random secret for it. The replaced secret
+//! stays valid for a grace period, so clients can switch over without an
+//! outage, and is refused afterwards. Rotations are persisted as SHA-256
+//! hashes (`auth.api_keys.rotation.state_path`): the new secret is only ever
+//! returned by the rotation call itself.
+//!
+//! Rotations, uses of a replaced secret and keys nearing or past their expiry
+//! are recorded as audit events, logged under the `audit` target and kept in
+//! memory for the dashboard.
+
+use crate::config::{write_atomic, ApiKeyEntry, ApiKeyRotationConfig};
+use crate::error::{ProxyError, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+/// Audit events kept in memory
+const MAX_EVENTS: usize = 200;
+
+/// Current secret of a rotated key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotatedKey {
+    /// SHA-256 of the secret, hex encoded
+    pub key_hash: String,
+    /// When the secret was issued
+    pub rotated_at: DateTime<Utc>,
+    /// When the secret expires, replacing the configured `expires_at`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A replaced secret, valid until the end of its grace period
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetiredKey {
+    /// Name of the key the secret belonged to
+    pub name: String,
+    /// SHA-256 of the secret, hex encoded
+    pub key_hash: String,
+    /// End of the grace period
+    pub valid_until: DateTime<Utc>,
+}
+
+/// Rotation state persisted between restarts
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyRotationState {
+    /// Current secrets of rotated keys, by key name
+    #[serde(default)]
+    pub keys: BTreeMap<String, RotatedKey>,
+    /// Replaced secrets still in their grace period
+    #[serde(default)]
+    pub retired: Vec<RetiredKey>,
+}
+
+/// Options of a rotation request
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct KeyRotationRequest {
+    /// Expiry of the new secret (default: `key_lifetime_days` from now)
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Grace period of the replaced secret (default: `grace_period_seconds`)
+    #[serde(default)]
+    pub grace_period_seconds: Option<u64>,
+}
+
+/// Result of a rotation; the only place the new secret appears
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyRotation {
+    pub name: String,
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Until when the previous secret keeps working
+    pub previous_valid_until: DateTime<Utc>,
+}
+
+/// Kind of key lifecycle audit event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyEventKind {
+    /// A new secret was issued
+    Rotated,
+    /// A replaced secret was used during its grace period
+    RetiredKeyUsed,
+    /// A replaced secret's grace period ended
+    GracePeriodEnded,
+    /// A key is within the expiry warning window
+    ExpiringSoon,
+    /// A key expired
+    Expired,
+}
+
+/// Key lifecycle audit event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyAuditEvent {
+    pub timestamp: DateTime<Utc>,
+    pub key_name: String,
+    pub kind: KeyEventKind,
+    pub message: String,
+}
+
+/// Lifecycle of a configured key, without its secret
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyLifecycle {
+    pub name: String,
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotated_at: Option<DateTime<Utc>>,
+    /// End of the grace period of each replaced secret still accepted
+    pub retired_valid_until: Vec<DateTime<Utc>>,
+    /// `ok`, `expiring` or `expired`
+    pub status: &'static str,
+}
+
+/// Rotated secrets and key lifecycle events
+#[derive(Debug)]
+pub struct ApiKeyStore {
+    path: Option<PathBuf>,
+    config: ApiKeyRotationConfig,
+    state: RwLock<ApiKeyRotationState>,
+    events: Mutex<VecDeque<KeyAuditEvent>>,
+    /// Keys (and status) whose expiry warning was already recorded
+    warned: Mutex<HashSet<(String, &'static str)>>,
+}
+
+impl ApiKeyStore {
+    /// Open the store, loading earlier rotations from `config.state_path` if it exists
+    pub fn open(config: ApiKeyRotationConfig) -> Result<Self> {
+        let path = PathBuf::from(&config.state_path);
+        let state = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| ProxyError::auth(format!("Failed to read API key state '{}': {}", path.display(), e)))?;
+            serde_json::from_str(&content)
+                .map_err(|e| ProxyError::auth(format!("Invalid API key state '{}': {}", path.display(), e)))?
+        } else {
+            ApiKeyRotationState::default()
+        };
+
+        Ok(Self::with_state(Some(path), config, state))
+    }
+
+    /// Store that keeps rotations in memory only
+    pub fn in_memory(config: ApiKeyRotationConfig) -> Self {
+        Self::with_state(None, config, ApiKeyRotationState::default())
+    }
+
+    fn with_state(path: Option<PathBuf>, config: ApiKeyRotationConfig, state: ApiKeyRotationState) -> Self {
+        Self {
+            path,
+            config,
+            state: RwLock::new(state),
+            events: Mutex::new(VecDeque::new()),
+            warned: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Find the configured key a presented secret belongs to
+    ///
+    /// The returned entry carries the expiry of the current secret. Replaced
+    /// secrets are accepted until their grace period ends.
+    pub fn resolve(&self, api_key: &str, configured: &[ApiKeyEntry]) -> Option<ApiKeyEntry> {
+        let key_hash = hash_key(api_key);
+        let state = self.state.read().ok()?;
+
+        let current = configured.iter().find(|entry| match state.keys.get(&entry.name) {
+            Some(rotated) => rotated.key_hash == key_hash,
+            None => entry.key == api_key,
+        });
+        if let Some(entry) = current {
+            return Some(effective_entry(entry, state.keys.get(&entry.name)));
+        }
+
+        let now = Utc::now();
+        let retired = state.retired.iter().find(|retired| retired.key_hash == key_hash && retired.valid_until > now)?;
+        let entry = configured.iter().find(|entry| entry.name == retired.name)?;
+        let entry = effective_entry(entry, state.keys.get(&entry.name));
+        let valid_until = retired.valid_until;
+        drop(state);
+
+        self.record(&entry.name, KeyEventKind::RetiredKeyUsed, format!(
+            "Replaced secret of API key '{}' used; it stops working at {}", entry.name, valid_until.to_rfc3339()
+        ));
+        Some(entry)
+    }
+
+    /// Issue a new secret for the configured key `name`
+    pub fn rotate(&self, name: &str, configured: &[ApiKeyEntry], request: &KeyRotationRequest) -> Result<KeyRotation> {
+        let entry = configured.iter().find(|entry| entry.name == name)
+            .ok_or_else(|| ProxyError::validation(format!("Unknown API key '{}'", name)))?;
+
+        let now = Utc::now();
+        if request.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(ProxyError::validation("Expiry of the new key must be in the future".to_string()));
+        }
+        let grace_period = request.grace_period_seconds.unwrap_or(self.config.grace_period_seconds);
+        let previous_valid_until = now + Duration::seconds(grace_period.min(u64::from(u32::MAX)) as i64);
+        let expires_at = request.expires_at
+            .or_else(|| self.config.key_lifetime_days.map(|days| now + Duration::days(i64::from(days))));
+
+        let key = generate_key();
+        self.update(|state| {
+            let previous_hash = state.keys.get(name)
+                .map(|rotated| rotated.key_hash.clone())
+                .unwrap_or_else(|| hash_key(&entry.key));
+            state.retired.push(RetiredKey { name: name.to_string(), key_hash: previous_hash, valid_until: previous_valid_until });
+            state.keys.insert(name.to_string(), RotatedKey { key_hash: hash_key(&key), rotated_at: now, expires_at });
+        })?;
+        if let Ok(mut warned) = self.warned.lock() {
+            warned.retain(|(key_name, _)| key_name != name);
+        }
+
+        self.record(name, KeyEventKind::Rotated, format!(
+            "API key '{}' rotated; the previous secret stays valid until {}", name, previous_valid_until.to_rfc3339()
+        ));
+        Ok(KeyRotation { name: name.to_string(), key, expires_at, previous_valid_until })
+    }
+
+    /// Lifecycle of every configured key, recording expiry warnings and ended grace periods
+    pub fn lifecycle(&self, configured: &[ApiKeyEntry]) -> Vec<KeyLifecycle> {
+        self.prune_retired();
+        let Ok(state) = self.state.read().map(|state| state.clone()) else {
+            return Vec::new();
+        };
+
+        configured.iter().map(|entry| {
+            let entry = effective_entry(entry, state.keys.get(&entry.name));
+            let expires_at = expiry(&entry);
+            let status = self.check_expiry(&entry);
+            KeyLifecycle {
+                name: entry.name.clone(),
+                active: entry.active,
+                expires_at,
+                rotated_at: state.keys.get(&entry.name).map(|rotated| rotated.rotated_at),
+                retired_valid_until: state.retired.iter()
+                    .filter(|retired| retired.name == entry.name)
+                    .map(|retired| retired.valid_until)
+                    .collect(),
+                status,
+            }
+        }).collect()
+    }
+
+    /// `ok`, `expiring` or `expired`, recording an audit event the first time a key
+    /// enters the warning window or expires
+    pub fn check_expiry(&self, entry: &ApiKeyEntry) -> &'static str {
+        let Some(expires_at) = expiry(entry) else {
+            return "ok";
+        };
+        let now = Utc::now();
+        let (status, kind, message) = if expires_at <= now {
+            ("expired", KeyEventKind::Expired, format!("API key '{}' expired at {}", entry.name, expires_at.to_rfc3339()))
+        } else if expires_at - now <= Duration::days(i64::from(self.config.expiry_warning_days)) {
+            ("expiring", KeyEventKind::ExpiringSoon, format!("API key '{}' expires at {}", entry.name, expires_at.to_rfc3339()))
+        } else {
+            return "ok";
+        };
+
+        let first = self.warned.lock()
+            .map(|mut warned| warned.insert((entry.name.clone(), status)))
+            .unwrap_or(false);
+        if first {
+            self.record(&entry.name, kind, message);
+        }
+        status
+    }
+
+    /// Recent audit events, newest first
+    pub fn events(&self, limit: usize) -> Vec<KeyAuditEvent> {
+        self.events.lock()
+            .map(|events| events.iter().rev().take(limit).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Drop replaced secrets whose grace period ended
+    fn prune_retired(&self) {
+        let now = Utc::now();
+        let ended: Vec<String> = match self.state.read() {
+            Ok(state) => state.retired.iter().filter(|retired| retired.valid_until <= now).map(|retired| retired.name.clone()).collect(),
+            Err(_) => return,
+        };
+        if ended.is_empty() {
+            return;
+        }
+        if let Err(e) = self.update(|state| state.retired.retain(|retired| retired.valid_until > now)) {
+            warn!("Failed to prune replaced API keys: {}", e);
+            return;
+        }
+        for name in ended {
+            self.record(&name, KeyEventKind::GracePeriodEnded, format!("Grace period of a replaced secret of API key '{}' ended", name));
+        }
+    }
+
+    fn record(&self, key_name: &str, kind: KeyEventKind, message: String) {
+        match kind {
+            KeyEventKind::Rotated | KeyEventKind::GracePeriodEnded => info!(target: "audit", api_key_name = %key_name, event = ?kind, "{}", message),
+            _ => warn!(target: "audit", api_key_name = %key_name, event = ?kind, "{}", message),
+        }
+        if let Ok(mut events) = self.events.lock() {
+            events.push_back(KeyAuditEvent { timestamp: Utc::now(), key_name: key_name.to_string(), kind, message });
+            while events.len() > MAX_EVENTS {
+                events.pop_front();
+            }
+        }
+    }
+
+    fn update(&self, change: impl FnOnce(&mut ApiKeyRotationState)) -> Result<()> {
+        let mut state = self.state.write()
+            .map_err(|_| ProxyError::auth("API key state lock poisoned"))?;
+        let mut updated = state.clone();
+        change(&mut updated);
+        self.save(&updated)?;
+        *state = updated;
+        Ok(())
+    }
+
+    fn save(&self, state: &ApiKeyRotationState) -> Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ProxyError::auth(format!("Failed to create '{}': {}", parent.display(), e)))?;
+        }
+        let content = serde_json::to_string_pretty(state)?;
+        write_atomic(path, content.as_bytes(), true)
+            .map_err(|e| ProxyError::auth(format!("Failed to write API key state '{}': {}", path.display(), e)))
+    }
+}
+
+/// The configured entry with the expiry of its current secret
+fn effective_entry(entry: &ApiKeyEntry, rotated: Option<&RotatedKey>) -> ApiKeyEntry {
+    let mut entry = entry.clone();
+    if let Some(rotated) = rotated {
+        entry.expires_at = rotated.expires_at.map(|expires_at| expires_at.to_rfc3339());
+    }
+    entry
+}
+
+fn expiry(entry: &ApiKeyEntry) -> Option<DateTime<Utc>> {
+    entry.expires_at.as_deref()
+        .and_then(|expires_at| DateTime::parse_from_rfc3339(expires_at).ok())
+        .map(|expires_at| expires_at.with_timezone(&Utc))
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// 256 bits from the OS random number generator
+fn generate_key() -> String {
+    format!("mt_{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> Vec<ApiKeyEntry> {
+        vec![ApiKeyEntry::new("ci_bot_key_1234567890".to_string(), "ci-bot".to_string())]
+    }
+
+    #[test]
+    fn test_rotation_keeps_previous_secret_for_grace_period() {
+        let store = ApiKeyStore::in_memory(ApiKeyRotationConfig::default());
+        let configured = keys();
+
+        let first = store.rotate("ci-bot", &configured, &KeyRotationRequest::default()).unwrap();
+        assert_eq!(store.resolve(&first.key, &configured).unwrap().name, "ci-bot");
+        assert!(store.resolve("ci_bot_key_1234567890", &configured).is_some());
+        assert_eq!(store.events(10)[0].kind, KeyEventKind::RetiredKeyUsed);
+
+        // Without a grace period the replaced secret stops working at once
+        let request = KeyRotationRequest { grace_period_seconds: Some(0), ..Default::default() };
+        let second = store.rotate("ci-bot", &configured, &request).unwrap();
+        assert!(store.resolve(&first.key, &configured).is_none());
+        assert!(store.resolve(&second.key, &configured).is_some());
+        assert!(store.resolve("ci_bot_key_1234567890", &configured).is_some());
+
+        assert!(store.rotate("unknown", &configured, &KeyRotationRequest::default()).is_err());
+    }
+
+    #[test]
+    fn test_expiry_warnings() {
+        let config = ApiKeyRotationConfig { key_lifetime_days: Some(7), ..Default::default() };
+        let store = ApiKeyStore::in_memory(config);
+        let mut configured = keys();
+        configured.push(ApiKeyEntry::new("old_key_1234567890".to_string(), "old".to_string()));
+        configured[1].expires_at = Some("2020-01-01T00:00:00Z".to_string());
+
+        let rotation = store.rotate("ci-bot", &configured, &KeyRotationRequest::default()).unwrap();
+        assert!(rotation.expires_at.is_some());
+
+        let lifecycle = store.lifecycle(&configured);
+        assert_eq!(lifecycle[0].status, "expiring");
+        assert!(lifecycle[0].rotated_at.is_some());
+        assert_eq!(lifecycle[0].retired_valid_until.len(), 1);
+        assert_eq!(lifecycle[1].status, "expired");
+
+        // Each warning is recorded once
+        store.lifecycle(&configured);
+        let warnings = store.events(10).iter()
+            .filter(|event| matches!(event.kind, KeyEventKind::ExpiringSoon | KeyEventKind::Expired))
+            .count();
+        assert_eq!(warnings, 2);
+    }
+
+    #[test]
+    fn test_state_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ApiKeyRotationConfig {
+            state_path: dir.path().join("api-keys.json").to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let configured = keys();
+
+        let rotation = ApiKeyStore::open(config.clone()).unwrap()
+            .rotate("ci-bot", &configured, &KeyRotationRequest::default()).unwrap();
+        let content = std::fs::read_to_string(&config.state_path).unwrap();
+        assert!(!content.contains(&rotation.key));
+        // Written through a temporary file that doesn't outlive the save
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&config.state_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let reopened = ApiKeyStore::open(config).unwrap();
+        assert!(reopened.resolve(&rotation.key, &configured).is_some());
+    }
+}
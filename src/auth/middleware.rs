@@ -1,14 +1,16 @@
 //! Authentication middleware for MCP Proxy
 
-use crate::auth::{ApiKeyValidator, JwtValidator, JwtValidationResult, OAuthValidator, OAuthValidationResult};
-use crate::config::{AuthConfig, ApiKeyEntry, AuthType};
+use crate::auth::{ApiKeyValidator, ClientCertIdentity, ClientCertValidator, ClientRegistry, GuestIdentity, GuestValidator, HmacIdentity, HmacValidator, IdentityClaims, JwtValidator, JwtValidationResult, OAuthValidator, OAuthValidationResult, RoleDryRun, RoleMapper};
+use crate::config::{AuthConfig, ApiKeyEntry, AuthType, JwtConfig, ResourceServerConfig, TenantAuthConfig};
 use crate::error::{ProxyError, Result};
 use crate::mcp::errors::McpErrorCode;
+use crate::registry::toolset::ToolScope;
 use crate::routing::middleware::{MiddlewareContext, RouterMiddleware};
 use crate::routing::types::AgentResult;
 use actix_web::{HttpRequest, HttpResponse};
 use async_trait::async_trait;
 use serde_json::json;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
@@ -21,6 +23,12 @@ pub enum AuthenticationResult {
     OAuth(OAuthValidationResult),
     /// JWT authentication result
     Jwt(JwtValidationResult),
+    /// Client certificate authentication result
+    ClientCert(ClientCertIdentity),
+    /// Request without credentials served with guest access
+    Guest(GuestIdentity),
+    /// HMAC signed request authentication result
+    Hmac(HmacIdentity),
 }
 
 impl AuthenticationResult {
@@ -34,6 +42,75 @@ impl AuthenticationResult {
                 vec!["read".to_string(), "write".to_string()]
             }
             AuthenticationResult::Jwt(jwt_result) => jwt_result.permissions.clone(),
+            AuthenticationResult::ClientCert(identity) => identity.permissions.clone(),
+            AuthenticationResult::Guest(identity) => identity.permissions.clone(),
+            AuthenticationResult::Hmac(identity) => identity.permissions.clone(),
+        }
+    }
+
+    /// Toolset the authenticated caller is restricted to, if any
+    pub fn toolset(&self) -> Option<&str> {
+        match self {
+            AuthenticationResult::ApiKey(key_entry) => key_entry.toolset.as_deref(),
+            AuthenticationResult::ClientCert(identity) => identity.toolset.as_deref(),
+            AuthenticationResult::Guest(identity) => identity.toolset.as_deref(),
+            AuthenticationResult::Hmac(identity) => identity.toolset.as_deref(),
+            AuthenticationResult::OAuth(_) | AuthenticationResult::Jwt(_) => None,
+        }
+    }
+
+    /// Toolset and scope patterns limiting what the caller can see and call
+    pub fn tool_scope(&self) -> ToolScope {
+        let patterns = match self {
+            AuthenticationResult::ApiKey(key_entry) => key_entry.scopes.clone(),
+            AuthenticationResult::Guest(identity) => identity.scopes.clone(),
+            AuthenticationResult::OAuth(_) | AuthenticationResult::Jwt(_) | AuthenticationResult::ClientCert(_) | AuthenticationResult::Hmac(_) => Vec::new(),
+        };
+        ToolScope { patterns, ..ToolScope::bound(self.toolset()) }
+    }
+
+    /// Name of the API key used, for API key authentication
+    pub fn api_key_name(&self) -> Option<&str> {
+        match self {
+            AuthenticationResult::ApiKey(key_entry) => Some(&key_entry.name),
+            AuthenticationResult::OAuth(_) | AuthenticationResult::Jwt(_) | AuthenticationResult::ClientCert(_) | AuthenticationResult::Guest(_) | AuthenticationResult::Hmac(_) => None,
+        }
+    }
+
+    /// Client the caller's calls count against for rate limits and budgets: its API key, or guest access
+    pub fn client_name(&self) -> Option<&str> {
+        match self {
+            AuthenticationResult::Guest(_) => Some(crate::routing::rate_limit::GUEST_CLIENT),
+            _ => self.api_key_name(),
+        }
+    }
+
+    /// Token upstream calls can be made on behalf of the user with (OAuth and JWT bearer tokens)
+    pub fn subject_token(&self, req: &HttpRequest) -> Option<String> {
+        match self {
+            AuthenticationResult::OAuth(_) | AuthenticationResult::Jwt(_) => crate::auth::bearer_token(req),
+            AuthenticationResult::ApiKey(_) | AuthenticationResult::ClientCert(_) | AuthenticationResult::Guest(_) | AuthenticationResult::Hmac(_) => None,
+        }
+    }
+
+    /// Roles of an OAuth or JWT user, including those granted by the role mappings
+    pub fn roles(&self) -> Vec<String> {
+        match self {
+            AuthenticationResult::OAuth(oauth_result) => oauth_result.roles.clone(),
+            AuthenticationResult::Jwt(jwt_result) => jwt_result.user_info.roles.clone().unwrap_or_default(),
+            AuthenticationResult::ApiKey(_) | AuthenticationResult::ClientCert(_) | AuthenticationResult::Guest(_) | AuthenticationResult::Hmac(_) => Vec::new(),
+        }
+    }
+
+    /// Authentication method that accepted the request
+    pub fn method(&self) -> &'static str {
+        match self {
+            AuthenticationResult::ApiKey(_) => "api_key",
+            AuthenticationResult::OAuth(_) => "oauth",
+            AuthenticationResult::Jwt(_) => "jwt",
+            AuthenticationResult::ClientCert(_) => "client_cert",
+            AuthenticationResult::Hmac(_) => "hmac",
+            AuthenticationResult::Guest(_) => "guest",
         }
     }
 
@@ -43,6 +120,9 @@ impl AuthenticationResult {
             AuthenticationResult::ApiKey(key_entry) => key_entry.name.clone(),
             AuthenticationResult::OAuth(oauth_result) => oauth_result.user_info.id.clone(),
             AuthenticationResult::Jwt(jwt_result) => jwt_result.user_info.id.clone(),
+            AuthenticationResult::ClientCert(identity) => identity.get_user_id(),
+            AuthenticationResult::Guest(identity) => identity.get_user_id(),
+            AuthenticationResult::Hmac(identity) => identity.get_user_id(),
         }
     }
 }
@@ -55,33 +135,122 @@ pub struct AuthenticationMiddleware {
     oauth_validator: OAuthValidator,
     /// JWT validator
     jwt_validator: JwtValidator,
+    /// Client certificate validator
+    client_cert_validator: ClientCertValidator,
+    /// HMAC request signature validator
+    hmac_validator: HmacValidator,
+    /// Guest access for requests without credentials
+    guest_validator: GuestValidator,
+    /// Authentication methods tried for each request, in order
+    methods: Vec<AuthType>,
+    /// Protected resource metadata advertised to clients
+    resource_server: ResourceServerConfig,
+    /// Issuers of the JWTs accepted, the default authorization servers
+    jwt_issuers: Vec<String>,
+    /// Dynamically registered OAuth clients, when registration is enabled
+    client_registry: Option<ClientRegistry>,
+    /// Permissions granted to each role
+    roles: BTreeMap<String, Vec<String>>,
+    /// Roles granted by provider scopes and claims
+    role_mapper: RoleMapper,
+    /// Tenants authenticated with their own configuration
+    tenants: Vec<Tenant>,
     /// Whether to log authentication events
     log_auth_events: bool,
 }
 
+/// Authentication of the requests made to one tenant
+struct Tenant {
+    config: TenantAuthConfig,
+    middleware: AuthenticationMiddleware,
+}
+
 impl AuthenticationMiddleware {
     /// Create new authentication middleware
     pub fn new(config: AuthConfig) -> Result<Self> {
-        let jwt_validator = JwtValidator::new(config.jwt.clone())?;
-        Ok(Self {
-            api_key_validator: ApiKeyValidator::new(config.clone()),
-            oauth_validator: OAuthValidator::new(config.clone()),
-            jwt_validator,
-            log_auth_events: true,
-        })
+        Self::with_logging(config, true)
     }
 
     /// Create new authentication middleware with logging configuration
     pub fn with_logging(config: AuthConfig, log_auth_events: bool) -> Result<Self> {
-        let jwt_validator = JwtValidator::new(config.jwt.clone())?;
+        let jwt_validator = JwtValidator::new(Self::jwt_config(&config))?;
+        let jwt_issuers = config.jwt.as_ref().map_or_else(Vec::new, |jwt| {
+            jwt.issuer.iter().cloned().chain(jwt.trusted_issuers.iter().map(|trusted| trusted.issuer.clone())).collect()
+        });
+        let client_registry = match config.client_registration.clone().filter(|registration| registration.enabled) {
+            Some(registration) => Some(ClientRegistry::open(registration)?),
+            None => None,
+        };
+        let tenants = config.tenants.iter()
+            .map(|tenant| Ok(Tenant {
+                config: tenant.clone(),
+                middleware: Self::with_logging(tenant.auth.clone(), log_auth_events)?,
+            }))
+            .collect::<Result<Vec<_>>>()?;
         Ok(Self {
-            api_key_validator: ApiKeyValidator::new(config.clone()),
+            api_key_validator: ApiKeyValidator::open(config.clone())?,
             oauth_validator: OAuthValidator::new(config.clone()),
             jwt_validator,
+            client_cert_validator: ClientCertValidator::new(config.clone()),
+            hmac_validator: HmacValidator::new(config.clone()),
+            guest_validator: GuestValidator::new(&config),
+            methods: Self::methods(&config),
+            resource_server: config.resource_server.unwrap_or_default(),
+            jwt_issuers,
+            client_registry,
+            roles: config.roles.clone(),
+            role_mapper: RoleMapper::new(config.role_mappings.clone()),
+            tenants,
             log_auth_events,
         })
     }
 
+    /// Middleware authenticating `req`: that of the tenant the request is made to, or this one
+    pub fn for_request(&self, req: &HttpRequest) -> &AuthenticationMiddleware {
+        let host = req.connection_info().host().to_string();
+        match self.tenants.iter().find(|tenant| tenant.config.matches(&host, req.path())) {
+            Some(tenant) => {
+                debug!(tenant = %tenant.config.name, "Authenticating request with tenant configuration");
+                &tenant.middleware
+            }
+            None => self,
+        }
+    }
+
+    /// Authentication methods to try: those configured, or every method in turn,
+    /// each validator only accepting credentials when configured
+    fn methods(config: &AuthConfig) -> Vec<AuthType> {
+        if config.methods.is_empty() {
+            vec![AuthType::ApiKey, AuthType::OAuth, AuthType::Jwt, AuthType::ClientCert, AuthType::Hmac]
+        } else {
+            config.methods.clone()
+        }
+    }
+
+    /// JWT configuration, accepting tokens issued for this server's resource URI
+    fn jwt_config(config: &AuthConfig) -> Option<JwtConfig> {
+        let mut jwt = config.jwt.clone()?;
+        if let Some(resource) = config.resource_server.as_ref().and_then(|server| server.resource.clone()) {
+            jwt.audiences.push(resource);
+        }
+        Some(jwt)
+    }
+
+    /// API key validator, for key rotation and lifecycle reports
+    pub fn api_keys(&self) -> &ApiKeyValidator {
+        &self.api_key_validator
+    }
+
+    /// Dynamically registered OAuth clients, if registration is enabled
+    pub fn client_registry(&self) -> Option<&ClientRegistry> {
+        self.client_registry.as_ref()
+    }
+
+    /// Tenant names and the middleware authenticating their requests
+    pub fn tenants(&self) -> impl Iterator<Item = (&str, &AuthenticationMiddleware)> {
+        self.tenants.iter().map(|tenant| (tenant.config.name.as_str(), &tenant.middleware))
+    }
+
     /// Validate authentication for an HTTP request
     pub async fn validate_http_request(&self, req: &HttpRequest) -> Result<Option<AuthenticationResult>> {
         // If authentication is disabled, allow all requests
@@ -90,106 +259,136 @@ impl AuthenticationMiddleware {
             return Ok(None);
         }
 
-        let mut api_key_error: Option<crate::error::ProxyError> = None;
-        let mut oauth_error: Option<crate::error::ProxyError> = None;
-        let mut jwt_error: Option<crate::error::ProxyError> = None;
-
-        // Try API key authentication first
-        match self.api_key_validator.validate_request(req) {
-            Ok(Some(key_entry)) => {
-                if self.log_auth_events {
-                    info!(
-                        api_key_name = %key_entry.name,
-                        permissions = ?key_entry.permissions,
-                        auth_type = "api_key",
-                        "Authentication successful"
-                    );
+        if let Some(guest) = self.guest_validator.validate_request(req) {
+            return Ok(Some(AuthenticationResult::Guest(guest)));
+        }
+
+        // Try each method in order; the first accepting the request's credentials authenticates it
+        let mut errors = Vec::new();
+        for method in &self.methods {
+            match self.validate_with(method, req).await {
+                Ok(Some(mut auth_result)) => {
+                    self.map_roles(&mut auth_result);
+                    if self.log_auth_events {
+                        info!(
+                            target: "audit",
+                            user_id = %auth_result.get_user_id(),
+                            permissions = ?auth_result.get_permissions(),
+                            auth_method = auth_result.method(),
+                            "Authentication successful"
+                        );
+                    }
+                    return Ok(Some(auth_result));
+                }
+                Ok(None) => {
+                    debug!(auth_method = %method, "Authentication method not configured, trying the next one");
+                }
+                Err(e) => {
+                    debug!(auth_method = %method, "Authentication failed, trying the next method");
+                    errors.push((method, e));
                 }
-                return Ok(Some(AuthenticationResult::ApiKey(key_entry)));
-            }
-            Ok(None) => {
-                // API key auth is disabled or not configured, try OAuth
-                debug!("API key authentication not configured, trying OAuth");
             }
-            Err(e) => {
-                // API key validation failed, store error and try OAuth as fallback
-                debug!("API key validation failed, trying OAuth as fallback");
-                api_key_error = Some(e);
+        }
+
+        // All methods failed or are not configured: report the failure of the first method tried
+        if let Some((method, error)) = errors.into_iter().next() {
+            if self.log_auth_events {
+                warn!(
+                    error = %error,
+                    auth_type = %method,
+                    remote_addr = ?req.connection_info().peer_addr(),
+                    user_agent = ?req.headers().get("user-agent"),
+                    "Authentication failed"
+                );
             }
+            return Err(error);
         }
 
-        // Try OAuth authentication
-        match self.oauth_validator.validate_request(req).await {
-            Ok(Some(oauth_result)) => {
-                if self.log_auth_events {
-                    info!(
-                        user_id = %oauth_result.user_info.id,
-                        user_email = ?oauth_result.user_info.email,
-                        auth_type = "oauth",
-                        "OAuth authentication successful"
-                    );
+        // All methods are not configured, but authentication is enabled
+        // This shouldn't happen in normal configuration, but handle gracefully
+        debug!("Authentication enabled but no authentication methods configured");
+        Ok(None)
+    }
+
+    /// Authenticate a request with one method, `None` if the method is not configured
+    async fn validate_with(&self, method: &AuthType, req: &HttpRequest) -> Result<Option<AuthenticationResult>> {
+        Ok(match method {
+            AuthType::ApiKey => self.api_key_validator.validate_request(req)?.map(AuthenticationResult::ApiKey),
+            AuthType::OAuth => self.oauth_validator.validate_request(req).await?.map(AuthenticationResult::OAuth),
+            AuthType::Jwt => self.jwt_validator.validate_request(req).await?.map(AuthenticationResult::Jwt),
+            AuthType::ClientCert => self.client_cert_validator.validate_request(req)?.map(AuthenticationResult::ClientCert),
+            AuthType::Hmac => self.hmac_validator.validate_request(req)?.map(AuthenticationResult::Hmac),
+            AuthType::None => None,
+        })
+    }
+
+    /// Grant OAuth and JWT users the roles of the role mappings matching their scopes and claims
+    fn map_roles(&self, auth_result: &mut AuthenticationResult) {
+        match auth_result {
+            AuthenticationResult::Jwt(jwt_result) => {
+                let mapped = self.role_mapper.roles_for(&IdentityClaims::from_jwt(jwt_result));
+                let roles = jwt_result.user_info.roles.get_or_insert_with(Vec::new);
+                for role in mapped {
+                    if !roles.contains(&role) {
+                        roles.push(role);
+                    }
                 }
-                return Ok(Some(AuthenticationResult::OAuth(oauth_result)));
             }
-            Ok(None) => {
-                debug!("OAuth authentication disabled or not configured, trying JWT");
-            }
-            Err(e) => {
-                debug!("OAuth authentication failed, trying JWT as fallback");
-                oauth_error = Some(e);
+            AuthenticationResult::OAuth(oauth_result) => {
+                oauth_result.roles = self.role_mapper.roles_for(&IdentityClaims::from_oauth(oauth_result));
             }
+            _ => {}
         }
+    }
 
-        // Try JWT authentication
-        match self.jwt_validator.validate_request(req) {
-            Ok(Some(jwt_result)) => {
-                if self.log_auth_events {
-                    info!(
-                        user_id = %jwt_result.user_info.id,
-                        user_email = ?jwt_result.user_info.email,
-                        auth_type = "jwt",
-                        "JWT authentication successful"
-                    );
-                }
-                return Ok(Some(AuthenticationResult::Jwt(jwt_result)));
-            }
-            Ok(None) => {
-                debug!("JWT authentication disabled or not configured");
+    /// Roles and permissions an OAuth access token or JWT would receive, without authenticating a request
+    pub async fn role_dry_run(&self, token: &str) -> Result<RoleDryRun> {
+        let mut auth_result = None;
+        let mut errors = Vec::new();
+        if self.methods.contains(&AuthType::Jwt) {
+            match self.jwt_validator.validate_standalone_token(token).await {
+                Ok(jwt_result) => auth_result = jwt_result.map(AuthenticationResult::Jwt),
+                Err(e) => errors.push(e),
             }
-            Err(e) => {
-                debug!("JWT authentication failed");
-                jwt_error = Some(e);
+        }
+        if auth_result.is_none() && self.methods.contains(&AuthType::OAuth) && self.oauth_validator.config().uses(&AuthType::OAuth) {
+            match self.oauth_validator.validate_token(token).await {
+                Ok(oauth_result) => auth_result = oauth_result.map(AuthenticationResult::OAuth),
+                Err(e) => errors.push(e),
             }
         }
+        let Some(mut auth_result) = auth_result else {
+            return Err(errors.into_iter().next()
+                .unwrap_or_else(|| ProxyError::auth("Neither OAuth nor JWT authentication is configured")));
+        };
 
-        // If we reach here, all authentication methods failed or are not configured
-        // Determine which error to return based on what was attempted
-        let errors = [
-            ("API key", api_key_error.as_ref()),
-            ("OAuth", oauth_error.as_ref()),
-            ("JWT", jwt_error.as_ref()),
-        ];
-
-        // Find the first error to return (prioritize API key, then OAuth, then JWT)
-        for (auth_type, error_opt) in &errors {
-            if let Some(error) = error_opt {
-                if self.log_auth_events {
-                    warn!(
-                        error = %error,
-                        auth_type = auth_type,
-                        remote_addr = ?req.connection_info().peer_addr(),
-                        user_agent = ?req.headers().get("user-agent"),
-                        "Authentication failed"
-                    );
-                }
-                return Err((*error).clone());
+        let claims = match &auth_result {
+            AuthenticationResult::Jwt(jwt_result) => IdentityClaims::from_jwt(jwt_result),
+            AuthenticationResult::OAuth(oauth_result) => IdentityClaims::from_oauth(oauth_result),
+            _ => IdentityClaims::default(),
+        };
+        self.map_roles(&mut auth_result);
+        let roles = match &auth_result {
+            AuthenticationResult::Jwt(jwt_result) => jwt_result.user_info.roles.clone().unwrap_or_default(),
+            AuthenticationResult::OAuth(oauth_result) => oauth_result.roles.clone(),
+            _ => Vec::new(),
+        };
+        let mut permissions = auth_result.get_permissions();
+        for permission in roles.iter().filter_map(|role| self.roles.get(role)).flatten() {
+            if !permissions.contains(permission) {
+                permissions.push(permission.clone());
             }
         }
 
-        // All methods are not configured, but authentication is enabled
-        // This shouldn't happen in normal configuration, but handle gracefully
-        debug!("Authentication enabled but no authentication methods configured");
-        Ok(None)
+        Ok(RoleDryRun {
+            auth_method: auth_result.method(),
+            user_id: auth_result.get_user_id(),
+            matched_mappings: self.role_mapper.matching(&claims),
+            mapped_roles: self.role_mapper.roles_for(&claims),
+            claims,
+            roles,
+            permissions,
+        })
     }
 
     /// Check if an authenticated user has a specific permission
@@ -198,14 +397,23 @@ impl AuthenticationMiddleware {
             AuthenticationResult::ApiKey(key_entry) => {
                 self.api_key_validator.check_permission(key_entry, permission)
             }
-            AuthenticationResult::OAuth(_oauth_result) => {
-                // For OAuth, check against default permissions
-                // In a real implementation, you might want to map OAuth scopes to permissions
+            AuthenticationResult::OAuth(oauth_result) => {
+                // For OAuth, check against default permissions and those of the mapped roles
                 let permissions = auth_result.get_permissions();
-                permissions.contains(&permission.to_string())
+                permissions.contains(&permission.to_string()) || self.role_grants(&oauth_result.roles, permission)
             }
             AuthenticationResult::Jwt(jwt_result) => {
                 self.jwt_validator.check_permission(jwt_result, permission)
+                    || self.role_grants(jwt_result.user_info.roles.as_deref().unwrap_or_default(), permission)
+            }
+            AuthenticationResult::ClientCert(identity) => {
+                self.client_cert_validator.check_permission(identity, permission)
+            }
+            AuthenticationResult::Guest(identity) => {
+                identity.permissions.iter().any(|granted| granted == permission)
+            }
+            AuthenticationResult::Hmac(identity) => {
+                self.hmac_validator.check_permission(identity, permission)
             }
         };
 
@@ -221,6 +429,13 @@ impl AuthenticationMiddleware {
         has_permission
     }
 
+    /// Whether one of `roles` is granted `permission` by the role configuration
+    fn role_grants(&self, roles: &[String], permission: &str) -> bool {
+        roles.iter()
+            .filter_map(|role| self.roles.get(role))
+            .any(|permissions| permissions.iter().any(|granted| granted == permission))
+    }
+
     /// Create an authentication error response for HTTP endpoints
     pub fn create_auth_error_response(&self, error: &ProxyError) -> HttpResponse {
         let error_body = json!({
@@ -236,6 +451,63 @@ impl AuthenticationMiddleware {
             .json(error_body)
     }
 
+    /// Canonical URI of this server, defaulting to the origin the request was made to
+    fn resource_uri(&self, req: &HttpRequest) -> String {
+        self.resource_server.resource.clone().unwrap_or_else(|| {
+            let info = req.connection_info();
+            format!("{}://{}", info.scheme(), info.host())
+        })
+    }
+
+    /// OAuth protected resource metadata (RFC 9728) describing how to authenticate to this server
+    pub fn protected_resource_metadata(&self, req: &HttpRequest) -> serde_json::Value {
+        let authorization_servers = if self.resource_server.authorization_servers.is_empty() {
+            &self.jwt_issuers
+        } else {
+            &self.resource_server.authorization_servers
+        };
+
+        let mut metadata = json!({
+            "resource": self.resource_uri(req),
+            "authorization_servers": authorization_servers,
+            "bearer_methods_supported": ["header"],
+        });
+        if !self.resource_server.scopes_supported.is_empty() {
+            metadata["scopes_supported"] = json!(self.resource_server.scopes_supported);
+        }
+        if let Some(documentation) = &self.resource_server.resource_documentation {
+            metadata["resource_documentation"] = json!(documentation);
+        }
+        metadata
+    }
+
+    /// URL of the protected resource metadata: the well-known path inserted
+    /// between the origin and the path of the resource URI
+    pub fn resource_metadata_url(&self, req: &HttpRequest) -> String {
+        let resource = self.resource_uri(req);
+        match url::Url::parse(&resource) {
+            Ok(url) => format!(
+                "{}/.well-known/oauth-protected-resource{}",
+                url.origin().ascii_serialization(),
+                url.path().trim_end_matches('/')
+            ),
+            Err(_) => format!("{}/.well-known/oauth-protected-resource", resource),
+        }
+    }
+
+    /// `WWW-Authenticate` challenge of a 401 response to `req`
+    pub fn www_authenticate(&self, req: &HttpRequest) -> String {
+        let mut challenge = format!("Bearer resource_metadata=\"{}\"", self.resource_metadata_url(req));
+        if !self.resource_server.scopes_supported.is_empty() {
+            challenge.push_str(&format!(", scope=\"{}\"", self.resource_server.scopes_supported.join(" ")));
+        }
+        // RFC 6750: no error code when the request carried no credentials
+        if req.headers().contains_key("Authorization") {
+            challenge.push_str(", error=\"invalid_token\"");
+        }
+        challenge
+    }
+
     /// Create an authentication error response for MCP protocol
     pub fn create_mcp_auth_error(&self, request_id: Option<&serde_json::Value>, error: &ProxyError) -> String {
         let response = json!({
@@ -355,6 +627,7 @@ mod tests {
             require_header: true,
             header_name: "Authorization".to_string(),
             header_format: "Bearer {key}".to_string(),
+            rotation: Default::default(),
         });
         config
     }
@@ -414,4 +687,57 @@ mod tests {
         assert!(!middleware.check_permission(&read_only_auth, "write"));
         assert!(!middleware.check_permission(&read_only_auth, "admin"));
     }
+
+    #[tokio::test]
+    async fn test_method_chain() {
+        let mut config = create_test_auth_config();
+        config.methods = vec![AuthType::Jwt, AuthType::ApiKey];
+        config.jwt = Some(JwtConfig {
+            secret: "test_secret_key_that_is_at_least_32_characters_long".to_string(),
+            algorithm: "HS256".to_string(),
+            expiration: 3600,
+            issuer: None,
+            audience: None,
+            jwks_url: None,
+            trusted_issuers: vec![],
+            audiences: vec![],
+            jwks_cache_seconds: 3600,
+            jwks_min_refresh_seconds: 30,
+        });
+        let middleware = AuthenticationMiddleware::new(config.clone()).unwrap();
+
+        // An API key is not a JWT, so authentication falls through to API keys
+        let req = TestRequest::default()
+            .insert_header(("Authorization", "Bearer admin_key_123456789"))
+            .to_http_request();
+        let result = middleware.validate_http_request(&req).await.unwrap().unwrap();
+        assert_eq!(result.method(), "api_key");
+
+        let token = middleware.jwt_validator.generate_token("alice", vec!["read".to_string()], None).unwrap();
+        let req = TestRequest::default()
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_http_request();
+        let result = middleware.validate_http_request(&req).await.unwrap().unwrap();
+        assert_eq!(result.method(), "jwt");
+
+        // Methods left out of the chain are not tried
+        config.methods = vec![AuthType::Jwt];
+        let middleware = AuthenticationMiddleware::new(config).unwrap();
+        let req = TestRequest::default()
+            .insert_header(("Authorization", "Bearer admin_key_123456789"))
+            .to_http_request();
+        assert!(middleware.validate_http_request(&req).await.is_err());
+    }
+
+    #[test]
+    fn test_role_grants() {
+        let mut config = create_test_auth_config();
+        config.roles.insert("operator".to_string(), vec!["read".to_string(), "write".to_string()]);
+        let middleware = AuthenticationMiddleware::new(config).unwrap();
+
+        let roles = vec!["viewer".to_string(), "operator".to_string()];
+        assert!(middleware.role_grants(&roles, "write"));
+        assert!(!middleware.role_grants(&roles, "admin"));
+        assert!(!middleware.role_grants(&[], "read"));
+    }
 }
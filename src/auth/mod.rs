@@ -1,14 +1,36 @@
 //! Authentication module for MCP Proxy
 //!
 //! This module provides authentication middleware and utilities for securing
-//! MCP proxy endpoints with API key, OAuth, and JWT authentication.
+//! MCP proxy endpoints with API key, OAuth, JWT, and client certificate authentication.
 
 pub mod api_key;
+pub mod client_cert;
+pub mod client_registration;
+pub mod device_code;
+pub mod guest;
+pub mod hmac;
+pub mod jwks;
 pub mod jwt;
+pub mod key_rotation;
 pub mod middleware;
+pub mod network_policy;
 pub mod oauth;
+pub mod role_mapping;
+pub mod token_encryption;
+pub mod token_exchange;
 
 pub use api_key::*;
+pub use client_cert::*;
+pub use client_registration::*;
+pub use device_code::*;
+pub use guest::*;
+pub use hmac::*;
+pub use jwks::*;
 pub use jwt::*;
+pub use key_rotation::*;
 pub use middleware::*;
+pub use network_policy::*;
 pub use oauth::*;
+pub use role_mapping::*;
+pub use token_encryption::*;
+pub use token_exchange::*;
@@ -0,0 +1,512 @@
+//! Network access policy for MCP, dashboard and gRPC endpoints
+//!
+//! Each endpoint class can allow and deny client networks (CIDR) and, with a
+//! MaxMind GeoIP2/GeoLite2 country database, client countries:
+//!
+//! ```yaml
+//! network_policy:
+//!   geoip_database: /var/lib/GeoIP/GeoLite2-Country.mmdb
+//!   mcp:
+//!     allow: ["10.0.0.0/8"]
+//!     allow_countries: ["DE", "FR"]
+//!     deny: ["10.66.0.0/16"]
+//!   dashboard:
+//!     allow: ["192.168.0.0/16", "127.0.0.1"]
+//!   grpc:
+//!     deny_countries: ["KP"]
+//! ```
+//!
+//! A client in a `deny` network or `deny_countries` country is refused. When
+//! `allow` or `allow_countries` are listed, a client must be in one of the
+//! networks or countries. The client address is the peer address, or, for
+//! requests from `trusted_proxies` (default: `server.tls.trusted_proxies`),
+//! the nearest address in `X-Forwarded-For` that is not a trusted proxy.
+//! Refused requests are logged to the `audit` target. `/health` is never
+//! refused.
+
+use crate::error::{ProxyError, Result};
+use crate::tls::trusted_proxy::CidrRange;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::HttpResponse;
+use futures_util::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use std::rc::Rc;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Header listing the addresses a request was forwarded for
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Networks and countries allowed or denied for an endpoint class
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkRulesConfig {
+    /// Networks clients must be in, unless in `allow_countries`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    /// Networks refused
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+    /// ISO 3166-1 alpha-2 countries clients must be in, unless in `allow`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_countries: Vec<String>,
+    /// ISO 3166-1 alpha-2 countries refused
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny_countries: Vec<String>,
+}
+
+impl NetworkRulesConfig {
+    fn uses_countries(&self) -> bool {
+        !self.allow_countries.is_empty() || !self.deny_countries.is_empty()
+    }
+}
+
+/// Network access policy of the server's endpoints
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkPolicyConfig {
+    /// MaxMind country database (`.mmdb`), needed for country rules
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geoip_database: Option<String>,
+    /// Proxies whose `X-Forwarded-For` is trusted (default: `server.tls.trusted_proxies`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trusted_proxies: Option<Vec<String>>,
+    /// MCP endpoints: everything but the dashboard and `/health`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp: Option<NetworkRulesConfig>,
+    /// Dashboard UI and API under `/dashboard`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dashboard: Option<NetworkRulesConfig>,
+    /// gRPC server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grpc: Option<NetworkRulesConfig>,
+}
+
+impl NetworkPolicyConfig {
+    pub fn validate(&self) -> Result<()> {
+        for proxy in self.trusted_proxies.iter().flatten() {
+            CidrRange::from_str(proxy)
+                .map_err(|e| ProxyError::config(format!("Invalid network_policy.trusted_proxies entry '{}': {}", proxy, e)))?;
+        }
+        for (endpoint, rules) in self.classes() {
+            let Some(rules) = rules else { continue };
+            for cidr in rules.allow.iter().chain(&rules.deny) {
+                CidrRange::from_str(cidr)
+                    .map_err(|e| ProxyError::config(format!("Invalid network_policy.{} network '{}': {}", endpoint, cidr, e)))?;
+            }
+            for country in rules.allow_countries.iter().chain(&rules.deny_countries) {
+                if country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic()) {
+                    return Err(ProxyError::config(format!(
+                        "network_policy.{} country '{}' is not an ISO 3166-1 alpha-2 code", endpoint, country
+                    )));
+                }
+            }
+            if rules.uses_countries() && self.geoip_database.is_none() {
+                return Err(ProxyError::config(format!(
+                    "network_policy.{} restricts countries, which needs network_policy.geoip_database", endpoint
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn classes(&self) -> [(EndpointClass, Option<&NetworkRulesConfig>); 3] {
+        [
+            (EndpointClass::Mcp, self.mcp.as_ref()),
+            (EndpointClass::Dashboard, self.dashboard.as_ref()),
+            (EndpointClass::Grpc, self.grpc.as_ref()),
+        ]
+    }
+}
+
+/// Endpoints sharing network rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointClass {
+    Mcp,
+    Dashboard,
+    Grpc,
+}
+
+impl EndpointClass {
+    /// Class of an HTTP request path; `None` for paths never refused
+    pub fn of_path(path: &str) -> Option<Self> {
+        if path == "/health" {
+            None
+        } else if path == "/dashboard" || path.starts_with("/dashboard/") {
+            Some(EndpointClass::Dashboard)
+        } else {
+            Some(EndpointClass::Mcp)
+        }
+    }
+}
+
+impl std::fmt::Display for EndpointClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            EndpointClass::Mcp => "mcp",
+            EndpointClass::Dashboard => "dashboard",
+            EndpointClass::Grpc => "grpc",
+        })
+    }
+}
+
+/// A client refused by the network policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkDenial {
+    pub endpoint: EndpointClass,
+    /// Client address, if known
+    pub client_ip: Option<IpAddr>,
+    pub country: Option<String>,
+    pub reason: String,
+}
+
+struct Rules {
+    allow: Vec<CidrRange>,
+    deny: Vec<CidrRange>,
+    allow_countries: Vec<String>,
+    deny_countries: Vec<String>,
+}
+
+impl Rules {
+    fn new(config: &NetworkRulesConfig) -> Result<Self> {
+        let networks = |cidrs: &[String]| cidrs.iter().map(|cidr| CidrRange::from_str(cidr)).collect::<Result<Vec<_>>>();
+        let countries = |codes: &[String]| codes.iter().map(|code| code.to_ascii_uppercase()).collect();
+        Ok(Self {
+            allow: networks(&config.allow)?,
+            deny: networks(&config.deny)?,
+            allow_countries: countries(&config.allow_countries),
+            deny_countries: countries(&config.deny_countries),
+        })
+    }
+
+    fn uses_countries(&self) -> bool {
+        !self.allow_countries.is_empty() || !self.deny_countries.is_empty()
+    }
+}
+
+type CountryLookup = Box<dyn Fn(IpAddr) -> Option<String> + Send + Sync>;
+
+/// Network rules of the endpoint classes, checked against client addresses
+pub struct NetworkPolicy {
+    mcp: Option<Rules>,
+    dashboard: Option<Rules>,
+    grpc: Option<Rules>,
+    trusted_proxies: Vec<CidrRange>,
+    countries: Option<CountryLookup>,
+}
+
+impl std::fmt::Debug for NetworkPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NetworkPolicy").finish_non_exhaustive()
+    }
+}
+
+impl NetworkPolicy {
+    /// Build the policy, trusting `default_trusted_proxies` unless the policy lists its own
+    pub fn new(config: &NetworkPolicyConfig, default_trusted_proxies: &[String]) -> Result<Self> {
+        config.validate()?;
+        let rules = |rules: &Option<NetworkRulesConfig>| rules.as_ref().map(Rules::new).transpose();
+        let countries = config.geoip_database.as_deref().map(open_geoip_database).transpose()?;
+        Ok(Self {
+            mcp: rules(&config.mcp)?,
+            dashboard: rules(&config.dashboard)?,
+            grpc: rules(&config.grpc)?,
+            trusted_proxies: config.trusted_proxies.as_deref().unwrap_or(default_trusted_proxies).iter()
+                .map(|proxy| CidrRange::from_str(proxy))
+                .collect::<Result<_>>()?,
+            countries,
+        })
+    }
+
+    fn rules(&self, endpoint: EndpointClass) -> Option<&Rules> {
+        match endpoint {
+            EndpointClass::Mcp => self.mcp.as_ref(),
+            EndpointClass::Dashboard => self.dashboard.as_ref(),
+            EndpointClass::Grpc => self.grpc.as_ref(),
+        }
+    }
+
+    fn is_trusted_proxy(&self, ip: &IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|range| range.contains(ip))
+    }
+
+    /// Client address of a request from `peer` with an `X-Forwarded-For` header
+    pub fn client_ip(&self, peer: IpAddr, forwarded_for: Option<&str>) -> IpAddr {
+        let mut client = peer.to_canonical();
+        if !self.is_trusted_proxy(&client) {
+            return client;
+        }
+        // Walk back from the nearest hop; only trusted proxies' entries are believed
+        for hop in forwarded_for.unwrap_or_default().rsplit(',') {
+            let Some(ip) = parse_hop(hop) else { break };
+            client = ip.to_canonical();
+            if !self.is_trusted_proxy(&client) {
+                break;
+            }
+        }
+        client
+    }
+
+    /// Check a client of `endpoint`, logging refusals to the audit target
+    pub fn check(&self, endpoint: EndpointClass, client_ip: Option<IpAddr>) -> std::result::Result<(), NetworkDenial> {
+        let Some(rules) = self.rules(endpoint) else {
+            return Ok(());
+        };
+        let result = self.evaluate(rules, client_ip);
+        if let Err(reason) = result {
+            let country = client_ip.filter(|_| rules.uses_countries()).and_then(|ip| self.country(ip));
+            warn!(
+                target: "audit",
+                event_type = "network_access_denied",
+                endpoint = %endpoint,
+                client_ip = %client_ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string()),
+                country = country.as_deref().unwrap_or("unknown"),
+                reason = %reason,
+                "Refused request from outside the allowed networks"
+            );
+            return Err(NetworkDenial { endpoint, client_ip, country, reason });
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self, rules: &Rules, client_ip: Option<IpAddr>) -> std::result::Result<(), String> {
+        let Some(ip) = client_ip else {
+            return Err("client address unknown".to_string());
+        };
+        if rules.deny.iter().any(|range| range.contains(&ip)) {
+            return Err(format!("{} is in a denied network", ip));
+        }
+        let country = if rules.uses_countries() { self.country(ip) } else { None };
+        if let Some(country) = country.as_ref().filter(|country| rules.deny_countries.contains(country)) {
+            return Err(format!("{} is in denied country {}", ip, country));
+        }
+        if rules.allow.is_empty() && rules.allow_countries.is_empty() {
+            return Ok(());
+        }
+        let allowed_network = rules.allow.iter().any(|range| range.contains(&ip));
+        let allowed_country = country.as_ref().is_some_and(|country| rules.allow_countries.contains(country));
+        if allowed_network || allowed_country {
+            Ok(())
+        } else {
+            Err(format!("{} is not in an allowed network or country", ip))
+        }
+    }
+
+    fn country(&self, ip: IpAddr) -> Option<String> {
+        self.countries.as_ref().and_then(|lookup| lookup(ip))
+    }
+
+    /// Check a gRPC request, refusing it with `PERMISSION_DENIED`
+    pub fn check_grpc<T>(&self, request: &tonic::Request<T>) -> std::result::Result<(), tonic::Status> {
+        let forwarded_for = request.metadata().get(FORWARDED_FOR_HEADER).and_then(|value| value.to_str().ok());
+        let client_ip = request.remote_addr().map(|peer| self.client_ip(peer.ip(), forwarded_for));
+        self.check(EndpointClass::Grpc, client_ip)
+            .map_err(|_| tonic::Status::permission_denied("Access from this network is not allowed"))
+    }
+}
+
+/// Address of an `X-Forwarded-For` entry, which may carry a port
+#[cfg(feature = "geoip")]
+fn open_geoip_database(path: &str) -> Result<CountryLookup> {
+    let reader = maxminddb::Reader::open_readfile(path)
+        .map_err(|e| ProxyError::config(format!("Failed to open GeoIP database {}: {}", path, e)))?;
+    Ok(Box::new(move |ip| {
+        reader.lookup::<maxminddb::geoip2::Country>(ip).ok()
+            .and_then(|record| record.country)
+            .and_then(|country| country.iso_code)
+            .map(str::to_string)
+    }))
+}
+
+#[cfg(not(feature = "geoip"))]
+fn open_geoip_database(path: &str) -> Result<CountryLookup> {
+    Err(ProxyError::config(format!(
+        "Cannot open GeoIP database {}: country rules need the `geoip` feature", path
+    )))
+}
+
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    let hop = hop.trim();
+    hop.parse::<IpAddr>().ok()
+        .or_else(|| hop.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+/// gRPC interceptor applying the policy's `grpc` rules
+pub fn grpc_interceptor(
+    policy: Option<Arc<NetworkPolicy>>,
+) -> impl FnMut(tonic::Request<()>) -> std::result::Result<tonic::Request<()>, tonic::Status> + Clone {
+    move |request| {
+        if let Some(policy) = &policy {
+            policy.check_grpc(&request)?;
+        }
+        Ok(request)
+    }
+}
+
+/// Middleware refusing HTTP requests from outside the allowed networks
+pub struct NetworkFilter {
+    policy: Option<Arc<NetworkPolicy>>,
+}
+
+impl NetworkFilter {
+    pub fn new(policy: Option<Arc<NetworkPolicy>>) -> Self {
+        Self { policy }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for NetworkFilter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = NetworkFilterService<S>;
+    type InitError = ();
+    type Future = std::future::Ready<std::result::Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        std::future::ready(Ok(NetworkFilterService { service: Rc::new(service), policy: self.policy.clone() }))
+    }
+}
+
+/// Service created by [`NetworkFilter`]
+pub struct NetworkFilterService<S> {
+    service: Rc<S>,
+    policy: Option<Arc<NetworkPolicy>>,
+}
+
+impl<S, B> Service<ServiceRequest> for NetworkFilterService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, std::result::Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let (Some(policy), Some(endpoint)) = (&self.policy, EndpointClass::of_path(req.path())) {
+            let forwarded_for = req.headers().get(FORWARDED_FOR_HEADER).and_then(|value| value.to_str().ok());
+            let client_ip = req.peer_addr().map(|peer| policy.client_ip(peer.ip(), forwarded_for));
+            if let Err(denial) = policy.check(endpoint, client_ip) {
+                let response = HttpResponse::Forbidden().json(json!({
+                    "error": {
+                        "code": "NETWORK_NOT_ALLOWED",
+                        "message": "Access from this network is not allowed",
+                        "type": "authorization_error"
+                    }
+                }));
+                let error = actix_web::error::InternalError::from_response(denial.reason, response);
+                return Box::pin(async move { Err(error.into()) });
+            }
+        }
+        let service = Rc::clone(&self.service);
+        Box::pin(async move { service.call(req).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App};
+
+    fn policy(yaml: &str) -> NetworkPolicy {
+        let config: NetworkPolicyConfig = serde_yaml::from_str(yaml).unwrap();
+        NetworkPolicy::new(&config, &["10.0.0.0/8".to_string()]).unwrap()
+    }
+
+    fn ip(address: &str) -> Option<IpAddr> {
+        Some(address.parse().unwrap())
+    }
+
+    /// Countries of the documentation ranges
+    fn with_countries(mut policy: NetworkPolicy) -> NetworkPolicy {
+        policy.countries = Some(Box::new(|ip| match ip.to_string().as_str() {
+            address if address.starts_with("203.0.113.") => Some("DE".to_string()),
+            address if address.starts_with("198.51.100.") => Some("KP".to_string()),
+            _ => None,
+        }));
+        policy
+    }
+
+    #[test]
+    fn test_trusted_proxy_client_ip() {
+        let policy = policy("mcp: { deny: ['192.0.2.0/24'] }");
+        let proxy = "10.0.0.5".parse().unwrap();
+        assert_eq!(policy.client_ip(proxy, Some("192.0.2.7, 10.1.1.1")), "192.0.2.7".parse::<IpAddr>().unwrap());
+        assert_eq!(policy.client_ip(proxy, Some("192.0.2.7:5555")), "192.0.2.7".parse::<IpAddr>().unwrap());
+        assert_eq!(policy.client_ip(proxy, None), proxy);
+
+        // Spoofed entries are only believed behind trusted proxies
+        let direct = "203.0.113.9".parse().unwrap();
+        assert_eq!(policy.client_ip(direct, Some("10.0.0.1")), direct);
+        assert_eq!(policy.client_ip(proxy, Some("10.0.0.1, 203.0.113.9")), direct);
+    }
+
+    #[test]
+    fn test_network_rules() {
+        let policy = policy("dashboard: { allow: ['192.168.0.0/16'], deny: ['192.168.66.0/24'] }");
+        assert!(policy.check(EndpointClass::Dashboard, ip("192.168.1.10")).is_ok());
+        assert!(policy.check(EndpointClass::Dashboard, ip("192.168.66.10")).is_err());
+        assert!(policy.check(EndpointClass::Dashboard, ip("203.0.113.9")).is_err());
+        assert!(policy.check(EndpointClass::Dashboard, None).is_err());
+        assert!(policy.check(EndpointClass::Mcp, ip("203.0.113.9")).is_ok());
+    }
+
+    #[test]
+    fn test_country_rules() {
+        let config: NetworkPolicyConfig =
+            serde_yaml::from_str("grpc: { allow: ['10.0.0.0/8'], allow_countries: ['de'] }\nmcp: { deny_countries: ['KP'] }").unwrap();
+        let policy = with_countries(NetworkPolicy {
+            mcp: config.mcp.as_ref().map(|rules| Rules::new(rules).unwrap()),
+            dashboard: None,
+            grpc: config.grpc.as_ref().map(|rules| Rules::new(rules).unwrap()),
+            trusted_proxies: Vec::new(),
+            countries: None,
+        });
+        assert!(policy.check(EndpointClass::Grpc, ip("203.0.113.9")).is_ok());
+        assert!(policy.check(EndpointClass::Grpc, ip("10.2.3.4")).is_ok());
+        let denial = policy.check(EndpointClass::Grpc, ip("192.0.2.7")).unwrap_err();
+        assert_eq!(denial.country, None);
+        let denial = policy.check(EndpointClass::Mcp, ip("198.51.100.3")).unwrap_err();
+        assert_eq!(denial.country.as_deref(), Some("KP"));
+    }
+
+    #[test]
+    fn test_validation() {
+        let config = |yaml: &str| serde_yaml::from_str::<NetworkPolicyConfig>(yaml).unwrap().validate();
+        assert!(config("mcp: { allow: ['10.0.0.0/8', '::1'] }").is_ok());
+        assert!(config("mcp: { allow: ['10.0.0.0/33'] }").is_err());
+        assert!(config("grpc: { deny_countries: ['KP'] }").is_err());
+        assert!(config("geoip_database: countries.mmdb\ngrpc: { deny_countries: ['North Korea'] }").is_err());
+        assert!(config("trusted_proxies: ['proxy']").is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_http_filter() {
+        let policy = Arc::new(policy("dashboard: { allow: ['127.0.0.1'] }\nmcp: { allow: ['192.168.0.0/16'] }"));
+        let app = test::init_service(
+            App::new()
+                .wrap(NetworkFilter::new(Some(policy)))
+                .route("/health", web::get().to(|| async { HttpResponse::Ok().finish() }))
+                .route("/dashboard/api/status", web::get().to(|| async { HttpResponse::Ok().finish() }))
+                .route("/mcp/tools", web::get().to(|| async { HttpResponse::Ok().finish() })),
+        ).await;
+        let request = |path: &str, peer: &str| test::TestRequest::get().uri(path).peer_addr(peer.parse().unwrap());
+
+        let response = test::call_service(&app, request("/dashboard/api/status", "127.0.0.1:4000").to_request()).await;
+        assert!(response.status().is_success());
+        let response = test::try_call_service(&app, request("/dashboard/api/status", "192.168.1.1:4000").to_request()).await;
+        assert_eq!(response.err().unwrap().error_response().status(), actix_web::http::StatusCode::FORBIDDEN);
+        let response = test::call_service(&app, request("/health", "203.0.113.9:4000").to_request()).await;
+        assert!(response.status().is_success());
+
+        // The client behind a trusted proxy is checked, not the proxy
+        let forwarded = request("/mcp/tools", "10.0.0.5:4000").insert_header(("X-Forwarded-For", "192.168.1.1")).to_request();
+        assert!(test::call_service(&app, forwarded).await.status().is_success());
+    }
+}
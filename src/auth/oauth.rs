@@ -46,6 +46,8 @@ pub struct OAuthValidationResult {
     pub expires_at: Option<u64>,
     /// Token scopes
     pub scopes: Vec<String>,
+    /// Roles granted by the role mappings
+    pub roles: Vec<String>,
 }
 
 /// OAuth 2.0 authentication validator
@@ -81,7 +83,7 @@ impl OAuthValidator {
         }
 
         // Only validate OAuth auth type
-        if self.config.r#type != AuthType::OAuth {
+        if !self.config.uses(&AuthType::OAuth) {
             debug!("Non-OAuth auth type, skipping OAuth validation");
             return Ok(None);
         }
@@ -119,6 +121,13 @@ impl OAuthValidator {
         }
     }
 
+    /// Validate an access token obtained outside an HTTP request (e.g. by the device flow)
+    pub async fn validate_token(&self, access_token: &str) -> Result<Option<OAuthValidationResult>> {
+        let oauth_config = self.config.oauth.as_ref()
+            .ok_or_else(|| ProxyError::auth("OAuth configuration missing"))?;
+        self.validate_access_token(access_token, oauth_config).await
+    }
+
     /// Validate an access token with the OAuth provider
     async fn validate_access_token(
         &self,
@@ -170,6 +179,7 @@ impl OAuthValidator {
             user_info,
             expires_at: Some(expires_at),
             scopes: vec!["read".to_string(), "write".to_string()], // Default scopes
+            roles: Vec::new(),
         }))
     }
 
@@ -210,6 +220,10 @@ impl OAuthValidator {
         params.insert("redirect_uri", redirect_uri);
         params.insert("state", state);
         params.insert("scope", self.get_default_scope(&oauth_config.provider));
+        // RFC 8707: ask for tokens bound to this server
+        if let Some(resource) = self.resource() {
+            params.insert("resource", resource);
+        }
 
         let query_string = params
             .iter()
@@ -220,6 +234,11 @@ impl OAuthValidator {
         Ok(format!("{}?{}", oauth_config.auth_url, query_string))
     }
 
+    /// Resource URI of this server, when configured
+    fn resource(&self) -> Option<&str> {
+        self.config.resource_server.as_ref()?.resource.as_deref()
+    }
+
     /// Get default scope for OAuth provider
     pub fn get_default_scope(&self, provider: &str) -> &str {
         match provider.to_lowercase().as_str() {
@@ -247,6 +266,9 @@ impl OAuthValidator {
         params.insert("redirect_uri", redirect_uri);
         params.insert("client_id", &oauth_config.client_id);
         params.insert("client_secret", &oauth_config.client_secret);
+        if let Some(resource) = self.resource() {
+            params.insert("resource", resource);
+        }
 
         let response = self
             .client
@@ -295,6 +317,7 @@ mod tests {
             client_secret: "test_client_secret".to_string(),
             auth_url: "https://github.com/login/oauth/authorize".to_string(),
             token_url: "https://github.com/login/oauth/access_token".to_string(),
+            device_auth_url: None,
         });
         config
     }
@@ -0,0 +1,187 @@
+//! Mapping of identity provider scopes and claims to roles
+//!
+//! `auth.role_mappings` grant roles to OAuth and JWT users from what their
+//! provider says about them, so permissions can follow the groups and
+//! organizations managed there:
+//!
+//! ```yaml
+//! auth:
+//!   roles:
+//!     operator: ["read", "write"]
+//!   role_mappings:
+//!     - roles: ["operator"]
+//!       groups: ["platform-team"]
+//!       email_domains: ["example.com"]
+//! ```
+//!
+//! Roles are mapped once, when the user authenticates, and add to the roles
+//! in the token's own `roles` claim.
+
+use crate::auth::{JwtValidationResult, OAuthValidationResult};
+use crate::config::RoleMappingConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// What a user's provider says about them, as used by the role mappings
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IdentityClaims {
+    /// Scopes of the token
+    pub scopes: Vec<String>,
+    /// Groups of the user
+    pub groups: Vec<String>,
+    /// Organizations of the user
+    pub orgs: Vec<String>,
+    /// Email address of the user
+    pub email: Option<String>,
+}
+
+impl IdentityClaims {
+    /// Claims of a validated JWT
+    pub fn from_jwt(result: &JwtValidationResult) -> Self {
+        let extra = &result.claims.extra;
+        let mut scopes = claim_values(extra.get("scope"));
+        scopes.extend(claim_values(extra.get("scp")));
+        let mut orgs = claim_values(extra.get("org"));
+        orgs.extend(claim_values(extra.get("orgs")));
+        Self {
+            scopes,
+            groups: claim_values(extra.get("groups")),
+            orgs,
+            email: result.user_info.email.clone()
+                .or_else(|| extra.get("email").and_then(Value::as_str).map(str::to_string)),
+        }
+    }
+
+    /// Claims of a validated OAuth access token
+    pub fn from_oauth(result: &OAuthValidationResult) -> Self {
+        Self {
+            scopes: result.scopes.clone(),
+            email: result.user_info.email.clone(),
+            ..Self::default()
+        }
+    }
+
+    fn email_domain(&self) -> Option<&str> {
+        self.email.as_deref()?.rsplit_once('@').map(|(_, domain)| domain)
+    }
+}
+
+/// Values of a claim holding a space-separated string or an array of strings
+fn claim_values(claim: Option<&Value>) -> Vec<String> {
+    match claim {
+        Some(Value::String(values)) => values.split_whitespace().map(str::to_string).collect(),
+        Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Evaluates the role mappings
+#[derive(Debug, Clone, Default)]
+pub struct RoleMapper {
+    mappings: Vec<RoleMappingConfig>,
+}
+
+impl RoleMapper {
+    pub fn new(mappings: Vec<RoleMappingConfig>) -> Self {
+        Self { mappings }
+    }
+
+    /// Indexes of the mappings matching `claims`
+    pub fn matching(&self, claims: &IdentityClaims) -> Vec<usize> {
+        self.mappings.iter()
+            .enumerate()
+            .filter(|(_, mapping)| Self::matches(mapping, claims))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Roles granted to a user with `claims`, without duplicates
+    pub fn roles_for(&self, claims: &IdentityClaims) -> Vec<String> {
+        let mut roles: Vec<String> = Vec::new();
+        for index in self.matching(claims) {
+            for role in &self.mappings[index].roles {
+                if !roles.contains(role) {
+                    roles.push(role.clone());
+                }
+            }
+        }
+        roles
+    }
+
+    fn matches(mapping: &RoleMappingConfig, claims: &IdentityClaims) -> bool {
+        let any_of = |wanted: &[String], actual: &[String]| wanted.is_empty() || wanted.iter().any(|value| actual.contains(value));
+        let domain_matches = mapping.email_domains.is_empty() || claims.email_domain().is_some_and(|domain| {
+            mapping.email_domains.iter().any(|wanted| wanted.eq_ignore_ascii_case(domain))
+        });
+        any_of(&mapping.scopes, &claims.scopes)
+            && any_of(&mapping.groups, &claims.groups)
+            && any_of(&mapping.orgs, &claims.orgs)
+            && domain_matches
+    }
+}
+
+/// Body of a role mapping dry run request
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoleDryRunRequest {
+    /// OAuth access token or JWT to evaluate
+    pub token: String,
+}
+
+/// Roles a token would receive, as reported by the role mapping dry run
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleDryRun {
+    /// Authentication method that accepted the token
+    pub auth_method: &'static str,
+    /// User the token belongs to
+    pub user_id: String,
+    /// Scopes and claims the mappings were evaluated against
+    pub claims: IdentityClaims,
+    /// Indexes of the matching `role_mappings` entries
+    pub matched_mappings: Vec<usize>,
+    /// Roles granted by the mappings
+    pub mapped_roles: Vec<String>,
+    /// All roles of the user: those of the token and the mapped ones
+    pub roles: Vec<String>,
+    /// Permissions the user would have
+    pub permissions: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mapping(roles: &[&str], groups: &[&str], email_domains: &[&str]) -> RoleMappingConfig {
+        RoleMappingConfig {
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+            scopes: Vec::new(),
+            groups: groups.iter().map(|group| group.to_string()).collect(),
+            orgs: Vec::new(),
+            email_domains: email_domains.iter().map(|domain| domain.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_all_criteria_must_match() {
+        let mapper = RoleMapper::new(vec![
+            mapping(&["operator"], &["platform-team", "sre"], &["example.com"]),
+            mapping(&["viewer"], &[], &["Example.com"]),
+        ]);
+        let mut claims = IdentityClaims {
+            groups: vec!["sre".to_string()],
+            email: Some("alice@example.com".to_string()),
+            ..IdentityClaims::default()
+        };
+        assert_eq!(mapper.roles_for(&claims), vec!["operator", "viewer"]);
+
+        claims.email = Some("alice@partner.org".to_string());
+        assert!(mapper.roles_for(&claims).is_empty());
+    }
+
+    #[test]
+    fn test_claim_values() {
+        assert_eq!(claim_values(Some(&json!("read:tools mcp:admin"))), vec!["read:tools", "mcp:admin"]);
+        assert_eq!(claim_values(Some(&json!(["a", "b", 1]))), vec!["a", "b"]);
+        assert!(claim_values(None).is_empty());
+    }
+}
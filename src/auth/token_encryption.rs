@@ -0,0 +1,184 @@
+//! Encryption of OAuth tokens stored on disk
+//!
+//! With `auth.token_encryption` configured, tokens are sealed with
+//! AES-256-GCM under a key encryption key (KEK) before they are written. The
+//! KEK is a secret reference, so it can come from the environment, a file or
+//! Vault, and never sits in the config file:
+//!
+//! ```yaml
+//! auth:
+//!   token_encryption:
+//!     kek: "${env:MAGICTUNNEL_TOKEN_KEK}"
+//!     previous_keks: ["${file:/run/secrets/old-token-kek}"]
+//! ```
+//!
+//! Every sealed token records the id of the key it was sealed with. Tokens
+//! sealed with one of the `previous_keks` can still be opened and are
+//! re-sealed with the current key, which is how keys are rotated.
+
+use crate::config::secrets::resolve_secret;
+use crate::config::TokenEncryptionConfig;
+use crate::error::{ProxyError, Result};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Version of the sealed token format
+const SEALED_VERSION: u32 = 1;
+
+/// A token encrypted with [`TokenCipher`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedToken {
+    /// Format version
+    pub version: u32,
+    /// Id of the key the token is encrypted with
+    pub key_id: String,
+    /// AES-GCM nonce, base64 encoded
+    pub nonce: String,
+    /// Encrypted token and authentication tag, base64 encoded
+    pub ciphertext: String,
+}
+
+struct DataKey {
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+impl DataKey {
+    fn new(kek: &[u8]) -> Self {
+        let key = Sha256::digest(kek);
+        let id = hex::encode(&Sha256::new().chain_update(b"magictunnel-token-kek").chain_update(key).finalize()[..8]);
+        Self { id, cipher: Aes256Gcm::new(&key) }
+    }
+}
+
+/// Seals and opens stored tokens with the current and previous KEKs
+pub struct TokenCipher {
+    current: DataKey,
+    previous: Vec<DataKey>,
+}
+
+impl std::fmt::Debug for TokenCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenCipher").field("key_id", &self.current.id).finish_non_exhaustive()
+    }
+}
+
+impl TokenCipher {
+    /// Cipher of the configured KEKs, resolving their secret references
+    pub async fn from_config(config: &TokenEncryptionConfig) -> Result<Self> {
+        let current = resolve_secret(&config.kek).await?;
+        let mut previous = Vec::with_capacity(config.previous_keks.len());
+        for kek in &config.previous_keks {
+            previous.push(resolve_secret(kek).await?);
+        }
+        Ok(Self::new(current.as_bytes(), previous.iter().map(|kek| kek.as_bytes())))
+    }
+
+    /// Cipher sealing with `current` and also opening tokens sealed with `previous` keys
+    pub fn new<'a>(current: &[u8], previous: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        Self {
+            current: DataKey::new(current),
+            previous: previous.into_iter().map(DataKey::new).collect(),
+        }
+    }
+
+    /// Id of the key tokens are sealed with
+    pub fn key_id(&self) -> &str {
+        &self.current.id
+    }
+
+    /// Encrypt a token with the current key
+    pub fn seal(&self, plaintext: &[u8]) -> Result<SealedToken> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.current.cipher.encrypt(&nonce, plaintext)
+            .map_err(|_| ProxyError::auth("Failed to encrypt token"))?;
+        Ok(SealedToken {
+            version: SEALED_VERSION,
+            key_id: self.current.id.clone(),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    /// Decrypt a token sealed with the current or a previous key
+    pub fn open(&self, sealed: &SealedToken) -> Result<Vec<u8>> {
+        if sealed.version != SEALED_VERSION {
+            return Err(ProxyError::auth(format!("Unsupported sealed token version {}", sealed.version)));
+        }
+        let key = std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|key| key.id == sealed.key_id)
+            .ok_or_else(|| ProxyError::auth(format!("Token is encrypted with unknown key '{}'", sealed.key_id)))?;
+        let nonce = STANDARD.decode(&sealed.nonce)
+            .ok()
+            .filter(|nonce| nonce.len() == 12)
+            .ok_or_else(|| ProxyError::auth("Sealed token has an invalid nonce"))?;
+        let ciphertext = STANDARD.decode(&sealed.ciphertext)
+            .map_err(|_| ProxyError::auth("Sealed token has invalid ciphertext"))?;
+        key.cipher.decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| ProxyError::auth(format!("Failed to decrypt token sealed with key '{}'", sealed.key_id)))
+    }
+
+    /// Whether the token is sealed with a previous key and should be re-sealed
+    pub fn needs_rotation(&self, sealed: &SealedToken) -> bool {
+        sealed.key_id != self.current.id
+    }
+
+    /// Re-seal a token with the current key
+    pub fn rotate(&self, sealed: &SealedToken) -> Result<SealedToken> {
+        self.seal(&self.open(sealed)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let cipher = TokenCipher::new(b"current-key-0123456789abcdefghijkl", []);
+        let sealed = cipher.seal(b"access-token").unwrap();
+        assert_eq!(sealed.key_id, cipher.key_id());
+        assert!(!STANDARD.decode(&sealed.ciphertext).unwrap().windows(12).any(|w| w == b"access-token"));
+        assert_eq!(cipher.open(&sealed).unwrap(), b"access-token");
+
+        // Nonces are random, and tampering is detected
+        assert_ne!(cipher.seal(b"access-token").unwrap().nonce, sealed.nonce);
+        let mut tampered = sealed.clone();
+        tampered.ciphertext = STANDARD.encode(b"not the sealed token at all");
+        assert!(cipher.open(&tampered).is_err());
+
+        let other = TokenCipher::new(b"another-key-0123456789abcdefghijk", []);
+        assert!(other.open(&sealed).is_err());
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let old_key: &[u8] = b"old-key-0123456789abcdefghijklmnop";
+        let old = TokenCipher::new(old_key, []);
+        let sealed = old.seal(b"refresh-token").unwrap();
+
+        let rotated = TokenCipher::new(b"new-key-0123456789abcdefghijklmnop", [old_key]);
+        assert!(rotated.needs_rotation(&sealed));
+        let resealed = rotated.rotate(&sealed).unwrap();
+        assert!(!rotated.needs_rotation(&resealed));
+        assert_eq!(resealed.key_id, rotated.key_id());
+
+        // Once the old key is dropped, only the re-sealed token can be opened
+        let current_only = TokenCipher::new(b"new-key-0123456789abcdefghijklmnop", []);
+        assert_eq!(current_only.open(&resealed).unwrap(), b"refresh-token");
+        assert!(current_only.open(&sealed).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_from_config_resolves_references() {
+        std::env::set_var("MAGICTUNNEL_TEST_TOKEN_KEK", "env-key-0123456789abcdefghijklmnop");
+        let config = TokenEncryptionConfig { kek: "${env:MAGICTUNNEL_TEST_TOKEN_KEK}".to_string(), previous_keks: Vec::new() };
+        let cipher = TokenCipher::from_config(&config).await.unwrap();
+        let literal = TokenCipher::new(b"env-key-0123456789abcdefghijklmnop", []);
+        assert_eq!(cipher.key_id(), literal.key_id());
+    }
+}
@@ -0,0 +1,296 @@
+//! OAuth 2.0 token exchange (RFC 8693) for calls made on behalf of users
+//!
+//! When a user authenticates with an OAuth or JWT bearer token, calls to
+//! upstream services can carry a token issued for that user instead of a
+//! shared service credential. The user's token is exchanged at the identity
+//! provider for one scoped to the upstream's audience:
+//!
+//! ```yaml
+//! # external-mcp-servers.yaml
+//! http_services:
+//!   crm:
+//!     base_url: https://crm.example.com/mcp
+//!     auth:
+//!       type: token_exchange
+//!       token_url: https://login.example.com/oauth/token
+//!       client_id: magictunnel
+//!       client_secret: ${env:TOKEN_EXCHANGE_SECRET}
+//!       audience: https://crm.example.com
+//!       fallback_token: ${env:CRM_SERVICE_TOKEN}   # calls without a user token
+//!
+//! # capability file
+//! routing:
+//!   type: http
+//!   config:
+//!     url: https://billing.example.com/invoices
+//!     token_exchange:
+//!       token_url: https://login.example.com/oauth/token
+//!       client_id: magictunnel
+//!       client_secret: ${env:TOKEN_EXCHANGE_SECRET}
+//!       audience: https://billing.example.com
+//! ```
+//!
+//! Exchanged tokens are cached per user token and audience until shortly
+//! before they expire. Calls without a user token (API key callers, startup
+//! tool discovery) use `fallback_token`, or fail if it is not set.
+
+use crate::config::secrets::{redact_secret, resolve_secret};
+use crate::error::{ProxyError, Result};
+use actix_web::HttpRequest;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+const GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+/// Exchanged tokens are refreshed this long before they expire
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+/// Lifetime assumed for exchanged tokens the provider gives no `expires_in` for
+const DEFAULT_LIFETIME: Duration = Duration::from_secs(60);
+
+tokio::task_local! {
+    static SUBJECT_TOKEN: String;
+}
+
+/// Run `future` with its upstream calls made on behalf of the user holding `token`
+pub async fn on_behalf_of<F: Future>(token: Option<String>, future: F) -> F::Output {
+    match token {
+        Some(token) => SUBJECT_TOKEN.scope(token, future).await,
+        None => future.await,
+    }
+}
+
+/// Token of the user the current call is made for
+pub fn subject_token() -> Option<String> {
+    SUBJECT_TOKEN.try_with(Clone::clone).ok()
+}
+
+/// Bearer token of a request, as presented by the user
+pub fn bearer_token(req: &HttpRequest) -> Option<String> {
+    let header = req.headers().get("Authorization")?.to_str().ok()?;
+    header.strip_prefix("Bearer ").map(|token| token.trim().to_string())
+}
+
+fn default_subject_token_type() -> String { ACCESS_TOKEN_TYPE.to_string() }
+
+/// Token exchange settings of an upstream service.
+/// `client_secret` and `fallback_token` may be secret references (see [`crate::config::secrets`]).
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenExchangeConfig {
+    /// Token endpoint of the identity provider
+    pub token_url: String,
+    /// Client MagicTunnel authenticates to the token endpoint as
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: String,
+    /// Audience the exchanged token is requested for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String>,
+    /// Resource URI the exchanged token is requested for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    /// Scopes requested for the exchanged token, space separated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Type of the user's token (default: access token)
+    #[serde(default = "default_subject_token_type")]
+    pub subject_token_type: String,
+    /// Token sent when the call is not made on behalf of a user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_token: Option<String>,
+}
+
+impl std::fmt::Debug for TokenExchangeConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenExchangeConfig")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &redact_secret(&self.client_secret))
+            .field("audience", &self.audience)
+            .field("resource", &self.resource)
+            .field("scope", &self.scope)
+            .field("subject_token_type", &self.subject_token_type)
+            .field("fallback_token", &self.fallback_token.as_deref().map(redact_secret))
+            .finish()
+    }
+}
+
+/// Token endpoint response
+#[derive(Deserialize)]
+struct TokenExchangeResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+/// Token endpoint error response
+#[derive(Deserialize)]
+struct TokenExchangeError {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// Exchanged token, keyed by user token hash and requested audience
+struct CachedToken {
+    access_token: String,
+    expires_at: Instant,
+}
+
+type CacheKey = (String, String, String, Option<String>, Option<String>, Option<String>);
+
+fn cache() -> &'static Mutex<HashMap<CacheKey, CachedToken>> {
+    static CACHE: OnceLock<Mutex<HashMap<CacheKey, CachedToken>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn http_client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .unwrap_or_else(|_| Client::new())
+    })
+}
+
+impl TokenExchangeConfig {
+    /// Resolve secret references in the credentials
+    pub async fn resolve(&self) -> Result<Self> {
+        let fallback_token = match &self.fallback_token {
+            Some(token) => Some(resolve_secret(token).await?),
+            None => None,
+        };
+        Ok(Self {
+            client_secret: resolve_secret(&self.client_secret).await?,
+            fallback_token,
+            ..self.clone()
+        })
+    }
+
+    /// Bearer token for an upstream call: the current user's token exchanged
+    /// for this upstream's audience, or the fallback token outside user calls
+    pub async fn access_token(&self) -> Result<String> {
+        let Some(subject_token) = subject_token() else {
+            return self.fallback_token.clone().ok_or_else(|| {
+                ProxyError::auth(format!(
+                    "Calls to {} are made on behalf of users and need an OAuth or JWT authenticated caller",
+                    self.audience.as_deref().or(self.resource.as_deref()).unwrap_or(&self.token_url)
+                ))
+            });
+        };
+
+        let key = self.cache_key(&subject_token);
+        if let Some(cached) = cache().lock().unwrap().get(&key) {
+            if cached.expires_at > Instant::now() {
+                return Ok(cached.access_token.clone());
+            }
+        }
+
+        let response = self.exchange(&subject_token).await?;
+        let lifetime = response.expires_in.map_or(DEFAULT_LIFETIME, Duration::from_secs);
+        if lifetime > EXPIRY_MARGIN {
+            let mut cache = cache().lock().unwrap();
+            let now = Instant::now();
+            cache.retain(|_, cached| cached.expires_at > now);
+            cache.insert(key, CachedToken {
+                access_token: response.access_token.clone(),
+                expires_at: now + lifetime - EXPIRY_MARGIN,
+            });
+        }
+        Ok(response.access_token)
+    }
+
+    /// Key of the exchanged token cache; holds a hash of the user's token rather than the token
+    fn cache_key(&self, subject_token: &str) -> CacheKey {
+        (
+            hex::encode(Sha256::digest(subject_token.as_bytes())),
+            self.token_url.clone(),
+            self.client_id.clone(),
+            self.audience.clone(),
+            self.resource.clone(),
+            self.scope.clone(),
+        )
+    }
+
+    async fn exchange(&self, subject_token: &str) -> Result<TokenExchangeResponse> {
+        let mut form = vec![
+            ("grant_type", GRANT_TYPE),
+            ("subject_token", subject_token),
+            ("subject_token_type", self.subject_token_type.as_str()),
+            ("requested_token_type", ACCESS_TOKEN_TYPE),
+        ];
+        form.extend(self.audience.as_deref().map(|audience| ("audience", audience)));
+        form.extend(self.resource.as_deref().map(|resource| ("resource", resource)));
+        form.extend(self.scope.as_deref().map(|scope| ("scope", scope)));
+
+        debug!(token_url = %self.token_url, audience = ?self.audience, "Exchanging user token");
+        let response = http_client()
+            .post(&self.token_url)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| ProxyError::auth(format!("Token exchange request to {} failed: {}", self.token_url, e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let reason = match response.json::<TokenExchangeError>().await {
+                Ok(error) => error.error_description.map_or(error.error.clone(), |description| {
+                    format!("{}: {}", error.error, description)
+                }),
+                Err(_) => status.to_string(),
+            };
+            warn!(token_url = %self.token_url, status = %status, "Token exchange rejected: {}", reason);
+            return Err(ProxyError::auth(format!("Token exchange rejected: {}", reason)));
+        }
+
+        response.json::<TokenExchangeResponse>().await
+            .map_err(|e| ProxyError::auth(format!("Invalid token exchange response: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange_config() -> TokenExchangeConfig {
+        TokenExchangeConfig {
+            token_url: "http://127.0.0.1:9/oauth/token".to_string(),
+            client_id: "magictunnel".to_string(),
+            client_secret: "secret".to_string(),
+            audience: Some("https://crm.example.com".to_string()),
+            resource: None,
+            scope: None,
+            subject_token_type: default_subject_token_type(),
+            fallback_token: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_outside_user_calls() {
+        let config = exchange_config();
+        assert!(config.access_token().await.is_err());
+
+        let config = TokenExchangeConfig { fallback_token: Some("service-token".to_string()), ..config };
+        assert_eq!(config.access_token().await.unwrap(), "service-token");
+    }
+
+    #[test]
+    fn test_cache_key_hides_user_token() {
+        let config = exchange_config();
+        let key = config.cache_key("user-token");
+        assert_ne!(key.0, "user-token");
+        assert_eq!(key, config.cache_key("user-token"));
+        assert_ne!(key, config.cache_key("other-user-token"));
+
+        let other_audience = TokenExchangeConfig { audience: Some("https://billing.example.com".to_string()), ..config };
+        assert_ne!(key, other_audience.cache_key("user-token"));
+    }
+}
@@ -0,0 +1,148 @@
+//! Audit Log CLI
+//!
+//! This CLI tool verifies the hash chains of stored audit events, reading
+//! them from JSON lines files (such as the gzipped objects of the S3 archive)
+//! or from the Postgres audit store of the configuration.
+
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use flate2::read::GzDecoder;
+use magictunnel::audit::integrity::{verify, IntegrityIssue};
+use magictunnel::audit::postgres::PostgresStore;
+use magictunnel::audit::{AuditEvent, AuditQuery, QueryBackend};
+use magictunnel::config::secrets::resolve_secret;
+use magictunnel::config::Config;
+use magictunnel::error::{ProxyError, Result};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(name = "magictunnel-audit")]
+#[command(about = "MagicTunnel Audit Log Verification")]
+#[command(version)]
+struct Cli {
+    /// Configuration file path
+    #[arg(short, long, default_value = "config.yaml")]
+    config: PathBuf,
+
+    /// Log level (trace, debug, info, warn, error)
+    #[arg(short, long, default_value = "info")]
+    log_level: String,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Check audit events for modifications and gaps in their hash chains
+    Verify {
+        /// JSON lines files of events, gzipped if ending in `.gz`; the
+        /// configured Postgres audit store is read when none are given
+        files: Vec<PathBuf>,
+        /// Only events at or after this time (RFC 3339), from Postgres
+        #[arg(long)]
+        from: Option<DateTime<Utc>>,
+        /// Only events before this time (RFC 3339), from Postgres
+        #[arg(long)]
+        until: Option<DateTime<Utc>>,
+        /// Most events read from Postgres
+        #[arg(long, default_value_t = 10_000)]
+        limit: usize,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    // Initialize logging
+    tracing_subscriber::fmt()
+        .with_max_level(cli.log_level.parse().unwrap_or(tracing::Level::INFO))
+        .init();
+
+    // Load configuration
+    let config = Config::load(&cli.config, None, None)?;
+
+    match cli.command {
+        Commands::Verify { files, from, until, limit } => {
+            let intact = verify_events(&config, &files, AuditQuery { from, until, limit: Some(limit), ..Default::default() }).await?;
+            if !intact {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn verify_events(config: &Config, files: &[PathBuf], query: AuditQuery) -> Result<bool> {
+    let audit = config.audit.clone().unwrap_or_default();
+    let events = if files.is_empty() {
+        let postgres = audit.postgres.clone()
+            .ok_or_else(|| ProxyError::config("No files given and no audit.postgres store configured"))?;
+        PostgresStore::connect(postgres).await?.query(&query).await?
+    } else {
+        let mut events = Vec::new();
+        for file in files {
+            events.extend(read_events(file)?);
+        }
+        events
+    };
+
+    let signing_key = match audit.integrity.as_ref().and_then(|integrity| integrity.signing_key.as_ref()) {
+        Some(key) => Some(resolve_secret(key).await?.into_bytes()),
+        None => {
+            println!("⚠️  No audit.integrity.signing_key configured; anchor signatures are not checked");
+            None
+        }
+    };
+    let anchor_every = audit.integrity.as_ref().map(|integrity| integrity.anchor_every);
+    let report = verify(&events, signing_key.as_deref(), anchor_every);
+
+    println!("Audit Log Verification");
+    println!("======================");
+    println!("Events: {}", report.events);
+    for chain in &report.chains {
+        println!(
+            "  Chain {}: events {}-{} ({} stored, {} signed anchors)",
+            chain.chain_id, chain.first_sequence, chain.last_sequence, chain.events, chain.signed_anchors
+        );
+    }
+    if report.is_intact() {
+        println!("✅ No modifications or gaps found");
+        return Ok(true);
+    }
+    println!("❌ {} issues found:", report.issues.len());
+    for issue in &report.issues {
+        match issue {
+            IntegrityIssue::Unchained { timestamp, message } => println!("  Unchained event at {}: {}", timestamp, message),
+            IntegrityIssue::Gap { chain_id, after, before } => println!("  Chain {}: events {} to {} missing", chain_id, after + 1, before - 1),
+            IntegrityIssue::Duplicate { chain_id, sequence } => println!("  Chain {}: event {} stored more than once", chain_id, sequence),
+            IntegrityIssue::Modified { chain_id, sequence } => println!("  Chain {}: event {} modified", chain_id, sequence),
+            IntegrityIssue::BrokenLink { chain_id, sequence } => println!("  Chain {}: event {} doesn't follow the previous event", chain_id, sequence),
+            IntegrityIssue::BadSignature { chain_id, sequence } => println!("  Chain {}: event {} has a missing or wrong signature", chain_id, sequence),
+        }
+    }
+    Ok(false)
+}
+
+/// Events of a JSON lines file
+fn read_events(path: &Path) -> Result<Vec<AuditEvent>> {
+    let file = File::open(path)
+        .map_err(|e| ProxyError::config(format!("Failed to open {}: {}", path.display(), e)))?;
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|extension| extension == "gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    BufReader::new(reader).lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+        .map(|(number, line)| {
+            let line = line.map_err(|e| ProxyError::config(format!("Failed to read {}: {}", path.display(), e)))?;
+            serde_json::from_str(&line)
+                .map_err(|e| ProxyError::config(format!("{}:{}: not an audit event: {}", path.display(), number + 1, e)))
+        })
+        .collect()
+}
@@ -12,6 +12,10 @@
 //! - GraphQL schemas (SDL or JSON introspection)
 //! - gRPC/protobuf service definitions
 //! - OpenAPI specifications (v3.0, JSON or YAML)
+//! - Database schemas (PostgreSQL, MySQL or SQLite introspection)
+//! - Command-line tools (`--help` output or man pages)
+//! - HTTP Archive (HAR) captures
+//! - AWS botocore service models (json and rest-json protocols)
 //!
 //! # Features
 //!
@@ -19,7 +23,7 @@
 //! - **Multiple Generator Types**: Support for GraphQL, gRPC, and OpenAPI
 //! - **Authentication**: Various authentication methods (Bearer, API Key, Basic, OAuth)
 //! - **Customization**: Prefix, naming conventions, filtering, and more
-//! - **Utility Commands**: Initialize config files, merge capability files, validate
+//! - **Utility Commands**: Initialize config files, merge capability files, validate, migrate
 //!
 //! # Usage
 //!
@@ -33,6 +37,18 @@
 //! # Generate from OpenAPI specification
 //! magictunnel-cli openapi --spec openapi.json --base-url https://api.example.com --output capabilities.yaml
 //!
+//! # Generate CRUD tools from a database
+//! magictunnel-cli database --connection postgres://user@localhost/app --output capabilities.yaml --read-only
+//!
+//! # Expose an existing CLI from its --help output
+//! magictunnel-cli cli --binary git --subcommands status,log --output git-tools.yaml
+//!
+//! # Import requests captured in a HAR file
+//! magictunnel-cli har --input session.har --hosts api.example.com --output capabilities.yaml
+//!
+//! # Expose selected AWS operations from a botocore model, signed with SigV4
+//! magictunnel-cli aws --model dynamodb/2012-08-10/service-2.json --region eu-west-1 --operations GetItem,Query --output dynamodb.yaml
+//!
 //! # Initialize a configuration file
 //! magictunnel-cli init --output config.yaml
 //!
@@ -41,23 +57,52 @@
 //!
 //! # Validate capability files
 //! magictunnel-cli validate --input capabilities.yaml --strict
+//!
+//! # Report strict validation errors as JSON for editors
+//! magictunnel-cli validate --input capabilities.yaml --strict --format json
+//!
+//! # Export the capability file JSON Schema
+//! magictunnel-cli schema --output capability-file.schema.json
+//!
+//! # Upgrade capability files to the current schema version in place
+//! # (--force also rewrites files with comments, which are lost)
+//! magictunnel-cli migrate --input capabilities/legacy.yaml
+//!
+//! # Compare two capability directories
+//! magictunnel-cli registry diff capabilities-v1/ capabilities/ --fail-on-breaking
+//!
+//! # Copy the effective registry of one instance to another
+//! magictunnel-cli registry export --server http://prod:3001 -o registry.json
+//! magictunnel-cli registry import registry.json --server http://staging:3001 --overwrite
+//!
+//! # Switch a running server to another embedding model, and back
+//! magictunnel-cli embeddings swap ollama:nomic-embed-text --server http://prod:3001
+//! magictunnel-cli embeddings rollback --server http://prod:3001
 //! ```
 
 use clap::{Arg, ArgMatches, Command, ArgAction};
+use magictunnel::discovery::{EmbeddingModelChange, EmbeddingProviderConfig};
 use magictunnel::error::{ProxyError, Result};
 use magictunnel::registry::{
     generator_common::{
         read_file_content, write_capability_file, CapabilityGeneratorBase,
         AuthConfig, AuthType
     },
+    cli_generator::{CliCapabilityGenerator, CliGeneratorConfig},
+    database_generator::{DatabaseCapabilityGenerator, DatabaseGeneratorConfig},
     generator_config::{GeneratorConfigFile, example_config_yaml},
+    migration::{has_yaml_comments, migrate_content, parse_capability_file},
+    snapshot::RegistrySnapshot,
+    har_generator::{HarCapabilityGenerator, HarGeneratorConfig},
+    aws_generator::{AwsCapabilityGenerator, AwsGeneratorConfig},
     graphql_generator::{AuthConfig as GraphQLAuthConfig, AuthType as GraphQLAuthType},
     grpc_generator::{GrpcCapabilityGenerator, GrpcGeneratorConfig, StreamingStrategy, AuthConfig as GrpcAuthConfig, AuthType as GrpcAuthType},
     openapi_generator::{OpenAPICapabilityGenerator, NamingConvention, AuthConfig as OpenAPIAuthConfig, AuthType as OpenAPIAuthType},
     types::CapabilityFile,
     commands::{
         GraphQLGeneratorAdapter, GrpcGeneratorAdapter, OpenAPIGeneratorAdapter,
-        CapabilityMerger, CapabilityValidator, merge::MergeStrategy
+        CapabilityMerger, CapabilityValidator, CapabilityDiffer, merge::MergeStrategy,
+        capability_file_schema, Severity, StrictValidator
     },
 };
 use std::collections::HashMap;
@@ -375,6 +420,224 @@ async fn main() -> Result<()> {
                         .default_value("magictunnel-cli.yaml")
                 )
         )
+        .subcommand(
+            Command::new("database")
+                .about("Generate CRUD tools by introspecting a PostgreSQL, MySQL or SQLite database")
+                .arg(
+                    Arg::new("connection")
+                        .short('c')
+                        .long("connection")
+                        .value_name("CONNECTION")
+                        .help("Connection string (postgres://..., mysql://... or SQLite file path)")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Output capability file (YAML)")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("routing-connection")
+                        .long("routing-connection")
+                        .value_name("CONNECTION")
+                        .help("Connection string written into generated tools (defaults to --connection)")
+                )
+                .arg(
+                    Arg::new("prefix")
+                        .short('p')
+                        .long("prefix")
+                        .value_name("PREFIX")
+                        .help("Tool name prefix")
+                )
+                .arg(
+                    Arg::new("tables")
+                        .long("tables")
+                        .value_name("TABLES")
+                        .help("Comma-separated list of tables to include")
+                )
+                .arg(
+                    Arg::new("schema")
+                        .long("schema")
+                        .value_name("SCHEMA")
+                        .help("PostgreSQL schema to introspect (MySQL uses the database in the connection string)")
+                        .default_value("public")
+                )
+                .arg(
+                    Arg::new("timeout")
+                        .long("timeout")
+                        .value_name("SECONDS")
+                        .help("Query timeout for generated tools")
+                        .value_parser(clap::value_parser!(u64))
+                )
+                .arg(
+                    Arg::new("read-only")
+                        .long("read-only")
+                        .help("Only generate read-only list/get tools")
+                        .action(ArgAction::SetTrue)
+                )
+        )
+        .subcommand(
+            Command::new("cli")
+                .about("Generate subprocess tools by parsing a command-line tool's --help output")
+                .arg(
+                    Arg::new("binary")
+                        .short('b')
+                        .long("binary")
+                        .value_name("BINARY")
+                        .help("Command to describe (name on PATH or path)")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Output capability file (YAML)")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("subcommands")
+                        .long("subcommands")
+                        .value_name("SUBCOMMANDS")
+                        .help("Comma-separated subcommands to generate tools for (e.g. 'status,log')")
+                )
+                .arg(
+                    Arg::new("discover-subcommands")
+                        .long("discover-subcommands")
+                        .help("Generate a tool for every subcommand listed in the help output")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("no-man")
+                        .long("no-man")
+                        .help("Don't fall back to the man page when --help can't be parsed")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("prefix")
+                        .short('p')
+                        .long("prefix")
+                        .value_name("PREFIX")
+                        .help("Tool name prefix (defaults to the binary name)")
+                )
+                .arg(
+                    Arg::new("timeout")
+                        .long("timeout")
+                        .value_name("SECONDS")
+                        .help("Execution timeout for generated tools")
+                        .value_parser(clap::value_parser!(u64))
+                )
+        )
+        .subcommand(
+            Command::new("har")
+                .about("Generate HTTP tools from an HTTP Archive (HAR) capture")
+                .arg(
+                    Arg::new("input")
+                        .short('i')
+                        .long("input")
+                        .value_name("FILE")
+                        .help("HAR file exported from a browser or proxy")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Output capability file (YAML)")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("base-url")
+                        .short('u')
+                        .long("base-url")
+                        .value_name("URL")
+                        .help("Base URL for generated tools (defaults to the captured host)")
+                )
+                .arg(
+                    Arg::new("hosts")
+                        .long("hosts")
+                        .value_name("HOSTS")
+                        .help("Comma-separated list of hosts to import")
+                )
+                .arg(
+                    Arg::new("methods")
+                        .short('m')
+                        .long("methods")
+                        .value_name("METHODS")
+                        .help("Comma-separated list of HTTP methods to import")
+                )
+                .arg(
+                    Arg::new("prefix")
+                        .short('p')
+                        .long("prefix")
+                        .value_name("PREFIX")
+                        .help("Tool name prefix")
+                )
+                .arg(
+                    Arg::new("include-static")
+                        .long("include-static")
+                        .help("Also import requests for images, scripts, stylesheets and fonts")
+                        .action(ArgAction::SetTrue)
+                )
+        )
+        .subcommand(
+            Command::new("aws")
+                .about("Generate SigV4-signed HTTP tools from an AWS botocore service model")
+                .arg(
+                    Arg::new("model")
+                        .short('m')
+                        .long("model")
+                        .value_name("FILE")
+                        .help("botocore service model (service-2.json)")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Output capability file (YAML)")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("region")
+                        .short('r')
+                        .long("region")
+                        .value_name("REGION")
+                        .help("AWS region to call and sign for")
+                        .default_value("us-east-1")
+                )
+                .arg(
+                    Arg::new("operations")
+                        .long("operations")
+                        .value_name("OPERATIONS")
+                        .help("Comma-separated list of operations to expose (e.g. GetItem,Query)")
+                )
+                .arg(
+                    Arg::new("read-only")
+                        .long("read-only")
+                        .help("Only expose read-only operations (Describe*, Get*, List*, ...)")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("endpoint-url")
+                        .short('u')
+                        .long("endpoint-url")
+                        .value_name("URL")
+                        .help("Endpoint override (defaults to the regional service endpoint)")
+                )
+                .arg(
+                    Arg::new("prefix")
+                        .short('p')
+                        .long("prefix")
+                        .value_name("PREFIX")
+                        .help("Tool name prefix (defaults to the service endpoint prefix)")
+                )
+        )
         .subcommand(
             Command::new("merge")
                 .about("Merge multiple capability files into one")
@@ -418,10 +681,138 @@ async fn main() -> Result<()> {
                     Arg::new("strict")
                         .short('s')
                         .long("strict")
-                        .help("Enable strict validation")
+                        .help("Validate against the capability file JSON Schema, input schemas and routing configs")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Output format for strict mode diagnostics (text, json)")
+                        .value_parser(["text", "json"])
+                        .default_value("text")
+                )
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("Print the JSON Schema for capability files")
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Write the schema to a file instead of stdout")
+                )
+        )
+        .subcommand(
+            Command::new("migrate")
+                .about("Upgrade capability files to the current schema version")
+                .arg(
+                    Arg::new("input")
+                        .short('i')
+                        .long("input")
+                        .value_name("FILES")
+                        .help("Capability files to migrate in place (comma-separated)")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Report what would change without rewriting files")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("Rewrite files that have comments, which migration removes")
                         .action(ArgAction::SetTrue)
                 )
         )
+        // Registry utilities
+        .subcommand(
+            Command::new("registry")
+                .about("Inspect capability registries")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("diff")
+                        .about("Show added, removed and changed tools between two capability files, directories or registry snapshots")
+                        .arg(
+                            Arg::new("old")
+                                .value_name("OLD")
+                                .help("Old capability file, directory or registry snapshot (.json)")
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::new("new")
+                                .value_name("NEW")
+                                .help("New capability file, directory or registry snapshot (.json)")
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::new("format")
+                                .short('f')
+                                .long("format")
+                                .value_name("FORMAT")
+                                .help("Output format (text, json)")
+                                .default_value("text")
+                        )
+                        .arg(
+                            Arg::new("fail-on-breaking")
+                                .long("fail-on-breaking")
+                                .help("Exit with an error if the diff contains breaking changes")
+                                .action(ArgAction::SetTrue)
+                        )
+                )
+                .subcommand(
+                    Command::new("export")
+                        .about("Export the effective registry of a running server as a snapshot")
+                        .arg(
+                            Arg::new("server")
+                                .long("server")
+                                .value_name("URL")
+                                .help("MagicTunnel server URL")
+                                .default_value("http://localhost:3001")
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .short('o')
+                                .long("output")
+                                .value_name("FILE")
+                                .help("Output snapshot file")
+                                .required(true)
+                        )
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Import a registry snapshot into a running server")
+                        .arg(
+                            Arg::new("snapshot")
+                                .value_name("FILE")
+                                .help("Snapshot file created by 'registry export'")
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::new("server")
+                                .long("server")
+                                .value_name("URL")
+                                .help("MagicTunnel server URL")
+                                .default_value("http://localhost:3001")
+                        )
+                        .arg(
+                            Arg::new("target-dir")
+                                .long("target-dir")
+                                .value_name("DIR")
+                                .help("Directory on the server the capability files are written under (default: its working directory)")
+                        )
+                        .arg(
+                            Arg::new("overwrite")
+                                .long("overwrite")
+                                .help("Replace capability files that already exist")
+                                .action(ArgAction::SetTrue)
+                        )
+                )
+        )
         // MCP Resources Management
         .subcommand(
             Command::new("resources")
@@ -651,6 +1042,50 @@ async fn main() -> Result<()> {
                         .about("Perform health check")
                 )
         )
+        // Embedding Model Management
+        .subcommand(
+            Command::new("embeddings")
+                .about("Manage the embedding model of a running server's semantic search")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new("server")
+                        .long("server")
+                        .value_name("URL")
+                        .help("MagicTunnel server URL")
+                        .default_value("http://localhost:3001")
+                )
+                .subcommand(
+                    Command::new("status")
+                        .about("Show the embedding model in use and the latest swap")
+                )
+                .subcommand(
+                    Command::new("swap")
+                        .about("Re-index all tools with another embedding model and switch to it")
+                        .arg(
+                            Arg::new("model")
+                                .value_name("MODEL")
+                                .help("New model name, e.g. ollama:nomic-embed-text or fastembed:bge-small-en-v1.5")
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::new("provider")
+                                .long("provider")
+                                .value_name("FILE")
+                                .help("YAML or JSON file with a remote embedding provider config (type, model, ...)")
+                        )
+                        .arg(
+                            Arg::new("no-wait")
+                                .long("no-wait")
+                                .help("Return once the swap has started instead of waiting for it")
+                                .action(ArgAction::SetTrue)
+                        )
+                )
+                .subcommand(
+                    Command::new("rollback")
+                        .about("Switch back to the embedding model the latest swap replaced")
+                )
+        )
         .get_matches();
 
     match matches.subcommand() {
@@ -675,12 +1110,33 @@ async fn main() -> Result<()> {
                 generate_openapi_from_args(sub_matches)?;
             }
         },
+        Some(("database", sub_matches)) => {
+            generate_database_from_args(sub_matches).await?;
+        },
+        Some(("cli", sub_matches)) => {
+            generate_cli_from_args(sub_matches).await?;
+        },
+        Some(("har", sub_matches)) => {
+            generate_har_from_args(sub_matches)?;
+        },
+        Some(("aws", sub_matches)) => {
+            generate_aws_from_args(sub_matches)?;
+        },
         Some(("merge", sub_matches)) => {
             merge_capability_files(sub_matches)?;
         },
         Some(("validate", sub_matches)) => {
             validate_capability_files(sub_matches)?;
         },
+        Some(("schema", sub_matches)) => {
+            export_capability_schema(sub_matches)?;
+        },
+        Some(("migrate", sub_matches)) => {
+            migrate_capability_files(sub_matches)?;
+        },
+        Some(("registry", sub_matches)) => {
+            handle_registry_command(sub_matches).await?;
+        },
         Some(("init", sub_matches)) => {
             let output_file = sub_matches.get_one::<String>("output").unwrap();
             initialize_config_file(output_file)?;
@@ -705,6 +1161,10 @@ async fn main() -> Result<()> {
             let server_url = sub_matches.get_one::<String>("server").unwrap();
             handle_server_command(sub_matches, server_url).await?;
         },
+        Some(("embeddings", sub_matches)) => {
+            let server_url = sub_matches.get_one::<String>("server").unwrap();
+            handle_embeddings_command(sub_matches, server_url).await?;
+        },
         _ => unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
     }
 
@@ -1555,6 +2015,153 @@ fn generate_openapi_from_args(matches: &clap::ArgMatches) -> Result<()> {
 }
 
 
+/// Generate capabilities from a HAR capture
+///
+/// Groups the captured requests by method and path template and writes
+/// one HTTP tool per group with schemas inferred from the observed parameters.
+fn generate_har_from_args(matches: &clap::ArgMatches) -> Result<()> {
+    let input_file = matches.get_one::<String>("input").unwrap();
+    let output_file = matches.get_one::<String>("output").unwrap();
+    let split_list = |value: &String| -> Vec<String> {
+        value.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect()
+    };
+
+    let content = fs::read_to_string(input_file)
+        .map_err(|e| ProxyError::config(format!("Failed to read HAR file '{}': {}", input_file, e)))?;
+
+    let config = HarGeneratorConfig {
+        base_url: matches.get_one::<String>("base-url").cloned(),
+        include_hosts: matches.get_one::<String>("hosts").map(split_list),
+        method_filter: matches.get_one::<String>("methods").map(split_list),
+        skip_static_assets: !matches.get_flag("include-static"),
+        tool_prefix: matches.get_one::<String>("prefix").cloned(),
+    };
+
+    println!("Importing HAR capture from '{}'...", input_file);
+    let capability_file = HarCapabilityGenerator::new(config).generate_from_har(&content)?;
+
+    println!("Generated {} tools from HAR capture", capability_file.tools.len());
+    write_capability_file(&capability_file, output_file)?;
+    println!("Capability file written to '{}'", output_file);
+
+    println!("\nGenerated tools:");
+    for tool in &capability_file.tools {
+        println!("  - {}: {}", tool.name, tool.description);
+    }
+
+    Ok(())
+}
+
+
+/// Generate capabilities from an AWS botocore service model
+///
+/// Writes one SigV4-signed HTTP tool per selected operation. Credentials are
+/// read from the standard AWS environment variables when the tools are called.
+fn generate_aws_from_args(matches: &clap::ArgMatches) -> Result<()> {
+    let model_file = matches.get_one::<String>("model").unwrap();
+    let output_file = matches.get_one::<String>("output").unwrap();
+
+    let content = fs::read_to_string(model_file)
+        .map_err(|e| ProxyError::config(format!("Failed to read botocore model '{}': {}", model_file, e)))?;
+
+    let config = AwsGeneratorConfig {
+        region: matches.get_one::<String>("region").unwrap().clone(),
+        endpoint_url: matches.get_one::<String>("endpoint-url").cloned(),
+        include_operations: matches.get_one::<String>("operations").map(|value| {
+            value.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect()
+        }),
+        read_only: matches.get_flag("read-only"),
+        tool_prefix: matches.get_one::<String>("prefix").cloned(),
+    };
+
+    println!("Generating AWS tools from '{}'...", model_file);
+    let capability_file = AwsCapabilityGenerator::new(config).generate_from_model(&content)?;
+
+    println!("Generated {} tools", capability_file.tools.len());
+    write_capability_file(&capability_file, output_file)?;
+    println!("Capability file written to '{}'", output_file);
+
+    println!("\nGenerated tools:");
+    for tool in &capability_file.tools {
+        println!("  - {}: {}", tool.name, tool.description);
+    }
+
+    Ok(())
+}
+
+
+/// Generate capabilities from a command-line tool's help output
+///
+/// Runs `--help` (or reads the man page) for the binary and each requested
+/// subcommand and writes subprocess-routed tools for them.
+async fn generate_cli_from_args(matches: &clap::ArgMatches) -> Result<()> {
+    let binary = matches.get_one::<String>("binary").unwrap();
+    let output_file = matches.get_one::<String>("output").unwrap();
+
+    let config = CliGeneratorConfig {
+        binary: binary.clone(),
+        subcommands: matches.get_one::<String>("subcommands")
+            .map(|subcommands| subcommands.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default(),
+        discover_subcommands: matches.get_flag("discover-subcommands"),
+        use_man_page: !matches.get_flag("no-man"),
+        tool_prefix: matches.get_one::<String>("prefix").cloned(),
+        timeout: matches.get_one::<u64>("timeout").copied(),
+    };
+
+    println!("Parsing help output of '{}'...", binary);
+    let capability_file = CliCapabilityGenerator::new(config).generate().await?;
+
+    println!("Generated {} tools from command-line help", capability_file.tools.len());
+    write_capability_file(&capability_file, output_file)?;
+    println!("Capability file written to '{}'", output_file);
+
+    println!("\nGenerated tools:");
+    for tool in &capability_file.tools {
+        println!("  - {}: {}", tool.name, tool.description);
+    }
+
+    Ok(())
+}
+
+
+/// Generate capabilities by introspecting a database
+///
+/// Connects to the database, reads its tables and columns and writes
+/// parameterized list/get/insert/update tools routed through the database agent.
+async fn generate_database_from_args(matches: &clap::ArgMatches) -> Result<()> {
+    let connection = matches.get_one::<String>("connection").unwrap();
+    let output_file = matches.get_one::<String>("output").unwrap();
+
+    let config = DatabaseGeneratorConfig {
+        connection_string: connection.clone(),
+        routing_connection_string: matches.get_one::<String>("routing-connection").cloned(),
+        schema: matches.get_one::<String>("schema").unwrap().clone(),
+        table_filter: matches.get_one::<String>("tables")
+            .map(|tables| tables.split(',').map(|t| t.trim().to_string()).collect()),
+        tool_prefix: matches.get_one::<String>("prefix").cloned(),
+        read_only: matches.get_flag("read-only"),
+        timeout: matches.get_one::<u64>("timeout").copied(),
+        ..Default::default()
+    };
+    let generator = DatabaseCapabilityGenerator::new(config);
+
+    println!("Introspecting {:?} database...", generator.db_type());
+    let capability_file = generator.generate().await?;
+
+    println!("Generated {} tools from database schema", capability_file.tools.len());
+    write_capability_file(&capability_file, output_file)?;
+    println!("Capability file written to '{}'", output_file);
+
+    println!("\nGenerated tools:");
+    for tool in &capability_file.tools {
+        println!("  - {}: {}", tool.name, tool.description);
+    }
+
+    Ok(())
+}
+
+
 /// Merge capability files
 ///
 /// This function merges multiple capability files into a single file,
@@ -1607,10 +2214,7 @@ fn merge_capability_files(matches: &clap::ArgMatches) -> Result<()> {
         println!("Reading '{}'...", file_path);
         let content = read_file_content(file_path)?;
         
-        let file: CapabilityFile = serde_yaml::from_str(&content)
-            .map_err(|e| ProxyError::config(format!(
-                "Failed to parse capability file '{}': {}", file_path, e
-            )))?;
+        let file: CapabilityFile = parse_capability_file(&content, file_path)?;
         
         capability_files.push(file);
     }
@@ -1669,9 +2273,12 @@ fn validate_capability_files(matches: &clap::ArgMatches) -> Result<()> {
         return Err(ProxyError::config("No input files specified"));
     }
     
-    println!("Validating {} capability files{}...",
-             input_files.len(),
-             if strict_mode { " (strict mode)" } else { "" });
+    if strict_mode {
+        let format = matches.get_one::<String>("format").map(String::as_str).unwrap_or("text");
+        return validate_capability_files_strict(&input_files, format);
+    }
+
+    println!("Validating {} capability files...", input_files.len());
     
     // Create validator
     let validator = CapabilityValidator::new();
@@ -1684,10 +2291,7 @@ fn validate_capability_files(matches: &clap::ArgMatches) -> Result<()> {
         println!("Validating '{}'...", file_path);
         let content = read_file_content(file_path)?;
         
-        let file: CapabilityFile = serde_yaml::from_str(&content)
-            .map_err(|e| ProxyError::config(format!(
-                "Failed to parse capability file '{}': {}", file_path, e
-            )))?;
+        let file: CapabilityFile = parse_capability_file(&content, file_path)?;
         
         // Get validation issues
         let issues = validator.get_validation_issues(&file);
@@ -1710,16 +2314,252 @@ fn validate_capability_files(matches: &clap::ArgMatches) -> Result<()> {
     println!("  Valid files: {}", input_files.len() - (if all_valid { 0 } else { 1 }));
     println!("  Total issues: {}", all_issues.len());
     
-    // In strict mode, fail if any issues were found
-    if strict_mode && !all_valid {
+    Ok(())
+}
+
+/// Validate capability files against the canonical schema
+///
+/// Reports unknown fields, input schemas that do not compile and incomplete
+/// routing configs with their line and column, either as text
+/// (`file:line:column: ...`) or as a JSON array for editor integration.
+/// Fails if any error-level diagnostics were found.
+fn validate_capability_files_strict(input_files: &[&str], format: &str) -> Result<()> {
+    let validator = StrictValidator::new();
+    let mut reports = Vec::new();
+    let mut error_count = 0;
+    let mut warning_count = 0;
+
+    for file_path in input_files {
+        let content = read_file_content(file_path)?;
+        for diagnostic in validator.validate_content(&content) {
+            match diagnostic.severity {
+                Severity::Error => error_count += 1,
+                Severity::Warning => warning_count += 1,
+            }
+            reports.push((file_path.to_string(), diagnostic));
+        }
+    }
+
+    if format == "json" {
+        let output: Vec<Value> = reports.iter()
+            .map(|(file, diagnostic)| {
+                let mut value = serde_json::to_value(diagnostic).unwrap_or_default();
+                value["file"] = json!(file);
+                value
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        for (file, diagnostic) in &reports {
+            println!("{}:{}", file, diagnostic);
+        }
+        println!("\nValidated {} files: {} errors, {} warnings",
+                 input_files.len(), error_count, warning_count);
+    }
+
+    if error_count > 0 {
         return Err(ProxyError::validation(format!(
-            "Validation failed with {} issues", all_issues.len()
+            "Strict validation failed with {} errors", error_count
         )));
     }
-    
+
     Ok(())
 }
 
+/// Print or write the JSON Schema describing the capability file format
+fn export_capability_schema(matches: &clap::ArgMatches) -> Result<()> {
+    let schema = serde_json::to_string_pretty(&capability_file_schema())?;
+
+    match matches.get_one::<String>("output") {
+        Some(output_file) => {
+            fs::write(output_file, format!("{}\n", schema))?;
+            println!("Wrote capability file schema to '{}'", output_file);
+        }
+        None => println!("{}", schema),
+    }
+
+    Ok(())
+}
+
+/// Migrate capability files to the current schema version
+///
+/// This function upgrades one or more capability files written in an older
+/// format and rewrites them in place, printing a deprecation note for each
+/// legacy construct that was rewritten. Rewritten files lose their comments
+/// and formatting, so files with comments are left untouched unless
+/// `--force` is given.
+///
+/// # Arguments
+///
+/// * `matches` - The command-line arguments for the migrate subcommand
+///
+/// # Returns
+///
+/// A Result indicating success or an error with details
+///
+/// # Example
+///
+/// ```
+/// migrate_capability_files(sub_matches)?;
+/// ```
+fn migrate_capability_files(matches: &clap::ArgMatches) -> Result<()> {
+    let input_files_str = matches.get_one::<String>("input").unwrap();
+    let dry_run = matches.get_flag("dry-run");
+    let force = matches.get_flag("force");
+
+    let input_files: Vec<&str> = input_files_str.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    if input_files.is_empty() {
+        return Err(ProxyError::config("No input files specified"));
+    }
+
+    let mut migrated_count = 0;
+    let mut skipped = Vec::new();
+    for file_path in &input_files {
+        let content = read_file_content(file_path)?;
+        let outcome = migrate_content(&content)
+            .map_err(|e| ProxyError::config(format!(
+                "Failed to migrate capability file '{}': {}", file_path, e
+            )))?;
+
+        if !outcome.migrated() {
+            println!("'{}' is already at schema_version {}", file_path, outcome.to_version);
+            continue;
+        }
+
+        println!("'{}': schema_version {} -> {}", file_path, outcome.from_version, outcome.to_version);
+        for warning in &outcome.warnings {
+            println!("  - {}", warning);
+        }
+
+        // Make sure the upgraded document still deserializes before touching the file
+        let upgraded = outcome.to_yaml()?;
+        parse_capability_file(&upgraded, file_path)?;
+
+        if has_yaml_comments(&content) {
+            println!("  ! '{}' has comments; the rewritten file will not keep them or its formatting", file_path);
+            if !force && !dry_run {
+                skipped.push(*file_path);
+                continue;
+            }
+        }
+
+        if !dry_run {
+            fs::write(file_path, upgraded)
+                .map_err(|e| ProxyError::config(format!("Failed to write '{}': {}", file_path, e)))?;
+        }
+        migrated_count += 1;
+    }
+
+    println!("\n{} of {} files {}",
+             migrated_count,
+             input_files.len(),
+             if dry_run { "would be migrated (dry run)" } else { "migrated" });
+
+    if !skipped.is_empty() {
+        return Err(ProxyError::config(format!(
+            "Not rewriting {} because migrating would remove their comments; re-run with --force to rewrite them anyway",
+            skipped.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Handle registry subcommands
+///
+/// # Arguments
+///
+/// * `matches` - The command-line arguments for the registry subcommand
+///
+/// # Returns
+///
+/// A Result indicating success or an error with details
+async fn handle_registry_command(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("diff", sub_matches)) => {
+            let old = sub_matches.get_one::<String>("old").unwrap();
+            let new = sub_matches.get_one::<String>("new").unwrap();
+            let format = sub_matches.get_one::<String>("format").unwrap();
+
+            let diff = CapabilityDiffer::new().diff_paths(old, new)?;
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&diff)?),
+                "text" => print!("{}", diff.to_text()),
+                _ => return Err(ProxyError::config(format!("Invalid format: {}. Use 'text' or 'json'", format))),
+            }
+
+            if sub_matches.get_flag("fail-on-breaking") && diff.has_breaking_changes() {
+                return Err(ProxyError::validation("Capability diff contains breaking changes"));
+            }
+        },
+        Some(("export", sub_matches)) => {
+            let server_url = sub_matches.get_one::<String>("server").unwrap();
+            let output_file = sub_matches.get_one::<String>("output").unwrap();
+            println!("📦 Exporting registry snapshot from {}...", server_url);
+
+            let response = Client::new()
+                .get(&format!("{}/dashboard/api/registry/snapshot", server_url))
+                .send()
+                .await
+                .map_err(|e| ProxyError::connection(format!("Failed to fetch registry snapshot: {}", e)))?;
+
+            if !response.status().is_success() {
+                return Err(ProxyError::connection(format!("Server returned status: {}", response.status())));
+            }
+
+            let snapshot: RegistrySnapshot = response.json().await
+                .map_err(|e| ProxyError::connection(format!("Failed to parse registry snapshot: {}", e)))?;
+            fs::write(output_file, serde_json::to_string_pretty(&snapshot)?)
+                .map_err(|e| ProxyError::config(format!("Failed to write file: {}", e)))?;
+
+            println!("✅ Exported {} capability file(s) with {} tool(s) to {}", snapshot.files.len(), snapshot.tool_count(), output_file);
+        },
+        Some(("import", sub_matches)) => {
+            let snapshot_file = sub_matches.get_one::<String>("snapshot").unwrap();
+            let server_url = sub_matches.get_one::<String>("server").unwrap();
+
+            let content = fs::read_to_string(snapshot_file)
+                .map_err(|e| ProxyError::config(format!("Failed to read snapshot file '{}': {}", snapshot_file, e)))?;
+            let snapshot: RegistrySnapshot = serde_json::from_str(&content)
+                .map_err(|e| ProxyError::config(format!("Failed to parse snapshot file '{}': {}", snapshot_file, e)))?;
+            snapshot.validate()?;
+
+            println!("📥 Importing {} capability file(s) into {}...", snapshot.files.len(), server_url);
+            let response = Client::new()
+                .post(&format!("{}/dashboard/api/registry/snapshot/import", server_url))
+                .json(&json!({
+                    "snapshot": snapshot,
+                    "target_dir": sub_matches.get_one::<String>("target-dir"),
+                    "overwrite": sub_matches.get_flag("overwrite")
+                }))
+                .send()
+                .await
+                .map_err(|e| ProxyError::connection(format!("Failed to import registry snapshot: {}", e)))?;
+
+            let status = response.status();
+            let data: Value = response.json().await
+                .map_err(|e| ProxyError::connection(format!("Failed to parse response: {}", e)))?;
+            if !status.is_success() {
+                return Err(ProxyError::connection(format!(
+                    "Server returned status {}: {}",
+                    status,
+                    data.get("error").and_then(|e| e.as_str()).unwrap_or("unknown error")
+                )));
+            }
+
+            let report = &data["report"];
+            let count = |key: &str| report[key].as_array().map(|a| a.len()).unwrap_or(0);
+            println!("✅ Wrote {} file(s), skipped {} existing file(s); the registry now has {} tool(s)",
+                count("written"), count("skipped"), data["tool_count"]);
+        },
+        _ => unreachable!("subcommand_required prevents `None`"),
+    }
+
+    Ok(())
+}
 
 /// Parse streaming strategy from string
 ///
@@ -2321,4 +3161,126 @@ async fn handle_server_command(matches: &ArgMatches, server_url: &str) -> Result
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+async fn handle_embeddings_command(matches: &ArgMatches, server_url: &str) -> Result<()> {
+    let client = Client::new();
+    let model_url = format!("{}/dashboard/api/discovery/embeddings/model", server_url);
+
+    match matches.subcommand() {
+        Some(("status", _)) => {
+            let status = fetch_embedding_model_status(&client, &model_url).await?;
+            print_embedding_model_status(&status);
+        },
+        Some(("swap", sub_matches)) => {
+            let model = sub_matches.get_one::<String>("model").unwrap();
+            let provider = match sub_matches.get_one::<String>("provider") {
+                Some(provider_file) => {
+                    let content = fs::read_to_string(provider_file)
+                        .map_err(|e| ProxyError::config(format!("Failed to read provider file '{}': {}", provider_file, e)))?;
+                    let provider: EmbeddingProviderConfig = serde_yaml::from_str(&content)
+                        .map_err(|e| ProxyError::config(format!("Failed to parse provider file '{}': {}", provider_file, e)))?;
+                    Some(provider)
+                }
+                None => None,
+            };
+            let change = EmbeddingModelChange { model_name: model.clone(), provider };
+            change.validate()?;
+
+            println!("🧠 Re-indexing tools with {} on {}...", model, server_url);
+            let response = client
+                .post(&model_url)
+                .json(&change)
+                .send()
+                .await
+                .map_err(|e| ProxyError::connection(format!("Failed to start embedding model swap: {}", e)))?;
+            let status_code = response.status();
+            let data: Value = response.json().await
+                .map_err(|e| ProxyError::connection(format!("Failed to parse response: {}", e)))?;
+            if !status_code.is_success() {
+                return Err(ProxyError::connection(format!(
+                    "Server returned status {}: {}",
+                    status_code,
+                    data.get("error").and_then(|e| e.as_str()).unwrap_or("unknown error")
+                )));
+            }
+
+            if sub_matches.get_flag("no-wait") {
+                println!("✅ Swap started; check progress with 'magictunnel-cli embeddings status'");
+                return Ok(());
+            }
+
+            let status = loop {
+                tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                let status = fetch_embedding_model_status(&client, &model_url).await?;
+                if status["state"] != "building" {
+                    break status;
+                }
+                print!(".");
+                io::stdout().flush().ok();
+            };
+            println!();
+            print_embedding_model_status(&status);
+            if status["state"] != "completed" {
+                return Err(ProxyError::validation(format!(
+                    "Embedding model swap failed: {}", status["error"].as_str().unwrap_or("unknown error")
+                )));
+            }
+        },
+        Some(("rollback", _)) => {
+            println!("⏪ Rolling back the embedding model on {}...", server_url);
+            let response = client
+                .post(&format!("{}/rollback", model_url))
+                .send()
+                .await
+                .map_err(|e| ProxyError::connection(format!("Failed to roll back embedding model: {}", e)))?;
+            let status_code = response.status();
+            let data: Value = response.json().await
+                .map_err(|e| ProxyError::connection(format!("Failed to parse response: {}", e)))?;
+            if !status_code.is_success() {
+                return Err(ProxyError::connection(format!(
+                    "Server returned status {}: {}",
+                    status_code,
+                    data.get("error").and_then(|e| e.as_str()).unwrap_or("unknown error")
+                )));
+            }
+            print_embedding_model_status(&data["status"]);
+        },
+        _ => unreachable!("subcommand_required prevents `None`"),
+    }
+
+    Ok(())
+}
+
+/// Embedding model status of a running server
+async fn fetch_embedding_model_status(client: &Client, model_url: &str) -> Result<Value> {
+    let response = client
+        .get(model_url)
+        .send()
+        .await
+        .map_err(|e| ProxyError::connection(format!("Failed to fetch embedding model status: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(ProxyError::connection(format!("Server returned status: {}", response.status())));
+    }
+    let data: Value = response.json().await
+        .map_err(|e| ProxyError::connection(format!("Failed to parse response: {}", e)))?;
+    Ok(data["status"].clone())
+}
+
+fn print_embedding_model_status(status: &Value) {
+    let model = |key: &str| status[key].as_str().unwrap_or("none").to_string();
+    println!("🧠 Embedding model: {}", model("active_model"));
+    println!("   Rollback model: {}", model("rollback_model"));
+    match status["state"].as_str().unwrap_or("idle") {
+        "building" => println!("   ⏳ Building index with {}", model("target_model")),
+        "completed" => {
+            let report = &status["report"];
+            println!("   ✅ Swapped {} for {}: {} tools, {} dimensions, built in {}ms",
+                report["previous_model"].as_str().unwrap_or("?"), report["model"].as_str().unwrap_or("?"),
+                report["tools_indexed"], report["dimensions"], report["duration_ms"]);
+        }
+        "failed" => println!("   ❌ Swap to {} failed: {}", model("target_model"), status["error"].as_str().unwrap_or("unknown error")),
+        "rolled_back" => println!("   ⏪ Rolled back"),
+        _ => {}
+    }
+}
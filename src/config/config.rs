@@ -15,6 +15,7 @@ fn default_client_version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
 }
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
@@ -64,6 +65,54 @@ pub struct Config {
     pub visibility: Option<VisibilityConfig>,
     /// Smart Discovery configuration
     pub smart_discovery: Option<crate::discovery::SmartDiscoveryConfig>,
+    /// Rate limits on tool calls per tool, API key and external MCP server
+    #[serde(default)]
+    pub rate_limiting: Option<crate::routing::rate_limit::RateLimitingConfig>,
+    /// Timeouts of tool calls by default, agent type and tool
+    #[serde(default)]
+    pub timeouts: Option<crate::routing::timeout::TimeoutConfig>,
+    /// Tools that are always dry-run instead of dispatched
+    #[serde(default)]
+    pub dry_run: Option<crate::routing::dry_run::DryRunConfig>,
+    /// Where background jobs are persisted and how long finished jobs are kept
+    #[serde(default)]
+    pub jobs: Option<crate::mcp::jobs::JobsConfig>,
+    /// Spend budgets for tools with a cost, per API key, user and session
+    #[serde(default)]
+    pub costs: Option<crate::routing::cost::CostsConfig>,
+    /// Idle and absolute timeouts of MCP sessions
+    #[serde(default)]
+    pub sessions: Option<crate::mcp::session::SessionsConfig>,
+    /// Calls per hour and day and cumulative cost allowed per API key, user and role
+    #[serde(default)]
+    pub quotas: Option<crate::routing::quota::QuotasConfig>,
+    /// Time windows restricting when tools may be called
+    #[serde(default)]
+    pub access_windows: Option<crate::routing::access_window::AccessWindowsConfig>,
+    /// Open Policy Agent policies authorizing tool calls
+    #[serde(default)]
+    pub policy: Option<crate::routing::policy::PolicyConfig>,
+    /// Redaction of credentials in tool arguments and results
+    #[serde(default)]
+    pub secret_scanning: Option<crate::routing::secret_scan::SecretScanningConfig>,
+    /// Detection of personal data in tool arguments and results, and what is done about it
+    #[serde(default)]
+    pub pii: Option<crate::routing::pii::PiiConfig>,
+    /// Tool calls that wait for an approver's decision
+    #[serde(default)]
+    pub approvals: Option<crate::routing::approval::ApprovalConfig>,
+    /// Export of audit events to storage backends such as syslog
+    #[serde(default)]
+    pub audit: Option<crate::audit::AuditConfig>,
+    /// Baselines of API key and user behavior, alerting on or throttling calls deviating from them
+    #[serde(default)]
+    pub threat_detection: Option<crate::routing::threat_detection::ThreatDetectionConfig>,
+    /// Networks and countries allowed to reach the MCP, dashboard and gRPC endpoints
+    #[serde(default)]
+    pub network_policy: Option<crate::auth::network_policy::NetworkPolicyConfig>,
+    /// Circuit breakers short-circuiting calls to repeatedly failing tools
+    #[serde(default)]
+    pub circuit_breakers: Option<crate::routing::ToolCircuitBreakerConfig>,
 }
 
 /// Server configuration
@@ -143,6 +192,198 @@ pub struct RegistryConfig {
     pub hot_reload: bool,
     /// Validation settings
     pub validation: ValidationConfig,
+    /// Settings for remote (git, HTTP, S3) entries in `paths`
+    #[serde(default)]
+    pub remote: RemoteRegistryConfig,
+    /// SQLite storage settings (used when `type` is `sqlite`)
+    #[serde(default)]
+    pub sqlite: SqliteRegistryConfig,
+    /// Resolution of unversioned names when several versions of a tool are registered
+    #[serde(default)]
+    pub versioning: ToolVersioningConfig,
+    /// Named toolsets: tag expressions (e.g. `git and not destructive`) or bundles
+    /// of tools that can be bound to API keys or activated by sessions
+    #[serde(default)]
+    pub toolsets: std::collections::HashMap<String, ToolsetDefinition>,
+    /// `${VAR}` / `${VAR:-default}` interpolation in capability file routing configs
+    #[serde(default)]
+    pub env_templating: EnvTemplatingConfig,
+    /// Persisted enable/disable and hide/show overrides set at runtime
+    #[serde(default)]
+    pub tool_state: ToolStateConfig,
+}
+
+/// Named toolset definition
+///
+/// Either a tag expression, or a bundle listing tools by name, optionally
+/// extended with a tag expression:
+///
+/// ```yaml
+/// toolsets:
+///   safe_git: "git and not destructive"
+///   code_review:
+///     description: "Pull request review"
+///     tools: [github_get_pull_request, github_list_pr_files]
+///     tags: "lint or diff"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolsetDefinition {
+    /// Tag expression selecting the tools
+    Expression(String),
+    /// Tools listed by name and/or selected by a tag expression
+    Bundle {
+        /// Description shown to clients
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        /// Tool names; a bare name includes every version of a versioned tool
+        #[serde(default)]
+        tools: Vec<String>,
+        /// Tag expression selecting further tools
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tags: Option<String>,
+    },
+}
+
+impl From<String> for ToolsetDefinition {
+    fn from(expression: String) -> Self {
+        ToolsetDefinition::Expression(expression)
+    }
+}
+
+impl From<&str> for ToolsetDefinition {
+    fn from(expression: &str) -> Self {
+        ToolsetDefinition::Expression(expression.to_string())
+    }
+}
+
+/// Environment variable templating in capability files
+///
+/// String values in tool routing configs (URLs, headers, commands, ...) may
+/// reference environment variables as `${VAR}` or `${VAR:-default}`; they are
+/// resolved when the capability files are loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvTemplatingConfig {
+    /// Interpolate environment variables at load time
+    #[serde(default = "default_env_templating_enabled")]
+    pub enabled: bool,
+    /// Fail the load when a variable is unset and has no default
+    /// (otherwise the placeholder is kept and a warning is logged)
+    #[serde(default)]
+    pub strict: bool,
+}
+
+fn default_env_templating_enabled() -> bool { true }
+
+impl Default for EnvTemplatingConfig {
+    fn default() -> Self {
+        Self { enabled: default_env_templating_enabled(), strict: false }
+    }
+}
+
+/// Tool versioning configuration
+///
+/// Tools can be registered under versioned names such as `search_code@v2`.
+/// Calls that use an explicit version always get that version; calls that use
+/// the bare name (`search_code`) are resolved as follows:
+/// 1. a version pinned in `defaults`
+/// 2. an unversioned definition with exactly that name
+/// 3. the version selected by `default_policy`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolVersioningConfig {
+    /// Policy for picking the default version of a tool
+    #[serde(default)]
+    pub default_policy: ToolVersionPolicy,
+    /// Pinned default versions by bare tool name (e.g. `search_code: v1`)
+    #[serde(default)]
+    pub defaults: std::collections::HashMap<String, String>,
+}
+
+/// Policy for picking the default version of a tool
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolVersionPolicy {
+    /// Resolve to the highest version
+    #[default]
+    Latest,
+    /// Resolve to the lowest version, so clients opt in to newer versions explicitly
+    Oldest,
+}
+
+/// SQLite registry storage configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqliteRegistryConfig {
+    /// Database file the parsed capability files are persisted to
+    #[serde(default = "default_sqlite_registry_path")]
+    pub path: String,
+}
+
+fn default_sqlite_registry_path() -> String { "./.magictunnel/registry.db".to_string() }
+
+impl Default for SqliteRegistryConfig {
+    fn default() -> Self {
+        Self { path: default_sqlite_registry_path() }
+    }
+}
+
+/// Runtime tool state configuration
+///
+/// Tools and capability files enabled, disabled, hidden or shown through the
+/// management API are recorded in this file instead of the capability YAML,
+/// and re-applied on every registry load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolStateConfig {
+    /// JSON file the overrides are persisted to
+    #[serde(default = "default_tool_state_path")]
+    pub path: String,
+}
+
+fn default_tool_state_path() -> String { "./.magictunnel/tool-state.json".to_string() }
+
+impl Default for ToolStateConfig {
+    fn default() -> Self {
+        Self { path: default_tool_state_path() }
+    }
+}
+
+/// Remote registry source configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteRegistryConfig {
+    /// Local directory remote sources are synced into
+    #[serde(default = "default_remote_cache_dir")]
+    pub cache_dir: String,
+    /// Interval between remote refreshes in seconds (0 disables)
+    #[serde(default = "default_remote_refresh_interval")]
+    pub refresh_interval_seconds: u64,
+    /// Timeout for a single sync (HTTP request, git or aws command) in seconds
+    #[serde(default = "default_remote_timeout")]
+    pub timeout_seconds: u64,
+    /// Headers sent with HTTP source requests; values may be secret references
+    #[serde(default)]
+    pub http_headers: std::collections::HashMap<String, String>,
+    /// AWS region for S3 sources
+    #[serde(default)]
+    pub s3_region: Option<String>,
+    /// Custom S3 endpoint (e.g. MinIO)
+    #[serde(default)]
+    pub s3_endpoint_url: Option<String>,
+}
+
+fn default_remote_cache_dir() -> String { "./.magictunnel/registry-cache".to_string() }
+fn default_remote_refresh_interval() -> u64 { 300 }
+fn default_remote_timeout() -> u64 { 60 }
+
+impl Default for RemoteRegistryConfig {
+    fn default() -> Self {
+        Self {
+            cache_dir: default_remote_cache_dir(),
+            refresh_interval_seconds: default_remote_refresh_interval(),
+            timeout_seconds: default_remote_timeout(),
+            http_headers: std::collections::HashMap::new(),
+            s3_region: None,
+            s3_endpoint_url: None,
+        }
+    }
 }
 
 /// Validation configuration
@@ -152,6 +393,9 @@ pub struct ValidationConfig {
     pub strict: bool,
     /// Allow unknown fields
     pub allow_unknown_fields: bool,
+    /// What happens to tool results that don't match the tool's output schema
+    #[serde(default)]
+    pub output: crate::registry::output_schema::OutputValidationMode,
 }
 
 /// Authentication configuration
@@ -161,12 +405,203 @@ pub struct AuthConfig {
     pub enabled: bool,
     /// Authentication type (api_key, oauth, jwt)
     pub r#type: AuthType,
+    /// Authentication methods tried in order for each request, the first
+    /// accepting the request's credentials authenticating it (default: `type` alone)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<AuthType>,
     /// API key configuration (for api_key auth)
     pub api_keys: Option<ApiKeyConfig>,
     /// OAuth configuration (for oauth auth)
     pub oauth: Option<OAuthConfig>,
     /// JWT configuration (for jwt auth)
     pub jwt: Option<JwtConfig>,
+    /// Client certificate configuration (for client_cert auth)
+    #[serde(default)]
+    pub client_cert: Option<ClientCertConfig>,
+    /// Request signing configuration (for hmac auth)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hmac: Option<HmacConfig>,
+    /// OAuth protected resource metadata advertised to MCP clients
+    #[serde(default)]
+    pub resource_server: Option<ResourceServerConfig>,
+    /// OAuth dynamic client registration
+    #[serde(default)]
+    pub client_registration: Option<ClientRegistrationConfig>,
+    /// Permissions granted to each role; JWT users get those of the roles in their token
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub roles: BTreeMap<String, Vec<String>>,
+    /// Roles granted to OAuth and JWT users by their provider scopes and claims
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub role_mappings: Vec<RoleMappingConfig>,
+    /// Restricted access for requests that present no credentials
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guest: Option<GuestAccessConfig>,
+    /// Authentication of requests made to a tenant's hosts or path prefix,
+    /// replacing this configuration for them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tenants: Vec<TenantAuthConfig>,
+    /// Encryption of OAuth tokens stored on disk; they are stored in plaintext without it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_encryption: Option<TokenEncryptionConfig>,
+}
+
+/// Authentication configuration of one tenant
+///
+/// A request belongs to the first tenant whose `hosts` contain its Host header
+/// (port ignored) and whose `path_prefix` its path starts with; criteria left
+/// unset match any request. Tenants have their own providers, API keys and
+/// roles, so credentials of one tenant are not accepted by another.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantAuthConfig {
+    /// Tenant name, used in logs
+    pub name: String,
+    /// Host names served to the tenant
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// Path prefix served to the tenant
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+    /// Authentication of the tenant's requests
+    #[serde(flatten)]
+    pub auth: AuthConfig,
+}
+
+impl TenantAuthConfig {
+    /// Whether a request to `host` and `path` belongs to the tenant
+    pub fn matches(&self, host: &str, path: &str) -> bool {
+        let host = host.split(':').next().unwrap_or(host);
+        let host_matches = self.hosts.is_empty() || self.hosts.iter().any(|tenant_host| tenant_host.eq_ignore_ascii_case(host));
+        let path_matches = self.path_prefix.as_deref().map_or(true, |prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            path == prefix || path.starts_with(&format!("{}/", prefix))
+        });
+        host_matches && path_matches
+    }
+}
+
+/// Roles granted to users whose provider scopes and claims match
+///
+/// Every criterion given must match, by any of its values; criteria left
+/// empty match any user. Mappings are evaluated when a user authenticates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleMappingConfig {
+    /// Roles granted, defined in `auth.roles`
+    pub roles: Vec<String>,
+    /// Scopes of the token (`scope` or `scp` claim, OAuth token scopes)
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Groups of the user (`groups` claim)
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// Organizations of the user (`org` or `orgs` claim)
+    #[serde(default)]
+    pub orgs: Vec<String>,
+    /// Domains of the user's email address
+    #[serde(default)]
+    pub email_domains: Vec<String>,
+}
+
+impl RoleMappingConfig {
+    pub fn validate(&self, roles: &BTreeMap<String, Vec<String>>) -> Result<()> {
+        if self.roles.is_empty() {
+            return Err(ProxyError::config("Role mapping must grant at least one role"));
+        }
+        if let Some(role) = self.roles.iter().find(|role| !roles.contains_key(*role)) {
+            return Err(ProxyError::config(format!("Role mapping grants undefined role '{}'", role)));
+        }
+        if self.scopes.is_empty() && self.groups.is_empty() && self.orgs.is_empty() && self.email_domains.is_empty() {
+            return Err(ProxyError::config(format!(
+                "Role mapping granting {:?} needs at least one of scopes, groups, orgs or email_domains",
+                self.roles
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn default_guest_permissions() -> Vec<String> { vec!["read".to_string(), "write".to_string()] }
+
+/// Guest access: requests without credentials are served as guests instead
+/// of being rejected, limited to a toolset or scope patterns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuestAccessConfig {
+    /// Serve requests without credentials as guests
+    #[serde(default)]
+    pub enabled: bool,
+    /// Toolset guests are restricted to
+    #[serde(default)]
+    pub toolset: Option<String>,
+    /// Tool name patterns guests are restricted to
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Permissions of guests (default: read and write, listing and calling tools)
+    #[serde(default = "default_guest_permissions")]
+    pub permissions: Vec<String>,
+}
+
+impl GuestAccessConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.toolset.is_none() && self.scopes.is_empty() {
+            return Err(ProxyError::config("Guest access must be restricted to a toolset or scopes"));
+        }
+        if self.permissions.iter().any(|permission| permission == "admin") {
+            return Err(ProxyError::config("Guests cannot be granted the 'admin' permission"));
+        }
+        crate::registry::toolset::ScopePatterns::parse(&self.scopes)
+            .map_err(|e| ProxyError::config(format!("Invalid guest scopes: {}", e)))?;
+        Ok(())
+    }
+}
+
+fn default_client_registration_path() -> String { "./.magictunnel/oauth-clients.json".to_string() }
+fn default_require_approval() -> bool { true }
+
+/// OAuth dynamic client registration (RFC 7591) at `/auth/oauth/register`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientRegistrationConfig {
+    /// Accept client registrations
+    #[serde(default)]
+    pub enabled: bool,
+    /// Hold new clients until they are approved in the dashboard
+    #[serde(default = "default_require_approval")]
+    pub require_approval: bool,
+    /// File registered clients are persisted to
+    #[serde(default = "default_client_registration_path")]
+    pub state_path: String,
+}
+
+impl Default for ClientRegistrationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            require_approval: default_require_approval(),
+            state_path: default_client_registration_path(),
+        }
+    }
+}
+
+/// OAuth 2.0 protected resource settings (RFC 9728)
+///
+/// Served at `/.well-known/oauth-protected-resource` and referenced from the
+/// `WWW-Authenticate` challenge of 401 responses, so MCP clients can discover
+/// where to get a token for this server.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResourceServerConfig {
+    /// Canonical URI of this server; JWTs must name it as an audience (default: the request's origin, not enforced)
+    #[serde(default)]
+    pub resource: Option<String>,
+    /// Authorization servers issuing tokens for this server (default: the JWT issuers)
+    #[serde(default)]
+    pub authorization_servers: Vec<String>,
+    /// Scopes clients can request
+    #[serde(default)]
+    pub scopes_supported: Vec<String>,
+    /// Documentation for developers of clients
+    #[serde(default)]
+    pub resource_documentation: Option<String>,
 }
 
 /// Authentication type enumeration
@@ -181,6 +616,10 @@ pub enum AuthType {
     OAuth,
     /// JWT token authentication
     Jwt,
+    /// TLS client certificate (mTLS) authentication
+    ClientCert,
+    /// HMAC request signing with shared secrets
+    Hmac,
 }
 
 impl std::fmt::Display for AuthType {
@@ -190,6 +629,8 @@ impl std::fmt::Display for AuthType {
             AuthType::ApiKey => write!(f, "api_key"),
             AuthType::OAuth => write!(f, "oauth"),
             AuthType::Jwt => write!(f, "jwt"),
+            AuthType::ClientCert => write!(f, "client_cert"),
+            AuthType::Hmac => write!(f, "hmac"),
         }
     }
 }
@@ -200,6 +641,44 @@ impl PartialEq<&str> for AuthType {
     }
 }
 
+/// Encryption of stored OAuth tokens
+///
+/// Tokens are encrypted with AES-256-GCM under a key derived from `kek`, a
+/// secret reference (`${env:..}`, `${file:..}` or `${vault:..}`). A key kept
+/// in a KMS is read through `${vault:..}`; there is no other KMS client. To
+/// rotate the key, set the new one as `kek` and move the old one to
+/// `previous_keks`: tokens still encrypted with it are re-encrypted with the
+/// new key when they are read, after which it can be removed.
+///
+/// Whenever this is configured, plaintext token storage is refused and
+/// plaintext files are encrypted on first read. There is no separate runtime
+/// mode that turns this on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenEncryptionConfig {
+    /// Key encryption key
+    pub kek: String,
+    /// Keys stored tokens may still be encrypted with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previous_keks: Vec<String>,
+}
+
+impl TokenEncryptionConfig {
+    /// Validate token encryption configuration
+    pub fn validate(&self) -> Result<()> {
+        for kek in std::iter::once(&self.kek).chain(&self.previous_keks) {
+            if kek.is_empty() {
+                return Err(ProxyError::config("Token encryption key cannot be empty"));
+            }
+            if !crate::config::secrets::is_secret_reference(kek) && kek.len() < 32 {
+                return Err(ProxyError::config(
+                    "Literal token encryption key is too short. Minimum length is 32 characters",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// API key configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiKeyConfig {
@@ -211,6 +690,87 @@ pub struct ApiKeyConfig {
     pub header_name: String,
     /// Expected header format (default: "Bearer {key}")
     pub header_format: String,
+    /// Rotation and expiry warnings
+    #[serde(default)]
+    pub rotation: ApiKeyRotationConfig,
+}
+
+/// Per-user directory for state that must not depend on the working
+/// directory, such as cached credentials: the platform's local data
+/// directory, or `~/.magictunnel` where there is none
+pub fn user_data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .map(|dir| dir.join("magictunnel"))
+        .or_else(|| dirs::home_dir().map(|dir| dir.join(".magictunnel")))
+        .unwrap_or_else(|| PathBuf::from(".magictunnel"))
+}
+
+/// Replace `path` with `content` without readers ever seeing a partial file
+///
+/// The content is written and synced to a uniquely named file next to
+/// `path`, so concurrent saves don't clobber each other's, which is then
+/// renamed over it. With `private`, the file is readable and writable by the
+/// current user only from the moment it is created.
+pub fn write_atomic(path: &Path, content: &[u8], private: bool) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", uuid::Uuid::new_v4().simple()));
+    let tmp_path = path.with_file_name(name);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    let write = || -> std::io::Result<()> {
+        let mut file = options.open(&tmp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)
+    };
+    write().map_err(|e| {
+        let _ = std::fs::remove_file(&tmp_path);
+        e
+    })
+}
+
+fn default_api_key_state_path() -> String { "./.magictunnel/api-keys.json".to_string() }
+fn default_grace_period_seconds() -> u64 { 86400 }
+fn default_expiry_warning_days() -> u32 { 14 }
+
+/// API key rotation configuration
+///
+/// Rotating a key issues a new secret; the previous one keeps working for
+/// the grace period. Rotated secrets are persisted (as SHA-256 hashes) in
+/// `state_path`, so they survive restarts without editing this file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyRotationConfig {
+    /// JSON file the rotated keys are persisted to
+    #[serde(default = "default_api_key_state_path")]
+    pub state_path: String,
+    /// How long a replaced secret stays valid (default: one day)
+    #[serde(default = "default_grace_period_seconds")]
+    pub grace_period_seconds: u64,
+    /// Lifetime of rotated keys in days; `None` keeps them valid until rotated again
+    #[serde(default)]
+    pub key_lifetime_days: Option<u32>,
+    /// Warn this many days before a key expires
+    #[serde(default = "default_expiry_warning_days")]
+    pub expiry_warning_days: u32,
+}
+
+impl Default for ApiKeyRotationConfig {
+    fn default() -> Self {
+        Self {
+            state_path: default_api_key_state_path(),
+            grace_period_seconds: default_grace_period_seconds(),
+            key_lifetime_days: None,
+            expiry_warning_days: default_expiry_warning_days(),
+        }
+    }
 }
 
 /// Individual API key entry with metadata
@@ -228,12 +788,27 @@ pub struct ApiKeyEntry {
     pub expires_at: Option<String>,
     /// Whether this key is active
     pub active: bool,
+    /// Toolset (from `registry.toolsets`) this key is restricted to
+    #[serde(default)]
+    pub toolset: Option<String>,
+    /// Tools this key may list and call, as `tool:<glob>` or `capability:<glob>`
+    /// patterns (a bare glob matches tool names); empty allows every tool
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
 }
 
+fn default_jwks_cache_seconds() -> u64 { 3600 }
+fn default_jwks_min_refresh_seconds() -> u64 { 30 }
+
 /// JWT configuration
+///
+/// Tokens are verified with `secret`, or, when `jwks_url` or
+/// `trusted_issuers` are set, with the public keys the identity provider
+/// publishes at its JWKS endpoint.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JwtConfig {
     /// JWT secret key for validation
+    #[serde(default)]
     pub secret: String,
     /// JWT algorithm (HS256, RS256, etc.)
     pub algorithm: String,
@@ -243,6 +818,148 @@ pub struct JwtConfig {
     pub issuer: Option<String>,
     /// JWT audience
     pub audience: Option<String>,
+    /// JWKS endpoint publishing the keys tokens of `issuer` are signed with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwks_url: Option<String>,
+    /// Further identity providers whose tokens are accepted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_issuers: Vec<JwtIssuerConfig>,
+    /// Audiences accepted besides `audience`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audiences: Vec<String>,
+    /// How long fetched JWKS keys are cached, in seconds
+    #[serde(default = "default_jwks_cache_seconds")]
+    pub jwks_cache_seconds: u64,
+    /// Minimum time between JWKS re-fetches triggered by an unknown `kid`, in seconds
+    #[serde(default = "default_jwks_min_refresh_seconds")]
+    pub jwks_min_refresh_seconds: u64,
+}
+
+/// Identity provider trusted to issue JWTs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwtIssuerConfig {
+    /// Expected `iss` claim
+    pub issuer: String,
+    /// JWKS endpoint publishing the issuer's signing keys
+    pub jwks_url: String,
+}
+
+impl JwtConfig {
+    /// Whether tokens are verified with keys fetched from JWKS endpoints
+    pub fn uses_jwks(&self) -> bool {
+        self.jwks_url.is_some() || !self.trusted_issuers.is_empty()
+    }
+
+    /// Every audience a token may be issued for
+    pub fn accepted_audiences(&self) -> Vec<String> {
+        self.audience.iter().chain(&self.audiences).cloned().collect()
+    }
+}
+
+fn default_client_cert_header() -> String { "X-Client-Cert".to_string() }
+
+/// Client certificate (mTLS) configuration
+///
+/// The certificate comes from the TLS handshake in `application` TLS mode
+/// (verified against `tls.ca_file`), or from `forwarded_header` when the
+/// request comes from one of `tls.trusted_proxies`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientCertConfig {
+    /// Header a trusted proxy forwards the verified client certificate in, as URL-encoded PEM
+    #[serde(default = "default_client_cert_header")]
+    pub forwarded_header: String,
+    /// Mappings from certificate identities to permissions; the first match applies
+    pub mappings: Vec<ClientCertMapping>,
+}
+
+/// Permissions granted to clients whose certificate matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientCertMapping {
+    /// Human-readable name for this mapping
+    pub name: String,
+    /// Pattern the certificate's subject common name must match (`*` wildcards)
+    #[serde(default)]
+    pub subject: Option<String>,
+    /// Pattern one of the certificate's subject alternative names (DNS, URI, email or IP) must match
+    #[serde(default)]
+    pub san: Option<String>,
+    /// Permissions for matching clients
+    pub permissions: Vec<String>,
+    /// Toolset (from `registry.toolsets`) matching clients are restricted to
+    #[serde(default)]
+    pub toolset: Option<String>,
+}
+
+fn default_hmac_max_age_seconds() -> u64 { 300 }
+
+/// HMAC request signing authentication
+///
+/// Callers sign each request with a shared secret: `X-MagicTunnel-Signature`
+/// is `sha256=` followed by the hex HMAC-SHA256 of
+/// `<timestamp>.<METHOD>.<path and query>.<body>`, with the Unix timestamp in
+/// `X-MagicTunnel-Timestamp` and the client name in `X-MagicTunnel-Client`.
+/// Signatures older than `max_age_seconds`, or seen before, are rejected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HmacConfig {
+    /// Callers allowed to sign requests
+    pub clients: Vec<HmacClientConfig>,
+    /// Clock skew and replay window a signature is accepted within (default: 300)
+    #[serde(default = "default_hmac_max_age_seconds")]
+    pub max_age_seconds: u64,
+}
+
+/// A caller signing requests with a shared secret
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HmacClientConfig {
+    /// Client name sent in `X-MagicTunnel-Client`
+    pub name: String,
+    /// Shared secret (at least 32 characters)
+    pub secret: String,
+    /// Permissions of the client
+    pub permissions: Vec<String>,
+    /// Toolset (from `registry.toolsets`) the client is restricted to
+    #[serde(default)]
+    pub toolset: Option<String>,
+}
+
+impl std::fmt::Debug for HmacClientConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HmacClientConfig")
+            .field("name", &self.name)
+            .field("secret", &crate::config::secrets::redact_secret(&self.secret))
+            .field("permissions", &self.permissions)
+            .field("toolset", &self.toolset)
+            .finish()
+    }
+}
+
+impl HmacConfig {
+    /// Validate request signing configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.clients.is_empty() {
+            return Err(ProxyError::config("HMAC authentication requires at least one client"));
+        }
+        if self.max_age_seconds == 0 {
+            return Err(ProxyError::config("HMAC max_age_seconds must be greater than zero"));
+        }
+
+        let mut names = std::collections::HashSet::new();
+        for client in &self.clients {
+            if client.name.is_empty() {
+                return Err(ProxyError::config("HMAC client name cannot be empty"));
+            }
+            if !names.insert(client.name.as_str()) {
+                return Err(ProxyError::config(format!("Duplicate HMAC client '{}'", client.name)));
+            }
+            if client.secret.len() < 32 {
+                return Err(ProxyError::config(format!(
+                    "Secret of HMAC client '{}' is too short. Minimum length is 32 characters",
+                    client.name
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 /// OAuth configuration
@@ -258,6 +975,9 @@ pub struct OAuthConfig {
     pub auth_url: String,
     /// Token URL
     pub token_url: String,
+    /// Device authorization URL (RFC 8628), enabling interactive sign-in in stdio mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_auth_url: Option<String>,
 }
 
 /// Logging configuration
@@ -407,6 +1127,70 @@ pub struct ExternalMcpConfig {
     pub refresh_interval_minutes: u64,
     /// Container runtime configuration
     pub containers: Option<ContainerConfig>,
+    /// Circuit breaker applied to each External MCP server
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Maximum number of servers started (and discovered) concurrently
+    #[serde(default = "default_startup_concurrency")]
+    pub startup_concurrency: usize,
+    /// Prompt-injection scanning and quarantine of discovered capabilities
+    #[serde(default)]
+    pub injection_scan: crate::mcp::injection_scan::InjectionScanConfig,
+}
+
+fn default_startup_concurrency() -> usize { 4 }
+
+/// Circuit breaker configuration for upstream MCP servers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Whether the circuit breaker is enabled
+    #[serde(default = "default_circuit_breaker_enabled")]
+    pub enabled: bool,
+    /// Consecutive failures that open the circuit
+    #[serde(default = "default_circuit_failure_threshold")]
+    pub failure_threshold: u32,
+    /// Failure ratio (0.0-1.0) over the rolling window that opens the circuit
+    #[serde(default = "default_circuit_error_rate_threshold")]
+    pub error_rate_threshold: f64,
+    /// Number of recent calls considered for the error rate
+    #[serde(default = "default_circuit_window_size")]
+    pub window_size: u32,
+    /// Minimum calls in the window before the error rate is evaluated
+    #[serde(default = "default_circuit_min_requests")]
+    pub min_requests: u32,
+    /// Calls slower than this (in milliseconds) count as failures
+    #[serde(default = "default_circuit_slow_call_threshold_ms")]
+    pub slow_call_threshold_ms: u64,
+    /// How long the circuit stays open before probing (in seconds)
+    #[serde(default = "default_circuit_open_duration_secs")]
+    pub open_duration_secs: u64,
+    /// Number of probe calls allowed while half-open
+    #[serde(default = "default_circuit_half_open_max_calls")]
+    pub half_open_max_calls: u32,
+}
+
+fn default_circuit_breaker_enabled() -> bool { true }
+fn default_circuit_failure_threshold() -> u32 { 5 }
+fn default_circuit_error_rate_threshold() -> f64 { 0.5 }
+fn default_circuit_window_size() -> u32 { 20 }
+fn default_circuit_min_requests() -> u32 { 10 }
+fn default_circuit_slow_call_threshold_ms() -> u64 { 30000 }
+fn default_circuit_open_duration_secs() -> u64 { 30 }
+fn default_circuit_half_open_max_calls() -> u32 { 1 }
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_circuit_breaker_enabled(),
+            failure_threshold: default_circuit_failure_threshold(),
+            error_rate_threshold: default_circuit_error_rate_threshold(),
+            window_size: default_circuit_window_size(),
+            min_requests: default_circuit_min_requests(),
+            slow_call_threshold_ms: default_circuit_slow_call_threshold_ms(),
+            open_duration_secs: default_circuit_open_duration_secs(),
+            half_open_max_calls: default_circuit_half_open_max_calls(),
+        }
+    }
 }
 
 
@@ -437,8 +1221,52 @@ pub struct McpServerConfig {
     pub env: Option<std::collections::HashMap<String, String>>,
     /// Working directory for the process
     pub cwd: Option<String>,
+    /// Launch the server on a remote host over SSH instead of locally.
+    /// `command`, `args`, `env` and `cwd` then apply on the remote side.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<SshTransportConfig>,
 }
 
+/// SSH transport for External MCP servers running on a remote host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshTransportConfig {
+    /// Remote host name or address
+    pub host: String,
+    /// SSH port
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+    /// Remote user (defaults to the local SSH configuration)
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Private key used for authentication
+    #[serde(default)]
+    pub identity_file: Option<String>,
+    /// Allow authentication through a running ssh-agent
+    #[serde(default = "default_ssh_use_agent")]
+    pub use_agent: bool,
+    /// Pinned host public key in OpenSSH format (e.g. "ssh-ed25519 AAAA...").
+    /// When set, only this key is accepted for the host.
+    #[serde(default)]
+    pub host_key: Option<String>,
+    /// known_hosts file to verify the host against (ignored when `host_key` is set)
+    #[serde(default)]
+    pub known_hosts_file: Option<String>,
+    /// Connection timeout in seconds
+    #[serde(default = "default_ssh_connect_timeout")]
+    pub connect_timeout: u64,
+    /// Keep-alive interval in seconds (0 disables)
+    #[serde(default = "default_ssh_keepalive_interval")]
+    pub keepalive_interval: u64,
+    /// Additional `-o Option=value` settings passed to ssh
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+fn default_ssh_port() -> u16 { 22 }
+fn default_ssh_use_agent() -> bool { true }
+fn default_ssh_connect_timeout() -> u64 { 10 }
+fn default_ssh_keepalive_interval() -> u64 { 15 }
+
 /// External MCP Servers Configuration (Claude Desktop format)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExternalMcpServersConfig {
@@ -542,7 +1370,7 @@ pub struct WebSocketServiceConfig {
 }
 
 /// HTTP Authentication Type
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum HttpAuthType {
     #[serde(rename = "none")]
@@ -553,10 +1381,13 @@ pub enum HttpAuthType {
     ApiKey { header: String, key: String },
     #[serde(rename = "basic")]
     Basic { username: String, password: String },
+    /// The caller's OAuth/JWT token, exchanged for one issued to this service
+    #[serde(rename = "token_exchange")]
+    TokenExchange(crate::auth::TokenExchangeConfig),
 }
 
 /// SSE Authentication Type
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum SseAuthType {
     #[serde(rename = "none")]
@@ -570,7 +1401,7 @@ pub enum SseAuthType {
 }
 
 /// WebSocket Authentication Type (future)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum WebSocketAuthType {
     #[serde(rename = "none")]
@@ -581,6 +1412,34 @@ pub enum WebSocketAuthType {
     ApiKey { header: String, key: String },
 }
 
+// Credentials are redacted in debug output (config dumps end up in logs)
+impl std::fmt::Debug for HttpAuthType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&crate::mcp::clients::HttpAuthConfig::from(self), f)
+    }
+}
+
+impl std::fmt::Debug for SseAuthType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&crate::mcp::clients::SseAuthConfig::from(self), f)
+    }
+}
+
+impl std::fmt::Debug for WebSocketAuthType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use super::secrets::redact_secret;
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Bearer { token } => f.debug_struct("Bearer").field("token", &redact_secret(token)).finish(),
+            Self::ApiKey { header, key } => f
+                .debug_struct("ApiKey")
+                .field("header", header)
+                .field("key", &redact_secret(key))
+                .finish(),
+        }
+    }
+}
+
 // Default value functions
 fn default_timeout() -> u64 { 30 }
 fn default_retry_attempts() -> u32 { 3 }
@@ -604,6 +1463,9 @@ impl Default for ExternalMcpConfig {
             capabilities_output_dir: "./capabilities/external-mcp".to_string(),
             refresh_interval_minutes: 60,
             containers: Some(ContainerConfig::default()),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            startup_concurrency: default_startup_concurrency(),
+            injection_scan: Default::default(),
         }
     }
 }
@@ -647,6 +1509,7 @@ impl From<&HttpAuthType> for crate::mcp::clients::HttpAuthConfig {
                 username: username.clone(), 
                 password: password.clone() 
             },
+            HttpAuthType::TokenExchange(exchange) => Self::TokenExchange(exchange.clone()),
         }
     }
 }
@@ -720,6 +1583,22 @@ impl Default for Config {
             conflict_resolution: None,
             visibility: None,
             smart_discovery: None,
+            rate_limiting: None,
+            timeouts: None,
+            dry_run: None,
+            jobs: None,
+            costs: None,
+            sessions: None,
+            policy: None,
+            secret_scanning: None,
+            pii: None,
+            approvals: None,
+            access_windows: None,
+            quotas: None,
+            audit: None,
+            threat_detection: None,
+            network_policy: None,
+            circuit_breakers: None,
         }
     }
 }
@@ -1025,8 +1904,12 @@ impl RegistryConfig {
 
         // Validate supported registry types
         match self.r#type.as_str() {
-            "file" => {
-                // File-based registry validation
+            "file" | "sqlite" => {
+                // File-based registry validation (the SQLite backend imports the same files)
+                if self.r#type == "sqlite" && self.sqlite.path.is_empty() {
+                    return Err(ProxyError::config("registry.sqlite.path cannot be empty for the sqlite registry"));
+                }
+
                 if self.paths.is_empty() {
                     return Err(ProxyError::config("Registry paths cannot be empty for file-based registry"));
                 }
@@ -1040,6 +1923,11 @@ impl RegistryConfig {
                         )));
                     }
 
+                    if let Some(source) = crate::registry::remote_source::RemoteSource::parse(path) {
+                        source.validate()?;
+                        continue;
+                    }
+
                     // Check for potentially dangerous paths
                     if path.contains("..") {
                         return Err(ProxyError::config(format!(
@@ -1059,12 +1947,19 @@ impl RegistryConfig {
             }
             _ => {
                 return Err(ProxyError::config(format!(
-                    "Unsupported registry type: '{}'. Supported types: file",
+                    "Unsupported registry type: '{}'. Supported types: file, sqlite",
                     self.r#type
                 )));
             }
         }
 
+        // Validate toolset definitions
+        for (name, definition) in &self.toolsets {
+            crate::registry::toolset::Toolset::from_definition(name, definition).map_err(|e| {
+                ProxyError::config(format!("Invalid toolset '{}': {}", name, e))
+            })?;
+        }
+
         // Validate validation config
         self.validation.validate()?;
 
@@ -1192,6 +2087,12 @@ impl Default for RegistryConfig {
             paths: vec!["./data".to_string()],
             hot_reload: true,
             validation: ValidationConfig::default(),
+            remote: RemoteRegistryConfig::default(),
+            sqlite: SqliteRegistryConfig::default(),
+            versioning: ToolVersioningConfig::default(),
+            toolsets: std::collections::HashMap::new(),
+            env_templating: EnvTemplatingConfig::default(),
+            tool_state: ToolStateConfig::default(),
         }
     }
 }
@@ -1210,6 +2111,7 @@ impl Default for ValidationConfig {
         Self {
             strict: true,
             allow_unknown_fields: false,
+            output: Default::default(),
         }
     }
 }
@@ -1219,9 +2121,19 @@ impl Default for AuthConfig {
         Self {
             enabled: false, // Disabled by default for backward compatibility
             r#type: AuthType::None,
+            methods: Vec::new(),
             api_keys: None,
             oauth: None,
             jwt: None,
+            client_cert: None,
+            hmac: None,
+            resource_server: None,
+            client_registration: None,
+            roles: BTreeMap::new(),
+            role_mappings: Vec::new(),
+            guest: None,
+            tenants: Vec::new(),
+            token_encryption: None,
         }
     }
 }
@@ -1239,6 +2151,7 @@ impl Default for ApiKeyConfig {
             require_header: true,
             header_name: "Authorization".to_string(),
             header_format: "Bearer {key}".to_string(),
+            rotation: ApiKeyRotationConfig::default(),
         }
     }
 }
@@ -1260,6 +2173,8 @@ impl ApiKeyEntry {
             permissions: vec!["read".to_string(), "write".to_string()],
             expires_at: None,
             active: true,
+            toolset: None,
+            scopes: Vec::new(),
         }
     }
 
@@ -1272,6 +2187,8 @@ impl ApiKeyEntry {
             permissions,
             expires_at: None,
             active: true,
+            toolset: None,
+            scopes: Vec::new(),
         }
     }
 
@@ -1300,12 +2217,59 @@ impl ApiKeyEntry {
 impl AuthConfig {
     /// Validate authentication configuration
     pub fn validate(&self) -> Result<()> {
+        // Tenants can require authentication on a server that does not
+        self.validate_tenants()?;
+
+        if let Some(token_encryption) = &self.token_encryption {
+            token_encryption.validate()?;
+        }
+
         // If authentication is disabled, no validation needed
         if !self.enabled {
             return Ok(());
         }
 
-        match &self.r#type {
+        if let Some((role, _)) = self.roles.iter().find(|(role, _)| role.is_empty()) {
+            return Err(ProxyError::config(format!("Role name cannot be empty: '{}'", role)));
+        }
+        for mapping in &self.role_mappings {
+            mapping.validate(&self.roles)?;
+        }
+
+        if let Some(guest) = self.guest.as_ref().filter(|guest| guest.enabled) {
+            guest.validate()?;
+            let mut keys = self.api_keys.iter().flat_map(|api_keys| &api_keys.keys);
+            if keys.any(|key| key.name == crate::routing::rate_limit::GUEST_CLIENT) {
+                return Err(ProxyError::config(format!(
+                    "API key name '{}' is reserved for guest access",
+                    crate::routing::rate_limit::GUEST_CLIENT
+                )));
+            }
+        }
+
+        if let Some(resource_server) = &self.resource_server {
+            resource_server.validate()?;
+        }
+
+        if self.client_registration.as_ref().is_some_and(|registration| registration.enabled && registration.state_path.is_empty()) {
+            return Err(ProxyError::config("Client registration state path cannot be empty"));
+        }
+
+        for (index, method) in self.methods.iter().enumerate() {
+            if *method == AuthType::None {
+                return Err(ProxyError::config("Authentication method 'none' cannot be part of the methods chain"));
+            }
+            if self.methods[..index].contains(method) {
+                return Err(ProxyError::config(format!("Authentication method '{}' is listed more than once", method)));
+            }
+        }
+
+        self.methods().iter().try_for_each(|method| self.validate_method(method))
+    }
+
+    /// Validate the configuration of one authentication method
+    fn validate_method(&self, method: &AuthType) -> Result<()> {
+        match method {
             AuthType::None => {
                 // No authentication - no additional validation needed
                 Ok(())
@@ -1352,6 +2316,13 @@ impl AuthConfig {
                                     )));
                                 }
                             }
+
+                            if let Err(e) = crate::registry::toolset::ScopePatterns::parse(&key_entry.scopes) {
+                                return Err(ProxyError::config(format!(
+                                    "Invalid scopes for API key '{}': {}",
+                                    key_entry.name, e
+                                )));
+                            }
                         }
 
                         // Validate header configuration
@@ -1369,6 +2340,14 @@ impl AuthConfig {
                             ));
                         }
 
+                        if api_key_config.rotation.state_path.is_empty() {
+                            return Err(ProxyError::config("API key rotation state path cannot be empty"));
+                        }
+
+                        if api_key_config.rotation.key_lifetime_days == Some(0) {
+                            return Err(ProxyError::config("API key lifetime must be at least one day"));
+                        }
+
                         Ok(())
                     }
                     None => Err(ProxyError::config(
@@ -1394,6 +2373,24 @@ impl AuthConfig {
                     ))
                 }
             }
+            AuthType::ClientCert => {
+                // Validate client certificate configuration
+                match &self.client_cert {
+                    Some(client_cert_config) => client_cert_config.validate(),
+                    None => Err(ProxyError::config(
+                        "Client certificate authentication enabled but no client certificate configuration provided"
+                    ))
+                }
+            }
+            AuthType::Hmac => {
+                // Validate request signing configuration
+                match &self.hmac {
+                    Some(hmac_config) => hmac_config.validate(),
+                    None => Err(ProxyError::config(
+                        "HMAC authentication enabled but no HMAC configuration provided"
+                    ))
+                }
+            }
         }
     }
 
@@ -1414,6 +2411,77 @@ impl AuthConfig {
             false
         }
     }
+
+    /// Authentication methods tried for each request, in order
+    pub fn methods(&self) -> &[AuthType] {
+        if self.methods.is_empty() {
+            std::slice::from_ref(&self.r#type)
+        } else {
+            &self.methods
+        }
+    }
+
+    /// Whether requests can authenticate with `method`
+    pub fn uses(&self, method: &AuthType) -> bool {
+        self.methods().contains(method)
+    }
+
+    /// Whether this configuration or any tenant's enables authentication
+    pub fn any_enabled(&self) -> bool {
+        self.enabled || self.tenants.iter().any(|tenant| tenant.auth.enabled)
+    }
+
+    fn validate_tenants(&self) -> Result<()> {
+        // Persisted state must not be shared, or tenants would see each other's keys and clients
+        let mut state_paths = std::collections::HashSet::new();
+        let configs = std::iter::once(self).chain(self.tenants.iter().map(|tenant| &tenant.auth));
+        for config in configs.filter(|config| config.enabled) {
+            let key_path = config.api_keys.as_ref()
+                .filter(|_| config.uses(&AuthType::ApiKey))
+                .map(|api_keys| api_keys.rotation.state_path.as_str());
+            let client_path = config.client_registration.as_ref()
+                .filter(|registration| registration.enabled)
+                .map(|registration| registration.state_path.as_str());
+            for path in key_path.into_iter().chain(client_path) {
+                if !state_paths.insert(path) {
+                    return Err(ProxyError::config(format!(
+                        "Auth state path '{}' is used by more than one tenant",
+                        path
+                    )));
+                }
+            }
+        }
+
+        let mut names = std::collections::HashSet::new();
+        for tenant in &self.tenants {
+            if tenant.name.is_empty() {
+                return Err(ProxyError::config("Tenant name cannot be empty"));
+            }
+            if !names.insert(tenant.name.as_str()) {
+                return Err(ProxyError::config(format!("Duplicate tenant '{}'", tenant.name)));
+            }
+            if tenant.hosts.is_empty() && tenant.path_prefix.is_none() {
+                return Err(ProxyError::config(format!(
+                    "Tenant '{}' must set hosts or a path_prefix",
+                    tenant.name
+                )));
+            }
+            if tenant.path_prefix.as_deref().is_some_and(|prefix| !prefix.starts_with('/')) {
+                return Err(ProxyError::config(format!(
+                    "Path prefix of tenant '{}' must start with '/'",
+                    tenant.name
+                )));
+            }
+            if !tenant.auth.tenants.is_empty() {
+                return Err(ProxyError::config(format!("Tenant '{}' cannot define tenants", tenant.name)));
+            }
+            tenant.auth.validate().map_err(|e| match e {
+                ProxyError::Config { message } => ProxyError::config(format!("Tenant '{}': {}", tenant.name, message)),
+                other => other,
+            })?;
+        }
+        Ok(())
+    }
 }
 
 impl OAuthConfig {
@@ -1454,6 +2522,81 @@ impl OAuthConfig {
             )));
         }
 
+        if let Some(device_auth_url) = &self.device_auth_url {
+            if !device_auth_url.starts_with("http://") && !device_auth_url.starts_with("https://") {
+                return Err(ProxyError::config(format!(
+                    "OAuth device authorization URL must start with http:// or https://: '{}'",
+                    device_auth_url
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ResourceServerConfig {
+    /// Validate protected resource configuration
+    pub fn validate(&self) -> Result<()> {
+        if let Some(resource) = &self.resource {
+            let url = url::Url::parse(resource)
+                .map_err(|e| ProxyError::config(format!("Invalid protected resource URI '{}': {}", resource, e)))?;
+            // RFC 8707: an absolute URI without a fragment
+            if url.fragment().is_some() || !matches!(url.scheme(), "https" | "http") {
+                return Err(ProxyError::config(format!(
+                    "Protected resource URI '{}' must be an http(s) URI without a fragment",
+                    resource
+                )));
+            }
+        }
+
+        for server in &self.authorization_servers {
+            url::Url::parse(server)
+                .map_err(|e| ProxyError::config(format!("Invalid authorization server URI '{}': {}", server, e)))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ClientCertConfig {
+    /// Validate client certificate configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.forwarded_header.is_empty() {
+            return Err(ProxyError::config("Client certificate forwarded header cannot be empty"));
+        }
+
+        if self.mappings.is_empty() {
+            return Err(ProxyError::config(
+                "Client certificate authentication requires at least one mapping"
+            ));
+        }
+
+        for (index, mapping) in self.mappings.iter().enumerate() {
+            if mapping.name.is_empty() {
+                return Err(ProxyError::config(format!(
+                    "Client certificate mapping name at index {} cannot be empty",
+                    index
+                )));
+            }
+
+            if mapping.subject.is_none() && mapping.san.is_none() {
+                return Err(ProxyError::config(format!(
+                    "Client certificate mapping '{}' needs a subject or san pattern",
+                    mapping.name
+                )));
+            }
+
+            for pattern in mapping.subject.iter().chain(mapping.san.iter()) {
+                if let Err(e) = globset::Glob::new(pattern) {
+                    return Err(ProxyError::config(format!(
+                        "Invalid pattern '{}' in client certificate mapping '{}': {}",
+                        pattern, mapping.name, e
+                    )));
+                }
+            }
+        }
+
         Ok(())
     }
 }
@@ -1461,6 +2604,10 @@ impl OAuthConfig {
 impl JwtConfig {
     /// Validate JWT configuration
     pub fn validate(&self) -> Result<()> {
+        if self.uses_jwks() {
+            return self.validate_jwks();
+        }
+
         if self.secret.is_empty() {
             return Err(ProxyError::config("JWT secret cannot be empty"));
         }
@@ -1501,6 +2648,43 @@ impl JwtConfig {
 
         Ok(())
     }
+
+    /// Validate the JWKS endpoints and their issuers
+    fn validate_jwks(&self) -> Result<()> {
+        let urls = self.jwks_url.iter().chain(self.trusted_issuers.iter().map(|i| &i.jwks_url));
+        for url in urls {
+            let parsed = url::Url::parse(url)
+                .map_err(|e| ProxyError::config(format!("Invalid JWKS URL '{}': {}", url, e)))?;
+            if !matches!(parsed.scheme(), "https" | "http") {
+                return Err(ProxyError::config(format!("JWKS URL '{}' must use http or https", url)));
+            }
+        }
+
+        if self.jwks_url.is_some() && !self.trusted_issuers.is_empty() && self.issuer.is_none() {
+            return Err(ProxyError::config(
+                "JWT issuer is required for jwks_url when trusted_issuers are also configured"
+            ));
+        }
+
+        let mut issuers = std::collections::HashSet::new();
+        if self.jwks_url.is_some() {
+            issuers.extend(self.issuer.as_deref());
+        }
+        for trusted in &self.trusted_issuers {
+            if trusted.issuer.is_empty() {
+                return Err(ProxyError::config("JWT trusted issuer cannot be empty"));
+            }
+            if !issuers.insert(trusted.issuer.as_str()) {
+                return Err(ProxyError::config(format!("Duplicate JWT trusted issuer '{}'", trusted.issuer)));
+            }
+        }
+
+        if self.jwks_min_refresh_seconds == 0 {
+            return Err(ProxyError::config("JWT jwks_min_refresh_seconds must be greater than 0"));
+        }
+
+        Ok(())
+    }
 }
 
 impl LoggingConfig {
@@ -1971,6 +3155,101 @@ impl Config {
             logging.validate()?;
         }
 
+        // Validate rate limits if present
+        if let Some(ref rate_limiting) = self.rate_limiting {
+            rate_limiting.validate()?;
+        }
+
+        // Validate timeouts if present
+        if let Some(ref timeouts) = self.timeouts {
+            timeouts.validate().map_err(|e| ProxyError::config(format!("Invalid timeouts configuration: {}", e)))?;
+        }
+
+        // Validate background jobs if present
+        if let Some(ref jobs) = self.jobs {
+            jobs.validate()?;
+        }
+
+        // Validate injection scanning of External MCP capabilities if present
+        if let Some(ref external_mcp) = self.external_mcp {
+            external_mcp.injection_scan.validate()?;
+        }
+
+        // Validate spend budgets if present
+        if let Some(ref costs) = self.costs {
+            costs.validate()?;
+        }
+
+        if let Some(ref sessions) = self.sessions {
+            sessions.validate()?;
+        }
+
+        if let Some(ref quotas) = self.quotas {
+            quotas.validate()?;
+        }
+
+        if let Some(ref access_windows) = self.access_windows {
+            access_windows.validate()?;
+        }
+
+        if let Some(ref policy) = self.policy {
+            policy.validate()?;
+        }
+
+        if let Some(ref secret_scanning) = self.secret_scanning {
+            secret_scanning.validate()?;
+        }
+
+        if let Some(ref pii) = self.pii {
+            pii.validate()?;
+        }
+
+        if let Some(ref approvals) = self.approvals {
+            approvals.validate()?;
+        }
+
+        if let Some(ref audit) = self.audit {
+            audit.validate()?;
+        }
+
+        if let Some(ref threat_detection) = self.threat_detection {
+            threat_detection.validate()?;
+        }
+
+        if let Some(ref network_policy) = self.network_policy {
+            network_policy.validate()?;
+        }
+
+        if let Some(ref circuit_breakers) = self.circuit_breakers {
+            circuit_breakers.validate()?;
+        }
+
+        // Validate the remote embedding provider if present
+        if let Some(provider) = self.smart_discovery.as_ref().and_then(|sd| sd.semantic_search.provider.as_ref()) {
+            provider.validate()?;
+        }
+
+        // Validate the shared vector store if present
+        if let Some(vector_store) = self.smart_discovery.as_ref().and_then(|sd| sd.semantic_search.vector_store.as_ref()) {
+            vector_store.validate()?;
+        }
+
+        // Validate the ANN index if present
+        if let Some(ann_index) = self.smart_discovery.as_ref().and_then(|sd| sd.semantic_search.ann_index.as_ref()) {
+            ann_index.validate()?;
+        }
+
+        // Validate the LLM providers, hybrid ranking weights, feedback, usage boosts, semantic cache, clarification and categories
+        if let Some(ref smart_discovery) = self.smart_discovery {
+            smart_discovery.llm_mapper.validate()?;
+            smart_discovery.hybrid_weights.validate()?;
+            smart_discovery.feedback.validate()?;
+            smart_discovery.usage_boost.validate()?;
+            smart_discovery.cache.semantic.validate()?;
+            smart_discovery.clarification.validate()?;
+            smart_discovery.categories.validate()?;
+        }
+
         // Note: Legacy MCP proxy validation removed - use remote_mcp instead
 
         // Cross-validation checks
@@ -2000,8 +3279,9 @@ impl Config {
 
         // Validate that authentication is properly configured if enabled
         if let Some(ref auth) = self.auth {
-            if auth.enabled {
-                match auth.r#type {
+            let methods = if auth.enabled { auth.methods() } else { &[] };
+            for method in methods {
+                match method {
                     AuthType::ApiKey => {
                         if auth.api_keys.as_ref().map_or(true, |keys| keys.is_empty()) {
                             return Err(ProxyError::config(
@@ -2023,6 +3303,20 @@ impl Config {
                             ));
                         }
                     }
+                    AuthType::ClientCert => {
+                        if auth.client_cert.is_none() {
+                            return Err(ProxyError::config(
+                                "Client certificate authentication enabled but no client certificate configuration provided"
+                            ));
+                        }
+                    }
+                    AuthType::Hmac => {
+                        if auth.hmac.is_none() {
+                            return Err(ProxyError::config(
+                                "HMAC authentication enabled but no HMAC configuration provided"
+                            ));
+                        }
+                    }
                     AuthType::None => {
                         // No additional validation needed for "none" type
                     }
@@ -2030,6 +3324,32 @@ impl Config {
             }
         }
 
+        // Validate that API keys only reference defined toolsets
+        if let Some(keys) = self.auth.as_ref().and_then(|auth| auth.api_keys.as_ref()) {
+            for key in &keys.keys {
+                if let Some(ref toolset) = key.toolset {
+                    if !self.registry.toolsets.contains_key(toolset) {
+                        return Err(ProxyError::config(format!(
+                            "API key '{}' references unknown toolset '{}'", key.name, toolset
+                        )));
+                    }
+                }
+            }
+        }
+
+        // Same for client certificate mappings
+        if let Some(client_cert) = self.auth.as_ref().and_then(|auth| auth.client_cert.as_ref()) {
+            for mapping in &client_cert.mappings {
+                if let Some(ref toolset) = mapping.toolset {
+                    if !self.registry.toolsets.contains_key(toolset) {
+                        return Err(ProxyError::config(format!(
+                            "Client certificate mapping '{}' references unknown toolset '{}'", mapping.name, toolset
+                        )));
+                    }
+                }
+            }
+        }
+
         Ok(())
     }
 }
@@ -3,19 +3,22 @@
 //! This module provides configuration management and loading utilities.
 
 mod config;
+pub mod secrets;
 
 // Re-export the main configuration types
 pub use config::{
-    Config, ServerConfig, RegistryConfig, AuthConfig, LoggingConfig, ValidationConfig, OAuthConfig,
+    user_data_dir, write_atomic,
+    Config, ServerConfig, RegistryConfig, RemoteRegistryConfig, SqliteRegistryConfig, ToolsetDefinition, EnvTemplatingConfig, ToolStateConfig, ToolVersioningConfig, ToolVersionPolicy, AuthConfig, LoggingConfig, ValidationConfig, OAuthConfig,
     ConflictResolutionStrategy, AggregationConfig, VisibilityConfig,
     // Authentication types
-    AuthType, ApiKeyConfig, ApiKeyEntry, JwtConfig,
+    AuthType, ApiKeyConfig, ApiKeyEntry, ApiKeyRotationConfig, JwtConfig, JwtIssuerConfig, ClientCertConfig, ClientCertMapping, ClientRegistrationConfig, HmacConfig, HmacClientConfig, ResourceServerConfig, TenantAuthConfig, GuestAccessConfig, RoleMappingConfig, TokenEncryptionConfig,
     // TLS types
     TlsConfig, TlsMode,
     // MCP Client types
     McpClientConfig,
     // External MCP types (unified local/remote)
-    ExternalMcpConfig, ContainerConfig, McpServerConfig, ExternalMcpServersConfig,
+    ExternalMcpConfig, ContainerConfig, McpServerConfig, ExternalMcpServersConfig, CircuitBreakerConfig,
+    SshTransportConfig,
     // Network MCP service types
     HttpServiceConfig, SseServiceConfig, WebSocketServiceConfig,
     HttpAuthType, SseAuthType, WebSocketAuthType
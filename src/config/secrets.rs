@@ -0,0 +1,325 @@
+//! Secret references in configuration values
+//!
+//! Credentials for upstream services don't have to be stored in the config
+//! files. A value can reference a secret instead:
+//!
+//! - `${env:VAR}` - environment variable
+//! - `${file:/path/to/secret}` - file contents (trailing newline trimmed)
+//! - `${vault:secret/data/app#field}` - HashiCorp Vault KV (v1 or v2) secret
+//!   field (`value` when no field is given), using `VAULT_ADDR` and `VAULT_TOKEN`
+//!
+//! References are resolved when a connection is made, never at load time, so
+//! the resolved values don't end up in the in-memory config, logs or the
+//! dashboard. [`redact_secret`] and [`redact_config_text`] mask literal
+//! credentials for display, and [`restore_redacted_config_text`] puts them
+//! back into edited config text before it's saved.
+
+use crate::error::{ProxyError, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Placeholder shown instead of a secret value
+pub const REDACTED: &str = "***";
+
+const VAULT_TIMEOUT_SECS: u64 = 10;
+
+/// Config keys whose values are treated as credentials when redacting config text
+const SECRET_KEYS: &[&str] = &[
+    "token", "key", "api_key", "apikey", "password", "passwd", "secret", "client_secret",
+    "access_token", "refresh_token", "bearer_token", "auth_token", "session_token",
+    "private_key", "secret_key", "access_key", "secret_access_key", "signing_key",
+];
+
+/// Suffixes of credential env vars such as `GITHUB_TOKEN` or `DB_PASSWORD`
+const SECRET_KEY_SUFFIXES: &[&str] = &[
+    "_token", "_secret", "_password", "_api_key", "_private_key", "_secret_key", "_access_key", "_signing_key",
+];
+
+/// Whether the value contains a `${env:..}`, `${file:..}` or `${vault:..}` reference
+pub fn is_secret_reference(value: &str) -> bool {
+    ["${env:", "${file:", "${vault:"].iter().any(|prefix| value.contains(prefix))
+}
+
+/// Resolve all secret references in `value`. Values without references are returned unchanged.
+pub async fn resolve_secret(value: &str) -> Result<String> {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = find_reference(rest) {
+        result.push_str(&rest[..start]);
+        let reference = &rest[start + 2..];
+        let end = reference
+            .find('}')
+            .ok_or_else(|| ProxyError::config(format!("Unterminated secret reference in '{}'", redact_secret(value))))?;
+        let (kind, target) = reference[..end]
+            .split_once(':')
+            .ok_or_else(|| ProxyError::config("Malformed secret reference".to_string()))?;
+
+        let resolved = match kind {
+            "env" => std::env::var(target)
+                .map_err(|_| ProxyError::config(format!("Secret reference: environment variable '{}' is not set", target)))?,
+            "file" => tokio::fs::read_to_string(target)
+                .await
+                .map_err(|e| ProxyError::config(format!("Secret reference: failed to read '{}': {}", target, e)))?
+                .trim_end_matches(['\r', '\n'])
+                .to_string(),
+            // find_reference only matches env/file/vault
+            _ => resolve_vault(target).await?,
+        };
+
+        result.push_str(&resolved);
+        rest = &reference[end + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Mask a credential for display. References are safe to show and are kept as-is.
+pub fn redact_secret(value: &str) -> String {
+    if value.is_empty() || is_secret_reference(value) {
+        value.to_string()
+    } else {
+        REDACTED.to_string()
+    }
+}
+
+/// Mask literal credentials in YAML config text (e.g. for the dashboard's config views).
+/// Secret references and comments are left untouched.
+pub fn redact_config_text(content: &str) -> String {
+    let mut redacted: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('#') {
+                return line.to_string();
+            }
+            let Some((key, value)) = trimmed.split_once(':') else {
+                return line.to_string();
+            };
+            let key_name = key.trim_start_matches("- ").trim().trim_matches('"');
+            let value = value.trim();
+            let value_only = value.split(" #").next().unwrap_or(value).trim().trim_matches('"');
+            if !is_secret_key(key_name) || !is_literal_scalar(value_only) || is_secret_reference(value_only) {
+                return line.to_string();
+            }
+            let indent = &line[..line.len() - trimmed.len()];
+            format!("{}{}: \"{}\"", indent, key, REDACTED)
+        })
+        .collect();
+    if content.ends_with('\n') {
+        redacted.push(String::new());
+    }
+    redacted.join("\n")
+}
+
+/// Put the stored values back for secrets that are still [`REDACTED`] in
+/// config text edited from [`redact_config_text`] output.
+///
+/// Secrets are matched to `original` by their key path (and position among
+/// equal paths, for lists). A redacted secret without a stored value is an error,
+/// since saving would otherwise replace the credential with the placeholder.
+pub fn restore_redacted_config_text(content: &str, original: &str) -> Result<String> {
+    let mut stored: HashMap<String, Vec<&str>> = HashMap::new();
+    for (path, line) in key_paths(original).into_iter().zip(original.split('\n')) {
+        if let (Some(path), Some((_, value))) = (path, line.split_once(':')) {
+            stored.entry(path).or_default().push(value);
+        }
+    }
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let paths = key_paths(content);
+    let mut restored = Vec::new();
+    for (path, line) in paths.iter().zip(content.split('\n')) {
+        let (Some(path), Some((key, value))) = (path, line.split_once(':')) else {
+            restored.push(line.to_string());
+            continue;
+        };
+        let index = seen.entry(path.as_str()).or_default();
+        *index += 1;
+        let key_name = key.trim_start().trim_start_matches("- ").trim().trim_matches('"');
+        let value_only = value.split(" #").next().unwrap_or(value).trim().trim_matches('"');
+        if value_only != REDACTED || !is_secret_key(key_name) {
+            restored.push(line.to_string());
+            continue;
+        }
+        let value = stored
+            .get(path)
+            .and_then(|values| values.get(*index - 1))
+            .filter(|value| value.trim().trim_matches('"') != REDACTED)
+            .ok_or_else(|| ProxyError::validation(format!(
+                "'{}' is still redacted and has no stored value; enter the secret or a ${{env:..}} reference", path
+            )))?;
+        restored.push(format!("{}:{}", key, value));
+    }
+    Ok(restored.join("\n"))
+}
+
+/// Key path of each line of YAML text, e.g. `auth.token`; `None` for lines without a key
+fn key_paths(content: &str) -> Vec<Option<String>> {
+    let mut parents: Vec<(usize, String)> = Vec::new();
+    content
+        .split('\n')
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('#') {
+                return None;
+            }
+            let (mut key, _) = trimmed.split_once(':')?;
+            let mut indent = line.len() - trimmed.len();
+            while let Some(item) = key.strip_prefix("- ") {
+                indent += 2;
+                key = item.trim_start();
+            }
+            while parents.last().is_some_and(|(parent, _)| *parent >= indent) {
+                parents.pop();
+            }
+            let key = key.trim().trim_matches('"').to_string();
+            let path = parents
+                .iter()
+                .map(|(_, parent)| parent.as_str())
+                .chain([key.as_str()])
+                .collect::<Vec<_>>()
+                .join(".");
+            parents.push((indent, key));
+            Some(path)
+        })
+        .collect()
+}
+
+/// Values worth redacting: scalars other than empty or null (not `{..}`/`[..]` collections)
+fn is_literal_scalar(value: &str) -> bool {
+    !(value.is_empty() || value == "null" || value == "~" || value.starts_with('{') || value.starts_with('['))
+}
+
+/// Credential keys: auth fields from an allow-list and env vars such as `GITHUB_TOKEN` or `DB_PASSWORD`
+pub(crate) fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEYS.contains(&key.as_str()) || SECRET_KEY_SUFFIXES.iter().any(|suffix| key.ends_with(suffix))
+}
+
+fn find_reference(value: &str) -> Option<usize> {
+    ["${env:", "${file:", "${vault:"]
+        .iter()
+        .filter_map(|prefix| value.find(prefix))
+        .min()
+}
+
+async fn resolve_vault(target: &str) -> Result<String> {
+    let (path, field) = target.split_once('#').unwrap_or((target, "value"));
+    let addr = std::env::var("VAULT_ADDR")
+        .map_err(|_| ProxyError::config("Secret reference: VAULT_ADDR is not set".to_string()))?;
+    let token = std::env::var("VAULT_TOKEN")
+        .map_err(|_| ProxyError::config("Secret reference: VAULT_TOKEN is not set".to_string()))?;
+
+    let url = format!("{}/v1/{}", addr.trim_end_matches('/'), path.trim_start_matches('/'));
+    let response = reqwest::Client::new()
+        .get(&url)
+        .header("X-Vault-Token", token)
+        .timeout(Duration::from_secs(VAULT_TIMEOUT_SECS))
+        .send()
+        .await
+        .map_err(|e| ProxyError::connection(format!("Vault request for '{}' failed: {}", path, e)))?;
+
+    if !response.status().is_success() {
+        return Err(ProxyError::connection(format!(
+            "Vault returned {} for secret '{}'",
+            response.status(),
+            path
+        )));
+    }
+
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| ProxyError::connection(format!("Invalid Vault response for '{}': {}", path, e)))?;
+    vault_field(&body, field)
+        .ok_or_else(|| ProxyError::config(format!("Vault secret '{}' has no field '{}'", path, field)))
+}
+
+/// Extract a field from a Vault response (KV v2 nests the data one level deeper)
+fn vault_field(body: &Value, field: &str) -> Option<String> {
+    let data = body.get("data")?;
+    let value = data
+        .get("data")
+        .and_then(|inner| inner.get(field))
+        .or_else(|| data.get(field))?;
+    match value {
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_resolve_env_and_file_references() {
+        std::env::set_var("MAGICTUNNEL_TEST_SECRET", "s3cret");
+        assert_eq!(resolve_secret("${env:MAGICTUNNEL_TEST_SECRET}").await.unwrap(), "s3cret");
+        assert_eq!(resolve_secret("Token ${env:MAGICTUNNEL_TEST_SECRET}!").await.unwrap(), "Token s3cret!");
+        assert_eq!(resolve_secret("plain-value").await.unwrap(), "plain-value");
+
+        let path = std::env::temp_dir().join(format!("magictunnel-secret-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "from-file\n").unwrap();
+        let resolved = resolve_secret(&format!("${{file:{}}}", path.display())).await.unwrap();
+        assert_eq!(resolved, "from-file");
+        std::fs::remove_file(&path).ok();
+
+        assert!(resolve_secret("${env:MAGICTUNNEL_TEST_MISSING_SECRET}").await.is_err());
+        assert!(resolve_secret("${env:UNTERMINATED").await.is_err());
+        // Plain ${VAR} placeholders are not secret references and are left alone
+        assert_eq!(resolve_secret("${HOME}").await.unwrap(), "${HOME}");
+    }
+
+    #[test]
+    fn test_vault_field_supports_kv_v1_and_v2() {
+        let v2 = json!({"data": {"data": {"token": "abc"}, "metadata": {}}});
+        let v1 = json!({"data": {"value": "xyz"}});
+        assert_eq!(vault_field(&v2, "token").as_deref(), Some("abc"));
+        assert_eq!(vault_field(&v1, "value").as_deref(), Some("xyz"));
+        assert_eq!(vault_field(&v1, "missing"), None);
+    }
+
+    #[test]
+    fn test_redaction_keeps_references() {
+        assert_eq!(redact_secret("literal-token"), REDACTED);
+        assert_eq!(redact_secret("${vault:secret/data/app#token}"), "${vault:secret/data/app#token}");
+
+        let yaml = "auth:\n  type: bearer\n  token: \"abc123\"  # prod\n  key: ${env:API_KEY}\n# token: example\nenv:\n  GITHUB_TOKEN: ghp_x\n  PATH: /usr/bin\n";
+        assert_eq!(
+            redact_config_text(yaml),
+            "auth:\n  type: bearer\n  token: \"***\"\n  key: ${env:API_KEY}\n# token: example\nenv:\n  GITHUB_TOKEN: \"***\"\n  PATH: /usr/bin\n"
+        );
+    }
+
+    #[test]
+    fn test_redaction_only_covers_credential_keys() {
+        let yaml = "cache_key: tool-name\nvalue: 42\nmonkey: banana\napi_key: null\nDB_PASSWORD: hunter2\n";
+        assert_eq!(
+            redact_config_text(yaml),
+            "cache_key: tool-name\nvalue: 42\nmonkey: banana\napi_key: null\nDB_PASSWORD: \"***\"\n"
+        );
+    }
+
+    #[test]
+    fn test_redacted_config_round_trip_keeps_secrets() {
+        let stored = "auth:\n  type: bearer\n  token: \"abc123\"  # prod\napi_keys:\n  keys:\n    - key: first-key-0123456789\n      name: a\n    - key: second-key-0123456789\n      name: b\n";
+        let edited = redact_config_text(stored).replace("type: bearer", "type: oauth").replace("name: b", "name: c");
+        assert_eq!(
+            restore_redacted_config_text(&edited, stored).unwrap(),
+            stored.replace("type: bearer", "type: oauth").replace("name: b", "name: c")
+        );
+
+        // A changed secret is saved as entered
+        let changed = redact_config_text(stored).replacen("\"***\"", "new-token", 1);
+        assert!(restore_redacted_config_text(&changed, stored).unwrap().contains("token: new-token"));
+
+        // A new redacted secret has nothing to restore
+        let added = format!("{}\nextra:\n  password: \"***\"\n", redact_config_text(stored));
+        assert!(restore_redacted_config_text(&added, stored).is_err());
+    }
+}
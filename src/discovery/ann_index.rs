@@ -0,0 +1,610 @@
+//! On-disk approximate nearest neighbour index
+//!
+//! Comparing a query with every stored embedding gets slow and memory-hungry
+//! for registries with tens of thousands of tools. With `ann_index`
+//! configured, semantic search walks an HNSW graph instead:
+//!
+//! ```yaml
+//! smart_discovery:
+//!   semantic_search:
+//!     ann_index:
+//!       path: ./data/embeddings/ann
+//!       min_tools: 10000
+//! ```
+//!
+//! The vectors live in a memory-mapped file, so the OS pages them in as
+//! searches touch them, and the graph is persisted next to it. Tools are
+//! inserted and deleted as the registry changes; deleted tools stay in the
+//! graph as tombstones until they make up a quarter of it, when the index is
+//! rebuilt on the next save.
+
+use crate::error::{ProxyError, Result};
+use memmap2::MmapMut;
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+/// `smart_discovery.semantic_search.ann_index`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnnIndexConfig {
+    /// Directory holding the index files
+    pub path: PathBuf,
+
+    /// Search exhaustively below this many tools, where it's fast enough and exact
+    pub min_tools: usize,
+
+    /// Neighbours per node on the upper layers (twice as many on the bottom layer)
+    pub m: usize,
+
+    /// Candidates considered when inserting; higher builds a better graph, slower
+    pub ef_construction: usize,
+
+    /// Candidates considered when searching; higher is more accurate, slower
+    pub ef_search: usize,
+}
+
+impl Default for AnnIndexConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("./data/embeddings/ann"),
+            min_tools: 10_000,
+            m: 16,
+            ef_construction: 100,
+            ef_search: 64,
+        }
+    }
+}
+
+impl AnnIndexConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.m < 2 {
+            return Err(ProxyError::config("ann_index.m must be at least 2"));
+        }
+        if self.ef_construction < self.m {
+            return Err(ProxyError::config("ann_index.ef_construction must be at least m"));
+        }
+        if self.ef_search == 0 {
+            return Err(ProxyError::config("ann_index.ef_search must be greater than 0"));
+        }
+        Ok(())
+    }
+}
+
+const MAGIC: &[u8; 8] = b"MTANN\0\0\x01";
+const VECTORS_FILE: &str = "vectors.f32";
+const GRAPH_FILE: &str = "graph.bin";
+/// Rows the vectors file grows by at least
+const MIN_CAPACITY: usize = 1024;
+const MAX_LEVEL: usize = 16;
+
+/// A node ordered by its distance to the query
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Scored {
+    distance: f32,
+    id: u32,
+}
+
+impl Eq for Scored {}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance.total_cmp(&other.distance).then(self.id.cmp(&other.id))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug)]
+struct Node {
+    name: String,
+    /// Neighbours on each layer the node is on, bottom layer first
+    neighbors: Vec<Vec<u32>>,
+    deleted: bool,
+}
+
+/// HNSW index over unit-length embeddings, keyed by tool name
+pub struct AnnIndex {
+    config: AnnIndexConfig,
+    dimensions: usize,
+    file: File,
+    vectors: MmapMut,
+    nodes: Vec<Node>,
+    ids: HashMap<String, u32>,
+    entry: Option<u32>,
+    deleted: usize,
+}
+
+impl std::fmt::Debug for AnnIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnnIndex")
+            .field("path", &self.config.path)
+            .field("dimensions", &self.dimensions)
+            .field("len", &self.len())
+            .field("deleted", &self.deleted)
+            .finish()
+    }
+}
+
+impl AnnIndex {
+    /// Open the index in `config.path` for `dimensions`-sized embeddings.
+    /// A missing, unreadable or differently-sized index is replaced by an empty one.
+    pub fn open(config: AnnIndexConfig, dimensions: usize) -> Result<Self> {
+        if dimensions == 0 {
+            return Err(ProxyError::config("ANN index dimensions must be greater than 0"));
+        }
+        std::fs::create_dir_all(&config.path)
+            .map_err(|e| ProxyError::config(format!("Failed to create ANN index directory '{}': {}", config.path.display(), e)))?;
+
+        let graph = match std::fs::read(config.path.join(GRAPH_FILE)) {
+            Ok(bytes) => match decode_graph(&bytes) {
+                Some((stored_dimensions, nodes, entry)) if stored_dimensions == dimensions => Some((nodes, entry)),
+                Some((stored_dimensions, ..)) => {
+                    info!("ANN index holds {}-dimensional vectors, rebuilding for {}", stored_dimensions, dimensions);
+                    None
+                }
+                None => {
+                    warn!("ANN index graph in '{}' is corrupt, rebuilding", config.path.display());
+                    None
+                }
+            },
+            Err(_) => None,
+        };
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(config.path.join(VECTORS_FILE))
+            .map_err(|e| ProxyError::config(format!("Failed to open ANN index vectors: {}", e)))?;
+        let row_bytes = (dimensions * 4) as u64;
+        let rows = file.metadata().map(|m| m.len() / row_bytes).unwrap_or(0) as usize;
+
+        // The graph must not refer to vectors the file doesn't have
+        let (nodes, entry) = match graph {
+            Some((nodes, entry)) if nodes.len() <= rows => (nodes, entry),
+            _ => (Vec::new(), None),
+        };
+        if rows < MIN_CAPACITY {
+            file.set_len(MIN_CAPACITY as u64 * row_bytes)
+                .map_err(|e| ProxyError::config(format!("Failed to size ANN index vectors: {}", e)))?;
+        }
+        let vectors = map(&file)?;
+
+        let ids = nodes.iter().enumerate()
+            .filter(|(_, node)| !node.deleted)
+            .map(|(id, node)| (node.name.clone(), id as u32))
+            .collect();
+        let deleted = nodes.iter().filter(|node| node.deleted).count();
+        Ok(Self { config, dimensions, file, vectors, nodes, ids, entry, deleted })
+    }
+
+    /// Dimensions of the indexed embeddings
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Number of indexed tools
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Whether `tool_name` is indexed
+    pub fn contains(&self, tool_name: &str) -> bool {
+        self.ids.contains_key(tool_name)
+    }
+
+    /// Index `embedding` for `tool_name`, replacing any previous embedding
+    pub fn insert(&mut self, tool_name: &str, embedding: &[f32]) -> Result<()> {
+        if embedding.len() != self.dimensions {
+            return Err(ProxyError::config(format!(
+                "Cannot add a {}-dimensional embedding to a {}-dimensional ANN index", embedding.len(), self.dimensions
+            )));
+        }
+        self.remove(tool_name);
+
+        let id = self.nodes.len() as u32;
+        self.ensure_capacity(self.nodes.len() + 1)?;
+        let vector = normalize(embedding);
+        let start = id as usize * self.dimensions;
+        self.floats_mut()[start..start + self.dimensions].copy_from_slice(&vector);
+
+        let level = self.random_level(tool_name, id);
+        self.nodes.push(Node { name: tool_name.to_string(), neighbors: vec![Vec::new(); level + 1], deleted: false });
+        self.ids.insert(tool_name.to_string(), id);
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(id);
+            return Ok(());
+        };
+        let top = self.nodes[entry as usize].neighbors.len() - 1;
+
+        let mut entry_points = vec![entry];
+        for layer in (level + 1..=top).rev() {
+            entry_points = vec![self.search_layer(&vector, &entry_points, 1, layer)[0].id];
+        }
+        for layer in (0..=level.min(top)).rev() {
+            let candidates = self.search_layer(&vector, &entry_points, self.config.ef_construction, layer);
+            let neighbors: Vec<u32> = candidates.iter()
+                .filter(|candidate| candidate.id != id)
+                .take(self.config.m)
+                .map(|candidate| candidate.id)
+                .collect();
+            for &neighbor in &neighbors {
+                self.connect(neighbor, id, layer);
+            }
+            self.nodes[id as usize].neighbors[layer] = neighbors;
+            entry_points = candidates.into_iter().map(|candidate| candidate.id).collect();
+        }
+        if level > top {
+            self.entry = Some(id);
+        }
+        Ok(())
+    }
+
+    /// Remove `tool_name` from the index; returns whether it was indexed
+    pub fn remove(&mut self, tool_name: &str) -> bool {
+        match self.ids.remove(tool_name) {
+            Some(id) => {
+                // Tombstoned nodes keep routing searches until the index is compacted
+                self.nodes[id as usize].deleted = true;
+                self.deleted += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Up to `limit` tools most similar to `query`, best first, with their cosine similarity
+    pub fn search(&self, query: &[f32], limit: usize) -> Result<Vec<(String, f64)>> {
+        if query.len() != self.dimensions {
+            return Err(ProxyError::config(format!(
+                "Cannot search a {}-dimensional ANN index with a {}-dimensional embedding", self.dimensions, query.len()
+            )));
+        }
+        let Some(entry) = self.entry else { return Ok(Vec::new()) };
+        let query = normalize(query);
+
+        let mut entry_points = vec![entry];
+        for layer in (1..self.nodes[entry as usize].neighbors.len()).rev() {
+            entry_points = vec![self.search_layer(&query, &entry_points, 1, layer)[0].id];
+        }
+        // Tombstones take up candidate slots, so look at more candidates when there are many
+        let ef = self.config.ef_search.max(limit) + self.deleted.min(self.config.ef_search * 4);
+        Ok(self.search_layer(&query, &entry_points, ef, 0)
+            .into_iter()
+            .filter(|scored| !self.nodes[scored.id as usize].deleted)
+            .take(limit)
+            .map(|scored| (self.nodes[scored.id as usize].name.clone(), 1.0 - scored.distance as f64))
+            .collect())
+    }
+
+    /// Flush the vectors and write the graph, compacting the index first if
+    /// tombstones make up a quarter of it
+    pub fn save(&mut self) -> Result<()> {
+        if self.deleted > 64 && self.deleted * 4 > self.nodes.len() {
+            self.compact()?;
+        }
+        self.vectors.flush()
+            .map_err(|e| ProxyError::config(format!("Failed to flush ANN index vectors: {}", e)))?;
+
+        let path = self.config.path.join(GRAPH_FILE);
+        let tmp = path.with_extension("tmp");
+        let write = || -> std::io::Result<()> {
+            let mut file = std::io::BufWriter::new(File::create(&tmp)?);
+            file.write_all(&encode_graph(self.dimensions, &self.nodes, self.entry))?;
+            file.into_inner()?.sync_all()?;
+            std::fs::rename(&tmp, &path)
+        };
+        write().map_err(|e| ProxyError::config(format!("Failed to write ANN index graph: {}", e)))
+    }
+
+    /// Rebuild the graph without tombstones
+    fn compact(&mut self) -> Result<()> {
+        info!("Compacting ANN index ({} live, {} deleted)", self.len(), self.deleted);
+        let live: Vec<(String, Vec<f32>)> = self.nodes.iter().enumerate()
+            .filter(|(_, node)| !node.deleted)
+            .map(|(id, node)| (node.name.clone(), self.vector(id as u32).to_vec()))
+            .collect();
+        self.nodes.clear();
+        self.ids.clear();
+        self.entry = None;
+        self.deleted = 0;
+        for (name, vector) in live {
+            self.insert(&name, &vector)?;
+        }
+        Ok(())
+    }
+
+    /// Candidates on `layer` closest to `query`, nearest first
+    fn search_layer(&self, query: &[f32], entry_points: &[u32], ef: usize, layer: usize) -> Vec<Scored> {
+        let mut visited: HashSet<u32> = entry_points.iter().copied().collect();
+        let mut candidates = BinaryHeap::new();
+        let mut results = BinaryHeap::new();
+        for &id in entry_points {
+            let scored = Scored { distance: self.distance(query, id), id };
+            candidates.push(Reverse(scored));
+            results.push(scored);
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            if results.len() >= ef && results.peek().is_some_and(|furthest: &Scored| current.distance > furthest.distance) {
+                break;
+            }
+            let Some(neighbors) = self.nodes[current.id as usize].neighbors.get(layer) else { continue };
+            for &neighbor in neighbors {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let scored = Scored { distance: self.distance(query, neighbor), id: neighbor };
+                if results.len() < ef || results.peek().is_some_and(|furthest| scored.distance < furthest.distance) {
+                    candidates.push(Reverse(scored));
+                    results.push(scored);
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    /// Link `from` to `to` on `layer`, keeping only the closest neighbours
+    fn connect(&mut self, from: u32, to: u32, layer: usize) {
+        let max = if layer == 0 { self.config.m * 2 } else { self.config.m };
+        let neighbors = &mut self.nodes[from as usize].neighbors[layer];
+        neighbors.push(to);
+        if neighbors.len() <= max {
+            return;
+        }
+
+        let base = self.vector(from).to_vec();
+        let mut scored: Vec<Scored> = self.nodes[from as usize].neighbors[layer].iter()
+            .map(|&id| Scored { distance: self.distance(&base, id), id })
+            .collect();
+        scored.sort();
+        scored.truncate(max);
+        self.nodes[from as usize].neighbors[layer] = scored.into_iter().map(|scored| scored.id).collect();
+    }
+
+    /// Layer of a new node: exponentially fewer nodes on each higher layer
+    fn random_level(&self, tool_name: &str, id: u32) -> usize {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        (tool_name, id).hash(&mut hasher);
+        let uniform = ((hasher.finish() >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        ((-uniform.ln() / (self.config.m as f64).ln()) as usize).min(MAX_LEVEL)
+    }
+
+    fn distance(&self, query: &[f32], id: u32) -> f32 {
+        1.0 - query.iter().zip(self.vector(id)).map(|(a, b)| a * b).sum::<f32>()
+    }
+
+    fn vector(&self, id: u32) -> &[f32] {
+        let start = id as usize * self.dimensions;
+        &self.floats()[start..start + self.dimensions]
+    }
+
+    fn floats(&self) -> &[f32] {
+        // SAFETY: the mapping is page-aligned and any bit pattern is a valid f32
+        let (prefix, floats, _) = unsafe { self.vectors.align_to::<f32>() };
+        debug_assert!(prefix.is_empty());
+        floats
+    }
+
+    fn floats_mut(&mut self) -> &mut [f32] {
+        // SAFETY: as in `floats`
+        let (prefix, floats, _) = unsafe { self.vectors.align_to_mut::<f32>() };
+        debug_assert!(prefix.is_empty());
+        floats
+    }
+
+    /// Grow the vectors file to hold at least `rows` vectors
+    fn ensure_capacity(&mut self, rows: usize) -> Result<()> {
+        let row_bytes = self.dimensions * 4;
+        let capacity = self.vectors.len() / row_bytes;
+        if rows <= capacity {
+            return Ok(());
+        }
+        let new_capacity = rows.max(capacity * 2).max(MIN_CAPACITY);
+        self.vectors.flush()
+            .map_err(|e| ProxyError::config(format!("Failed to flush ANN index vectors: {}", e)))?;
+        self.file.set_len((new_capacity * row_bytes) as u64)
+            .map_err(|e| ProxyError::config(format!("Failed to grow ANN index vectors: {}", e)))?;
+        self.vectors = map(&self.file)?;
+        Ok(())
+    }
+}
+
+fn map(file: &File) -> Result<MmapMut> {
+    // SAFETY: the vectors file is owned by this index; nothing else resizes or writes it while mapped
+    unsafe { MmapMut::map_mut(file) }
+        .map_err(|e| ProxyError::config(format!("Failed to memory-map ANN index vectors: {}", e)))
+}
+
+fn normalize(embedding: &[f32]) -> Vec<f32> {
+    let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        embedding.iter().map(|x| x / norm).collect()
+    } else {
+        embedding.to_vec()
+    }
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn encode_graph(dimensions: usize, nodes: &[Node], entry: Option<u32>) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    put_u32(&mut bytes, dimensions as u32);
+    put_u32(&mut bytes, nodes.len() as u32);
+    put_u32(&mut bytes, entry.unwrap_or(u32::MAX));
+    for node in nodes {
+        bytes.push(node.deleted as u8);
+        put_u32(&mut bytes, node.name.len() as u32);
+        bytes.extend_from_slice(node.name.as_bytes());
+        put_u32(&mut bytes, node.neighbors.len() as u32);
+        for layer in &node.neighbors {
+            put_u32(&mut bytes, layer.len() as u32);
+            for &neighbor in layer {
+                put_u32(&mut bytes, neighbor);
+            }
+        }
+    }
+    bytes
+}
+
+/// Reads an encoded graph front to back
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < len {
+            return None;
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+}
+
+fn decode_graph(bytes: &[u8]) -> Option<(usize, Vec<Node>, Option<u32>)> {
+    let mut reader = Reader { bytes: bytes.strip_prefix(MAGIC.as_slice())? };
+    let dimensions = reader.u32()? as usize;
+    let count = reader.u32()? as usize;
+    let entry = Some(reader.u32()?).filter(|&entry| entry != u32::MAX);
+    if entry.is_some_and(|entry| entry as usize >= count) {
+        return None;
+    }
+
+    let mut nodes = Vec::with_capacity(count.min(bytes.len()));
+    for _ in 0..count {
+        let deleted = reader.take(1)?[0] != 0;
+        let name_len = reader.u32()? as usize;
+        let name = String::from_utf8(reader.take(name_len)?.to_vec()).ok()?;
+        let levels = reader.u32()? as usize;
+        let mut neighbors = Vec::with_capacity(levels.min(MAX_LEVEL + 1));
+        for _ in 0..levels {
+            let len = reader.u32()? as usize;
+            let layer = (0..len).map(|_| reader.u32()).collect::<Option<Vec<u32>>>()?;
+            if layer.iter().any(|&id| id as usize >= count) {
+                return None;
+            }
+            neighbors.push(layer);
+        }
+        nodes.push(Node { name, neighbors, deleted });
+    }
+    Some((dimensions, nodes, entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(dir: &tempfile::TempDir) -> AnnIndexConfig {
+        AnnIndexConfig { path: dir.path().to_path_buf(), ..AnnIndexConfig::default() }
+    }
+
+    /// Deterministic pseudo-random unit vectors
+    fn vector(seed: u64, dimensions: usize) -> Vec<f32> {
+        let mut state = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (0..dimensions).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % 2000) as f32 / 1000.0 - 1.0
+        }).collect()
+    }
+
+    #[test]
+    fn test_search_finds_nearest() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = AnnIndex::open(config(&dir), 16).unwrap();
+        for i in 0..500 {
+            index.insert(&format!("tool_{}", i), &vector(i, 16)).unwrap();
+        }
+        assert_eq!(index.len(), 500);
+
+        for i in [0, 123, 499] {
+            let results = index.search(&vector(i, 16), 3).unwrap();
+            assert_eq!(results[0].0, format!("tool_{}", i));
+            assert!((results[0].1 - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_remove_and_replace() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = AnnIndex::open(config(&dir), 8).unwrap();
+        for i in 0..50 {
+            index.insert(&format!("tool_{}", i), &vector(i, 8)).unwrap();
+        }
+        assert!(index.remove("tool_7"));
+        assert!(!index.remove("tool_7"));
+        assert!(index.search(&vector(7, 8), 5).unwrap().iter().all(|(name, _)| name != "tool_7"));
+
+        index.insert("tool_8", &vector(1000, 8)).unwrap();
+        assert_eq!(index.search(&vector(1000, 8), 1).unwrap()[0].0, "tool_8");
+        assert_eq!(index.len(), 49);
+        assert!(index.insert("tool_9", &[1.0; 4]).is_err());
+    }
+
+    #[test]
+    fn test_persists_and_reopens() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = AnnIndex::open(config(&dir), 8).unwrap();
+        for i in 0..2000 {
+            index.insert(&format!("tool_{}", i), &vector(i, 8)).unwrap();
+        }
+        index.remove("tool_3");
+        index.save().unwrap();
+        drop(index);
+
+        let index = AnnIndex::open(config(&dir), 8).unwrap();
+        assert_eq!(index.len(), 1999);
+        assert!(!index.contains("tool_3"));
+        assert_eq!(index.search(&vector(1500, 8), 1).unwrap()[0].0, "tool_1500");
+
+        // A different embedding size starts over
+        assert!(AnnIndex::open(config(&dir), 4).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compaction_drops_tombstones() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = AnnIndex::open(config(&dir), 8).unwrap();
+        for i in 0..200 {
+            index.insert(&format!("tool_{}", i), &vector(i, 8)).unwrap();
+        }
+        for i in 0..100 {
+            index.remove(&format!("tool_{}", i));
+        }
+        index.save().unwrap();
+        assert_eq!(index.nodes.len(), 100);
+        assert_eq!(index.deleted, 0);
+        assert_eq!(index.search(&vector(150, 8), 1).unwrap()[0].0, "tool_150");
+    }
+}
@@ -3,9 +3,17 @@
 //! This module provides various caching strategies to improve performance
 //! of the smart discovery system, including tool matching cache, LLM response
 //! cache, and request deduplication.
+//!
+//! The semantic query cache sits behind the exact-match caches: it keys
+//! discovery results by the embedding of the request, so paraphrases of a
+//! request seen recently ("ping google.com" / "check if google.com is up")
+//! reuse its tool matches and parameter extraction instead of running the
+//! retrieval and LLM mapping pipeline again.
 
+use crate::discovery::exclusions::DiscoveryExclusions;
 use crate::discovery::types::*;
-use crate::error::Result;
+use crate::error::{ProxyError, Result};
+use crate::registry::toolset::{caller_scope, ToolScope};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -95,6 +103,9 @@ pub struct DiscoveryCacheConfig {
     pub registry_ttl: Duration,
     /// Enable/disable caching
     pub enabled: bool,
+    /// Semantic caching of paraphrased requests
+    #[serde(default)]
+    pub semantic: SemanticCacheConfig,
 }
 
 impl Default for DiscoveryCacheConfig {
@@ -107,10 +118,50 @@ impl Default for DiscoveryCacheConfig {
             max_registry_entries: 100,
             registry_ttl: Duration::from_secs(60), // 1 minute
             enabled: true,
+            semantic: SemanticCacheConfig::default(),
+        }
+    }
+}
+
+/// `smart_discovery.cache.semantic`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SemanticCacheConfig {
+    /// Reuse results of earlier requests whose embedding is close enough (needs semantic search)
+    pub enabled: bool,
+    /// Cosine similarity above which two requests count as the same (0.0-1.0)
+    pub similarity_threshold: f64,
+    /// Maximum number of cached requests
+    pub max_entries: usize,
+    /// TTL for cached requests; all of them are also dropped when the registry changes
+    #[serde(with = "duration_secs")]
+    pub ttl: Duration,
+}
+
+impl Default for SemanticCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            similarity_threshold: 0.95,
+            max_entries: 500,
+            ttl: Duration::from_secs(600), // 10 minutes
         }
     }
 }
 
+impl SemanticCacheConfig {
+    /// Validate the configuration
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.similarity_threshold) {
+            return Err(ProxyError::config("cache.semantic.similarity_threshold must be between 0.0 and 1.0"));
+        }
+        if self.enabled && self.max_entries == 0 {
+            return Err(ProxyError::config("cache.semantic.max_entries must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
 /// Cache key for tool matching
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ToolMatchCacheKey {
@@ -122,6 +173,10 @@ pub struct ToolMatchCacheKey {
     pub confidence_threshold: String, // Serialized as string for hashing
     /// Tool selection mode (rule_based or llm_based)
     pub tool_selection_mode: String,
+    /// Tools the request rules out
+    pub exclusions: DiscoveryExclusions,
+    /// Scope of the caller the tools were matched for
+    pub scope: ToolScope,
 }
 
 impl ToolMatchCacheKey {
@@ -132,6 +187,8 @@ impl ToolMatchCacheKey {
             context: request.context.clone(),
             confidence_threshold: format!("{:.2}", request.confidence_threshold.unwrap_or(0.7)),
             tool_selection_mode: tool_selection_mode.to_string(),
+            exclusions: DiscoveryExclusions::for_request(request),
+            scope: caller_scope(),
         }
     }
 }
@@ -212,6 +269,8 @@ pub struct DiscoveryCache {
     llm_responses: Arc<RwLock<HashMap<LlmCacheKey, CacheEntry<ParameterExtraction>>>>,
     /// Registry tools cache
     registry_tools: Arc<RwLock<Option<CacheEntry<Vec<(String, crate::registry::types::ToolDefinition)>>>>>,
+    /// Semantic cache of paraphrased requests
+    semantic: SemanticQueryCache,
     /// Cache statistics
     stats: Arc<RwLock<CacheStats>>,
 }
@@ -219,11 +278,16 @@ pub struct DiscoveryCache {
 impl DiscoveryCache {
     /// Create a new discovery cache
     pub fn new(config: DiscoveryCacheConfig) -> Self {
+        let semantic = SemanticQueryCache::new(SemanticCacheConfig {
+            enabled: config.enabled && config.semantic.enabled,
+            ..config.semantic.clone()
+        });
         Self {
             config,
             tool_matches: Arc::new(RwLock::new(HashMap::new())),
             llm_responses: Arc::new(RwLock::new(HashMap::new())),
             registry_tools: Arc::new(RwLock::new(None)),
+            semantic,
             stats: Arc::new(RwLock::new(CacheStats::default())),
         }
     }
@@ -238,6 +302,11 @@ impl DiscoveryCache {
         self.config.enabled
     }
 
+    /// Semantic cache of paraphrased requests
+    pub fn semantic(&self) -> &SemanticQueryCache {
+        &self.semantic
+    }
+
     /// Get tool matches from cache
     pub async fn get_tool_matches(&self, key: &ToolMatchCacheKey) -> Option<Vec<ToolMatch>> {
         if !self.config.enabled {
@@ -376,6 +445,7 @@ impl DiscoveryCache {
         tool_matches.clear();
         llm_responses.clear();
         *registry_tools = None;
+        self.semantic.clear().await;
 
         info!("Cleared all discovery caches");
     }
@@ -440,6 +510,262 @@ impl DiscoveryCache {
     }
 }
 
+/// Cache key for semantic lookups
+#[derive(Debug, Clone)]
+pub struct SemanticCacheKey {
+    /// Embedding of the user request
+    pub embedding: Vec<f32>,
+    /// Literal values in the request, which must match exactly
+    pub entities: Vec<String>,
+    /// Optional context
+    pub context: Option<String>,
+    /// Confidence threshold
+    pub confidence_threshold: String,
+    /// Tool selection mode
+    pub tool_selection_mode: String,
+    /// Tools the request rules out
+    pub exclusions: DiscoveryExclusions,
+    /// Scope of the caller the tools were matched for
+    pub scope: ToolScope,
+}
+
+impl SemanticCacheKey {
+    /// Create a new semantic cache key from a smart discovery request and its embedding
+    pub fn new(request: &SmartDiscoveryRequest, tool_selection_mode: &str, embedding: Vec<f32>) -> Self {
+        Self {
+            embedding,
+            entities: request_entities(&request.request),
+            context: request.context.clone(),
+            confidence_threshold: format!("{:.2}", request.confidence_threshold.unwrap_or(0.7)),
+            tool_selection_mode: tool_selection_mode.to_string(),
+            exclusions: DiscoveryExclusions::for_request(request),
+            scope: caller_scope(),
+        }
+    }
+
+    /// Whether everything but the embedding matches
+    fn same_scope(&self, other: &SemanticCacheKey) -> bool {
+        self.entities == other.entities
+            && self.context == other.context
+            && self.confidence_threshold == other.confidence_threshold
+            && self.tool_selection_mode == other.tool_selection_mode
+            && self.exclusions == other.exclusions
+            && self.scope == other.scope
+    }
+}
+
+/// Results cached for a request
+#[derive(Debug, Clone)]
+struct SemanticCacheEntry {
+    key: SemanticCacheKey,
+    matches: Vec<ToolMatch>,
+    /// Parameter extractions by (tool name, schema hash)
+    extractions: HashMap<(String, String), ParameterExtraction>,
+}
+
+/// Cache of discovery results keyed by request embedding
+///
+/// Lookups scan the cached requests for the most similar one, which is fine
+/// for the few hundred entries this cache holds.
+pub struct SemanticQueryCache {
+    config: SemanticCacheConfig,
+    entries: RwLock<Vec<CacheEntry<SemanticCacheEntry>>>,
+    /// Embeddings of recent requests, so each request is embedded once
+    embeddings: RwLock<HashMap<String, Vec<f32>>>,
+    /// Fingerprint of the registry the entries were computed against
+    registry_fingerprint: RwLock<Option<u64>>,
+    stats: RwLock<CacheStats>,
+}
+
+impl SemanticQueryCache {
+    /// Create a new semantic query cache
+    pub fn new(config: SemanticCacheConfig) -> Self {
+        Self {
+            config,
+            entries: RwLock::new(Vec::new()),
+            embeddings: RwLock::new(HashMap::new()),
+            registry_fingerprint: RwLock::new(None),
+            stats: RwLock::new(CacheStats::default()),
+        }
+    }
+
+    /// Check if semantic caching is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Drop every entry if the registry changed since they were cached
+    pub async fn check_registry(&self, fingerprint: u64) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let mut current = self.registry_fingerprint.write().await;
+        if current.is_some_and(|current| current != fingerprint) {
+            let mut entries = self.entries.write().await;
+            let dropped = entries.len();
+            entries.clear();
+            self.stats.write().await.evictions += dropped as u64;
+            info!("Registry changed, dropped {} semantic cache entries", dropped);
+        }
+        *current = Some(fingerprint);
+    }
+
+    /// Embedding of a request seen recently
+    pub async fn get_embedding(&self, request: &str) -> Option<Vec<f32>> {
+        self.embeddings.read().await.get(request).cloned()
+    }
+
+    /// Remember the embedding of a request
+    pub async fn store_embedding(&self, request: &str, embedding: Vec<f32>) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let mut embeddings = self.embeddings.write().await;
+        if embeddings.len() >= self.config.max_entries {
+            embeddings.clear();
+        }
+        embeddings.insert(request.to_string(), embedding);
+    }
+
+    /// Tool matches of the most similar cached request, if similar enough
+    pub async fn get_tool_matches(&self, key: &SemanticCacheKey) -> Option<Vec<ToolMatch>> {
+        if !self.config.enabled {
+            return None;
+        }
+
+        let mut entries = self.entries.write().await;
+        entries.retain(|entry| !entry.is_expired());
+        let result = match self.most_similar(&entries, key) {
+            Some((index, similarity)) => {
+                debug!("Semantic cache hit (similarity {:.3})", similarity);
+                Some(entries[index].get().matches.clone())
+            }
+            None => None,
+        };
+        drop(entries);
+
+        let mut stats = self.stats.write().await;
+        if result.is_some() {
+            stats.record_hit();
+        } else {
+            stats.record_miss();
+        }
+        result
+    }
+
+    /// Store the tool matches of a request
+    pub async fn store_tool_matches(&self, key: SemanticCacheKey, matches: Vec<ToolMatch>) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let mut entries = self.entries.write().await;
+        entries.retain(|entry| !entry.is_expired());
+        if entries.len() >= self.config.max_entries {
+            let evict_count = (entries.len() / 4).max(1); // Evict 25% of entries
+            entries.sort_by_key(|entry| entry.created_at);
+            entries.drain(..evict_count);
+            self.stats.write().await.evictions += evict_count as u64;
+            debug!("Evicted {} semantic cache entries", evict_count);
+        }
+
+        let entry = SemanticCacheEntry { key, matches, extractions: HashMap::new() };
+        entries.push(CacheEntry::new(entry, self.config.ttl));
+        debug!("Stored tool matches in semantic cache, total entries: {}", entries.len());
+    }
+
+    /// Parameter extraction for a tool cached with the most similar request
+    pub async fn get_parameter_extraction(&self, key: &SemanticCacheKey, tool_name: &str, schema_hash: &str) -> Option<ParameterExtraction> {
+        if !self.config.enabled {
+            return None;
+        }
+
+        let entries = self.entries.read().await;
+        let (index, _) = self.most_similar(&entries, key)?;
+        let extraction = entries[index].value.extractions.get(&(tool_name.to_string(), schema_hash.to_string())).cloned();
+        drop(entries);
+
+        if extraction.is_some() {
+            self.stats.write().await.record_hit();
+            debug!("Semantic cache hit for parameter extraction of tool: {}", tool_name);
+        }
+        extraction
+    }
+
+    /// Store a parameter extraction with the most similar cached request
+    pub async fn store_parameter_extraction(&self, key: &SemanticCacheKey, tool_name: &str, schema_hash: &str, extraction: ParameterExtraction) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let mut entries = self.entries.write().await;
+        if let Some((index, _)) = self.most_similar(&entries, key) {
+            entries[index].value.extractions.insert((tool_name.to_string(), schema_hash.to_string()), extraction);
+        }
+    }
+
+    /// Clear all entries
+    pub async fn clear(&self) {
+        self.entries.write().await.clear();
+        self.embeddings.write().await.clear();
+    }
+
+    /// Get cache statistics
+    pub async fn get_stats(&self) -> CacheStats {
+        let mut stats = self.stats.read().await.clone();
+        stats.entries = self.entries.read().await.len() as u64;
+        stats
+    }
+
+    /// Index and similarity of the live entry most similar to `key`, if above the threshold
+    fn most_similar(&self, entries: &[CacheEntry<SemanticCacheEntry>], key: &SemanticCacheKey) -> Option<(usize, f64)> {
+        entries.iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.is_expired() && entry.value.key.same_scope(key))
+            .map(|(index, entry)| (index, cosine_similarity(&entry.value.key.embedding, &key.embedding)))
+            .filter(|(_, similarity)| *similarity >= self.config.similarity_threshold)
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+    }
+}
+
+/// Literal values in a request: words with digits or `.`, `/`, `@`, `:`, and quoted text
+///
+/// Embeddings of "ping google.com" and "ping yahoo.com" are nearly identical,
+/// but their results aren't interchangeable, so these must match exactly.
+pub fn request_entities(request: &str) -> Vec<String> {
+    let mut entities: Vec<String> = request.split(['"', '`'])
+        .skip(1)
+        .step_by(2)
+        .map(|quoted| quoted.trim().to_string())
+        .filter(|quoted| !quoted.is_empty())
+        .collect();
+    entities.extend(request.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| matches!(c, ',' | ';' | '!' | '?' | '(' | ')' | '"' | '\'' | '`')).trim_end_matches('.'))
+        .filter(|word| word.chars().any(|c| c.is_ascii_digit() || matches!(c, '.' | '/' | '@' | ':' | '\\')))
+        .map(str::to_lowercase));
+    entities.sort();
+    entities.dedup();
+    entities
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f64, 0.0f64, 0.0f64);
+    for (x, y) in a.iter().zip(b) {
+        dot += *x as f64 * *y as f64;
+        norm_a += *x as f64 * *x as f64;
+        norm_b += *y as f64 * *y as f64;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
 /// Create a simple hash for tool schema to detect changes
 pub fn create_schema_hash(schema: &serde_json::Value) -> String {
     use std::collections::hash_map::DefaultHasher;
@@ -473,6 +799,8 @@ mod tests {
             context: None,
             confidence_threshold: "0.70".to_string(),
             tool_selection_mode: "rule_based".to_string(),
+            exclusions: DiscoveryExclusions::default(),
+            scope: ToolScope::default(),
         };
         
         // Test cache miss
@@ -484,6 +812,7 @@ mod tests {
             confidence_score: 0.8,
             reasoning: "test reasoning".to_string(),
             meets_threshold: true,
+            breakdown: ScoreBreakdown::default(),
         }];
         
         cache.store_tool_matches(key.clone(), matches.clone()).await;
@@ -503,6 +832,8 @@ mod tests {
             context: None,
             confidence_threshold: "0.70".to_string(),
             tool_selection_mode: "rule_based".to_string(),
+            exclusions: DiscoveryExclusions::default(),
+            scope: ToolScope::default(),
         };
         
         // Should be a miss
@@ -536,4 +867,67 @@ mod tests {
         assert_eq!(hash1, hash2);
         assert_ne!(hash1, hash3);
     }
+
+    fn semantic_key(request: &str, embedding: Vec<f32>) -> SemanticCacheKey {
+        let request = SmartDiscoveryRequest {
+            request: request.to_string(),
+            context: None,
+            preferred_tools: None,
+            confidence_threshold: None,
+            include_error_details: None,
+            sequential_mode: None,
+            conversation: None,
+            exclusions: None,
+        };
+        SemanticCacheKey::new(&request, "hybrid", embedding)
+    }
+
+    #[test]
+    fn test_request_entities() {
+        assert_eq!(request_entities("ping google.com 3 times"), vec!["3", "google.com"]);
+        assert_eq!(request_entities("Read /tmp/notes.txt, please."), vec!["/tmp/notes.txt"]);
+        assert_eq!(request_entities("search for \"rust async\" on the web"), vec!["rust async"]);
+        assert!(request_entities("what's the weather like").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_semantic_cache_matches_paraphrases() {
+        let cache = SemanticQueryCache::new(SemanticCacheConfig { enabled: true, ..SemanticCacheConfig::default() });
+        let matches = vec![ToolMatch {
+            tool_name: "ping".to_string(),
+            confidence_score: 0.9,
+            reasoning: "test reasoning".to_string(),
+            meets_threshold: true,
+            breakdown: ScoreBreakdown::default(),
+        }];
+        cache.store_tool_matches(semantic_key("ping google.com", vec![1.0, 0.0, 0.1]), matches).await;
+
+        // A paraphrase with a nearly identical embedding hits
+        let paraphrase = semantic_key("check whether google.com is up", vec![0.99, 0.01, 0.1]);
+        assert_eq!(cache.get_tool_matches(&paraphrase).await.unwrap()[0].tool_name, "ping");
+
+        // Different literal values or a distant embedding miss
+        assert!(cache.get_tool_matches(&semantic_key("ping yahoo.com", vec![1.0, 0.0, 0.1])).await.is_none());
+        assert!(cache.get_tool_matches(&semantic_key("ping google.com", vec![0.0, 1.0, 0.0])).await.is_none());
+
+        let extraction = ParameterExtraction {
+            parameters: HashMap::from([("host".to_string(), serde_json::json!("google.com"))]),
+            status: ExtractionStatus::Success,
+            warnings: Vec::new(),
+            used_defaults: HashMap::new(),
+        };
+        cache.store_parameter_extraction(&paraphrase, "ping", "abc", extraction).await;
+        assert!(cache.get_parameter_extraction(&paraphrase, "ping", "abc").await.is_some());
+        assert!(cache.get_parameter_extraction(&paraphrase, "ping", "def").await.is_none());
+
+        // A registry change drops everything
+        cache.check_registry(1).await;
+        assert!(cache.get_tool_matches(&paraphrase).await.is_some());
+        cache.check_registry(2).await;
+        assert!(cache.get_tool_matches(&paraphrase).await.is_none());
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.entries, 0);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,381 @@
+//! Automatic tool categories
+//!
+//! Aggregating several MCP servers easily yields a catalog of thousands of
+//! tools, too many to page through. With `smart_discovery.categories`
+//! enabled, the tool embeddings are clustered with k-means every
+//! `refresh_interval_secs`, and each cluster becomes a category named after
+//! the terms that set its tools apart. Categories are added to the tools as
+//! registry tags (`category:<name>`), so tag filters, toolsets and filtered
+//! search can use them, and can be explored with the `browse_categories`
+//! tool or the dashboard API.
+//!
+//! ```yaml
+//! smart_discovery:
+//!   categories:
+//!     enabled: true
+//!     refresh_interval_secs: 3600
+//!     min_tools: 20
+//!     tools_per_category: 15
+//!     max_categories: 40
+//! ```
+
+use crate::discovery::keyword_index::{tokenize, STOP_WORDS};
+use crate::error::{ProxyError, Result};
+use crate::registry::types::ToolDefinition;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Name of the MCP tool that lists the categories and their tools
+pub const CATEGORIES_TOOL_NAME: &str = "browse_categories";
+
+/// Most k-means rounds; clustering usually settles well before
+const MAX_ITERATIONS: usize = 25;
+
+/// Terms a category name is made of
+const NAME_TERMS: usize = 2;
+
+/// Terms listed as a category's keywords
+const KEYWORD_TERMS: usize = 5;
+
+/// `smart_discovery.categories`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolCategoriesConfig {
+    /// Derive categories from the tool embeddings (needs semantic search)
+    pub enabled: bool,
+
+    /// Seconds between recomputations of the categories
+    pub refresh_interval_secs: u64,
+
+    /// Embedded tools needed before categories are derived; smaller catalogs are easy enough to browse
+    pub min_tools: usize,
+
+    /// Average number of tools per category, which sets how many categories there are
+    pub tools_per_category: usize,
+
+    /// Most categories derived, however large the catalog
+    pub max_categories: usize,
+
+    /// Prefix of the category tags added to tools
+    pub tag_prefix: String,
+}
+
+impl Default for ToolCategoriesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            refresh_interval_secs: 3600,
+            min_tools: 20,
+            tools_per_category: 15,
+            max_categories: 40,
+            tag_prefix: "category:".to_string(),
+        }
+    }
+}
+
+impl ToolCategoriesConfig {
+    /// Validate the configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.refresh_interval_secs == 0 {
+            return Err(ProxyError::config("categories.refresh_interval_secs must be greater than 0"));
+        }
+        if self.min_tools < 2 {
+            return Err(ProxyError::config("categories.min_tools must be at least 2"));
+        }
+        if self.tools_per_category < 2 {
+            return Err(ProxyError::config("categories.tools_per_category must be at least 2"));
+        }
+        if self.max_categories < 2 {
+            return Err(ProxyError::config("categories.max_categories must be at least 2"));
+        }
+        if self.tag_prefix.trim().is_empty() {
+            return Err(ProxyError::config("categories.tag_prefix cannot be empty"));
+        }
+        Ok(())
+    }
+
+    /// Number of categories for `tool_count` tools
+    fn category_count(&self, tool_count: usize) -> usize {
+        tool_count.div_ceil(self.tools_per_category).clamp(2, self.max_categories).min(tool_count)
+    }
+}
+
+/// A group of similar tools
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCategory {
+    /// Name made of the terms that set the category's tools apart, e.g. `file-directory`
+    pub name: String,
+
+    /// Registry tag of the category's tools
+    pub tag: String,
+
+    /// Most distinctive terms of the category, best first
+    pub keywords: Vec<String>,
+
+    /// Tools in the category, sorted
+    pub tools: Vec<String>,
+}
+
+/// Categories derived from the latest clustering
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCategories {
+    /// Categories, largest first
+    pub categories: Vec<ToolCategory>,
+
+    pub generated_at: DateTime<Utc>,
+}
+
+impl ToolCategories {
+    /// Cluster the `tools` that have embeddings into categories
+    ///
+    /// Returns `None` when fewer than `config.min_tools` tools have embeddings.
+    pub fn build(
+        tools: &[(String, ToolDefinition)],
+        embeddings: &HashMap<String, Vec<f32>>,
+        config: &ToolCategoriesConfig,
+    ) -> Option<Self> {
+        // Sorted by name, so the same catalog always gets the same categories
+        let mut embedded: Vec<(&String, &ToolDefinition, &Vec<f32>)> = tools.iter()
+            .filter_map(|(name, tool)| embeddings.get(name).map(|embedding| (name, tool, embedding)))
+            .collect();
+        embedded.sort_by(|a, b| a.0.cmp(b.0));
+        // Embeddings of another size (e.g. left over from a model swap) can't be compared
+        if let Some(dimensions) = embedded.first().map(|(_, _, embedding)| embedding.len()) {
+            embedded.retain(|(_, _, embedding)| embedding.len() == dimensions && dimensions > 0);
+        }
+        if embedded.len() < config.min_tools {
+            return None;
+        }
+
+        let vectors: Vec<Vec<f32>> = embedded.iter().map(|(_, _, embedding)| normalized(embedding)).collect();
+        let assignments = kmeans(&vectors, config.category_count(vectors.len()));
+
+        let terms: Vec<BTreeSet<String>> = embedded.iter().map(|(name, tool, _)| tool_terms(name, tool)).collect();
+        let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+        for tool_terms in &terms {
+            for term in tool_terms {
+                *document_frequency.entry(term.as_str()).or_default() += 1;
+            }
+        }
+
+        let mut clusters: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (tool, cluster) in assignments.into_iter().enumerate() {
+            clusters.entry(cluster).or_default().push(tool);
+        }
+        let mut clusters: Vec<Vec<usize>> = clusters.into_values().collect();
+        clusters.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a[0].cmp(&b[0])));
+
+        let mut categories: Vec<ToolCategory> = Vec::with_capacity(clusters.len());
+        for (position, members) in clusters.into_iter().enumerate() {
+            let keywords = distinctive_terms(&members, &terms, &document_frequency, embedded.len());
+            let mut name = if keywords.is_empty() {
+                format!("category-{}", position + 1)
+            } else {
+                keywords.iter().take(NAME_TERMS).cloned().collect::<Vec<_>>().join("-")
+            };
+            if categories.iter().any(|category| category.name == name) {
+                name = format!("{}-{}", name, position + 1);
+            }
+            categories.push(ToolCategory {
+                tag: format!("{}{}", config.tag_prefix, name),
+                name,
+                keywords,
+                tools: members.iter().map(|&tool| embedded[tool].0.clone()).collect(),
+            });
+        }
+
+        Some(Self { categories, generated_at: Utc::now() })
+    }
+
+    /// Category tags by tool, for the registry
+    pub fn tags(&self) -> HashMap<String, Vec<String>> {
+        self.categories.iter()
+            .flat_map(|category| category.tools.iter().map(|tool| (tool.clone(), vec![category.tag.clone()])))
+            .collect()
+    }
+
+    /// Category with the given name or tag
+    pub fn get(&self, category: &str) -> Option<&ToolCategory> {
+        self.categories.iter().find(|c| c.name == category || c.tag == category)
+    }
+}
+
+/// Search terms of a tool's name and description worth naming a category after
+fn tool_terms(name: &str, tool: &ToolDefinition) -> BTreeSet<String> {
+    tokenize(&format!("{} {}", name, tool.description))
+        .into_iter()
+        // Whole identifiers are too specific, and their parts are kept anyway
+        .filter(|term| term.len() > 2 && !term.contains('_') && !term.contains(|c: char| c.is_ascii_digit()))
+        .filter(|term| !STOP_WORDS.contains(&term.as_str()))
+        .collect()
+}
+
+/// Terms common in the `members` of a cluster but rare elsewhere, best first
+fn distinctive_terms(
+    members: &[usize],
+    terms: &[BTreeSet<String>],
+    document_frequency: &HashMap<&str, usize>,
+    tool_count: usize,
+) -> Vec<String> {
+    let mut cluster_frequency: HashMap<&str, usize> = HashMap::new();
+    for &tool in members {
+        for term in &terms[tool] {
+            *cluster_frequency.entry(term.as_str()).or_default() += 1;
+        }
+    }
+
+    let mut scored: Vec<(&str, f64)> = cluster_frequency.into_iter()
+        // A term only one tool of a larger cluster uses says little about the rest
+        .filter(|(_, count)| *count > 1 || members.len() == 1)
+        .map(|(term, count)| {
+            let idf = (tool_count as f64 / document_frequency[term] as f64).ln();
+            (term, count as f64 / members.len() as f64 * idf)
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.0.cmp(b.0)));
+    scored.into_iter().take(KEYWORD_TERMS).map(|(term, _)| term.to_string()).collect()
+}
+
+/// `vector` scaled to unit length
+fn normalized(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter().map(|x| x / norm).collect()
+    } else {
+        vector.to_vec()
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Cluster of each of the unit `vectors`, using k-means on cosine similarity
+///
+/// Centroids start at mutually distant vectors (farthest-first from the first
+/// vector) rather than random ones, so results are reproducible.
+fn kmeans(vectors: &[Vec<f32>], k: usize) -> Vec<usize> {
+    let nearest = |vector: &[f32], centroids: &[Vec<f32>]| -> (usize, f32) {
+        centroids.iter()
+            .enumerate()
+            .map(|(i, centroid)| (i, dot(vector, centroid)))
+            .fold((0, f32::NEG_INFINITY), |best, candidate| if candidate.1 > best.1 { candidate } else { best })
+    };
+
+    let mut centroids: Vec<Vec<f32>> = vec![vectors[0].clone()];
+    while centroids.len() < k {
+        let farthest = vectors.iter()
+            .enumerate()
+            .map(|(i, vector)| (i, nearest(vector, &centroids).1))
+            .fold((0, f32::INFINITY), |best, candidate| if candidate.1 < best.1 { candidate } else { best });
+        centroids.push(vectors[farthest.0].clone());
+    }
+
+    let mut assignments = vec![usize::MAX; vectors.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (vector, assignment) in vectors.iter().zip(assignments.iter_mut()) {
+            let cluster = nearest(vector, &centroids).0;
+            if *assignment != cluster {
+                *assignment = cluster;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+
+        for (cluster, centroid) in centroids.iter_mut().enumerate() {
+            let mut sum = vec![0.0; centroid.len()];
+            for (vector, _) in vectors.iter().zip(&assignments).filter(|(_, assignment)| **assignment == cluster) {
+                for (total, x) in sum.iter_mut().zip(vector) {
+                    *total += x;
+                }
+            }
+            // An emptied cluster keeps its centroid and may pick up tools again
+            if sum.iter().any(|x| *x != 0.0) {
+                *centroid = normalized(&sum);
+            }
+        }
+    }
+    assignments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::types::RoutingConfig;
+
+    fn tool(name: &str, description: &str) -> (String, ToolDefinition) {
+        let tool = ToolDefinition::new_with_fields(
+            name.to_string(),
+            description.to_string(),
+            serde_json::json!({"type": "object"}),
+            RoutingConfig::new("subprocess".to_string(), serde_json::json!({"command": "echo"})),
+            None,
+        )
+        .unwrap();
+        (name.to_string(), tool)
+    }
+
+    /// Two groups of tools with embeddings pointing two different ways
+    fn catalog() -> (Vec<(String, ToolDefinition)>, HashMap<String, Vec<f32>>) {
+        let tools = vec![
+            tool("read_file", "Read a file from a directory"),
+            tool("write_file", "Write a file to a directory"),
+            tool("list_directory", "List the files in a directory"),
+            tool("http_get", "Send an HTTP request to a URL"),
+            tool("http_post", "Send an HTTP request with a body to a URL"),
+            tool("ping_host", "Check whether a network host responds"),
+        ];
+        let embeddings = HashMap::from([
+            ("read_file".to_string(), vec![1.0, 0.1, 0.0]),
+            ("write_file".to_string(), vec![0.9, 0.2, 0.0]),
+            ("list_directory".to_string(), vec![1.0, 0.0, 0.1]),
+            ("http_get".to_string(), vec![0.0, 1.0, 0.1]),
+            ("http_post".to_string(), vec![0.1, 0.9, 0.0]),
+            ("ping_host".to_string(), vec![0.0, 1.0, 0.2]),
+        ]);
+        (tools, embeddings)
+    }
+
+    fn config() -> ToolCategoriesConfig {
+        ToolCategoriesConfig { min_tools: 4, tools_per_category: 3, ..Default::default() }
+    }
+
+    #[test]
+    fn test_build_groups_similar_tools() {
+        let (tools, embeddings) = catalog();
+        let categories = ToolCategories::build(&tools, &embeddings, &config()).unwrap();
+
+        assert_eq!(categories.categories.len(), 2);
+        let files = categories.categories.iter().find(|c| c.tools.contains(&"read_file".to_string())).unwrap();
+        assert_eq!(files.tools, vec!["list_directory", "read_file", "write_file"]);
+        assert_eq!(files.name, "file-directory");
+        assert_eq!(files.tag, "category:file-directory");
+        let http = categories.categories.iter().find(|c| c.tools.contains(&"http_get".to_string())).unwrap();
+        assert_eq!(http.tools, vec!["http_get", "http_post", "ping_host"]);
+
+        assert_eq!(categories.tags()["ping_host"], vec![http.tag.clone()]);
+        assert_eq!(categories.get(&files.tag), Some(files));
+        assert_eq!(categories.get(&http.name), Some(http));
+    }
+
+    #[test]
+    fn test_small_catalogs_get_no_categories() {
+        let (tools, embeddings) = catalog();
+        let config = ToolCategoriesConfig { min_tools: 10, ..config() };
+        assert!(ToolCategories::build(&tools, &embeddings, &config).is_none());
+        assert!(ToolCategories::build(&tools, &HashMap::new(), &ToolCategoriesConfig::default()).is_none());
+    }
+
+    #[test]
+    fn test_category_count() {
+        let config = ToolCategoriesConfig::default();
+        assert_eq!(config.category_count(20), 2);
+        assert_eq!(config.category_count(150), 10);
+        assert_eq!(config.category_count(10_000), 40);
+    }
+}
@@ -0,0 +1,182 @@
+//! Clarifying questions for low-confidence discovery
+//!
+//! When no tool meets the confidence threshold, smart discovery used to run
+//! its best guess anyway. With `smart_discovery.clarification` enabled it asks
+//! instead: the response carries a question listing the likeliest tools, both
+//! as plain data and as MCP `elicitation/create` parameters for clients that
+//! support elicitation. The client answers by sending the request again with
+//! the chosen tool in `preferred_tools`, which is then used even below the
+//! threshold.
+//!
+//! ```yaml
+//! smart_discovery:
+//!   clarification:
+//!     enabled: true
+//!     max_candidates: 3
+//!     min_candidate_confidence: 0.1
+//! ```
+
+use crate::discovery::types::ToolMatch;
+use crate::error::{ProxyError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// `smart_discovery.clarification`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClarificationConfig {
+    /// Ask which tool was meant instead of running a tool below the confidence threshold
+    pub enabled: bool,
+
+    /// Most candidate tools offered in a question
+    pub max_candidates: usize,
+
+    /// Tools scoring below this aren't offered; with none left, discovery reports that nothing matched
+    pub min_candidate_confidence: f64,
+}
+
+impl Default for ClarificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_candidates: 3,
+            min_candidate_confidence: 0.1,
+        }
+    }
+}
+
+impl ClarificationConfig {
+    /// Validate the configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.max_candidates == 0 {
+            return Err(ProxyError::config("clarification.max_candidates must be at least 1"));
+        }
+        if !(0.0..=1.0).contains(&self.min_candidate_confidence) {
+            return Err(ProxyError::config("clarification.min_candidate_confidence must be between 0.0 and 1.0"));
+        }
+        Ok(())
+    }
+}
+
+/// A tool offered in a clarifying question
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClarificationCandidate {
+    pub tool_name: String,
+    pub description: String,
+    pub confidence_score: f64,
+}
+
+/// Question returned instead of running a tool nobody is sure about
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryClarification {
+    /// The question for the user
+    pub message: String,
+
+    /// Tools the request may be meant for, likeliest first
+    pub candidates: Vec<ClarificationCandidate>,
+
+    /// The same question as MCP `elicitation/create` parameters
+    pub elicitation: serde_json::Value,
+}
+
+impl DiscoveryClarification {
+    /// Question about `request` offering the likeliest of `matches`, or `None` when none is likely enough
+    ///
+    /// `describe` looks up a tool's description.
+    pub fn build(
+        request: &str,
+        matches: &[ToolMatch],
+        threshold: f64,
+        config: &ClarificationConfig,
+        describe: impl Fn(&str) -> Option<String>,
+    ) -> Option<Self> {
+        let mut likely: Vec<&ToolMatch> = matches.iter()
+            .filter(|tool_match| tool_match.confidence_score >= config.min_candidate_confidence)
+            .collect();
+        likely.sort_by(|a, b| b.confidence_score.partial_cmp(&a.confidence_score).unwrap_or(std::cmp::Ordering::Equal));
+        let candidates: Vec<ClarificationCandidate> = likely.into_iter()
+            .take(config.max_candidates)
+            .map(|tool_match| ClarificationCandidate {
+                tool_name: tool_match.tool_name.clone(),
+                description: describe(&tool_match.tool_name).unwrap_or_default(),
+                confidence_score: tool_match.confidence_score,
+            })
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let message = format!(
+            "I'm not sure which tool fits \"{}\": the best match scored {:.2}, below the {:.2} needed to run it. \
+             Which of these did you mean? Send the request again with that tool in preferred_tools, or describe it in more detail.",
+            request, candidates[0].confidence_score, threshold
+        );
+        let elicitation = json!({
+            "message": message,
+            "requestedSchema": {
+                "type": "object",
+                "properties": {
+                    "tool": {
+                        "type": "string",
+                        "title": "Tool",
+                        "enum": candidates.iter().map(|c| c.tool_name.as_str()).collect::<Vec<_>>(),
+                        "enumNames": candidates.iter()
+                            .map(|c| if c.description.is_empty() { c.tool_name.clone() } else { format!("{}: {}", c.tool_name, c.description) })
+                            .collect::<Vec<_>>(),
+                    },
+                    "details": {
+                        "type": "string",
+                        "title": "Details",
+                        "description": "Anything else that makes the request clearer",
+                    },
+                },
+                "required": ["tool"],
+            },
+        });
+
+        Some(Self { message, candidates, elicitation })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::types::ScoreBreakdown;
+
+    fn tool_match(tool_name: &str, confidence_score: f64) -> ToolMatch {
+        ToolMatch {
+            tool_name: tool_name.to_string(),
+            confidence_score,
+            reasoning: String::new(),
+            meets_threshold: false,
+            breakdown: ScoreBreakdown::default(),
+        }
+    }
+
+    #[test]
+    fn test_build_offers_likeliest_tools() {
+        let matches = vec![
+            tool_match("http_get", 0.5),
+            tool_match("ping", 0.4),
+            tool_match("read_file", 0.3),
+            tool_match("write_file", 0.2),
+            tool_match("noise", 0.05),
+        ];
+        let describe = |name: &str| (name == "ping").then(|| "Ping a host".to_string());
+        let clarification = DiscoveryClarification::build("check example.com", &matches, 0.7, &ClarificationConfig::default(), describe).unwrap();
+
+        let names: Vec<_> = clarification.candidates.iter().map(|c| c.tool_name.as_str()).collect();
+        assert_eq!(names, vec!["http_get", "ping", "read_file"]);
+        assert!(clarification.message.contains("0.50"));
+        let schema = &clarification.elicitation["requestedSchema"]["properties"]["tool"];
+        assert_eq!(schema["enum"], json!(["http_get", "ping", "read_file"]));
+        assert_eq!(schema["enumNames"][1], json!("ping: Ping a host"));
+    }
+
+    #[test]
+    fn test_build_needs_a_likely_tool() {
+        let matches = vec![tool_match("noise", 0.05)];
+        assert!(DiscoveryClarification::build("xyz", &matches, 0.7, &ClarificationConfig::default(), |_| None).is_none());
+        assert!(DiscoveryClarification::build("xyz", &[], 0.7, &ClarificationConfig::default(), |_| None).is_none());
+    }
+}
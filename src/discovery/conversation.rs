@@ -0,0 +1,204 @@
+//! Conversation-aware discovery
+//!
+//! Clients can send the recent conversation along with a smart discovery
+//! request: the last few messages and the tool calls made so far. Follow-ups
+//! such as "now do the same for repo B" name neither the tool nor most of its
+//! arguments, so before discovery runs:
+//!
+//! - the conversation is summarised into the request context, which tool
+//!   selection and parameter mapping already take into account;
+//! - a follow-up is rewritten into a standalone request by the LLM (when LLM
+//!   mapping is enabled) and the tool called last is preferred;
+//! - after parameter mapping, arguments the follow-up leaves out or left at
+//!   their defaults are carried over from the earlier call to the same tool.
+//!
+//! ```json
+//! {
+//!   "request": "now do the same for repo B",
+//!   "conversation": {
+//!     "messages": [{"role": "user", "content": "list open PRs in repo A"}],
+//!     "tool_calls": [{"tool_name": "github_list_prs", "arguments": {"repo": "A", "state": "open"}}]
+//!   }
+//! }
+//! ```
+
+use crate::discovery::types::{ConversationContext, ExtractionStatus, ParameterExtraction, PriorToolCall};
+use crate::registry::types::ToolDefinition;
+
+/// Messages and tool calls, each, that make it into the summary
+const MAX_SUMMARY_ITEMS: usize = 5;
+
+/// Longest message kept in the summary, in characters
+const MAX_MESSAGE_CHARS: usize = 300;
+
+/// Words that refer back to something earlier in the conversation
+const FOLLOW_UP_WORDS: &[&str] = &["again", "also", "instead", "previous", "same", "them", "those", "too"];
+
+/// Phrases that refer back to something earlier in the conversation
+const FOLLOW_UP_PHRASES: &[&str] = &[
+    "as before", "do it", "do that", "for it", "how about", "like before", "now do", "now for", "that one",
+    "this one", "what about", "with it",
+];
+
+/// Whether `request` refers back to earlier turns instead of standing on its own
+pub fn is_follow_up(request: &str) -> bool {
+    let words: Vec<String> = request
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.iter().any(|word| FOLLOW_UP_WORDS.contains(&word.as_str())) {
+        return true;
+    }
+    let text = format!(" {} ", words.join(" "));
+    FOLLOW_UP_PHRASES.iter().any(|phrase| text.contains(&format!(" {} ", phrase)))
+}
+
+/// The conversation as context text, most recent turns only
+pub fn conversation_summary(conversation: &ConversationContext) -> String {
+    let mut lines = Vec::new();
+
+    let messages = &conversation.messages[conversation.messages.len().saturating_sub(MAX_SUMMARY_ITEMS)..];
+    if !messages.is_empty() {
+        lines.push("Recent conversation:".to_string());
+        for message in messages {
+            let content: String = message.content.chars().take(MAX_MESSAGE_CHARS).collect();
+            lines.push(format!("- {}: {}", message.role, content.trim()));
+        }
+    }
+
+    let tool_calls = &conversation.tool_calls[conversation.tool_calls.len().saturating_sub(MAX_SUMMARY_ITEMS)..];
+    if !tool_calls.is_empty() {
+        lines.push("Earlier tool calls:".to_string());
+        for call in tool_calls {
+            let arguments = serde_json::to_string(&call.arguments).unwrap_or_default();
+            match &call.request {
+                Some(request) => lines.push(format!("- {} {} (for \"{}\")", call.tool_name, arguments, request)),
+                None => lines.push(format!("- {} {}", call.tool_name, arguments)),
+            }
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Fill arguments `extraction` is missing, or only has defaults for, from an earlier call to the same tool
+///
+/// Returns the names of the carried-over arguments. Failed extractions are
+/// left alone, since carrying everything over would just repeat the earlier call.
+pub fn carry_over_arguments(extraction: &mut ParameterExtraction, previous: &PriorToolCall, tool_def: &ToolDefinition) -> Vec<String> {
+    if matches!(extraction.status, ExtractionStatus::Failed) {
+        return Vec::new();
+    }
+
+    let properties = tool_def.input_schema.get("properties").and_then(|p| p.as_object());
+    let mut carried: Vec<String> = previous.arguments.iter()
+        .filter(|(name, value)| !value.is_null() && properties.map_or(true, |properties| properties.contains_key(*name)))
+        .filter(|(name, _)| !extraction.parameters.contains_key(*name) || extraction.used_defaults.contains_key(*name))
+        .map(|(name, _)| name.clone())
+        .collect();
+    carried.sort();
+
+    for name in &carried {
+        extraction.parameters.insert(name.clone(), previous.arguments[name].clone());
+        extraction.used_defaults.remove(name);
+    }
+    if !carried.is_empty() {
+        extraction.warnings.push(format!("Reused {} from the earlier {} call", carried.join(", "), previous.tool_name));
+
+        let required = tool_def.input_schema.get("required").and_then(|r| r.as_array());
+        let complete = required.map_or(true, |required| {
+            required.iter()
+                .filter_map(|name| name.as_str())
+                .all(|name| extraction.parameters.get(name).is_some_and(|value| !value.is_null()))
+        });
+        if complete {
+            extraction.status = ExtractionStatus::Success;
+        }
+    }
+    carried
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::types::ConversationMessage;
+    use crate::registry::types::RoutingConfig;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn previous_call() -> PriorToolCall {
+        PriorToolCall {
+            tool_name: "github_list_prs".to_string(),
+            arguments: HashMap::from([
+                ("repo".to_string(), json!("A")),
+                ("state".to_string(), json!("open")),
+                ("limit".to_string(), json!(50)),
+            ]),
+            request: Some("list open PRs in repo A".to_string()),
+        }
+    }
+
+    fn tool() -> ToolDefinition {
+        ToolDefinition::new_with_fields(
+            "github_list_prs".to_string(),
+            "List pull requests of a repository".to_string(),
+            json!({
+                "type": "object",
+                "properties": {"repo": {"type": "string"}, "state": {"type": "string"}, "limit": {"type": "integer", "default": 10}},
+                "required": ["repo", "state"]
+            }),
+            RoutingConfig::new("subprocess".to_string(), json!({"command": "echo"})),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_is_follow_up() {
+        assert!(is_follow_up("now do the same for repo B"));
+        assert!(is_follow_up("What about staging?"));
+        assert!(is_follow_up("run it again"));
+        assert!(!is_follow_up("list open PRs in repo A"));
+        assert!(!is_follow_up("ping google.com"));
+    }
+
+    #[test]
+    fn test_conversation_summary() {
+        let conversation = ConversationContext {
+            messages: vec![ConversationMessage { role: "user".to_string(), content: "list open PRs in repo A".to_string() }],
+            tool_calls: vec![PriorToolCall { arguments: HashMap::from([("repo".to_string(), json!("A"))]), ..previous_call() }],
+        };
+        assert_eq!(
+            conversation_summary(&conversation),
+            "Recent conversation:\n- user: list open PRs in repo A\nEarlier tool calls:\n- github_list_prs {\"repo\":\"A\"} (for \"list open PRs in repo A\")"
+        );
+        assert!(conversation_summary(&ConversationContext::default()).is_empty());
+    }
+
+    #[test]
+    fn test_carry_over_arguments() {
+        let mut extraction = ParameterExtraction {
+            parameters: HashMap::from([("repo".to_string(), json!("B")), ("limit".to_string(), json!(10))]),
+            status: ExtractionStatus::Incomplete,
+            warnings: Vec::new(),
+            used_defaults: HashMap::from([("limit".to_string(), json!(10))]),
+        };
+        let carried = carry_over_arguments(&mut extraction, &previous_call(), &tool());
+
+        assert_eq!(carried, vec!["limit", "state"]);
+        assert_eq!(extraction.parameters["repo"], json!("B"));
+        assert_eq!(extraction.parameters["state"], json!("open"));
+        assert_eq!(extraction.parameters["limit"], json!(50));
+        assert!(extraction.used_defaults.is_empty());
+        assert!(matches!(extraction.status, ExtractionStatus::Success));
+
+        let mut failed = ParameterExtraction {
+            parameters: HashMap::new(),
+            status: ExtractionStatus::Failed,
+            warnings: Vec::new(),
+            used_defaults: HashMap::new(),
+        };
+        assert!(carry_over_arguments(&mut failed, &previous_call(), &tool()).is_empty());
+    }
+}
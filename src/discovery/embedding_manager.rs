@@ -3,16 +3,18 @@
 //! This module handles dynamic embedding lifecycle management including:
 //! - Adding/removing embeddings when tools are enabled/disabled
 //! - Detecting capability changes and updating embeddings
+//! - Re-embedding just the tools a registry hot-reload changed
 //! - Merging new dynamic embeddings into persistent storage
 //! - Preventing overwrites of user-configured settings
 
 use crate::discovery::semantic::{SemanticSearchService, ToolMetadata};
 use crate::error::{ProxyError, Result};
+use crate::registry::types::ToolDefinition;
 use crate::registry::service::RegistryService;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::{RwLock, mpsc};
+use tokio::sync::{RwLock, broadcast, mpsc};
 use tokio::time::{interval, Duration};
 use tracing::{debug, error, info, warn};
 use notify::{Watcher, RecursiveMode, Event, EventKind};
@@ -78,7 +80,8 @@ pub struct EmbeddingManagerConfig {
     pub background_monitoring: bool,
     /// Whether to preserve user disabled settings during external MCP updates
     pub preserve_user_settings: bool,
-    /// Whether to enable file watching for hot-reload
+    /// Whether to enable file watching for hot-reload, and to re-embed the
+    /// tools registry hot-reloads change as soon as they happen
     pub enable_hot_reload: bool,
 }
 
@@ -115,6 +118,9 @@ pub struct EmbeddingManager {
     /// Background task handle
     background_task_handle: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
     
+    /// Handle of the task following registry changes
+    registry_task_handle: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
+    
     /// File watcher handle for hot-reload
     _file_watcher: Arc<RwLock<Option<Box<dyn Watcher + Send + Sync>>>>,
 }
@@ -133,6 +139,7 @@ impl EmbeddingManager {
             last_known_state: Arc::new(RwLock::new(HashMap::new())),
             user_disabled_tools: Arc::new(RwLock::new(HashSet::new())),
             background_task_handle: Arc::new(RwLock::new(None)),
+            registry_task_handle: Arc::new(RwLock::new(None)),
             _file_watcher: Arc::new(RwLock::new(None)),
         }
     }
@@ -157,6 +164,7 @@ impl EmbeddingManager {
                 warn!("Failed to start file watching for embeddings hot-reload: {}", e);
                 info!("Falling back to timer-based monitoring only");
             }
+            self.start_registry_watching().await;
         }
         
         Ok(())
@@ -185,6 +193,7 @@ impl EmbeddingManager {
                     last_known_state: Arc::clone(&last_known_state),
                     user_disabled_tools: Arc::clone(&user_disabled_tools),
                     background_task_handle: Arc::new(RwLock::new(None)), // Avoid circular reference
+                    registry_task_handle: Arc::new(RwLock::new(None)),
                     _file_watcher: Arc::new(RwLock::new(None)),
                 };
                 
@@ -208,6 +217,58 @@ impl EmbeddingManager {
         info!("Started background embedding monitoring (interval: {}s)", self.config.check_interval_seconds);
     }
     
+    /// Re-embed the tools registry reloads change, as they happen
+    ///
+    /// Editing one capability file re-embeds the tools it defines instead of
+    /// waiting for the next background check or a full sync.
+    async fn start_registry_watching(&self) {
+        let mut changes = self.registry.subscribe_changes();
+        let manager = EmbeddingManager {
+            registry: Arc::clone(&self.registry),
+            semantic_search: Arc::clone(&self.semantic_search),
+            config: self.config.clone(),
+            last_known_state: Arc::clone(&self.last_known_state),
+            user_disabled_tools: Arc::clone(&self.user_disabled_tools),
+            background_task_handle: Arc::new(RwLock::new(None)),
+            registry_task_handle: Arc::new(RwLock::new(None)), // Avoid circular reference
+            _file_watcher: Arc::new(RwLock::new(None)),
+        };
+        
+        let handle = tokio::spawn(async move {
+            loop {
+                let result = match changes.recv().await {
+                    Ok(change) => {
+                        let tool_names: Vec<String> = change.changed.into_iter().chain(change.removed).collect();
+                        debug!("Registry changed {} tools, updating their embeddings", tool_names.len());
+                        manager.sync_tools(&tool_names).await
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Missed {} registry changes, syncing all embeddings", skipped);
+                        manager.sync_embeddings().await
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                
+                match result {
+                    Ok(summary) => {
+                        if summary.created + summary.updated + summary.removed > 0 {
+                            info!("Registry change embedding sync: {} created, {} updated, {} removed",
+                                  summary.created, summary.updated, summary.removed);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Registry change embedding sync failed: {}", e);
+                    }
+                }
+            }
+        });
+        
+        let mut task_handle = self.registry_task_handle.write().await;
+        *task_handle = Some(handle);
+        
+        info!("Following registry changes for incremental embedding updates");
+    }
+    
     /// Start file watching for embeddings hot-reload
     async fn start_file_watching(&self) -> Result<()> {
         info!("Starting file watching for embeddings hot-reload");
@@ -294,6 +355,7 @@ impl EmbeddingManager {
                             last_known_state: Arc::clone(&last_known_state),
                             user_disabled_tools: Arc::clone(&user_disabled_tools),
                             background_task_handle: Arc::new(RwLock::new(None)),
+                            registry_task_handle: Arc::new(RwLock::new(None)),
                             _file_watcher: Arc::new(RwLock::new(None)),
                         };
                         
@@ -317,11 +379,25 @@ impl EmbeddingManager {
     
     /// Synchronize embeddings with current tool state
     pub async fn sync_embeddings(&self) -> Result<EmbeddingChangeSummary> {
-        let start_time = SystemTime::now();
         info!("Starting embedding synchronization");
+        self.sync(None).await
+    }
+    
+    /// Synchronize the embeddings of `tool_names` only, leaving other tools alone
+    pub async fn sync_tools(&self, tool_names: &[String]) -> Result<EmbeddingChangeSummary> {
+        debug!("Starting embedding synchronization of {} tools", tool_names.len());
+        let scope: HashSet<String> = tool_names.iter().cloned().collect();
+        self.sync(Some(&scope)).await
+    }
+    
+    /// Synchronize the embeddings of the tools in `scope`, or of all tools
+    async fn sync(&self, scope: Option<&HashSet<String>>) -> Result<EmbeddingChangeSummary> {
+        let start_time = SystemTime::now();
+        let in_scope = |tool_name: &String| scope.map_or(true, |scope| scope.contains(tool_name));
         
         // Get current tool state from registry
-        let current_tools = self.get_current_tool_state().await;
+        let mut current_tools = self.get_current_tool_state().await;
+        current_tools.retain(|tool_name, _| in_scope(tool_name));
         let mut last_state = self.last_known_state.write().await;
         
         // Detect changes
@@ -332,6 +408,7 @@ impl EmbeddingManager {
         let mut failed = 0;
         
         // Check for new or updated tools
+        let mut pending = Vec::new();
         for (tool_name, (current_hash, enabled, hidden)) in &current_tools {
             let operation_status = if let Some((last_hash, last_enabled, last_hidden)) = last_state.get(tool_name) {
                 // Tool exists, check for changes
@@ -349,38 +426,46 @@ impl EmbeddingManager {
             };
             
             if operation_status != EmbeddingStatus::UpToDate {
-                let result = self.handle_tool_embedding(
-                    tool_name,
-                    operation_status.clone(),
-                    *enabled,
-                    *hidden,
-                ).await;
-                
-                let success = result.is_ok();
-                if !success {
-                    failed += 1;
-                } else {
-                    match operation_status {
-                        EmbeddingStatus::NeedsCreation => created += 1,
-                        EmbeddingStatus::NeedsUpdate => updated += 1,
-                        _ => {}
-                    }
+                pending.push((tool_name, operation_status, *enabled, *hidden));
+            }
+        }
+        
+        // Embed all new and changed tools together so remote providers can batch them
+        let mut embeddings = self.generate_pending_embeddings(&pending).await;
+        
+        for (tool_name, operation_status, enabled, hidden) in pending {
+            let result = self.handle_tool_embedding(
+                tool_name,
+                operation_status.clone(),
+                enabled,
+                hidden,
+                embeddings.remove(tool_name),
+            ).await;
+            
+            let success = result.is_ok();
+            if !success {
+                failed += 1;
+            } else {
+                match operation_status {
+                    EmbeddingStatus::NeedsCreation => created += 1,
+                    EmbeddingStatus::NeedsUpdate => updated += 1,
+                    _ => {}
                 }
-                
-                operations.push(EmbeddingOperation {
-                    tool_name: tool_name.clone(),
-                    status: operation_status,
-                    reason: self.get_operation_reason(tool_name, &last_state, &current_tools),
-                    success,
-                    error: result.err().map(|e| e.to_string()),
-                });
             }
+            
+            operations.push(EmbeddingOperation {
+                tool_name: tool_name.clone(),
+                status: operation_status,
+                reason: self.get_operation_reason(tool_name, &last_state, &current_tools),
+                success,
+                error: result.err().map(|e| e.to_string()),
+            });
         }
         
         // Check for removed tools
         let current_tool_names: HashSet<_> = current_tools.keys().collect();
         let removed_tools: Vec<_> = last_state.keys()
-            .filter(|name| !current_tool_names.contains(name))
+            .filter(|name| in_scope(*name) && !current_tool_names.contains(name))
             .cloned()
             .collect();
         
@@ -389,6 +474,7 @@ impl EmbeddingManager {
             let success = result.is_ok();
             
             if success {
+                last_state.remove(&tool_name);
                 removed += 1;
             } else {
                 failed += 1;
@@ -404,7 +490,11 @@ impl EmbeddingManager {
         }
         
         // Update last known state
-        *last_state = current_tools;
+        if scope.is_some() {
+            last_state.extend(current_tools);
+        } else {
+            *last_state = current_tools;
+        }
         drop(last_state);
         
         // Auto-save if configured
@@ -442,8 +532,8 @@ impl EmbeddingManager {
         let enabled_tools = self.registry.get_enabled_tools();
         
         for (tool_name, tool_def) in enabled_tools {
-            // Skip smart_discovery_tool itself to avoid recursion
-            if tool_name == "smart_discovery_tool" || tool_name == "smart_tool_discovery" {
+            // Skip smart_discovery_tool itself to avoid recursion, and its feedback tool
+            if tool_name == "smart_discovery_tool" || tool_name == "smart_tool_discovery" || tool_name == crate::discovery::feedback::FEEDBACK_TOOL_NAME || tool_name == crate::discovery::service::PLAN_TOOL_NAME || tool_name == crate::discovery::categories::CATEGORIES_TOOL_NAME {
                 continue;
             }
             
@@ -454,6 +544,55 @@ impl EmbeddingManager {
         tool_state
     }
     
+    /// Text embedded for a tool
+    fn embedding_text(tool_def: &ToolDefinition) -> String {
+        format!("{}: {}", tool_def.name, tool_def.description)
+    }
+    
+    /// Embeddings for the tools in `pending`: reused from the shared vector
+    /// store when another node already embedded the same content, otherwise
+    /// generated in one batch. Tools missing from the result (e.g. because
+    /// the batch failed) are embedded one at a time.
+    async fn generate_pending_embeddings(
+        &self,
+        pending: &[(&String, EmbeddingStatus, bool, bool)],
+    ) -> HashMap<String, Vec<f32>> {
+        let mut tools: Vec<(String, ToolDefinition)> = pending.iter()
+            .filter_map(|(name, ..)| self.registry.get_tool(name).map(|tool_def| ((*name).clone(), tool_def)))
+            .collect();
+        let mut embeddings = HashMap::new();
+        
+        if self.semantic_search.has_vector_store() && !tools.is_empty() {
+            let names: Vec<String> = tools.iter().map(|(name, _)| name.clone()).collect();
+            match self.semantic_search.shared_embeddings(&names).await {
+                Ok(points) => {
+                    let hashes: HashMap<&String, String> = tools.iter()
+                        .map(|(name, tool_def)| (name, self.semantic_search.generate_content_hash(tool_def)))
+                        .collect();
+                    for point in points {
+                        if hashes.get(&point.metadata.name) == Some(&point.metadata.content_hash) {
+                            embeddings.insert(point.metadata.name, point.embedding);
+                        }
+                    }
+                }
+                Err(e) => warn!("Failed to read shared embeddings, generating them locally: {}", e),
+            }
+            tools.retain(|(name, _)| !embeddings.contains_key(name));
+            debug!("Reused {} shared embeddings", embeddings.len());
+        }
+        
+        if tools.len() < 2 || !self.semantic_search.has_provider() {
+            return embeddings;
+        }
+        
+        let texts: Vec<String> = tools.iter().map(|(_, tool_def)| Self::embedding_text(tool_def)).collect();
+        match self.semantic_search.generate_embeddings(&texts).await {
+            Ok(generated) => embeddings.extend(tools.into_iter().map(|(name, _)| name).zip(generated)),
+            Err(e) => warn!("Batch embedding of {} tools failed, embedding them individually: {}", tools.len(), e),
+        }
+        embeddings
+    }
+    
     /// Handle embedding operation for a specific tool
     async fn handle_tool_embedding(
         &self,
@@ -461,6 +600,7 @@ impl EmbeddingManager {
         status: EmbeddingStatus,
         enabled: bool,
         hidden: bool,
+        embedding: Option<Vec<f32>>,
     ) -> Result<()> {
         // Get the tool definition
         let tool_def = self.registry.get_tool(tool_name)
@@ -475,11 +615,11 @@ impl EmbeddingManager {
             }
         }
         
-        // Create the text to embed (combine name and description)
-        let embedding_text = format!("{}: {}", tool_def.name, tool_def.description);
-        
-        // Generate embedding
-        let embedding = self.semantic_search.generate_embedding(&embedding_text).await?;
+        // Generate embedding unless it was batched
+        let embedding = match embedding {
+            Some(embedding) => embedding,
+            None => self.semantic_search.generate_embedding(&Self::embedding_text(&tool_def)).await?,
+        };
         
         // Create metadata
         let metadata = ToolMetadata {
@@ -493,11 +633,12 @@ impl EmbeddingManager {
                 .unwrap_or_default()
                 .as_secs(),
             embedding_dims: embedding.len(),
+            tags: tool_def.tags.clone(),
+            server: ToolMetadata::server_of(&tool_def),
         };
         
         // Store the embedding
-        let mut storage = self.semantic_search.storage.write().await;
-        storage.add_tool_embedding(tool_name.to_string(), embedding, metadata);
+        self.semantic_search.index_tool_embedding(embedding, metadata).await?;
         
         debug!("Handled embedding for tool '{}' with status: {:?}", tool_name, status);
         Ok(())
@@ -505,8 +646,7 @@ impl EmbeddingManager {
     
     /// Remove tool embedding
     async fn remove_tool_embedding(&self, tool_name: &str) -> Result<()> {
-        let mut storage = self.semantic_search.storage.write().await;
-        storage.remove_tool_embedding(tool_name);
+        self.semantic_search.remove_tool_embedding(tool_name).await?;
         debug!("Removed embedding for tool: {}", tool_name);
         Ok(())
     }
@@ -605,6 +745,11 @@ impl EmbeddingManager {
             info!("Stopped background embedding monitoring");
         }
         
+        let mut registry_task_handle = self.registry_task_handle.write().await;
+        if let Some(handle) = registry_task_handle.take() {
+            handle.abort();
+        }
+        
         // Save embeddings if needed
         if self.config.auto_save {
             self.semantic_search.save_embeddings().await?;
@@ -0,0 +1,371 @@
+//! Remote embedding providers
+//!
+//! Semantic search can embed tool descriptions and queries with a hosted
+//! embedding API instead of a `model_name` prefix:
+//!
+//! ```yaml
+//! smart_discovery:
+//!   semantic_search:
+//!     provider:
+//!       type: voyage            # openai, cohere or voyage
+//!       model: voyage-3-lite
+//!       api_key_env: VOYAGE_API_KEY
+//!       batch_size: 64
+//! ```
+//!
+//! Texts are sent in batches of `batch_size`, and rate-limited requests
+//! (HTTP 429) are retried after the provider's `Retry-After`, or with
+//! exponential backoff when it doesn't send one.
+
+use crate::error::{ProxyError, Result};
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Hosted embedding APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingProviderType {
+    OpenAi,
+    Cohere,
+    Voyage,
+}
+
+impl EmbeddingProviderType {
+    fn default_base_url(self) -> &'static str {
+        match self {
+            Self::OpenAi => "https://api.openai.com/v1",
+            Self::Cohere => "https://api.cohere.com/v2",
+            Self::Voyage => "https://api.voyageai.com/v1",
+        }
+    }
+
+    fn default_api_key_env(self) -> &'static str {
+        match self {
+            Self::OpenAi => "OPENAI_API_KEY",
+            Self::Cohere => "COHERE_API_KEY",
+            Self::Voyage => "VOYAGE_API_KEY",
+        }
+    }
+
+    /// Largest batch the API accepts in one request
+    fn max_batch_size(self) -> usize {
+        match self {
+            Self::OpenAi => 2048,
+            Self::Cohere => 96,
+            Self::Voyage => 128,
+        }
+    }
+}
+
+/// `smart_discovery.semantic_search.provider`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingProviderConfig {
+    /// Which API to call
+    pub r#type: EmbeddingProviderType,
+
+    /// Embedding model, e.g. `text-embedding-3-small`, `embed-english-v3.0`, `voyage-3`
+    pub model: String,
+
+    /// API key; read from `api_key_env` when unset
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+
+    /// Environment variable holding the API key (default: OPENAI_API_KEY, COHERE_API_KEY or VOYAGE_API_KEY)
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+
+    /// Override the API base URL, e.g. for a proxy or Azure-compatible endpoint
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// Texts per request, capped at the provider's limit
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+
+    /// Times to retry a rate-limited request before giving up
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Request timeout in seconds
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+
+    /// Embedding dimensions the model produces. Known models don't need
+    /// this; for others it lets stale stored embeddings be detected up front.
+    #[serde(default)]
+    pub dimensions: Option<usize>,
+}
+
+fn default_batch_size() -> usize {
+    64
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+impl EmbeddingProviderConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.model.trim().is_empty() {
+            return Err(ProxyError::config("Embedding provider model cannot be empty"));
+        }
+        if self.batch_size == 0 {
+            return Err(ProxyError::config("Embedding provider batch_size must be greater than 0"));
+        }
+        if self.timeout == 0 {
+            return Err(ProxyError::config("Embedding provider timeout must be greater than 0"));
+        }
+        if self.dimensions == Some(0) {
+            return Err(ProxyError::config("Embedding provider dimensions must be greater than 0"));
+        }
+        Ok(())
+    }
+
+    /// Dimensions of the configured model, if known
+    pub fn expected_dimensions(&self) -> Option<usize> {
+        self.dimensions.or_else(|| known_dimensions(self.r#type, &self.model))
+    }
+
+    fn api_key(&self) -> Result<String> {
+        if let Some(api_key) = &self.api_key {
+            return Ok(api_key.clone());
+        }
+        let env = self.api_key_env.as_deref().unwrap_or(self.r#type.default_api_key_env());
+        std::env::var(env).map_err(|_| ProxyError::config(format!(
+            "{} environment variable not set for the {:?} embedding provider", env, self.r#type
+        )))
+    }
+}
+
+fn known_dimensions(provider: EmbeddingProviderType, model: &str) -> Option<usize> {
+    use EmbeddingProviderType::*;
+    match (provider, model) {
+        (OpenAi, "text-embedding-3-small") | (OpenAi, "text-embedding-ada-002") => Some(1536),
+        (OpenAi, "text-embedding-3-large") => Some(3072),
+        (Cohere, "embed-english-v3.0") | (Cohere, "embed-multilingual-v3.0") => Some(1024),
+        (Cohere, "embed-english-light-v3.0") | (Cohere, "embed-multilingual-light-v3.0") => Some(384),
+        (Voyage, "voyage-3") | (Voyage, "voyage-3.5") | (Voyage, "voyage-code-3") => Some(1024),
+        (Voyage, "voyage-3-lite") => Some(512),
+        (Voyage, "voyage-3-large") => Some(1024),
+        _ => None,
+    }
+}
+
+/// What the embedded text will be used for. Cohere and Voyage embed
+/// documents and queries differently; OpenAI ignores it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingInput {
+    /// Tool descriptions stored for search
+    Document,
+    /// Search queries
+    Query,
+}
+
+/// A service that turns text into embedding vectors
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Provider and model, for logs and stats
+    fn name(&self) -> String;
+
+    /// Dimensions of the embeddings this provider returns, if known
+    fn dimensions(&self) -> Option<usize>;
+
+    /// Largest number of texts sent in one [`embed_batch`](Self::embed_batch) call
+    fn batch_size(&self) -> usize;
+
+    /// Embed one batch of texts, returning one embedding per text in order
+    async fn embed_batch(&self, texts: &[String], input: EmbeddingInput) -> Result<Vec<Vec<f32>>>;
+
+    /// Embed any number of texts, split into batches
+    async fn embed(&self, texts: &[String], input: EmbeddingInput) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.batch_size().max(1)) {
+            let batch_embeddings = self.embed_batch(batch, input).await?;
+            if batch_embeddings.len() != batch.len() {
+                return Err(ProxyError::connection(format!(
+                    "{} returned {} embeddings for {} texts", self.name(), batch_embeddings.len(), batch.len()
+                )));
+            }
+            embeddings.extend(batch_embeddings);
+        }
+        Ok(embeddings)
+    }
+}
+
+/// Create the provider described by `config`
+pub fn build_provider(config: &EmbeddingProviderConfig) -> Result<Arc<dyn EmbeddingProvider>> {
+    config.validate()?;
+    Ok(Arc::new(RemoteEmbeddingProvider {
+        config: config.clone(),
+        api_key: config.api_key()?,
+        client: Client::builder()
+            .timeout(Duration::from_secs(config.timeout))
+            .build()
+            .map_err(|e| ProxyError::config(format!("Failed to create embedding HTTP client: {}", e)))?,
+    }))
+}
+
+/// OpenAI, Cohere and Voyage differ only in request and response shape
+struct RemoteEmbeddingProvider {
+    config: EmbeddingProviderConfig,
+    api_key: String,
+    client: Client,
+}
+
+impl RemoteEmbeddingProvider {
+    fn base_url(&self) -> &str {
+        self.config.base_url.as_deref().unwrap_or(self.config.r#type.default_base_url()).trim_end_matches('/')
+    }
+
+    fn request(&self, texts: &[String], input: EmbeddingInput) -> RequestBuilder {
+        let (path, body) = match self.config.r#type {
+            EmbeddingProviderType::OpenAi => ("embeddings", json!({
+                "model": self.config.model,
+                "input": texts,
+                "encoding_format": "float",
+            })),
+            EmbeddingProviderType::Cohere => ("embed", json!({
+                "model": self.config.model,
+                "texts": texts,
+                "input_type": match input {
+                    EmbeddingInput::Document => "search_document",
+                    EmbeddingInput::Query => "search_query",
+                },
+                "embedding_types": ["float"],
+            })),
+            EmbeddingProviderType::Voyage => ("embeddings", json!({
+                "model": self.config.model,
+                "input": texts,
+                "input_type": match input {
+                    EmbeddingInput::Document => "document",
+                    EmbeddingInput::Query => "query",
+                },
+            })),
+        };
+        self.client
+            .post(format!("{}/{}", self.base_url(), path))
+            .bearer_auth(&self.api_key)
+            .json(&body)
+    }
+
+    fn parse(&self, response: &Value) -> Result<Vec<Vec<f32>>> {
+        let invalid = || ProxyError::connection(format!("Invalid {} embedding response format", self.name()));
+        let to_vector = |embedding: &Value| -> Result<Vec<f32>> {
+            embedding.as_array()
+                .ok_or_else(invalid)
+                .map(|values| values.iter().map(|v| v.as_f64().unwrap_or(0.0) as f32).collect())
+        };
+
+        match self.config.r#type {
+            EmbeddingProviderType::Cohere => response["embeddings"]["float"].as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(to_vector)
+                .collect(),
+            // OpenAI and Voyage tag each embedding with the index of its input
+            EmbeddingProviderType::OpenAi | EmbeddingProviderType::Voyage => {
+                let mut data = response["data"].as_array().ok_or_else(invalid)?.iter().collect::<Vec<_>>();
+                data.sort_by_key(|item| item["index"].as_u64().unwrap_or(0));
+                data.into_iter().map(|item| to_vector(&item["embedding"])).collect()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for RemoteEmbeddingProvider {
+    fn name(&self) -> String {
+        format!("{:?}:{}", self.config.r#type, self.config.model).to_lowercase()
+    }
+
+    fn dimensions(&self) -> Option<usize> {
+        self.config.expected_dimensions()
+    }
+
+    fn batch_size(&self) -> usize {
+        self.config.batch_size.min(self.config.r#type.max_batch_size())
+    }
+
+    async fn embed_batch(&self, texts: &[String], input: EmbeddingInput) -> Result<Vec<Vec<f32>>> {
+        let mut attempt = 0;
+        loop {
+            let response = self.request(texts, input).send().await
+                .map_err(|e| ProxyError::connection(format!("{} embedding request failed: {}", self.name(), e)))?;
+
+            if response.status() == StatusCode::TOO_MANY_REQUESTS && attempt < self.config.max_retries {
+                let delay = retry_after(&response).unwrap_or_else(|| Duration::from_millis(500 * 2u64.pow(attempt)));
+                warn!("{} embedding request rate limited, retrying in {:?}", self.name(), delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response.text().await.unwrap_or_default();
+                return Err(ProxyError::connection(format!("{} embedding API error ({}): {}", self.name(), status, error_text)));
+            }
+
+            let json: Value = response.json().await
+                .map_err(|e| ProxyError::connection(format!("Failed to parse {} embedding response: {}", self.name(), e)))?;
+            let embeddings = self.parse(&json)?;
+            debug!("Embedded {} texts with {}", texts.len(), self.name());
+            return Ok(embeddings);
+        }
+    }
+}
+
+/// Delay asked for by a rate-limited response's `Retry-After` header, in seconds
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    response.headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str().ok()?
+        .trim()
+        .parse::<f64>().ok()
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(r#type: EmbeddingProviderType, model: &str) -> EmbeddingProviderConfig {
+        serde_json::from_value(json!({"type": r#type, "model": model, "api_key": "test"})).unwrap()
+    }
+
+    #[test]
+    fn test_config_defaults_and_validation() {
+        let config = config(EmbeddingProviderType::Voyage, "voyage-3-lite");
+        assert_eq!(config.batch_size, 64);
+        assert_eq!(config.max_retries, 3);
+        assert_eq!(config.expected_dimensions(), Some(512));
+        assert!(config.validate().is_ok());
+
+        let mut custom = config.clone();
+        custom.model = "my-finetune".to_string();
+        assert_eq!(custom.expected_dimensions(), None);
+        custom.dimensions = Some(768);
+        assert_eq!(custom.expected_dimensions(), Some(768));
+
+        let mut invalid = config;
+        invalid.batch_size = 0;
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_batch_size_capped_at_provider_limit() {
+        let mut config = config(EmbeddingProviderType::Cohere, "embed-english-v3.0");
+        config.batch_size = 500;
+        assert_eq!(build_provider(&config).unwrap().batch_size(), 96);
+    }
+}
@@ -0,0 +1,258 @@
+//! Exclusions in discovery requests
+//!
+//! Requests can rule tools out, either in words ("list the pods, read-only",
+//! "free up disk space, nothing destructive", "search the issues, exclude
+//! server jira") or with the `exclusions` field. Unlike the rest of a
+//! request, exclusions aren't weighed against how well tools match: the tools
+//! they rule out are dropped before ranking, going by the tools' MCP
+//! annotations (`readOnlyHint`, `destructiveHint`), the external MCP server
+//! they are proxied from and their tags.
+//!
+//! ```json
+//! {
+//!   "request": "free up disk space",
+//!   "exclusions": {"exclude_destructive": true, "exclude_servers": ["legacy-ops"]}
+//! }
+//! ```
+
+use crate::discovery::semantic::ToolMetadata;
+use crate::discovery::types::SmartDiscoveryRequest;
+use crate::registry::types::ToolDefinition;
+use crate::registry::versioning::split_versioned_name;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Tools a discovery request rules out
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscoveryExclusions {
+    /// Only tools annotated `readOnlyHint`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+
+    /// No tools annotated `destructiveHint`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub exclude_destructive: bool,
+
+    /// No tools proxied from these external MCP servers
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude_servers: Vec<String>,
+
+    /// No tools carrying any of these tags
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude_tags: Vec<String>,
+
+    /// No tools with these names; a bare name covers every version of a versioned tool
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude_tools: Vec<String>,
+}
+
+impl DiscoveryExclusions {
+    /// Whether nothing is ruled out
+    pub fn is_empty(&self) -> bool {
+        !self.read_only
+            && !self.exclude_destructive
+            && self.exclude_servers.is_empty()
+            && self.exclude_tags.is_empty()
+            && self.exclude_tools.is_empty()
+    }
+
+    /// Exclusions stated in `request`'s text together with those in its `exclusions` field
+    pub fn for_request(request: &SmartDiscoveryRequest) -> Self {
+        let mut exclusions = parse_exclusions(&request.request);
+        if let Some(explicit) = &request.exclusions {
+            exclusions.read_only |= explicit.read_only;
+            exclusions.exclude_destructive |= explicit.exclude_destructive;
+            exclusions.exclude_servers.extend(explicit.exclude_servers.iter().cloned());
+            exclusions.exclude_tags.extend(explicit.exclude_tags.iter().cloned());
+            exclusions.exclude_tools.extend(explicit.exclude_tools.iter().cloned());
+        }
+        // Sorted and deduplicated, so equal exclusions make equal cache keys
+        for list in [&mut exclusions.exclude_servers, &mut exclusions.exclude_tags, &mut exclusions.exclude_tools] {
+            list.sort();
+            list.dedup();
+        }
+        exclusions
+    }
+
+    /// Why `tool` is ruled out, or `None` when it may be used
+    pub fn excludes(&self, name: &str, tool: &ToolDefinition) -> Option<String> {
+        if self.read_only && !tool.is_read_only() {
+            return Some("not read-only".to_string());
+        }
+        if self.exclude_destructive && tool.is_destructive() {
+            return Some("destructive".to_string());
+        }
+        if let Some(server) = ToolMetadata::server_of(tool)
+            .filter(|server| self.exclude_servers.iter().any(|excluded| excluded.eq_ignore_ascii_case(server)))
+        {
+            return Some(format!("from server {}", server));
+        }
+        if let Some(tag) = tool.tags.iter().find(|tag| self.exclude_tags.iter().any(|excluded| excluded.eq_ignore_ascii_case(tag))) {
+            return Some(format!("tagged {}", tag));
+        }
+        let (base_name, _) = split_versioned_name(name);
+        if self.exclude_tools.iter().any(|excluded| excluded == name || excluded == base_name) {
+            return Some("excluded by name".to_string());
+        }
+        None
+    }
+
+    /// The exclusions in words, for logs and reasoning
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.read_only {
+            parts.push("read-only".to_string());
+        }
+        if self.exclude_destructive {
+            parts.push("not destructive".to_string());
+        }
+        for (label, values) in [("servers", &self.exclude_servers), ("tags", &self.exclude_tags), ("tools", &self.exclude_tools)] {
+            if !values.is_empty() {
+                parts.push(format!("not {} {}", label, values.join(", ")));
+            }
+        }
+        parts.join("; ")
+    }
+}
+
+/// Exclusions stated in words in `request`
+pub fn parse_exclusions(request: &str) -> DiscoveryExclusions {
+    let request = request.to_lowercase();
+    let captured = |pattern: &Regex| -> Vec<String> {
+        pattern.captures_iter(&request)
+            .filter_map(|captures| captures.iter().skip(1).flatten().next())
+            .map(|value| value.as_str().trim_end_matches('.').to_string())
+            .collect()
+    };
+
+    let patterns = patterns();
+    DiscoveryExclusions {
+        read_only: patterns.read_only.is_match(&request),
+        exclude_destructive: patterns.not_destructive.is_match(&request),
+        exclude_servers: captured(&patterns.server),
+        exclude_tags: captured(&patterns.tag),
+        exclude_tools: captured(&patterns.tool),
+    }
+}
+
+struct Patterns {
+    read_only: Regex,
+    not_destructive: Regex,
+    server: Regex,
+    tag: Regex,
+    tool: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let exclude = r"(?:exclude|excluding|except|without|not from|not on|avoid|avoiding|skip|skipping)";
+        Patterns {
+            read_only: Regex::new(r"\b(?:read[- ]?only|without (?:making )?(?:any )?changes|(?:don't|do not) (?:change|modify) anything)\b")
+                .expect("valid read-only pattern"),
+            not_destructive: Regex::new(r"\b(?:non[- ]?destructive|(?:not|nothing|no|without|avoid) destructive|(?:don't|do not) (?:delete|destroy) anything)\b")
+                .expect("valid destructive pattern"),
+            server: Regex::new(&format!(r"\b{} (?:the )?(?:(?:mcp )?server ([\w.-]+)|([\w.-]+) (?:mcp )?server)\b", exclude))
+                .expect("valid server pattern"),
+            tag: Regex::new(&format!(r"\b(?:{} (?:tools )?tag(?:ged)? ([\w:.-]+)|not tagged ([\w:.-]+))", exclude))
+                .expect("valid tag pattern"),
+            tool: Regex::new(r"\b(?:exclude|excluding|except|without using|not using|(?:don't|do not) use|avoid|skip) (?:the )?(?:tool ([\w.@-]+)|([\w.@-]+) tool)\b")
+                .expect("valid tool pattern"),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::types::RoutingConfig;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn tool(name: &str, annotations: &[(&str, &str)], server: Option<&str>, tags: &[&str]) -> ToolDefinition {
+        let annotations = (!annotations.is_empty())
+            .then(|| annotations.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>());
+        let routing = match server {
+            Some(server) => RoutingConfig::new("external_mcp".to_string(), json!({"server_name": server})),
+            None => RoutingConfig::new("subprocess".to_string(), json!({"command": "echo"})),
+        };
+        let mut tool = ToolDefinition::new_with_fields(
+            name.to_string(),
+            format!("The {} tool", name),
+            json!({"type": "object"}),
+            routing,
+            annotations,
+        )
+        .unwrap();
+        tool.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        tool
+    }
+
+    #[test]
+    fn test_parse_exclusions() {
+        let exclusions = parse_exclusions("List the pods, read-only please, and nothing destructive");
+        assert!(exclusions.read_only);
+        assert!(exclusions.exclude_destructive);
+
+        let exclusions = parse_exclusions("search open issues, exclude server jira and skip the github server");
+        assert_eq!(exclusions.exclude_servers, vec!["jira", "github"]);
+        assert!(!exclusions.read_only);
+
+        let exclusions = parse_exclusions("convert the file without tag experimental, don't use the pandoc tool");
+        assert_eq!(exclusions.exclude_tags, vec!["experimental"]);
+        assert_eq!(exclusions.exclude_tools, vec!["pandoc"]);
+
+        assert!(parse_exclusions("delete the temporary files").is_empty());
+    }
+
+    #[test]
+    fn test_for_request_merges_explicit_exclusions() {
+        let request = SmartDiscoveryRequest {
+            request: "search issues, exclude server jira".to_string(),
+            context: None,
+            preferred_tools: None,
+            confidence_threshold: None,
+            include_error_details: None,
+            sequential_mode: None,
+            conversation: None,
+            exclusions: Some(DiscoveryExclusions {
+                exclude_servers: vec!["linear".to_string(), "jira".to_string()],
+                exclude_destructive: true,
+                ..Default::default()
+            }),
+        };
+        let exclusions = DiscoveryExclusions::for_request(&request);
+        assert_eq!(exclusions.exclude_servers, vec!["jira", "linear"]);
+        assert!(exclusions.exclude_destructive);
+        assert_eq!(exclusions.describe(), "not destructive; not servers jira, linear");
+    }
+
+    #[test]
+    fn test_excludes() {
+        let exclusions = DiscoveryExclusions {
+            read_only: true,
+            exclude_servers: vec!["Jira".to_string()],
+            exclude_tags: vec!["experimental".to_string()],
+            exclude_tools: vec!["search_code".to_string()],
+            ..Default::default()
+        };
+        let read_only = [("readOnlyHint", "true")];
+
+        let allowed = tool("github_search", &read_only, Some("github"), &["git"]);
+        assert_eq!(exclusions.excludes("github_search", &allowed), None);
+
+        let writer = tool("github_create_issue", &[], Some("github"), &[]);
+        assert_eq!(exclusions.excludes("github_create_issue", &writer).as_deref(), Some("not read-only"));
+
+        let jira = tool("jira_search", &read_only, Some("jira"), &[]);
+        assert_eq!(exclusions.excludes("jira_search", &jira).as_deref(), Some("from server jira"));
+
+        let tagged = tool("beta_search", &read_only, None, &["Experimental"]);
+        assert_eq!(exclusions.excludes("beta_search", &tagged).as_deref(), Some("tagged Experimental"));
+
+        let versioned = tool("search_code@v2", &read_only, None, &[]);
+        assert!(exclusions.excludes("search_code@v2", &versioned).is_some());
+    }
+}
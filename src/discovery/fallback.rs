@@ -941,10 +941,20 @@ mod tests {
                 routing: crate::registry::RoutingConfig {
                     r#type: "test".to_string(),
                     config: serde_json::json!({}),
+                    fallback: Vec::new(),
+                    canary: None,
                 },
                 annotations: None,
                 hidden: false,
                 enabled: true,
+                deprecated: false,
+                deprecation_reason: None,
+                sunset_date: None,
+                tags: Vec::new(),
+                output_schema: None,
+                cache: None,
+                background: false,
+                cost: None,
             }),
             ("http_request".to_string(), ToolDefinition {
                 name: "http_request".to_string(),
@@ -953,10 +963,20 @@ mod tests {
                 routing: crate::registry::RoutingConfig {
                     r#type: "test".to_string(),
                     config: serde_json::json!({}),
+                    fallback: Vec::new(),
+                    canary: None,
                 },
                 annotations: None,
                 hidden: false,
                 enabled: true,
+                deprecated: false,
+                deprecation_reason: None,
+                sunset_date: None,
+                tags: Vec::new(),
+                output_schema: None,
+                cache: None,
+                background: false,
+                cost: None,
             }),
         ]
     }
@@ -1036,6 +1056,8 @@ mod tests {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let result = manager.execute_fallback(&request, &tools, "No matches found");
@@ -0,0 +1,340 @@
+//! Discovery feedback
+//!
+//! Clients report whether the tool smart discovery picked actually solved
+//! their request, through `POST /dashboard/api/discovery/feedback` or the
+//! `smart_discovery_feedback` tool. Reports are kept per tool and turn into a
+//! ranking boost: tools that keep solving requests move up, tools that keep
+//! being picked for requests they can't handle move down and are listed as
+//! mis-matched in the dashboard.
+//!
+//! ```yaml
+//! smart_discovery:
+//!   feedback:
+//!     storage_file: ./data/discovery_feedback.json
+//!     max_boost: 0.1
+//!     min_reports: 3
+//! ```
+
+use crate::error::{ProxyError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+/// Name of the MCP tool clients report feedback with
+pub const FEEDBACK_TOOL_NAME: &str = "smart_discovery_feedback";
+
+/// `smart_discovery.feedback`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscoveryFeedbackConfig {
+    /// Whether feedback is accepted and applied to rankings
+    pub enabled: bool,
+
+    /// File the feedback is persisted to
+    pub storage_file: PathBuf,
+
+    /// Largest confidence adjustment feedback can make, up or down (0.0-1.0)
+    pub max_boost: f64,
+
+    /// Reports a tool needs before its ranking is adjusted or it can be flagged
+    pub min_reports: u64,
+
+    /// Tools solving at most this share of their reports are flagged as mis-matched
+    pub mismatch_threshold: f64,
+
+    /// Individual reports kept for the dashboard
+    pub max_history: usize,
+}
+
+impl Default for DiscoveryFeedbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            storage_file: PathBuf::from("./data/discovery_feedback.json"),
+            max_boost: 0.1,
+            min_reports: 3,
+            mismatch_threshold: 0.5,
+            max_history: 1000,
+        }
+    }
+}
+
+impl DiscoveryFeedbackConfig {
+    /// Validate the configuration
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.max_boost) {
+            return Err(ProxyError::config("feedback.max_boost must be between 0.0 and 1.0"));
+        }
+        if !(0.0..=1.0).contains(&self.mismatch_threshold) {
+            return Err(ProxyError::config("feedback.mismatch_threshold must be between 0.0 and 1.0"));
+        }
+        if self.min_reports == 0 {
+            return Err(ProxyError::config("feedback.min_reports must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+/// A client's report on a discovery result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryFeedback {
+    /// The tool discovery selected
+    pub tool_name: String,
+
+    /// Whether the tool solved the request
+    pub solved: bool,
+
+    /// The request sent to smart discovery
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<String>,
+
+    /// The tool that should have been selected, when it wasn't
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_tool: Option<String>,
+
+    /// Free-form note
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// A stored report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackRecord {
+    #[serde(flatten)]
+    pub feedback: DiscoveryFeedback,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Feedback totals for a tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolFeedbackStats {
+    pub tool_name: String,
+
+    /// Times the tool was selected and solved the request
+    pub solved: u64,
+
+    /// Times the tool was selected and didn't solve the request
+    pub unsolved: u64,
+
+    /// Times clients named the tool as the one that should have been selected
+    pub expected: u64,
+
+    pub last_feedback: Option<DateTime<Utc>>,
+}
+
+impl ToolFeedbackStats {
+    fn new(tool_name: &str) -> Self {
+        Self { tool_name: tool_name.to_string(), ..Self::default() }
+    }
+
+    /// Total reports concerning the tool
+    pub fn reports(&self) -> u64 {
+        self.solved + self.unsolved + self.expected
+    }
+
+    /// Share of the reports in the tool's favour (0.0-1.0)
+    pub fn solve_rate(&self) -> f64 {
+        match self.reports() {
+            0 => 0.0,
+            reports => (self.solved + self.expected) as f64 / reports as f64,
+        }
+    }
+
+    /// Confidence adjustment for the tool, within ±`max_boost`
+    ///
+    /// The solve rate is smoothed towards 0.5 so a few reports can't swing a
+    /// tool as far as many reports do.
+    pub fn boost(&self, config: &DiscoveryFeedbackConfig) -> f64 {
+        let reports = self.reports();
+        if reports < config.min_reports {
+            return 0.0;
+        }
+        let smoothed = (self.solved + self.expected + 1) as f64 / (reports + 2) as f64;
+        (smoothed - 0.5) * 2.0 * config.max_boost
+    }
+}
+
+/// Persisted feedback
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FeedbackData {
+    tools: HashMap<String, ToolFeedbackStats>,
+    history: VecDeque<FeedbackRecord>,
+}
+
+/// Stores discovery feedback and derives ranking boosts from it
+pub struct FeedbackStore {
+    config: DiscoveryFeedbackConfig,
+    data: RwLock<FeedbackData>,
+}
+
+impl FeedbackStore {
+    /// Open the store, loading any feedback persisted earlier
+    pub async fn open(config: DiscoveryFeedbackConfig) -> Self {
+        let data = match tokio::fs::read_to_string(&config.storage_file).await {
+            Ok(json) => match serde_json::from_str::<FeedbackData>(&json) {
+                Ok(data) => {
+                    info!("Loaded discovery feedback for {} tools from {}", data.tools.len(), config.storage_file.display());
+                    data
+                }
+                Err(e) => {
+                    warn!("Failed to parse discovery feedback in {}: {}. Starting with no feedback.", config.storage_file.display(), e);
+                    FeedbackData::default()
+                }
+            },
+            Err(_) => FeedbackData::default(),
+        };
+
+        Self { config, data: RwLock::new(data) }
+    }
+
+    pub fn config(&self) -> &DiscoveryFeedbackConfig {
+        &self.config
+    }
+
+    /// Record a report and return the updated totals of the reported tool
+    pub async fn record(&self, feedback: DiscoveryFeedback) -> Result<ToolFeedbackStats> {
+        if feedback.tool_name.trim().is_empty() {
+            return Err(ProxyError::validation("Feedback requires a tool_name"));
+        }
+
+        let now = Utc::now();
+        let stats = {
+            let mut data = self.data.write().await;
+
+            let stats = data.tools.entry(feedback.tool_name.clone())
+                .or_insert_with(|| ToolFeedbackStats::new(&feedback.tool_name));
+            if feedback.solved {
+                stats.solved += 1;
+            } else {
+                stats.unsolved += 1;
+            }
+            stats.last_feedback = Some(now);
+            let stats = stats.clone();
+
+            if let Some(expected) = feedback.expected_tool.as_ref().filter(|expected| !feedback.solved && **expected != feedback.tool_name) {
+                let expected_stats = data.tools.entry(expected.clone())
+                    .or_insert_with(|| ToolFeedbackStats::new(expected));
+                expected_stats.expected += 1;
+                expected_stats.last_feedback = Some(now);
+            }
+
+            data.history.push_back(FeedbackRecord { feedback, timestamp: now });
+            while data.history.len() > self.config.max_history {
+                data.history.pop_front();
+            }
+            stats
+        };
+        debug!("Recorded discovery feedback for '{}': {} solved, {} unsolved", stats.tool_name, stats.solved, stats.unsolved);
+
+        if let Err(e) = self.save().await {
+            warn!("Failed to save discovery feedback: {}", e);
+        }
+        Ok(stats)
+    }
+
+    /// Ranking boosts of all tools with enough feedback
+    pub async fn boosts(&self) -> HashMap<String, f64> {
+        self.data.read().await.tools.values()
+            .map(|stats| (stats.tool_name.clone(), stats.boost(&self.config)))
+            .filter(|(_, boost)| *boost != 0.0)
+            .collect()
+    }
+
+    /// Feedback totals of all tools, most reported first
+    pub async fn stats(&self) -> Vec<ToolFeedbackStats> {
+        let mut stats: Vec<_> = self.data.read().await.tools.values().cloned().collect();
+        stats.sort_by(|a, b| b.reports().cmp(&a.reports()).then_with(|| a.tool_name.cmp(&b.tool_name)));
+        stats
+    }
+
+    /// Tools that chronically fail to solve the requests they're selected for, worst first
+    pub async fn mismatched_tools(&self) -> Vec<ToolFeedbackStats> {
+        let mut stats: Vec<_> = self.stats().await.into_iter()
+            .filter(|stats| stats.solved + stats.unsolved >= self.config.min_reports)
+            .filter(|stats| stats.solve_rate() <= self.config.mismatch_threshold)
+            .collect();
+        stats.sort_by(|a, b| a.solve_rate().partial_cmp(&b.solve_rate()).unwrap_or(std::cmp::Ordering::Equal));
+        stats
+    }
+
+    /// Most recent reports, newest first
+    pub async fn recent(&self, limit: usize) -> Vec<FeedbackRecord> {
+        self.data.read().await.history.iter().rev().take(limit).cloned().collect()
+    }
+
+    async fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&*self.data.read().await)?;
+        if let Some(parent) = self.config.storage_file.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&self.config.storage_file, json).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config(temp_dir: &TempDir) -> DiscoveryFeedbackConfig {
+        DiscoveryFeedbackConfig {
+            storage_file: temp_dir.path().join("feedback.json"),
+            ..DiscoveryFeedbackConfig::default()
+        }
+    }
+
+    fn feedback(tool_name: &str, solved: bool, expected_tool: Option<&str>) -> DiscoveryFeedback {
+        DiscoveryFeedback {
+            tool_name: tool_name.to_string(),
+            solved,
+            request: Some("ping google.com".to_string()),
+            expected_tool: expected_tool.map(str::to_string),
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn test_boost_needs_min_reports() {
+        let config = DiscoveryFeedbackConfig::default();
+        let mut stats = ToolFeedbackStats::new("ping");
+        stats.solved = 2;
+        assert_eq!(stats.boost(&config), 0.0);
+
+        stats.solved = 100;
+        assert!(stats.boost(&config) > 0.09 && stats.boost(&config) < config.max_boost);
+
+        stats.solved = 0;
+        stats.unsolved = 100;
+        assert!(stats.boost(&config) < -0.09 && stats.boost(&config) > -config.max_boost);
+    }
+
+    #[tokio::test]
+    async fn test_record_and_flag_mismatched_tools() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FeedbackStore::open(config(&temp_dir)).await;
+
+        for _ in 0..3 {
+            store.record(feedback("http_get", false, Some("ping"))).await.unwrap();
+        }
+        store.record(feedback("ping", true, None)).await.unwrap();
+        assert!(store.record(feedback(" ", true, None)).await.is_err());
+
+        let boosts = store.boosts().await;
+        assert!(boosts["http_get"] < 0.0);
+        assert!(boosts["ping"] > 0.0);
+
+        let mismatched = store.mismatched_tools().await;
+        assert_eq!(mismatched.len(), 1);
+        assert_eq!(mismatched[0].tool_name, "http_get");
+        assert_eq!(store.recent(2).await[0].feedback.tool_name, "ping");
+
+        // Feedback survives a restart
+        let reopened = FeedbackStore::open(config(&temp_dir)).await;
+        assert_eq!(reopened.stats().await.len(), 2);
+        assert_eq!(reopened.recent(10).await.len(), 4);
+    }
+}
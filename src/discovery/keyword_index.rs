@@ -0,0 +1,293 @@
+//! BM25 keyword index over tools
+//!
+//! Embeddings are good at paraphrases but often miss exact tool names and
+//! jargon ("jq", "s3_put_object", "k8s"). Hybrid discovery also scores every
+//! tool with BM25 over its name, tags, keywords and description, and adds that
+//! score to the semantic, rule-based and LLM ones using
+//! `smart_discovery.hybrid_weights.keyword`.
+//!
+//! Keywords come from the comma-separated `keywords` annotation, which tool
+//! authors (or an enhancement pass) can use to list synonyms and jargon that
+//! don't fit naturally in the description.
+
+use crate::registry::types::ToolDefinition;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Annotation listing extra comma-separated search keywords for a tool
+pub const KEYWORDS_ANNOTATION: &str = "keywords";
+
+/// Term frequency saturation
+const K1: f64 = 1.2;
+/// Document length normalisation
+const B: f64 = 0.75;
+
+/// How much a term counts depending on where it appears
+const NAME_WEIGHT: f64 = 3.0;
+const TAG_WEIGHT: f64 = 2.0;
+const KEYWORD_WEIGHT: f64 = 2.0;
+const DESCRIPTION_WEIGHT: f64 = 1.0;
+
+/// Query words that carry no signal about which tool is wanted
+pub(crate) const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "can", "do", "for", "from", "i", "in", "is", "it",
+    "me", "my", "of", "on", "or", "please", "some", "that", "the", "this", "to", "with", "you",
+];
+
+#[derive(Debug)]
+struct Document {
+    name: String,
+    length: f64,
+}
+
+/// BM25 index over the discoverable tools
+#[derive(Debug, Default)]
+pub struct KeywordIndex {
+    documents: Vec<Document>,
+    /// Term -> (document, weighted term frequency)
+    postings: HashMap<String, Vec<(usize, f64)>>,
+    average_length: f64,
+}
+
+impl KeywordIndex {
+    /// Build an index over `tools`
+    pub fn build<'a>(tools: impl IntoIterator<Item = (&'a String, &'a ToolDefinition)>) -> Self {
+        let mut index = Self::default();
+
+        for (name, tool) in tools {
+            let mut frequencies: HashMap<String, f64> = HashMap::new();
+            let mut add = |text: &str, weight: f64| {
+                for term in tokenize(text) {
+                    *frequencies.entry(term).or_default() += weight;
+                }
+            };
+
+            add(name, NAME_WEIGHT);
+            for tag in &tool.tags {
+                add(tag, TAG_WEIGHT);
+            }
+            if let Some(keywords) = tool.annotations.as_ref().and_then(|a| a.get(KEYWORDS_ANNOTATION)) {
+                for keyword in keywords.split(',') {
+                    add(keyword, KEYWORD_WEIGHT);
+                }
+            }
+            add(&tool.description, DESCRIPTION_WEIGHT);
+
+            let id = index.documents.len();
+            let length = frequencies.values().sum();
+            for (term, frequency) in frequencies {
+                index.postings.entry(term).or_default().push((id, frequency));
+            }
+            index.documents.push(Document { name: name.clone(), length });
+        }
+
+        if !index.documents.is_empty() {
+            index.average_length = index.documents.iter().map(|d| d.length).sum::<f64>() / index.documents.len() as f64;
+        }
+        index
+    }
+
+    /// Number of indexed tools
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Tools matching `query`, best first, with scores in 0.0-1.0
+    ///
+    /// Raw BM25 scores are unbounded, so each is divided by the score a tool
+    /// would get by matching every known query term as strongly as possible.
+    /// That keeps scores comparable across queries, unlike dividing by the
+    /// best match, which would give a weak best match a perfect score.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<(String, f64)> {
+        let terms: HashSet<String> = tokenize(query)
+            .into_iter()
+            .filter(|term| !STOP_WORDS.contains(&term.as_str()))
+            .collect();
+
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        let mut best_possible = 0.0;
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else { continue };
+            let idf = self.idf(postings.len());
+            best_possible += idf * (K1 + 1.0);
+
+            for &(id, frequency) in postings {
+                let length_norm = 1.0 - B + B * self.documents[id].length / self.average_length;
+                *scores.entry(id).or_default() += idf * frequency * (K1 + 1.0) / (frequency + K1 * length_norm);
+            }
+        }
+
+        if best_possible <= 0.0 {
+            return Vec::new();
+        }
+        let mut matches: Vec<(String, f64)> = scores
+            .into_iter()
+            .map(|(id, score)| (self.documents[id].name.clone(), (score / best_possible).min(1.0)))
+            .collect();
+        matches.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.0.cmp(&b.0)));
+        matches.truncate(limit);
+        matches
+    }
+
+    fn idf(&self, document_frequency: usize) -> f64 {
+        let n = self.documents.len() as f64;
+        let df = document_frequency as f64;
+        ((n - df + 0.5) / (df + 0.5) + 1.0).ln()
+    }
+}
+
+/// Request terms found in one field of a tool's metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataMatch {
+    /// `name`, `tags`, `keywords` or `description`
+    pub field: String,
+    pub terms: Vec<String>,
+}
+
+/// Which of `tool`'s searchable fields contain terms of `query`, in index weight order
+pub fn matched_metadata(query: &str, name: &str, tool: &ToolDefinition) -> Vec<MetadataMatch> {
+    let mut query_terms: Vec<String> = Vec::new();
+    for term in tokenize(query) {
+        if !STOP_WORDS.contains(&term.as_str()) && !query_terms.contains(&term) {
+            query_terms.push(term);
+        }
+    }
+
+    let keywords = tool.annotations.as_ref()
+        .and_then(|a| a.get(KEYWORDS_ANNOTATION))
+        .map(|keywords| keywords.replace(',', " "))
+        .unwrap_or_default();
+    let fields = [
+        ("name", name.to_string()),
+        ("tags", tool.tags.join(" ")),
+        ("keywords", keywords),
+        ("description", tool.description.clone()),
+    ];
+
+    fields.into_iter()
+        .filter_map(|(field, text)| {
+            let field_terms: HashSet<String> = tokenize(&text).into_iter().collect();
+            let terms: Vec<String> = query_terms.iter().filter(|term| field_terms.contains(*term)).cloned().collect();
+            (!terms.is_empty()).then(|| MetadataMatch { field: field.to_string(), terms })
+        })
+        .collect()
+}
+
+/// Lowercased search terms in `text`
+///
+/// Identifiers are kept whole and also split into their parts, so
+/// `readFile`, `read_file` and "read file" all match a `read_file` tool,
+/// with the exact identifier matching best.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    for word in text.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')) {
+        let word = word.trim_matches(|c| c == '_' || c == '-');
+        if word.is_empty() {
+            continue;
+        }
+
+        let parts = split_identifier(word);
+        if parts.len() > 1 {
+            terms.push(parts.join("_"));
+        }
+        terms.extend(parts);
+    }
+    terms
+}
+
+/// Split on `_`/`-` and camelCase boundaries
+fn split_identifier(word: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    for segment in word.split(['_', '-']).filter(|s| !s.is_empty()) {
+        let mut current = String::new();
+        let mut previous: Option<char> = None;
+        for c in segment.chars() {
+            if c.is_uppercase() && previous.is_some_and(|p| p.is_lowercase() || p.is_numeric()) && !current.is_empty() {
+                parts.push(std::mem::take(&mut current));
+            }
+            current.extend(c.to_lowercase());
+            previous = Some(c);
+        }
+        if !current.is_empty() {
+            parts.push(current);
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::types::RoutingConfig;
+
+    fn tool(name: &str, description: &str, keywords: Option<&str>) -> (String, ToolDefinition) {
+        let annotations = keywords.map(|keywords| HashMap::from([(KEYWORDS_ANNOTATION.to_string(), keywords.to_string())]));
+        let tool = ToolDefinition::new_with_fields(
+            name.to_string(),
+            description.to_string(),
+            serde_json::json!({"type": "object"}),
+            RoutingConfig::new("subprocess".to_string(), serde_json::json!({"command": "echo"})),
+            annotations,
+        )
+        .unwrap();
+        (name.to_string(), tool)
+    }
+
+    fn index(tools: &[(String, ToolDefinition)]) -> KeywordIndex {
+        KeywordIndex::build(tools.iter().map(|(name, tool)| (name, tool)))
+    }
+
+    #[test]
+    fn test_tokenize_identifiers() {
+        assert_eq!(tokenize("readFile"), vec!["read_file", "read", "file"]);
+        assert_eq!(tokenize("s3-put_object"), vec!["s3_put_object", "s3", "put", "object"]);
+        assert_eq!(tokenize("Run the JQ filter!"), vec!["run", "the", "jq", "filter"]);
+    }
+
+    #[test]
+    fn test_exact_tool_name_ranks_first() {
+        let tools = vec![
+            tool("read_file", "Read the contents of a file", None),
+            tool("read_url", "Read a web page", None),
+            tool("write_file", "Write contents to a file", None),
+        ];
+        let matches = index(&tools).search("read_file", 10);
+        assert_eq!(matches[0].0, "read_file");
+        assert!(matches[0].1 > matches[1].1);
+        assert!(matches.iter().all(|(_, score)| (0.0..=1.0).contains(score)));
+    }
+
+    #[test]
+    fn test_keywords_annotation_is_searched() {
+        let tools = vec![
+            tool("json_query", "Filter JSON documents", Some("jq, jsonpath")),
+            tool("http_get", "Fetch a URL", None),
+        ];
+        let matches = index(&tools).search("run a jq expression", 10);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].0, "json_query");
+    }
+
+    #[test]
+    fn test_matched_metadata() {
+        let (name, tool) = tool("json_query", "Filter JSON documents", Some("jq, jsonpath"));
+        let matches = matched_metadata("query json with jq", &name, &tool);
+        assert_eq!(matches, vec![
+            MetadataMatch { field: "name".to_string(), terms: vec!["query".to_string(), "json".to_string()] },
+            MetadataMatch { field: "keywords".to_string(), terms: vec!["jq".to_string()] },
+            MetadataMatch { field: "description".to_string(), terms: vec!["json".to_string()] },
+        ]);
+        assert!(matched_metadata("ping a host", &name, &tool).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_terms_match_nothing() {
+        let tools = vec![tool("read_file", "Read the contents of a file", None)];
+        assert!(index(&tools).search("please do the thing", 10).is_empty());
+        assert!(KeywordIndex::default().search("read", 10).is_empty());
+    }
+}
@@ -3,24 +3,24 @@
 //! This module implements LLM-based parameter mapping that converts natural language
 //! requests into structured tool parameters using various LLM providers.
 
+use crate::discovery::llm_provider::{build_llm_provider, LlmFunction, LlmProvider, LlmProviderConfig, LlmRequest};
 use crate::discovery::types::*;
 use crate::error::{ProxyError, Result};
 use crate::registry::types::ToolDefinition;
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::time::timeout as tokio_timeout;
 use tracing::{debug, error, info, warn};
 
 /// Configuration for LLM parameter mapping
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmMapperConfig {
-    /// LLM provider (openai, anthropic, ollama, etc.)
+    /// LLM provider (openai, openai-compatible, anthropic, azure-openai or ollama)
     pub provider: String,
     
-    /// Model name to use
+    /// Model name to use; for Azure OpenAI, the deployment name
     pub model: String,
     
     /// API key (if required)
@@ -40,6 +40,14 @@ pub struct LlmMapperConfig {
     
     /// Whether to enable parameter mapping
     pub enabled: bool,
+    
+    /// Azure OpenAI API version
+    #[serde(default)]
+    pub api_version: Option<String>,
+    
+    /// Providers to try, in order, when the primary one fails
+    #[serde(default)]
+    pub fallback: Vec<LlmProviderConfig>,
 }
 
 impl Default for LlmMapperConfig {
@@ -53,62 +61,55 @@ impl Default for LlmMapperConfig {
             timeout: 30,
             max_retries: 3,
             enabled: true,
+            api_version: None,
+            fallback: Vec::new(),
         }
     }
 }
 
-/// LLM parameter mapper service
-pub struct LlmParameterMapper {
-    config: LlmMapperConfig,
-    client: Client,
-}
-
-/// OpenAI API request structure
-#[derive(Debug, Serialize)]
-struct OpenAIRequest {
-    model: String,
-    messages: Vec<OpenAIMessage>,
-    temperature: f32,
-    max_tokens: Option<u32>,
-}
-
-/// OpenAI message structure
-#[derive(Debug, Serialize)]
-struct OpenAIMessage {
-    role: String,
-    content: String,
-}
-
-/// OpenAI API response structure
-#[derive(Debug, Deserialize)]
-struct OpenAIResponse {
-    choices: Vec<OpenAIChoice>,
-}
+impl LlmMapperConfig {
+    /// The primary provider followed by the fallbacks
+    pub fn providers(&self) -> Vec<LlmProviderConfig> {
+        let primary = LlmProviderConfig {
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            api_key: self.api_key.clone(),
+            api_key_env: self.api_key_env.clone(),
+            base_url: self.base_url.clone(),
+            api_version: self.api_version.clone(),
+        };
+        std::iter::once(primary).chain(self.fallback.iter().cloned()).collect()
+    }
 
-/// OpenAI choice structure
-#[derive(Debug, Deserialize)]
-struct OpenAIChoice {
-    message: OpenAIResponseMessage,
+    /// Validate the configuration
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for provider in self.providers() {
+            provider.validate()?;
+        }
+        Ok(())
+    }
 }
 
-/// OpenAI response message structure
-#[derive(Debug, Deserialize)]
-struct OpenAIResponseMessage {
-    content: String,
+/// LLM parameter mapper service
+pub struct LlmParameterMapper {
+    config: LlmMapperConfig,
+    /// Primary provider and fallbacks; `None` when mapping is disabled
+    provider: Option<Arc<dyn LlmProvider>>,
 }
 
 impl LlmParameterMapper {
     /// Create a new LLM parameter mapper
     pub fn new(config: LlmMapperConfig) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout))
-            .danger_accept_invalid_certs(false)
-            .use_rustls_tls()
-            .tls_built_in_root_certs(true) // Use built-in webpki root certificates
-            .build()
-            .map_err(|e| ProxyError::routing(format!("Failed to create HTTP client: {}", e)))?;
-
-        Ok(Self { config, client })
+        let provider = if config.enabled {
+            Some(build_llm_provider(&config.providers(), Duration::from_secs(config.timeout))?)
+        } else {
+            None
+        };
+
+        Ok(Self { config, provider })
     }
 
     /// Create a new LLM parameter mapper with default configuration
@@ -176,20 +177,15 @@ impl LlmParameterMapper {
         debug!("LLM Request - Tool Schema: {}", serde_json::to_string_pretty(&tool_def.input_schema).unwrap_or_else(|_| "Unable to serialize schema".to_string()));
         debug!("LLM Request - Full Prompt: {}", prompt);
         
-        // Call LLM based on provider
-        let llm_response = match self.config.provider.as_str() {
-            "openai" | "openai-compatible" => {
-                self.call_openai_llm(&prompt).await?
-            }
-            "ollama" => {
-                self.call_ollama_llm(&prompt).await?
-            }
-            _ => {
-                return Err(ProxyError::routing(format!(
-                    "Unsupported LLM provider: {}", self.config.provider
-                )));
-            }
-        };
+        // Ask for a call to the tool itself, so providers return arguments matching its schema
+        let llm_response = self.provider()?.complete(&LlmRequest {
+            function: Some(LlmFunction {
+                name: tool_def.name.clone(),
+                description: tool_def.description.clone(),
+                parameters: tool_def.input_schema.clone(),
+            }),
+            ..LlmRequest::text(prompt, 1000)
+        }).await?;
 
         debug!("LLM Response - Raw: {}", llm_response);
 
@@ -273,119 +269,14 @@ JSON Response:"#,
         Ok(prompt)
     }
 
-    /// Call OpenAI-compatible LLM
-    async fn call_openai_llm(&self, prompt: &str) -> Result<String> {
-        let api_key = self.config.api_key.as_ref().ok_or_else(|| {
-            ProxyError::routing("API key required for OpenAI LLM".to_string())
-        })?;
-
-        let base_url = self.config.base_url.as_ref()
-            .map(|u| u.clone())
-            .unwrap_or_else(|| "https://api.openai.com/v1".to_string());
-
-        let request_body = OpenAIRequest {
-            model: self.config.model.clone(),
-            messages: vec![OpenAIMessage {
-                role: "user".to_string(),
-                content: prompt.to_string(),
-            }],
-            temperature: 0.1, // Low temperature for consistent parameter extraction
-            max_tokens: Some(1000), // Reasonable limit for parameter extraction
-        };
-
-        let url = format!("{}/chat/completions", base_url);
-        
-        debug!("Calling OpenAI LLM at: {}", url);
-        
-        let response = tokio_timeout(
-            Duration::from_secs(self.config.timeout),
-            self.client
-                .post(&url)
-                .header("Authorization", format!("Bearer {}", api_key))
-                .header("Content-Type", "application/json")
-                .json(&request_body)
-                .send()
-        )
-        .await
-        .map_err(|_| ProxyError::routing("LLM request timeout".to_string()))?
-        .map_err(|e| ProxyError::routing(format!("LLM request failed: {}", e)))?;
-
-        let status = response.status();
-        let response_text = response.text().await
-            .map_err(|e| ProxyError::routing(format!("Failed to read LLM response: {}", e)))?;
-
-        if !status.is_success() {
-            return Err(ProxyError::routing(format!(
-                "LLM request failed with status {}: {}", status, response_text
-            )));
-        }
-
-        let openai_response: OpenAIResponse = serde_json::from_str(&response_text)
-            .map_err(|e| ProxyError::routing(format!("Failed to parse LLM response: {}", e)))?;
-
-        let content = openai_response.choices
-            .first()
-            .ok_or_else(|| ProxyError::routing("No choices in LLM response".to_string()))?
-            .message
-            .content
-            .clone();
-
-        debug!("LLM response: {}", content);
-        Ok(content)
+    /// Complete a free-form prompt with the configured providers
+    pub async fn complete(&self, prompt: &str, max_tokens: u32) -> Result<String> {
+        self.provider()?.complete(&LlmRequest::text(prompt, max_tokens)).await
     }
 
-    /// Call Ollama LLM
-    async fn call_ollama_llm(&self, prompt: &str) -> Result<String> {
-        let base_url = self.config.base_url.as_ref()
-            .map(|u| u.clone())
-            .unwrap_or_else(|| "http://localhost:11434".to_string());
-
-        let request_body = json!({
-            "model": self.config.model,
-            "prompt": prompt,
-            "stream": false,
-            "options": {
-                "temperature": 0.1,
-                "num_predict": 1000
-            }
-        });
-
-        let url = format!("{}/api/generate", base_url);
-        
-        debug!("Calling Ollama LLM at: {}", url);
-        
-        let response = tokio_timeout(
-            Duration::from_secs(self.config.timeout),
-            self.client
-                .post(&url)
-                .header("Content-Type", "application/json")
-                .json(&request_body)
-                .send()
-        )
-        .await
-        .map_err(|_| ProxyError::routing("LLM request timeout".to_string()))?
-        .map_err(|e| ProxyError::routing(format!("LLM request failed: {}", e)))?;
-
-        let status = response.status();
-        let response_text = response.text().await
-            .map_err(|e| ProxyError::routing(format!("Failed to read LLM response: {}", e)))?;
-
-        if !status.is_success() {
-            return Err(ProxyError::routing(format!(
-                "LLM request failed with status {}: {}", status, response_text
-            )));
-        }
-
-        let ollama_response: Value = serde_json::from_str(&response_text)
-            .map_err(|e| ProxyError::routing(format!("Failed to parse LLM response: {}", e)))?;
-
-        let content = ollama_response["response"]
-            .as_str()
-            .ok_or_else(|| ProxyError::routing("No response in Ollama response".to_string()))?
-            .to_string();
-
-        debug!("LLM response: {}", content);
-        Ok(content)
+    fn provider(&self) -> Result<&Arc<dyn LlmProvider>> {
+        self.provider.as_ref()
+            .ok_or_else(|| ProxyError::routing("LLM mapper is disabled".to_string()))
     }
 
     /// Parse LLM response and extract parameters
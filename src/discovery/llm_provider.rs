@@ -0,0 +1,533 @@
+//! LLM providers for parameter mapping
+//!
+//! The parameter mapper and sequential-mode planning talk to an LLM through
+//! the [`LlmProvider`] trait, so a deployment picks its provider in config:
+//!
+//! ```yaml
+//! smart_discovery:
+//!   llm_mapper:
+//!     provider: azure-openai        # openai, openai-compatible, anthropic, azure-openai or ollama
+//!     model: gpt-4o-mini            # for Azure, the deployment name
+//!     base_url: https://my-resource.openai.azure.com
+//!     api_key_env: AZURE_OPENAI_API_KEY
+//!     fallback:
+//!     - provider: anthropic
+//!       model: claude-3-5-haiku-latest
+//!     - provider: ollama
+//!       model: llama3.1
+//! ```
+//!
+//! Parameter extraction uses each provider's own way of forcing structured
+//! output: OpenAI-style function calling, Anthropic tool use, or an Ollama
+//! JSON schema `format`. When a provider fails, the next one is tried, and
+//! a provider that just failed is tried last for the next minute.
+
+use crate::error::{ProxyError, Result};
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// How long a failed provider is moved behind the others
+const FAILURE_COOLDOWN: Duration = Duration::from_secs(60);
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const DEFAULT_AZURE_API_VERSION: &str = "2024-06-01";
+
+/// Supported LLM APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LlmProviderType {
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// Local or hosted servers speaking the OpenAI chat API (vLLM, LM Studio, llama.cpp, ...)
+    #[serde(rename = "openai-compatible")]
+    OpenAiCompatible,
+    Anthropic,
+    #[serde(rename = "azure-openai", alias = "azure")]
+    AzureOpenAi,
+    Ollama,
+}
+
+impl LlmProviderType {
+    /// Parse a `provider` setting
+    pub fn parse(provider: &str) -> Result<Self> {
+        serde_json::from_value(Value::String(provider.to_lowercase()))
+            .map_err(|_| ProxyError::config(format!(
+                "Unsupported LLM provider '{}' (expected openai, openai-compatible, anthropic, azure-openai or ollama)", provider
+            )))
+    }
+
+    fn default_base_url(self) -> Option<&'static str> {
+        match self {
+            Self::OpenAi => Some("https://api.openai.com/v1"),
+            Self::Anthropic => Some("https://api.anthropic.com/v1"),
+            Self::Ollama => Some("http://localhost:11434"),
+            // Both depend entirely on the deployment
+            Self::OpenAiCompatible | Self::AzureOpenAi => None,
+        }
+    }
+
+    fn default_api_key_env(self) -> Option<&'static str> {
+        match self {
+            Self::OpenAi => Some("OPENAI_API_KEY"),
+            Self::Anthropic => Some("ANTHROPIC_API_KEY"),
+            Self::AzureOpenAi => Some("AZURE_OPENAI_API_KEY"),
+            Self::OpenAiCompatible | Self::Ollama => None,
+        }
+    }
+
+    /// Whether requests fail without an API key
+    fn requires_api_key(self) -> bool {
+        matches!(self, Self::OpenAi | Self::Anthropic | Self::AzureOpenAi)
+    }
+}
+
+/// One provider in `smart_discovery.llm_mapper.fallback`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmProviderConfig {
+    /// openai, openai-compatible, anthropic, azure-openai or ollama
+    pub provider: String,
+
+    /// Model name; for Azure OpenAI, the deployment name
+    pub model: String,
+
+    /// API key; read from `api_key_env` when unset
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+
+    /// Environment variable holding the API key (default: OPENAI_API_KEY, ANTHROPIC_API_KEY or AZURE_OPENAI_API_KEY)
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+
+    /// API base URL; required for openai-compatible and azure-openai
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// Azure OpenAI API version (default: 2024-06-01)
+    #[serde(default)]
+    pub api_version: Option<String>,
+}
+
+impl LlmProviderConfig {
+    pub fn validate(&self) -> Result<()> {
+        let provider_type = LlmProviderType::parse(&self.provider)?;
+        if self.model.trim().is_empty() {
+            return Err(ProxyError::config(format!("LLM provider '{}' needs a model", self.provider)));
+        }
+        if provider_type.default_base_url().is_none() && self.base_url.is_none() {
+            return Err(ProxyError::config(format!("LLM provider '{}' needs a base_url", self.provider)));
+        }
+        Ok(())
+    }
+
+    fn api_key(&self, provider_type: LlmProviderType) -> Result<Option<String>> {
+        if let Some(api_key) = &self.api_key {
+            return Ok(Some(api_key.clone()));
+        }
+        let api_key = self.api_key_env.as_deref()
+            .or(provider_type.default_api_key_env())
+            .and_then(|env| std::env::var(env).ok());
+        if api_key.is_none() && provider_type.requires_api_key() {
+            return Err(ProxyError::config(format!(
+                "No API key for LLM provider '{}': set api_key or the {} environment variable",
+                self.provider,
+                self.api_key_env.as_deref().or(provider_type.default_api_key_env()).unwrap_or("api_key_env")
+            )));
+        }
+        Ok(api_key)
+    }
+}
+
+/// A function the LLM must call, whose arguments are the structured output
+#[derive(Debug, Clone)]
+pub struct LlmFunction {
+    pub name: String,
+    pub description: String,
+    /// JSON schema of the arguments
+    pub parameters: Value,
+}
+
+/// A single-prompt completion request
+#[derive(Debug, Clone)]
+pub struct LlmRequest {
+    pub prompt: String,
+    pub max_tokens: u32,
+    pub temperature: f32,
+    /// When set, the response is the JSON arguments of a call to this function
+    pub function: Option<LlmFunction>,
+}
+
+impl LlmRequest {
+    /// Plain text completion
+    pub fn text(prompt: impl Into<String>, max_tokens: u32) -> Self {
+        Self { prompt: prompt.into(), max_tokens, temperature: 0.1, function: None }
+    }
+}
+
+/// An LLM API that completes prompts
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Provider and model, for logs
+    fn name(&self) -> String;
+
+    /// Complete `request`, returning the text, or the function arguments as JSON
+    async fn complete(&self, request: &LlmRequest) -> Result<String>;
+}
+
+/// Create the provider chain for the primary provider and its fallbacks, in order
+pub fn build_llm_provider(providers: &[LlmProviderConfig], timeout: Duration) -> Result<Arc<dyn LlmProvider>> {
+    let mut built: Vec<Arc<dyn LlmProvider>> = Vec::with_capacity(providers.len());
+    for config in providers {
+        config.validate()?;
+        built.push(Arc::new(HttpLlmProvider {
+            provider_type: LlmProviderType::parse(&config.provider)?,
+            config: config.clone(),
+            client: Client::builder()
+                .timeout(timeout)
+                .use_rustls_tls()
+                .tls_built_in_root_certs(true)
+                .build()
+                .map_err(|e| ProxyError::config(format!("Failed to create LLM HTTP client: {}", e)))?,
+        }));
+    }
+
+    match built.len() {
+        0 => Err(ProxyError::config("No LLM provider configured")),
+        1 => Ok(built.remove(0)),
+        _ => Ok(Arc::new(FallbackLlmProvider::new(built))),
+    }
+}
+
+/// OpenAI, Azure OpenAI, OpenAI-compatible servers, Anthropic and Ollama over HTTP
+struct HttpLlmProvider {
+    provider_type: LlmProviderType,
+    config: LlmProviderConfig,
+    client: Client,
+}
+
+impl HttpLlmProvider {
+    fn base_url(&self) -> &str {
+        self.config.base_url.as_deref()
+            .or(self.provider_type.default_base_url())
+            .unwrap_or_default()
+            .trim_end_matches('/')
+    }
+
+    fn url(&self) -> String {
+        match self.provider_type {
+            LlmProviderType::OpenAi | LlmProviderType::OpenAiCompatible => format!("{}/chat/completions", self.base_url()),
+            LlmProviderType::AzureOpenAi => format!(
+                "{}/openai/deployments/{}/chat/completions?api-version={}",
+                self.base_url(),
+                self.config.model,
+                self.config.api_version.as_deref().unwrap_or(DEFAULT_AZURE_API_VERSION)
+            ),
+            LlmProviderType::Anthropic => format!("{}/messages", self.base_url()),
+            LlmProviderType::Ollama => format!("{}/api/generate", self.base_url()),
+        }
+    }
+
+    fn body(&self, request: &LlmRequest) -> Value {
+        match self.provider_type {
+            LlmProviderType::OpenAi | LlmProviderType::OpenAiCompatible | LlmProviderType::AzureOpenAi => {
+                let mut body = json!({
+                    "model": self.config.model,
+                    "messages": [{ "role": "user", "content": request.prompt }],
+                    "temperature": request.temperature,
+                    "max_tokens": request.max_tokens,
+                });
+                if let Some(function) = &request.function {
+                    let name = function_name(&function.name);
+                    body["tools"] = json!([{
+                        "type": "function",
+                        "function": {
+                            "name": name,
+                            "description": function.description,
+                            "parameters": object_schema(&function.parameters),
+                        }
+                    }]);
+                    body["tool_choice"] = json!({ "type": "function", "function": { "name": name } });
+                }
+                body
+            }
+            LlmProviderType::Anthropic => {
+                let mut body = json!({
+                    "model": self.config.model,
+                    "messages": [{ "role": "user", "content": request.prompt }],
+                    "temperature": request.temperature,
+                    "max_tokens": request.max_tokens,
+                });
+                if let Some(function) = &request.function {
+                    let name = function_name(&function.name);
+                    body["tools"] = json!([{
+                        "name": name,
+                        "description": function.description,
+                        "input_schema": object_schema(&function.parameters),
+                    }]);
+                    body["tool_choice"] = json!({ "type": "tool", "name": name });
+                }
+                body
+            }
+            LlmProviderType::Ollama => {
+                let mut body = json!({
+                    "model": self.config.model,
+                    "prompt": request.prompt,
+                    "stream": false,
+                    "options": {
+                        "temperature": request.temperature,
+                        "num_predict": request.max_tokens,
+                    }
+                });
+                if let Some(function) = &request.function {
+                    body["format"] = object_schema(&function.parameters);
+                }
+                body
+            }
+        }
+    }
+
+    fn authorize(&self, builder: RequestBuilder, api_key: Option<String>) -> RequestBuilder {
+        let Some(api_key) = api_key else { return builder };
+        match self.provider_type {
+            LlmProviderType::Anthropic => builder
+                .header("x-api-key", api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION),
+            LlmProviderType::AzureOpenAi => builder.header("api-key", api_key),
+            _ => builder.bearer_auth(api_key),
+        }
+    }
+
+    fn parse(&self, response: &Value) -> Result<String> {
+        let invalid = || ProxyError::routing(format!("Invalid {} response format", self.name()));
+        match self.provider_type {
+            LlmProviderType::OpenAi | LlmProviderType::OpenAiCompatible | LlmProviderType::AzureOpenAi => {
+                let message = &response["choices"][0]["message"];
+                // Servers that ignore `tools` answer in plain content instead
+                if let Some(arguments) = message["tool_calls"][0]["function"]["arguments"].as_str() {
+                    return Ok(arguments.to_string());
+                }
+                message["content"].as_str().map(str::to_string).ok_or_else(invalid)
+            }
+            LlmProviderType::Anthropic => {
+                let content = response["content"].as_array().ok_or_else(invalid)?;
+                if let Some(tool_use) = content.iter().find(|block| block["type"] == "tool_use") {
+                    return Ok(tool_use["input"].to_string());
+                }
+                content.iter()
+                    .find_map(|block| block["text"].as_str())
+                    .map(str::to_string)
+                    .ok_or_else(invalid)
+            }
+            LlmProviderType::Ollama => response["response"].as_str().map(str::to_string).ok_or_else(invalid),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for HttpLlmProvider {
+    fn name(&self) -> String {
+        format!("{}:{}", self.config.provider, self.config.model)
+    }
+
+    async fn complete(&self, request: &LlmRequest) -> Result<String> {
+        let api_key = self.config.api_key(self.provider_type)?;
+        let url = self.url();
+        debug!("Calling {} at {}", self.name(), url);
+
+        let response = self.authorize(self.client.post(&url), api_key)
+            .json(&self.body(request))
+            .send()
+            .await
+            .map_err(|e| ProxyError::routing(format!("{} request failed: {}", self.name(), e)))?;
+
+        let status = response.status();
+        let response_text = response.text().await
+            .map_err(|e| ProxyError::routing(format!("Failed to read {} response: {}", self.name(), e)))?;
+        if !status.is_success() {
+            return Err(ProxyError::routing(format!(
+                "{} request failed with status {}: {}", self.name(), status, response_text
+            )));
+        }
+
+        let json: Value = serde_json::from_str(&response_text)
+            .map_err(|e| ProxyError::routing(format!("Failed to parse {} response: {}", self.name(), e)))?;
+        let content = self.parse(&json)?;
+        debug!("{} response: {}", self.name(), content);
+        Ok(content)
+    }
+}
+
+/// Tries providers in order, moving ones that just failed to the back
+pub struct FallbackLlmProvider {
+    providers: Vec<Arc<dyn LlmProvider>>,
+    failed_at: Mutex<Vec<Option<Instant>>>,
+}
+
+impl FallbackLlmProvider {
+    pub fn new(providers: Vec<Arc<dyn LlmProvider>>) -> Self {
+        let failed_at = Mutex::new(vec![None; providers.len()]);
+        Self { providers, failed_at }
+    }
+
+    /// Provider indexes in the order to try them
+    fn order(&self) -> Vec<usize> {
+        let failed_at = self.failed_at.lock().unwrap();
+        let cooling_down = |index: &usize| failed_at[*index].is_some_and(|at| at.elapsed() < FAILURE_COOLDOWN);
+        let (cooling, healthy): (Vec<usize>, Vec<usize>) = (0..self.providers.len()).partition(cooling_down);
+        healthy.into_iter().chain(cooling).collect()
+    }
+}
+
+#[async_trait]
+impl LlmProvider for FallbackLlmProvider {
+    fn name(&self) -> String {
+        self.providers.iter().map(|provider| provider.name()).collect::<Vec<_>>().join(" -> ")
+    }
+
+    async fn complete(&self, request: &LlmRequest) -> Result<String> {
+        let mut errors = Vec::new();
+        for index in self.order() {
+            let provider = &self.providers[index];
+            match provider.complete(request).await {
+                Ok(content) => {
+                    self.failed_at.lock().unwrap()[index] = None;
+                    return Ok(content);
+                }
+                Err(e) => {
+                    warn!("LLM provider {} failed, trying the next one: {}", provider.name(), e);
+                    self.failed_at.lock().unwrap()[index] = Some(Instant::now());
+                    errors.push(format!("{}: {}", provider.name(), e));
+                }
+            }
+        }
+        Err(ProxyError::routing(format!("All LLM providers failed: {}", errors.join("; "))))
+    }
+}
+
+/// Function names are limited to `[a-zA-Z0-9_-]{1,64}` by OpenAI and Anthropic
+fn function_name(name: &str) -> String {
+    let name: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .take(64)
+        .collect();
+    if name.is_empty() { "extract_parameters".to_string() } else { name }
+}
+
+/// Function parameters must be an object schema
+fn object_schema(schema: &Value) -> Value {
+    if schema.get("type").and_then(Value::as_str) == Some("object") {
+        schema.clone()
+    } else {
+        json!({ "type": "object", "properties": schema.get("properties").cloned().unwrap_or_else(|| json!({})) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn provider(provider: &str) -> HttpLlmProvider {
+        let config = LlmProviderConfig {
+            provider: provider.to_string(),
+            model: "test-model".to_string(),
+            api_key: Some("key".to_string()),
+            api_key_env: None,
+            base_url: Some("https://llm.example.com/".to_string()),
+            api_version: None,
+        };
+        HttpLlmProvider {
+            provider_type: LlmProviderType::parse(provider).unwrap(),
+            config,
+            client: Client::new(),
+        }
+    }
+
+    fn extraction_request() -> LlmRequest {
+        LlmRequest {
+            function: Some(LlmFunction {
+                name: "network.ping".to_string(),
+                description: "Ping a host".to_string(),
+                parameters: json!({"type": "object", "properties": {"host": {"type": "string"}}}),
+            }),
+            ..LlmRequest::text("ping example.com", 100)
+        }
+    }
+
+    #[test]
+    fn test_parse_provider_types() {
+        assert_eq!(LlmProviderType::parse("OpenAI").unwrap(), LlmProviderType::OpenAi);
+        assert_eq!(LlmProviderType::parse("azure").unwrap(), LlmProviderType::AzureOpenAi);
+        assert_eq!(LlmProviderType::parse("openai-compatible").unwrap(), LlmProviderType::OpenAiCompatible);
+        assert!(LlmProviderType::parse("mock").is_err());
+
+        let mut config = provider("openai-compatible").config;
+        config.base_url = None;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_function_calling_formats() {
+        let request = extraction_request();
+
+        let openai = provider("azure-openai");
+        assert_eq!(openai.url(), "https://llm.example.com/openai/deployments/test-model/chat/completions?api-version=2024-06-01");
+        let body = openai.body(&request);
+        assert_eq!(body["tools"][0]["function"]["name"], "network_ping");
+        assert_eq!(body["tool_choice"]["function"]["name"], "network_ping");
+        let arguments = json!({"choices": [{"message": {"content": null, "tool_calls": [{"function": {"arguments": "{\"host\":\"example.com\"}"}}]}}]});
+        assert_eq!(openai.parse(&arguments).unwrap(), r#"{"host":"example.com"}"#);
+
+        let anthropic = provider("anthropic");
+        let body = anthropic.body(&request);
+        assert_eq!(body["tools"][0]["input_schema"]["properties"]["host"]["type"], "string");
+        assert_eq!(body["tool_choice"], json!({"type": "tool", "name": "network_ping"}));
+        let tool_use = json!({"content": [{"type": "text", "text": "Sure"}, {"type": "tool_use", "input": {"host": "example.com"}}]});
+        assert_eq!(anthropic.parse(&tool_use).unwrap(), r#"{"host":"example.com"}"#);
+
+        let ollama = provider("ollama");
+        assert_eq!(ollama.body(&request)["format"]["type"], "object");
+        assert!(ollama.body(&LlmRequest::text("hi", 10)).get("format").is_none());
+        assert_eq!(ollama.parse(&json!({"response": "{}"})).unwrap(), "{}");
+    }
+
+    struct CountingProvider {
+        name: &'static str,
+        fail: bool,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LlmProvider for CountingProvider {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        async fn complete(&self, _request: &LlmRequest) -> Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                Err(ProxyError::routing("unavailable"))
+            } else {
+                Ok(self.name.to_string())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_skips_failed_provider() {
+        let primary = Arc::new(CountingProvider { name: "primary", fail: true, calls: AtomicUsize::new(0) });
+        let backup = Arc::new(CountingProvider { name: "backup", fail: false, calls: AtomicUsize::new(0) });
+        let fallback = FallbackLlmProvider::new(vec![primary.clone(), backup.clone()]);
+
+        let request = LlmRequest::text("hi", 10);
+        assert_eq!(fallback.complete(&request).await.unwrap(), "backup");
+        // The failed primary is now tried last, so it isn't called again
+        assert_eq!(fallback.complete(&request).await.unwrap(), "backup");
+        assert_eq!(primary.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(backup.calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,152 @@
+//! In-process embedding models
+//!
+//! Models named `fastembed:<model>` (e.g. `fastembed:all-MiniLM-L6-v2`) run on
+//! the ONNX runtime inside MagicTunnel instead of calling an embedding API.
+//! The model files are downloaded into `model.cache_dir` the first time the
+//! model is loaded and reused afterwards, so copying a populated cache
+//! directory lets `--pregenerate-embeddings` run without network access.
+//!
+//! The runtime is built with the `embeddings` feature (on by default); without
+//! it no models are available and loading one fails.
+
+use crate::error::{ProxyError, Result};
+#[cfg(feature = "embeddings")]
+use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "embeddings")]
+use std::sync::Mutex;
+#[cfg(feature = "embeddings")]
+use tracing::info;
+
+/// Prefix of model names served by [`LocalEmbedder`]
+pub const LOCAL_MODEL_PREFIX: &str = "fastembed:";
+
+/// Models that can run in-process, with their embedding dimensions
+#[cfg(feature = "embeddings")]
+const MODELS: &[(&str, EmbeddingModel, usize)] = &[
+    ("all-MiniLM-L6-v2", EmbeddingModel::AllMiniLML6V2, 384),
+    ("all-MiniLM-L12-v2", EmbeddingModel::AllMiniLML12V2, 384),
+    ("bge-small-en-v1.5", EmbeddingModel::BGESmallENV15, 384),
+    ("bge-base-en-v1.5", EmbeddingModel::BGEBaseENV15, 768),
+    ("nomic-embed-text-v1.5", EmbeddingModel::NomicEmbedTextV15, 768),
+    ("paraphrase-multilingual-MiniLM-L12-v2", EmbeddingModel::ParaphraseMLMiniLML12V2, 384),
+    ("multilingual-e5-small", EmbeddingModel::MultilingualE5Small, 384),
+];
+
+#[cfg(feature = "embeddings")]
+fn find_model(name: &str) -> Option<&'static (&'static str, EmbeddingModel, usize)> {
+    MODELS.iter().find(|(model_name, _, _)| model_name.eq_ignore_ascii_case(name))
+}
+
+/// Names of the models that can run in-process
+#[cfg(feature = "embeddings")]
+pub fn local_models() -> Vec<&'static str> {
+    MODELS.iter().map(|(name, _, _)| *name).collect()
+}
+
+/// Names of the models that can run in-process
+#[cfg(not(feature = "embeddings"))]
+pub fn local_models() -> Vec<&'static str> {
+    Vec::new()
+}
+
+/// Embedding dimensions of an in-process model
+#[cfg(feature = "embeddings")]
+pub fn local_model_dimensions(name: &str) -> Option<usize> {
+    find_model(name).map(|(_, _, dimensions)| *dimensions)
+}
+
+/// Embedding dimensions of an in-process model
+#[cfg(not(feature = "embeddings"))]
+pub fn local_model_dimensions(_name: &str) -> Option<usize> {
+    None
+}
+
+/// An embedding model loaded into the ONNX runtime
+pub struct LocalEmbedder {
+    name: String,
+    #[cfg(feature = "embeddings")]
+    model: Mutex<TextEmbedding>,
+}
+
+impl std::fmt::Debug for LocalEmbedder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalEmbedder").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+impl LocalEmbedder {
+    /// Load the model `name`, downloading it into `cache_dir` if it isn't there yet
+    #[cfg(feature = "embeddings")]
+    pub async fn load(name: &str, cache_dir: &Path) -> Result<Arc<Self>> {
+        let (model_name, model, _) = find_model(name).ok_or_else(|| ProxyError::config(format!(
+            "Unknown local embedding model '{}'; available: {}", name, local_models().join(", ")
+        )))?;
+        info!("Loading local embedding model '{}' from {}", model_name, cache_dir.display());
+
+        let options = InitOptions::new(model.clone())
+            .with_cache_dir(cache_dir.to_path_buf())
+            .with_show_download_progress(false);
+        // Downloading and loading the model blocks for a while
+        let model = tokio::task::spawn_blocking(move || TextEmbedding::try_new(options))
+            .await
+            .map_err(|e| ProxyError::config(format!("Loading embedding model '{}' panicked: {}", model_name, e)))?
+            .map_err(|e| ProxyError::config(format!("Failed to load embedding model '{}': {}", model_name, e)))?;
+
+        Ok(Arc::new(Self { name: model_name.to_string(), model: Mutex::new(model) }))
+    }
+
+    /// Load the model `name`; this build has no embedding runtime
+    #[cfg(not(feature = "embeddings"))]
+    pub async fn load(name: &str, _cache_dir: &Path) -> Result<Arc<Self>> {
+        Err(ProxyError::config(format!(
+            "Cannot load local embedding model '{}': this build was compiled without the `embeddings` feature", name
+        )))
+    }
+
+    /// Model name, as listed by [`local_models`]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Embed `texts`, returning one embedding per text in order
+    #[cfg(feature = "embeddings")]
+    pub async fn embed(self: &Arc<Self>, texts: Vec<String>, batch_size: usize) -> Result<Vec<Vec<f32>>> {
+        let embedder = Arc::clone(self);
+        tokio::task::spawn_blocking(move || {
+            let model = embedder.model.lock().map_err(|_| ProxyError::routing("Embedding model lock poisoned"))?;
+            model.embed(texts, Some(batch_size.max(1)))
+                .map_err(|e| ProxyError::routing(format!("Embedding with '{}' failed: {}", embedder.name, e)))
+        })
+        .await
+        .map_err(|e| ProxyError::routing(format!("Embedding task panicked: {}", e)))?
+    }
+
+    /// Embed `texts`; never called, as no model can be loaded in this build
+    #[cfg(not(feature = "embeddings"))]
+    pub async fn embed(self: &Arc<Self>, _texts: Vec<String>, _batch_size: usize) -> Result<Vec<Vec<f32>>> {
+        Err(ProxyError::routing(format!("Embedding with '{}' is not supported by this build", self.name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "embeddings")]
+    #[test]
+    fn test_model_lookup() {
+        assert_eq!(local_model_dimensions("all-MiniLM-L6-v2"), Some(384));
+        assert_eq!(local_model_dimensions("BGE-BASE-EN-V1.5"), Some(768));
+        assert_eq!(local_model_dimensions("text-embedding-3-small"), None);
+    }
+
+    #[cfg(feature = "embeddings")]
+    #[tokio::test]
+    async fn test_unknown_model_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let error = LocalEmbedder::load("no-such-model", dir.path()).await.unwrap_err();
+        assert!(error.to_string().contains("all-MiniLM-L6-v2"));
+    }
+}
@@ -4,20 +4,46 @@
 //! intelligent tool interface for discovering and executing tools based on natural
 //! language requests.
 
+pub mod ann_index;
 pub mod cache;
+pub mod categories;
+pub mod clarification;
+pub mod conversation;
 pub mod embedding_manager;
+pub mod embedding_provider;
+pub mod exclusions;
 pub mod fallback;
+pub mod feedback;
+pub mod keyword_index;
 pub mod llm_mapper;
+pub mod llm_provider;
+pub mod local_embedding;
+pub mod model_swap;
 pub mod performance;
 pub mod semantic;
 pub mod service;
 pub mod types;
+pub mod usage_boost;
+pub mod vector_store;
 
+pub use ann_index::*;
 pub use cache::*;
+pub use categories::*;
+pub use clarification::*;
+pub use conversation::*;
 pub use embedding_manager::*;
+pub use embedding_provider::*;
+pub use exclusions::*;
 pub use fallback::*;
+pub use feedback::*;
+pub use keyword_index::*;
 pub use llm_mapper::*;
+pub use llm_provider::*;
+pub use local_embedding::*;
+pub use model_swap::*;
 pub use performance::*;
 pub use semantic::*;
 pub use service::*;
-pub use types::*;
\ No newline at end of file
+pub use types::*;
+pub use usage_boost::*;
+pub use vector_store::*;
\ No newline at end of file
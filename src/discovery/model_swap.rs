@@ -0,0 +1,297 @@
+//! Embedding model hot-swap
+//!
+//! Switching semantic search to another embedding model used to mean editing
+//! the config, running `--pregenerate-embeddings` and restarting. A swap does
+//! it at runtime instead:
+//!
+//! 1. a complete index is built with the new model in a directory of its own,
+//!    while the current index keeps serving searches;
+//! 2. its embeddings are checked to all have the same dimensions, matching
+//!    the provider's when known;
+//! 3. it replaces the current index in one step, and the replaced index is
+//!    kept so the swap can be rolled back.
+//!
+//! A swap lasts until restart; set `semantic_search.model_name` (or
+//! `provider`) in the config to keep it.
+
+use crate::discovery::embedding_manager::EmbeddingManager;
+use crate::discovery::embedding_provider::EmbeddingProviderConfig;
+use crate::discovery::semantic::{SemanticSearchConfig, SemanticSearchService};
+use crate::discovery::vector_store::VectorStoreConfig;
+use crate::error::{ProxyError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Directory, next to the configured embedding files, holding the indexes of swapped-in models
+const MODELS_DIR: &str = "models";
+
+/// Embedding model to switch to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingModelChange {
+    /// New `semantic_search.model_name`, e.g. `ollama:nomic-embed-text` or `fastembed:bge-small-en-v1.5`
+    pub model_name: String,
+
+    /// New `semantic_search.provider`; without one, `model_name` selects the model
+    #[serde(default)]
+    pub provider: Option<EmbeddingProviderConfig>,
+}
+
+impl EmbeddingModelChange {
+    /// Validate the change
+    pub fn validate(&self) -> Result<()> {
+        if self.model_name.trim().is_empty() {
+            return Err(ProxyError::validation("model_name cannot be empty"));
+        }
+        if let Some(provider) = &self.provider {
+            provider.validate()?;
+        }
+        Ok(())
+    }
+
+    /// `current` switched to this model, with its own embedding files, ANN index and vector store collection
+    pub fn apply(&self, current: &SemanticSearchConfig) -> SemanticSearchConfig {
+        let mut config = current.clone();
+        config.model_name = self.model_name.clone();
+        config.provider = self.provider.clone();
+
+        let slug = model_slug(&model_label(&config));
+        let dir = current.storage.embeddings_file.parent().unwrap_or(Path::new(".")).join(MODELS_DIR).join(&slug);
+        for file in [&mut config.storage.embeddings_file, &mut config.storage.metadata_file, &mut config.storage.hash_file] {
+            if let Some(name) = file.file_name() {
+                *file = dir.join(name);
+            }
+        }
+        if let Some(ann_index) = config.ann_index.as_mut() {
+            ann_index.path = dir.join("ann");
+        }
+        if let Some(VectorStoreConfig::Qdrant(qdrant)) = config.vector_store.as_mut() {
+            qdrant.collection = format!("{}_{}", qdrant.collection, slug);
+        }
+        config
+    }
+}
+
+/// Remove what an earlier swap to the same model left in `config`'s storage, so a new index starts empty
+pub async fn clear_index_storage(config: &SemanticSearchConfig) -> Result<()> {
+    for file in [&config.storage.embeddings_file, &config.storage.metadata_file, &config.storage.hash_file] {
+        match tokio::fs::remove_file(file).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(ProxyError::config(format!("Failed to remove '{}': {}", file.display(), e)));
+            }
+            _ => {}
+        }
+    }
+    if let Some(ann_index) = &config.ann_index {
+        match tokio::fs::remove_dir_all(&ann_index.path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(ProxyError::config(format!("Failed to remove '{}': {}", ann_index.path.display(), e)));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Name of the model `config` embeds with, e.g. `ollama:nomic-embed-text` or `voyage:voyage-3-lite`
+pub fn model_label(config: &SemanticSearchConfig) -> String {
+    match &config.provider {
+        Some(provider) => format!("{:?}:{}", provider.r#type, provider.model).to_lowercase(),
+        None => config.model_name.clone(),
+    }
+}
+
+/// `label` as a directory name
+fn model_slug(label: &str) -> String {
+    label.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+/// Dimensions shared by every embedding of a new index
+///
+/// `dimensions` are the distinct sizes found in the index; `expected` is the
+/// provider's, when known.
+pub fn validate_index_dimensions(dimensions: &[usize], expected: Option<usize>) -> Result<usize> {
+    match dimensions {
+        [] => Err(ProxyError::validation("The new model produced no embeddings")),
+        [0] => Err(ProxyError::validation("The new model produced empty embeddings")),
+        [dimensions] => match expected {
+            Some(expected) if expected != *dimensions => Err(ProxyError::validation(format!(
+                "The new model produced {}-dimensional embeddings, but {} were expected", dimensions, expected
+            ))),
+            _ => Ok(*dimensions),
+        },
+        _ => Err(ProxyError::validation(format!(
+            "The new model produced embeddings of different sizes: {:?}", dimensions
+        ))),
+    }
+}
+
+/// Semantic search service and the manager keeping its embeddings in sync, swapped together
+pub struct SemanticIndex {
+    pub search: Arc<SemanticSearchService>,
+    pub manager: Arc<EmbeddingManager>,
+}
+
+impl SemanticIndex {
+    /// Name of the model the index embeds with
+    pub fn model(&self) -> String {
+        model_label(self.search.config())
+    }
+}
+
+/// Outcome of a completed swap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelSwapReport {
+    /// Model swapped in
+    pub model: String,
+
+    /// Model swapped out, which a rollback returns to
+    pub previous_model: String,
+
+    /// Embedding dimensions of the new index
+    pub dimensions: usize,
+
+    /// Tools embedded with the new model
+    pub tools_indexed: usize,
+
+    /// Time taken to build the new index
+    pub duration_ms: u64,
+}
+
+/// Where the latest swap is at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelSwapState {
+    /// No swap has run
+    Idle,
+    /// The new index is being built
+    Building,
+    /// The new index is in use
+    Completed,
+    /// The latest swap or rollback failed; the index in use was kept
+    Failed,
+    /// The previous index was put back
+    RolledBack,
+}
+
+/// Embedding model status, as reported by the admin endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelSwapStatus {
+    pub state: ModelSwapState,
+
+    /// Model in use
+    pub active_model: Option<String>,
+
+    /// Model a rollback would return to
+    pub rollback_model: Option<String>,
+
+    /// Model of the latest swap
+    pub target_model: Option<String>,
+
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+
+    /// Report of the latest completed swap
+    pub report: Option<ModelSwapReport>,
+
+    /// Why the latest swap failed
+    pub error: Option<String>,
+}
+
+impl Default for ModelSwapStatus {
+    fn default() -> Self {
+        Self {
+            state: ModelSwapState::Idle,
+            active_model: None,
+            rollback_model: None,
+            target_model: None,
+            started_at: None,
+            finished_at: None,
+            report: None,
+            error: None,
+        }
+    }
+}
+
+impl ModelSwapStatus {
+    /// Record the start of a swap to `model`, unless one is already running
+    pub fn begin(&mut self, model: String) -> Result<()> {
+        if self.state == ModelSwapState::Building {
+            return Err(ProxyError::validation(format!(
+                "A swap to {} is already in progress", self.target_model.as_deref().unwrap_or("another model")
+            )));
+        }
+        self.state = ModelSwapState::Building;
+        self.target_model = Some(model);
+        self.started_at = Some(Utc::now());
+        self.finished_at = None;
+        self.error = None;
+        Ok(())
+    }
+
+    /// Record the outcome of the running swap
+    pub fn finish(&mut self, result: &Result<ModelSwapReport>) {
+        match result {
+            Ok(report) => {
+                self.state = ModelSwapState::Completed;
+                self.report = Some(report.clone());
+                self.finished_at = Some(Utc::now());
+            }
+            Err(e) => self.failed(e),
+        }
+    }
+
+    /// Record that the running swap or rollback failed
+    pub fn failed(&mut self, error: &ProxyError) {
+        self.state = ModelSwapState::Failed;
+        self.error = Some(error.to_string());
+        self.finished_at = Some(Utc::now());
+    }
+
+    /// Record that the running rollback put the previous index back
+    pub fn rolled_back(&mut self) {
+        self.state = ModelSwapState::RolledBack;
+        self.finished_at = Some(Utc::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_apply_keeps_new_index_apart() {
+        let change = EmbeddingModelChange { model_name: "ollama:nomic-embed-text".to_string(), provider: None };
+        let config = change.apply(&SemanticSearchConfig::default());
+
+        assert_eq!(config.model_name, "ollama:nomic-embed-text");
+        assert_eq!(config.storage.embeddings_file, PathBuf::from("./data/embeddings/models/ollama_nomic-embed-text/tool_embeddings.bin"));
+        assert_eq!(config.storage.hash_file, PathBuf::from("./data/embeddings/models/ollama_nomic-embed-text/content_hashes.json"));
+        assert_eq!(model_label(&config), "ollama:nomic-embed-text");
+    }
+
+    #[test]
+    fn test_validate_index_dimensions() {
+        assert_eq!(validate_index_dimensions(&[768], None).unwrap(), 768);
+        assert_eq!(validate_index_dimensions(&[1536], Some(1536)).unwrap(), 1536);
+        assert!(validate_index_dimensions(&[768], Some(1536)).is_err());
+        assert!(validate_index_dimensions(&[384, 768], None).is_err());
+        assert!(validate_index_dimensions(&[], None).is_err());
+    }
+
+    #[test]
+    fn test_status_rejects_concurrent_swaps() {
+        let mut status = ModelSwapStatus::default();
+        status.begin("a".to_string()).unwrap();
+        assert!(status.begin("b".to_string()).is_err());
+
+        status.failed(&ProxyError::validation("bad dimensions"));
+        assert_eq!(status.state, ModelSwapState::Failed);
+        status.begin("b".to_string()).unwrap();
+        assert_eq!(status.target_model.as_deref(), Some("b"));
+    }
+}
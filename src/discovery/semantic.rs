@@ -3,13 +3,17 @@
 //! This module implements semantic search capabilities for tool discovery using
 //! sentence transformers and persistent embedding storage.
 
+use crate::discovery::embedding_provider::{build_provider, EmbeddingInput, EmbeddingProvider, EmbeddingProviderConfig};
+use crate::discovery::ann_index::{AnnIndex, AnnIndexConfig};
+use crate::discovery::vector_store::{build_vector_store, VectorFilter, VectorPoint, VectorStore, VectorStoreConfig};
+use crate::discovery::local_embedding::{local_model_dimensions, LocalEmbedder, LOCAL_MODEL_PREFIX};
 use crate::error::{ProxyError, Result};
 use crate::registry::types::ToolDefinition;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{OnceCell, RwLock};
 use tracing::{debug, error, info, warn};
 
 /// Configuration for semantic search
@@ -21,6 +25,18 @@ pub struct SemanticSearchConfig {
     /// Embedding model name (e.g., "all-MiniLM-L6-v2")
     pub model_name: String,
     
+    /// Remote embedding provider; takes precedence over `model_name` when set
+    #[serde(default)]
+    pub provider: Option<EmbeddingProviderConfig>,
+    
+    /// Shared vector store, searched instead of the local embedding files
+    #[serde(default)]
+    pub vector_store: Option<VectorStoreConfig>,
+    
+    /// On-disk ANN index, searched instead of comparing every embedding
+    #[serde(default)]
+    pub ann_index: Option<AnnIndexConfig>,
+    
     /// Minimum similarity threshold for semantic matches
     pub similarity_threshold: f64,
     
@@ -99,6 +115,9 @@ impl Default for SemanticSearchConfig {
         Self {
             enabled: true,
             model_name: "all-MiniLM-L6-v2".to_string(),
+            provider: None,
+            vector_store: None,
+            ann_index: None,
             similarity_threshold: 0.7,
             max_results: 10,
             storage: StorageConfig {
@@ -149,6 +168,21 @@ pub struct ToolMetadata {
     
     /// Embedding dimensions
     pub embedding_dims: usize,
+    
+    /// Tool tags, for filtered search
+    #[serde(default)]
+    pub tags: Vec<String>,
+    
+    /// External MCP server the tool is proxied from, for filtered search
+    #[serde(default)]
+    pub server: Option<String>,
+}
+
+impl ToolMetadata {
+    /// External MCP server `tool_def` is proxied from
+    pub fn server_of(tool_def: &ToolDefinition) -> Option<String> {
+        tool_def.routing.config.get("server_name").and_then(|v| v.as_str()).map(str::to_string)
+    }
 }
 
 /// Semantic search result
@@ -249,6 +283,23 @@ impl EmbeddingStorage {
             .collect()
     }
     
+    /// Tools whose stored embedding doesn't have `dimensions` dimensions
+    pub fn dimension_mismatches(&self, dimensions: usize) -> Vec<String> {
+        self.embeddings
+            .iter()
+            .filter(|(_, embedding)| embedding.len() != dimensions)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+    
+    /// Distinct sizes of the stored embeddings, smallest first
+    pub fn dimensions(&self) -> Vec<usize> {
+        let mut dimensions: Vec<usize> = self.embeddings.values().map(Vec::len).collect();
+        dimensions.sort_unstable();
+        dimensions.dedup();
+        dimensions
+    }
+    
     /// Check if storage is dirty (needs saving)
     pub fn is_dirty(&self) -> bool {
         self.dirty
@@ -278,6 +329,18 @@ pub struct SemanticSearchService {
     
     /// Whether the model is loaded
     model_loaded: Arc<RwLock<bool>>,
+
+    /// In-process model, for `fastembed:` model names
+    local_model: Arc<OnceCell<Arc<LocalEmbedder>>>,
+    
+    /// Remote embedding provider, when `provider` is configured
+    provider: Arc<OnceCell<Arc<dyn EmbeddingProvider>>>,
+    
+    /// Shared vector store, when `vector_store` is configured
+    vector_store: Arc<OnceCell<Arc<dyn VectorStore>>>,
+    
+    /// ANN index, once opened for the embeddings' dimensions
+    ann: Arc<RwLock<Option<AnnIndex>>>,
 }
 
 impl SemanticSearchService {
@@ -287,6 +350,10 @@ impl SemanticSearchService {
             config,
             storage: Arc::new(RwLock::new(EmbeddingStorage::new())),
             model_loaded: Arc::new(RwLock::new(false)),
+            local_model: Arc::new(OnceCell::new()),
+            provider: Arc::new(OnceCell::new()),
+            vector_store: Arc::new(OnceCell::new()),
+            ann: Arc::new(RwLock::new(None)),
         }
     }
     
@@ -305,6 +372,15 @@ impl SemanticSearchService {
         // Load existing embeddings from storage
         self.load_embeddings().await?;
         
+        // Embeddings stored by a different model can't be compared with the provider's
+        if let Some(dimensions) = self.config.provider.as_ref().and_then(|p| p.expected_dimensions()) {
+            let mismatches = self.storage.read().await.dimension_mismatches(dimensions);
+            if !mismatches.is_empty() {
+                warn!("{} stored embeddings don't have the {} dimensions of the configured provider and will be regenerated",
+                      mismatches.len(), dimensions);
+            }
+        }
+        
         // Initialize the embedding model (lazy loading if enabled)
         if !self.config.performance.lazy_loading {
             self.load_model().await?;
@@ -424,6 +500,11 @@ impl SemanticSearchService {
         
         // Save embeddings
         self.save_embeddings_binary(&storage.embeddings).await?;
+        drop(storage);
+        
+        if let Some(index) = self.ann.write().await.as_mut() {
+            index.save()?;
+        }
         
         info!("Embeddings saved successfully");
         Ok(())
@@ -475,6 +556,13 @@ impl SemanticSearchService {
     async fn load_model(&self) -> Result<()> {
         info!("Initializing embedding model: {}", self.config.model_name);
         
+        if let Some(provider) = self.provider().await? {
+            info!("Using remote embedding provider: {}", provider.name());
+            let mut model_loaded = self.model_loaded.write().await;
+            *model_loaded = true;
+            return Ok(());
+        }
+        
         // Check if we're using an external API or local model
         match self.config.model_name.as_str() {
             name if name.starts_with("openai:") => {
@@ -498,6 +586,10 @@ impl SemanticSearchService {
                     warn!("Could not connect to Ollama server at {}: {}", ollama_url, e);
                 }
             }
+            name if name.starts_with(LOCAL_MODEL_PREFIX) => {
+                info!("Using in-process embedding model: {}", name);
+                self.local_embedder().await?;
+            }
             name if name.starts_with("local:") => {
                 info!("Using local embedding model: {}", name);
                 // For local models, we'd initialize the model here
@@ -516,9 +608,89 @@ impl SemanticSearchService {
         Ok(())
     }
     
-    /// Generate embedding for text using the configured model
-    pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        // Ensure model is loaded
+    /// Remote embedding provider, built on first use
+    async fn provider(&self) -> Result<Option<&Arc<dyn EmbeddingProvider>>> {
+        match &self.config.provider {
+            Some(config) => self.provider.get_or_try_init(|| async { build_provider(config) }).await.map(Some),
+            None => Ok(None),
+        }
+    }
+    
+    /// Shared vector store, built on first use
+    async fn vector_store(&self) -> Result<Option<&Arc<dyn VectorStore>>> {
+        match &self.config.vector_store {
+            Some(config) => self.vector_store.get_or_try_init(|| async { build_vector_store(config) }).await.map(Some),
+            None => Ok(None),
+        }
+    }
+    
+    /// Whether embeddings are shared through a vector store
+    pub fn has_vector_store(&self) -> bool {
+        self.config.vector_store.is_some()
+    }
+    
+    /// Store a tool's embedding locally and in the shared vector store
+    pub async fn index_tool_embedding(&self, embedding: Vec<f32>, metadata: ToolMetadata) -> Result<()> {
+        if let Some(store) = self.vector_store().await? {
+            store.upsert(&[VectorPoint { embedding: embedding.clone(), metadata: metadata.clone() }]).await?;
+        }
+        if self.config.ann_index.is_some() {
+            self.ensure_ann_index(embedding.len()).await?;
+            if let Some(index) = self.ann.write().await.as_mut() {
+                index.insert(&metadata.name, &embedding)?;
+            }
+        }
+        let mut storage = self.storage.write().await;
+        storage.add_tool_embedding(metadata.name.clone(), embedding, metadata);
+        Ok(())
+    }
+    
+    /// Remove a tool's embedding locally and from the shared vector store
+    pub async fn remove_tool_embedding(&self, tool_name: &str) -> Result<()> {
+        if let Some(store) = self.vector_store().await? {
+            store.delete(&[tool_name.to_string()]).await?;
+        }
+        if let Some(index) = self.ann.write().await.as_mut() {
+            index.remove(tool_name);
+        }
+        self.storage.write().await.remove_tool_embedding(tool_name);
+        Ok(())
+    }
+    
+    /// Open the ANN index for `dimensions`-sized embeddings, building it from
+    /// the stored embeddings if it's new
+    async fn ensure_ann_index(&self, dimensions: usize) -> Result<()> {
+        let Some(config) = &self.config.ann_index else { return Ok(()) };
+        let mut ann = self.ann.write().await;
+        if ann.as_ref().map(AnnIndex::dimensions) == Some(dimensions) {
+            return Ok(());
+        }
+        
+        let mut index = AnnIndex::open(config.clone(), dimensions)?;
+        if index.is_empty() {
+            let storage = self.storage.read().await;
+            for (tool_name, embedding) in storage.embeddings.iter().filter(|(_, embedding)| embedding.len() == dimensions) {
+                index.insert(tool_name, embedding)?;
+            }
+            if !index.is_empty() {
+                info!("Built ANN index of {} tool embeddings", index.len());
+                index.save()?;
+            }
+        }
+        *ann = Some(index);
+        Ok(())
+    }
+    
+    /// Embeddings another node already stored in the shared vector store
+    pub async fn shared_embeddings(&self, tool_names: &[String]) -> Result<Vec<VectorPoint>> {
+        match self.vector_store().await? {
+            Some(store) => store.get(tool_names).await,
+            None => Ok(Vec::new()),
+        }
+    }
+    
+    /// Make sure the model is loaded before embedding
+    async fn ensure_model_loaded(&self) -> Result<()> {
         if self.config.performance.lazy_loading {
             let model_loaded = self.model_loaded.read().await;
             if !*model_loaded {
@@ -526,6 +698,43 @@ impl SemanticSearchService {
                 self.load_model().await?;
             }
         }
+        Ok(())
+    }
+    
+    /// Generate embedding for text using the configured model
+    pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_text(text, EmbeddingInput::Document).await
+    }
+    
+    /// Generate embeddings for several texts, batching requests to a remote provider
+    pub async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.ensure_model_loaded().await?;
+        
+        match self.provider().await? {
+            Some(provider) => {
+                let embeddings = provider.embed(texts, EmbeddingInput::Document).await?;
+                Ok(embeddings.into_iter().map(|embedding| self.finish_embedding(embedding)).collect())
+            }
+            None => {
+                let mut embeddings = Vec::with_capacity(texts.len());
+                for text in texts {
+                    embeddings.push(self.generate_embedding(text).await?);
+                }
+                Ok(embeddings)
+            }
+        }
+    }
+    
+    /// Generate embedding for a document or a search query
+    async fn embed_text(&self, text: &str, input: EmbeddingInput) -> Result<Vec<f32>> {
+        self.ensure_model_loaded().await?;
+        
+        if let Some(provider) = self.provider().await? {
+            let embedding = provider.embed(&[text.to_string()], input).await?
+                .pop()
+                .ok_or_else(|| ProxyError::connection(format!("{} returned no embedding", provider.name())))?;
+            return Ok(self.finish_embedding(embedding));
+        }
         
         // Route to appropriate embedding method based on model configuration
         let embedding = match self.config.model_name.as_str() {
@@ -541,6 +750,12 @@ impl SemanticSearchService {
                 self.generate_api_embedding(text, &std::env::var("EMBEDDING_API_URL")
                     .unwrap_or_else(|_| "http://localhost:8080".to_string())).await?
             }
+            name if name.starts_with(LOCAL_MODEL_PREFIX) => {
+                let embedder = self.local_embedder().await?;
+                embedder.embed(vec![text.to_string()], 1).await?
+                    .pop()
+                    .ok_or_else(|| ProxyError::routing("Embedding model returned no embedding"))?
+            }
             name if name.starts_with("local:") => {
                 let model_path = name.strip_prefix("local:").unwrap_or("");
                 self.generate_local_embedding(text, model_path).await?
@@ -551,12 +766,7 @@ impl SemanticSearchService {
             }
         };
         
-        // Normalize if configured
-        let final_embedding = if self.config.model.normalize_embeddings {
-            self.normalize_embedding(embedding)
-        } else {
-            embedding
-        };
+        let final_embedding = self.finish_embedding(embedding);
         
         debug!("Generated {}-dimensional embedding for text: {}", final_embedding.len(), 
                if text.len() > 50 { format!("{}...", &text[..50]) } else { text.to_string() });
@@ -564,6 +774,15 @@ impl SemanticSearchService {
         Ok(final_embedding)
     }
     
+    /// Normalize if configured
+    fn finish_embedding(&self, embedding: Vec<f32>) -> Vec<f32> {
+        if self.config.model.normalize_embeddings {
+            self.normalize_embedding(embedding)
+        } else {
+            embedding
+        }
+    }
+    
     /// Generate embedding using OpenAI API
     async fn generate_openai_embedding(&self, text: &str, model: &str) -> Result<Vec<f32>> {
         use reqwest::Client;
@@ -605,6 +824,14 @@ impl SemanticSearchService {
         Ok(embedding)
     }
     
+    /// In-process model named by `model_name`, loaded into `model.cache_dir` on first use
+    async fn local_embedder(&self) -> Result<&Arc<LocalEmbedder>> {
+        let name = self.config.model_name.strip_prefix(LOCAL_MODEL_PREFIX).unwrap_or_default();
+        self.local_model
+            .get_or_try_init(|| LocalEmbedder::load(name, &self.config.model.cache_dir))
+            .await
+    }
+    
     /// Generate embedding using local model (placeholder for local inference)
     async fn generate_local_embedding(&self, text: &str, _model_path: &str) -> Result<Vec<f32>> {
         // This is a placeholder for local model inference
@@ -718,6 +945,9 @@ impl SemanticSearchService {
             name if name.starts_with("openai:text-embedding-3-large") => 3072,
             name if name.starts_with("ollama:") => 768, // nomic-embed-text is 768-dim
             name if name.starts_with("external:") => 768, // Default for external APIs
+            name if name.starts_with(LOCAL_MODEL_PREFIX) => {
+                local_model_dimensions(&name[LOCAL_MODEL_PREFIX.len()..]).unwrap_or(384)
+            }
             _ => 384, // Default fallback
         };
         
@@ -770,24 +1000,71 @@ impl SemanticSearchService {
     
     /// Search for similar tools using semantic similarity
     pub async fn search_similar_tools(&self, query: &str) -> Result<Vec<SemanticMatch>> {
+        self.search_similar_tools_filtered(query, &VectorFilter::default()).await
+    }
+    
+    /// Search for similar tools, restricted to those passing `filter`
+    pub async fn search_similar_tools_filtered(&self, query: &str, filter: &VectorFilter) -> Result<Vec<SemanticMatch>> {
         if !self.config.enabled {
             return Ok(Vec::new());
         }
         
         // Generate embedding for the query
-        let query_embedding = self.generate_embedding(query).await?;
+        let query_embedding = self.embed_text(query, EmbeddingInput::Query).await?;
+        
+        if let Some(store) = self.vector_store().await? {
+            let matches: Vec<SemanticMatch> = store
+                .search(&query_embedding, self.config.max_results, self.config.similarity_threshold, filter)
+                .await?
+                .into_iter()
+                .map(|(metadata, similarity_score)| SemanticMatch {
+                    tool_name: metadata.name,
+                    similarity_score,
+                    enabled: metadata.enabled,
+                    hidden: metadata.hidden,
+                })
+                .collect();
+            debug!("Found {} semantic matches in {} for query: '{}'", matches.len(), store.name(), query);
+            return Ok(matches);
+        }
         
         let storage = self.storage.read().await;
-        let mut matches = Vec::new();
+        let mismatches = storage.dimension_mismatches(query_embedding.len());
+        if !mismatches.is_empty() {
+            return Err(ProxyError::config(format!(
+                "{} stored tool embeddings (e.g. '{}') don't match the {}-dimensional embeddings of the configured model; \
+                 regenerate them with --pregenerate-embeddings",
+                mismatches.len(), mismatches[0], query_embedding.len()
+            )));
+        }
         
-        // Calculate similarity with all tool embeddings
-        for (tool_name, tool_embedding) in &storage.embeddings {
-            let similarity = self.calculate_cosine_similarity(&query_embedding, tool_embedding);
-            
+        let use_ann_index = self.config.ann_index.as_ref().is_some_and(|config| storage.embeddings.len() >= config.min_tools);
+        drop(storage);
+        
+        let similarities: Vec<(String, f64)> = if use_ann_index {
+            self.ensure_ann_index(query_embedding.len()).await?;
+            // Fetch extra candidates when filtering so filtered-out tools don't leave the results short
+            let limit = if filter.is_empty() { self.config.max_results } else { self.config.max_results * 10 };
+            let ann = self.ann.read().await;
+            match ann.as_ref() {
+                Some(index) => index.search(&query_embedding, limit)?,
+                None => Vec::new(),
+            }
+        } else {
+            // Calculate similarity with all tool embeddings
+            let storage = self.storage.read().await;
+            storage.embeddings.iter()
+                .map(|(tool_name, tool_embedding)| (tool_name.clone(), self.calculate_cosine_similarity(&query_embedding, tool_embedding)))
+                .collect()
+        };
+        
+        let storage = self.storage.read().await;
+        let mut matches = Vec::new();
+        for (tool_name, similarity) in similarities {
             if similarity >= self.config.similarity_threshold {
-                if let Some(metadata) = storage.get_metadata(tool_name) {
+                if let Some(metadata) = storage.get_metadata(&tool_name).filter(|metadata| filter.matches(metadata)) {
                     matches.push(SemanticMatch {
-                        tool_name: tool_name.clone(),
+                        tool_name,
                         similarity_score: similarity,
                         enabled: metadata.enabled,
                         hidden: metadata.hidden,
@@ -843,6 +1120,16 @@ impl SemanticSearchService {
         self.config.enabled
     }
     
+    /// Configuration the service was created with
+    pub fn config(&self) -> &SemanticSearchConfig {
+        &self.config
+    }
+    
+    /// Whether embeddings come from a remote provider
+    pub fn has_provider(&self) -> bool {
+        self.config.provider.is_some()
+    }
+    
     /// Get service statistics
     pub async fn get_stats(&self) -> HashMap<String, serde_json::Value> {
         let storage = self.storage.read().await;
@@ -851,6 +1138,12 @@ impl SemanticSearchService {
         let mut stats = HashMap::new();
         stats.insert("enabled".to_string(), serde_json::Value::Bool(self.config.enabled));
         stats.insert("model_name".to_string(), serde_json::Value::String(self.config.model_name.clone()));
+        if let Some(provider) = self.provider.get() {
+            stats.insert("embedding_provider".to_string(), serde_json::Value::String(provider.name()));
+        }
+        if let Some(store) = self.vector_store.get() {
+            stats.insert("vector_store".to_string(), serde_json::Value::String(store.name()));
+        }
         stats.insert("total_embeddings".to_string(), serde_json::Value::Number(total.into()));
         stats.insert("enabled_tools".to_string(), serde_json::Value::Number(enabled.into()));
         stats.insert("hidden_tools".to_string(), serde_json::Value::Number(hidden.into()));
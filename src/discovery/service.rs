@@ -5,28 +5,50 @@
 
 use crate::discovery::types::*;
 use crate::discovery::llm_mapper::{LlmParameterMapper, LlmMapperConfig};
-use crate::discovery::cache::{DiscoveryCache, DiscoveryCacheConfig, ToolMatchCacheKey, LlmCacheKey, create_schema_hash};
+use crate::discovery::cache::{DiscoveryCache, DiscoveryCacheConfig, ToolMatchCacheKey, LlmCacheKey, SemanticCacheKey, create_schema_hash};
+use crate::discovery::categories::{ToolCategories, ToolCategoriesConfig, CATEGORIES_TOOL_NAME};
+use crate::discovery::clarification::{ClarificationConfig, DiscoveryClarification};
+use crate::discovery::conversation::{carry_over_arguments, conversation_summary, is_follow_up};
+use crate::discovery::exclusions::DiscoveryExclusions;
 use crate::discovery::fallback::{FallbackManager, FallbackConfig, ErrorCategory, SmartDiscoveryError};
 use crate::discovery::semantic::{SemanticSearchService, SemanticSearchConfig};
 use crate::discovery::embedding_manager::{EmbeddingManager, EmbeddingManagerConfig};
+use crate::discovery::feedback::{DiscoveryFeedback, DiscoveryFeedbackConfig, FeedbackStore, ToolFeedbackStats, FEEDBACK_TOOL_NAME};
+use crate::discovery::keyword_index::{matched_metadata, KeywordIndex};
+use crate::discovery::model_swap::{clear_index_storage, model_label, validate_index_dimensions, EmbeddingModelChange, ModelSwapReport, ModelSwapStatus, SemanticIndex};
+use crate::discovery::usage_boost::{usage_boosts, UsageBoostConfig};
 use crate::error::{ProxyError, Result};
 use crate::registry::service::RegistryService;
+use crate::registry::toolset::caller_scope;
 use crate::registry::types::ToolDefinition;
 use crate::routing::Router;
 use crate::mcp::types::{ToolCall, ToolResult};
 use crate::metrics::tool_metrics::{ToolMetricsCollector, ToolExecutionRecord, ToolExecutionResult, DiscoveryRanking};
-use serde_json::json;
+use arc_swap::ArcSwapOption;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 use chrono::Utc;
 use uuid::Uuid;
 
+/// Name of the MCP tool that plans multi-step tool chains
+pub const PLAN_TOOL_NAME: &str = "smart_discovery_plan";
+
+/// Tools of each category listed in the category overview
+const CATEGORY_SAMPLE_TOOLS: usize = 5;
+
+/// Confidence multiplier applied to deprecated tools during discovery
+const DEPRECATED_TOOL_PENALTY: f64 = 0.5;
+
 /// Helper function for serde default value of true
 fn default_true() -> bool {
     true
 }
 
+fn default_max_chain_steps() -> usize {
+    5
+}
+
 /// Configuration for LLM-based tool selection
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct LlmToolSelectionConfig {
@@ -121,8 +143,87 @@ pub struct SmartDiscoveryConfig {
     #[serde(default = "default_true")]
     pub enable_sequential_mode: bool,
     
+    /// Maximum number of steps in a planned tool chain
+    #[serde(default = "default_max_chain_steps")]
+    pub max_chain_steps: usize,
+    
     /// Whether to enable tool metrics collection
     pub tool_metrics_enabled: Option<bool>,
+
+    /// How much each method contributes to a tool's score in hybrid mode
+    #[serde(default)]
+    pub hybrid_weights: HybridWeights,
+
+    /// Client feedback on discovery results, used to adjust rankings
+    #[serde(default)]
+    pub feedback: DiscoveryFeedbackConfig,
+
+    /// Ranking adjustments from tool success rates, usage and latency
+    #[serde(default)]
+    pub usage_boost: UsageBoostConfig,
+
+    /// Clarifying questions instead of running tools below the confidence threshold
+    #[serde(default)]
+    pub clarification: ClarificationConfig,
+
+    /// Categories derived by clustering the tool embeddings
+    #[serde(default)]
+    pub categories: ToolCategoriesConfig,
+}
+
+/// Weights of the methods combined in hybrid mode
+///
+/// A tool's hybrid score is the sum of each method's confidence times its
+/// weight, so with weights summing to 1.0 a tool every method is sure about
+/// scores 1.0. Setting a weight to 0.0 skips that method.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct HybridWeights {
+    /// Embedding similarity
+    pub semantic: f64,
+
+    /// BM25 over tool names, tags, keywords and descriptions
+    pub keyword: f64,
+
+    /// Rule-based name and description matching
+    pub rule_based: f64,
+
+    /// LLM evaluation (only when LLM tool selection is enabled)
+    pub llm: f64,
+}
+
+impl Default for HybridWeights {
+    fn default() -> Self {
+        Self {
+            semantic: 0.25,
+            keyword: 0.15,
+            rule_based: 0.10,
+            llm: 0.50,
+        }
+    }
+}
+
+impl HybridWeights {
+    /// Validate the weights
+    pub fn validate(&self) -> Result<()> {
+        let weights = [
+            ("semantic", self.semantic),
+            ("keyword", self.keyword),
+            ("rule_based", self.rule_based),
+            ("llm", self.llm),
+        ];
+        for (name, weight) in weights {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(ProxyError::config(format!(
+                    "smart_discovery.hybrid_weights.{} must be a non-negative number", name
+                )));
+            }
+        }
+        if weights.iter().all(|(_, weight)| *weight == 0.0) {
+            return Err(ProxyError::config("smart_discovery.hybrid_weights must not all be zero"));
+        }
+        Ok(())
+    }
 }
 
 impl Default for SmartDiscoveryConfig {
@@ -141,7 +242,13 @@ impl Default for SmartDiscoveryConfig {
             fallback: FallbackConfig::default(),
             semantic_search: SemanticSearchConfig::default(),
             enable_sequential_mode: true,
+            max_chain_steps: default_max_chain_steps(),
             tool_metrics_enabled: Some(true),
+            hybrid_weights: HybridWeights::default(),
+            feedback: DiscoveryFeedbackConfig::default(),
+            usage_boost: UsageBoostConfig::default(),
+            clarification: ClarificationConfig::default(),
+            categories: ToolCategoriesConfig::default(),
         }
     }
 }
@@ -166,14 +273,27 @@ pub struct SmartDiscoveryService {
     /// Agent router for executing discovered tools
     router: Arc<tokio::sync::RwLock<Option<Arc<Router>>>>,
     
-    /// Semantic search service
-    semantic_search: Option<Arc<SemanticSearchService>>,
+    /// Semantic search service and the embedding manager keeping it in sync;
+    /// replaced as a whole when the embedding model is swapped
+    semantic: ArcSwapOption<SemanticIndex>,
+    
+    /// Index replaced by the latest embedding model swap, kept for rollback
+    previous_semantic: std::sync::Mutex<Option<Arc<SemanticIndex>>>,
     
-    /// Embedding manager for dynamic lifecycle management
-    embedding_manager: Option<Arc<EmbeddingManager>>,
+    /// Progress of embedding model swaps
+    model_swap: std::sync::Mutex<ModelSwapStatus>,
     
     /// Tool metrics collector for tracking usage and performance
     tool_metrics: Option<Arc<ToolMetricsCollector>>,
+
+    /// BM25 index for hybrid mode, with the fingerprint of the tools it covers
+    keyword_index: tokio::sync::RwLock<Option<(u64, Arc<KeywordIndex>)>>,
+
+    /// Client feedback on discovery results (if enabled)
+    feedback: Option<Arc<FeedbackStore>>,
+
+    /// Tool categories from the latest clustering of the embeddings
+    categories: tokio::sync::RwLock<Option<Arc<ToolCategories>>>,
 }
 
 impl SmartDiscoveryService {
@@ -188,23 +308,16 @@ impl SmartDiscoveryService {
         let cache = DiscoveryCache::new(config.cache.clone());
         let fallback_manager = std::sync::Mutex::new(FallbackManager::new(config.fallback.clone()));
         
-        // Initialize semantic search service if enabled
-        let semantic_search = if config.semantic_search.enabled {
-            let service = SemanticSearchService::new(config.semantic_search.clone());
-            Some(Arc::new(service))
-        } else {
-            None
-        };
-        
-        // Initialize embedding manager if semantic search is enabled
-        let embedding_manager = if let Some(ref semantic_service) = semantic_search {
+        // Initialize semantic search service and its embedding manager if enabled
+        let semantic = if config.semantic_search.enabled {
+            let search = Arc::new(SemanticSearchService::new(config.semantic_search.clone()));
             let manager_config = EmbeddingManagerConfig::default();
             let manager = EmbeddingManager::new(
                 Arc::clone(&registry),
-                Arc::clone(semantic_service),
+                Arc::clone(&search),
                 manager_config,
             );
-            Some(Arc::new(manager))
+            Some(Arc::new(SemanticIndex { search, manager: Arc::new(manager) }))
         } else {
             None
         };
@@ -223,16 +336,26 @@ impl SmartDiscoveryService {
             None
         };
         
+        let feedback = if config.feedback.enabled {
+            Some(Arc::new(FeedbackStore::open(config.feedback.clone()).await))
+        } else {
+            None
+        };
+        
         Ok(Self { 
             registry, 
             config, 
             llm_mapper, 
             cache, 
             fallback_manager,
-            semantic_search,
-            embedding_manager,
+            semantic: ArcSwapOption::new(semantic),
+            previous_semantic: std::sync::Mutex::new(None),
+            model_swap: std::sync::Mutex::new(ModelSwapStatus::default()),
             router: Arc::new(tokio::sync::RwLock::new(router)),
             tool_metrics,
+            keyword_index: tokio::sync::RwLock::new(None),
+            feedback,
+            categories: tokio::sync::RwLock::new(None),
         })
     }
 
@@ -249,24 +372,300 @@ impl SmartDiscoveryService {
     
     /// Initialize the smart discovery service (call after construction)
     pub async fn initialize(&self) -> Result<()> {
-        if let Some(semantic_search) = &self.semantic_search {
-            semantic_search.initialize().await?;
+        if let Some(semantic) = self.semantic.load_full() {
+            semantic.search.initialize().await?;
             info!("Semantic search service initialized");
             
             // Initialize embedding manager
-            if let Some(embedding_manager) = &self.embedding_manager {
-                embedding_manager.initialize().await?;
-                info!("Embedding manager initialized");
+            semantic.manager.initialize().await?;
+            info!("Embedding manager initialized");
+        }
+        Ok(())
+    }
+    
+    /// Semantic search service in use (if enabled)
+    fn semantic_search(&self) -> Option<Arc<SemanticSearchService>> {
+        self.semantic.load().as_ref().map(|semantic| Arc::clone(&semantic.search))
+    }
+    
+    /// Re-synchronize tool embeddings with the current registry state.
+    /// Returns `None` when semantic search (and therefore the embedding manager) is disabled.
+    pub async fn sync_embeddings(&self) -> Result<Option<crate::discovery::embedding_manager::EmbeddingChangeSummary>> {
+        match self.semantic.load_full() {
+            Some(semantic) => Ok(Some(semantic.manager.sync_embeddings().await?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Embedding model in use and progress of the latest model swap
+    pub fn embedding_model_status(&self) -> ModelSwapStatus {
+        let mut status = self.model_swap.lock().map(|status| status.clone()).unwrap_or_default();
+        status.active_model = self.semantic.load().as_ref().map(|semantic| semantic.model());
+        status.rollback_model = self.previous_semantic.lock().ok()
+            .and_then(|previous| previous.as_ref().map(|semantic| semantic.model()));
+        status
+    }
+    
+    /// Start switching semantic search to another embedding model in the background.
+    /// Returns once the swap has started; `embedding_model_status` reports how it went.
+    pub fn start_embedding_model_swap(self: &Arc<Self>, change: EmbeddingModelChange) -> Result<ModelSwapStatus> {
+        let current = self.begin_model_swap(&change)?;
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            // The outcome is recorded in the swap status
+            let _ = service.run_model_swap(current, change).await;
+        });
+        Ok(self.embedding_model_status())
+    }
+    
+    /// Switch semantic search to another embedding model
+    ///
+    /// A complete index is built with the new model while the current one keeps
+    /// serving searches, and is only swapped in once its dimensions check out.
+    /// The replaced index is kept for `rollback_embedding_model`.
+    pub async fn swap_embedding_model(&self, change: EmbeddingModelChange) -> Result<ModelSwapReport> {
+        let current = self.begin_model_swap(&change)?;
+        self.run_model_swap(current, change).await
+    }
+    
+    /// Validate `change` and mark a swap as running, returning the index in use
+    fn begin_model_swap(&self, change: &EmbeddingModelChange) -> Result<Arc<SemanticIndex>> {
+        change.validate()?;
+        let current = self.semantic.load_full()
+            .ok_or_else(|| ProxyError::config("Semantic search is disabled"))?;
+        let target = model_label(&change.apply(current.search.config()));
+        self.model_swap.lock()
+            .map_err(|_| ProxyError::config("Embedding model status is unavailable"))?
+            .begin(target)?;
+        Ok(current)
+    }
+    
+    /// Build the new index, swap it in and record the outcome
+    async fn run_model_swap(&self, current: Arc<SemanticIndex>, change: EmbeddingModelChange) -> Result<ModelSwapReport> {
+        let result = self.build_and_swap_index(current, &change).await;
+        match &result {
+            Ok(report) => info!("Swapped embedding model {} for {}: {} tools, {} dimensions, built in {}ms",
+                                report.previous_model, report.model, report.tools_indexed, report.dimensions, report.duration_ms),
+            Err(e) => error!("Embedding model swap failed, keeping the current model: {}", e),
+        }
+        if let Ok(mut status) = self.model_swap.lock() {
+            status.finish(&result);
+        }
+        result
+    }
+    
+    async fn build_and_swap_index(&self, current: Arc<SemanticIndex>, change: &EmbeddingModelChange) -> Result<ModelSwapReport> {
+        let start_time = std::time::Instant::now();
+        let config = change.apply(current.search.config());
+        info!("Building embedding index with {} in {}", model_label(&config),
+              config.storage.embeddings_file.parent().map(|dir| dir.display().to_string()).unwrap_or_default());
+        clear_index_storage(&config).await?;
+        
+        let search = Arc::new(SemanticSearchService::new(config));
+        search.initialize().await?;
+        let manager = Arc::new(EmbeddingManager::new(
+            Arc::clone(&self.registry),
+            Arc::clone(&search),
+            EmbeddingManagerConfig::default(),
+        ));
+        let summary = manager.sync_embeddings().await?;
+        if summary.failed > 0 {
+            let errors: Vec<String> = summary.operations.iter()
+                .filter(|operation| !operation.success)
+                .take(3)
+                .map(|operation| format!("{}: {}", operation.tool_name, operation.error.as_deref().unwrap_or("unknown error")))
+                .collect();
+            return Err(ProxyError::validation(format!(
+                "Failed to embed {} tools with the new model: {}", summary.failed, errors.join("; ")
+            )));
+        }
+        let expected = search.config().provider.as_ref().and_then(|provider| provider.expected_dimensions());
+        let dimensions = validate_index_dimensions(&search.storage.read().await.dimensions(), expected)?;
+        search.save_embeddings().await?;
+        
+        // Keep the new index in sync with the registry from here on
+        manager.initialize().await?;
+        let semantic = Arc::new(SemanticIndex { search, manager });
+        let report = ModelSwapReport {
+            model: semantic.model(),
+            previous_model: current.model(),
+            dimensions,
+            tools_indexed: summary.created + summary.updated,
+            duration_ms: start_time.elapsed().as_millis() as u64,
+        };
+        
+        self.semantic.store(Some(semantic));
+        // Cached request embeddings come from the old model
+        self.cache.clear_all().await;
+        if let Err(e) = current.manager.shutdown().await {
+            warn!("Failed to shut down the replaced embedding manager: {}", e);
+        }
+        if let Ok(mut previous) = self.previous_semantic.lock() {
+            *previous = Some(current);
+        }
+        Ok(report)
+    }
+    
+    /// Put back the index the latest embedding model swap replaced
+    ///
+    /// It first catches up on registry changes made since the swap. The index
+    /// it replaces is kept in turn, so rolling back again undoes the rollback.
+    pub async fn rollback_embedding_model(&self) -> Result<ModelSwapStatus> {
+        let target = self.previous_semantic.lock().ok()
+            .and_then(|previous| previous.as_ref().map(|semantic| semantic.model()))
+            .ok_or_else(|| ProxyError::validation("There is no previous embedding model to roll back to"))?;
+        self.model_swap.lock()
+            .map_err(|_| ProxyError::config("Embedding model status is unavailable"))?
+            .begin(target)?;
+        
+        let result = self.restore_previous_index().await;
+        if let Ok(mut status) = self.model_swap.lock() {
+            match &result {
+                Ok(()) => status.rolled_back(),
+                Err(e) => status.failed(e),
+            }
+        }
+        result?;
+        Ok(self.embedding_model_status())
+    }
+    
+    async fn restore_previous_index(&self) -> Result<()> {
+        let previous = self.previous_semantic.lock().ok().and_then(|mut previous| previous.take())
+            .ok_or_else(|| ProxyError::validation("There is no previous embedding model to roll back to"))?;
+        if let Err(e) = previous.manager.initialize().await {
+            if let Ok(mut slot) = self.previous_semantic.lock() {
+                *slot = Some(previous);
+            }
+            return Err(e);
+        }
+        
+        info!("Rolling back to embedding model {}", previous.model());
+        let replaced = self.semantic.swap(Some(previous));
+        self.cache.clear_all().await;
+        if let Some(replaced) = replaced {
+            if let Err(e) = replaced.manager.shutdown().await {
+                warn!("Failed to shut down the replaced embedding manager: {}", e);
+            }
+            if let Ok(mut slot) = self.previous_semantic.lock() {
+                *slot = Some(replaced);
             }
         }
         Ok(())
     }
     
+    /// Recompute the tool categories every `categories.refresh_interval_secs` in the background
+    ///
+    /// Does nothing when categories or semantic search are disabled.
+    pub fn start_category_refresh(self: &Arc<Self>) {
+        if !self.config.categories.enabled || self.semantic.load().is_none() {
+            return;
+        }
+        let service = Arc::downgrade(self);
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(self.config.categories.refresh_interval_secs));
+        tokio::spawn(async move {
+            loop {
+                ticker.tick().await;
+                // Stop once the service is gone
+                let Some(service) = service.upgrade() else { break };
+                if let Err(e) = service.refresh_categories().await {
+                    warn!("Failed to refresh tool categories: {}", e);
+                }
+            }
+        });
+    }
+    
+    /// Cluster the tool embeddings into categories and tag the tools with them
+    ///
+    /// Returns `None`, and removes the category tags, when too few tools have embeddings.
+    pub async fn refresh_categories(&self) -> Result<Option<Arc<ToolCategories>>> {
+        if !self.config.categories.enabled {
+            return Err(ProxyError::config("Tool categories are disabled"));
+        }
+        let search = self.semantic_search()
+            .ok_or_else(|| ProxyError::config("Tool categories need semantic search"))?;
+        
+        let tools = self.registry.get_enabled_tools();
+        let embeddings: HashMap<String, Vec<f32>> = {
+            let storage = search.storage.read().await;
+            tools.iter()
+                .filter_map(|(name, _)| storage.get_embedding(name).map(|embedding| (name.clone(), embedding.clone())))
+                .collect()
+        };
+        let categories = ToolCategories::build(&tools, &embeddings, &self.config.categories).map(Arc::new);
+        match &categories {
+            Some(categories) => info!("Derived {} tool categories from {} embedded tools", categories.categories.len(), embeddings.len()),
+            None => debug!("Only {} tools have embeddings, too few for categories", embeddings.len()),
+        }
+        
+        self.registry.set_generated_tags(categories.as_ref().map(|categories| categories.tags()).unwrap_or_default())?;
+        *self.categories.write().await = categories.clone();
+        Ok(categories)
+    }
+    
+    /// Categories from the latest refresh (if any)
+    pub async fn tool_categories(&self) -> Option<Arc<ToolCategories>> {
+        self.categories.read().await.clone()
+    }
+    
+    /// Overview of the categories, or the tools of one `category` (given by name or tag)
+    pub async fn browse_categories(&self, category: Option<&str>) -> Result<serde_json::Value> {
+        let categories = self.tool_categories().await
+            .ok_or_else(|| ProxyError::validation("There are no tool categories yet"))?;
+        
+        let Some(category) = category else {
+            let overview: Vec<serde_json::Value> = categories.categories.iter()
+                .map(|category| serde_json::json!({
+                    "name": category.name,
+                    "tag": category.tag,
+                    "keywords": category.keywords,
+                    "tool_count": category.tools.len(),
+                    "sample_tools": category.tools.iter().take(CATEGORY_SAMPLE_TOOLS).collect::<Vec<_>>(),
+                }))
+                .collect();
+            return Ok(serde_json::json!({
+                "categories": overview,
+                "generated_at": categories.generated_at,
+            }));
+        };
+        
+        let category = categories.get(category)
+            .ok_or_else(|| ProxyError::validation(format!("Unknown category '{}'", category)))?;
+        let tools: Vec<serde_json::Value> = category.tools.iter()
+            .map(|name| serde_json::json!({
+                "name": name,
+                "description": self.registry.get_tool(name).map(|tool| tool.description.clone()).unwrap_or_default(),
+            }))
+            .collect();
+        Ok(serde_json::json!({
+            "name": category.name,
+            "tag": category.tag,
+            "keywords": category.keywords,
+            "tools": tools,
+        }))
+    }
+    
     /// Get the tool metrics collector (if enabled)
     pub fn tool_metrics(&self) -> Option<Arc<ToolMetricsCollector>> {
         self.tool_metrics.clone()
     }
 
+    /// Get the discovery feedback store (if enabled)
+    pub fn feedback(&self) -> Option<Arc<FeedbackStore>> {
+        self.feedback.clone()
+    }
+
+    /// Record whether a discovered tool solved the request it was selected for
+    pub async fn record_feedback(&self, feedback: DiscoveryFeedback) -> Result<ToolFeedbackStats> {
+        let store = self.feedback.as_ref()
+            .ok_or_else(|| ProxyError::config("Discovery feedback is disabled"))?;
+        for tool_name in std::iter::once(&feedback.tool_name).chain(feedback.expected_tool.as_ref()) {
+            if self.registry.get_tool(tool_name).is_none() {
+                return Err(ProxyError::validation(format!("Tool '{}' not found", tool_name)));
+            }
+        }
+        store.record(feedback).await
+    }
+
     /// Process a smart discovery request
     pub fn discover_and_execute(&self, request: SmartDiscoveryRequest) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<SmartDiscoveryResponse>> + Send + '_>> {
         Box::pin(async move {
@@ -281,6 +680,14 @@ impl SmartDiscoveryService {
             ).await;
         }
 
+        // Resolve follow-ups against the conversation, remembering the call a follow-up may repeat
+        let original_request = request.request.clone();
+        let previous_call = request.conversation.as_ref()
+            .filter(|_| is_follow_up(&request.request))
+            .and_then(|conversation| conversation.tool_calls.last().cloned());
+        let request = self.resolve_conversation(request).await;
+        let resolved_request = (request.request != original_request).then(|| request.request.clone());
+
         // Check if sequential mode is enabled and request is multi-step
         let sequential_mode = self.config.enable_sequential_mode && request.sequential_mode.unwrap_or(self.config.enable_sequential_mode);
         let mut original_request_for_next_step: Option<SmartDiscoveryRequest> = None;
@@ -316,6 +723,10 @@ impl SmartDiscoveryService {
             Ok(match_) => match_,
             Err(e) => {
                 warn!("Failed to select best tool match: {}", e);
+                if let Some(clarification) = self.clarify(&tool_matches, &effective_request) {
+                    info!("❓ Asking which tool was meant instead of running a low-confidence match");
+                    return Ok(self.create_clarification_response(clarification, &tool_matches));
+                }
                 return self.create_error_response_with_fallback(
                     format!("Failed to select best tool match: {}", e),
                     &if tool_matches.is_empty() { ErrorCategory::NoToolsFound } else { ErrorCategory::LowConfidence },
@@ -348,7 +759,14 @@ impl SmartDiscoveryService {
         let schema_hash = create_schema_hash(&tool_def.input_schema);
         let llm_cache_key = LlmCacheKey::new(&effective_request, &best_match.tool_name, &schema_hash);
         
-        let parameter_extraction = if let Some(cached_extraction) = self.cache.get_llm_response(&llm_cache_key).await {
+        let semantic_key = self.semantic_cache_key(&effective_request).await;
+        let cached_extraction = match (self.cache.get_llm_response(&llm_cache_key).await, &semantic_key) {
+            (Some(extraction), _) => Some(extraction),
+            (None, Some(semantic_key)) => self.cache.semantic().get_parameter_extraction(semantic_key, &best_match.tool_name, &schema_hash).await,
+            (None, None) => None,
+        };
+        
+        let mut parameter_extraction = if let Some(cached_extraction) = cached_extraction {
             info!("✅ Using cached LLM parameter extraction for tool: {}", best_match.tool_name);
             info!("📦 Cached extraction status: {:?}", cached_extraction.status);
             if let Ok(params_json) = serde_json::to_string_pretty(&cached_extraction.parameters) {
@@ -372,6 +790,9 @@ impl SmartDiscoveryService {
                     
                     // Cache the LLM response for future use
                     self.cache.store_llm_response(llm_cache_key, extraction.clone()).await;
+                    if let Some(ref semantic_key) = semantic_key {
+                        self.cache.semantic().store_parameter_extraction(semantic_key, &best_match.tool_name, &schema_hash, extraction.clone()).await;
+                    }
                     info!("💾 Cached LLM parameter extraction for tool: {}", best_match.tool_name);
                     extraction
                 }
@@ -386,6 +807,14 @@ impl SmartDiscoveryService {
             }
         };
         
+        // A follow-up to the tool called last keeps the arguments it doesn't change
+        if let Some(previous) = previous_call.as_ref().filter(|previous| previous.tool_name == best_match.tool_name) {
+            let carried = carry_over_arguments(&mut parameter_extraction, previous, &tool_def);
+            if !carried.is_empty() {
+                info!("🔁 Carried over {:?} from the earlier '{}' call", carried, previous.tool_name);
+            }
+        }
+        
         // Record tool usage for fallback statistics
         if let Ok(mut fallback_manager) = self.fallback_manager.lock() {
             fallback_manager.record_tool_usage(&best_match.tool_name);
@@ -405,19 +834,13 @@ impl SmartDiscoveryService {
         metadata.original_tool = Some(best_match.tool_name.clone());
         metadata.confidence_score = best_match.confidence_score;
         metadata.reasoning = Some(best_match.reasoning.clone());
+        metadata.score_breakdown = Some(best_match.breakdown.clone());
+        metadata.resolved_request = resolved_request;
         metadata.mapped_parameters = Some(parameter_extraction.parameters.clone());
         metadata.extraction_status = Some(format!("{:?}", parameter_extraction.status));
         
         // Include all tool candidates with their confidence scores for debugging and analysis
-        let tool_candidates: Vec<crate::discovery::types::ToolCandidateInfo> = tool_matches.iter().map(|m| {
-            crate::discovery::types::ToolCandidateInfo {
-                tool_name: m.tool_name.clone(),
-                confidence_score: m.confidence_score,
-                reasoning: m.reasoning.clone(),
-                meets_threshold: m.meets_threshold,
-            }
-        }).collect();
-        metadata.tool_candidates = Some(tool_candidates);
+        metadata.tool_candidates = Some(tool_matches.iter().map(ToolCandidateInfo::from).collect());
         
         // Record discovery rankings for tool metrics
         if let Some(ref metrics_collector) = self.tool_metrics {
@@ -630,6 +1053,7 @@ impl SmartDiscoveryService {
                 error_details: None,
                 metadata: metadata.clone(),
                 next_step: None,
+                clarification: None,
             };
             
             match self.generate_next_step_recommendation(original_req, &effective_request, &temp_response).await {
@@ -668,6 +1092,7 @@ impl SmartDiscoveryService {
             },
             metadata,
             next_step,
+            clarification: None,
         })
         })
     }
@@ -678,7 +1103,7 @@ impl SmartDiscoveryService {
         let cache_key = ToolMatchCacheKey::from_request(request, &self.config.tool_selection_mode);
         if let Some(cached_matches) = self.cache.get_tool_matches(&cache_key).await {
             debug!("Using cached tool matches for request: {} (mode: {})", request.request, self.config.tool_selection_mode);
-            return Ok(cached_matches);
+            return Ok(self.apply_ranking_boosts(cached_matches, request).await);
         }
         
         // Get all enabled tools (both visible and hidden for smart discovery)
@@ -688,15 +1113,15 @@ impl SmartDiscoveryService {
             // Filter out smart_tool_discovery from cached tools as well
             cached_tools.into_iter()
                 .filter(|(tool_name, _)| {
-                    tool_name != "smart_discovery_tool" && tool_name != "smart_tool_discovery"
+                    tool_name != "smart_discovery_tool" && tool_name != "smart_tool_discovery" && tool_name != FEEDBACK_TOOL_NAME && tool_name != PLAN_TOOL_NAME && tool_name != CATEGORIES_TOOL_NAME
                 })
                 .collect()
         } else {
             let tools: Vec<(String, ToolDefinition)> = self.registry.get_enabled_tools()
                 .into_iter()
                 .filter(|(tool_name, _)| {
-                    // Skip smart_discovery_tool itself to avoid recursion
-                    tool_name != "smart_discovery_tool" && tool_name != "smart_tool_discovery"
+                    // Skip smart_discovery_tool itself to avoid recursion, and its feedback tool
+                    tool_name != "smart_discovery_tool" && tool_name != "smart_tool_discovery" && tool_name != FEEDBACK_TOOL_NAME && tool_name != PLAN_TOOL_NAME && tool_name != CATEGORIES_TOOL_NAME
                 })
                 .collect();
             // Cache the registry tools for future use
@@ -707,6 +1132,49 @@ impl SmartDiscoveryService {
         
         debug!("Found {} discoverable tools to search", all_tools.len());
         
+        // Then for a paraphrase of a recent request
+        let semantic_key = self.semantic_cache_key(request).await;
+        if let Some(ref semantic_key) = semantic_key {
+            self.cache.semantic().check_registry(self.registry_fingerprint(&all_tools)).await;
+            if let Some(cached_matches) = self.cache.semantic().get_tool_matches(semantic_key).await {
+                debug!("Using semantically cached tool matches for request: {}", request.request);
+                return Ok(self.apply_ranking_boosts(cached_matches, request).await);
+            }
+        }
+        
+        // Drop the tools the request rules out, so no strategy can rank them
+        let exclusions = DiscoveryExclusions::for_request(request);
+        let all_tools: Vec<(String, ToolDefinition)> = if exclusions.is_empty() {
+            all_tools
+        } else {
+            let before = all_tools.len();
+            let kept: Vec<(String, ToolDefinition)> = all_tools.into_iter()
+                .filter(|(name, tool_def)| match exclusions.excludes(name, tool_def) {
+                    Some(reason) => {
+                        debug!("Excluding tool {}: {}", name, reason);
+                        false
+                    }
+                    None => true,
+                })
+                .collect();
+            info!("Excluded {} of {} tools ({})", before - kept.len(), before, exclusions.describe());
+            kept
+        };
+
+        // Callers limited to a toolset or API key scopes only discover the tools they could call
+        let scope = caller_scope();
+        let all_tools: Vec<(String, ToolDefinition)> = if scope.is_unrestricted() {
+            all_tools
+        } else {
+            let filter = self.registry.scope_filter(&scope)?;
+            let before = all_tools.len();
+            let kept: Vec<(String, ToolDefinition)> = all_tools.into_iter()
+                .filter(|(_, tool_def)| filter.matches(tool_def))
+                .collect();
+            debug!("Caller scope leaves {} of {} tools", kept.len(), before);
+            kept
+        };
+
         // Choose tool selection method based on configuration
         let matches = match self.config.tool_selection_mode.as_str() {
             "llm_based" => {
@@ -727,12 +1195,134 @@ impl SmartDiscoveryService {
             }
         };
         
+        let mut matches = self.down_rank_deprecated_tools(matches, &all_tools, request);
+        for tool_match in matches.iter_mut() {
+            if let Some((name, tool_def)) = all_tools.iter().find(|(name, _)| *name == tool_match.tool_name) {
+                tool_match.breakdown.matched_metadata = matched_metadata(&request.request, name, tool_def);
+            }
+        }
+        
         debug!("Found {} potential tool matches", matches.len());
         
-        // Cache the results for future use
+        // Cache the results for future use; boosts are applied on the way out so new feedback and metrics count at once
         self.cache.store_tool_matches(cache_key, matches.clone()).await;
+        if let Some(semantic_key) = semantic_key {
+            self.cache.semantic().store_tool_matches(semantic_key, matches.clone()).await;
+        }
         
-        Ok(matches)
+        Ok(self.apply_ranking_boosts(matches, request).await)
+    }
+
+    /// Semantic cache key for `request`, when semantic caching is on and the request can be embedded
+    async fn semantic_cache_key(&self, request: &SmartDiscoveryRequest) -> Option<SemanticCacheKey> {
+        let semantic_cache = self.cache.semantic();
+        if !semantic_cache.is_enabled() {
+            return None;
+        }
+        let semantic_search = self.semantic_search()?;
+
+        let embedding = match semantic_cache.get_embedding(&request.request).await {
+            Some(embedding) => embedding,
+            None => match semantic_search.generate_embedding(&request.request).await {
+                Ok(embedding) => {
+                    semantic_cache.store_embedding(&request.request, embedding.clone()).await;
+                    embedding
+                }
+                Err(e) => {
+                    debug!("Skipping semantic cache, failed to embed request: {}", e);
+                    return None;
+                }
+            },
+        };
+        Some(SemanticCacheKey::new(request, &self.config.tool_selection_mode, embedding))
+    }
+
+    /// Fingerprint of the discoverable tools, which changes whenever the registry does
+    fn registry_fingerprint(&self, tools: &[(String, ToolDefinition)]) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.registry.metadata().last_updated.hash(&mut hasher);
+        for (name, tool) in tools {
+            name.hash(&mut hasher);
+            tool.description.hash(&mut hasher);
+            tool.tags.hash(&mut hasher);
+            tool.input_schema.to_string().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Adjust confidence by the feedback clients reported on each tool and by its usage metrics
+    async fn apply_ranking_boosts(&self, mut matches: Vec<ToolMatch>, request: &SmartDiscoveryRequest) -> Vec<ToolMatch> {
+        let feedback_boosts = match &self.feedback {
+            Some(feedback) => feedback.boosts().await,
+            None => HashMap::new(),
+        };
+        let usage_adjustments = match self.tool_metrics.as_ref().filter(|_| self.config.usage_boost.enabled) {
+            Some(tool_metrics) => usage_boosts(&tool_metrics.get_usage().await, &self.config.usage_boost, Utc::now()),
+            None => HashMap::new(),
+        };
+        if feedback_boosts.is_empty() && usage_adjustments.is_empty() {
+            return matches;
+        }
+
+        let threshold = self.get_confidence_threshold(request);
+        for tool_match in matches.iter_mut() {
+            let feedback_boost = feedback_boosts.get(&tool_match.tool_name).copied();
+            let usage_boost = usage_adjustments.get(&tool_match.tool_name);
+            if feedback_boost.is_none() && usage_boost.is_none() {
+                continue;
+            }
+
+            if let Some(boost) = feedback_boost {
+                tool_match.confidence_score = (tool_match.confidence_score + boost).clamp(0.0, 1.0);
+                tool_match.reasoning = format!("{} (feedback {:+.3})", tool_match.reasoning, boost);
+                tool_match.breakdown.feedback_boost = Some(boost);
+                if boost < 0.0 {
+                    tool_match.breakdown.penalties.push(format!("feedback {:+.3}: clients reported it didn't solve their requests", boost));
+                }
+            }
+            if let Some(usage_boost) = usage_boost {
+                tool_match.confidence_score = (tool_match.confidence_score + usage_boost.boost).clamp(0.0, 1.0);
+                tool_match.reasoning = format!("{} ({})", tool_match.reasoning, usage_boost.describe());
+                if usage_boost.reliability < 0.0 {
+                    tool_match.breakdown.penalties.push(format!("unreliable: reliability {:+.2}", usage_boost.reliability));
+                }
+                if usage_boost.latency < 0.0 {
+                    tool_match.breakdown.penalties.push(format!("slow: latency {:+.2}", usage_boost.latency));
+                }
+                tool_match.breakdown.usage_boost = Some(usage_boost.clone());
+            }
+            tool_match.meets_threshold = tool_match.confidence_score >= threshold;
+        }
+        matches.sort_by(|a, b| b.confidence_score.partial_cmp(&a.confidence_score).unwrap_or(std::cmp::Ordering::Equal));
+        matches
+    }
+
+    /// Lower the confidence of deprecated tools so non-deprecated alternatives win ties
+    fn down_rank_deprecated_tools(
+        &self,
+        mut matches: Vec<ToolMatch>,
+        all_tools: &[(String, ToolDefinition)],
+        request: &SmartDiscoveryRequest,
+    ) -> Vec<ToolMatch> {
+        let deprecated: HashSet<&str> = all_tools.iter()
+            .filter(|(_, tool_def)| tool_def.is_deprecated())
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if deprecated.is_empty() {
+            return matches;
+        }
+
+        let threshold = self.get_confidence_threshold(request);
+        for tool_match in matches.iter_mut().filter(|m| deprecated.contains(m.tool_name.as_str())) {
+            tool_match.confidence_score *= DEPRECATED_TOOL_PENALTY;
+            tool_match.meets_threshold = tool_match.confidence_score >= threshold;
+            tool_match.reasoning = format!("{} (deprecated)", tool_match.reasoning);
+            tool_match.breakdown.penalties.push(format!("deprecated: confidence x{}", DEPRECATED_TOOL_PENALTY));
+        }
+        matches.sort_by(|a, b| b.confidence_score.partial_cmp(&a.confidence_score).unwrap_or(std::cmp::Ordering::Equal));
+        matches
     }
 
     /// Select the best tool match from the candidates
@@ -759,8 +1349,11 @@ impl SmartDiscoveryService {
         // Find the best match that meets the threshold
         let best_match = matches.iter()
             .find(|m| m.meets_threshold)
-            .or_else(|| matches.first()) // Fall back to highest confidence even if below threshold
-            .ok_or_else(|| ProxyError::validation("No suitable tool match found"))?;
+            // A tool the client asked for, e.g. answering a clarifying question, is taken even below the threshold
+            .or_else(|| matches.iter().find(|m| request.preferred_tools.as_ref().is_some_and(|preferred| preferred.contains(&m.tool_name))))
+            // Without clarifying questions, fall back to highest confidence even if below threshold
+            .or_else(|| matches.first().filter(|_| !self.config.clarification.enabled))
+            .ok_or_else(|| ProxyError::validation(format!("No tool meets the confidence threshold of {:.2}", threshold)))?;
         
         info!("Tool Discovery - SELECTED: '{}' with confidence {:.2} (meets_threshold: {})", 
               best_match.tool_name, best_match.confidence_score, best_match.meets_threshold);
@@ -1129,6 +1722,7 @@ impl SmartDiscoveryService {
                     help_instructions: Some(
                         "For more help, try: 1) Rephrasing your request, 2) Being more specific, 3) Providing examples".to_string()
                     ),
+                    clarification: None,
                 })
             } else {
                 None
@@ -1138,6 +1732,39 @@ impl SmartDiscoveryService {
         })
     }
 
+    /// Clarifying question for a request no tool confidently matches, if clarification is enabled
+    fn clarify(&self, matches: &[ToolMatch], request: &SmartDiscoveryRequest) -> Option<DiscoveryClarification> {
+        if !self.config.clarification.enabled {
+            return None;
+        }
+        DiscoveryClarification::build(
+            &request.request,
+            matches,
+            self.get_confidence_threshold(request),
+            &self.config.clarification,
+            |tool_name| self.registry.get_tool(tool_name).map(|tool_def| tool_def.description.clone()),
+        )
+    }
+
+    /// Response asking the client which tool was meant
+    fn create_clarification_response(&self, clarification: DiscoveryClarification, matches: &[ToolMatch]) -> SmartDiscoveryResponse {
+        let mut metadata = SmartDiscoveryMetadata::default();
+        metadata.confidence_score = clarification.candidates[0].confidence_score;
+        metadata.reasoning = Some(clarification.message.clone());
+        metadata.tool_candidates = Some(matches.iter().map(ToolCandidateInfo::from).collect());
+
+        SmartDiscoveryResponse {
+            success: false,
+            data: Some(serde_json::json!({ "clarification": clarification })),
+            error: Some("Clarification needed: no tool is a confident match for the request".to_string()),
+            error_summary: Some(clarification.message.clone()),
+            error_details: None,
+            metadata,
+            next_step: None,
+            clarification: Some(clarification),
+        }
+    }
+
     /// Create an error response
     fn create_error_response(
         &self,
@@ -1153,6 +1780,7 @@ impl SmartDiscoveryService {
             error_details: None,
             metadata: metadata.unwrap_or_default(),
             next_step: None,
+            clarification: None,
         })
     }
 
@@ -1201,13 +1829,22 @@ impl SmartDiscoveryService {
     /// Get cache statistics
     pub async fn get_cache_stats(&self) -> serde_json::Value {
         let stats = self.cache.get_stats().await;
+        let semantic_stats = self.cache.semantic().get_stats().await;
         serde_json::json!({
             "enabled": self.cache.is_enabled(),
             "hits": stats.hits,
             "misses": stats.misses,
             "hit_rate": stats.hit_rate,
             "evictions": stats.evictions,
-            "entries": stats.entries
+            "entries": stats.entries,
+            "semantic": {
+                "enabled": self.cache.semantic().is_enabled(),
+                "hits": semantic_stats.hits,
+                "misses": semantic_stats.misses,
+                "hit_rate": semantic_stats.hit_rate,
+                "evictions": semantic_stats.evictions,
+                "entries": semantic_stats.entries
+            }
         })
     }
 
@@ -1346,6 +1983,7 @@ impl SmartDiscoveryService {
                         confidence_score: confidence,
                         reasoning: format!("Preferred tool '{}' with confidence {:.2}", tool_name, confidence),
                         meets_threshold: confidence >= self.get_confidence_threshold(request),
+                        breakdown: ScoreBreakdown { rule_based: Some(confidence), ..ScoreBreakdown::default() },
                     });
                 }
             }
@@ -1388,6 +2026,7 @@ impl SmartDiscoveryService {
                         confidence_score: confidence,
                         reasoning: self.generate_reasoning(tool_def, request, confidence),
                         meets_threshold: confidence >= self.get_confidence_threshold(request),
+                        breakdown: ScoreBreakdown { rule_based: Some(confidence), ..ScoreBreakdown::default() },
                     });
                     
                     // Check if we have enough high-quality matches to stop early
@@ -1677,6 +2316,8 @@ impl SmartDiscoveryService {
                 confidence_threshold: None,
                 include_error_details: None,
                 sequential_mode: None,
+                conversation: None,
+                exclusions: None,
             };
             
             // Check if tool would match without constraints
@@ -1924,6 +2565,12 @@ impl SmartDiscoveryService {
                     debug!("🔍 Tool constraint analysis: {} - confidence: {:.3} -> {:.3}, violations: {}, can_fulfill: {}", 
                            tool_name, confidence, adjusted_confidence, constraint_violations, can_fulfill);
                     
+                    let mut breakdown = ScoreBreakdown { llm: Some(adjusted_confidence), ..ScoreBreakdown::default() };
+                    if adjusted_confidence < confidence {
+                        breakdown.penalties.push(format!(
+                            "{} constraint violation: LLM score {:.3} -> {:.3}", constraint_violations, confidence, adjusted_confidence
+                        ));
+                    }
                     matches.push(ToolMatch {
                         tool_name: tool_name.to_string(),
                         confidence_score: adjusted_confidence,
@@ -1935,7 +2582,10 @@ impl SmartDiscoveryService {
                             confidence_threshold: None,
                             include_error_details: None,
                             sequential_mode: None,
+                            conversation: None,
+                            exclusions: None,
                         }),
+                        breakdown,
                     });
                 }
             }
@@ -1951,6 +2601,8 @@ impl SmartDiscoveryService {
                 confidence_threshold: None,
                 include_error_details: None,
                 sequential_mode: None,
+                conversation: None,
+                exclusions: None,
             };
             
             for (tool_name, tool_def) in tools {
@@ -1960,6 +2612,7 @@ impl SmartDiscoveryService {
                     confidence_score: confidence,
                     reasoning: format!("Rule-based fallback: {}", self.generate_reasoning(tool_def, &request, confidence)),
                     meets_threshold: confidence >= self.get_confidence_threshold(&request),
+                    breakdown: ScoreBreakdown { rule_based: Some(confidence), ..ScoreBreakdown::default() },
                 });
             }
         }
@@ -2092,7 +2745,7 @@ impl SmartDiscoveryService {
         let mut matches = Vec::new();
         
         // Check if semantic search is available
-        let semantic_search = match &self.semantic_search {
+        let semantic_search = match self.semantic_search() {
             Some(service) => service,
             None => {
                 warn!("Semantic search not available, falling back to rule-based");
@@ -2120,6 +2773,7 @@ impl SmartDiscoveryService {
                     confidence_score: semantic_match.similarity_score,
                     reasoning,
                     meets_threshold: semantic_match.similarity_score >= self.get_confidence_threshold(request),
+                    breakdown: ScoreBreakdown { semantic: Some(semantic_match.similarity_score), ..ScoreBreakdown::default() },
                 });
             }
         }
@@ -2141,6 +2795,7 @@ impl SmartDiscoveryService {
                             confidence_score: confidence,
                             reasoning: format!("Preferred tool (rule-based: {:.3})", confidence),
                             meets_threshold: confidence >= self.get_confidence_threshold(request),
+                            breakdown: ScoreBreakdown { rule_based: Some(confidence), ..ScoreBreakdown::default() },
                         });
                     }
                 }
@@ -2156,12 +2811,13 @@ impl SmartDiscoveryService {
         Ok(matches)
     }
     
-    /// Hybrid tool matching combining semantic, rule-based, and optionally LLM approaches
-    /// Weight distribution: LLM (55%), Semantic (30%), Rule-based (15%)
+    /// Hybrid tool matching combining semantic, keyword, rule-based, and optionally LLM approaches
+    /// Weighted by `hybrid_weights`, by default LLM (50%), Semantic (25%), Keyword (15%), Rule-based (10%)
     async fn find_matching_tools_hybrid(&self, request: &SmartDiscoveryRequest, all_tools: &[(String, ToolDefinition)]) -> Result<Vec<ToolMatch>> {
         info!("Starting hybrid tool matching with {} strategies", 
-              if self.config.llm_tool_selection.enabled { 3 } else { 2 });
+              if self.config.llm_tool_selection.enabled { 4 } else { 3 });
         
+        let weights = &self.config.hybrid_weights;
         let mut all_matches: HashMap<String, ToolMatch> = HashMap::new();
         
         // Weighted scoring approach: Each method contributes to a tool's total confidence score
@@ -2170,8 +2826,10 @@ impl SmartDiscoveryService {
         
         info!("Running semantic and rule-based evaluation for {} tools", all_tools.len());
         
-        // Run semantic search for all tools - Weight: 30%
-        let semantic_result = if let Some(semantic_search) = &self.semantic_search {
+        // Run semantic search for all tools
+        let semantic_result = if weights.semantic <= 0.0 {
+            Vec::new()
+        } else if let Some(semantic_search) = self.semantic_search() {
             match semantic_search.search_similar_tools(&request.request).await {
                 Ok(matches) => {
                     info!("✅ Semantic search completed: {} matches found", matches.len());
@@ -2187,27 +2845,41 @@ impl SmartDiscoveryService {
             Vec::new()
         };
         
-        // Run rule-based evaluation for all tools - Weight: 15%
-        let rule_based_result = match self.find_matching_tools_rule_based(request, all_tools).await {
-            Ok(matches) => {
-                info!("✅ Rule-based search completed: {} matches found", matches.len());
-                matches
-            }
-            Err(e) => {
-                warn!("❌ Rule-based search failed: {}", e);
-                Vec::new()
+        // Run BM25 keyword search for all tools
+        let keyword_result = if weights.keyword > 0.0 {
+            let matches = self.keyword_index(all_tools).await.search(&request.request, all_tools.len());
+            info!("✅ Keyword search completed: {} matches found", matches.len());
+            matches
+        } else {
+            Vec::new()
+        };
+        
+        // Run rule-based evaluation for all tools
+        let rule_based_result = if weights.rule_based <= 0.0 {
+            Vec::new()
+        } else {
+            match self.find_matching_tools_rule_based(request, all_tools).await {
+                Ok(matches) => {
+                    info!("✅ Rule-based search completed: {} matches found", matches.len());
+                    matches
+                }
+                Err(e) => {
+                    warn!("❌ Rule-based search failed: {}", e);
+                    Vec::new()
+                }
             }
         };
         
         // Process semantic matches
         for semantic_match in semantic_result {
             if let Some((_, _tool_def)) = all_tools.iter().find(|(name, _)| name == &semantic_match.tool_name) {
-                let weighted_score = semantic_match.similarity_score * 0.30; // Weight semantic as 30%
+                let weighted_score = semantic_match.similarity_score * weights.semantic;
                 let tool_match = ToolMatch {
                     tool_name: semantic_match.tool_name.clone(),
                     confidence_score: weighted_score,
                     reasoning: format!("Semantic: {:.3}", semantic_match.similarity_score),
                     meets_threshold: false, // Will be recalculated
+                    breakdown: ScoreBreakdown { semantic: Some(semantic_match.similarity_score), ..ScoreBreakdown::default() },
                 };
                 let tool_name = semantic_match.tool_name.clone();
                 all_matches.insert(semantic_match.tool_name, tool_match);
@@ -2215,14 +2887,36 @@ impl SmartDiscoveryService {
             }
         }
         
+        // Process keyword matches
+        for (tool_name, score) in keyword_result {
+            let weighted_score = score * weights.keyword;
+            
+            if let Some(existing) = all_matches.get_mut(&tool_name) {
+                existing.confidence_score += weighted_score;
+                existing.reasoning = format!("{}, BM25: {:.3}", existing.reasoning, score);
+                existing.breakdown.keyword = Some(score);
+                debug!("📊 Tool '{}' enhanced via BM25, combined score: {:.3}", existing.tool_name, existing.confidence_score);
+            } else {
+                debug!("📊 Tool '{}' added via BM25 with score {:.3}", tool_name, weighted_score);
+                all_matches.insert(tool_name.clone(), ToolMatch {
+                    tool_name,
+                    confidence_score: weighted_score,
+                    reasoning: format!("BM25: {:.3}", score),
+                    meets_threshold: false, // Will be recalculated
+                    breakdown: ScoreBreakdown { keyword: Some(score), ..ScoreBreakdown::default() },
+                });
+            }
+        }
+        
         // Process rule-based matches
         for rule_match in rule_based_result {
-            let weighted_score = rule_match.confidence_score * 0.15; // Weight rule-based as 15%
+            let weighted_score = rule_match.confidence_score * weights.rule_based;
             
             if let Some(existing) = all_matches.get_mut(&rule_match.tool_name) {
-                // Combine with existing semantic score
+                // Combine with existing semantic and keyword scores
                 existing.confidence_score += weighted_score;
                 existing.reasoning = format!("{}, Rule: {:.3}", existing.reasoning, rule_match.confidence_score);
+                existing.breakdown.rule_based = Some(rule_match.confidence_score);
                 debug!("📊 Tool '{}' enhanced via Rule-based, combined score: {:.3}", existing.tool_name, existing.confidence_score);
             } else {
                 // Tool only found by rule-based
@@ -2231,16 +2925,17 @@ impl SmartDiscoveryService {
                     confidence_score: weighted_score,
                     reasoning: format!("Rule: {:.3}", rule_match.confidence_score),
                     meets_threshold: false, // Will be recalculated
+                    breakdown: ScoreBreakdown { rule_based: Some(rule_match.confidence_score), ..ScoreBreakdown::default() },
                 };
                 all_matches.insert(rule_match.tool_name.clone(), tool_match);
                 debug!("📊 Tool '{}' added via Rule-based with score {:.3}", rule_match.tool_name, weighted_score);
             }
         }
         
-        info!("🔗 Combined scoring complete: {} tools have semantic+rule scores", all_matches.len());
+        info!("🔗 Combined scoring complete: {} tools have semantic+keyword+rule scores", all_matches.len());
         
-        // 3. LLM-based matches (if enabled and available) - Weight: 55%
-        if self.config.llm_tool_selection.enabled {
+        // LLM-based matches (if enabled and available)
+        if self.config.llm_tool_selection.enabled && weights.llm > 0.0 {
             // Multi-criteria selection: 30 tools total for balanced cost/coverage
             // - 10 from top scorers (best semantic+rule matches)
             // - 5 random sample (discovery of unexpected matches)  
@@ -2255,17 +2950,20 @@ impl SmartDiscoveryService {
                     Ok(llm_matches) => {
                         info!("LLM evaluation found {} matches", llm_matches.len());
                         for llm_match in llm_matches {
-                            let weighted_score = llm_match.confidence_score * 0.55; // Weight LLM as 55%
+                            let weighted_score = llm_match.confidence_score * weights.llm;
                             
                             if let Some(existing) = all_matches.get_mut(&llm_match.tool_name) {
                                 existing.confidence_score += weighted_score;
                                 existing.reasoning = format!("{}, LLM: {:.3}", existing.reasoning, llm_match.confidence_score);
+                                existing.breakdown.llm = Some(llm_match.confidence_score);
+                                existing.breakdown.penalties.extend(llm_match.breakdown.penalties);
                             } else {
                                 all_matches.insert(llm_match.tool_name.clone(), ToolMatch {
                                     tool_name: llm_match.tool_name,
                                     confidence_score: weighted_score,
                                     reasoning: format!("LLM: {:.3}", llm_match.confidence_score),
                                     meets_threshold: false, // Will be recalculated
+                                    breakdown: llm_match.breakdown,
                                 });
                             }
                         }
@@ -2279,11 +2977,12 @@ impl SmartDiscoveryService {
         
         // Convert to final matches and recalculate threshold compliance
         // Note: Final confidence scores represent weighted combination of all methods
-        // Examples with new weights:
-        // - Perfect LLM only: 1.0 * 0.55 = 0.55
-        // - Perfect Semantic only: 1.0 * 0.30 = 0.30  
-        // - Perfect Rule-based only: 1.0 * 0.15 = 0.15
-        // - Perfect all methods: 1.0 * 0.55 + 1.0 * 0.30 + 1.0 * 0.15 = 1.0
+        // Examples with the default weights:
+        // - Perfect LLM only: 1.0 * 0.50 = 0.50
+        // - Perfect Semantic only: 1.0 * 0.25 = 0.25
+        // - Perfect Keyword only: 1.0 * 0.15 = 0.15
+        // - Perfect Rule-based only: 1.0 * 0.10 = 0.10
+        // - Perfect all methods: 0.50 + 0.25 + 0.15 + 0.10 = 1.0
         let threshold = self.get_confidence_threshold(request);
         let mut final_matches: Vec<ToolMatch> = all_matches.into_values()
             .map(|mut m| {
@@ -2303,6 +3002,33 @@ impl SmartDiscoveryService {
         Ok(final_matches)
     }
     
+    /// BM25 index over `tools`, rebuilt when the tools' searchable text changes
+    async fn keyword_index(&self, tools: &[(String, ToolDefinition)]) -> Arc<KeywordIndex> {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for (name, tool) in tools {
+            name.hash(&mut hasher);
+            tool.description.hash(&mut hasher);
+            tool.tags.hash(&mut hasher);
+            tool.annotations.as_ref()
+                .and_then(|annotations| annotations.get(crate::discovery::keyword_index::KEYWORDS_ANNOTATION))
+                .hash(&mut hasher);
+        }
+        let fingerprint = hasher.finish();
+
+        if let Some((cached, index)) = self.keyword_index.read().await.as_ref() {
+            if *cached == fingerprint {
+                return Arc::clone(index);
+            }
+        }
+
+        let index = Arc::new(KeywordIndex::build(tools.iter().map(|(name, tool)| (name, tool))));
+        debug!("Built keyword index over {} tools", index.len());
+        *self.keyword_index.write().await = Some((fingerprint, Arc::clone(&index)));
+        index
+    }
+    
     /// Select LLM candidates using multi-criteria approach for optimal cost/coverage balance
     /// Returns up to 30 tools: 10 top scorers + 5 random + 5 low scorers + 10 category-matched
     async fn select_llm_candidates(
@@ -2538,6 +3264,227 @@ impl SmartDiscoveryService {
         Ok(None)
     }
 
+    /// Rank the candidate tools for a request with their score breakdowns, without executing anything
+    pub async fn explain_discovery(&self, request: &SmartDiscoveryRequest) -> Result<DiscoveryExplanation> {
+        if !self.config.enabled {
+            return Err(ProxyError::config("Smart discovery is disabled"));
+        }
+        if request.request.trim().is_empty() {
+            return Err(ProxyError::validation("Discovery explanation requires a request"));
+        }
+        let request = &self.resolve_conversation(request.clone()).await;
+
+        let matches = self.find_matching_tools(request).await?;
+        let selected_tool = self.select_best_tool_match(&matches, request).ok().map(|best_match| best_match.tool_name);
+        Ok(DiscoveryExplanation {
+            request: request.request.clone(),
+            tool_selection_mode: self.config.tool_selection_mode.clone(),
+            confidence_threshold: self.get_confidence_threshold(request),
+            selected_tool,
+            candidates: matches.iter().map(ToolCandidateInfo::from).collect(),
+        })
+    }
+
+    /// Propose an ordered chain of tool calls for a request that needs several tools
+    ///
+    /// Nothing is executed: each step gets its best tool match and the
+    /// parameters that could be mapped from the request. A single-step
+    /// request produces a one-step chain.
+    pub async fn plan_tool_chain(&self, request: &SmartDiscoveryRequest) -> Result<ToolChainPlan> {
+        if !self.config.enabled {
+            return Err(ProxyError::config("Smart discovery is disabled"));
+        }
+        if request.request.trim().is_empty() {
+            return Err(ProxyError::validation("Tool chain planning requires a request"));
+        }
+        let request = &self.resolve_conversation(request.clone()).await;
+
+        let mut step_requests = self.decompose_into_steps(request).await;
+        step_requests.truncate(self.config.max_chain_steps.max(1));
+        info!("🔗 Planning a tool chain of {} steps for request: {}", step_requests.len(), request.request);
+
+        let mut steps: Vec<ToolChainStep> = Vec::with_capacity(step_requests.len());
+        for step_text in step_requests {
+            // Tell parameter mapping where the step fits, so it doesn't expect outputs of earlier steps in the request
+            let mut context = format!("Step {} of the request \"{}\"", steps.len() + 1, request.request);
+            if !steps.is_empty() {
+                let earlier: Vec<String> = steps.iter()
+                    .map(|step| format!("{} ({})", step.request, step.tool_name.as_deref().unwrap_or("no tool")))
+                    .collect();
+                context.push_str(&format!(". Earlier steps: {}", earlier.join("; ")));
+            }
+            if let Some(ref extra) = request.context {
+                context.push_str(&format!(". {}", extra));
+            }
+
+            let step_request = SmartDiscoveryRequest {
+                request: step_text,
+                context: Some(context),
+                preferred_tools: request.preferred_tools.clone(),
+                confidence_threshold: request.confidence_threshold,
+                include_error_details: None,
+                sequential_mode: Some(false),
+                conversation: request.conversation.clone(),
+                exclusions: Some(DiscoveryExclusions::for_request(request)),
+            };
+            steps.push(self.plan_chain_step(steps.len() + 1, &step_request).await);
+        }
+
+        let complete = !steps.is_empty() && steps.iter().all(|step| {
+            step.tool_name.is_some() && step.meets_threshold && step.missing_parameters.is_empty()
+        });
+        Ok(ToolChainPlan {
+            request: request.request.clone(),
+            steps,
+            complete,
+        })
+    }
+
+    /// Match a tool and map parameters for one step of a tool chain
+    async fn plan_chain_step(&self, step: usize, request: &SmartDiscoveryRequest) -> ToolChainStep {
+        let mut planned = ToolChainStep {
+            step,
+            request: request.request.clone(),
+            tool_name: None,
+            confidence_score: 0.0,
+            meets_threshold: false,
+            reasoning: String::new(),
+            parameters: HashMap::new(),
+            missing_parameters: Vec::new(),
+        };
+
+        let best_match = match self.find_matching_tools(request).await.and_then(|matches| self.select_best_tool_match(&matches, request)) {
+            Ok(best_match) => best_match,
+            Err(e) => {
+                planned.reasoning = e.to_string();
+                return planned;
+            }
+        };
+        let Some(tool_def) = self.registry.get_tool(&best_match.tool_name) else {
+            planned.reasoning = format!("Tool '{}' not found in registry", best_match.tool_name);
+            return planned;
+        };
+
+        match self.llm_mapper.extract_parameters(request, &tool_def).await {
+            Ok(extraction) => planned.parameters = extraction.parameters,
+            Err(e) => warn!("Parameter extraction failed for chain step {} ('{}'): {}", step, best_match.tool_name, e),
+        }
+        planned.missing_parameters = tool_def.input_schema.get("required")
+            .and_then(|required| required.as_array())
+            .map(|required| required.iter()
+                .filter_map(|name| name.as_str())
+                .filter(|name| !planned.parameters.contains_key(*name))
+                .map(str::to_string)
+                .collect())
+            .unwrap_or_default();
+
+        planned.tool_name = Some(best_match.tool_name);
+        planned.confidence_score = best_match.confidence_score;
+        planned.meets_threshold = best_match.meets_threshold;
+        planned.reasoning = best_match.reasoning;
+        planned
+    }
+
+    /// Break a request into the steps it needs, with the LLM when it's available
+    async fn decompose_into_steps(&self, request: &SmartDiscoveryRequest) -> Vec<String> {
+        #[derive(serde::Deserialize)]
+        struct ChainSteps {
+            steps: Vec<String>,
+        }
+
+        let prompt = format!(
+            r#"Break this request into the ordered steps needed to fulfil it. Each step must be a single action that one tool can perform.
+
+USER REQUEST: "{}"
+
+EXAMPLES:
+Request: "Find the PR for issue 42 and post a summary to Slack"
+Response: {{"steps": ["Find the pull request for issue 42", "Post a summary of the pull request to Slack"]}}
+
+Request: "Read the config file"
+Response: {{"steps": ["Read the config file"]}}
+
+Respond with JSON only: {{"steps": ["..."]}}"#,
+            request.request
+        );
+
+        match self.call_llm_for_chain_plan(&prompt).await {
+            Ok(response) => match serde_json::from_str::<ChainSteps>(&self.extract_json_from_response(&response)) {
+                Ok(chain) => {
+                    let steps: Vec<String> = chain.steps.into_iter()
+                        .map(|step| step.trim().to_string())
+                        .filter(|step| !step.is_empty())
+                        .collect();
+                    if !steps.is_empty() {
+                        return steps;
+                    }
+                }
+                Err(e) => warn!("Failed to parse tool chain steps from LLM response: {}", e),
+            },
+            Err(e) => debug!("Splitting request into steps without LLM: {}", e),
+        }
+
+        split_request_steps(&request.request)
+    }
+
+    /// Resolve a request against the conversation it belongs to
+    ///
+    /// The conversation is summarised into the context, and a follow-up is
+    /// rewritten into a standalone request with the tool called last
+    /// preferred. The resolved request carries no conversation, so resolving
+    /// it again changes nothing.
+    async fn resolve_conversation(&self, mut request: SmartDiscoveryRequest) -> SmartDiscoveryRequest {
+        let Some(conversation) = request.conversation.take().filter(|conversation| !conversation.is_empty()) else {
+            return request;
+        };
+        let summary = conversation_summary(&conversation);
+
+        if is_follow_up(&request.request) {
+            if let Some(previous) = conversation.tool_calls.last() {
+                let preferred_tools = request.preferred_tools.get_or_insert_with(Vec::new);
+                if !preferred_tools.contains(&previous.tool_name) {
+                    preferred_tools.insert(0, previous.tool_name.clone());
+                }
+            }
+
+            if self.config.llm_mapper.enabled {
+                // The rewrite may leave out exclusions stated in words, so they are kept apart
+                let exclusions = DiscoveryExclusions::for_request(&request);
+                if !exclusions.is_empty() {
+                    request.exclusions = Some(exclusions);
+                }
+
+                let prompt = format!(
+                    r#"Rewrite the user's latest request so it can be understood without the conversation.
+Replace references like "the same", "it" or "again" with what they refer to, keep everything the user changed, and don't add anything else.
+
+{}
+
+Latest request: "{}"
+
+Respond with ONLY the rewritten request:"#,
+                    summary, request.request
+                );
+                match self.llm_mapper.complete(&prompt, 200).await {
+                    Ok(resolved) => {
+                        let resolved = resolved.trim().trim_matches('"').trim();
+                        if !resolved.is_empty() {
+                            info!("💬 Resolved follow-up \"{}\" to \"{}\"", request.request, resolved);
+                            request.request = resolved.to_string();
+                        }
+                    }
+                    Err(e) => warn!("Failed to resolve follow-up request with LLM: {}", e),
+                }
+            }
+        }
+
+        request.context = Some(match request.context.take() {
+            Some(context) => format!("{}\n\n{}", context, summary),
+            None => summary,
+        });
+        request
+    }
+
     /// Check if a request is likely to be multi-step
     fn is_likely_multi_step(&self, request: &str) -> bool {
         let request_lower = request.to_lowercase();
@@ -2588,6 +3535,8 @@ Extract the first step as a simple, clear request:"#,
                 confidence_threshold: request.confidence_threshold,
                 include_error_details: request.include_error_details,
                 sequential_mode: Some(false), // Don't recurse
+                conversation: request.conversation.clone(),
+                exclusions: Some(DiscoveryExclusions::for_request(request)),
             });
         }
 
@@ -2678,259 +3627,19 @@ Respond in JSON format:
 
     /// Call LLM for first step extraction
     async fn call_llm_for_first_step(&self, prompt: &str) -> Result<String> {
-        // Use the same LLM configuration as parameter extraction
-        let config = &self.config.llm_mapper;
-        
-        if !config.enabled {
-            return Err(ProxyError::routing("LLM mapper is disabled".to_string()));
-        }
-        
-        match config.provider.as_str() {
-            "openai" | "openai-compatible" => {
-                self.call_openai_llm_sequential(prompt, "first_step").await
-            }
-            "anthropic" => {
-                self.call_anthropic_llm_sequential(prompt, "first_step").await
-            }
-            "ollama" => {
-                self.call_ollama_llm_sequential(prompt, "first_step").await
-            }
-            _ => Err(ProxyError::routing(format!("Unsupported LLM provider: {}", config.provider)))
-        }
-    }
-
-    /// Call LLM for next step recommendation
-    async fn call_llm_for_next_step(&self, prompt: &str) -> Result<String> {
-        // Use the same LLM configuration as parameter extraction
-        let config = &self.config.llm_mapper;
-        
-        if !config.enabled {
-            return Err(ProxyError::routing("LLM mapper is disabled".to_string()));
-        }
-        
-        match config.provider.as_str() {
-            "openai" | "openai-compatible" => {
-                self.call_openai_llm_sequential(prompt, "next_step").await
-            }
-            "anthropic" => {
-                self.call_anthropic_llm_sequential(prompt, "next_step").await
-            }
-            "ollama" => {
-                self.call_ollama_llm_sequential(prompt, "next_step").await
-            }
-            _ => Err(ProxyError::routing(format!("Unsupported LLM provider: {}", config.provider)))
-        }
+        // Shorter response for first step
+        self.llm_mapper.complete(prompt, 500).await
     }
 
-    /// Call OpenAI LLM for sequential operations
-    async fn call_openai_llm_sequential(&self, prompt: &str, operation_type: &str) -> Result<String> {
-        let config = &self.config.llm_mapper;
-        
-        let api_key = if let Some(key) = &config.api_key {
-            key.clone()
-        } else if let Some(env_var) = &config.api_key_env {
-            std::env::var(env_var).map_err(|_| ProxyError::routing("API key environment variable not set".to_string()))?
-        } else {
-            return Err(ProxyError::routing("API key required for OpenAI LLM".to_string()));
-        };
-
-        let base_url = config.base_url.as_ref()
-            .map(|u| u.clone())
-            .unwrap_or_else(|| "https://api.openai.com/v1".to_string());
-
-        let max_tokens = match operation_type {
-            "first_step" => Some(500), // Shorter response for first step
-            "next_step" => Some(800),  // Longer for JSON response
-            _ => Some(600),
-        };
-
-        let request_body = json!({
-            "model": config.model,
-            "messages": [
-                {
-                    "role": "user",
-                    "content": prompt
-                }
-            ],
-            "temperature": 0.1,
-            "max_tokens": max_tokens
-        });
-
-        let url = format!("{}/chat/completions", base_url);
-        
-        debug!("🤖 Calling OpenAI LLM for {}: {}", operation_type, url);
-        
-        let client = reqwest::Client::new();
-        let response = tokio::time::timeout(
-            std::time::Duration::from_secs(config.timeout),
-            client
-                .post(&url)
-                .header("Authorization", format!("Bearer {}", api_key))
-                .header("Content-Type", "application/json")
-                .json(&request_body)
-                .send()
-        )
-        .await
-        .map_err(|_| ProxyError::routing("LLM request timeout".to_string()))?
-        .map_err(|e| ProxyError::routing(format!("LLM request failed: {}", e)))?;
-
-        let status = response.status();
-        let response_text = response.text().await
-            .map_err(|e| ProxyError::routing(format!("Failed to read LLM response: {}", e)))?;
-
-        if !status.is_success() {
-            return Err(ProxyError::routing(format!(
-                "LLM request failed with status {}: {}", status, response_text
-            )));
-        }
-
-        let openai_response: serde_json::Value = serde_json::from_str(&response_text)
-            .map_err(|e| ProxyError::routing(format!("Failed to parse LLM response: {}", e)))?;
-
-        let content = openai_response["choices"][0]["message"]["content"]
-            .as_str()
-            .ok_or_else(|| ProxyError::routing("No content in LLM response".to_string()))?
-            .to_string();
-
-        debug!("🤖 LLM {} response: {}", operation_type, content);
-        Ok(content)
+    /// Call LLM for tool chain planning
+    async fn call_llm_for_chain_plan(&self, prompt: &str) -> Result<String> {
+        self.llm_mapper.complete(prompt, 600).await
     }
 
-    /// Call Anthropic LLM for sequential operations
-    async fn call_anthropic_llm_sequential(&self, prompt: &str, operation_type: &str) -> Result<String> {
-        let config = &self.config.llm_mapper;
-        
-        let api_key = if let Some(key) = &config.api_key {
-            key.clone()
-        } else if let Some(env_var) = &config.api_key_env {
-            std::env::var(env_var).map_err(|_| ProxyError::routing("API key environment variable not set".to_string()))?
-        } else {
-            return Err(ProxyError::routing("API key required for Anthropic LLM".to_string()));
-        };
-
-        let base_url = config.base_url.as_ref()
-            .map(|u| u.clone())
-            .unwrap_or_else(|| "https://api.anthropic.com/v1".to_string());
-
-        let max_tokens = match operation_type {
-            "first_step" => 500,
-            "next_step" => 800,
-            _ => 600,
-        };
-
-        let request_body = json!({
-            "model": config.model,
-            "max_tokens": max_tokens,
-            "messages": [
-                {
-                    "role": "user",
-                    "content": prompt
-                }
-            ]
-        });
-
-        let url = format!("{}/messages", base_url);
-        
-        debug!("🤖 Calling Anthropic LLM for {}: {}", operation_type, url);
-        
-        let client = reqwest::Client::new();
-        let response = tokio::time::timeout(
-            std::time::Duration::from_secs(config.timeout),
-            client
-                .post(&url)
-                .header("x-api-key", api_key)
-                .header("Content-Type", "application/json")
-                .header("anthropic-version", "2023-06-01")
-                .json(&request_body)
-                .send()
-        )
-        .await
-        .map_err(|_| ProxyError::routing("LLM request timeout".to_string()))?
-        .map_err(|e| ProxyError::routing(format!("LLM request failed: {}", e)))?;
-
-        let status = response.status();
-        let response_text = response.text().await
-            .map_err(|e| ProxyError::routing(format!("Failed to read LLM response: {}", e)))?;
-
-        if !status.is_success() {
-            return Err(ProxyError::routing(format!(
-                "LLM request failed with status {}: {}", status, response_text
-            )));
-        }
-
-        let anthropic_response: serde_json::Value = serde_json::from_str(&response_text)
-            .map_err(|e| ProxyError::routing(format!("Failed to parse LLM response: {}", e)))?;
-
-        let content = anthropic_response["content"][0]["text"]
-            .as_str()
-            .ok_or_else(|| ProxyError::routing("No content in Anthropic response".to_string()))?
-            .to_string();
-
-        debug!("🤖 Anthropic {} response: {}", operation_type, content);
-        Ok(content)
-    }
-
-    /// Call Ollama LLM for sequential operations
-    async fn call_ollama_llm_sequential(&self, prompt: &str, operation_type: &str) -> Result<String> {
-        let config = &self.config.llm_mapper;
-        
-        let base_url = config.base_url.as_ref()
-            .map(|u| u.clone())
-            .unwrap_or_else(|| "http://localhost:11434".to_string());
-
-        let max_predict = match operation_type {
-            "first_step" => 500,
-            "next_step" => 800,
-            _ => 600,
-        };
-
-        let request_body = json!({
-            "model": config.model,
-            "prompt": prompt,
-            "stream": false,
-            "options": {
-                "temperature": 0.1,
-                "num_predict": max_predict
-            }
-        });
-
-        let url = format!("{}/api/generate", base_url);
-        
-        debug!("🤖 Calling Ollama LLM for {}: {}", operation_type, url);
-        
-        let client = reqwest::Client::new();
-        let response = tokio::time::timeout(
-            std::time::Duration::from_secs(config.timeout),
-            client
-                .post(&url)
-                .header("Content-Type", "application/json")
-                .json(&request_body)
-                .send()
-        )
-        .await
-        .map_err(|_| ProxyError::routing("LLM request timeout".to_string()))?
-        .map_err(|e| ProxyError::routing(format!("LLM request failed: {}", e)))?;
-
-        let status = response.status();
-        let response_text = response.text().await
-            .map_err(|e| ProxyError::routing(format!("Failed to read LLM response: {}", e)))?;
-
-        if !status.is_success() {
-            return Err(ProxyError::routing(format!(
-                "LLM request failed with status {}: {}", status, response_text
-            )));
-        }
-
-        let ollama_response: serde_json::Value = serde_json::from_str(&response_text)
-            .map_err(|e| ProxyError::routing(format!("Failed to parse LLM response: {}", e)))?;
-
-        let content = ollama_response["response"]
-            .as_str()
-            .ok_or_else(|| ProxyError::routing("No response in Ollama response".to_string()))?
-            .to_string();
-
-        debug!("🤖 Ollama {} response: {}", operation_type, content);
-        Ok(content)
+    /// Call LLM for next step recommendation
+    async fn call_llm_for_next_step(&self, prompt: &str) -> Result<String> {
+        // Longer for JSON response
+        self.llm_mapper.complete(prompt, 800).await
     }
 
     #[cfg(test)]
@@ -2952,6 +3661,8 @@ Respond in JSON format:
                 timeout: 30,
                 max_retries: 3,
                 enabled: false, // Disable LLM for testing
+                api_version: None,
+                fallback: Vec::new(),
             },
             llm_tool_selection: LlmToolSelectionConfig {
                 enabled: false,
@@ -2969,7 +3680,55 @@ Respond in JSON format:
             fallback: FallbackConfig::default(),
             semantic_search: SemanticSearchConfig::default(),
             enable_sequential_mode: true,
+            max_chain_steps: default_max_chain_steps(),
             tool_metrics_enabled: Some(true),
+            hybrid_weights: HybridWeights::default(),
+            feedback: DiscoveryFeedbackConfig::default(),
+            usage_boost: UsageBoostConfig::default(),
+            clarification: ClarificationConfig::default(),
+            categories: ToolCategoriesConfig::default(),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Split a request into steps on sequencing words, for when no LLM is available
+///
+/// A bare "and" only splits when both sides are phrases of their own, so
+/// "read and write the file" stays one step.
+fn split_request_steps(request: &str) -> Vec<String> {
+    const SEPARATORS: &[&str] = &[" and then ", ", then ", " then ", " after that ", "; ", ", and ", " and "];
+
+    let mut steps = vec![request.trim().to_string()];
+    for separator in SEPARATORS {
+        steps = steps.into_iter()
+            .flat_map(|step| {
+                // ASCII lowercasing keeps byte offsets valid for slicing the original
+                let lower = step.to_ascii_lowercase();
+                let mut parts = Vec::new();
+                let mut start = 0;
+                for (index, _) in lower.match_indices(separator) {
+                    let (before, after) = (&step[start..index], &step[index + separator.len()..]);
+                    if *separator == " and " && (before.split_whitespace().count() < 2 || after.split_whitespace().count() < 2) {
+                        continue;
+                    }
+                    parts.push(before.to_string());
+                    start = index + separator.len();
+                }
+                parts.push(step[start..].to_string());
+                parts
+            })
+            .collect();
+    }
+
+    steps.into_iter()
+        .map(|step| {
+            let step = step.trim().trim_end_matches(['.', ',', ';']);
+            let lower = step.to_ascii_lowercase();
+            let prefix = ["then ", "and ", "first ", "finally ", "next "].iter()
+                .find(|prefix| lower.starts_with(**prefix))
+                .map_or(0, |prefix| prefix.len());
+            step[prefix..].trim().to_string()
+        })
+        .filter(|step| !step.is_empty())
+        .collect()
+}
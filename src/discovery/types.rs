@@ -3,6 +3,10 @@
 //! This module defines the core types used in the Smart Tool Discovery system,
 //! including request/response structures, error types, and configuration.
 
+use crate::discovery::clarification::DiscoveryClarification;
+use crate::discovery::exclusions::DiscoveryExclusions;
+use crate::discovery::keyword_index::MetadataMatch;
+use crate::discovery::usage_boost::UsageBoost;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -26,6 +30,54 @@ pub struct SmartDiscoveryRequest {
     
     /// Enable smart sequential execution for multi-step tasks (default: true)
     pub sequential_mode: Option<bool>,
+
+    /// Earlier turns of the conversation, used to resolve follow-up requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation: Option<ConversationContext>,
+
+    /// Tools to rule out before ranking, on top of those the request rules out in words
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclusions: Option<DiscoveryExclusions>,
+}
+
+/// Recent conversation a request belongs to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConversationContext {
+    /// Recent messages, oldest first
+    #[serde(default)]
+    pub messages: Vec<ConversationMessage>,
+
+    /// Tool calls made earlier in the conversation, oldest first
+    #[serde(default)]
+    pub tool_calls: Vec<PriorToolCall>,
+}
+
+impl ConversationContext {
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty() && self.tool_calls.is_empty()
+    }
+}
+
+/// A message from earlier in the conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMessage {
+    /// `user`, `assistant`, ...
+    pub role: String,
+    pub content: String,
+}
+
+/// A tool call from earlier in the conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorToolCall {
+    pub tool_name: String,
+
+    /// Arguments the tool was called with
+    #[serde(default)]
+    pub arguments: HashMap<String, serde_json::Value>,
+
+    /// The request that led to the call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<String>,
 }
 
 /// Response structure for smart tool discovery
@@ -51,6 +103,10 @@ pub struct SmartDiscoveryResponse {
     
     /// Recommended next step (for sequential execution)
     pub next_step: Option<NextStepRecommendation>,
+
+    /// Question asked instead of running a tool when no tool is a confident match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clarification: Option<DiscoveryClarification>,
 }
 
 /// Recommendation for the next step in a sequential workflow
@@ -69,6 +125,48 @@ pub struct NextStepRecommendation {
     pub alternatives: Option<Vec<String>>,
 }
 
+/// A proposed ordered chain of tool calls for a multi-step request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolChainPlan {
+    /// The original request
+    pub request: String,
+    
+    /// The steps, in the order they should run
+    pub steps: Vec<ToolChainStep>,
+    
+    /// Whether every step has a confident tool match and all its required parameters
+    pub complete: bool,
+}
+
+/// One tool call in a tool chain plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolChainStep {
+    /// Position in the chain (1-based)
+    pub step: usize,
+    
+    /// What this step should accomplish
+    pub request: String,
+    
+    /// The best tool for the step, if any was found
+    pub tool_name: Option<String>,
+    
+    /// Confidence score for the tool match (0.0-1.0)
+    pub confidence_score: f64,
+    
+    /// Whether the match meets the confidence threshold
+    pub meets_threshold: bool,
+    
+    /// Why the tool was selected, or why none was
+    pub reasoning: String,
+    
+    /// Parameters mapped from the request
+    pub parameters: HashMap<String, serde_json::Value>,
+    
+    /// Required parameters the request doesn't provide; in later steps these
+    /// usually come from the output of an earlier one
+    pub missing_parameters: Vec<String>,
+}
+
 /// Detailed error information for progressive disclosure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorDetails {
@@ -99,6 +197,77 @@ pub struct ToolCandidateInfo {
     
     /// Whether this tool meets the confidence threshold
     pub meets_threshold: bool,
+    
+    /// How the confidence score was arrived at
+    #[serde(default)]
+    pub breakdown: ScoreBreakdown,
+}
+
+impl From<&ToolMatch> for ToolCandidateInfo {
+    fn from(tool_match: &ToolMatch) -> Self {
+        Self {
+            tool_name: tool_match.tool_name.clone(),
+            confidence_score: tool_match.confidence_score,
+            reasoning: tool_match.reasoning.clone(),
+            meets_threshold: tool_match.meets_threshold,
+            breakdown: tool_match.breakdown.clone(),
+        }
+    }
+}
+
+/// How a tool's confidence score was arrived at
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    /// Semantic similarity to the request, before weighting (0.0-1.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub semantic: Option<f64>,
+    
+    /// BM25 keyword score, before weighting (0.0-1.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyword: Option<f64>,
+    
+    /// Rule-based score, before weighting (0.0-1.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_based: Option<f64>,
+    
+    /// LLM-assigned score, before weighting (0.0-1.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm: Option<f64>,
+    
+    /// Adjustment from client feedback
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback_boost: Option<f64>,
+    
+    /// Adjustment from tool metrics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage_boost: Option<UsageBoost>,
+    
+    /// Why the score was lowered
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub penalties: Vec<String>,
+    
+    /// Request terms found in the tool's name, tags, keywords and description
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_metadata: Vec<MetadataMatch>,
+}
+
+/// Ranked discovery candidates for a request, without executing anything
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryExplanation {
+    /// The request that was explained
+    pub request: String,
+    
+    /// Tool selection mode used
+    pub tool_selection_mode: String,
+    
+    /// Confidence a tool needs to be selected
+    pub confidence_threshold: f64,
+    
+    /// The tool discovery would execute, if any
+    pub selected_tool: Option<String>,
+    
+    /// All candidates, best first
+    pub candidates: Vec<ToolCandidateInfo>,
 }
 
 /// Metadata about the discovery process
@@ -124,6 +293,14 @@ pub struct SmartDiscoveryMetadata {
     
     /// All tool candidates that were considered during discovery with their confidence scores
     pub tool_candidates: Option<Vec<ToolCandidateInfo>>,
+    
+    /// How the selected tool's confidence score was arrived at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_breakdown: Option<ScoreBreakdown>,
+
+    /// The standalone request a follow-up was resolved to using the conversation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_request: Option<String>,
 }
 
 /// Error response structure for failed discovery (backward compatibility)
@@ -263,6 +440,9 @@ pub struct ToolMatch {
     
     /// Whether this match meets the confidence threshold
     pub meets_threshold: bool,
+    
+    /// How the confidence score was arrived at
+    pub breakdown: ScoreBreakdown,
 }
 
 /// Result of parameter extraction from natural language
@@ -304,6 +484,8 @@ impl Default for SmartDiscoveryMetadata {
             mapped_parameters: None,
             extraction_status: None,
             tool_candidates: None,
+            score_breakdown: None,
+            resolved_request: None,
         }
     }
 }
\ No newline at end of file
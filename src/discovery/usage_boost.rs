@@ -0,0 +1,204 @@
+//! Usage-based ranking boosts
+//!
+//! Tool metrics know which tools get used, succeed and respond quickly. With
+//! `smart_discovery.usage_boost` enabled, discovery raises the confidence of
+//! tools that succeed and are used often and recently, and lowers it for
+//! flaky or slow ones. Each adjustment shows up in the match reasoning.
+//!
+//! ```yaml
+//! smart_discovery:
+//!   usage_boost:
+//!     max_boost: 0.1
+//!     min_executions: 5
+//!     reliability_weight: 0.7
+//!     popularity_weight: 0.2
+//!     latency_weight: 0.1
+//! ```
+
+use crate::error::{ProxyError, Result};
+use crate::metrics::tool_metrics::ToolUsage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// `smart_discovery.usage_boost`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageBoostConfig {
+    /// Whether tool metrics adjust discovery rankings
+    pub enabled: bool,
+
+    /// Largest confidence adjustment, up or down (0.0-1.0)
+    pub max_boost: f64,
+
+    /// Executions a tool needs before its metrics count
+    pub min_executions: u64,
+
+    /// Weight of the success rate, which promotes reliable and demotes flaky tools
+    pub reliability_weight: f64,
+
+    /// Weight of how often, and how recently, the tool was used successfully
+    pub popularity_weight: f64,
+
+    /// Weight of the slow-call penalty
+    pub latency_weight: f64,
+
+    /// Hours after which past usage counts half as much
+    pub recency_half_life_hours: f64,
+
+    /// Average latency (ms) above which a tool starts being demoted; at twice this it gets the full penalty
+    pub slow_call_ms: f64,
+}
+
+impl Default for UsageBoostConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_boost: 0.1,
+            min_executions: 5,
+            reliability_weight: 0.7,
+            popularity_weight: 0.2,
+            latency_weight: 0.1,
+            recency_half_life_hours: 168.0,
+            slow_call_ms: 5000.0,
+        }
+    }
+}
+
+impl UsageBoostConfig {
+    /// Validate the configuration
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.max_boost) {
+            return Err(ProxyError::config("usage_boost.max_boost must be between 0.0 and 1.0"));
+        }
+        for (name, weight) in [
+            ("reliability_weight", self.reliability_weight),
+            ("popularity_weight", self.popularity_weight),
+            ("latency_weight", self.latency_weight),
+        ] {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(ProxyError::config(format!("usage_boost.{} must be a non-negative number", name)));
+            }
+        }
+        if self.recency_half_life_hours <= 0.0 {
+            return Err(ProxyError::config("usage_boost.recency_half_life_hours must be greater than 0"));
+        }
+        if self.slow_call_ms <= 0.0 {
+            return Err(ProxyError::config("usage_boost.slow_call_ms must be greater than 0"));
+        }
+        Ok(())
+    }
+}
+
+/// A tool's usage-based adjustment and the figures behind it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageBoost {
+    /// Confidence adjustment, within ±`max_boost`
+    pub boost: f64,
+    /// Success rate component (-1.0-1.0)
+    pub reliability: f64,
+    /// Recency-weighted usage component (0.0-1.0)
+    pub popularity: f64,
+    /// Slow-call component (-1.0-0.0)
+    pub latency: f64,
+}
+
+impl UsageBoost {
+    /// Score breakdown for match reasoning
+    pub fn describe(&self) -> String {
+        format!(
+            "usage {:+.3}: reliability {:+.2}, popularity {:.2}, latency {:+.2}",
+            self.boost, self.reliability, self.popularity, self.latency
+        )
+    }
+}
+
+/// Usage-based adjustments of the tools with enough executions
+pub fn usage_boosts(usage: &HashMap<String, ToolUsage>, config: &UsageBoostConfig, now: DateTime<Utc>) -> HashMap<String, UsageBoost> {
+    let total_weight = config.reliability_weight + config.popularity_weight + config.latency_weight;
+    if total_weight <= 0.0 || config.max_boost <= 0.0 {
+        return HashMap::new();
+    }
+
+    let eligible: Vec<_> = usage.iter()
+        .filter(|(_, usage)| usage.total_executions >= config.min_executions)
+        .collect();
+    // Popularity counts successful executions, relative to the most successful tool
+    let successes = |usage: &ToolUsage| usage.total_executions as f64 * usage.success_rate;
+    let most_successes = eligible.iter().map(|(_, usage)| successes(usage)).fold(0.0, f64::max);
+    let popularity_scale = (1.0 + most_successes).ln();
+
+    eligible.into_iter()
+        .map(|(name, usage)| {
+            let reliability = usage.success_rate * 2.0 - 1.0;
+
+            let age_hours = usage.last_execution
+                .map_or(f64::INFINITY, |last| (now - last).num_seconds().max(0) as f64 / 3600.0);
+            let recency = 0.5f64.powf(age_hours / config.recency_half_life_hours);
+            let popularity = if popularity_scale > 0.0 {
+                (1.0 + successes(usage)).ln() / popularity_scale * recency
+            } else {
+                0.0
+            };
+
+            let latency = -(usage.avg_execution_time_ms / config.slow_call_ms - 1.0).clamp(0.0, 1.0);
+
+            let score = (config.reliability_weight * reliability
+                + config.popularity_weight * popularity
+                + config.latency_weight * latency)
+                / total_weight;
+            (name.clone(), UsageBoost {
+                boost: score.clamp(-1.0, 1.0) * config.max_boost,
+                reliability,
+                popularity,
+                latency,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn usage(total_executions: u64, success_rate: f64, avg_execution_time_ms: f64, hours_ago: i64) -> ToolUsage {
+        ToolUsage {
+            total_executions,
+            success_rate,
+            avg_execution_time_ms,
+            last_execution: Some(Utc::now() - Duration::hours(hours_ago)),
+        }
+    }
+
+    #[test]
+    fn test_reliable_popular_tools_rank_above_flaky_ones() {
+        let usage = HashMap::from([
+            ("reliable".to_string(), usage(200, 0.99, 100.0, 1)),
+            ("flaky".to_string(), usage(200, 0.3, 100.0, 1)),
+            ("slow".to_string(), usage(200, 0.99, 20_000.0, 1)),
+            ("new".to_string(), usage(2, 1.0, 100.0, 1)),
+        ]);
+        let config = UsageBoostConfig::default();
+        let boosts = usage_boosts(&usage, &config, Utc::now());
+
+        assert!(boosts["reliable"].boost > 0.0);
+        assert!(boosts["flaky"].boost < 0.0);
+        assert!(boosts["slow"].boost < boosts["reliable"].boost);
+        assert_eq!(boosts["slow"].latency, -1.0);
+        assert!(!boosts.contains_key("new"));
+        assert!(boosts.values().all(|b| b.boost.abs() <= config.max_boost));
+        assert!(boosts["reliable"].describe().starts_with("usage +"));
+    }
+
+    #[test]
+    fn test_popularity_decays_with_age() {
+        let usage = HashMap::from([
+            ("recent".to_string(), usage(100, 0.9, 100.0, 0)),
+            ("stale".to_string(), usage(100, 0.9, 100.0, 24 * 30)),
+        ]);
+        let boosts = usage_boosts(&usage, &UsageBoostConfig::default(), Utc::now());
+        assert!(boosts["recent"].popularity > 0.99);
+        assert!(boosts["stale"].popularity < 0.1);
+    }
+}
@@ -0,0 +1,374 @@
+//! Shared vector stores for tool embeddings
+//!
+//! By default every node keeps its tool embeddings in local files. With a
+//! vector store configured, embeddings are also upserted into a shared index
+//! whenever the registry changes, semantic search queries that index, and a
+//! node reuses embeddings another node already stored for unchanged tools:
+//!
+//! ```yaml
+//! smart_discovery:
+//!   semantic_search:
+//!     vector_store:
+//!       type: qdrant
+//!       url: http://qdrant:6333
+//!       collection: magictunnel_tools
+//!       api_key_env: QDRANT_API_KEY
+//! ```
+
+use crate::discovery::semantic::ToolMetadata;
+use crate::error::{ProxyError, Result};
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// A tool embedding with its metadata
+#[derive(Debug, Clone)]
+pub struct VectorPoint {
+    pub embedding: Vec<f32>,
+    pub metadata: ToolMetadata,
+}
+
+/// Restricts semantic search to some tools
+#[derive(Debug, Clone, Default)]
+pub struct VectorFilter {
+    /// Only tools with at least one of these tags
+    pub tags: Vec<String>,
+    /// Only tools proxied from this external MCP server
+    pub server: Option<String>,
+}
+
+impl VectorFilter {
+    /// Whether the filter lets every tool through
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.server.is_none()
+    }
+
+    /// Whether `metadata` passes the filter
+    pub fn matches(&self, metadata: &ToolMetadata) -> bool {
+        (self.tags.is_empty() || self.tags.iter().any(|tag| metadata.tags.contains(tag)))
+            && self.server.as_ref().map_or(true, |server| metadata.server.as_ref() == Some(server))
+    }
+}
+
+/// An index of tool embeddings that several MagicTunnel nodes can share
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    /// Backend and collection, for logs and stats
+    fn name(&self) -> String;
+
+    /// Insert or replace the embeddings of these tools
+    async fn upsert(&self, points: &[VectorPoint]) -> Result<()>;
+
+    /// Remove the embeddings of these tools
+    async fn delete(&self, tool_names: &[String]) -> Result<()>;
+
+    /// Stored embeddings of these tools; tools that aren't stored are left out
+    async fn get(&self, tool_names: &[String]) -> Result<Vec<VectorPoint>>;
+
+    /// Tools most similar to `vector`, best first, with their similarity scores
+    async fn search(
+        &self,
+        vector: &[f32],
+        limit: usize,
+        score_threshold: f64,
+        filter: &VectorFilter,
+    ) -> Result<Vec<(ToolMetadata, f64)>>;
+}
+
+/// `smart_discovery.semantic_search.vector_store`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum VectorStoreConfig {
+    Qdrant(QdrantConfig),
+}
+
+impl VectorStoreConfig {
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::Qdrant(config) => config.validate(),
+        }
+    }
+}
+
+/// Create the vector store described by `config`
+pub fn build_vector_store(config: &VectorStoreConfig) -> Result<Arc<dyn VectorStore>> {
+    config.validate()?;
+    match config {
+        VectorStoreConfig::Qdrant(config) => Ok(Arc::new(QdrantStore::new(config.clone())?)),
+    }
+}
+
+/// Qdrant connection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QdrantConfig {
+    /// REST endpoint, e.g. `http://qdrant:6333`
+    pub url: String,
+
+    /// Collection holding the tool embeddings; created on first use
+    #[serde(default = "default_collection")]
+    pub collection: String,
+
+    /// API key; read from `api_key_env` when unset
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+
+    /// Environment variable holding the API key
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+
+    /// Request timeout in seconds
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_collection() -> String {
+    "magictunnel_tools".to_string()
+}
+
+fn default_timeout() -> u64 {
+    10
+}
+
+impl QdrantConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(ProxyError::config(format!("Qdrant url '{}' must start with http:// or https://", self.url)));
+        }
+        if self.collection.trim().is_empty() {
+            return Err(ProxyError::config("Qdrant collection cannot be empty"));
+        }
+        if self.timeout == 0 {
+            return Err(ProxyError::config("Qdrant timeout must be greater than 0"));
+        }
+        Ok(())
+    }
+
+    fn api_key(&self) -> Result<Option<String>> {
+        if self.api_key.is_some() {
+            return Ok(self.api_key.clone());
+        }
+        match &self.api_key_env {
+            Some(env) => std::env::var(env)
+                .map(Some)
+                .map_err(|_| ProxyError::config(format!("{} environment variable not set for Qdrant", env))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Tool embeddings in a Qdrant collection. Point IDs are derived from tool
+/// names and the tool metadata is stored as the point payload.
+pub struct QdrantStore {
+    config: QdrantConfig,
+    api_key: Option<String>,
+    client: Client,
+    /// Vector size of the collection, once it's known to exist
+    dimensions: OnceCell<usize>,
+}
+
+impl QdrantStore {
+    pub fn new(config: QdrantConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout))
+            .build()
+            .map_err(|e| ProxyError::config(format!("Failed to create Qdrant HTTP client: {}", e)))?;
+        Ok(Self { api_key: config.api_key()?, config, client, dimensions: OnceCell::new() })
+    }
+
+    fn point_id(tool_name: &str) -> String {
+        Uuid::new_v5(&Uuid::NAMESPACE_OID, tool_name.as_bytes()).to_string()
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> RequestBuilder {
+        let url = format!("{}/collections/{}{}", self.config.url.trim_end_matches('/'), self.config.collection, path);
+        let request = self.client.request(method, url);
+        match &self.api_key {
+            Some(api_key) => request.header("api-key", api_key),
+            None => request,
+        }
+    }
+
+    /// Send `request`, returning the `result` of the response, or `None` if the collection doesn't exist
+    async fn send(&self, action: &str, request: RequestBuilder) -> Result<Option<Value>> {
+        let response = request.send().await
+            .map_err(|e| ProxyError::connection(format!("Qdrant {} request failed: {}", action, e)))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(ProxyError::connection(format!("Qdrant {} failed ({}): {}", action, status, error_text)));
+        }
+        let json: Value = response.json().await
+            .map_err(|e| ProxyError::connection(format!("Failed to parse Qdrant {} response: {}", action, e)))?;
+        Ok(Some(json["result"].clone()))
+    }
+
+    /// Create the collection for `dimensions`-sized vectors if it doesn't exist,
+    /// and make sure an existing collection has that size
+    async fn ensure_collection(&self, dimensions: usize) -> Result<()> {
+        let existing = *self.dimensions.get_or_try_init(|| async {
+            if let Some(collection) = self.send("get collection", self.request(reqwest::Method::GET, "")).await? {
+                let size = collection["config"]["params"]["vectors"]["size"].as_u64()
+                    .ok_or_else(|| ProxyError::config(format!(
+                        "Qdrant collection '{}' must have a single unnamed vector", self.config.collection
+                    )))?;
+                return Ok::<_, ProxyError>(size as usize);
+            }
+
+            info!("Creating Qdrant collection '{}' with {}-dimensional vectors", self.config.collection, dimensions);
+            self.send("create collection", self.request(reqwest::Method::PUT, "")
+                .json(&json!({"vectors": {"size": dimensions, "distance": "Cosine"}}))).await?;
+            // Index the payload fields semantic search filters on
+            for field in ["tags", "server"] {
+                self.send("create payload index", self.request(reqwest::Method::PUT, "/index?wait=true")
+                    .json(&json!({"field_name": field, "field_schema": "keyword"}))).await?;
+            }
+            Ok(dimensions)
+        }).await?;
+
+        if existing != dimensions {
+            return Err(ProxyError::config(format!(
+                "Qdrant collection '{}' holds {}-dimensional vectors but the embedding model produces {}; \
+                 use a new collection or regenerate the embeddings",
+                self.config.collection, existing, dimensions
+            )));
+        }
+        Ok(())
+    }
+
+    fn parse_point(point: &Value) -> Option<(ToolMetadata, Option<Vec<f32>>)> {
+        let metadata = serde_json::from_value(point["payload"].clone()).ok()?;
+        let embedding = point["vector"].as_array()
+            .map(|values| values.iter().map(|v| v.as_f64().unwrap_or(0.0) as f32).collect());
+        Some((metadata, embedding))
+    }
+}
+
+#[async_trait]
+impl VectorStore for QdrantStore {
+    fn name(&self) -> String {
+        format!("qdrant:{}", self.config.collection)
+    }
+
+    async fn upsert(&self, points: &[VectorPoint]) -> Result<()> {
+        let Some(first) = points.first() else { return Ok(()) };
+        self.ensure_collection(first.embedding.len()).await?;
+
+        let points: Vec<Value> = points.iter().map(|point| json!({
+            "id": Self::point_id(&point.metadata.name),
+            "vector": point.embedding,
+            "payload": point.metadata,
+        })).collect();
+        debug!("Upserting {} tool embeddings into {}", points.len(), self.name());
+        self.send("upsert", self.request(reqwest::Method::PUT, "/points?wait=true")
+            .json(&json!({"points": points}))).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, tool_names: &[String]) -> Result<()> {
+        if tool_names.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<String> = tool_names.iter().map(|name| Self::point_id(name)).collect();
+        self.send("delete", self.request(reqwest::Method::POST, "/points/delete?wait=true")
+            .json(&json!({"points": ids}))).await?;
+        Ok(())
+    }
+
+    async fn get(&self, tool_names: &[String]) -> Result<Vec<VectorPoint>> {
+        if tool_names.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids: Vec<String> = tool_names.iter().map(|name| Self::point_id(name)).collect();
+        let result = self.send("retrieve", self.request(reqwest::Method::POST, "/points")
+            .json(&json!({"ids": ids, "with_payload": true, "with_vector": true}))).await?;
+
+        Ok(result.as_ref().and_then(Value::as_array).into_iter().flatten()
+            .filter_map(Self::parse_point)
+            .filter_map(|(metadata, embedding)| Some(VectorPoint { embedding: embedding?, metadata }))
+            .collect())
+    }
+
+    async fn search(
+        &self,
+        vector: &[f32],
+        limit: usize,
+        score_threshold: f64,
+        filter: &VectorFilter,
+    ) -> Result<Vec<(ToolMetadata, f64)>> {
+        self.ensure_collection(vector.len()).await?;
+
+        let mut must = Vec::new();
+        if !filter.tags.is_empty() {
+            must.push(json!({"key": "tags", "match": {"any": filter.tags}}));
+        }
+        if let Some(server) = &filter.server {
+            must.push(json!({"key": "server", "match": {"value": server}}));
+        }
+        let result = self.send("search", self.request(reqwest::Method::POST, "/points/search").json(&json!({
+            "vector": vector,
+            "limit": limit,
+            "score_threshold": score_threshold,
+            "filter": {"must": must},
+            "with_payload": true,
+        }))).await?;
+
+        Ok(result.as_ref().and_then(Value::as_array).into_iter().flatten()
+            .filter_map(|point| Some((Self::parse_point(point)?.0, point["score"].as_f64()?)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(tags: &[&str], server: Option<&str>) -> ToolMetadata {
+        ToolMetadata {
+            name: "read_file".to_string(),
+            description: "Read a file".to_string(),
+            enabled: true,
+            hidden: false,
+            content_hash: "abc".to_string(),
+            last_updated: 0,
+            embedding_dims: 3,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            server: server.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let tool = metadata(&["files", "read"], Some("filesystem"));
+        assert!(VectorFilter::default().matches(&tool));
+        assert!(VectorFilter { tags: vec!["files".to_string(), "net".to_string()], server: None }.matches(&tool));
+        assert!(!VectorFilter { tags: vec!["net".to_string()], server: None }.matches(&tool));
+        assert!(VectorFilter { tags: vec![], server: Some("filesystem".to_string()) }.matches(&tool));
+        assert!(!VectorFilter { tags: vec![], server: Some("github".to_string()) }.matches(&metadata(&[], None)));
+    }
+
+    #[test]
+    fn test_config() {
+        let config: VectorStoreConfig = serde_json::from_value(json!({"type": "qdrant", "url": "http://qdrant:6333"})).unwrap();
+        let VectorStoreConfig::Qdrant(qdrant) = &config;
+        assert_eq!(qdrant.collection, "magictunnel_tools");
+        assert!(config.validate().is_ok());
+
+        let config: VectorStoreConfig = serde_json::from_value(json!({"type": "qdrant", "url": "qdrant:6333"})).unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_point_ids_are_stable() {
+        assert_eq!(QdrantStore::point_id("read_file"), QdrantStore::point_id("read_file"));
+        assert_ne!(QdrantStore::point_id("read_file"), QdrantStore::point_id("write_file"));
+    }
+}
@@ -4,6 +4,7 @@
 //! and routes tool calls to various agents and endpoints without requiring full MCP
 //! server implementations for each capability.
 
+pub mod audit;
 pub mod auth;
 pub mod config;
 pub mod discovery;
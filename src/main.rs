@@ -1,9 +1,10 @@
 use anyhow::Result;
 use clap::Parser;
 use std::path::PathBuf;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 use serde_json::json;
 
+mod audit;
 mod auth;
 mod config;
 mod discovery;
@@ -92,6 +93,10 @@ async fn main() -> Result<()> {
     
     info!("Configuration loaded successfully");
 
+    if let Some(ref audit) = config.audit {
+        audit::configure(audit).await?;
+    }
+
     if cli.discover_local {
         // Run external MCP discovery once and exit
         info!("Running external MCP discovery");
@@ -126,6 +131,7 @@ async fn main() -> Result<()> {
 
         // Get registry from the server for gRPC server
         let registry = http_server.registry().clone();
+        let network_policy = http_server.network_policy().cloned();
 
         // Initialize gRPC server with registry
         let grpc_server = McpGrpcServer::new(registry.clone());
@@ -141,7 +147,7 @@ async fn main() -> Result<()> {
             // Import the generated service
             use grpc::mcp_service_server::McpServiceServer;
 
-            let service = McpServiceServer::new(grpc_server);
+            let service = McpServiceServer::with_interceptor(grpc_server, auth::grpc_interceptor(network_policy));
 
             if let Err(e) = Server::builder()
                 .add_service(service)
@@ -172,32 +178,67 @@ async fn run_stdio_mode(config: Config) -> Result<()> {
     use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
     use serde_json::json;
 
+    // Sign the user in with the OAuth device flow when it is configured, so calls
+    // are attributed to and made on behalf of that user
+    let session = match &config.auth {
+        Some(auth_config) => auth::stdio_session(auth_config, auth::default_session_path()).await?,
+        None => None,
+    };
+    let user = session.as_ref().map(|session| session.user_id.clone());
+    let subject_token = session.map(|session| session.access_token);
+
     // Initialize MCP server with full configuration (including external MCP integration)
     let mcp_server = McpServer::with_config(&config).await?;
 
     // Set up stdin/stdout
     let stdin = tokio::io::stdin();
     let mut stdout = tokio::io::stdout();
-    let mut reader = BufReader::new(stdin);
-    let mut line = String::new();
+    let mut lines = BufReader::new(stdin).lines();
+
+    // Server-side notifications (list_changed, resource updates) are interleaved with responses
+    let mut notifications = mcp_server.notification_manager().subscribe();
 
     info!("MCP Proxy stdio mode ready - waiting for JSON-RPC messages");
 
     loop {
-        line.clear();
-        match reader.read_line(&mut line).await {
-            Ok(0) => {
+        // `next_line` is cancel-safe, so no partial input is lost when a notification wins the race
+        let next_line = tokio::select! {
+            next_line = lines.next_line() => next_line,
+            notification = notifications.recv() => {
+                match notification {
+                    Ok(notification) => {
+                        let message = notification.to_jsonrpc().to_string();
+                        if stdout.write_all(message.as_bytes()).await.is_err()
+                            || stdout.write_all(b"\n").await.is_err()
+                            || stdout.flush().await.is_err()
+                        {
+                            error!("Failed to write notification to stdout");
+                            break;
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("stdio client lagged, dropped {} notifications", skipped);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+                continue;
+            }
+        };
+
+        match next_line {
+            Ok(None) => {
                 // EOF - client disconnected
                 info!("stdin closed, shutting down stdio mode");
                 break;
             }
-            Ok(_) => {
+            Ok(Some(line)) => {
                 let trimmed_line = line.trim();
                 if trimmed_line.is_empty() {
                     continue;
                 }
 
-                match handle_stdio_message(&mcp_server, trimmed_line).await {
+                let handled = routing::cost::as_user(user.clone(), handle_stdio_message(&mcp_server, trimmed_line));
+                match auth::on_behalf_of(subject_token.clone(), handled).await {
                     Ok(Some(response)) => {
                         if let Err(e) = stdout.write_all(response.as_bytes()).await {
                             error!("Failed to write response to stdout: {}", e);
@@ -462,11 +503,12 @@ async fn pregenerate_embeddings_and_exit(config: Config) -> Result<()> {
 }
 
 fn init_logging(level: &str) -> Result<()> {
-    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(level));
 
+    // The log level filters the log only; audit events reach the audit backends whatever it is
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::fmt::layer()
@@ -475,8 +517,9 @@ fn init_logging(level: &str) -> Result<()> {
                 .with_file(true)
                 .with_line_number(true)
                 .with_writer(std::io::stderr) // Send logs to stderr for stdio mode
+                .with_filter(env_filter)
         )
-        .with(env_filter)
+        .with(audit::layer())
         .init();
 
     Ok(())
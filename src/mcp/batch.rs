@@ -0,0 +1,127 @@
+//! Batch tool calls
+//!
+//! Agent frameworks that fan out to many tools at once can send the calls in
+//! one `tools/call_batch` request (or `POST /mcp/call/batch`) instead of one
+//! round trip per call:
+//!
+//! ```json
+//! {"calls": [{"name": "get_weather", "arguments": {"city": "Oslo"}},
+//!            {"name": "get_weather", "arguments": {"city": "Lima"}}],
+//!  "maxConcurrency": 4}
+//! ```
+//!
+//! The calls are independent: they run concurrently, at most `maxConcurrency`
+//! at a time, and one failing does not affect the others. Results come back
+//! in the order of the calls.
+
+use crate::error::{ProxyError, Result};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::Value;
+use std::future::Future;
+
+/// Most calls accepted in one batch
+pub const MAX_BATCH_SIZE: usize = 100;
+
+/// Calls run at once when the request doesn't say
+pub const DEFAULT_MAX_CONCURRENCY: usize = 8;
+
+/// Most calls run at once, whatever the request says
+pub const MAX_CONCURRENCY: usize = 32;
+
+fn default_max_concurrency() -> usize {
+    DEFAULT_MAX_CONCURRENCY
+}
+
+/// Params of `tools/call_batch`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchToolCallRequest {
+    /// `tools/call` params of each call, `_meta` included
+    pub calls: Vec<Value>,
+    /// Calls run at once, capped at [`MAX_CONCURRENCY`]
+    #[serde(default = "default_max_concurrency", alias = "max_concurrency")]
+    pub max_concurrency: usize,
+}
+
+impl BatchToolCallRequest {
+    /// Parse and check the params of a batch request
+    pub fn from_params(params: Option<&Value>) -> Result<Self> {
+        let params = params.ok_or_else(|| ProxyError::validation("Batch request requires a 'calls' list"))?;
+        let request: Self = serde_json::from_value(params.clone())
+            .map_err(|e| ProxyError::validation(format!("Invalid batch request: {}", e)))?;
+        request.validate()?;
+        Ok(request)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.calls.is_empty() {
+            return Err(ProxyError::validation("Batch request has no calls"));
+        }
+        if self.calls.len() > MAX_BATCH_SIZE {
+            return Err(ProxyError::validation(format!(
+                "Batch request has {} calls; at most {} are allowed", self.calls.len(), MAX_BATCH_SIZE
+            )));
+        }
+        if self.max_concurrency == 0 {
+            return Err(ProxyError::validation("Batch maxConcurrency must be at least 1"));
+        }
+        Ok(())
+    }
+
+    /// Run every call with `call`, returning the outcomes in the order of the calls
+    ///
+    /// The calls are polled from the current task, so task-local state such as
+    /// the rate-limited client applies to each of them.
+    pub async fn run<F, Fut, T>(self, call: F) -> Vec<T>
+    where
+        F: FnMut(Value) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let max_concurrency = self.max_concurrency.min(MAX_CONCURRENCY);
+        futures_util::stream::iter(self.calls)
+            .map(call)
+            .buffered(max_concurrency)
+            .collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn test_from_params() {
+        let request = BatchToolCallRequest::from_params(Some(&json!({"calls": [{"name": "a"}]}))).unwrap();
+        assert_eq!(request.max_concurrency, DEFAULT_MAX_CONCURRENCY);
+
+        assert!(BatchToolCallRequest::from_params(None).is_err());
+        assert!(BatchToolCallRequest::from_params(Some(&json!({"calls": []}))).is_err());
+        assert!(BatchToolCallRequest::from_params(Some(&json!({"calls": [{}], "maxConcurrency": 0}))).is_err());
+        let too_many = vec![json!({"name": "a"}); MAX_BATCH_SIZE + 1];
+        assert!(BatchToolCallRequest::from_params(Some(&json!({"calls": too_many}))).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_keeps_order_and_limits_concurrency() {
+        let calls: Vec<Value> = (0..6).map(|i| json!({"delay": 6 - i, "index": i})).collect();
+        let request = BatchToolCallRequest { calls, max_concurrency: 2 };
+        let running = &AtomicUsize::new(0);
+        let peak = &AtomicUsize::new(0);
+
+        let results = request.run(|call| async move {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(call["delay"].as_u64().unwrap() * 5)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            call["index"].as_u64().unwrap()
+        }).await;
+
+        assert_eq!(results, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,161 @@
+//! Capability re-sync support for External MCP servers
+//!
+//! Holds the per-server snapshot of upstream capabilities (tools, prompts and
+//! resources) and computes the difference between two discovery runs so that
+//! callers only refresh the registry, embeddings and clients when something
+//! actually changed upstream.
+
+use crate::mcp::types::{PromptTemplate, Resource, Tool};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Capabilities discovered from a single External MCP server
+#[derive(Debug, Clone, Default)]
+pub struct ServerCapabilitySnapshot {
+    /// Tools reported by `tools/list`
+    pub tools: Vec<Tool>,
+    /// Prompts reported by `prompts/list`
+    pub prompts: Vec<PromptTemplate>,
+    /// Resources reported by `resources/list`
+    pub resources: Vec<Resource>,
+}
+
+/// Difference between two capability snapshots of the same server
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityChange {
+    /// Server the change was detected on
+    pub server_name: String,
+    /// Names of tools that appeared upstream
+    pub tools_added: Vec<String>,
+    /// Names of tools that disappeared upstream
+    pub tools_removed: Vec<String>,
+    /// Names of tools whose description or schema changed
+    pub tools_changed: Vec<String>,
+    /// Whether the prompt list changed in any way
+    pub prompts_changed: bool,
+    /// Whether the resource list changed in any way
+    pub resources_changed: bool,
+}
+
+impl CapabilityChange {
+    /// Compute the change between the previously cached snapshot (if any) and a new one
+    pub fn diff(server_name: &str, previous: Option<&ServerCapabilitySnapshot>, current: &ServerCapabilitySnapshot) -> Self {
+        let empty = ServerCapabilitySnapshot::default();
+        let previous = previous.unwrap_or(&empty);
+
+        let old_tools = keyed_by(&previous.tools, |t| t.name.clone());
+        let new_tools = keyed_by(&current.tools, |t| t.name.clone());
+
+        let mut change = CapabilityChange {
+            server_name: server_name.to_string(),
+            ..Default::default()
+        };
+
+        for (name, new_value) in &new_tools {
+            match old_tools.get(name) {
+                None => change.tools_added.push(name.clone()),
+                Some(old_value) if old_value != new_value => change.tools_changed.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        change.tools_removed = old_tools
+            .keys()
+            .filter(|name| !new_tools.contains_key(*name))
+            .cloned()
+            .collect();
+
+        change.prompts_changed =
+            keyed_by(&previous.prompts, |p| p.name.clone()) != keyed_by(&current.prompts, |p| p.name.clone());
+        change.resources_changed =
+            keyed_by(&previous.resources, |r| r.uri.clone()) != keyed_by(&current.resources, |r| r.uri.clone());
+
+        change
+    }
+
+    /// Whether the tool set changed (added, removed or modified tools)
+    pub fn has_tool_changes(&self) -> bool {
+        !self.tools_added.is_empty() || !self.tools_removed.is_empty() || !self.tools_changed.is_empty()
+    }
+
+    /// Whether nothing changed at all
+    pub fn is_empty(&self) -> bool {
+        !self.has_tool_changes() && !self.prompts_changed && !self.resources_changed
+    }
+
+    /// Names of all tools touched by this change
+    pub fn affected_tools(&self) -> BTreeSet<String> {
+        self.tools_added
+            .iter()
+            .chain(self.tools_removed.iter())
+            .chain(self.tools_changed.iter())
+            .cloned()
+            .collect()
+    }
+}
+
+/// Index items by key as JSON values, so comparisons ignore ordering and don't
+/// require `PartialEq` on the protocol types
+fn keyed_by<T: Serialize>(items: &[T], key: impl Fn(&T) -> String) -> BTreeMap<String, Value> {
+    items
+        .iter()
+        .map(|item| (key(item), serde_json::to_value(item).unwrap_or(Value::Null)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(name: &str, description: &str) -> Tool {
+        Tool::new(name.to_string(), description.to_string(), json!({"type": "object"})).unwrap()
+    }
+
+    #[test]
+    fn test_initial_discovery_reports_all_tools_added() {
+        let current = ServerCapabilitySnapshot {
+            tools: vec![tool("read_file", "Read a file"), tool("write_file", "Write a file")],
+            ..Default::default()
+        };
+
+        let change = CapabilityChange::diff("filesystem", None, &current);
+        assert_eq!(change.tools_added, vec!["read_file", "write_file"]);
+        assert!(change.tools_removed.is_empty());
+        assert!(!change.prompts_changed);
+        assert!(!change.resources_changed);
+        assert!(change.has_tool_changes());
+    }
+
+    #[test]
+    fn test_diff_detects_added_removed_and_changed_tools() {
+        let previous = ServerCapabilitySnapshot {
+            tools: vec![tool("read_file", "Read a file"), tool("delete_file", "Delete a file")],
+            ..Default::default()
+        };
+        let current = ServerCapabilitySnapshot {
+            tools: vec![tool("write_file", "Write a file"), tool("read_file", "Read a file from disk")],
+            ..Default::default()
+        };
+
+        let change = CapabilityChange::diff("filesystem", Some(&previous), &current);
+        assert_eq!(change.tools_added, vec!["write_file"]);
+        assert_eq!(change.tools_removed, vec!["delete_file"]);
+        assert_eq!(change.tools_changed, vec!["read_file"]);
+        assert_eq!(change.affected_tools().len(), 3);
+    }
+
+    #[test]
+    fn test_reordered_tools_are_not_a_change() {
+        let previous = ServerCapabilitySnapshot {
+            tools: vec![tool("a", "A"), tool("b", "B")],
+            ..Default::default()
+        };
+        let current = ServerCapabilitySnapshot {
+            tools: vec![tool("b", "B"), tool("a", "A")],
+            ..Default::default()
+        };
+
+        assert!(CapabilityChange::diff("srv", Some(&previous), &current).is_empty());
+    }
+}
@@ -0,0 +1,387 @@
+//! Circuit breaker for upstream MCP servers
+//!
+//! Tracks the outcome and latency of calls to an upstream server and opens the
+//! circuit after repeated failures (consecutive failures or a high error rate
+//! over a rolling window). While open, calls fail fast instead of waiting for
+//! their own timeout; after a cool-down a limited number of half-open probes
+//! decide whether the circuit closes again.
+
+use crate::config::CircuitBreakerConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Circuit breaker state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Calls flow normally
+    Closed,
+    /// Calls are rejected until the cool-down expires
+    Open,
+    /// A limited number of probe calls are let through
+    HalfOpen,
+}
+
+/// Serializable view of a circuit breaker for status and dashboard endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerStatus {
+    /// Current state
+    pub state: CircuitState,
+    /// Consecutive failures recorded
+    pub consecutive_failures: u32,
+    /// Failure ratio over the rolling window
+    pub error_rate: f64,
+    /// Calls in the rolling window
+    pub window_calls: usize,
+    /// Seconds until the next probe is allowed (only while open)
+    pub retry_after_secs: Option<u64>,
+    /// Number of times the circuit has opened
+    pub times_opened: u64,
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    state: CircuitState,
+    consecutive_failures: u32,
+    /// Rolling window of call outcomes, `true` = failure
+    window: VecDeque<bool>,
+    opened_at: Option<Instant>,
+    half_open_in_flight: u32,
+    half_open_successes: u32,
+    times_opened: u64,
+}
+
+/// Circuit breaker guarding a single upstream
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    inner: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    /// Create a new circuit breaker in the closed state
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                window: VecDeque::new(),
+                opened_at: None,
+                half_open_in_flight: 0,
+                half_open_successes: 0,
+                times_opened: 0,
+            }),
+        }
+    }
+
+    /// Ask permission to make a call. Returns the remaining cool-down when the
+    /// circuit is open (or all half-open probe slots are taken).
+    pub fn try_acquire(&self) -> std::result::Result<(), Duration> {
+        self.take_slot().map(|_| ())
+    }
+
+    /// Like `try_acquire`, but returns a permit that gives its half-open probe
+    /// slot back when dropped without an outcome being recorded, so a call
+    /// that is abandoned after acquiring cannot wedge the circuit half-open.
+    pub fn acquire(self: &Arc<Self>) -> std::result::Result<CircuitPermit, Duration> {
+        let probe = self.take_slot()?;
+        Ok(CircuitPermit { breaker: Arc::clone(self), probe, settled: false })
+    }
+
+    /// Take a call slot, returning whether it is a half-open probe slot
+    fn take_slot(&self) -> std::result::Result<bool, Duration> {
+        if !self.config.enabled {
+            return Ok(false);
+        }
+
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::Closed => Ok(false),
+            CircuitState::Open => {
+                let remaining = self.remaining_open_time(&inner);
+                if remaining.is_zero() {
+                    inner.state = CircuitState::HalfOpen;
+                    inner.half_open_in_flight = 1;
+                    inner.half_open_successes = 0;
+                    Ok(true)
+                } else {
+                    Err(remaining)
+                }
+            }
+            CircuitState::HalfOpen => {
+                if inner.half_open_in_flight < self.config.half_open_max_calls.max(1) {
+                    inner.half_open_in_flight += 1;
+                    Ok(true)
+                } else {
+                    Err(Duration::from_secs(1))
+                }
+            }
+        }
+    }
+
+    /// Record the outcome of a call that was allowed by `try_acquire`
+    pub fn record_success(&self, latency: Duration) {
+        if !self.config.enabled {
+            return;
+        }
+        if latency > Duration::from_millis(self.config.slow_call_threshold_ms) {
+            // Slow calls hurt callers just as much as failures
+            self.record_outcome(true);
+        } else {
+            self.record_outcome(false);
+        }
+    }
+
+    /// Record a failed call
+    pub fn record_failure(&self) {
+        if !self.config.enabled {
+            return;
+        }
+        self.record_outcome(true);
+    }
+
+    /// Give back a half-open probe slot without recording an outcome
+    fn release_probe(&self) {
+        let mut inner = self.lock();
+        if inner.state == CircuitState::HalfOpen {
+            inner.half_open_in_flight = inner.half_open_in_flight.saturating_sub(1);
+        }
+    }
+
+    /// Current state of the circuit
+    pub fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// Snapshot for status reporting
+    pub fn status(&self) -> CircuitBreakerStatus {
+        let inner = self.lock();
+        CircuitBreakerStatus {
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            error_rate: Self::error_rate(&inner.window),
+            window_calls: inner.window.len(),
+            retry_after_secs: match inner.state {
+                CircuitState::Open => Some(self.remaining_open_time(&inner).as_secs()),
+                _ => None,
+            },
+            times_opened: inner.times_opened,
+        }
+    }
+
+    /// Force the circuit closed and clear its history (e.g. after a server restart)
+    pub fn reset(&self) {
+        let mut inner = self.lock();
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.window.clear();
+        inner.opened_at = None;
+        inner.half_open_in_flight = 0;
+        inner.half_open_successes = 0;
+    }
+
+    fn record_outcome(&self, failed: bool) {
+        let mut inner = self.lock();
+
+        match inner.state {
+            CircuitState::HalfOpen => {
+                inner.half_open_in_flight = inner.half_open_in_flight.saturating_sub(1);
+                if failed {
+                    self.open(&mut inner);
+                } else {
+                    inner.half_open_successes += 1;
+                    if inner.half_open_successes >= self.config.half_open_max_calls.max(1) {
+                        inner.state = CircuitState::Closed;
+                        inner.consecutive_failures = 0;
+                        inner.window.clear();
+                        inner.opened_at = None;
+                    }
+                }
+            }
+            CircuitState::Closed => {
+                inner.window.push_back(failed);
+                while inner.window.len() > self.config.window_size.max(1) as usize {
+                    inner.window.pop_front();
+                }
+                if failed {
+                    inner.consecutive_failures += 1;
+                } else {
+                    inner.consecutive_failures = 0;
+                }
+
+                let too_many_consecutive = inner.consecutive_failures >= self.config.failure_threshold.max(1);
+                let error_rate_exceeded = inner.window.len() >= self.config.min_requests as usize
+                    && Self::error_rate(&inner.window) >= self.config.error_rate_threshold;
+                if too_many_consecutive || error_rate_exceeded {
+                    self.open(&mut inner);
+                }
+            }
+            // Late results from calls started before the circuit opened
+            CircuitState::Open => {}
+        }
+    }
+
+    fn open(&self, inner: &mut BreakerState) {
+        inner.state = CircuitState::Open;
+        inner.opened_at = Some(Instant::now());
+        inner.half_open_in_flight = 0;
+        inner.half_open_successes = 0;
+        inner.times_opened += 1;
+    }
+
+    fn remaining_open_time(&self, inner: &BreakerState) -> Duration {
+        let open_duration = Duration::from_secs(self.config.open_duration_secs);
+        inner
+            .opened_at
+            .map(|opened_at| open_duration.saturating_sub(opened_at.elapsed()))
+            .unwrap_or(Duration::ZERO)
+    }
+
+    fn error_rate(window: &VecDeque<bool>) -> f64 {
+        if window.is_empty() {
+            return 0.0;
+        }
+        window.iter().filter(|failed| **failed).count() as f64 / window.len() as f64
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Permission to make one call, returned by [`CircuitBreaker::acquire`]
+#[derive(Debug)]
+pub struct CircuitPermit {
+    breaker: Arc<CircuitBreaker>,
+    probe: bool,
+    settled: bool,
+}
+
+impl CircuitPermit {
+    /// Record that the call succeeded after `latency`
+    pub fn record_success(mut self, latency: Duration) {
+        self.settled = true;
+        self.breaker.record_success(latency);
+    }
+
+    /// Record that the call failed
+    pub fn record_failure(mut self) {
+        self.settled = true;
+        self.breaker.record_failure();
+    }
+}
+
+impl Drop for CircuitPermit {
+    fn drop(&mut self) {
+        if self.probe && !self.settled {
+            self.breaker.release_probe();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            failure_threshold: 3,
+            min_requests: 4,
+            window_size: 10,
+            open_duration_secs: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig { open_duration_secs: 60, ..test_config() });
+        for _ in 0..3 {
+            assert!(breaker.try_acquire().is_ok());
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.try_acquire().is_err());
+        assert_eq!(breaker.status().times_opened, 1);
+    }
+
+    #[test]
+    fn test_opens_on_error_rate() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 100,
+            error_rate_threshold: 0.5,
+            open_duration_secs: 60,
+            ..test_config()
+        });
+        breaker.record_success(Duration::from_millis(1));
+        breaker.record_failure();
+        breaker.record_success(Duration::from_millis(1));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[test]
+    fn test_half_open_probe_closes_or_reopens() {
+        let breaker = CircuitBreaker::new(test_config());
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // Cool-down of 0s: the next call becomes a probe, and only one probe is allowed
+        assert!(breaker.try_acquire().is_ok());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.try_acquire().is_err());
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        assert!(breaker.try_acquire().is_ok());
+        breaker.record_success(Duration::from_millis(1));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_dropped_permit_releases_probe_slot() {
+        let breaker = Arc::new(CircuitBreaker::new(test_config()));
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+
+        let permit = breaker.acquire().unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.acquire().is_err());
+        drop(permit);
+
+        // The abandoned probe neither closed nor reopened the circuit, but freed its slot
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        breaker.acquire().unwrap().record_success(Duration::from_millis(1));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_slow_calls_count_as_failures() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            slow_call_threshold_ms: 10,
+            open_duration_secs: 60,
+            ..test_config()
+        });
+        for _ in 0..3 {
+            breaker.record_success(Duration::from_millis(50));
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[test]
+    fn test_disabled_breaker_never_opens() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig { enabled: false, ..test_config() });
+        for _ in 0..10 {
+            breaker.record_failure();
+        }
+        assert!(breaker.try_acquire().is_ok());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
@@ -4,6 +4,8 @@
 //! that expose MCP-over-HTTP endpoints. It provides connection pooling, authentication,
 //! error handling, and retry logic.
 
+use crate::auth::TokenExchangeConfig;
+use crate::config::secrets::{redact_secret, resolve_secret};
 use crate::error::{ProxyError, Result};
 use crate::mcp::types::{Tool, McpRequest, McpResponse};
 use reqwest::{Client, RequestBuilder};
@@ -11,13 +13,14 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{OnceCell, RwLock};
 use tracing::{debug, info, warn};
 use url::Url;
 use uuid::Uuid;
 
-/// Authentication configuration for HTTP MCP client
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Authentication configuration for HTTP MCP client.
+/// Credentials may be secret references (see [`crate::config::secrets`]).
+#[derive(Clone, Serialize, Deserialize)]
 pub enum HttpAuthConfig {
     /// No authentication
     None,
@@ -27,6 +30,47 @@ pub enum HttpAuthConfig {
     ApiKey { header: String, key: String },
     /// Basic authentication
     Basic { username: String, password: String },
+    /// Token exchanged for the user the call is made on behalf of
+    TokenExchange(TokenExchangeConfig),
+}
+
+impl HttpAuthConfig {
+    /// Resolve secret references in the credentials
+    pub async fn resolve(&self) -> Result<Self> {
+        Ok(match self {
+            Self::None => Self::None,
+            Self::Bearer { token } => Self::Bearer { token: resolve_secret(token).await? },
+            Self::ApiKey { header, key } => Self::ApiKey {
+                header: header.clone(),
+                key: resolve_secret(key).await?,
+            },
+            Self::Basic { username, password } => Self::Basic {
+                username: resolve_secret(username).await?,
+                password: resolve_secret(password).await?,
+            },
+            Self::TokenExchange(exchange) => Self::TokenExchange(exchange.resolve().await?),
+        })
+    }
+}
+
+impl std::fmt::Debug for HttpAuthConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Bearer { token } => f.debug_struct("Bearer").field("token", &redact_secret(token)).finish(),
+            Self::ApiKey { header, key } => f
+                .debug_struct("ApiKey")
+                .field("header", header)
+                .field("key", &redact_secret(key))
+                .finish(),
+            Self::Basic { username, password } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &redact_secret(password))
+                .finish(),
+            Self::TokenExchange(exchange) => f.debug_tuple("TokenExchange").field(exchange).finish(),
+        }
+    }
 }
 
 /// HTTP MCP client configuration
@@ -75,6 +119,8 @@ pub struct HttpMcpClient {
     cached_tools: Arc<RwLock<Option<Vec<Tool>>>>,
     /// Service identifier
     service_id: String,
+    /// Credentials with secret references resolved (on first request)
+    resolved_auth: Arc<OnceCell<HttpAuthConfig>>,
 }
 
 impl HttpMcpClient {
@@ -104,6 +150,7 @@ impl HttpMcpClient {
             base_url,
             cached_tools: Arc::new(RwLock::new(None)),
             service_id,
+            resolved_auth: Arc::new(OnceCell::new()),
         })
     }
 
@@ -215,7 +262,7 @@ impl HttpMcpClient {
             .json(request);
 
         // Add authentication
-        req_builder = self.add_authentication(req_builder)?;
+        req_builder = self.add_authentication(req_builder).await?;
 
         // Send the request
         let response = req_builder
@@ -249,8 +296,12 @@ impl HttpMcpClient {
     }
 
     /// Add authentication headers to the request
-    fn add_authentication(&self, mut req_builder: RequestBuilder) -> Result<RequestBuilder> {
-        match &self.config.auth {
+    async fn add_authentication(&self, mut req_builder: RequestBuilder) -> Result<RequestBuilder> {
+        let auth = self
+            .resolved_auth
+            .get_or_try_init(|| self.config.auth.resolve())
+            .await?;
+        match auth {
             HttpAuthConfig::None => {
                 // No authentication
             }
@@ -263,6 +314,10 @@ impl HttpMcpClient {
             HttpAuthConfig::Basic { username, password } => {
                 req_builder = req_builder.basic_auth(username, Some(password));
             }
+            HttpAuthConfig::TokenExchange(exchange) => {
+                let token = exchange.access_token().await?;
+                req_builder = req_builder.header("Authorization", format!("Bearer {}", token));
+            }
         }
 
         Ok(req_builder)
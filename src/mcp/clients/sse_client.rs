@@ -4,6 +4,7 @@
 //! that expose MCP-over-SSE endpoints. It provides connection lifecycle management,
 //! single-session request queuing, heartbeat mechanism, and auto-reconnection.
 
+use crate::config::secrets::{redact_secret, resolve_secret};
 use crate::error::{ProxyError, Result};
 use crate::mcp::types::{Tool, McpRequest, McpResponse};
 use eventsource_client::SSE;
@@ -18,8 +19,9 @@ use tracing::{debug, info, warn, error};
 use url::Url;
 use uuid::Uuid;
 
-/// Authentication configuration for SSE MCP client
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Authentication configuration for SSE MCP client.
+/// Credentials may be secret references (see [`crate::config::secrets`]).
+#[derive(Clone, Serialize, Deserialize)]
 pub enum SseAuthConfig {
     /// No authentication
     None,
@@ -31,6 +33,43 @@ pub enum SseAuthConfig {
     QueryParam { param: String, value: String },
 }
 
+impl SseAuthConfig {
+    /// Resolve secret references in the credentials
+    pub async fn resolve(&self) -> Result<Self> {
+        Ok(match self {
+            Self::None => Self::None,
+            Self::Bearer { token } => Self::Bearer { token: resolve_secret(token).await? },
+            Self::ApiKey { header, key } => Self::ApiKey {
+                header: header.clone(),
+                key: resolve_secret(key).await?,
+            },
+            Self::QueryParam { param, value } => Self::QueryParam {
+                param: param.clone(),
+                value: resolve_secret(value).await?,
+            },
+        })
+    }
+}
+
+impl std::fmt::Debug for SseAuthConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Bearer { token } => f.debug_struct("Bearer").field("token", &redact_secret(token)).finish(),
+            Self::ApiKey { header, key } => f
+                .debug_struct("ApiKey")
+                .field("header", header)
+                .field("key", &redact_secret(key))
+                .finish(),
+            Self::QueryParam { param, value } => f
+                .debug_struct("QueryParam")
+                .field("param", param)
+                .field("value", &redact_secret(value))
+                .finish(),
+        }
+    }
+}
+
 /// SSE MCP client configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SseClientConfig {
@@ -122,6 +161,8 @@ pub struct SseMcpClient {
     queue_task: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
     /// Last heartbeat time
     last_heartbeat: Arc<RwLock<Option<Instant>>>,
+    /// Credentials with secret references resolved (refreshed on every connect)
+    resolved_auth: Arc<RwLock<Option<SseAuthConfig>>>,
 }
 
 impl SseMcpClient {
@@ -142,6 +183,7 @@ impl SseMcpClient {
             connection_task: Arc::new(RwLock::new(None)),
             queue_task: Arc::new(RwLock::new(None)),
             last_heartbeat: Arc::new(RwLock::new(None)),
+            resolved_auth: Arc::new(RwLock::new(None)),
         })
     }
 
@@ -162,8 +204,18 @@ impl SseMcpClient {
         let mut client_builder = eventsource_client::ClientBuilder::for_url(&self.config.base_url)
             .map_err(|e| ProxyError::connection(format!("Failed to create SSE client: {}", e)))?;
 
+        // Resolve secret references at connection time so rotated credentials are picked up
+        let auth = match self.config.auth.resolve().await {
+            Ok(auth) => auth,
+            Err(e) => {
+                *self.connection_state.write().await = ConnectionState::Disconnected;
+                return Err(e);
+            }
+        };
+        *self.resolved_auth.write().await = Some(auth.clone());
+
         // Add authentication headers
-        client_builder = self.add_authentication(client_builder)?;
+        client_builder = self.add_authentication(client_builder, &auth)?;
 
         // Create event channel
         let (event_tx, event_rx) = mpsc::unbounded_channel();
@@ -373,7 +425,7 @@ impl SseMcpClient {
         let mut request_builder = client.post(&self.config.base_url);
 
         // Add authentication
-        request_builder = self.add_http_authentication(request_builder)?;
+        request_builder = self.add_http_authentication(request_builder).await?;
 
         // Send the request
         let response = request_builder
@@ -585,8 +637,9 @@ impl SseMcpClient {
     fn add_authentication(
         &self,
         mut client_builder: eventsource_client::ClientBuilder,
+        auth: &SseAuthConfig,
     ) -> Result<eventsource_client::ClientBuilder> {
-        match &self.config.auth {
+        match auth {
             SseAuthConfig::None => {
                 // No authentication
             }
@@ -612,11 +665,20 @@ impl SseMcpClient {
     }
 
     /// Add authentication to HTTP request builder
-    fn add_http_authentication(
+    async fn add_http_authentication(
         &self,
         mut request_builder: reqwest::RequestBuilder,
     ) -> Result<reqwest::RequestBuilder> {
-        match &self.config.auth {
+        let cached = self.resolved_auth.read().await.clone();
+        let auth = match cached {
+            Some(auth) => auth,
+            None => {
+                let auth = self.config.auth.resolve().await?;
+                *self.resolved_auth.write().await = Some(auth.clone());
+                auth
+            }
+        };
+        match &auth {
             SseAuthConfig::None => {
                 // No authentication
             }
@@ -177,9 +177,12 @@ impl ExternalMcpIntegration {
         if let Some(manager) = &self.manager {
             let active_servers = manager.get_active_servers().await;
             let health_status = manager.get_health_status().await;
+            let circuit_breakers = manager.get_circuit_breaker_status().await;
             
             status.insert("active_servers".to_string(), serde_json::json!(active_servers));
             status.insert("health_status".to_string(), serde_json::json!(health_status));
+            status.insert("circuit_breakers".to_string(), serde_json::json!(circuit_breakers));
+            status.insert("startup".to_string(), serde_json::json!(manager.get_startup_report().await));
         }
         
         status
@@ -245,6 +248,16 @@ impl ExternalMcpIntegration {
         }
     }
 
+    /// Subscribe to upstream capability changes detected by periodic re-discovery
+    pub fn subscribe_capability_changes(&self) -> Option<tokio::sync::broadcast::Receiver<crate::mcp::capability_sync::CapabilityChange>> {
+        self.manager.as_ref().map(|manager| manager.subscribe_capability_changes())
+    }
+
+    /// Subscribe to notifications emitted by External MCP servers
+    pub fn subscribe_upstream_notifications(&self) -> Option<tokio::sync::broadcast::Receiver<crate::mcp::upstream_notifications::UpstreamNotification>> {
+        self.manager.as_ref().map(|manager| manager.subscribe_upstream_notifications())
+    }
+
     /// Check if External MCP is enabled and running
     pub fn is_enabled(&self) -> bool {
         self.manager.is_some()
@@ -348,8 +361,19 @@ pub mod utils {
             return Err(ProxyError::config("Server args cannot be empty".to_string()));
         }
 
-        // Validate working directory if specified
-        if let Some(ref cwd) = config.cwd {
+        if let Some(ref ssh) = config.ssh {
+            if ssh.host.is_empty() {
+                return Err(ProxyError::config("SSH host cannot be empty".to_string()));
+            }
+            if let Some(ref identity_file) = ssh.identity_file {
+                if !std::path::Path::new(identity_file).exists() {
+                    return Err(ProxyError::config(format!("SSH identity file does not exist: {}", identity_file)));
+                }
+            }
+        }
+
+        // Validate working directory if specified (remote directories can't be checked locally)
+        if let (Some(cwd), None) = (&config.cwd, &config.ssh) {
             if !std::path::Path::new(cwd).exists() {
                 return Err(ProxyError::config(format!("Working directory does not exist: {}", cwd)));
             }
@@ -6,19 +6,38 @@
 use crate::config::{ExternalMcpConfig, ExternalMcpServersConfig, ContainerConfig, McpClientConfig};
 use crate::error::{ProxyError, Result};
 use crate::mcp::external_process::ExternalMcpProcess;
-use crate::mcp::types::{Tool, McpRequest, McpResponse};
+use crate::mcp::types::{Tool, McpRequest, McpResponse, PromptTemplate, Resource, ResourceContent};
+use crate::mcp::capability_sync::{CapabilityChange, ServerCapabilitySnapshot};
+use crate::mcp::injection_scan::{CapabilityApproval, CapabilityKind, Quarantine};
+use crate::mcp::circuit_breaker::{CircuitBreaker, CircuitBreakerStatus};
+use crate::mcp::upstream_notifications::{remap_tool_name, UpstreamNotification, UpstreamNotificationAction};
 use crate::mcp::metrics::{McpMetricsCollector, McpHealthThresholds, HealthStatus};
 use crate::mcp::health_checker::{McpHealthChecker, HealthCheckConfig};
-use crate::registry::types::{CapabilityFile, ToolDefinition, RoutingConfig};
+use crate::registry::types::{CapabilityFile, CAPABILITY_SCHEMA_VERSION, ToolDefinition, RoutingConfig};
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::RwLock;
-use tokio::time::{interval, Duration};
+use tokio::sync::{broadcast, RwLock};
+use tokio::time::{interval, interval_at, Duration};
 use tracing::{debug, error, info, warn};
 
+/// Startup readiness of a single External MCP server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerReadiness {
+    /// Server name from configuration
+    pub server_name: String,
+    /// Whether the server started and completed the MCP handshake
+    pub ready: bool,
+    /// Startup error, if the server is not ready
+    pub error: Option<String>,
+    /// Time spent starting the server (in milliseconds)
+    pub startup_ms: u64,
+}
+
 /// Manages multiple External MCP server processes
 pub struct ExternalMcpManager {
     /// Configuration for External MCP
@@ -31,6 +50,20 @@ pub struct ExternalMcpManager {
     processes: Arc<RwLock<HashMap<String, ExternalMcpProcess>>>,
     /// Discovered capabilities from all servers
     capabilities: Arc<RwLock<HashMap<String, Vec<Tool>>>>,
+    /// Discovered prompts from all servers
+    prompts: Arc<RwLock<HashMap<String, Vec<PromptTemplate>>>>,
+    /// Discovered resources from all servers
+    resources: Arc<RwLock<HashMap<String, Vec<Resource>>>>,
+    /// Broadcasts capability changes detected during (re-)discovery
+    change_sender: broadcast::Sender<CapabilityChange>,
+    /// Discovered capabilities withheld for possible prompt injection
+    quarantine: Arc<Quarantine>,
+    /// Broadcasts notifications emitted by upstream servers
+    notification_sender: broadcast::Sender<UpstreamNotification>,
+    /// Per-server circuit breakers guarding tool execution
+    circuit_breakers: Arc<RwLock<HashMap<String, Arc<CircuitBreaker>>>>,
+    /// Readiness of each configured server after the last startup
+    startup_report: Arc<RwLock<Vec<ServerReadiness>>>,
     /// Metrics collector for observability
     metrics_collector: Arc<McpMetricsCollector>,
     /// Health checker for active monitoring
@@ -48,12 +81,23 @@ impl ExternalMcpManager {
         // Initialize health checker with default configuration
         let health_checker = Arc::new(McpHealthChecker::new(HealthCheckConfig::default()));
 
+        let (change_sender, _) = broadcast::channel(100);
+        let (notification_sender, _) = broadcast::channel(1000);
+        let quarantine = Arc::new(Quarantine::new(config.injection_scan.clone()));
+
         Self {
             config,
             client_config,
             container_config,
             processes: Arc::new(RwLock::new(HashMap::new())),
             capabilities: Arc::new(RwLock::new(HashMap::new())),
+            prompts: Arc::new(RwLock::new(HashMap::new())),
+            resources: Arc::new(RwLock::new(HashMap::new())),
+            change_sender,
+            quarantine,
+            notification_sender,
+            circuit_breakers: Arc::new(RwLock::new(HashMap::new())),
+            startup_report: Arc::new(RwLock::new(Vec::new())),
             metrics_collector,
             health_checker,
         }
@@ -70,24 +114,35 @@ impl ExternalMcpManager {
         info!("Loading external MCP server configurations...");
         let servers_config = self.load_servers_config().await?;
         
-        // Track successfully started servers
-        let mut started_servers = 0;
         let total_servers = servers_config.mcp_servers.as_ref().map(|s| s.len()).unwrap_or(0);
-        
-        // Start all configured servers
-        if let Some(mcp_servers) = servers_config.mcp_servers {
-            for (server_name, server_config) in mcp_servers {
-                match self.start_server(server_name.clone(), server_config).await {
+        let concurrency = self.config.startup_concurrency.max(1);
+        info!("Starting {} External MCP servers (up to {} in parallel)", total_servers, concurrency);
+
+        // Start all configured servers concurrently; a slow or failing server doesn't hold up the rest
+        let mut report: Vec<ServerReadiness> = stream::iter(servers_config.mcp_servers.unwrap_or_default())
+            .map(|(server_name, server_config)| async move {
+                let started_at = Instant::now();
+                let result = self.start_server(server_name.clone(), server_config).await;
+                let startup_ms = started_at.elapsed().as_millis() as u64;
+                match result {
                     Ok(_) => {
-                        info!("Successfully started External MCP server: {}", server_name);
-                        started_servers += 1;
+                        info!("Successfully started External MCP server: {} ({}ms)", server_name, startup_ms);
+                        ServerReadiness { server_name, ready: true, error: None, startup_ms }
                     }
                     Err(e) => {
                         error!("Failed to start External MCP server '{}': {}", server_name, e);
+                        ServerReadiness { server_name, ready: false, error: Some(e.to_string()), startup_ms }
                     }
                 }
-            }
-        }
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+        report.sort_by(|a, b| a.server_name.cmp(&b.server_name));
+
+        let started_servers = report.iter().filter(|r| r.ready).count();
+        Self::log_startup_summary(&report);
+        *self.startup_report.write().await = report;
 
         // Check if any servers started successfully
         if started_servers == 0 {
@@ -99,7 +154,11 @@ impl ExternalMcpManager {
             return Err(ProxyError::connection(error_msg));
         }
 
-        info!("Started {}/{} External MCP servers successfully", started_servers, total_servers);
+        if started_servers < total_servers {
+            warn!("Started {}/{} External MCP servers - serving with partial availability", started_servers, total_servers);
+        } else {
+            info!("Started {}/{} External MCP servers successfully", started_servers, total_servers);
+        }
 
         // Initialize metrics for all servers
         self.initialize_server_metrics().await;
@@ -107,6 +166,9 @@ impl ExternalMcpManager {
         // Start periodic capability discovery and health monitoring
         self.start_periodic_monitoring().await;
 
+        // React to list_changed notifications pushed by upstream servers
+        self.start_upstream_notification_listener();
+
         // Perform initial capability discovery
         self.discover_all_capabilities().await?;
 
@@ -114,6 +176,24 @@ impl ExternalMcpManager {
         Ok(())
     }
 
+    /// Log a per-server readiness summary after startup
+    fn log_startup_summary(report: &[ServerReadiness]) {
+        info!("📋 External MCP startup summary:");
+        for entry in report {
+            if entry.ready {
+                info!("  ✅ {} ready ({}ms)", entry.server_name, entry.startup_ms);
+            } else {
+                warn!("  ❌ {} not ready ({}ms): {}", entry.server_name, entry.startup_ms,
+                      entry.error.as_deref().unwrap_or("unknown error"));
+            }
+        }
+    }
+
+    /// Get the per-server readiness report from the last startup
+    pub async fn get_startup_report(&self) -> Vec<ServerReadiness> {
+        self.startup_report.read().await.clone()
+    }
+
     /// Load server configurations from file
     async fn load_servers_config(&self) -> Result<ExternalMcpServersConfig> {
         let config_path = &self.config.config_file;
@@ -306,6 +386,7 @@ mcpServers:
 
         // Create and start new process
         let mut process = ExternalMcpProcess::new(name.clone(), config, self.client_config.clone());
+        process.set_notification_sender(self.notification_sender.clone());
         process.start().await?;
 
         // Perform MCP handshake
@@ -392,21 +473,31 @@ mcpServers:
     async fn start_periodic_monitoring(&self) {
         let processes = Arc::clone(&self.processes);
         let capabilities = Arc::clone(&self.capabilities);
+        let prompts = Arc::clone(&self.prompts);
+        let resources = Arc::clone(&self.resources);
+        let change_sender = self.change_sender.clone();
+        let quarantine = Arc::clone(&self.quarantine);
         let metrics_collector = Arc::clone(&self.metrics_collector);
         let health_checker = Arc::clone(&self.health_checker);
         let config = self.config.clone();
 
         tokio::spawn(async move {
-            // Capability discovery interval (longer)
-            let mut discovery_interval = interval(Duration::from_secs(config.refresh_interval_minutes * 60));
+            // Capability re-discovery interval (longer); a refresh interval of 0 disables re-sync.
+            // The first tick is delayed by one period since start() already ran an initial discovery.
+            let rediscovery_enabled = config.refresh_interval_minutes > 0;
+            let discovery_period = Duration::from_secs(config.refresh_interval_minutes.max(1) * 60);
+            let mut discovery_interval = interval_at(tokio::time::Instant::now() + discovery_period, discovery_period);
+            if !rediscovery_enabled {
+                info!("🔍 [MONITOR] Periodic capability re-discovery disabled (refresh_interval_minutes = 0)");
+            }
             
             // Health check interval (shorter)
             let mut health_interval = interval(Duration::from_secs(30)); // Every 30 seconds
 
             loop {
                 tokio::select! {
-                    _ = discovery_interval.tick() => {
-                        info!("🔍 [MONITOR] Starting periodic capability discovery for External MCP servers");
+                    _ = discovery_interval.tick(), if rediscovery_enabled => {
+                        info!("🔍 [MONITOR] Starting periodic capability re-discovery for External MCP servers");
                         
                         let process_names: Vec<String> = {
                             let processes_guard = processes.read().await;
@@ -417,6 +508,10 @@ mcpServers:
                             if let Err(e) = Self::discover_server_capabilities_static(
                                 &processes,
                                 &capabilities,
+                                &prompts,
+                                &resources,
+                                &change_sender,
+                                &quarantine,
                                 &server_name,
                                 &config,
                             ).await {
@@ -482,11 +577,13 @@ mcpServers:
             processes.keys().cloned().collect()
         };
 
-        for server_name in server_names {
-            if let Err(e) = self.discover_server_capabilities(&server_name).await {
-                error!("Failed to discover capabilities for server '{}': {}", server_name, e);
-            }
-        }
+        stream::iter(server_names)
+            .for_each_concurrent(self.config.startup_concurrency.max(1), |server_name| async move {
+                if let Err(e) = self.discover_server_capabilities(&server_name).await {
+                    error!("Failed to discover capabilities for server '{}': {}", server_name, e);
+                }
+            })
+            .await;
 
         Ok(())
     }
@@ -496,15 +593,91 @@ mcpServers:
         Self::discover_server_capabilities_static(
             &self.processes,
             &self.capabilities,
+            &self.prompts,
+            &self.resources,
+            &self.change_sender,
+            &self.quarantine,
             server_name,
             &self.config,
         ).await
     }
 
+    /// Re-discover a server's capabilities whenever it reports that its tools, prompts or
+    /// resources changed, so downstream clients see the change without waiting for the next
+    /// periodic re-sync
+    fn start_upstream_notification_listener(&self) {
+        let mut receiver = self.notification_sender.subscribe();
+        let processes = Arc::clone(&self.processes);
+        let capabilities = Arc::clone(&self.capabilities);
+        let prompts = Arc::clone(&self.prompts);
+        let resources = Arc::clone(&self.resources);
+        let change_sender = self.change_sender.clone();
+        let quarantine = Arc::clone(&self.quarantine);
+        let config = self.config.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let upstream = match receiver.recv().await {
+                    Ok(upstream) => upstream,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Upstream notification listener lagged, skipped {} notifications", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                if upstream.action() != UpstreamNotificationAction::Rediscover {
+                    continue;
+                }
+
+                info!("🔔 External MCP server '{}' sent {}, re-discovering capabilities",
+                      upstream.server_name, upstream.notification.method);
+                if let Err(e) = Self::discover_server_capabilities_static(
+                    &processes,
+                    &capabilities,
+                    &prompts,
+                    &resources,
+                    &change_sender,
+                    &quarantine,
+                    &upstream.server_name,
+                    &config,
+                ).await {
+                    error!("Failed to re-discover capabilities for server '{}': {}", upstream.server_name, e);
+                }
+            }
+        });
+    }
+
+    /// Subscribe to notifications emitted by upstream servers
+    pub fn subscribe_upstream_notifications(&self) -> broadcast::Receiver<UpstreamNotification> {
+        self.notification_sender.subscribe()
+    }
+
+    /// Subscribe to capability changes detected while (re-)discovering upstream servers
+    pub fn subscribe_capability_changes(&self) -> broadcast::Receiver<CapabilityChange> {
+        self.change_sender.subscribe()
+    }
+
+    /// Discovered capabilities withheld for possible prompt injection, and their approvals
+    pub fn quarantine(&self) -> &Arc<Quarantine> {
+        &self.quarantine
+    }
+
+    /// Approve a quarantined capability and re-discover its server to expose it
+    pub async fn approve_capability(&self, server_name: &str, kind: CapabilityKind, name: &str, approved_by: Option<String>) -> Result<CapabilityApproval> {
+        let approval = self.quarantine.approve(server_name, kind, name, approved_by)?;
+        self.discover_server_capabilities(server_name).await?;
+        Ok(approval)
+    }
+
     /// Static method for capability discovery (used by periodic task)
     async fn discover_server_capabilities_static(
         processes: &Arc<RwLock<HashMap<String, ExternalMcpProcess>>>,
         capabilities: &Arc<RwLock<HashMap<String, Vec<Tool>>>>,
+        prompts: &Arc<RwLock<HashMap<String, Vec<PromptTemplate>>>>,
+        resources: &Arc<RwLock<HashMap<String, Vec<Resource>>>>,
+        change_sender: &broadcast::Sender<CapabilityChange>,
+        quarantine: &Quarantine,
         server_name: &str,
         config: &ExternalMcpConfig,
     ) -> Result<()> {
@@ -570,19 +743,148 @@ mcpServers:
             }
         };
 
+        // Prompts and resources are optional capabilities; servers that don't support them
+        // simply report nothing
+        let (server_prompts, server_resources) = {
+            let processes_guard = processes.read().await;
+            match processes_guard.get(server_name) {
+                Some(process) => (
+                    Self::list_optional::<PromptTemplate>(process, "prompts/list", "prompts").await,
+                    Self::list_optional::<Resource>(process, "resources/list", "resources").await,
+                ),
+                None => (Vec::new(), Vec::new()),
+            }
+        };
+
+        // Capabilities that may carry prompt injection are withheld until approved
+        let current = quarantine.screen(server_name, ServerCapabilitySnapshot {
+            tools,
+            prompts: server_prompts,
+            resources: server_resources,
+        });
+        let tools = current.tools.clone();
+
+        // Diff against the cached set before replacing it
+        let previous = {
+            let capabilities_guard = capabilities.read().await;
+            let prompts_guard = prompts.read().await;
+            let resources_guard = resources.read().await;
+            capabilities_guard.get(server_name).map(|cached_tools| ServerCapabilitySnapshot {
+                tools: cached_tools.clone(),
+                prompts: prompts_guard.get(server_name).cloned().unwrap_or_default(),
+                resources: resources_guard.get(server_name).cloned().unwrap_or_default(),
+            })
+        };
+        let change = CapabilityChange::diff(server_name, previous.as_ref(), &current);
+
         // Store discovered capabilities
         {
             let mut capabilities_guard = capabilities.write().await;
-            capabilities_guard.insert(server_name.to_string(), tools.clone());
+            capabilities_guard.insert(server_name.to_string(), current.tools);
+        }
+        {
+            let mut prompts_guard = prompts.write().await;
+            prompts_guard.insert(server_name.to_string(), current.prompts);
+        }
+        {
+            let mut resources_guard = resources.write().await;
+            resources_guard.insert(server_name.to_string(), current.resources);
         }
 
         // Generate capability file
         Self::generate_capability_file(server_name, &tools, config).await?;
 
+        if change.is_empty() {
+            debug!("No capability changes for External MCP server '{}'", server_name);
+        } else {
+            info!("🔄 Capabilities changed on External MCP server '{}': +{} -{} ~{} tools, prompts changed: {}, resources changed: {}",
+                  server_name, change.tools_added.len(), change.tools_removed.len(), change.tools_changed.len(),
+                  change.prompts_changed, change.resources_changed);
+            if change_sender.send(change).is_err() {
+                debug!("No subscribers for capability changes of server '{}'", server_name);
+            }
+        }
+
         info!("Successfully discovered and generated capabilities for External MCP server: {}", server_name);
         Ok(())
     }
 
+    /// List an optional capability (prompts/resources), returning an empty list if unsupported
+    async fn list_optional<T: serde::de::DeserializeOwned>(process: &ExternalMcpProcess, method: &str, key: &str) -> Vec<T> {
+        match process.send_request(method, Some(json!({}))).await {
+            Ok(response) => {
+                if let Some(error) = response.error {
+                    debug!("Server '{}' does not support {}: {}", process.name, method, error.message);
+                    return Vec::new();
+                }
+                response.result
+                    .and_then(|result| result.get(key).cloned())
+                    .and_then(|items| match serde_json::from_value::<Vec<T>>(items) {
+                        Ok(items) => Some(items),
+                        Err(e) => {
+                            warn!("Failed to parse {} response from server '{}': {}", method, process.name, e);
+                            None
+                        }
+                    })
+                    .unwrap_or_default()
+            }
+            Err(e) => {
+                debug!("Failed to send {} to server '{}': {}", method, process.name, e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Get prompts discovered from a specific server
+    pub async fn get_server_prompts(&self, server_name: &str) -> Option<Vec<PromptTemplate>> {
+        let prompts = self.prompts.read().await;
+        prompts.get(server_name).cloned()
+    }
+
+    /// Get resources discovered from a specific server
+    pub async fn get_server_resources(&self, server_name: &str) -> Option<Vec<Resource>> {
+        let resources = self.resources.read().await;
+        resources.get(server_name).cloned()
+    }
+
+    /// Get resources discovered from all servers
+    pub async fn get_all_resources(&self) -> HashMap<String, Vec<Resource>> {
+        self.resources.read().await.clone()
+    }
+
+    /// Read a resource from the server that exposes it
+    ///
+    /// Only resources the server listed (and that passed screening) can be
+    /// read. The content is returned with the server's own URI.
+    pub async fn read_resource(&self, server_name: &str, uri: &str) -> Result<ResourceContent> {
+        let listed = self.resources.read().await
+            .get(server_name)
+            .is_some_and(|resources| resources.iter().any(|resource| resource.uri == uri));
+        if !listed {
+            return Err(ProxyError::validation(format!("Resource not found on External MCP server '{}': {}", server_name, uri)));
+        }
+
+        let processes = self.processes.read().await;
+        let process = processes.get(server_name)
+            .ok_or_else(|| ProxyError::mcp(format!("External MCP server '{}' not found", server_name)))?;
+        if !process.is_running().await {
+            return Err(ProxyError::connection(format!("External MCP server '{}' is not running", server_name)));
+        }
+
+        let response = process.send_request("resources/read", Some(json!({ "uri": uri }))).await?;
+        if let Some(error) = response.error {
+            return Err(ProxyError::mcp(format!(
+                "External MCP server '{}' failed to read resource '{}': {}", server_name, uri, error.message
+            )));
+        }
+        response.result
+            .and_then(|result| result.get("contents").and_then(|contents| contents.get(0)).cloned())
+            .and_then(|content| serde_json::from_value::<ResourceContent>(content).ok())
+            .ok_or_else(|| ProxyError::mcp(format!(
+                "External MCP server '{}' returned no content for resource '{}'", server_name, uri
+            )))
+    }
+
     /// Generate capability file for a server
     async fn generate_capability_file(server_name: &str, tools: &[Tool], config: &ExternalMcpConfig) -> Result<()> {
         debug!("Generating capability file for External MCP server: {}", server_name);
@@ -600,7 +902,7 @@ mcpServers:
 
         // Convert tools to capability format
         let tool_definitions: Vec<ToolDefinition> = tools.iter().map(|tool| {
-            let tool_full_name = format!("{}_{}", tool.name, server_name);
+            let tool_full_name = remap_tool_name(server_name, &tool.name);
             
             // Get existing settings for this tool, preserving user preferences
             let (enabled, hidden) = existing_settings.get(&tool_full_name)
@@ -621,6 +923,8 @@ mcpServers:
                         "timeout": 30,
                         "retry_count": 2
                     }),
+                    fallback: Vec::new(),
+                    canary: None,
                 },
                 annotations: Some({
                     let mut annotations = std::collections::HashMap::new();
@@ -631,11 +935,20 @@ mcpServers:
                 }),
                 hidden, // Preserve user setting or use default
                 enabled, // Preserve user setting or use default
+                deprecated: false,
+                deprecation_reason: None,
+                sunset_date: None,
+                tags: Vec::new(),
+                output_schema: None,
+                cache: None,
+                background: false,
+                cost: None,
             }
         }).collect();
 
         // Create capability file
         let capability_file = CapabilityFile {
+            schema_version: Some(CAPABILITY_SCHEMA_VERSION),
             metadata: Some(crate::registry::types::FileMetadata {
                 name: Some(format!("{}-external-mcp", server_name)),
                 version: Some("1.0.0".to_string()),
@@ -764,8 +1077,21 @@ impl ExternalMcpManager {
             }
         };
 
+        // Fail fast while the server's circuit is open instead of stacking up timeouts
+        let breaker = self.circuit_breaker(server_name).await;
+        if let Err(retry_after) = breaker.try_acquire() {
+            self.metrics_collector.record_request_error(server_name, "circuit_open", "tools/call").await;
+            warn!("⚡ [CIRCUIT] Rejecting tool '{}' on External MCP server '{}': circuit open", tool_name, server_name);
+            return Err(ProxyError::connection(format!(
+                "External MCP server '{}' is temporarily unavailable: circuit breaker open after repeated failures, retry in {}s",
+                server_name,
+                retry_after.as_secs().max(1)
+            )));
+        }
+
         if !process.is_running().await {
             let error = format!("External MCP server '{}' is not running", server_name);
+            breaker.record_failure();
             self.metrics_collector.record_request_error(server_name, "server_not_running", "tools/call").await;
             return Err(ProxyError::connection(error));
         }
@@ -778,6 +1104,10 @@ impl ExternalMcpManager {
         match process.send_request("tools/call", Some(params)).await {
             Ok(response) => {
                 let elapsed_ms = start_time.elapsed().as_millis() as f64;
+
+                // The server answered, so it counts as healthy for the circuit breaker even if
+                // the tool itself reported an error
+                breaker.record_success(start_time.elapsed());
                 
                 if let Some(error) = response.error {
                     // Record error in metrics
@@ -818,6 +1148,7 @@ impl ExternalMcpManager {
             }
             Err(e) => {
                 let elapsed_ms = start_time.elapsed().as_millis() as f64;
+                breaker.record_failure();
                 
                 // Record request failure in metrics
                 self.metrics_collector.record_request_error(server_name, "request_failed", "tools/call").await;
@@ -830,6 +1161,25 @@ impl ExternalMcpManager {
         }
     }
 
+    /// Get (or lazily create) the circuit breaker for a server
+    async fn circuit_breaker(&self, server_name: &str) -> Arc<CircuitBreaker> {
+        if let Some(breaker) = self.circuit_breakers.read().await.get(server_name) {
+            return Arc::clone(breaker);
+        }
+        let mut breakers = self.circuit_breakers.write().await;
+        Arc::clone(breakers
+            .entry(server_name.to_string())
+            .or_insert_with(|| Arc::new(CircuitBreaker::new(self.config.circuit_breaker.clone()))))
+    }
+
+    /// Get circuit breaker status for every server that has handled a call
+    pub async fn get_circuit_breaker_status(&self) -> HashMap<String, CircuitBreakerStatus> {
+        self.circuit_breakers.read().await
+            .iter()
+            .map(|(name, breaker)| (name.clone(), breaker.status()))
+            .collect()
+    }
+
     /// Get all available tools from all servers
     pub async fn get_all_tools(&self) -> HashMap<String, Vec<Tool>> {
         let capabilities = self.capabilities.read().await;
@@ -919,6 +1269,8 @@ impl ExternalMcpManager {
             let mut capabilities = self.capabilities.write().await;
             capabilities.clear();
         }
+        self.prompts.write().await.clear();
+        self.resources.write().await.clear();
 
         info!("All External MCP servers stopped");
         Ok(())
@@ -945,6 +1297,8 @@ impl ExternalMcpManager {
             let mut capabilities = self.capabilities.write().await;
             capabilities.remove(server_name);
         }
+        self.prompts.write().await.remove(server_name);
+        self.resources.write().await.remove(server_name);
 
         info!("External MCP server '{}' stopped and removed from active servers", server_name);
         Ok(())
@@ -972,6 +1326,11 @@ impl ExternalMcpManager {
             return Err(ProxyError::config(format!("Server '{}' not found in configuration", server_name)));
         }
 
+        // A fresh process starts with a clean circuit
+        if let Some(breaker) = self.circuit_breakers.read().await.get(server_name) {
+            breaker.reset();
+        }
+
         info!("Successfully restarted External MCP server: {}", server_name);
         Ok(())
     }
@@ -1,18 +1,21 @@
 //! External MCP Process Management
 //! 
 //! This module manages MCP server processes using Claude Desktop's exact configuration format.
-//! It can spawn local processes (npx, uv run, python, node) and containerized processes (docker run).
+//! It can spawn local processes (npx, uv run, python, node), containerized processes (docker run)
+//! and processes on remote hosts over SSH.
 
 use crate::config::{McpServerConfig, ExternalMcpServersConfig, ContainerConfig, McpClientConfig};
 use crate::error::{ProxyError, Result};
 use crate::mcp::types::{McpRequest, McpResponse, Tool};
+use crate::mcp::upstream_notifications::UpstreamNotification;
+use crate::mcp::ssh_transport::{build_ssh_command, default_known_hosts_dir};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::process::Stdio;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
 use tokio::time::{timeout, Duration, Instant};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
@@ -40,6 +43,8 @@ pub struct ExternalMcpProcess {
     is_healthy: Arc<RwLock<bool>>,
     /// Process start time for uptime calculation
     start_time: Option<Instant>,
+    /// Where server-initiated notifications are forwarded (if anyone listens)
+    notification_sender: Option<broadcast::Sender<UpstreamNotification>>,
 }
 
 impl ExternalMcpProcess {
@@ -58,30 +63,43 @@ impl ExternalMcpProcess {
             max_restart_attempts,
             is_healthy: Arc::new(RwLock::new(false)),
             start_time: None,
+            notification_sender: None,
         }
     }
 
+    /// Forward notifications emitted by this server to the given channel
+    pub fn set_notification_sender(&mut self, sender: broadcast::Sender<UpstreamNotification>) {
+        self.notification_sender = Some(sender);
+    }
+
     /// Start the MCP server process
     pub async fn start(&mut self) -> Result<()> {
         info!("Starting External MCP server: {}", self.name);
 
         // Build command from configuration
-        let mut cmd = Command::new(&self.config.command);
-        cmd.args(&self.config.args);
+        let mut cmd = if let Some(ref ssh) = self.config.ssh {
+            // Remote server: env and cwd are applied on the remote side
+            info!("Launching MCP server '{}' on {} over SSH", self.name, ssh.host);
+            build_ssh_command(&self.name, &self.config, ssh, &default_known_hosts_dir())?
+        } else {
+            let mut cmd = Command::new(&self.config.command);
+            cmd.args(&self.config.args);
 
-        // Set environment variables
-        if let Some(ref env) = self.config.env {
-            for (key, value) in env {
-                // Support environment variable expansion
-                let expanded_value = expand_env_vars(value);
-                cmd.env(key, expanded_value);
+            // Set environment variables
+            if let Some(ref env) = self.config.env {
+                for (key, value) in env {
+                    // Support environment variable expansion
+                    let expanded_value = expand_env_vars(value);
+                    cmd.env(key, expanded_value);
+                }
             }
-        }
 
-        // Set working directory
-        if let Some(ref cwd) = self.config.cwd {
-            cmd.current_dir(cwd);
-        }
+            // Set working directory
+            if let Some(ref cwd) = self.config.cwd {
+                cmd.current_dir(cwd);
+            }
+            cmd
+        };
 
         // Configure stdio for MCP communication
         cmd.stdin(Stdio::piped())
@@ -126,12 +144,25 @@ impl ExternalMcpProcess {
         let pending_requests = Arc::clone(&self.pending_requests);
         let server_name = self.name.clone();
         let is_healthy = Arc::clone(&self.is_healthy);
+        let notification_sender = self.notification_sender.clone();
         tokio::spawn(async move {
             let reader = BufReader::new(stdout);
             let mut lines = reader.lines();
             
             while let Ok(Some(line)) = lines.next_line().await {
                 debug!("MCP server '{}' stdout: {}", server_name, line);
+
+                // Server-initiated notifications carry no id; forward them instead of
+                // treating them as malformed responses
+                if let Ok(message) = serde_json::from_str::<Value>(&line) {
+                    if let Some(notification) = UpstreamNotification::from_message(&server_name, &message) {
+                        debug!("MCP server '{}' sent notification: {}", server_name, notification.notification.method);
+                        if let Some(ref sender) = notification_sender {
+                            let _ = sender.send(notification);
+                        }
+                        continue;
+                    }
+                }
                 
                 // Parse JSON-RPC response
                 match serde_json::from_str::<McpResponse>(&line) {
@@ -0,0 +1,521 @@
+//! Background jobs for long-running tools
+//!
+//! Calls to a tool flagged `background: true` don't wait for the tool: they
+//! return a job ID at once and the tool runs as a job. Clients follow the job
+//! with the generated tools:
+//!
+//! - `job_status` — the job's state (`running`, `completed`, `failed`,
+//!   `cancelled` or `interrupted`)
+//! - `job_result` — the tool's result once the job has finished
+//! - `job_cancel` — stop a running job
+//!
+//! A job belongs to the caller that started it: the user the call was made
+//! for or, failing that, its API key. Other callers are told the job doesn't
+//! exist. Jobs started without either are open to anyone.
+//!
+//! Jobs are persisted as JSON (`jobs.path`), so their results survive a
+//! restart. The file is readable by the current user only and, with
+//! `auth.token_encryption` configured, encrypted like stored tokens. Jobs that
+//! were still running when the server stopped are marked `interrupted`.
+//! Finished jobs are dropped after `jobs.retention_hours`.
+
+use crate::auth::{SealedToken, TokenCipher};
+use crate::config::write_atomic;
+use crate::error::{ProxyError, Result};
+use crate::mcp::types::{Tool, ToolCall, ToolResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::watch;
+use tokio::task::AbortHandle;
+use tracing::{debug, warn};
+
+/// Generated tool reporting a job's state
+pub const JOB_STATUS_TOOL: &str = "job_status";
+/// Generated tool returning a finished job's result
+pub const JOB_RESULT_TOOL: &str = "job_result";
+/// Generated tool cancelling a running job
+pub const JOB_CANCEL_TOOL: &str = "job_cancel";
+
+fn default_jobs_path() -> String {
+    "./.magictunnel/jobs.json".to_string()
+}
+
+fn default_retention_hours() -> u64 {
+    24
+}
+
+/// Where jobs are persisted and for how long finished jobs are kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobsConfig {
+    /// JSON file jobs are persisted to
+    #[serde(default = "default_jobs_path")]
+    pub path: String,
+    /// Hours a finished job's result stays available
+    #[serde(default = "default_retention_hours")]
+    pub retention_hours: u64,
+}
+
+impl JobsConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.path.trim().is_empty() {
+            return Err(ProxyError::config("jobs.path cannot be empty"));
+        }
+        if self.retention_hours == 0 {
+            return Err(ProxyError::config("jobs.retention_hours must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self { path: default_jobs_path(), retention_hours: default_retention_hours() }
+    }
+}
+
+/// Lifecycle state of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    /// The tool returned a successful result
+    Completed,
+    /// The tool returned an error
+    Failed,
+    Cancelled,
+    /// The server stopped while the job was running
+    Interrupted,
+}
+
+impl JobState {
+    pub fn is_finished(self) -> bool {
+        self != JobState::Running
+    }
+}
+
+/// A tool call running, or having run, in the background
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub tool_name: String,
+    pub arguments: Value,
+    pub state: JobState,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Caller that started the job, see [`current_owner`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// The tool's result, once the job has finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<ToolResult>,
+}
+
+impl Job {
+    /// The job without its result, as reported by `job_status`
+    pub fn status(&self) -> Value {
+        json!({
+            "job_id": self.id,
+            "tool_name": self.tool_name,
+            "state": self.state,
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+        })
+    }
+
+    /// Whether `caller` may see and cancel the job
+    fn is_visible_to(&self, caller: Option<&str>) -> bool {
+        self.owner.is_none() || self.owner.as_deref() == caller
+    }
+}
+
+/// Owner of the jobs the current call starts: its user, or else its API key
+pub fn current_owner() -> Option<String> {
+    crate::routing::cost::current_user().or_else(crate::routing::rate_limit::current_client)
+}
+
+/// The jobs file, sealed when a cipher is configured
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredJobs {
+    Sealed { sealed: SealedToken },
+    Plain(BTreeMap<String, Job>),
+}
+
+/// Background jobs, persisted to a JSON file when opened with a path
+#[derive(Debug, Default)]
+pub struct JobManager {
+    path: Option<PathBuf>,
+    cipher: Option<Arc<TokenCipher>>,
+    retention: Option<chrono::Duration>,
+    jobs: Mutex<BTreeMap<String, Job>>,
+    tasks: Mutex<HashMap<String, AbortHandle>>,
+    /// Latest snapshot of the jobs for the writer task, started by the first save
+    writer: OnceLock<watch::Sender<Vec<u8>>>,
+}
+
+impl JobManager {
+    /// Jobs kept in memory only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the job store, loading jobs from `config.path` if it exists
+    ///
+    /// With a `cipher` the store is written encrypted; a plain store is
+    /// encrypted the next time it is saved.
+    pub async fn open(config: &JobsConfig, cipher: Option<Arc<TokenCipher>>) -> Result<Self> {
+        let path = PathBuf::from(&config.path);
+        let mut jobs = match tokio::fs::read(&path).await {
+            Ok(content) => Self::decode(&path, &content, cipher.as_deref())?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(ProxyError::config(format!("Failed to read jobs '{}': {}", path.display(), e))),
+        };
+        let mut interrupted = false;
+        for job in jobs.values_mut().filter(|job| job.state == JobState::Running) {
+            job.state = JobState::Interrupted;
+            job.updated_at = Utc::now();
+            interrupted = true;
+        }
+
+        let manager = Self {
+            path: Some(path),
+            cipher,
+            retention: Some(chrono::Duration::hours(config.retention_hours as i64)),
+            jobs: Mutex::new(jobs),
+            ..Default::default()
+        };
+        if interrupted {
+            if let Ok(mut jobs) = manager.jobs.lock() {
+                manager.save(&mut jobs);
+            }
+        }
+        Ok(manager)
+    }
+
+    fn decode(path: &Path, content: &[u8], cipher: Option<&TokenCipher>) -> Result<BTreeMap<String, Job>> {
+        let invalid = |e: String| ProxyError::config(format!("Invalid jobs file '{}': {}", path.display(), e));
+        match (serde_json::from_slice(content).map_err(|e| invalid(e.to_string()))?, cipher) {
+            (StoredJobs::Plain(jobs), _) => Ok(jobs),
+            (StoredJobs::Sealed { sealed }, Some(cipher)) => {
+                let content = cipher.open(&sealed).map_err(|e| invalid(e.to_string()))?;
+                serde_json::from_slice(&content).map_err(|e| invalid(e.to_string()))
+            }
+            (StoredJobs::Sealed { .. }, None) => {
+                Err(invalid("it is encrypted but auth.token_encryption is not configured".to_string()))
+            }
+        }
+    }
+
+    /// Run `work`, the call of `tool_name` with `arguments`, as a new job of `owner`
+    pub fn start<F>(self: &Arc<Self>, tool_name: &str, arguments: &Value, owner: Option<String>, work: F) -> Job
+    where
+        F: Future<Output = ToolResult> + Send + 'static,
+    {
+        let now = Utc::now();
+        let job = Job {
+            id: uuid::Uuid::new_v4().to_string(),
+            tool_name: tool_name.to_string(),
+            arguments: arguments.clone(),
+            state: JobState::Running,
+            created_at: now,
+            updated_at: now,
+            owner,
+            result: None,
+        };
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.insert(job.id.clone(), job.clone());
+            self.save(&mut jobs);
+        }
+        debug!("Started job {} for tool '{}'", job.id, tool_name);
+
+        // Holding the lock until the handle is stored keeps a fast job from finishing first
+        let mut tasks = self.tasks.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let manager = Arc::clone(self);
+        let id = job.id.clone();
+        let task = tokio::spawn(async move {
+            let result = work.await;
+            manager.finish(&id, result);
+        });
+        tasks.insert(job.id.clone(), task.abort_handle());
+        job
+    }
+
+    fn finish(&self, id: &str, result: ToolResult) {
+        if let Ok(mut tasks) = self.tasks.lock() {
+            tasks.remove(id);
+        }
+        let Ok(mut jobs) = self.jobs.lock() else { return };
+        let Some(job) = jobs.get_mut(id).filter(|job| job.state == JobState::Running) else { return };
+        job.state = if result.is_error { JobState::Failed } else { JobState::Completed };
+        job.updated_at = Utc::now();
+        job.result = Some(result);
+        debug!("Job {} {:?}", id, job.state);
+        self.save(&mut jobs);
+    }
+
+    /// A job by ID
+    pub fn get(&self, id: &str) -> Option<Job> {
+        self.jobs.lock().ok()?.get(id).cloned()
+    }
+
+    /// All jobs, oldest first
+    pub fn list(&self) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self.jobs.lock().map(|jobs| jobs.values().cloned().collect()).unwrap_or_default();
+        jobs.sort_by_key(|job| job.created_at);
+        jobs
+    }
+
+    /// Stop a running job of `caller`
+    pub fn cancel(&self, id: &str, caller: Option<&str>) -> Result<Job> {
+        let mut jobs = self.jobs.lock().map_err(|_| ProxyError::routing("Job store lock poisoned"))?;
+        let job = jobs.get_mut(id)
+            .filter(|job| job.is_visible_to(caller))
+            .ok_or_else(|| ProxyError::validation(format!("Unknown job '{}'", id)))?;
+        if job.state.is_finished() {
+            return Err(ProxyError::validation(format!("Job '{}' has already finished ({:?})", id, job.state)));
+        }
+        if let Some(task) = self.tasks.lock().ok().and_then(|mut tasks| tasks.remove(id)) {
+            task.abort();
+        }
+        job.state = JobState::Cancelled;
+        job.updated_at = Utc::now();
+        let job = job.clone();
+        self.save(&mut jobs);
+        Ok(job)
+    }
+
+    /// Handle a call by `caller` to one of the generated job tools; `None` for any other tool
+    pub fn call_tool(&self, tool_call: &ToolCall, caller: Option<&str>) -> Option<ToolResult> {
+        if ![JOB_STATUS_TOOL, JOB_RESULT_TOOL, JOB_CANCEL_TOOL].contains(&tool_call.name.as_str()) {
+            return None;
+        }
+        let Some(id) = tool_call.arguments.get("job_id").and_then(|v| v.as_str()) else {
+            return Some(ToolResult::error(format!("{} requires a 'job_id' string", tool_call.name)));
+        };
+
+        let result = match tool_call.name.as_str() {
+            JOB_CANCEL_TOOL => match self.cancel(id, caller) {
+                Ok(job) => ToolResult::success(job.status()),
+                Err(e) => ToolResult::error(e.to_string()),
+            },
+            _ => match self.get(id).filter(|job| job.is_visible_to(caller)) {
+                None => ToolResult::error(format!("Unknown job '{}'", id)),
+                Some(job) if tool_call.name == JOB_STATUS_TOOL => ToolResult::success(job.status()),
+                Some(Job { result: Some(result), .. }) => result,
+                Some(job) => ToolResult::error_with_metadata(
+                    format!("Job '{}' has no result: it is {:?}", id, job.state),
+                    json!({ "error_category": "job_not_finished", "job": job.status() }),
+                ),
+            },
+        };
+        Some(result)
+    }
+
+    /// Persist the jobs, dropping finished ones past their retention
+    ///
+    /// Only the snapshot is taken here, under the lock; a writer task writes
+    /// the latest one, skipping snapshots superseded while it was busy.
+    fn save(&self, jobs: &mut BTreeMap<String, Job>) {
+        if let Some(retention) = self.retention {
+            let cutoff = Utc::now() - retention;
+            jobs.retain(|_, job| !job.state.is_finished() || job.updated_at > cutoff);
+        }
+        let Some(path) = &self.path else { return };
+
+        let snapshot = match serde_json::to_vec_pretty(jobs) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                warn!("Failed to persist jobs to '{}': {}", path.display(), e);
+                return;
+            }
+        };
+        let writer = self.writer.get_or_init(|| {
+            let (sender, receiver) = watch::channel(Vec::new());
+            tokio::spawn(write_jobs(path.clone(), self.cipher.clone(), receiver));
+            sender
+        });
+        writer.send_replace(snapshot);
+    }
+}
+
+/// Write each new snapshot of the jobs to `path` until the manager is dropped
+async fn write_jobs(path: PathBuf, cipher: Option<Arc<TokenCipher>>, mut snapshots: watch::Receiver<Vec<u8>>) {
+    while snapshots.changed().await.is_ok() {
+        let snapshot = snapshots.borrow_and_update().clone();
+        if let Err(e) = write_snapshot(&path, cipher.as_deref(), snapshot).await {
+            // Jobs keep running; only their persistence is lost
+            warn!("Failed to persist jobs to '{}': {}", path.display(), e);
+        }
+    }
+}
+
+async fn write_snapshot(path: &Path, cipher: Option<&TokenCipher>, snapshot: Vec<u8>) -> Result<()> {
+    let content = match cipher {
+        Some(cipher) => serde_json::to_vec(&StoredJobs::Sealed { sealed: cipher.seal(&snapshot)? })?,
+        None => snapshot,
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    // Private: jobs hold tool arguments and results
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || write_atomic(&path, &content, true))
+        .await
+        .map_err(|e| ProxyError::mcp(format!("Jobs writer failed: {}", e)))??;
+    Ok(())
+}
+
+/// The generated `job_status`, `job_result` and `job_cancel` tools
+pub fn job_tools() -> Vec<Tool> {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "job_id": {"type": "string", "description": "ID returned when the background job was started"}
+        },
+        "required": ["job_id"]
+    });
+    [
+        (JOB_STATUS_TOOL, "Get the state of a background job"),
+        (JOB_RESULT_TOOL, "Get the result of a finished background job"),
+        (JOB_CANCEL_TOOL, "Cancel a running background job"),
+    ]
+    .into_iter()
+    .filter_map(|(name, description)| Tool::new(name.to_string(), description.to_string(), schema.clone()).ok())
+    .collect()
+}
+
+/// Result of a call to a background tool: the ID of the job it started
+pub fn job_started_result(job: &Job) -> ToolResult {
+    ToolResult::success_with_metadata(
+        json!({
+            "job_id": job.id,
+            "state": job.state,
+            "message": format!("Started job {}; use {} and {} to follow it", job.id, JOB_STATUS_TOOL, JOB_RESULT_TOOL),
+        }),
+        json!({ "tool_name": job.tool_name, "background": true, "job_id": job.id }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn wait_for(manager: &JobManager, id: &str, state: JobState) -> Job {
+        for _ in 0..100 {
+            let job = manager.get(id).unwrap();
+            if job.state == state {
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {} never reached {:?}", id, state);
+    }
+
+    #[tokio::test]
+    async fn test_job_runs_in_background() {
+        let manager = Arc::new(JobManager::new());
+        let job = manager.start("build", &json!({}), None, async { ToolResult::success(json!({"artifact": "app.tar"})) });
+        assert_eq!(job.state, JobState::Running);
+
+        let job = wait_for(&manager, &job.id, JobState::Completed).await;
+        let call = ToolCall::new(JOB_RESULT_TOOL.to_string(), json!({"job_id": job.id}));
+        let result = manager.call_tool(&call, None).unwrap();
+        assert_eq!(result.data.unwrap()["artifact"], "app.tar");
+        assert!(manager.call_tool(&ToolCall::new("build".to_string(), json!({})), None).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_running_job() {
+        let manager = Arc::new(JobManager::new());
+        let job = manager.start("build", &json!({}), None, async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            ToolResult::success(json!({}))
+        });
+
+        let cancelled = manager.call_tool(&ToolCall::new(JOB_CANCEL_TOOL.to_string(), json!({"job_id": job.id})), None).unwrap();
+        assert!(!cancelled.is_error);
+        assert_eq!(manager.get(&job.id).unwrap().state, JobState::Cancelled);
+        assert!(manager.cancel(&job.id, None).is_err());
+
+        let result = manager.call_tool(&ToolCall::new(JOB_RESULT_TOOL.to_string(), json!({"job_id": job.id})), None).unwrap();
+        assert!(result.is_error);
+    }
+
+    #[tokio::test]
+    async fn test_jobs_are_private_to_their_owner() {
+        let manager = Arc::new(JobManager::new());
+        let job = manager.start("build", &json!({}), Some("alice".to_string()), std::future::pending());
+        let call = |name: &str| ToolCall::new(name.to_string(), json!({"job_id": job.id}));
+
+        for name in [JOB_STATUS_TOOL, JOB_RESULT_TOOL, JOB_CANCEL_TOOL] {
+            for caller in [Some("bob"), None] {
+                let result = manager.call_tool(&call(name), caller).unwrap();
+                assert!(result.is_error);
+                assert!(result.error.unwrap().contains("Unknown job"));
+            }
+        }
+        assert_eq!(manager.get(&job.id).unwrap().state, JobState::Running);
+        assert!(!manager.call_tool(&call(JOB_STATUS_TOOL), Some("alice")).unwrap().is_error);
+        assert!(!manager.call_tool(&call(JOB_CANCEL_TOOL), Some("alice")).unwrap().is_error);
+    }
+
+    /// Wait until the writer task has persisted `id` to `path`
+    async fn wait_for_saved(path: &Path, id: &str) {
+        for _ in 0..100 {
+            if tokio::fs::read(path).await.is_ok_and(|content| String::from_utf8_lossy(&content).contains(id)) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {} was never saved", id);
+    }
+
+    #[tokio::test]
+    async fn test_jobs_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.json");
+        let config = JobsConfig { path: path.display().to_string(), retention_hours: 24 };
+
+        let manager = Arc::new(JobManager::open(&config, None).await.unwrap());
+        let done = manager.start("build", &json!({}), Some("alice".to_string()), async { ToolResult::success(json!({"ok": true})) });
+        wait_for(&manager, &done.id, JobState::Completed).await;
+        let running = manager.start("deploy", &json!({}), None, std::future::pending());
+        wait_for_saved(&path, &running.id).await;
+
+        let reopened = JobManager::open(&config, None).await.unwrap();
+        let done = reopened.get(&done.id).unwrap();
+        assert_eq!(done.owner.as_deref(), Some("alice"));
+        assert_eq!(done.result.unwrap().data.unwrap()["ok"], true);
+        assert_eq!(reopened.get(&running.id).unwrap().state, JobState::Interrupted);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.json");
+        let config = JobsConfig { path: path.display().to_string(), retention_hours: 24 };
+        let cipher = || Some(Arc::new(TokenCipher::new(b"jobs-key-0123456789abcdefghijklmn", [])));
+
+        let manager = Arc::new(JobManager::open(&config, cipher()).await.unwrap());
+        let job = manager.start("build", &json!({"token": "s3cret"}), None, std::future::pending());
+        wait_for_saved(&path, "ciphertext").await;
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("s3cret") && !content.contains(&job.id));
+
+        let reopened = JobManager::open(&config, cipher()).await.unwrap();
+        assert_eq!(reopened.get(&job.id).unwrap().arguments["token"], "s3cret");
+        assert!(JobManager::open(&config, None).await.is_err());
+    }
+}
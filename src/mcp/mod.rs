@@ -9,6 +9,11 @@ pub mod client;
 pub mod external_process;
 pub mod external_manager;
 pub mod external_integration;
+pub mod capability_sync;
+pub mod injection_scan;
+pub mod upstream_notifications;
+pub mod circuit_breaker;
+pub mod ssh_transport;
 pub mod network_service_manager;
 // Network clients for external MCP services
 pub mod clients;
@@ -18,6 +23,9 @@ pub mod resources;
 pub mod prompts;
 pub mod logging;
 pub mod notifications;
+pub mod progress;
+pub mod batch;
+pub mod jobs;
 pub mod errors;
 pub mod session;
 pub mod validation;
@@ -32,6 +40,9 @@ pub use server::McpServer;
 pub use external_integration::{ExternalMcpIntegration, ExternalMcpAgent};
 pub use external_manager::ExternalMcpManager;
 pub use external_process::ExternalMcpProcess;
+pub use capability_sync::{CapabilityChange, ServerCapabilitySnapshot};
+pub use upstream_notifications::{UpstreamNotification, UpstreamNotificationAction};
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerStatus, CircuitPermit, CircuitState};
 pub use network_service_manager::{NetworkMcpServiceManager, NetworkMcpService};
 // Network clients
 pub use clients::{HttpMcpClient, HttpClientConfig, HttpAuthConfig, SseMcpClient, SseClientConfig, SseAuthConfig};
@@ -41,7 +52,7 @@ pub use prompts::*;
 pub use logging::*;
 pub use notifications::*;
 pub use errors::{McpError, McpErrorCode};
-pub use session::{McpSessionManager, McpSession, SessionConfig, ClientInfo, SessionStats};
+pub use session::{McpSessionManager, McpSession, SessionConfig, SessionExpiry, SessionTimeouts, SessionsConfig, ClientInfo, SessionStats};
 pub use validation::{McpMessageValidator, ValidationConfig};
 pub use metrics::{McpMetricsCollector, McpServiceMetrics, HealthStatus, HealthCheckResult, McpMetricsSummary};
 pub use health_checker::{McpHealthChecker, HealthCheckConfig};
@@ -258,6 +258,14 @@ impl NetworkMcpServiceManager {
                 annotations: None,
                 hidden: false,
                 enabled: true,
+                deprecated: false,
+                deprecation_reason: None,
+                sunset_date: None,
+                tags: Vec::new(),
+                output_schema: None,
+                cache: None,
+                background: false,
+                cost: None,
             }
         }).collect();
 
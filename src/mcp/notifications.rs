@@ -6,7 +6,7 @@
 use crate::error::{Result, ProxyError};
 use crate::mcp::types::McpNotification;
 use serde_json::Value;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 use tokio::sync::broadcast;
 use tracing::{debug, info};
@@ -17,6 +17,8 @@ pub struct McpNotificationManager {
     notification_sender: broadcast::Sender<McpNotification>,
     /// Set of subscribed resource URIs
     resource_subscriptions: Arc<RwLock<HashSet<String>>>,
+    /// Resource URIs each session subscribed to
+    session_subscriptions: Arc<RwLock<HashMap<String, HashSet<String>>>>,
     /// Capability flags
     capabilities: NotificationCapabilities,
 }
@@ -52,6 +54,7 @@ impl McpNotificationManager {
         Self {
             notification_sender: sender,
             resource_subscriptions: Arc::new(RwLock::new(HashSet::new())),
+            session_subscriptions: Arc::new(RwLock::new(HashMap::new())),
             capabilities: NotificationCapabilities::default(),
         }
     }
@@ -62,6 +65,7 @@ impl McpNotificationManager {
         Self {
             notification_sender: sender,
             resource_subscriptions: Arc::new(RwLock::new(HashSet::new())),
+            session_subscriptions: Arc::new(RwLock::new(HashMap::new())),
             capabilities,
         }
     }
@@ -159,6 +163,61 @@ impl McpNotificationManager {
         Ok(())
     }
 
+    /// Subscribe a session to updates of a resource
+    pub fn subscribe_session_to_resource(&self, session_id: &str, uri: String) -> Result<()> {
+        self.subscribe_to_resource(uri.clone())?;
+        let mut sessions = self.session_subscriptions.write()
+            .map_err(|e| ProxyError::mcp(format!("Failed to acquire write lock: {}", e)))?;
+        sessions.entry(session_id.to_string()).or_default().insert(uri);
+        Ok(())
+    }
+
+    /// Unsubscribe a session from updates of a resource
+    pub fn unsubscribe_session_from_resource(&self, session_id: &str, uri: &str) -> Result<()> {
+        let still_subscribed = {
+            let mut sessions = self.session_subscriptions.write()
+                .map_err(|e| ProxyError::mcp(format!("Failed to acquire write lock: {}", e)))?;
+            if let Some(uris) = sessions.get_mut(session_id) {
+                uris.remove(uri);
+                if uris.is_empty() {
+                    sessions.remove(session_id);
+                }
+            }
+            sessions.values().any(|uris| uris.contains(uri))
+        };
+        if still_subscribed {
+            return Ok(());
+        }
+        self.unsubscribe_from_resource(uri)
+    }
+
+    /// Drop the subscriptions of a session that ended
+    pub fn forget_session(&self, session_id: &str) {
+        let uris = match self.session_subscriptions.read() {
+            Ok(sessions) => sessions.get(session_id).cloned().unwrap_or_default(),
+            Err(_) => return,
+        };
+        for uri in uris {
+            let _ = self.unsubscribe_session_from_resource(session_id, &uri);
+        }
+    }
+
+    /// Whether a notification should be delivered to a session
+    ///
+    /// Resource updates go only to the sessions subscribed to the resource;
+    /// every other notification goes to all sessions.
+    pub fn is_for_session(&self, session_id: &str, notification: &McpNotification) -> bool {
+        if notification.method != "notifications/resources/updated" {
+            return true;
+        }
+        let Some(uri) = notification.params.as_ref().and_then(|p| p.get("uri")).and_then(Value::as_str) else {
+            return false;
+        };
+        self.session_subscriptions.read()
+            .map(|sessions| sessions.get(session_id).is_some_and(|uris| uris.contains(uri)))
+            .unwrap_or(false)
+    }
+
     /// Notify that a resource has been updated
     pub fn notify_resource_updated(&self, uri: String) -> Result<()> {
         if !self.capabilities.resource_subscriptions {
@@ -0,0 +1,87 @@
+//! Progress notifications for long-running tool calls
+//!
+//! When a `tools/call` request carries `_meta.progressToken` and the transport
+//! can push messages while the call runs, the transport runs the call inside
+//! [`ProgressReporter::scope`]. Agents then report progress through
+//! [`current`] without the reporter being threaded through the router.
+
+use crate::mcp::types::McpNotification;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+
+tokio::task_local! {
+    static PROGRESS: ProgressReporter;
+}
+
+/// Sends `notifications/progress` for one request
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    token: Value,
+    sender: UnboundedSender<McpNotification>,
+    progress: Arc<AtomicU64>,
+}
+
+impl ProgressReporter {
+    pub fn new(token: Value, sender: UnboundedSender<McpNotification>) -> Self {
+        Self { token, sender, progress: Arc::new(AtomicU64::new(0)) }
+    }
+
+    /// Reporter for a request whose params carry `_meta.progressToken`
+    pub fn for_request(params: Option<&Value>, sender: UnboundedSender<McpNotification>) -> Option<Self> {
+        let token = params?.get("_meta")?.get("progressToken")?;
+        (token.is_string() || token.is_number()).then(|| Self::new(token.clone(), sender))
+    }
+
+    /// Report a step with a message; progress increases by one per call
+    pub fn report(&self, message: impl Into<String>) {
+        let progress = self.progress.fetch_add(1, Ordering::Relaxed) + 1;
+        // The client may have gone away; progress is best-effort
+        let _ = self.sender.send(McpNotification::progress(self.token.clone(), progress, None, Some(message.into())));
+    }
+
+    /// Run `future` with this reporter as the [`current`] one
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        PROGRESS.scope(self, future).await
+    }
+}
+
+/// Reporter of the request being handled, if its client asked for progress
+pub fn current() -> Option<ProgressReporter> {
+    PROGRESS.try_with(|reporter| reporter.clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_reports_within_scope() {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let params = json!({"name": "build", "_meta": {"progressToken": "tok-1"}});
+        let reporter = ProgressReporter::for_request(Some(&params), sender).unwrap();
+
+        assert!(current().is_none());
+        reporter.scope(async {
+            let reporter = current().unwrap();
+            reporter.report("pulling image");
+            reporter.report("running");
+        }).await;
+
+        let first = receiver.recv().await.unwrap();
+        assert_eq!(first.method, "notifications/progress");
+        assert_eq!(first.params.unwrap(), json!({"progressToken": "tok-1", "progress": 1, "message": "pulling image"}));
+        assert_eq!(receiver.recv().await.unwrap().params.unwrap()["progress"], 2);
+    }
+
+    #[test]
+    fn test_requires_progress_token() {
+        let (sender, _receiver) = tokio::sync::mpsc::unbounded_channel();
+        assert!(ProgressReporter::for_request(Some(&json!({"name": "build"})), sender.clone()).is_none());
+        assert!(ProgressReporter::for_request(Some(&json!({"_meta": {"progressToken": {}}})), sender.clone()).is_none());
+        assert!(ProgressReporter::for_request(None, sender).is_none());
+    }
+}
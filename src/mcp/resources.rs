@@ -7,7 +7,9 @@
 //! - Resource URI handling
 
 use crate::error::{Result, ProxyError};
+use crate::mcp::external_manager::ExternalMcpManager;
 use crate::mcp::types::{Resource, ResourceContent, ResourceAnnotations};
+use crate::mcp::upstream_notifications::{parse_resource_uri, remap_resource_uri, EXTERNAL_RESOURCE_SCHEME};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::fs;
@@ -238,6 +240,49 @@ impl ResourceProvider for FileResourceProvider {
     }
 }
 
+/// Resources of External MCP servers, exposed as `external-mcp://<server>/<uri>`
+pub struct ExternalResourceProvider {
+    manager: Arc<ExternalMcpManager>,
+}
+
+impl ExternalResourceProvider {
+    /// Create a provider serving the resources discovered by `manager`
+    pub fn new(manager: Arc<ExternalMcpManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait::async_trait]
+impl ResourceProvider for ExternalResourceProvider {
+    async fn list_resources(&self, _cursor: Option<String>) -> Result<(Vec<Resource>, Option<String>)> {
+        let mut resources = Vec::new();
+        for (server_name, server_resources) in self.manager.get_all_resources().await {
+            resources.extend(server_resources.into_iter().map(|mut resource| {
+                resource.uri = remap_resource_uri(&server_name, &resource.uri);
+                resource
+            }));
+        }
+        resources.sort_by(|a, b| a.uri.cmp(&b.uri));
+        Ok((resources, None))
+    }
+
+    async fn read_resource(&self, uri: &str) -> Result<ResourceContent> {
+        let (server_name, upstream_uri) = parse_resource_uri(uri)
+            .ok_or_else(|| ProxyError::validation(format!("Invalid URI for this provider: {}", uri)))?;
+        let mut content = self.manager.read_resource(server_name, upstream_uri).await?;
+        content.uri = uri.to_string();
+        Ok(content)
+    }
+
+    fn supports_uri(&self, uri: &str) -> bool {
+        uri.strip_prefix(EXTERNAL_RESOURCE_SCHEME).is_some_and(|rest| rest.starts_with("://"))
+    }
+
+    fn name(&self) -> &str {
+        "external-mcp"
+    }
+}
+
 /// Resource manager that coordinates multiple resource providers
 pub struct ResourceManager {
     /// Registered resource providers
@@ -1,22 +1,27 @@
 //! MCP Server implementation
 
-use crate::auth::AuthenticationMiddleware;
+use crate::auth::{AuthenticationMiddleware, AuthenticationResult, NetworkPolicy};
 use crate::config::{RegistryConfig, AuthConfig, TlsConfig, TlsMode};
 use crate::error::{Result, ProxyError};
 
 
 
 use crate::mcp::types::*;
-use crate::mcp::resources::{ResourceManager, FileResourceProvider};
+use crate::mcp::resources::{ExternalResourceProvider, ResourceManager, FileResourceProvider};
 use crate::mcp::prompts::{PromptManager};
 use crate::mcp::logging::{McpLoggerManager, McpLogger};
 use crate::mcp::notifications::{McpNotificationManager};
 
 
+use crate::mcp::batch::BatchToolCallRequest;
 use crate::mcp::errors::{McpError, McpErrorCode};
-use crate::mcp::session::McpSessionManager;
+use crate::mcp::session::{McpSessionManager, SessionConfig};
 use crate::mcp::validation::McpMessageValidator;
+use crate::registry::output_schema::{structured_output, OutputValidationMode, SchemaDriftTracker};
 use crate::registry::service::RegistryService;
+use crate::registry::toolset::{TagExpression, ToolFilter, ToolListParams, ToolScope};
+use crate::registry::types::ToolDefinition;
+use crate::mcp::jobs::{self, JobManager};
 use crate::routing::{Router, types::AgentResult};
 use crate::web::configure_dashboard_api;
 use actix_web::{web, App, HttpServer, HttpResponse, middleware::Logger, HttpRequest};
@@ -26,8 +31,13 @@ use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::{debug, info, warn, error};
 use serde_json::{json, Value};
 use std::sync::Arc;
+use std::time::Duration;
+
+/// How often sessions are checked for expiry
+const SESSION_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
 
 /// MCP Server that handles protocol communication
+#[derive(Clone)]
 pub struct McpServer {
     /// High-performance registry service
     registry: Arc<RegistryService>,
@@ -53,6 +63,12 @@ pub struct McpServer {
     smart_discovery: Option<Arc<crate::discovery::SmartDiscoveryService>>,
     /// External MCP integration for managing external MCP servers ✅ **NEW**
     external_integration: Option<Arc<tokio::sync::RwLock<crate::mcp::external_integration::ExternalMcpIntegration>>>,
+    /// Per-tool mismatches between outputs and output schemas
+    schema_drift: Arc<SchemaDriftTracker>,
+    /// Calls to background tools, followed with the job tools
+    jobs: Arc<JobManager>,
+    /// Networks and countries allowed to reach the endpoints, if configured
+    network_policy: Option<Arc<NetworkPolicy>>,
 }
 
 impl McpServer {
@@ -120,6 +136,76 @@ impl McpServer {
     }
 
     /// Format ToolResult for MCP protocol with essential next_step information
+    /// Attach a deprecation warning to the result of a deprecated tool call
+    fn with_deprecation_warning(mut result: ToolResult, tool_def: &ToolDefinition) -> ToolResult {
+        if let Some(warning) = tool_def.deprecation_warning() {
+            warn!("{}", warning);
+            match result.metadata.as_mut().and_then(|m| m.as_object_mut()) {
+                Some(metadata) => {
+                    metadata.insert("deprecation_warning".to_string(), json!(warning));
+                }
+                None => result.metadata = Some(json!({ "deprecation_warning": warning })),
+            }
+        }
+        result
+    }
+
+    /// Attach structured content to a successful result of a tool with an output schema
+    ///
+    /// Output that does not match the schema is recorded as schema drift. By
+    /// default it is left unstructured and the violations are reported in the
+    /// metadata; with `registry.validation.output: fail` the call fails instead.
+    fn with_structured_content(&self, mut result: ToolResult, tool_def: &ToolDefinition) -> ToolResult {
+        // Dry runs describe the call rather than returning the tool's output
+        let dry_run = result.metadata.as_ref().and_then(|metadata| metadata.get("dry_run")) == Some(&json!(true));
+        if !result.success || dry_run {
+            return result;
+        }
+        let (Some(output_schema), Some(data)) = (&tool_def.output_schema, &result.data) else {
+            return result;
+        };
+
+        let output = structured_output(data);
+        let violations = self.schema_drift.validate(&tool_def.name, output_schema, &output);
+        if violations.is_empty() {
+            result.structured_content = Some(output);
+            return result;
+        }
+
+        let error = format!(
+            "Output of tool '{}' does not match its output schema: {}",
+            tool_def.name,
+            violations.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+        );
+        warn!("{}", error);
+        let mut metadata = match result.metadata.take() {
+            Some(Value::Object(metadata)) => metadata,
+            _ => serde_json::Map::new(),
+        };
+        metadata.insert("output_validation_errors".to_string(), json!(violations));
+        match self.registry.output_validation_mode() {
+            OutputValidationMode::Warn => {
+                metadata.insert("output_validation_error".to_string(), json!(error));
+                result.metadata = Some(Value::Object(metadata));
+                result
+            }
+            OutputValidationMode::Fail => {
+                metadata.insert("error_category".to_string(), json!("output_validation_failure"));
+                ToolResult::error_with_metadata(error, Value::Object(metadata))
+            }
+        }
+    }
+
+    /// Get the per-tool schema drift metrics
+    pub fn schema_drift(&self) -> &Arc<SchemaDriftTracker> {
+        &self.schema_drift
+    }
+
+    /// Networks and countries allowed to reach the endpoints, if configured
+    pub fn network_policy(&self) -> Option<&Arc<NetworkPolicy>> {
+        self.network_policy.as_ref()
+    }
+
     fn format_mcp_response(&self, tool_result: ToolResult) -> Value {
         // Start with the base response structure
         let mut response = json!({
@@ -132,6 +218,11 @@ impl McpServer {
             response["error"] = json!(error);
         }
 
+        // Surface deprecation warnings so clients can migrate off the tool
+        if let Some(warning) = tool_result.metadata.as_ref().and_then(|m| m.get("deprecation_warning")) {
+            response["warnings"] = json!([warning]);
+        }
+
         // Build enhanced content with next_step info
         let mut enhanced_response = json!({});
         
@@ -181,6 +272,10 @@ impl McpServer {
         
         response["content"] = json!(enhanced_content);
 
+        if let Some(structured_content) = tool_result.structured_content {
+            response["structuredContent"] = structured_content;
+        }
+
         response
     }
 
@@ -236,6 +331,10 @@ impl McpServer {
             message_validator,
             smart_discovery: None, // No smart discovery by default
             external_integration: None, // No external MCP integration by default
+            schema_drift: Arc::default(),
+            jobs: Arc::default(),
+            network_policy: None,
+            circuit_breakers: None,
         })
     }
 
@@ -260,6 +359,10 @@ impl McpServer {
             message_validator,
             smart_discovery: None, // No smart discovery by default
             external_integration: None, // No external MCP integration by default
+            schema_drift: Arc::default(),
+            jobs: Arc::default(),
+            network_policy: None,
+            circuit_breakers: None,
         }
     }
 
@@ -331,10 +434,10 @@ impl McpServer {
         // Set notification manager on registry for list_changed notifications
         registry.set_notification_manager(notification_manager.clone());
 
-
-
-        // Create session manager with default configuration
-        let session_manager = Arc::new(McpSessionManager::new());
+        // Create session manager with the configured timeouts
+        let session_config = config.sessions.as_ref().map(SessionConfig::from).unwrap_or_default();
+        let session_manager = Arc::new(McpSessionManager::with_config(session_config));
+        Self::spawn_session_sweeper(&session_manager);
 
         // Create message validator with default configuration
         let message_validator = Arc::new(McpMessageValidator::new());
@@ -385,6 +488,7 @@ impl McpServer {
                                 error!("Failed to initialize smart discovery service: {}", e);
                             } else {
                                 info!("Smart discovery service initialized successfully");
+                                service_clone.start_category_refresh();
                             }
                         });
                         
@@ -439,6 +543,77 @@ impl McpServer {
                 Arc::new(Router::with_registry(registry.clone()))
             }
         };
+        if let Some(ref rate_limiting) = config.rate_limiting {
+            router.rate_limiter().configure(rate_limiting.clone());
+        }
+        if let Some(ref timeouts) = config.timeouts {
+            router.configure_timeouts(timeouts.clone());
+        }
+        if let Some(ref dry_run) = config.dry_run {
+            router.configure_dry_run(dry_run.clone());
+        }
+        if let Some(ref costs) = config.costs {
+            router.cost_tracker().configure(costs.clone());
+        }
+        if let Some(ref quotas) = config.quotas {
+            router.quota_tracker().configure(quotas.clone());
+        }
+        if let Some(ref threat_detection) = config.threat_detection {
+            router.threat_detector().configure(Some(threat_detection.clone()));
+        }
+        if let Some(ref access_windows) = config.access_windows {
+            router.configure_access_windows(Some(crate::routing::access_window::AccessWindows::new(access_windows.clone())?));
+        }
+        if let Some(ref policy) = config.policy {
+            router.configure_policy(Some(crate::routing::policy::PolicyEngine::new(policy.clone())?));
+        }
+        if let Some(ref secret_scanning) = config.secret_scanning {
+            router.configure_secret_scanning(Some(crate::routing::secret_scan::SecretScanner::new(secret_scanning.clone())?));
+        }
+        if let Some(ref approvals) = config.approvals {
+            router.approvals().configure(approvals.clone());
+        }
+        if let Some(ref pii) = config.pii {
+            router.configure_pii(Some(crate::routing::pii::PiiMasker::new(pii.clone())?));
+        }
+        if let Some(ref circuit_breakers) = config.circuit_breakers {
+            router.configure_middleware(crate::routing::MiddlewareChain::new().add_middleware(
+                Arc::new(crate::routing::CircuitBreakerMiddleware::from_config(circuit_breakers)),
+            ));
+        }
+
+        // Client networks are checked by the HTTP middleware and the gRPC interceptor
+        let trusted_proxies = config.server.tls.as_ref().map(|tls| tls.trusted_proxies.clone()).unwrap_or_default();
+        let network_policy = config.network_policy.as_ref()
+            .map(|policy| NetworkPolicy::new(policy, &trusted_proxies).map(Arc::new))
+            .transpose()?;
+
+        // Propagate upstream capability changes found by periodic re-discovery, and
+        // resource updates pushed by upstream servers
+        if external_mcp_started {
+            let integration = external_integration.read().await;
+            if let Some(receiver) = integration.subscribe_capability_changes() {
+                Self::spawn_capability_change_listener(
+                    receiver,
+                    registry.clone(),
+                    smart_discovery.clone(),
+                    notification_manager.clone(),
+                );
+            }
+            if let Some(receiver) = integration.subscribe_upstream_notifications() {
+                Self::spawn_upstream_notification_forwarder(receiver, notification_manager.clone());
+            }
+            // Serve the upstream resources those updates refer to
+            if let Some(manager) = integration.get_manager() {
+                resource_manager.add_provider(Arc::new(ExternalResourceProvider::new(Arc::clone(manager)))).await;
+            }
+        }
+
+        // Jobs hold tool arguments and results, so they are encrypted like stored tokens
+        let jobs_cipher = match config.auth.as_ref().and_then(|auth| auth.token_encryption.as_ref()) {
+            Some(token_encryption) => Some(Arc::new(crate::auth::TokenCipher::from_config(token_encryption).await?)),
+            None => None,
+        };
 
         let server = Self {
             registry,
@@ -453,11 +628,123 @@ impl McpServer {
             message_validator,
             smart_discovery,
             external_integration: if external_mcp_started { Some(external_integration) } else { None },
+            schema_drift: Arc::default(),
+            jobs: Arc::new(JobManager::open(&config.jobs.clone().unwrap_or_default(), jobs_cipher).await?),
+            network_policy,
         };
 
         Ok(server)
     }
 
+    /// Refresh the registry and embeddings and notify clients whenever an upstream server's
+    /// tools, prompts or resources change
+    fn spawn_capability_change_listener(
+        mut receiver: tokio::sync::broadcast::Receiver<crate::mcp::capability_sync::CapabilityChange>,
+        registry: Arc<RegistryService>,
+        smart_discovery: Option<Arc<crate::discovery::SmartDiscoveryService>>,
+        notification_manager: Arc<McpNotificationManager>,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                let change = match receiver.recv().await {
+                    Ok(change) => change,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Capability change listener lagged, skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+
+                info!("🔄 Applying upstream capability changes from '{}'", change.server_name);
+
+                if change.has_tool_changes() {
+                    // Reloading the registry also emits notifications/tools/list_changed
+                    if let Err(e) = registry.reload_registry().await {
+                        warn!("Failed to reload registry after upstream changes from '{}': {}", change.server_name, e);
+                        if let Err(e) = notification_manager.notify_tools_list_changed() {
+                            warn!("Failed to send tools list_changed notification: {}", e);
+                        }
+                    }
+
+                    if let Some(ref discovery) = smart_discovery {
+                        match discovery.sync_embeddings().await {
+                            Ok(Some(summary)) => debug!("Embeddings re-synced after upstream changes: {:?}", summary),
+                            Ok(None) => {}
+                            Err(e) => warn!("Failed to re-sync embeddings after upstream changes from '{}': {}", change.server_name, e),
+                        }
+                    }
+                }
+
+                if change.prompts_changed {
+                    if let Err(e) = notification_manager.notify_prompts_list_changed() {
+                        warn!("Failed to send prompts list_changed notification: {}", e);
+                    }
+                }
+
+                if change.resources_changed {
+                    if let Err(e) = notification_manager.notify_resources_list_changed() {
+                        warn!("Failed to send resources list_changed notification: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Remove expired sessions that no open connection ends itself, such as those of HTTP
+    /// clients, so they are not kept until restart
+    fn spawn_session_sweeper(session_manager: &Arc<McpSessionManager>) {
+        let session_manager = Arc::downgrade(session_manager);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SESSION_SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(session_manager) = session_manager.upgrade() else {
+                    break;
+                };
+                let removed = session_manager.cleanup_expired_sessions();
+                if removed > 0 {
+                    debug!("Removed {} expired sessions", removed);
+                }
+            }
+        });
+    }
+
+    /// Forward resource updates from upstream servers (with URIs remapped into the proxy's
+    /// namespace) to downstream clients subscribed to them
+    fn spawn_upstream_notification_forwarder(
+        mut receiver: tokio::sync::broadcast::Receiver<crate::mcp::upstream_notifications::UpstreamNotification>,
+        notification_manager: Arc<McpNotificationManager>,
+    ) {
+        use crate::mcp::upstream_notifications::UpstreamNotificationAction;
+
+        tokio::spawn(async move {
+            loop {
+                let upstream = match receiver.recv().await {
+                    Ok(upstream) => upstream,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Upstream notification forwarder lagged, skipped {} notifications", skipped);
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+
+                match upstream.action() {
+                    UpstreamNotificationAction::ForwardResourceUpdated { uri } => {
+                        debug!("Forwarding resource update from '{}': {}", upstream.server_name, uri);
+                        if let Err(e) = notification_manager.notify_resource_updated(uri) {
+                            warn!("Failed to forward resource update from '{}': {}", upstream.server_name, e);
+                        }
+                    }
+                    // list_changed is handled by re-discovery in the external manager
+                    UpstreamNotificationAction::Rediscover => {}
+                    UpstreamNotificationAction::Ignore => {
+                        debug!("Ignoring upstream notification '{}' from '{}'", upstream.notification.method, upstream.server_name);
+                    }
+                }
+            }
+        });
+    }
+
     /// Create MCP server with registry and resource manager
     pub fn with_registry_and_resources(
         registry: Arc<RegistryService>,
@@ -481,12 +768,16 @@ impl McpServer {
             message_validator,
             smart_discovery: None, // No smart discovery by default
             external_integration: None, // No external MCP integration by default
+            schema_drift: Arc::default(),
+            jobs: Arc::default(),
+            network_policy: None,
+            circuit_breakers: None,
         }
     }
 
     /// Configure authentication for the MCP server
     pub fn with_authentication(mut self, auth_config: AuthConfig) -> Result<Self> {
-        if auth_config.enabled {
+        if auth_config.any_enabled() {
             info!("Enabling authentication with type: {}", auth_config.r#type);
             let auth_middleware = AuthenticationMiddleware::new(auth_config)?;
             self.auth_middleware = Some(Arc::new(auth_middleware));
@@ -511,12 +802,15 @@ impl McpServer {
         };
 
         let server_data = web::Data::new(Arc::clone(&self.registry));
+        let network_policy = self.network_policy.clone();
         let mcp_server_data = web::Data::new(Arc::new(self));
 
         let server = HttpServer::new(move || {
             let mut app = App::new()
                 .app_data(server_data.clone())
                 .app_data(mcp_server_data.clone())
+                .wrap(crate::auth::SignedBody)
+                .wrap(crate::auth::NetworkFilter::new(network_policy.clone()))
                 .wrap(Logger::default());
 
             // Add TLS config to app data if available
@@ -534,6 +828,7 @@ impl McpServer {
                 // Standard HTTP endpoints (backward compatibility)
                 .route("/mcp/tools", web::get().to(list_tools_handler))
                 .route("/mcp/call", web::post().to(call_tool_handler))
+                .route("/mcp/call/batch", web::post().to(call_tool_batch_handler))
 
                 // Resource endpoints
                 .route("/mcp/resources", web::get().to(list_resources_handler))
@@ -551,10 +846,28 @@ impl McpServer {
                 .route("/mcp/stream", web::get().to(sse_handler))
                 .route("/mcp/call/stream", web::post().to(streaming_tool_handler))
 
+                // Callbacks of `webhook`-routed jobs, authenticated by their signature
+                .route("/webhooks/callbacks/{correlation_id}", web::post().to(webhook_callback_handler))
+
+                // Approve/deny links sent to approvers, authenticated by their signature
+                .route("/approvals/{id}/{action}", web::get().to(approval_link_page_handler))
+                .route("/approvals/{id}/{action}", web::post().to(approval_link_handler))
+
                 // OAuth authentication endpoints
                 .route("/auth/oauth/authorize", web::get().to(oauth_authorize_handler))
                 .route("/auth/oauth/callback", web::get().to(oauth_callback_handler))
                 .route("/auth/oauth/token", web::post().to(oauth_token_handler))
+                .route("/auth/oauth/register", web::post().to(oauth_register_handler))
+
+                // API key rotation
+                .route("/auth/keys/{name}/rotate", web::post().to(api_key_rotate_handler))
+
+                // Roles a token would receive from the role mappings
+                .route("/auth/roles/dry-run", web::post().to(role_dry_run_handler))
+
+                // OAuth protected resource metadata (RFC 9728), at the root and per resource path
+                .route("/.well-known/oauth-protected-resource", web::get().to(protected_resource_metadata_handler))
+                .route("/.well-known/oauth-protected-resource/{path:.*}", web::get().to(protected_resource_metadata_handler))
 
                 // Dashboard API routes
                 .configure({
@@ -568,7 +881,9 @@ impl McpServer {
                 })
 
                 // TODO: Add gRPC endpoints (will need separate gRPC server)
-        });
+        })
+        // Keep client certificates of TLS connections for client certificate authentication
+        .on_connect(crate::auth::store_peer_certificate);
 
         // Bind server with appropriate TLS configuration
         match effective_mode {
@@ -669,10 +984,31 @@ impl McpServer {
 
         let private_key = rustls::PrivateKey(keys.into_iter().next().unwrap());
 
+        // With a CA, ask clients for certificates it issued; clients without one can still connect
+        let client_cert_verifier = match &tls_config.ca_file {
+            Some(ca_file) => {
+                let ca_file = File::open(ca_file)
+                    .map_err(|e| ProxyError::config(format!("Failed to open CA certificate file: {}", e)))?;
+                let ca_certs = rustls_pemfile::certs(&mut BufReader::new(ca_file))
+                    .map_err(|e| ProxyError::config(format!("Failed to parse CA certificate file: {}", e)))?;
+                let mut roots = rustls::RootCertStore::empty();
+                for ca_cert in ca_certs {
+                    roots.add(&rustls::Certificate(ca_cert))
+                        .map_err(|e| ProxyError::config(format!("Invalid CA certificate: {}", e)))?;
+                }
+                if roots.is_empty() {
+                    return Err(ProxyError::config("No certificates found in CA certificate file"));
+                }
+                info!("Requesting client certificates issued by {} CA certificate(s)", roots.len());
+                rustls::server::AllowAnyAnonymousOrAuthenticatedClient::new(roots)
+            }
+            None => rustls::server::NoClientAuth::new(),
+        };
+
         // Build rustls config
         let config = rustls::ServerConfig::builder()
             .with_safe_defaults()
-            .with_no_client_auth()
+            .with_client_cert_verifier(client_cert_verifier)
             .with_single_cert(cert_chain.into_iter().map(rustls::Certificate).collect(), private_key)
             .map_err(|e| ProxyError::config(format!("Failed to build TLS configuration: {}", e)))?;
 
@@ -689,25 +1025,40 @@ impl McpServer {
 
     /// Handle list_tools request
     pub async fn list_tools(&self) -> Result<Vec<Tool>> {
+        self.list_tools_filtered(&ToolFilter::new()).await
+    }
+
+    /// Handle list_tools request, keeping only tools that pass `filter`
+    pub async fn list_tools_filtered(&self, filter: &ToolFilter) -> Result<Vec<Tool>> {
         debug!("Handling list_tools request");
 
         // Get tools from high-performance registry
         let tool_names = self.registry.list_tools();
         let tool_count = tool_names.len();
         let mut tools = Vec::new();
+        let mut has_background_tools = false;
 
         for tool_name in tool_names {
-            if let Some(tool_def) = self.registry.get_tool(&tool_name) {
+            if let Some(tool_def) = self.registry.get_tool(&tool_name).filter(|tool_def| filter.matches(tool_def)) {
                 // Convert ToolDefinition to MCP Tool
-                let tool = crate::mcp::types::Tool::new(
+                let mut tool = crate::mcp::types::Tool::new(
                     tool_def.name().to_string(),
                     tool_def.description().to_string(),
                     tool_def.input_schema.clone(),
                 )?;
+                tool.annotations = tool_def.deprecation_annotations();
+                tool.output_schema = tool_def.output_schema.clone();
                 tools.push(tool);
+                has_background_tools |= tool_def.background;
             }
         }
 
+        // Background tools are followed with the job tools
+        if has_background_tools {
+            let registered: std::collections::HashSet<String> = tools.iter().map(|tool| tool.name.clone()).collect();
+            tools.extend(jobs::job_tools().into_iter().filter(|tool| !registered.contains(&tool.name)));
+        }
+
         // Note: Legacy proxy tools removed - use remote_mcp discovery instead
 
         info!("Returning {} tools (local)", tools.len());
@@ -748,34 +1099,14 @@ impl McpServer {
                 ));
             }
 
-            // Route to appropriate local agent using the router
-            match self.router.route(&tool_call, &tool_def).await {
-                Ok(agent_result) => {
-                    // Convert AgentResult to ToolResult using helper
-                    let metadata = json!({
-                        "tool_name": tool_call.name,
-                        "validated": true,
-                        "registry_lookup": "success",
-                        "routing_type": tool_def.routing_type(),
-                        "source": "local"
-                    });
-                    return Ok(Self::agent_result_to_tool_result(agent_result, &tool_call.name, Some(metadata)));
-                }
-                Err(e) => {
-                    error!("Local tool '{}' execution failed: {}", tool_call.name, e);
-                    return Ok(ToolResult::error_with_metadata(
-                        format!("Local tool execution failed: {}", e),
-                        json!({
-                            "tool_name": tool_call.name,
-                            "validated": true,
-                            "registry_lookup": "success",
-                            "routing_type": tool_def.routing_type(),
-                            "source": "local",
-                            "error_category": "execution_failure"
-                        })
-                    ));
-                }
+            if tool_def.background && !crate::routing::dry_run::is_dry_run() {
+                return Ok(self.start_job(&tool_call, &tool_def));
             }
+            return Ok(self.execute_local_tool(&tool_call, &tool_def).await);
+        }
+
+        if let Some(result) = self.jobs.call_tool(&tool_call, jobs::current_owner().as_deref()) {
+            return Ok(result);
         }
 
         // Tool not found in local registry
@@ -791,6 +1122,62 @@ impl McpServer {
         ))
     }
 
+    /// Route a validated call of a local tool and convert its result
+    async fn execute_local_tool(&self, tool_call: &ToolCall, tool_def: &ToolDefinition) -> ToolResult {
+        // Route to appropriate local agent using the router
+        let result = match self.router.route(tool_call, tool_def).await {
+            Ok(agent_result) => {
+                // Convert AgentResult to ToolResult using helper
+                let metadata = json!({
+                    "tool_name": tool_call.name,
+                    "validated": true,
+                    "registry_lookup": "success",
+                    "routing_type": tool_def.routing_type(),
+                    "source": "local"
+                });
+                Self::agent_result_to_tool_result(agent_result, &tool_call.name, Some(metadata))
+            }
+            Err(e) => {
+                error!("Local tool '{}' execution failed: {}", tool_call.name, e);
+                ToolResult::error_with_metadata(
+                    format!("Local tool execution failed: {}", e),
+                    json!({
+                        "tool_name": tool_call.name,
+                        "validated": true,
+                        "registry_lookup": "success",
+                        "routing_type": tool_def.routing_type(),
+                        "source": "local",
+                        "error_category": "execution_failure"
+                    })
+                )
+            }
+        };
+        let result = self.with_structured_content(result, tool_def);
+        Self::with_deprecation_warning(result, tool_def)
+    }
+
+    /// Run a validated call of a background tool as a job and return its ID
+    fn start_job(&self, tool_call: &ToolCall, tool_def: &ToolDefinition) -> ToolResult {
+        // The job outlives the request, so carry over the caller and timeout it made the call with
+        let client = crate::routing::rate_limit::current_client();
+        let (user, session) = (crate::routing::cost::current_user(), crate::routing::cost::current_session());
+        let roles = crate::routing::quota::current_roles();
+        let subject_token = crate::auth::subject_token();
+        let timeout_secs = crate::routing::timeout::call_timeout();
+        let server = self.clone();
+        let (call, def) = (tool_call.clone(), tool_def.clone());
+        let owner = jobs::current_owner();
+        let job = self.jobs.start(&tool_call.name, &tool_call.arguments, owner, async move {
+            let execution = crate::routing::timeout::with_call_timeout(timeout_secs, server.execute_local_tool(&call, &def));
+            let execution = crate::routing::cost::in_session(session, crate::routing::cost::as_user(user, execution));
+            let execution = crate::routing::quota::with_roles(roles, execution);
+            let execution = crate::auth::on_behalf_of(subject_token, execution);
+            crate::routing::rate_limit::as_client(client, execution).await
+        });
+        info!("Started background job {} for tool '{}'", job.id, tool_call.name);
+        Self::with_deprecation_warning(jobs::job_started_result(&job), tool_def)
+    }
+
     /// Handle list_resources request
     pub async fn list_resources(&self, cursor: Option<String>) -> Result<ResourceListResponse> {
         debug!("Handling list_resources request");
@@ -991,7 +1378,13 @@ impl McpServer {
                 "tools": {
                     "listChanged": notification_caps.tools_list_changed
                 },
-                "completion": {}
+                "completion": {},
+                "experimental": {
+                    "callBatch": {
+                        "maxBatchSize": crate::mcp::batch::MAX_BATCH_SIZE,
+                        "maxConcurrency": crate::mcp::batch::MAX_CONCURRENCY
+                    }
+                }
             },
             "serverInfo": {
                 "name": "magictunnel",
@@ -1011,8 +1404,164 @@ impl McpServer {
         &self.prompt_manager
     }
 
+    /// Build the tools/list filter from the caller's toolsets and the request params
+    pub fn tool_filter(&self, scope: &ToolScope, params: &ToolListParams) -> Result<ToolFilter> {
+        let mut filter = self.registry.scope_filter(scope)?;
+        if let Some(ref toolset) = params.toolset {
+            filter = filter.and_toolset(self.registry.toolset(toolset)?);
+        }
+        if !params.tags.is_empty() {
+            filter = filter.and(TagExpression::all_of(&params.tags));
+        }
+        Ok(filter)
+    }
+
+    /// Check whether a tool may be called by a caller bound to `toolset`
+    ///
+    /// Unknown tools pass, so that the call fails with the usual not-found error.
+    pub fn is_tool_in_toolset(&self, tool_name: &str, toolset: Option<&str>) -> bool {
+        self.is_tool_in_scope(tool_name, &ToolScope::bound(toolset))
+    }
+
+    /// Check whether a tool may be called by a caller limited to `scope`
+    ///
+    /// Unknown tools pass, so that the call fails with the usual not-found error.
+    pub fn is_tool_in_scope(&self, tool_name: &str, scope: &ToolScope) -> bool {
+        if scope.is_unrestricted() {
+            return true;
+        }
+        match (self.tool_filter(scope, &ToolListParams::default()), self.registry.get_tool(tool_name)) {
+            (Ok(filter), Some(tool_def)) => filter.matches(&tool_def),
+            (Ok(_), None) => true,
+            (Err(_), _) => false,
+        }
+    }
+
+    /// Apply the `toolset` / `toolsets` initialize params to a session
+    ///
+    /// The toolsets are activated as with `toolsets/activate`; a session whose
+    /// API key is bound to a toolset stays limited to it.
+    fn select_session_toolset(&self, session_id: &str, request: &McpRequest) -> Result<()> {
+        let toolsets = toolset_names_param(request.params.as_ref());
+        if toolsets.is_empty() {
+            return Ok(());
+        }
+        for toolset in &toolsets {
+            self.registry.toolset(toolset)?;
+        }
+        self.session_manager.set_active_toolsets(session_id, toolsets)
+    }
+
+    /// Handle `toolsets/activate` and `toolsets/deactivate` for a session
+    ///
+    /// Deactivating without naming toolsets deactivates all of them. Returns the
+    /// toolsets active afterwards.
+    fn switch_session_toolsets(&self, session_id: &str, activate: bool, params: Option<&Value>) -> Result<Vec<String>> {
+        let names = toolset_names_param(params);
+        let mut active = self.session_manager.tool_scope(session_id).active;
+
+        if activate {
+            if names.is_empty() {
+                return Err(ProxyError::validation("Name the toolsets to activate".to_string()));
+            }
+            for name in names {
+                self.registry.toolset(&name)?;
+                if !active.contains(&name) {
+                    active.push(name);
+                }
+            }
+        } else if names.is_empty() {
+            active.clear();
+        } else {
+            active.retain(|name| !names.contains(name));
+        }
+
+        self.session_manager.set_active_toolsets(session_id, active.clone())?;
+        Ok(active)
+    }
+
+    /// Configured toolsets with the number of tools each exposes to `scope`
+    fn list_toolsets(&self, scope: &ToolScope) -> Result<Value> {
+        let all_tools = self.registry.get_enabled_tools();
+        let bound_filter = self.tool_filter(&scope.without_activations(), &ToolListParams::default())?;
+
+        let mut toolsets = Vec::new();
+        for name in self.registry.toolset_names() {
+            let toolset = self.registry.toolset(&name)?;
+            let tool_count = all_tools.iter()
+                .filter(|(_, tool)| !tool.is_hidden() && bound_filter.matches(tool) && toolset.matches(tool))
+                .count();
+            toolsets.push(json!({
+                "name": name,
+                "description": toolset.description,
+                "toolCount": tool_count,
+                "active": scope.active.contains(&name),
+            }));
+        }
+        Ok(json!({ "toolsets": toolsets, "bound": scope.bound }))
+    }
+
+    /// Run the tool call described by `tools/call` params, honouring its `_meta` options
+    ///
+    /// Returns the MCP result, or the JSON-RPC error code and message to report.
+    async fn call_tool_params(&self, params: Value, scope: &ToolScope) -> std::result::Result<Value, (McpErrorCode, String)> {
+        let cache_bypass = params.pointer("/_meta/cacheBypass").and_then(|v| v.as_bool()).unwrap_or(false);
+        let call_timeout = params.pointer("/_meta/timeoutSeconds").and_then(|v| v.as_u64()).filter(|&secs| secs > 0);
+        let dry_run = params.pointer("/_meta/dryRun").and_then(|v| v.as_bool()).unwrap_or(false);
+        let tool_call = serde_json::from_value::<ToolCall>(params)
+            .map_err(|e| (McpErrorCode::InvalidParams, format!("Invalid tool call parameters: {}", e)))?;
+        if !self.is_tool_in_scope(&tool_call.name, scope) {
+            return Err((McpErrorCode::InvalidParams, scope_error_message(&tool_call.name, scope)));
+        }
+
+        let call = crate::registry::toolset::in_scope(scope, self.call_tool(tool_call));
+        let call = crate::routing::timeout::with_call_timeout(call_timeout, call);
+        let result = match (dry_run, cache_bypass) {
+            (true, _) => crate::routing::dry_run::dry_run(call).await,
+            (false, true) => crate::routing::cache::bypass(call).await,
+            (false, false) => call.await,
+        };
+        result
+            .map(|result| self.format_mcp_response(result))
+            .map_err(|e| (McpErrorCode::InternalError, format!("Tool execution failed: {}", e)))
+    }
+
+    /// Run the calls of a `tools/call_batch` request, returning one entry per call in order
+    pub async fn call_tool_batch(&self, batch: BatchToolCallRequest, scope: &ToolScope) -> Vec<Value> {
+        debug!("Running batch of {} tool calls", batch.calls.len());
+        let results = batch.run(|params| self.call_tool_params(params, scope)).await;
+        results.into_iter().enumerate().map(|(index, result)| match result {
+            Ok(result) => json!({ "index": index, "result": result }),
+            Err((code, message)) => json!({ "index": index, "error": { "code": code.code(), "message": message } }),
+        }).collect()
+    }
+
     /// Handle MCP JSON-RPC 2.0 request (unified handler for all transports)
     pub async fn handle_mcp_request(&self, request: McpRequest) -> Result<Option<String>> {
+        self.handle_mcp_request_with_toolset(request, None).await
+    }
+
+    /// Handle MCP JSON-RPC 2.0 request from a caller restricted to `toolset`
+    pub async fn handle_mcp_request_with_toolset(&self, request: McpRequest, toolset: Option<&str>) -> Result<Option<String>> {
+        self.handle_mcp_request_with_scope(request, &ToolScope::bound(toolset)).await
+    }
+
+    /// Handle MCP JSON-RPC 2.0 request from a caller limited to the toolset and scopes of its API key
+    pub async fn handle_mcp_request_with_scope(&self, request: McpRequest, scope: &ToolScope) -> Result<Option<String>> {
+        self.handle_mcp_request_in_scope(request, scope, None).await
+    }
+
+    /// Handle MCP JSON-RPC 2.0 request from a session, using its bound and active toolsets
+    pub async fn handle_session_request(&self, request: McpRequest, session_id: &str) -> Result<Option<String>> {
+        let scope = self.session_manager.tool_scope(session_id);
+        let handled = self.handle_mcp_request_in_scope(request, &scope, Some(session_id));
+        crate::routing::cost::in_session(Some(session_id.to_string()), handled).await
+    }
+
+    /// Handle MCP JSON-RPC 2.0 request from a caller limited to `scope`
+    ///
+    /// Switching toolsets requires a session; stateless callers can only list them.
+    async fn handle_mcp_request_in_scope(&self, request: McpRequest, scope: &ToolScope, session_id: Option<&str>) -> Result<Option<String>> {
         debug!("Handling MCP method: {}", request.method);
 
         // Route to appropriate handler based on method
@@ -1031,47 +1580,74 @@ impl McpServer {
                 return Ok(None);
             }
             "tools/list" => {
-                match self.list_tools().await {
-                    Ok(tools) => {
-                        if let Some(ref id) = request.id {
-                            self.create_success_response(id, json!({"tools": tools}))
-                        } else {
-                            self.create_error_response(None, McpErrorCode::InvalidRequest, "Request must have an ID")
+                let list_params = ToolListParams::from_params(request.params.as_ref());
+                match self.tool_filter(scope, &list_params) {
+                    Ok(filter) => match self.list_tools_filtered(&filter).await {
+                        Ok(tools) => {
+                            if let Some(ref id) = request.id {
+                                self.create_success_response(id, json!({"tools": tools}))
+                            } else {
+                                self.create_error_response(None, McpErrorCode::InvalidRequest, "Request must have an ID")
+                            }
                         }
-                    }
+                        Err(e) => self.create_error_response(
+                            request.id.as_ref(),
+                            McpErrorCode::InternalError,
+                            &format!("Failed to list tools: {}", e)
+                        ),
+                    },
                     Err(e) => self.create_error_response(
                         request.id.as_ref(),
-                        McpErrorCode::InternalError,
-                        &format!("Failed to list tools: {}", e)
+                        McpErrorCode::InvalidParams,
+                        &format!("Invalid tool filter: {}", e)
                     ),
                 }
             }
             "tools/call" => {
                 let params = request.params.unwrap_or(json!({}));
-                match serde_json::from_value::<ToolCall>(params) {
-                    Ok(tool_call) => {
-                        match self.call_tool(tool_call).await {
-                            Ok(result) => {
-                                if let Some(ref id) = request.id {
-                                    // For MCP protocol, include essential next_step info if available
-                                    let mcp_result = self.format_mcp_response(result);
-                                    self.create_success_response(id, json!(mcp_result))
-                                } else {
-                                    self.create_error_response(None, McpErrorCode::InvalidRequest, "Request must have an ID")
-                                }
-                            }
-                            Err(e) => self.create_error_response(
-                                request.id.as_ref(),
-                                McpErrorCode::InternalError,
-                                &format!("Tool execution failed: {}", e)
-                            ),
-                        }
-                    }
-                    Err(e) => self.create_error_response(
+                match (self.call_tool_params(params, scope).await, request.id.as_ref()) {
+                    // For MCP protocol, include essential next_step info if available
+                    (Ok(mcp_result), Some(id)) => self.create_success_response(id, mcp_result),
+                    (Ok(_), None) => self.create_error_response(None, McpErrorCode::InvalidRequest, "Request must have an ID"),
+                    (Err((code, message)), id) => self.create_error_response(id, code, &message),
+                }
+            }
+            "tools/call_batch" => match (BatchToolCallRequest::from_params(request.params.as_ref()), request.id.as_ref()) {
+                (Ok(batch), Some(id)) => {
+                    let results = self.call_tool_batch(batch, scope).await;
+                    self.create_success_response(id, json!({ "results": results }))
+                }
+                (Ok(_), None) => self.create_error_response(None, McpErrorCode::InvalidRequest, "Request must have an ID"),
+                (Err(e), id) => self.create_error_response(id, McpErrorCode::InvalidParams, &e.to_string()),
+            },
+            "toolsets/list" => match (self.list_toolsets(scope), request.id.as_ref()) {
+                (Ok(toolsets), Some(id)) => self.create_success_response(id, toolsets),
+                (Ok(_), None) => self.create_error_response(None, McpErrorCode::InvalidRequest, "Request must have an ID"),
+                (Err(e), id) => self.create_error_response(
+                    id,
+                    McpErrorCode::InternalError,
+                    &format!("Failed to list toolsets: {}", e)
+                ),
+            },
+            "toolsets/activate" | "toolsets/deactivate" => {
+                let activate = request.method == "toolsets/activate";
+                match session_id {
+                    None => self.create_error_response(
                         request.id.as_ref(),
-                        McpErrorCode::InvalidParams,
-                        &format!("Invalid tool call parameters: {}", e)
+                        McpErrorCode::InvalidRequest,
+                        "Toolsets can only be switched within a session"
                     ),
+                    Some(session_id) => match self.switch_session_toolsets(session_id, activate, request.params.as_ref()) {
+                        Ok(active) => match request.id {
+                            Some(ref id) => self.create_success_response(id, json!({ "active": active })),
+                            None => self.create_error_response(None, McpErrorCode::InvalidRequest, "Request must have an ID"),
+                        },
+                        Err(e) => self.create_error_response(
+                            request.id.as_ref(),
+                            McpErrorCode::InvalidParams,
+                            &format!("Failed to switch toolsets: {}", e)
+                        ),
+                    },
                 }
             }
             "resources/list" => {
@@ -1114,6 +1690,41 @@ impl McpServer {
                     ),
                 }
             }
+            "resources/subscribe" | "resources/unsubscribe" => {
+                let params = request.params.unwrap_or(json!({}));
+                let uri = match params.get("uri").and_then(|u| u.as_str()) {
+                    Some(uri) => uri.to_string(),
+                    None => return Ok(Some(self.create_error_response(
+                        request.id.as_ref(),
+                        McpErrorCode::InvalidParams,
+                        "Missing required parameter: uri"
+                    ))),
+                };
+
+                // Subscriptions are per session, so updates reach only the sessions that asked
+                let subscribe = request.method == "resources/subscribe";
+                let result = match session_id {
+                    Some(session_id) if subscribe => self.notification_manager.subscribe_session_to_resource(session_id, uri),
+                    Some(session_id) => self.notification_manager.unsubscribe_session_from_resource(session_id, &uri),
+                    None if subscribe => self.notification_manager.subscribe_to_resource(uri),
+                    None => self.notification_manager.unsubscribe_from_resource(&uri),
+                };
+
+                match result {
+                    Ok(()) => {
+                        if let Some(ref id) = request.id {
+                            self.create_success_response(id, json!({}))
+                        } else {
+                            self.create_error_response(None, McpErrorCode::InvalidRequest, "Request must have an ID")
+                        }
+                    }
+                    Err(e) => self.create_error_response(
+                        request.id.as_ref(),
+                        McpErrorCode::InternalError,
+                        &format!("Failed to update resource subscription: {}", e)
+                    ),
+                }
+            }
             "prompts/list" => {
                 let params = request.params.unwrap_or(json!({}));
                 let cursor = params.get("cursor")
@@ -1268,6 +1879,16 @@ impl McpServer {
         &self.registry
     }
 
+    /// Get the router tool calls are sent through
+    pub fn router(&self) -> &Arc<Router> {
+        &self.router
+    }
+
+    /// Get the session manager
+    pub fn session_manager(&self) -> &Arc<McpSessionManager> {
+        &self.session_manager
+    }
+
     /// Get the smart discovery service if available
     pub fn smart_discovery(&self) -> Option<&Arc<crate::discovery::SmartDiscoveryService>> {
         self.smart_discovery.as_ref()
@@ -1281,8 +1902,9 @@ async fn check_authentication(
     req: &HttpRequest,
     auth_middleware: &Option<Arc<AuthenticationMiddleware>>,
     required_permission: &str,
-) -> std::result::Result<(), HttpResponse> {
+) -> std::result::Result<Option<AuthenticationResult>, HttpResponse> {
     if let Some(auth) = auth_middleware {
+        let auth = auth.for_request(req);
         match auth.validate_http_request(req).await {
             Ok(Some(auth_result)) => {
                 // Check if the authenticated user has the required permission
@@ -1298,11 +1920,11 @@ async fn check_authentication(
                         .content_type("application/json")
                         .json(error_response));
                 }
-                Ok(())
+                Ok(Some(auth_result))
             }
             Ok(None) => {
                 // Authentication disabled
-                Ok(())
+                Ok(None)
             }
             Err(e) => {
                 let error_response = json!({
@@ -1314,12 +1936,30 @@ async fn check_authentication(
                 });
                 Err(HttpResponse::Unauthorized()
                     .content_type("application/json")
+                    .insert_header(("WWW-Authenticate", auth.www_authenticate(req)))
                     .json(error_response))
             }
         }
     } else {
         // No authentication configured
-        Ok(())
+        Ok(None)
+    }
+}
+
+/// OAuth protected resource metadata, telling MCP clients where to get tokens for this server
+pub async fn protected_resource_metadata_handler(
+    req: HttpRequest,
+    mcp_server: web::Data<Arc<McpServer>>,
+) -> HttpResponse {
+    match &mcp_server.auth_middleware {
+        Some(auth) => HttpResponse::Ok().json(auth.for_request(&req).protected_resource_metadata(&req)),
+        None => HttpResponse::NotFound().json(json!({
+            "error": {
+                "code": "AUTHENTICATION_DISABLED",
+                "message": "Authentication is not enabled on this server",
+                "type": "configuration_error"
+            }
+        })),
     }
 }
 
@@ -1339,12 +1979,21 @@ pub async fn mcp_jsonrpc_handler(
 ) -> HttpResponse {
     // Check authentication with read permission for most operations
     // Tool execution will be checked separately in the unified handler
-    if let Err(auth_error) = check_authentication(&req, &mcp_server.auth_middleware, "read").await {
-        return auth_error;
-    }
+    let auth_result = match check_authentication(&req, &mcp_server.auth_middleware, "read").await {
+        Ok(auth_result) => auth_result,
+        Err(auth_error) => return auth_error,
+    };
+    let scope = auth_result.as_ref().map(|auth| auth.tool_scope()).unwrap_or_default();
+    let client = auth_result.as_ref().and_then(|auth| auth.client_name()).map(String::from);
+    let user = auth_result.as_ref().map(|auth| auth.get_user_id());
+    let subject_token = auth_result.as_ref().and_then(|auth| auth.subject_token(&req));
 
     // Use the unified MCP handler
-    match mcp_server.handle_mcp_request(body.into_inner()).await {
+    let roles = auth_result.as_ref().map(|auth| auth.roles()).unwrap_or_default();
+    let handled = crate::routing::cost::as_user(user, mcp_server.handle_mcp_request_with_scope(body.into_inner(), &scope));
+    let handled = crate::routing::quota::with_roles(roles, handled);
+    let handled = crate::auth::on_behalf_of(subject_token, handled);
+    match crate::routing::rate_limit::as_client(client, handled).await {
         Ok(Some(response)) => {
             // Parse the JSON response to return as proper JSON
             match serde_json::from_str::<serde_json::Value>(&response) {
@@ -1375,11 +2024,17 @@ pub async fn list_tools_handler(
     mcp_server: web::Data<Arc<McpServer>>,
 ) -> HttpResponse {
     // Check authentication
-    if let Err(auth_error) = check_authentication(&req, &mcp_server.auth_middleware, "read").await {
-        return auth_error;
-    }
+    let auth_result = match check_authentication(&req, &mcp_server.auth_middleware, "read").await {
+        Ok(auth_result) => auth_result,
+        Err(auth_error) => return auth_error,
+    };
+    let scope = auth_result.as_ref().map(|auth| auth.tool_scope()).unwrap_or_default();
 
-    match list_tools_from_registry(&registry).await {
+    let tools = match mcp_server.tool_filter(&scope, &ToolListParams::default()) {
+        Ok(filter) => list_tools_from_registry(&registry, &filter).await,
+        Err(e) => Err(e),
+    };
+    match tools {
         Ok(tools) => HttpResponse::Ok().json(json!({
             "tools": tools
         })),
@@ -1403,11 +2058,29 @@ pub async fn call_tool_handler(
     mcp_server: web::Data<Arc<McpServer>>,
 ) -> HttpResponse {
     // Check authentication with write permission for tool execution
-    if let Err(auth_error) = check_authentication(&req, &mcp_server.auth_middleware, "write").await {
-        return auth_error;
+    let auth_result = match check_authentication(&req, &mcp_server.auth_middleware, "write").await {
+        Ok(auth_result) => auth_result,
+        Err(auth_error) => return auth_error,
+    };
+    let scope = auth_result.as_ref().map(|auth| auth.tool_scope()).unwrap_or_default();
+    if !mcp_server.is_tool_in_scope(&tool_call.name, &scope) {
+        return HttpResponse::Forbidden().json(json!({
+            "error": {
+                "code": "TOOL_NOT_IN_TOOLSET",
+                "message": scope_error_message(&tool_call.name, &scope),
+                "type": "authorization_error"
+            }
+        }));
     }
 
-    match mcp_server.call_tool_with_router(&tool_call).await {
+    let client = auth_result.as_ref().and_then(|auth| auth.client_name()).map(String::from);
+    let user = auth_result.as_ref().map(|auth| auth.get_user_id());
+    let subject_token = auth_result.as_ref().and_then(|auth| auth.subject_token(&req));
+    let call = crate::registry::toolset::in_scope(&scope, mcp_server.call_tool_with_router(&tool_call));
+    let roles = auth_result.as_ref().map(|auth| auth.roles()).unwrap_or_default();
+    let call = crate::routing::quota::with_roles(roles, crate::routing::cost::as_user(user, call));
+    let call = crate::auth::on_behalf_of(subject_token, call);
+    match crate::routing::rate_limit::as_client(client, call).await {
         Ok(result) => HttpResponse::Ok().json(result),
         Err(e) => {
             error!("Failed to call tool '{}': {}", tool_call.name, e);
@@ -1421,6 +2094,147 @@ pub async fn call_tool_handler(
     }
 }
 
+/// Batch tool call endpoint, running independent calls concurrently
+pub async fn call_tool_batch_handler(
+    req: HttpRequest,
+    body: web::Json<Value>,
+    mcp_server: web::Data<Arc<McpServer>>,
+) -> HttpResponse {
+    // Same permission as a single tool call
+    let auth_result = match check_authentication(&req, &mcp_server.auth_middleware, "write").await {
+        Ok(auth_result) => auth_result,
+        Err(auth_error) => return auth_error,
+    };
+    let batch = match BatchToolCallRequest::from_params(Some(&body)) {
+        Ok(batch) => batch,
+        Err(e) => {
+            let mcp_error: McpError = e.into();
+            return HttpResponse::BadRequest().json(json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": mcp_error
+            }));
+        }
+    };
+
+    // Calls outside the caller's scope fail individually, like the rest of the batch
+    let scope = auth_result.as_ref().map(|auth| auth.tool_scope()).unwrap_or_default();
+    let client = auth_result.as_ref().and_then(|auth| auth.client_name()).map(String::from);
+    let user = auth_result.as_ref().map(|auth| auth.get_user_id());
+    let subject_token = auth_result.as_ref().and_then(|auth| auth.subject_token(&req));
+    let roles = auth_result.as_ref().map(|auth| auth.roles()).unwrap_or_default();
+    let batch = crate::routing::cost::as_user(user, mcp_server.call_tool_batch(batch, &scope));
+    let batch = crate::routing::quota::with_roles(roles, batch);
+    let batch = crate::auth::on_behalf_of(subject_token, batch);
+    let results = crate::routing::rate_limit::as_client(client, batch).await;
+    HttpResponse::Ok().json(json!({ "results": results }))
+}
+
+/// Callback endpoint for jobs submitted by `webhook` agents
+///
+/// Backends don't hold API keys; each callback is checked against the signature
+/// of the job it names instead.
+pub async fn webhook_callback_handler(
+    req: HttpRequest,
+    correlation_id: web::Path<String>,
+    body: web::Bytes,
+) -> HttpResponse {
+    use crate::routing::webhook::{self, CallbackError};
+
+    let header = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok());
+    let delivered = webhook::pending_jobs().deliver(
+        &correlation_id,
+        header(webhook::TIMESTAMP_HEADER),
+        header(webhook::SIGNATURE_HEADER),
+        &body,
+    );
+    match delivered {
+        Ok(()) => HttpResponse::Ok().json(json!({ "status": "accepted" })),
+        Err(e) => {
+            warn!("Rejected webhook callback for job '{}': {}", correlation_id, e);
+            let body = json!({ "error": e.to_string() });
+            match e {
+                CallbackError::UnknownJob => HttpResponse::NotFound().json(body),
+                CallbackError::InvalidSignature => HttpResponse::Unauthorized().json(body),
+                CallbackError::InvalidBody(_) => HttpResponse::BadRequest().json(body),
+            }
+        }
+    }
+}
+
+/// Query of approve/deny links sent to approvers
+#[derive(Debug, serde::Deserialize)]
+pub struct ApprovalLinkQuery {
+    pub expires: i64,
+    pub signature: String,
+}
+
+fn approval_link_error(id: &str, e: crate::routing::approval_notify::LinkError) -> HttpResponse {
+    use crate::routing::approval_notify::LinkError;
+
+    warn!("Rejected approval link for '{}': {}", id, e);
+    let body = json!({ "error": e.to_string() });
+    match e {
+        LinkError::NotConfigured | LinkError::UnknownApproval => HttpResponse::NotFound().json(body),
+        LinkError::Expired => HttpResponse::Gone().json(body),
+        LinkError::InvalidSignature => HttpResponse::Unauthorized().json(body),
+    }
+}
+
+/// Confirmation page of an approve/deny link
+///
+/// Opening a link doesn't decide, so link previews and mail scanners can't;
+/// the page posts the same link back instead.
+pub async fn approval_link_page_handler(
+    path: web::Path<(String, String)>,
+    query: web::Query<ApprovalLinkQuery>,
+    server: web::Data<Arc<McpServer>>,
+) -> HttpResponse {
+    use crate::routing::approval_notify::LinkAction;
+
+    let (id, action) = path.into_inner();
+    let Some(action) = LinkAction::parse(&action) else {
+        return HttpResponse::NotFound().json(json!({ "error": "unknown action" }));
+    };
+    let request = match server.router().approvals().verify_link(&id, action, query.expires, &query.signature).await {
+        Ok(request) => request,
+        Err(e) => return approval_link_error(&id, e),
+    };
+    let escape = |text: &str| text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;");
+    let verb = if action == LinkAction::Approve { "Approve" } else { "Deny" };
+    let page = format!(
+        "<!DOCTYPE html><html><head><title>{verb} tool call</title></head><body>\
+         <h1>{verb} call to '{tool}'?</h1><p>{reason}</p><pre>{arguments}</pre>\
+         <form method=\"post\"><button type=\"submit\">{verb}</button></form></body></html>",
+        verb = verb,
+        tool = escape(&request.tool_name),
+        reason = escape(&request.reason),
+        arguments = escape(&serde_json::to_string_pretty(&request.arguments).unwrap_or_default()),
+    );
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(page)
+}
+
+/// Approve or deny a waiting call through a signed link
+pub async fn approval_link_handler(
+    path: web::Path<(String, String)>,
+    query: web::Query<ApprovalLinkQuery>,
+    server: web::Data<Arc<McpServer>>,
+) -> HttpResponse {
+    use crate::routing::approval_notify::LinkAction;
+
+    let (id, action) = path.into_inner();
+    let Some(action) = LinkAction::parse(&action) else {
+        return HttpResponse::NotFound().json(json!({ "error": "unknown action" }));
+    };
+    match server.router().approvals().decide_by_link(&id, action, query.expires, &query.signature).await {
+        Ok(request) => HttpResponse::Ok().json(json!({
+            "status": if action == LinkAction::Approve { "approved" } else { "denied" },
+            "approval": request,
+        })),
+        Err(e) => approval_link_error(&id, e),
+    }
+}
+
 /// List resources endpoint
 pub async fn list_resources_handler(
     query: web::Query<ResourceListRequest>,
@@ -1525,27 +2339,81 @@ pub async fn websocket_handler(
     stream: web::Payload,
     mcp_server: web::Data<Arc<McpServer>>,
 ) -> actix_web::Result<HttpResponse> {
+    // Connections made with an API key bound to a toolset or scopes are restricted
+    // to them, and their tool calls count against the key's rate limit
+    let auth_result = match mcp_server.auth_middleware() {
+        Some(auth) => auth.for_request(&req).validate_http_request(&req).await.ok().flatten(),
+        None => None,
+    };
+    let caller = WebSocketCaller {
+        scope: auth_result.as_ref().map(|auth| auth.tool_scope()).unwrap_or_default(),
+        client: auth_result.as_ref().and_then(|auth| auth.client_name()).map(String::from),
+        user: auth_result.as_ref().map(|auth| auth.get_user_id()),
+        roles: auth_result.as_ref().map(|auth| auth.roles()).unwrap_or_default(),
+        subject_token: auth_result.as_ref().and_then(|auth| auth.subject_token(&req)),
+        auth_method: auth_result.as_ref().map(|auth| auth.method()),
+    };
+
     let (response, session, msg_stream) = actix_ws::handle(&req, stream)?;
 
     // Clone the server for the spawned task
     let server = mcp_server.get_ref().clone();
 
     // Spawn a task to handle WebSocket messages
-    actix_web::rt::spawn(handle_websocket_session(session, msg_stream, server));
+    actix_web::rt::spawn(handle_websocket_session(session, msg_stream, server, caller));
 
     Ok(response)
 }
 
+/// Message for a call to a tool outside the caller's scope
+fn scope_error_message(tool_name: &str, scope: &ToolScope) -> String {
+    let toolsets = scope.toolset_names();
+    if toolsets.is_empty() {
+        format!("Tool '{}' is not within the scopes of this API key", tool_name)
+    } else {
+        format!("Tool '{}' is not available in toolset '{}'", tool_name, toolsets.join("', '"))
+    }
+}
+
+/// Toolset names from a `toolset` (string) or `toolsets` (array) param
+fn toolset_names_param(params: Option<&Value>) -> Vec<String> {
+    let Some(params) = params else {
+        return Vec::new();
+    };
+    match (params.get("toolsets"), params.get("toolset")) {
+        (Some(Value::Array(names)), _) => names.iter().filter_map(|n| n.as_str()).map(String::from).collect(),
+        (_, Some(Value::String(name))) => vec![name.clone()],
+        _ => Vec::new(),
+    }
+}
+
+/// Who opened a WebSocket connection, from its authentication
+struct WebSocketCaller {
+    /// Toolset and scopes of the caller's API key
+    scope: ToolScope,
+    /// Client the calls count against for rate limits
+    client: Option<String>,
+    /// User the calls are charged to
+    user: Option<String>,
+    /// Roles whose quotas the calls count against
+    roles: Vec<String>,
+    /// Token upstream calls are made on behalf of
+    subject_token: Option<String>,
+    /// Authentication method the caller used
+    auth_method: Option<&'static str>,
+}
+
 /// Handle WebSocket session with MCP protocol support
 async fn handle_websocket_session(
     mut session: actix_ws::Session,
     mut msg_stream: actix_ws::MessageStream,
     server: Arc<McpServer>,
+    caller: WebSocketCaller,
 ) {
     debug!("WebSocket session started");
 
     // Create session for this WebSocket connection
-    let session_id = match server.session_manager.create_session() {
+    let session_id = match server.session_manager.create_authenticated_session(caller.auth_method) {
         Ok(id) => id,
         Err(e) => {
             error!("Failed to create session: {}", e);
@@ -1558,8 +2426,53 @@ async fn handle_websocket_session(
             return;
         }
     };
+    if !caller.scope.is_unrestricted() {
+        let _ = server.session_manager.bind_scope(&session_id, caller.scope);
+    }
+    // This connection ends the session when it expires, not the sweeper
+    let _ = server.session_manager.mark_connected(&session_id);
+
+    // Push server-side notifications (list_changed, resource updates) to this client
+    let mut notifications = server.notification_manager.subscribe();
+    let mut expiry = None;
+
+    loop {
+        // The session ends at its idle or absolute timeout, whichever comes first
+        let Some(deadline) = server.session_manager.session_deadline(&session_id) else {
+            break;
+        };
+        let msg = tokio::select! {
+            msg = msg_stream.next() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = tokio::time::sleep_until(deadline.into()) => {
+                expiry = server.session_manager.session_expiry(&session_id);
+                if expiry.is_some() {
+                    break;
+                }
+                continue;
+            }
+            notification = notifications.recv() => {
+                match notification {
+                    Ok(notification) if !server.notification_manager.is_for_session(&session_id, &notification) => {}
+                    Ok(notification) => {
+                        if session.text(notification.to_jsonrpc().to_string()).await.is_err() {
+                            warn!("Failed to send notification to WebSocket client");
+                            break;
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("WebSocket session {} lagged, dropped {} notifications", session_id, skipped);
+                    }
+                    // The notification manager lives as long as the server, so this only
+                    // happens during shutdown
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+                continue;
+            }
+        };
 
-    while let Some(msg) = msg_stream.next().await {
         match msg {
             Ok(Message::Text(text)) => {
                 debug!("Received WebSocket message: {}", text);
@@ -1637,7 +2550,9 @@ async fn handle_websocket_session(
 
                 // Handle initialize method with protocol version negotiation
                 if request.method == "initialize" {
-                    match server.session_manager.handle_initialize(&session_id, &request) {
+                    let initialized = server.select_session_toolset(&session_id, &request)
+                        .and_then(|_| server.session_manager.handle_initialize(&session_id, &request));
+                    match initialized {
                         Ok(negotiated_version) => {
                             info!("Session {} initialized with protocol version {}", session_id, negotiated_version);
                             // Update server capabilities with negotiated version
@@ -1673,13 +2588,61 @@ async fn handle_websocket_session(
                 let _ = server.session_manager.update_activity(&session_id);
 
                 // Use unified MCP handler
-                match server.handle_mcp_request(request).await {
+                let switches_toolsets = matches!(request.method.as_str(), "toolsets/activate" | "toolsets/deactivate");
+
+                // Tool calls that asked for progress get notifications while they run
+                let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+                let reporter = if request.method == "tools/call" {
+                    crate::mcp::progress::ProgressReporter::for_request(request.params.as_ref(), progress_tx)
+                } else {
+                    None
+                };
+                let handled = crate::routing::rate_limit::as_client(caller.client.clone(), crate::routing::cost::as_user(caller.user.clone(), async {
+                    match reporter {
+                        Some(reporter) => reporter.scope(server.handle_session_request(request, &session_id)).await,
+                        None => server.handle_session_request(request, &session_id).await,
+                    }
+                }));
+                let handled = crate::routing::quota::with_roles(caller.roles.clone(), handled);
+                let handled = crate::auth::on_behalf_of(caller.subject_token.clone(), handled);
+                tokio::pin!(handled);
+                let mut disconnected = false;
+                let handled = loop {
+                    tokio::select! {
+                        result = &mut handled => break result,
+                        Some(notification) = progress_rx.recv(), if !disconnected => {
+                            disconnected = session.text(notification.to_jsonrpc().to_string()).await.is_err();
+                        }
+                    }
+                };
+                while let Ok(notification) = progress_rx.try_recv() {
+                    if disconnected {
+                        break;
+                    }
+                    disconnected = session.text(notification.to_jsonrpc().to_string()).await.is_err();
+                }
+                if disconnected {
+                    warn!("Failed to send progress notification to WebSocket client");
+                    break;
+                }
+
+                match handled {
                     Ok(response) => {
                         if let Some(response_text) = response {
+                            let switched = switches_toolsets && serde_json::from_str::<Value>(&response_text)
+                                .is_ok_and(|response| response.get("error").is_none());
                             if session.text(response_text).await.is_err() {
                                 warn!("Failed to send WebSocket response");
                                 break;
                             }
+                            // The session's tool list changed; other sessions are unaffected
+                            if switched {
+                                let notification = McpNotification::tools_list_changed().to_jsonrpc().to_string();
+                                if session.text(notification).await.is_err() {
+                                    warn!("Failed to send notification to WebSocket client");
+                                    break;
+                                }
+                            }
                         }
                     }
                     Err(e) => {
@@ -1704,7 +2667,22 @@ async fn handle_websocket_session(
         }
     }
 
+    // Tell the client why an expired session ends before closing the connection
+    if let Some(expiry) = expiry {
+        if let Some(expired) = server.session_manager.get_session(&session_id) {
+            McpSessionManager::audit_expiry(&expired, expiry);
+        }
+        let notification = McpNotification::session_terminated(&session_id, expiry.reason());
+        let _ = session.text(notification.to_jsonrpc().to_string()).await;
+        let _ = session.close(Some(actix_ws::CloseReason {
+            code: actix_ws::CloseCode::Normal,
+            description: Some(format!("Session expired: {}", expiry.reason())),
+        })).await;
+    }
+
     // Clean up session when WebSocket connection closes
+    server.router.cost_tracker().forget_session(&session_id);
+    server.notification_manager.forget_session(&session_id);
     if let Err(e) = server.session_manager.remove_session(&session_id) {
         warn!("Failed to remove session {}: {}", session_id, e);
     } else {
@@ -1739,39 +2717,83 @@ pub async fn sse_handler() -> HttpResponse {
 }
 
 /// Streaming tool execution handler
+///
+/// Runs the call with a progress reporter, so agents that report progress
+/// (subprocess stdout, LLM output, Kubernetes job logs) stream it as `progress`
+/// events before the final `result` or `error` event.
 pub async fn streaming_tool_handler(
     req: HttpRequest,
-    _tool_call: web::Json<ToolCall>,
+    tool_call: web::Json<ToolCall>,
     mcp_server: web::Data<Arc<McpServer>>,
 ) -> HttpResponse {
     // Check authentication with write permission for tool execution
-    if let Err(auth_error) = check_authentication(&req, &mcp_server.auth_middleware, "write").await {
-        return auth_error;
+    let auth_result = match check_authentication(&req, &mcp_server.auth_middleware, "write").await {
+        Ok(auth_result) => auth_result,
+        Err(auth_error) => return auth_error,
+    };
+    let scope = auth_result.as_ref().map(|auth| auth.tool_scope()).unwrap_or_default();
+    if !mcp_server.is_tool_in_scope(&tool_call.name, &scope) {
+        return HttpResponse::Forbidden().json(json!({
+            "error": {
+                "code": "TOOL_NOT_IN_TOOLSET",
+                "message": scope_error_message(&tool_call.name, &scope),
+                "type": "authorization_error"
+            }
+        }));
     }
     use actix_web::http::header;
 
+    let client = auth_result.as_ref().and_then(|auth| auth.client_name()).map(String::from);
+    let user = auth_result.as_ref().map(|auth| auth.get_user_id());
+    let subject_token = auth_result.as_ref().and_then(|auth| auth.subject_token(&req));
+    let roles = auth_result.as_ref().map(|auth| auth.roles()).unwrap_or_default();
+    let tool_call = tool_call.into_inner();
+    let server = mcp_server.get_ref().clone();
+
     let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let send = move |event: Value| tx.send(Ok::<_, actix_web::Error>(web::Bytes::from(format!("data: {}\n\n", event)))).is_ok();
 
-    // Spawn a task to simulate streaming tool execution
     actix_web::rt::spawn(async move {
-        // Send initial response
-        let start_msg = "data: {\"type\": \"start\", \"message\": \"Tool execution started\"}\n\n";
-        if tx.send(Ok::<_, actix_web::Error>(web::Bytes::from(start_msg))).is_err() {
+        if !send(json!({"type": "start", "message": "Tool execution started"})) {
             return;
         }
 
-        // Simulate progress updates
-        for i in 1..=5 {
-            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-            let progress_msg = format!("data: {{\"type\": \"progress\", \"step\": {}, \"message\": \"Processing step {}\"}}\n\n", i, i);
-            if tx.send(Ok::<_, actix_web::Error>(web::Bytes::from(progress_msg))).is_err() {
+        let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+        let reporter = crate::mcp::progress::ProgressReporter::new(json!(tool_call.name), progress_tx);
+        let call = crate::registry::toolset::in_scope(&scope, reporter.scope(server.call_tool_with_router(&tool_call)));
+        let call = crate::routing::quota::with_roles(roles, crate::routing::cost::as_user(user, call));
+        let call = crate::auth::on_behalf_of(subject_token, call);
+        let call = crate::routing::rate_limit::as_client(client, call);
+        tokio::pin!(call);
+
+        let progress_event = |notification: McpNotification| {
+            let params = notification.params.unwrap_or_default();
+            json!({"type": "progress", "step": params["progress"], "message": params["message"]})
+        };
+        // Dropping the call when the client goes away cancels it
+        let result = loop {
+            tokio::select! {
+                result = &mut call => break result,
+                Some(notification) = progress_rx.recv() => {
+                    if !send(progress_event(notification)) {
+                        return;
+                    }
+                }
+            }
+        };
+        while let Ok(notification) = progress_rx.try_recv() {
+            if !send(progress_event(notification)) {
                 return;
             }
         }
 
-        // Send final result
-        let result_msg = "data: {\"type\": \"result\", \"success\": true, \"data\": \"Tool execution completed\"}\n\n";
-        let _ = tx.send(Ok::<_, actix_web::Error>(web::Bytes::from(result_msg)));
+        let _ = send(match result {
+            Ok(result) => json!({"type": "result", "success": result.success, "data": result}),
+            Err(e) => {
+                error!("Failed to call tool '{}': {}", tool_call.name, e);
+                json!({"type": "error", "error": McpError::from(e)})
+            }
+        });
     });
 
     HttpResponse::Ok()
@@ -1784,20 +2806,22 @@ pub async fn streaming_tool_handler(
 // Helper functions for HTTP handlers
 
 /// List tools from registry service
-async fn list_tools_from_registry(registry: &Arc<RegistryService>) -> Result<Vec<Tool>> {
+async fn list_tools_from_registry(registry: &Arc<RegistryService>, filter: &ToolFilter) -> Result<Vec<Tool>> {
     debug!("Listing tools from registry");
 
     let tool_names = registry.list_tools();
     let mut tools = Vec::new();
 
     for tool_name in tool_names {
-        if let Some(tool_def) = registry.get_tool(&tool_name) {
+        if let Some(tool_def) = registry.get_tool(&tool_name).filter(|tool_def| filter.matches(tool_def)) {
             // Convert ToolDefinition to MCP Tool
-            let tool = crate::mcp::types::Tool::new(
+            let mut tool = crate::mcp::types::Tool::new(
                 tool_def.name().to_string(),
                 tool_def.description().to_string(),
                 tool_def.input_schema.clone(),
             )?;
+            tool.annotations = tool_def.deprecation_annotations();
+            tool.output_schema = tool_def.output_schema.clone();
             tools.push(tool);
         }
     }
@@ -1816,13 +2840,15 @@ impl McpServer {
         info!("🚀 TOOL CALL START - Tool: '{}' with {} arguments", tool_call.name, arg_count);
         
         // Log the arguments being passed to the tool
-        if let Ok(args_json) = serde_json::to_string_pretty(&tool_call.arguments) {
+        if let Ok(args_json) = serde_json::to_string_pretty(&self.router.redact_for_logging(&tool_call.arguments)) {
             info!("📝 Tool call arguments:\n{}", args_json);
         }
 
         // Validate tool exists in registry
-        let tool_def = self.registry.get_tool(&tool_call.name)
-            .ok_or_else(|| ProxyError::validation(format!("Tool '{}' not found", tool_call.name)))?;
+        let Some(tool_def) = self.registry.get_tool(&tool_call.name) else {
+            return self.jobs.call_tool(tool_call, jobs::current_owner().as_deref())
+                .ok_or_else(|| ProxyError::validation(format!("Tool '{}' not found", tool_call.name)));
+        };
         
         info!("✅ Tool found in registry: '{}' - {}", tool_call.name, tool_def.description);
         info!("🔧 Tool routing type: {:?}", tool_def.routing_type());
@@ -1843,6 +2869,10 @@ impl McpServer {
             }
         }
 
+        if tool_def.background && !crate::routing::dry_run::is_dry_run() {
+            return Ok(self.start_job(tool_call, &tool_def));
+        }
+
         // Route to appropriate agent using the configured router (which has external MCP integration)
         info!("🎯 Routing tool call to agent...");
         let start_time = std::time::Instant::now();
@@ -1874,7 +2904,9 @@ impl McpServer {
                     "routing_type": tool_def.routing_type(),
                     "execution_time_ms": duration.as_millis()
                 });
-                Ok(Self::agent_result_to_tool_result(agent_result, &tool_call.name, Some(metadata)))
+                let result = Self::agent_result_to_tool_result(agent_result, &tool_call.name, Some(metadata));
+                let result = self.with_structured_content(result, &tool_def);
+                Ok(Self::with_deprecation_warning(result, &tool_def))
             }
             Err(e) => {
                 let duration = start_time.elapsed();
@@ -1930,6 +2962,7 @@ async fn oauth_authorize_handler(
     mcp_server: web::Data<Arc<McpServer>>,
 ) -> HttpResponse {
     if let Some(auth_middleware) = &mcp_server.auth_middleware {
+        let auth_middleware = auth_middleware.for_request(&req);
         // Extract redirect_uri and state from query parameters
         let redirect_uri = query.get("redirect_uri")
             .unwrap_or(&"http://localhost:8080/auth/oauth/callback".to_string())
@@ -1938,6 +2971,17 @@ async fn oauth_authorize_handler(
             .unwrap_or(&"default_state".to_string())
             .clone();
 
+        // Dynamically registered clients must be approved and use a registered redirect URI
+        if let (Some(registry), Some(client_id)) = (auth_middleware.client_registry(), query.get("client_id")) {
+            if let Err(e) = registry.check_authorization(client_id, &redirect_uri) {
+                warn!(client_id = %client_id, "OAuth authorization refused: {}", e);
+                return HttpResponse::BadRequest().json(json!({
+                    "error": "unauthorized_client",
+                    "error_description": e.to_string()
+                }));
+            }
+        }
+
         // Get authorization URL from OAuth validator
         match auth_middleware.get_oauth_authorization_url(&redirect_uri, &state) {
             Ok(auth_url) => {
@@ -1980,6 +3024,7 @@ async fn oauth_callback_handler(
     mcp_server: web::Data<Arc<McpServer>>,
 ) -> HttpResponse {
     if let Some(auth_middleware) = &mcp_server.auth_middleware {
+        let auth_middleware = auth_middleware.for_request(&req);
         // Extract authorization code and state from query parameters
         let code = match query.get("code") {
             Some(code) => code,
@@ -2046,6 +3091,7 @@ async fn oauth_token_handler(
     mcp_server: web::Data<Arc<McpServer>>,
 ) -> HttpResponse {
     if let Some(auth_middleware) = &mcp_server.auth_middleware {
+        let auth_middleware = auth_middleware.for_request(&req);
         match auth_middleware.validate_http_request(&req).await {
             Ok(Some(auth_result)) => {
                 match auth_result {
@@ -2088,6 +3134,42 @@ async fn oauth_token_handler(
                             .content_type("application/json")
                             .json(error_response)
                     }
+                    crate::auth::AuthenticationResult::ClientCert(_) => {
+                        let error_response = json!({
+                            "error": {
+                                "code": "WRONG_AUTH_TYPE",
+                                "message": "Expected OAuth token, got client certificate",
+                                "type": "authentication_error"
+                            }
+                        });
+                        HttpResponse::BadRequest()
+                            .content_type("application/json")
+                            .json(error_response)
+                    }
+                    crate::auth::AuthenticationResult::Hmac(_) => {
+                        let error_response = json!({
+                            "error": {
+                                "code": "WRONG_AUTH_TYPE",
+                                "message": "Expected OAuth token, got HMAC signed request",
+                                "type": "authentication_error"
+                            }
+                        });
+                        HttpResponse::BadRequest()
+                            .content_type("application/json")
+                            .json(error_response)
+                    }
+                    crate::auth::AuthenticationResult::Guest(_) => {
+                        let error_response = json!({
+                            "error": {
+                                "code": "NO_TOKEN_PROVIDED",
+                                "message": "No authentication token provided",
+                                "type": "authentication_error"
+                            }
+                        });
+                        HttpResponse::Unauthorized()
+                            .content_type("application/json")
+                            .json(error_response)
+                    }
                 }
             }
             Ok(None) => {
@@ -2129,6 +3211,131 @@ async fn oauth_token_handler(
     }
 }
 
+/// OAuth dynamic client registration endpoint (RFC 7591)
+pub async fn oauth_register_handler(
+    body: web::Json<crate::auth::ClientMetadata>,
+    mcp_server: web::Data<Arc<McpServer>>,
+) -> HttpResponse {
+    let Some(registry) = mcp_server.auth_middleware.as_ref().and_then(|auth| auth.client_registry()) else {
+        return HttpResponse::NotFound().json(json!({
+            "error": "invalid_request",
+            "error_description": "Client registration is not enabled"
+        }));
+    };
+
+    match registry.register(body.into_inner()) {
+        Ok(client) => HttpResponse::Created().json(client),
+        Err(e) => {
+            let response = json!({
+                "error": e.code(),
+                "error_description": e.description()
+            });
+            match e {
+                crate::auth::RegistrationError::Unavailable(_) => HttpResponse::ServiceUnavailable().json(response),
+                _ => HttpResponse::BadRequest().json(response),
+            }
+        }
+    }
+}
+
+/// Role mapping dry run endpoint
+///
+/// Validates an OAuth access token or JWT and reports the roles and
+/// permissions its user would receive, without using it to authenticate.
+/// Requires the `admin` permission.
+pub async fn role_dry_run_handler(
+    req: HttpRequest,
+    body: web::Json<crate::auth::RoleDryRunRequest>,
+    mcp_server: web::Data<Arc<McpServer>>,
+) -> HttpResponse {
+    let Some(auth_middleware) = &mcp_server.auth_middleware else {
+        return HttpResponse::NotFound().json(json!({
+            "error": {
+                "code": "AUTH_DISABLED",
+                "message": "Authentication is not enabled",
+                "type": "configuration_error"
+            }
+        }));
+    };
+    if let Err(auth_error) = check_authentication(&req, &mcp_server.auth_middleware, "admin").await {
+        return auth_error;
+    }
+
+    match auth_middleware.for_request(&req).role_dry_run(&body.token).await {
+        Ok(dry_run) => HttpResponse::Ok().json(dry_run),
+        Err(e) => HttpResponse::UnprocessableEntity().json(json!({
+            "error": {
+                "code": "INVALID_TOKEN",
+                "message": e.to_string(),
+                "type": "authentication_error"
+            }
+        })),
+    }
+}
+
+/// API key rotation endpoint
+///
+/// Issues a new secret for the named key, which is returned once; the current
+/// secret keeps working for the grace period. Keys can rotate themselves,
+/// other keys need the `admin` permission.
+pub async fn api_key_rotate_handler(
+    req: HttpRequest,
+    name: web::Path<String>,
+    body: Option<web::Json<crate::auth::KeyRotationRequest>>,
+    mcp_server: web::Data<Arc<McpServer>>,
+) -> HttpResponse {
+    let disabled = || HttpResponse::NotFound().json(json!({
+        "error": {
+            "code": "API_KEYS_DISABLED",
+            "message": "API key authentication is not enabled",
+            "type": "configuration_error"
+        }
+    }));
+    let Some(auth_middleware) = &mcp_server.auth_middleware else {
+        return disabled();
+    };
+    let auth_middleware = auth_middleware.for_request(&req);
+    let auth_result = match check_authentication(&req, &mcp_server.auth_middleware, "read").await {
+        Ok(Some(auth_result)) => auth_result,
+        Ok(None) => return disabled(),
+        Err(auth_error) => return auth_error,
+    };
+    if auth_result.api_key_name() != Some(name.as_str()) && !auth_middleware.check_permission(&auth_result, "admin") {
+        return HttpResponse::Forbidden().json(json!({
+            "error": {
+                "code": "INSUFFICIENT_PERMISSIONS",
+                "message": "Rotating another API key requires the 'admin' permission",
+                "type": "authorization_error"
+            }
+        }));
+    }
+
+    let request = body.map(web::Json::into_inner).unwrap_or_default();
+    match auth_middleware.api_keys().rotate_key(&name, &request) {
+        Ok(rotation) => {
+            info!(api_key_name = %name, rotated_by = %auth_result.get_user_id(), "API key rotated");
+            HttpResponse::Ok().json(rotation)
+        }
+        Err(e @ ProxyError::Validation { .. }) => HttpResponse::BadRequest().json(json!({
+            "error": {
+                "code": "INVALID_ROTATION",
+                "message": e.to_string(),
+                "type": "validation_error"
+            }
+        })),
+        Err(e) => {
+            error!("Failed to rotate API key '{}': {}", name, e);
+            HttpResponse::InternalServerError().json(json!({
+                "error": {
+                    "code": "ROTATION_FAILED",
+                    "message": e.to_string(),
+                    "type": "internal_error"
+                }
+            }))
+        }
+    }
+}
+
 
 
 
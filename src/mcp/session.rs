@@ -2,10 +2,23 @@
 //! 
 //! Provides session tracking for WebSocket connections with request ID uniqueness validation
 //! and protocol version negotiation according to the MCP specification.
+//!
+//! Sessions end after an idle timeout and, optionally, an absolute lifetime,
+//! both overridable per authentication method:
+//!
+//! ```yaml
+//! sessions:
+//!   idle_timeout_seconds: 1800
+//!   max_lifetime_seconds: 86400
+//!   auth_methods:
+//!     guest: { idle_timeout_seconds: 300, max_lifetime_seconds: 3600 }
+//! ```
 
 use crate::error::{Result, ProxyError};
 use crate::mcp::types::McpRequest;
-use std::collections::{HashMap, HashSet};
+use crate::registry::toolset::ToolScope;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
@@ -47,6 +60,16 @@ pub struct McpSession {
     pub last_activity: Instant,
     /// Whether the session has been initialized
     pub initialized: bool,
+    /// Toolset the session is restricted to (from its API key)
+    pub toolset: Option<String>,
+    /// Toolsets activated by the client (`toolsets/activate` or initialize params)
+    pub active_toolsets: Vec<String>,
+    /// Tool and capability scope patterns of the session's API key
+    pub scopes: Vec<String>,
+    /// Authentication method the client used (`api_key`, `jwt`, `guest`, ...), if any
+    pub auth_method: Option<String>,
+    /// Whether an open connection owns the session and ends it itself when it expires
+    pub connected: bool,
 }
 
 /// Client information from MCP initialize request
@@ -74,8 +97,12 @@ pub struct McpSessionManager {
 pub struct SessionConfig {
     /// Maximum number of active sessions
     pub max_sessions: usize,
-    /// Session timeout duration
-    pub session_timeout: Duration,
+    /// Time without activity after which a session ends
+    pub idle_timeout: Duration,
+    /// Time after creation after which a session ends however active it is
+    pub max_lifetime: Option<Duration>,
+    /// Timeouts of sessions by the authentication method their client used
+    pub auth_method_timeouts: BTreeMap<String, SessionTimeouts>,
     /// Maximum request IDs per session
     pub max_request_ids_per_session: usize,
     /// Enable strict protocol version validation
@@ -86,13 +113,89 @@ impl Default for SessionConfig {
     fn default() -> Self {
         Self {
             max_sessions: MAX_ACTIVE_SESSIONS,
-            session_timeout: SESSION_TIMEOUT,
+            idle_timeout: SESSION_TIMEOUT,
+            max_lifetime: None,
+            auth_method_timeouts: BTreeMap::new(),
             max_request_ids_per_session: MAX_REQUEST_IDS_PER_SESSION,
             strict_version_validation: true,
         }
     }
 }
 
+impl From<&SessionsConfig> for SessionConfig {
+    fn from(config: &SessionsConfig) -> Self {
+        let defaults = Self::default();
+        Self {
+            idle_timeout: config.timeouts.idle_timeout_seconds.map_or(defaults.idle_timeout, Duration::from_secs),
+            max_lifetime: config.timeouts.max_lifetime_seconds.map(Duration::from_secs),
+            auth_method_timeouts: config.auth_methods.clone(),
+            ..defaults
+        }
+    }
+}
+
+/// Session timeouts, in seconds
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionTimeouts {
+    /// Seconds without activity after which a session ends
+    #[serde(default)]
+    pub idle_timeout_seconds: Option<u64>,
+    /// Seconds after creation after which a session ends however active it is
+    #[serde(default)]
+    pub max_lifetime_seconds: Option<u64>,
+}
+
+/// Session timeouts of the `sessions` config section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionsConfig {
+    /// Timeouts of all sessions (default: 30 minutes idle, no absolute lifetime)
+    #[serde(flatten)]
+    pub timeouts: SessionTimeouts,
+    /// Timeouts replacing those above for sessions of clients that
+    /// authenticated with a method (`api_key`, `oauth`, `jwt`, `client_cert`, `hmac`, `guest`)
+    #[serde(default)]
+    pub auth_methods: BTreeMap<String, SessionTimeouts>,
+}
+
+impl SessionsConfig {
+    pub fn validate(&self) -> Result<()> {
+        const METHODS: &[&str] = &["api_key", "oauth", "jwt", "client_cert", "hmac", "guest"];
+        if let Some(method) = self.auth_methods.keys().find(|method| !METHODS.contains(&method.as_str())) {
+            return Err(ProxyError::config(format!(
+                "Unknown authentication method '{}' in sessions.auth_methods. Expected one of: {}",
+                method,
+                METHODS.join(", ")
+            )));
+        }
+        let all_timeouts = std::iter::once(&self.timeouts).chain(self.auth_methods.values());
+        for timeouts in all_timeouts {
+            if timeouts.idle_timeout_seconds == Some(0) || timeouts.max_lifetime_seconds == Some(0) {
+                return Err(ProxyError::config("Session timeouts must be at least one second"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a session ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionExpiry {
+    /// No activity within the idle timeout
+    Idle,
+    /// Older than the absolute lifetime
+    Lifetime,
+}
+
+impl SessionExpiry {
+    /// Reason reported to the client and in audit events
+    pub fn reason(&self) -> &'static str {
+        match self {
+            SessionExpiry::Idle => "idle_timeout",
+            SessionExpiry::Lifetime => "max_lifetime",
+        }
+    }
+}
+
 impl McpSessionManager {
     /// Create a new session manager
     pub fn new() -> Self {
@@ -109,6 +212,11 @@ impl McpSessionManager {
 
     /// Create a new session
     pub fn create_session(&self) -> Result<String> {
+        self.create_authenticated_session(None)
+    }
+
+    /// Create a new session for a client that authenticated with `auth_method`
+    pub fn create_authenticated_session(&self, auth_method: Option<&str>) -> Result<String> {
         let session_id = Uuid::new_v4().to_string();
         
         // Check session limit
@@ -127,6 +235,11 @@ impl McpSessionManager {
             created_at: Instant::now(),
             last_activity: Instant::now(),
             initialized: false,
+            toolset: None,
+            active_toolsets: Vec::new(),
+            scopes: Vec::new(),
+            auth_method: auth_method.map(str::to_string),
+            connected: false,
         };
 
         // Add session
@@ -167,6 +280,74 @@ impl McpSessionManager {
         }
     }
 
+    /// Restrict a session to a toolset
+    pub fn bind_toolset(&self, session_id: &str, toolset: String) -> Result<()> {
+        let mut sessions = self.sessions.write().unwrap();
+        if let Some(session) = sessions.get_mut(session_id) {
+            info!("Bound session '{}' to toolset '{}'", session_id, toolset);
+            session.toolset = Some(toolset);
+            Ok(())
+        } else {
+            Err(ProxyError::mcp(format!("Session not found: {}", session_id)))
+        }
+    }
+
+    /// Restrict a session to the toolset and scope patterns of its API key
+    pub fn bind_scope(&self, session_id: &str, scope: ToolScope) -> Result<()> {
+        let mut sessions = self.sessions.write().unwrap();
+        if let Some(session) = sessions.get_mut(session_id) {
+            info!("Bound session '{}' to toolset {:?} and scopes {:?}", session_id, scope.bound, scope.patterns);
+            session.toolset = scope.bound;
+            session.scopes = scope.patterns;
+            Ok(())
+        } else {
+            Err(ProxyError::mcp(format!("Session not found: {}", session_id)))
+        }
+    }
+
+    /// Leave expiry of a session to the connection that owns it, so it can tell
+    /// its client why the session ended
+    pub fn mark_connected(&self, session_id: &str) -> Result<()> {
+        let mut sessions = self.sessions.write().unwrap();
+        match sessions.get_mut(session_id) {
+            Some(session) => {
+                session.connected = true;
+                Ok(())
+            }
+            None => Err(ProxyError::mcp(format!("Session not found: {}", session_id))),
+        }
+    }
+
+    /// Toolset a session is restricted to, if any
+    pub fn session_toolset(&self, session_id: &str) -> Option<String> {
+        let sessions = self.sessions.read().unwrap();
+        sessions.get(session_id).and_then(|session| session.toolset.clone())
+    }
+
+    /// Replace the toolsets a session has activated
+    pub fn set_active_toolsets(&self, session_id: &str, toolsets: Vec<String>) -> Result<()> {
+        let mut sessions = self.sessions.write().unwrap();
+        if let Some(session) = sessions.get_mut(session_id) {
+            info!("Session '{}' active toolsets: {:?}", session_id, toolsets);
+            session.active_toolsets = toolsets;
+            Ok(())
+        } else {
+            Err(ProxyError::mcp(format!("Session not found: {}", session_id)))
+        }
+    }
+
+    /// Bound and activated toolsets and scope patterns of a session
+    pub fn tool_scope(&self, session_id: &str) -> ToolScope {
+        let sessions = self.sessions.read().unwrap();
+        sessions.get(session_id)
+            .map(|session| ToolScope {
+                bound: session.toolset.clone(),
+                active: session.active_toolsets.clone(),
+                patterns: session.scopes.clone(),
+            })
+            .unwrap_or_default()
+    }
+
     /// Validate request ID uniqueness within session
     pub fn validate_request_id(&self, session_id: &str, request_id: &str) -> Result<()> {
         let mut sessions = self.sessions.write().unwrap();
@@ -274,16 +455,60 @@ impl McpSessionManager {
         Ok(DEFAULT_PROTOCOL_VERSION.to_string())
     }
 
-    /// Clean up expired sessions
+    /// Idle timeout and absolute lifetime of a session, by its authentication method
+    fn timeouts(&self, session: &McpSession) -> (Duration, Option<Duration>) {
+        let overrides = session.auth_method.as_ref().and_then(|method| self.config.auth_method_timeouts.get(method));
+        let idle_timeout = overrides.and_then(|timeouts| timeouts.idle_timeout_seconds).map(Duration::from_secs);
+        let max_lifetime = overrides.and_then(|timeouts| timeouts.max_lifetime_seconds).map(Duration::from_secs);
+        (idle_timeout.unwrap_or(self.config.idle_timeout), max_lifetime.or(self.config.max_lifetime))
+    }
+
+    /// When a session ends unless active before, and why
+    fn deadline(&self, session: &McpSession) -> (Instant, SessionExpiry) {
+        let (idle_timeout, max_lifetime) = self.timeouts(session);
+        let idle_deadline = session.last_activity + idle_timeout;
+        match max_lifetime.map(|lifetime| session.created_at + lifetime) {
+            Some(lifetime_deadline) if lifetime_deadline <= idle_deadline => (lifetime_deadline, SessionExpiry::Lifetime),
+            _ => (idle_deadline, SessionExpiry::Idle),
+        }
+    }
+
+    /// When a session ends unless active before, `None` if there is no such session
+    pub fn session_deadline(&self, session_id: &str) -> Option<Instant> {
+        let sessions = self.sessions.read().unwrap();
+        sessions.get(session_id).map(|session| self.deadline(session).0)
+    }
+
+    /// Why a session has ended, `None` while it is live
+    pub fn session_expiry(&self, session_id: &str) -> Option<SessionExpiry> {
+        let sessions = self.sessions.read().unwrap();
+        let (deadline, expiry) = self.deadline(sessions.get(session_id)?);
+        (Instant::now() >= deadline).then_some(expiry)
+    }
+
+    /// Record the end of an expired session in the audit log
+    pub fn audit_expiry(session: &McpSession, expiry: SessionExpiry) {
+        info!(
+            target: "audit",
+            session_id = %session.id,
+            auth_method = session.auth_method.as_deref().unwrap_or("none"),
+            reason = expiry.reason(),
+            age_seconds = session.created_at.elapsed().as_secs(),
+            "Session expired"
+        );
+    }
+
+    /// Clean up expired sessions, except those a connection ends itself
     pub fn cleanup_expired_sessions(&self) -> usize {
         let mut sessions = self.sessions.write().unwrap();
         let now = Instant::now();
         let initial_count = sessions.len();
 
-        sessions.retain(|session_id, session| {
-            let expired = now.duration_since(session.last_activity) > self.config.session_timeout;
+        sessions.retain(|_, session| {
+            let (deadline, expiry) = self.deadline(session);
+            let expired = !session.connected && now >= deadline;
             if expired {
-                info!("Removing expired session: {}", session_id);
+                Self::audit_expiry(session, expiry);
             }
             !expired
         });
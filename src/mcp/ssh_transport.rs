@@ -0,0 +1,305 @@
+//! SSH transport for External MCP servers
+//!
+//! Runs a stdio MCP server on a remote host through the local `ssh` client.
+//! The remote process' stdin/stdout are forwarded over the SSH channel, so the
+//! rest of the process management works exactly as for a local server and no
+//! HTTP endpoint has to be exposed on the remote side.
+
+use crate::config::{McpServerConfig, SshTransportConfig};
+use crate::error::{ProxyError, Result};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Build the `ssh` invocation that launches `config` on the remote host.
+///
+/// `known_hosts_dir` is where a pinned host key is written so ssh can verify
+/// against it; it is only used when `ssh.host_key` is set.
+pub fn build_ssh_command(
+    server_name: &str,
+    config: &McpServerConfig,
+    ssh: &SshTransportConfig,
+    known_hosts_dir: &Path,
+) -> Result<Command> {
+    let args = ssh_args(server_name, config, ssh, known_hosts_dir)?;
+    let mut cmd = Command::new("ssh");
+    cmd.args(args);
+    Ok(cmd)
+}
+
+/// Arguments passed to the local `ssh` binary
+pub fn ssh_args(
+    server_name: &str,
+    config: &McpServerConfig,
+    ssh: &SshTransportConfig,
+    known_hosts_dir: &Path,
+) -> Result<Vec<String>> {
+    if ssh.host.is_empty() {
+        return Err(ProxyError::config(format!("SSH host cannot be empty for MCP server '{}'", server_name)));
+    }
+
+    let mut args = vec![
+        // No pseudo-terminal: the channel must stay a clean byte stream for JSON-RPC
+        "-T".to_string(),
+        "-p".to_string(),
+        ssh.port.to_string(),
+        // Never fall back to interactive password / passphrase prompts
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        "StrictHostKeyChecking=yes".to_string(),
+        "-o".to_string(),
+        format!("ConnectTimeout={}", ssh.connect_timeout),
+    ];
+
+    if ssh.keepalive_interval > 0 {
+        args.push("-o".to_string());
+        args.push(format!("ServerAliveInterval={}", ssh.keepalive_interval));
+    }
+
+    if let Some(ref identity_file) = ssh.identity_file {
+        args.push("-i".to_string());
+        args.push(identity_file.clone());
+        args.push("-o".to_string());
+        args.push("IdentitiesOnly=yes".to_string());
+    }
+
+    if !ssh.use_agent {
+        args.push("-o".to_string());
+        args.push("IdentityAgent=none".to_string());
+    }
+
+    if let Some(ref host_key) = ssh.host_key {
+        let known_hosts = write_pinned_known_hosts(server_name, ssh, host_key, known_hosts_dir)?;
+        args.push("-o".to_string());
+        args.push(format!("UserKnownHostsFile={}", known_hosts.display()));
+        args.push("-o".to_string());
+        args.push("GlobalKnownHostsFile=/dev/null".to_string());
+    } else if let Some(ref known_hosts_file) = ssh.known_hosts_file {
+        args.push("-o".to_string());
+        args.push(format!("UserKnownHostsFile={}", known_hosts_file));
+    }
+
+    for option in &ssh.options {
+        args.push("-o".to_string());
+        args.push(option.clone());
+    }
+
+    args.push(match ssh.user {
+        Some(ref user) => format!("{}@{}", user, ssh.host),
+        None => ssh.host.clone(),
+    });
+    args.push("--".to_string());
+    args.push(remote_command(config));
+
+    Ok(args)
+}
+
+/// Shell command line executed on the remote host
+pub fn remote_command(config: &McpServerConfig) -> String {
+    let mut parts = Vec::new();
+
+    if let Some(ref cwd) = config.cwd {
+        parts.push(format!("cd {} &&", shell_quote(cwd)));
+    }
+
+    parts.push("exec".to_string());
+
+    if let Some(ref env) = config.env {
+        if !env.is_empty() {
+            // Sort for a stable command line (easier to audit in process listings and logs)
+            let mut vars: Vec<_> = env.iter().collect();
+            vars.sort();
+            parts.push("env".to_string());
+            for (key, value) in vars {
+                let value = super::external_process::expand_env_vars(value);
+                parts.push(shell_quote(&format!("{}={}", key, value)));
+            }
+        }
+    }
+
+    parts.push(shell_quote(&config.command));
+    parts.extend(config.args.iter().map(|arg| shell_quote(arg)));
+
+    parts.join(" ")
+}
+
+/// Quote a string for a POSIX shell
+fn shell_quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@,+%".contains(c))
+    {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Write a known_hosts file containing only the pinned key for this host
+fn write_pinned_known_hosts(
+    server_name: &str,
+    ssh: &SshTransportConfig,
+    host_key: &str,
+    dir: &Path,
+) -> Result<PathBuf> {
+    let host_key = host_key.trim();
+    if host_key.split_whitespace().count() < 2 {
+        return Err(ProxyError::config(format!(
+            "Invalid SSH host key for MCP server '{}': expected '<type> <base64-key>'",
+            server_name
+        )));
+    }
+
+    let host_pattern = if ssh.port == 22 {
+        ssh.host.clone()
+    } else {
+        format!("[{}]:{}", ssh.host, ssh.port)
+    };
+
+    ensure_private_dir(dir)?;
+    let path = dir.join(format!("{}.known_hosts", server_name));
+    std::fs::write(&path, format!("{} {}\n", host_pattern, host_key)).map_err(|e| {
+        ProxyError::config(format!("Failed to write known_hosts file {}: {}", path.display(), e))
+    })?;
+
+    Ok(path)
+}
+
+/// Create `dir` accessible by the current user only, and refuse to use an
+/// existing one that is a symlink, owned by another user or open to others,
+/// since whoever can write to it can replace the pinned host keys
+fn ensure_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir).map_err(|e| {
+        ProxyError::config(format!("Failed to create known_hosts directory {}: {}", dir.display(), e))
+    })?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = std::fs::symlink_metadata(dir).map_err(|e| {
+            ProxyError::config(format!("Failed to inspect known_hosts directory {}: {}", dir.display(), e))
+        })?;
+        // SAFETY: geteuid has no preconditions and cannot fail
+        let uid = unsafe { libc::geteuid() };
+        if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+            return Err(ProxyError::config(format!(
+                "known_hosts directory {} must be a directory owned by the current user with mode 0700",
+                dir.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Default directory for pinned known_hosts files, private to the current user
+pub fn default_known_hosts_dir() -> PathBuf {
+    crate::config::user_data_dir().join("ssh-known-hosts")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn ssh_config() -> SshTransportConfig {
+        SshTransportConfig {
+            host: "db.internal".to_string(),
+            port: 2222,
+            user: Some("mcp".to_string()),
+            identity_file: Some("/keys/id_ed25519".to_string()),
+            use_agent: false,
+            host_key: None,
+            known_hosts_file: None,
+            connect_timeout: 10,
+            keepalive_interval: 15,
+            options: Vec::new(),
+        }
+    }
+
+    fn server_config() -> McpServerConfig {
+        let mut env = HashMap::new();
+        env.insert("DB_NAME".to_string(), "it's prod".to_string());
+        McpServerConfig {
+            command: "npx".to_string(),
+            args: vec!["-y".to_string(), "@modelcontextprotocol/server-postgres".to_string()],
+            env: Some(env),
+            cwd: Some("/opt/mcp servers".to_string()),
+            ssh: Some(ssh_config()),
+        }
+    }
+
+    #[test]
+    fn test_remote_command_is_quoted() {
+        assert_eq!(
+            remote_command(&server_config()),
+            "cd '/opt/mcp servers' && exec env 'DB_NAME=it'\\''s prod' npx -y @modelcontextprotocol/server-postgres"
+        );
+    }
+
+    #[test]
+    fn test_ssh_args_use_key_auth_and_strict_host_checking() {
+        let dir = std::env::temp_dir();
+        let args = ssh_args("postgres", &server_config(), &ssh_config(), &dir).unwrap();
+
+        assert_eq!(&args[..3], &["-T", "-p", "2222"]);
+        assert!(args.contains(&"BatchMode=yes".to_string()));
+        assert!(args.contains(&"StrictHostKeyChecking=yes".to_string()));
+        assert!(args.contains(&"IdentityAgent=none".to_string()));
+        assert!(args.windows(2).any(|w| w[0] == "-i" && w[1] == "/keys/id_ed25519"));
+        assert_eq!(args[args.len() - 3], "mcp@db.internal");
+        assert_eq!(args[args.len() - 2], "--");
+    }
+
+    #[test]
+    fn test_pinned_host_key_writes_known_hosts() {
+        let dir = std::env::temp_dir().join(format!("magictunnel-ssh-test-{}", uuid::Uuid::new_v4()));
+        let ssh = SshTransportConfig {
+            host_key: Some("ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIExample".to_string()),
+            ..ssh_config()
+        };
+        let args = ssh_args("postgres", &server_config(), &ssh, &dir).unwrap();
+
+        let known_hosts = dir.join("postgres.known_hosts");
+        assert!(args.contains(&format!("UserKnownHostsFile={}", known_hosts.display())));
+        assert_eq!(
+            std::fs::read_to_string(&known_hosts).unwrap(),
+            "[db.internal]:2222 ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIExample\n"
+        );
+        std::fs::remove_dir_all(&dir).ok();
+
+        let invalid = SshTransportConfig { host_key: Some("not-a-key".to_string()), ..ssh_config() };
+        assert!(ssh_args("postgres", &server_config(), &invalid, &dir).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_known_hosts_dir_must_be_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let parent = tempfile::tempdir().unwrap();
+        let ssh = SshTransportConfig {
+            host_key: Some("ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIExample".to_string()),
+            ..ssh_config()
+        };
+
+        let dir = parent.path().join("known-hosts");
+        ssh_args("postgres", &server_config(), &ssh, &dir).unwrap();
+        assert_eq!(std::fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+
+        // A directory others can write to, or a symlink planted in its place, is refused
+        let shared = parent.path().join("shared");
+        std::fs::create_dir(&shared).unwrap();
+        std::fs::set_permissions(&shared, std::fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(ssh_args("postgres", &server_config(), &ssh, &shared).is_err());
+
+        let link = parent.path().join("link");
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+        assert!(ssh_args("postgres", &server_config(), &ssh, &link).is_err());
+    }
+}
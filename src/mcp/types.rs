@@ -144,6 +144,15 @@ pub struct ToolAnnotations {
     /// Indicates if tool has open-world semantics
     #[serde(rename = "openWorldHint")]
     pub open_world_hint: Option<bool>,
+    /// Indicates the tool is deprecated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<bool>,
+    /// Why the tool is deprecated
+    #[serde(rename = "deprecationReason", default, skip_serializing_if = "Option::is_none")]
+    pub deprecation_reason: Option<String>,
+    /// Date (YYYY-MM-DD) after which the tool may be removed
+    #[serde(rename = "sunsetDate", default, skip_serializing_if = "Option::is_none")]
+    pub sunset_date: Option<String>,
 }
 
 impl ToolAnnotations {
@@ -155,6 +164,9 @@ impl ToolAnnotations {
             destructive_hint: None,
             idempotent_hint: None,
             open_world_hint: None,
+            deprecated: None,
+            deprecation_reason: None,
+            sunset_date: None,
         }
     }
 
@@ -166,6 +178,9 @@ impl ToolAnnotations {
             destructive_hint: None,
             idempotent_hint: None,
             open_world_hint: None,
+            deprecated: None,
+            deprecation_reason: None,
+            sunset_date: None,
         }
     }
 
@@ -788,6 +803,9 @@ pub struct ToolResult {
     /// Legacy data field for backward compatibility
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<Value>,
+    /// Structured output matching the tool's output schema
+    #[serde(rename = "structuredContent", default, skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
 }
 
 impl ToolResult {
@@ -803,6 +821,7 @@ impl ToolResult {
             error: None,
             metadata: None,
             data: Some(data), // Keep for backward compatibility
+            structured_content: None,
         }
     }
 
@@ -815,6 +834,7 @@ impl ToolResult {
             error: None,
             metadata: None,
             data: None,
+            structured_content: None,
         }
     }
 
@@ -830,6 +850,7 @@ impl ToolResult {
             error: None,
             metadata: Some(metadata),
             data: Some(data), // Keep for backward compatibility
+            structured_content: None,
         }
     }
 
@@ -843,6 +864,7 @@ impl ToolResult {
             error: Some(error),
             metadata: None,
             data: None,
+            structured_content: None,
         }
     }
 
@@ -856,6 +878,7 @@ impl ToolResult {
             error: Some(error),
             metadata: Some(metadata),
             data: None,
+            structured_content: None,
         }
     }
 
@@ -1147,6 +1170,29 @@ impl McpNotification {
         )
     }
 
+    /// Create a progress notification for a request that supplied `progress_token`
+    pub fn progress(progress_token: Value, progress: u64, total: Option<u64>, message: Option<String>) -> Self {
+        let mut params = serde_json::json!({
+            "progressToken": progress_token,
+            "progress": progress,
+        });
+        if let Some(total) = total {
+            params["total"] = total.into();
+        }
+        if let Some(message) = message {
+            params["message"] = message.into();
+        }
+        Self::with_params("notifications/progress".to_string(), params)
+    }
+
+    /// Create a notification that the server ended the client's session, sent before closing the connection
+    pub fn session_terminated(session_id: &str, reason: &str) -> Self {
+        Self::with_params(
+            "notifications/session/terminated".to_string(),
+            serde_json::json!({ "sessionId": session_id, "reason": reason })
+        )
+    }
+
     /// Create a log message notification
     pub fn log_message(log_message: LogMessage) -> Self {
         Self::with_params(
@@ -1154,6 +1200,18 @@ impl McpNotification {
             serde_json::to_value(log_message).unwrap_or_default()
         )
     }
+
+    /// Render as a JSON-RPC 2.0 notification message ready to send to a client
+    pub fn to_jsonrpc(&self) -> Value {
+        let mut message = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": self.method,
+        });
+        if let Some(ref params) = self.params {
+            message["params"] = params.clone();
+        }
+        message
+    }
 }
 
 // ============================================================================
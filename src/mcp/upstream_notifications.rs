@@ -0,0 +1,137 @@
+//! Upstream notification forwarding
+//!
+//! External MCP servers push JSON-RPC notifications (messages without an `id`)
+//! over their stdout. This module classifies them and maps upstream identifiers
+//! into the names and URIs MagicTunnel exposes downstream, so the notification
+//! can be re-emitted to connected clients. Resource updates go only to the
+//! sessions subscribed to the resource.
+
+use crate::mcp::types::McpNotification;
+use serde_json::Value;
+
+/// URI scheme used to namespace resources that originate from External MCP servers
+pub const EXTERNAL_RESOURCE_SCHEME: &str = "external-mcp";
+
+/// A notification received from an External MCP server
+#[derive(Debug, Clone)]
+pub struct UpstreamNotification {
+    /// Server that emitted the notification
+    pub server_name: String,
+    /// The notification as sent by the server
+    pub notification: McpNotification,
+}
+
+/// What the proxy should do with an upstream notification
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpstreamNotificationAction {
+    /// The server's tools, prompts or resources changed; re-discover its capabilities.
+    /// Downstream list_changed notifications follow from the capability diff.
+    Rediscover,
+    /// A resource changed; forward `notifications/resources/updated` with the remapped URI
+    ForwardResourceUpdated { uri: String },
+    /// Not relevant to downstream clients
+    Ignore,
+}
+
+impl UpstreamNotification {
+    /// Try to interpret a raw JSON-RPC message from a server as a notification.
+    /// Returns `None` for responses and requests (messages carrying an `id`).
+    pub fn from_message(server_name: &str, message: &Value) -> Option<Self> {
+        if message.get("id").is_some() {
+            return None;
+        }
+        let method = message.get("method")?.as_str()?.to_string();
+        Some(Self {
+            server_name: server_name.to_string(),
+            notification: McpNotification {
+                method,
+                params: message.get("params").cloned(),
+            },
+        })
+    }
+
+    /// Decide how the notification should be propagated downstream
+    pub fn action(&self) -> UpstreamNotificationAction {
+        match self.notification.method.as_str() {
+            "notifications/tools/list_changed"
+            | "notifications/prompts/list_changed"
+            | "notifications/resources/list_changed" => UpstreamNotificationAction::Rediscover,
+            "notifications/resources/updated" => {
+                match self.notification.params.as_ref().and_then(|p| p.get("uri")).and_then(|u| u.as_str()) {
+                    Some(uri) => UpstreamNotificationAction::ForwardResourceUpdated {
+                        uri: remap_resource_uri(&self.server_name, uri),
+                    },
+                    None => UpstreamNotificationAction::Ignore,
+                }
+            }
+            _ => UpstreamNotificationAction::Ignore,
+        }
+    }
+}
+
+/// Map an upstream resource URI into the proxy's namespace (`external-mcp://<server>/<uri>`)
+pub fn remap_resource_uri(server_name: &str, uri: &str) -> String {
+    format!("{}://{}/{}", EXTERNAL_RESOURCE_SCHEME, server_name, uri)
+}
+
+/// Split a URI of the proxy's namespace into the server and its upstream URI
+pub fn parse_resource_uri(uri: &str) -> Option<(&str, &str)> {
+    let rest = uri.strip_prefix(EXTERNAL_RESOURCE_SCHEME)?.strip_prefix("://")?;
+    rest.split_once('/').filter(|(server, upstream)| !server.is_empty() && !upstream.is_empty())
+}
+
+/// Map an upstream tool name to the name exposed by MagicTunnel (`<tool>_<server>`)
+pub fn remap_tool_name(server_name: &str, tool_name: &str) -> String {
+    format!("{}_{}", tool_name, server_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_responses_are_not_notifications() {
+        let response = json!({"jsonrpc": "2.0", "id": "1", "result": {}});
+        assert!(UpstreamNotification::from_message("fs", &response).is_none());
+    }
+
+    #[test]
+    fn test_list_changed_triggers_rediscovery() {
+        let message = json!({"jsonrpc": "2.0", "method": "notifications/tools/list_changed"});
+        let notification = UpstreamNotification::from_message("fs", &message).unwrap();
+        assert_eq!(notification.action(), UpstreamNotificationAction::Rediscover);
+    }
+
+    #[test]
+    fn test_resource_updated_is_remapped() {
+        let message = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/resources/updated",
+            "params": {"uri": "file:///tmp/notes.txt"}
+        });
+        let notification = UpstreamNotification::from_message("filesystem", &message).unwrap();
+        assert_eq!(
+            notification.action(),
+            UpstreamNotificationAction::ForwardResourceUpdated {
+                uri: "external-mcp://filesystem/file:///tmp/notes.txt".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_resource_uri_round_trip() {
+        let uri = remap_resource_uri("filesystem", "file:///tmp/notes.txt");
+        assert_eq!(parse_resource_uri(&uri), Some(("filesystem", "file:///tmp/notes.txt")));
+        assert_eq!(parse_resource_uri("file:///tmp/notes.txt"), None);
+        assert_eq!(parse_resource_uri("external-mcp://filesystem"), None);
+    }
+
+    #[test]
+    fn test_unknown_notifications_are_ignored() {
+        let message = json!({"jsonrpc": "2.0", "method": "notifications/progress", "params": {}});
+        let notification = UpstreamNotification::from_message("fs", &message).unwrap();
+        assert_eq!(notification.action(), UpstreamNotificationAction::Ignore);
+        assert_eq!(remap_tool_name("fs", "read_file"), "read_file_fs");
+    }
+}
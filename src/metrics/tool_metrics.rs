@@ -327,6 +327,19 @@ pub struct ToolMetricsSummary {
     pub last_updated: DateTime<Utc>,
 }
 
+/// Usage figures of a tool, as used for discovery ranking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUsage {
+    /// Total number of executions
+    pub total_executions: u64,
+    /// Success rate (0.0 to 1.0)
+    pub success_rate: f64,
+    /// Average execution time in milliseconds
+    pub avg_execution_time_ms: f64,
+    /// Last time the tool was executed
+    pub last_execution: Option<DateTime<Utc>>,
+}
+
 /// Main tool metrics collector
 /// Persistent storage structure for tool metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -595,6 +608,20 @@ impl ToolMetricsCollector {
         history.iter().rev().take(limit).cloned().collect()
     }
     
+    /// Get the usage figures of every tool that has been executed
+    pub async fn get_usage(&self) -> HashMap<String, ToolUsage> {
+        let metrics = self.tool_metrics.read().await;
+        metrics.iter()
+            .filter(|(_, m)| m.total_executions > 0)
+            .map(|(name, m)| (name.clone(), ToolUsage {
+                total_executions: m.total_executions,
+                success_rate: m.success_rate,
+                avg_execution_time_ms: m.avg_execution_time_ms,
+                last_execution: m.last_execution,
+            }))
+            .collect()
+    }
+    
     /// Get top performing tools by various metrics
     pub async fn get_top_tools(&self, metric: &str, limit: usize) -> Vec<(String, f64)> {
         let metrics = self.tool_metrics.read().await;
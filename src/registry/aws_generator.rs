@@ -0,0 +1,627 @@
+//! AWS Capability Generator
+//!
+//! This module turns botocore service models (the JSON files under
+//! `botocore/data/<service>/<api-version>/service-2.json`, which are generated
+//! from the services' Smithy models) into MCP tool definitions. Each selected
+//! operation becomes an HTTP tool whose routing config signs requests with AWS
+//! Signature Version 4, so curated AWS actions can be exposed without writing
+//! any glue code.
+//!
+//! Services using the `json` and `rest-json` protocols (DynamoDB, Lambda,
+//! Kinesis, SQS, Secrets Manager, CloudWatch Logs, ECS, Step Functions, ...) are
+//! supported. The XML-based `query`, `ec2` and `rest-xml` protocols are not.
+
+use crate::error::{ProxyError, Result};
+use crate::registry::types::{CapabilityFile, FileMetadata, ToolDefinition, RoutingConfig};
+use crate::routing::aws_sigv4::AwsSigV4Config;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Operation name prefixes treated as read-only
+const READ_ONLY_PREFIXES: &[&str] = &[
+    "Describe", "Get", "List", "Query", "Scan", "BatchGet", "Search", "Lookup", "Check", "Validate",
+];
+
+/// Maximum nesting depth expanded when converting shapes to JSON Schema
+const MAX_SHAPE_DEPTH: usize = 12;
+
+/// Configuration for the AWS capability generator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AwsGeneratorConfig {
+    /// Region the generated tools call and sign for
+    pub region: String,
+    /// Endpoint override (e.g. a VPC endpoint or LocalStack); defaults to the regional endpoint
+    pub endpoint_url: Option<String>,
+    /// Only generate tools for these operations (exact operation names)
+    pub include_operations: Option<Vec<String>>,
+    /// Only generate tools for read-only operations (Describe*, Get*, List*, ...)
+    pub read_only: bool,
+    /// Tool name prefix (defaults to the service's endpoint prefix)
+    pub tool_prefix: Option<String>,
+}
+
+impl Default for AwsGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            region: "us-east-1".to_string(),
+            endpoint_url: None,
+            include_operations: None,
+            read_only: false,
+            tool_prefix: None,
+        }
+    }
+}
+
+/// Wire protocols the generator can express as HTTP routing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    Json,
+    RestJson,
+}
+
+/// AWS capability generator
+pub struct AwsCapabilityGenerator {
+    /// Generator configuration
+    pub config: AwsGeneratorConfig,
+}
+
+impl AwsCapabilityGenerator {
+    /// Create a new AWS capability generator
+    pub fn new(config: AwsGeneratorConfig) -> Self {
+        Self { config }
+    }
+
+    /// Generate a capability file from a botocore service model
+    pub fn generate_from_model(&self, content: &str) -> Result<CapabilityFile> {
+        let model: Value = serde_json::from_str(content)
+            .map_err(|e| ProxyError::config(format!("Invalid botocore model: {}", e)))?;
+        let metadata = model.get("metadata").and_then(Value::as_object)
+            .ok_or_else(|| ProxyError::config("Invalid botocore model: missing metadata".to_string()))?;
+        let operations = model.get("operations").and_then(Value::as_object)
+            .ok_or_else(|| ProxyError::config("Invalid botocore model: missing operations".to_string()))?;
+        let shapes = model.get("shapes").and_then(Value::as_object).cloned().unwrap_or_default();
+
+        let meta_str = |key: &str| metadata.get(key).and_then(Value::as_str);
+        let endpoint_prefix = meta_str("endpointPrefix")
+            .ok_or_else(|| ProxyError::config("Invalid botocore model: missing metadata.endpointPrefix".to_string()))?;
+        let service_id = meta_str("serviceId").unwrap_or(endpoint_prefix);
+        let protocol = parse_protocol(metadata)?;
+
+        if let Some(requested) = &self.config.include_operations {
+            let missing: Vec<&str> = requested.iter()
+                .filter(|name| !operations.contains_key(name.as_str()))
+                .map(String::as_str)
+                .collect();
+            if !missing.is_empty() {
+                return Err(ProxyError::config(format!(
+                    "Operations not found in the {} model: {}", service_id, missing.join(", ")
+                )));
+            }
+        }
+
+        let service = ServiceContext {
+            protocol,
+            endpoint: self.endpoint(endpoint_prefix),
+            sigv4: AwsSigV4Config::new(meta_str("signingName").unwrap_or(endpoint_prefix), self.config.region.clone()),
+            service_id: service_id.to_string(),
+            tag: endpoint_prefix.to_string(),
+            target_prefix: meta_str("targetPrefix").map(String::from),
+            json_version: meta_str("jsonVersion").unwrap_or("1.0").to_string(),
+            shapes,
+        };
+
+        let mut tools = Vec::new();
+        for (name, operation) in operations {
+            if !self.include_operation(name) {
+                continue;
+            }
+            tools.push(self.tool(&service, name, operation)?);
+        }
+
+        let api_version = meta_str("apiVersion").unwrap_or("unknown");
+        let file_metadata = FileMetadata::with_name(format!("aws-{}", endpoint_prefix))
+            .description(format!(
+                "Auto-generated from the {} botocore model (API version {})", service_id, api_version
+            ))
+            .version("1.0.0".to_string())
+            .author("AWS Capability Generator".to_string())
+            .tags(vec!["aws".to_string(), endpoint_prefix.to_string()]);
+
+        CapabilityFile::with_metadata(file_metadata, tools)
+    }
+
+    fn endpoint(&self, endpoint_prefix: &str) -> String {
+        match &self.config.endpoint_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => {
+                let suffix = if self.config.region.starts_with("cn-") { "amazonaws.com.cn" } else { "amazonaws.com" };
+                format!("https://{}.{}.{}", endpoint_prefix, self.config.region, suffix)
+            }
+        }
+    }
+
+    fn include_operation(&self, name: &str) -> bool {
+        if let Some(operations) = &self.config.include_operations {
+            if !operations.iter().any(|op| op == name) {
+                return false;
+            }
+        }
+        !self.config.read_only || is_read_only(name)
+    }
+
+    fn tool(&self, service: &ServiceContext, name: &str, operation: &Value) -> Result<ToolDefinition> {
+        let input = operation.pointer("/input/shape").and_then(Value::as_str)
+            .and_then(|shape| service.shapes.get(shape));
+
+        let mut input_schema = match input {
+            Some(shape) => service.schema_for(shape, 0),
+            None => json!({"type": "object", "properties": {}}),
+        };
+        if input_schema.get("required").is_none() {
+            input_schema["required"] = json!([]);
+        }
+
+        let mut config = Map::new();
+        match service.protocol {
+            Protocol::Json => {
+                let target_prefix = service.target_prefix.as_deref()
+                    .ok_or_else(|| ProxyError::config("Invalid botocore model: json protocol without metadata.targetPrefix".to_string()))?;
+                config.insert("method".to_string(), json!("POST"));
+                config.insert("url".to_string(), json!(format!("{}/", service.endpoint)));
+                config.insert("headers".to_string(), json!({
+                    "Content-Type": format!("application/x-amz-json-{}", service.json_version),
+                    "X-Amz-Target": format!("{}.{}", target_prefix, name)
+                }));
+            }
+            Protocol::RestJson => {
+                let method = operation.pointer("/http/method").and_then(Value::as_str).unwrap_or("POST");
+                let request_uri = operation.pointer("/http/requestUri").and_then(Value::as_str).unwrap_or("/");
+                let bindings = input.map(http_bindings).unwrap_or_default();
+
+                config.insert("method".to_string(), json!(method));
+                config.insert("url".to_string(), json!(format!(
+                    "{}{}", service.endpoint, uri_template(request_uri, &bindings.path)
+                )));
+                // Greedy labels (`{Key+}`) span several segments, so they keep their slashes
+                let mut path_params: Vec<&String> = bindings.path.iter()
+                    .filter(|(label, _)| !request_uri.contains(&format!("{{{}+}}", label)))
+                    .map(|(_, member)| member)
+                    .collect();
+                path_params.sort();
+                if !path_params.is_empty() {
+                    config.insert("path_params".to_string(), json!(path_params));
+                }
+                if !bindings.query.is_empty() {
+                    let query: Map<String, Value> = bindings.query.iter()
+                        .map(|(name, member)| (name.clone(), json!(member)))
+                        .collect();
+                    config.insert("query_params".to_string(), Value::Object(query));
+                }
+                config.insert("body_params".to_string(), json!(bindings.body));
+                let mut headers = Map::new();
+                headers.insert("Content-Type".to_string(), json!("application/json"));
+                for (header, member) in &bindings.headers {
+                    headers.insert(header.clone(), json!(format!("{{{}}}", member)));
+                }
+                config.insert("headers".to_string(), Value::Object(headers));
+            }
+        }
+        config.insert("aws_sigv4".to_string(), serde_json::to_value(&service.sigv4)?);
+
+        let mut annotations = HashMap::new();
+        annotations.insert("source".to_string(), "aws".to_string());
+        annotations.insert("aws_service".to_string(), service.service_id.clone());
+        annotations.insert("aws_operation".to_string(), name.to_string());
+
+        let mut tags = vec!["aws".to_string(), service.tag.clone()];
+        if is_read_only(name) {
+            tags.push("read-only".to_string());
+        }
+
+        let prefix = self.config.tool_prefix.as_deref().unwrap_or(&service.tag);
+        let tool_name = format!("{}_{}", sanitize(prefix), snake_case(name));
+        let documentation = operation.get("documentation").and_then(Value::as_str).map(summarize_documentation);
+
+        Ok(ToolDefinition {
+            name: tool_name,
+            description: match documentation {
+                Some(doc) if !doc.is_empty() => doc,
+                _ => format!("Call the {} {} operation", service.service_id, name),
+            },
+            input_schema,
+            routing: RoutingConfig::new("http".to_string(), Value::Object(config)),
+            annotations: Some(annotations),
+            hidden: true, // Generated tools are hidden by default (consistent with other generators)
+            enabled: true,
+            deprecated: operation.get("deprecated").and_then(Value::as_bool).unwrap_or(false),
+            deprecation_reason: operation.get("deprecatedMessage").and_then(Value::as_str).map(String::from),
+            sunset_date: None,
+            tags,
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
+        })
+    }
+}
+
+/// Service-wide values shared by every generated tool
+struct ServiceContext {
+    protocol: Protocol,
+    endpoint: String,
+    sigv4: AwsSigV4Config,
+    service_id: String,
+    tag: String,
+    target_prefix: Option<String>,
+    json_version: String,
+    shapes: Map<String, Value>,
+}
+
+impl ServiceContext {
+    /// Convert a botocore shape to JSON Schema
+    fn schema_for(&self, shape: &Value, depth: usize) -> Value {
+        let mut schema = match shape.get("type").and_then(Value::as_str).unwrap_or("string") {
+            "structure" if depth < MAX_SHAPE_DEPTH => {
+                let mut properties = Map::new();
+                for (name, member) in shape.get("members").and_then(Value::as_object).into_iter().flatten() {
+                    let mut member_schema = self.member_schema(member, depth);
+                    if let Some(doc) = member.get("documentation").and_then(Value::as_str).map(summarize_documentation) {
+                        if !doc.is_empty() {
+                            member_schema["description"] = json!(doc);
+                        }
+                    }
+                    properties.insert(name.clone(), member_schema);
+                }
+                let mut schema = json!({"type": "object", "properties": properties});
+                if let Some(required) = shape.get("required").filter(|r| r.as_array().is_some_and(|r| !r.is_empty())) {
+                    schema["required"] = required.clone();
+                }
+                schema
+            }
+            // Recursive shapes (e.g. DynamoDB AttributeValue) stop expanding here
+            "structure" => json!({"type": "object"}),
+            "list" => {
+                let items = shape.get("member")
+                    .map(|member| self.member_schema(member, depth))
+                    .unwrap_or_else(|| json!({}));
+                json!({"type": "array", "items": items})
+            }
+            "map" => {
+                let values = shape.get("value")
+                    .map(|value| self.member_schema(value, depth))
+                    .unwrap_or_else(|| json!({}));
+                json!({"type": "object", "additionalProperties": values})
+            }
+            "integer" | "long" => json!({"type": "integer"}),
+            "float" | "double" => json!({"type": "number"}),
+            "boolean" => json!({"type": "boolean"}),
+            "timestamp" => json!({"type": "number", "description": "Unix epoch seconds"}),
+            "blob" => json!({"type": "string", "contentEncoding": "base64"}),
+            _ => {
+                let mut schema = json!({"type": "string"});
+                if let Some(values) = shape.get("enum") {
+                    schema["enum"] = values.clone();
+                }
+                if let Some(pattern) = shape.get("pattern") {
+                    schema["pattern"] = pattern.clone();
+                }
+                schema
+            }
+        };
+
+        let (min_key, max_key) = match schema["type"].as_str() {
+            Some("string") => ("minLength", "maxLength"),
+            Some("array") => ("minItems", "maxItems"),
+            Some("integer") | Some("number") => ("minimum", "maximum"),
+            _ => return schema,
+        };
+        if let Some(min) = shape.get("min") {
+            schema[min_key] = min.clone();
+        }
+        if let Some(max) = shape.get("max") {
+            schema[max_key] = max.clone();
+        }
+        schema
+    }
+
+    fn member_schema(&self, member: &Value, depth: usize) -> Value {
+        member.get("shape").and_then(Value::as_str)
+            .and_then(|name| self.shapes.get(name))
+            .map(|shape| self.schema_for(shape, depth + 1))
+            .unwrap_or_else(|| json!({}))
+    }
+}
+
+/// Where rest-json input members are placed in the HTTP request
+#[derive(Debug, Default)]
+struct HttpBindings {
+    /// URI label -> member name
+    path: HashMap<String, String>,
+    /// Query parameter name -> member name
+    query: Vec<(String, String)>,
+    /// Header name -> member name (required members only)
+    headers: Vec<(String, String)>,
+    /// Member names sent in the JSON body
+    body: Vec<String>,
+}
+
+fn http_bindings(shape: &Value) -> HttpBindings {
+    let mut bindings = HttpBindings::default();
+    let required: Vec<&str> = shape.get("required").and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    for (name, member) in shape.get("members").and_then(Value::as_object).into_iter().flatten() {
+        let location_name = member.get("locationName").and_then(Value::as_str).unwrap_or(name).to_string();
+        match member.get("location").and_then(Value::as_str) {
+            Some("uri") => {
+                bindings.path.insert(location_name, name.clone());
+            }
+            Some("querystring") => bindings.query.push((location_name, name.clone())),
+            // Optional headers would be sent with an unsubstituted placeholder
+            Some("header") if required.contains(&name.as_str()) => bindings.headers.push((location_name, name.clone())),
+            None => bindings.body.push(name.clone()),
+            _ => {}
+        }
+    }
+    bindings.query.sort();
+    bindings.body.sort();
+    bindings
+}
+
+/// Rewrite `{Label}` / `{Label+}` URI labels to `{member}` placeholders
+fn uri_template(request_uri: &str, path: &HashMap<String, String>) -> String {
+    let mut template = String::with_capacity(request_uri.len());
+    let mut rest = request_uri;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else { break };
+        template.push_str(&rest[..start]);
+        let label = rest[start + 1..start + end].trim_end_matches('+');
+        let member = path.get(label).map(String::as_str).unwrap_or(label);
+        template.push_str(&format!("{{{}}}", member));
+        rest = &rest[start + end + 1..];
+    }
+    template.push_str(rest);
+    template
+}
+
+fn parse_protocol(metadata: &Map<String, Value>) -> Result<Protocol> {
+    // Newer models list every supported protocol in order of preference
+    let candidates: Vec<&str> = match metadata.get("protocols").and_then(Value::as_array) {
+        Some(protocols) => protocols.iter().filter_map(Value::as_str).collect(),
+        None => metadata.get("protocol").and_then(Value::as_str).into_iter().collect(),
+    };
+    candidates.iter()
+        .find_map(|p| match *p {
+            "json" => Some(Protocol::Json),
+            "rest-json" => Some(Protocol::RestJson),
+            _ => None,
+        })
+        .ok_or_else(|| ProxyError::config(format!(
+            "Unsupported AWS protocol '{}': only json and rest-json services can be generated",
+            candidates.join(", ")
+        )))
+}
+
+fn is_read_only(operation: &str) -> bool {
+    READ_ONLY_PREFIXES.iter().any(|prefix| {
+        operation.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with(|c: char| c.is_ascii_uppercase()))
+    })
+}
+
+/// `DescribeDBInstances` -> `describe_db_instances`
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::with_capacity(name.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if prev.is_ascii_lowercase() || prev.is_ascii_digit() || (prev.is_ascii_uppercase() && next_is_lower) {
+                result.push('_');
+            }
+        }
+        result.push(c.to_ascii_lowercase());
+    }
+    result
+}
+
+fn sanitize(prefix: &str) -> String {
+    prefix.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' }).collect()
+}
+
+/// Strip the HTML markup from botocore documentation and keep the first paragraph
+fn summarize_documentation(documentation: &str) -> String {
+    let first_paragraph = documentation.split("</p>").next().unwrap_or(documentation);
+    let mut text = String::with_capacity(first_paragraph.len());
+    let mut in_tag = false;
+    for c in first_paragraph.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DYNAMODB: &str = r#"{
+        "version": "2.0",
+        "metadata": {
+            "apiVersion": "2012-08-10",
+            "endpointPrefix": "dynamodb",
+            "jsonVersion": "1.0",
+            "protocol": "json",
+            "serviceId": "DynamoDB",
+            "signatureVersion": "v4",
+            "targetPrefix": "DynamoDB_20120810"
+        },
+        "operations": {
+            "GetItem": {
+                "name": "GetItem",
+                "http": {"method": "POST", "requestUri": "/"},
+                "input": {"shape": "GetItemInput"},
+                "documentation": "<p>The <code>GetItem</code> operation returns a set of attributes for the item with the given primary key.</p> <p>More details.</p>"
+            },
+            "DeleteTable": {
+                "name": "DeleteTable",
+                "http": {"method": "POST", "requestUri": "/"},
+                "input": {"shape": "DeleteTableInput"}
+            }
+        },
+        "shapes": {
+            "GetItemInput": {
+                "type": "structure",
+                "required": ["TableName", "Key"],
+                "members": {
+                    "TableName": {"shape": "TableName", "documentation": "<p>The name of the table.</p>"},
+                    "Key": {"shape": "Key"},
+                    "ConsistentRead": {"shape": "Boolean"}
+                }
+            },
+            "DeleteTableInput": {
+                "type": "structure",
+                "required": ["TableName"],
+                "members": {"TableName": {"shape": "TableName"}}
+            },
+            "TableName": {"type": "string", "max": 255, "min": 3, "pattern": "[a-zA-Z0-9_.-]+"},
+            "Key": {"type": "map", "key": {"shape": "String"}, "value": {"shape": "AttributeValue"}},
+            "AttributeValue": {
+                "type": "structure",
+                "members": {
+                    "S": {"shape": "String"},
+                    "M": {"shape": "MapAttributeValue"}
+                }
+            },
+            "MapAttributeValue": {"type": "map", "key": {"shape": "String"}, "value": {"shape": "AttributeValue"}},
+            "String": {"type": "string"},
+            "Boolean": {"type": "boolean"}
+        }
+    }"#;
+
+    const LAMBDA: &str = r#"{
+        "metadata": {
+            "apiVersion": "2015-03-31",
+            "endpointPrefix": "lambda",
+            "protocol": "rest-json",
+            "serviceId": "Lambda"
+        },
+        "operations": {
+            "Invoke": {
+                "name": "Invoke",
+                "http": {"method": "POST", "requestUri": "/2015-03-31/functions/{FunctionName}/invocations"},
+                "input": {"shape": "InvocationRequest"},
+                "deprecated": true,
+                "deprecatedMessage": "Use InvokeWithResponseStream"
+            }
+        },
+        "shapes": {
+            "InvocationRequest": {
+                "type": "structure",
+                "required": ["FunctionName"],
+                "members": {
+                    "FunctionName": {"shape": "String", "location": "uri", "locationName": "FunctionName"},
+                    "InvocationType": {"shape": "String", "location": "header", "locationName": "X-Amz-Invocation-Type"},
+                    "Qualifier": {"shape": "String", "location": "querystring", "locationName": "Qualifier"},
+                    "Payload": {"shape": "Blob"}
+                }
+            },
+            "String": {"type": "string"},
+            "Blob": {"type": "blob"}
+        }
+    }"#;
+
+    fn tool<'a>(file: &'a CapabilityFile, name: &str) -> &'a ToolDefinition {
+        file.tools.iter().find(|t| t.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_json_protocol_operations() {
+        let generator = AwsCapabilityGenerator::new(AwsGeneratorConfig {
+            region: "eu-west-1".to_string(),
+            ..Default::default()
+        });
+        let file = generator.generate_from_model(DYNAMODB).unwrap();
+        assert_eq!(file.tools.len(), 2);
+
+        let get_item = tool(&file, "dynamodb_get_item");
+        assert_eq!(get_item.description, "The GetItem operation returns a set of attributes for the item with the given primary key.");
+        let config = &get_item.routing.config;
+        assert_eq!(config["url"], "https://dynamodb.eu-west-1.amazonaws.com/");
+        assert_eq!(config["headers"]["X-Amz-Target"], "DynamoDB_20120810.GetItem");
+        assert_eq!(config["headers"]["Content-Type"], "application/x-amz-json-1.0");
+        assert_eq!(config["aws_sigv4"]["service"], "dynamodb");
+        assert_eq!(config["aws_sigv4"]["region"], "eu-west-1");
+        assert!(get_item.tags.contains(&"read-only".to_string()));
+
+        let schema = &get_item.input_schema;
+        assert_eq!(schema["required"], json!(["TableName", "Key"]));
+        assert_eq!(schema["properties"]["TableName"]["maxLength"], 255);
+        assert_eq!(schema["properties"]["TableName"]["description"], "The name of the table.");
+        assert_eq!(schema["properties"]["ConsistentRead"]["type"], "boolean");
+        // Recursive shapes (AttributeValue -> map -> AttributeValue) still terminate
+        assert_eq!(schema["properties"]["Key"]["additionalProperties"]["type"], "object");
+    }
+
+    #[test]
+    fn test_operation_selection() {
+        let generator = AwsCapabilityGenerator::new(AwsGeneratorConfig {
+            read_only: true,
+            tool_prefix: Some("ddb".to_string()),
+            ..Default::default()
+        });
+        let file = generator.generate_from_model(DYNAMODB).unwrap();
+        let names: Vec<&str> = file.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["ddb_get_item"]);
+
+        let generator = AwsCapabilityGenerator::new(AwsGeneratorConfig {
+            include_operations: Some(vec!["PutItem".to_string()]),
+            ..Default::default()
+        });
+        assert!(generator.generate_from_model(DYNAMODB).is_err());
+    }
+
+    #[test]
+    fn test_rest_json_bindings() {
+        let generator = AwsCapabilityGenerator::new(AwsGeneratorConfig {
+            endpoint_url: Some("http://localhost:4566/".to_string()),
+            ..Default::default()
+        });
+        let file = generator.generate_from_model(LAMBDA).unwrap();
+        let invoke = tool(&file, "lambda_invoke");
+
+        let config = &invoke.routing.config;
+        assert_eq!(config["method"], "POST");
+        assert_eq!(config["url"], "http://localhost:4566/2015-03-31/functions/{FunctionName}/invocations");
+        assert_eq!(config["path_params"], json!(["FunctionName"]));
+        assert_eq!(config["query_params"], json!({"Qualifier": "Qualifier"}));
+        // Header members are not sent in the body
+        assert_eq!(config["body_params"], json!(["Payload"]));
+        // Optional header members are not templated into the headers
+        assert!(config["headers"].get("X-Amz-Invocation-Type").is_none());
+        assert_eq!(invoke.input_schema["properties"]["Payload"]["contentEncoding"], "base64");
+        assert!(invoke.deprecated);
+        assert_eq!(invoke.deprecation_reason.as_deref(), Some("Use InvokeWithResponseStream"));
+    }
+
+    #[test]
+    fn test_unsupported_protocol() {
+        let model = r#"{"metadata": {"endpointPrefix": "sts", "protocol": "query"}, "operations": {}}"#;
+        let generator = AwsCapabilityGenerator::new(AwsGeneratorConfig::default());
+        assert!(generator.generate_from_model(model).is_err());
+    }
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(snake_case("DescribeDBInstances"), "describe_db_instances");
+        assert_eq!(snake_case("GetItem"), "get_item");
+        assert_eq!(snake_case("ListTagsForResource"), "list_tags_for_resource");
+        assert_eq!(snake_case("PutObjectV2"), "put_object_v2");
+    }
+}
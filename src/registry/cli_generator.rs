@@ -0,0 +1,751 @@
+//! CLI Capability Generator
+//!
+//! This module generates MCP tool definitions for existing command-line tools. It runs
+//! `<binary> [subcommand] --help` (falling back to the man page), parses the usage line,
+//! options and arguments, and emits subprocess-routed tools whose flags are passed only
+//! when the corresponding parameter is supplied.
+//!
+//! Both clap/argparse style (`Usage:`, `Options:`, `Arguments:`, `Commands:`) and GNU/man
+//! style help (`-a, --all` with the description on the same or the next line) are understood.
+
+use crate::error::{ProxyError, Result};
+use crate::registry::types::{CapabilityFile, FileMetadata, ToolDefinition, RoutingConfig};
+use crate::routing::types::OptionalArg;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+/// Time allowed for `--help` / `man` to produce output
+const HELP_TIMEOUT_SECS: u64 = 10;
+
+/// Flags that are never exposed as tool parameters
+const SKIPPED_FLAGS: &[&str] = &["help", "version", "h", "V"];
+
+/// Value names that are mapped to integer parameters
+const INTEGER_VALUE_NAMES: &[&str] = &[
+    "N", "NUM", "NUMBER", "COUNT", "INT", "INTEGER", "SECONDS", "SECS", "PORT", "DEPTH", "LINES", "SIZE", "JOBS",
+];
+
+/// Configuration for the CLI capability generator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CliGeneratorConfig {
+    /// Binary to describe (name on `PATH` or a path)
+    pub binary: String,
+    /// Subcommands to generate tools for, e.g. `["status", "log"]`.
+    /// When empty, one tool is generated for the binary and, if `discover_subcommands`
+    /// is set, one per subcommand listed in its help.
+    pub subcommands: Vec<String>,
+    /// Generate tools for subcommands listed in the top-level help
+    pub discover_subcommands: bool,
+    /// Fall back to `man <binary>` when `--help` yields no options
+    pub use_man_page: bool,
+    /// Tool name prefix (defaults to the binary name)
+    pub tool_prefix: Option<String>,
+    /// Execution timeout in seconds for generated tools
+    pub timeout: Option<u64>,
+}
+
+impl Default for CliGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            binary: String::new(),
+            subcommands: Vec::new(),
+            discover_subcommands: false,
+            use_man_page: true,
+            tool_prefix: None,
+            timeout: None,
+        }
+    }
+}
+
+/// A parsed command-line option
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CliOption {
+    /// Long flag without dashes, e.g. `output`
+    pub long: Option<String>,
+    /// Short flag without the dash, e.g. `o`
+    pub short: Option<String>,
+    /// Value placeholder, e.g. `FILE`; `None` for boolean switches
+    pub value_name: Option<String>,
+    /// Whether the value is attached with `=` (`--color[=WHEN]`)
+    pub equals: bool,
+    /// Option description
+    pub description: String,
+    /// Default from `[default: ..]`
+    pub default: Option<String>,
+    /// Allowed values from `[possible values: ..]`
+    pub possible_values: Vec<String>,
+}
+
+/// A parsed positional argument
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CliArgument {
+    /// Argument name as shown in the usage line
+    pub name: String,
+    /// Whether the argument is required
+    pub required: bool,
+    /// Whether the argument accepts multiple values (`FILE...`)
+    pub variadic: bool,
+    /// Argument description
+    pub description: String,
+}
+
+/// Parsed `--help` output
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CliHelp {
+    /// Short description of the command
+    pub description: String,
+    /// Options in help order
+    pub options: Vec<CliOption>,
+    /// Positional arguments in usage order
+    pub arguments: Vec<CliArgument>,
+    /// Subcommands as `(name, description)`
+    pub subcommands: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    None,
+    Options,
+    Arguments,
+    Commands,
+    Other,
+}
+
+impl CliHelp {
+    /// Parse help or man page text
+    pub fn parse(text: &str) -> Self {
+        let lines: Vec<&str> = text.lines().map(|l| l.trim_end()).collect();
+        let mut help = CliHelp::default();
+        let mut section = Section::None;
+        let mut usage = String::new();
+        let mut argument_descriptions: Vec<(String, String)> = Vec::new();
+
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            let trimmed = line.trim_start();
+            let indented = line.len() > trimmed.len();
+            i += 1;
+
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let lower = trimmed.to_lowercase();
+            if lower.starts_with("usage:") {
+                usage = trimmed[6..].trim().to_string();
+                // Multi-line usage (clap wraps long usage lines)
+                while i < lines.len() && lines[i].starts_with(' ') && !lines[i].trim().is_empty() && usage.len() < 400 {
+                    if lines[i].trim_start().starts_with('-') {
+                        break;
+                    }
+                    usage.push(' ');
+                    usage.push_str(lines[i].trim());
+                    i += 1;
+                }
+                section = Section::Other;
+                continue;
+            }
+
+            if !indented {
+                if let Some(found) = section_header(trimmed) {
+                    section = found;
+                    continue;
+                }
+                if usage.is_empty() && help.description.is_empty() && section == Section::None {
+                    help.description = trimmed.to_string();
+                    continue;
+                }
+                if section == Section::Other && help.description.is_empty() && !usage.is_empty() {
+                    help.description = trimmed.to_string();
+                    continue;
+                }
+            }
+
+            if trimmed.starts_with('-') && section != Section::Commands {
+                let (spec, mut description) = split_columns(trimmed);
+                let indent = line.len() - trimmed.len();
+                if !description.is_empty() {
+                    // Wrapped description lines are indented past the flag column
+                    while i < lines.len() {
+                        let next = lines[i];
+                        let next_trimmed = next.trim_start();
+                        if next_trimmed.is_empty() || next_trimmed.starts_with('-') || next.len() - next_trimmed.len() <= indent + 4 {
+                            break;
+                        }
+                        description.push(' ');
+                        description.push_str(next_trimmed);
+                        i += 1;
+                    }
+                } else {
+                    // Man page style: description on the following, deeper indented lines
+                    while i < lines.len() {
+                        let next = lines[i].trim();
+                        if next.is_empty() || next.starts_with('-') {
+                            break;
+                        }
+                        if !description.is_empty() {
+                            description.push(' ');
+                        }
+                        description.push_str(next);
+                        i += 1;
+                        // A sentence is enough for a parameter description
+                        if description.ends_with('.') {
+                            break;
+                        }
+                    }
+                }
+                if let Some(option) = parse_option(&spec, &description) {
+                    help.options.push(option);
+                }
+                continue;
+            }
+
+            match section {
+                Section::Arguments => {
+                    let (name, description) = split_columns(trimmed);
+                    let name = clean_argument_name(&name);
+                    if !name.is_empty() {
+                        argument_descriptions.push((name, description));
+                    }
+                }
+                Section::Commands => {
+                    let (name, description) = split_columns(trimmed);
+                    let name = name.split([',', ' ']).next().unwrap_or("").to_string();
+                    if is_subcommand_name(&name) && name != "help" {
+                        help.subcommands.push((name, description));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        help.arguments = parse_usage_arguments(&usage, !help.subcommands.is_empty());
+        for (name, description) in argument_descriptions {
+            match help.arguments.iter_mut().find(|a| a.name.eq_ignore_ascii_case(&name)) {
+                Some(argument) => argument.description = description,
+                None => help.arguments.push(CliArgument { name, required: false, variadic: false, description }),
+            }
+        }
+
+        help
+    }
+}
+
+/// CLI capability generator
+pub struct CliCapabilityGenerator {
+    /// Generator configuration
+    pub config: CliGeneratorConfig,
+}
+
+impl CliCapabilityGenerator {
+    /// Create a new CLI capability generator
+    pub fn new(config: CliGeneratorConfig) -> Self {
+        Self { config }
+    }
+
+    /// Run the binary's help and generate a capability file
+    pub async fn generate(&self) -> Result<CapabilityFile> {
+        let mut commands: Vec<(Vec<String>, CliHelp)> = Vec::new();
+
+        if self.config.subcommands.is_empty() {
+            let help = self.read_help(&[]).await?;
+            let discovered: Vec<String> = if self.config.discover_subcommands {
+                help.subcommands.iter().map(|(name, _)| name.clone()).collect()
+            } else {
+                Vec::new()
+            };
+            commands.push((Vec::new(), help));
+            for subcommand in discovered {
+                match self.read_help(std::slice::from_ref(&subcommand)).await {
+                    Ok(help) => commands.push((vec![subcommand], help)),
+                    Err(e) => tracing::warn!("Skipping subcommand '{}': {}", subcommand, e),
+                }
+            }
+        } else {
+            for subcommand in &self.config.subcommands {
+                let path: Vec<String> = subcommand.split_whitespace().map(String::from).collect();
+                let help = self.read_help(&path).await?;
+                commands.push((path, help));
+            }
+        }
+
+        self.generate_from_help(&commands)
+    }
+
+    /// Generate a capability file from already parsed help, one tool per `(subcommand path, help)`
+    pub fn generate_from_help(&self, commands: &[(Vec<String>, CliHelp)]) -> Result<CapabilityFile> {
+        let tools = commands
+            .iter()
+            .map(|(path, help)| self.tool(path, help))
+            .collect::<Result<Vec<_>>>()?;
+
+        let metadata = FileMetadata::with_name(format!("{}-cli", self.binary_name()))
+            .description(format!("Auto-generated tools for the {} command line", self.binary_name()))
+            .version("1.0.0".to_string())
+            .author("CLI Capability Generator".to_string())
+            .tags(vec!["cli".to_string(), self.binary_name()]);
+
+        CapabilityFile::with_metadata(metadata, tools)
+    }
+
+    /// Capture help output for a subcommand path, falling back to the man page
+    async fn read_help(&self, path: &[String]) -> Result<CliHelp> {
+        let mut args: Vec<String> = path.to_vec();
+        args.push("--help".to_string());
+        let text = run_for_output(&self.config.binary, &args).await.unwrap_or_default();
+        let help = CliHelp::parse(&text);
+        if !help.options.is_empty() || !help.arguments.is_empty() || !help.subcommands.is_empty() {
+            return Ok(help);
+        }
+
+        if self.config.use_man_page {
+            let page = if path.is_empty() {
+                self.binary_name()
+            } else {
+                format!("{}-{}", self.binary_name(), path.join("-"))
+            };
+            let text = run_for_output("man", &["-P".to_string(), "cat".to_string(), page.clone()])
+                .await
+                .unwrap_or_default();
+            let help = CliHelp::parse(&strip_overstrike(&text));
+            if !help.options.is_empty() || !help.arguments.is_empty() {
+                return Ok(help);
+            }
+        }
+
+        Err(ProxyError::config(format!(
+            "Could not parse any options from '{} {} --help'",
+            self.config.binary,
+            path.join(" ")
+        )))
+    }
+
+    fn binary_name(&self) -> String {
+        std::path::Path::new(&self.config.binary)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.config.binary.clone())
+    }
+
+    fn tool(&self, path: &[String], help: &CliHelp) -> Result<ToolDefinition> {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        let mut optional_args = Vec::new();
+
+        for option in &help.options {
+            let Some(param) = option_param_name(option) else { continue };
+            if properties.contains_key(&param) {
+                continue;
+            }
+            let flag = match (&option.long, &option.short) {
+                (Some(long), _) => format!("--{}", long),
+                (None, Some(short)) => format!("-{}", short),
+                (None, None) => continue,
+            };
+            let placeholder = format!("{{{{{}}}}}", param);
+            let args = match (&option.value_name, option.equals) {
+                (None, _) => vec![flag],
+                (Some(_), true) => vec![format!("{}={}", flag, placeholder)],
+                (Some(_), false) => vec![flag, placeholder],
+            };
+            properties.insert(param.clone(), option_schema(option));
+            optional_args.push(OptionalArg { param, args, positional: false });
+        }
+
+        for argument in &help.arguments {
+            let mut param = sanitize_param_name(&argument.name);
+            if properties.contains_key(&param) {
+                param = format!("{}_arg", param);
+            }
+            let description = if argument.description.is_empty() {
+                format!("{} argument", argument.name)
+            } else {
+                argument.description.clone()
+            };
+            let schema = if argument.variadic {
+                json!({"type": "array", "items": {"type": "string"}, "description": description})
+            } else {
+                json!({"type": "string", "description": description})
+            };
+            properties.insert(param.clone(), schema);
+            if argument.required {
+                required.push(param.clone());
+            }
+            optional_args.push(OptionalArg { param: param.clone(), args: vec![format!("{{{{{}}}}}", param)], positional: true });
+        }
+
+        let mut routing = Map::new();
+        routing.insert("command".to_string(), json!(self.config.binary));
+        routing.insert("args".to_string(), json!(path));
+        routing.insert("optional_args".to_string(), serde_json::to_value(&optional_args)?);
+        if let Some(timeout) = self.config.timeout {
+            routing.insert("timeout".to_string(), json!(timeout));
+        }
+
+        let prefix = self.config.tool_prefix.clone().unwrap_or_else(|| self.binary_name());
+        let name = std::iter::once(prefix.as_str())
+            .chain(path.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join("_");
+
+        let command_line = std::iter::once(self.binary_name())
+            .chain(path.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let description = if help.description.is_empty() {
+            format!("Run `{}`", command_line)
+        } else {
+            format!("{} (runs `{}`)", help.description, command_line)
+        };
+
+        let mut annotations = std::collections::HashMap::new();
+        annotations.insert("source".to_string(), "cli-help".to_string());
+        annotations.insert("command".to_string(), command_line);
+
+        Ok(ToolDefinition {
+            name: sanitize_param_name(&name),
+            description,
+            input_schema: json!({
+                "type": "object",
+                "properties": properties,
+                "required": required
+            }),
+            routing: RoutingConfig::new("subprocess".to_string(), Value::Object(routing)),
+            annotations: Some(annotations),
+            hidden: true, // Generated tools are hidden by default (consistent with other generators)
+            enabled: true,
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
+        })
+    }
+}
+
+/// Run a command and return stdout, or stderr when stdout is empty (some tools print help there)
+async fn run_for_output(command: &str, args: &[String]) -> Result<String> {
+    let output = tokio::time::timeout(
+        Duration::from_secs(HELP_TIMEOUT_SECS),
+        tokio::process::Command::new(command)
+            .args(args)
+            .env("COLUMNS", "200")
+            .env("MANWIDTH", "200")
+            .stdin(std::process::Stdio::null())
+            .output(),
+    )
+    .await
+    .map_err(|_| ProxyError::timeout(format!("'{}' did not exit within {}s", command, HELP_TIMEOUT_SECS)))?
+    .map_err(|e| ProxyError::config(format!("Failed to run '{}': {}", command, e)))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if stdout.trim().is_empty() {
+        Ok(String::from_utf8_lossy(&output.stderr).to_string())
+    } else {
+        Ok(stdout)
+    }
+}
+
+/// Remove backspace overstrike formatting (`N\x08N`, `_\x08N`) from man output
+fn strip_overstrike(text: &str) -> String {
+    let mut result: Vec<char> = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\u{8}' {
+            result.pop();
+        } else {
+            result.push(c);
+        }
+    }
+    result.into_iter().collect()
+}
+
+fn section_header(line: &str) -> Option<Section> {
+    let header = line.trim_end_matches(':');
+    let is_header = line.ends_with(':') || (header.len() > 2 && header.chars().all(|c| c.is_ascii_uppercase() || c == ' '));
+    if !is_header || header.split_whitespace().count() > 3 {
+        return None;
+    }
+    let header = header.to_lowercase();
+    Some(if header.contains("option") || header.contains("flag") {
+        Section::Options
+    } else if header.contains("argument") || header == "args" {
+        Section::Arguments
+    } else if header.contains("command") {
+        Section::Commands
+    } else {
+        Section::Other
+    })
+}
+
+/// Split `spec   description` at the first run of two or more spaces (or a tab)
+fn split_columns(line: &str) -> (String, String) {
+    let bytes = line.as_bytes();
+    for i in 0..bytes.len() {
+        if bytes[i] == b'\t' || (bytes[i] == b' ' && bytes.get(i + 1) == Some(&b' ')) {
+            return (line[..i].trim().to_string(), line[i..].trim().to_string());
+        }
+    }
+    (line.trim().to_string(), String::new())
+}
+
+fn parse_option(spec: &str, description: &str) -> Option<CliOption> {
+    let mut option = CliOption {
+        long: None,
+        short: None,
+        value_name: None,
+        equals: false,
+        description: description.to_string(),
+        default: bracketed(description, "default:"),
+        possible_values: bracketed(description, "possible values:")
+            .map(|values| values.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect())
+            .unwrap_or_default(),
+    };
+
+    for token in spec.split([',', ' ']).map(str::trim).filter(|t| !t.is_empty()) {
+        if let Some(long) = token.strip_prefix("--") {
+            let (name, value) = match long.split_once("[=").or_else(|| long.split_once('=')) {
+                Some((name, value)) => {
+                    option.equals = true;
+                    (name, Some(value))
+                }
+                None => (long, None),
+            };
+            option.long = Some(name.to_string());
+            if let Some(value) = value {
+                option.value_name = Some(clean_argument_name(value));
+            }
+        } else if let Some(short) = token.strip_prefix('-') {
+            let mut chars = short.chars();
+            if let Some(c) = chars.next() {
+                option.short = Some(c.to_string());
+                // `-nNUM` / `-n<NUM>`
+                let rest: String = chars.collect();
+                if !rest.is_empty() && option.value_name.is_none() {
+                    option.value_name = Some(clean_argument_name(&rest));
+                }
+            }
+        } else if option.value_name.is_none() {
+            option.value_name = Some(clean_argument_name(token));
+        }
+    }
+
+    if option.long.is_none() && option.short.is_none() {
+        return None;
+    }
+    Some(option)
+}
+
+/// Text inside `[prefix ...]` in a description
+fn bracketed(description: &str, prefix: &str) -> Option<String> {
+    let start = description.find(&format!("[{}", prefix))? + 1 + prefix.len();
+    let end = description[start..].find(']')? + start;
+    Some(description[start..end].trim().to_string())
+}
+
+/// Positional arguments from a usage line such as `tool [OPTIONS] <SRC> [DEST]...`
+fn parse_usage_arguments(usage: &str, has_subcommands: bool) -> Vec<CliArgument> {
+    let mut arguments = Vec::new();
+    // Skip the program name (and subcommand path, which is lowercase)
+    for token in usage.split_whitespace().skip(1) {
+        let lower = token.to_lowercase();
+        let optional = token.starts_with('[');
+        let inner = token.trim_start_matches('[').trim_end_matches("...").trim_end_matches(']').trim_end_matches("...");
+        if inner.starts_with('-')
+            || lower.contains("option")
+            || inner.contains('|')
+            || (has_subcommands && (lower.contains("command") || lower.contains("args")))
+        {
+            continue;
+        }
+        let is_placeholder = inner.starts_with('<') || (!inner.is_empty() && inner.chars().all(|c| c.is_ascii_uppercase() || c == '_' || c == '-'));
+        if !is_placeholder {
+            continue;
+        }
+        let name = clean_argument_name(inner);
+        if name.is_empty() || arguments.iter().any(|a: &CliArgument| a.name == name) {
+            continue;
+        }
+        arguments.push(CliArgument {
+            name,
+            required: !optional,
+            variadic: token.contains("..."),
+            description: String::new(),
+        });
+    }
+    arguments
+}
+
+fn clean_argument_name(name: &str) -> String {
+    name.trim_matches(|c: char| matches!(c, '<' | '>' | '[' | ']' | '=' | '.' | ' '))
+        .to_string()
+}
+
+fn is_subcommand_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+fn option_param_name(option: &CliOption) -> Option<String> {
+    let name = option.long.as_ref().or(option.short.as_ref())?;
+    if SKIPPED_FLAGS.contains(&name.as_str()) {
+        return None;
+    }
+    Some(sanitize_param_name(name))
+}
+
+fn option_schema(option: &CliOption) -> Value {
+    let mut description = option.description.clone();
+    if description.is_empty() {
+        description = format!("{} flag", option.long.as_ref().or(option.short.as_ref()).unwrap_or(&String::new()));
+    }
+
+    let mut schema = match &option.value_name {
+        None => json!({"type": "boolean"}),
+        Some(value_name) if INTEGER_VALUE_NAMES.contains(&value_name.to_uppercase().as_str()) => json!({"type": "integer"}),
+        Some(_) => json!({"type": "string"}),
+    };
+    let obj = schema.as_object_mut().expect("schema is an object");
+    obj.insert("description".to_string(), json!(description));
+    if !option.possible_values.is_empty() {
+        obj.insert("enum".to_string(), json!(option.possible_values));
+    }
+    if let Some(default) = &option.default {
+        let default = match obj.get("type").and_then(Value::as_str) {
+            Some("integer") => default.parse::<i64>().map(Value::from).unwrap_or_else(|_| json!(default)),
+            Some("boolean") => default.parse::<bool>().map(Value::from).unwrap_or_else(|_| json!(default)),
+            _ => json!(default),
+        };
+        obj.insert("default".to_string(), default);
+    }
+    schema
+}
+
+fn sanitize_param_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect::<String>()
+        .trim_matches('_')
+        .to_string();
+    // Flags such as `-1` need an identifier-like parameter name
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("flag_{}", name)
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLAP_HELP: &str = "\
+Search files for a pattern
+
+Usage: finder [OPTIONS] <PATTERN> [PATH]...
+
+Arguments:
+  <PATTERN>  Regular expression to search for
+  [PATH]...  Files or directories to search
+
+Options:
+  -i, --ignore-case          Case insensitive search
+  -m, --max-count <NUM>      Stop after NUM matches
+      --color <WHEN>         When to use colors [default: auto] [possible values: auto, always, never]
+  -h, --help                 Print help
+  -V, --version              Print version
+";
+
+    const GNU_HELP: &str = "\
+Usage: lister [OPTION]... [FILE]...
+List information about the FILEs (the current directory by default).
+
+  -a, --all                  do not ignore entries starting with .
+      --block-size=SIZE      with -l, scale sizes by SIZE when printing them;
+                               see SIZE format below
+  -1                         list one file per line
+      --color[=WHEN]         colorize the output
+  -l                         use a long listing format
+      --help     display this help and exit
+";
+
+    #[test]
+    fn test_parse_clap_help() {
+        let help = CliHelp::parse(CLAP_HELP);
+        assert_eq!(help.description, "Search files for a pattern");
+        assert_eq!(help.options.len(), 5);
+        assert_eq!(help.options[1].long.as_deref(), Some("max-count"));
+        assert_eq!(help.options[1].value_name.as_deref(), Some("NUM"));
+        assert_eq!(help.options[2].default.as_deref(), Some("auto"));
+        assert_eq!(help.options[2].possible_values, vec!["auto", "always", "never"]);
+
+        assert_eq!(help.arguments.len(), 2);
+        assert!(help.arguments[0].required);
+        assert_eq!(help.arguments[0].description, "Regular expression to search for");
+        assert!(!help.arguments[1].required);
+        assert!(help.arguments[1].variadic);
+    }
+
+    #[test]
+    fn test_parse_gnu_and_man_style_help() {
+        let help = CliHelp::parse(GNU_HELP);
+        assert_eq!(help.description, "List information about the FILEs (the current directory by default).");
+        let block_size = help.options.iter().find(|o| o.long.as_deref() == Some("block-size")).unwrap();
+        assert!(block_size.equals);
+        assert_eq!(block_size.value_name.as_deref(), Some("SIZE"));
+        assert_eq!(block_size.description, "with -l, scale sizes by SIZE when printing them; see SIZE format below");
+        assert_eq!(option_param_name(&help.options[2]).as_deref(), Some("flag_1"));
+        assert_eq!(help.arguments[0].name, "FILE");
+
+        let man = "OPTIONS\n       -r, --recursive\n              copy directories recursively\n\n       -t, --target-directory=DIRECTORY\n              copy all SOURCE arguments into DIRECTORY\n";
+        let help = CliHelp::parse(man);
+        assert_eq!(help.options.len(), 2);
+        assert_eq!(help.options[0].description, "copy directories recursively");
+        assert_eq!(help.options[1].value_name.as_deref(), Some("DIRECTORY"));
+    }
+
+    #[test]
+    fn test_parse_subcommands() {
+        let help = CliHelp::parse("Usage: vcs <COMMAND>\n\nCommands:\n  status  Show the working tree status\n  log     Show commit logs\n  help    Print this message\n");
+        assert_eq!(
+            help.subcommands,
+            vec![
+                ("status".to_string(), "Show the working tree status".to_string()),
+                ("log".to_string(), "Show commit logs".to_string())
+            ]
+        );
+        assert!(help.arguments.is_empty());
+    }
+
+    #[test]
+    fn test_generate_subprocess_tool() {
+        let generator = CliCapabilityGenerator::new(CliGeneratorConfig {
+            binary: "/usr/local/bin/finder".to_string(),
+            timeout: Some(20),
+            ..Default::default()
+        });
+        let file = generator.generate_from_help(&[(Vec::new(), CliHelp::parse(CLAP_HELP))]).unwrap();
+        let tool = &file.tools[0];
+
+        assert_eq!(tool.name, "finder");
+        assert_eq!(tool.routing.r#type, "subprocess");
+        assert_eq!(tool.routing.config["command"], "/usr/local/bin/finder");
+        assert_eq!(tool.input_schema["required"], json!(["pattern"]));
+        assert_eq!(tool.input_schema["properties"]["ignore_case"]["type"], "boolean");
+        assert_eq!(tool.input_schema["properties"]["max_count"]["type"], "integer");
+        assert_eq!(tool.input_schema["properties"]["color"]["default"], "auto");
+        assert_eq!(tool.input_schema["properties"]["path"]["type"], "array");
+        assert!(tool.input_schema["properties"].get("help").is_none());
+
+        let optional_args: Vec<OptionalArg> = serde_json::from_value(tool.routing.config["optional_args"].clone()).unwrap();
+        assert_eq!(optional_args[1], OptionalArg { param: "max_count".to_string(), args: vec!["--max-count".to_string(), "{{max_count}}".to_string()], positional: false });
+        assert_eq!(optional_args.last().unwrap().args, vec!["{{path}}"]);
+        assert!(optional_args.last().unwrap().positional);
+    }
+}
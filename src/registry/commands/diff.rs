@@ -0,0 +1,496 @@
+//! Capability Differ
+//!
+//! This module compares two sets of tool definitions, e.g. two capability
+//! directories, a registry snapshot (`.json`) and a directory, or a directory
+//! and the live registry, and reports what changed.
+//!
+//! # Overview
+//!
+//! Tools are matched by name. For each pair the differ compares:
+//!
+//! - **Input schema**: property additions/removals, type changes, required
+//!   fields and enum values
+//! - **Routing**: routing type and routing config values
+//! - **Metadata**: description, annotations, hidden and enabled flags
+//!
+//! # Breaking Changes
+//!
+//! A change is breaking when an existing client call could stop working:
+//!
+//! - A tool was removed
+//! - A property was removed or its type changed
+//! - A property became required (or a new required property was added)
+//! - Allowed enum values were removed
+//! - The routing type changed
+
+use crate::error::{ProxyError, Result};
+use crate::registry::migration::parse_capability_file;
+use crate::registry::snapshot::RegistrySnapshot;
+use crate::registry::types::ToolDefinition;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Category of a single change to a tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Description text changed
+    Description,
+    /// Input schema changed
+    Schema,
+    /// Routing type or config changed
+    Routing,
+    /// Annotations changed
+    Annotations,
+    /// Hidden or enabled flag changed
+    Visibility,
+}
+
+/// A single change to a tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeDetail {
+    /// Change category
+    pub kind: ChangeKind,
+    /// Whether existing callers may break
+    pub breaking: bool,
+    /// Human-readable description of the change
+    pub message: String,
+}
+
+/// All changes to a tool present on both sides
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolChange {
+    /// Tool name
+    pub name: String,
+    /// Individual changes
+    pub changes: Vec<ChangeDetail>,
+}
+
+impl ToolChange {
+    /// Whether any of the changes is breaking
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(|c| c.breaking)
+    }
+}
+
+/// Result of comparing two tool sets
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CapabilityDiff {
+    /// Tools only present in the new set
+    pub added: Vec<String>,
+    /// Tools only present in the old set
+    pub removed: Vec<String>,
+    /// Tools present in both sets with differences
+    pub changed: Vec<ToolChange>,
+}
+
+impl CapabilityDiff {
+    /// Whether the two sets are identical
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Whether the diff contains breaking changes
+    pub fn has_breaking_changes(&self) -> bool {
+        !self.removed.is_empty() || self.changed.iter().any(ToolChange::is_breaking)
+    }
+
+    /// Plain-text report, one line per change
+    pub fn to_text(&self) -> String {
+        if self.is_empty() {
+            return "No changes\n".to_string();
+        }
+
+        let mut out = String::new();
+        for name in &self.added {
+            out.push_str(&format!("+ {}\n", name));
+        }
+        for name in &self.removed {
+            out.push_str(&format!("- {} [BREAKING]\n", name));
+        }
+        for change in &self.changed {
+            out.push_str(&format!("~ {}{}\n", change.name, if change.is_breaking() { " [BREAKING]" } else { "" }));
+            for detail in &change.changes {
+                out.push_str(&format!(
+                    "    {}{}\n",
+                    detail.message,
+                    if detail.breaking { " (breaking)" } else { "" }
+                ));
+            }
+        }
+        out.push_str(&format!(
+            "\n{} added, {} removed, {} changed{}\n",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            if self.has_breaking_changes() { " - contains breaking changes" } else { "" }
+        ));
+        out
+    }
+}
+
+/// Capability Differ
+///
+/// Compares two sets of tool definitions.
+///
+/// # Example
+///
+/// ```no_run
+/// use magictunnel::registry::commands::CapabilityDiffer;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let differ = CapabilityDiffer::new();
+/// let diff = differ.diff_paths("capabilities-v1", "capabilities")?;
+/// print!("{}", diff.to_text());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CapabilityDiffer;
+
+impl CapabilityDiffer {
+    /// Create a new capability differ
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Compare two capability files or directories
+    pub fn diff_paths(&self, old: &str, new: &str) -> Result<CapabilityDiff> {
+        let old_tools = Self::load_tools(old)?;
+        let new_tools = Self::load_tools(new)?;
+        Ok(self.diff(&old_tools, &new_tools))
+    }
+
+    /// Load all tools from a capability file, a directory of capability files
+    /// or a registry snapshot (`.json`)
+    pub fn load_tools(path: &str) -> Result<BTreeMap<String, ToolDefinition>> {
+        let root = Path::new(path);
+        if !root.exists() {
+            return Err(ProxyError::registry(format!("Path does not exist: {}", path)));
+        }
+
+        let mut tools = BTreeMap::new();
+        let files = WalkDir::new(root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| is_capability_file(entry.path()) || (entry.depth() == 0 && is_snapshot(entry.path())));
+
+        for entry in files {
+            let content = std::fs::read_to_string(entry.path())?;
+            add_tools(&mut tools, entry.path(), &content)?;
+        }
+
+        Ok(tools)
+    }
+
+    /// [`Self::load_tools`] with non-blocking file IO
+    pub async fn load_tools_async(path: &Path) -> Result<BTreeMap<String, ToolDefinition>> {
+        let metadata = tokio::fs::metadata(path).await
+            .map_err(|_| ProxyError::registry(format!("Path does not exist: {}", path.display())))?;
+
+        let mut files = Vec::new();
+        if metadata.is_file() {
+            if is_capability_file(path) || is_snapshot(path) {
+                files.push(path.to_path_buf());
+            }
+        } else {
+            let mut dirs = vec![path.to_path_buf()];
+            while let Some(dir) = dirs.pop() {
+                let mut entries = tokio::fs::read_dir(&dir).await?;
+                while let Some(entry) = entries.next_entry().await? {
+                    let file_type = entry.file_type().await?;
+                    if file_type.is_dir() {
+                        dirs.push(entry.path());
+                    } else if file_type.is_file() && is_capability_file(&entry.path()) {
+                        files.push(entry.path());
+                    }
+                }
+            }
+            files.sort();
+        }
+
+        let mut tools = BTreeMap::new();
+        for file in files {
+            let content = tokio::fs::read_to_string(&file).await?;
+            add_tools(&mut tools, &file, &content)?;
+        }
+        Ok(tools)
+    }
+
+    /// Compare two tool sets keyed by name
+    pub fn diff(
+        &self,
+        old: &BTreeMap<String, ToolDefinition>,
+        new: &BTreeMap<String, ToolDefinition>,
+    ) -> CapabilityDiff {
+        let mut diff = CapabilityDiff::default();
+
+        for (name, new_tool) in new {
+            match old.get(name) {
+                None => diff.added.push(name.clone()),
+                Some(old_tool) => {
+                    let changes = self.diff_tool(old_tool, new_tool);
+                    if !changes.is_empty() {
+                        diff.changed.push(ToolChange { name: name.clone(), changes });
+                    }
+                }
+            }
+        }
+        diff.removed = old.keys().filter(|name| !new.contains_key(*name)).cloned().collect();
+
+        diff
+    }
+
+    /// Compare two versions of the same tool
+    pub fn diff_tool(&self, old: &ToolDefinition, new: &ToolDefinition) -> Vec<ChangeDetail> {
+        let mut changes = Vec::new();
+
+        if old.description != new.description {
+            changes.push(ChangeDetail {
+                kind: ChangeKind::Description,
+                breaking: false,
+                message: "description changed".to_string(),
+            });
+        }
+
+        diff_schema(&old.input_schema, &new.input_schema, "", &mut changes);
+
+        if old.routing.r#type != new.routing.r#type {
+            changes.push(ChangeDetail {
+                kind: ChangeKind::Routing,
+                breaking: true,
+                message: format!("routing type changed from '{}' to '{}'", old.routing.r#type, new.routing.r#type),
+            });
+        } else if old.routing.config != new.routing.config {
+            changes.push(ChangeDetail {
+                kind: ChangeKind::Routing,
+                breaking: false,
+                message: format!("routing config changed ({})", changed_keys(&old.routing.config, &new.routing.config).join(", ")),
+            });
+        }
+
+        if old.annotations != new.annotations {
+            changes.push(ChangeDetail {
+                kind: ChangeKind::Annotations,
+                breaking: false,
+                message: "annotations changed".to_string(),
+            });
+        }
+
+        if old.hidden != new.hidden {
+            changes.push(ChangeDetail {
+                kind: ChangeKind::Visibility,
+                breaking: false,
+                message: if new.hidden { "tool is now hidden" } else { "tool is now visible" }.to_string(),
+            });
+        }
+        if old.enabled != new.enabled {
+            changes.push(ChangeDetail {
+                kind: ChangeKind::Visibility,
+                breaking: !new.enabled,
+                message: if new.enabled { "tool was enabled" } else { "tool was disabled" }.to_string(),
+            });
+        }
+
+        changes
+    }
+}
+
+fn is_capability_file(path: &Path) -> bool {
+    matches!(path.extension().and_then(|e| e.to_str()), Some("yaml") | Some("yml"))
+}
+
+fn is_snapshot(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some("json")
+}
+
+/// Add the tools of a capability file, or of every file in a registry snapshot
+fn add_tools(tools: &mut BTreeMap<String, ToolDefinition>, path: &Path, content: &str) -> Result<()> {
+    let files = if is_snapshot(path) {
+        let snapshot: RegistrySnapshot = serde_json::from_str(content)
+            .map_err(|e| ProxyError::registry(format!("Invalid registry snapshot '{}': {}", path.display(), e)))?;
+        snapshot.files.into_iter().map(|file| file.capability_file).collect()
+    } else {
+        vec![parse_capability_file(content, &path.display().to_string())?]
+    };
+    for file in files {
+        for tool in file.tools {
+            tools.insert(tool.name.clone(), tool);
+        }
+    }
+    Ok(())
+}
+
+/// Compare two JSON schemas, recursing into object properties
+fn diff_schema(old: &Value, new: &Value, prefix: &str, changes: &mut Vec<ChangeDetail>) {
+    let label = |name: &str| if prefix.is_empty() { name.to_string() } else { format!("{}.{}", prefix, name) };
+
+    if !prefix.is_empty() && old.get("type") != new.get("type") {
+        push_schema_change(changes, true, format!(
+            "parameter '{}' type changed from {} to {}", prefix, type_name(old), type_name(new)
+        ));
+        return;
+    }
+
+    match (old.get("enum").and_then(Value::as_array), new.get("enum").and_then(Value::as_array)) {
+        (Some(old_enum), Some(new_enum)) => {
+            let removed: Vec<String> = old_enum.iter().filter(|v| !new_enum.contains(v)).map(|v| v.to_string()).collect();
+            if !removed.is_empty() {
+                push_schema_change(changes, true, format!("parameter '{}' no longer accepts {}", prefix, removed.join(", ")));
+            }
+            if new_enum.iter().any(|v| !old_enum.contains(v)) {
+                push_schema_change(changes, false, format!("parameter '{}' accepts new values", prefix));
+            }
+        }
+        (None, Some(_)) if !prefix.is_empty() => {
+            push_schema_change(changes, true, format!("parameter '{}' is now restricted to an enum", prefix));
+        }
+        _ => {}
+    }
+
+    let old_props = properties(old);
+    let new_props = properties(new);
+    let old_required = required(old);
+    let new_required = required(new);
+
+    for name in old_props.keys().filter(|name| !new_props.contains_key(*name)) {
+        push_schema_change(changes, true, format!("parameter '{}' removed", label(name)));
+    }
+    for (name, schema) in &new_props {
+        match old_props.get(name) {
+            None if new_required.contains(name) => {
+                push_schema_change(changes, true, format!("required parameter '{}' added", label(name)));
+            }
+            None => push_schema_change(changes, false, format!("optional parameter '{}' added", label(name))),
+            Some(old_schema) => {
+                if new_required.contains(name) && !old_required.contains(name) {
+                    push_schema_change(changes, true, format!("parameter '{}' is now required", label(name)));
+                } else if old_required.contains(name) && !new_required.contains(name) {
+                    push_schema_change(changes, false, format!("parameter '{}' is now optional", label(name)));
+                }
+                diff_schema(old_schema, schema, &label(name), changes);
+            }
+        }
+    }
+}
+
+fn push_schema_change(changes: &mut Vec<ChangeDetail>, breaking: bool, message: String) {
+    changes.push(ChangeDetail { kind: ChangeKind::Schema, breaking, message });
+}
+
+fn properties(schema: &Value) -> BTreeMap<String, Value> {
+    schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|props| props.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default()
+}
+
+fn required(schema: &Value) -> BTreeSet<String> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn type_name(schema: &Value) -> String {
+    schema.get("type").map(|t| t.to_string()).unwrap_or_else(|| "any".to_string())
+}
+
+/// Top-level config keys whose values differ
+fn changed_keys(old: &Value, new: &Value) -> Vec<String> {
+    match (old.as_object(), new.as_object()) {
+        (Some(old), Some(new)) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            keys.into_iter().filter(|k| old.get(*k) != new.get(*k)).cloned().collect()
+        }
+        _ => vec!["config".to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::types::RoutingConfig;
+    use serde_json::json;
+
+    fn tool(name: &str, schema: Value, routing_type: &str) -> ToolDefinition {
+        ToolDefinition::new_with_fields(
+            name.to_string(),
+            format!("{} tool", name),
+            schema,
+            RoutingConfig::new(routing_type.to_string(), json!({"url": "http://localhost", "method": "GET"})),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn set(tools: Vec<ToolDefinition>) -> BTreeMap<String, ToolDefinition> {
+        tools.into_iter().map(|t| (t.name.clone(), t)).collect()
+    }
+
+    #[test]
+    fn test_added_removed_and_breaking_schema_changes() {
+        let old = set(vec![
+            tool("search", json!({"type": "object", "properties": {"q": {"type": "string"}, "limit": {"type": "integer"}}}), "http"),
+            tool("legacy", json!({"type": "object"}), "http"),
+        ]);
+        let new = set(vec![
+            tool("search", json!({"type": "object", "properties": {"q": {"type": "string"}, "page": {"type": "integer"}}, "required": ["q"]}), "http"),
+            tool("fresh", json!({"type": "object"}), "http"),
+        ]);
+
+        let diff = CapabilityDiffer::new().diff(&old, &new);
+        assert_eq!(diff.added, vec!["fresh"]);
+        assert_eq!(diff.removed, vec!["legacy"]);
+        assert_eq!(diff.changed.len(), 1);
+
+        let messages: Vec<&str> = diff.changed[0].changes.iter().map(|c| c.message.as_str()).collect();
+        assert!(messages.contains(&"parameter 'limit' removed"));
+        assert!(messages.contains(&"optional parameter 'page' added"));
+        assert!(messages.contains(&"parameter 'q' is now required"));
+        assert!(diff.has_breaking_changes());
+    }
+
+    #[test]
+    fn test_non_breaking_changes() {
+        let old = set(vec![tool("t", json!({"type": "object", "properties": {"mode": {"type": "string", "enum": ["a"]}}}), "http")]);
+        let new = set(vec![tool("t", json!({"type": "object", "properties": {"mode": {"type": "string", "enum": ["a", "b"]}}}), "http")]);
+
+        let diff = CapabilityDiffer::new().diff(&old, &new);
+        assert_eq!(diff.changed.len(), 1);
+        assert!(!diff.has_breaking_changes());
+
+        let same = CapabilityDiffer::new().diff(&old, &old);
+        assert!(same.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_loads_snapshots_and_directories() {
+        use crate::registry::types::CapabilityFile;
+
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = RegistrySnapshot::new(vec![(
+            dir.path().join("capabilities/search.yaml"),
+            CapabilityFile::new(vec![tool("search", json!({"type": "object"}), "http")]).unwrap(),
+        )]);
+        let snapshot_path = dir.path().join("snapshot.json");
+        std::fs::write(&snapshot_path, serde_json::to_string(&snapshot).unwrap()).unwrap();
+        snapshot.write_to_dir(&dir.path().join("restored"), false).unwrap();
+
+        let from_snapshot = CapabilityDiffer::load_tools(snapshot_path.to_str().unwrap()).unwrap();
+        assert_eq!(from_snapshot.keys().collect::<Vec<_>>(), vec!["search"]);
+        assert_eq!(CapabilityDiffer::load_tools_async(&snapshot_path).await.unwrap().len(), 1);
+
+        // Snapshots inside a directory are not capability files
+        let restored = CapabilityDiffer::load_tools_async(dir.path()).await.unwrap();
+        assert_eq!(restored.keys().collect::<Vec<_>>(), vec!["search"]);
+        assert!(CapabilityDiffer::new().diff(&from_snapshot, &restored).is_empty());
+    }
+}
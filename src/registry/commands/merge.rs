@@ -32,7 +32,7 @@
 //! - Tags: Combines all unique tags into a single set
 
 use crate::error::{ProxyError, Result};
-use crate::registry::types::{CapabilityFile, FileMetadata, ToolDefinition};
+use crate::registry::types::{CapabilityFile, CAPABILITY_SCHEMA_VERSION, FileMetadata, ToolDefinition};
 use std::collections::{HashMap, HashSet};
 
 /// Capability Merger
@@ -138,6 +138,7 @@ impl CapabilityMerger {
 
         // Create merged file
         let merged_file = CapabilityFile {
+            schema_version: Some(CAPABILITY_SCHEMA_VERSION),
             metadata: Some(metadata),
             tools: all_tools,
         };
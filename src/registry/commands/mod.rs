@@ -3,14 +3,18 @@
 //! This module provides adapter classes for each generator type that implement
 //! the CapabilityGenerator trait, allowing them to be used with the unified CLI.
 
+pub mod diff;
 pub mod graphql;
 pub mod grpc;
 pub mod openapi;
 pub mod merge;
+pub mod schema;
 pub mod validate;
 
+pub use diff::{CapabilityDiff, CapabilityDiffer};
 pub use graphql::GraphQLGeneratorAdapter;
 pub use grpc::GrpcGeneratorAdapter;
 pub use openapi::OpenAPIGeneratorAdapter;
 pub use self::merge::{CapabilityMerger, MergeStrategy};
+pub use self::schema::{capability_file_schema, Diagnostic, Severity, StrictValidator};
 pub use self::validate::CapabilityValidator;
\ No newline at end of file
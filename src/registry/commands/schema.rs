@@ -0,0 +1,607 @@
+//! Capability file JSON Schema and strict validation
+//!
+//! [`capability_file_schema`] describes the capability file format as a
+//! JSON Schema (draft 7) that editors can use for completion and inline
+//! errors. [`StrictValidator`] checks files against it and additionally
+//! compiles every tool's `inputSchema` and `outputSchema` and validates
+//! routing configurations.
+//!
+//! Problems are reported as [`Diagnostic`]s carrying a JSON pointer into the
+//! document and, where it can be resolved, the line and column in the source
+//! file, so `magictunnel-cli validate --strict --format json` output can be
+//! consumed by editor integrations.
+
+use crate::registry::migration::migrate_document;
+use crate::registry::types::{CapabilityFile, CAPABILITY_SCHEMA_VERSION};
+use jsonschema::error::ValidationErrorKind;
+use jsonschema::JSONSchema;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Routing types understood by the agent router
+pub const ROUTING_TYPES: &[&str] = &[
+    "subprocess",
+    "http",
+    "lambda",
+    "llm",
+    "websocket",
+    "database",
+    "kafka",
+    "amqp",
+    "ssh",
+    "docker",
+    "kubernetes_job",
+    "webhook",
+    "wasm",
+    "script",
+    "grpc",
+    "sse",
+    "graphql",
+    "external_mcp",
+    "smart_discovery",
+];
+
+/// Canonical JSON Schema for the capability file format
+pub fn capability_file_schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "$id": "https://magictunnel.dev/schemas/capability-file.json",
+        "title": "MagicTunnel capability file",
+        "type": "object",
+        "additionalProperties": false,
+        "required": ["tools"],
+        "properties": {
+            "schema_version": {
+                "description": "Capability file format version",
+                "type": "integer",
+                "minimum": 1,
+                "maximum": CAPABILITY_SCHEMA_VERSION
+            },
+            "metadata": {
+                "type": ["object", "null"],
+                "additionalProperties": false,
+                "properties": {
+                    "name": { "type": ["string", "null"] },
+                    "description": { "type": ["string", "null"] },
+                    "version": { "type": ["string", "null"] },
+                    "author": { "type": ["string", "null"] },
+                    "tags": { "type": ["array", "null"], "items": { "type": "string" } }
+                }
+            },
+            "tools": {
+                "type": "array",
+                "items": { "$ref": "#/definitions/tool" }
+            }
+        },
+        "definitions": {
+            "tool": {
+                "type": "object",
+                "additionalProperties": false,
+                "required": ["name", "description", "inputSchema", "routing"],
+                "properties": {
+                    "name": {
+                        "description": "Unique tool name, optionally versioned as name@version",
+                        "type": "string",
+                        "minLength": 1
+                    },
+                    "description": { "type": "string", "minLength": 1 },
+                    "inputSchema": {
+                        "description": "JSON Schema for the tool arguments",
+                        "type": "object"
+                    },
+                    "outputSchema": {
+                        "description": "JSON Schema for the tool's structured output",
+                        "type": "object"
+                    },
+                    "routing": { "$ref": "#/definitions/routing" },
+                    "annotations": {
+                        "type": ["object", "null"],
+                        "additionalProperties": { "type": "string" }
+                    },
+                    "hidden": { "type": "boolean" },
+                    "enabled": { "type": "boolean" },
+                    "deprecated": { "type": "boolean" },
+                    "deprecation_reason": { "type": ["string", "null"] },
+                    "sunset_date": {
+                        "type": ["string", "null"],
+                        "pattern": "^[0-9]{4}-[0-9]{2}-[0-9]{2}$"
+                    },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "cache": {
+                        "description": "Cache successful results of this tool",
+                        "type": ["object", "null"],
+                        "additionalProperties": false,
+                        "required": ["ttl_seconds"],
+                        "properties": {
+                            "ttl_seconds": { "type": "integer", "minimum": 1 },
+                            "max_entries": { "type": "integer", "minimum": 1 }
+                        }
+                    },
+                    "background": {
+                        "description": "Run calls as background jobs",
+                        "type": "boolean"
+                    },
+                    "cost": {
+                        "description": "What a call costs, charged to the caller's budgets",
+                        "type": ["object", "null"],
+                        "additionalProperties": false,
+                        "properties": {
+                            "per_call": { "type": "number", "minimum": 0 },
+                            "per_token": { "type": "number", "minimum": 0 }
+                        }
+                    }
+                }
+            },
+            "routing": {
+                "type": "object",
+                "additionalProperties": false,
+                "required": ["type", "config"],
+                "properties": {
+                    "type": { "type": "string", "enum": ROUTING_TYPES },
+                    "config": { "type": "object" },
+                    "fallback": {
+                        "description": "Routings tried in order when this one fails",
+                        "type": "array",
+                        "items": { "$ref": "#/definitions/fallback_routing" }
+                    },
+                    "canary": {
+                        "description": "Alternate routing that serves a percentage of calls",
+                        "type": ["object", "null"],
+                        "additionalProperties": false,
+                        "required": ["percentage", "routing"],
+                        "properties": {
+                            "percentage": { "type": "number", "minimum": 0, "maximum": 100 },
+                            "routing": { "$ref": "#/definitions/routing" }
+                        }
+                    }
+                }
+            },
+            "fallback_routing": {
+                "description": "A fallback leg; type `cache` serves the tool's last cached result",
+                "type": "object",
+                "additionalProperties": false,
+                "required": ["type"],
+                "properties": {
+                    "type": { "type": "string", "enum": fallback_routing_types() },
+                    "config": { "type": "object" }
+                }
+            }
+        }
+    })
+}
+
+/// Routing types of fallback legs: the agent types and the last cached result
+fn fallback_routing_types() -> Vec<&'static str> {
+    ROUTING_TYPES.iter().copied().chain([crate::routing::cache::FALLBACK_ROUTING_TYPE]).collect()
+}
+
+/// Severity of a validation diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The file will not load or will misbehave
+    Error,
+    /// The file loads but uses a deprecated construct
+    Warning,
+}
+
+/// A single validation problem with its location
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    /// Error or warning
+    pub severity: Severity,
+    /// Short identifier of the check that failed
+    pub rule: String,
+    /// JSON pointer to the offending value (empty for the whole document)
+    pub path: String,
+    /// 1-based line in the source file, when it could be resolved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 1-based column in the source file, when it could be resolved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "{}:{}: ", line, self.column.unwrap_or(1))?;
+        }
+        let path = if self.path.is_empty() { "/" } else { &self.path };
+        write!(f, "{} [{}] at {}: {}", match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }, self.rule, path, self.message)
+    }
+}
+
+/// Strict capability file validator
+pub struct StrictValidator {
+    schema: JSONSchema,
+}
+
+impl StrictValidator {
+    /// Create a validator for the current capability file format
+    pub fn new() -> Self {
+        let schema = JSONSchema::compile(&capability_file_schema())
+            .expect("capability file schema is valid");
+        Self { schema }
+    }
+
+    /// Validate the contents of a capability file
+    pub fn validate_content(&self, content: &str) -> Vec<Diagnostic> {
+        let locator = YamlLocator::new(content);
+        let mut diagnostics = Vec::new();
+
+        let document: serde_yaml::Value = match serde_yaml::from_str(content) {
+            Ok(document) => document,
+            Err(e) => {
+                let location = e.location();
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    rule: "yaml-syntax".to_string(),
+                    path: String::new(),
+                    line: location.as_ref().map(|l| l.line()),
+                    column: location.as_ref().map(|l| l.column()),
+                    message: e.to_string(),
+                });
+                return diagnostics;
+            }
+        };
+
+        // Legacy files are checked in their upgraded form; each rewritten
+        // construct is reported as a warning
+        let outcome = match migrate_document(document) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                diagnostics.push(locator.diagnostic(Severity::Error, "schema-version", "/schema_version", e.to_string()));
+                return diagnostics;
+            }
+        };
+        for warning in &outcome.warnings {
+            diagnostics.push(locator.diagnostic(Severity::Warning, "deprecated-format", "", warning.clone()));
+        }
+
+        let document = match serde_json::to_value(&outcome.document) {
+            Ok(document) => document,
+            Err(e) => {
+                diagnostics.push(locator.diagnostic(Severity::Error, "yaml-syntax", "", e.to_string()));
+                return diagnostics;
+            }
+        };
+
+        if let Err(errors) = self.schema.validate(&document) {
+            for error in errors {
+                let mut path = error.instance_path.to_string();
+                // Point unknown fields at the field itself rather than its parent
+                if let ValidationErrorKind::AdditionalProperties { unexpected } = &error.kind {
+                    for field in unexpected {
+                        let field_path = format!("{}/{}", path, escape_pointer(field));
+                        diagnostics.push(locator.diagnostic(
+                            Severity::Error,
+                            "unknown-field",
+                            &field_path,
+                            format!("Unknown field '{}'", field),
+                        ));
+                    }
+                    continue;
+                }
+                if path == "/" {
+                    path.clear();
+                }
+                diagnostics.push(locator.diagnostic(Severity::Error, "schema", &path, error.to_string()));
+            }
+        }
+
+        // Semantic checks need the typed form; skip them when the shape is wrong
+        let Ok(file) = serde_json::from_value::<CapabilityFile>(document) else {
+            return diagnostics;
+        };
+
+        let mut first_index: HashMap<&str, usize> = HashMap::new();
+        for (index, tool) in file.tools.iter().enumerate() {
+            if let Some(&first) = first_index.get(tool.name.as_str()) {
+                diagnostics.push(locator.diagnostic(
+                    Severity::Error,
+                    "duplicate-name",
+                    &format!("/tools/{}/name", index),
+                    format!("Tool name '{}' is already used by tool #{}", tool.name, first),
+                ));
+            } else {
+                first_index.insert(tool.name.as_str(), index);
+            }
+            if let Err(e) = JSONSchema::compile(&tool.input_schema) {
+                diagnostics.push(locator.diagnostic(
+                    Severity::Error,
+                    "input-schema",
+                    &format!("/tools/{}/inputSchema", index),
+                    format!("Tool '{}' has an invalid inputSchema: {}", tool.name, e),
+                ));
+            }
+            if let Some(Err(e)) = tool.output_schema.as_ref().map(JSONSchema::compile) {
+                diagnostics.push(locator.diagnostic(
+                    Severity::Error,
+                    "output-schema",
+                    &format!("/tools/{}/outputSchema", index),
+                    format!("Tool '{}' has an invalid outputSchema: {}", tool.name, e),
+                ));
+            }
+            if let Err(e) = tool.routing.validate() {
+                diagnostics.push(locator.diagnostic(
+                    Severity::Error,
+                    "routing",
+                    &format!("/tools/{}/routing", index),
+                    format!("Tool '{}' has an invalid routing configuration: {}", tool.name, e),
+                ));
+            } else if let Err(e) = tool.validate() {
+                diagnostics.push(locator.diagnostic(Severity::Error, "tool", &format!("/tools/{}", index), e.to_string()));
+            }
+        }
+
+        diagnostics
+    }
+}
+
+impl Default for StrictValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+/// Maps JSON pointers to source positions in block-style YAML
+///
+/// Flow-style collections (`{type: object}`) and multi-line scalars are not
+/// descended into; pointers below them resolve to the nearest enclosing node.
+struct YamlLocator {
+    positions: HashMap<String, (usize, usize)>,
+}
+
+struct Frame {
+    indent: usize,
+    pointer: String,
+    is_item: bool,
+    next_index: usize,
+}
+
+impl YamlLocator {
+    fn new(content: &str) -> Self {
+        let mut positions = HashMap::new();
+        let mut frames: Vec<Frame> = Vec::new();
+        let mut root_next_index = 0;
+        let mut block_scalar_indent: Option<usize> = None;
+
+        for (line_index, line) in content.lines().enumerate() {
+            let trimmed = line.trim_start();
+            let mut indent = line.len() - trimmed.len();
+
+            if let Some(scalar_indent) = block_scalar_indent {
+                if trimmed.is_empty() || indent > scalar_indent {
+                    continue;
+                }
+                block_scalar_indent = None;
+            }
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("---") || trimmed.starts_with("...") {
+                continue;
+            }
+
+            let mut rest = trimmed;
+            loop {
+                if rest == "-" || rest.starts_with("- ") {
+                    while frames.last().is_some_and(|f| f.indent > indent || (f.indent == indent && f.is_item)) {
+                        frames.pop();
+                    }
+                    let index = match frames.last_mut() {
+                        Some(parent) => {
+                            parent.next_index += 1;
+                            parent.next_index - 1
+                        }
+                        None => {
+                            root_next_index += 1;
+                            root_next_index - 1
+                        }
+                    };
+                    let pointer = format!("{}/{}", frames.last().map(|f| f.pointer.as_str()).unwrap_or(""), index);
+                    positions.insert(pointer.clone(), (line_index + 1, indent + 1));
+                    frames.push(Frame { indent, pointer, is_item: true, next_index: 0 });
+
+                    let after_dash = &rest[1..];
+                    let item = after_dash.trim_start();
+                    if item.is_empty() || item.starts_with('#') {
+                        break;
+                    }
+                    indent += 1 + (after_dash.len() - item.len());
+                    rest = item;
+                    continue;
+                }
+
+                if let Some((key, value)) = split_key(rest) {
+                    while frames.last().is_some_and(|f| f.indent > indent || (f.indent == indent && !f.is_item)) {
+                        frames.pop();
+                    }
+                    let pointer = format!(
+                        "{}/{}",
+                        frames.last().map(|f| f.pointer.as_str()).unwrap_or(""),
+                        escape_pointer(&key)
+                    );
+                    positions.insert(pointer.clone(), (line_index + 1, indent + 1));
+                    frames.push(Frame { indent, pointer, is_item: false, next_index: 0 });
+
+                    if value.starts_with('|') || value.starts_with('>') {
+                        block_scalar_indent = Some(indent);
+                    }
+                }
+                break;
+            }
+        }
+
+        Self { positions }
+    }
+
+    /// Position of the node at `pointer`, or of its nearest located ancestor
+    fn locate(&self, pointer: &str) -> Option<(usize, usize)> {
+        let mut pointer = pointer;
+        loop {
+            if let Some(position) = self.positions.get(pointer) {
+                return Some(*position);
+            }
+            pointer = &pointer[..pointer.rfind('/')?];
+        }
+    }
+
+    fn diagnostic(&self, severity: Severity, rule: &str, path: &str, message: String) -> Diagnostic {
+        let position = self.locate(path);
+        Diagnostic {
+            severity,
+            rule: rule.to_string(),
+            path: path.to_string(),
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+            message,
+        }
+    }
+}
+
+/// Split `key: value` (or `key:`) into the unquoted key and the value text
+fn split_key(text: &str) -> Option<(String, &str)> {
+    let (key, rest) = if let Some(quote) = text.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let end = text[1..].find(quote)? + 1;
+        (text[1..end].to_string(), &text[end + 1..])
+    } else {
+        let end = text.find(": ").or_else(|| text.strip_suffix(':').map(|s| s.len()))?;
+        if text.starts_with(['{', '[']) {
+            return None;
+        }
+        (text[..end].trim_end().to_string(), &text[end..])
+    };
+    let value = rest.strip_prefix(':')?;
+    Some((key, value.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = "schema_version: 2
+tools:
+  - name: get_user
+    description: Fetch a user
+    inputSchema:
+      type: object
+    routing:
+      type: http
+      config:
+        url: https://example.com/users
+        method: GET
+";
+
+    #[test]
+    fn test_valid_file() {
+        let diagnostics = StrictValidator::new().validate_content(VALID);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_unknown_field_location() {
+        let content = VALID.replace("    description: Fetch a user\n", "    description: Fetch a user\n    summary: |\n      key: not a field\n");
+        let diagnostics = StrictValidator::new().validate_content(&content);
+
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.rule, "unknown-field");
+        assert_eq!(diagnostic.path, "/tools/0/summary");
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(5), Some(5)));
+    }
+
+    #[test]
+    fn test_input_schema_and_routing_checks() {
+        let content = VALID
+            .replace("      type: object\n", "      type: not-a-type\n")
+            .replace("        url: https://example.com/users\n", "");
+        let diagnostics = StrictValidator::new().validate_content(&content);
+
+        let rules: Vec<&str> = diagnostics.iter().map(|d| d.rule.as_str()).collect();
+        assert!(rules.contains(&"input-schema"), "{:?}", diagnostics);
+        let routing = diagnostics.iter().find(|d| d.rule == "routing").unwrap();
+        assert_eq!(routing.path, "/tools/0/routing");
+        assert_eq!(routing.line, Some(7));
+    }
+
+    #[test]
+    fn test_every_tool_and_routing_field_is_known() {
+        let content = "schema_version: 2
+metadata:
+  name: weather
+  description: Weather tools
+  version: 1.0.0
+  author: ops
+  tags: [weather]
+tools:
+  - name: get_weather@2.0.0
+    description: Current weather
+    inputSchema:
+      type: object
+    outputSchema:
+      type: object
+    routing:
+      type: http
+      config:
+        url: https://weather.example.com/current
+        method: GET
+      fallback:
+        - type: http
+          config:
+            url: https://backup.example.com/current
+        - type: cache
+          config:
+            max_age_seconds: 3600
+      canary:
+        percentage: 10
+        routing:
+          type: http
+          config:
+            url: https://canary.example.com/current
+    annotations:
+      readOnlyHint: 'true'
+    hidden: false
+    enabled: true
+    deprecated: true
+    deprecation_reason: Use get_forecast
+    sunset_date: 2030-01-01
+    tags: [weather]
+    cache:
+      ttl_seconds: 300
+      max_entries: 100
+    background: true
+    cost:
+      per_call: 0.01
+      per_token: 0.0001
+";
+        let diagnostics = StrictValidator::new().validate_content(content);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        // The fixture sets every field the tool and routing types serialize
+        let file: CapabilityFile = serde_yaml::from_str(content).unwrap();
+        let tool = serde_json::to_value(&file.tools[0]).unwrap();
+        let schema = capability_file_schema();
+        for (value, definition) in [(&tool, "tool"), (&tool["routing"], "routing")] {
+            let known = schema["definitions"][definition]["properties"].as_object().unwrap();
+            for field in value.as_object().unwrap().keys() {
+                assert!(known.contains_key(field), "'{}' is missing from the {} schema", field, definition);
+            }
+        }
+    }
+
+    #[test]
+    fn test_yaml_syntax_error() {
+        let diagnostics = StrictValidator::new().validate_content("tools: [\n");
+        assert_eq!(diagnostics[0].rule, "yaml-syntax");
+        assert!(diagnostics[0].line.is_some());
+    }
+}
@@ -0,0 +1,856 @@
+//! Database Capability Generator
+//!
+//! This module generates MCP tool definitions from a live database. It connects to
+//! PostgreSQL, MySQL or SQLite, introspects tables and columns, and emits list/get/insert/update
+//! tools routed through the `database` agent. Generated queries always use positional
+//! placeholders bound from tool arguments, never string substitution.
+
+use crate::error::{ProxyError, Result};
+use crate::registry::types::{CapabilityFile, FileMetadata, ToolDefinition, RoutingConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// Default page size for generated list tools
+const DEFAULT_LIST_LIMIT: u64 = 100;
+
+/// PostgreSQL types the database agent can bind directly; other column types are
+/// bound as text and cast in the query
+const POSTGRES_BINDABLE_TYPES: &[&str] = &[
+    "bool", "int2", "int4", "int8", "float4", "float8", "text", "varchar", "bpchar", "name",
+];
+
+/// Supported database engines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseType {
+    /// PostgreSQL
+    Postgres,
+    /// SQLite (connection string is a file path)
+    Sqlite,
+    /// MySQL or MariaDB
+    Mysql,
+}
+
+impl DatabaseType {
+    /// Infer the engine from a connection string
+    pub fn from_connection_string(connection_string: &str) -> Self {
+        let lower = connection_string.to_lowercase();
+        if lower.starts_with("postgres://") || lower.starts_with("postgresql://") || lower.contains("host=") {
+            DatabaseType::Postgres
+        } else if lower.starts_with("mysql://") || lower.starts_with("mariadb://") {
+            DatabaseType::Mysql
+        } else {
+            DatabaseType::Sqlite
+        }
+    }
+
+    /// Value of the `db_type` routing field
+    fn routing_name(&self) -> &'static str {
+        match self {
+            DatabaseType::Postgres => "postgresql",
+            DatabaseType::Sqlite => "sqlite",
+            DatabaseType::Mysql => "mysql",
+        }
+    }
+
+    /// Positional placeholder for the n-th (1-based) bound parameter
+    fn placeholder(&self, index: usize) -> String {
+        match self {
+            DatabaseType::Postgres => format!("${}", index),
+            // MySQL placeholders are bound in the order they appear
+            DatabaseType::Mysql => "?".to_string(),
+            DatabaseType::Sqlite => format!("?{}", index),
+        }
+    }
+
+    /// Quote an SQL identifier
+    fn quote_identifier(&self, name: &str) -> String {
+        match self {
+            DatabaseType::Mysql => format!("`{}`", name.replace('`', "``")),
+            _ => format!("\"{}\"", name.replace('"', "\"\"")),
+        }
+    }
+
+    /// `RETURNING *` where the engine supports it on INSERT and UPDATE
+    fn returning(&self) -> &'static str {
+        match self {
+            DatabaseType::Mysql => "",
+            _ => " RETURNING *",
+        }
+    }
+}
+
+/// Configuration for the database capability generator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseGeneratorConfig {
+    /// Connection string used for introspection
+    pub connection_string: String,
+    /// Connection string written into generated tools (defaults to `connection_string`).
+    /// Useful to keep credentials used at generation time out of capability files.
+    pub routing_connection_string: Option<String>,
+    /// Database engine (inferred from the connection string when not set)
+    pub db_type: Option<DatabaseType>,
+    /// PostgreSQL schema to introspect (MySQL uses the connection's database)
+    pub schema: String,
+    /// Only generate tools for these tables
+    pub table_filter: Option<Vec<String>>,
+    /// Tool name prefix
+    pub tool_prefix: Option<String>,
+    /// Only generate read tools (list/get)
+    pub read_only: bool,
+    /// Query timeout in seconds for generated tools
+    pub timeout: Option<u64>,
+}
+
+impl Default for DatabaseGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            connection_string: String::new(),
+            routing_connection_string: None,
+            db_type: None,
+            schema: "public".to_string(),
+            table_filter: None,
+            tool_prefix: None,
+            read_only: false,
+            timeout: None,
+        }
+    }
+}
+
+/// An introspected table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableSchema {
+    /// Table name
+    pub name: String,
+    /// Columns in ordinal order
+    pub columns: Vec<ColumnSchema>,
+}
+
+/// An introspected column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnSchema {
+    /// Column name
+    pub name: String,
+    /// Database type name (PostgreSQL `udt_name`, MySQL `DATA_TYPE`, SQLite declared type)
+    pub data_type: String,
+    /// Whether NULL is allowed
+    pub nullable: bool,
+    /// Whether the column is part of the primary key
+    pub primary_key: bool,
+    /// Whether the column has a default (or is auto-generated)
+    pub has_default: bool,
+}
+
+impl TableSchema {
+    fn primary_key(&self) -> Vec<&ColumnSchema> {
+        self.columns.iter().filter(|c| c.primary_key).collect()
+    }
+}
+
+/// Database capability generator
+pub struct DatabaseCapabilityGenerator {
+    /// Generator configuration
+    pub config: DatabaseGeneratorConfig,
+}
+
+impl DatabaseCapabilityGenerator {
+    /// Create a new database capability generator
+    pub fn new(config: DatabaseGeneratorConfig) -> Self {
+        Self { config }
+    }
+
+    /// Database engine in use
+    pub fn db_type(&self) -> DatabaseType {
+        self.config
+            .db_type
+            .unwrap_or_else(|| DatabaseType::from_connection_string(&self.config.connection_string))
+    }
+
+    /// Introspect the database and generate a capability file
+    pub async fn generate(&self) -> Result<CapabilityFile> {
+        let tables = self.introspect().await?;
+        self.generate_from_tables(&tables)
+    }
+
+    /// Read table and column definitions from the database
+    pub async fn introspect(&self) -> Result<Vec<TableSchema>> {
+        let tables = match self.db_type() {
+            DatabaseType::Postgres => self.introspect_postgres().await?,
+            DatabaseType::Sqlite => self.introspect_sqlite().await?,
+            DatabaseType::Mysql => self.introspect_mysql().await?,
+        };
+
+        Ok(tables
+            .into_iter()
+            .filter(|table| match &self.config.table_filter {
+                Some(filter) => filter.iter().any(|name| name == &table.name),
+                None => true,
+            })
+            .collect())
+    }
+
+    async fn introspect_postgres(&self) -> Result<Vec<TableSchema>> {
+        let (client, connection) = tokio_postgres::connect(&self.config.connection_string, tokio_postgres::NoTls)
+            .await
+            .map_err(|e| ProxyError::connection(format!("PostgreSQL connection failed: {}", e)))?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::warn!("PostgreSQL introspection connection error: {}", e);
+            }
+        });
+
+        let rows = client
+            .query(
+                "SELECT c.table_name::text, c.column_name::text, c.udt_name::text, c.is_nullable = 'YES', \
+                        c.column_default IS NOT NULL OR c.is_identity = 'YES', \
+                        EXISTS (SELECT 1 FROM information_schema.table_constraints tc \
+                                JOIN information_schema.key_column_usage k \
+                                  ON k.constraint_name = tc.constraint_name AND k.table_schema = tc.table_schema \
+                                WHERE tc.constraint_type = 'PRIMARY KEY' AND tc.table_schema = c.table_schema \
+                                  AND tc.table_name = c.table_name AND k.column_name = c.column_name) \
+                 FROM information_schema.columns c \
+                 JOIN information_schema.tables t \
+                   ON t.table_schema = c.table_schema AND t.table_name = c.table_name AND t.table_type = 'BASE TABLE' \
+                 WHERE c.table_schema = $1 \
+                 ORDER BY c.table_name, c.ordinal_position",
+                &[&self.config.schema],
+            )
+            .await
+            .map_err(|e| ProxyError::connection(format!("PostgreSQL introspection failed: {}", e)))?;
+
+        let mut tables: Vec<TableSchema> = Vec::new();
+        for row in rows {
+            let table_name: String = row.get(0);
+            let column = ColumnSchema {
+                name: row.get(1),
+                data_type: row.get(2),
+                nullable: row.get(3),
+                has_default: row.get(4),
+                primary_key: row.get(5),
+            };
+            match tables.last_mut() {
+                Some(table) if table.name == table_name => table.columns.push(column),
+                _ => tables.push(TableSchema { name: table_name, columns: vec![column] }),
+            }
+        }
+        Ok(tables)
+    }
+
+    #[cfg(feature = "mysql")]
+    async fn introspect_mysql(&self) -> Result<Vec<TableSchema>> {
+        use mysql_async::prelude::Queryable;
+
+        let opts = mysql_async::Opts::from_url(&self.config.connection_string)
+            .map_err(|e| ProxyError::config(format!("Invalid MySQL connection string: {}", e)))?;
+        let mut conn = mysql_async::Conn::new(opts)
+            .await
+            .map_err(|e| ProxyError::connection(format!("MySQL connection failed: {}", e)))?;
+
+        let rows: std::result::Result<Vec<(String, String, String, i64, i64, i64)>, _> = conn
+            .query(
+                "SELECT c.TABLE_NAME, c.COLUMN_NAME, c.DATA_TYPE, c.IS_NULLABLE = 'YES', \
+                        c.COLUMN_DEFAULT IS NOT NULL OR c.EXTRA LIKE '%auto_increment%' OR c.EXTRA LIKE '%GENERATED%', \
+                        c.COLUMN_KEY = 'PRI' \
+                 FROM information_schema.COLUMNS c \
+                 JOIN information_schema.TABLES t \
+                   ON t.TABLE_SCHEMA = c.TABLE_SCHEMA AND t.TABLE_NAME = c.TABLE_NAME AND t.TABLE_TYPE = 'BASE TABLE' \
+                 WHERE c.TABLE_SCHEMA = DATABASE() \
+                 ORDER BY c.TABLE_NAME, c.ORDINAL_POSITION",
+            )
+            .await;
+        if let Err(e) = conn.disconnect().await {
+            tracing::warn!("MySQL introspection disconnect failed: {}", e);
+        }
+        let rows = rows.map_err(|e| ProxyError::connection(format!("MySQL introspection failed: {}", e)))?;
+
+        let mut tables: Vec<TableSchema> = Vec::new();
+        for (table_name, name, data_type, nullable, has_default, primary_key) in rows {
+            let column = ColumnSchema {
+                name,
+                data_type,
+                nullable: nullable != 0,
+                has_default: has_default != 0,
+                primary_key: primary_key != 0,
+            };
+            match tables.last_mut() {
+                Some(table) if table.name == table_name => table.columns.push(column),
+                _ => tables.push(TableSchema { name: table_name, columns: vec![column] }),
+            }
+        }
+        Ok(tables)
+    }
+
+    #[cfg(not(feature = "mysql"))]
+    async fn introspect_mysql(&self) -> Result<Vec<TableSchema>> {
+        Err(ProxyError::config("MySQL introspection needs the `mysql` feature, which this build was compiled without".to_string()))
+    }
+
+    async fn introspect_sqlite(&self) -> Result<Vec<TableSchema>> {
+        let path = self.config.connection_string.trim_start_matches("sqlite://").to_string();
+        tokio::task::spawn_blocking(move || -> Result<Vec<TableSchema>> {
+            let conn = rusqlite::Connection::open_with_flags(&path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(|e| ProxyError::connection(format!("SQLite connection failed: {}", e)))?;
+            let sqlite_err = |e: rusqlite::Error| ProxyError::connection(format!("SQLite introspection failed: {}", e));
+
+            let mut stmt = conn
+                .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
+                .map_err(sqlite_err)?;
+            let table_names = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .map_err(sqlite_err)?
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(sqlite_err)?;
+
+            let mut tables = Vec::new();
+            for table_name in table_names {
+                let mut stmt = conn
+                    .prepare(&format!("PRAGMA table_info({})", DatabaseType::Sqlite.quote_identifier(&table_name)))
+                    .map_err(sqlite_err)?;
+                let columns = stmt
+                    .query_map([], |row| {
+                        let data_type: String = row.get(2)?;
+                        let not_null: bool = row.get(3)?;
+                        let default: Option<String> = row.get(4)?;
+                        let pk_position: i64 = row.get(5)?;
+                        // INTEGER PRIMARY KEY is an alias for the rowid and is assigned automatically
+                        let rowid_alias = pk_position == 1 && data_type.eq_ignore_ascii_case("integer");
+                        Ok(ColumnSchema {
+                            name: row.get(1)?,
+                            nullable: !not_null && pk_position == 0,
+                            primary_key: pk_position > 0,
+                            has_default: default.is_some() || rowid_alias,
+                            data_type,
+                        })
+                    })
+                    .map_err(sqlite_err)?
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(sqlite_err)?;
+                tables.push(TableSchema { name: table_name, columns });
+            }
+            Ok(tables)
+        })
+        .await
+        .map_err(|e| ProxyError::connection(format!("SQLite introspection task failed: {}", e)))?
+    }
+
+    /// Generate a capability file from already introspected tables
+    pub fn generate_from_tables(&self, tables: &[TableSchema]) -> Result<CapabilityFile> {
+        let mut tools = Vec::new();
+
+        for table in tables {
+            tools.push(self.list_tool(table)?);
+            if !table.primary_key().is_empty() {
+                tools.push(self.get_tool(table)?);
+            }
+            if !self.config.read_only {
+                tools.push(self.insert_tool(table)?);
+                if !table.primary_key().is_empty() {
+                    tools.push(self.update_tool(table)?);
+                }
+            }
+        }
+
+        let metadata = FileMetadata::with_name("database-capabilities".to_string())
+            .description(format!("Auto-generated {} table tools", self.db_type().routing_name()))
+            .version("1.0.0".to_string())
+            .author("Database Capability Generator".to_string())
+            .tags(vec!["database".to_string(), self.db_type().routing_name().to_string()]);
+
+        CapabilityFile::with_metadata(metadata, tools)
+    }
+
+    fn list_tool(&self, table: &TableSchema) -> Result<ToolDefinition> {
+        let db = self.db_type();
+        let pk_columns = table.primary_key().iter().map(|c| self.quote(&c.name)).collect::<Vec<_>>().join(", ");
+        let (query, params) = if db == DatabaseType::Mysql {
+            // MySQL only takes literals or bound integers in LIMIT, so the page is picked by row number
+            let over = match pk_columns.as_str() {
+                "" => String::new(),
+                pk => format!("ORDER BY {}", pk),
+            };
+            let query = format!(
+                "SELECT {} FROM (SELECT {}, ROW_NUMBER() OVER ({}) AS `_row_number` FROM {}) AS `numbered` \
+                 WHERE `_row_number` > COALESCE(?, 0) AND `_row_number` <= COALESCE(?, 0) + COALESCE(?, {}) \
+                 ORDER BY `_row_number`",
+                self.column_list(table),
+                self.column_list(table),
+                over,
+                self.quote(&table.name),
+                DEFAULT_LIST_LIMIT
+            );
+            (query, vec!["offset".to_string(), "offset".to_string(), "limit".to_string()])
+        } else {
+            let order_by = match pk_columns.as_str() {
+                "" => String::new(),
+                pk => format!(" ORDER BY {}", pk),
+            };
+            let query = format!(
+                "SELECT {} FROM {}{} LIMIT COALESCE({}, {}) OFFSET COALESCE({}, 0)",
+                self.column_list(table),
+                self.quote(&table.name),
+                order_by,
+                db.placeholder(1),
+                DEFAULT_LIST_LIMIT,
+                db.placeholder(2)
+            );
+            (query, vec!["limit".to_string(), "offset".to_string()])
+        };
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "limit": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": format!("Maximum number of rows to return (default {})", DEFAULT_LIST_LIMIT)
+                },
+                "offset": {"type": "integer", "minimum": 0, "description": "Number of rows to skip"}
+            },
+            "required": []
+        });
+        self.tool(
+            table,
+            format!("list_{}", table.name),
+            format!("List rows from the {} table", table.name),
+            schema,
+            query,
+            params,
+            true,
+        )
+    }
+
+    fn get_tool(&self, table: &TableSchema) -> Result<ToolDefinition> {
+        let pk = table.primary_key();
+        let (condition, params) = self.key_condition(&pk, 1);
+        let query = format!(
+            "SELECT {} FROM {} WHERE {}",
+            self.column_list(table),
+            self.quote(&table.name),
+            condition
+        );
+        let schema = self.object_schema(pk.iter().copied(), |_| true);
+        self.tool(
+            table,
+            format!("get_{}", table.name),
+            format!("Get a row from the {} table by primary key", table.name),
+            schema,
+            query,
+            params,
+            true,
+        )
+    }
+
+    fn insert_tool(&self, table: &TableSchema) -> Result<ToolDefinition> {
+        let db = self.db_type();
+        // Columns with defaults are left to the database
+        let columns: Vec<&ColumnSchema> = table.columns.iter().filter(|c| !c.has_default).collect();
+
+        let query = if columns.is_empty() {
+            match db {
+                DatabaseType::Mysql => format!("INSERT INTO {} () VALUES ()", self.quote(&table.name)),
+                _ => format!("INSERT INTO {} DEFAULT VALUES{}", self.quote(&table.name), db.returning()),
+            }
+        } else {
+            format!(
+                "INSERT INTO {} ({}) VALUES ({}){}",
+                self.quote(&table.name),
+                columns.iter().map(|c| self.quote(&c.name)).collect::<Vec<_>>().join(", "),
+                columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| self.bind_placeholder(db, i + 1, c))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                db.returning()
+            )
+        };
+        let schema = self.object_schema(columns.iter().copied(), |c| !c.nullable);
+        self.tool(
+            table,
+            format!("insert_{}", table.name),
+            format!("Insert a row into the {} table", table.name),
+            schema,
+            query,
+            columns.iter().map(|c| c.name.clone()).collect(),
+            false,
+        )
+    }
+
+    fn update_tool(&self, table: &TableSchema) -> Result<ToolDefinition> {
+        let db = self.db_type();
+        let pk = table.primary_key();
+        let columns: Vec<&ColumnSchema> = table.columns.iter().filter(|c| !c.primary_key).collect();
+        if columns.is_empty() {
+            return Err(ProxyError::config(format!("Table {} has no updatable columns", table.name)));
+        }
+
+        // Omitted arguments keep their current value; each column binds whether
+        // its argument was given (`name?`) and then the value, which may be null
+        let assignments = columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                format!(
+                    "{} = CASE WHEN {} THEN {} ELSE {} END",
+                    self.quote(&c.name),
+                    db.placeholder(2 * i + 1),
+                    self.bind_placeholder(db, 2 * i + 2, c),
+                    self.quote(&c.name)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let (condition, key_params) = self.key_condition(&pk, 2 * columns.len() + 1);
+        let query = format!(
+            "UPDATE {} SET {} WHERE {}{}",
+            self.quote(&table.name),
+            assignments,
+            condition,
+            db.returning()
+        );
+
+        let mut params: Vec<String> = columns.iter().flat_map(|c| [format!("{}?", c.name), c.name.clone()]).collect();
+        params.extend(key_params);
+        let schema = self.object_schema(pk.iter().copied().chain(columns.iter().copied()), |c| c.primary_key);
+        self.tool(
+            table,
+            format!("update_{}", table.name),
+            format!(
+                "Update a row in the {} table by primary key (omitted columns are left unchanged, null clears a column)",
+                table.name
+            ),
+            schema,
+            query,
+            params,
+            false,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn tool(
+        &self,
+        table: &TableSchema,
+        name: String,
+        description: String,
+        input_schema: Value,
+        query: String,
+        params: Vec<String>,
+        read_only: bool,
+    ) -> Result<ToolDefinition> {
+        let mut routing = Map::new();
+        routing.insert("db_type".to_string(), json!(self.db_type().routing_name()));
+        routing.insert(
+            "connection_string".to_string(),
+            json!(self.config.routing_connection_string.as_ref().unwrap_or(&self.config.connection_string)),
+        );
+        routing.insert("query".to_string(), json!(query));
+        routing.insert("params".to_string(), json!(params));
+        routing.insert("read_only".to_string(), json!(read_only));
+        if let Some(timeout) = self.config.timeout {
+            routing.insert("timeout".to_string(), json!(timeout));
+        }
+
+        let mut annotations = std::collections::HashMap::new();
+        annotations.insert("table".to_string(), table.name.clone());
+        annotations.insert("read_only".to_string(), read_only.to_string());
+
+        let name = match &self.config.tool_prefix {
+            Some(prefix) => format!("{}_{}", prefix, name),
+            None => name,
+        };
+
+        Ok(ToolDefinition {
+            name: sanitize_tool_name(&name),
+            description,
+            input_schema,
+            routing: RoutingConfig::new("database".to_string(), Value::Object(routing)),
+            annotations: Some(annotations),
+            hidden: true, // Generated tools are hidden by default (consistent with other generators)
+            enabled: true,
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
+        })
+    }
+
+    fn column_list(&self, table: &TableSchema) -> String {
+        table.columns.iter().map(|c| self.quote(&c.name)).collect::<Vec<_>>().join(", ")
+    }
+
+    fn quote(&self, name: &str) -> String {
+        self.db_type().quote_identifier(name)
+    }
+
+    /// `pk1 = $n AND pk2 = $n+1 ...` plus the bound argument names
+    fn key_condition(&self, pk: &[&ColumnSchema], first_index: usize) -> (String, Vec<String>) {
+        let db = self.db_type();
+        let condition = pk
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{} = {}", self.quote(&c.name), self.bind_placeholder(db, first_index + i, c)))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        (condition, pk.iter().map(|c| c.name.clone()).collect())
+    }
+
+    /// Placeholder for a column value; PostgreSQL types the agent can't bind are sent as text and cast
+    fn bind_placeholder(&self, db: DatabaseType, index: usize, column: &ColumnSchema) -> String {
+        let placeholder = db.placeholder(index);
+        if db == DatabaseType::Postgres && !POSTGRES_BINDABLE_TYPES.contains(&column.data_type.as_str()) {
+            format!("{}::text::{}", placeholder, column.data_type)
+        } else {
+            placeholder
+        }
+    }
+
+    fn object_schema<'a>(
+        &self,
+        columns: impl Iterator<Item = &'a ColumnSchema>,
+        is_required: impl Fn(&ColumnSchema) -> bool,
+    ) -> Value {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for column in columns {
+            properties.insert(column.name.clone(), self.column_json_schema(column));
+            if is_required(column) {
+                required.push(column.name.clone());
+            }
+        }
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required
+        })
+    }
+
+    /// JSON Schema for a column's values
+    pub fn column_json_schema(&self, column: &ColumnSchema) -> Value {
+        let data_type = column.data_type.to_lowercase();
+        let mut schema = match self.db_type() {
+            DatabaseType::Sqlite => sqlite_type_schema(&data_type),
+            DatabaseType::Mysql => mysql_type_schema(&data_type),
+            DatabaseType::Postgres => postgres_type_schema(&data_type),
+        };
+        if let Some(obj) = schema.as_object_mut() {
+            obj.insert("description".to_string(), json!(format!("{} ({})", column.name, column.data_type)));
+            if column.nullable {
+                if let Some(Value::String(ty)) = obj.get("type").cloned() {
+                    obj.insert("type".to_string(), json!([ty, "null"]));
+                }
+            }
+        }
+        schema
+    }
+}
+
+fn postgres_type_schema(data_type: &str) -> Value {
+    match data_type {
+        "int2" | "int4" | "int8" | "smallint" | "integer" | "bigint" | "serial" | "bigserial" => json!({"type": "integer"}),
+        "float4" | "float8" | "numeric" | "real" | "double precision" | "money" => json!({"type": "number"}),
+        "bool" | "boolean" => json!({"type": "boolean"}),
+        "uuid" => json!({"type": "string", "format": "uuid"}),
+        "date" => json!({"type": "string", "format": "date"}),
+        "timestamp" | "timestamptz" => json!({"type": "string", "format": "date-time"}),
+        "time" | "timetz" => json!({"type": "string", "format": "time"}),
+        // JSON values are bound as text, so callers pass the serialized document
+        "json" | "jsonb" => json!({"type": "string", "contentMediaType": "application/json"}),
+        "bytea" => json!({"type": "string", "contentEncoding": "base64"}),
+        _ => json!({"type": "string"}),
+    }
+}
+
+fn mysql_type_schema(data_type: &str) -> Value {
+    match data_type {
+        "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" | "year" => json!({"type": "integer"}),
+        "float" | "double" | "real" => json!({"type": "number"}),
+        // DECIMAL values are returned as strings so no precision is lost
+        "decimal" | "numeric" => json!({"type": "string", "pattern": "^-?[0-9]+(\\.[0-9]+)?$"}),
+        "bool" | "boolean" => json!({"type": "boolean"}),
+        "date" => json!({"type": "string", "format": "date"}),
+        "datetime" | "timestamp" => json!({"type": "string"}),
+        "time" => json!({"type": "string", "format": "time"}),
+        "json" => json!({"type": "string", "contentMediaType": "application/json"}),
+        _ => json!({"type": "string"}),
+    }
+}
+
+/// SQLite column affinity rules (https://www.sqlite.org/datatype3.html)
+fn sqlite_type_schema(data_type: &str) -> Value {
+    if data_type.contains("int") {
+        json!({"type": "integer"})
+    } else if data_type.contains("char") || data_type.contains("clob") || data_type.contains("text") {
+        json!({"type": "string"})
+    } else if data_type.contains("blob") || data_type.is_empty() {
+        json!({"type": "string"})
+    } else if data_type.contains("bool") {
+        json!({"type": "boolean"})
+    } else {
+        json!({"type": "number"})
+    }
+}
+
+fn sanitize_tool_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn users_table() -> TableSchema {
+        TableSchema {
+            name: "users".to_string(),
+            columns: vec![
+                ColumnSchema { name: "id".to_string(), data_type: "int4".to_string(), nullable: false, primary_key: true, has_default: true },
+                ColumnSchema { name: "email".to_string(), data_type: "text".to_string(), nullable: false, primary_key: false, has_default: false },
+                ColumnSchema { name: "created_at".to_string(), data_type: "timestamptz".to_string(), nullable: true, primary_key: false, has_default: false },
+            ],
+        }
+    }
+
+    fn postgres_generator(read_only: bool) -> DatabaseCapabilityGenerator {
+        DatabaseCapabilityGenerator::new(DatabaseGeneratorConfig {
+            connection_string: "postgres://introspect@localhost/app".to_string(),
+            routing_connection_string: Some("postgres://app@localhost/app".to_string()),
+            read_only,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_generates_parameterized_crud_tools() {
+        let file = postgres_generator(false).generate_from_tables(&[users_table()]).unwrap();
+        let names: Vec<&str> = file.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["list_users", "get_users", "insert_users", "update_users"]);
+
+        let insert = &file.tools[2];
+        assert_eq!(
+            insert.routing.config["query"],
+            "INSERT INTO \"users\" (\"email\", \"created_at\") VALUES ($1, $2::text::timestamptz) RETURNING *"
+        );
+        assert_eq!(insert.routing.config["params"], json!(["email", "created_at"]));
+        assert_eq!(insert.routing.config["read_only"], false);
+        assert_eq!(insert.routing.config["connection_string"], "postgres://app@localhost/app");
+        assert_eq!(insert.input_schema["required"], json!(["email"]));
+        assert_eq!(insert.input_schema["properties"]["created_at"]["type"], json!(["string", "null"]));
+
+        let update = &file.tools[3];
+        assert_eq!(
+            update.routing.config["query"],
+            "UPDATE \"users\" SET \"email\" = CASE WHEN $1 THEN $2 ELSE \"email\" END, \"created_at\" = CASE WHEN $3 THEN $4::text::timestamptz ELSE \"created_at\" END WHERE \"id\" = $5 RETURNING *"
+        );
+        assert_eq!(update.routing.config["params"], json!(["email?", "email", "created_at?", "created_at", "id"]));
+    }
+
+    #[test]
+    fn test_generates_mysql_tools() {
+        let generator = DatabaseCapabilityGenerator::new(DatabaseGeneratorConfig {
+            connection_string: "mysql://app@localhost:3306/shop".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(generator.db_type(), DatabaseType::Mysql);
+        let mut table = users_table();
+        for column in &mut table.columns {
+            column.data_type = match column.name.as_str() {
+                "id" => "int",
+                "email" => "varchar",
+                _ => "datetime",
+            }.to_string();
+        }
+        let file = generator.generate_from_tables(&[table]).unwrap();
+
+        let list = &file.tools[0];
+        assert_eq!(
+            list.routing.config["query"],
+            "SELECT `id`, `email`, `created_at` FROM (SELECT `id`, `email`, `created_at`, ROW_NUMBER() OVER (ORDER BY `id`) AS `_row_number` FROM `users`) AS `numbered` \
+             WHERE `_row_number` > COALESCE(?, 0) AND `_row_number` <= COALESCE(?, 0) + COALESCE(?, 100) \
+             ORDER BY `_row_number`"
+        );
+        assert_eq!(list.routing.config["params"], json!(["offset", "offset", "limit"]));
+        assert_eq!(list.routing.config["db_type"], "mysql");
+
+        let insert = &file.tools[2];
+        assert_eq!(insert.routing.config["query"], "INSERT INTO `users` (`email`, `created_at`) VALUES (?, ?)");
+        let update = &file.tools[3];
+        assert_eq!(
+            update.routing.config["query"],
+            "UPDATE `users` SET `email` = CASE WHEN ? THEN ? ELSE `email` END, `created_at` = CASE WHEN ? THEN ? ELSE `created_at` END WHERE `id` = ?"
+        );
+        assert_eq!(update.input_schema["properties"]["id"]["type"], "integer");
+    }
+
+    #[test]
+    fn test_read_only_mode_only_emits_read_tools() {
+        let file = postgres_generator(true).generate_from_tables(&[users_table()]).unwrap();
+        assert_eq!(file.tools.len(), 2);
+        for tool in &file.tools {
+            assert_eq!(tool.routing.config["read_only"], true);
+            assert_eq!(tool.annotations.as_ref().unwrap()["read_only"], "true");
+        }
+        assert_eq!(file.tools[1].routing.config["query"], "SELECT \"id\", \"email\", \"created_at\" FROM \"users\" WHERE \"id\" = $1");
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_introspection() {
+        let path = std::env::temp_dir().join(format!("magictunnel-dbgen-{}.db", uuid::Uuid::new_v4()));
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE notes (id INTEGER PRIMARY KEY, title TEXT NOT NULL, body TEXT, score REAL DEFAULT 0);",
+            )
+            .unwrap();
+        }
+
+        let generator = DatabaseCapabilityGenerator::new(DatabaseGeneratorConfig {
+            connection_string: path.display().to_string(),
+            ..Default::default()
+        });
+        assert_eq!(generator.db_type(), DatabaseType::Sqlite);
+        let file = generator.generate().await.unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let insert = file.tools.iter().find(|t| t.name == "insert_notes").unwrap();
+        assert_eq!(insert.routing.config["query"], "INSERT INTO \"notes\" (\"title\", \"body\") VALUES (?1, ?2) RETURNING *");
+        assert_eq!(insert.input_schema["required"], json!(["title"]));
+        let list = file.tools.iter().find(|t| t.name == "list_notes").unwrap();
+        assert_eq!(list.routing.config["params"], json!(["limit", "offset"]));
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_update_sets_only_given_columns() {
+        use crate::mcp::ToolCall;
+        use crate::routing::{AgentRouter, DefaultAgentRouter};
+
+        let path = std::env::temp_dir().join(format!("magictunnel-dbgen-{}.db", uuid::Uuid::new_v4()));
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE notes (id INTEGER PRIMARY KEY, title TEXT NOT NULL, body TEXT);
+                 INSERT INTO notes (id, title, body) VALUES (1, 'draft', 'text');",
+            )
+            .unwrap();
+        }
+        let generator = DatabaseCapabilityGenerator::new(DatabaseGeneratorConfig {
+            connection_string: path.display().to_string(),
+            ..Default::default()
+        });
+        let file = generator.generate().await.unwrap();
+        let update = file.tools.iter().find(|t| t.name == "update_notes").unwrap();
+
+        let router = DefaultAgentRouter::new();
+        let agent = router.parse_routing_config(&update.routing).unwrap();
+        let call = ToolCall::new("update_notes".to_string(), json!({"id": 1, "body": null}));
+        let result = router.execute_with_agent(&call, &agent).await.unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert!(result.success, "{:?}", result.error);
+        // The omitted title is kept and the explicit null clears the body
+        assert_eq!(result.data.unwrap()["rows"], json!([{"id": 1, "title": "draft", "body": null}]));
+    }
+}
@@ -0,0 +1,213 @@
+//! Environment variable templating in capability files
+//!
+//! String values in a tool's routing config (URLs, headers, commands, env, ...)
+//! can reference environment variables, resolved when the registry is loaded:
+//!
+//! - `${VAR}` - value of `VAR`
+//! - `${VAR:-default}` - value of `VAR`, or `default` when it is unset or empty
+//! - `$${VAR}` - a literal `${VAR}`
+//!
+//! Secret references (`${env:..}`, `${file:..}`, `${vault:..}`) are not
+//! templates; they are left as-is and resolved when a connection is made.
+//!
+//! Resolved values of credential-looking variables (`GITHUB_TOKEN`,
+//! `DB_PASSWORD`, ...) and of values under credential-looking keys (`token`,
+//! `Authorization`, ...) are collected so that [`redact_values`] can mask them
+//! wherever the resolved definitions are displayed.
+
+use crate::config::secrets::{is_secret_key, REDACTED};
+use crate::error::{ProxyError, Result};
+use crate::registry::types::ToolDefinition;
+use serde_json::Value;
+use std::collections::HashSet;
+use tracing::warn;
+
+/// Resolved values shorter than this are not masked, as they would match
+/// unrelated text
+const MIN_REDACTED_LEN: usize = 4;
+
+/// Resolves `${VAR}` templates and remembers which resolved values are secrets
+#[derive(Debug, Default)]
+pub struct EnvTemplater {
+    strict: bool,
+    secrets: HashSet<String>,
+}
+
+impl EnvTemplater {
+    /// Create a templater; in strict mode an unset variable without default is an error
+    pub fn new(strict: bool) -> Self {
+        Self { strict, secrets: HashSet::new() }
+    }
+
+    /// Resolve the templates in a tool's routing config
+    pub fn apply(&mut self, tool: &mut ToolDefinition) -> Result<()> {
+        self.interpolate_value(&mut tool.routing.config, false)
+            .map_err(|e| ProxyError::config(format!("Tool '{}': {}", tool.name, e)))
+    }
+
+    /// Resolve the templates in every string of `value`
+    pub fn interpolate_value(&mut self, value: &mut Value, secret: bool) -> Result<()> {
+        match value {
+            Value::String(s) => {
+                if s.contains("${") {
+                    *s = self.interpolate(s, secret)?;
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.interpolate_value(item, secret)?;
+                }
+            }
+            Value::Object(object) => {
+                for (key, item) in object.iter_mut() {
+                    let secret = secret || is_secret_key(key) || key.eq_ignore_ascii_case("authorization");
+                    self.interpolate_value(item, secret)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Resolve the templates in a string
+    ///
+    /// `secret` marks the string as a credential, so every resolved value in
+    /// it is collected for redaction.
+    pub fn interpolate(&mut self, value: &str, secret: bool) -> Result<String> {
+        let mut result = String::with_capacity(value.len());
+        let mut rest = value;
+
+        while let Some(start) = rest.find("${") {
+            // `$${VAR}` escapes the template
+            if rest[..start].ends_with('$') {
+                result.push_str(&rest[..start - 1]);
+                result.push_str("${");
+                rest = &rest[start + 2..];
+                continue;
+            }
+
+            result.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('}') else {
+                result.push_str(&rest[start..]);
+                return Ok(result);
+            };
+            let template = &rest[start..start + end + 1];
+            let expression = &template[2..template.len() - 1];
+            rest = &rest[start + end + 1..];
+
+            let (name, default) = match expression.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (expression, None),
+            };
+            // Anything else (e.g. `${env:VAR}` secret references) is not a template
+            if !is_variable_name(name) {
+                result.push_str(template);
+                continue;
+            }
+
+            match std::env::var(name).ok().filter(|v| !v.is_empty()) {
+                Some(resolved) => {
+                    if secret || is_secret_key(name) {
+                        self.secrets.insert(resolved.clone());
+                    }
+                    result.push_str(&resolved);
+                }
+                None => match default {
+                    Some(default) => result.push_str(default),
+                    None if self.strict => {
+                        return Err(ProxyError::config(format!("Environment variable '{}' is not set", name)));
+                    }
+                    None => {
+                        warn!("Environment variable '{}' is not set; keeping '{}'", name, template);
+                        result.push_str(template);
+                    }
+                },
+            }
+        }
+
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// Resolved values to redact
+    pub fn into_secrets(self) -> HashSet<String> {
+        self.secrets
+    }
+}
+
+/// Replace resolved secret values in every string of `value` with [`REDACTED`]
+pub fn redact_values(value: &mut Value, secrets: &HashSet<String>) {
+    match value {
+        Value::String(s) => {
+            for secret in secrets.iter().filter(|secret| secret.len() >= MIN_REDACTED_LEN) {
+                if s.contains(secret.as_str()) {
+                    *s = s.replace(secret.as_str(), REDACTED);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| redact_values(item, secrets)),
+        Value::Object(object) => object.values_mut().for_each(|item| redact_values(item, secrets)),
+        _ => {}
+    }
+}
+
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_interpolate() {
+        std::env::set_var("MAGICTUNNEL_TEST_TEMPLATE_HOST", "api.example.com");
+        let mut templater = EnvTemplater::new(false);
+
+        assert_eq!(
+            templater.interpolate("https://${MAGICTUNNEL_TEST_TEMPLATE_HOST}/v1", false).unwrap(),
+            "https://api.example.com/v1"
+        );
+        assert_eq!(
+            templater.interpolate("${MAGICTUNNEL_TEST_TEMPLATE_UNSET:-localhost}:8080", false).unwrap(),
+            "localhost:8080"
+        );
+        assert_eq!(templater.interpolate("$${MAGICTUNNEL_TEST_TEMPLATE_HOST}", false).unwrap(), "${MAGICTUNNEL_TEST_TEMPLATE_HOST}");
+        assert_eq!(templater.interpolate("${env:GITHUB_TOKEN}", false).unwrap(), "${env:GITHUB_TOKEN}");
+        assert_eq!(templater.interpolate("{param}", false).unwrap(), "{param}");
+        assert_eq!(
+            templater.interpolate("${MAGICTUNNEL_TEST_TEMPLATE_UNSET}", false).unwrap(),
+            "${MAGICTUNNEL_TEST_TEMPLATE_UNSET}"
+        );
+        assert!(templater.into_secrets().is_empty());
+    }
+
+    #[test]
+    fn test_strict_mode_fails_on_missing_variables() {
+        let mut templater = EnvTemplater::new(true);
+        assert!(templater.interpolate("${MAGICTUNNEL_TEST_TEMPLATE_UNSET}", false).is_err());
+        assert_eq!(templater.interpolate("${MAGICTUNNEL_TEST_TEMPLATE_UNSET:-}", false).unwrap(), "");
+    }
+
+    #[test]
+    fn test_resolved_secrets_are_redacted() {
+        std::env::set_var("MAGICTUNNEL_TEST_TEMPLATE_TOKEN", "tok-12345");
+        std::env::set_var("MAGICTUNNEL_TEST_TEMPLATE_CREDENTIAL", "cred-67890");
+        let mut templater = EnvTemplater::new(false);
+
+        let mut config = json!({
+            "url": "https://api.example.com/?key=${MAGICTUNNEL_TEST_TEMPLATE_TOKEN}",
+            "headers": {"Authorization": "Bearer ${MAGICTUNNEL_TEST_TEMPLATE_CREDENTIAL}"}
+        });
+        templater.interpolate_value(&mut config, false).unwrap();
+        assert_eq!(config["headers"]["Authorization"], "Bearer cred-67890");
+
+        let secrets = templater.into_secrets();
+        redact_values(&mut config, &secrets);
+        assert_eq!(config["url"], "https://api.example.com/?key=***");
+        assert_eq!(config["headers"]["Authorization"], "Bearer ***");
+    }
+}
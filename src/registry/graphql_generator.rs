@@ -1,6 +1,6 @@
 use crate::registry::types::{CapabilityFile, ToolDefinition, RoutingConfig, FileMetadata};
 use crate::error::ProxyError;
-use serde_json::{Value, Map};
+use serde_json::{json, Value, Map};
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
@@ -56,6 +56,8 @@ pub struct GraphQLCapabilityGenerator {
     interface_types: HashMap<String, InterfaceType>,
     /// Union type definitions
     union_types: HashMap<String, UnionType>,
+    /// Object type definitions (used for output schemas)
+    object_types: HashMap<String, ObjectType>,
     /// Custom scalar types
     custom_scalars: std::collections::HashSet<String>,
     /// Whether to validate introspection schemas comprehensively
@@ -325,6 +327,13 @@ pub struct UnionType {
     pub possible_types: Vec<String>,
 }
 
+#[derive(Debug, Clone)]
+pub struct ObjectType {
+    pub name: String,
+    pub description: Option<String>,
+    pub fields: Vec<InterfaceField>,
+}
+
 #[derive(Debug, Clone)]
 pub struct SchemaExtension {
     pub extension_type: ExtensionType,
@@ -354,6 +363,7 @@ impl GraphQLCapabilityGenerator {
             enum_types: HashMap::new(),
             interface_types: HashMap::new(),
             union_types: HashMap::new(),
+            object_types: HashMap::new(),
             custom_scalars: std::collections::HashSet::new(),
             validate_introspection: true, // Default to true for comprehensive validation
         }
@@ -416,6 +426,9 @@ impl GraphQLCapabilityGenerator {
         // Extract Union type definitions
         self.extract_union_types_from_sdl(&merged_schema)?;
 
+        // Extract Object type definitions
+        self.extract_object_types_from_sdl(&merged_schema)?;
+
         // Parse Query type
         if let Some(query_operations) = self.extract_operations_from_sdl(&merged_schema, "Query")? {
             operations.extend(query_operations);
@@ -5060,6 +5073,15 @@ impl GraphQLCapabilityGenerator {
                         let union_type = self.parse_union_from_introspection(type_def)?;
                         self.union_types.insert(type_name.to_string(), union_type);
                     }
+                    "OBJECT" if !type_name.starts_with("__") => {
+                        // Object types share the shape of interfaces (name, description, fields)
+                        let interface_type = self.parse_interface_from_introspection(type_def)?;
+                        self.object_types.insert(type_name.to_string(), ObjectType {
+                            name: interface_type.name,
+                            description: interface_type.description,
+                            fields: interface_type.fields,
+                        });
+                    }
                     "SCALAR" => {
                         // Add custom scalar to our known types
                         self.custom_scalars.insert(type_name.to_string());
@@ -5995,6 +6017,56 @@ impl GraphQLCapabilityGenerator {
         })
     }
 
+    /// Extract Object type definitions from SDL schema
+    ///
+    /// Only `type` keywords at the start of a line are considered so that the
+    /// word in descriptions is not mistaken for a definition.
+    fn extract_object_types_from_sdl(&mut self, schema_sdl: &str) -> Result<(), ProxyError> {
+        let mut line_start = 0;
+        for line in schema_sdl.split_inclusive('\n') {
+            let content = &schema_sdl[line_start..];
+            line_start += line.len();
+
+            let Some(after_type) = line.trim_start().strip_prefix("type ") else { continue };
+            let Some(name_end) = after_type.find([' ', '{', '@', '\n', '\r']) else { continue };
+            let object_name = after_type[..name_end].trim().to_string();
+            if matches!(object_name.as_str(), "Query" | "Mutation" | "Subscription") {
+                continue;
+            }
+
+            // Find the opening brace and its matching closing brace
+            let Some(brace_start) = content.find('{') else { continue };
+            let content = &content[brace_start + 1..];
+            let mut brace_count = 1;
+            let mut end_pos = 0;
+
+            for (i, ch) in content.char_indices() {
+                match ch {
+                    '{' => brace_count += 1,
+                    '}' => {
+                        brace_count -= 1;
+                        if brace_count == 0 {
+                            end_pos = i;
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if end_pos > 0 {
+                let parsed = self.parse_interface_from_sdl(&object_name, &content[..end_pos])?;
+                self.object_types.insert(object_name.clone(), ObjectType {
+                    name: object_name,
+                    description: None,
+                    fields: parsed.fields,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     /// Extract Union type definitions from SDL schema
     fn extract_union_types_from_sdl(&mut self, schema_sdl: &str) -> Result<(), ProxyError> {
         // Find all union type definitions
@@ -6708,11 +6780,17 @@ impl GraphQLCapabilityGenerator {
         // Generate JSON schema for input parameters
         let input_schema = self.generate_input_schema(&operation.arguments)?;
 
-        // Create routing configuration for GraphQL HTTP request
-        let routing = RoutingConfig::new(
-            "http".to_string(),
-            self.create_graphql_routing_config(&operation)?,
-        );
+        // Queries and mutations are plain HTTP requests; subscriptions need the GraphQL agent's WebSocket transport
+        let routing = match operation.operation_type {
+            OperationType::Subscription => RoutingConfig::new(
+                "graphql".to_string(),
+                self.create_graphql_subscription_routing_config(&operation)?,
+            ),
+            _ => RoutingConfig::new(
+                "http".to_string(),
+                self.create_graphql_routing_config(&operation)?,
+            ),
+        };
 
         // Build description with directive-based enhancements
         let mut description = operation.description.unwrap_or_else(|| {
@@ -6736,6 +6814,7 @@ impl GraphQLCapabilityGenerator {
 
         // Create annotations from directives
         let annotations = self.create_annotations_from_directives(&operation.directives)?;
+        let output_schema = self.generate_output_schema(&operation)?;
 
         Ok(ToolDefinition {
             name: tool_name,
@@ -6745,9 +6824,95 @@ impl GraphQLCapabilityGenerator {
             annotations,
             hidden: false, // GraphQL tools are visible by default
             enabled: true, // GraphQL tools are enabled by default
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: Some(output_schema),
+            cache: None,
+            background: false,
+            cost: None,
         })
     }
 
+    /// Generate the output schema: the GraphQL response envelope with the
+    /// operation's return type under `data`
+    ///
+    /// Fields are never marked required since the routed query selects only
+    /// part of the returned object.
+    fn generate_output_schema(&self, operation: &GraphQLOperation) -> Result<Value, ProxyError> {
+        let mut visited = Vec::new();
+        let result_schema = self.graphql_output_type_to_json_schema(&operation.return_type, &mut visited)?;
+
+        Ok(json!({
+            "type": "object",
+            "properties": {
+                "data": {
+                    "type": ["object", "null"],
+                    "properties": { operation.name.clone(): result_schema }
+                },
+                "errors": {
+                    "type": "array",
+                    "items": { "type": "object" }
+                }
+            }
+        }))
+    }
+
+    /// Convert a GraphQL return type to JSON Schema, resolving object types
+    ///
+    /// `visited` holds the object types being expanded; recursive references
+    /// are emitted as plain objects.
+    fn graphql_output_type_to_json_schema(&self, graphql_type: &str, visited: &mut Vec<String>) -> Result<Value, ProxyError> {
+        let (graphql_type, non_null) = match graphql_type.strip_suffix('!') {
+            Some(inner) => (inner, true),
+            None => (graphql_type, false),
+        };
+
+        let schema = if graphql_type.starts_with('[') && graphql_type.ends_with(']') {
+            let items = self.graphql_output_type_to_json_schema(&graphql_type[1..graphql_type.len() - 1], visited)?;
+            json!({ "type": "array", "items": items })
+        } else if let Some(object_type) = self.object_types.get(graphql_type) {
+            let mut schema = Map::new();
+            schema.insert("type".to_string(), json!("object"));
+            if let Some(description) = &object_type.description {
+                schema.insert("description".to_string(), json!(description));
+            }
+            if !visited.contains(&object_type.name) {
+                visited.push(object_type.name.clone());
+                let mut properties = Map::new();
+                properties.insert("__typename".to_string(), json!({ "type": "string" }));
+                for field in &object_type.fields {
+                    let field_type = if field.required {
+                        format!("{}!", field.field_type.trim_end_matches('!'))
+                    } else {
+                        field.field_type.clone()
+                    };
+                    properties.insert(field.name.clone(), self.graphql_output_type_to_json_schema(&field_type, visited)?);
+                }
+                visited.pop();
+                schema.insert("properties".to_string(), Value::Object(properties));
+            }
+            Value::Object(schema)
+        } else if self.interface_types.contains_key(graphql_type) || self.union_types.contains_key(graphql_type) {
+            json!({ "type": "object", "description": format!("GraphQL type: {}", graphql_type) })
+        } else if self.is_built_in_type(graphql_type)
+            || self.enum_types.contains_key(graphql_type)
+            || self.custom_scalars.contains(graphql_type)
+            || self.handle_custom_scalar(graphql_type).is_some() {
+            self.graphql_type_to_json_schema(graphql_type)?
+        } else {
+            // Unknown type: accept any value rather than guess its shape
+            json!({ "description": format!("GraphQL type: {}", graphql_type) })
+        };
+
+        if non_null {
+            Ok(schema)
+        } else {
+            Ok(json!({ "anyOf": [schema, { "type": "null" }] }))
+        }
+    }
+
     /// Generate JSON schema for operation arguments
     fn generate_input_schema(&self, arguments: &[GraphQLArgument]) -> Result<Value, ProxyError> {
         let mut properties = Map::new();
@@ -7159,6 +7324,56 @@ impl GraphQLCapabilityGenerator {
         // Set headers
         let mut headers = Map::new();
         headers.insert("Content-Type".to_string(), Value::String("application/json".to_string()));
+        headers.extend(self.create_auth_headers());
+        
+        config.insert("headers".to_string(), Value::Object(headers));
+        
+        // Create GraphQL query body template
+        let query_template = self.create_graphql_query_template(operation)?;
+        config.insert("body".to_string(), Value::String(query_template));
+        
+        Ok(Value::Object(config))
+    }
+
+    /// Create GraphQL agent routing configuration for a subscription operation
+    ///
+    /// Arguments are passed as GraphQL variables; the agent uses the tool
+    /// arguments as variables when none are configured.
+    fn create_graphql_subscription_routing_config(&self, operation: &GraphQLOperation) -> Result<Value, ProxyError> {
+        let variable_definitions: Vec<String> = operation.arguments.iter()
+            .map(|arg| format!("${}: {}{}", arg.name, arg.arg_type, if arg.required { "!" } else { "" }))
+            .collect();
+        let field_arguments: Vec<String> = operation.arguments.iter()
+            .map(|arg| format!("{}: ${}", arg.name, arg.name))
+            .collect();
+
+        let query = if operation.arguments.is_empty() {
+            format!("subscription {} {{ {} {{ __typename }} }}", operation.name, operation.name)
+        } else {
+            format!(
+                "subscription {}({}) {{ {}({}) {{ __typename }} }}",
+                operation.name,
+                variable_definitions.join(", "),
+                operation.name,
+                field_arguments.join(", ")
+            )
+        };
+
+        Ok(serde_json::json!({
+            "endpoint": self.endpoint_url,
+            "query": query,
+            "operation_name": operation.name,
+            "headers": self.create_auth_headers(),
+            "subscription": {
+                "max_events": 10,
+                "max_duration_secs": 60
+            }
+        }))
+    }
+
+    /// Headers for the configured authentication
+    fn create_auth_headers(&self) -> Map<String, Value> {
+        let mut headers = Map::new();
         
         // Add authentication headers if configured
         if let Some(auth) = &self.auth_config {
@@ -7184,13 +7399,7 @@ impl GraphQLCapabilityGenerator {
             }
         }
         
-        config.insert("headers".to_string(), Value::Object(headers));
-        
-        // Create GraphQL query body template
-        let query_template = self.create_graphql_query_template(operation)?;
-        config.insert("body".to_string(), Value::String(query_template));
-        
-        Ok(Value::Object(config))
+        headers
     }
 
     /// Create GraphQL query template for the operation
@@ -7834,6 +8043,37 @@ mod tests {
         assert_eq!(generator.tool_prefix, Some("gql".to_string()));
     }
 
+    #[test]
+    fn test_output_schema_from_return_type() {
+        let mut generator = GraphQLCapabilityGenerator::new("https://api.example.com/graphql".to_string());
+
+        let schema = r#"
+            type Query {
+                getUser(id: ID!): User
+            }
+
+            type User {
+                id: ID!
+                name: String
+                friends: [User!]
+            }
+        "#;
+
+        let capability_file = generator.generate_from_sdl(schema).unwrap();
+        let output_schema = capability_file.tools[0].output_schema.as_ref().unwrap();
+        assert_eq!(output_schema["type"], "object");
+
+        let user = &output_schema["properties"]["data"]["properties"]["getUser"]["anyOf"][0];
+        assert_eq!(user["type"], "object");
+        assert_eq!(user["properties"]["id"]["type"], "string");
+        assert!(user.get("required").is_none());
+
+        // The recursive reference is not expanded again
+        let friend = &user["properties"]["friends"]["anyOf"][0]["items"];
+        assert_eq!(friend["type"], "object");
+        assert!(friend.get("properties").is_none());
+    }
+
     #[test]
     fn test_parse_simple_sdl_schema() {
         let mut generator = GraphQLCapabilityGenerator::new("https://api.example.com/graphql".to_string());
@@ -10728,6 +10968,38 @@ type User {
         assert_eq!(query_multi_args, expected_json_multi_args);
     }
 
+    #[test]
+    fn test_subscription_routing_uses_graphql_agent() {
+        let mut generator = GraphQLCapabilityGenerator::new("https://api.example.com/graphql".to_string());
+        let schema = r#"
+            type Message {
+                id: ID!
+            }
+
+            type Query {
+                ping: String
+            }
+
+            type Subscription {
+                messageAddedToChannel(channelId: ID!, limit: Int): Message
+            }
+        "#;
+
+        let capability_file = generator.generate_from_sdl(schema).unwrap();
+        let ping = capability_file.tools.iter().find(|tool| tool.name == "ping").unwrap();
+        assert_eq!(ping.routing.routing_type(), "http");
+
+        let subscription = capability_file.tools.iter().find(|tool| tool.name == "messageAddedToChannel").unwrap();
+        assert_eq!(subscription.routing.routing_type(), "graphql");
+        let config = &subscription.routing.config;
+        assert_eq!(config["endpoint"], "https://api.example.com/graphql");
+        assert_eq!(
+            config["query"],
+            "subscription messageAddedToChannel($channelId: ID!, $limit: Int) { messageAddedToChannel(channelId: $channelId, limit: $limit) { __typename } }"
+        );
+        assert_eq!(config["subscription"]["max_events"], 10);
+    }
+
     #[test]
     fn test_spec_compliant_error_reporting() {
         let mut generator = GraphQLCapabilityGenerator::new("https://api.example.com/graphql".to_string());
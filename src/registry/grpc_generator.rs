@@ -4,7 +4,7 @@
 //! It supports parsing .proto files and converting gRPC service methods into MCP tools.
 
 use crate::error::{ProxyError, Result};
-use crate::registry::types::{CapabilityFile, FileMetadata, ToolDefinition, RoutingConfig};
+use crate::registry::types::{CapabilityFile, CAPABILITY_SCHEMA_VERSION, FileMetadata, ToolDefinition, RoutingConfig};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
@@ -273,6 +273,7 @@ impl GrpcCapabilityGenerator {
         
         // Create and return the capability file
         Ok(CapabilityFile {
+            schema_version: Some(CAPABILITY_SCHEMA_VERSION),
             metadata: Some(metadata),
             tools: all_tools,
         })
@@ -463,6 +464,7 @@ impl GrpcCapabilityGenerator {
             .tags(vec!["grpc".to_string(), service.name.to_lowercase()]);
         
         Ok(CapabilityFile {
+            schema_version: Some(CAPABILITY_SCHEMA_VERSION),
             metadata: Some(file_metadata),
             tools,
         })
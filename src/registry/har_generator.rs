@@ -0,0 +1,567 @@
+//! HAR Capability Generator
+//!
+//! This module turns HTTP Archive (HAR) captures into MCP tool definitions. Requests are
+//! grouped by method and path template (ID-like path segments such as `42` or UUIDs become
+//! `{user_id}` style parameters), and input schemas are inferred from the query, form and
+//! JSON body parameters actually observed. A parameter is required only if it appeared in
+//! every captured request of its group.
+//!
+//! Captured headers (cookies, tokens) are deliberately not copied into the generated tools.
+
+use crate::error::{ProxyError, Result};
+use crate::registry::types::{CapabilityFile, FileMetadata, ToolDefinition, RoutingConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Maximum number of distinct example values kept per parameter
+const MAX_EXAMPLES: usize = 3;
+
+/// File extensions of static assets skipped by default
+const STATIC_EXTENSIONS: &[&str] = &[
+    ".js", ".css", ".png", ".jpg", ".jpeg", ".gif", ".svg", ".ico", ".woff", ".woff2", ".ttf", ".map", ".webp",
+];
+
+/// Configuration for the HAR capability generator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarGeneratorConfig {
+    /// Base URL for generated tools (defaults to the scheme and host of the captured requests)
+    pub base_url: Option<String>,
+    /// Only import requests to these hosts
+    pub include_hosts: Option<Vec<String>>,
+    /// Only import these HTTP methods
+    pub method_filter: Option<Vec<String>>,
+    /// Skip images, scripts, stylesheets and fonts
+    pub skip_static_assets: bool,
+    /// Tool name prefix
+    pub tool_prefix: Option<String>,
+}
+
+impl Default for HarGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            include_hosts: None,
+            method_filter: None,
+            skip_static_assets: true,
+            tool_prefix: None,
+        }
+    }
+}
+
+/// Observed values of one parameter across a request group
+#[derive(Debug, Clone, Default)]
+struct ObservedParam {
+    schema: Value,
+    count: usize,
+    examples: Vec<Value>,
+}
+
+/// Requests sharing a method, origin and path template
+#[derive(Debug, Clone)]
+struct RequestShape {
+    method: String,
+    origin: String,
+    template: String,
+    path_params: Vec<String>,
+    count: usize,
+    query: BTreeMap<String, ObservedParam>,
+    body: BTreeMap<String, ObservedParam>,
+    /// Most specific body media type seen
+    body_mime_type: Option<String>,
+}
+
+/// HAR capability generator
+pub struct HarCapabilityGenerator {
+    /// Generator configuration
+    pub config: HarGeneratorConfig,
+}
+
+impl HarCapabilityGenerator {
+    /// Create a new HAR capability generator
+    pub fn new(config: HarGeneratorConfig) -> Self {
+        Self { config }
+    }
+
+    /// Generate a capability file from HAR JSON content
+    pub fn generate_from_har(&self, content: &str) -> Result<CapabilityFile> {
+        let har: Value = serde_json::from_str(content)
+            .map_err(|e| ProxyError::config(format!("Invalid HAR file: {}", e)))?;
+        let entries = har
+            .pointer("/log/entries")
+            .and_then(Value::as_array)
+            .ok_or_else(|| ProxyError::config("Invalid HAR file: missing log.entries".to_string()))?;
+
+        // Keyed by method + origin + template; insertion order is kept for stable output
+        let mut shapes: Vec<RequestShape> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+
+        for entry in entries {
+            let Some(request) = entry.get("request") else { continue };
+            let method = request.get("method").and_then(Value::as_str).unwrap_or("GET").to_uppercase();
+            let Some(url) = request.get("url").and_then(Value::as_str).and_then(|u| url::Url::parse(u).ok()) else {
+                continue;
+            };
+            if !self.include_request(&method, &url, entry) {
+                continue;
+            }
+
+            let origin = url.origin().ascii_serialization();
+            let (template, path_params, path_values) = path_template(url.path());
+            let key = format!("{} {}{}", method, origin, template);
+            let shape_index = *index.entry(key).or_insert_with(|| {
+                shapes.push(RequestShape {
+                    method: method.clone(),
+                    origin: origin.clone(),
+                    template: template.clone(),
+                    path_params: path_params.clone(),
+                    count: 0,
+                    query: BTreeMap::new(),
+                    body: BTreeMap::new(),
+                    body_mime_type: None,
+                });
+                shapes.len() - 1
+            });
+            let shape = &mut shapes[shape_index];
+            shape.count += 1;
+
+            // Path values are only collected as examples
+            for (name, value) in path_params.iter().zip(path_values) {
+                observe(&mut shape.query, name, &infer_scalar(&value));
+            }
+            for (name, value) in url.query_pairs() {
+                observe(&mut shape.query, &name, &infer_scalar(&value));
+            }
+            if let Some(post_data) = request.get("postData") {
+                observe_body(shape, post_data);
+            }
+        }
+
+        let mut tools = Vec::new();
+        let mut names: HashMap<String, usize> = HashMap::new();
+        for shape in &shapes {
+            let mut tool = self.tool(shape)?;
+            // The same template on different hosts would produce the same name
+            let seen = names.entry(tool.name.clone()).or_insert(0);
+            *seen += 1;
+            if *seen > 1 {
+                tool.name = format!("{}_{}", tool.name, seen);
+            }
+            tools.push(tool);
+        }
+
+        let metadata = FileMetadata::with_name("har-capabilities".to_string())
+            .description(format!("Auto-generated from {} captured HTTP requests", entries.len()))
+            .version("1.0.0".to_string())
+            .author("HAR Capability Generator".to_string())
+            .tags(vec!["har".to_string(), "http".to_string()]);
+
+        CapabilityFile::with_metadata(metadata, tools)
+    }
+
+    fn include_request(&self, method: &str, url: &url::Url, entry: &Value) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        if let Some(hosts) = &self.config.include_hosts {
+            if !url.host_str().is_some_and(|host| hosts.iter().any(|h| h == host)) {
+                return false;
+            }
+        }
+        if let Some(methods) = &self.config.method_filter {
+            if !methods.iter().any(|m| m.eq_ignore_ascii_case(method)) {
+                return false;
+            }
+        }
+        if self.config.skip_static_assets {
+            let path = url.path().to_lowercase();
+            if STATIC_EXTENSIONS.iter().any(|ext| path.ends_with(ext)) {
+                return false;
+            }
+            let mime_type = entry.pointer("/response/content/mimeType").and_then(Value::as_str).unwrap_or("");
+            if ["image/", "font/", "text/css", "javascript"].iter().any(|m| mime_type.contains(m)) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn tool(&self, shape: &RequestShape) -> Result<ToolDefinition> {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        let mut query_params = Vec::new();
+        let mut body_params = Vec::new();
+
+        for (params, is_query) in [(&shape.query, true), (&shape.body, false)] {
+            for (name, param) in params {
+                if properties.contains_key(name) {
+                    continue;
+                }
+                let mut schema = param.schema.clone();
+                if let Some(obj) = schema.as_object_mut() {
+                    if !param.examples.is_empty() {
+                        obj.insert("examples".to_string(), json!(param.examples));
+                    }
+                }
+                properties.insert(name.clone(), schema);
+                let is_path = shape.path_params.contains(name);
+                if is_path || param.count == shape.count {
+                    required.push(name.clone());
+                }
+                if is_path {
+                    continue;
+                }
+                if is_query {
+                    query_params.push(name.clone());
+                } else {
+                    body_params.push(name.clone());
+                }
+            }
+        }
+
+        let base_url = self.config.base_url.as_deref().unwrap_or(&shape.origin).trim_end_matches('/');
+        let mut config = Map::new();
+        config.insert("method".to_string(), json!(shape.method));
+        config.insert("url".to_string(), json!(format!("{}{}", base_url, shape.template)));
+        if !shape.path_params.is_empty() {
+            config.insert("path_params".to_string(), json!(shape.path_params));
+        }
+        if !query_params.is_empty() {
+            config.insert("query_params".to_string(), json!(query_params));
+        }
+        if let Some(mime_type) = &shape.body_mime_type {
+            config.insert("body_params".to_string(), json!(body_params));
+            let mut headers = Map::new();
+            headers.insert("Content-Type".to_string(), json!(mime_type));
+            config.insert("headers".to_string(), Value::Object(headers));
+        }
+
+        let mut annotations = HashMap::new();
+        annotations.insert("source".to_string(), "har".to_string());
+        annotations.insert("observed_requests".to_string(), shape.count.to_string());
+
+        let name = tool_name(&shape.method, &shape.template);
+        let name = match &self.config.tool_prefix {
+            Some(prefix) => format!("{}_{}", prefix, name),
+            None => name,
+        };
+
+        Ok(ToolDefinition {
+            name,
+            description: format!(
+                "{} {} (inferred from {} captured request{})",
+                shape.method,
+                shape.template,
+                shape.count,
+                if shape.count == 1 { "" } else { "s" }
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": properties,
+                "required": required
+            }),
+            routing: RoutingConfig::new("http".to_string(), Value::Object(config)),
+            annotations: Some(annotations),
+            hidden: true, // Generated tools are hidden by default (consistent with other generators)
+            enabled: true,
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
+        })
+    }
+}
+
+fn observe_body(shape: &mut RequestShape, post_data: &Value) {
+    let mime_type = post_data.get("mimeType").and_then(Value::as_str).unwrap_or("").to_string();
+    let text = post_data.get("text").and_then(Value::as_str).unwrap_or("");
+
+    if mime_type.contains("json") || text.trim_start().starts_with('{') {
+        if let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(text) {
+            for (name, value) in &fields {
+                observe(&mut shape.body, name, value);
+            }
+            shape.body_mime_type = Some("application/json".to_string());
+        }
+        return;
+    }
+
+    // Form posts: HAR lists fields in `params`, some tools only fill in `text`
+    let fields: Vec<(String, String)> = match post_data.get("params").and_then(Value::as_array) {
+        Some(params) if !params.is_empty() => params
+            .iter()
+            .filter_map(|p| Some((p.get("name")?.as_str()?.to_string(), p.get("value")?.as_str().unwrap_or("").to_string())))
+            .collect(),
+        _ if mime_type.contains("x-www-form-urlencoded") => url::form_urlencoded::parse(text.as_bytes())
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    if !fields.is_empty() {
+        for (name, value) in fields {
+            observe(&mut shape.body, &name, &infer_scalar(&value));
+        }
+        shape.body_mime_type.get_or_insert_with(|| "application/x-www-form-urlencoded".to_string());
+    }
+}
+
+fn observe(params: &mut BTreeMap<String, ObservedParam>, name: &str, value: &Value) {
+    let param = params.entry(name.to_string()).or_default();
+    let schema = schema_for_value(value);
+    param.schema = if param.count == 0 { schema } else { merge_schemas(&param.schema, &schema) };
+    param.count += 1;
+    let is_scalar = !value.is_object() && !value.is_array() && !value.is_null();
+    if is_scalar && param.examples.len() < MAX_EXAMPLES && !param.examples.contains(value) {
+        param.examples.push(value.clone());
+    }
+}
+
+/// Interpret a query/form/path string as the JSON scalar it most likely is
+fn infer_scalar(value: &str) -> Value {
+    if let Ok(i) = value.parse::<i64>() {
+        // Leading zeros (zip codes, account numbers) are identifiers, not numbers
+        if !(value.len() > 1 && value.starts_with('0')) {
+            return json!(i);
+        }
+    }
+    if let Ok(f) = value.parse::<f64>() {
+        if f.is_finite() && value.contains('.') {
+            return json!(f);
+        }
+    }
+    match value {
+        "true" | "false" => json!(value == "true"),
+        _ => json!(value),
+    }
+}
+
+fn schema_for_value(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({"type": "null"}),
+        Value::Bool(_) => json!({"type": "boolean"}),
+        Value::Number(n) if n.is_i64() || n.is_u64() => json!({"type": "integer"}),
+        Value::Number(_) => json!({"type": "number"}),
+        Value::String(_) => json!({"type": "string"}),
+        Value::Array(items) => {
+            let item_schema = items
+                .iter()
+                .map(schema_for_value)
+                .reduce(|a, b| merge_schemas(&a, &b))
+                .unwrap_or_else(|| json!({}));
+            json!({"type": "array", "items": item_schema})
+        }
+        Value::Object(fields) => {
+            let properties: Map<String, Value> = fields.iter().map(|(k, v)| (k.clone(), schema_for_value(v))).collect();
+            json!({"type": "object", "properties": properties})
+        }
+    }
+}
+
+/// Combine two inferred schemas: integer widens to number, conflicting types become a type list
+fn merge_schemas(a: &Value, b: &Value) -> Value {
+    if a == b {
+        return a.clone();
+    }
+    let type_of = |s: &Value| s.get("type").and_then(Value::as_str).map(String::from);
+    match (type_of(a).as_deref(), type_of(b).as_deref()) {
+        (Some("integer"), Some("number")) | (Some("number"), Some("integer")) => json!({"type": "number"}),
+        (Some("object"), Some("object")) => {
+            let mut properties = a.get("properties").and_then(Value::as_object).cloned().unwrap_or_default();
+            for (key, schema) in b.get("properties").and_then(Value::as_object).into_iter().flatten() {
+                let merged = match properties.get(key) {
+                    Some(existing) => merge_schemas(existing, schema),
+                    None => schema.clone(),
+                };
+                properties.insert(key.clone(), merged);
+            }
+            json!({"type": "object", "properties": properties})
+        }
+        (Some("array"), Some("array")) => {
+            let items = merge_schemas(a.get("items").unwrap_or(&json!({})), b.get("items").unwrap_or(&json!({})));
+            json!({"type": "array", "items": items})
+        }
+        _ => {
+            let mut types: Vec<Value> = Vec::new();
+            for schema in [a, b] {
+                match schema.get("type") {
+                    Some(Value::Array(list)) => types.extend(list.iter().cloned()),
+                    Some(t) => types.push(t.clone()),
+                    None => {}
+                }
+            }
+            let mut unique: Vec<Value> = Vec::new();
+            for t in types {
+                if !unique.contains(&t) {
+                    unique.push(t);
+                }
+            }
+            if unique.is_empty() {
+                json!({})
+            } else {
+                json!({"type": unique})
+            }
+        }
+    }
+}
+
+/// Replace ID-like path segments with named parameters, returning the template,
+/// parameter names and the concrete values that were replaced
+fn path_template(path: &str) -> (String, Vec<String>, Vec<String>) {
+    let mut template = String::new();
+    let mut params = Vec::new();
+    let mut values = Vec::new();
+    let mut previous = "";
+
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        template.push('/');
+        if is_id_segment(segment) {
+            let mut name = match singular(previous) {
+                Some(resource) => format!("{}_id", resource),
+                None => "id".to_string(),
+            };
+            if params.contains(&name) {
+                name = format!("{}_{}", name, params.len() + 1);
+            }
+            template.push_str(&format!("{{{}}}", name));
+            params.push(name);
+            values.push(segment.to_string());
+        } else {
+            template.push_str(segment);
+        }
+        previous = segment;
+    }
+
+    if template.is_empty() {
+        template.push('/');
+    }
+    (template, params, values)
+}
+
+fn is_id_segment(segment: &str) -> bool {
+    let digits = segment.chars().filter(|c| c.is_ascii_digit()).count();
+    let is_hex = segment.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+    segment.chars().all(|c| c.is_ascii_digit())
+        || (segment.len() == 36 && is_hex && segment.matches('-').count() == 4)
+        || (segment.len() >= 16 && is_hex && digits > 0)
+        // Opaque tokens such as `aZ3kP9xQ2mLw8vB1` mixing letters and digits
+        || (segment.len() >= 20 && digits >= 3 && segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+}
+
+fn singular(segment: &str) -> Option<String> {
+    if segment.is_empty() || is_id_segment(segment) || !segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return None;
+    }
+    let lower = segment.to_lowercase().replace('-', "_");
+    Some(if let Some(stem) = lower.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if lower.ends_with("ss") {
+        lower
+    } else {
+        lower.strip_suffix('s').map(String::from).unwrap_or(lower)
+    })
+}
+
+fn tool_name(method: &str, template: &str) -> String {
+    let path: Vec<String> = template
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.trim_matches(|c| c == '{' || c == '}'))
+        .map(|s| s.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' }).collect())
+        .collect();
+    if path.is_empty() {
+        format!("{}_root", method.to_lowercase())
+    } else {
+        format!("{}_{}", method.to_lowercase(), path.join("_"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HAR: &str = r#"{
+        "log": {
+            "version": "1.2",
+            "entries": [
+                {"request": {"method": "GET", "url": "https://api.example.com/users/42?expand=true&page=1", "headers": [{"name": "Cookie", "value": "session=secret"}]},
+                 "response": {"content": {"mimeType": "application/json"}}},
+                {"request": {"method": "GET", "url": "https://api.example.com/users/7?page=2"},
+                 "response": {"content": {"mimeType": "application/json"}}},
+                {"request": {"method": "POST", "url": "https://api.example.com/users/7/orders",
+                             "postData": {"mimeType": "application/json", "text": "{\"sku\": \"A-1\", \"quantity\": 2, \"gift\": false}"}},
+                 "response": {"content": {"mimeType": "application/json"}}},
+                {"request": {"method": "POST", "url": "https://api.example.com/users/9/orders",
+                             "postData": {"mimeType": "application/json", "text": "{\"sku\": \"B-2\", \"quantity\": 1.5}"}},
+                 "response": {"content": {"mimeType": "application/json"}}},
+                {"request": {"method": "GET", "url": "https://api.example.com/static/app.js"},
+                 "response": {"content": {"mimeType": "application/javascript"}}},
+                {"request": {"method": "GET", "url": "https://cdn.example.com/logo.png"},
+                 "response": {"content": {"mimeType": "image/png"}}}
+            ]
+        }
+    }"#;
+
+    fn tool<'a>(file: &'a CapabilityFile, name: &str) -> &'a ToolDefinition {
+        file.tools.iter().find(|t| t.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_path_templates() {
+        assert_eq!(path_template("/users/42/orders").0, "/users/{user_id}/orders");
+        assert_eq!(path_template("/categories/3f2504e0-4f89-11d3-9a0c-0305e82c3301").0, "/categories/{category_id}");
+        assert_eq!(path_template("/v2/search").0, "/v2/search");
+        assert_eq!(path_template("/").0, "/");
+    }
+
+    #[test]
+    fn test_deduplicates_requests_by_method_and_template() {
+        let generator = HarCapabilityGenerator::new(HarGeneratorConfig::default());
+        let file = generator.generate_from_har(HAR).unwrap();
+
+        let names: Vec<&str> = file.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["get_users_user_id", "post_users_user_id_orders"]);
+
+        let get_user = tool(&file, "get_users_user_id");
+        assert_eq!(get_user.routing.config["url"], "https://api.example.com/users/{user_id}");
+        assert_eq!(get_user.routing.config["query_params"], json!(["expand", "page"]));
+        assert!(get_user.routing.config.get("headers").is_none());
+        assert_eq!(get_user.annotations.as_ref().unwrap()["observed_requests"], "2");
+    }
+
+    #[test]
+    fn test_infers_schemas_from_observed_values() {
+        let generator = HarCapabilityGenerator::new(HarGeneratorConfig {
+            base_url: Some("https://staging.example.com/".to_string()),
+            ..Default::default()
+        });
+        let file = generator.generate_from_har(HAR).unwrap();
+
+        let get_user = tool(&file, "get_users_user_id");
+        let properties = &get_user.input_schema["properties"];
+        assert_eq!(properties["page"]["type"], "integer");
+        assert_eq!(properties["page"]["examples"], json!([1, 2]));
+        assert_eq!(properties["expand"]["type"], "boolean");
+        // `page` and the path parameter were seen every time, `expand` only once
+        assert_eq!(get_user.input_schema["required"], json!(["page", "user_id"]));
+
+        let create_order = tool(&file, "post_users_user_id_orders");
+        assert_eq!(create_order.routing.config["url"], "https://staging.example.com/users/{user_id}/orders");
+        assert_eq!(create_order.routing.config["headers"]["Content-Type"], "application/json");
+        assert_eq!(create_order.routing.config["body_params"], json!(["gift", "quantity", "sku"]));
+        let properties = &create_order.input_schema["properties"];
+        assert_eq!(properties["quantity"]["type"], "number");
+        assert_eq!(properties["gift"]["type"], "boolean");
+        assert_eq!(create_order.input_schema["required"], json!(["user_id", "quantity", "sku"]));
+    }
+
+    #[test]
+    fn test_invalid_har_is_rejected() {
+        let generator = HarCapabilityGenerator::new(HarGeneratorConfig::default());
+        assert!(generator.generate_from_har("{}").is_err());
+        assert!(generator.generate_from_har("not json").is_err());
+    }
+}
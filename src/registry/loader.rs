@@ -2,6 +2,8 @@
 
 use crate::config::RegistryConfig;
 use crate::error::{ProxyError, Result};
+use crate::registry::migration::parse_capability_file;
+use crate::registry::remote_source::{RemoteSource, RemoteSourceSyncer};
 use crate::registry::types::*;
 use std::path::Path;
 use tracing::{debug, info, warn};
@@ -9,12 +11,14 @@ use tracing::{debug, info, warn};
 /// Registry loader that discovers and loads capability files
 pub struct RegistryLoader {
     config: RegistryConfig,
+    remote_syncer: RemoteSourceSyncer,
 }
 
 impl RegistryLoader {
     /// Create a new registry loader
     pub fn new(config: RegistryConfig) -> Self {
-        Self { config }
+        let remote_syncer = RemoteSourceSyncer::new(config.remote.clone());
+        Self { config, remote_syncer }
     }
 
     /// Load all capability files from configured paths
@@ -44,8 +48,12 @@ impl RegistryLoader {
         Ok(all_files)
     }
 
-    /// Load capability files from a single path (file or directory)
+    /// Load capability files from a single path (file, directory or remote source)
     async fn load_from_path(&self, path: &str) -> Result<Vec<CapabilityFile>> {
+        if let Some(source) = RemoteSource::parse(path) {
+            return self.load_remote_source(&source).await;
+        }
+
         let path = Path::new(path);
         
         if path.is_file() {
@@ -61,6 +69,27 @@ impl RegistryLoader {
         }
     }
 
+    /// Sync a remote source into the local cache and load it from there
+    async fn load_remote_source(&self, source: &RemoteSource) -> Result<Vec<CapabilityFile>> {
+        let local_path = match self.remote_syncer.sync(source).await {
+            Ok(outcome) => outcome.local_path,
+            Err(e) => {
+                let cached = self.remote_syncer.local_path(source);
+                if !cached.exists() {
+                    return Err(e);
+                }
+                warn!("Failed to sync registry source {}, using cached copy: {}", source.display(), e);
+                cached
+            }
+        };
+
+        if local_path.is_file() {
+            Ok(vec![self.load_file(&local_path).await?])
+        } else {
+            self.discover_and_load_directory(&local_path).await
+        }
+    }
+
     /// Load a single capability file
     async fn load_file(&self, path: &Path) -> Result<CapabilityFile> {
         debug!("Loading capability file: {}", path.display());
@@ -69,9 +98,7 @@ impl RegistryLoader {
             ProxyError::registry(format!("Failed to read file {}: {}", path.display(), e))
         })?;
 
-        let capability_file: CapabilityFile = serde_yaml::from_str(&content).map_err(|e| {
-            ProxyError::registry(format!("Failed to parse YAML file {}: {}", path.display(), e))
-        })?;
+        let capability_file = parse_capability_file(&content, &path.display().to_string())?;
 
         // Validate if strict mode is enabled
         if self.config.validation.strict {
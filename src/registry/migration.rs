@@ -0,0 +1,260 @@
+//! Capability file format versioning and migration
+//!
+//! Capability files carry a `schema_version`. Files without one are treated as
+//! version 1, the format used before versioning was introduced. On read, the
+//! raw YAML is upgraded step by step to [`CAPABILITY_SCHEMA_VERSION`] before it
+//! is deserialized, and each legacy construct that was rewritten produces a
+//! deprecation warning. `magictunnel-cli migrate` applies the same steps and
+//! writes the result back to disk. The file is rewritten from the parsed
+//! document, so comments and formatting are not kept; files with comments
+//! are only rewritten with `--force`.
+
+use crate::error::{ProxyError, Result};
+use crate::registry::types::{CapabilityFile, CAPABILITY_SCHEMA_VERSION};
+use serde_yaml::{Mapping, Value};
+use tracing::warn;
+
+/// Version assumed for files without a `schema_version` field
+pub const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+/// A single format upgrade from `from` to `from + 1`
+struct Migration {
+    from: u32,
+    migrate: fn(&mut Mapping, &mut Vec<String>),
+}
+
+/// All migrations, in order
+const MIGRATIONS: &[Migration] = &[Migration { from: 1, migrate: migrate_v1_to_v2 }];
+
+/// Result of upgrading a capability document
+#[derive(Debug, Clone)]
+pub struct MigrationOutcome {
+    /// Upgraded document
+    pub document: Value,
+    /// Version the document was read as
+    pub from_version: u32,
+    /// Version after migration
+    pub to_version: u32,
+    /// Deprecation warnings for each rewritten construct
+    pub warnings: Vec<String>,
+}
+
+impl MigrationOutcome {
+    /// Whether anything was changed
+    pub fn migrated(&self) -> bool {
+        self.from_version != self.to_version || !self.warnings.is_empty()
+    }
+
+    /// Serialize the upgraded document as YAML
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(&self.document)
+            .map_err(|e| ProxyError::registry(format!("Failed to serialize migrated capability file: {}", e)))
+    }
+}
+
+/// Upgrade raw capability file YAML to the current schema version
+pub fn migrate_content(content: &str) -> Result<MigrationOutcome> {
+    let document: Value = serde_yaml::from_str(content)
+        .map_err(|e| ProxyError::registry(format!("Failed to parse YAML: {}", e)))?;
+    migrate_document(document)
+}
+
+/// Upgrade a parsed capability document to the current schema version
+pub fn migrate_document(document: Value) -> Result<MigrationOutcome> {
+    let Value::Mapping(mut mapping) = document else {
+        return Err(ProxyError::registry("Capability file must be a YAML mapping".to_string()));
+    };
+
+    let from_version = match mapping.get("schema_version") {
+        None | Some(Value::Null) => LEGACY_SCHEMA_VERSION,
+        Some(value) => value
+            .as_u64()
+            .map(|v| v as u32)
+            .ok_or_else(|| ProxyError::registry(format!("Invalid schema_version: {:?}", value)))?,
+    };
+    if from_version > CAPABILITY_SCHEMA_VERSION {
+        return Err(ProxyError::registry(format!(
+            "Capability file schema_version {} is newer than the supported version {}",
+            from_version, CAPABILITY_SCHEMA_VERSION
+        )));
+    }
+
+    let mut warnings = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= from_version) {
+        (migration.migrate)(&mut mapping, &mut warnings);
+    }
+
+    // Keep schema_version as the first key so it's visible at the top of the file
+    mapping.remove("schema_version");
+    let mut upgraded = Mapping::new();
+    upgraded.insert(Value::from("schema_version"), Value::from(CAPABILITY_SCHEMA_VERSION));
+    upgraded.extend(mapping);
+
+    Ok(MigrationOutcome {
+        document: Value::Mapping(upgraded),
+        from_version,
+        to_version: CAPABILITY_SCHEMA_VERSION,
+        warnings,
+    })
+}
+
+/// Whether `content` may hold YAML comments, which rewriting it would drop
+///
+/// A `#` at the start of a line or after whitespace counts, so a `#` inside a
+/// string can also match; that only errs toward keeping the file as it is.
+pub fn has_yaml_comments(content: &str) -> bool {
+    content.lines().any(|line| line.trim_start().starts_with('#') || line.contains(" #") || line.contains("\t#"))
+}
+
+/// Parse a capability file, upgrading older formats and logging deprecation warnings
+pub fn parse_capability_file(content: &str, source: &str) -> Result<CapabilityFile> {
+    let outcome = migrate_content(content)
+        .map_err(|e| ProxyError::registry(format!("Failed to parse YAML file {}: {}", source, e)))?;
+
+    if outcome.migrated() {
+        warn!(
+            "Capability file {} uses schema_version {} and was upgraded to {} on load; run `magictunnel-cli migrate --input {}` to update it",
+            source, outcome.from_version, outcome.to_version, source
+        );
+        for warning in &outcome.warnings {
+            warn!("{}: {}", source, warning);
+        }
+    }
+
+    serde_yaml::from_value(outcome.document)
+        .map_err(|e| ProxyError::registry(format!("Failed to parse YAML file {}: {}", source, e)))
+}
+
+/// Version 1 -> 2:
+/// - `input_schema` is renamed to `inputSchema`
+/// - routing settings given next to `type` are moved into `routing.config`
+/// - subprocess `environment` is renamed to `env` (the only key the agent reads)
+/// - non-string annotation values are converted to strings
+fn migrate_v1_to_v2(document: &mut Mapping, warnings: &mut Vec<String>) {
+    let Some(Value::Sequence(tools)) = document.get_mut("tools") else { return };
+
+    for tool in tools.iter_mut() {
+        let Value::Mapping(tool) = tool else { continue };
+        let name = tool.get("name").and_then(Value::as_str).unwrap_or("<unnamed>").to_string();
+
+        if let Some(schema) = tool.remove("input_schema") {
+            if !tool.contains_key("inputSchema") {
+                tool.insert(Value::from("inputSchema"), schema);
+            }
+            warnings.push(format!("tool '{}': `input_schema` is deprecated, use `inputSchema`", name));
+        }
+
+        if let Some(Value::Mapping(routing)) = tool.get_mut("routing") {
+            let inline_keys: Vec<Value> = routing
+                .keys()
+                .filter(|k| !matches!(k.as_str(), Some("type") | Some("config")))
+                .cloned()
+                .collect();
+            if !inline_keys.is_empty() {
+                if !matches!(routing.get("config"), Some(Value::Mapping(_))) {
+                    routing.insert(Value::from("config"), Value::Mapping(Mapping::new()));
+                }
+                for key in inline_keys {
+                    if let Some(value) = routing.remove(&key) {
+                        if let Some(Value::Mapping(config)) = routing.get_mut("config") {
+                            config.entry(key).or_insert(value);
+                        }
+                    }
+                }
+                warnings.push(format!("tool '{}': routing settings outside `routing.config` are deprecated", name));
+            }
+
+            let is_subprocess = routing.get("type").and_then(Value::as_str) == Some("subprocess");
+            if let Some(Value::Mapping(config)) = routing.get_mut("config") {
+                if is_subprocess {
+                    if let Some(environment) = config.remove("environment") {
+                        if !config.contains_key("env") {
+                            config.insert(Value::from("env"), environment);
+                        }
+                        warnings.push(format!("tool '{}': subprocess `environment` is deprecated, use `env`", name));
+                    }
+                }
+            }
+        }
+
+        if let Some(Value::Mapping(annotations)) = tool.get_mut("annotations") {
+            let mut converted = false;
+            for (_, value) in annotations.iter_mut() {
+                let text = match value {
+                    Value::Bool(b) => b.to_string(),
+                    Value::Number(n) => n.to_string(),
+                    _ => continue,
+                };
+                *value = Value::from(text);
+                converted = true;
+            }
+            if converted {
+                warnings.push(format!("tool '{}': non-string annotation values are deprecated", name));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGACY: &str = r#"
+metadata:
+  name: legacy
+tools:
+  - name: run_backup
+    description: Back up a database
+    input_schema:
+      type: object
+    routing:
+      type: subprocess
+      command: pg_dump
+      args: ["{database}"]
+      environment:
+        PGPASSWORD: "${env:DB_PASSWORD}"
+    annotations:
+      destructive: false
+      retries: 3
+"#;
+
+    #[test]
+    fn test_legacy_file_is_upgraded() {
+        let outcome = migrate_content(LEGACY).unwrap();
+        assert_eq!(outcome.from_version, LEGACY_SCHEMA_VERSION);
+        assert_eq!(outcome.to_version, CAPABILITY_SCHEMA_VERSION);
+        assert_eq!(outcome.warnings.len(), 4);
+
+        let file: CapabilityFile = serde_yaml::from_value(outcome.document.clone()).unwrap();
+        assert_eq!(file.schema_version, Some(CAPABILITY_SCHEMA_VERSION));
+        let tool = &file.tools[0];
+        assert_eq!(tool.input_schema["type"], "object");
+        assert_eq!(tool.routing.config["command"], "pg_dump");
+        assert_eq!(tool.routing.config["env"]["PGPASSWORD"], "${env:DB_PASSWORD}");
+        assert!(tool.routing.config.get("environment").is_none());
+        assert_eq!(tool.annotations.as_ref().unwrap()["retries"], "3");
+
+        assert!(outcome.to_yaml().unwrap().starts_with("schema_version: 2\n"));
+    }
+
+    #[test]
+    fn test_current_file_is_unchanged() {
+        let current = "schema_version: 2\ntools:\n  - name: t\n    description: d\n    inputSchema: {type: object}\n    routing: {type: http, config: {url: 'http://x'}}\n";
+        let outcome = migrate_content(current).unwrap();
+        assert!(!outcome.migrated());
+        assert!(parse_capability_file(current, "current.yaml").is_ok());
+    }
+
+    #[test]
+    fn test_has_yaml_comments() {
+        assert!(has_yaml_comments("# Legacy tools\ntools: []\n"));
+        assert!(has_yaml_comments("tools: [] # none yet\n"));
+        assert!(!has_yaml_comments("tools:\n  - name: issue#42\n"));
+    }
+
+    #[test]
+    fn test_newer_schema_version_is_rejected() {
+        let err = migrate_content("schema_version: 99\ntools: []\n").unwrap_err();
+        assert!(err.to_string().contains("newer than the supported version"));
+    }
+}
@@ -1,16 +1,29 @@
 //! Capability registry for managing tool definitions and routing
 
 
+pub mod aws_generator;
+pub mod cli_generator;
 pub mod commands;
+pub mod database_generator;
+pub mod env_template;
 pub mod generator_common;
 pub mod generator_config;
 pub mod graphql_generator;
+pub mod har_generator;
 pub mod grpc_generator;
 pub mod loader;
+pub mod migration;
 pub mod openapi_generator;
+pub mod output_schema;
+pub mod remote_source;
 pub mod service;
+pub mod snapshot;
+pub mod sqlite_store;
 pub mod tool_aggregation;
+pub mod tool_state;
+pub mod toolset;
 pub mod types;
+pub mod versioning;
 
 
 pub use commands::{
@@ -20,6 +33,6 @@ pub use commands::{
 pub use generator_common::{AuthConfig, AuthType, CapabilityGenerator, GeneratorRegistry};
 pub use generator_config::GeneratorConfigFile;
 pub use loader::RegistryLoader;
-pub use service::{RegistryService, CapabilityRegistry, RegistryMetadata};
+pub use service::{RegistryService, CapabilityRegistry, RegistryChange, RegistryMetadata};
 pub use tool_aggregation::{ToolAggregationService, AggregatedTool, AggregationStats};
 pub use types::*;
@@ -1,17 +1,20 @@
 //! OpenAPI Capability Generator
 //!
 //! This module provides functionality to generate MCP tool definitions from OpenAPI specifications.
-//! It supports both OpenAPI 3.0 and Swagger 2.0 formats, parsing JSON and YAML specifications
-//! to create corresponding MCP tools for REST API endpoints.
+//! It supports OpenAPI 3.0, OpenAPI 3.1 and Swagger 2.0 formats, parsing JSON and YAML specifications
+//! to create corresponding MCP tools for REST API endpoints (and, for 3.1, webhooks).
 
 use crate::error::{ProxyError, Result};
 use crate::registry::types::{CapabilityFile, FileMetadata, ToolDefinition, RoutingConfig};
+use crate::registry::output_schema::object_output_schema;
 use openapiv3::{OpenAPI, Operation, Parameter, RequestBody, Response};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use base64::{Engine as _, engine::general_purpose};
 
+mod openapi31;
+
 /// Authentication configuration for OpenAPI endpoints
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthConfig {
@@ -83,6 +86,8 @@ pub struct OpenAPIOperation {
     pub tags: Vec<String>,
     /// Whether the operation is deprecated
     pub deprecated: bool,
+    /// Webhook name when the operation describes an OpenAPI 3.1 webhook
+    pub webhook: Option<String>,
 }
 
 /// Represents an OpenAPI parameter
@@ -145,6 +150,8 @@ pub struct OpenAPICapabilityGenerator {
     pub naming_convention: NamingConvention,
     /// Whether to include deprecated operations
     pub include_deprecated: bool,
+    /// Where webhook tools deliver events (defaults to `base_url`)
+    pub webhook_base_url: Option<String>,
 }
 
 /// Naming convention for generated tools
@@ -279,6 +286,7 @@ impl OpenAPICapabilityGenerator {
             method_filter: None,
             naming_convention: NamingConvention::default(),
             include_deprecated: false,
+            webhook_base_url: None,
         }
     }
 
@@ -324,13 +332,33 @@ impl OpenAPICapabilityGenerator {
         self
     }
 
+    /// Set the base URL webhook tools deliver events to
+    pub fn with_webhook_base_url(mut self, url: String) -> Self {
+        self.webhook_base_url = Some(url);
+        self
+    }
+
     /// Generate capability file from OpenAPI 3.0 specification
+    /// (3.1 documents are detected and handled by [`Self::generate_from_openapi31`])
     pub fn generate_from_openapi3(&mut self, spec_content: &str) -> Result<CapabilityFile> {
+        if let Ok(spec) = self.parse_openapi31_spec(spec_content) {
+            if openapi31::is_openapi31(&spec) {
+                return self.generate_from_openapi31(spec_content);
+            }
+        }
+
         let openapi_spec = self.parse_openapi3_spec(spec_content)?;
         let operations = self.extract_operations_from_openapi3(&openapi_spec)?;
         self.generate_capability_file(operations)
     }
 
+    /// Generate capability file from OpenAPI 3.1 specification, including webhooks
+    pub fn generate_from_openapi31(&mut self, spec_content: &str) -> Result<CapabilityFile> {
+        let spec = self.parse_openapi31_spec(spec_content)?;
+        let operations = self.extract_operations_from_openapi31(&spec)?;
+        self.generate_capability_file(operations)
+    }
+
     /// Generate capability file from Swagger 2.0 specification
     pub fn generate_from_swagger2(&mut self, spec_content: &str) -> Result<CapabilityFile> {
         let swagger_spec = self.parse_swagger2_spec(spec_content)?;
@@ -461,6 +489,7 @@ impl OpenAPICapabilityGenerator {
             responses,
             tags: operation.tags.clone(),
             deprecated: operation.deprecated,
+            webhook: None,
         })
     }
 
@@ -1202,6 +1231,7 @@ impl OpenAPICapabilityGenerator {
             responses,
             tags: operation.tags.clone().unwrap_or_default(),
             deprecated: operation.deprecated.unwrap_or(false),
+            webhook: None,
         })
     }
 
@@ -1388,15 +1418,25 @@ impl OpenAPICapabilityGenerator {
         let description = self.generate_tool_description(&operation);
         let input_schema = self.generate_input_schema(&operation)?;
         let routing = self.create_routing_config(&operation)?;
+        let annotations = self.generate_annotations(&operation, &input_schema);
+        let output_schema = self.generate_output_schema(&operation);
 
         Ok(ToolDefinition {
             name: tool_name,
             description,
             input_schema,
             routing,
-            annotations: None, // TODO: Add annotations support
+            annotations,
             hidden: true, // OpenAPI tools are hidden by default (consistent with other tools)
             enabled: true, // OpenAPI tools are enabled by default
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: operation.tags.clone(),
+            output_schema,
+            cache: None,
+            background: false,
+            cost: None,
         })
     }
 
@@ -1440,6 +1480,10 @@ impl OpenAPICapabilityGenerator {
             description = format!("{} {}", operation.method, operation.path);
         }
 
+        if let Some(webhook) = &operation.webhook {
+            description = format!("Webhook '{}': {}", webhook, description);
+        }
+
         if operation.deprecated {
             description = format!("⚠️ DEPRECATED: {}", description);
         }
@@ -1447,6 +1491,42 @@ impl OpenAPICapabilityGenerator {
         description
     }
 
+    /// Generate tool annotations: webhook metadata and input examples.
+    ///
+    /// Examples are kept per input property under `elicitation_examples` so
+    /// clients can offer them when asking the user for missing arguments.
+    fn generate_annotations(&self, operation: &OpenAPIOperation, input_schema: &Value) -> Option<HashMap<String, String>> {
+        let mut annotations = HashMap::new();
+
+        if let Some(webhook) = &operation.webhook {
+            annotations.insert("webhook".to_string(), webhook.clone());
+            annotations.insert("notification_method".to_string(), format!("notifications/webhooks/{}", webhook));
+        }
+
+        let examples: serde_json::Map<String, Value> = input_schema
+            .get("properties")
+            .and_then(|p| p.as_object())
+            .map(|properties| {
+                properties
+                    .iter()
+                    .filter_map(|(name, schema)| {
+                        schema.get("examples").filter(|e| e.as_array().map_or(false, |e| !e.is_empty()))
+                            .map(|examples| (name.clone(), examples.clone()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !examples.is_empty() {
+            annotations.insert("elicitation_examples".to_string(), Value::Object(examples).to_string());
+        }
+
+        if annotations.is_empty() {
+            None
+        } else {
+            Some(annotations)
+        }
+    }
+
     /// Generate JSON schema for input parameters
     fn generate_input_schema(&self, operation: &OpenAPIOperation) -> Result<Value> {
         let mut properties = serde_json::Map::new();
@@ -1474,11 +1554,21 @@ impl OpenAPICapabilityGenerator {
 
         // Add request body parameters
         if let Some(request_body) = &operation.request_body {
-            if request_body.content.contains_key("application/json") {
-                properties.insert("body".to_string(), json!({
-                    "type": "object",
-                    "description": request_body.description.clone().unwrap_or_else(|| "Request body".to_string())
-                }));
+            if let Some(body_schema) = request_body.content.get("application/json") {
+                let description = request_body.description.clone().unwrap_or_else(|| "Request body".to_string());
+                // Use the resolved body schema when there is one, otherwise a generic object
+                let body = match body_schema {
+                    Value::Object(schema) if schema.contains_key("properties") => {
+                        let mut schema = schema.clone();
+                        schema.entry("description").or_insert_with(|| json!(description));
+                        Value::Object(schema)
+                    }
+                    _ => json!({
+                        "type": "object",
+                        "description": description
+                    }),
+                };
+                properties.insert("body".to_string(), body);
                 if request_body.required {
                     required.push("body".to_string());
                 }
@@ -1492,12 +1582,35 @@ impl OpenAPICapabilityGenerator {
         }))
     }
 
+    /// Generate the output schema from the first 2xx response with a JSON body
+    fn generate_output_schema(&self, operation: &OpenAPIOperation) -> Option<Value> {
+        let mut statuses: Vec<&String> = operation.responses.keys()
+            .filter(|status| status.starts_with('2'))
+            .collect();
+        statuses.sort();
+
+        statuses.into_iter().find_map(|status| {
+            let content = &operation.responses[status].content;
+            content.get("application/json")
+                .or_else(|| {
+                    let mut media_types: Vec<&String> = content.keys().filter(|m| m.contains("json")).collect();
+                    media_types.sort();
+                    media_types.first().map(|m| &content[*m])
+                })
+                .map(|schema| object_output_schema(schema.clone()))
+        })
+    }
+
     /// Create routing configuration for HTTP request
     fn create_routing_config(&self, operation: &OpenAPIOperation) -> Result<RoutingConfig> {
         let mut config = serde_json::Map::new();
 
         config.insert("method".to_string(), json!(operation.method));
-        config.insert("url".to_string(), json!(format!("{}{}", self.base_url, operation.path)));
+        let base_url = match (&operation.webhook, &self.webhook_base_url) {
+            (Some(_), Some(webhook_base_url)) => webhook_base_url,
+            _ => &self.base_url,
+        };
+        config.insert("url".to_string(), json!(format!("{}{}", base_url, operation.path)));
 
         // Add authentication headers
         if let Some(auth_config) = &self.auth_config {
@@ -1557,6 +1670,10 @@ impl OpenAPICapabilityGenerator {
 #[cfg(test)]
 mod swagger2_tests;
 
+// Include OpenAPI 3.1 tests
+#[cfg(test)]
+mod openapi31_tests;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1631,6 +1748,47 @@ mod tests {
         assert_eq!(capability_file.tools.len(), 1);
         assert_eq!(capability_file.tools[0].name, "getUsers");
         assert_eq!(capability_file.tools[0].description, "Get all users");
+        assert!(capability_file.tools[0].output_schema.is_none());
+    }
+
+    #[test]
+    fn test_output_schema_from_success_response() {
+        let mut generator = OpenAPICapabilityGenerator::new("https://api.example.com".to_string());
+
+        let openapi_spec = r#"
+        {
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {
+                "/users": {
+                    "get": {
+                        "operationId": "listUsers",
+                        "responses": {
+                            "200": {
+                                "description": "Success",
+                                "content": {
+                                    "application/json": {
+                                        "schema": {"type": "array", "items": {"type": "string"}}
+                                    }
+                                }
+                            },
+                            "404": {
+                                "description": "Not found",
+                                "content": {
+                                    "application/json": {"schema": {"type": "object"}}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        "#;
+
+        let capability_file = generator.generate_from_openapi3(openapi_spec).unwrap();
+        let output_schema = capability_file.tools[0].output_schema.as_ref().unwrap();
+        assert_eq!(output_schema["type"], "object");
+        assert_eq!(output_schema["properties"]["result"]["type"], "array");
     }
 
     #[test]
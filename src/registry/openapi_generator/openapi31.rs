@@ -0,0 +1,348 @@
+//! OpenAPI 3.1 support for the OpenAPI Capability Generator
+//!
+//! `openapiv3` only models 3.0 documents, so 3.1 specifications are walked as
+//! plain JSON. 3.1 schemas are JSON Schema 2020-12, which MCP input schemas
+//! already are, so conversion mostly means inlining `$ref`s, folding the
+//! deprecated `example` keyword into `examples` and dropping OpenAPI-only
+//! keywords. Type arrays, `const` and the other 2020-12 keywords pass through.
+//! Webhooks are extracted as operations tagged with their webhook name.
+
+use super::{OpenAPICapabilityGenerator, OpenAPIOperation, OpenAPIParameter, OpenAPIRequestBody, OpenAPIResponse};
+use crate::error::{ProxyError, Result};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+
+const HTTP_METHODS: [&str; 8] = ["get", "post", "put", "patch", "delete", "head", "options", "trace"];
+
+/// Keywords whose value is a single subschema
+const SUBSCHEMA_KEYWORDS: &[&str] = &[
+    "items", "additionalProperties", "not", "if", "then", "else", "contains",
+    "propertyNames", "unevaluatedItems", "unevaluatedProperties", "contentSchema",
+];
+
+/// Keywords whose value is an array of subschemas
+const SUBSCHEMA_ARRAY_KEYWORDS: &[&str] = &["allOf", "anyOf", "oneOf", "prefixItems"];
+
+/// Keywords whose value is a map of subschemas
+const SUBSCHEMA_MAP_KEYWORDS: &[&str] = &["properties", "patternProperties", "dependentSchemas", "$defs"];
+
+/// OpenAPI-specific keywords that mean nothing to JSON Schema consumers
+const OPENAPI_ONLY_KEYWORDS: &[&str] = &["discriminator", "xml", "externalDocs", "$schema"];
+
+/// Whether the parsed document declares OpenAPI 3.1
+pub(super) fn is_openapi31(spec: &Value) -> bool {
+    spec.get("openapi")
+        .and_then(|v| v.as_str())
+        .map(|version| version.starts_with("3.1"))
+        .unwrap_or(false)
+}
+
+impl OpenAPICapabilityGenerator {
+    /// Parse an OpenAPI 3.1 document from JSON or YAML
+    pub(super) fn parse_openapi31_spec(&self, spec_content: &str) -> Result<Value> {
+        if let Ok(spec) = serde_json::from_str::<Value>(spec_content) {
+            return Ok(spec);
+        }
+        serde_yaml::from_str::<Value>(spec_content)
+            .map_err(|e| ProxyError::validation(format!("Failed to parse OpenAPI 3.1 specification: {}", e)))
+    }
+
+    /// Extract operations (including webhooks) from an OpenAPI 3.1 document
+    pub(super) fn extract_operations_from_openapi31(&self, spec: &Value) -> Result<Vec<OpenAPIOperation>> {
+        let mut operations = Vec::new();
+
+        if let Some(paths) = spec.get("paths").and_then(|p| p.as_object()) {
+            for (path, path_item) in paths {
+                self.extract_openapi31_path_item(spec, &mut operations, path, path_item, None)?;
+            }
+        }
+
+        if let Some(webhooks) = spec.get("webhooks").and_then(|w| w.as_object()) {
+            for (name, path_item) in webhooks {
+                let path = format!("/webhooks/{}", name);
+                self.extract_openapi31_path_item(spec, &mut operations, &path, path_item, Some(name))?;
+            }
+        }
+
+        self.apply_filters(operations)
+    }
+
+    fn extract_openapi31_path_item(
+        &self,
+        spec: &Value,
+        operations: &mut Vec<OpenAPIOperation>,
+        path: &str,
+        path_item: &Value,
+        webhook: Option<&String>,
+    ) -> Result<()> {
+        let path_item = resolve_reference(spec, path_item)?;
+        let shared_parameters = path_item.get("parameters").and_then(|p| p.as_array()).cloned().unwrap_or_default();
+
+        for method in HTTP_METHODS {
+            if let Some(operation) = path_item.get(method) {
+                let mut operation = self.convert_openapi31_operation(spec, path, method, operation, &shared_parameters)?;
+                operation.webhook = webhook.cloned();
+                operations.push(operation);
+            }
+        }
+        Ok(())
+    }
+
+    fn convert_openapi31_operation(
+        &self,
+        spec: &Value,
+        path: &str,
+        method: &str,
+        operation: &Value,
+        shared_parameters: &[Value],
+    ) -> Result<OpenAPIOperation> {
+        // Operation-level parameters override path-level ones with the same name and location
+        let mut parameters: Vec<OpenAPIParameter> = Vec::new();
+        let operation_parameters = operation.get("parameters").and_then(|p| p.as_array()).cloned().unwrap_or_default();
+        for parameter in shared_parameters.iter().chain(operation_parameters.iter()) {
+            let parameter = self.convert_openapi31_parameter(spec, parameter)?;
+            parameters.retain(|p| !(p.name == parameter.name && p.location == parameter.location));
+            parameters.push(parameter);
+        }
+
+        let request_body = match operation.get("requestBody") {
+            Some(body) => Some(self.convert_openapi31_request_body(spec, body)?),
+            None => None,
+        };
+
+        let mut responses = HashMap::new();
+        if let Some(response_map) = operation.get("responses").and_then(|r| r.as_object()) {
+            for (status_code, response) in response_map {
+                responses.insert(status_code.clone(), self.convert_openapi31_response(spec, response)?);
+            }
+        }
+
+        Ok(OpenAPIOperation {
+            method: method.to_uppercase(),
+            path: path.to_string(),
+            operation_id: string_field(operation, "operationId"),
+            summary: string_field(operation, "summary"),
+            description: string_field(operation, "description"),
+            parameters,
+            request_body,
+            responses,
+            tags: operation
+                .get("tags")
+                .and_then(|t| t.as_array())
+                .map(|tags| tags.iter().filter_map(|t| t.as_str().map(String::from)).collect())
+                .unwrap_or_default(),
+            deprecated: operation.get("deprecated").and_then(|d| d.as_bool()).unwrap_or(false),
+            webhook: None,
+        })
+    }
+
+    fn convert_openapi31_parameter(&self, spec: &Value, parameter: &Value) -> Result<OpenAPIParameter> {
+        let parameter = resolve_reference(spec, parameter)?;
+        let name = string_field(&parameter, "name")
+            .ok_or_else(|| ProxyError::validation("OpenAPI 3.1 parameter is missing 'name'".to_string()))?;
+        let location = string_field(&parameter, "in").unwrap_or_else(|| "query".to_string());
+
+        let mut schema = match parameter.get("schema") {
+            Some(schema) => convert_openapi31_schema(spec, schema)?,
+            None => json!({"type": "string"}),
+        };
+        let description = string_field(&parameter, "description");
+        if let (Some(description), Some(obj)) = (&description, schema.as_object_mut()) {
+            obj.entry("description").or_insert_with(|| json!(description));
+        }
+        merge_examples(&mut schema, &parameter);
+
+        Ok(OpenAPIParameter {
+            default: schema.get("default").cloned(),
+            name,
+            // Path parameters are always required
+            required: location == "path" || parameter.get("required").and_then(|r| r.as_bool()).unwrap_or(false),
+            location,
+            description,
+            schema,
+        })
+    }
+
+    fn convert_openapi31_request_body(&self, spec: &Value, body: &Value) -> Result<OpenAPIRequestBody> {
+        let body = resolve_reference(spec, body)?;
+        let mut content = HashMap::new();
+
+        if let Some(media_types) = body.get("content").and_then(|c| c.as_object()) {
+            for (media_type, media) in media_types {
+                let mut schema = match media.get("schema") {
+                    Some(schema) => convert_openapi31_schema(spec, schema)?,
+                    None => json!({
+                        "type": "object",
+                        "description": format!("Request body for {}", media_type)
+                    }),
+                };
+                merge_examples(&mut schema, media);
+                content.insert(media_type.clone(), schema);
+            }
+        }
+
+        Ok(OpenAPIRequestBody {
+            description: string_field(&body, "description"),
+            required: body.get("required").and_then(|r| r.as_bool()).unwrap_or(false),
+            content,
+        })
+    }
+
+    fn convert_openapi31_response(&self, spec: &Value, response: &Value) -> Result<OpenAPIResponse> {
+        let response = resolve_reference(spec, response)?;
+        let mut content = HashMap::new();
+        let mut headers = HashMap::new();
+
+        if let Some(media_types) = response.get("content").and_then(|c| c.as_object()) {
+            for (media_type, media) in media_types {
+                if let Some(schema) = media.get("schema") {
+                    content.insert(media_type.clone(), convert_openapi31_schema(spec, schema)?);
+                }
+            }
+        }
+        if let Some(header_map) = response.get("headers").and_then(|h| h.as_object()) {
+            for (name, header) in header_map {
+                let header = resolve_reference(spec, header)?;
+                let schema = match header.get("schema") {
+                    Some(schema) => convert_openapi31_schema(spec, schema)?,
+                    None => json!({"type": "string"}),
+                };
+                headers.insert(name.clone(), schema);
+            }
+        }
+
+        Ok(OpenAPIResponse {
+            description: string_field(&response, "description").unwrap_or_default(),
+            content,
+            headers,
+        })
+    }
+}
+
+/// Convert an OpenAPI 3.1 schema into a self-contained JSON Schema
+pub(super) fn convert_openapi31_schema(spec: &Value, schema: &Value) -> Result<Value> {
+    convert_schema(spec, schema, &mut HashSet::new())
+}
+
+fn convert_schema(spec: &Value, schema: &Value, visiting: &mut HashSet<String>) -> Result<Value> {
+    let object = match schema {
+        Value::Object(object) => object,
+        // Boolean schemas (`true` / `false`) are valid in 2020-12
+        other => return Ok(other.clone()),
+    };
+
+    let mut result = Map::new();
+
+    if let Some(reference) = object.get("$ref").and_then(|r| r.as_str()) {
+        if visiting.contains(reference) {
+            // Recursive structure: stop expanding instead of looping forever
+            let name = reference.rsplit('/').next().unwrap_or(reference);
+            result.insert("type".to_string(), json!("object"));
+            result.insert("description".to_string(), json!(format!("Recursive reference to {}", name)));
+        } else {
+            let target = lookup_reference(spec, reference)?;
+            visiting.insert(reference.to_string());
+            let resolved = convert_schema(spec, &target, visiting)?;
+            visiting.remove(reference);
+            if let Value::Object(resolved) = resolved {
+                result.extend(resolved);
+            }
+        }
+    }
+
+    // Sibling keywords next to $ref are allowed in 3.1 and take precedence
+    for (keyword, value) in object {
+        let keyword = keyword.as_str();
+        if keyword == "$ref" || OPENAPI_ONLY_KEYWORDS.contains(&keyword) {
+            continue;
+        }
+
+        let converted = if SUBSCHEMA_KEYWORDS.contains(&keyword) {
+            convert_schema(spec, value, visiting)?
+        } else if SUBSCHEMA_ARRAY_KEYWORDS.contains(&keyword) {
+            match value.as_array() {
+                Some(schemas) => Value::Array(
+                    schemas.iter().map(|s| convert_schema(spec, s, visiting)).collect::<Result<_>>()?,
+                ),
+                None => value.clone(),
+            }
+        } else if SUBSCHEMA_MAP_KEYWORDS.contains(&keyword) {
+            match value.as_object() {
+                Some(schemas) => {
+                    let mut converted = Map::new();
+                    for (name, s) in schemas {
+                        converted.insert(name.clone(), convert_schema(spec, s, visiting)?);
+                    }
+                    Value::Object(converted)
+                }
+                None => value.clone(),
+            }
+        } else {
+            value.clone()
+        };
+        result.insert(keyword.to_string(), converted);
+    }
+
+    // `example` is deprecated in 3.1 in favour of the JSON Schema `examples` array
+    if let Some(example) = result.remove("example") {
+        let examples = result.entry("examples").or_insert_with(|| json!([]));
+        if let Some(examples) = examples.as_array_mut() {
+            if !examples.contains(&example) {
+                examples.push(example);
+            }
+        }
+    }
+
+    Ok(Value::Object(result))
+}
+
+/// Resolve a top-level `$ref` on a parameter, request body, response, header or path item
+fn resolve_reference(spec: &Value, value: &Value) -> Result<Value> {
+    let mut current = value.clone();
+    // Follow chains of references, bounded to guard against cycles
+    for _ in 0..16 {
+        match current.get("$ref").and_then(|r| r.as_str()) {
+            Some(reference) => current = lookup_reference(spec, reference)?,
+            None => return Ok(current),
+        }
+    }
+    Err(ProxyError::validation("OpenAPI 3.1 reference chain is too deep".to_string()))
+}
+
+fn lookup_reference(spec: &Value, reference: &str) -> Result<Value> {
+    let pointer = reference
+        .strip_prefix('#')
+        .ok_or_else(|| ProxyError::validation(format!("External references are not supported: {}", reference)))?;
+    spec.pointer(pointer)
+        .cloned()
+        .ok_or_else(|| ProxyError::validation(format!("Unresolved reference: {}", reference)))
+}
+
+/// Fold `example` / `examples` declared on a parameter or media type into the schema's `examples`
+fn merge_examples(schema: &mut Value, holder: &Value) {
+    let mut found = Vec::new();
+    if let Some(example) = holder.get("example") {
+        found.push(example.clone());
+    }
+    if let Some(examples) = holder.get("examples").and_then(|e| e.as_object()) {
+        // OpenAPI Example Objects: { name: { value, summary, ... } }
+        found.extend(examples.values().filter_map(|example| example.get("value").cloned()));
+    }
+    if found.is_empty() {
+        return;
+    }
+    if let Some(obj) = schema.as_object_mut() {
+        let examples = obj.entry("examples").or_insert_with(|| json!([]));
+        if let Some(examples) = examples.as_array_mut() {
+            for example in found {
+                if !examples.contains(&example) {
+                    examples.push(example);
+                }
+            }
+        }
+    }
+}
+
+fn string_field(value: &Value, field: &str) -> Option<String> {
+    value.get(field).and_then(|v| v.as_str()).map(String::from)
+}
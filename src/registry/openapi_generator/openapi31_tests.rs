@@ -0,0 +1,133 @@
+//! Tests for OpenAPI 3.1 support in OpenAPI Capability Generator
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use serde_json::json;
+
+    const PETSTORE_31: &str = r##"
+    {
+        "openapi": "3.1.0",
+        "info": {"title": "Pets", "version": "1.0.0"},
+        "paths": {
+            "/pets/{petId}": {
+                "parameters": [
+                    {"name": "petId", "in": "path", "required": true, "schema": {"type": "string"}, "example": "pet-42"}
+                ],
+                "get": {
+                    "operationId": "getPet",
+                    "summary": "Get a pet",
+                    "parameters": [
+                        {
+                            "name": "fields",
+                            "in": "query",
+                            "schema": {"type": ["string", "null"]},
+                            "examples": {"short": {"value": "name,tag"}}
+                        }
+                    ],
+                    "responses": {"200": {"description": "A pet"}}
+                }
+            },
+            "/pets": {
+                "post": {
+                    "operationId": "createPet",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {"schema": {"$ref": "#/components/schemas/Pet"}}
+                        }
+                    },
+                    "responses": {"201": {"description": "Created"}}
+                }
+            }
+        },
+        "webhooks": {
+            "newPet": {
+                "post": {
+                    "operationId": "newPetWebhook",
+                    "summary": "A pet was added",
+                    "requestBody": {
+                        "content": {
+                            "application/json": {"schema": {"$ref": "#/components/schemas/Pet"}}
+                        }
+                    },
+                    "responses": {"200": {"description": "Acknowledged"}}
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "Pet": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {
+                        "name": {"type": "string", "example": "Rex"},
+                        "kind": {"const": "dog"},
+                        "tags": {"type": "array", "prefixItems": [{"type": "string"}]},
+                        "parent": {"$ref": "#/components/schemas/Pet"}
+                    },
+                    "discriminator": {"propertyName": "kind"}
+                }
+            }
+        }
+    }
+    "##;
+
+    fn tool<'a>(file: &'a CapabilityFile, name: &str) -> &'a ToolDefinition {
+        file.tools.iter().find(|t| t.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_openapi31_detected_from_spec_and_openapi3_entry_points() {
+        let mut generator = OpenAPICapabilityGenerator::new("https://api.example.com".to_string());
+        let from_spec = generator.generate_from_spec(PETSTORE_31).unwrap();
+        let from_openapi3 = generator.generate_from_openapi3(PETSTORE_31).unwrap();
+
+        assert_eq!(from_spec.tools.len(), 3);
+        assert_eq!(from_openapi3.tools.len(), 3);
+    }
+
+    #[test]
+    fn test_openapi31_schemas_keep_2020_12_keywords() {
+        let mut generator = OpenAPICapabilityGenerator::new("https://api.example.com".to_string());
+        let file = generator.generate_from_openapi31(PETSTORE_31).unwrap();
+
+        let get_pet = tool(&file, "getPet");
+        assert_eq!(get_pet.input_schema["properties"]["fields"]["type"], json!(["string", "null"]));
+        assert_eq!(get_pet.input_schema["required"], json!(["petId"]));
+
+        let body = &tool(&file, "createPet").input_schema["properties"]["body"];
+        assert_eq!(body["properties"]["kind"]["const"], "dog");
+        assert_eq!(body["properties"]["tags"]["prefixItems"][0]["type"], "string");
+        assert_eq!(body["properties"]["name"]["examples"], json!(["Rex"]));
+        assert!(body.get("discriminator").is_none());
+        // Recursive references are cut off instead of expanded forever
+        assert_eq!(body["properties"]["parent"]["description"], "Recursive reference to Pet");
+    }
+
+    #[test]
+    fn test_openapi31_webhooks_become_notification_tools() {
+        let mut generator = OpenAPICapabilityGenerator::new("https://api.example.com".to_string())
+            .with_webhook_base_url("https://hooks.internal".to_string());
+        let file = generator.generate_from_openapi31(PETSTORE_31).unwrap();
+
+        let webhook = tool(&file, "newPetWebhook");
+        let annotations = webhook.annotations.as_ref().unwrap();
+        assert_eq!(annotations["webhook"], "newPet");
+        assert_eq!(annotations["notification_method"], "notifications/webhooks/newPet");
+        assert!(webhook.description.starts_with("Webhook 'newPet'"));
+        assert_eq!(webhook.routing.config["url"], "https://hooks.internal/webhooks/newPet");
+        assert_eq!(webhook.routing.config["method"], "POST");
+    }
+
+    #[test]
+    fn test_openapi31_examples_preserved_as_elicitation_metadata() {
+        let mut generator = OpenAPICapabilityGenerator::new("https://api.example.com".to_string());
+        let file = generator.generate_from_openapi31(PETSTORE_31).unwrap();
+
+        let annotations = tool(&file, "getPet").annotations.clone().unwrap();
+        let examples: Value = serde_json::from_str(&annotations["elicitation_examples"]).unwrap();
+        assert_eq!(examples["petId"], json!(["pet-42"]));
+        assert_eq!(examples["fields"], json!(["name,tag"]));
+    }
+}
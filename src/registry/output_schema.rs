@@ -0,0 +1,307 @@
+//! Tool output schemas
+//!
+//! MCP requires a tool's `outputSchema` to describe an object, and the
+//! `structuredContent` of its results to match it. Generators infer output
+//! schemas from response bodies and return types; responses that are not
+//! objects (arrays, scalars) are wrapped as `{"result": <value>}` on both sides
+//! so the schema and the structured content always agree.
+//!
+//! Outputs are validated against the schema when a call returns. A mismatch
+//! either fails the call or is reported next to the result, depending on
+//! [`OutputValidationMode`], and is counted per tool so that backends drifting
+//! away from their declared schema show up before clients break. Each tool's
+//! schema is compiled once and recompiled only when the schema changes.
+
+use jsonschema::JSONSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Field holding non-object outputs
+pub const RESULT_FIELD: &str = "result";
+
+/// Make `schema` usable as an MCP output schema, wrapping non-object schemas
+pub fn object_output_schema(mut schema: Value) -> Value {
+    strip_unresolved_refs(&mut schema);
+    if describes_object(&schema) {
+        if schema.get("type").is_none() {
+            schema["type"] = json!("object");
+        }
+        return schema;
+    }
+
+    json!({
+        "type": "object",
+        "properties": { RESULT_FIELD: schema },
+        "required": [RESULT_FIELD]
+    })
+}
+
+/// Extract the structured output of a successful tool call from its result data
+///
+/// HTTP-routed tools return `{status, headers, body}`; their output is the
+/// response body, parsed as JSON when possible. Non-object outputs are wrapped
+/// the same way as [`object_output_schema`] wraps their schema.
+pub fn structured_output(data: &Value) -> Value {
+    let output = match (data.get("status"), data.get("body")) {
+        (Some(_), Some(Value::String(body))) => {
+            serde_json::from_str(body).unwrap_or_else(|_| Value::String(body.clone()))
+        }
+        _ => data.clone(),
+    };
+
+    if output.is_object() {
+        output
+    } else {
+        json!({ RESULT_FIELD: output })
+    }
+}
+
+/// What happens to a result whose output does not match the tool's output schema
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputValidationMode {
+    /// Return the result without structured content and report the mismatch in its metadata
+    #[default]
+    Warn,
+    /// Fail the call with the mismatches
+    Fail,
+}
+
+/// A place where an output does not match its schema
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value, empty for the output itself
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.path.as_str() {
+            "" => write!(f, "{}", self.message),
+            path => write!(f, "{}: {}", path, self.message),
+        }
+    }
+}
+
+/// Where `output` does not match `schema`; empty if it matches
+///
+/// Compiles `schema` on every call; [`SchemaDriftTracker::validate`] keeps the
+/// compiled schema of each tool.
+pub fn schema_violations(schema: &Value, output: &Value) -> Vec<SchemaViolation> {
+    CompiledSchema::compile(schema).violations(output)
+}
+
+/// An output schema compiled for validation, or why it does not compile
+struct CompiledSchema {
+    source: Value,
+    schema: std::result::Result<JSONSchema, String>,
+}
+
+impl std::fmt::Debug for CompiledSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompiledSchema").field("source", &self.source).finish_non_exhaustive()
+    }
+}
+
+impl CompiledSchema {
+    fn compile(schema: &Value) -> Self {
+        Self {
+            source: schema.clone(),
+            schema: JSONSchema::compile(schema).map_err(|e| e.to_string()),
+        }
+    }
+
+    fn violations(&self, output: &Value) -> Vec<SchemaViolation> {
+        let schema = match &self.schema {
+            Ok(schema) => schema,
+            Err(e) => {
+                return vec![SchemaViolation { path: String::new(), message: format!("invalid output schema: {}", e) }];
+            }
+        };
+        let violations = match schema.validate(output) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .map(|e| SchemaViolation { path: e.instance_path.to_string(), message: e.to_string() })
+                .collect(),
+        };
+        violations
+    }
+}
+
+/// How often a tool's outputs matched its output schema
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SchemaDriftMetrics {
+    /// Outputs validated
+    pub validated: u64,
+    /// Outputs that did not match
+    pub mismatches: u64,
+    /// Share of outputs that did not match, from 0 to 1
+    pub drift_rate: f64,
+    /// Violations of the last mismatching output
+    pub last_violations: Vec<SchemaViolation>,
+}
+
+/// Per-tool schema drift metrics, and the compiled output schema of each tool
+#[derive(Debug, Default)]
+pub struct SchemaDriftTracker {
+    tools: Mutex<HashMap<String, SchemaDriftMetrics>>,
+    schemas: Mutex<HashMap<String, Arc<CompiledSchema>>>,
+}
+
+impl SchemaDriftTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validate an output of `tool` against its output `schema` and record the outcome
+    pub fn validate(&self, tool: &str, schema: &Value, output: &Value) -> Vec<SchemaViolation> {
+        let compiled = {
+            let mut schemas = self.schemas.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            match schemas.get(tool).filter(|compiled| compiled.source == *schema) {
+                Some(compiled) => Arc::clone(compiled),
+                None => {
+                    let compiled = Arc::new(CompiledSchema::compile(schema));
+                    schemas.insert(tool.to_string(), Arc::clone(&compiled));
+                    compiled
+                }
+            }
+        };
+        let violations = compiled.violations(output);
+        self.record(tool, &violations);
+        violations
+    }
+
+    /// Record the outcome of validating an output of `tool`
+    pub fn record(&self, tool: &str, violations: &[SchemaViolation]) {
+        if let Ok(mut tools) = self.tools.lock() {
+            let metrics = tools.entry(tool.to_string()).or_default();
+            metrics.validated += 1;
+            if !violations.is_empty() {
+                metrics.mismatches += 1;
+                metrics.last_violations = violations.to_vec();
+            }
+            metrics.drift_rate = metrics.mismatches as f64 / metrics.validated as f64;
+        }
+    }
+
+    /// Drift metrics of a tool
+    pub fn metrics(&self, tool: &str) -> Option<SchemaDriftMetrics> {
+        self.tools.lock().ok()?.get(tool).cloned()
+    }
+
+    /// Drift metrics of every tool with a validated output
+    pub fn all_metrics(&self) -> HashMap<String, SchemaDriftMetrics> {
+        self.tools.lock().map(|tools| tools.clone()).unwrap_or_default()
+    }
+}
+
+fn describes_object(schema: &Value) -> bool {
+    match schema.get("type") {
+        Some(Value::String(t)) => t == "object",
+        Some(_) => false,
+        None => schema.get("properties").is_some(),
+    }
+}
+
+/// Remove `$ref`s that point outside the schema (e.g. `#/components/schemas/..`)
+///
+/// Generators that could not inline a reference leave a placeholder with the
+/// `$ref` next to a type and description; the reference would not resolve
+/// inside the tool's schema and make it fail to compile.
+fn strip_unresolved_refs(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            let is_local = object.get("$ref")
+                .and_then(Value::as_str)
+                .is_some_and(|r| r == "#" || r.starts_with("#/definitions/") || r.starts_with("#/$defs/"));
+            if !is_local {
+                object.remove("$ref");
+            }
+            for value in object.values_mut() {
+                strip_unresolved_refs(value);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_unresolved_refs),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_output_schema() {
+        let schema = object_output_schema(json!({"properties": {"id": {"type": "integer"}}}));
+        assert_eq!(schema["type"], "object");
+
+        let schema = object_output_schema(json!({"type": "array", "items": {"type": "string"}}));
+        assert_eq!(schema["properties"]["result"]["type"], "array");
+        assert_eq!(schema["required"], json!(["result"]));
+
+        let schema = object_output_schema(json!({"$ref": "#/components/schemas/User", "type": "object"}));
+        assert!(schema.get("$ref").is_none());
+    }
+
+    #[test]
+    fn test_structured_output() {
+        let http = json!({"status": 200, "headers": {}, "body": "{\"id\": 1}"});
+        assert_eq!(structured_output(&http), json!({"id": 1}));
+
+        let http = json!({"status": 200, "headers": {}, "body": "[1, 2]"});
+        assert_eq!(structured_output(&http), json!({"result": [1, 2]}));
+
+        let plain = json!({"stdout": "ok"});
+        assert_eq!(structured_output(&plain), plain);
+    }
+
+    #[test]
+    fn test_schema_violations() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"id": {"type": "integer"}},
+            "required": ["id"]
+        });
+        assert!(schema_violations(&schema, &json!({"id": 1})).is_empty());
+
+        let violations = schema_violations(&schema, &json!({"id": "1"}));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, "/id");
+
+        let violations = schema_violations(&schema, &json!({}));
+        assert_eq!(violations[0].path, "");
+        assert!(violations[0].to_string().contains("id"));
+    }
+
+    #[test]
+    fn test_schema_drift_tracker() {
+        let tracker = SchemaDriftTracker::new();
+        tracker.record("get_user", &[]);
+        tracker.record("get_user", &[SchemaViolation { path: "/id".to_string(), message: "not an integer".to_string() }]);
+
+        let metrics = tracker.metrics("get_user").unwrap();
+        assert_eq!((metrics.validated, metrics.mismatches), (2, 1));
+        assert_eq!(metrics.drift_rate, 0.5);
+        assert_eq!(metrics.last_violations[0].path, "/id");
+        assert!(tracker.metrics("other").is_none());
+        assert_eq!(tracker.all_metrics().len(), 1);
+    }
+
+    #[test]
+    fn test_validate_caches_compiled_schema() {
+        let tracker = SchemaDriftTracker::new();
+        let schema = json!({"type": "object", "properties": {"id": {"type": "integer"}}});
+        assert!(tracker.validate("get_user", &schema, &json!({"id": 1})).is_empty());
+        let compiled = Arc::clone(&tracker.schemas.lock().unwrap()["get_user"]);
+        assert_eq!(tracker.validate("get_user", &schema, &json!({"id": "1"})).len(), 1);
+        assert!(Arc::ptr_eq(&compiled, &tracker.schemas.lock().unwrap()["get_user"]));
+
+        // A reloaded tool with a different schema is recompiled
+        let schema = json!({"type": "object", "properties": {"id": {"type": "string"}}});
+        assert!(tracker.validate("get_user", &schema, &json!({"id": "1"})).is_empty());
+        assert_eq!(tracker.metrics("get_user").unwrap().validated, 3);
+    }
+}
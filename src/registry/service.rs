@@ -5,21 +5,38 @@
 //! - Near-instant hot-reloading with file system watching
 //! - Parallel YAML processing across CPU cores
 //! - Smart caching with incremental updates
+//! - Remote (git, HTTP, S3) sources synced to a local cache and refreshed periodically
+//! - Optional SQLite storage backend for fast cold starts of very large registries
+//! - Versioned tools (`name@version`) with default-version resolution for bare names
+//! - `${VAR}` / `${VAR:-default}` environment templating in routing configs, resolved at load time
+//! - Snapshot export/import of the effective registry for reproducible deployments
+//! - Persistent runtime enable/disable and hide/show overrides for tools and capability files
+//! - Per-tool change events on every reload, so dependents (e.g. embeddings) update incrementally
+//! - Runtime-generated tags (e.g. discovery categories) merged into the tools' own
 
 use crate::config::RegistryConfig;
 use crate::error::{ProxyError, Result};
+use crate::registry::env_template::{redact_values, EnvTemplater};
+use crate::registry::migration::parse_capability_file;
+use crate::registry::output_schema::OutputValidationMode;
+use crate::registry::remote_source::{RemoteSource, RemoteSourceSyncer};
+use crate::registry::snapshot::{RegistrySnapshot, SnapshotImportReport};
+use crate::registry::sqlite_store::SqliteRegistryStore;
+use crate::registry::tool_state::{ToolState, ToolStateOverrides, ToolStateStore};
+use crate::registry::toolset::{ScopePatterns, ToolFilter, ToolScope, Toolset};
 use crate::registry::types::*;
+use crate::registry::versioning::{compare_versions, resolve_default_versions, split_versioned_name};
 use crate::mcp::notifications::McpNotificationManager;
 use arc_swap::ArcSwap;
 use dashmap::DashMap;
 use globset::{Glob, GlobMatcher};
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
 use rayon::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
-use std::time::{Duration, Instant, SystemTime};
-use tokio::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, mpsc};
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 use walkdir::WalkDir;
@@ -29,9 +46,6 @@ pub struct RegistryService {
     /// Lock-free atomic registry for zero-contention reads
     registry: ArcSwap<CapabilityRegistry>,
 
-    /// Concurrent cache for fast lookups
-    cache: DashMap<String, Arc<CapabilityFile>>,
-
     /// Compiled glob patterns for performance
     patterns: Vec<GlobMatcher>,
 
@@ -49,6 +63,41 @@ pub struct RegistryService {
 
     /// Optional notification manager for MCP list_changed notifications
     notification_manager: RwLock<Option<Arc<McpNotificationManager>>>,
+
+    /// Syncs remote entries in `config.paths` into the local cache
+    remote_syncer: RemoteSourceSyncer,
+
+    /// Persistent store used when `config.type` is `sqlite`
+    sqlite_store: Option<Arc<SqliteRegistryStore>>,
+
+    /// Secret values resolved from `${VAR}` templates, masked in dashboard views
+    resolved_secrets: RwLock<HashSet<String>>,
+
+    /// Runtime enable/disable and hide/show overrides, applied on every build
+    tool_state: ToolStateStore,
+
+    /// Publishes the tools each reload added, changed or removed
+    changes: broadcast::Sender<RegistryChange>,
+
+    /// Tags computed at runtime (e.g. discovery categories) by tool name, added on every build
+    generated_tags: RwLock<HashMap<String, Vec<String>>>,
+}
+
+/// Tools affected by a registry reload
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RegistryChange {
+    /// Tools that were added or whose definition changed
+    pub changed: Vec<String>,
+
+    /// Tools that are no longer in the registry
+    pub removed: Vec<String>,
+}
+
+impl RegistryChange {
+    /// Whether the reload left every tool as it was
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
 }
 
 /// Complete capability registry with metadata
@@ -59,6 +108,9 @@ pub struct CapabilityRegistry {
     
     /// Tools indexed by name for fast lookup
     tools: HashMap<String, Arc<ToolDefinition>>,
+
+    /// Full tool name each versioned bare name resolves to (e.g. `search_code` -> `search_code@v2`)
+    default_versions: HashMap<String, String>,
     
     /// Registry metadata
     metadata: RegistryMetadata,
@@ -90,13 +142,37 @@ impl CapabilityRegistry {
 
     /// Get a tool definition by name
     pub fn get_tool(&self, name: &str) -> Option<ToolDefinition> {
-        self.tools.get(name).map(|tool_def| (**tool_def).clone())
+        self.tools.get(self.resolve_name(name)).map(|tool_def| (**tool_def).clone())
+    }
+
+    /// Resolve a bare tool name to its default version; other names are returned unchanged
+    pub fn resolve_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.default_versions.get(name).map(String::as_str).unwrap_or(name)
     }
 
     /// Get registry metadata
     pub fn metadata(&self) -> &RegistryMetadata {
         &self.metadata
     }
+
+    /// Tools added, changed or removed compared to `previous`
+    pub fn changes_since(&self, previous: &CapabilityRegistry) -> RegistryChange {
+        let definition = |tool: &ToolDefinition| serde_json::to_value(tool).ok();
+        let mut changed: Vec<String> = self.tools.iter()
+            .filter(|(name, tool)| {
+                previous.tools.get(*name)
+                    .map_or(true, |old| definition(old) != definition(tool))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        let mut removed: Vec<String> = previous.tools.keys()
+            .filter(|name| !self.tools.contains_key(*name))
+            .cloned()
+            .collect();
+        changed.sort();
+        removed.sort();
+        RegistryChange { changed, removed }
+    }
 }
 
 impl RegistryService {
@@ -111,6 +187,7 @@ impl RegistryService {
         let initial_registry = CapabilityRegistry {
             files: HashMap::new(),
             tools: HashMap::new(),
+            default_versions: HashMap::new(),
             metadata: RegistryMetadata {
                 file_count: 0,
                 tool_count: 0,
@@ -119,17 +196,34 @@ impl RegistryService {
             },
         };
         
+        let remote_syncer = RemoteSourceSyncer::new(config.remote.clone());
+        let sqlite_store = if config.r#type == "sqlite" {
+            info!("Using SQLite registry storage at {}", config.sqlite.path);
+            Some(Arc::new(SqliteRegistryStore::open(Path::new(&config.sqlite.path))?))
+        } else {
+            None
+        };
+        let tool_state = ToolStateStore::open(&config.tool_state.path)?;
+        let (changes, _) = broadcast::channel(64);
         let mut service = Self {
             registry: ArcSwap::from_pointee(initial_registry),
-            cache: DashMap::new(),
             patterns,
             config,
             file_times: DashMap::new(),
             _watcher: None,
             event_rx: None,
             notification_manager: RwLock::new(None),
+            remote_syncer,
+            sqlite_store,
+            resolved_secrets: RwLock::new(HashSet::new()),
+            tool_state,
+            changes,
+            generated_tags: RwLock::new(HashMap::new()),
         };
         
+        // Fetch remote sources before the initial load
+        service.sync_remote_sources().await;
+
         // Perform initial load
         service.reload_registry().await?;
         
@@ -170,9 +264,60 @@ impl RegistryService {
         } else {
             info!("🔧 DEBUG: NOT starting hot-reload background task - config.hot_reload = {}", config.hot_reload);
         }
+
+        let refresh_interval = config.remote.refresh_interval_seconds;
+        if refresh_interval > 0 && config.paths.iter().any(|p| RemoteSource::is_remote(p)) {
+            info!("Refreshing remote registry sources every {}s", refresh_interval);
+            let service_clone = service.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(refresh_interval));
+                // The first tick completes immediately and the initial sync already ran in new()
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = service_clone.refresh_remote_sources().await {
+                        error!("Remote registry refresh failed: {}", e);
+                    }
+                }
+            });
+        }
         
         Ok(service)
     }
+
+    /// Sync all remote sources, returning whether any of them changed.
+    /// Failures are logged and the previously cached copy is kept.
+    async fn sync_remote_sources(&self) -> bool {
+        let mut changed = false;
+        for path_str in &self.config.paths {
+            let Some(source) = RemoteSource::parse(path_str) else { continue };
+            match self.remote_syncer.sync(&source).await {
+                Ok(outcome) => {
+                    if outcome.changed {
+                        info!(
+                            "Registry source {} updated{}",
+                            source.display(),
+                            outcome.revision.map(|r| format!(" to {}", r)).unwrap_or_default()
+                        );
+                    }
+                    changed |= outcome.changed;
+                }
+                Err(e) => warn!("Failed to sync registry source {}, using cached copy: {}", source.display(), e),
+            }
+        }
+        changed
+    }
+
+    /// Re-sync remote sources and reload the registry if any of them changed.
+    /// A reload sends `tools/list_changed` to connected clients.
+    pub async fn refresh_remote_sources(&self) -> Result<bool> {
+        if !self.sync_remote_sources().await {
+            debug!("Remote registry sources unchanged");
+            return Ok(false);
+        }
+        self.reload_registry().await?;
+        Ok(true)
+    }
     
     /// Run the hot-reload loop (static method to avoid ownership issues)
     async fn run_hot_reload_loop(service: Arc<Self>) {
@@ -318,14 +463,85 @@ impl RegistryService {
     
     /// Get a tool definition by name (sub-microsecond lookup)
     pub fn get_tool(&self, name: &str) -> Option<Arc<ToolDefinition>> {
-        // First check cache for fastest access
-        if let Some(file) = self.cache.get(name) {
-            return file.get_tool(name).map(|t| Arc::new(t.clone()));
+        let registry = self.registry.load();
+        registry.tools.get(registry.resolve_name(name)).cloned()
+    }
+
+    /// Resolve a tool name to the registered tool it refers to
+    ///
+    /// Versioned names (`search_code@v1`) and unversioned tools resolve to
+    /// themselves; bare names of versioned tools resolve to their default version.
+    pub fn resolve_tool_name(&self, name: &str) -> String {
+        self.registry.load().resolve_name(name).to_string()
+    }
+
+    /// What happens to tool results that don't match the tool's output schema
+    pub fn output_validation_mode(&self) -> OutputValidationMode {
+        self.config.validation.output
+    }
+
+    /// Named toolset from `registry.toolsets`
+    pub fn toolset(&self, name: &str) -> Result<Toolset> {
+        let definition = self.config.toolsets.get(name)
+            .ok_or_else(|| ProxyError::validation(format!("Unknown toolset '{}'", name)))?;
+        Toolset::from_definition(name, definition)
+    }
+
+    /// Filter admitting the tools a caller limited to `scope` may see and call
+    pub fn scope_filter(&self, scope: &ToolScope) -> Result<ToolFilter> {
+        let mut filter = ToolFilter::new();
+        if let Some(ref toolset) = scope.bound {
+            filter = filter.and_toolset(self.toolset(toolset)?);
         }
-        
-        // Fallback to registry lookup
+        if !scope.active.is_empty() {
+            let active = scope.active.iter()
+                .map(|toolset| self.toolset(toolset))
+                .collect::<Result<Vec<_>>>()?;
+            filter = filter.and_any_toolset(active);
+        }
+        if !scope.patterns.is_empty() {
+            let patterns = ScopePatterns::parse(&scope.patterns)?;
+            let capability_tools = if patterns.has_capabilities() {
+                self.tools_in_capabilities(|capability| patterns.matches_capability(capability))
+            } else {
+                HashSet::new()
+            };
+            filter = filter.and_patterns(patterns, capability_tools);
+        }
+        Ok(filter)
+    }
+
+    /// Names of the tools in the capability files whose stem or `metadata.name` satisfies `matches`
+    pub fn tools_in_capabilities(&self, matches: impl Fn(&str) -> bool) -> HashSet<String> {
+        let registry = self.registry.load();
+        registry.files.iter()
+            .filter(|(path, file)| {
+                path.file_stem().and_then(|stem| stem.to_str()).is_some_and(&matches)
+                    || file.metadata.as_ref().and_then(|metadata| metadata.name.as_deref()).is_some_and(&matches)
+            })
+            .flat_map(|(_, file)| file.tools.iter().map(|tool| tool.name.clone()))
+            .collect()
+    }
+
+    /// Names of all configured toolsets
+    pub fn toolset_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.config.toolsets.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// All registered versions of a tool, oldest first
+    pub fn list_tool_versions(&self, name: &str) -> Vec<String> {
+        let (base, _) = split_versioned_name(name);
         let registry = self.registry.load();
-        registry.tools.get(name).cloned()
+        let mut versions: Vec<&str> = registry.tools.keys()
+            .filter_map(|tool_name| match split_versioned_name(tool_name) {
+                (tool_base, Some(version)) if tool_base == base => Some(version),
+                _ => None,
+            })
+            .collect();
+        versions.sort_by(|a, b| compare_versions(a, b));
+        versions.into_iter().map(String::from).collect()
     }
     
     /// List all available tools (visible and enabled only)
@@ -458,13 +674,11 @@ impl RegistryService {
     }
 
     /// Set tool visibility by name
+    ///
+    /// The change is persisted in the tool state overlay rather than the
+    /// capability file, and survives restarts.
     pub async fn set_tool_hidden(&self, tool_name: &str, hidden: bool) -> Result<()> {
-        // This is a read-only operation on the current registry
-        // For persistent changes, tools need to be modified in their source files
-        warn!("set_tool_hidden called for '{}' with hidden={}, but registry is read-only. Modify source capability files for persistent changes.", tool_name, hidden);
-        Err(crate::error::ProxyError::registry(
-            "Registry is read-only. Modify source capability files to change tool visibility.".to_string()
-        ))
+        self.set_tool_state(tool_name, ToolState { enabled: None, hidden: Some(hidden) })
     }
 
     /// Check if a tool is hidden
@@ -482,13 +696,99 @@ impl RegistryService {
     }
 
     /// Set tool enabled status by name
+    ///
+    /// The change is persisted in the tool state overlay rather than the
+    /// capability file, and survives restarts.
     pub async fn set_tool_enabled(&self, tool_name: &str, enabled: bool) -> Result<()> {
-        // This is a read-only operation on the current registry
-        // For persistent changes, tools need to be modified in their source files
-        warn!("set_tool_enabled called for '{}' with enabled={}, but registry is read-only. Modify source capability files for persistent changes.", tool_name, enabled);
-        Err(crate::error::ProxyError::registry(
-            "Registry is read-only. Modify source capability files to change tool enabled status.".to_string()
-        ))
+        self.set_tool_state(tool_name, ToolState { enabled: Some(enabled), hidden: None })
+    }
+
+    /// Override the enabled/hidden flags of a tool (unset flags are left as they are)
+    pub fn set_tool_state(&self, tool_name: &str, state: ToolState) -> Result<()> {
+        let registry = self.registry.load();
+        let name = registry.resolve_name(tool_name);
+        if !registry.tools.contains_key(name) {
+            return Err(ProxyError::validation(format!("Tool '{}' not found", tool_name)));
+        }
+        self.tool_state.set_tool(name, state)?;
+        info!("Updated tool state of '{}': {:?}", name, state);
+        self.rebuild_with_tool_state()
+    }
+
+    /// Override the enabled/hidden flags of every tool in a capability file
+    ///
+    /// `file_path` is the path as listed by [`Self::list_capability_files`].
+    pub fn set_file_state(&self, file_path: &str, state: ToolState) -> Result<()> {
+        let registry = self.registry.load();
+        if !registry.files.contains_key(Path::new(file_path)) {
+            return Err(ProxyError::validation(format!("Capability file '{}' not found", file_path)));
+        }
+        self.tool_state.set_file(file_path, state)?;
+        info!("Updated tool state of capability file '{}': {:?}", file_path, state);
+        self.rebuild_with_tool_state()
+    }
+
+    /// Remove a tool's override, restoring the flags from its capability file
+    pub fn reset_tool_state(&self, tool_name: &str) -> Result<()> {
+        let name = self.resolve_tool_name(tool_name);
+        self.tool_state.clear_tool(&name)?;
+        self.rebuild_with_tool_state()
+    }
+
+    /// Remove a capability file's override
+    pub fn reset_file_state(&self, file_path: &str) -> Result<()> {
+        self.tool_state.clear_file(file_path)?;
+        self.rebuild_with_tool_state()
+    }
+
+    /// Current tool and capability file overrides
+    pub fn tool_state_overrides(&self) -> ToolStateOverrides {
+        self.tool_state.overrides()
+    }
+
+    /// Replace the runtime-generated tags, added to each tool next to the tags from its capability file
+    ///
+    /// Generated tags aren't persisted or exported in snapshots; whoever
+    /// generates them sets them again after a restart.
+    pub fn set_generated_tags(&self, tags: HashMap<String, Vec<String>>) -> Result<()> {
+        {
+            let mut generated_tags = self.generated_tags.write()
+                .map_err(|_| ProxyError::registry("Generated tags lock poisoned".to_string()))?;
+            if *generated_tags == tags {
+                return Ok(());
+            }
+            *generated_tags = tags;
+        }
+        self.rebuild_with_tool_state()
+    }
+
+    /// Loaded capability files with the names of their tools, sorted by path
+    pub fn list_capability_files(&self) -> Vec<(String, Vec<String>)> {
+        let registry = self.registry.load();
+        let mut files: Vec<(String, Vec<String>)> = registry.files.iter()
+            .map(|(path, file)| (
+                path.display().to_string(),
+                file.tools.iter().map(|tool| tool.name.clone()).collect(),
+            ))
+            .collect();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        files
+    }
+
+    /// Rebuild the index from the loaded files with the current overrides
+    /// (no files are re-read) and notify clients
+    fn rebuild_with_tool_state(&self) -> Result<()> {
+        let start_time = Instant::now();
+        let registry = self.registry.load();
+        let files = registry.files.iter()
+            .map(|(path, file)| (path.clone(), (**file).clone()))
+            .collect();
+        let new_registry = self.build_registry(files, Duration::from_millis(registry.metadata.load_duration_ms))?;
+        self.swap_registry(new_registry);
+        debug!("Applied tool state overrides in {}ms", start_time.elapsed().as_millis());
+
+        self.notify_tools_list_changed();
+        Ok(())
     }
 
     /// Check if a tool is enabled
@@ -528,10 +828,7 @@ impl RegistryService {
         let load_duration = start_time.elapsed();
 
         // Atomic swap - zero downtime update
-        self.registry.store(Arc::new(new_registry));
-
-        // Update cache
-        self.update_cache().await;
+        self.swap_registry(new_registry);
 
         info!(
             "Registry reload completed in {}ms - {} files, {} tools",
@@ -551,8 +848,12 @@ impl RegistryService {
     /// Phase 2: Loading - Read file contents in parallel
     /// Phase 3: Parsing - Parse YAML content in parallel
     /// Phase 4: Validation - Validate capability files in parallel
-    /// Phase 5: Update - Build registry and update cache
+    /// Phase 5: Update - Build registry
     async fn execute_parallel_pipeline(&self, incremental: bool) -> Result<CapabilityRegistry> {
+        if let Some(store) = self.sqlite_store.clone() {
+            return self.execute_sqlite_pipeline(&store).await;
+        }
+
         let pipeline_start = Instant::now();
 
         // Phase 1: Discovery - Find all capability files
@@ -607,8 +908,7 @@ impl RegistryService {
         let parsed_files: Vec<(PathBuf, CapabilityFile)> = file_contents
             .par_iter()
             .map(|(path, content)| {
-                let capability_file: CapabilityFile = serde_yaml::from_str(content)
-                    .map_err(|e| ProxyError::registry(format!("Failed to parse YAML file {}: {}", path.display(), e)))?;
+                let capability_file = parse_capability_file(content, &path.display().to_string())?;
                 Ok((path.clone(), capability_file))
             })
             .collect::<Result<Vec<_>>>()?;
@@ -617,12 +917,12 @@ impl RegistryService {
 
         // Phase 4: Validation - Validate capability files in parallel
         let validation_start = Instant::now();
-        let validated_files: Vec<CapabilityFile> = parsed_files
+        let validated_files: Vec<(PathBuf, CapabilityFile)> = parsed_files
             .par_iter()
             .map(|(path, capability_file)| {
                 capability_file.validate()
                     .map_err(|e| ProxyError::registry(format!("Validation failed for {}: {}", path.display(), e)))?;
-                Ok(capability_file.clone())
+                Ok((path.clone(), capability_file.clone()))
             })
             .collect::<Result<Vec<_>>>()?;
         let validation_duration = validation_start.elapsed();
@@ -641,11 +941,63 @@ impl RegistryService {
         Ok(registry)
     }
     
+    /// SQLite variant of the pipeline: only files whose modification time differs
+    /// from the stored one are read, parsed and validated. The registry itself
+    /// is then built from the database.
+    async fn execute_sqlite_pipeline(&self, store: &SqliteRegistryStore) -> Result<CapabilityRegistry> {
+        let pipeline_start = Instant::now();
+
+        let file_paths = self.discover_capability_files().await?;
+        let stored_times = store.file_times()?;
+
+        let changed: Vec<(PathBuf, i64)> = file_paths
+            .iter()
+            .map(|path| (path.clone(), modified_ns(path)))
+            .filter(|(path, modified)| stored_times.get(path) != Some(modified))
+            .collect();
+        let discovered: HashSet<&PathBuf> = file_paths.iter().collect();
+        let removed: Vec<PathBuf> = stored_times
+            .keys()
+            .filter(|path| !discovered.contains(path))
+            .cloned()
+            .collect();
+
+        let updated: Vec<(PathBuf, i64, CapabilityFile)> = changed
+            .par_iter()
+            .map(|(path, modified)| {
+                let content = std::fs::read_to_string(path)
+                    .map_err(|e| ProxyError::registry(format!("Failed to read file {}: {}", path.display(), e)))?;
+                let capability_file = parse_capability_file(&content, &path.display().to_string())?;
+                capability_file.validate()
+                    .map_err(|e| ProxyError::registry(format!("Validation failed for {}: {}", path.display(), e)))?;
+                Ok((path.clone(), *modified, capability_file))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if !updated.is_empty() || !removed.is_empty() {
+            store.apply_changes(&updated, &removed)?;
+        }
+
+        // Keep discovery order so duplicate tool names resolve like the file backend
+        let mut stored: HashMap<PathBuf, CapabilityFile> = store.load_files()?.into_iter().collect();
+        let capability_files: Vec<(PathBuf, CapabilityFile)> = file_paths.iter()
+            .filter_map(|path| stored.remove(path).map(|file| (path.clone(), file)))
+            .collect();
+
+        info!("SQLite pipeline completed in {:?} ({} files, {} re-imported, {} removed)",
+              pipeline_start.elapsed(), capability_files.len(), updated.len(), removed.len());
+
+        self.build_registry(capability_files, pipeline_start.elapsed())
+    }
+
     /// Compile glob patterns for high-performance matching
     fn compile_glob_patterns(paths: &[String]) -> Result<Vec<GlobMatcher>> {
         let mut patterns = Vec::new();
 
         for path in paths {
+            if RemoteSource::is_remote(path) {
+                continue;
+            }
             if path.contains('*') || path.contains('?') || path.contains('[') {
                 let glob = Glob::new(path)
                     .map_err(|e| ProxyError::registry(format!("Invalid glob pattern '{}': {}", path, e)))?;
@@ -666,7 +1018,10 @@ impl RegistryService {
         // Process each configured path
         for path_str in &self.config.paths {
             debug!("Processing path: {}", path_str);
-            let path = Path::new(path_str);
+            // Remote sources are loaded from their local cache
+            let remote = RemoteSource::parse(path_str);
+            let local_path = remote.as_ref().map(|source| self.remote_syncer.local_path(source));
+            let path = local_path.as_deref().unwrap_or_else(|| Path::new(path_str));
 
             if path.is_file() {
                 // Single file
@@ -679,7 +1034,7 @@ impl RegistryService {
                 debug!("Path is a directory: {}", path_str);
                 let dir_paths = self.discover_directory_files(path).await?;
                 all_paths.extend(dir_paths);
-            } else if path_str.contains('*') || path_str.contains('?') || path_str.contains('[') {
+            } else if remote.is_none() && (path_str.contains('*') || path_str.contains('?') || path_str.contains('[')) {
                 // Glob pattern
                 debug!("Path is a glob pattern: {}", path_str);
                 let glob_paths = self.discover_glob_files(path_str).await?;
@@ -760,8 +1115,7 @@ impl RegistryService {
         let content = fs::read_to_string(path)
             .map_err(|e| ProxyError::registry(format!("Failed to read file {}: {}", path.display(), e)))?;
 
-        let capability_file: CapabilityFile = serde_yaml::from_str(&content)
-            .map_err(|e| ProxyError::registry(format!("Failed to parse YAML file {}: {}", path.display(), e)))?;
+        let capability_file = parse_capability_file(&content, &path.display().to_string())?;
 
         // Validate the capability file
         capability_file.validate()
@@ -778,22 +1132,44 @@ impl RegistryService {
     }
 
     /// Build a new registry from capability files
-    fn build_registry(&self, capability_files: Vec<CapabilityFile>, load_duration: Duration) -> Result<CapabilityRegistry> {
+    fn build_registry(&self, capability_files: Vec<(PathBuf, CapabilityFile)>, load_duration: Duration) -> Result<CapabilityRegistry> {
         let mut files = HashMap::new();
         let mut tools = HashMap::new();
+        let templating = &self.config.env_templating;
+        let mut templater = templating.enabled.then(|| EnvTemplater::new(templating.strict));
+        let tool_state = self.tool_state.overrides();
+        let generated_tags = self.generated_tags.read().map(|tags| tags.clone()).unwrap_or_default();
 
-        for (index, file) in capability_files.into_iter().enumerate() {
-            let file_path = PathBuf::from(format!("file_{}", index)); // TODO: Use actual paths
+        for (file_path, file) in capability_files {
             let arc_file = Arc::new(file);
 
-            // Index tools from this file
+            // Index tools from this file; the files keep their `${VAR}` templates
+            // (e.g. for snapshots) and YAML flags, while the index holds the
+            // resolved definitions with the runtime overrides and generated tags applied
             for tool in &arc_file.tools {
-                tools.insert(tool.name().to_string(), Arc::new(tool.clone()));
+                let mut tool = tool.clone();
+                if let Some(templater) = templater.as_mut() {
+                    templater.apply(&mut tool)
+                        .map_err(|e| ProxyError::registry(format!("Failed to resolve templates in {}: {}", file_path.display(), e)))?;
+                }
+                tool_state.apply(&mut tool, &file_path);
+                for tag in generated_tags.get(tool.name()).into_iter().flatten() {
+                    if !tool.tags.contains(tag) {
+                        tool.tags.push(tag.clone());
+                    }
+                }
+                tools.insert(tool.name().to_string(), Arc::new(tool));
             }
 
             files.insert(file_path, arc_file);
         }
 
+        if let Ok(mut resolved_secrets) = self.resolved_secrets.write() {
+            *resolved_secrets = templater.map(EnvTemplater::into_secrets).unwrap_or_default();
+        }
+
+        let default_versions = resolve_default_versions(tools.keys().map(String::as_str), &self.config.versioning);
+
         let metadata = RegistryMetadata {
             file_count: files.len(),
             tool_count: tools.len(),
@@ -804,27 +1180,11 @@ impl RegistryService {
         Ok(CapabilityRegistry {
             files,
             tools,
+            default_versions,
             metadata,
         })
     }
 
-    /// Update the concurrent cache for fast lookups
-    async fn update_cache(&self) {
-        let registry = self.registry.load();
-
-        // Clear old cache
-        self.cache.clear();
-
-        // Populate cache with most frequently accessed tools
-        for (name, _tool_def) in &registry.tools {
-            if let Some(file) = registry.files.values().find(|f| f.get_tool(name).is_some()) {
-                self.cache.insert(name.clone(), file.clone());
-            }
-        }
-
-        debug!("Cache updated with {} entries", self.cache.len());
-    }
-
     /// Set up file system watcher for hot-reload
     async fn setup_file_watcher(&mut self) -> Result<()> {
         info!("🔧 DEBUG: setup_file_watcher() called - starting file watcher setup");
@@ -940,6 +1300,88 @@ impl RegistryService {
         false
     }
 
+    /// Look up a tool in the SQLite store by name, bypassing the in-memory registry.
+    /// Returns `None` when the registry is not backed by SQLite.
+    pub fn get_stored_tool(&self, name: &str) -> Result<Option<ToolDefinition>> {
+        match self.sqlite_store {
+            Some(ref store) => store.get_tool(name),
+            None => Ok(None),
+        }
+    }
+
+    /// Mask secret values resolved from `${VAR}` templates in `value` (for display)
+    pub fn redact_resolved_secrets(&self, value: &mut serde_json::Value) {
+        if let Ok(resolved_secrets) = self.resolved_secrets.read() {
+            redact_values(value, &resolved_secrets);
+        }
+    }
+
+    /// Snapshot every loaded capability file, with the runtime overrides
+    /// folded into the tools' flags
+    pub fn export_snapshot(&self) -> RegistrySnapshot {
+        let registry = self.registry.load();
+        let tool_state = self.tool_state.overrides();
+        RegistrySnapshot::new(registry.files.iter().map(|(path, file)| {
+            let mut file = (**file).clone();
+            for tool in &mut file.tools {
+                tool_state.apply(tool, path);
+            }
+            (path.clone(), file)
+        }))
+    }
+
+    /// Write a snapshot's capability files under `target_dir` and reload the registry
+    ///
+    /// `target_dir` must be covered by the registry paths for the imported
+    /// tools to be served.
+    pub async fn import_snapshot(
+        &self,
+        snapshot: &RegistrySnapshot,
+        target_dir: &Path,
+        overwrite: bool,
+    ) -> Result<SnapshotImportReport> {
+        let report = snapshot.write_to_dir(target_dir, overwrite)?;
+        info!(
+            "Imported registry snapshot into {}: {} files written, {} skipped",
+            target_dir.display(), report.written.len(), report.skipped.len()
+        );
+        self.reload_registry().await?;
+        Ok(report)
+    }
+
+    /// Local files and directories the registry loads from: the configured
+    /// paths, the directory part of glob patterns, and the cache of remote sources
+    pub fn capability_roots(&self) -> Vec<PathBuf> {
+        self.config.paths.iter().map(|path_str| match RemoteSource::parse(path_str) {
+            Some(source) => self.remote_syncer.local_path(&source),
+            None => {
+                let root: PathBuf = Path::new(path_str).components()
+                    .take_while(|c| !c.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+                    .collect();
+                if root.as_os_str().is_empty() { PathBuf::from(".") } else { root }
+            }
+        }).collect()
+    }
+
+    /// Subscribe to the tools each reload adds, changes or removes
+    ///
+    /// Reloads that leave every tool as it was aren't published. A receiver
+    /// that falls behind gets `RecvError::Lagged` and should resync in full.
+    pub fn subscribe_changes(&self) -> broadcast::Receiver<RegistryChange> {
+        self.changes.subscribe()
+    }
+
+    /// Swap in `new_registry` and publish which tools it changed
+    fn swap_registry(&self, new_registry: CapabilityRegistry) {
+        let previous = self.registry.swap(Arc::new(new_registry));
+        let change = self.registry.load().changes_since(&previous);
+        if !change.is_empty() {
+            debug!("Registry change: {} tools added or changed, {} removed", change.changed.len(), change.removed.len());
+            // No subscribers is fine
+            let _ = self.changes.send(change);
+        }
+    }
+
     /// Set notification manager for list_changed notifications
     pub fn set_notification_manager(&self, notification_manager: Arc<McpNotificationManager>) {
         if let Ok(mut manager) = self.notification_manager.write() {
@@ -958,3 +1400,13 @@ impl RegistryService {
         }
     }
 }
+
+/// File modification time in nanoseconds since the epoch (0 if unavailable)
+fn modified_ns(path: &Path) -> i64 {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as i64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,213 @@
+//! Registry snapshots
+//!
+//! A snapshot captures the effective registry of a running instance as a
+//! single JSON document: every loaded capability file, including the files
+//! generated for external MCP servers and synced from remote sources, with
+//! the tools' current `enabled` and `hidden` flags. Importing a snapshot on
+//! another instance writes the files back under a target directory (keeping
+//! their relative layout) and reloads the registry, so both instances serve
+//! the same tools.
+
+use crate::error::{ProxyError, Result};
+use crate::registry::types::CapabilityFile;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+/// Current snapshot format version
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// Exported registry state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrySnapshot {
+    /// Snapshot format version
+    pub format_version: u32,
+    /// When the snapshot was taken
+    pub created_at: DateTime<Utc>,
+    /// Version of the instance that took the snapshot
+    pub magictunnel_version: String,
+    /// Capability files, sorted by path
+    pub files: Vec<SnapshotFile>,
+}
+
+/// A capability file in a snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    /// Relative path the file is restored to
+    pub path: String,
+    /// File contents
+    pub capability_file: CapabilityFile,
+}
+
+/// Outcome of writing a snapshot to disk
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotImportReport {
+    /// Files written
+    pub written: Vec<String>,
+    /// Existing files left untouched (import without overwrite)
+    pub skipped: Vec<String>,
+    /// Number of tools in the written files
+    pub tool_count: usize,
+}
+
+impl RegistrySnapshot {
+    /// Build a snapshot from loaded capability files
+    ///
+    /// Paths below the current directory are stored relative to it; other
+    /// paths (e.g. the remote source cache) keep their components without the
+    /// root.
+    pub fn new(files: impl IntoIterator<Item = (PathBuf, CapabilityFile)>) -> Self {
+        let current_dir = std::env::current_dir().ok();
+        let mut files: Vec<SnapshotFile> = files.into_iter()
+            .map(|(path, capability_file)| SnapshotFile {
+                path: snapshot_path(&path, current_dir.as_deref()),
+                capability_file,
+            })
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Self {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            created_at: Utc::now(),
+            magictunnel_version: env!("CARGO_PKG_VERSION").to_string(),
+            files,
+        }
+    }
+
+    /// Total number of tools in the snapshot
+    pub fn tool_count(&self) -> usize {
+        self.files.iter().map(|f| f.capability_file.tools.len()).sum()
+    }
+
+    /// Check the format version, file paths and capability files
+    pub fn validate(&self) -> Result<()> {
+        if self.format_version > SNAPSHOT_FORMAT_VERSION {
+            return Err(ProxyError::validation(format!(
+                "Snapshot format version {} is newer than the supported version {}",
+                self.format_version, SNAPSHOT_FORMAT_VERSION
+            )));
+        }
+
+        let mut paths = HashSet::new();
+        for file in &self.files {
+            relative_path(&file.path)?;
+            if !paths.insert(file.path.as_str()) {
+                return Err(ProxyError::validation(format!("Snapshot contains '{}' more than once", file.path)));
+            }
+            file.capability_file.validate().map_err(|e| {
+                ProxyError::validation(format!("Invalid capability file '{}' in snapshot: {}", file.path, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Write the snapshot's files under `target_dir`
+    ///
+    /// Existing files are only replaced when `overwrite` is set.
+    pub fn write_to_dir(&self, target_dir: &Path, overwrite: bool) -> Result<SnapshotImportReport> {
+        self.validate()?;
+
+        let mut report = SnapshotImportReport::default();
+        for file in &self.files {
+            let path = target_dir.join(relative_path(&file.path)?);
+            if path.exists() && !overwrite {
+                report.skipped.push(file.path.clone());
+                continue;
+            }
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| {
+                    ProxyError::config(format!("Failed to create directory '{}': {}", parent.display(), e))
+                })?;
+            }
+            let content = serde_yaml::to_string(&file.capability_file)
+                .map_err(|e| ProxyError::config(format!("Failed to serialize '{}': {}", file.path, e)))?;
+            std::fs::write(&path, content)
+                .map_err(|e| ProxyError::config(format!("Failed to write '{}': {}", path.display(), e)))?;
+
+            report.written.push(file.path.clone());
+            report.tool_count += file.capability_file.tools.len();
+        }
+        Ok(report)
+    }
+}
+
+fn snapshot_path(path: &Path, current_dir: Option<&Path>) -> String {
+    let relative = current_dir
+        .and_then(|dir| path.strip_prefix(dir).ok())
+        .unwrap_or(path);
+    relative.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Parse a snapshot path, rejecting anything that could escape the target directory
+fn relative_path(path: &str) -> Result<PathBuf> {
+    let parsed = Path::new(path);
+    if path.is_empty() || !parsed.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(ProxyError::validation(format!("Invalid snapshot file path '{}'", path)));
+    }
+    Ok(parsed.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::types::{RoutingConfig, ToolDefinition};
+    use serde_json::json;
+
+    fn capability_file(tool_name: &str) -> CapabilityFile {
+        let tool = ToolDefinition::new_with_all_fields(
+            tool_name.to_string(),
+            format!("{} tool", tool_name),
+            json!({"type": "object"}),
+            RoutingConfig::new("subprocess".to_string(), json!({"command": "echo"})),
+            None,
+            false,
+            false,
+        ).unwrap();
+        CapabilityFile::new(vec![tool]).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = RegistrySnapshot::new(vec![
+            (dir.path().join("capabilities/b.yaml"), capability_file("tool_b")),
+            (PathBuf::from("capabilities/a.yaml"), capability_file("tool_a")),
+        ]);
+        assert_eq!(snapshot.files[0].path, "capabilities/a.yaml");
+        assert_eq!(snapshot.tool_count(), 2);
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: RegistrySnapshot = serde_json::from_str(&json).unwrap();
+
+        let target = tempfile::tempdir().unwrap();
+        let report = snapshot.write_to_dir(target.path(), false).unwrap();
+        assert_eq!(report.written.len(), 2);
+        assert_eq!(report.tool_count, 2);
+
+        let written = std::fs::read_to_string(target.path().join("capabilities/a.yaml")).unwrap();
+        let file: CapabilityFile = serde_yaml::from_str(&written).unwrap();
+        assert!(!file.tools[0].enabled);
+
+        let report = snapshot.write_to_dir(target.path(), false).unwrap();
+        assert!(report.written.is_empty());
+        assert_eq!(report.skipped.len(), 2);
+    }
+
+    #[test]
+    fn test_snapshot_rejects_escaping_paths() {
+        let mut snapshot = RegistrySnapshot::new(vec![(PathBuf::from("a.yaml"), capability_file("tool_a"))]);
+        snapshot.files[0].path = "../a.yaml".to_string();
+        assert!(snapshot.validate().is_err());
+
+        snapshot.files[0].path = "/etc/a.yaml".to_string();
+        assert!(snapshot.validate().is_err());
+    }
+}
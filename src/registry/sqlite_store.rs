@@ -0,0 +1,256 @@
+//! SQLite-backed registry storage
+//!
+//! With `registry.type: sqlite` the parsed capability files are persisted to a
+//! SQLite database. Capability files are still the source of truth, but on
+//! startup and reload only files whose modification time changed since the last
+//! import are read and parsed again; everything else is loaded straight from
+//! the database. Tools are indexed by name so single lookups don't require
+//! loading the whole registry.
+
+use crate::error::{ProxyError, Result};
+use crate::registry::types::{CapabilityFile, FileMetadata, ToolDefinition};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Version of the database layout, bumped when the tables change
+const STORE_SCHEMA_VERSION: i64 = 1;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS store_meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS capability_files (
+    path TEXT PRIMARY KEY,
+    modified_ns INTEGER NOT NULL,
+    schema_version INTEGER,
+    metadata TEXT
+);
+CREATE TABLE IF NOT EXISTS tools (
+    file_path TEXT NOT NULL REFERENCES capability_files(path) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    definition TEXT NOT NULL,
+    PRIMARY KEY (file_path, position)
+);
+CREATE INDEX IF NOT EXISTS idx_tools_name ON tools(name);
+"#;
+
+/// Persistent store for capability files and their tools
+pub struct SqliteRegistryStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteRegistryStore {
+    /// Open (or create) the store at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| ProxyError::registry(format!("Failed to open registry database {}: {}", path.display(), e)))?;
+        Self::init(conn)
+    }
+
+    /// Open an in-memory store
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory().map_err(sql_error)?;
+        Self::init(conn)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL; PRAGMA foreign_keys = ON;")
+            .map_err(sql_error)?;
+
+        let version: Option<i64> = conn
+            .query_row("SELECT value FROM store_meta WHERE key = 'schema_version'", [], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()
+            .unwrap_or(None)
+            .and_then(|v| v.parse().ok());
+
+        // The store is only a cache of the capability files, so an incompatible
+        // layout is simply dropped and rebuilt from them
+        if version.is_some_and(|v| v != STORE_SCHEMA_VERSION) {
+            conn.execute_batch("DROP TABLE IF EXISTS tools; DROP TABLE IF EXISTS capability_files; DROP TABLE IF EXISTS store_meta;")
+                .map_err(sql_error)?;
+        }
+
+        conn.execute_batch(SCHEMA).map_err(sql_error)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO store_meta (key, value) VALUES ('schema_version', ?1)",
+            params![STORE_SCHEMA_VERSION.to_string()],
+        )
+        .map_err(sql_error)?;
+
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|_| ProxyError::registry("Registry database lock poisoned".to_string()))
+    }
+
+    /// Modification time (nanoseconds since the epoch) of every stored file
+    pub fn file_times(&self) -> Result<HashMap<PathBuf, i64>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT path, modified_ns FROM capability_files").map_err(sql_error)?;
+        let rows = stmt
+            .query_map([], |row| Ok((PathBuf::from(row.get::<_, String>(0)?), row.get::<_, i64>(1)?)))
+            .map_err(sql_error)?;
+        rows.collect::<rusqlite::Result<HashMap<_, _>>>().map_err(sql_error)
+    }
+
+    /// Apply a batch of changes in a single transaction: `updated` files replace
+    /// their stored copy, `removed` files are deleted together with their tools
+    pub fn apply_changes(&self, updated: &[(PathBuf, i64, CapabilityFile)], removed: &[PathBuf]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction().map_err(sql_error)?;
+
+        for path in removed {
+            tx.execute("DELETE FROM capability_files WHERE path = ?1", params![path_key(path)])
+                .map_err(sql_error)?;
+        }
+
+        for (path, modified_ns, file) in updated {
+            let key = path_key(path);
+            let metadata = file.metadata.as_ref().map(serde_json::to_string).transpose()?;
+            tx.execute("DELETE FROM tools WHERE file_path = ?1", params![key]).map_err(sql_error)?;
+            tx.execute(
+                "INSERT OR REPLACE INTO capability_files (path, modified_ns, schema_version, metadata) VALUES (?1, ?2, ?3, ?4)",
+                params![key, modified_ns, file.schema_version, metadata],
+            )
+            .map_err(sql_error)?;
+
+            let mut insert = tx
+                .prepare_cached("INSERT INTO tools (file_path, position, name, definition) VALUES (?1, ?2, ?3, ?4)")
+                .map_err(sql_error)?;
+            for (position, tool) in file.tools.iter().enumerate() {
+                insert
+                    .execute(params![key, position as i64, tool.name, serde_json::to_string(tool)?])
+                    .map_err(sql_error)?;
+            }
+        }
+
+        tx.commit().map_err(sql_error)
+    }
+
+    /// Load every stored capability file, ordered by path
+    pub fn load_files(&self) -> Result<Vec<(PathBuf, CapabilityFile)>> {
+        let conn = self.conn()?;
+
+        let mut files: Vec<(PathBuf, CapabilityFile)> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        {
+            let mut stmt = conn
+                .prepare("SELECT path, schema_version, metadata FROM capability_files ORDER BY path")
+                .map_err(sql_error)?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, Option<u32>>(1)?, row.get::<_, Option<String>>(2)?))
+                })
+                .map_err(sql_error)?;
+            for row in rows {
+                let (path, schema_version, metadata) = row.map_err(sql_error)?;
+                let metadata: Option<FileMetadata> = metadata.map(|m| serde_json::from_str(&m)).transpose()?;
+                index.insert(path.clone(), files.len());
+                files.push((PathBuf::from(path), CapabilityFile { schema_version, metadata, tools: Vec::new() }));
+            }
+        }
+
+        let mut stmt = conn
+            .prepare("SELECT file_path, definition FROM tools ORDER BY file_path, position")
+            .map_err(sql_error)?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(sql_error)?;
+        for row in rows {
+            let (file_path, definition) = row.map_err(sql_error)?;
+            if let Some(&i) = index.get(&file_path) {
+                files[i].1.tools.push(serde_json::from_str(&definition)?);
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Look up a tool by name using the name index
+    pub fn get_tool(&self, name: &str) -> Result<Option<ToolDefinition>> {
+        let conn = self.conn()?;
+        let definition: Option<String> = conn
+            .query_row(
+                "SELECT definition FROM tools WHERE name = ?1 ORDER BY file_path DESC, position DESC LIMIT 1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(sql_error)?;
+        definition.map(|d| serde_json::from_str(&d).map_err(Into::into)).transpose()
+    }
+
+    /// Number of stored tools
+    pub fn tool_count(&self) -> Result<usize> {
+        let conn = self.conn()?;
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM tools", [], |row| row.get(0)).map_err(sql_error)?;
+        Ok(count as usize)
+    }
+}
+
+fn path_key(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+fn sql_error(e: rusqlite::Error) -> ProxyError {
+    ProxyError::registry(format!("Registry database error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::types::RoutingConfig;
+    use serde_json::json;
+
+    fn file(names: &[&str]) -> CapabilityFile {
+        let tools = names
+            .iter()
+            .map(|name| {
+                ToolDefinition::new_with_fields(
+                    name.to_string(),
+                    format!("{} tool", name),
+                    json!({"type": "object"}),
+                    RoutingConfig::new("http".to_string(), json!({"url": "http://localhost", "method": "GET"})),
+                    None,
+                )
+                .unwrap()
+            })
+            .collect();
+        CapabilityFile::new(tools).unwrap()
+    }
+
+    #[test]
+    fn test_incremental_updates() {
+        let store = SqliteRegistryStore::open_in_memory().unwrap();
+        let a = PathBuf::from("caps/a.yaml");
+        let b = PathBuf::from("caps/b.yaml");
+
+        store.apply_changes(&[(a.clone(), 1, file(&["a1", "a2"])), (b.clone(), 1, file(&["b1"]))], &[]).unwrap();
+        assert_eq!(store.tool_count().unwrap(), 3);
+        assert_eq!(store.file_times().unwrap()[&a], 1);
+
+        // Replacing a file drops tools that are no longer in it
+        store.apply_changes(&[(a.clone(), 2, file(&["a3"]))], &[]).unwrap();
+        assert!(store.get_tool("a1").unwrap().is_none());
+        assert_eq!(store.get_tool("a3").unwrap().unwrap().description, "a3 tool");
+        assert_eq!(store.file_times().unwrap()[&a], 2);
+
+        store.apply_changes(&[], &[b.clone()]).unwrap();
+        let files = store.load_files().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, a);
+        assert_eq!(files[0].1.tools.len(), 1);
+    }
+}
@@ -240,6 +240,8 @@ impl ToolAggregationService {
         let routing = RoutingConfig {
             r#type: "mcp_proxy".to_string(),
             config: routing_config,
+            fallback: Vec::new(),
+            canary: None,
         };
 
         Ok(ToolDefinition {
@@ -250,6 +252,14 @@ impl ToolAggregationService {
             annotations: None,
             hidden: false, // Aggregated tools are visible by default
             enabled: true, // Aggregated tools are enabled by default
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
         })
     }
 }
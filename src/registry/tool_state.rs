@@ -0,0 +1,216 @@
+//! Persistent tool enable/disable overlay
+//!
+//! Enabling, disabling, hiding or showing tools at runtime doesn't edit the
+//! capability YAML. The changes are recorded in an overlay that is persisted
+//! as JSON (`registry.tool_state.path`) and applied whenever the registry is
+//! built, so they survive restarts and registry reloads.
+//!
+//! Overrides can target a single tool or a whole capability file; a tool
+//! override takes precedence over its file's override, which takes
+//! precedence over the flags in the YAML.
+
+use crate::config::write_atomic;
+use crate::error::{ProxyError, Result};
+use crate::registry::types::ToolDefinition;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Overridden flags; `None` keeps the value from the capability file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hidden: Option<bool>,
+}
+
+impl ToolState {
+    /// Whether the state overrides nothing
+    pub fn is_empty(&self) -> bool {
+        self.enabled.is_none() && self.hidden.is_none()
+    }
+
+    /// Combine with `other`, whose set flags win
+    pub fn merge(self, other: ToolState) -> ToolState {
+        ToolState {
+            enabled: other.enabled.or(self.enabled),
+            hidden: other.hidden.or(self.hidden),
+        }
+    }
+}
+
+/// All overrides
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolStateOverrides {
+    /// Overrides by tool name
+    #[serde(default)]
+    pub tools: BTreeMap<String, ToolState>,
+    /// Overrides by capability file path (as listed by the registry)
+    #[serde(default)]
+    pub files: BTreeMap<String, ToolState>,
+}
+
+impl ToolStateOverrides {
+    /// Whether there are no overrides
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty() && self.files.is_empty()
+    }
+
+    /// Apply the overrides to a tool loaded from `file_path`
+    pub fn apply(&self, tool: &mut ToolDefinition, file_path: &Path) {
+        let file_state = self.files.get(&file_path.display().to_string()).copied().unwrap_or_default();
+        let state = file_state.merge(self.tools.get(&tool.name).copied().unwrap_or_default());
+        if let Some(enabled) = state.enabled {
+            tool.set_enabled(enabled);
+        }
+        if let Some(hidden) = state.hidden {
+            tool.set_hidden(hidden);
+        }
+    }
+}
+
+/// Overlay store persisted as a JSON file
+#[derive(Debug)]
+pub struct ToolStateStore {
+    path: PathBuf,
+    overrides: RwLock<ToolStateOverrides>,
+}
+
+impl ToolStateStore {
+    /// Open the store, loading existing overrides from `path` if it exists
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let overrides = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| ProxyError::registry(format!("Failed to read tool state '{}': {}", path.display(), e)))?;
+            serde_json::from_str(&content)
+                .map_err(|e| ProxyError::registry(format!("Invalid tool state '{}': {}", path.display(), e)))?
+        } else {
+            ToolStateOverrides::default()
+        };
+
+        Ok(Self { path, overrides: RwLock::new(overrides) })
+    }
+
+    /// Current overrides
+    pub fn overrides(&self) -> ToolStateOverrides {
+        self.overrides.read().map(|o| o.clone()).unwrap_or_default()
+    }
+
+    /// Merge `state` into the override of a tool (an empty state removes it)
+    pub fn set_tool(&self, name: &str, state: ToolState) -> Result<()> {
+        self.update(|overrides| set_entry(&mut overrides.tools, name, state))
+    }
+
+    /// Merge `state` into the override of a capability file (an empty state removes it)
+    pub fn set_file(&self, path: &str, state: ToolState) -> Result<()> {
+        self.update(|overrides| set_entry(&mut overrides.files, path, state))
+    }
+
+    /// Remove the override of a tool
+    pub fn clear_tool(&self, name: &str) -> Result<()> {
+        self.update(|overrides| {
+            overrides.tools.remove(name);
+        })
+    }
+
+    /// Remove the override of a capability file
+    pub fn clear_file(&self, path: &str) -> Result<()> {
+        self.update(|overrides| {
+            overrides.files.remove(path);
+        })
+    }
+
+    /// Replace all overrides
+    pub fn replace(&self, overrides: ToolStateOverrides) -> Result<()> {
+        self.update(|current| *current = overrides)
+    }
+
+    fn update(&self, change: impl FnOnce(&mut ToolStateOverrides)) -> Result<()> {
+        let mut overrides = self.overrides.write()
+            .map_err(|_| ProxyError::registry("Tool state lock poisoned".to_string()))?;
+        let mut updated = overrides.clone();
+        change(&mut updated);
+        self.save(&updated)?;
+        *overrides = updated;
+        Ok(())
+    }
+
+    fn save(&self, overrides: &ToolStateOverrides) -> Result<()> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ProxyError::registry(format!("Failed to create '{}': {}", parent.display(), e)))?;
+        }
+        let content = serde_json::to_string_pretty(overrides)?;
+        write_atomic(&self.path, content.as_bytes(), false)
+            .map_err(|e| ProxyError::registry(format!("Failed to write tool state '{}': {}", self.path.display(), e)))
+    }
+}
+
+fn set_entry(entries: &mut BTreeMap<String, ToolState>, key: &str, state: ToolState) {
+    let merged = entries.get(key).copied().unwrap_or_default().merge(state);
+    if merged.is_empty() {
+        entries.remove(key);
+    } else {
+        entries.insert(key.to_string(), merged);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::types::RoutingConfig;
+    use serde_json::json;
+
+    fn tool(name: &str) -> ToolDefinition {
+        ToolDefinition::new_with_fields(
+            name.to_string(),
+            format!("{} tool", name),
+            json!({"type": "object"}),
+            RoutingConfig::new("subprocess".to_string(), json!({"command": "echo"})),
+            None,
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_tool_override_wins_over_file_override() {
+        let mut overrides = ToolStateOverrides::default();
+        overrides.files.insert("caps/git.yaml".to_string(), ToolState { enabled: Some(false), hidden: Some(true) });
+        overrides.tools.insert("git_status".to_string(), ToolState { enabled: Some(true), hidden: None });
+
+        let mut status = tool("git_status");
+        overrides.apply(&mut status, Path::new("caps/git.yaml"));
+        assert!(status.is_enabled());
+        assert!(status.is_hidden());
+
+        let mut log = tool("git_log");
+        overrides.apply(&mut log, Path::new("caps/git.yaml"));
+        assert!(!log.is_enabled());
+
+        let mut other = tool("ping");
+        overrides.apply(&mut other, Path::new("caps/net.yaml"));
+        assert!(other.is_enabled());
+        assert!(!other.is_hidden());
+    }
+
+    #[test]
+    fn test_store_persists_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state/tool-state.json");
+
+        let store = ToolStateStore::open(&path).unwrap();
+        store.set_tool("ping", ToolState { enabled: Some(false), hidden: None }).unwrap();
+        store.set_tool("ping", ToolState { enabled: None, hidden: Some(true) }).unwrap();
+        store.set_file("caps/git.yaml", ToolState { enabled: Some(false), hidden: None }).unwrap();
+
+        let reopened = ToolStateStore::open(&path).unwrap();
+        let overrides = reopened.overrides();
+        assert_eq!(overrides.tools["ping"], ToolState { enabled: Some(false), hidden: Some(true) });
+        assert_eq!(overrides.files["caps/git.yaml"].enabled, Some(false));
+
+        reopened.clear_tool("ping").unwrap();
+        assert!(ToolStateStore::open(&path).unwrap().overrides().tools.is_empty());
+    }
+}
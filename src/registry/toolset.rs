@@ -0,0 +1,548 @@
+//! Tag expressions and toolsets
+//!
+//! Tools carry free-form `tags`. A tag expression selects tools by their tags:
+//!
+//! - `git` matches tools tagged `git`
+//! - `git and not destructive` / `git & !destructive`
+//! - `(github or gitlab) and read`
+//! - `*` matches every tool
+//!
+//! Operators name toolsets in `registry.toolsets`, either as a tag expression
+//! or as a bundle of tools listed by name. A toolset bound to an API key limits
+//! everything the key can see and call; sessions switch toolsets on and off with
+//! `toolsets/activate` and `toolsets/deactivate`, and clients can narrow
+//! `tools/list` further with the `tags` and `toolset` request params.
+//!
+//! API keys can also carry `scopes`, glob patterns over tool names
+//! (`tool:github_*`, or just `github_*`) and capability files
+//! (`capability:billing`, matching the file stem or its `metadata.name`).
+//! A scoped key sees and calls only the tools matching one of its patterns,
+//! including through smart discovery.
+
+use crate::config::ToolsetDefinition;
+use crate::error::{ProxyError, Result};
+use crate::registry::types::ToolDefinition;
+use crate::registry::versioning::split_versioned_name;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::future::Future;
+
+/// Parsed tag expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagExpression {
+    /// Matches every tool
+    Any,
+    /// Matches tools carrying the tag
+    Tag(String),
+    /// Matches tools not matched by the inner expression
+    Not(Box<TagExpression>),
+    /// Matches tools matched by both expressions
+    And(Box<TagExpression>, Box<TagExpression>),
+    /// Matches tools matched by either expression
+    Or(Box<TagExpression>, Box<TagExpression>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Tag(String),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl TagExpression {
+    /// Parse a tag expression
+    pub fn parse(expression: &str) -> Result<Self> {
+        let tokens = tokenize(expression)?;
+        if tokens.is_empty() {
+            return Err(ProxyError::validation("Tag expression cannot be empty".to_string()));
+        }
+
+        let mut parser = Parser { tokens: &tokens, pos: 0 };
+        let parsed = parser.parse_or()?;
+        if parser.pos != tokens.len() {
+            return Err(ProxyError::validation(format!(
+                "Unexpected {:?} in tag expression '{}'", tokens[parser.pos], expression
+            )));
+        }
+        Ok(parsed)
+    }
+
+    /// Expression matching tools that carry all of `tags`
+    pub fn all_of(tags: &[String]) -> Self {
+        tags.iter()
+            .map(|tag| TagExpression::Tag(tag.clone()))
+            .reduce(|a, b| TagExpression::And(Box::new(a), Box::new(b)))
+            .unwrap_or(TagExpression::Any)
+    }
+
+    /// Check the expression against a set of tags (case-insensitive)
+    pub fn matches(&self, tags: &[String]) -> bool {
+        match self {
+            TagExpression::Any => true,
+            TagExpression::Tag(tag) => tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            TagExpression::Not(inner) => !inner.matches(tags),
+            TagExpression::And(a, b) => a.matches(tags) && b.matches(tags),
+            TagExpression::Or(a, b) => a.matches(tags) || b.matches(tags),
+        }
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '!' => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            '&' | '|' => {
+                chars.next();
+                // Accept both `&` and `&&`, `|` and `||`
+                if chars.peek() == Some(&c) {
+                    chars.next();
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+            }
+            c if is_tag_char(c) => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek().filter(|c| is_tag_char(**c)) {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.to_ascii_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Tag(word),
+                });
+            }
+            other => {
+                return Err(ProxyError::validation(format!(
+                    "Invalid character '{}' in tag expression '{}'", other, expression
+                )));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':' | '/' | '*')
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn parse_or(&mut self) -> Result<TagExpression> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = TagExpression::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<TagExpression> {
+        let mut left = self.parse_unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.pos += 1,
+                // Adjacent terms are an implicit `and`
+                Some(Token::Tag(_)) | Some(Token::Not) | Some(Token::Open) => {}
+                _ => break,
+            }
+            let right = self.parse_unary()?;
+            left = TagExpression::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<TagExpression> {
+        let token = self.peek().cloned()
+            .ok_or_else(|| ProxyError::validation("Unexpected end of tag expression".to_string()))?;
+        self.pos += 1;
+
+        match token {
+            Token::Not => Ok(TagExpression::Not(Box::new(self.parse_unary()?))),
+            Token::Open => {
+                let inner = self.parse_or()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err(ProxyError::validation("Missing ')' in tag expression".to_string()));
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Token::Tag(tag) if tag == "*" => Ok(TagExpression::Any),
+            Token::Tag(tag) => Ok(TagExpression::Tag(tag)),
+            other => Err(ProxyError::validation(format!("Unexpected {:?} in tag expression", other))),
+        }
+    }
+}
+
+/// A named toolset from `registry.toolsets`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toolset {
+    /// Toolset name
+    pub name: String,
+    /// Description shown to clients
+    pub description: Option<String>,
+    /// Tools listed by name
+    tools: HashSet<String>,
+    /// Tag expression selecting further tools
+    expression: Option<TagExpression>,
+}
+
+impl Toolset {
+    /// Build a toolset from its configured definition
+    pub fn from_definition(name: &str, definition: &ToolsetDefinition) -> Result<Self> {
+        let (description, tools, expression) = match definition {
+            ToolsetDefinition::Expression(expression) => (None, HashSet::new(), Some(TagExpression::parse(expression)?)),
+            ToolsetDefinition::Bundle { description, tools, tags } => {
+                if tools.is_empty() && tags.is_none() {
+                    return Err(ProxyError::validation(format!("Toolset '{}' lists no tools and no tags", name)));
+                }
+                let expression = tags.as_deref().map(TagExpression::parse).transpose()?;
+                (description.clone(), tools.iter().cloned().collect(), expression)
+            }
+        };
+
+        Ok(Self { name: name.to_string(), description, tools, expression })
+    }
+
+    /// Check whether a tool belongs to the toolset
+    ///
+    /// Listing a bare name includes every version of a versioned tool.
+    pub fn matches(&self, tool: &ToolDefinition) -> bool {
+        self.tools.contains(&tool.name)
+            || self.tools.contains(split_versioned_name(&tool.name).0)
+            || self.expression.as_ref().is_some_and(|expression| expression.matches(&tool.tags))
+    }
+}
+
+/// Tool and capability patterns from an API key's `scopes`
+#[derive(Debug, Clone)]
+pub struct ScopePatterns {
+    tools: GlobSet,
+    capabilities: GlobSet,
+}
+
+impl ScopePatterns {
+    /// Parse `tool:<glob>`, `capability:<glob>` and bare (tool name) patterns
+    pub fn parse(patterns: &[String]) -> Result<Self> {
+        let mut tools = GlobSetBuilder::new();
+        let mut capabilities = GlobSetBuilder::new();
+        for pattern in patterns {
+            let (builder, glob) = match pattern.split_once(':') {
+                Some(("tool", glob)) => (&mut tools, glob),
+                Some(("capability", glob)) => (&mut capabilities, glob),
+                Some((kind, _)) => {
+                    return Err(ProxyError::validation(format!("Unknown scope kind '{}' in '{}'", kind, pattern)));
+                }
+                None => (&mut tools, pattern.as_str()),
+            };
+            if glob.is_empty() {
+                return Err(ProxyError::validation(format!("Empty scope pattern '{}'", pattern)));
+            }
+            let glob = Glob::new(glob)
+                .map_err(|e| ProxyError::validation(format!("Invalid scope pattern '{}': {}", pattern, e)))?;
+            builder.add(glob);
+        }
+
+        let build = |builder: GlobSetBuilder| builder.build()
+            .map_err(|e| ProxyError::validation(format!("Invalid scope patterns: {}", e)));
+        Ok(Self { tools: build(tools)?, capabilities: build(capabilities)? })
+    }
+
+    /// Check a tool name against the tool patterns
+    ///
+    /// A pattern matching the bare name matches every version of a versioned tool.
+    pub fn matches_tool(&self, name: &str) -> bool {
+        self.tools.is_match(name) || self.tools.is_match(split_versioned_name(name).0)
+    }
+
+    /// Check a capability name against the capability patterns
+    pub fn matches_capability(&self, capability: &str) -> bool {
+        self.capabilities.is_match(capability)
+    }
+
+    /// Whether any capability patterns were given
+    pub fn has_capabilities(&self) -> bool {
+        !self.capabilities.is_empty()
+    }
+}
+
+/// Toolsets and scope patterns limiting what a caller can see and call
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ToolScope {
+    /// Toolset bound to the caller's API key; activations never widen it
+    pub bound: Option<String>,
+    /// Toolsets activated by the session; tools must be in at least one of them
+    pub active: Vec<String>,
+    /// Scope patterns of the caller's API key; tools must match at least one of them
+    pub patterns: Vec<String>,
+}
+
+impl ToolScope {
+    /// Scope of a caller bound to `toolset`, if any
+    pub fn bound(toolset: Option<&str>) -> Self {
+        Self { bound: toolset.map(String::from), ..Self::default() }
+    }
+
+    /// Whether the scope lets every tool through
+    pub fn is_unrestricted(&self) -> bool {
+        self.bound.is_none() && self.active.is_empty() && self.patterns.is_empty()
+    }
+
+    /// Names of the toolsets in the scope
+    pub fn toolset_names(&self) -> Vec<&str> {
+        self.bound.iter().chain(self.active.iter()).map(String::as_str).collect()
+    }
+
+    /// The part of the scope fixed by the caller's credentials, without session activations
+    pub fn without_activations(&self) -> Self {
+        Self { active: Vec::new(), ..self.clone() }
+    }
+}
+
+tokio::task_local! {
+    static CALLER_SCOPE: ToolScope;
+}
+
+/// Run `future` on behalf of a caller limited to `scope`
+///
+/// Smart discovery only considers the tools the caller could call directly.
+pub async fn in_scope<F: Future>(scope: &ToolScope, future: F) -> F::Output {
+    if scope.is_unrestricted() {
+        future.await
+    } else {
+        CALLER_SCOPE.scope(scope.clone(), future).await
+    }
+}
+
+/// Scope of the caller the current call is made for (unrestricted outside [`in_scope`])
+pub fn caller_scope() -> ToolScope {
+    CALLER_SCOPE.try_with(Clone::clone).unwrap_or_default()
+}
+
+#[derive(Debug, Clone)]
+enum FilterTerm {
+    Tags(TagExpression),
+    AnyToolset(Vec<Toolset>),
+    Patterns(ScopePatterns, HashSet<String>),
+}
+
+/// Tool filter applied to tools/list and tools/call
+///
+/// Every term must match for a tool to pass, so a toolset bound to an API key
+/// or activated by a session can only be narrowed, never widened, by request
+/// params.
+#[derive(Debug, Clone, Default)]
+pub struct ToolFilter {
+    terms: Vec<FilterTerm>,
+}
+
+impl ToolFilter {
+    /// Filter that lets every tool through
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an expression that tools must also match
+    pub fn and(mut self, expression: TagExpression) -> Self {
+        self.terms.push(FilterTerm::Tags(expression));
+        self
+    }
+
+    /// Add a toolset that tools must also belong to
+    pub fn and_toolset(self, toolset: Toolset) -> Self {
+        self.and_any_toolset(vec![toolset])
+    }
+
+    /// Add toolsets that tools must belong to at least one of
+    pub fn and_any_toolset(mut self, toolsets: Vec<Toolset>) -> Self {
+        self.terms.push(FilterTerm::AnyToolset(toolsets));
+        self
+    }
+
+    /// Add scope patterns that tools must also match; `capability_tools` are
+    /// the tools of the capabilities the patterns match
+    pub fn and_patterns(mut self, patterns: ScopePatterns, capability_tools: HashSet<String>) -> Self {
+        self.terms.push(FilterTerm::Patterns(patterns, capability_tools));
+        self
+    }
+
+    /// Whether the filter restricts anything
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Check whether a tool passes the filter
+    pub fn matches(&self, tool: &ToolDefinition) -> bool {
+        self.terms.iter().all(|term| match term {
+            FilterTerm::Tags(expression) => expression.matches(&tool.tags),
+            FilterTerm::AnyToolset(toolsets) => toolsets.iter().any(|toolset| toolset.matches(tool)),
+            FilterTerm::Patterns(patterns, capability_tools) => {
+                patterns.matches_tool(&tool.name) || capability_tools.contains(&tool.name)
+            }
+        })
+    }
+}
+
+/// Tag filters requested by a client in `tools/list` params
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolListParams {
+    /// Tags a tool must all carry
+    pub tags: Vec<String>,
+    /// Named toolset to restrict the list to
+    pub toolset: Option<String>,
+}
+
+impl ToolListParams {
+    /// Read `tags` (array or comma-separated string) and `toolset` from request params
+    pub fn from_params(params: Option<&Value>) -> Self {
+        let Some(params) = params else {
+            return Self::default();
+        };
+
+        let tags = match params.get("tags") {
+            Some(Value::Array(tags)) => tags.iter().filter_map(|t| t.as_str()).map(String::from).collect(),
+            Some(Value::String(tags)) => tags.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect(),
+            _ => Vec::new(),
+        };
+        let toolset = params.get("toolset").and_then(|t| t.as_str()).map(String::from);
+
+        Self { tags, toolset }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_and_match() {
+        let expr = TagExpression::parse("(github or gitlab) and not destructive").unwrap();
+        assert!(expr.matches(&tags(&["github", "read"])));
+        assert!(!expr.matches(&tags(&["gitlab", "destructive"])));
+        assert!(!expr.matches(&tags(&["jira"])));
+
+        let expr = TagExpression::parse("git & !Destructive").unwrap();
+        assert!(expr.matches(&tags(&["GIT"])));
+        assert!(!expr.matches(&tags(&["git", "destructive"])));
+
+        // Adjacent tags are an implicit `and`
+        assert_eq!(TagExpression::parse("a b").unwrap(), TagExpression::parse("a and b").unwrap());
+        assert!(TagExpression::parse("*").unwrap().matches(&[]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(TagExpression::parse("").is_err());
+        assert!(TagExpression::parse("(git").is_err());
+        assert!(TagExpression::parse("git and").is_err());
+        assert!(TagExpression::parse("git)").is_err());
+        assert!(TagExpression::parse("git = 1").is_err());
+    }
+
+    #[test]
+    fn test_toolset_bundles() {
+        let tool = |name: &str, tool_tags: &[&str]| {
+            let mut tool = ToolDefinition::new_with_fields(
+                name.to_string(),
+                format!("{} tool", name),
+                json!({"type": "object"}),
+                crate::registry::types::RoutingConfig::new("subprocess".to_string(), json!({"command": "echo"})),
+                None,
+            ).unwrap();
+            tool.tags = tags(tool_tags);
+            tool
+        };
+
+        let review = Toolset::from_definition("review", &ToolsetDefinition::Bundle {
+            description: Some("Code review".to_string()),
+            tools: vec!["get_pull_request".to_string()],
+            tags: Some("lint".to_string()),
+        }).unwrap();
+        assert!(review.matches(&tool("get_pull_request", &[])));
+        assert!(review.matches(&tool("get_pull_request@v2", &[])));
+        assert!(review.matches(&tool("clippy", &["lint"])));
+        assert!(!review.matches(&tool("deploy", &["ops"])));
+
+        let ops = Toolset::from_definition("ops", &"ops".into()).unwrap();
+        let filter = ToolFilter::new().and_any_toolset(vec![review, ops]).and(TagExpression::parse("not lint").unwrap());
+        assert!(filter.matches(&tool("deploy", &["ops"])));
+        assert!(!filter.matches(&tool("clippy", &["lint"])));
+
+        let empty = ToolsetDefinition::Bundle { description: None, tools: vec![], tags: None };
+        assert!(Toolset::from_definition("empty", &empty).is_err());
+    }
+
+    #[test]
+    fn test_scope_patterns() {
+        let patterns = ScopePatterns::parse(&tags(&["github_*", "tool:jira_search", "capability:billing*"])).unwrap();
+        assert!(patterns.matches_tool("github_list_issues"));
+        assert!(patterns.matches_tool("jira_search@v2"));
+        assert!(!patterns.matches_tool("jira_create_issue"));
+        assert!(patterns.matches_capability("billing-api"));
+        assert!(!patterns.matches_capability("github"));
+
+        assert!(ScopePatterns::parse(&tags(&["server:github"])).is_err());
+        assert!(ScopePatterns::parse(&tags(&["tool:"])).is_err());
+        assert!(ScopePatterns::parse(&tags(&["tool:[a-"])).is_err());
+
+        let tool = |name: &str| ToolDefinition::new_with_fields(
+            name.to_string(),
+            format!("{} tool", name),
+            json!({"type": "object"}),
+            crate::registry::types::RoutingConfig::new("subprocess".to_string(), json!({"command": "echo"})),
+            None,
+        ).unwrap();
+        let capability_tools = ["create_invoice".to_string()].into_iter().collect();
+        let filter = ToolFilter::new().and_patterns(patterns, capability_tools);
+        assert!(filter.matches(&tool("github_create_pr")));
+        assert!(filter.matches(&tool("create_invoice")));
+        assert!(!filter.matches(&tool("deploy")));
+    }
+
+    #[test]
+    fn test_list_params() {
+        let params = ToolListParams::from_params(Some(&json!({"tags": ["git", "read"], "toolset": "dev"})));
+        assert_eq!(params.tags, tags(&["git", "read"]));
+        assert_eq!(params.toolset.as_deref(), Some("dev"));
+
+        let params = ToolListParams::from_params(Some(&json!({"tags": "git, read"})));
+        assert_eq!(params.tags, tags(&["git", "read"]));
+        assert_eq!(ToolListParams::from_params(None), ToolListParams::default());
+    }
+}
@@ -6,6 +6,9 @@ use crate::error::{ProxyError, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// Current capability file format version (see [`crate::registry::migration`])
+pub const CAPABILITY_SCHEMA_VERSION: u32 = 2;
+
 /// Default value for the enabled field
 fn default_enabled() -> bool {
     true
@@ -18,6 +21,13 @@ pub struct RoutingConfig {
     pub r#type: String,
     /// Configuration specific to the routing type
     pub config: Value,
+    /// Routings tried in order when this one fails or times out; a `cache`
+    /// routing serves the tool's last successful result
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback: Vec<RoutingConfig>,
+    /// Alternate routing that serves a percentage of calls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canary: Option<crate::routing::canary::CanaryConfig>,
 }
 
 impl RoutingConfig {
@@ -26,9 +36,17 @@ impl RoutingConfig {
         Self {
             r#type: routing_type,
             config,
+            fallback: Vec::new(),
+            canary: None,
         }
     }
 
+    /// Set the routings tried when this one fails
+    pub fn with_fallback(mut self, fallback: Vec<RoutingConfig>) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
     /// Validate the routing configuration
     pub fn validate(&self) -> Result<()> {
         // Validate routing type
@@ -36,6 +54,32 @@ impl RoutingConfig {
             return Err(crate::error::ProxyError::validation("Routing type cannot be empty"));
         }
 
+        if let Some(ref canary) = self.canary {
+            canary.validate()?;
+        }
+
+        for leg in &self.fallback {
+            if !leg.fallback.is_empty() {
+                return Err(crate::error::ProxyError::validation(
+                    "Fallback routings cannot have their own fallback"
+                ));
+            }
+            if leg.r#type != crate::routing::cache::FALLBACK_ROUTING_TYPE {
+                leg.validate()?;
+            }
+        }
+
+        crate::routing::transform::TransformConfig::from_routing(&self.config)?;
+        crate::routing::retry::RetryPolicy::from_routing(&self.config)?;
+
+        // Load-balanced configs are validated as each endpoint will be routed
+        if let Some(balancing) = crate::routing::load_balancer::LoadBalancingConfig::from_routing(&self.config)? {
+            for index in 0..balancing.endpoints.len() {
+                RoutingConfig::new(self.r#type.clone(), balancing.endpoint_config(&self.config, index)).validate()?;
+            }
+            return Ok(());
+        }
+
         // Validate known routing types
         match self.r#type.as_str() {
             "subprocess" => self.validate_subprocess_config(),
@@ -43,6 +87,16 @@ impl RoutingConfig {
             "llm" => self.validate_llm_config(),
             "websocket" => self.validate_websocket_config(),
             "external_mcp" => self.validate_external_mcp_config(),
+            "database" => self.validate_database_config(),
+            "lambda" => self.validate_lambda_config(),
+            "kafka" => self.validate_kafka_config(),
+            "amqp" => self.validate_amqp_config(),
+            "ssh" => self.validate_ssh_config(),
+            "docker" => self.validate_docker_config(),
+            "kubernetes_job" => self.validate_kubernetes_job_config(),
+            "webhook" => self.validate_webhook_config(),
+            "wasm" => self.validate_wasm_config(),
+            "script" => self.validate_script_config(),
             _ => {
                 // Allow unknown types but warn
                 tracing::warn!("Unknown routing type: {}", self.r#type);
@@ -102,6 +156,162 @@ impl RoutingConfig {
         Ok(())
     }
 
+    /// Validate AWS Lambda routing configuration
+    fn validate_lambda_config(&self) -> Result<()> {
+        let config = &self.config;
+
+        if config.get("function_name").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "Lambda routing requires 'function_name' field"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate database routing configuration
+    fn validate_database_config(&self) -> Result<()> {
+        let config = &self.config;
+
+        if config.get("query").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "Database routing requires 'query' field"
+            ));
+        }
+
+        if let Some(db_type) = config.get("db_type").and_then(|v| v.as_str()) {
+            if !matches!(db_type, "sqlite" | "postgresql" | "postgres" | "mysql" | "mariadb") {
+                return Err(crate::error::ProxyError::validation(format!(
+                    "Unsupported database type '{}'; expected sqlite, postgresql or mysql", db_type
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate Kafka routing configuration
+    fn validate_kafka_config(&self) -> Result<()> {
+        let config = &self.config;
+
+        if config.get("brokers").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "Kafka routing requires 'brokers' field"
+            ));
+        }
+
+        if config.get("topic").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "Kafka routing requires 'topic' field"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate AMQP routing configuration
+    fn validate_amqp_config(&self) -> Result<()> {
+        let config = &self.config;
+
+        if config.get("url").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "AMQP routing requires 'url' field"
+            ));
+        }
+
+        if config.get("routing_key").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "AMQP routing requires 'routing_key' field"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate SSH routing configuration
+    fn validate_ssh_config(&self) -> Result<()> {
+        let config = &self.config;
+
+        if config.get("host").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "SSH routing requires 'host' field"
+            ));
+        }
+
+        if config.get("command").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "SSH routing requires 'command' field"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate Docker routing configuration
+    fn validate_docker_config(&self) -> Result<()> {
+        let config = &self.config;
+
+        if config.get("image").is_none() && config.get("container").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "Docker routing requires 'image' or 'container' field"
+            ));
+        }
+
+        if config.get("command").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "Docker routing requires 'command' field"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate Kubernetes Job routing configuration
+    fn validate_kubernetes_job_config(&self) -> Result<()> {
+        let config = &self.config;
+
+        if config.get("pod_spec").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "Kubernetes Job routing requires 'pod_spec' field"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate webhook routing configuration
+    fn validate_webhook_config(&self) -> Result<()> {
+        let webhook: crate::routing::webhook::WebhookConfig = serde_json::from_value(self.config.clone())
+            .map_err(|e| crate::error::ProxyError::validation(format!("Invalid webhook routing config: {}", e)))?;
+        webhook.validate()
+    }
+
+    /// Validate WebAssembly routing configuration
+    fn validate_wasm_config(&self) -> Result<()> {
+        let config = &self.config;
+
+        if config.get("module").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "WebAssembly routing requires 'module' field"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate script routing configuration
+    fn validate_script_config(&self) -> Result<()> {
+        let config = &self.config;
+
+        if config.get("script").is_none() {
+            return Err(crate::error::ProxyError::validation(
+                "Script routing requires 'script' field"
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Validate WebSocket routing configuration
     fn validate_websocket_config(&self) -> Result<()> {
         let config = &self.config;
@@ -135,7 +345,7 @@ impl RoutingConfig {
 
     /// Check if routing type is supported
     pub fn is_supported_type(&self) -> bool {
-        matches!(self.r#type.as_str(), "subprocess" | "http" | "llm" | "websocket" | "database" | "external_mcp")
+        matches!(self.r#type.as_str(), "subprocess" | "http" | "lambda" | "llm" | "websocket" | "database" | "kafka" | "amqp" | "ssh" | "docker" | "kubernetes_job" | "webhook" | "wasm" | "script" | "external_mcp")
     }
 }
 
@@ -149,6 +359,9 @@ pub struct ToolDefinition {
     /// JSON Schema for input parameters
     #[serde(rename = "inputSchema")]
     pub input_schema: serde_json::Value,
+    /// JSON Schema describing the structured output (must describe an object)
+    #[serde(rename = "outputSchema", default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
     /// Routing configuration
     pub routing: RoutingConfig,
     /// Optional annotations for metadata
@@ -161,6 +374,61 @@ pub struct ToolDefinition {
     /// Disabled tools are not considered for routing or execution, regardless of visibility
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+    /// Whether this tool is deprecated (default: false)
+    /// Deprecated tools keep working but are ranked lower in discovery and calls carry a warning
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    /// Why the tool is deprecated and what to use instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecation_reason: Option<String>,
+    /// Date (YYYY-MM-DD) after which the tool may be removed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sunset_date: Option<String>,
+    /// Tags (categories) used to filter tools/list and to build toolsets
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Cache successful results of this tool (opt-in; for read-only tools)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<ToolCacheConfig>,
+    /// Run calls as background jobs: the call returns a job ID at once and
+    /// `job_status`/`job_result`/`job_cancel` follow the job
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub background: bool,
+    /// What a call of this tool costs, charged to the caller's budgets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<ToolCostConfig>,
+}
+
+/// Results kept per tool when a cache doesn't set `max_entries`
+pub const DEFAULT_CACHE_MAX_ENTRIES: usize = 1000;
+
+fn default_cache_max_entries() -> usize {
+    DEFAULT_CACHE_MAX_ENTRIES
+}
+
+/// Result caching for a tool
+///
+/// Results are keyed by tool name and arguments; calls whose `_meta` sets
+/// `cacheBypass: true` skip the cache and refresh the entry.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolCacheConfig {
+    /// How long a cached result is served
+    pub ttl_seconds: u64,
+    /// Results kept for this tool; the oldest are evicted first
+    #[serde(default = "default_cache_max_entries")]
+    pub max_entries: usize,
+}
+
+/// Cost of a tool's calls, in whatever unit budgets use (credits, dollars, ...)
+///
+/// Successful calls are charged `per_call` plus `per_token` for each token
+/// reported in the result's usage.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct ToolCostConfig {
+    #[serde(default)]
+    pub per_call: f64,
+    #[serde(default)]
+    pub per_token: f64,
 }
 
 impl ToolDefinition {
@@ -171,12 +439,28 @@ impl ToolDefinition {
             description: tool.description.clone().unwrap_or_else(|| format!("Tool: {}", tool.name)),
             input_schema: tool.input_schema.clone(),
             routing,
-            annotations: tool.annotations.as_ref().map(|_ann| {
-                // Convert MCP annotations to simple string map
-                std::collections::HashMap::new()
+            annotations: tool.annotations.as_ref().map(|annotations| {
+                // Keep the MCP behavior hints, as strings
+                [
+                    ("readOnlyHint", annotations.read_only_hint),
+                    ("destructiveHint", annotations.destructive_hint),
+                    ("idempotentHint", annotations.idempotent_hint),
+                    ("openWorldHint", annotations.open_world_hint),
+                ]
+                .into_iter()
+                .filter_map(|(hint, value)| Some((hint.to_string(), value?.to_string())))
+                .collect()
             }),
             hidden: true, // Default to hidden (consistent with other tools)
             enabled: true, // Default to enabled
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
         };
         definition.validate()?;
         Ok(definition)
@@ -198,6 +482,14 @@ impl ToolDefinition {
             annotations,
             hidden: false, // Default to visible
             enabled: true, // Default to enabled
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
         };
         definition.validate()?;
         Ok(definition)
@@ -221,6 +513,14 @@ impl ToolDefinition {
             annotations,
             hidden,
             enabled,
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
         };
         definition.validate()?;
         Ok(definition)
@@ -245,15 +545,9 @@ impl ToolDefinition {
             description: Some(self.description.clone()),
             title: None,
             input_schema: self.input_schema.clone(),
-            output_schema: None,
-            annotations: self.annotations.as_ref().map(|_ann| {
-                ToolAnnotations {
-                    title: None,
-                    read_only_hint: None,
-                    destructive_hint: None,
-                    idempotent_hint: None,
-                    open_world_hint: None,
-                }
+            output_schema: self.output_schema.clone(),
+            annotations: self.deprecation_annotations().or_else(|| {
+                self.annotations.as_ref().map(|_ann| ToolAnnotations::new())
             }),
         }
     }
@@ -265,6 +559,13 @@ impl ToolDefinition {
             return Err(ProxyError::validation("Tool name cannot be empty".to_string()));
         }
 
+        // Validate the version suffix, if any
+        if self.name.contains(crate::registry::versioning::VERSION_SEPARATOR) && self.version().is_none() {
+            return Err(ProxyError::validation(format!(
+                "Tool name '{}' must have the form name@version", self.name
+            )));
+        }
+
         // Validate the description
         if self.description.is_empty() {
             return Err(ProxyError::validation("Tool description cannot be empty".to_string()));
@@ -275,6 +576,50 @@ impl ToolDefinition {
             return Err(ProxyError::validation("Input schema must be a JSON object".to_string()));
         }
 
+        // Validate the output schema
+        if let Some(ref output_schema) = self.output_schema {
+            if output_schema.get("type").and_then(|t| t.as_str()) != Some("object") {
+                return Err(ProxyError::validation(format!(
+                    "Tool '{}' has an invalid outputSchema: it must describe an object", self.name
+                )));
+            }
+        }
+
+        // Validate the sunset date
+        if let Some(ref sunset_date) = self.sunset_date {
+            if chrono::NaiveDate::parse_from_str(sunset_date, "%Y-%m-%d").is_err() {
+                return Err(ProxyError::validation(format!(
+                    "Tool '{}' has invalid sunset_date '{}', expected YYYY-MM-DD", self.name, sunset_date
+                )));
+            }
+        }
+
+        // A retry policy only applies to tools that are safe to call twice
+        if self.routing.config.get(crate::routing::retry::CONFIG_KEY).is_some() && !self.is_idempotent() {
+            tracing::warn!(
+                "Tool '{}' has a retry policy but is not annotated readOnlyHint or idempotentHint; its calls won't be retried",
+                self.name
+            );
+        }
+
+        // Validate the cache configuration
+        if let Some(ref cache) = self.cache {
+            if cache.ttl_seconds == 0 || cache.max_entries == 0 {
+                return Err(ProxyError::validation(format!(
+                    "Tool '{}' has an invalid cache: ttl_seconds and max_entries must be positive", self.name
+                )));
+            }
+        }
+
+        // Validate the cost
+        if let Some(ref cost) = self.cost {
+            if !(cost.per_call >= 0.0 && cost.per_token >= 0.0 && cost.per_call.is_finite() && cost.per_token.is_finite()) {
+                return Err(ProxyError::validation(format!(
+                    "Tool '{}' has an invalid cost: per_call and per_token cannot be negative", self.name
+                )));
+            }
+        }
+
         // Validate the routing configuration
         self.routing.validate()?;
 
@@ -360,6 +705,70 @@ impl ToolDefinition {
         tool
     }
 
+    /// Tool name without its version suffix (`search_code@v2` -> `search_code`)
+    pub fn base_name(&self) -> &str {
+        crate::registry::versioning::split_versioned_name(&self.name).0
+    }
+
+    /// Version suffix of a versioned tool (`search_code@v2` -> `v2`)
+    pub fn version(&self) -> Option<&str> {
+        crate::registry::versioning::split_versioned_name(&self.name).1
+    }
+
+    /// Check if this tool is deprecated
+    pub fn is_deprecated(&self) -> bool {
+        self.deprecated
+    }
+
+    /// Human-readable deprecation warning, or `None` if the tool is not deprecated
+    pub fn deprecation_warning(&self) -> Option<String> {
+        if !self.deprecated {
+            return None;
+        }
+        let mut warning = format!("Tool '{}' is deprecated", self.name);
+        if let Some(ref sunset_date) = self.sunset_date {
+            warning.push_str(&format!(" and will be removed after {}", sunset_date));
+        }
+        if let Some(ref reason) = self.deprecation_reason {
+            warning.push_str(&format!(": {}", reason));
+        }
+        Some(warning)
+    }
+
+    /// Whether calling the tool again with the same arguments is safe: it is
+    /// annotated `readOnlyHint` or `idempotentHint`
+    pub fn is_idempotent(&self) -> bool {
+        self.annotations.as_ref().map_or(false, |annotations| {
+            ["readOnlyHint", "idempotentHint"].iter()
+                .any(|hint| annotations.get(*hint).map(String::as_str) == Some("true"))
+        })
+    }
+
+    /// Whether the tool may make destructive changes: it is annotated `destructiveHint`
+    pub fn is_destructive(&self) -> bool {
+        self.annotations.as_ref()
+            .map_or(false, |annotations| annotations.get("destructiveHint").map(String::as_str) == Some("true"))
+    }
+
+    /// Whether the tool leaves its environment unchanged: it is annotated `readOnlyHint`
+    pub fn is_read_only(&self) -> bool {
+        self.annotations.as_ref()
+            .map_or(false, |annotations| annotations.get("readOnlyHint").map(String::as_str) == Some("true"))
+    }
+
+    /// MCP annotations describing this tool's deprecation, if any
+    pub fn deprecation_annotations(&self) -> Option<ToolAnnotations> {
+        if !self.deprecated {
+            return None;
+        }
+        Some(ToolAnnotations {
+            deprecated: Some(true),
+            deprecation_reason: self.deprecation_reason.clone(),
+            sunset_date: self.sunset_date.clone(),
+            ..ToolAnnotations::new()
+        })
+    }
+
     /// Validate arguments for this tool
     pub fn validate_arguments(&self, arguments: &Value) -> Result<()> {
         // Create a temporary Tool to validate arguments
@@ -371,6 +780,10 @@ impl ToolDefinition {
 /// Capability file structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CapabilityFile {
+    /// Capability file format version; files without one are legacy (version 1)
+    /// files and are upgraded when loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
     /// File metadata
     pub metadata: Option<FileMetadata>,
     /// Tool definitions
@@ -381,6 +794,7 @@ impl CapabilityFile {
     /// Create a new capability file
     pub fn new(tools: Vec<ToolDefinition>) -> Result<Self> {
         let file = Self {
+            schema_version: Some(CAPABILITY_SCHEMA_VERSION),
             metadata: None,
             tools,
         };
@@ -391,6 +805,7 @@ impl CapabilityFile {
     /// Create a new capability file with metadata
     pub fn with_metadata(metadata: FileMetadata, tools: Vec<ToolDefinition>) -> Result<Self> {
         let file = Self {
+            schema_version: Some(CAPABILITY_SCHEMA_VERSION),
             metadata: Some(metadata),
             tools,
         };
@@ -0,0 +1,132 @@
+//! Tool versioning
+//!
+//! A tool can be registered under several versioned names (`search_code@v1`,
+//! `search_code@v2`) so capability authors can ship breaking schema changes
+//! while clients pinned to an older version keep working. Lookups by bare name
+//! are resolved to a default version according to [`ToolVersioningConfig`].
+
+use crate::config::{ToolVersionPolicy, ToolVersioningConfig};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use tracing::warn;
+
+/// Separator between a tool name and its version
+pub const VERSION_SEPARATOR: char = '@';
+
+/// Split `name@version` into its base name and version
+pub fn split_versioned_name(name: &str) -> (&str, Option<&str>) {
+    match name.rsplit_once(VERSION_SEPARATOR) {
+        Some((base, version)) if !base.is_empty() && !version.is_empty() => (base, Some(version)),
+        _ => (name, None),
+    }
+}
+
+/// Compare two version strings
+///
+/// A leading `v` is ignored and versions are compared segment by segment
+/// (split on `.` and `-`), numerically where both segments are numbers, so
+/// `v10` sorts after `v9` and `1.2.0` after `1.1.5`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn segments(version: &str) -> Vec<&str> {
+        version
+            .trim_start_matches(['v', 'V'])
+            .split(['.', '-'])
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
+    let (a_segments, b_segments) = (segments(a), segments(b));
+    for (x, y) in a_segments.iter().zip(b_segments.iter()) {
+        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a_segments.len().cmp(&b_segments.len())
+}
+
+/// Work out which registered tool each versioned bare name resolves to
+///
+/// Returns a map from bare name to full tool name. Bare names that should be
+/// served by an unversioned definition of the same name are not included.
+pub fn resolve_default_versions<'a>(
+    tool_names: impl IntoIterator<Item = &'a str>,
+    config: &ToolVersioningConfig,
+) -> HashMap<String, String> {
+    let mut unversioned = HashSet::new();
+    let mut versions: HashMap<&str, Vec<&str>> = HashMap::new();
+    for name in tool_names {
+        match split_versioned_name(name) {
+            (base, Some(version)) => versions.entry(base).or_default().push(version),
+            (base, None) => {
+                unversioned.insert(base);
+            }
+        }
+    }
+
+    let mut defaults = HashMap::new();
+    for (base, mut available) in versions {
+        if let Some(pinned) = config.defaults.get(base) {
+            if available.contains(&pinned.as_str()) {
+                defaults.insert(base.to_string(), format!("{}{}{}", base, VERSION_SEPARATOR, pinned));
+                continue;
+            }
+            warn!("Pinned default version '{}' of tool '{}' is not registered, using the default policy", pinned, base);
+        }
+
+        if unversioned.contains(base) {
+            continue;
+        }
+
+        available.sort_by(|a, b| compare_versions(a, b));
+        let version = match config.default_policy {
+            ToolVersionPolicy::Latest => available.last(),
+            ToolVersionPolicy::Oldest => available.first(),
+        };
+        if let Some(version) = version {
+            defaults.insert(base.to_string(), format!("{}{}{}", base, VERSION_SEPARATOR, version));
+        }
+    }
+    defaults
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_versioned_name() {
+        assert_eq!(split_versioned_name("search_code@v2"), ("search_code", Some("v2")));
+        assert_eq!(split_versioned_name("search_code"), ("search_code", None));
+        assert_eq!(split_versioned_name("search_code@"), ("search_code@", None));
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("v10", "v9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2.0", "1.10.0"), Ordering::Less);
+        assert_eq!(compare_versions("v2", "2"), Ordering::Equal);
+        assert_eq!(compare_versions("v2", "v2.1"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_resolve_default_versions() {
+        let names = ["search_code@v1", "search_code@v2", "search_code@v10", "read_file", "read_file@v2"];
+
+        let latest = resolve_default_versions(names, &ToolVersioningConfig::default());
+        assert_eq!(latest["search_code"], "search_code@v10");
+        // An unversioned definition keeps serving the bare name
+        assert!(!latest.contains_key("read_file"));
+
+        let config = ToolVersioningConfig {
+            default_policy: ToolVersionPolicy::Oldest,
+            defaults: HashMap::from([("read_file".to_string(), "v2".to_string())]),
+        };
+        let oldest = resolve_default_versions(names, &config);
+        assert_eq!(oldest["search_code"], "search_code@v1");
+        assert_eq!(oldest["read_file"], "read_file@v2");
+    }
+}
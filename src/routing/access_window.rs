@@ -0,0 +1,453 @@
+//! Time windows in which tools may be called
+//!
+//! `access_windows` restricts tools to approved windows, such as business
+//! hours, and keeps them closed during others, such as a release freeze:
+//!
+//! ```yaml
+//! access_windows:
+//!   timezone: "Europe/Berlin"
+//!   windows:
+//!     business_hours: { days: ["mon", "tue", "wed", "thu", "fri"], start: "09:00", end: "18:00" }
+//!     nightly: { cron: "* 0-5 * * *" }
+//!     release_freeze: { from: "2026-12-19T00:00:00Z", until: "2027-01-04T00:00:00Z" }
+//!   rules:
+//!     - tools: ["deploy_*", "capability:infrastructure"]
+//!       allowed: ["business_hours"]
+//!       denied: ["release_freeze"]
+//!     - tools: ["reindex_*"]
+//!       api_keys: ["ci-bot"]
+//!       allowed: ["nightly"]
+//! ```
+//!
+//! Rules use the syntax of API key scopes for their tools and apply to every
+//! caller unless they list `api_keys` or `users`. A window is open when all
+//! of its criteria hold; a call is refused when a matching rule has `allowed`
+//! windows of which none is open, or a `denied` window that is open.
+
+use crate::error::{ProxyError, Result};
+use crate::mcp::ToolCall;
+use crate::registry::toolset::ScopePatterns;
+use crate::routing::types::AgentResult;
+use crate::routing::{cost, rate_limit};
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use tracing::info;
+
+/// How far ahead the next opening or closing of a window is looked for
+const LOOKAHEAD_MINUTES: i64 = 8 * 24 * 60;
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+/// A named time window; every criterion given must hold for it to be open
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccessWindowConfig {
+    /// Days of the week (`mon` to `sun`; default: every day)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<String>,
+    /// Time of day the window opens (`HH:MM`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    /// Time of day the window closes (`HH:MM`); before `start` for windows spanning midnight
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    /// Cron expression of the minutes the window is open
+    /// (`minute hour day-of-month month day-of-week`, with Sunday as 0 or 7)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+    /// Instant the window opens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<DateTime<Utc>>,
+    /// Instant the window closes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<DateTime<Utc>>,
+    /// Time zone of `days`, `start`, `end` and `cron` (default: that of `access_windows`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+/// Windows restricting calls to some tools
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessRuleConfig {
+    /// Tool name and capability patterns, as in API key scopes
+    pub tools: Vec<String>,
+    /// API keys the rule applies to (default: every caller)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub api_keys: Vec<String>,
+    /// Users the rule applies to (default: every caller)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<String>,
+    /// Windows the tools may be called in (default: any time)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed: Vec<String>,
+    /// Windows the tools may not be called in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied: Vec<String>,
+}
+
+/// When tools may be called
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessWindowsConfig {
+    /// Time zone of the windows (IANA name)
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// Windows by name
+    #[serde(default)]
+    pub windows: BTreeMap<String, AccessWindowConfig>,
+    /// Rules restricting tools to windows, all of which apply
+    #[serde(default)]
+    pub rules: Vec<AccessRuleConfig>,
+}
+
+impl AccessWindowsConfig {
+    pub fn validate(&self) -> Result<()> {
+        AccessWindows::new(self.clone()).map(|_| ())
+    }
+}
+
+/// A window with its criteria parsed
+#[derive(Debug)]
+struct Window {
+    timezone: Tz,
+    days: Vec<Weekday>,
+    hours: Option<(NaiveTime, NaiveTime)>,
+    cron: Option<cron::Schedule>,
+    from: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+impl Window {
+    fn parse(name: &str, config: &AccessWindowConfig, timezone: Tz) -> Result<Self> {
+        let invalid = |what: String| ProxyError::config(format!("Invalid access window '{}': {}", name, what));
+        let timezone = match &config.timezone {
+            Some(timezone) => parse_timezone(timezone).map_err(invalid)?,
+            None => timezone,
+        };
+        let days = config.days.iter()
+            .map(|day| Weekday::from_str(day).map_err(|_| invalid(format!("unknown day '{}'", day))))
+            .collect::<Result<Vec<_>>>()?;
+        let time = |time: &str| NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| invalid(format!("'{}' is not a HH:MM time", time)));
+        let hours = match (&config.start, &config.end) {
+            (Some(start), Some(end)) => Some((time(start)?, time(end)?)),
+            (None, None) => None,
+            _ => return Err(invalid("start and end must be given together".to_string())),
+        };
+        let cron = config.cron.as_deref().map(|expression| {
+            let mut fields: Vec<String> = expression.split_whitespace().map(str::to_string).collect();
+            if fields.len() != 5 {
+                return Err(invalid(format!("cron expression '{}' must have 5 fields", expression)));
+            }
+            fields[4] = weekday_names(&fields[4]).map_err(|e| invalid(format!("cron expression '{}': {}", expression, e)))?;
+            cron::Schedule::from_str(&format!("0 {}", fields.join(" ")))
+                .map_err(|e| invalid(format!("cron expression '{}': {}", expression, e)))
+        }).transpose()?;
+        if let (Some(from), Some(until)) = (config.from, config.until) {
+            if until <= from {
+                return Err(invalid("until must be after from".to_string()));
+            }
+        }
+        if days.is_empty() && hours.is_none() && cron.is_none() && config.from.is_none() && config.until.is_none() {
+            return Err(invalid("needs days, start and end, cron, from or until".to_string()));
+        }
+        Ok(Self { timezone, days, hours, cron, from: config.from, until: config.until })
+    }
+
+    /// Whether the window is open at `now`
+    fn is_open(&self, now: DateTime<Utc>) -> bool {
+        if self.from.is_some_and(|from| now < from) || self.until.is_some_and(|until| now >= until) {
+            return false;
+        }
+        let local = now.with_timezone(&self.timezone);
+        if !self.days.is_empty() && !self.days.contains(&local.weekday()) {
+            return false;
+        }
+        if let Some((start, end)) = self.hours {
+            let time = local.time();
+            let within = if start <= end { start <= time && time < end } else { time >= start || time < end };
+            if !within {
+                return false;
+            }
+        }
+        match &self.cron {
+            Some(schedule) => local.with_second(0).and_then(|minute| minute.with_nanosecond(0))
+                .is_some_and(|minute| schedule.includes(minute)),
+            None => true,
+        }
+    }
+}
+
+/// Day-of-week field of a POSIX cron expression, where 0 and 7 are Sunday,
+/// as the day names `cron::Schedule` reads (it counts from 1 for Sunday)
+fn weekday_names(field: &str) -> std::result::Result<String, String> {
+    const NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    if field == "*" || field.chars().any(|c| c.is_ascii_alphabetic()) {
+        return Ok(field.to_string());
+    }
+    let day = |day: &str| day.parse::<usize>().ok().filter(|day| *day <= 7)
+        .ok_or_else(|| format!("'{}' is not a day of the week (0-7)", day));
+    let mut days = [false; 7];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>().ok().filter(|step| *step > 0)
+                .ok_or_else(|| format!("'{}' is not a valid step", step))?),
+            None => (part, 1),
+        };
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" => (0, 6),
+            Some((first, last)) => (day(first)?, day(last)?),
+            None if part.contains('/') => (day(range)?, 6),
+            None => (day(range)?, day(range)?),
+        };
+        if first > last {
+            return Err(format!("day range '{}' runs backwards", range));
+        }
+        for day in (first..=last).step_by(step) {
+            days[day % 7] = true;
+        }
+    }
+    Ok(NAMES.iter().zip(days).filter(|(_, set)| *set).map(|(name, _)| *name).collect::<Vec<_>>().join(","))
+}
+
+fn parse_timezone(timezone: &str) -> std::result::Result<Tz, String> {
+    timezone.parse::<Tz>().map_err(|_| format!("unknown time zone '{}'", timezone))
+}
+
+/// A rule with its patterns parsed
+#[derive(Debug)]
+struct Rule {
+    config: AccessRuleConfig,
+    tools: ScopePatterns,
+}
+
+impl Rule {
+    fn applies_to(&self, tool: &str, api_key: Option<&str>, user: Option<&str>) -> bool {
+        let subject = |listed: &[String], caller: Option<&str>| caller.is_some_and(|caller| listed.iter().any(|name| name == caller));
+        let for_everyone = self.config.api_keys.is_empty() && self.config.users.is_empty();
+        self.tools.matches_tool(tool)
+            && (for_everyone || subject(&self.config.api_keys, api_key) || subject(&self.config.users, user))
+    }
+}
+
+/// Why a call was refused
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutsideWindow {
+    /// Windows the call was checked against
+    pub windows: Vec<String>,
+    /// Whether it was refused because a denied window is open
+    pub denied: bool,
+    /// When the call can next be made, if that is within the coming week
+    pub next_allowed: Option<DateTime<Utc>>,
+}
+
+impl OutsideWindow {
+    /// Tool error for a refused call
+    pub fn into_agent_result(self, tool_call: &ToolCall) -> AgentResult {
+        let windows = self.windows.join(", ");
+        let mut error = if self.denied {
+            format!("Tool '{}' cannot be called during {}", tool_call.name, windows)
+        } else {
+            format!("Tool '{}' is outside its allowed window ({})", tool_call.name, windows)
+        };
+        if let Some(next) = self.next_allowed {
+            error.push_str(&format!("; it can be called again from {}", next.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)));
+        }
+        AgentResult {
+            success: false,
+            data: None,
+            error: Some(error),
+            metadata: Some(json!({
+                "error_category": "outside_allowed_window",
+                "access_window": {
+                    "windows": self.windows,
+                    "denied": self.denied,
+                    "next_allowed": self.next_allowed,
+                },
+            })),
+        }
+    }
+}
+
+/// Decides whether tools may be called now
+#[derive(Debug)]
+pub struct AccessWindows {
+    windows: BTreeMap<String, Window>,
+    rules: Vec<Rule>,
+}
+
+impl AccessWindows {
+    pub fn new(config: AccessWindowsConfig) -> Result<Self> {
+        let timezone = parse_timezone(&config.timezone)
+            .map_err(|e| ProxyError::config(format!("Invalid access_windows.timezone: {}", e)))?;
+        let windows = config.windows.iter()
+            .map(|(name, window)| Ok((name.clone(), Window::parse(name, window, timezone)?)))
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        let rules = config.rules.into_iter()
+            .map(|rule| {
+                if rule.allowed.is_empty() && rule.denied.is_empty() {
+                    return Err(ProxyError::config(format!("Access rule for {:?} needs allowed or denied windows", rule.tools)));
+                }
+                if let Some(name) = rule.allowed.iter().chain(&rule.denied).find(|name| !windows.contains_key(*name)) {
+                    return Err(ProxyError::config(format!("Access rule for {:?} names undefined window '{}'", rule.tools, name)));
+                }
+                if rule.tools.is_empty() {
+                    return Err(ProxyError::config("Access rules must list tools"));
+                }
+                let tools = ScopePatterns::parse(&rule.tools).map_err(|e| ProxyError::config(e.to_string()))?;
+                Ok(Rule { config: rule, tools })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { windows, rules })
+    }
+
+    /// Check a call by the current caller against the rules
+    pub fn check(&self, tool_call: &ToolCall) -> std::result::Result<(), OutsideWindow> {
+        let api_key = rate_limit::current_client();
+        let user = cost::current_user();
+        let checked = self.check_at(&tool_call.name, api_key.as_deref(), user.as_deref(), Utc::now());
+        if let Err(refusal) = &checked {
+            info!(
+                target: "audit",
+                tool = %tool_call.name,
+                api_key = ?api_key,
+                user = ?user,
+                windows = ?refusal.windows,
+                "Tool call outside its allowed window"
+            );
+        }
+        checked
+    }
+
+    fn check_at(&self, tool: &str, api_key: Option<&str>, user: Option<&str>, now: DateTime<Utc>) -> std::result::Result<(), OutsideWindow> {
+        let rules: Vec<&Rule> = self.rules.iter().filter(|rule| rule.applies_to(tool, api_key, user)).collect();
+        let refusal = rules.iter().find_map(|rule| {
+            let open = |name: &String| self.windows[name].is_open(now);
+            let denied: Vec<String> = rule.config.denied.iter().filter(|name| open(name)).cloned().collect();
+            if !denied.is_empty() {
+                Some((denied, true))
+            } else if !rule.config.allowed.is_empty() && !rule.config.allowed.iter().any(open) {
+                Some((rule.config.allowed.clone(), false))
+            } else {
+                None
+            }
+        });
+        let Some((windows, denied)) = refusal else {
+            return Ok(());
+        };
+        let next_allowed = (1..=LOOKAHEAD_MINUTES)
+            .filter_map(|minutes| (now + Duration::minutes(minutes)).with_second(0)?.with_nanosecond(0))
+            .find(|time| rules.iter().all(|rule| self.allows(rule, *time)));
+        Err(OutsideWindow { windows, denied, next_allowed })
+    }
+
+    fn allows(&self, rule: &Rule, time: DateTime<Utc>) -> bool {
+        let open = |name: &String| self.windows[name].is_open(time);
+        !rule.config.denied.iter().any(open) && (rule.config.allowed.is_empty() || rule.config.allowed.iter().any(open))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(timezone: Tz, y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        timezone.with_ymd_and_hms(y, m, d, h, min, 0).unwrap().with_timezone(&Utc)
+    }
+
+    fn windows() -> AccessWindows {
+        AccessWindows::new(serde_yaml::from_str(r#"
+timezone: Europe/Berlin
+windows:
+  business_hours: { days: [mon, tue, wed, thu, fri], start: "09:00", end: "18:00" }
+  nightly: { cron: "* 0-5 * * *" }
+  night_shift: { start: "22:00", end: "06:00" }
+  release_freeze: { from: "2026-12-19T00:00:00Z", until: "2027-01-04T00:00:00Z" }
+rules:
+  - tools: ["deploy_*"]
+    allowed: [business_hours]
+    denied: [release_freeze]
+  - tools: ["reindex"]
+    api_keys: ["ci-bot"]
+    allowed: [nightly]
+"#).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_business_hours() {
+        let windows = windows();
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        // Thursday 2026-10-15
+        assert!(windows.check_at("deploy_app", None, None, at(berlin, 2026, 10, 15, 10, 0)).is_ok());
+        let refusal = windows.check_at("deploy_app", None, None, at(berlin, 2026, 10, 15, 18, 30)).unwrap_err();
+        assert_eq!(refusal.windows, vec!["business_hours"]);
+        assert!(!refusal.denied);
+        assert_eq!(refusal.next_allowed, Some(at(berlin, 2026, 10, 16, 9, 0)));
+        // Saturday
+        let refusal = windows.check_at("deploy_app", None, None, at(berlin, 2026, 10, 17, 10, 0)).unwrap_err();
+        assert_eq!(refusal.next_allowed, Some(at(berlin, 2026, 10, 19, 9, 0)));
+        assert!(windows.check_at("list_apps", None, None, at(berlin, 2026, 10, 17, 10, 0)).is_ok());
+    }
+
+    #[test]
+    fn test_freeze_and_subjects() {
+        let windows = windows();
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        let refusal = windows.check_at("deploy_app", None, None, at(berlin, 2026, 12, 22, 10, 0)).unwrap_err();
+        assert_eq!(refusal.windows, vec!["release_freeze"]);
+        assert!(refusal.denied);
+        assert_eq!(refusal.next_allowed, None);
+
+        assert!(windows.check_at("reindex", Some("ci-bot"), None, at(berlin, 2026, 10, 15, 3, 30)).is_ok());
+        assert!(windows.check_at("reindex", Some("ci-bot"), None, at(berlin, 2026, 10, 15, 12, 0)).is_err());
+        assert!(windows.check_at("reindex", Some("alice-key"), None, at(berlin, 2026, 10, 15, 12, 0)).is_ok());
+    }
+
+    #[test]
+    fn test_window_spanning_midnight() {
+        let windows = windows();
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        let night_shift = &windows.windows["night_shift"];
+        assert!(night_shift.is_open(at(berlin, 2026, 10, 15, 23, 0)));
+        assert!(night_shift.is_open(at(berlin, 2026, 10, 16, 5, 59)));
+        assert!(!night_shift.is_open(at(berlin, 2026, 10, 16, 6, 0)));
+    }
+
+    #[test]
+    fn test_cron_weekdays_count_from_sunday() {
+        let config: AccessWindowConfig = serde_yaml::from_str("{ cron: '* 9-17 * * 1-5' }").unwrap();
+        let weekdays = Window::parse("weekdays", &config, Tz::UTC).unwrap();
+        // Sunday and Monday 2026-10-18 and 19
+        assert!(!weekdays.is_open(at(Tz::UTC, 2026, 10, 18, 10, 0)));
+        assert!(weekdays.is_open(at(Tz::UTC, 2026, 10, 19, 10, 0)));
+        assert!(!weekdays.is_open(at(Tz::UTC, 2026, 10, 24, 10, 0)));
+
+        let config: AccessWindowConfig = serde_yaml::from_str("{ cron: '* * * * 0,6' }").unwrap();
+        let weekends = Window::parse("weekends", &config, Tz::UTC).unwrap();
+        assert!(weekends.is_open(at(Tz::UTC, 2026, 10, 18, 10, 0)));
+        assert!(!weekends.is_open(at(Tz::UTC, 2026, 10, 19, 10, 0)));
+        assert_eq!(weekday_names("5-7").unwrap(), "Sun,Fri,Sat");
+        assert_eq!(weekday_names("*/2").unwrap(), "Sun,Tue,Thu,Sat");
+        assert!(weekday_names("8").is_err());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let invalid = [
+            "windows: { w: { start: '09:00' } }",
+            "windows: { w: { days: [someday] } }",
+            "windows: { w: { cron: '0 0 * * * *' } }",
+            "windows: { w: {} }",
+            "timezone: Mars/Olympus",
+            "rules: [{ tools: ['x'], allowed: [missing] }]",
+        ];
+        for yaml in invalid {
+            let config: AccessWindowsConfig = serde_yaml::from_str(yaml).unwrap();
+            assert!(config.validate().is_err(), "{}", yaml);
+        }
+    }
+}
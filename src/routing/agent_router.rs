@@ -5,7 +5,7 @@ use std::sync::Arc;
 use crate::error::Result;
 use crate::mcp::ToolCall;
 use crate::registry::{RoutingConfig, ToolDefinition};
-use crate::routing::types::{AgentResult, AgentType};
+use crate::routing::types::{AgentResult, AgentType, OptionalArg, SmartDiscoveryOperation};
 use crate::discovery::SmartDiscoveryRequest;
 use async_trait::async_trait;
 use base64::Engine;
@@ -41,6 +41,9 @@ pub struct DefaultAgentRouter {
     registry: Option<Arc<crate::registry::RegistryService>>,
     // Smart discovery service for intelligent tool selection
     smart_discovery: Option<Arc<crate::discovery::SmartDiscoveryService>>,
+    // Broker connections shared by AMQP tools
+    #[cfg(feature = "amqp")]
+    amqp_pool: Arc<crate::routing::amqp::AmqpConnectionPool>,
 }
 
 impl DefaultAgentRouter {
@@ -50,6 +53,8 @@ impl DefaultAgentRouter {
             external_mcp: None,
             registry: None,
             smart_discovery: None,
+            #[cfg(feature = "amqp")]
+            amqp_pool: Arc::new(crate::routing::amqp::AmqpConnectionPool::new()),
         }
     }
 
@@ -108,6 +113,11 @@ impl AgentRouter for DefaultAgentRouter {
                         .map(|obj| obj.iter()
                             .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
                             .collect()),
+                    optional_args: config.get("optional_args")
+                        .map(|v| serde_json::from_value(v.clone()))
+                        .transpose()
+                        .map_err(|e| ProxyError::routing(format!("Invalid optional_args config: {}", e)))?
+                        .unwrap_or_default(),
                 })
             }
             "http" => {
@@ -128,6 +138,15 @@ impl AgentRouter for DefaultAgentRouter {
                             .collect()),
                     timeout: config.get("timeout")
                         .and_then(|v| v.as_u64()),
+                    aws_sigv4: config.get("aws_sigv4")
+                        .map(|v| serde_json::from_value(v.clone()))
+                        .transpose()
+                        .map_err(|e| crate::error::ProxyError::routing(format!("Invalid aws_sigv4 config: {}", e)))?,
+                    token_exchange: config.get("token_exchange")
+                        .map(|v| serde_json::from_value(v.clone()))
+                        .transpose()
+                        .map_err(|e| crate::error::ProxyError::routing(format!("Invalid token_exchange config: {}", e)))?,
+                    params: parse_http_params(config)?,
                 })
             }
             "llm" => {
@@ -165,6 +184,38 @@ impl AgentRouter for DefaultAgentRouter {
                             .collect()),
                 })
             }
+            "lambda" => {
+                let config = &routing.config;
+                let str_field = |name: &str| config.get(name).and_then(|v| v.as_str()).map(String::from);
+
+                let region = str_field("region")
+                    .or_else(|| std::env::var("AWS_REGION").ok())
+                    .or_else(|| std::env::var("AWS_DEFAULT_REGION").ok())
+                    .ok_or_else(|| ProxyError::routing("Lambda agent requires region (or AWS_REGION)".to_string()))?;
+                let mut aws = crate::routing::aws_sigv4::AwsSigV4Config::new("lambda", region);
+                if let Some(access_key_id) = str_field("access_key_id") {
+                    aws.access_key_id = access_key_id;
+                }
+                if let Some(secret_access_key) = str_field("secret_access_key") {
+                    aws.secret_access_key = secret_access_key;
+                }
+                aws.session_token = str_field("session_token");
+
+                Ok(AgentType::Lambda {
+                    function_name: str_field("function_name")
+                        .ok_or_else(|| ProxyError::routing("Lambda agent requires function_name".to_string()))?,
+                    qualifier: str_field("qualifier"),
+                    invocation_type: str_field("invocation_type")
+                        .map(|v| crate::routing::aws_lambda::LambdaInvocationType::parse(&v))
+                        .transpose()?
+                        .unwrap_or_default(),
+                    payload: config.get("payload").cloned(),
+                    endpoint: str_field("endpoint"),
+                    timeout: config.get("timeout")
+                        .and_then(|v| v.as_u64()),
+                    aws,
+                })
+            }
             "database" => {
                 let config = &routing.config;
                 Ok(AgentType::Database {
@@ -182,6 +233,175 @@ impl AgentRouter for DefaultAgentRouter {
                         .to_string(),
                     timeout: config.get("timeout")
                         .and_then(|v| v.as_u64()),
+                    params: config.get("params")
+                        .and_then(|v| v.as_array())
+                        .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+                        .unwrap_or_default(),
+                    read_only: config.get("read_only")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false),
+                    max_rows: config.get("max_rows")
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as usize)
+                        .unwrap_or(crate::routing::types::DEFAULT_MAX_ROWS),
+                })
+            }
+
+            "kafka" => {
+                let config = &routing.config;
+                let str_field = |name: &str| config.get(name).and_then(|v| v.as_str()).map(String::from);
+                let partition_field = |name: &str| config.get(name)
+                    .and_then(|v| v.as_i64())
+                    .map(|p| i32::try_from(p).map_err(|_| ProxyError::routing(format!("Invalid Kafka {}: {}", name, p))))
+                    .transpose();
+
+                // Brokers are a list or a comma-separated string
+                let brokers: Vec<String> = match config.get("brokers") {
+                    Some(serde_json::Value::Array(arr)) => arr.iter().filter_map(|v| v.as_str().map(String::from)).collect(),
+                    Some(serde_json::Value::String(s)) => s.split(',').map(|b| b.trim().to_string()).filter(|b| !b.is_empty()).collect(),
+                    _ => Vec::new(),
+                };
+                if brokers.is_empty() {
+                    return Err(ProxyError::routing("Kafka agent requires brokers".to_string()));
+                }
+
+                let reply = match str_field("response_topic") {
+                    Some(topic) => Some(crate::routing::kafka::KafkaReplyConfig {
+                        topic,
+                        partition: partition_field("response_partition")?.unwrap_or(0),
+                    }),
+                    None => None,
+                };
+
+                Ok(AgentType::Kafka {
+                    brokers,
+                    topic: str_field("topic")
+                        .ok_or_else(|| ProxyError::routing("Kafka agent requires topic".to_string()))?,
+                    partition: partition_field("partition")?.unwrap_or(0),
+                    key: str_field("key"),
+                    payload: config.get("payload").cloned(),
+                    headers: config.get("headers")
+                        .and_then(|v| v.as_object())
+                        .map(|obj| obj.iter().filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string()))).collect())
+                        .unwrap_or_default(),
+                    reply,
+                    timeout: config.get("timeout")
+                        .and_then(|v| v.as_u64()),
+                })
+            }
+
+            "amqp" => {
+                let config = &routing.config;
+                let str_field = |name: &str| config.get(name).and_then(|v| v.as_str()).map(String::from);
+
+                Ok(AgentType::Amqp {
+                    url: str_field("url")
+                        .ok_or_else(|| ProxyError::routing("AMQP agent requires url".to_string()))?,
+                    exchange: str_field("exchange").unwrap_or_default(),
+                    routing_key: str_field("routing_key")
+                        .ok_or_else(|| ProxyError::routing("AMQP agent requires routing_key".to_string()))?,
+                    payload: config.get("payload").cloned(),
+                    headers: config.get("headers")
+                        .and_then(|v| v.as_object())
+                        .map(|obj| obj.iter().filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string()))).collect())
+                        .unwrap_or_default(),
+                    await_reply: config.get("await_reply")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(true),
+                    ca_cert: str_field("ca_cert"),
+                    timeout: config.get("timeout")
+                        .and_then(|v| v.as_u64()),
+                })
+            }
+
+            "ssh" => {
+                let config = &routing.config;
+                Ok(AgentType::Ssh {
+                    command: config.get("command")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ProxyError::routing("SSH agent requires command".to_string()))?
+                        .to_string(),
+                    timeout: config.get("timeout")
+                        .and_then(|v| v.as_u64()),
+                    max_output_bytes: config.get("max_output_bytes")
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as usize)
+                        .unwrap_or(crate::routing::process::DEFAULT_MAX_OUTPUT_BYTES),
+                    connection: serde_json::from_value(config.clone())
+                        .map_err(|e| ProxyError::routing(format!("Invalid SSH config: {}", e)))?,
+                })
+            }
+
+            "docker" => {
+                let config = &routing.config;
+                let docker: crate::routing::docker::DockerConfig = serde_json::from_value(config.clone())
+                    .map_err(|e| ProxyError::routing(format!("Invalid Docker config: {}", e)))?;
+                docker.validate()?;
+
+                Ok(AgentType::Docker {
+                    timeout: config.get("timeout")
+                        .and_then(|v| v.as_u64()),
+                    max_output_bytes: config.get("max_output_bytes")
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as usize)
+                        .unwrap_or(crate::routing::process::DEFAULT_MAX_OUTPUT_BYTES),
+                    config: docker,
+                })
+            }
+
+            "kubernetes_job" => {
+                let config = &routing.config;
+                let job: crate::routing::kubernetes::KubernetesJobConfig = serde_json::from_value(config.clone())
+                    .map_err(|e| ProxyError::routing(format!("Invalid Kubernetes Job config: {}", e)))?;
+                job.validate()?;
+
+                Ok(AgentType::KubernetesJob {
+                    timeout: config.get("timeout")
+                        .and_then(|v| v.as_u64()),
+                    max_output_bytes: config.get("max_output_bytes")
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as usize)
+                        .unwrap_or(crate::routing::process::DEFAULT_MAX_OUTPUT_BYTES),
+                    config: job,
+                })
+            }
+
+            "webhook" => {
+                let config = &routing.config;
+                let webhook: crate::routing::webhook::WebhookConfig = serde_json::from_value(config.clone())
+                    .map_err(|e| ProxyError::routing(format!("Invalid webhook config: {}", e)))?;
+                webhook.validate()?;
+
+                Ok(AgentType::Webhook {
+                    timeout: config.get("timeout")
+                        .and_then(|v| v.as_u64()),
+                    config: webhook,
+                })
+            }
+
+            "wasm" => {
+                let config = &routing.config;
+                Ok(AgentType::Wasm {
+                    timeout: config.get("timeout")
+                        .and_then(|v| v.as_u64()),
+                    max_output_bytes: config.get("max_output_bytes")
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as usize)
+                        .unwrap_or(crate::routing::process::DEFAULT_MAX_OUTPUT_BYTES),
+                    config: serde_json::from_value(config.clone())
+                        .map_err(|e| ProxyError::routing(format!("Invalid WebAssembly config: {}", e)))?,
+                })
+            }
+
+            "script" => {
+                let config = &routing.config;
+                let script: crate::routing::script::ScriptConfig = serde_json::from_value(config.clone())
+                    .map_err(|e| ProxyError::routing(format!("Invalid script config: {}", e)))?;
+                script.validate()?;
+                Ok(AgentType::Script {
+                    timeout: config.get("timeout")
+                        .and_then(|v| v.as_u64()),
+                    config: script,
                 })
             }
 
@@ -255,6 +475,10 @@ impl AgentRouter for DefaultAgentRouter {
                     operation_name: config.get("operation_name")
                         .and_then(|v| v.as_str())
                         .map(|s| s.to_string()),
+                    subscription: config.get("subscription")
+                        .map(|v| serde_json::from_value(v.clone()))
+                        .transpose()
+                        .map_err(|e| ProxyError::routing(format!("Invalid GraphQL subscription config: {}", e)))?,
                 })
             }
             "external_mcp" => {
@@ -285,6 +509,11 @@ impl AgentRouter for DefaultAgentRouter {
                     enabled: config.get("enabled")
                         .and_then(|v| v.as_bool())
                         .unwrap_or(true),
+                    operation: match config.get("operation") {
+                        Some(operation) => serde_json::from_value(operation.clone())
+                            .map_err(|e| ProxyError::routing(format!("Invalid smart discovery operation: {}", e)))?,
+                        None => SmartDiscoveryOperation::Discover,
+                    },
                 })
             }
             _ => Err(ProxyError::routing(format!(
@@ -352,11 +581,14 @@ impl AgentRouter for DefaultAgentRouter {
 
         // Regular agent execution for non-external MCP tools
         match agent {
-            AgentType::Subprocess { command, args, timeout, env } => {
-                self.execute_subprocess_agent(tool_call, command, args, *timeout, env).await
+            AgentType::Subprocess { command, args, timeout, env, optional_args } => {
+                self.execute_subprocess_agent(tool_call, command, args, optional_args, *timeout, env).await
+            }
+            AgentType::Http { method, url, headers, timeout, aws_sigv4, token_exchange, params } => {
+                self.execute_http_agent(tool_call, method, url, headers, *timeout, aws_sigv4.as_ref(), token_exchange.as_ref(), params).await
             }
-            AgentType::Http { method, url, headers, timeout } => {
-                self.execute_http_agent(tool_call, method, url, headers, *timeout).await
+            AgentType::Lambda { function_name, qualifier, invocation_type, payload, endpoint, timeout, aws } => {
+                self.execute_lambda_agent(tool_call, function_name, qualifier.as_deref(), *invocation_type, payload.as_ref(), endpoint.as_deref(), *timeout, aws).await
             }
             AgentType::Llm { provider, model, api_key, base_url, timeout } => {
                 self.execute_llm_agent(tool_call, provider, model, api_key, base_url, *timeout).await
@@ -364,8 +596,41 @@ impl AgentRouter for DefaultAgentRouter {
             AgentType::WebSocket { url, headers } => {
                 self.execute_websocket_agent(tool_call, url, headers).await
             }
-            AgentType::Database { db_type, connection_string, query, timeout } => {
-                self.execute_database_agent(tool_call, db_type, connection_string, query, *timeout).await
+            AgentType::Database { db_type, connection_string, query, timeout, params, read_only, max_rows } => {
+                self.execute_database_agent(tool_call, db_type, connection_string, query, *timeout, params, *read_only, *max_rows).await
+            }
+            #[cfg(feature = "kafka")]
+            AgentType::Kafka { brokers, topic, partition, key, payload, headers, reply, timeout } => {
+                self.execute_kafka_agent(tool_call, brokers, topic, *partition, key.as_deref(), payload.as_ref(), headers, reply.as_ref(), *timeout).await
+            }
+            #[cfg(not(feature = "kafka"))]
+            AgentType::Kafka { .. } => Err(feature_disabled("kafka", "kafka")),
+            #[cfg(feature = "amqp")]
+            AgentType::Amqp { url, exchange, routing_key, payload, headers, await_reply, ca_cert, timeout } => {
+                self.execute_amqp_agent(tool_call, url, exchange, routing_key, payload.as_ref(), headers, *await_reply, ca_cert.as_deref(), *timeout).await
+            }
+            #[cfg(not(feature = "amqp"))]
+            AgentType::Amqp { .. } => Err(feature_disabled("amqp", "amqp")),
+            AgentType::Ssh { command, timeout, max_output_bytes, connection } => {
+                self.execute_ssh_agent(tool_call, command, *timeout, *max_output_bytes, connection).await
+            }
+            AgentType::Docker { timeout, max_output_bytes, config } => {
+                self.execute_docker_agent(tool_call, *timeout, *max_output_bytes, config).await
+            }
+            AgentType::KubernetesJob { timeout, max_output_bytes, config } => {
+                self.execute_kubernetes_job_agent(tool_call, *timeout, *max_output_bytes, config).await
+            }
+            AgentType::Webhook { timeout, config } => {
+                self.execute_webhook_agent(tool_call, *timeout, config).await
+            }
+            #[cfg(feature = "wasm")]
+            AgentType::Wasm { timeout, max_output_bytes, config } => {
+                self.execute_wasm_agent(tool_call, *timeout, *max_output_bytes, config).await
+            }
+            #[cfg(not(feature = "wasm"))]
+            AgentType::Wasm { .. } => Err(feature_disabled("wasm", "wasm")),
+            AgentType::Script { timeout, config } => {
+                self.execute_script_agent(tool_call, *timeout, config).await
             }
             AgentType::Grpc { endpoint, service, method, headers, timeout, request_body } => {
                 self.execute_grpc_agent(tool_call, endpoint, service, method, headers, *timeout, request_body).await
@@ -373,8 +638,8 @@ impl AgentRouter for DefaultAgentRouter {
             AgentType::Sse { url, headers, timeout, max_events, event_filter } => {
                 self.execute_sse_agent(tool_call, url, headers, *timeout, *max_events, event_filter).await
             }
-            AgentType::GraphQL { endpoint, query, variables, headers, timeout, operation_name } => {
-                self.execute_graphql_agent(tool_call, endpoint, query, variables, headers, *timeout, operation_name).await
+            AgentType::GraphQL { endpoint, query, variables, headers, timeout, operation_name, subscription } => {
+                self.execute_graphql_agent(tool_call, endpoint, query, variables, headers, *timeout, operation_name, subscription).await
             }
             // External MCP agent type
             AgentType::ExternalMcp { server_name, tool_name, .. } => {
@@ -384,9 +649,18 @@ impl AgentRouter for DefaultAgentRouter {
                 )))
             }
             // Smart Discovery agent type
-            AgentType::SmartDiscovery { enabled } => {
+            AgentType::SmartDiscovery { enabled, operation: SmartDiscoveryOperation::Discover } => {
                 self.execute_smart_discovery_agent(tool_call, *enabled).await
             }
+            AgentType::SmartDiscovery { enabled, operation: SmartDiscoveryOperation::Plan } => {
+                self.execute_smart_discovery_plan_agent(tool_call, *enabled).await
+            }
+            AgentType::SmartDiscovery { enabled, operation: SmartDiscoveryOperation::Feedback } => {
+                self.execute_smart_discovery_feedback_agent(tool_call, *enabled).await
+            }
+            AgentType::SmartDiscovery { enabled, operation: SmartDiscoveryOperation::Categories } => {
+                self.execute_smart_discovery_categories_agent(tool_call, *enabled).await
+            }
         }
     }
 }
@@ -398,10 +672,11 @@ impl DefaultAgentRouter {
         tool_call: &ToolCall,
         command: &str,
         args: &[String],
+        optional_args: &[OptionalArg],
         timeout: Option<u64>,
         env: &Option<std::collections::HashMap<String, String>>
     ) -> Result<AgentResult> {
-        use crate::routing::substitution::substitute_parameters;
+        use crate::routing::substitution::{expand_optional_args, substitute_parameters};
         use tokio::process::Command;
         use tokio::time::{timeout as tokio_timeout, Duration};
         use serde_json::json;
@@ -409,7 +684,8 @@ impl DefaultAgentRouter {
         debug!("Executing subprocess agent: {} {:?}", command, args);
 
         // Substitute parameters in command and args
-        let substituted_args = substitute_parameters(args, &tool_call.arguments)?;
+        let mut substituted_args = substitute_parameters(args, &tool_call.arguments)?;
+        substituted_args.extend(expand_optional_args(optional_args, &tool_call.arguments)?);
 
         // Create command
         let mut cmd = Command::new(command);
@@ -422,23 +698,33 @@ impl DefaultAgentRouter {
             }
         }
 
-        // Execute with timeout
+        // Execute with timeout and bounded output capture; when the client
+        // asked for progress, stdout lines are streamed to it as they are written
         let timeout_duration = Duration::from_secs(timeout.unwrap_or(30));
-        let result = tokio_timeout(timeout_duration, cmd.output()).await;
+        let max_output_bytes = crate::routing::process::DEFAULT_MAX_OUTPUT_BYTES;
+        let result = match crate::mcp::progress::current() {
+            Some(progress) => {
+                let run = crate::routing::process::run_streaming(cmd, max_output_bytes, &progress);
+                tokio_timeout(timeout_duration, run).await
+            }
+            None => tokio_timeout(timeout_duration, crate::routing::process::run_command(cmd, max_output_bytes)).await,
+        };
 
         match result {
             Ok(Ok(output)) => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                
-                if output.status.success() {
+                let mut data = json!({
+                    "stdout": output.stdout,
+                    "stderr": output.stderr,
+                    "exit_code": output.exit_code
+                });
+                if output.truncated {
+                    data["truncated"] = json!(true);
+                }
+
+                if output.is_success() {
                     Ok(AgentResult {
                         success: true,
-                        data: Some(json!({
-                            "stdout": stdout,
-                            "stderr": stderr,
-                            "exit_code": output.status.code()
-                        })),
+                        data: Some(data),
                         error: None,
                         metadata: Some(json!({
                             "tool_name": tool_call.name,
@@ -450,12 +736,8 @@ impl DefaultAgentRouter {
                 } else {
                     Ok(AgentResult {
                         success: false,
-                        data: Some(json!({
-                            "stdout": stdout,
-                            "stderr": stderr,
-                            "exit_code": output.status.code()
-                        })),
-                        error: Some(format!("Command failed with exit code: {:?}", output.status.code())),
+                        data: Some(data),
+                        error: Some(format!("Command failed with exit code: {:?}", output.exit_code)),
                         metadata: Some(json!({
                             "tool_name": tool_call.name,
                             "execution_type": "subprocess",
@@ -497,17 +779,20 @@ impl DefaultAgentRouter {
         method: &str,
         url: &str,
         headers: &Option<std::collections::HashMap<String, String>>,
-        timeout: Option<u64>
+        timeout: Option<u64>,
+        aws_sigv4: Option<&crate::routing::aws_sigv4::AwsSigV4Config>,
+        token_exchange: Option<&crate::auth::TokenExchangeConfig>,
+        params: &crate::routing::types::HttpParams,
     ) -> Result<AgentResult> {
-        use crate::routing::substitution::{substitute_parameter_string, substitute_headers};
+        use crate::routing::substitution::{http_request_body, substitute_http_url, substitute_headers};
         use reqwest::Client;
         use serde_json::json;
         use tokio::time::{timeout as tokio_timeout, Duration};
 
         debug!("Executing HTTP agent: {} {}", method, url);
 
-        // Substitute parameters in URL
-        let substituted_url = substitute_parameter_string(url, &tool_call.arguments)?;
+        // Substitute parameters in URL, path and query string
+        let substituted_url = substitute_http_url(url, params, &tool_call.arguments)?;
 
         // Substitute parameters in headers
         let substituted_headers = substitute_headers(headers, &tool_call.arguments)?;
@@ -549,9 +834,40 @@ impl DefaultAgentRouter {
             }
         }
 
-        // Add JSON body for POST/PUT/PATCH requests
-        if matches!(method.to_uppercase().as_str(), "POST" | "PUT" | "PATCH") {
-            request_builder = request_builder.json(&tool_call.arguments);
+        // Call on behalf of the user with a token issued to this service
+        if let Some(exchange) = token_exchange {
+            let token = exchange.resolve().await?.access_token().await?;
+            request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
+        }
+
+        let has_body = matches!(method.to_uppercase().as_str(), "POST" | "PUT" | "PATCH");
+        let mut signed_headers: Vec<(String, String)> = substituted_headers.clone().unwrap_or_default().into_iter().collect();
+        let content_type = match signed_headers.iter().find(|(name, _)| name.eq_ignore_ascii_case("content-type")) {
+            Some((_, value)) => value.clone(),
+            None => {
+                if has_body {
+                    request_builder = request_builder.header("Content-Type", "application/json");
+                    signed_headers.push(("Content-Type".to_string(), "application/json".to_string()));
+                }
+                "application/json".to_string()
+            }
+        };
+        // Serialized up front since a signature covers the exact body bytes
+        let body = if has_body { http_request_body(params, &tool_call.arguments, &content_type)? } else { Vec::new() };
+
+        if let Some(sigv4) = aws_sigv4 {
+            let parsed_url = url::Url::parse(&substituted_url)
+                .map_err(|e| crate::error::ProxyError::routing(format!("Invalid URL '{}': {}", substituted_url, e)))?;
+            let credentials = sigv4.credentials().await?;
+            let auth_headers = crate::routing::aws_sigv4::sign_request(
+                method, &parsed_url, &signed_headers, &body, sigv4, &credentials, chrono::Utc::now(),
+            )?;
+            for (key, value) in auth_headers {
+                request_builder = request_builder.header(key, value);
+            }
+        }
+        if has_body {
+            request_builder = request_builder.body(body);
         }
 
         // Execute request with timeout
@@ -628,6 +944,659 @@ impl DefaultAgentRouter {
         }
     }
 
+    /// Execute AWS Lambda agent
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_lambda_agent(
+        &self,
+        tool_call: &ToolCall,
+        function_name: &str,
+        qualifier: Option<&str>,
+        invocation_type: crate::routing::aws_lambda::LambdaInvocationType,
+        payload: Option<&serde_json::Value>,
+        endpoint: Option<&str>,
+        timeout: Option<u64>,
+        aws: &crate::routing::aws_sigv4::AwsSigV4Config
+    ) -> Result<AgentResult> {
+        use crate::routing::aws_lambda::{invoke, invoke_url};
+        use crate::routing::substitution::{substitute_json_value, substitute_parameter_string};
+        use tokio::time::{timeout as tokio_timeout, Duration};
+
+        let function_name = substitute_parameter_string(function_name, &tool_call.arguments)?;
+        let qualifier = qualifier.map(|q| substitute_parameter_string(q, &tool_call.arguments)).transpose()?;
+        debug!("Executing Lambda agent: {} ({:?})", function_name, invocation_type);
+
+        // The event is the payload template with the arguments substituted, or the arguments themselves
+        let event = match payload {
+            Some(template) => substitute_json_value(template, &tool_call.arguments)?,
+            None => tool_call.arguments.clone(),
+        };
+        let url = invoke_url(endpoint, &aws.region, &function_name, qualifier.as_deref())?;
+
+        let timeout_secs = timeout.unwrap_or(60);
+        let timeout_duration = Duration::from_secs(timeout_secs);
+        let client = reqwest::Client::builder()
+            .timeout(timeout_duration)
+            .use_rustls_tls()
+            .tls_built_in_root_certs(true)
+            .build()
+            .map_err(|e| crate::error::ProxyError::routing(format!("Failed to create HTTP client: {}", e)))?;
+
+        let metadata = json!({
+            "tool_name": tool_call.name,
+            "execution_type": "lambda",
+            "function_name": function_name,
+            "qualifier": qualifier,
+            "invocation_type": invocation_type,
+        });
+
+        match tokio_timeout(timeout_duration, invoke(&client, &url, invocation_type, &event, aws)).await {
+            Ok(Ok(invocation)) => {
+                let mut metadata = metadata;
+                metadata["status_code"] = json!(invocation.status);
+                metadata["request_id"] = json!(invocation.request_id);
+                metadata["executed_version"] = json!(invocation.executed_version);
+                Ok(AgentResult {
+                    success: invocation.is_success(),
+                    error: invocation.error_message(),
+                    data: Some(invocation.to_data()),
+                    metadata: Some(metadata),
+                })
+            }
+            Ok(Err(e)) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                metadata: Some(metadata),
+            }),
+            Err(_) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(format!("Lambda invocation timed out after {} seconds", timeout_secs)),
+                metadata: Some(metadata),
+            }),
+        }
+    }
+
+    /// Execute Kafka agent
+    #[cfg(feature = "kafka")]
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_kafka_agent(
+        &self,
+        tool_call: &ToolCall,
+        brokers: &[String],
+        topic: &str,
+        partition: i32,
+        key: Option<&str>,
+        payload: Option<&serde_json::Value>,
+        headers: &std::collections::HashMap<String, String>,
+        reply: Option<&crate::routing::kafka::KafkaReplyConfig>,
+        timeout: Option<u64>,
+    ) -> Result<AgentResult> {
+        use crate::routing::kafka;
+        use crate::routing::substitution::{substitute_json_value, substitute_parameter_string};
+        use tokio::time::{timeout as tokio_timeout, Duration};
+
+        let topic = substitute_parameter_string(topic, &tool_call.arguments)?;
+        debug!("Executing Kafka agent: {}[{}]", topic, partition);
+
+        // The value is the payload template with the arguments substituted, or the arguments themselves
+        let value = match payload {
+            Some(template) => substitute_json_value(template, &tool_call.arguments)?,
+            None => tool_call.arguments.clone(),
+        };
+        let key = key.map(|k| substitute_parameter_string(k, &tool_call.arguments)).transpose()?;
+        let headers = headers.iter()
+            .map(|(name, value)| Ok((name.clone(), substitute_parameter_string(value, &tool_call.arguments)?)))
+            .collect::<Result<std::collections::HashMap<_, _>>>()?;
+        let correlation_id = reply.map(|_| uuid::Uuid::new_v4().to_string());
+
+        let timeout_secs = timeout.unwrap_or(30);
+        let mut metadata = json!({
+            "tool_name": tool_call.name,
+            "execution_type": "kafka",
+            "topic": topic,
+            "partition": partition,
+            "correlation_id": correlation_id,
+        });
+
+        let exchange = async {
+            let client = kafka::connect(brokers).await?;
+            // Note the reply partition's end before publishing so a fast reply isn't missed
+            let reply_partition = match reply {
+                Some(reply) => {
+                    let partition_client = kafka::partition_client(&client, &reply.topic, reply.partition).await?;
+                    let start = kafka::next_offset(&partition_client).await?;
+                    Some((partition_client, start))
+                }
+                None => None,
+            };
+
+            let record = kafka::build_record(
+                key.as_deref(),
+                &value,
+                &headers,
+                correlation_id.as_deref().zip(reply),
+            )?;
+            let request_partition = kafka::partition_client(&client, &topic, partition).await?;
+            let offset = kafka::publish(&request_partition, record).await?;
+
+            let response = match (&reply_partition, &correlation_id) {
+                (Some((partition_client, start)), Some(correlation_id)) => {
+                    Some(kafka::await_reply(partition_client, *start, correlation_id).await?)
+                }
+                _ => None,
+            };
+            Ok::<_, crate::error::ProxyError>((offset, response))
+        };
+
+        match tokio_timeout(Duration::from_secs(timeout_secs), exchange).await {
+            Ok(Ok((offset, response))) => {
+                metadata["offset"] = json!(offset);
+                let data = response.unwrap_or_else(|| json!({
+                    "status": "published",
+                    "topic": topic,
+                    "partition": partition,
+                    "offset": offset,
+                }));
+                Ok(AgentResult {
+                    success: true,
+                    data: Some(data),
+                    error: None,
+                    metadata: Some(metadata),
+                })
+            }
+            Ok(Err(e)) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                metadata: Some(metadata),
+            }),
+            Err(_) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(match reply {
+                    Some(reply) => format!("No reply on Kafka topic '{}' within {} seconds", reply.topic, timeout_secs),
+                    None => format!("Publishing to Kafka timed out after {} seconds", timeout_secs),
+                }),
+                metadata: Some(metadata),
+            }),
+        }
+    }
+
+    /// Execute AMQP agent
+    #[cfg(feature = "amqp")]
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_amqp_agent(
+        &self,
+        tool_call: &ToolCall,
+        url: &str,
+        exchange: &str,
+        routing_key: &str,
+        payload: Option<&serde_json::Value>,
+        headers: &std::collections::HashMap<String, String>,
+        await_reply: bool,
+        ca_cert: Option<&str>,
+        timeout: Option<u64>,
+    ) -> Result<AgentResult> {
+        use crate::routing::amqp::{self, AmqpRequest};
+        use crate::routing::substitution::{substitute_json_value, substitute_parameter_string};
+        use tokio::time::{timeout as tokio_timeout, Duration};
+
+        let routing_key = substitute_parameter_string(routing_key, &tool_call.arguments)?;
+        debug!("Executing AMQP agent: exchange '{}', routing key '{}'", exchange, routing_key);
+
+        // The body is the payload template with the arguments substituted, or the arguments themselves
+        let body = match payload {
+            Some(template) => substitute_json_value(template, &tool_call.arguments)?,
+            None => tool_call.arguments.clone(),
+        };
+        let (body, content_type) = amqp::encode_body(&body)?;
+        let headers = headers.iter()
+            .map(|(name, value)| Ok((name.clone(), substitute_parameter_string(value, &tool_call.arguments)?)))
+            .collect::<Result<std::collections::HashMap<_, _>>>()?;
+        let correlation_id = await_reply.then(|| uuid::Uuid::new_v4().to_string());
+
+        let timeout_secs = timeout.unwrap_or(30);
+        let metadata = json!({
+            "tool_name": tool_call.name,
+            "execution_type": "amqp",
+            "exchange": exchange,
+            "routing_key": routing_key,
+            "correlation_id": correlation_id,
+        });
+
+        let exchange_call = async {
+            let connection = self.amqp_pool.connection(url, ca_cert).await?;
+            amqp::call(&connection, AmqpRequest {
+                exchange,
+                routing_key: &routing_key,
+                body,
+                content_type,
+                headers: &headers,
+                correlation_id: correlation_id.as_deref(),
+            }).await
+        };
+
+        match tokio_timeout(Duration::from_secs(timeout_secs), exchange_call).await {
+            Ok(Ok(reply)) => Ok(AgentResult {
+                success: true,
+                data: Some(reply.unwrap_or_else(|| json!({
+                    "status": "published",
+                    "exchange": exchange,
+                    "routing_key": routing_key,
+                }))),
+                error: None,
+                metadata: Some(metadata),
+            }),
+            Ok(Err(e)) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                metadata: Some(metadata),
+            }),
+            Err(_) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(if await_reply {
+                    format!("No AMQP reply for routing key '{}' within {} seconds", routing_key, timeout_secs)
+                } else {
+                    format!("Publishing to AMQP timed out after {} seconds", timeout_secs)
+                }),
+                metadata: Some(metadata),
+            }),
+        }
+    }
+
+    /// Execute SSH agent
+    async fn execute_ssh_agent(
+        &self,
+        tool_call: &ToolCall,
+        command: &str,
+        timeout: Option<u64>,
+        max_output_bytes: usize,
+        connection: &crate::routing::ssh::SshConnection,
+    ) -> Result<AgentResult> {
+        use crate::routing::substitution::{substitute_parameter_string, substitute_shell_command};
+        use tokio::time::{timeout as tokio_timeout, Duration};
+
+        let host = substitute_parameter_string(&connection.host, &tool_call.arguments)?;
+        let remote_command = substitute_shell_command(command, &tool_call.arguments)?;
+        debug!("Executing SSH agent on {}: {}", host, remote_command);
+
+        let metadata = json!({
+            "tool_name": tool_call.name,
+            "execution_type": "ssh",
+            "host": host,
+            "user": connection.user,
+            "command": remote_command,
+        });
+        let args = match connection.ssh_args(&host, &remote_command) {
+            Ok(args) => args,
+            Err(e) => return Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                metadata: Some(metadata),
+            }),
+        };
+
+        let timeout_secs = timeout.unwrap_or(60);
+        match tokio_timeout(Duration::from_secs(timeout_secs), crate::routing::process::run_captured(&connection.ssh_binary, &args, max_output_bytes)).await {
+            Ok(Ok(output)) => Ok(AgentResult {
+                success: output.is_success(),
+                error: crate::routing::ssh::error_message(&output),
+                data: Some(json!({
+                    "stdout": output.stdout,
+                    "stderr": output.stderr,
+                    "exit_code": output.exit_code,
+                    "truncated": output.truncated,
+                })),
+                metadata: Some(metadata),
+            }),
+            Ok(Err(e)) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                metadata: Some(metadata),
+            }),
+            Err(_) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(format!("Remote command timed out after {} seconds", timeout_secs)),
+                metadata: Some(metadata),
+            }),
+        }
+    }
+
+    /// Execute Docker agent
+    async fn execute_docker_agent(
+        &self,
+        tool_call: &ToolCall,
+        timeout: Option<u64>,
+        max_output_bytes: usize,
+        config: &crate::routing::docker::DockerConfig,
+    ) -> Result<AgentResult> {
+        use crate::routing::docker::{self, DockerCommand};
+        use crate::routing::substitution::{substitute_parameter_string, substitute_parameters, substitute_shell_command};
+        use tokio::time::{timeout as tokio_timeout, Duration};
+
+        let command = match &config.command {
+            DockerCommand::Args(args) => substitute_parameters(args, &tool_call.arguments)?,
+            DockerCommand::Shell(command) => vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                substitute_shell_command(command, &tool_call.arguments)?,
+            ],
+        };
+        let mut env = config.env.iter()
+            .map(|(key, value)| Ok((key.clone(), substitute_parameter_string(value, &tool_call.arguments)?)))
+            .collect::<Result<Vec<_>>>()?;
+        env.sort();
+        let name = format!("magictunnel-{}", uuid::Uuid::new_v4());
+        let args = config.docker_args(&name, &env, &command);
+        debug!("Executing Docker agent: {} {:?}", config.docker_binary, args);
+
+        let metadata = json!({
+            "tool_name": tool_call.name,
+            "execution_type": "docker",
+            "image": config.image,
+            "container": config.container.as_deref().unwrap_or(&name),
+            "command": command,
+        });
+
+        let timeout_secs = timeout.unwrap_or(120);
+        match tokio_timeout(Duration::from_secs(timeout_secs), crate::routing::process::run_captured(&config.docker_binary, &args, max_output_bytes)).await {
+            Ok(Ok(output)) => Ok(AgentResult {
+                success: output.is_success(),
+                error: docker::error_message(&output),
+                data: Some(json!({
+                    "stdout": output.stdout,
+                    "stderr": output.stderr,
+                    "exit_code": output.exit_code,
+                    "truncated": output.truncated,
+                })),
+                metadata: Some(metadata),
+            }),
+            Ok(Err(e)) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                metadata: Some(metadata),
+            }),
+            Err(_) => {
+                // Killing the client doesn't stop the container it started
+                if config.container.is_none() {
+                    docker::remove_container(&config.docker_binary, &name).await;
+                }
+                Ok(AgentResult {
+                    success: false,
+                    data: None,
+                    error: Some(format!("Container command timed out after {} seconds", timeout_secs)),
+                    metadata: Some(metadata),
+                })
+            }
+        }
+    }
+
+    /// Execute Kubernetes Job agent
+    async fn execute_kubernetes_job_agent(
+        &self,
+        tool_call: &ToolCall,
+        timeout: Option<u64>,
+        max_output_bytes: usize,
+        config: &crate::routing::kubernetes::KubernetesJobConfig,
+    ) -> Result<AgentResult> {
+        use crate::routing::kubernetes::JobState;
+        use crate::routing::substitution::substitute_json_value;
+        use tokio::time::{timeout as tokio_timeout, Duration};
+
+        let pod_spec = substitute_json_value(&config.pod_spec, &tool_call.arguments)?;
+        let name = config.job_name();
+        let manifest = config.job_manifest(&name, pod_spec);
+        debug!("Executing Kubernetes Job agent: {}", name);
+
+        let metadata = json!({
+            "tool_name": tool_call.name,
+            "execution_type": "kubernetes_job",
+            "job": name,
+            "namespace": config.namespace,
+        });
+
+        let progress = crate::mcp::progress::current();
+        let timeout_secs = timeout.unwrap_or(600);
+        let outcome = tokio_timeout(
+            Duration::from_secs(timeout_secs),
+            config.run_job(&name, &manifest, max_output_bytes, progress.as_ref()),
+        ).await;
+        // Timed-out Jobs are deleted too, so they don't keep running unobserved
+        if !config.keep_job {
+            config.delete_job(&name).await;
+        }
+
+        match outcome {
+            Ok(Ok(run)) => {
+                let (success, status, error) = match &run.state {
+                    JobState::Succeeded => (true, "succeeded", None),
+                    JobState::Failed { reason } => (false, "failed", Some(format!("Kubernetes Job failed: {}", reason))),
+                    JobState::Running => (false, "running", Some("Kubernetes Job did not finish".to_string())),
+                };
+                Ok(AgentResult {
+                    success,
+                    error,
+                    data: Some(json!({
+                        "job": name,
+                        "status": status,
+                        "exit_code": run.exit_code,
+                        "logs": run.logs.text,
+                        "truncated": run.logs.truncated,
+                    })),
+                    metadata: Some(metadata),
+                })
+            }
+            Ok(Err(e)) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                metadata: Some(metadata),
+            }),
+            Err(_) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(format!("Kubernetes Job '{}' did not finish within {} seconds", name, timeout_secs)),
+                metadata: Some(metadata),
+            }),
+        }
+    }
+
+    /// Execute webhook agent: submit the job, then wait for its callback
+    async fn execute_webhook_agent(
+        &self,
+        tool_call: &ToolCall,
+        timeout: Option<u64>,
+        config: &crate::routing::webhook::WebhookConfig,
+    ) -> Result<AgentResult> {
+        use crate::error::ProxyError;
+        use crate::routing::substitution::{substitute_json_value, substitute_parameter_string};
+        use crate::routing::webhook::{self, JobOutcome};
+        use tokio::time::{timeout as tokio_timeout, Duration};
+
+        let correlation_id = uuid::Uuid::new_v4().to_string();
+        let parameters = config.template_parameters(&tool_call.arguments, &correlation_id);
+        let url = substitute_parameter_string(&config.url, &parameters)?;
+        let body = match &config.payload {
+            Some(template) => substitute_json_value(template, &parameters)?,
+            None => tool_call.arguments.clone(),
+        };
+        let body = serde_json::to_vec(&body)?;
+        let method = reqwest::Method::from_bytes(config.method.to_uppercase().as_bytes())
+            .map_err(|e| ProxyError::routing(format!("Invalid webhook method '{}': {}", config.method, e)))?;
+        debug!("Executing webhook agent: {} {} ({})", method, url, correlation_id);
+
+        let metadata = json!({
+            "tool_name": tool_call.name,
+            "execution_type": "webhook",
+            "url": url,
+            "correlation_id": correlation_id,
+        });
+
+        let client = reqwest::Client::builder()
+            .use_rustls_tls()
+            .tls_built_in_root_certs(true)
+            .build()
+            .map_err(|e| ProxyError::routing(format!("Failed to create HTTP client: {}", e)))?;
+        let mut request = client.request(method, &url)
+            .header("Content-Type", "application/json")
+            .header(webhook::CORRELATION_ID_HEADER, &correlation_id)
+            .header(webhook::CALLBACK_URL_HEADER, config.callback_url(&correlation_id));
+        for (name, value) in &config.headers {
+            request = request.header(name, substitute_parameter_string(value, &parameters)?);
+        }
+        let timestamp = chrono::Utc::now().timestamp();
+        let request = request
+            .header(webhook::TIMESTAMP_HEADER, timestamp.to_string())
+            .header(webhook::SIGNATURE_HEADER, webhook::sign(&config.secret, timestamp, &body))
+            .body(body);
+
+        // Register before submitting so a fast callback isn't missed
+        let jobs = webhook::pending_jobs();
+        let progress = crate::mcp::progress::current();
+        let completion = jobs.register(&correlation_id, &config.secret, progress.clone());
+
+        let timeout_secs = timeout.unwrap_or(600);
+        let job = async {
+            let response = request.send().await
+                .map_err(|e| ProxyError::routing(format!("Webhook job submission failed: {}", e)))?;
+            if !response.status().is_success() {
+                return Err(ProxyError::routing(format!("Webhook job submission failed with status: {}", response.status())));
+            }
+            if let Some(progress) = &progress {
+                progress.report(format!("Job {} submitted, waiting for callback", correlation_id));
+            }
+            completion.await
+                .map_err(|_| ProxyError::routing("Webhook job was abandoned before it called back"))
+        };
+        let outcome = tokio_timeout(Duration::from_secs(timeout_secs), job).await;
+        jobs.remove(&correlation_id);
+
+        match outcome {
+            Ok(Ok(JobOutcome::Completed(result))) => Ok(AgentResult {
+                success: true,
+                data: Some(result),
+                error: None,
+                metadata: Some(metadata),
+            }),
+            Ok(Ok(JobOutcome::Failed(error))) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(format!("Webhook job failed: {}", error)),
+                metadata: Some(metadata),
+            }),
+            Ok(Err(e)) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                metadata: Some(metadata),
+            }),
+            Err(_) => Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some(format!("Webhook job {} did not call back within {} seconds", correlation_id, timeout_secs)),
+                metadata: Some(metadata),
+            }),
+        }
+    }
+
+    /// Execute WebAssembly agent
+    #[cfg(feature = "wasm")]
+    async fn execute_wasm_agent(
+        &self,
+        tool_call: &ToolCall,
+        timeout: Option<u64>,
+        max_output_bytes: usize,
+        config: &crate::routing::wasm::WasmConfig,
+    ) -> Result<AgentResult> {
+        use crate::routing::substitution::{substitute_parameter_string, substitute_parameters};
+
+        debug!("Executing WebAssembly agent: {}", config.module);
+
+        // argv[0] is the program name, as for a native command
+        let mut argv = vec![config.module.clone()];
+        argv.extend(substitute_parameters(&config.args, &tool_call.arguments)?);
+        let stdin = match &config.stdin {
+            Some(template) => substitute_parameter_string(template, &tool_call.arguments)?.into_bytes(),
+            None => serde_json::to_vec(&tool_call.arguments)?,
+        };
+        let mut env = config.env.iter()
+            .map(|(key, value)| Ok((key.clone(), substitute_parameter_string(value, &tool_call.arguments)?)))
+            .collect::<Result<Vec<_>>>()?;
+        env.sort();
+
+        let timeout_duration = std::time::Duration::from_secs(timeout.unwrap_or(10));
+        let runtime = crate::routing::wasm::runtime()?;
+        let module = runtime.module(std::path::Path::new(&config.module))?;
+        let run_config = config.clone();
+        let output = tokio::task::spawn_blocking(move || {
+            runtime.run(&module, &argv, &env, stdin, &run_config, timeout_duration, max_output_bytes)
+        })
+        .await
+        .map_err(|e| crate::error::ProxyError::routing(format!("WebAssembly task failed: {}", e)))??;
+
+        Ok(AgentResult {
+            success: output.is_success(),
+            error: output.error_message(),
+            data: Some(json!({
+                "stdout": output.output.stdout,
+                "stderr": output.output.stderr,
+                "exit_code": output.exit_code(),
+                "truncated": output.output.truncated,
+            })),
+            metadata: Some(json!({
+                "tool_name": tool_call.name,
+                "execution_type": "wasm",
+                "module": config.module,
+                "fuel_used": output.fuel_used,
+            })),
+        })
+    }
+
+    /// Execute script agent
+    async fn execute_script_agent(
+        &self,
+        tool_call: &ToolCall,
+        timeout: Option<u64>,
+        config: &crate::routing::script::ScriptConfig,
+    ) -> Result<AgentResult> {
+        debug!("Executing {:?} script for tool: {}", config.language, tool_call.name);
+
+        let timeout_duration = std::time::Duration::from_secs(timeout.unwrap_or(5));
+        let script = config.clone();
+        let arguments = tool_call.arguments.clone();
+        let output = tokio::task::spawn_blocking(move || script.run(&arguments, timeout_duration))
+            .await
+            .map_err(|e| ProxyError::routing(format!("Script task failed: {}", e)))??;
+
+        let metadata = Some(json!({
+            "tool_name": tool_call.name,
+            "execution_type": "script",
+            "language": config.language,
+            "output": output.output,
+        }));
+        Ok(match output.result {
+            Ok(value) => AgentResult {
+                success: true,
+                data: Some(value),
+                error: None,
+                metadata,
+            },
+            Err(message) => AgentResult {
+                success: false,
+                data: None,
+                error: Some(message),
+                metadata,
+            },
+        })
+    }
+
     /// Execute LLM agent
     async fn execute_llm_agent(
         &self,
@@ -673,6 +1642,7 @@ impl DefaultAgentRouter {
         base_url: &Option<String>,
         timeout: Option<u64>
     ) -> Result<AgentResult> {
+        use crate::routing::streaming::LlmStreamFormat;
         use reqwest::Client;
         use serde_json::json;
         use tokio::time::{timeout as tokio_timeout, Duration};
@@ -697,6 +1667,8 @@ impl DefaultAgentRouter {
             .build()
             .map_err(|e| crate::error::ProxyError::routing(format!("Failed to create HTTP client: {}", e)))?;
 
+        // Clients that asked for progress get the text as it is generated
+        let progress = crate::mcp::progress::current();
         let request_body = json!({
             "model": model,
             "messages": [
@@ -706,7 +1678,8 @@ impl DefaultAgentRouter {
                 }
             ],
             "max_tokens": tool_call.arguments.get("max_tokens").unwrap_or(&json!(1000)),
-            "temperature": tool_call.arguments.get("temperature").unwrap_or(&json!(0.7))
+            "temperature": tool_call.arguments.get("temperature").unwrap_or(&json!(0.7)),
+            "stream": progress.is_some()
         });
 
         let result = tokio_timeout(
@@ -722,7 +1695,13 @@ impl DefaultAgentRouter {
         match result {
             Ok(Ok(response)) => {
                 let status = response.status();
-                match response.json::<serde_json::Value>().await {
+                let body = match &progress {
+                    Some(progress) if status.is_success() => {
+                        crate::routing::streaming::read_llm_stream(response, LlmStreamFormat::OpenAi, progress).await
+                    }
+                    _ => response.json::<serde_json::Value>().await.map_err(|e| crate::error::ProxyError::routing(e.to_string())),
+                };
+                match body {
                     Ok(response_json) => {
                         let success = status.is_success();
                         Ok(AgentResult {
@@ -785,6 +1764,7 @@ impl DefaultAgentRouter {
         base_url: &Option<String>,
         timeout: Option<u64>
     ) -> Result<AgentResult> {
+        use crate::routing::streaming::LlmStreamFormat;
         use reqwest::Client;
         use serde_json::json;
         use tokio::time::{timeout as tokio_timeout, Duration};
@@ -805,10 +1785,12 @@ impl DefaultAgentRouter {
             .build()
             .map_err(|e| crate::error::ProxyError::routing(format!("Failed to create HTTP client: {}", e)))?;
 
+        // Clients that asked for progress get the text as it is generated
+        let progress = crate::mcp::progress::current();
         let request_body = json!({
             "model": model,
             "prompt": prompt,
-            "stream": false
+            "stream": progress.is_some()
         });
 
         let result = tokio_timeout(
@@ -823,7 +1805,13 @@ impl DefaultAgentRouter {
         match result {
             Ok(Ok(response)) => {
                 let status = response.status();
-                match response.json::<serde_json::Value>().await {
+                let body = match &progress {
+                    Some(progress) if status.is_success() => {
+                        crate::routing::streaming::read_llm_stream(response, LlmStreamFormat::Ollama, progress).await
+                    }
+                    _ => response.json::<serde_json::Value>().await.map_err(|e| crate::error::ProxyError::routing(e.to_string())),
+                };
+                match body {
                     Ok(response_json) => {
                         let success = status.is_success();
                         Ok(AgentResult {
@@ -1022,13 +2010,17 @@ impl DefaultAgentRouter {
     }
 
     /// Execute database agent
+    #[allow(clippy::too_many_arguments)]
     async fn execute_database_agent(
         &self,
         tool_call: &ToolCall,
         db_type: &str,
         connection_string: &str,
         query: &str,
-        timeout: Option<u64>
+        timeout: Option<u64>,
+        params: &[String],
+        read_only: bool,
+        max_rows: usize,
     ) -> Result<AgentResult> {
         use crate::routing::substitution::substitute_parameter_string;
         use serde_json::json;
@@ -1036,19 +2028,51 @@ impl DefaultAgentRouter {
 
         debug!("Executing database agent: {} on {}", db_type, connection_string);
 
-        // Substitute parameters in connection string and query
+        // Substitute parameters in connection string and, for non-parameterized queries, the query
         let substituted_connection = substitute_parameter_string(connection_string, &tool_call.arguments)?;
-        let substituted_query = substitute_parameter_string(query, &tool_call.arguments)?;
+        let substituted_query = if params.is_empty() {
+            substitute_parameter_string(query, &tool_call.arguments)?
+        } else {
+            query.to_string()
+        };
+        let bind_values: Vec<serde_json::Value> = params
+            .iter()
+            .map(|name| match name.strip_suffix('?') {
+                // `name?` binds whether the argument was given, telling an omitted argument from an explicit null
+                Some(name) => serde_json::Value::Bool(tool_call.arguments.get(name).is_some()),
+                None => tool_call.arguments.get(name).cloned().unwrap_or(serde_json::Value::Null),
+            })
+            .collect();
+
+        if read_only && !is_read_query(&substituted_query) {
+            return Ok(AgentResult {
+                success: false,
+                data: None,
+                error: Some("Read-only tool cannot execute a statement that modifies data".to_string()),
+                metadata: Some(json!({
+                    "tool_name": tool_call.name,
+                    "execution_type": "database",
+                    "db_type": db_type,
+                    "error_type": "read_only_violation"
+                })),
+            });
+        }
 
         let timeout_duration = Duration::from_secs(timeout.unwrap_or(30));
 
         let result = tokio_timeout(timeout_duration, async {
             match db_type {
                 "postgresql" | "postgres" => {
-                    self.execute_postgres_query(&substituted_connection, &substituted_query).await
+                    self.execute_postgres_query(&substituted_connection, &substituted_query, &bind_values, read_only, max_rows).await
                 }
+                #[cfg(feature = "mysql")]
+                "mysql" | "mariadb" => {
+                    self.execute_mysql_query(&substituted_connection, &substituted_query, &bind_values, read_only, max_rows).await
+                }
+                #[cfg(not(feature = "mysql"))]
+                "mysql" | "mariadb" => Err(feature_disabled(db_type, "mysql")),
                 "sqlite" => {
-                    self.execute_sqlite_query(&substituted_connection, &substituted_query).await
+                    self.execute_sqlite_query(&substituted_connection, &substituted_query, &bind_values, read_only, max_rows).await
                 }
                 _ => Err(crate::error::ProxyError::routing(format!(
                     "Unsupported database type: {}",
@@ -1098,8 +2122,12 @@ impl DefaultAgentRouter {
     async fn execute_postgres_query(
         &self,
         connection_string: &str,
-        query: &str
+        query: &str,
+        bind_values: &[serde_json::Value],
+        read_only: bool,
+        max_rows: usize,
     ) -> Result<serde_json::Value> {
+        use futures_util::TryStreamExt;
         use tokio_postgres::NoTls;
         use serde_json::json;
 
@@ -1114,76 +2142,149 @@ impl DefaultAgentRouter {
             }
         });
 
-        // Execute query
-        let rows = client.query(query, &[]).await
+        if read_only {
+            client.batch_execute("SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY").await
+                .map_err(|e| crate::error::ProxyError::routing(format!("PostgreSQL read-only session failed: {}", e)))?;
+        }
+
+        // Execute query, binding parameters with the types PostgreSQL inferred for them
+        let statement = client.prepare(query).await
+            .map_err(|e| crate::error::ProxyError::routing(format!("PostgreSQL query preparation failed: {}", e)))?;
+        let params = statement.params().iter().zip(bind_values)
+            .enumerate()
+            .map(|(i, (ty, value))| postgres_bind_value(i + 1, ty, value))
+            .collect::<Result<Vec<_>>>()?;
+        let param_refs = params.iter().map(|p| p.as_ref() as &(dyn tokio_postgres::types::ToSql + Sync));
+        let rows = client.query_raw(&statement, param_refs).await
             .map_err(|e| crate::error::ProxyError::routing(format!("PostgreSQL query failed: {}", e)))?;
+        futures_util::pin_mut!(rows);
 
-        // Convert rows to JSON
+        // Convert rows to JSON, stopping at the row limit
         let mut results = Vec::new();
-        for row in rows {
+        let mut truncated = false;
+        while let Some(row) = rows.try_next().await
+            .map_err(|e| crate::error::ProxyError::routing(format!("PostgreSQL query failed: {}", e)))?
+        {
+            if results.len() == max_rows {
+                truncated = true;
+                break;
+            }
             let mut row_data = serde_json::Map::new();
             for (i, column) in row.columns().iter().enumerate() {
-                let column_name = column.name();
-                let value: serde_json::Value = match column.type_() {
-                    &tokio_postgres::types::Type::INT4 => {
-                        json!(row.get::<_, Option<i32>>(i))
-                    }
-                    &tokio_postgres::types::Type::INT8 => {
-                        json!(row.get::<_, Option<i64>>(i))
-                    }
-                    &tokio_postgres::types::Type::TEXT | &tokio_postgres::types::Type::VARCHAR => {
-                        json!(row.get::<_, Option<String>>(i))
-                    }
-                    &tokio_postgres::types::Type::BOOL => {
-                        json!(row.get::<_, Option<bool>>(i))
-                    }
-                    &tokio_postgres::types::Type::FLOAT4 => {
-                        json!(row.get::<_, Option<f32>>(i))
-                    }
-                    &tokio_postgres::types::Type::FLOAT8 => {
-                        json!(row.get::<_, Option<f64>>(i))
-                    }
-                    _ => {
-                        // Fallback to string representation
-                        json!(row.get::<_, Option<String>>(i))
-                    }
-                };
-                row_data.insert(column_name.to_string(), value);
+                row_data.insert(column.name().to_string(), postgres_json_value(&row, i, column.type_()));
             }
             results.push(json!(row_data));
         }
 
         Ok(json!({
             "rows": results,
-            "row_count": results.len()
+            "row_count": results.len(),
+            "truncated": truncated
         }))
     }
 
+    /// Execute MySQL query
+    #[cfg(feature = "mysql")]
+    async fn execute_mysql_query(
+        &self,
+        connection_string: &str,
+        query: &str,
+        bind_values: &[serde_json::Value],
+        read_only: bool,
+        max_rows: usize,
+    ) -> Result<serde_json::Value> {
+        use mysql_async::prelude::Queryable;
+        use serde_json::json;
+
+        let opts = mysql_async::Opts::from_url(connection_string)
+            .map_err(|e| crate::error::ProxyError::routing(format!("Invalid MySQL connection string: {}", e)))?;
+        let mut conn = mysql_async::Conn::new(opts).await
+            .map_err(|e| crate::error::ProxyError::routing(format!("MySQL connection failed: {}", e)))?;
+
+        if read_only {
+            conn.query_drop("SET SESSION TRANSACTION READ ONLY").await
+                .map_err(|e| crate::error::ProxyError::routing(format!("MySQL read-only session failed: {}", e)))?;
+        }
+
+        let params = mysql_async::Params::from(bind_values.iter().map(mysql_bind_value).collect::<Vec<_>>());
+        let query_result: std::result::Result<_, mysql_async::Error> = async {
+            let mut result = conn.exec_iter(query, params).await?;
+            let mut results = Vec::new();
+            let mut truncated = false;
+            while let Some(row) = result.next().await? {
+                if results.len() == max_rows {
+                    truncated = true;
+                    break;
+                }
+                let mut row_data = serde_json::Map::new();
+                for (i, column) in row.columns_ref().iter().enumerate() {
+                    let value = row.as_ref(i).map(|v| mysql_json_value(v, column)).unwrap_or(serde_json::Value::Null);
+                    row_data.insert(column.name_str().into_owned(), value);
+                }
+                results.push(json!(row_data));
+            }
+            // MySQL has no RETURNING, so writes report what they changed instead
+            let affected_rows = result.affected_rows();
+            let last_insert_id = result.last_insert_id();
+            // Discard the rows past the limit so the connection can be closed cleanly
+            result.drop_result().await?;
+            Ok((results, truncated, affected_rows, last_insert_id))
+        }.await;
+
+        if let Err(e) = conn.disconnect().await {
+            tracing::warn!("MySQL disconnect failed: {}", e);
+        }
+        let (results, truncated, affected_rows, last_insert_id) = query_result
+            .map_err(|e| crate::error::ProxyError::routing(format!("MySQL query failed: {}", e)))?;
+
+        let mut data = json!({
+            "rows": results,
+            "row_count": results.len(),
+            "truncated": truncated
+        });
+        if results.is_empty() {
+            data["affected_rows"] = json!(affected_rows);
+            if let Some(last_insert_id) = last_insert_id {
+                data["last_insert_id"] = json!(last_insert_id);
+            }
+        }
+        Ok(data)
+    }
+
     /// Execute SQLite query
     async fn execute_sqlite_query(
         &self,
         connection_string: &str,
-        query: &str
+        query: &str,
+        bind_values: &[serde_json::Value],
+        read_only: bool,
+        max_rows: usize,
     ) -> Result<serde_json::Value> {
-        use rusqlite::{Connection, params};
+        use rusqlite::Connection;
         use serde_json::json;
 
         // Execute in blocking task since rusqlite is synchronous
         let connection_string = connection_string.to_string();
         let query = query.to_string();
+        let bind_values: Vec<rusqlite::types::Value> = bind_values.iter().map(sqlite_bind_value).collect();
 
         let result = tokio::task::spawn_blocking(move || {
             // Connect to SQLite
             let conn = Connection::open(&connection_string)
                 .map_err(|e| crate::error::ProxyError::routing(format!("SQLite connection failed: {}", e)))?;
 
+            if read_only {
+                conn.pragma_update(None, "query_only", true)
+                    .map_err(|e| crate::error::ProxyError::routing(format!("SQLite read-only mode failed: {}", e)))?;
+            }
+
             // Prepare and execute query
             let mut stmt = conn.prepare(&query)
                 .map_err(|e| crate::error::ProxyError::routing(format!("SQLite query preparation failed: {}", e)))?;
 
             let column_names: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
 
-            let rows = stmt.query_map(params![], |row| {
+            let rows = stmt.query_map(rusqlite::params_from_iter(bind_values.iter()), |row| {
                 let mut row_data = serde_json::Map::new();
                 for (i, column_name) in column_names.iter().enumerate() {
                     let value: serde_json::Value = match row.get_ref(i) {
@@ -1200,13 +2301,19 @@ impl DefaultAgentRouter {
             }).map_err(|e| crate::error::ProxyError::routing(format!("SQLite query execution failed: {}", e)))?;
 
             let mut results = Vec::new();
+            let mut truncated = false;
             for row in rows {
+                if results.len() == max_rows {
+                    truncated = true;
+                    break;
+                }
                 results.push(row.map_err(|e| crate::error::ProxyError::routing(format!("SQLite row processing failed: {}", e)))?);
             }
 
             Ok(json!({
                 "rows": results,
-                "row_count": results.len()
+                "row_count": results.len(),
+                "truncated": truncated
             }))
         }).await;
 
@@ -1373,23 +2480,12 @@ impl DefaultAgentRouter {
 
         let timeout_duration = Duration::from_secs(timeout.unwrap_or(30));
 
-        let result = tokio_timeout(timeout_duration, async {
-            // For now, we'll implement a mock SSE call for testing
-            // In a real implementation, you would:
-            // 1. Create SSE client with reqwest or eventsource-stream
-            // 2. Connect to the SSE endpoint
-            // 3. Listen for events and filter/aggregate as needed
-            // 4. Return collected events or stream them
-
-            let response_data = self.make_generic_sse_call(
-                &substituted_url,
-                &substituted_headers,
-                max_events,
-                &substituted_event_filter,
-            ).await?;
-
-            Ok::<serde_json::Value, crate::error::ProxyError>(response_data)
-        }).await;
+        let result = tokio_timeout(timeout_duration, self.make_generic_sse_call(
+            &substituted_url,
+            &substituted_headers,
+            max_events,
+            &substituted_event_filter,
+        )).await;
 
         match result {
             Ok(Ok(data)) => Ok(AgentResult {
@@ -1429,57 +2525,49 @@ impl DefaultAgentRouter {
         }
     }
 
-    /// Make a generic SSE call (simplified implementation)
+    /// Subscribe to an SSE endpoint and collect its events, streaming each
+    /// one as progress when the client asked for it
     async fn make_generic_sse_call(
         &self,
         url: &str,
-        _headers: &Option<std::collections::HashMap<String, String>>,
+        headers: &Option<std::collections::HashMap<String, String>>,
         max_events: Option<u32>,
         event_filter: &Option<String>,
     ) -> Result<serde_json::Value> {
+        use crate::routing::streaming::{read_sse_events, DEFAULT_MAX_SSE_EVENTS};
         use serde_json::json;
 
-        // This is a placeholder implementation for generic SSE calls
-        // In a real implementation, you would need:
-        // 1. SSE client library (e.g., eventsource-stream, reqwest with streaming)
-        // 2. Event parsing and filtering logic
-        // 3. Real-time event collection and aggregation
-        // 4. Proper connection management and reconnection logic
-
         debug!("Making generic SSE call to {}", url);
 
-        // For now, return a mock response indicating the call was attempted
-        let mock_events = vec![
-            json!({
-                "id": "1",
-                "event": "message",
-                "data": "Mock SSE event 1",
-                "timestamp": chrono::Utc::now().to_rfc3339()
-            }),
-            json!({
-                "id": "2",
-                "event": "update",
-                "data": "Mock SSE event 2",
-                "timestamp": chrono::Utc::now().to_rfc3339()
-            })
-        ];
+        let client = reqwest::Client::builder()
+            .use_rustls_tls()
+            .tls_built_in_root_certs(true)
+            .build()
+            .map_err(|e| crate::error::ProxyError::routing(format!("Failed to create HTTP client: {}", e)))?;
+        let mut request = client.get(url).header("Accept", "text/event-stream");
+        for (name, value) in headers.iter().flatten() {
+            request = request.header(name, value);
+        }
 
-        let response_data = json!({
-            "status": "success",
-            "url": url,
-            "events": mock_events,
-            "event_count": mock_events.len(),
-            "max_events": max_events,
-            "event_filter": event_filter,
-            "message": "SSE connection established (mock implementation)",
-            "timestamp": chrono::Utc::now().to_rfc3339(),
-            "note": "This is a placeholder implementation. For production use, implement proper SSE client with event streaming."
-        });
+        let response = request.send().await
+            .map_err(|e| crate::error::ProxyError::routing(format!("Failed to connect to SSE endpoint: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(crate::error::ProxyError::routing(format!("SSE endpoint returned HTTP {}", response.status())));
+        }
 
-        Ok(response_data)
+        let progress = crate::mcp::progress::current();
+        let max_events = max_events.unwrap_or(DEFAULT_MAX_SSE_EVENTS);
+        let events = read_sse_events(response, max_events, event_filter.as_deref(), progress.as_ref()).await?;
+
+        Ok(json!({
+            "url": url,
+            "event_count": events.len(),
+            "events": events,
+        }))
     }
 
     /// Execute GraphQL agent
+    #[allow(clippy::too_many_arguments)]
     async fn execute_graphql_agent(
         &self,
         tool_call: &ToolCall,
@@ -1489,6 +2577,7 @@ impl DefaultAgentRouter {
         headers: &Option<std::collections::HashMap<String, String>>,
         timeout: Option<u64>,
         operation_name: &Option<String>,
+        subscription: &Option<crate::routing::graphql_ws::SubscriptionConfig>,
     ) -> Result<AgentResult> {
         use crate::routing::substitution::{substitute_parameter_string, substitute_headers, substitute_json_value};
         use serde_json::json;
@@ -1529,6 +2618,28 @@ impl DefaultAgentRouter {
                 .map(|s| s.to_string())
         };
 
+        // Subscriptions run over WebSocket until they complete or hit a limit
+        if let Some(subscription_query) = substituted_query.as_deref()
+            .filter(|q| subscription.is_some() || crate::routing::graphql_ws::is_subscription(q))
+        {
+            // Generated subscription tools take the operation's variables as their arguments
+            let subscription_variables = substituted_variables.clone()
+                .unwrap_or_else(|| tool_call.arguments.clone());
+            let init_payload = match subscription.as_ref().and_then(|s| s.init_payload.as_ref()) {
+                Some(payload) => Some(substitute_json_value(payload, &tool_call.arguments)?),
+                None => None,
+            };
+            let request = crate::routing::graphql_ws::SubscriptionRequest {
+                endpoint: &substituted_endpoint,
+                query: subscription_query,
+                variables: Some(&subscription_variables),
+                operation_name: substituted_operation_name.as_deref(),
+                headers: substituted_headers.as_ref(),
+                init_payload: init_payload.as_ref(),
+            };
+            return self.execute_graphql_subscription(tool_call, &request, &subscription.clone().unwrap_or_default()).await;
+        }
+
         let start_time = std::time::Instant::now();
         let timeout_duration = Duration::from_secs(timeout.unwrap_or(30));
 
@@ -1591,6 +2702,54 @@ impl DefaultAgentRouter {
         }
     }
 
+    /// Execute a GraphQL subscription, reporting each event as progress
+    async fn execute_graphql_subscription(
+        &self,
+        tool_call: &ToolCall,
+        request: &crate::routing::graphql_ws::SubscriptionRequest<'_>,
+        config: &crate::routing::graphql_ws::SubscriptionConfig,
+    ) -> Result<AgentResult> {
+        debug!("Executing GraphQL subscription: {}", request.endpoint);
+
+        let start_time = std::time::Instant::now();
+        let progress = crate::mcp::progress::current();
+
+        match crate::routing::graphql_ws::subscribe(request, config, progress.as_ref()).await {
+            Ok(outcome) => Ok(AgentResult {
+                success: true,
+                data: Some(json!({
+                    "events": outcome.events,
+                    "event_count": outcome.events.len(),
+                    "end_reason": outcome.end,
+                })),
+                error: None,
+                metadata: Some(json!({
+                    "tool_name": tool_call.name,
+                    "execution_type": "graphql",
+                    "operation_type": "subscription",
+                    "endpoint": request.endpoint,
+                    "operation_name": request.operation_name,
+                    "execution_time_ms": start_time.elapsed().as_millis()
+                })),
+            }),
+            Err(e) => {
+                error!("GraphQL subscription failed: {}", e);
+                Ok(AgentResult {
+                    success: false,
+                    data: None,
+                    error: Some(e.to_string()),
+                    metadata: Some(json!({
+                        "tool_name": tool_call.name,
+                        "execution_type": "graphql",
+                        "operation_type": "subscription",
+                        "endpoint": request.endpoint,
+                        "error_type": "graphql_error"
+                    })),
+                })
+            }
+        }
+    }
+
     /// Make a GraphQL request (simplified implementation)
     async fn make_graphql_request(
         &self,
@@ -1725,7 +2884,13 @@ impl DefaultAgentRouter {
                                         metadata["confidence_score"] = json!(discovery_response.metadata.confidence_score);
                                         metadata["discovery_reasoning"] = json!(discovery_response.metadata.reasoning);
                                         
-                                        // Include tool candidates for debugging and analysis
+                                        // Include the score breakdown and tool candidates for debugging and analysis
+                                        if let Some(score_breakdown) = &discovery_response.metadata.score_breakdown {
+                                            metadata["score_breakdown"] = json!(score_breakdown);
+                                        }
+                                        if let Some(resolved_request) = &discovery_response.metadata.resolved_request {
+                                            metadata["resolved_request"] = json!(resolved_request);
+                                        }
                                         if let Some(tool_candidates) = &discovery_response.metadata.tool_candidates {
                                             metadata["tool_candidates"] = json!(tool_candidates);
                                         }
@@ -1742,7 +2907,13 @@ impl DefaultAgentRouter {
                                             "discovery_reasoning": discovery_response.metadata.reasoning
                                         });
                                         
-                                        // Include tool candidates for debugging and analysis
+                                        // Include the score breakdown and tool candidates for debugging and analysis
+                                        if let Some(score_breakdown) = &discovery_response.metadata.score_breakdown {
+                                            new_metadata["score_breakdown"] = json!(score_breakdown);
+                                        }
+                                        if let Some(resolved_request) = &discovery_response.metadata.resolved_request {
+                                            new_metadata["resolved_request"] = json!(resolved_request);
+                                        }
                                         if let Some(tool_candidates) = &discovery_response.metadata.tool_candidates {
                                             new_metadata["tool_candidates"] = json!(tool_candidates);
                                         }
@@ -1844,6 +3015,159 @@ impl DefaultAgentRouter {
         }
     }
 
+    /// Plan a multi-step tool chain for a request without executing it
+    async fn execute_smart_discovery_plan_agent(
+        &self,
+        tool_call: &ToolCall,
+        enabled: bool,
+    ) -> Result<AgentResult> {
+        use serde_json::json;
+
+        let service = match &self.smart_discovery {
+            Some(service) if enabled => service,
+            _ => {
+                return Ok(AgentResult {
+                    success: false,
+                    data: None,
+                    error: Some("Smart discovery service not available".to_string()),
+                    metadata: Some(json!({
+                        "tool_name": tool_call.name,
+                        "execution_type": "smart_discovery_plan",
+                        "error": "service_not_available"
+                    })),
+                });
+            }
+        };
+
+        let planned = match self.parse_smart_discovery_request(tool_call) {
+            Ok(request) => service.plan_tool_chain(&request).await,
+            Err(e) => Err(e),
+        };
+        Ok(match planned {
+            Ok(plan) => AgentResult {
+                success: true,
+                data: Some(json!({ "plan": plan })),
+                error: None,
+                metadata: Some(json!({
+                    "tool_name": tool_call.name,
+                    "execution_type": "smart_discovery_plan"
+                })),
+            },
+            Err(e) => AgentResult {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                metadata: Some(json!({
+                    "tool_name": tool_call.name,
+                    "execution_type": "smart_discovery_plan",
+                    "error": "planning_failed"
+                })),
+            },
+        })
+    }
+
+    /// Record a client's feedback on a smart discovery result
+    async fn execute_smart_discovery_feedback_agent(
+        &self,
+        tool_call: &ToolCall,
+        enabled: bool,
+    ) -> Result<AgentResult> {
+        use serde_json::json;
+
+        let service = match &self.smart_discovery {
+            Some(service) if enabled => service,
+            _ => {
+                return Ok(AgentResult {
+                    success: false,
+                    data: None,
+                    error: Some("Smart discovery service not available".to_string()),
+                    metadata: Some(json!({
+                        "tool_name": tool_call.name,
+                        "execution_type": "smart_discovery_feedback",
+                        "error": "service_not_available"
+                    })),
+                });
+            }
+        };
+
+        let recorded = match serde_json::from_value::<crate::discovery::DiscoveryFeedback>(tool_call.arguments.clone()) {
+            Ok(feedback) => service.record_feedback(feedback).await,
+            Err(e) => Err(crate::error::ProxyError::validation(format!("Invalid feedback: {}", e))),
+        };
+        Ok(match recorded {
+            Ok(stats) => AgentResult {
+                success: true,
+                data: Some(json!({
+                    "recorded": true,
+                    "tool_stats": stats
+                })),
+                error: None,
+                metadata: Some(json!({
+                    "tool_name": tool_call.name,
+                    "execution_type": "smart_discovery_feedback"
+                })),
+            },
+            Err(e) => AgentResult {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                metadata: Some(json!({
+                    "tool_name": tool_call.name,
+                    "execution_type": "smart_discovery_feedback",
+                    "error": "feedback_rejected"
+                })),
+            },
+        })
+    }
+
+    /// Browse the tool categories derived from the embeddings
+    async fn execute_smart_discovery_categories_agent(
+        &self,
+        tool_call: &ToolCall,
+        enabled: bool,
+    ) -> Result<AgentResult> {
+        use serde_json::json;
+
+        let service = match &self.smart_discovery {
+            Some(service) if enabled => service,
+            _ => {
+                return Ok(AgentResult {
+                    success: false,
+                    data: None,
+                    error: Some("Smart discovery service not available".to_string()),
+                    metadata: Some(json!({
+                        "tool_name": tool_call.name,
+                        "execution_type": "smart_discovery_categories",
+                        "error": "service_not_available"
+                    })),
+                });
+            }
+        };
+
+        let category = tool_call.arguments.get("category").and_then(|v| v.as_str());
+        Ok(match service.browse_categories(category).await {
+            Ok(categories) => AgentResult {
+                success: true,
+                data: Some(categories),
+                error: None,
+                metadata: Some(json!({
+                    "tool_name": tool_call.name,
+                    "execution_type": "smart_discovery_categories"
+                })),
+            },
+            Err(e) => AgentResult {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                metadata: Some(json!({
+                    "tool_name": tool_call.name,
+                    "execution_type": "smart_discovery_categories",
+                    "error": "categories_unavailable"
+                })),
+            },
+        })
+    }
+
     /// Parse smart discovery request from tool call
     fn parse_smart_discovery_request(&self, tool_call: &ToolCall) -> Result<SmartDiscoveryRequest> {
         let request_str = tool_call.arguments.get("request")
@@ -1866,6 +3190,18 @@ impl DefaultAgentRouter {
         let confidence_threshold = tool_call.arguments.get("confidence_threshold")
             .and_then(|v| v.as_f64());
 
+        let conversation = tool_call.arguments.get("conversation")
+            .filter(|v| !v.is_null())
+            .map(|v| serde_json::from_value(v.clone()))
+            .transpose()
+            .map_err(|e| crate::error::ProxyError::validation(format!("Invalid 'conversation' parameter: {}", e)))?;
+
+        let exclusions = tool_call.arguments.get("exclusions")
+            .filter(|v| !v.is_null())
+            .map(|v| serde_json::from_value(v.clone()))
+            .transpose()
+            .map_err(|e| crate::error::ProxyError::validation(format!("Invalid 'exclusions' parameter: {}", e)))?;
+
         Ok(SmartDiscoveryRequest {
             request: request_str.to_string(),
             context,
@@ -1873,6 +3209,228 @@ impl DefaultAgentRouter {
             confidence_threshold,
             include_error_details: None,
             sequential_mode: None,
+            conversation,
+            exclusions,
         })
     }
 }
+
+/// Whether a statement only reads data (SELECT, WITH ... SELECT, VALUES, EXPLAIN).
+/// Read-only sessions enforce this on the database side as well.
+/// Parse the `path_params`, `query_params` and `body_params` of an HTTP agent.
+/// `query_params` is either a list of argument names, or a map of query
+/// parameter name to the argument it is taken from.
+fn parse_http_params(config: &serde_json::Value) -> Result<crate::routing::types::HttpParams> {
+    use crate::error::ProxyError;
+
+    let names = |key: &str| -> Result<Option<Vec<String>>> {
+        match config.get(key) {
+            None | Some(serde_json::Value::Null) => Ok(None),
+            Some(serde_json::Value::Array(items)) => items.iter()
+                .map(|item| item.as_str().map(str::to_string)
+                    .ok_or_else(|| ProxyError::routing(format!("HTTP agent '{}' entries must be strings", key))))
+                .collect::<Result<Vec<_>>>()
+                .map(Some),
+            Some(_) => Err(ProxyError::routing(format!("HTTP agent '{}' must be a list of argument names", key))),
+        }
+    };
+
+    let query = match config.get("query_params") {
+        Some(serde_json::Value::Object(map)) => map.iter()
+            .map(|(name, arg)| arg.as_str()
+                .map(|arg| (name.clone(), arg.to_string()))
+                .ok_or_else(|| ProxyError::routing(format!("HTTP agent query parameter '{}' must name an argument", name))))
+            .collect::<Result<Vec<_>>>()?,
+        _ => names("query_params")?.unwrap_or_default().into_iter().map(|name| (name.clone(), name)).collect(),
+    };
+
+    Ok(crate::routing::types::HttpParams {
+        path: names("path_params")?.unwrap_or_default(),
+        query,
+        body: names("body_params")?,
+    })
+}
+
+/// Error for a routing compiled out of this build
+#[cfg(not(all(feature = "kafka", feature = "amqp", feature = "wasm", feature = "mysql")))]
+fn feature_disabled(routing: &str, feature: &str) -> crate::error::ProxyError {
+    crate::error::ProxyError::routing(format!(
+        "'{}' is not supported by this build; rebuild with the `{}` feature", routing, feature
+    ))
+}
+
+fn is_read_query(query: &str) -> bool {
+    let statement = query.trim_start().to_ascii_lowercase();
+    let first_word = statement.split(|c: char| c.is_whitespace() || c == '(').next().unwrap_or("");
+    let single_statement = !query.trim().trim_end_matches(';').contains(';');
+    single_statement && matches!(first_word, "select" | "with" | "values" | "explain")
+}
+
+/// Convert a JSON argument into a PostgreSQL parameter of the type inferred for placeholder `$index`
+fn postgres_bind_value(
+    index: usize,
+    ty: &tokio_postgres::types::Type,
+    value: &serde_json::Value,
+) -> Result<Box<dyn tokio_postgres::types::ToSql + Sync + Send>> {
+    use tokio_postgres::types::Type;
+    use serde_json::Value;
+
+    let invalid = || crate::error::ProxyError::routing(format!(
+        "Parameter ${} expects {}, got {}", index, ty, value
+    ));
+
+    Ok(match *ty {
+        Type::BOOL => Box::new(match value {
+            Value::Null => None,
+            Value::Bool(b) => Some(*b),
+            _ => return Err(invalid()),
+        }),
+        Type::INT2 => Box::new(match value {
+            Value::Null => None,
+            _ => Some(value.as_i64().and_then(|v| i16::try_from(v).ok()).ok_or_else(invalid)?),
+        }),
+        Type::INT4 => Box::new(match value {
+            Value::Null => None,
+            _ => Some(value.as_i64().and_then(|v| i32::try_from(v).ok()).ok_or_else(invalid)?),
+        }),
+        Type::INT8 => Box::new(match value {
+            Value::Null => None,
+            _ => Some(value.as_i64().ok_or_else(invalid)?),
+        }),
+        Type::FLOAT4 => Box::new(match value {
+            Value::Null => None,
+            _ => Some(value.as_f64().ok_or_else(invalid)? as f32),
+        }),
+        Type::FLOAT8 => Box::new(match value {
+            Value::Null => None,
+            _ => Some(value.as_f64().ok_or_else(invalid)?),
+        }),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN => Box::new(match value {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }),
+        _ => {
+            return Err(crate::error::ProxyError::routing(format!(
+                "Parameter ${} has unsupported type {}; cast it in the query (e.g. ${}::text::{})",
+                index, ty, index, ty.name()
+            )))
+        }
+    })
+}
+
+/// Convert a PostgreSQL column to JSON
+///
+/// Types without a direct JSON mapping are read as text when PostgreSQL can
+/// send them that way and are `null` otherwise.
+fn postgres_json_value(row: &tokio_postgres::Row, index: usize, ty: &tokio_postgres::types::Type) -> serde_json::Value {
+    use serde_json::json;
+    use tokio_postgres::types::Type;
+
+    fn get<'a, T: tokio_postgres::types::FromSql<'a>>(row: &'a tokio_postgres::Row, index: usize) -> Option<T> {
+        row.try_get::<_, Option<T>>(index).ok().flatten()
+    }
+
+    match *ty {
+        Type::BOOL => json!(get::<bool>(row, index)),
+        Type::INT2 => json!(get::<i16>(row, index)),
+        Type::INT4 => json!(get::<i32>(row, index)),
+        Type::INT8 => json!(get::<i64>(row, index)),
+        Type::OID => json!(get::<u32>(row, index)),
+        Type::FLOAT4 => json!(get::<f32>(row, index)),
+        Type::FLOAT8 => json!(get::<f64>(row, index)),
+        Type::JSON | Type::JSONB => get::<serde_json::Value>(row, index).unwrap_or(serde_json::Value::Null),
+        Type::UUID => json!(get::<uuid::Uuid>(row, index)),
+        Type::TIMESTAMPTZ => json!(get::<chrono::DateTime<chrono::Utc>>(row, index)),
+        Type::TIMESTAMP => json!(get::<chrono::NaiveDateTime>(row, index)),
+        Type::DATE => json!(get::<chrono::NaiveDate>(row, index)),
+        Type::TIME => json!(get::<chrono::NaiveTime>(row, index)),
+        Type::BYTEA => json!(get::<Vec<u8>>(row, index).map(|b| base64::prelude::BASE64_STANDARD.encode(b))),
+        Type::TEXT_ARRAY | Type::VARCHAR_ARRAY => json!(get::<Vec<Option<String>>>(row, index)),
+        Type::INT4_ARRAY => json!(get::<Vec<Option<i32>>>(row, index)),
+        Type::INT8_ARRAY => json!(get::<Vec<Option<i64>>>(row, index)),
+        _ => json!(get::<String>(row, index)),
+    }
+}
+
+/// Convert a JSON argument into a MySQL parameter
+#[cfg(feature = "mysql")]
+fn mysql_bind_value(value: &serde_json::Value) -> mysql_async::Value {
+    use mysql_async::Value as SqlValue;
+    use serde_json::Value;
+
+    match value {
+        Value::Null => SqlValue::NULL,
+        Value::Bool(b) => SqlValue::Int(*b as i64),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => SqlValue::Int(i),
+            (None, Some(u)) => SqlValue::UInt(u),
+            _ => SqlValue::Double(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Bytes(s.clone().into_bytes()),
+        other => SqlValue::Bytes(other.to_string().into_bytes()),
+    }
+}
+
+/// MySQL character set number of binary strings (BLOB, BINARY, VARBINARY)
+const MYSQL_BINARY_CHARSET: u16 = 63;
+
+/// Convert a MySQL column value to JSON
+///
+/// DECIMAL values stay strings so no precision is lost; binary strings are base64.
+#[cfg(feature = "mysql")]
+fn mysql_json_value(value: &mysql_async::Value, column: &mysql_async::Column) -> serde_json::Value {
+    use mysql_async::consts::ColumnType;
+    use mysql_async::Value as SqlValue;
+    use serde_json::json;
+
+    match value {
+        SqlValue::NULL => serde_json::Value::Null,
+        SqlValue::Int(i) => json!(i),
+        SqlValue::UInt(u) => json!(u),
+        SqlValue::Float(f) => json!(f),
+        SqlValue::Double(d) => json!(d),
+        SqlValue::Bytes(bytes) if column.column_type() == ColumnType::MYSQL_TYPE_JSON => {
+            serde_json::from_slice(bytes).unwrap_or_else(|_| json!(String::from_utf8_lossy(bytes)))
+        }
+        SqlValue::Bytes(bytes) if column.character_set() == MYSQL_BINARY_CHARSET => {
+            json!(base64::prelude::BASE64_STANDARD.encode(bytes))
+        }
+        SqlValue::Bytes(bytes) => json!(String::from_utf8_lossy(bytes)),
+        SqlValue::Date(year, month, day, hour, minute, second, micros) => {
+            if column.column_type() == ColumnType::MYSQL_TYPE_DATE {
+                json!(format!("{:04}-{:02}-{:02}", year, month, day))
+            } else if *micros > 0 {
+                json!(format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}", year, month, day, hour, minute, second, micros))
+            } else {
+                json!(format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, hour, minute, second))
+            }
+        }
+        SqlValue::Time(negative, days, hours, minutes, seconds, micros) => {
+            let sign = if *negative { "-" } else { "" };
+            let hours = u32::from(*hours) + days * 24;
+            if *micros > 0 {
+                json!(format!("{}{:02}:{:02}:{:02}.{:06}", sign, hours, minutes, seconds, micros))
+            } else {
+                json!(format!("{}{:02}:{:02}:{:02}", sign, hours, minutes, seconds))
+            }
+        }
+    }
+}
+
+/// Convert a JSON argument into a SQLite value
+fn sqlite_bind_value(value: &serde_json::Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value as SqlValue;
+    use serde_json::Value;
+
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
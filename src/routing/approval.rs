@@ -0,0 +1,496 @@
+//! Human approval of high-risk tool calls
+//!
+//! Calls matching the `approvals` rules are parked until an approver
+//! approves or denies them through the dashboard API:
+//!
+//! ```yaml
+//! approvals:
+//!   destructive_tools: true        # tools annotated destructiveHint
+//!   tools: ["transfer_funds"]
+//!   argument_patterns:
+//!     - tool: "run_sql"            # optional; any tool when omitted
+//!       pointer: "/query"          # optional; all string arguments when omitted
+//!       pattern: "(?i)\\b(drop|truncate|delete)\\b"
+//!   timeout_seconds: 900           # pending calls are denied after this
+//!   notifications: { ... }         # see approval_notify
+//! ```
+//!
+//! While a call waits, its caller receives `notifications/progress` if it
+//! asked for progress, and once it is approved the call runs and the caller
+//! gets the tool's result as usual. Approvers can also be notified and decide
+//! through signed links, see [`crate::routing::approval_notify`].
+
+use crate::error::{ProxyError, Result};
+use crate::mcp::progress;
+use crate::mcp::ToolCall;
+use crate::registry::ToolDefinition;
+use crate::routing::approval_notify::{self, ApprovalNotificationsConfig, ApprovalNotifier, LinkAction, LinkError};
+use crate::routing::{cost, rate_limit};
+use crate::routing::types::AgentResult;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::info;
+use uuid::Uuid;
+
+fn default_timeout_seconds() -> u64 {
+    900
+}
+
+fn default_progress_interval_seconds() -> u64 {
+    30
+}
+
+/// Arguments that make a call need approval
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArgumentPattern {
+    /// Tool the pattern applies to (default: every tool)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// JSON pointer of the argument to match (default: every string argument)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<String>,
+    /// Regular expression the argument is matched against
+    pub pattern: String,
+}
+
+/// Which tool calls need approval
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalConfig {
+    /// Calls to tools annotated `destructiveHint` need approval
+    #[serde(default)]
+    pub destructive_tools: bool,
+    /// Tools whose calls need approval
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Calls with matching arguments need approval
+    #[serde(default)]
+    pub argument_patterns: Vec<ArgumentPattern>,
+    /// Seconds a call waits for a decision before it is denied
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+    /// Seconds between progress updates to a waiting caller
+    #[serde(default = "default_progress_interval_seconds")]
+    pub progress_interval_seconds: u64,
+    /// Where approvers are notified of calls waiting for approval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<ApprovalNotificationsConfig>,
+}
+
+impl Default for ApprovalConfig {
+    fn default() -> Self {
+        Self {
+            destructive_tools: false,
+            tools: Vec::new(),
+            argument_patterns: Vec::new(),
+            timeout_seconds: default_timeout_seconds(),
+            progress_interval_seconds: default_progress_interval_seconds(),
+            notifications: None,
+        }
+    }
+}
+
+impl ApprovalConfig {
+    pub fn validate(&self) -> Result<()> {
+        for argument in &self.argument_patterns {
+            Regex::new(&argument.pattern).map_err(|e| {
+                ProxyError::config(format!("Invalid approvals argument pattern '{}': {}", argument.pattern, e))
+            })?;
+            if argument.pointer.as_ref().is_some_and(|pointer| !pointer.starts_with('/')) {
+                return Err(ProxyError::config("approvals argument pointers must start with '/'"));
+            }
+        }
+        if self.timeout_seconds == 0 || self.progress_interval_seconds == 0 {
+            return Err(ProxyError::config("approvals.timeout_seconds and progress_interval_seconds must be at least 1"));
+        }
+        if let Some(notifications) = &self.notifications {
+            notifications.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Approval rules with their patterns compiled
+#[derive(Debug, Default)]
+struct Rules {
+    config: ApprovalConfig,
+    patterns: Vec<(ArgumentPattern, Regex)>,
+    notifier: Option<Arc<ApprovalNotifier>>,
+}
+
+/// A call waiting for approval, as shown to approvers
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub id: String,
+    pub tool_name: String,
+    pub arguments: Value,
+    /// Why the call needs approval
+    pub reason: String,
+    /// API key the call was made with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// User the call was made by
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// An approver's decision
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApprovalDecision {
+    /// Whether the call may run; set from the endpoint used
+    #[serde(default)]
+    pub approved: bool,
+    /// Who decided
+    #[serde(default)]
+    pub approver: Option<String>,
+    /// Note passed on to the caller
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// How waiting for approval ended
+#[derive(Debug)]
+pub enum ApprovalOutcome {
+    Approved { id: String, decision: ApprovalDecision },
+    Denied { id: String, decision: ApprovalDecision },
+    TimedOut { id: String },
+}
+
+impl ApprovalOutcome {
+    /// Tool error for a call that was not approved, `None` if it was
+    pub fn refusal(&self, tool_call: &ToolCall) -> Option<AgentResult> {
+        let (category, error, id) = match self {
+            ApprovalOutcome::Approved { .. } => return None,
+            ApprovalOutcome::Denied { id, decision } => {
+                let by = decision.approver.as_deref().map(|approver| format!(" by {}", approver)).unwrap_or_default();
+                let comment = decision.comment.as_deref().map(|comment| format!(": {}", comment)).unwrap_or_default();
+                ("approval_denied", format!("Call to tool '{}' was denied{}{}", tool_call.name, by, comment), id)
+            }
+            ApprovalOutcome::TimedOut { id } => {
+                ("approval_timeout", format!("Call to tool '{}' was not approved in time", tool_call.name), id)
+            }
+        };
+        Some(AgentResult {
+            success: false,
+            data: None,
+            error: Some(error),
+            metadata: Some(json!({
+                "error_category": category,
+                "approval": { "id": id },
+            })),
+        })
+    }
+}
+
+struct Pending {
+    request: ApprovalRequest,
+    sender: oneshot::Sender<ApprovalDecision>,
+}
+
+/// Parks calls that need approval until an approver decides
+#[derive(Default)]
+pub struct ApprovalManager {
+    rules: RwLock<Rules>,
+    pending: Mutex<BTreeMap<String, Pending>>,
+}
+
+impl std::fmt::Debug for ApprovalManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApprovalManager").finish_non_exhaustive()
+    }
+}
+
+impl ApprovalManager {
+    /// Replace the rules; calls already waiting keep waiting
+    pub fn configure(&self, config: ApprovalConfig) {
+        let patterns = config.argument_patterns.iter()
+            .filter_map(|argument| Regex::new(&argument.pattern).ok().map(|regex| (argument.clone(), regex)))
+            .collect();
+        let notifier = config.notifications.clone().map(|notifications| Arc::new(ApprovalNotifier::new(notifications)));
+        if let Ok(mut rules) = self.rules.write() {
+            *rules = Rules { config, patterns, notifier };
+        }
+    }
+
+    /// Why a call needs approval, `None` if it doesn't
+    pub fn requires_approval(&self, tool_call: &ToolCall, tool_def: &ToolDefinition) -> Option<String> {
+        let rules = self.rules.read().ok()?;
+        if rules.config.tools.iter().any(|tool| *tool == tool_call.name) {
+            return Some(format!("tool '{}' requires approval", tool_call.name));
+        }
+        if rules.config.destructive_tools && tool_def.is_destructive() {
+            return Some(format!("tool '{}' is destructive", tool_call.name));
+        }
+        rules.patterns.iter()
+            .filter(|(argument, _)| argument.tool.as_ref().map_or(true, |tool| *tool == tool_call.name))
+            .find(|(argument, regex)| match &argument.pointer {
+                Some(pointer) => tool_call.arguments.pointer(pointer).and_then(Value::as_str).is_some_and(|value| regex.is_match(value)),
+                None => any_string(&tool_call.arguments, &|value| regex.is_match(value)),
+            })
+            .map(|(argument, _)| match &argument.pointer {
+                Some(pointer) => format!("argument '{}' matches '{}'", pointer, argument.pattern),
+                None => format!("arguments match '{}'", argument.pattern),
+            })
+    }
+
+    /// Park a call until it is approved, denied or times out, keeping a
+    /// caller that asked for progress informed while it waits
+    pub async fn wait_for_approval(&self, tool_call: &ToolCall, reason: String) -> ApprovalOutcome {
+        let (timeout, interval, notifier) = match self.rules.read() {
+            Ok(rules) => (rules.config.timeout_seconds, rules.config.progress_interval_seconds, rules.notifier.clone()),
+            Err(_) => (default_timeout_seconds(), default_progress_interval_seconds(), None),
+        };
+        let (request, mut receiver) = self.park(tool_call, reason, Duration::from_secs(timeout));
+        let id = request.id.clone();
+        if let Some(notifier) = notifier {
+            let request = request.clone();
+            tokio::spawn(async move { notifier.notify(&request).await });
+        }
+        let reporter = progress::current();
+        let message = format!("Waiting for approval of call {} ({})", id, request.reason);
+
+        let deadline = tokio::time::sleep(Duration::from_secs(timeout));
+        tokio::pin!(deadline);
+        let mut ticks = tokio::time::interval(Duration::from_secs(interval));
+        let decision = loop {
+            tokio::select! {
+                decision = &mut receiver => break decision.ok(),
+                _ = &mut deadline => break None,
+                _ = ticks.tick() => {
+                    if let Some(reporter) = &reporter {
+                        reporter.report(message.clone());
+                    }
+                }
+            }
+        };
+
+        match decision {
+            Some(decision) => {
+                if let Some(reporter) = &reporter {
+                    let verdict = if decision.approved { "Approved" } else { "Denied" };
+                    reporter.report(match &decision.approver {
+                        Some(approver) => format!("{} by {}", verdict, approver),
+                        None => verdict.to_string(),
+                    });
+                }
+                if decision.approved {
+                    ApprovalOutcome::Approved { id, decision }
+                } else {
+                    ApprovalOutcome::Denied { id, decision }
+                }
+            }
+            None => {
+                self.pending.lock().unwrap().remove(&id);
+                info!(target: "audit", approval_id = %id, tool = %tool_call.name, "Approval request timed out");
+                ApprovalOutcome::TimedOut { id }
+            }
+        }
+    }
+
+    fn park(&self, tool_call: &ToolCall, reason: String, timeout: Duration) -> (ApprovalRequest, oneshot::Receiver<ApprovalDecision>) {
+        let now = Utc::now();
+        let request = ApprovalRequest {
+            id: Uuid::new_v4().to_string(),
+            tool_name: tool_call.name.clone(),
+            arguments: tool_call.arguments.clone(),
+            reason,
+            api_key: rate_limit::current_client(),
+            user: cost::current_user(),
+            requested_at: now,
+            expires_at: now + chrono::Duration::from_std(timeout).unwrap_or_else(|_| chrono::Duration::zero()),
+        };
+        info!(
+            target: "audit",
+            approval_id = %request.id,
+            tool = %request.tool_name,
+            reason = %request.reason,
+            api_key = ?request.api_key,
+            user = ?request.user,
+            "Tool call waiting for approval"
+        );
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(request.id.clone(), Pending { request: request.clone(), sender });
+        (request, receiver)
+    }
+
+    /// Calls waiting for approval, oldest first
+    pub fn pending(&self) -> Vec<ApprovalRequest> {
+        let mut requests: Vec<ApprovalRequest> = self.pending.lock().unwrap()
+            .values()
+            .map(|pending| pending.request.clone())
+            .collect();
+        requests.sort_by_key(|request| request.requested_at);
+        requests
+    }
+
+    /// A call waiting for approval
+    pub fn request(&self, id: &str) -> Option<ApprovalRequest> {
+        self.pending.lock().unwrap().get(id).map(|pending| pending.request.clone())
+    }
+
+    /// Check a signed approve/deny link without deciding
+    pub async fn verify_link(&self, id: &str, action: LinkAction, expires: i64, signature: &str) -> std::result::Result<ApprovalRequest, LinkError> {
+        let notifier = self.rules.read().ok().and_then(|rules| rules.notifier.clone()).ok_or(LinkError::NotConfigured)?;
+        let secret = notifier.link_secret().await?;
+        approval_notify::verify_link(&secret, id, action, expires, signature)?;
+        self.request(id).ok_or(LinkError::UnknownApproval)
+    }
+
+    /// Approve or deny a waiting call through a signed link
+    pub async fn decide_by_link(&self, id: &str, action: LinkAction, expires: i64, signature: &str) -> std::result::Result<ApprovalRequest, LinkError> {
+        self.verify_link(id, action, expires, signature).await?;
+        let decision = ApprovalDecision {
+            approved: action == LinkAction::Approve,
+            approver: Some("signed link".to_string()),
+            comment: None,
+        };
+        self.decide(id, decision).map_err(|_| LinkError::UnknownApproval)
+    }
+
+    /// Approve or deny a waiting call
+    pub fn decide(&self, id: &str, decision: ApprovalDecision) -> Result<ApprovalRequest> {
+        let pending = self.pending.lock().unwrap().remove(id)
+            .ok_or_else(|| ProxyError::validation(format!("No call is waiting for approval with ID '{}'", id)))?;
+        info!(
+            target: "audit",
+            approval_id = %id,
+            tool = %pending.request.tool_name,
+            approved = decision.approved,
+            approver = ?decision.approver,
+            comment = ?decision.comment,
+            "Tool call {}", if decision.approved { "approved" } else { "denied" }
+        );
+        // The caller may have gone away, in which case the call just doesn't run
+        let _ = pending.sender.send(decision);
+        Ok(pending.request)
+    }
+}
+
+/// Whether any string in `value` satisfies `matches`
+fn any_string(value: &Value, matches: &dyn Fn(&str) -> bool) -> bool {
+    match value {
+        Value::String(text) => matches(text),
+        Value::Array(items) => items.iter().any(|item| any_string(item, matches)),
+        Value::Object(fields) => fields.values().any(|field| any_string(field, matches)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::RoutingConfig;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn tool(name: &str, destructive: bool) -> ToolDefinition {
+        let annotations = destructive.then(|| HashMap::from([("destructiveHint".to_string(), "true".to_string())]));
+        ToolDefinition::new_with_fields(
+            name.to_string(),
+            "A tool".to_string(),
+            json!({"type": "object"}),
+            RoutingConfig::new("http".to_string(), json!({"url": "http://localhost"})),
+            annotations,
+        ).unwrap()
+    }
+
+    fn manager() -> ApprovalManager {
+        let manager = ApprovalManager::default();
+        manager.configure(serde_yaml::from_str(r#"
+destructive_tools: true
+tools: ["transfer_funds"]
+argument_patterns:
+  - tool: run_sql
+    pointer: /query
+    pattern: "(?i)\\bdrop\\b"
+  - pattern: "rm -rf"
+timeout_seconds: 1
+"#).unwrap());
+        manager
+    }
+
+    #[test]
+    fn test_rules() {
+        let manager = manager();
+        let call = |name: &str, arguments: Value| ToolCall::new(name.to_string(), arguments);
+        assert!(manager.requires_approval(&call("transfer_funds", json!({})), &tool("transfer_funds", false)).is_some());
+        assert!(manager.requires_approval(&call("delete_user", json!({})), &tool("delete_user", true)).is_some());
+        assert!(manager.requires_approval(&call("run_sql", json!({"query": "DROP TABLE users"})), &tool("run_sql", false)).is_some());
+        assert!(manager.requires_approval(&call("run_sql", json!({"query": "SELECT 1"})), &tool("run_sql", false)).is_none());
+        assert!(manager.requires_approval(&call("other", json!({"query": "drop it"})), &tool("other", false)).is_none());
+        assert!(manager.requires_approval(&call("shell", json!({"argv": ["sh", "-c", "rm -rf /tmp/x"]})), &tool("shell", false)).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_approve_and_deny() {
+        let manager = Arc::new(manager());
+        let call = ToolCall::new("transfer_funds".to_string(), json!({"amount": 100}));
+
+        let waiting = tokio::spawn({
+            let manager = manager.clone();
+            let call = call.clone();
+            async move { manager.wait_for_approval(&call, "test".to_string()).await }
+        });
+        while manager.pending().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        let request = manager.pending().remove(0);
+        assert_eq!(request.tool_name, "transfer_funds");
+        manager.decide(&request.id, ApprovalDecision { approved: false, approver: Some("bob".to_string()), comment: Some("too much".to_string()) }).unwrap();
+
+        let outcome = waiting.await.unwrap();
+        let refusal = outcome.refusal(&call).unwrap();
+        assert_eq!(refusal.error.as_deref(), Some("Call to tool 'transfer_funds' was denied by bob: too much"));
+        assert!(manager.pending().is_empty());
+        assert!(manager.decide(&request.id, ApprovalDecision::default()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_times_out() {
+        let manager = manager();
+        let call = ToolCall::new("transfer_funds".to_string(), json!({}));
+        let outcome = manager.wait_for_approval(&call, "test".to_string()).await;
+        assert!(matches!(outcome, ApprovalOutcome::TimedOut { .. }));
+        assert_eq!(outcome.refusal(&call).unwrap().metadata.unwrap()["error_category"], "approval_timeout");
+        assert!(manager.pending().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_decide_by_link() {
+        let manager = Arc::new(manager());
+        let mut config = manager.rules.read().unwrap().config.clone();
+        config.notifications = Some(ApprovalNotificationsConfig {
+            public_url: Some("https://tunnel.example.com".to_string()),
+            link_secret: Some("secret".to_string()),
+            webhooks: vec![approval_notify::WebhookNotifierConfig { url: "http://127.0.0.1:9/hooks".to_string(), headers: HashMap::new() }],
+            ..Default::default()
+        });
+        manager.configure(config);
+        let call = ToolCall::new("transfer_funds".to_string(), json!({}));
+
+        let waiting = tokio::spawn({
+            let manager = manager.clone();
+            let call = call.clone();
+            async move { manager.wait_for_approval(&call, "test".to_string()).await }
+        });
+        while manager.pending().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        let request = manager.pending().remove(0);
+        let expires = request.expires_at.timestamp();
+        let deny = approval_notify::sign_link("secret", &request.id, LinkAction::Deny, expires);
+        assert_eq!(manager.decide_by_link(&request.id, LinkAction::Approve, expires, &deny).await.unwrap_err(), LinkError::InvalidSignature);
+        let approve = approval_notify::sign_link("secret", &request.id, LinkAction::Approve, expires);
+        assert_eq!(manager.decide_by_link(&request.id, LinkAction::Approve, expires, &approve).await.unwrap().id, request.id);
+
+        assert!(matches!(waiting.await.unwrap(), ApprovalOutcome::Approved { .. }));
+        assert_eq!(manager.decide_by_link(&request.id, LinkAction::Approve, expires, &approve).await.unwrap_err(), LinkError::UnknownApproval);
+    }
+}
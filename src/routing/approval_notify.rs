@@ -0,0 +1,418 @@
+//! Notifications to approvers of tool calls waiting for approval
+//!
+//! Approvers don't have to watch the dashboard: each parked call can be
+//! posted to Slack, to HTTP webhooks and by email, with links that approve or
+//! deny it without logging in:
+//!
+//! ```yaml
+//! approvals:
+//!   tools: ["transfer_funds"]
+//!   notifications:
+//!     public_url: "https://tunnel.example.com"
+//!     link_secret: "${env:APPROVAL_LINK_SECRET}"
+//!     slack:
+//!       webhook_url: "${env:SLACK_WEBHOOK_URL}"
+//!     webhooks:
+//!       - url: "https://ops.example.com/hooks/approvals"
+//!     email:
+//!       smtp_host: "smtp.example.com"
+//!       username: "magictunnel"
+//!       password: "${env:SMTP_PASSWORD}"
+//!       from: "magictunnel@example.com"
+//!       to: ["oncall@example.com"]
+//! ```
+//!
+//! Links point to `/approvals/{id}/approve` and `/approvals/{id}/deny` and
+//! carry an HMAC-SHA256 signature over the approval ID, the action and the
+//! expiry, which is when the call stops waiting. Opening a link shows a
+//! confirmation page, so link previews and mail scanners can't decide.
+
+use crate::config::secrets::resolve_secret;
+use crate::error::{ProxyError, Result};
+use crate::routing::approval::ApprovalRequest;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+const NOTIFY_TIMEOUT_SECS: u64 = 10;
+
+/// Slack incoming webhook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlackNotifierConfig {
+    /// Incoming webhook URL; may be a secret reference
+    pub webhook_url: String,
+}
+
+/// HTTP endpoint receiving approval requests as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookNotifierConfig {
+    pub url: String,
+    /// Headers sent with each request; values may be secret references
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// How the SMTP connection is secured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpTls {
+    /// Upgrade with STARTTLS (port 587)
+    #[default]
+    Starttls,
+    /// TLS from the start (port 465)
+    Tls,
+    /// Plain text, for a relay on a trusted network
+    None,
+}
+
+/// Email sent through an SMTP server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmailNotifierConfig {
+    pub smtp_host: String,
+    /// SMTP port (default: the one of the TLS mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smtp_port: Option<u16>,
+    #[serde(default)]
+    pub tls: SmtpTls,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// SMTP password; may be a secret reference
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+/// Where approvers are notified of calls waiting for approval
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalNotificationsConfig {
+    /// Base URL of this server as approvers reach it, for approve/deny links
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_url: Option<String>,
+    /// Key signing approve/deny links; may be a secret reference
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_secret: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slack: Option<SlackNotifierConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookNotifierConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<EmailNotifierConfig>,
+}
+
+impl ApprovalNotificationsConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.public_url.is_some() != self.link_secret.is_some() {
+            return Err(ProxyError::config(
+                "approvals.notifications needs both public_url and link_secret for approve/deny links",
+            ));
+        }
+        let urls = self.public_url.iter().chain(self.webhooks.iter().map(|webhook| &webhook.url));
+        for url in urls {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(ProxyError::config(format!("approvals.notifications URL '{}' must be an http(s) URL", url)));
+            }
+        }
+        if let Some(email) = &self.email {
+            if email.smtp_host.trim().is_empty() || email.to.is_empty() {
+                return Err(ProxyError::config("approvals.notifications.email needs an smtp_host and recipients"));
+            }
+            if email.password.is_some() && email.username.is_none() {
+                return Err(ProxyError::config("approvals.notifications.email has a password but no username"));
+            }
+        }
+        if self.slack.is_none() && self.webhooks.is_empty() && self.email.is_none() {
+            return Err(ProxyError::config("approvals.notifications needs slack, webhooks or email"));
+        }
+        Ok(())
+    }
+}
+
+/// Action an approval link takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkAction {
+    Approve,
+    Deny,
+}
+
+impl LinkAction {
+    pub fn parse(action: &str) -> Option<Self> {
+        match action {
+            "approve" => Some(LinkAction::Approve),
+            "deny" => Some(LinkAction::Deny),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkAction::Approve => "approve",
+            LinkAction::Deny => "deny",
+        }
+    }
+}
+
+/// Why an approval link was refused
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum LinkError {
+    #[error("approval links are not configured")]
+    NotConfigured,
+    #[error("no call is waiting for this approval")]
+    UnknownApproval,
+    #[error("the link has expired")]
+    Expired,
+    #[error("invalid link signature")]
+    InvalidSignature,
+}
+
+fn mac(secret: &str, id: &str, action: LinkAction, expires: i64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}.{}", id, action.as_str(), expires).as_bytes());
+    mac
+}
+
+/// Signature of an approval link
+pub fn sign_link(secret: &str, id: &str, action: LinkAction, expires: i64) -> String {
+    hex::encode(mac(secret, id, action, expires).finalize().into_bytes())
+}
+
+/// Check an approval link's signature and expiry
+pub fn verify_link(secret: &str, id: &str, action: LinkAction, expires: i64, signature: &str) -> std::result::Result<(), LinkError> {
+    let signature = hex::decode(signature).map_err(|_| LinkError::InvalidSignature)?;
+    mac(secret, id, action, expires).verify_slice(&signature).map_err(|_| LinkError::InvalidSignature)?;
+    if chrono::Utc::now().timestamp() > expires {
+        return Err(LinkError::Expired);
+    }
+    Ok(())
+}
+
+/// Approve and deny links of a request
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApprovalLinks {
+    pub approve: String,
+    pub deny: String,
+}
+
+/// Sends approval requests to the configured channels
+pub struct ApprovalNotifier {
+    config: ApprovalNotificationsConfig,
+    client: reqwest::Client,
+}
+
+impl std::fmt::Debug for ApprovalNotifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApprovalNotifier").finish_non_exhaustive()
+    }
+}
+
+impl ApprovalNotifier {
+    pub fn new(config: ApprovalNotificationsConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(NOTIFY_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_default();
+        Self { config, client }
+    }
+
+    /// Resolved key signing approval links, if links are configured
+    pub async fn link_secret(&self) -> std::result::Result<String, LinkError> {
+        let secret = self.config.link_secret.as_deref().ok_or(LinkError::NotConfigured)?;
+        resolve_secret(secret).await.map_err(|e| {
+            warn!("Failed to resolve the approval link secret: {}", e);
+            LinkError::NotConfigured
+        })
+    }
+
+    /// Signed approve and deny links of a request, if links are configured
+    pub async fn links(&self, request: &ApprovalRequest) -> Option<ApprovalLinks> {
+        let public_url = self.config.public_url.as_deref()?.trim_end_matches('/');
+        let secret = self.link_secret().await.ok()?;
+        let expires = request.expires_at.timestamp();
+        let link = |action: LinkAction| {
+            format!(
+                "{}/approvals/{}/{}?expires={}&signature={}",
+                public_url, request.id, action.as_str(), expires, sign_link(&secret, &request.id, action, expires)
+            )
+        };
+        Some(ApprovalLinks { approve: link(LinkAction::Approve), deny: link(LinkAction::Deny) })
+    }
+
+    /// Notify every channel of a new request; failures are logged, not returned
+    pub async fn notify(&self, request: &ApprovalRequest) {
+        let links = self.links(request).await;
+        if let Some(slack) = &self.config.slack {
+            if let Err(e) = self.notify_slack(slack, request, links.as_ref()).await {
+                warn!("Failed to notify Slack of approval {}: {}", request.id, e);
+            }
+        }
+        for webhook in &self.config.webhooks {
+            if let Err(e) = self.notify_webhook(webhook, request, links.as_ref()).await {
+                warn!("Failed to notify webhook '{}' of approval {}: {}", webhook.url, request.id, e);
+            }
+        }
+        if let Some(email) = &self.config.email {
+            if let Err(e) = Self::notify_email(email, request, links.as_ref()).await {
+                warn!("Failed to email approval {}: {}", request.id, e);
+            }
+        }
+        debug!("Sent notifications of approval {}", request.id);
+    }
+
+    async fn notify_slack(&self, slack: &SlackNotifierConfig, request: &ApprovalRequest, links: Option<&ApprovalLinks>) -> Result<()> {
+        let text = summary(request);
+        let mut blocks = vec![json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": format!("{}\n```{}```", text, request.arguments) },
+        })];
+        if let Some(links) = links {
+            blocks.push(json!({
+                "type": "actions",
+                "elements": [
+                    { "type": "button", "style": "primary", "text": { "type": "plain_text", "text": "Approve" }, "url": links.approve },
+                    { "type": "button", "style": "danger", "text": { "type": "plain_text", "text": "Deny" }, "url": links.deny },
+                ],
+            }));
+        }
+        let url = resolve_secret(&slack.webhook_url).await?;
+        self.post(&url, &HashMap::new(), &json!({ "text": text, "blocks": blocks })).await
+    }
+
+    async fn notify_webhook(&self, webhook: &WebhookNotifierConfig, request: &ApprovalRequest, links: Option<&ApprovalLinks>) -> Result<()> {
+        let body = json!({
+            "event": "approval_requested",
+            "approval": request,
+            "links": links,
+        });
+        self.post(&webhook.url, &webhook.headers, &body).await
+    }
+
+    async fn post(&self, url: &str, headers: &HashMap<String, String>, body: &serde_json::Value) -> Result<()> {
+        let mut post = self.client.post(url).json(body);
+        for (name, value) in headers {
+            post = post.header(name.as_str(), resolve_secret(value).await?);
+        }
+        post.send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| ProxyError::routing(format!("Notification request failed: {}", e)))?;
+        Ok(())
+    }
+
+    async fn notify_email(email: &EmailNotifierConfig, request: &ApprovalRequest, links: Option<&ApprovalLinks>) -> Result<()> {
+        use lettre::message::{header::ContentType, Mailbox};
+        use lettre::transport::smtp::authentication::Credentials;
+        use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+        let invalid = |e: lettre::address::AddressError| ProxyError::config(format!("Invalid email address: {}", e));
+        let mut message = Message::builder()
+            .from(email.from.parse::<Mailbox>().map_err(invalid)?)
+            .subject(format!("Approval needed: {}", request.tool_name));
+        for to in &email.to {
+            message = message.to(to.parse::<Mailbox>().map_err(invalid)?);
+        }
+        let mut body = format!("{}\n\nArguments:\n{}\n", summary(request), request.arguments);
+        if let Some(links) = links {
+            body.push_str(&format!("\nApprove: {}\nDeny: {}\n", links.approve, links.deny));
+        }
+        let message = message.header(ContentType::TEXT_PLAIN).body(body)
+            .map_err(|e| ProxyError::routing(format!("Failed to build approval email: {}", e)))?;
+
+        let smtp_error = |e: lettre::transport::smtp::Error| ProxyError::routing(format!("SMTP error: {}", e));
+        let mut transport = match email.tls {
+            SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&email.smtp_host).map_err(smtp_error)?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&email.smtp_host).map_err(smtp_error)?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&email.smtp_host),
+        };
+        if let Some(port) = email.smtp_port {
+            transport = transport.port(port);
+        }
+        if let Some(username) = &email.username {
+            let password = match &email.password {
+                Some(password) => resolve_secret(password).await?,
+                None => String::new(),
+            };
+            transport = transport.credentials(Credentials::new(username.clone(), password));
+        }
+        transport.build().send(message).await.map_err(smtp_error)?;
+        Ok(())
+    }
+}
+
+/// One-line description of a request
+fn summary(request: &ApprovalRequest) -> String {
+    let caller = request.user.as_deref().or(request.api_key.as_deref()).unwrap_or("an anonymous caller");
+    format!(
+        "Call to tool '{}' by {} needs approval: {} (expires {})",
+        request.tool_name,
+        caller,
+        request.reason,
+        request.expires_at.format("%Y-%m-%d %H:%M UTC")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn request() -> ApprovalRequest {
+        ApprovalRequest {
+            id: "a1".to_string(),
+            tool_name: "transfer_funds".to_string(),
+            arguments: json!({"amount": 250}),
+            reason: "tool 'transfer_funds' requires approval".to_string(),
+            api_key: None,
+            user: Some("alice".to_string()),
+            requested_at: Utc::now(),
+            expires_at: Utc::now() + chrono::Duration::minutes(15),
+        }
+    }
+
+    #[test]
+    fn test_link_signatures() {
+        let expires = Utc::now().timestamp() + 60;
+        let signature = sign_link("secret", "a1", LinkAction::Approve, expires);
+        assert_eq!(verify_link("secret", "a1", LinkAction::Approve, expires, &signature), Ok(()));
+        assert_eq!(verify_link("secret", "a1", LinkAction::Deny, expires, &signature), Err(LinkError::InvalidSignature));
+        assert_eq!(verify_link("other", "a1", LinkAction::Approve, expires, &signature), Err(LinkError::InvalidSignature));
+        assert_eq!(verify_link("secret", "a1", LinkAction::Approve, expires + 1, &signature), Err(LinkError::InvalidSignature));
+
+        let expired = Utc::now().timestamp() - 1;
+        let signature = sign_link("secret", "a1", LinkAction::Approve, expired);
+        assert_eq!(verify_link("secret", "a1", LinkAction::Approve, expired, &signature), Err(LinkError::Expired));
+    }
+
+    #[tokio::test]
+    async fn test_links() {
+        let notifier = ApprovalNotifier::new(ApprovalNotificationsConfig {
+            public_url: Some("https://tunnel.example.com/".to_string()),
+            link_secret: Some("secret".to_string()),
+            webhooks: vec![WebhookNotifierConfig { url: "https://ops.example.com/hooks".to_string(), headers: HashMap::new() }],
+            ..Default::default()
+        });
+        let request = request();
+        let links = notifier.links(&request).await.unwrap();
+        let expires = request.expires_at.timestamp();
+        assert_eq!(links.approve, format!(
+            "https://tunnel.example.com/approvals/a1/approve?expires={}&signature={}",
+            expires, sign_link("secret", "a1", LinkAction::Approve, expires)
+        ));
+        assert!(links.deny.contains("/approvals/a1/deny?"));
+        assert!(summary(&request).starts_with("Call to tool 'transfer_funds' by alice needs approval"));
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config: ApprovalNotificationsConfig = serde_yaml::from_str("slack: { webhook_url: 'https://hooks.slack.com/x' }").unwrap();
+        assert!(config.validate().is_ok());
+        let no_secret: ApprovalNotificationsConfig = serde_yaml::from_str("public_url: https://a\nslack: { webhook_url: x }").unwrap();
+        assert!(no_secret.validate().is_err());
+        assert!(ApprovalNotificationsConfig::default().validate().is_err());
+    }
+}
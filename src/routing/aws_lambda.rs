@@ -0,0 +1,228 @@
+//! AWS Lambda invocation for `lambda`-routed tools
+//!
+//! Functions are invoked through the Lambda Invoke API, signed with
+//! [`crate::routing::aws_sigv4`]:
+//!
+//! ```yaml
+//! routing:
+//!   type: lambda
+//!   config:
+//!     function_name: image-resizer        # name, ARN or partial ARN
+//!     region: us-east-1                   # defaults to AWS_REGION / AWS_DEFAULT_REGION
+//!     qualifier: prod                     # optional version or alias
+//!     invocation_type: RequestResponse    # or Event (asynchronous)
+//!     payload:                            # optional; defaults to the tool arguments
+//!       source: "{{url}}"
+//!       width: "{{width}}"
+//! ```
+//!
+//! Credentials are resolved like those of SigV4-signed HTTP tools, and an
+//! `endpoint` can point the agent at a Lambda-compatible emulator.
+//!
+//! The Invoke API is called directly rather than through `aws-sdk-lambda`.
+//! HTTP tools and the tools generated from botocore models need the same
+//! signer for services that have no SDK crate here, and it is checked against
+//! the AWS SigV4 test suite. Credentials are secret references rather than the
+//! SDK's provider chain, and the SDK would add its own runtime and HTTP client
+//! for this one request.
+
+use crate::error::{ProxyError, Result};
+use crate::routing::aws_sigv4::{sign_request, AwsSigV4Config};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Invoke API version path
+const INVOKE_API_VERSION: &str = "2015-03-31";
+
+/// How a function is invoked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LambdaInvocationType {
+    /// Wait for the function and return its response
+    #[default]
+    RequestResponse,
+    /// Queue the event and return immediately
+    Event,
+}
+
+impl LambdaInvocationType {
+    /// Parse an `invocation_type` value (`RequestResponse`/`sync` or `Event`/`async`)
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "requestresponse" | "sync" => Ok(Self::RequestResponse),
+            "event" | "async" => Ok(Self::Event),
+            _ => Err(ProxyError::routing(format!(
+                "Invalid Lambda invocation_type '{}': expected RequestResponse or Event", value
+            ))),
+        }
+    }
+
+    fn as_header(&self) -> &'static str {
+        match self {
+            Self::RequestResponse => "RequestResponse",
+            Self::Event => "Event",
+        }
+    }
+}
+
+/// Outcome of an invocation
+#[derive(Debug, Clone, PartialEq)]
+pub struct LambdaInvocation {
+    /// HTTP status of the Invoke API call
+    pub status: u16,
+    /// Request ID assigned by Lambda
+    pub request_id: Option<String>,
+    /// Function version that ran
+    pub executed_version: Option<String>,
+    /// Set when the function raised (`Handled` or `Unhandled`)
+    pub function_error: Option<String>,
+    /// Function response (parsed as JSON when possible)
+    pub payload: Value,
+}
+
+impl LambdaInvocation {
+    /// Whether the function ran (or was queued) without error
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status) && self.function_error.is_none()
+    }
+
+    /// Error message for a failed invocation
+    ///
+    /// Function errors carry `errorType`/`errorMessage`; API errors carry `message`.
+    pub fn error_message(&self) -> Option<String> {
+        if self.is_success() {
+            return None;
+        }
+        let message = ["errorMessage", "message", "Message"].iter()
+            .find_map(|key| self.payload.get(key).and_then(|v| v.as_str()))
+            .map(String::from)
+            .unwrap_or_else(|| self.payload.to_string());
+        Some(match (&self.function_error, self.payload.get("errorType").and_then(|v| v.as_str())) {
+            (Some(kind), Some(error_type)) => format!("Lambda function error ({}, {}): {}", kind, error_type, message),
+            (Some(kind), None) => format!("Lambda function error ({}): {}", kind, message),
+            (None, _) => format!("Lambda invocation failed with status {}: {}", self.status, message),
+        })
+    }
+
+    /// Result data returned to the client
+    pub fn to_data(&self) -> Value {
+        match self.status {
+            // Asynchronous invocations return no payload
+            202 => json!({
+                "status": "accepted",
+                "request_id": self.request_id,
+            }),
+            _ => self.payload.clone(),
+        }
+    }
+}
+
+/// Invoke API URL for a function
+///
+/// `function_name` may be an ARN; it is added as a single, escaped path segment.
+pub fn invoke_url(endpoint: Option<&str>, region: &str, function_name: &str, qualifier: Option<&str>) -> Result<url::Url> {
+    let endpoint = endpoint
+        .map(|e| e.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| format!("https://lambda.{}.amazonaws.com", region));
+    let mut url = url::Url::parse(&endpoint)
+        .map_err(|e| ProxyError::routing(format!("Invalid Lambda endpoint '{}': {}", endpoint, e)))?;
+    url.path_segments_mut()
+        .map_err(|_| ProxyError::routing(format!("Invalid Lambda endpoint '{}'", endpoint)))?
+        .pop_if_empty()
+        .extend([INVOKE_API_VERSION, "functions", function_name, "invocations"]);
+    if let Some(qualifier) = qualifier {
+        url.query_pairs_mut().append_pair("Qualifier", qualifier);
+    }
+    Ok(url)
+}
+
+/// Invoke a function with a JSON event
+pub async fn invoke(
+    client: &reqwest::Client,
+    url: &url::Url,
+    invocation_type: LambdaInvocationType,
+    event: &Value,
+    auth: &AwsSigV4Config,
+) -> Result<LambdaInvocation> {
+    let body = serde_json::to_vec(event)?;
+    let headers = vec![
+        ("Content-Type".to_string(), "application/json".to_string()),
+        ("X-Amz-Invocation-Type".to_string(), invocation_type.as_header().to_string()),
+    ];
+    let credentials = auth.credentials().await?;
+    let auth_headers = sign_request("POST", url, &headers, &body, auth, &credentials, chrono::Utc::now())?;
+
+    let mut request = client.post(url.clone()).body(body);
+    for (name, value) in headers.into_iter().chain(auth_headers) {
+        request = request.header(name, value);
+    }
+
+    let response = request.send().await
+        .map_err(|e| ProxyError::routing(format!("Lambda invocation failed: {}", e)))?;
+    let header = |name: &str| response.headers().get(name).and_then(|v| v.to_str().ok()).map(String::from);
+    let status = response.status().as_u16();
+    let request_id = header("x-amzn-requestid");
+    let executed_version = header("x-amz-executed-version");
+    let function_error = header("x-amz-function-error");
+
+    let text = response.text().await
+        .map_err(|e| ProxyError::routing(format!("Failed to read Lambda response: {}", e)))?;
+    let payload = if text.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_str(&text).unwrap_or(Value::String(text))
+    };
+
+    Ok(LambdaInvocation { status, request_id, executed_version, function_error, payload })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invocation(status: u16, function_error: Option<&str>, payload: Value) -> LambdaInvocation {
+        LambdaInvocation {
+            status,
+            request_id: Some("req-1".to_string()),
+            executed_version: None,
+            function_error: function_error.map(String::from),
+            payload,
+        }
+    }
+
+    #[test]
+    fn test_invoke_url() {
+        let url = invoke_url(None, "eu-west-1", "resize", Some("prod")).unwrap();
+        assert_eq!(url.as_str(), "https://lambda.eu-west-1.amazonaws.com/2015-03-31/functions/resize/invocations?Qualifier=prod");
+
+        let url = invoke_url(Some("http://localhost:4566/"), "us-east-1", "arn:aws:lambda:us-east-1:123456789012:function:resize", None).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "http://localhost:4566/2015-03-31/functions/arn:aws:lambda:us-east-1:123456789012:function:resize/invocations"
+        );
+    }
+
+    #[test]
+    fn test_invocation_results() {
+        let ok = invocation(200, None, json!({"width": 100}));
+        assert!(ok.is_success());
+        assert_eq!(ok.to_data(), json!({"width": 100}));
+
+        let queued = invocation(202, None, Value::Null);
+        assert!(queued.is_success());
+        assert_eq!(queued.to_data()["status"], "accepted");
+
+        let failed = invocation(200, Some("Unhandled"), json!({"errorType": "ValueError", "errorMessage": "bad width"}));
+        assert!(!failed.is_success());
+        assert_eq!(failed.error_message().unwrap(), "Lambda function error (Unhandled, ValueError): bad width");
+
+        let denied = invocation(403, None, json!({"message": "not authorized"}));
+        assert_eq!(denied.error_message().unwrap(), "Lambda invocation failed with status 403: not authorized");
+    }
+
+    #[test]
+    fn test_invocation_type_parsing() {
+        assert_eq!(LambdaInvocationType::parse("Event").unwrap(), LambdaInvocationType::Event);
+        assert_eq!(LambdaInvocationType::parse("sync").unwrap(), LambdaInvocationType::RequestResponse);
+        assert!(LambdaInvocationType::parse("DryRun").is_err());
+    }
+}
@@ -0,0 +1,236 @@
+//! Result cache for tools that opt in with a `cache` section
+//!
+//! ```yaml
+//! - name: get_weather
+//!   cache:
+//!     ttl_seconds: 300
+//!     max_entries: 500
+//! ```
+//!
+//! Successful results are cached per tool, keyed by the canonicalized
+//! arguments (object keys sorted), so argument order doesn't matter. Tools
+//! that call upstream with the caller's delegated credentials
+//! (`token_exchange`) are cached per caller as well (see [`current_subject`]).
+//! A call made inside [`bypass`] skips the cache and stores its fresh result.
+
+use crate::registry::{ToolCacheConfig, ToolDefinition};
+use crate::routing::types::AgentResult;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Routing type of a fallback leg that serves the tool's last cached result
+pub const FALLBACK_ROUTING_TYPE: &str = "cache";
+
+/// How long results are kept for a `cache` fallback leg without `max_age_seconds`
+pub const STALE_RESULT_RETENTION_SECS: u64 = 24 * 60 * 60;
+
+tokio::task_local! {
+    static BYPASS: ();
+}
+
+/// Run `future` with the result cache bypassed for its tool calls
+pub async fn bypass<F: Future>(future: F) -> F::Output {
+    BYPASS.scope((), future).await
+}
+
+/// Whether the current call asked to bypass the cache
+pub fn is_bypassed() -> bool {
+    BYPASS.try_with(|_| ()).is_ok()
+}
+
+struct CacheEntry {
+    result: AgentResult,
+    stored_at: Instant,
+    expires_at: Instant,
+}
+
+/// Cached results of all tools, keyed by tool name then by arguments
+#[derive(Default)]
+pub struct ToolResultCache {
+    tools: Mutex<HashMap<String, HashMap<String, CacheEntry>>>,
+}
+
+impl std::fmt::Debug for ToolResultCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolResultCache").finish_non_exhaustive()
+    }
+}
+
+impl ToolResultCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Unexpired result for `tool` called by `subject` with `arguments`
+    pub fn get(&self, tool: &str, subject: Option<&str>, arguments: &Value) -> Option<AgentResult> {
+        let tools = self.tools.lock().ok()?;
+        let entry = tools.get(tool)?.get(&cache_key(subject, arguments))?;
+        (entry.expires_at > Instant::now()).then(|| entry.result.clone())
+    }
+
+    /// Latest result for `tool` called by `subject` with `arguments`, even if expired, and its age
+    pub fn get_stale(&self, tool: &str, subject: Option<&str>, arguments: &Value) -> Option<(AgentResult, Duration)> {
+        let tools = self.tools.lock().ok()?;
+        let entry = tools.get(tool)?.get(&cache_key(subject, arguments))?;
+        Some((entry.result.clone(), entry.stored_at.elapsed()))
+    }
+
+    /// Store a result, evicting expired entries and then the oldest ones to stay within `max_entries`
+    pub fn insert(&self, tool: &str, subject: Option<&str>, arguments: &Value, result: &AgentResult, config: &ToolCacheConfig) {
+        let Ok(mut tools) = self.tools.lock() else {
+            return;
+        };
+        let entries = tools.entry(tool.to_string()).or_default();
+        let key = cache_key(subject, arguments);
+        let now = Instant::now();
+
+        if !entries.contains_key(&key) && entries.len() >= config.max_entries {
+            entries.retain(|_, entry| entry.expires_at > now);
+            while entries.len() >= config.max_entries {
+                let oldest = entries.iter()
+                    .min_by_key(|(_, entry)| entry.stored_at)
+                    .map(|(key, _)| key.clone());
+                match oldest {
+                    Some(oldest) => entries.remove(&oldest),
+                    None => break,
+                };
+            }
+        }
+
+        entries.insert(key, CacheEntry {
+            result: result.clone(),
+            stored_at: now,
+            expires_at: now + Duration::from_secs(config.ttl_seconds),
+        });
+    }
+
+    /// Drop all cached results of a tool
+    pub fn invalidate(&self, tool: &str) {
+        if let Ok(mut tools) = self.tools.lock() {
+            tools.remove(tool);
+        }
+    }
+
+    /// Number of results cached for a tool, including expired ones not yet evicted
+    pub fn len(&self, tool: &str) -> usize {
+        self.tools.lock().map(|tools| tools.get(tool).map_or(0, HashMap::len)).unwrap_or(0)
+    }
+}
+
+/// Record the cache outcome (`hit`, `miss` or `bypass`) in a result's metadata
+pub fn with_cache_status(mut result: AgentResult, status: &str) -> AgentResult {
+    match result.metadata {
+        Some(Value::Object(ref mut metadata)) => {
+            metadata.insert("cache".to_string(), Value::String(status.to_string()));
+        }
+        _ => result.metadata = Some(serde_json::json!({"cache": status})),
+    }
+    result
+}
+
+/// Caller the results of a call to `tool_def` are cached for: `None` for
+/// tools whose results everyone shares, the signed-in user or a hash of the
+/// caller's token for delegated tools, whose results depend on whose
+/// credentials the upstream call was made with
+pub fn current_subject(tool_def: &ToolDefinition) -> Option<String> {
+    let routing = &tool_def.routing;
+    let delegated = std::iter::once(routing)
+        .chain(&routing.fallback)
+        .chain(routing.canary.as_ref().map(|canary| canary.routing.as_ref()))
+        .any(|routing| routing.config.get("token_exchange").is_some());
+    if !delegated {
+        return None;
+    }
+    let subject = crate::routing::cost::current_user()
+        .map(|user| format!("user:{}", user))
+        .or_else(|| crate::auth::subject_token().map(|token| format!("token:{}", hex::encode(Sha256::digest(token)))))
+        .unwrap_or_default();
+    Some(subject)
+}
+
+/// Arguments serialized with object keys sorted, after the subject they were cached for
+fn cache_key(subject: Option<&str>, arguments: &Value) -> String {
+    fn canonicalize(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                // Rebuilt in sorted order, which serialization keeps even with preserve_order
+                Value::Object(keys.into_iter().map(|key| (key.clone(), canonicalize(&map[key]))).collect())
+            }
+            Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+            other => other.clone(),
+        }
+    }
+    match subject {
+        Some(subject) => format!("{}\n{}", subject, canonicalize(arguments)),
+        None => canonicalize(arguments).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result(value: i64) -> AgentResult {
+        AgentResult { success: true, data: Some(json!(value)), error: None, metadata: None }
+    }
+
+    fn config(max_entries: usize) -> ToolCacheConfig {
+        ToolCacheConfig { ttl_seconds: 60, max_entries }
+    }
+
+    #[test]
+    fn test_key_ignores_argument_order() {
+        assert_eq!(
+            cache_key(None, &json!({"b": 1, "a": {"y": 2, "x": [3, {"d": 4, "c": 5}]}})),
+            cache_key(None, &json!({"a": {"x": [3, {"c": 5, "d": 4}], "y": 2}, "b": 1})),
+        );
+        assert_ne!(cache_key(None, &json!({"a": 1})), cache_key(None, &json!({"a": "1"})));
+    }
+
+    #[test]
+    fn test_get_and_evict_oldest() {
+        let cache = ToolResultCache::new();
+        cache.insert("weather", None, &json!({"city": "Oslo"}), &result(1), &config(2));
+        cache.insert("weather", None, &json!({"city": "Lima"}), &result(2), &config(2));
+        assert_eq!(cache.get("weather", None, &json!({"city": "Oslo"})).unwrap().data, Some(json!(1)));
+        assert!(cache.get("search", None, &json!({"city": "Oslo"})).is_none());
+
+        cache.insert("weather", None, &json!({"city": "Pune"}), &result(3), &config(2));
+        assert_eq!(cache.len("weather"), 2);
+        assert!(cache.get("weather", None, &json!({"city": "Oslo"})).is_none());
+        assert!(cache.get("weather", None, &json!({"city": "Pune"})).is_some());
+
+        cache.invalidate("weather");
+        assert_eq!(cache.len("weather"), 0);
+    }
+
+    #[test]
+    fn test_expiry() {
+        let cache = ToolResultCache::new();
+        cache.insert("weather", None, &json!({}), &result(1), &ToolCacheConfig { ttl_seconds: 0, max_entries: 10 });
+        assert!(cache.get("weather", None, &json!({})).is_none());
+    }
+
+    #[test]
+    fn test_get_stale() {
+        let cache = ToolResultCache::new();
+        cache.insert("weather", None, &json!({}), &result(1), &ToolCacheConfig { ttl_seconds: 0, max_entries: 10 });
+        let (stale, age) = cache.get_stale("weather", None, &json!({})).unwrap();
+        assert_eq!(stale.data, Some(json!(1)));
+        assert!(age < Duration::from_secs(1));
+        assert!(cache.get_stale("weather", None, &json!({"city": "Oslo"})).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bypass_scope() {
+        assert!(!is_bypassed());
+        assert!(bypass(async { is_bypassed() }).await);
+    }
+}
@@ -0,0 +1,210 @@
+//! Canary routing: sending a share of a tool's calls to an alternate routing
+//!
+//! A routing with a `canary` section sends `percentage` of its calls to the
+//! canary routing, such as a new backend version, and the rest to itself:
+//!
+//! ```yaml
+//! routing:
+//!   type: http
+//!   config: { method: POST, url: "https://search-v1.internal/query" }
+//!   canary:
+//!     percentage: 10
+//!     routing:
+//!       type: http
+//!       config: { method: POST, url: "https://search-v2.internal/query" }
+//! ```
+//!
+//! The split is deterministic: after any number of calls, the canary has
+//! served its percentage of them, rounded down. Calls, errors and latency are
+//! tracked per variant so the two can be compared before cutting over.
+
+use crate::error::{ProxyError, Result};
+use crate::registry::RoutingConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The `canary` section of a routing config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CanaryConfig {
+    /// Share of calls sent to the canary, from 0 to 100
+    pub percentage: f64,
+    /// Routing that serves the canary's calls
+    pub routing: Box<RoutingConfig>,
+}
+
+impl CanaryConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=100.0).contains(&self.percentage) {
+            return Err(ProxyError::validation(format!(
+                "Canary percentage must be between 0 and 100, got {}", self.percentage
+            )));
+        }
+        if self.routing.canary.is_some() || !self.routing.fallback.is_empty() {
+            return Err(ProxyError::validation("Canary routing cannot have its own canary or fallback"));
+        }
+        self.routing.validate()
+    }
+}
+
+/// Which routing served a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Variant {
+    Primary,
+    Canary,
+}
+
+/// Outcomes of the calls served by one variant
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VariantMetrics {
+    pub calls: u64,
+    pub errors: u64,
+    /// Share of calls that failed, from 0 to 1
+    pub error_rate: f64,
+    pub avg_latency_ms: f64,
+}
+
+impl VariantMetrics {
+    fn record(&mut self, success: bool, latency: Duration) {
+        self.calls += 1;
+        if !success {
+            self.errors += 1;
+        }
+        self.error_rate = self.errors as f64 / self.calls as f64;
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        self.avg_latency_ms += (latency_ms - self.avg_latency_ms) / self.calls as f64;
+    }
+}
+
+/// Metrics of both variants of a tool
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CanaryMetrics {
+    /// Canary percentage when the last call was made
+    pub percentage: f64,
+    pub primary: VariantMetrics,
+    pub canary: VariantMetrics,
+}
+
+#[derive(Default)]
+struct ToolSplit {
+    /// Calls split since the percentage last changed
+    calls: u64,
+    canary_calls: u64,
+    metrics: CanaryMetrics,
+}
+
+/// Per-tool traffic split and variant metrics
+#[derive(Default)]
+pub struct CanarySplitter {
+    tools: Mutex<HashMap<String, ToolSplit>>,
+}
+
+impl std::fmt::Debug for CanarySplitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CanarySplitter").finish_non_exhaustive()
+    }
+}
+
+impl CanarySplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Variant that serves the next call to `tool`
+    pub fn choose(&self, tool: &str, config: &CanaryConfig) -> Variant {
+        let Ok(mut tools) = self.tools.lock() else {
+            return Variant::Primary;
+        };
+        let split = tools.entry(tool.to_string()).or_default();
+        if split.metrics.percentage != config.percentage {
+            split.calls = 0;
+            split.canary_calls = 0;
+            split.metrics.percentage = config.percentage;
+        }
+
+        split.calls += 1;
+        let due = (split.calls as f64 * config.percentage / 100.0).floor() as u64;
+        if due > split.canary_calls {
+            split.canary_calls += 1;
+            Variant::Canary
+        } else {
+            Variant::Primary
+        }
+    }
+
+    /// Record the outcome of a call served by `variant`
+    pub fn record(&self, tool: &str, variant: Variant, success: bool, latency: Duration) {
+        if let Ok(mut tools) = self.tools.lock() {
+            let metrics = &mut tools.entry(tool.to_string()).or_default().metrics;
+            match variant {
+                Variant::Primary => metrics.primary.record(success, latency),
+                Variant::Canary => metrics.canary.record(success, latency),
+            }
+        }
+    }
+
+    /// Metrics of a tool's variants
+    pub fn metrics(&self, tool: &str) -> Option<CanaryMetrics> {
+        self.tools.lock().ok()?.get(tool).map(|split| split.metrics.clone())
+    }
+
+    /// Metrics of every tool with a canary
+    pub fn all_metrics(&self) -> HashMap<String, CanaryMetrics> {
+        self.tools.lock()
+            .map(|tools| tools.iter().map(|(tool, split)| (tool.clone(), split.metrics.clone())).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(percentage: f64) -> CanaryConfig {
+        CanaryConfig {
+            percentage,
+            routing: Box::new(RoutingConfig::new("http".to_string(), json!({"method": "GET", "url": "http://v2"}))),
+        }
+    }
+
+    #[test]
+    fn test_split_matches_percentage() {
+        let splitter = CanarySplitter::new();
+        let variants: Vec<Variant> = (0..100).map(|_| splitter.choose("search", &config(25.0))).collect();
+        assert_eq!(variants.iter().filter(|&&v| v == Variant::Canary).count(), 25);
+        assert_eq!(variants[..4], [Variant::Primary, Variant::Primary, Variant::Primary, Variant::Canary]);
+
+        assert!((0..10).all(|_| splitter.choose("search", &config(0.0)) == Variant::Primary));
+        assert!((0..10).all(|_| splitter.choose("search", &config(100.0)) == Variant::Canary));
+    }
+
+    #[test]
+    fn test_variant_metrics() {
+        let splitter = CanarySplitter::new();
+        splitter.choose("search", &config(50.0));
+        splitter.record("search", Variant::Primary, true, Duration::from_millis(10));
+        splitter.record("search", Variant::Canary, true, Duration::from_millis(20));
+        splitter.record("search", Variant::Canary, false, Duration::from_millis(40));
+
+        let metrics = splitter.metrics("search").unwrap();
+        assert_eq!(metrics.percentage, 50.0);
+        assert_eq!((metrics.primary.calls, metrics.primary.errors), (1, 0));
+        assert_eq!((metrics.canary.calls, metrics.canary.errors), (2, 1));
+        assert_eq!(metrics.canary.error_rate, 0.5);
+        assert!((metrics.canary.avg_latency_ms - 30.0).abs() < 1e-9);
+        assert!(splitter.metrics("other").is_none());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(config(10.0).validate().is_ok());
+        assert!(config(120.0).validate().is_err());
+
+        let mut nested = config(10.0);
+        nested.routing.canary = Some(config(5.0));
+        assert!(nested.validate().is_err());
+    }
+}
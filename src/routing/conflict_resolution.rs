@@ -374,10 +374,20 @@ mod tests {
             routing: RoutingConfig {
                 r#type: "subprocess".to_string(),
                 config: json!({"command": "echo"}),
+                fallback: Vec::new(),
+                canary: None,
             },
             annotations: None,
             hidden: false, // Test tools are visible by default
             enabled: true, // Test tools are enabled by default
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
         };
         (name.to_string(), tool_def, source)
     }
@@ -0,0 +1,411 @@
+//! Costs of tool calls and spend budgets
+//!
+//! Tools declare what a call costs in their capability file; successful calls
+//! are charged to the API key, user and session that made them:
+//!
+//! ```yaml
+//! - name: summarize
+//!   cost: { per_call: 0.01, per_token: 0.00002 }
+//! ```
+//!
+//! Spend can be capped per API key (by key name), user and session:
+//!
+//! ```yaml
+//! costs:
+//!   budgets:
+//!     api_keys:
+//!       ci-bot: 50
+//!     default_api_key: 10      # keys not listed above
+//!     users:
+//!       alice: 25
+//!     default_session: 1
+//!   window_hours: 24           # spend resets every 24 hours (default: never)
+//! ```
+//!
+//! Once a budget is spent, calls to tools with a cost fail with a tool error
+//! until the window resets; tools without a cost keep working.
+
+use crate::error::{ProxyError, Result};
+use crate::registry::ToolCostConfig;
+use crate::routing::rate_limit;
+use crate::routing::types::AgentResult;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+tokio::task_local! {
+    static USER: String;
+    static SESSION: String;
+}
+
+/// Run `future` with its tool calls charged to `user`
+pub async fn as_user<F: Future>(user: Option<String>, future: F) -> F::Output {
+    match user {
+        Some(user) => USER.scope(user, future).await,
+        None => future.await,
+    }
+}
+
+/// Run `future` with its tool calls charged to the session `session_id`
+pub async fn in_session<F: Future>(session_id: Option<String>, future: F) -> F::Output {
+    match session_id {
+        Some(session_id) => SESSION.scope(session_id, future).await,
+        None => future.await,
+    }
+}
+
+/// User the current call is charged to
+pub fn current_user() -> Option<String> {
+    USER.try_with(Clone::clone).ok()
+}
+
+/// Session the current call is charged to
+pub fn current_session() -> Option<String> {
+    SESSION.try_with(Clone::clone).ok()
+}
+
+/// Spend limits, in the unit tool costs are declared in
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Budgets {
+    /// Budgets by API key name
+    #[serde(default)]
+    pub api_keys: HashMap<String, f64>,
+    /// Budget of each API key not listed in `api_keys`
+    #[serde(default)]
+    pub default_api_key: Option<f64>,
+    /// Budgets by user ID
+    #[serde(default)]
+    pub users: HashMap<String, f64>,
+    /// Budget of each user not listed in `users`
+    #[serde(default)]
+    pub default_user: Option<f64>,
+    /// Budget of each session
+    #[serde(default)]
+    pub default_session: Option<f64>,
+}
+
+/// Spend budgets applied by the router
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostsConfig {
+    #[serde(default)]
+    pub budgets: Budgets,
+    /// Hours after which spend starts again from zero (default: never)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_hours: Option<u64>,
+}
+
+impl CostsConfig {
+    pub fn validate(&self) -> Result<()> {
+        let budgets = &self.budgets;
+        let limits = budgets.api_keys.iter().map(|(name, budget)| ("API key", name.as_str(), budget))
+            .chain(budgets.default_api_key.iter().map(|budget| ("API key", "default", budget)))
+            .chain(budgets.users.iter().map(|(name, budget)| ("user", name.as_str(), budget)))
+            .chain(budgets.default_user.iter().map(|budget| ("user", "default", budget)))
+            .chain(budgets.default_session.iter().map(|budget| ("session", "default", budget)));
+        for (kind, name, budget) in limits {
+            if !(budget.is_finite() && *budget >= 0.0) {
+                return Err(ProxyError::config(format!("Budget for {} '{}' cannot be negative", kind, name)));
+            }
+        }
+        if self.window_hours == Some(0) {
+            return Err(ProxyError::config("costs.window_hours must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+/// What spend is accumulated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpendScope {
+    Tool,
+    ApiKey,
+    User,
+    Session,
+}
+
+/// Who a call is charged to
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Spender {
+    pub api_key: Option<String>,
+    pub user: Option<String>,
+    pub session: Option<String>,
+}
+
+impl Spender {
+    /// The API key, user and session of the current call
+    pub fn current() -> Self {
+        Self { api_key: rate_limit::current_client(), user: current_user(), session: current_session() }
+    }
+
+    fn keys(&self) -> impl Iterator<Item = (SpendScope, &str)> {
+        [(SpendScope::ApiKey, &self.api_key), (SpendScope::User, &self.user), (SpendScope::Session, &self.session)]
+            .into_iter()
+            .filter_map(|(scope, key)| key.as_deref().map(|key| (scope, key)))
+    }
+}
+
+/// A call rejected because a budget was spent
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetExceeded {
+    pub scope: SpendScope,
+    /// API key, user or session
+    pub key: String,
+    pub budget: f64,
+    pub spent: f64,
+    /// When the spend resets, if budgets have a window
+    pub resets_in: Option<Duration>,
+}
+
+impl BudgetExceeded {
+    /// Tool error describing the budget that was spent
+    pub fn into_agent_result(self) -> AgentResult {
+        let subject = match self.scope {
+            SpendScope::Tool => "tool",
+            SpendScope::ApiKey => "API key",
+            SpendScope::User => "user",
+            SpendScope::Session => "session",
+        };
+        AgentResult {
+            success: false,
+            data: None,
+            error: Some(format!(
+                "Budget exceeded: {} '{}' has spent {} of its budget of {}",
+                subject, self.key, round(self.spent), self.budget
+            )),
+            metadata: Some(json!({
+                "error_category": "budget_exceeded",
+                "budget": {
+                    "scope": self.scope,
+                    "key": self.key,
+                    "limit": self.budget,
+                    "spent": round(self.spent),
+                    "resets_in_seconds": self.resets_in.map(|resets_in| resets_in.as_secs()),
+                },
+            })),
+        }
+    }
+}
+
+/// Spend accumulated by one tool, API key, user or session
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpendReport {
+    pub scope: SpendScope,
+    pub key: String,
+    pub spent: f64,
+    pub calls: u64,
+    pub tokens: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget: Option<f64>,
+}
+
+struct Spend {
+    amount: f64,
+    calls: u64,
+    tokens: u64,
+    window_started: Instant,
+}
+
+/// Spend of every tool, API key, user and session, checked against budgets
+#[derive(Default)]
+pub struct CostTracker {
+    config: RwLock<CostsConfig>,
+    spend: Mutex<HashMap<(SpendScope, String), Spend>>,
+}
+
+impl std::fmt::Debug for CostTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CostTracker").finish_non_exhaustive()
+    }
+}
+
+impl CostTracker {
+    pub fn new(config: CostsConfig) -> Self {
+        Self { config: RwLock::new(config), spend: Mutex::default() }
+    }
+
+    /// Replace the budgets; spend so far is kept
+    pub fn configure(&self, config: CostsConfig) {
+        if let Ok(mut current) = self.config.write() {
+            *current = config;
+        }
+    }
+
+    fn budget(config: &CostsConfig, scope: SpendScope, key: &str) -> Option<f64> {
+        let budgets = &config.budgets;
+        match scope {
+            SpendScope::Tool => None,
+            SpendScope::ApiKey => budgets.api_keys.get(key).copied().or(budgets.default_api_key),
+            SpendScope::User => budgets.users.get(key).copied().or(budgets.default_user),
+            SpendScope::Session => budgets.default_session,
+        }
+    }
+
+    /// Whether `spender` still has budget left under every budget that applies
+    pub fn check(&self, spender: &Spender) -> std::result::Result<(), BudgetExceeded> {
+        let Ok(config) = self.config.read() else {
+            return Ok(());
+        };
+        let Ok(mut spend) = self.spend.lock() else {
+            return Ok(());
+        };
+        let window = config.window_hours.map(|hours| Duration::from_secs(hours * 3600));
+        let now = Instant::now();
+        for (scope, key) in spender.keys() {
+            let Some(budget) = Self::budget(&config, scope, key) else { continue };
+            let (spent, window_started) = match spend.get_mut(&(scope, key.to_string())) {
+                Some(entry) => {
+                    reset_expired(entry, window, now);
+                    (entry.amount, entry.window_started)
+                }
+                None => (0.0, now),
+            };
+            if spent >= budget {
+                return Err(BudgetExceeded {
+                    scope,
+                    key: key.to_string(),
+                    budget,
+                    spent,
+                    resets_in: window.map(|window| window.saturating_sub(now.duration_since(window_started))),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Charge a successful call of `tool` to `spender`, returning its cost
+    pub fn charge(&self, tool: &str, cost: &ToolCostConfig, tokens: u64, spender: &Spender) -> f64 {
+        let amount = round(cost.per_call + cost.per_token * tokens as f64);
+        let window = self.config.read().ok()
+            .and_then(|config| config.window_hours)
+            .map(|hours| Duration::from_secs(hours * 3600));
+        let Ok(mut spend) = self.spend.lock() else {
+            return amount;
+        };
+        let now = Instant::now();
+        for (scope, key) in std::iter::once((SpendScope::Tool, tool)).chain(spender.keys()) {
+            let entry = spend.entry((scope, key.to_string()))
+                .or_insert_with(|| Spend { amount: 0.0, calls: 0, tokens: 0, window_started: now });
+            reset_expired(entry, window, now);
+            entry.amount += amount;
+            entry.calls += 1;
+            entry.tokens += tokens;
+        }
+        amount
+    }
+
+    /// Drop the spend of a session that has ended
+    pub fn forget_session(&self, session_id: &str) {
+        if let Ok(mut spend) = self.spend.lock() {
+            spend.remove(&(SpendScope::Session, session_id.to_string()));
+        }
+    }
+
+    /// Spend of every tool, API key, user and session charged so far
+    pub fn report(&self) -> Vec<SpendReport> {
+        let config = self.config.read().map(|config| config.clone()).unwrap_or_default();
+        let Ok(spend) = self.spend.lock() else {
+            return Vec::new();
+        };
+        let mut report: Vec<SpendReport> = spend.iter().map(|((scope, key), spend)| SpendReport {
+            scope: *scope,
+            key: key.clone(),
+            spent: round(spend.amount),
+            calls: spend.calls,
+            tokens: spend.tokens,
+            budget: Self::budget(&config, *scope, key),
+        }).collect();
+        report.sort_by(|a, b| (a.scope, &a.key).cmp(&(b.scope, &b.key)));
+        report
+    }
+}
+
+fn reset_expired(spend: &mut Spend, window: Option<Duration>, now: Instant) {
+    if window.map_or(false, |window| now.duration_since(spend.window_started) >= window) {
+        *spend = Spend { amount: 0.0, calls: 0, tokens: 0, window_started: now };
+    }
+}
+
+/// Hide floating point noise from accumulated spend
+fn round(amount: f64) -> f64 {
+    (amount * 1e6).round() / 1e6
+}
+
+/// Tokens a result reports using: `tokens` in its metadata, or the usage
+/// returned by OpenAI, Anthropic or Ollama
+pub fn tokens_used(result: &AgentResult) -> u64 {
+    let u64_at = |value: &Option<Value>, pointer: &str| value.as_ref().and_then(|v| v.pointer(pointer)).and_then(Value::as_u64);
+    u64_at(&result.metadata, "/tokens")
+        .or_else(|| u64_at(&result.data, "/usage/total_tokens"))
+        .or_else(|| {
+            let input = u64_at(&result.data, "/usage/input_tokens");
+            let output = u64_at(&result.data, "/usage/output_tokens");
+            (input.is_some() || output.is_some()).then(|| input.unwrap_or(0) + output.unwrap_or(0))
+        })
+        .or_else(|| {
+            let prompt = u64_at(&result.data, "/prompt_eval_count");
+            let generated = u64_at(&result.data, "/eval_count");
+            (prompt.is_some() || generated.is_some()).then(|| prompt.unwrap_or(0) + generated.unwrap_or(0))
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spender(api_key: &str, session: &str) -> Spender {
+        Spender { api_key: Some(api_key.to_string()), user: None, session: Some(session.to_string()) }
+    }
+
+    #[test]
+    fn test_budget_exceeded_after_spend() {
+        let tracker = CostTracker::new(CostsConfig {
+            budgets: Budgets { default_api_key: Some(1.0), ..Default::default() },
+            window_hours: None,
+        });
+        let cost = ToolCostConfig { per_call: 0.5, per_token: 0.0 };
+        let ci = spender("ci", "s1");
+
+        assert!(tracker.check(&ci).is_ok());
+        assert_eq!(tracker.charge("summarize", &cost, 0, &ci), 0.5);
+        assert!(tracker.check(&ci).is_ok());
+        tracker.charge("summarize", &cost, 0, &ci);
+
+        let exceeded = tracker.check(&ci).unwrap_err();
+        assert_eq!((exceeded.scope, exceeded.key.as_str()), (SpendScope::ApiKey, "ci"));
+        assert!(tracker.check(&spender("dashboard", "s2")).is_ok());
+
+        let result = exceeded.into_agent_result();
+        assert!(!result.success);
+        assert_eq!(result.metadata.unwrap()["error_category"], "budget_exceeded");
+    }
+
+    #[test]
+    fn test_report_accumulates_tokens() {
+        let tracker = CostTracker::default();
+        let cost = ToolCostConfig { per_call: 0.01, per_token: 0.001 };
+        assert_eq!(tracker.charge("summarize", &cost, 100, &spender("ci", "s1")), 0.11);
+        tracker.charge("summarize", &cost, 50, &spender("ci", "s2"));
+
+        let report = tracker.report();
+        let api_key = report.iter().find(|r| r.scope == SpendScope::ApiKey).unwrap();
+        assert_eq!((api_key.spent, api_key.calls, api_key.tokens), (0.17, 2, 150));
+        assert_eq!(report.iter().filter(|r| r.scope == SpendScope::Session).count(), 2);
+
+        tracker.forget_session("s1");
+        assert_eq!(tracker.report().iter().filter(|r| r.scope == SpendScope::Session).count(), 1);
+    }
+
+    #[test]
+    fn test_tokens_used() {
+        let result = |data: Value| AgentResult { success: true, data: Some(data), error: None, metadata: None };
+        assert_eq!(tokens_used(&result(json!({"usage": {"total_tokens": 42}}))), 42);
+        assert_eq!(tokens_used(&result(json!({"usage": {"input_tokens": 10, "output_tokens": 5}}))), 15);
+        assert_eq!(tokens_used(&result(json!({"prompt_eval_count": 7, "eval_count": 3}))), 10);
+        assert_eq!(tokens_used(&result(json!({"answer": "hi"}))), 0);
+    }
+}
@@ -0,0 +1,217 @@
+//! Container execution for `docker`-routed tools
+//!
+//! Commands run through the docker CLI, either in a new throwaway container
+//! (`image`) or in an existing one (`container`):
+//!
+//! ```yaml
+//! routing:
+//!   type: docker
+//!   config:
+//!     image: python:3.12-slim
+//!     command: ["python", "-c", "{{code}}"]   # or a string run with `sh -c`
+//!     env:
+//!       LOG_LEVEL: "{{log_level}}"
+//!     mounts: ["/srv/data:/data:ro"]
+//!     network: none                          # default for new containers
+//!     memory: 256m
+//!     cpus: 0.5
+//!     read_only: true
+//!     timeout: 60
+//! ```
+//!
+//! New containers are removed when the command exits (or times out) and run
+//! without network access and with `no-new-privileges` unless configured otherwise.
+
+use crate::error::{ProxyError, Result};
+use crate::routing::process::CapturedOutput;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Exit status of `docker run` when the daemon fails to create the container
+pub const DOCKER_ERROR_EXIT_CODE: i32 = 125;
+
+/// Command to run in the container
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DockerCommand {
+    /// Argument vector; each item is substituted, no shell is involved
+    Args(Vec<String>),
+    /// Shell command run with `sh -c`; substituted arguments are shell-quoted
+    Shell(String),
+}
+
+/// Container, isolation and docker CLI settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DockerConfig {
+    /// Image to run a new container from
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Existing container to exec into
+    #[serde(default)]
+    pub container: Option<String>,
+    pub command: DockerCommand,
+    #[serde(default)]
+    pub workdir: Option<String>,
+    /// Environment variables; values may be `{{param}}` templates
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Bind mounts (`host_path:container_path[:ro]`), new containers only
+    #[serde(default)]
+    pub mounts: Vec<String>,
+    /// Network mode for new containers (defaults to `none`)
+    #[serde(default)]
+    pub network: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Memory limit for new containers (e.g. `512m`)
+    #[serde(default)]
+    pub memory: Option<String>,
+    /// CPU limit for new containers
+    #[serde(default)]
+    pub cpus: Option<f64>,
+    /// Process limit for new containers
+    #[serde(default)]
+    pub pids_limit: Option<u64>,
+    /// Mount the new container's root filesystem read-only
+    #[serde(default)]
+    pub read_only: bool,
+    /// docker client executable
+    #[serde(default = "default_docker_binary")]
+    pub docker_binary: String,
+}
+
+fn default_docker_binary() -> String {
+    "docker".to_string()
+}
+
+impl DockerConfig {
+    /// Check that exactly one of `image` / `container` is set and that
+    /// container-creation options aren't used with `container`
+    pub fn validate(&self) -> Result<()> {
+        match (&self.image, &self.container) {
+            (Some(_), Some(_)) => Err(ProxyError::routing("Docker agent takes either image or container, not both".to_string())),
+            (None, None) => Err(ProxyError::routing("Docker agent requires image or container".to_string())),
+            (None, Some(_)) if !self.mounts.is_empty() || self.network.is_some() || self.memory.is_some()
+                || self.cpus.is_some() || self.pids_limit.is_some() || self.read_only => Err(ProxyError::routing(
+                "Docker mounts, network and resource limits only apply to new containers (image)".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Arguments for the docker CLI
+    ///
+    /// `name` names new containers so they can be removed if the call times out.
+    pub fn docker_args(&self, name: &str, env: &[(String, String)], command: &[String]) -> Vec<String> {
+        let mut args = Vec::new();
+        match &self.container {
+            Some(_) => args.push("exec".to_string()),
+            None => {
+                args.extend([
+                    "run".to_string(), "--rm".to_string(),
+                    "--name".to_string(), name.to_string(),
+                    "--network".to_string(), self.network.clone().unwrap_or_else(|| "none".to_string()),
+                    "--security-opt".to_string(), "no-new-privileges".to_string(),
+                ]);
+                for mount in &self.mounts {
+                    args.extend(["-v".to_string(), mount.clone()]);
+                }
+                if let Some(memory) = &self.memory {
+                    args.extend(["--memory".to_string(), memory.clone()]);
+                }
+                if let Some(cpus) = self.cpus {
+                    args.extend(["--cpus".to_string(), cpus.to_string()]);
+                }
+                if let Some(pids_limit) = self.pids_limit {
+                    args.extend(["--pids-limit".to_string(), pids_limit.to_string()]);
+                }
+                if self.read_only {
+                    args.push("--read-only".to_string());
+                }
+            }
+        }
+        if let Some(workdir) = &self.workdir {
+            args.extend(["--workdir".to_string(), workdir.clone()]);
+        }
+        if let Some(user) = &self.user {
+            args.extend(["--user".to_string(), user.clone()]);
+        }
+        for (key, value) in env {
+            args.extend(["--env".to_string(), format!("{}={}", key, value)]);
+        }
+        args.push(self.container.clone().or_else(|| self.image.clone()).unwrap_or_default());
+        args.extend(command.iter().cloned());
+        args
+    }
+}
+
+/// Error message for a failed docker run
+pub fn error_message(output: &CapturedOutput) -> Option<String> {
+    match output.exit_code {
+        Some(0) => None,
+        Some(DOCKER_ERROR_EXIT_CODE) => Some(format!("Docker failed: {}", output.stderr.trim())),
+        Some(code) => Some(format!("Container command failed with exit code: {}", code)),
+        None => Some("Container command was terminated by a signal".to_string()),
+    }
+}
+
+/// Force-remove a container started by a call that timed out
+pub async fn remove_container(docker_binary: &str, name: &str) {
+    let result = tokio::process::Command::new(docker_binary)
+        .args(["rm", "--force", name])
+        .output()
+        .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to remove container '{}': {}", name, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(value: serde_json::Value) -> DockerConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_run_args() {
+        let docker = config(json!({
+            "image": "python:3.12-slim",
+            "command": ["python", "-c", "print(1)"],
+            "mounts": ["/srv/data:/data:ro"],
+            "memory": "256m",
+            "read_only": true,
+            "user": "1000"
+        }));
+        docker.validate().unwrap();
+
+        let env = vec![("LOG_LEVEL".to_string(), "debug".to_string())];
+        let args = docker.docker_args("magictunnel-1", &env, &["python".to_string(), "-c".to_string(), "print(1)".to_string()]);
+        assert_eq!(args, vec![
+            "run", "--rm", "--name", "magictunnel-1", "--network", "none",
+            "--security-opt", "no-new-privileges",
+            "-v", "/srv/data:/data:ro", "--memory", "256m", "--read-only",
+            "--user", "1000", "--env", "LOG_LEVEL=debug",
+            "python:3.12-slim", "python", "-c", "print(1)",
+        ]);
+    }
+
+    #[test]
+    fn test_exec_args() {
+        let docker = config(json!({"container": "sandbox", "command": "ls {{path}}", "workdir": "/work"}));
+        docker.validate().unwrap();
+        assert_eq!(docker.command, DockerCommand::Shell("ls {{path}}".to_string()));
+
+        let args = docker.docker_args("unused", &[], &["ls".to_string()]);
+        assert_eq!(args, vec!["exec", "--workdir", "/work", "sandbox", "ls"]);
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(config(json!({"command": ["ls"]})).validate().is_err());
+        assert!(config(json!({"image": "alpine", "container": "sandbox", "command": ["ls"]})).validate().is_err());
+        assert!(config(json!({"container": "sandbox", "command": ["ls"], "network": "host"})).validate().is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! Dry-run tool calls
+//!
+//! A call made inside [`dry_run`] (`_meta.dryRun: true` on `tools/call`) goes
+//! through argument validation, load balancing, request transforms and
+//! parameter substitution like any other, but instead of being dispatched it
+//! returns what would have been executed: the command and arguments, the URL,
+//! headers and body, the query, and so on. Tools annotated `destructiveHint`
+//! can be forced to dry-run for every caller:
+//!
+//! ```yaml
+//! dry_run:
+//!   destructive_tools: true
+//! ```
+//!
+//! Credentials in headers and LLM API keys are redacted from the preview.
+
+use crate::config::secrets::redact_secret;
+use crate::error::Result;
+use crate::mcp::ToolCall;
+use crate::routing::substitution::{
+    expand_optional_args, http_request_body, substitute_headers, substitute_http_url,
+    substitute_json_value, substitute_parameter_string, substitute_parameters, substitute_shell_command,
+};
+use crate::routing::types::{AgentResult, AgentType};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+
+tokio::task_local! {
+    static DRY_RUN: ();
+}
+
+/// Run `future` with its tool calls previewed instead of dispatched
+pub async fn dry_run<F: Future>(future: F) -> F::Output {
+    DRY_RUN.scope((), future).await
+}
+
+/// Whether the current call asked for a dry run
+pub fn is_dry_run() -> bool {
+    DRY_RUN.try_with(|_| ()).is_ok()
+}
+
+/// Server-wide dry-run policy
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DryRunConfig {
+    /// Never dispatch tools annotated `destructiveHint`; every call is a dry run
+    pub destructive_tools: bool,
+}
+
+/// What a call to `agent` would execute, with the call's arguments substituted
+pub fn preview(tool_call: &ToolCall, agent: &AgentType) -> Result<Value> {
+    let arguments = &tool_call.arguments;
+    let preview = match agent {
+        AgentType::Subprocess { command, args, env, optional_args, .. } => {
+            let mut args = substitute_parameters(args, arguments)?;
+            args.extend(expand_optional_args(optional_args, arguments)?);
+            json!({
+                "command": command,
+                "args": args,
+                "env": env.as_ref().map(|env| env.keys().collect::<Vec<_>>()),
+            })
+        }
+        AgentType::Http { method, url, headers, params, .. } => {
+            let has_body = matches!(method.to_uppercase().as_str(), "POST" | "PUT" | "PATCH");
+            let headers = substitute_headers(headers, arguments)?;
+            let body = if has_body {
+                let content_type = headers.iter().flatten()
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+                    .map_or("application/json", |(_, value)| value.as_str());
+                let body = http_request_body(params, arguments, content_type)?;
+                serde_json::from_slice(&body).unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned()))
+            } else {
+                Value::Null
+            };
+            json!({
+                "method": method.to_uppercase(),
+                "url": substitute_http_url(url, params, arguments)?,
+                "headers": redact_headers(headers),
+                "body": body,
+            })
+        }
+        AgentType::Lambda { function_name, qualifier, invocation_type, payload, .. } => json!({
+            "function_name": function_name,
+            "qualifier": qualifier,
+            "invocation_type": invocation_type,
+            "payload": substitute_payload(payload.as_ref(), arguments)?,
+        }),
+        AgentType::Llm { provider, model, base_url, .. } => json!({
+            "provider": provider,
+            "model": model,
+            "base_url": base_url,
+            "arguments": arguments,
+        }),
+        AgentType::WebSocket { url, headers } => json!({
+            "url": substitute_parameter_string(url, arguments)?,
+            "headers": redact_headers(substitute_headers(headers, arguments)?),
+            "message": arguments,
+        }),
+        AgentType::Database { db_type, query, params, read_only, .. } => {
+            // Parameterized queries are sent as-is with their bind values
+            let (query, bind_values) = if params.is_empty() {
+                (substitute_parameter_string(query, arguments)?, Vec::new())
+            } else {
+                let bind_values = params.iter().map(|name| arguments.get(name).cloned().unwrap_or(Value::Null)).collect();
+                (query.clone(), bind_values)
+            };
+            json!({"db_type": db_type, "query": query, "params": bind_values, "read_only": read_only})
+        }
+        AgentType::Kafka { brokers, topic, partition, key, payload, headers, reply, .. } => json!({
+            "brokers": brokers,
+            "topic": substitute_parameter_string(topic, arguments)?,
+            "partition": partition,
+            "key": key.as_deref().map(|key| substitute_parameter_string(key, arguments)).transpose()?,
+            "value": substitute_payload(payload.as_ref(), arguments)?,
+            "headers": redact_headers(substitute_headers(&Some(headers.clone()), arguments)?),
+            "reply_topic": reply.as_ref().map(|reply| &reply.topic),
+        }),
+        AgentType::Amqp { exchange, routing_key, payload, headers, await_reply, .. } => json!({
+            "exchange": exchange,
+            "routing_key": substitute_parameter_string(routing_key, arguments)?,
+            "body": substitute_payload(payload.as_ref(), arguments)?,
+            "headers": redact_headers(substitute_headers(&Some(headers.clone()), arguments)?),
+            "await_reply": await_reply,
+        }),
+        AgentType::Ssh { command, connection, .. } => json!({
+            "host": substitute_parameter_string(&connection.host, arguments)?,
+            "user": connection.user,
+            "command": substitute_shell_command(command, arguments)?,
+        }),
+        AgentType::Grpc { endpoint, service, method, headers, request_body, .. } => json!({
+            "endpoint": endpoint,
+            "service": service,
+            "method": method,
+            "headers": redact_headers(substitute_headers(headers, arguments)?),
+            "request": match request_body {
+                Some(template) => json!(substitute_parameter_string(template, arguments)?),
+                None => arguments.clone(),
+            },
+        }),
+        AgentType::Sse { url, headers, .. } => json!({
+            "url": substitute_parameter_string(url, arguments)?,
+            "headers": redact_headers(substitute_headers(headers, arguments)?),
+        }),
+        AgentType::GraphQL { endpoint, query, variables, headers, operation_name, .. } => json!({
+            "endpoint": endpoint,
+            "query": query,
+            "operation_name": operation_name,
+            "variables": match variables {
+                Some(template) => substitute_json_value(template, arguments)?,
+                None => arguments.clone(),
+            },
+            "headers": redact_headers(substitute_headers(headers, arguments)?),
+        }),
+        AgentType::Webhook { config, .. } => {
+            // The correlation ID is only assigned when the job is submitted
+            let parameters = config.template_parameters(arguments, "{{correlation_id}}");
+            json!({
+                "method": config.method.to_uppercase(),
+                "url": substitute_parameter_string(&config.url, &parameters)?,
+                "headers": redact_headers(substitute_headers(&Some(config.headers.clone()), &parameters)?),
+                "body": substitute_payload(config.payload.as_ref(), &parameters)?,
+                "callback_url": config.callback_url("{{correlation_id}}"),
+            })
+        }
+        AgentType::ExternalMcp { server_name, tool_name, .. } => json!({
+            "server_name": server_name,
+            "tool_name": tool_name,
+            "arguments": arguments,
+        }),
+        // Agents whose templating happens inside their own runtime
+        AgentType::Docker { .. }
+        | AgentType::KubernetesJob { .. }
+        | AgentType::Wasm { .. }
+        | AgentType::Script { .. }
+        | AgentType::SmartDiscovery { .. } => json!({
+            "config": serde_json::to_value(agent)?,
+            "arguments": arguments,
+        }),
+    };
+
+    let mut preview = preview;
+    preview["type"] = json!(agent.type_name());
+    Ok(preview)
+}
+
+/// Result returned in place of dispatching a call
+pub fn dry_run_result(tool_call: &ToolCall, would_execute: Vec<Value>) -> AgentResult {
+    AgentResult {
+        success: true,
+        data: Some(json!({
+            "dry_run": true,
+            "tool_name": tool_call.name,
+            "would_execute": would_execute,
+        })),
+        error: None,
+        metadata: Some(json!({
+            "tool_name": tool_call.name,
+            "dry_run": true,
+        })),
+    }
+}
+
+fn substitute_payload(payload: Option<&Value>, arguments: &Value) -> Result<Value> {
+    match payload {
+        Some(template) => substitute_json_value(template, arguments),
+        None => Ok(arguments.clone()),
+    }
+}
+
+fn redact_headers(headers: Option<HashMap<String, String>>) -> Option<HashMap<String, String>> {
+    headers.map(|headers| headers.into_iter().map(|(name, value)| {
+        let lower = name.to_lowercase();
+        let sensitive = ["authorization", "cookie", "token", "key", "secret", "password"]
+            .iter()
+            .any(|marker| lower.contains(marker));
+        let value = if sensitive { redact_secret(&value) } else { value };
+        (name, value)
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_substitutes_arguments() {
+        let call = ToolCall::new("delete_branch".to_string(), json!({"branch": "feature/x", "force": true}));
+        let agent = AgentType::Subprocess {
+            command: "git".to_string(),
+            args: vec!["branch".to_string(), "-d".to_string(), "{{branch}}".to_string()],
+            timeout: None,
+            env: None,
+            optional_args: vec![],
+        };
+        let preview = preview(&call, &agent).unwrap();
+        assert_eq!(preview["type"], "subprocess");
+        assert_eq!(preview["args"], json!(["branch", "-d", "feature/x"]));
+
+        let agent = AgentType::Http {
+            method: "delete".to_string(),
+            url: "https://git.example.com/branches/{{branch}}".to_string(),
+            headers: Some([
+                ("Authorization".to_string(), "Bearer abc".to_string()),
+                ("X-Branch".to_string(), "{{branch}}".to_string()),
+            ].into_iter().collect()),
+            timeout: None,
+            aws_sigv4: None,
+            token_exchange: None,
+            params: Default::default(),
+        };
+        let preview = preview(&call, &agent).unwrap();
+        assert_eq!(preview["method"], "DELETE");
+        assert_eq!(preview["url"], "https://git.example.com/branches/feature/x");
+        assert_ne!(preview["headers"]["Authorization"], "Bearer abc");
+        assert_eq!(preview["headers"]["X-Branch"], "feature/x");
+        assert!(preview["body"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_scope() {
+        assert!(!is_dry_run());
+        assert!(dry_run(async { is_dry_run() }).await);
+    }
+}
@@ -10,7 +10,7 @@ use crate::routing::timeout::TimeoutConfig;
 use crate::routing::types::{AgentResult, AgentType};
 use async_trait::async_trait;
 use std::sync::Arc;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 /// Enhanced agent router that supports middleware, retry logic, and centralized timeout configuration
 pub struct EnhancedAgentRouter {
@@ -127,153 +127,14 @@ impl EnhancedAgentRouter {
         &self.timeout_config
     }
 
-    /// Apply centralized timeout configuration to an agent type
-    /// Priority: existing_timeout (tool override) > centralized_config > default
-    fn apply_timeout_config(&self, agent: &AgentType) -> AgentType {
-
-        match agent {
-            AgentType::Subprocess { command, args, timeout, env } => {
-                let final_timeout = if timeout.is_some() {
-                    *timeout // Keep existing timeout (tool override)
-                } else {
-                    Some(self.timeout_config.get_timeout_secs("subprocess", None))
-                };
-
-                AgentType::Subprocess {
-                    command: command.clone(),
-                    args: args.clone(),
-                    timeout: final_timeout,
-                    env: env.clone(),
-                }
-            }
-
-            AgentType::Http { method, url, headers, timeout } => {
-                let final_timeout = if timeout.is_some() {
-                    *timeout // Keep existing timeout (tool override)
-                } else {
-                    Some(self.timeout_config.get_timeout_secs("http", None))
-                };
-
-                AgentType::Http {
-                    method: method.clone(),
-                    url: url.clone(),
-                    headers: headers.clone(),
-                    timeout: final_timeout,
-                }
-            }
-
-            AgentType::Llm { provider, model, api_key, base_url, timeout } => {
-                let final_timeout = if timeout.is_some() {
-                    *timeout // Keep existing timeout (tool override)
-                } else {
-                    Some(self.timeout_config.get_timeout_secs("llm", None))
-                };
-
-                AgentType::Llm {
-                    provider: provider.clone(),
-                    model: model.clone(),
-                    api_key: api_key.clone(),
-                    base_url: base_url.clone(),
-                    timeout: final_timeout,
-                }
-            }
-
-            AgentType::WebSocket { url, headers } => {
-                // WebSocket doesn't have timeout field in the enum, but we could add it
-                // For now, keep as-is since WebSocket uses hardcoded timeouts
-                AgentType::WebSocket {
-                    url: url.clone(),
-                    headers: headers.clone(),
-                }
-            }
-
-            AgentType::Database { db_type, connection_string, query, timeout } => {
-                let final_timeout = if timeout.is_some() {
-                    *timeout // Keep existing timeout (tool override)
-                } else {
-                    Some(self.timeout_config.get_timeout_secs("database", None))
-                };
-
-                AgentType::Database {
-                    db_type: db_type.clone(),
-                    connection_string: connection_string.clone(),
-                    query: query.clone(),
-                    timeout: final_timeout,
-                }
-            }
-
-            AgentType::Grpc { endpoint, service, method, headers, timeout, request_body } => {
-                let final_timeout = if timeout.is_some() {
-                    *timeout // Keep existing timeout (tool override)
-                } else {
-                    Some(self.timeout_config.get_timeout_secs("grpc", None))
-                };
-
-                AgentType::Grpc {
-                    endpoint: endpoint.clone(),
-                    service: service.clone(),
-                    method: method.clone(),
-                    headers: headers.clone(),
-                    timeout: final_timeout,
-                    request_body: request_body.clone(),
-                }
-            }
-
-            AgentType::Sse { url, headers, timeout, max_events, event_filter } => {
-                let final_timeout = if timeout.is_some() {
-                    *timeout // Keep existing timeout (tool override)
-                } else {
-                    Some(self.timeout_config.get_timeout_secs("sse", None))
-                };
-
-                AgentType::Sse {
-                    url: url.clone(),
-                    headers: headers.clone(),
-                    timeout: final_timeout,
-                    max_events: *max_events,
-                    event_filter: event_filter.clone(),
-                }
-            }
-
-            AgentType::GraphQL { endpoint, query, variables, headers, timeout, operation_name } => {
-                let final_timeout = if timeout.is_some() {
-                    *timeout // Keep existing timeout (tool override)
-                } else {
-                    Some(self.timeout_config.get_timeout_secs("graphql", None))
-                };
-
-                AgentType::GraphQL {
-                    endpoint: endpoint.clone(),
-                    query: query.clone(),
-                    variables: variables.clone(),
-                    headers: headers.clone(),
-                    timeout: final_timeout,
-                    operation_name: operation_name.clone(),
-                }
-            }
-
-            AgentType::ExternalMcp { server_name, tool_name, timeout, mapping_metadata } => {
-                let final_timeout = if timeout.is_some() {
-                    *timeout // Keep existing timeout (tool override)
-                } else {
-                    Some(self.timeout_config.get_timeout_secs("external_mcp", None))
-                };
-
-                AgentType::ExternalMcp {
-                    server_name: server_name.clone(),
-                    tool_name: tool_name.clone(),
-                    timeout: final_timeout,
-                    mapping_metadata: mapping_metadata.clone(),
-                }
-            }
-
-            AgentType::SmartDiscovery { enabled } => {
-                // SmartDiscovery doesn't have timeout field, so keep as-is
-                AgentType::SmartDiscovery {
-                    enabled: *enabled,
-                }
-            }
+    /// Apply the centralized timeout hierarchy to an agent
+    /// Priority: call override > per-tool config > existing timeout (tool override) > agent type > default
+    fn apply_timeout_config(&self, tool_name: &str, agent: &AgentType) -> AgentType {
+        let mut agent = agent.clone();
+        if let Some(timeout) = self.timeout_config.apply(tool_name, &mut agent) {
+            debug!(tool_name, timeout_secs = timeout.seconds, source = ?timeout.source, "Resolved agent timeout");
         }
+        agent
     }
 }
 
@@ -302,18 +163,19 @@ impl AgentRouter for EnhancedAgentRouter {
             "Enhanced router: Starting execution with middleware and retry logic"
         );
 
-        // Execute before_execution middleware
+        // Execute before_execution middleware; an error rejects the call,
+        // e.g. when a circuit breaker is open
         if let Err(e) = self.middleware.before_execution(&context).await {
-            error!(
+            warn!(
                 execution_id = %context.execution_id,
                 error = %e,
-                "Middleware before_execution failed"
+                "Middleware before_execution rejected the call"
             );
-            // Continue execution even if middleware fails
+            return Err(e);
         }
 
         // Apply centralized timeout configuration to the agent
-        let agent_with_timeout = self.apply_timeout_config(agent);
+        let agent_with_timeout = self.apply_timeout_config(&tool_call.name, agent);
 
         // Execute the actual agent with retry logic and centralized timeout
         let result = self.retry_executor.execute_with_retry(
@@ -411,6 +273,25 @@ impl EnhancedRouterBuilder {
         self
     }
 
+    /// Add middleware to the builder at `priority` instead of its own
+    pub fn add_middleware_with_priority(mut self, middleware: Arc<dyn crate::routing::middleware::RouterMiddleware>, priority: i32) -> Self {
+        self.middleware = self.middleware.add_middleware_with_priority(middleware, priority);
+        self
+    }
+
+    /// Add the middleware of a plugin registered with
+    /// [`register_middleware_plugin`](crate::routing::middleware::register_middleware_plugin)
+    pub fn with_plugin(self, name: &str, config: &serde_json::Value) -> Result<Self> {
+        let middleware = crate::routing::middleware::middleware_plugin(name, config)?;
+        Ok(self.add_middleware(middleware))
+    }
+
+    /// Remove middleware added earlier, by name
+    pub fn remove_middleware(mut self, name: &str) -> Self {
+        self.middleware = self.middleware.remove_middleware(name);
+        self
+    }
+
     /// Add logging middleware with default settings
     pub fn with_logging(self) -> Self {
         self.add_middleware(Arc::new(crate::routing::middleware::LoggingMiddleware::new()))
@@ -426,6 +307,11 @@ impl EnhancedRouterBuilder {
         self.add_middleware(Arc::new(crate::routing::middleware::MetricsMiddleware::new()))
     }
 
+    /// Add circuit breaker middleware with the given default settings
+    pub fn with_circuit_breaker(self, config: crate::config::CircuitBreakerConfig) -> Self {
+        self.add_middleware(Arc::new(crate::routing::middleware::CircuitBreakerMiddleware::with_config(config)))
+    }
+
     /// Set retry configuration
     pub fn with_retry_config(mut self, retry_config: RetryConfig) -> Self {
         self.retry_config = Some(retry_config);
@@ -519,6 +405,7 @@ mod tests {
                 max_delay_ms: 2000,
                 backoff_multiplier: 2.0,
                 use_jitter: true,
+                max_elapsed_ms: None,
             },
         );
 
@@ -529,6 +416,7 @@ mod tests {
                 max_delay_ms: 1000,
                 backoff_multiplier: 1.5,
                 use_jitter: false,
+                max_elapsed_ms: None,
             },
             per_agent_type,
         };
@@ -556,6 +444,7 @@ mod tests {
         let timeout_config = TimeoutConfig {
             default_timeout_secs: 40,
             per_agent_type,
+            per_tool: HashMap::new(),
             max_timeout_secs: 600,
         };
 
@@ -609,6 +498,7 @@ mod tests {
         let timeout_config = TimeoutConfig {
             default_timeout_secs: 40,
             per_agent_type,
+            per_tool: HashMap::new(),
             max_timeout_secs: 600,
         };
 
@@ -622,9 +512,10 @@ mod tests {
             args: vec!["test".to_string()],
             timeout: None,
             env: None,
+            optional_args: Vec::new(),
         };
 
-        let modified_agent = router.apply_timeout_config(&subprocess_agent);
+        let modified_agent = router.apply_timeout_config("echo_tool", &subprocess_agent);
         match modified_agent {
             AgentType::Subprocess { timeout, .. } => {
                 assert_eq!(timeout, Some(45)); // Should use configured timeout
@@ -638,9 +529,10 @@ mod tests {
             args: vec!["test".to_string()],
             timeout: Some(99), // Tool override
             env: None,
+            optional_args: Vec::new(),
         };
 
-        let modified_agent = router.apply_timeout_config(&subprocess_agent_with_timeout);
+        let modified_agent = router.apply_timeout_config("echo_tool", &subprocess_agent_with_timeout);
         match modified_agent {
             AgentType::Subprocess { timeout, .. } => {
                 assert_eq!(timeout, Some(99)); // Should preserve existing timeout
@@ -654,9 +546,12 @@ mod tests {
             url: "http://example.com".to_string(),
             headers: None,
             timeout: None,
+            aws_sigv4: None,
+            token_exchange: None,
+            params: Default::default(),
         };
 
-        let modified_agent = router.apply_timeout_config(&http_agent);
+        let modified_agent = router.apply_timeout_config("fetch_tool", &http_agent);
         match modified_agent {
             AgentType::Http { timeout, .. } => {
                 assert_eq!(timeout, Some(60)); // Should use configured timeout
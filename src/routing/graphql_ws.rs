@@ -0,0 +1,253 @@
+//! GraphQL subscriptions over WebSocket for `graphql`-routed tools
+//!
+//! Subscription operations are run with the `graphql-transport-ws` protocol
+//! (the protocol of the `graphql-ws` library). Each event is forwarded as a
+//! progress notification while the subscription runs, and all events are
+//! returned once it ends:
+//!
+//! ```yaml
+//! routing:
+//!   type: graphql
+//!   config:
+//!     endpoint: https://api.example.com/graphql   # http(s) is switched to ws(s)
+//!     query: "subscription($channel: ID!) { messageAdded(channel: $channel) { id text } }"
+//!     subscription:
+//!       max_events: 10          # stop after this many events
+//!       max_duration_secs: 60   # or after this long
+//!       init_payload:           # optional connection_init payload
+//!         authToken: "{{token}}"
+//! ```
+
+use crate::error::{ProxyError, Result};
+use crate::mcp::progress::ProgressReporter;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::time::{timeout_at, Duration, Instant};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+/// WebSocket subprotocol of `graphql-transport-ws`
+pub const SUBPROTOCOL: &str = "graphql-transport-ws";
+
+/// Id of the single operation run per connection
+const OPERATION_ID: &str = "1";
+
+fn default_max_events() -> usize {
+    10
+}
+
+fn default_max_duration_secs() -> u64 {
+    60
+}
+
+/// When to stop listening, and how to initialise the connection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionConfig {
+    /// Stop after this many events
+    #[serde(default = "default_max_events")]
+    pub max_events: usize,
+    /// Stop after this many seconds, including connecting
+    #[serde(default = "default_max_duration_secs")]
+    pub max_duration_secs: u64,
+    /// `connection_init` payload; `{{param}}` placeholders are substituted
+    #[serde(default)]
+    pub init_payload: Option<Value>,
+}
+
+impl Default for SubscriptionConfig {
+    fn default() -> Self {
+        Self {
+            max_events: default_max_events(),
+            max_duration_secs: default_max_duration_secs(),
+            init_payload: None,
+        }
+    }
+}
+
+/// A subscription operation to run
+#[derive(Debug, Clone)]
+pub struct SubscriptionRequest<'a> {
+    /// GraphQL endpoint; `http(s)` URLs are switched to `ws(s)`
+    pub endpoint: &'a str,
+    pub query: &'a str,
+    pub variables: Option<&'a Value>,
+    pub operation_name: Option<&'a str>,
+    /// Headers sent with the WebSocket upgrade request
+    pub headers: Option<&'a HashMap<String, String>>,
+    pub init_payload: Option<&'a Value>,
+}
+
+/// Why a subscription stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionEnd {
+    /// The server completed the subscription
+    Completed,
+    MaxEvents,
+    MaxDuration,
+}
+
+/// Events received before the subscription stopped
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriptionOutcome {
+    /// `next` payloads (`{"data": ..., "errors": ...}`) in the order received
+    pub events: Vec<Value>,
+    pub end: SubscriptionEnd,
+}
+
+/// Whether a GraphQL document's operation is a subscription
+pub fn is_subscription(query: &str) -> bool {
+    let document = query
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let keyword: String = document.trim_start().chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    keyword == "subscription"
+}
+
+/// WebSocket URL for a GraphQL endpoint
+pub fn websocket_url(endpoint: &str) -> Result<String> {
+    let mut url = url::Url::parse(endpoint)
+        .map_err(|e| ProxyError::routing(format!("Invalid GraphQL endpoint '{}': {}", endpoint, e)))?;
+    let scheme = match url.scheme() {
+        "http" | "ws" => "ws",
+        "https" | "wss" => "wss",
+        other => return Err(ProxyError::routing(format!("Unsupported GraphQL endpoint scheme: {}", other))),
+    };
+    url.set_scheme(scheme)
+        .map_err(|_| ProxyError::routing(format!("Invalid GraphQL endpoint '{}'", endpoint)))?;
+    Ok(url.to_string())
+}
+
+/// Run a subscription until the server completes it or a limit is reached
+///
+/// Each event is also sent to `progress`, if given. GraphQL errors reported
+/// with an `error` message, and the connection closing before the
+/// subscription completed, are errors.
+pub async fn subscribe(
+    request: &SubscriptionRequest<'_>,
+    config: &SubscriptionConfig,
+    progress: Option<&ProgressReporter>,
+) -> Result<SubscriptionOutcome> {
+    let deadline = Instant::now() + Duration::from_secs(config.max_duration_secs);
+
+    let mut ws_request = websocket_url(request.endpoint)?
+        .into_client_request()
+        .map_err(|e| ProxyError::routing(format!("Invalid GraphQL WebSocket request: {}", e)))?;
+    let headers = ws_request.headers_mut();
+    headers.insert("Sec-WebSocket-Protocol", HeaderValue::from_static(SUBPROTOCOL));
+    for (name, value) in request.headers.into_iter().flatten() {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| ProxyError::routing(format!("Invalid header name '{}': {}", name, e)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| ProxyError::routing(format!("Invalid value for header '{}': {}", name, e)))?;
+        headers.insert(name, value);
+    }
+
+    let (stream, _) = match timeout_at(deadline, tokio_tungstenite::connect_async(ws_request)).await {
+        Ok(connected) => connected
+            .map_err(|e| ProxyError::routing(format!("GraphQL WebSocket connection failed: {}", e)))?,
+        Err(_) => return Ok(SubscriptionOutcome { events: Vec::new(), end: SubscriptionEnd::MaxDuration }),
+    };
+    let (mut write, mut read) = stream.split();
+    let send_error = |e| ProxyError::routing(format!("Failed to send GraphQL WebSocket message: {}", e));
+
+    let mut init = json!({"type": "connection_init"});
+    if let Some(payload) = request.init_payload {
+        init["payload"] = payload.clone();
+    }
+    write.send(Message::Text(init.to_string())).await.map_err(send_error)?;
+
+    let mut acknowledged = false;
+    let mut events = Vec::new();
+    let end = loop {
+        let message = match timeout_at(deadline, read.next()).await {
+            Ok(Some(Ok(message))) => message,
+            Ok(Some(Err(e))) => return Err(ProxyError::routing(format!("GraphQL WebSocket error: {}", e))),
+            Ok(None) => return Err(ProxyError::routing("GraphQL WebSocket closed before the subscription completed".to_string())),
+            Err(_) => break SubscriptionEnd::MaxDuration,
+        };
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(frame) => {
+                let reason = frame.map(|f| format!("{} {}", u16::from(f.code), f.reason)).unwrap_or_default();
+                return Err(ProxyError::routing(format!(
+                    "GraphQL WebSocket closed before the subscription completed: {}", reason.trim()
+                )));
+            }
+            // Pings are answered by tungstenite; binary frames aren't part of the protocol
+            _ => continue,
+        };
+        let message: Value = serde_json::from_str(&text)
+            .map_err(|e| ProxyError::routing(format!("Invalid GraphQL WebSocket message: {}", e)))?;
+
+        match message.get("type").and_then(|t| t.as_str()).unwrap_or("") {
+            "connection_ack" if !acknowledged => {
+                acknowledged = true;
+                let mut payload = json!({"query": request.query});
+                if let Some(variables) = request.variables {
+                    payload["variables"] = variables.clone();
+                }
+                if let Some(operation_name) = request.operation_name {
+                    payload["operationName"] = json!(operation_name);
+                }
+                let subscribe = json!({"id": OPERATION_ID, "type": "subscribe", "payload": payload});
+                write.send(Message::Text(subscribe.to_string())).await.map_err(send_error)?;
+            }
+            "ping" => {
+                write.send(Message::Text(json!({"type": "pong"}).to_string())).await.map_err(send_error)?;
+            }
+            "next" => {
+                let payload = message.get("payload").cloned().unwrap_or(Value::Null);
+                if let Some(progress) = progress {
+                    progress.report(payload.to_string());
+                }
+                events.push(payload);
+                if events.len() >= config.max_events {
+                    break SubscriptionEnd::MaxEvents;
+                }
+            }
+            "error" => {
+                let errors = message.get("payload").cloned().unwrap_or(Value::Null);
+                return Err(ProxyError::routing(format!("GraphQL subscription failed: {}", errors)));
+            }
+            "complete" => break SubscriptionEnd::Completed,
+            _ => {}
+        }
+    };
+
+    // Stop the operation on the server, then close; both are best-effort
+    if end != SubscriptionEnd::Completed && acknowledged {
+        let complete = json!({"id": OPERATION_ID, "type": "complete"});
+        let _ = write.send(Message::Text(complete.to_string())).await;
+    }
+    let _ = write.send(Message::Close(None)).await;
+
+    Ok(SubscriptionOutcome { events, end })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_subscription() {
+        assert!(is_subscription("subscription { messageAdded { id } }"));
+        assert!(is_subscription("# live updates\n  subscription OnMessage($c: ID!) { messageAdded(channel: $c) { id } }"));
+        assert!(!is_subscription("query { subscriptions { id } }"));
+        assert!(!is_subscription("{ messageAdded { id } }"));
+        assert!(!is_subscription("subscriptionsQuery"));
+    }
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(websocket_url("https://api.example.com/graphql").unwrap(), "wss://api.example.com/graphql");
+        assert_eq!(websocket_url("http://localhost:4000/graphql?x=1").unwrap(), "ws://localhost:4000/graphql?x=1");
+        assert_eq!(websocket_url("ws://localhost/graphql").unwrap(), "ws://localhost/graphql");
+        assert!(websocket_url("ftp://example.com").is_err());
+    }
+}
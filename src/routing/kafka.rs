@@ -0,0 +1,221 @@
+//! Kafka request/reply for `kafka`-routed tools
+//!
+//! Tool calls are published as JSON records to a topic. When a response topic
+//! is configured, the agent waits for the reply carrying the same correlation ID:
+//!
+//! ```yaml
+//! routing:
+//!   type: kafka
+//!   config:
+//!     brokers: ["localhost:9092"]
+//!     topic: orders.requests
+//!     partition: 0                      # optional, defaults to 0
+//!     key: "{{order_id}}"               # optional record key
+//!     payload:                          # optional; defaults to the tool arguments
+//!       order: "{{order_id}}"
+//!     response_topic: orders.replies    # optional; omit for fire-and-forget
+//!     timeout: 30
+//! ```
+//!
+//! Requests carry `correlation_id` and `reply_to` headers; backends copy the
+//! `correlation_id` header onto their reply record.
+//!
+//! The client is built with the `kafka` feature (on by default); without it,
+//! `kafka` routings fail when called.
+
+#[cfg(feature = "kafka")]
+use crate::error::{ProxyError, Result};
+#[cfg(feature = "kafka")]
+use rskafka::client::partition::{Compression, OffsetAt, PartitionClient, UnknownTopicHandling};
+#[cfg(feature = "kafka")]
+use rskafka::client::{Client, ClientBuilder};
+#[cfg(feature = "kafka")]
+use rskafka::record::Record;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "kafka")]
+use serde_json::Value;
+#[cfg(feature = "kafka")]
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "kafka")]
+use std::time::Duration;
+
+/// Header carrying the ID that pairs a request with its reply
+pub const CORRELATION_HEADER: &str = "correlation_id";
+/// Header naming the topic the reply is expected on
+pub const REPLY_TO_HEADER: &str = "reply_to";
+
+/// Maximum bytes fetched per poll of the response topic
+#[cfg(feature = "kafka")]
+const FETCH_MAX_BYTES: i32 = 1_048_576;
+/// How long a poll waits for new records
+#[cfg(feature = "kafka")]
+const FETCH_MAX_WAIT_MS: i32 = 500;
+/// Pause between polls that returned nothing
+#[cfg(feature = "kafka")]
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Where replies are read from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KafkaReplyConfig {
+    pub topic: String,
+    #[serde(default)]
+    pub partition: i32,
+}
+
+/// Build the request record
+///
+/// String values are sent as-is; anything else is serialized as JSON.
+#[cfg(feature = "kafka")]
+pub fn build_record(
+    key: Option<&str>,
+    value: &Value,
+    headers: &HashMap<String, String>,
+    correlation: Option<(&str, &KafkaReplyConfig)>,
+) -> Result<Record> {
+    let value = match value {
+        Value::String(s) => s.clone().into_bytes(),
+        other => serde_json::to_vec(other)?,
+    };
+    let mut record_headers: BTreeMap<String, Vec<u8>> = headers
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone().into_bytes()))
+        .collect();
+    if let Some((correlation_id, reply)) = correlation {
+        record_headers.insert(CORRELATION_HEADER.to_string(), correlation_id.as_bytes().to_vec());
+        record_headers.insert(REPLY_TO_HEADER.to_string(), reply.topic.as_bytes().to_vec());
+    }
+
+    Ok(Record {
+        key: key.map(|k| k.as_bytes().to_vec()),
+        value: Some(value),
+        headers: record_headers,
+        timestamp: chrono::Utc::now(),
+    })
+}
+
+/// Correlation ID of a record, if it has one
+#[cfg(feature = "kafka")]
+pub fn correlation_id(record: &Record) -> Option<&str> {
+    record.headers.get(CORRELATION_HEADER).and_then(|v| std::str::from_utf8(v).ok())
+}
+
+/// Record value parsed as JSON, falling back to a string
+#[cfg(feature = "kafka")]
+pub fn decode_value(record: &Record) -> Value {
+    match record.value.as_deref() {
+        None | Some([]) => Value::Null,
+        Some(bytes) => serde_json::from_slice(bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).into_owned())),
+    }
+}
+
+/// Connect to the cluster
+#[cfg(feature = "kafka")]
+pub async fn connect(brokers: &[String]) -> Result<Client> {
+    ClientBuilder::new(brokers.to_vec())
+        .build()
+        .await
+        .map_err(|e| ProxyError::routing(format!("Failed to connect to Kafka brokers {:?}: {}", brokers, e)))
+}
+
+/// Client for one partition of a topic
+#[cfg(feature = "kafka")]
+pub async fn partition_client(client: &Client, topic: &str, partition: i32) -> Result<PartitionClient> {
+    client
+        .partition_client(topic, partition, UnknownTopicHandling::Error)
+        .await
+        .map_err(|e| ProxyError::routing(format!("Kafka topic '{}' partition {} unavailable: {}", topic, partition, e)))
+}
+
+/// Offset the next record written to the partition will get
+#[cfg(feature = "kafka")]
+pub async fn next_offset(partition: &PartitionClient) -> Result<i64> {
+    partition
+        .get_offset(OffsetAt::Latest)
+        .await
+        .map_err(|e| ProxyError::routing(format!("Failed to read Kafka offset: {}", e)))
+}
+
+/// Publish a record, returning its offset
+#[cfg(feature = "kafka")]
+pub async fn publish(partition: &PartitionClient, record: Record) -> Result<i64> {
+    partition
+        .produce(vec![record], Compression::NoCompression)
+        .await
+        .map_err(|e| ProxyError::routing(format!("Failed to publish to Kafka: {}", e)))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| ProxyError::routing("Kafka returned no offset for the published record".to_string()))
+}
+
+/// Poll the response partition from `offset` until the reply for `correlation_id` arrives
+///
+/// This never gives up on its own; callers bound it with a timeout.
+#[cfg(feature = "kafka")]
+pub async fn await_reply(partition: &PartitionClient, mut offset: i64, correlation_id: &str) -> Result<Value> {
+    loop {
+        let (records, _high_watermark) = partition
+            .fetch_records(offset, 1..FETCH_MAX_BYTES, FETCH_MAX_WAIT_MS)
+            .await
+            .map_err(|e| ProxyError::routing(format!("Failed to read Kafka replies: {}", e)))?;
+
+        if records.is_empty() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            continue;
+        }
+        for record in records {
+            offset = offset.max(record.offset + 1);
+            if correlation_id_matches(&record.record, correlation_id) {
+                return Ok(decode_value(&record.record));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+fn correlation_id_matches(record: &Record, expected: &str) -> bool {
+    correlation_id(record) == Some(expected)
+}
+
+#[cfg(all(test, feature = "kafka"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn reply_config() -> KafkaReplyConfig {
+        KafkaReplyConfig { topic: "orders.replies".to_string(), partition: 0 }
+    }
+
+    #[test]
+    fn test_build_record() {
+        let headers = HashMap::from([("source".to_string(), "magictunnel".to_string())]);
+        let record = build_record(Some("42"), &json!({"order": 42}), &headers, Some(("abc", &reply_config()))).unwrap();
+
+        assert_eq!(record.key.as_deref(), Some(b"42".as_slice()));
+        assert_eq!(decode_value(&record), json!({"order": 42}));
+        assert_eq!(correlation_id(&record), Some("abc"));
+        assert_eq!(record.headers[REPLY_TO_HEADER], b"orders.replies".to_vec());
+        assert_eq!(record.headers["source"], b"magictunnel".to_vec());
+
+        let record = build_record(None, &json!("plain text"), &HashMap::new(), None).unwrap();
+        assert_eq!(record.value.as_deref(), Some(b"plain text".as_slice()));
+        assert!(correlation_id(&record).is_none());
+    }
+
+    #[test]
+    fn test_decode_value() {
+        let mut record = build_record(None, &json!(null), &HashMap::new(), None).unwrap();
+        record.value = Some(b"not json".to_vec());
+        assert_eq!(decode_value(&record), json!("not json"));
+
+        record.value = None;
+        assert_eq!(decode_value(&record), Value::Null);
+    }
+
+    #[test]
+    fn test_correlation_matching() {
+        let record = build_record(None, &json!({}), &HashMap::new(), Some(("abc", &reply_config()))).unwrap();
+        assert!(correlation_id_matches(&record, "abc"));
+        assert!(!correlation_id_matches(&record, "abd"));
+    }
+}
@@ -0,0 +1,430 @@
+//! Kubernetes Jobs for `kubernetes_job`-routed tools
+//!
+//! Each call creates a Job from a templated pod spec, follows its logs and
+//! waits for it to finish. Jobs are managed through `kubectl`, so kubeconfig
+//! contexts and cluster credentials work as they do from a shell:
+//!
+//! ```yaml
+//! routing:
+//!   type: kubernetes_job
+//!   config:
+//!     namespace: tools
+//!     name_prefix: report
+//!     pod_spec:
+//!       containers:
+//!         - name: main
+//!           image: ghcr.io/acme/report:1.4
+//!           args: ["--month", "{{month}}"]
+//!     backoff_limit: 0
+//!     ttl_seconds_after_finished: 600
+//!     timeout: 900
+//! ```
+//!
+//! Log lines are sent as progress notifications to clients that asked for
+//! progress; the Job is deleted once it finishes unless `keep_job` is set.
+
+use crate::error::{ProxyError, Result};
+use crate::mcp::progress::ProgressReporter;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// How often the Job status is polled
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How long to wait for the log stream to end once the Job has finished
+const LOG_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Job settings and `kubectl` connection options
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KubernetesJobConfig {
+    /// Pod spec template; `{{param}}` placeholders are replaced with tool arguments
+    pub pod_spec: Value,
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Job names are `<name_prefix>-<random suffix>`
+    #[serde(default = "default_name_prefix")]
+    pub name_prefix: String,
+    /// Extra labels on the Job and its pods
+    #[serde(default)]
+    pub labels: std::collections::BTreeMap<String, String>,
+    /// Pod retries before the Job fails
+    #[serde(default)]
+    pub backoff_limit: u32,
+    /// Let the cluster delete finished Jobs after this many seconds
+    #[serde(default)]
+    pub ttl_seconds_after_finished: Option<u32>,
+    /// Keep the Job after it finishes instead of deleting it
+    #[serde(default)]
+    pub keep_job: bool,
+    #[serde(default)]
+    pub context: Option<String>,
+    #[serde(default)]
+    pub kubeconfig: Option<String>,
+    /// kubectl executable
+    #[serde(default = "default_kubectl_binary")]
+    pub kubectl_binary: String,
+}
+
+fn default_name_prefix() -> String {
+    "magictunnel".to_string()
+}
+
+fn default_kubectl_binary() -> String {
+    "kubectl".to_string()
+}
+
+/// State of a Job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Failed { reason: String },
+}
+
+/// Logs collected from a Job, capped in size
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobLogs {
+    pub text: String,
+    /// Whether lines were dropped at the cap
+    pub truncated: bool,
+    max_bytes: usize,
+}
+
+impl JobLogs {
+    pub fn new(max_bytes: usize) -> Self {
+        Self { text: String::new(), truncated: false, max_bytes }
+    }
+
+    pub fn push_line(&mut self, line: &str) {
+        if self.text.len() + line.len() + 1 > self.max_bytes {
+            self.truncated = true;
+        } else {
+            self.text.push_str(line);
+            self.text.push('\n');
+        }
+    }
+}
+
+/// Outcome of a finished Job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobRun {
+    pub state: JobState,
+    /// Exit code of the last pod's container, when it could be determined
+    pub exit_code: Option<i64>,
+    pub logs: JobLogs,
+}
+
+impl KubernetesJobConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.pod_spec.get("containers").is_some_and(|c| c.is_array()) {
+            return Err(ProxyError::routing("Kubernetes Job pod_spec requires a containers list".to_string()));
+        }
+        Ok(())
+    }
+
+    /// New Job name: the sanitized prefix plus a random suffix, within the 63 character limit
+    pub fn job_name(&self) -> String {
+        let mut prefix: String = self.name_prefix
+            .to_ascii_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        prefix.truncate(52);
+        let prefix = prefix.trim_matches('-');
+        let suffix = &uuid::Uuid::new_v4().simple().to_string()[..10];
+        if prefix.is_empty() {
+            format!("job-{}", suffix)
+        } else {
+            format!("{}-{}", prefix, suffix)
+        }
+    }
+
+    /// Job manifest for a rendered pod spec
+    pub fn job_manifest(&self, name: &str, mut pod_spec: Value) -> Value {
+        if pod_spec.get("restartPolicy").is_none() {
+            pod_spec["restartPolicy"] = json!("Never");
+        }
+        let mut labels = json!({
+            "app.kubernetes.io/managed-by": "magictunnel",
+        });
+        for (key, value) in &self.labels {
+            labels[key] = json!(value);
+        }
+
+        let mut spec = json!({
+            "backoffLimit": self.backoff_limit,
+            "template": {
+                "metadata": { "labels": labels },
+                "spec": pod_spec,
+            },
+        });
+        if let Some(ttl) = self.ttl_seconds_after_finished {
+            spec["ttlSecondsAfterFinished"] = json!(ttl);
+        }
+
+        json!({
+            "apiVersion": "batch/v1",
+            "kind": "Job",
+            "metadata": {
+                "name": name,
+                "labels": labels,
+            },
+            "spec": spec,
+        })
+    }
+
+    /// kubectl arguments selecting the context, kubeconfig and namespace
+    pub fn kubectl_args(&self, args: &[&str]) -> Vec<String> {
+        let mut full = Vec::new();
+        if let Some(kubeconfig) = &self.kubeconfig {
+            full.extend(["--kubeconfig".to_string(), shellexpand::tilde(kubeconfig).into_owned()]);
+        }
+        if let Some(context) = &self.context {
+            full.extend(["--context".to_string(), context.clone()]);
+        }
+        if let Some(namespace) = &self.namespace {
+            full.extend(["--namespace".to_string(), namespace.clone()]);
+        }
+        full.extend(args.iter().map(|arg| arg.to_string()));
+        full
+    }
+
+    async fn kubectl(&self, args: &[&str], input: Option<&[u8]>) -> Result<String> {
+        let output = crate::routing::process::run_with_input(
+            &self.kubectl_binary,
+            &self.kubectl_args(args),
+            input,
+            crate::routing::process::DEFAULT_MAX_OUTPUT_BYTES,
+        ).await?;
+        if output.is_success() {
+            Ok(output.stdout)
+        } else {
+            Err(ProxyError::routing(format!("kubectl {} failed: {}", args.first().unwrap_or(&""), output.stderr.trim())))
+        }
+    }
+
+    /// Create the Job
+    pub async fn create_job(&self, manifest: &Value) -> Result<()> {
+        let manifest = serde_json::to_vec(manifest)?;
+        self.kubectl(&["create", "-f", "-"], Some(&manifest)).await.map(|_| ())
+    }
+
+    /// Current state of the Job
+    pub async fn job_state(&self, name: &str) -> Result<JobState> {
+        let job: Value = serde_json::from_str(&self.kubectl(&["get", "job", name, "-o", "json"], None).await?)?;
+        Ok(job_state(&job))
+    }
+
+    /// Exit code of the Job's most recent pod, if it has terminated
+    pub async fn exit_code(&self, name: &str) -> Result<Option<i64>> {
+        let selector = format!("job-name={}", name);
+        let pods: Value = serde_json::from_str(&self.kubectl(&["get", "pods", "-l", &selector, "-o", "json"], None).await?)?;
+        Ok(latest_exit_code(&pods))
+    }
+
+    /// Delete the Job and its pods
+    pub async fn delete_job(&self, name: &str) {
+        let job = format!("job/{}", name);
+        if let Err(e) = self.kubectl(&["delete", &job, "--ignore-not-found", "--wait=false", "--cascade=background"], None).await {
+            tracing::warn!("Failed to delete Kubernetes Job '{}': {}", name, e);
+        }
+    }
+
+    /// Wait for the Job to finish
+    ///
+    /// This never gives up on its own; callers bound it with a timeout.
+    pub async fn wait_for_job(&self, name: &str) -> Result<JobState> {
+        loop {
+            match self.job_state(name).await? {
+                JobState::Running => tokio::time::sleep(POLL_INTERVAL).await,
+                finished => return Ok(finished),
+            }
+        }
+    }
+
+    /// Follow the Job's logs until its pod exits
+    ///
+    /// Each line is also reported as progress.
+    pub async fn follow_logs(&self, name: &str, logs: &mut JobLogs, progress: Option<&ProgressReporter>) {
+        let job = format!("job/{}", name);
+
+        // `kubectl logs` fails until the pod has started
+        loop {
+            let child = tokio::process::Command::new(&self.kubectl_binary)
+                .args(self.kubectl_args(&["logs", "--follow", "--pod-running-timeout=1m", &job]))
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .kill_on_drop(true)
+                .spawn();
+            let mut child = match child {
+                Ok(child) => child,
+                Err(e) => {
+                    tracing::warn!("Failed to follow logs of Kubernetes Job '{}': {}", name, e);
+                    return;
+                }
+            };
+
+            let mut lines = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+            let mut received = false;
+            while let Ok(Some(line)) = lines.next_line().await {
+                received = true;
+                if let Some(progress) = progress {
+                    progress.report(line.clone());
+                }
+                logs.push_line(&line);
+            }
+
+            let status = child.wait().await;
+            if received || status.is_ok_and(|s| s.success()) {
+                return;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Create the Job and wait for it to finish, following its logs
+    ///
+    /// This never gives up on its own; callers bound it with a timeout and
+    /// delete the Job afterwards.
+    pub async fn run_job(&self, name: &str, manifest: &Value, max_log_bytes: usize, progress: Option<&ProgressReporter>) -> Result<JobRun> {
+        self.create_job(manifest).await?;
+
+        let mut logs = JobLogs::new(max_log_bytes);
+        let state = {
+            let wait = self.wait_for_job(name);
+            let follow = self.follow_logs(name, &mut logs, progress);
+            tokio::pin!(wait, follow);
+
+            let mut followed = false;
+            let state = loop {
+                tokio::select! {
+                    state = &mut wait => break state?,
+                    _ = &mut follow, if !followed => followed = true,
+                }
+            };
+            if !followed {
+                let _ = tokio::time::timeout(LOG_GRACE_PERIOD, &mut follow).await;
+            }
+            state
+        };
+        let exit_code = self.exit_code(name).await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to read exit code of Kubernetes Job '{}': {}", name, e);
+            None
+        });
+
+        Ok(JobRun { state, exit_code, logs })
+    }
+}
+
+/// State of a Job from its JSON representation
+pub fn job_state(job: &Value) -> JobState {
+    let conditions = job.pointer("/status/conditions").and_then(|c| c.as_array()).cloned().unwrap_or_default();
+    for condition in &conditions {
+        if condition.get("status").and_then(|s| s.as_str()) != Some("True") {
+            continue;
+        }
+        match condition.get("type").and_then(|t| t.as_str()) {
+            Some("Complete") => return JobState::Succeeded,
+            Some("Failed") => {
+                let reason = condition.get("message")
+                    .or_else(|| condition.get("reason"))
+                    .and_then(|r| r.as_str())
+                    .unwrap_or("Job failed")
+                    .to_string();
+                return JobState::Failed { reason };
+            }
+            _ => {}
+        }
+    }
+    JobState::Running
+}
+
+/// Exit code of the most recently created pod's first terminated container
+pub fn latest_exit_code(pods: &Value) -> Option<i64> {
+    let items = pods.get("items")?.as_array()?;
+    let latest = items.iter().max_by_key(|pod| {
+        pod.pointer("/metadata/creationTimestamp").and_then(|t| t.as_str()).unwrap_or_default().to_string()
+    })?;
+    latest.pointer("/status/containerStatuses")?
+        .as_array()?
+        .iter()
+        .find_map(|status| status.pointer("/state/terminated/exitCode").and_then(|c| c.as_i64()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> KubernetesJobConfig {
+        serde_json::from_value(json!({
+            "namespace": "tools",
+            "name_prefix": "Monthly_Report",
+            "pod_spec": {"containers": [{"name": "main", "image": "alpine:3"}]},
+            "labels": {"team": "data"},
+            "ttl_seconds_after_finished": 600
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_job_manifest() {
+        let config = config();
+        config.validate().unwrap();
+
+        let name = config.job_name();
+        assert!(name.starts_with("monthly-report-"));
+        assert!(name.len() <= 63);
+
+        let manifest = config.job_manifest(&name, config.pod_spec.clone());
+        assert_eq!(manifest["kind"], "Job");
+        assert_eq!(manifest["metadata"]["name"], name.as_str());
+        assert_eq!(manifest["spec"]["backoffLimit"], 0);
+        assert_eq!(manifest["spec"]["ttlSecondsAfterFinished"], 600);
+        assert_eq!(manifest["spec"]["template"]["spec"]["restartPolicy"], "Never");
+        assert_eq!(manifest["spec"]["template"]["metadata"]["labels"]["team"], "data");
+    }
+
+    #[test]
+    fn test_job_logs_cap() {
+        let mut logs = JobLogs::new(12);
+        logs.push_line("step 1");
+        logs.push_line("step 2");
+        assert_eq!(logs.text, "step 1\n");
+        assert!(logs.truncated);
+    }
+
+    #[test]
+    fn test_kubectl_args() {
+        let args = config().kubectl_args(&["get", "job", "x"]);
+        assert_eq!(args, vec!["--namespace", "tools", "get", "job", "x"]);
+    }
+
+    #[test]
+    fn test_job_state() {
+        assert_eq!(job_state(&json!({"status": {"active": 1}})), JobState::Running);
+        assert_eq!(
+            job_state(&json!({"status": {"conditions": [{"type": "Complete", "status": "True"}]}})),
+            JobState::Succeeded
+        );
+        assert_eq!(
+            job_state(&json!({"status": {"conditions": [
+                {"type": "Failed", "status": "True", "reason": "BackoffLimitExceeded", "message": "Job has reached the specified backoff limit"}
+            ]}})),
+            JobState::Failed { reason: "Job has reached the specified backoff limit".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_latest_exit_code() {
+        let pods = json!({"items": [
+            {"metadata": {"creationTimestamp": "2024-01-01T00:00:00Z"},
+             "status": {"containerStatuses": [{"state": {"terminated": {"exitCode": 1}}}]}},
+            {"metadata": {"creationTimestamp": "2024-01-01T00:01:00Z"},
+             "status": {"containerStatuses": [{"state": {"terminated": {"exitCode": 0}}}]}}
+        ]});
+        assert_eq!(latest_exit_code(&pods), Some(0));
+        assert_eq!(latest_exit_code(&json!({"items": []})), None);
+    }
+}
@@ -0,0 +1,329 @@
+//! Load balancing a tool across several equivalent endpoints
+//!
+//! A routing config with a `load_balancing` section sends each call to one
+//! of its endpoints. Each endpoint lists the config fields it overrides, so
+//! the same mechanism balances HTTP URLs, GraphQL or gRPC endpoints, and
+//! upstream MCP servers exposing the same tool:
+//!
+//! ```yaml
+//! routing:
+//!   type: http
+//!   config:
+//!     method: POST
+//!     load_balancing:
+//!       strategy: weighted          # round_robin (default), least_latency or weighted
+//!       endpoints:
+//!         - { url: "https://search-1.internal/query", weight: 3 }
+//!         - { url: "https://search-2.internal/query" }
+//!       failure_threshold: 3        # consecutive failures before an endpoint is excluded
+//!       cooldown_secs: 30           # how long it stays excluded
+//! ```
+//!
+//! Endpoints that keep failing are skipped until their cooldown ends. When
+//! every endpoint is excluded, the one whose cooldown ends first is used.
+
+use crate::error::{ProxyError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Key of the load balancing section in a routing config
+pub const CONFIG_KEY: &str = "load_balancing";
+
+/// Weight of the latest call in the least-latency moving average
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// How the next endpoint is picked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    #[default]
+    RoundRobin,
+    /// Lowest moving-average latency; endpoints without calls yet go first
+    LeastLatency,
+    /// Smooth weighted round robin
+    Weighted,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// One endpoint: the routing config fields it overrides
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Endpoint {
+    /// Relative share of calls for the `weighted` strategy
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(flatten)]
+    pub overrides: Map<String, Value>,
+}
+
+fn default_failure_threshold() -> u32 {
+    3
+}
+
+fn default_cooldown_secs() -> u64 {
+    30
+}
+
+/// The `load_balancing` section of a routing config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoadBalancingConfig {
+    #[serde(default)]
+    pub strategy: Strategy,
+    pub endpoints: Vec<Endpoint>,
+    /// Consecutive failures before an endpoint is excluded
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// Seconds an excluded endpoint is skipped
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+impl LoadBalancingConfig {
+    /// The load balancing section of a routing config, if it has one
+    pub fn from_routing(config: &Value) -> Result<Option<Self>> {
+        let Some(section) = config.get(CONFIG_KEY) else {
+            return Ok(None);
+        };
+        let balancing: Self = serde_json::from_value(section.clone())
+            .map_err(|e| ProxyError::validation(format!("Invalid load_balancing config: {}", e)))?;
+        balancing.validate()?;
+        Ok(Some(balancing))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.endpoints.is_empty() {
+            return Err(ProxyError::validation("load_balancing requires at least one endpoint"));
+        }
+        if self.strategy == Strategy::Weighted && self.endpoints.iter().all(|e| e.weight == 0) {
+            return Err(ProxyError::validation("load_balancing with weighted strategy requires a positive weight"));
+        }
+        if self.failure_threshold == 0 {
+            return Err(ProxyError::validation("load_balancing failure_threshold must be positive"));
+        }
+        Ok(())
+    }
+
+    /// Routing config for one endpoint: the base config with the endpoint's
+    /// overrides applied and the load balancing section removed
+    pub fn endpoint_config(&self, config: &Value, index: usize) -> Value {
+        let mut merged = config.as_object().cloned().unwrap_or_default();
+        merged.remove(CONFIG_KEY);
+        if let Some(endpoint) = self.endpoints.get(index) {
+            for (key, value) in &endpoint.overrides {
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+        Value::Object(merged)
+    }
+}
+
+#[derive(Default)]
+struct EndpointState {
+    consecutive_failures: u32,
+    excluded_until: Option<Instant>,
+    /// Moving average of call latency in milliseconds
+    latency_ms: Option<f64>,
+    /// Smooth weighted round robin counter
+    current_weight: i64,
+}
+
+#[derive(Default)]
+struct ToolState {
+    /// Endpoint count the state was built for; a reload with a different count resets it
+    endpoint_count: usize,
+    next: usize,
+    endpoints: Vec<EndpointState>,
+}
+
+/// Per-tool endpoint health and selection state
+#[derive(Default)]
+pub struct LoadBalancer {
+    tools: Mutex<HashMap<String, ToolState>>,
+}
+
+impl std::fmt::Debug for LoadBalancer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadBalancer").finish_non_exhaustive()
+    }
+}
+
+impl LoadBalancer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index of the endpoint for the next call to `tool`
+    pub fn select(&self, tool: &str, config: &LoadBalancingConfig) -> usize {
+        let Ok(mut tools) = self.tools.lock() else {
+            return 0;
+        };
+        let state = tool_state(&mut tools, tool, config.endpoints.len());
+        let now = Instant::now();
+
+        let healthy: Vec<usize> = (0..config.endpoints.len())
+            .filter(|&i| state.endpoints[i].excluded_until.map_or(true, |until| until <= now))
+            .collect();
+        if healthy.is_empty() {
+            return (0..config.endpoints.len())
+                .min_by_key(|&i| state.endpoints[i].excluded_until)
+                .unwrap_or(0);
+        }
+
+        match config.strategy {
+            Strategy::RoundRobin => {
+                let start = state.next;
+                let selected = healthy.iter().copied()
+                    .find(|&i| i >= start)
+                    .unwrap_or(healthy[0]);
+                state.next = selected + 1;
+                selected
+            }
+            Strategy::LeastLatency => healthy.iter().copied()
+                .min_by(|&a, &b| {
+                    let latency = |i: usize| state.endpoints[i].latency_ms.unwrap_or(f64::NEG_INFINITY);
+                    latency(a).total_cmp(&latency(b))
+                })
+                .unwrap_or(healthy[0]),
+            Strategy::Weighted => {
+                let total: i64 = healthy.iter().map(|&i| i64::from(config.endpoints[i].weight)).sum();
+                for &i in &healthy {
+                    state.endpoints[i].current_weight += i64::from(config.endpoints[i].weight);
+                }
+                let selected = healthy.iter().copied()
+                    .max_by_key(|&i| (state.endpoints[i].current_weight, std::cmp::Reverse(i)))
+                    .unwrap_or(healthy[0]);
+                state.endpoints[selected].current_weight -= total;
+                selected
+            }
+        }
+    }
+
+    /// Record the outcome of a call sent to an endpoint
+    pub fn record(&self, tool: &str, config: &LoadBalancingConfig, index: usize, success: bool, latency: Duration) {
+        let Ok(mut tools) = self.tools.lock() else {
+            return;
+        };
+        let state = tool_state(&mut tools, tool, config.endpoints.len());
+        let Some(endpoint) = state.endpoints.get_mut(index) else {
+            return;
+        };
+
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        endpoint.latency_ms = Some(match endpoint.latency_ms {
+            Some(average) => average + LATENCY_SMOOTHING * (latency_ms - average),
+            None => latency_ms,
+        });
+
+        if success {
+            endpoint.consecutive_failures = 0;
+            endpoint.excluded_until = None;
+        } else {
+            endpoint.consecutive_failures += 1;
+            if endpoint.consecutive_failures >= config.failure_threshold {
+                endpoint.excluded_until = Some(Instant::now() + Duration::from_secs(config.cooldown_secs));
+            }
+        }
+    }
+
+    /// Whether an endpoint of `tool` is currently excluded after repeated failures
+    pub fn is_excluded(&self, tool: &str, index: usize) -> bool {
+        let now = Instant::now();
+        self.tools.lock().ok()
+            .and_then(|tools| tools.get(tool)?.endpoints.get(index)?.excluded_until)
+            .map_or(false, |until| until > now)
+    }
+}
+
+fn tool_state<'a>(tools: &'a mut HashMap<String, ToolState>, tool: &str, endpoint_count: usize) -> &'a mut ToolState {
+    let state = tools.entry(tool.to_string()).or_default();
+    if state.endpoint_count != endpoint_count {
+        *state = ToolState {
+            endpoint_count,
+            next: 0,
+            endpoints: (0..endpoint_count).map(|_| EndpointState::default()).collect(),
+        };
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(strategy: &str, weights: &[u32]) -> LoadBalancingConfig {
+        let endpoints: Vec<Value> = weights.iter().enumerate()
+            .map(|(i, weight)| json!({"url": format!("http://backend-{}", i), "weight": weight}))
+            .collect();
+        LoadBalancingConfig::from_routing(&json!({
+            "load_balancing": {"strategy": strategy, "endpoints": endpoints, "failure_threshold": 2}
+        })).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_endpoint_config() {
+        let routing = json!({
+            "method": "GET",
+            "url": "http://default",
+            "load_balancing": {"endpoints": [{"url": "http://a"}, {"url": "http://b", "timeout": 5}]}
+        });
+        let balancing = LoadBalancingConfig::from_routing(&routing).unwrap().unwrap();
+        assert_eq!(balancing.endpoints[0].weight, 1);
+        assert_eq!(balancing.endpoint_config(&routing, 1), json!({"method": "GET", "url": "http://b", "timeout": 5}));
+        assert!(LoadBalancingConfig::from_routing(&json!({"url": "http://a"})).unwrap().is_none());
+        assert!(LoadBalancingConfig::from_routing(&json!({"load_balancing": {"endpoints": []}})).is_err());
+    }
+
+    #[test]
+    fn test_round_robin_skips_failing_endpoints() {
+        let balancer = LoadBalancer::new();
+        let config = config("round_robin", &[1, 1, 1]);
+        let picks: Vec<usize> = (0..4).map(|_| balancer.select("search", &config)).collect();
+        assert_eq!(picks, vec![0, 1, 2, 0]);
+
+        for _ in 0..2 {
+            balancer.record("search", &config, 1, false, Duration::from_millis(5));
+        }
+        assert!(balancer.is_excluded("search", 1));
+        let picks: Vec<usize> = (0..4).map(|_| balancer.select("search", &config)).collect();
+        assert_eq!(picks, vec![2, 0, 2, 0]);
+    }
+
+    #[test]
+    fn test_weighted_distribution() {
+        let balancer = LoadBalancer::new();
+        let config = config("weighted", &[3, 1]);
+        let picks: Vec<usize> = (0..8).map(|_| balancer.select("search", &config)).collect();
+        assert_eq!(picks.iter().filter(|&&i| i == 0).count(), 6);
+        assert_eq!(picks[..4], [0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_least_latency() {
+        let balancer = LoadBalancer::new();
+        let config = config("least_latency", &[1, 1]);
+        balancer.record("search", &config, 0, true, Duration::from_millis(50));
+        // Unmeasured endpoints are tried first
+        assert_eq!(balancer.select("search", &config), 1);
+        balancer.record("search", &config, 1, true, Duration::from_millis(10));
+        assert_eq!(balancer.select("search", &config), 1);
+    }
+
+    #[test]
+    fn test_all_excluded_uses_earliest_recovery() {
+        let balancer = LoadBalancer::new();
+        let config = config("round_robin", &[1, 1]);
+        for index in [1, 0] {
+            for _ in 0..2 {
+                balancer.record("search", &config, index, false, Duration::ZERO);
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(balancer.select("search", &config), 1);
+    }
+}
@@ -1,12 +1,27 @@
-//! Middleware system for agent routing with logging and metrics support
-
+//! Middleware system for agent routing with logging, metrics and circuit breaker support
+//!
+//! Library consumers add their own policy or telemetry by implementing
+//! [`RouterMiddleware`] and passing it to
+//! [`EnhancedRouterBuilder::add_middleware`](crate::routing::EnhancedRouterBuilder::add_middleware).
+//! Middleware shipped in a separate crate can instead be registered by name
+//! with [`register_middleware_plugin`] and added with
+//! [`EnhancedRouterBuilder::with_plugin`](crate::routing::EnhancedRouterBuilder::with_plugin).
+//!
+//! A chain runs `before_execution` in ascending [`RouterMiddleware::priority`]
+//! and `after_execution`/`on_error` in reverse; middleware with equal
+//! priority keep the order they were added in.
+
+use crate::config::CircuitBreakerConfig;
 use crate::error::{ProxyError, Result};
+use crate::mcp::circuit_breaker::{CircuitBreaker, CircuitBreakerStatus, CircuitPermit};
 use crate::mcp::ToolCall;
 use crate::routing::types::{AgentResult, AgentType};
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::any::Any;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock, RwLock};
 use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
@@ -24,6 +39,9 @@ pub struct MiddlewareContext {
     pub start_time: Instant,
     /// Additional metadata
     pub metadata: HashMap<String, Value>,
+    /// Values held by middleware for the duration of the call, dropped with
+    /// the context even when a later middleware rejects the call
+    guards: Arc<std::sync::Mutex<HashMap<String, Box<dyn Any + Send>>>>,
 }
 
 impl MiddlewareContext {
@@ -35,6 +53,7 @@ impl MiddlewareContext {
             agent_type,
             start_time: Instant::now(),
             metadata: HashMap::new(),
+            guards: Arc::default(),
         }
     }
 
@@ -50,25 +69,40 @@ impl MiddlewareContext {
 
     /// Get the agent type as a string
     pub fn agent_type_name(&self) -> &'static str {
-        match &self.agent_type {
-            AgentType::Subprocess { .. } => "subprocess",
-            AgentType::Http { .. } => "http",
-            AgentType::Llm { .. } => "llm",
-            AgentType::WebSocket { .. } => "websocket",
-            AgentType::Database { .. } => "database",
-            AgentType::Grpc { .. } => "grpc",
-            AgentType::Sse { .. } => "sse",
-            AgentType::GraphQL { .. } => "graphql",
-            AgentType::ExternalMcp { .. } => "external_mcp",
-            AgentType::SmartDiscovery { .. } => "smart_discovery",
-        }
+        self.agent_type.type_name()
+    }
+
+    /// Keep `guard` alive until it is taken back with [`take_guard`](Self::take_guard)
+    /// or the context is dropped
+    pub fn hold_guard<T: Any + Send>(&self, key: impl Into<String>, guard: T) {
+        self.guards.lock().unwrap().insert(key.into(), Box::new(guard));
+    }
+
+    /// Take back a guard stored with [`hold_guard`](Self::hold_guard)
+    pub fn take_guard<T: Any + Send>(&self, key: &str) -> Option<T> {
+        let guard = self.guards.lock().unwrap().remove(key)?;
+        guard.downcast::<T>().ok().map(|guard| *guard)
     }
 }
 
+/// Priority of middleware that doesn't set its own
+pub const DEFAULT_MIDDLEWARE_PRIORITY: i32 = 0;
+
 /// Trait for routing middleware
 #[async_trait]
 pub trait RouterMiddleware: Send + Sync {
-    /// Called before agent execution
+    /// Name of the middleware in its chain, used to remove it and in logs
+    fn name(&self) -> &str {
+        let type_name = std::any::type_name::<Self>();
+        type_name.rsplit("::").next().unwrap_or(type_name)
+    }
+
+    /// Position in the chain: lower runs earlier before execution and later after it
+    fn priority(&self) -> i32 {
+        DEFAULT_MIDDLEWARE_PRIORITY
+    }
+
+    /// Called before agent execution; returning an error rejects the call
     async fn before_execution(&self, context: &MiddlewareContext) -> Result<()>;
     
     /// Called after successful agent execution
@@ -590,9 +624,168 @@ impl RouterMiddleware for MetricsMiddleware {
     }
 }
 
+/// Circuit breakers of the router's tools, from the `circuit_breakers` config section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolCircuitBreakerConfig {
+    /// Breaker settings for tools without their own
+    #[serde(flatten)]
+    pub defaults: CircuitBreakerConfig,
+    /// Per-tool breaker settings
+    #[serde(default)]
+    pub tools: HashMap<String, CircuitBreakerConfig>,
+}
+
+impl ToolCircuitBreakerConfig {
+    /// Validate the default and per-tool breaker settings
+    pub fn validate(&self) -> Result<()> {
+        let settings = std::iter::once(("circuit_breakers", &self.defaults))
+            .chain(self.tools.iter().map(|(tool_name, config)| (tool_name.as_str(), config)));
+        for (name, config) in settings {
+            if config.failure_threshold == 0 {
+                return Err(ProxyError::config(format!("{}: failure_threshold must be greater than 0", name)));
+            }
+            if !(0.0..=1.0).contains(&config.error_rate_threshold) {
+                return Err(ProxyError::config(format!("{}: error_rate_threshold must be between 0.0 and 1.0", name)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Circuit breaker middleware that short-circuits calls to repeatedly failing tools
+///
+/// Each tool gets its own breaker. Once it opens, calls to the tool are
+/// rejected with a "temporarily disabled" error until the cool-down expires;
+/// half-open probes then decide whether the tool is back.
+pub struct CircuitBreakerMiddleware {
+    /// Breaker settings for tools without their own
+    default_config: CircuitBreakerConfig,
+    /// Per-tool breaker settings
+    tool_configs: HashMap<String, CircuitBreakerConfig>,
+    /// Breakers by tool name, created on first call
+    breakers: std::sync::Mutex<HashMap<String, Arc<CircuitBreaker>>>,
+}
+
+impl CircuitBreakerMiddleware {
+    /// Create a circuit breaker middleware with default settings
+    pub fn new() -> Self {
+        Self::with_config(CircuitBreakerConfig::default())
+    }
+
+    /// Create a circuit breaker middleware with the given default settings
+    pub fn with_config(default_config: CircuitBreakerConfig) -> Self {
+        Self {
+            default_config,
+            tool_configs: HashMap::new(),
+            breakers: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create a circuit breaker middleware from the `circuit_breakers` config section
+    pub fn from_config(config: &ToolCircuitBreakerConfig) -> Self {
+        Self {
+            default_config: config.defaults.clone(),
+            tool_configs: config.tools.clone(),
+            breakers: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Use different settings, such as a lower failure threshold, for one tool
+    pub fn with_tool_config(mut self, tool_name: impl Into<String>, config: CircuitBreakerConfig) -> Self {
+        self.tool_configs.insert(tool_name.into(), config);
+        self
+    }
+
+    /// Status of a tool's breaker, if the tool has been called
+    pub fn status(&self, tool_name: &str) -> Option<CircuitBreakerStatus> {
+        self.breakers.lock().unwrap().get(tool_name).map(|breaker| breaker.status())
+    }
+
+    /// Status of every tool's breaker
+    pub fn all_statuses(&self) -> HashMap<String, CircuitBreakerStatus> {
+        self.breakers.lock().unwrap()
+            .iter()
+            .map(|(tool_name, breaker)| (tool_name.clone(), breaker.status()))
+            .collect()
+    }
+
+    /// Close a tool's circuit and clear its history
+    pub fn reset(&self, tool_name: &str) {
+        if let Some(breaker) = self.breakers.lock().unwrap().get(tool_name) {
+            breaker.reset();
+        }
+    }
+
+    fn breaker(&self, tool_name: &str) -> Arc<CircuitBreaker> {
+        self.breakers.lock().unwrap()
+            .entry(tool_name.to_string())
+            .or_insert_with(|| {
+                let config = self.tool_configs.get(tool_name).unwrap_or(&self.default_config);
+                Arc::new(CircuitBreaker::new(config.clone()))
+            })
+            .clone()
+    }
+}
+
+impl Default for CircuitBreakerMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Key of the circuit breaker permit held in the middleware context
+const CIRCUIT_PERMIT_GUARD: &str = "circuit_breaker_permit";
+
+#[async_trait]
+impl RouterMiddleware for CircuitBreakerMiddleware {
+    async fn before_execution(&self, context: &MiddlewareContext) -> Result<()> {
+        let tool_name = &context.tool_call.name;
+        match self.breaker(tool_name).acquire() {
+            // Held by the context so a half-open probe slot is given back if a
+            // later middleware rejects the call and no outcome is recorded
+            Ok(permit) => {
+                context.hold_guard(CIRCUIT_PERMIT_GUARD, permit);
+                Ok(())
+            }
+            Err(remaining) => {
+                let retry_after_secs = remaining.as_secs().max(1);
+                warn!(
+                    execution_id = %context.execution_id,
+                    tool_name = %tool_name,
+                    retry_after_secs,
+                    "Circuit breaker: Call rejected"
+                );
+                Err(ProxyError::routing(format!(
+                    "Tool '{}' is temporarily disabled after repeated failures; retry in {} seconds",
+                    tool_name, retry_after_secs
+                )))
+            }
+        }
+    }
+
+    async fn after_execution(&self, context: &MiddlewareContext, result: &AgentResult) -> Result<()> {
+        if let Some(permit) = context.take_guard::<CircuitPermit>(CIRCUIT_PERMIT_GUARD) {
+            if result.success {
+                permit.record_success(context.elapsed());
+            } else {
+                permit.record_failure();
+            }
+        }
+        Ok(())
+    }
+
+    async fn on_error(&self, context: &MiddlewareContext, _error: &ProxyError) -> Result<()> {
+        if let Some(permit) = context.take_guard::<CircuitPermit>(CIRCUIT_PERMIT_GUARD) {
+            permit.record_failure();
+        }
+        Ok(())
+    }
+}
+
 /// Chain of middleware that executes in order
 pub struct MiddlewareChain {
-    middleware: Vec<Arc<dyn RouterMiddleware>>,
+    /// Middleware with their priorities, sorted by priority
+    middleware: Vec<(i32, Arc<dyn RouterMiddleware>)>,
 }
 
 impl MiddlewareChain {
@@ -603,15 +796,33 @@ impl MiddlewareChain {
         }
     }
 
-    /// Add middleware to the chain
-    pub fn add_middleware(mut self, middleware: Arc<dyn RouterMiddleware>) -> Self {
-        self.middleware.push(middleware);
+    /// Add middleware to the chain at its own priority
+    pub fn add_middleware(self, middleware: Arc<dyn RouterMiddleware>) -> Self {
+        let priority = middleware.priority();
+        self.add_middleware_with_priority(middleware, priority)
+    }
+
+    /// Add middleware to the chain at `priority`, after any middleware with the same priority
+    pub fn add_middleware_with_priority(mut self, middleware: Arc<dyn RouterMiddleware>, priority: i32) -> Self {
+        let position = self.middleware.partition_point(|(existing, _)| *existing <= priority);
+        self.middleware.insert(position, (priority, middleware));
         self
     }
 
+    /// Remove every middleware named `name` from the chain
+    pub fn remove_middleware(mut self, name: &str) -> Self {
+        self.middleware.retain(|(_, middleware)| middleware.name() != name);
+        self
+    }
+
+    /// Names of the middleware in the order they run before execution
+    pub fn names(&self) -> Vec<&str> {
+        self.middleware.iter().map(|(_, middleware)| middleware.name()).collect()
+    }
+
     /// Execute before_execution for all middleware in the chain
     pub async fn before_execution(&self, context: &MiddlewareContext) -> Result<()> {
-        for middleware in &self.middleware {
+        for (_, middleware) in &self.middleware {
             middleware.before_execution(context).await?;
         }
         Ok(())
@@ -619,7 +830,7 @@ impl MiddlewareChain {
 
     /// Execute after_execution for all middleware in the chain (in reverse order)
     pub async fn after_execution(&self, context: &MiddlewareContext, result: &AgentResult) -> Result<()> {
-        for middleware in self.middleware.iter().rev() {
+        for (_, middleware) in self.middleware.iter().rev() {
             middleware.after_execution(context, result).await?;
         }
         Ok(())
@@ -627,7 +838,7 @@ impl MiddlewareChain {
 
     /// Execute on_error for all middleware in the chain (in reverse order)
     pub async fn on_error(&self, context: &MiddlewareContext, error: &ProxyError) -> Result<()> {
-        for middleware in self.middleware.iter().rev() {
+        for (_, middleware) in self.middleware.iter().rev() {
             middleware.on_error(context, error).await?;
         }
         Ok(())
@@ -649,3 +860,49 @@ impl Default for MiddlewareChain {
         Self::new()
     }
 }
+
+/// Builds a plugin middleware from its settings
+pub type MiddlewareFactory = Arc<dyn Fn(&Value) -> Result<Arc<dyn RouterMiddleware>> + Send + Sync>;
+
+fn plugins() -> &'static RwLock<HashMap<String, MiddlewareFactory>> {
+    static PLUGINS: OnceLock<RwLock<HashMap<String, MiddlewareFactory>>> = OnceLock::new();
+    PLUGINS.get_or_init(RwLock::default)
+}
+
+/// Register a middleware plugin under `name`, replacing any plugin registered under it
+///
+/// Crates providing middleware call this once at startup; routers then add
+/// the middleware by name with its settings.
+pub fn register_middleware_plugin<F>(name: &str, factory: F)
+where
+    F: Fn(&Value) -> Result<Arc<dyn RouterMiddleware>> + Send + Sync + 'static,
+{
+    let mut plugins = plugins().write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if plugins.insert(name.to_string(), Arc::new(factory)).is_some() {
+        warn!("Middleware plugin '{}' was registered again; replacing it", name);
+    }
+    debug!("Registered middleware plugin '{}'", name);
+}
+
+/// Build the middleware of the plugin registered under `name`
+pub fn middleware_plugin(name: &str, config: &Value) -> Result<Arc<dyn RouterMiddleware>> {
+    let factory = plugins()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ProxyError::config(format!("Unknown middleware plugin '{}'", name)))?;
+    factory(config).map_err(|e| ProxyError::config(format!("Failed to build middleware plugin '{}': {}", name, e)))
+}
+
+/// Names of the registered middleware plugins, sorted
+pub fn middleware_plugins() -> Vec<String> {
+    let mut names: Vec<String> = plugins()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .keys()
+        .cloned()
+        .collect();
+    names.sort();
+    names
+}
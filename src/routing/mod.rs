@@ -1,21 +1,50 @@
 //! Routing module for directing tool calls to appropriate agents/endpoints
 
+pub mod access_window;
 pub mod agent_router;
+#[cfg(feature = "amqp")]
+pub mod amqp;
+pub mod approval;
+pub mod approval_notify;
+pub mod aws_lambda;
+pub mod aws_sigv4;
+pub mod cache;
+pub mod canary;
 pub mod conflict_resolution;
+pub mod cost;
+pub mod docker;
+pub mod dry_run;
 pub mod enhanced_router;
+pub mod graphql_ws;
+pub mod kafka;
+pub mod kubernetes;
+pub mod load_balancer;
 
 pub mod middleware;
+pub mod pii;
+pub mod policy;
+pub mod process;
+pub mod quota;
+pub mod rate_limit;
 pub mod retry;
+pub mod script;
+pub mod secret_scan;
 pub mod timeout;
+pub mod transform;
 pub mod router;
+pub mod ssh;
+pub mod streaming;
 pub mod substitution;
+pub mod threat_detection;
 pub mod types;
+pub mod wasm;
+pub mod webhook;
 
 pub use agent_router::{AgentRouter, DefaultAgentRouter};
 pub use conflict_resolution::{CapabilitySource, ConflictInfo, ConflictResolver, ConflictResolutionConfig, ConflictSource};
 pub use enhanced_router::{EnhancedAgentRouter, EnhancedRouterBuilder};
 // Legacy hybrid routing removed - use external_mcp instead
-pub use middleware::{LoggingMiddleware, MetricsMiddleware, MiddlewareChain, MiddlewareContext, RouterMiddleware};
+pub use middleware::{CircuitBreakerMiddleware, LoggingMiddleware, MetricsMiddleware, MiddlewareChain, MiddlewareContext, RouterMiddleware, ToolCircuitBreakerConfig};
 pub use router::Router;
 pub use substitution::*;
 pub use types::*;
@@ -0,0 +1,349 @@
+//! Detection of personal data in tool arguments and results
+//!
+//! With a `pii` section, the strings in each call's arguments and in its
+//! result are scanned for personal data, and each tool's policy decides
+//! what happens when some is found:
+//!
+//! ```yaml
+//! pii:
+//!   detectors: ["email", "phone", "ssn", "iban"]   # default: all
+//!   patterns:
+//!     employee_id: "EMP-[0-9]{6}"
+//!   default_action: mask
+//!   tools:
+//!     crm_lookup: allow_with_audit
+//!     export_customers: block
+//! ```
+//!
+//! `mask` replaces personal data with `[MASKED:<kind>]`, `block` refuses the
+//! call (or withholds its result) and `allow_with_audit` passes it through.
+//! Every finding is logged as a `pii_detected` audit event. The policy
+//! applies alike to arguments, before the call is routed, and to results.
+
+use crate::error::{ProxyError, Result};
+use crate::mcp::ToolCall;
+use crate::routing::secret_scan::for_each_string;
+use crate::routing::types::AgentResult;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use tracing::warn;
+
+/// Detectors built in, as (kind, pattern)
+const BUILTIN_DETECTORS: &[(&str, &str)] = &[
+    ("email", r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b"),
+    ("iban", r"\b[A-Z]{2}[0-9]{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b"),
+    ("ssn", r"\b[0-9]{3}-[0-9]{2}-[0-9]{4}\b"),
+    ("phone", r"\+[0-9]{1,3}(?:[ .-]?[0-9]{2,4}){3,5}\b|(?:\+1[ .-]?)?(?:\([0-9]{3}\) ?|\b[0-9]{3}[ .-])[0-9]{3}[ .-][0-9]{4}\b"),
+];
+
+/// What to do with a call whose arguments or result hold personal data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiAction {
+    /// Refuse the call, or withhold its result
+    Block,
+    /// Replace personal data with a placeholder
+    #[default]
+    Mask,
+    /// Pass personal data through, recording it in the audit log
+    AllowWithAudit,
+}
+
+impl PiiAction {
+    fn name(&self) -> &'static str {
+        match self {
+            PiiAction::Block => "block",
+            PiiAction::Mask => "mask",
+            PiiAction::AllowWithAudit => "allow_with_audit",
+        }
+    }
+}
+
+/// Which personal data is detected and what is done about it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PiiConfig {
+    /// Built-in detectors to run
+    #[serde(default = "default_detectors")]
+    pub detectors: Vec<String>,
+    /// Additional detectors, as regular expressions by kind
+    #[serde(default)]
+    pub patterns: BTreeMap<String, String>,
+    /// Action for tools not listed in `tools`
+    #[serde(default)]
+    pub default_action: PiiAction,
+    /// Actions by tool name
+    #[serde(default)]
+    pub tools: HashMap<String, PiiAction>,
+}
+
+fn default_detectors() -> Vec<String> {
+    BUILTIN_DETECTORS.iter().map(|(kind, _)| kind.to_string()).collect()
+}
+
+impl Default for PiiConfig {
+    fn default() -> Self {
+        Self {
+            detectors: default_detectors(),
+            patterns: BTreeMap::new(),
+            default_action: PiiAction::default(),
+            tools: HashMap::new(),
+        }
+    }
+}
+
+impl PiiConfig {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(unknown) = self.detectors.iter().find(|kind| !BUILTIN_DETECTORS.iter().any(|(builtin, _)| builtin == kind)) {
+            return Err(ProxyError::config(format!(
+                "Unknown PII detector '{}'. Expected one of: {}",
+                unknown,
+                default_detectors().join(", ")
+            )));
+        }
+        for (kind, pattern) in &self.patterns {
+            Regex::new(pattern).map_err(|e| ProxyError::config(format!("Invalid pii pattern '{}': {}", kind, e)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Personal data found in a call
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PiiFinding {
+    /// Detector that found it
+    pub kind: String,
+    /// JSON pointer of the string it was found in
+    pub path: String,
+}
+
+/// A call refused, or a result withheld, because it holds personal data
+#[derive(Debug, Clone, PartialEq)]
+pub struct PiiBlocked {
+    /// `arguments` or `result`
+    pub stage: &'static str,
+    pub findings: Vec<PiiFinding>,
+}
+
+impl PiiBlocked {
+    /// Tool error describing the personal data found
+    pub fn into_agent_result(self, tool_name: &str) -> AgentResult {
+        let mut kinds: Vec<&str> = self.findings.iter().map(|finding| finding.kind.as_str()).collect();
+        kinds.sort_unstable();
+        kinds.dedup();
+        AgentResult {
+            success: false,
+            data: None,
+            error: Some(format!(
+                "Blocked call to tool '{}': its {} contain personal data ({})",
+                tool_name, self.stage, kinds.join(", ")
+            )),
+            metadata: Some(json!({
+                "error_category": "pii_blocked",
+                "pii": self.findings,
+            })),
+        }
+    }
+}
+
+/// Finds personal data and applies each tool's action to it
+pub struct PiiMasker {
+    config: PiiConfig,
+    detectors: Vec<(String, Regex)>,
+}
+
+impl PiiMasker {
+    pub fn new(config: PiiConfig) -> Result<Self> {
+        config.validate()?;
+        let builtin = BUILTIN_DETECTORS.iter()
+            .filter(|(kind, _)| config.detectors.iter().any(|enabled| enabled == kind))
+            .map(|(kind, pattern)| (kind.to_string(), pattern.to_string()));
+        let custom = config.patterns.iter().map(|(kind, pattern)| (kind.clone(), pattern.clone()));
+        let detectors = builtin.chain(custom)
+            .map(|(kind, pattern)| {
+                Regex::new(&pattern)
+                    .map(|regex| (kind, regex))
+                    .map_err(|e| ProxyError::config(format!("Invalid PII pattern: {}", e)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { config, detectors })
+    }
+
+    /// Action applied to a tool's calls
+    pub fn action(&self, tool_name: &str) -> PiiAction {
+        self.config.tools.get(tool_name).copied().unwrap_or(self.config.default_action)
+    }
+
+    /// The call after the tool's action was applied to its arguments, and what was found
+    pub fn mask_call(&self, tool_call: &ToolCall) -> std::result::Result<(ToolCall, Vec<PiiFinding>), PiiBlocked> {
+        let mut arguments = tool_call.arguments.clone();
+        let findings = self.apply(&tool_call.name, "arguments", &mut arguments, String::new())?;
+        Ok((ToolCall { name: tool_call.name.clone(), arguments }, findings))
+    }
+
+    /// The result after the tool's action was applied to its data and error, and what was found
+    pub fn mask_result(&self, tool_name: &str, mut result: AgentResult) -> std::result::Result<(AgentResult, Vec<PiiFinding>), PiiBlocked> {
+        let mut findings = Vec::new();
+        if let Some(data) = result.data.as_mut() {
+            findings = self.apply(tool_name, "result", data, "/data".to_string())?;
+        }
+        if let Some(error) = result.error.take() {
+            let mut error = Value::String(error);
+            findings.extend(self.apply(tool_name, "result", &mut error, "/error".to_string())?);
+            result.error = error.as_str().map(str::to_string);
+        }
+        Ok((result, findings))
+    }
+
+    /// A copy of `value` with all personal data masked, whatever the tools' actions, e.g. for logging
+    pub fn masked(&self, value: &Value) -> Value {
+        let mut value = value.clone();
+        for_each_string(&mut value, String::new(), &mut |text, path| self.scan_string(text, path, true, &mut Vec::new()));
+        value
+    }
+
+    fn apply(&self, tool_name: &str, stage: &'static str, value: &mut Value, path: String) -> std::result::Result<Vec<PiiFinding>, PiiBlocked> {
+        let action = self.action(tool_name);
+        let mask = action == PiiAction::Mask;
+        let mut findings = Vec::new();
+        for_each_string(value, path, &mut |text, path| self.scan_string(text, path, mask, &mut findings));
+        if findings.is_empty() {
+            return Ok(findings);
+        }
+
+        let kinds: Vec<&str> = findings.iter().map(|finding| finding.kind.as_str()).collect();
+        let paths: Vec<&str> = findings.iter().map(|finding| finding.path.as_str()).collect();
+        warn!(
+            target: "audit",
+            event_type = "pii_detected",
+            tool = %tool_name,
+            stage = stage,
+            action = action.name(),
+            kinds = ?kinds,
+            paths = ?paths,
+            "Found personal data in tool {}", stage
+        );
+        match action {
+            PiiAction::Block => Err(PiiBlocked { stage, findings }),
+            PiiAction::Mask | PiiAction::AllowWithAudit => Ok(findings),
+        }
+    }
+
+    /// Find the personal data in `text`, replacing it with placeholders if `mask`
+    fn scan_string(&self, text: &mut String, path: &str, mask: bool, findings: &mut Vec<PiiFinding>) {
+        for (kind, regex) in &self.detectors {
+            if !regex.is_match(text) {
+                continue;
+            }
+            let scanned = regex.replace_all(text, |captures: &Captures| {
+                let found = &captures[0];
+                if !is_valid(kind, found) {
+                    return found.to_string();
+                }
+                findings.push(PiiFinding { kind: kind.clone(), path: path.to_string() });
+                if mask {
+                    format!("[MASKED:{}]", kind)
+                } else {
+                    found.to_string()
+                }
+            });
+            if mask {
+                *text = scanned.into_owned();
+            }
+        }
+    }
+}
+
+/// Checks ruling out look-alikes of some kinds of personal data
+fn is_valid(kind: &str, found: &str) -> bool {
+    match kind {
+        "iban" => is_valid_iban(found),
+        "ssn" => {
+            let mut parts = found.split('-');
+            let (area, group, serial) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+            !(area == "000" || area == "666" || area.starts_with('9') || group == "00" || serial == "0000")
+        }
+        _ => true,
+    }
+}
+
+/// Whether an IBAN's check digits are right (ISO 13616 mod-97)
+fn is_valid_iban(iban: &str) -> bool {
+    let iban: String = iban.chars().filter(|c| !c.is_whitespace()).collect();
+    if !(15..=34).contains(&iban.len()) {
+        return false;
+    }
+    let (head, tail) = iban.split_at(4);
+    let mut remainder = 0u32;
+    for c in tail.chars().chain(head.chars()) {
+        let digits = match c.to_digit(36) {
+            Some(value) => value,
+            None => return false,
+        };
+        remainder = if digits >= 10 { (remainder * 100 + digits) % 97 } else { (remainder * 10 + digits) % 97 };
+    }
+    remainder == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn masker(default_action: PiiAction) -> PiiMasker {
+        PiiMasker::new(PiiConfig {
+            patterns: BTreeMap::from([("employee_id".to_string(), "EMP-[0-9]{6}".to_string())]),
+            default_action,
+            tools: HashMap::from([("crm_lookup".to_string(), PiiAction::AllowWithAudit)]),
+            ..PiiConfig::default()
+        }).unwrap()
+    }
+
+    #[test]
+    fn test_masks_personal_data() {
+        let call = ToolCall::new("send_invoice".to_string(), json!({
+            "to": "Jane Doe <jane.doe@example.com>",
+            "notes": ["Call (555) 123-4567 or +44 20 7946 0958", "SSN 123-45-6789, employee EMP-004211"],
+            "iban": "DE89 3704 0044 0532 0130 00",
+            "order": "2026-10-15 order 998-12-0000",
+        }));
+        let (masked, findings) = masker(PiiAction::Mask).mask_call(&call).unwrap();
+        let arguments = &masked.arguments;
+        assert_eq!(arguments["to"], "Jane Doe <[MASKED:email]>");
+        assert_eq!(arguments["notes"][0], "Call [MASKED:phone] or [MASKED:phone]");
+        assert_eq!(arguments["notes"][1], "SSN [MASKED:ssn], employee [MASKED:employee_id]");
+        assert_eq!(arguments["iban"], "[MASKED:iban]");
+        // Not a valid SSN
+        assert_eq!(arguments["order"], "2026-10-15 order 998-12-0000");
+        assert_eq!(findings.len(), 6);
+    }
+
+    #[test]
+    fn test_block_and_allow_with_audit() {
+        let masker = masker(PiiAction::Block);
+        let call = ToolCall::new("export".to_string(), json!({"email": "jane@example.com"}));
+        let blocked = masker.mask_call(&call).unwrap_err();
+        assert_eq!(blocked.stage, "arguments");
+        let result = blocked.into_agent_result("export");
+        assert!(result.error.unwrap().contains("(email)"));
+        assert_eq!(result.metadata.unwrap()["error_category"], "pii_blocked");
+
+        let result = AgentResult { success: true, data: Some(json!({"phone": "555-123-4567"})), error: None, metadata: None };
+        let (passed, findings) = masker.mask_result("crm_lookup", result).unwrap();
+        assert_eq!(passed.data.unwrap()["phone"], "555-123-4567");
+        assert_eq!(findings, vec![PiiFinding { kind: "phone".to_string(), path: "/data/phone".to_string() }]);
+    }
+
+    #[test]
+    fn test_iban_checksum() {
+        assert!(is_valid_iban("GB82 WEST 1234 5698 7654 32"));
+        assert!(is_valid_iban("DE89370400440532013000"));
+        assert!(!is_valid_iban("DE88370400440532013000"));
+    }
+
+    #[test]
+    fn test_unknown_detector() {
+        let config = PiiConfig { detectors: vec!["passport".to_string()], ..PiiConfig::default() };
+        assert!(config.validate().is_err());
+    }
+}
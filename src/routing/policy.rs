@@ -0,0 +1,408 @@
+//! Authorization of tool calls by Open Policy Agent (Rego) policies
+//!
+//! Each tool call is evaluated against a Rego decision before it reaches an
+//! agent, either by an OPA server such as a sidecar or in-process from local
+//! policy files:
+//!
+//! ```yaml
+//! policy:
+//!   opa_url: "http://localhost:8181"     # or policy_files: ["./policies/tools.rego"]
+//!   decision: "magictunnel/tools/allow"
+//!   cache_ttl_seconds: 60
+//! ```
+//!
+//! The decision's input describes the call:
+//!
+//! ```json
+//! {
+//!   "tool": "delete_record",
+//!   "arguments": {"id": 7},
+//!   "annotations": {"destructiveHint": "true"},
+//!   "api_key": "ci-bot", "user": "alice", "session": "...",
+//!   "time": {"rfc3339": "2026-10-15T09:30:00Z", "unix": 1791970200, "weekday": "Thu", "hour": 9}
+//! }
+//! ```
+//!
+//! A decision is either a boolean or an object with `allow` and an optional
+//! `reason`; an undefined decision denies the call. Decisions are cached by
+//! their input without `time`, so time-based rules may apply up to
+//! `cache_ttl_seconds` late.
+
+use crate::error::{ProxyError, Result};
+use crate::mcp::ToolCall;
+use crate::registry::ToolDefinition;
+use crate::routing::{cost, rate_limit};
+use crate::routing::types::AgentResult;
+use chrono::{Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Most decisions kept in the cache
+const MAX_CACHED_DECISIONS: usize = 10_000;
+
+/// Where and how tool calls are authorized
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyConfig {
+    /// URL of an OPA server evaluating the decision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opa_url: Option<String>,
+    /// Rego files evaluated in-process, when there is no `opa_url`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policy_files: Vec<PathBuf>,
+    /// Path of the decision under `data`, with `/` separators
+    #[serde(default = "default_decision")]
+    pub decision: String,
+    /// Seconds a decision is reused for calls with the same input (0 disables caching)
+    #[serde(default = "default_cache_ttl_seconds")]
+    pub cache_ttl_seconds: u64,
+    /// Milliseconds to wait for the OPA server
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Allow calls when the decision can't be evaluated
+    #[serde(default)]
+    pub fail_open: bool,
+}
+
+fn default_decision() -> String {
+    "magictunnel/allow".to_string()
+}
+
+fn default_cache_ttl_seconds() -> u64 {
+    60
+}
+
+fn default_timeout_ms() -> u64 {
+    1000
+}
+
+impl PolicyConfig {
+    pub fn validate(&self) -> Result<()> {
+        match (&self.opa_url, self.policy_files.is_empty()) {
+            (Some(_), false) => {
+                return Err(ProxyError::config("policy.opa_url and policy.policy_files cannot both be set"));
+            }
+            (None, true) => {
+                return Err(ProxyError::config("policy needs an opa_url or policy_files"));
+            }
+            (Some(url), true) if !(url.starts_with("http://") || url.starts_with("https://")) => {
+                return Err(ProxyError::config(format!("policy.opa_url '{}' must be an http(s) URL", url)));
+            }
+            _ => {}
+        }
+        let decision = self.decision.trim_matches('/');
+        if decision.is_empty() || decision.split('/').any(str::is_empty) {
+            return Err(ProxyError::config(format!("policy.decision '{}' is not a decision path", self.decision)));
+        }
+        if self.timeout_ms == 0 {
+            return Err(ProxyError::config("policy.timeout_ms must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a policy decision
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PolicyDecision {
+    pub allow: bool,
+    /// Why the call was denied, if the policy says
+    pub reason: Option<String>,
+}
+
+impl PolicyDecision {
+    /// Read the result of a decision, `None` when it is undefined
+    fn from_result(result: Option<&Value>) -> Self {
+        match result {
+            Some(Value::Bool(allow)) => Self { allow: *allow, reason: None },
+            Some(Value::Object(decision)) => Self {
+                allow: decision.get("allow").and_then(Value::as_bool).unwrap_or(false),
+                reason: decision.get("reason").and_then(Value::as_str).map(str::to_string),
+            },
+            _ => Self { allow: false, reason: Some("policy decision is undefined".to_string()) },
+        }
+    }
+
+    /// Tool error for a denied call
+    pub fn into_agent_result(self, tool_call: &ToolCall) -> AgentResult {
+        let reason = self.reason.unwrap_or_else(|| "denied by policy".to_string());
+        AgentResult {
+            success: false,
+            data: None,
+            error: Some(format!("Policy denied call to tool '{}': {}", tool_call.name, reason)),
+            metadata: Some(json!({
+                "error_category": "policy_denied",
+                "policy": { "reason": reason },
+            })),
+        }
+    }
+}
+
+/// Evaluates decisions
+enum Backend {
+    /// An OPA server's data API
+    Remote { client: reqwest::Client, url: String },
+    /// Policies loaded in-process
+    #[cfg(feature = "rego")]
+    Embedded { engine: Mutex<regorus::Engine>, rule: String },
+}
+
+/// Authorizes tool calls against the configured policies
+pub struct PolicyEngine {
+    config: PolicyConfig,
+    backend: Backend,
+    cache: Mutex<HashMap<String, (PolicyDecision, Instant)>>,
+}
+
+impl PolicyEngine {
+    /// Connect to the OPA server or load the policy files
+    pub fn new(config: PolicyConfig) -> Result<Self> {
+        config.validate()?;
+        let decision = config.decision.trim_matches('/').to_string();
+        let backend = match &config.opa_url {
+            Some(url) => Backend::Remote {
+                client: reqwest::Client::builder()
+                    .timeout(Duration::from_millis(config.timeout_ms))
+                    .build()
+                    .map_err(|e| ProxyError::config(format!("Failed to create OPA client: {}", e)))?,
+                url: format!("{}/v1/data/{}", url.trim_end_matches('/'), decision),
+            },
+            #[cfg(feature = "rego")]
+            None => {
+                let mut engine = regorus::Engine::new();
+                for path in &config.policy_files {
+                    engine.add_policy_from_file(path).map_err(|e| {
+                        ProxyError::config(format!("Failed to load policy file '{}': {}", path.display(), e))
+                    })?;
+                }
+                Backend::Embedded {
+                    engine: Mutex::new(engine),
+                    rule: format!("data.{}", decision.replace('/', ".")),
+                }
+            }
+            #[cfg(not(feature = "rego"))]
+            None => {
+                return Err(ProxyError::config(
+                    "policy.policy_files needs the `rego` feature; use policy.opa_url or rebuild with it".to_string(),
+                ));
+            }
+        };
+        Ok(Self { config, backend, cache: Mutex::new(HashMap::new()) })
+    }
+
+    /// Decide whether the current caller may make `tool_call`
+    pub async fn authorize(&self, tool_call: &ToolCall, tool_def: &ToolDefinition) -> PolicyDecision {
+        let mut input = Self::input(tool_call, tool_def);
+        let key = input.to_string();
+        if let Some(decision) = self.cached(&key) {
+            return decision;
+        }
+
+        input["time"] = Self::time();
+        let decision = match self.evaluate(&input).await {
+            Ok(decision) => decision,
+            Err(e) => {
+                warn!("Failed to evaluate policy for tool '{}': {}", tool_call.name, e);
+                // Failures aren't cached so the next call tries again
+                return PolicyDecision {
+                    allow: self.config.fail_open,
+                    reason: Some("policy could not be evaluated".to_string()),
+                };
+            }
+        };
+        if !decision.allow {
+            info!(target: "audit", tool = %tool_call.name, reason = ?decision.reason, "Policy denied tool call");
+        }
+        self.remember(key, &decision);
+        decision
+    }
+
+    /// Input of a decision, without the time
+    fn input(tool_call: &ToolCall, tool_def: &ToolDefinition) -> Value {
+        json!({
+            "tool": tool_call.name,
+            "arguments": tool_call.arguments,
+            "annotations": tool_def.annotations.clone().unwrap_or_default(),
+            "api_key": rate_limit::current_client(),
+            "user": cost::current_user(),
+            "session": cost::current_session(),
+        })
+    }
+
+    fn time() -> Value {
+        let now = Utc::now();
+        json!({
+            "rfc3339": now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "unix": now.timestamp(),
+            "weekday": now.format("%a").to_string(),
+            "hour": now.hour(),
+        })
+    }
+
+    async fn evaluate(&self, input: &Value) -> Result<PolicyDecision> {
+        match &self.backend {
+            Backend::Remote { client, url } => {
+                let response = client.post(url)
+                    .json(&json!({ "input": input }))
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| ProxyError::routing(format!("OPA request failed: {}", e)))?;
+                let body: Value = response.json().await
+                    .map_err(|e| ProxyError::routing(format!("Invalid OPA response: {}", e)))?;
+                Ok(PolicyDecision::from_result(body.get("result")))
+            }
+            #[cfg(feature = "rego")]
+            Backend::Embedded { engine, rule } => {
+                let input = regorus::Value::from_json_str(&input.to_string())
+                    .map_err(|e| ProxyError::routing(format!("Invalid policy input: {}", e)))?;
+                let mut engine = engine.lock().unwrap();
+                engine.set_input(input);
+                let result = engine.eval_rule(rule.clone())
+                    .map_err(|e| ProxyError::routing(format!("Failed to evaluate '{}': {}", rule, e)))?;
+                if result == regorus::Value::Undefined {
+                    return Ok(PolicyDecision::from_result(None));
+                }
+                let result = serde_json::to_value(&result)
+                    .map_err(|e| ProxyError::routing(format!("Invalid result of '{}': {}", rule, e)))?;
+                Ok(PolicyDecision::from_result(Some(&result)))
+            }
+        }
+    }
+
+    fn cached(&self, key: &str) -> Option<PolicyDecision> {
+        let ttl = Duration::from_secs(self.config.cache_ttl_seconds);
+        let cache = self.cache.lock().unwrap();
+        cache.get(key)
+            .filter(|(_, decided_at)| decided_at.elapsed() < ttl)
+            .map(|(decision, _)| decision.clone())
+    }
+
+    fn remember(&self, key: String, decision: &PolicyDecision) {
+        if self.config.cache_ttl_seconds == 0 {
+            return;
+        }
+        let ttl = Duration::from_secs(self.config.cache_ttl_seconds);
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHED_DECISIONS {
+            cache.retain(|_, (_, decided_at)| decided_at.elapsed() < ttl);
+            if cache.len() >= MAX_CACHED_DECISIONS {
+                cache.clear();
+            }
+        }
+        cache.insert(key, (decision.clone(), Instant::now()));
+    }
+
+    /// Forget all cached decisions, e.g. after the policies changed
+    pub fn clear_cache(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "rego")]
+    const POLICY: &str = r#"
+package magictunnel
+
+import rego.v1
+
+default allow := false
+
+allow if {
+    input.annotations.destructiveHint != "true"
+}
+
+allow if {
+    input.user == "admin"
+}
+"#;
+
+    #[cfg(feature = "rego")]
+    fn engine(dir: &tempfile::TempDir, cache_ttl_seconds: u64) -> PolicyEngine {
+        let path = dir.path().join("tools.rego");
+        std::fs::write(&path, POLICY).unwrap();
+        PolicyEngine::new(PolicyConfig {
+            opa_url: None,
+            policy_files: vec![path],
+            decision: default_decision(),
+            cache_ttl_seconds,
+            timeout_ms: default_timeout_ms(),
+            fail_open: false,
+        }).unwrap()
+    }
+
+    #[cfg(feature = "rego")]
+    fn tool(destructive: bool) -> ToolDefinition {
+        let annotations = destructive.then(|| HashMap::from([("destructiveHint".to_string(), "true".to_string())]));
+        ToolDefinition::new_with_fields(
+            "delete_record".to_string(),
+            "Delete a record".to_string(),
+            json!({"type": "object"}),
+            crate::registry::RoutingConfig::new("http".to_string(), json!({"url": "http://localhost"})),
+            annotations,
+        ).unwrap()
+    }
+
+    #[cfg(feature = "rego")]
+    #[tokio::test]
+    async fn test_embedded_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = engine(&dir, 0);
+        let call = ToolCall::new("delete_record".to_string(), json!({"id": 7}));
+
+        assert!(engine.authorize(&call, &tool(false)).await.allow);
+        let denied = engine.authorize(&call, &tool(true)).await;
+        assert!(!denied.allow);
+        let result = denied.into_agent_result(&call);
+        assert_eq!(result.metadata.unwrap()["error_category"], "policy_denied");
+
+        let allowed = cost::as_user(Some("admin".to_string()), engine.authorize(&call, &tool(true))).await;
+        assert!(allowed.allow);
+    }
+
+    #[cfg(feature = "rego")]
+    #[tokio::test]
+    async fn test_decisions_are_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = engine(&dir, 60);
+        let call = ToolCall::new("delete_record".to_string(), json!({"id": 7}));
+        assert!(!engine.authorize(&call, &tool(true)).await.allow);
+
+        // A cached decision is reused even after the policy would change its mind
+        if let Backend::Embedded { engine: rego, .. } = &engine.backend {
+            *rego.lock().unwrap() = regorus::Engine::new();
+        }
+        let cached = engine.authorize(&call, &tool(true)).await;
+        assert_eq!(cached, PolicyDecision { allow: false, reason: None });
+        engine.clear_cache();
+        assert!(engine.authorize(&call, &tool(true)).await.reason.is_some());
+    }
+
+    #[test]
+    fn test_decision_results() {
+        assert!(PolicyDecision::from_result(Some(&json!(true))).allow);
+        let decision = PolicyDecision::from_result(Some(&json!({"allow": false, "reason": "outside business hours"})));
+        assert_eq!(decision, PolicyDecision { allow: false, reason: Some("outside business hours".to_string()) });
+        assert!(!PolicyDecision::from_result(Some(&json!({"reason": "no allow"}))).allow);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config: PolicyConfig = serde_yaml::from_str("opa_url: http://localhost:8181").unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.decision, "magictunnel/allow");
+
+        let both: PolicyConfig = serde_yaml::from_str("opa_url: http://localhost:8181\npolicy_files: [a.rego]").unwrap();
+        assert!(both.validate().is_err());
+        let neither: PolicyConfig = serde_yaml::from_str("decision: a/b").unwrap();
+        assert!(neither.validate().is_err());
+        let bad_path: PolicyConfig = serde_yaml::from_str("opa_url: http://localhost:8181\ndecision: a//b").unwrap();
+        assert!(bad_path.validate().is_err());
+    }
+}
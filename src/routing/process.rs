@@ -0,0 +1,196 @@
+//! Running client executables (ssh, docker, kubectl) with bounded output capture
+
+use crate::error::{ProxyError, Result};
+use crate::mcp::progress::ProgressReporter;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+
+/// Default cap on captured stdout and stderr (each)
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Output of a finished process
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedOutput {
+    /// `None` when the process was killed by a signal
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Whether stdout or stderr was cut at the output limit
+    pub truncated: bool,
+}
+
+impl CapturedOutput {
+    pub fn is_success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Run `program`, capturing at most `max_output_bytes` of stdout and of stderr
+///
+/// The process is killed if the returned future is dropped (e.g. on timeout).
+pub async fn run_captured(program: &str, args: &[String], max_output_bytes: usize) -> Result<CapturedOutput> {
+    run_with_input(program, args, None, max_output_bytes).await
+}
+
+/// Like [`run_captured`], writing `input` to the process's stdin
+pub async fn run_with_input(program: &str, args: &[String], input: Option<&[u8]>, max_output_bytes: usize) -> Result<CapturedOutput> {
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| ProxyError::routing(format!("Failed to start '{}': {}", program, e)))?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input).await
+            .map_err(|e| ProxyError::routing(format!("Failed to write to '{}': {}", program, e)))?;
+        // Dropping stdin closes it so the process sees end of input
+    }
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let (stdout, stderr, status) = tokio::join!(
+        read_limited(stdout, max_output_bytes),
+        read_limited(stderr, max_output_bytes),
+        child.wait(),
+    );
+    let (stdout, stdout_truncated) = stdout?;
+    let (stderr, stderr_truncated) = stderr?;
+    let status = status.map_err(|e| ProxyError::routing(format!("Failed to wait for '{}': {}", program, e)))?;
+
+    Ok(CapturedOutput {
+        exit_code: status.code(),
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        truncated: stdout_truncated || stderr_truncated,
+    })
+}
+
+/// Run `command`, capturing at most `max_output_bytes` of stdout and of stderr
+pub async fn run_command(command: tokio::process::Command, max_output_bytes: usize) -> Result<CapturedOutput> {
+    spawn_and_capture(command, max_output_bytes, None).await
+}
+
+/// Run `command`, reporting each line of stdout as progress as soon as it is written
+///
+/// As with [`run_captured`], at most `max_output_bytes` of stdout and of stderr
+/// are kept for the result; the client has seen every stdout line by then.
+pub async fn run_streaming(command: tokio::process::Command, max_output_bytes: usize, progress: &ProgressReporter) -> Result<CapturedOutput> {
+    spawn_and_capture(command, max_output_bytes, Some(progress)).await
+}
+
+async fn spawn_and_capture(mut command: tokio::process::Command, max_output_bytes: usize, progress: Option<&ProgressReporter>) -> Result<CapturedOutput> {
+    let program = command.as_std().get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| ProxyError::routing(format!("Failed to start '{}': {}", program, e)))?;
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let read_stdout = async {
+        match progress {
+            Some(progress) => read_lines_limited(stdout, max_output_bytes, |line| progress.report(line)).await,
+            None => read_limited(stdout, max_output_bytes).await,
+        }
+    };
+    let (stdout, stderr, status) = tokio::join!(
+        read_stdout,
+        read_limited(stderr, max_output_bytes),
+        child.wait(),
+    );
+    let (stdout, stdout_truncated) = stdout?;
+    let (stderr, stderr_truncated) = stderr?;
+    let status = status.map_err(|e| ProxyError::routing(format!("Failed to wait for '{}': {}", program, e)))?;
+
+    Ok(CapturedOutput {
+        exit_code: status.code(),
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        truncated: stdout_truncated || stderr_truncated,
+    })
+}
+
+/// Like [`read_limited`], passing each line to `on_line` as it is read
+async fn read_lines_limited(reader: impl AsyncRead + Unpin, limit: usize, mut on_line: impl FnMut(String)) -> Result<(Vec<u8>, bool)> {
+    let mut reader = BufReader::new(reader);
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let n = reader.read_until(b'\n', &mut line).await
+            .map_err(|e| ProxyError::routing(format!("Failed to read process output: {}", e)))?;
+        if n == 0 {
+            return Ok((kept, truncated));
+        }
+        let room = limit.saturating_sub(kept.len());
+        if n > room {
+            truncated = true;
+        }
+        kept.extend_from_slice(&line[..n.min(room)]);
+        on_line(String::from_utf8_lossy(&line).trim_end_matches(['\n', '\r']).to_string());
+    }
+}
+
+/// Read a stream to the end, keeping at most `limit` bytes
+///
+/// The rest is drained so the process never blocks on a full pipe.
+async fn read_limited(mut reader: impl AsyncRead + Unpin, limit: usize) -> Result<(Vec<u8>, bool)> {
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf).await
+            .map_err(|e| ProxyError::routing(format!("Failed to read process output: {}", e)))?;
+        if n == 0 {
+            return Ok((kept, truncated));
+        }
+        let room = limit.saturating_sub(kept.len());
+        if n > room {
+            truncated = true;
+        }
+        kept.extend_from_slice(&buf[..n.min(room)]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_limited() {
+        let (kept, truncated) = read_limited(&b"hello world"[..], 5).await.unwrap();
+        assert_eq!(kept, b"hello");
+        assert!(truncated);
+
+        let (kept, truncated) = read_limited(&b"hi"[..], 5).await.unwrap();
+        assert_eq!(kept, b"hi");
+        assert!(!truncated);
+    }
+
+    #[tokio::test]
+    async fn test_read_lines_limited() {
+        let mut lines = Vec::new();
+        let (kept, truncated) = read_lines_limited(&b"one\r\ntwo\nthree"[..], 8, |line| lines.push(line)).await.unwrap();
+        assert_eq!(lines, ["one", "two", "three"]);
+        assert_eq!(kept, b"one\r\ntwo");
+        assert!(truncated);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_command_caps_output() {
+        let mut command = tokio::process::Command::new("sh");
+        command.args(["-c", "head -c 100000 /dev/zero | tr '\\0' x"]);
+        let output = run_command(command, 1024).await.unwrap();
+        assert!(output.is_success());
+        assert_eq!(output.stdout.len(), 1024);
+        assert!(output.truncated);
+    }
+}
@@ -0,0 +1,425 @@
+//! Usage quotas per API key, user and role
+//!
+//! Quotas cap how many tool calls a caller makes per hour and per day, and
+//! the cumulative cost of its calls to tools with a `cost`:
+//!
+//! ```yaml
+//! quotas:
+//!   api_keys:
+//!     ci-bot: { calls_per_hour: 500, calls_per_day: 5000 }
+//!   default_api_key: { calls_per_day: 1000 }   # keys not listed above
+//!   users:
+//!     alice: { cost: 25 }
+//!   default_user: { calls_per_hour: 100 }
+//!   roles:                                     # shared by every user with the role
+//!     contractors: { calls_per_day: 2000, cost: 100 }
+//! ```
+//!
+//! Hourly and daily counts start again at the top of each UTC hour and day;
+//! cost accumulates until the server restarts. Once a quota is used up, calls
+//! fail with a tool error until it resets.
+
+use crate::error::{ProxyError, Result};
+use crate::routing::types::AgentResult;
+use crate::routing::{cost, rate_limit};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, RwLock};
+use tracing::warn;
+
+tokio::task_local! {
+    static ROLES: Vec<String>;
+}
+
+/// Run `future` with its tool calls counted against the quotas of `roles`
+pub async fn with_roles<F: Future>(roles: Vec<String>, future: F) -> F::Output {
+    if roles.is_empty() {
+        future.await
+    } else {
+        ROLES.scope(roles, future).await
+    }
+}
+
+/// Roles of the caller the current call is made for
+pub fn current_roles() -> Vec<String> {
+    ROLES.try_with(Clone::clone).unwrap_or_default()
+}
+
+/// Limits of one API key, user or role; limits left unset don't apply
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calls_per_hour: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calls_per_day: Option<u64>,
+    /// Cumulative cost, in the unit tool costs are declared in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+/// Quotas applied by the router
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotasConfig {
+    /// Quotas by API key name
+    #[serde(default)]
+    pub api_keys: HashMap<String, QuotaLimits>,
+    /// Quota of each API key not listed in `api_keys`
+    #[serde(default)]
+    pub default_api_key: Option<QuotaLimits>,
+    /// Quotas by user ID
+    #[serde(default)]
+    pub users: HashMap<String, QuotaLimits>,
+    /// Quota of each user not listed in `users`
+    #[serde(default)]
+    pub default_user: Option<QuotaLimits>,
+    /// Quotas by role, shared by all users with the role
+    #[serde(default)]
+    pub roles: HashMap<String, QuotaLimits>,
+}
+
+impl QuotasConfig {
+    pub fn validate(&self) -> Result<()> {
+        let quotas = self.api_keys.iter().map(|(name, limits)| ("API key", name.as_str(), limits))
+            .chain(self.default_api_key.iter().map(|limits| ("API key", "default", limits)))
+            .chain(self.users.iter().map(|(name, limits)| ("user", name.as_str(), limits)))
+            .chain(self.default_user.iter().map(|limits| ("user", "default", limits)))
+            .chain(self.roles.iter().map(|(name, limits)| ("role", name.as_str(), limits)));
+        for (kind, name, limits) in quotas {
+            if limits.cost.is_some_and(|cost| !(cost.is_finite() && cost >= 0.0)) {
+                return Err(ProxyError::config(format!("Cost quota for {} '{}' cannot be negative", kind, name)));
+            }
+        }
+        Ok(())
+    }
+
+    fn limits(&self, scope: QuotaScope, key: &str) -> Option<&QuotaLimits> {
+        match scope {
+            QuotaScope::ApiKey => self.api_keys.get(key).or(self.default_api_key.as_ref()),
+            QuotaScope::User => self.users.get(key).or(self.default_user.as_ref()),
+            QuotaScope::Role => self.roles.get(key),
+        }
+    }
+}
+
+/// Who a quota belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaScope {
+    ApiKey,
+    User,
+    Role,
+}
+
+impl QuotaScope {
+    fn describe(&self) -> &'static str {
+        match self {
+            QuotaScope::ApiKey => "API key",
+            QuotaScope::User => "user",
+            QuotaScope::Role => "role",
+        }
+    }
+}
+
+/// The API key, user and roles of a call
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuotaSubjects {
+    pub api_key: Option<String>,
+    pub user: Option<String>,
+    pub roles: Vec<String>,
+}
+
+impl QuotaSubjects {
+    /// Subjects of the current call
+    pub fn current() -> Self {
+        Self { api_key: rate_limit::current_client(), user: cost::current_user(), roles: current_roles() }
+    }
+
+    fn keys(&self) -> impl Iterator<Item = (QuotaScope, &str)> {
+        self.api_key.iter().map(|key| (QuotaScope::ApiKey, key.as_str()))
+            .chain(self.user.iter().map(|user| (QuotaScope::User, user.as_str())))
+            .chain(self.roles.iter().map(|role| (QuotaScope::Role, role.as_str())))
+    }
+}
+
+/// Which limit of a quota was reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaLimit {
+    CallsPerHour,
+    CallsPerDay,
+    Cost,
+}
+
+/// A call rejected because a quota was used up
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotaExceeded {
+    pub scope: QuotaScope,
+    /// API key, user or role
+    pub key: String,
+    pub limit: QuotaLimit,
+    pub allowed: f64,
+    pub used: f64,
+    /// When the quota resets, for call quotas
+    pub resets_at: Option<DateTime<Utc>>,
+}
+
+impl QuotaExceeded {
+    /// Tool error describing the quota that was used up
+    pub fn into_agent_result(self) -> AgentResult {
+        let what = match self.limit {
+            QuotaLimit::CallsPerHour => "calls this hour",
+            QuotaLimit::CallsPerDay => "calls today",
+            QuotaLimit::Cost => "cost",
+        };
+        let mut error = format!(
+            "Quota exceeded: {} '{}' has used {} of its {} {}",
+            self.scope.describe(), self.key, self.used, self.allowed, what
+        );
+        if let Some(resets_at) = self.resets_at {
+            error.push_str(&format!("; it resets at {}", resets_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)));
+        }
+        AgentResult {
+            success: false,
+            data: None,
+            error: Some(error),
+            metadata: Some(json!({
+                "error_category": "quota_exceeded",
+                "quota": {
+                    "scope": self.scope,
+                    "key": self.key,
+                    "limit": self.limit,
+                    "allowed": self.allowed,
+                    "used": self.used,
+                    "resets_at": self.resets_at,
+                },
+            })),
+        }
+    }
+}
+
+/// Usage of one API key, user or role
+#[derive(Debug, Clone, Default)]
+struct Usage {
+    hour: Option<DateTime<Utc>>,
+    calls_this_hour: u64,
+    day: Option<DateTime<Utc>>,
+    calls_today: u64,
+    calls: u64,
+    cost: f64,
+    rejected: u64,
+}
+
+impl Usage {
+    /// Start the hourly and daily counts again once their period has passed
+    fn roll(&mut self, now: DateTime<Utc>) {
+        let (hour, day) = periods(now);
+        if self.hour != Some(hour) {
+            self.hour = Some(hour);
+            self.calls_this_hour = 0;
+        }
+        if self.day != Some(day) {
+            self.day = Some(day);
+            self.calls_today = 0;
+        }
+    }
+
+    fn exceeded(&self, limits: &QuotaLimits, now: DateTime<Utc>) -> Option<(QuotaLimit, f64, f64, Option<DateTime<Utc>>)> {
+        let (hour, day) = periods(now);
+        if let Some(allowed) = limits.calls_per_hour.filter(|allowed| self.calls_this_hour >= *allowed) {
+            return Some((QuotaLimit::CallsPerHour, allowed as f64, self.calls_this_hour as f64, Some(hour + Duration::hours(1))));
+        }
+        if let Some(allowed) = limits.calls_per_day.filter(|allowed| self.calls_today >= *allowed) {
+            return Some((QuotaLimit::CallsPerDay, allowed as f64, self.calls_today as f64, Some(day + Duration::days(1))));
+        }
+        if let Some(allowed) = limits.cost.filter(|allowed| self.cost >= *allowed) {
+            return Some((QuotaLimit::Cost, allowed, round(self.cost), None));
+        }
+        None
+    }
+}
+
+/// Start of the UTC hour and day `now` falls in
+fn periods(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let hour = now.duration_trunc(Duration::hours(1)).unwrap_or(now);
+    let day = now.duration_trunc(Duration::days(1)).unwrap_or(now);
+    (hour, day)
+}
+
+/// Quota state of one API key, user or role, as reported by the dashboard
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuotaReport {
+    pub scope: QuotaScope,
+    pub key: String,
+    pub calls_this_hour: u64,
+    pub calls_today: u64,
+    /// Calls since the server started
+    pub calls: u64,
+    pub cost: f64,
+    /// Calls refused because a quota was used up
+    pub rejected: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limits: Option<QuotaLimits>,
+    pub exceeded: bool,
+}
+
+/// Counts calls and cost per API key, user and role against their quotas
+#[derive(Default)]
+pub struct QuotaTracker {
+    config: RwLock<QuotasConfig>,
+    usage: Mutex<HashMap<(QuotaScope, String), Usage>>,
+}
+
+impl std::fmt::Debug for QuotaTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QuotaTracker").finish_non_exhaustive()
+    }
+}
+
+impl QuotaTracker {
+    pub fn new(config: QuotasConfig) -> Self {
+        Self { config: RwLock::new(config), usage: Mutex::default() }
+    }
+
+    /// Replace the quotas; usage so far is kept
+    pub fn configure(&self, config: QuotasConfig) {
+        if let Ok(mut current) = self.config.write() {
+            *current = config;
+        }
+    }
+
+    /// Count a call by `subjects`, unless one of their quotas is used up
+    pub fn admit(&self, subjects: &QuotaSubjects) -> std::result::Result<(), QuotaExceeded> {
+        self.admit_at(subjects, Utc::now())
+    }
+
+    fn admit_at(&self, subjects: &QuotaSubjects, now: DateTime<Utc>) -> std::result::Result<(), QuotaExceeded> {
+        let Ok(config) = self.config.read() else {
+            return Ok(());
+        };
+        let Ok(mut usage) = self.usage.lock() else {
+            return Ok(());
+        };
+        for (scope, key) in subjects.keys() {
+            let Some(limits) = config.limits(scope, key) else { continue };
+            let entry = usage.entry((scope, key.to_string())).or_default();
+            entry.roll(now);
+            if let Some((limit, allowed, used, resets_at)) = entry.exceeded(limits, now) {
+                entry.rejected += 1;
+                warn!(target: "audit", scope = ?scope, key = %key, limit = ?limit, "Quota exceeded");
+                return Err(QuotaExceeded { scope, key: key.to_string(), limit, allowed, used, resets_at });
+            }
+        }
+        for (scope, key) in subjects.keys() {
+            let entry = usage.entry((scope, key.to_string())).or_default();
+            entry.roll(now);
+            entry.calls_this_hour += 1;
+            entry.calls_today += 1;
+            entry.calls += 1;
+        }
+        Ok(())
+    }
+
+    /// Add the cost of a call to the usage of `subjects`
+    pub fn charge(&self, subjects: &QuotaSubjects, amount: f64) {
+        if let Ok(mut usage) = self.usage.lock() {
+            for (scope, key) in subjects.keys() {
+                usage.entry((scope, key.to_string())).or_default().cost += amount;
+            }
+        }
+    }
+
+    /// Quota state of every API key, user and role that made calls
+    pub fn report(&self) -> Vec<QuotaReport> {
+        let config = self.config.read().map(|config| config.clone()).unwrap_or_default();
+        let Ok(mut usage) = self.usage.lock() else {
+            return Vec::new();
+        };
+        let now = Utc::now();
+        let mut report: Vec<QuotaReport> = usage.iter_mut().map(|((scope, key), usage)| {
+            usage.roll(now);
+            let limits = config.limits(*scope, key).cloned();
+            QuotaReport {
+                scope: *scope,
+                key: key.clone(),
+                calls_this_hour: usage.calls_this_hour,
+                calls_today: usage.calls_today,
+                calls: usage.calls,
+                cost: round(usage.cost),
+                rejected: usage.rejected,
+                exceeded: limits.as_ref().is_some_and(|limits| usage.exceeded(limits, now).is_some()),
+                limits,
+            }
+        }).collect();
+        report.sort_by(|a, b| (a.scope, &a.key).cmp(&(b.scope, &b.key)));
+        report
+    }
+}
+
+/// Hide floating point noise from accumulated cost
+fn round(amount: f64) -> f64 {
+    (amount * 1e6).round() / 1e6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn tracker() -> QuotaTracker {
+        QuotaTracker::new(serde_yaml::from_str(r#"
+api_keys:
+  ci-bot: { calls_per_hour: 2 }
+default_user: { calls_per_day: 3 }
+roles:
+  contractors: { cost: 1.0 }
+"#).unwrap())
+    }
+
+    fn subjects(api_key: &str, user: &str, roles: &[&str]) -> QuotaSubjects {
+        QuotaSubjects {
+            api_key: Some(api_key.to_string()),
+            user: Some(user.to_string()),
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_hourly_quota_resets() {
+        let tracker = tracker();
+        let ci = subjects("ci-bot", "svc", &[]);
+        let at = |h: u32, m: u32| Utc.with_ymd_and_hms(2026, 10, 15, h, m, 0).unwrap();
+
+        assert!(tracker.admit_at(&ci, at(9, 0)).is_ok());
+        assert!(tracker.admit_at(&ci, at(9, 10)).is_ok());
+        let exceeded = tracker.admit_at(&ci, at(9, 59)).unwrap_err();
+        assert_eq!((exceeded.scope, exceeded.limit), (QuotaScope::ApiKey, QuotaLimit::CallsPerHour));
+        assert_eq!(exceeded.resets_at, Some(at(10, 0)));
+        // The user's daily quota counted the admitted calls only
+        assert!(tracker.admit_at(&ci, at(10, 0)).is_ok());
+        let exceeded = tracker.admit_at(&ci, at(10, 1)).unwrap_err();
+        assert_eq!((exceeded.scope, exceeded.limit), (QuotaScope::User, QuotaLimit::CallsPerDay));
+
+        let result = exceeded.into_agent_result();
+        assert_eq!(result.metadata.unwrap()["error_category"], "quota_exceeded");
+    }
+
+    #[test]
+    fn test_role_cost_quota_is_shared() {
+        let tracker = tracker();
+        let alice = subjects("dashboard", "alice", &["contractors"]);
+        let bob = subjects("dashboard", "bob", &["contractors"]);
+
+        assert!(tracker.admit(&alice).is_ok());
+        tracker.charge(&alice, 1.0);
+        let exceeded = tracker.admit(&bob).unwrap_err();
+        assert_eq!((exceeded.scope, exceeded.key.as_str(), exceeded.limit), (QuotaScope::Role, "contractors", QuotaLimit::Cost));
+        assert_eq!(exceeded.resets_at, None);
+
+        let report = tracker.report();
+        let role = report.iter().find(|report| report.scope == QuotaScope::Role).unwrap();
+        assert_eq!((role.calls, role.cost, role.rejected, role.exceeded), (1, 1.0, 1, true));
+    }
+}
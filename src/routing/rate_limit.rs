@@ -0,0 +1,347 @@
+//! Token-bucket rate limits on tool calls
+//!
+//! Limits can be set per tool, per API key (by key name), for guests and per
+//! external MCP server; a call must fit within every limit that applies to it:
+//!
+//! ```yaml
+//! rate_limiting:
+//!   tools:
+//!     search_web: { requests_per_minute: 30, burst: 5 }
+//!   api_keys:
+//!     ci-bot: { requests_per_minute: 120 }
+//!   default_api_key: { requests_per_minute: 600 }   # keys not listed above
+//!   guest: { requests_per_minute: 10 }              # shared by all guests
+//!   servers:
+//!     github: { requests_per_minute: 60 }
+//! ```
+//!
+//! Calls over a limit are not executed; they fail with a tool error carrying
+//! the limit that was hit and how long to wait before retrying.
+
+use crate::error::{ProxyError, Result};
+use crate::routing::types::AgentResult;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Client name calls made with guest access are attributed to
+pub const GUEST_CLIENT: &str = "guest";
+
+tokio::task_local! {
+    static CLIENT: String;
+}
+
+/// Run `future` with its tool calls attributed to the API key named `client`
+pub async fn as_client<F: Future>(client: Option<String>, future: F) -> F::Output {
+    match client {
+        Some(client) => CLIENT.scope(client, future).await,
+        None => future.await,
+    }
+}
+
+/// Name of the API key the current call was made with
+pub fn current_client() -> Option<String> {
+    CLIENT.try_with(Clone::clone).ok()
+}
+
+/// A token bucket refilled at `requests_per_minute`, holding up to `burst` calls
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub requests_per_minute: u32,
+    /// Calls allowed at once after a quiet period (default: `requests_per_minute`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+}
+
+impl RateLimit {
+    fn capacity(&self) -> f64 {
+        f64::from(self.burst.unwrap_or(self.requests_per_minute))
+    }
+
+    fn tokens_per_second(&self) -> f64 {
+        f64::from(self.requests_per_minute) / 60.0
+    }
+}
+
+/// Rate limits applied by the router
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitingConfig {
+    /// Limits by tool name
+    #[serde(default)]
+    pub tools: HashMap<String, RateLimit>,
+    /// Limits by API key name
+    #[serde(default)]
+    pub api_keys: HashMap<String, RateLimit>,
+    /// Limit for each API key not listed in `api_keys`
+    #[serde(default)]
+    pub default_api_key: Option<RateLimit>,
+    /// Limit shared by all guests; API key limits do not apply to them
+    #[serde(default)]
+    pub guest: Option<RateLimit>,
+    /// Limits by external MCP server name, shared by all of its tools
+    #[serde(default)]
+    pub servers: HashMap<String, RateLimit>,
+}
+
+impl RateLimitingConfig {
+    pub fn validate(&self) -> Result<()> {
+        let limits = self.tools.iter().map(|(name, limit)| ("tool", name.as_str(), limit))
+            .chain(self.api_keys.iter().map(|(name, limit)| ("API key", name.as_str(), limit)))
+            .chain(self.default_api_key.iter().map(|limit| ("API key", "default", limit)))
+            .chain(self.guest.iter().map(|limit| ("guest access", GUEST_CLIENT, limit)))
+            .chain(self.servers.iter().map(|(name, limit)| ("server", name.as_str(), limit)));
+        for (kind, name, limit) in limits {
+            if limit.requests_per_minute == 0 || limit.burst == Some(0) {
+                return Err(ProxyError::config(format!(
+                    "Rate limit for {} '{}' must allow at least one request", kind, name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a limit applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitScope {
+    Tool,
+    ApiKey,
+    Guest,
+    Server,
+}
+
+/// A call rejected because a limit was exhausted
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimited {
+    pub scope: LimitScope,
+    /// Tool, API key or server name
+    pub key: String,
+    pub limit: RateLimit,
+    /// When a call would next be allowed under this limit
+    pub retry_after: Duration,
+}
+
+impl RateLimited {
+    /// Tool error describing the limit and when to retry
+    pub fn into_agent_result(self) -> AgentResult {
+        let retry_after_secs = self.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        let subject = match self.scope {
+            LimitScope::Tool => "tool",
+            LimitScope::ApiKey => "API key",
+            LimitScope::Guest => "guest access",
+            LimitScope::Server => "server",
+        };
+        AgentResult {
+            success: false,
+            data: None,
+            error: Some(format!(
+                "Rate limited: {} '{}' allows {} requests per minute, retry after {}s",
+                subject, self.key, self.limit.requests_per_minute, retry_after_secs
+            )),
+            metadata: Some(json!({
+                "error_category": "rate_limited",
+                "retry_after_seconds": retry_after_secs,
+                "rate_limit": {
+                    "scope": self.scope,
+                    "key": self.key,
+                    "requests_per_minute": self.limit.requests_per_minute,
+                    "burst": self.limit.capacity() as u32,
+                },
+            })),
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.tokens_per_second()).min(limit.capacity());
+        self.refilled_at = now;
+    }
+
+    fn retry_after(&self, limit: &RateLimit) -> Option<Duration> {
+        (self.tokens < 1.0).then(|| Duration::from_secs_f64((1.0 - self.tokens) / limit.tokens_per_second()))
+    }
+}
+
+/// Buckets for every limited tool, API key and server
+#[derive(Default)]
+pub struct RateLimiter {
+    config: RwLock<RateLimitingConfig>,
+    buckets: Mutex<HashMap<(LimitScope, String), Bucket>>,
+}
+
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimiter").finish_non_exhaustive()
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitingConfig) -> Self {
+        Self { config: RwLock::new(config), buckets: Mutex::default() }
+    }
+
+    /// Replace the limits, starting every bucket full
+    pub fn configure(&self, config: RateLimitingConfig) {
+        if let Ok(mut current) = self.config.write() {
+            *current = config;
+        }
+        if let Ok(mut buckets) = self.buckets.lock() {
+            buckets.clear();
+        }
+    }
+
+    /// Take a token from every limit that applies to a call, or none if any is exhausted
+    ///
+    /// When several limits are exhausted, the one that frees up last is reported.
+    pub fn check(&self, tool: &str, api_key: Option<&str>, server: Option<&str>) -> std::result::Result<(), RateLimited> {
+        self.check_limits(Some(tool), api_key, server)
+    }
+
+    /// Take a token from a server's limit only
+    pub fn check_server(&self, server: &str) -> std::result::Result<(), RateLimited> {
+        self.check_limits(None, None, Some(server))
+    }
+
+    fn check_limits(&self, tool: Option<&str>, api_key: Option<&str>, server: Option<&str>) -> std::result::Result<(), RateLimited> {
+        let Ok(config) = self.config.read() else {
+            return Ok(());
+        };
+        let mut limits = Vec::new();
+        if let Some(tool) = tool {
+            if let Some(limit) = config.tools.get(tool) {
+                limits.push((LimitScope::Tool, tool, *limit));
+            }
+        }
+        if api_key == Some(GUEST_CLIENT) {
+            if let Some(limit) = config.guest {
+                limits.push((LimitScope::Guest, GUEST_CLIENT, limit));
+            }
+        } else if let Some(api_key) = api_key {
+            if let Some(limit) = config.api_keys.get(api_key).or(config.default_api_key.as_ref()) {
+                limits.push((LimitScope::ApiKey, api_key, *limit));
+            }
+        }
+        if let Some(server) = server {
+            if let Some(limit) = config.servers.get(server) {
+                limits.push((LimitScope::Server, server, *limit));
+            }
+        }
+        if limits.is_empty() {
+            return Ok(());
+        }
+
+        let Ok(mut buckets) = self.buckets.lock() else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        let mut exhausted: Option<RateLimited> = None;
+        for (scope, key, limit) in &limits {
+            let bucket = buckets.entry((*scope, key.to_string()))
+                .or_insert_with(|| Bucket { tokens: limit.capacity(), refilled_at: now });
+            bucket.refill(limit, now);
+            if let Some(retry_after) = bucket.retry_after(limit) {
+                if exhausted.as_ref().map_or(true, |e| retry_after > e.retry_after) {
+                    exhausted = Some(RateLimited { scope: *scope, key: key.to_string(), limit: *limit, retry_after });
+                }
+            }
+        }
+        if let Some(exhausted) = exhausted {
+            return Err(exhausted);
+        }
+
+        for (scope, key, _) in &limits {
+            if let Some(bucket) = buckets.get_mut(&(*scope, key.to_string())) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(requests_per_minute: u32, burst: u32) -> RateLimit {
+        RateLimit { requests_per_minute, burst: Some(burst) }
+    }
+
+    #[test]
+    fn test_burst_then_refill() {
+        let limiter = RateLimiter::new(RateLimitingConfig {
+            tools: HashMap::from([("search".to_string(), limit(60, 2))]),
+            ..Default::default()
+        });
+        assert!(limiter.check("search", None, None).is_ok());
+        assert!(limiter.check("search", None, None).is_ok());
+
+        let limited = limiter.check("search", None, None).unwrap_err();
+        assert_eq!(limited.scope, LimitScope::Tool);
+        assert!(limited.retry_after <= Duration::from_secs(1));
+        assert!(limiter.check("other", None, None).is_ok());
+
+        std::thread::sleep(Duration::from_millis(1100));
+        assert!(limiter.check("search", None, None).is_ok());
+    }
+
+    #[test]
+    fn test_rejected_calls_take_no_tokens() {
+        let limiter = RateLimiter::new(RateLimitingConfig {
+            default_api_key: Some(limit(60, 1)),
+            servers: HashMap::from([("github".to_string(), limit(60, 2))]),
+            ..Default::default()
+        });
+        assert!(limiter.check("issues", Some("ci"), Some("github")).is_ok());
+        let limited = limiter.check("issues", Some("ci"), Some("github")).unwrap_err();
+        assert_eq!((limited.scope, limited.key.as_str()), (LimitScope::ApiKey, "ci"));
+
+        // The rejected call left the server's second token for another key
+        assert!(limiter.check("issues", Some("dashboard"), Some("github")).is_ok());
+        assert_eq!(limiter.check("pulls", None, Some("github")).unwrap_err().scope, LimitScope::Server);
+    }
+
+    #[test]
+    fn test_guests_share_their_own_limit() {
+        let limiter = RateLimiter::new(RateLimitingConfig {
+            default_api_key: Some(limit(60, 5)),
+            guest: Some(limit(60, 1)),
+            ..Default::default()
+        });
+        assert!(limiter.check("docs_search", Some(GUEST_CLIENT), None).is_ok());
+        let limited = limiter.check("docs_search", Some(GUEST_CLIENT), None).unwrap_err();
+        assert_eq!((limited.scope, limited.key.as_str()), (LimitScope::Guest, GUEST_CLIENT));
+        assert!(limiter.check("docs_search", Some("ci"), None).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limited_result() {
+        let result = RateLimited {
+            scope: LimitScope::Server,
+            key: "github".to_string(),
+            limit: limit(60, 10),
+            retry_after: Duration::from_millis(1500),
+        }.into_agent_result();
+        assert!(!result.success);
+        let metadata = result.metadata.unwrap();
+        assert_eq!(metadata["error_category"], "rate_limited");
+        assert_eq!(metadata["retry_after_seconds"], 2);
+        assert_eq!(metadata["rate_limit"]["scope"], "server");
+    }
+
+    #[tokio::test]
+    async fn test_client_scope() {
+        assert_eq!(current_client(), None);
+        assert_eq!(as_client(Some("ci".to_string()), async { current_client() }).await, Some("ci".to_string()));
+    }
+}
@@ -1,15 +1,39 @@
 //! Retry logic for failed tool calls with configurable policies
+//!
+//! Besides the per-agent-type policies of the enhanced router, a tool can set
+//! its own policy in its routing config. It only applies to tools annotated
+//! `readOnlyHint` or `idempotentHint`, so calls with side effects are never
+//! run twice:
+//!
+//! ```yaml
+//! routing:
+//!   type: http
+//!   config:
+//!     method: GET
+//!     url: "https://rates.internal/latest"
+//!     retry:
+//!       max_attempts: 4
+//!       initial_delay_ms: 200
+//!       max_elapsed_ms: 5000
+//! annotations:
+//!   idempotentHint: "true"
+//! ```
 
 use crate::error::{ProxyError, Result};
 use crate::routing::types::{AgentResult, AgentType};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tracing::{debug, warn, error};
 
+/// Key of the per-tool retry policy in a routing config
+pub const CONFIG_KEY: &str = "retry";
+
 /// Retry policy configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RetryPolicy {
     /// Maximum number of retry attempts
     pub max_attempts: u32,
@@ -21,6 +45,9 @@ pub struct RetryPolicy {
     pub max_delay_ms: u64,
     /// Whether to use jitter to avoid thundering herd
     pub use_jitter: bool,
+    /// Stop retrying once this much time has passed since the first attempt (in milliseconds)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_elapsed_ms: Option<u64>,
 }
 
 impl Default for RetryPolicy {
@@ -31,6 +58,7 @@ impl Default for RetryPolicy {
             backoff_multiplier: 2.0,
             max_delay_ms: 30000,
             use_jitter: true,
+            max_elapsed_ms: None,
         }
     }
 }
@@ -44,6 +72,7 @@ impl RetryPolicy {
             backoff_multiplier,
             max_delay_ms: 30000,
             use_jitter: true,
+            max_elapsed_ms: None,
         }
     }
 
@@ -55,6 +84,7 @@ impl RetryPolicy {
             backoff_multiplier: 3.0,
             max_delay_ms: 60000,
             use_jitter: true,
+            max_elapsed_ms: None,
         }
     }
 
@@ -66,6 +96,41 @@ impl RetryPolicy {
             backoff_multiplier: 1.5,
             max_delay_ms: 15000,
             use_jitter: true,
+            max_elapsed_ms: None,
+        }
+    }
+
+    /// The retry policy of a routing config, if it has one
+    pub fn from_routing(config: &Value) -> Result<Option<Self>> {
+        let Some(section) = config.get(CONFIG_KEY) else {
+            return Ok(None);
+        };
+        let policy: Self = serde_json::from_value(section.clone())
+            .map_err(|e| ProxyError::validation(format!("Invalid retry config: {}", e)))?;
+        policy.validate()?;
+        Ok(Some(policy))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.max_attempts == 0 {
+            return Err(ProxyError::validation("retry max_attempts must be at least 1"));
+        }
+        if self.backoff_multiplier.is_nan() || self.backoff_multiplier < 1.0 {
+            return Err(ProxyError::validation("retry backoff_multiplier must be at least 1"));
+        }
+        Ok(())
+    }
+
+    /// Delay before retrying after attempt number `attempt` failed, or `None`
+    /// if attempts or the elapsed time budget are used up
+    pub fn next_delay(&self, attempt: u32, elapsed: Duration) -> Option<Duration> {
+        if attempt + 1 >= self.max_attempts {
+            return None;
+        }
+        let delay = self.calculate_delay(attempt);
+        match self.max_elapsed_ms {
+            Some(max_elapsed_ms) if elapsed + delay > Duration::from_millis(max_elapsed_ms) => None,
+            _ => Some(delay),
         }
     }
 
@@ -176,6 +241,45 @@ pub fn should_retry_error(error: &ProxyError) -> bool {
     }
 }
 
+/// Whether a failed result reports an error worth retrying
+pub fn should_retry_result(result: &AgentResult) -> bool {
+    !result.success && result.error.as_ref().map_or(false, |error| should_retry_error(&ProxyError::routing(error.clone())))
+}
+
+/// Run `operation` until it succeeds, fails with an error that isn't worth
+/// retrying, or `policy` gives up
+///
+/// Unlike [`RetryExecutor`], failed results are retried as well as errors.
+/// Returns the last outcome and the number of attempts made.
+pub async fn retry_with_policy<F, Fut>(policy: &RetryPolicy, operation_name: &str, operation: F) -> (Result<AgentResult>, u32)
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Result<AgentResult>>,
+{
+    let started = Instant::now();
+    let mut attempt = 0;
+    loop {
+        let outcome = operation().await;
+        let retryable = match &outcome {
+            Ok(result) => should_retry_result(result),
+            Err(error) => should_retry_error(error),
+        };
+        let delay = if retryable { policy.next_delay(attempt, started.elapsed()) } else { None };
+        let Some(delay) = delay else {
+            return (outcome, attempt + 1);
+        };
+
+        warn!(
+            operation = operation_name,
+            attempt = attempt + 1,
+            delay_ms = delay.as_millis(),
+            "Operation failed, retrying after delay"
+        );
+        sleep(delay).await;
+        attempt += 1;
+    }
+}
+
 /// Retry executor that handles the retry logic
 pub struct RetryExecutor {
     config: RetryConfig,
@@ -206,7 +310,7 @@ impl RetryExecutor {
         Fut: std::future::Future<Output = Result<AgentResult>>,
     {
         let agent_type_name = agent_type.type_name();
-        let policy = self.config.get_policy(&agent_type_name);
+        let policy = self.config.get_policy(agent_type_name);
         
         debug!(
             operation = operation_name,
@@ -215,6 +319,7 @@ impl RetryExecutor {
             "Starting operation with retry logic"
         );
 
+        let started = Instant::now();
         let mut last_error = None;
         
         for attempt in 0..policy.max_attempts {
@@ -245,8 +350,8 @@ impl RetryExecutor {
                         return Err(error);
                     }
                     
-                    // Check if we have more attempts left
-                    if attempt + 1 >= policy.max_attempts {
+                    // Check if we have more attempts and time left
+                    let Some(delay) = policy.next_delay(attempt, started.elapsed()) else {
                         error!(
                             operation = operation_name,
                             agent_type = agent_type_name,
@@ -255,10 +360,9 @@ impl RetryExecutor {
                             "All retry attempts exhausted, failing"
                         );
                         return Err(error);
-                    }
+                    };
                     
-                    // Calculate delay and wait
-                    let delay = policy.calculate_delay(attempt);
+                    // Wait before the next attempt
                     warn!(
                         operation = operation_name,
                         agent_type = agent_type_name,
@@ -284,25 +388,6 @@ impl Default for RetryExecutor {
     }
 }
 
-/// Extension trait for AgentType to get type name
-impl AgentType {
-    /// Get the string name of the agent type
-    pub fn type_name(&self) -> String {
-        match self {
-            AgentType::Subprocess { .. } => "subprocess".to_string(),
-            AgentType::Http { .. } => "http".to_string(),
-            AgentType::Llm { .. } => "llm".to_string(),
-            AgentType::WebSocket { .. } => "websocket".to_string(),
-            AgentType::Database { .. } => "database".to_string(),
-            AgentType::Grpc { .. } => "grpc".to_string(),
-            AgentType::Sse { .. } => "sse".to_string(),
-            AgentType::GraphQL { .. } => "graphql".to_string(),
-            AgentType::ExternalMcp { .. } => "external_mcp".to_string(),
-            AgentType::SmartDiscovery { .. } => "smart_discovery".to_string(),
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -343,6 +428,44 @@ mod tests {
         assert!(!should_retry_error(&ProxyError::config("invalid config".to_string())));
     }
 
+    #[test]
+    fn test_next_delay_respects_elapsed_budget() {
+        let policy = RetryPolicy { use_jitter: false, max_elapsed_ms: Some(2500), ..RetryPolicy::new(5, 1000, 2.0) };
+        assert_eq!(policy.next_delay(0, Duration::ZERO), Some(Duration::from_millis(1000)));
+        assert_eq!(policy.next_delay(1, Duration::from_millis(1000)), None);
+        assert_eq!(policy.next_delay(4, Duration::ZERO), None);
+
+        assert!(RetryPolicy::from_routing(&serde_json::json!({"url": "http://a"})).unwrap().is_none());
+        let policy = RetryPolicy::from_routing(&serde_json::json!({"retry": {"max_attempts": 4}})).unwrap().unwrap();
+        assert_eq!((policy.max_attempts, policy.initial_delay_ms), (4, 1000));
+        assert!(RetryPolicy::from_routing(&serde_json::json!({"retry": {"max_attempts": 0}})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_policy_retries_failed_results() {
+        let policy = RetryPolicy { use_jitter: false, ..RetryPolicy::new(3, 1, 1.0) };
+        let attempt_count = &AtomicU32::new(0);
+
+        let (result, attempts) = retry_with_policy(&policy, "test_operation", || async move {
+            let count = attempt_count.fetch_add(1, Ordering::SeqCst);
+            Ok(AgentResult {
+                success: count > 0,
+                data: None,
+                error: (count == 0).then(|| "HTTP 503 Service Unavailable".to_string()),
+                metadata: None,
+            })
+        }).await;
+        assert!(result.unwrap().success);
+        assert_eq!(attempts, 2);
+
+        // Failures that aren't worth retrying are returned at once
+        let (result, attempts) = retry_with_policy(&policy, "test_operation", || async {
+            Ok(AgentResult { success: false, data: None, error: Some("HTTP 404 Not Found".to_string()), metadata: None })
+        }).await;
+        assert!(!result.unwrap().success);
+        assert_eq!(attempts, 1);
+    }
+
     #[tokio::test]
     async fn test_retry_executor_success_on_first_attempt() {
         let executor = RetryExecutor::new();
@@ -351,6 +474,9 @@ mod tests {
             url: "http://example.com".to_string(),
             headers: None,
             timeout: None,
+            aws_sigv4: None,
+            token_exchange: None,
+            params: Default::default(),
         };
 
         let result = executor.execute_with_retry(&agent, "test_operation", || async {
@@ -374,6 +500,9 @@ mod tests {
             url: "http://example.com".to_string(),
             headers: None,
             timeout: None,
+            aws_sigv4: None,
+            token_exchange: None,
+            params: Default::default(),
         };
 
         let attempt_count = Arc::new(AtomicU32::new(0));
@@ -410,6 +539,9 @@ mod tests {
             url: "http://example.com".to_string(),
             headers: None,
             timeout: None,
+            aws_sigv4: None,
+            token_exchange: None,
+            params: Default::default(),
         };
 
         let attempt_count = Arc::new(AtomicU32::new(0));
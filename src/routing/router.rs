@@ -1,18 +1,67 @@
 //! Router implementation for directing tool calls to agents
 
-use crate::error::Result;
+use crate::error::{ProxyError, Result};
 use crate::mcp::ToolCall;
-use crate::registry::ToolDefinition;
-use crate::routing::{AgentRouter, DefaultAgentRouter, EnhancedRouterBuilder};
-use crate::routing::types::AgentResult;
-use std::sync::Arc;
-use tracing::debug;
+use crate::registry::{RoutingConfig, ToolCacheConfig, ToolDefinition, DEFAULT_CACHE_MAX_ENTRIES};
+use crate::routing::{AgentRouter, DefaultAgentRouter, EnhancedRouterBuilder, MiddlewareChain, MiddlewareContext};
+use crate::routing::cache::{self, ToolResultCache};
+use crate::routing::approval::ApprovalManager;
+use crate::routing::canary::{CanaryMetrics, CanarySplitter, Variant};
+use crate::routing::cost::{self, BudgetExceeded, CostTracker, Spender};
+use crate::routing::dry_run::{self, DryRunConfig};
+use crate::routing::load_balancer::{LoadBalancer, LoadBalancingConfig};
+use crate::routing::access_window::AccessWindows;
+use crate::routing::pii::PiiMasker;
+use crate::routing::quota::{QuotaExceeded, QuotaSubjects, QuotaTracker};
+use crate::routing::policy::PolicyEngine;
+use crate::routing::rate_limit::{self, RateLimited, RateLimiter};
+use crate::routing::retry::{self, RetryPolicy};
+use crate::routing::secret_scan::SecretScanner;
+use crate::routing::threat_detection::{ThreatDetector, Throttled};
+use crate::routing::timeout::TimeoutConfig;
+use crate::routing::transform::{self, TransformConfig};
+use crate::routing::types::{AgentResult, AgentType};
+use std::borrow::Cow;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use serde_json::json;
+use tracing::{debug, info, warn};
 
 /// Router that directs tool calls to appropriate agents
 #[derive(Clone)]
 pub struct Router {
     /// The underlying agent router implementation
     agent_router: Arc<dyn AgentRouter>,
+    /// Results of tools with a `cache` section
+    cache: Arc<ToolResultCache>,
+    /// Per-tool, per-API-key and per-server rate limits
+    rate_limiter: Arc<RateLimiter>,
+    /// Endpoint selection for tools with a `load_balancing` section
+    load_balancer: Arc<LoadBalancer>,
+    /// Traffic split and variant metrics for routings with a `canary` section
+    canary: Arc<CanarySplitter>,
+    /// Timeout hierarchy applied to agents
+    timeouts: Arc<RwLock<TimeoutConfig>>,
+    /// Which tools are always dry-run
+    dry_run: Arc<RwLock<DryRunConfig>>,
+    /// Spend per tool, API key, user and session, and its budgets
+    costs: Arc<CostTracker>,
+    /// Calls and cost per API key, user and role, and their quotas
+    quotas: Arc<QuotaTracker>,
+    /// Baseline behavior per API key and user, and calls deviating from it
+    threats: Arc<ThreatDetector>,
+    /// Time windows tools may be called in, if configured
+    access_windows: Arc<RwLock<Option<Arc<AccessWindows>>>>,
+    /// Policies authorizing tool calls, if configured
+    policy: Arc<RwLock<Option<Arc<PolicyEngine>>>>,
+    /// Redaction of secrets in arguments and results, if configured
+    secret_scanner: Arc<RwLock<Option<Arc<SecretScanner>>>>,
+    /// Per-tool handling of personal data in arguments and results, if configured
+    pii_masker: Arc<RwLock<Option<Arc<PiiMasker>>>>,
+    /// Calls parked until an approver decides on them
+    approvals: Arc<ApprovalManager>,
+    /// Middleware run around each agent execution, such as circuit breakers
+    middleware: Arc<RwLock<Arc<MiddlewareChain>>>,
 }
 
 impl Router {
@@ -20,6 +69,21 @@ impl Router {
     pub fn new() -> Self {
         Self {
             agent_router: Arc::new(DefaultAgentRouter::new()),
+            cache: Arc::default(),
+            rate_limiter: Arc::default(),
+            load_balancer: Arc::default(),
+            canary: Arc::default(),
+            timeouts: Arc::default(),
+            dry_run: Arc::default(),
+            costs: Arc::default(),
+            quotas: Arc::default(),
+            threats: Arc::default(),
+            access_windows: Arc::default(),
+            policy: Arc::default(),
+            secret_scanner: Arc::default(),
+            pii_masker: Arc::default(),
+            approvals: Arc::default(),
+            middleware: Arc::default(),
         }
     }
 
@@ -29,6 +93,21 @@ impl Router {
     ) -> Self {
         Self {
             agent_router: Arc::new(DefaultAgentRouter::new().with_external_mcp(external_mcp)),
+            cache: Arc::default(),
+            rate_limiter: Arc::default(),
+            load_balancer: Arc::default(),
+            canary: Arc::default(),
+            timeouts: Arc::default(),
+            dry_run: Arc::default(),
+            costs: Arc::default(),
+            quotas: Arc::default(),
+            threats: Arc::default(),
+            access_windows: Arc::default(),
+            policy: Arc::default(),
+            secret_scanner: Arc::default(),
+            pii_masker: Arc::default(),
+            approvals: Arc::default(),
+            middleware: Arc::default(),
         }
     }
 
@@ -36,6 +115,21 @@ impl Router {
     pub fn with_registry(registry: Arc<crate::registry::RegistryService>) -> Self {
         Self {
             agent_router: Arc::new(DefaultAgentRouter::new().with_registry(registry)),
+            cache: Arc::default(),
+            rate_limiter: Arc::default(),
+            load_balancer: Arc::default(),
+            canary: Arc::default(),
+            timeouts: Arc::default(),
+            dry_run: Arc::default(),
+            costs: Arc::default(),
+            quotas: Arc::default(),
+            threats: Arc::default(),
+            access_windows: Arc::default(),
+            policy: Arc::default(),
+            secret_scanner: Arc::default(),
+            pii_masker: Arc::default(),
+            approvals: Arc::default(),
+            middleware: Arc::default(),
         }
     }
 
@@ -48,6 +142,21 @@ impl Router {
             agent_router: Arc::new(DefaultAgentRouter::new()
                 .with_external_mcp(external_mcp)
                 .with_registry(registry)),
+            cache: Arc::default(),
+            rate_limiter: Arc::default(),
+            load_balancer: Arc::default(),
+            canary: Arc::default(),
+            timeouts: Arc::default(),
+            dry_run: Arc::default(),
+            costs: Arc::default(),
+            quotas: Arc::default(),
+            threats: Arc::default(),
+            access_windows: Arc::default(),
+            policy: Arc::default(),
+            secret_scanner: Arc::default(),
+            pii_masker: Arc::default(),
+            approvals: Arc::default(),
+            middleware: Arc::default(),
         }
     }
 
@@ -60,6 +169,21 @@ impl Router {
             agent_router: Arc::new(DefaultAgentRouter::new()
                 .with_registry(registry)
                 .with_smart_discovery(smart_discovery)),
+            cache: Arc::default(),
+            rate_limiter: Arc::default(),
+            load_balancer: Arc::default(),
+            canary: Arc::default(),
+            timeouts: Arc::default(),
+            dry_run: Arc::default(),
+            costs: Arc::default(),
+            quotas: Arc::default(),
+            threats: Arc::default(),
+            access_windows: Arc::default(),
+            policy: Arc::default(),
+            secret_scanner: Arc::default(),
+            pii_masker: Arc::default(),
+            approvals: Arc::default(),
+            middleware: Arc::default(),
         }
     }
 
@@ -74,18 +198,560 @@ impl Router {
                 .with_external_mcp(external_mcp)
                 .with_registry(registry)
                 .with_smart_discovery(smart_discovery)),
+            cache: Arc::default(),
+            rate_limiter: Arc::default(),
+            load_balancer: Arc::default(),
+            canary: Arc::default(),
+            timeouts: Arc::default(),
+            dry_run: Arc::default(),
+            costs: Arc::default(),
+            quotas: Arc::default(),
+            threats: Arc::default(),
+            access_windows: Arc::default(),
+            policy: Arc::default(),
+            secret_scanner: Arc::default(),
+            pii_masker: Arc::default(),
+            approvals: Arc::default(),
+            middleware: Arc::default(),
         }
     }
 
     /// Create a new router with custom agent router
     pub fn with_agent_router(agent_router: Arc<dyn AgentRouter>) -> Self {
-        Self { agent_router }
+        Self {
+            agent_router,
+            cache: Arc::default(),
+            rate_limiter: Arc::default(),
+            load_balancer: Arc::default(),
+            canary: Arc::default(),
+            timeouts: Arc::default(),
+            dry_run: Arc::default(),
+            costs: Arc::default(),
+            quotas: Arc::default(),
+            threats: Arc::default(),
+            access_windows: Arc::default(),
+            policy: Arc::default(),
+            secret_scanner: Arc::default(),
+            pii_masker: Arc::default(),
+            approvals: Arc::default(),
+            middleware: Arc::default(),
+        }
     }
 
     /// Route a tool call to the appropriate agent
+    ///
+    /// A call goes through these stages in order; any of the first four
+    /// refuses it before anything else happens:
+    ///
+    /// 1. Calls outside the tool's access windows are refused.
+    /// 2. Calls the policies deny are refused.
+    /// 3. Callers throttled after anomalous activity are refused.
+    /// 4. Callers over one of their quotas are refused.
+    /// 5. With secret scanning, secrets are redacted from the arguments and,
+    ///    later, the result. With PII detection, the tool's action is applied
+    ///    to personal data in both.
+    /// 6. Dry runs return what would be executed without dispatching anything.
+    /// 7. Calls needing approval wait for an approver's decision.
+    /// 8. Tools with a `cache` section are served from the result cache while
+    ///    their entry is fresh, unless the call bypasses the cache.
+    /// 9. The call must fit within the configured rate limits.
+    /// 10. Tools with a `cost` are refused once the caller has spent a budget;
+    ///     each successful call is charged to the caller.
+    /// 11. A routing with a `canary` section sends a share of its calls to the
+    ///     canary routing, and when the routing fails its `fallback` routings
+    ///     are tried in order. Tools with a `load_balancing` section are sent
+    ///     to one of their endpoints.
+    /// 12. Agents run with the timeout resolved from the call, the tool, its
+    ///     agent type or the default, reported as `timeout` in the result
+    ///     metadata. Tools annotated idempotent or read-only are retried
+    ///     according to their routing's `retry` section.
     pub async fn route(&self, tool_call: &ToolCall, tool_def: &ToolDefinition) -> Result<AgentResult> {
         debug!("Routing tool call: {}", tool_call.name);
-        self.agent_router.route(tool_call, tool_def).await
+        let access_windows = self.access_windows.read().ok().and_then(|windows| windows.clone());
+        if let Some(access_windows) = access_windows {
+            if let Err(refusal) = access_windows.check(tool_call) {
+                return Ok(refusal.into_agent_result(tool_call));
+            }
+        }
+
+        let policy = self.policy.read().ok().and_then(|policy| policy.clone());
+        if let Some(policy) = policy {
+            let decision = policy.authorize(tool_call, tool_def).await;
+            if !decision.allow {
+                return Ok(decision.into_agent_result(tool_call));
+            }
+        }
+
+        let subjects = QuotaSubjects::current();
+        if let Err(throttled) = self.threats.inspect(tool_call, tool_def, &subjects) {
+            return Ok(Self::throttled(tool_call, throttled));
+        }
+
+        if let Err(exceeded) = self.quotas.admit(&subjects) {
+            return Ok(Self::quota_exceeded(tool_call, exceeded));
+        }
+
+        let scanner = self.secret_scanner.read().ok().and_then(|scanner| scanner.clone());
+        let masker = self.pii_masker.read().ok().and_then(|masker| masker.clone());
+        if scanner.is_none() && masker.is_none() {
+            return self.route_call(tool_call, tool_def).await;
+        }
+
+        let mut tool_call = tool_call.clone();
+        let mut secrets = Vec::new();
+        let mut pii = Vec::new();
+        if let Some(scanner) = &scanner {
+            (tool_call, secrets) = scanner.sanitize_call(&tool_call);
+        }
+        if let Some(masker) = &masker {
+            match masker.mask_call(&tool_call) {
+                Ok((masked, findings)) => (tool_call, pii) = (masked, findings),
+                Err(blocked) => return Ok(blocked.into_agent_result(&tool_call.name)),
+            }
+        }
+
+        let mut result = self.route_call(&tool_call, tool_def).await?;
+        if let Some(scanner) = &scanner {
+            let (sanitized, findings) = scanner.sanitize_result(&tool_call.name, result);
+            result = sanitized;
+            secrets.extend(findings);
+        }
+        if let Some(masker) = &masker {
+            match masker.mask_result(&tool_call.name, result) {
+                Ok((masked, findings)) => {
+                    result = masked;
+                    pii.extend(findings);
+                }
+                Err(blocked) => return Ok(blocked.into_agent_result(&tool_call.name)),
+            }
+        }
+        if !secrets.is_empty() {
+            result = with_metadata(result, "secrets_redacted", json!(secrets));
+        }
+        if !pii.is_empty() {
+            result = with_metadata(result, "pii", json!(pii));
+        }
+        Ok(result)
+    }
+
+    /// Route a call that passed the policies, with its secrets and personal data handled
+    async fn route_call(&self, tool_call: &ToolCall, tool_def: &ToolDefinition) -> Result<AgentResult> {
+        if self.is_dry_run(tool_def) {
+            return self.route_dry_run(tool_call, tool_def);
+        }
+
+        if let Some(reason) = self.approvals.requires_approval(tool_call, tool_def) {
+            let outcome = self.approvals.wait_for_approval(tool_call, reason).await;
+            if let Some(refusal) = outcome.refusal(tool_call) {
+                return Ok(refusal);
+            }
+        }
+
+        let bypassed = cache::is_bypassed();
+        let cache_subject = cache::current_subject(tool_def);
+        if tool_def.cache.is_some() && !bypassed {
+            if let Some(result) = self.cache.get(&tool_call.name, cache_subject.as_deref(), &tool_call.arguments) {
+                debug!("Serving cached result for tool: {}", tool_call.name);
+                return Ok(cache::with_cache_status(result, "hit"));
+            }
+        }
+
+        let client = rate_limit::current_client();
+        if let Err(limited) = self.rate_limiter.check(&tool_call.name, client.as_deref(), None) {
+            return Ok(Self::rate_limited(tool_call, limited));
+        }
+        if client.as_deref() == Some(rate_limit::GUEST_CLIENT) {
+            info!(target: "audit", guest = true, tool = %tool_call.name, "Guest tool call");
+        }
+
+        // Only tools with a cost are refused once a budget is spent
+        let spender = Spender::current();
+        if tool_def.cost.is_some() {
+            if let Err(exceeded) = self.costs.check(&spender) {
+                return Ok(Self::budget_exceeded(tool_call, exceeded));
+            }
+        }
+
+        let variant = tool_def.routing.canary.as_ref()
+            .map(|canary| (canary, self.canary.choose(&tool_call.name, canary)));
+        let primary = match variant {
+            Some((canary, Variant::Canary)) => canary.routing.as_ref(),
+            _ => &tool_def.routing,
+        };
+
+        let legs: Vec<&RoutingConfig> = std::iter::once(primary).chain(&tool_def.routing.fallback).collect();
+        let mut failures = Vec::new();
+        for (leg, routing) in legs.iter().enumerate() {
+            let is_last = leg + 1 == legs.len();
+            let outcome = if leg > 0 && routing.r#type == cache::FALLBACK_ROUTING_TYPE {
+                self.route_to_cache(tool_call, cache_subject.as_deref(), routing)
+            } else {
+                // Load balancing state is kept per routing
+                let balancer_key = match (leg, variant) {
+                    (0, Some((_, Variant::Canary))) => format!("{}#canary", tool_call.name),
+                    (0, _) => tool_call.name.clone(),
+                    (leg, _) => format!("{}#fallback{}", tool_call.name, leg),
+                };
+                let started = Instant::now();
+                let outcome = self.route_leg(tool_call, tool_def, &balancer_key, routing).await;
+                if let (0, Some((_, variant))) = (leg, variant) {
+                    let success = matches!(&outcome, Ok(result) if result.success);
+                    self.canary.record(&tool_call.name, variant, success, started.elapsed());
+                }
+                outcome
+            };
+            let result = match outcome {
+                Ok(result) if result.success || is_last => result,
+                Err(e) if is_last => return Err(e),
+                Ok(result) => {
+                    let error = result.error.unwrap_or_else(|| "Unknown error".to_string());
+                    warn!("Routing '{}' of tool '{}' failed, trying fallback: {}", routing.r#type, tool_call.name, error);
+                    failures.push(json!({"leg": leg, "type": routing.r#type, "error": error}));
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Routing '{}' of tool '{}' failed, trying fallback: {}", routing.r#type, tool_call.name, e);
+                    failures.push(json!({"leg": leg, "type": routing.r#type, "error": e.to_string()}));
+                    continue;
+                }
+            };
+
+            let from_cache = leg > 0 && routing.r#type == cache::FALLBACK_ROUTING_TYPE;
+            if result.success && !from_cache {
+                if let Some(cache_config) = self.cache_config(tool_def) {
+                    self.cache.insert(&tool_call.name, cache_subject.as_deref(), &tool_call.arguments, &result, &cache_config);
+                }
+            }
+            let result = if tool_def.routing.fallback.is_empty() {
+                result
+            } else {
+                with_metadata(result, "fallback", json!({"leg": leg, "type": routing.r#type, "failures": failures}))
+            };
+            let result = match variant {
+                Some((_, variant)) => with_metadata(result, "canary_variant", json!(variant)),
+                None => result,
+            };
+            let result = match &tool_def.cost {
+                Some(tool_cost) if result.success && !from_cache => {
+                    let tokens = cost::tokens_used(&result);
+                    let amount = self.costs.charge(&tool_call.name, tool_cost, tokens, &spender);
+                    self.quotas.charge(&QuotaSubjects::current(), amount);
+                    with_metadata(result, "cost", json!({"amount": amount, "tokens": tokens}))
+                }
+                _ => result,
+            };
+            return Ok(match tool_def.cache {
+                Some(_) => cache::with_cache_status(result, if bypassed { "bypass" } else { "miss" }),
+                None => result,
+            });
+        }
+        unreachable!("a tool always has its primary routing")
+    }
+
+    /// Route with one routing of a tool (its own, its canary's or a fallback),
+    /// balancing across the routing's endpoints
+    async fn route_leg(&self, tool_call: &ToolCall, tool_def: &ToolDefinition, balancer_key: &str, routing: &RoutingConfig) -> Result<AgentResult> {
+        let balancing = LoadBalancingConfig::from_routing(&routing.config)?;
+        let (endpoint, mut leg_def) = match &balancing {
+            Some(balancing) => {
+                let index = self.load_balancer.select(balancer_key, balancing);
+                let mut leg_def = tool_def.clone();
+                leg_def.routing = RoutingConfig::new(routing.r#type.clone(), balancing.endpoint_config(&routing.config, index));
+                (Some(index), Cow::Owned(leg_def))
+            }
+            None if std::ptr::eq(routing, &tool_def.routing) => (None, Cow::Borrowed(tool_def)),
+            None => {
+                let mut leg_def = tool_def.clone();
+                leg_def.routing = routing.clone();
+                (None, Cow::Owned(leg_def))
+            }
+        };
+
+        let transform = TransformConfig::from_routing(&leg_def.routing.config)?;
+        let transformed_call;
+        let tool_call = match &transform {
+            Some(transform) => {
+                let routing = &mut leg_def.to_mut().routing;
+                routing.config = transform::without_transform(&routing.config);
+                transformed_call = ToolCall::new(tool_call.name.clone(), transform.apply_request(&tool_call.arguments)?);
+                &transformed_call
+            }
+            None => tool_call,
+        };
+
+        if leg_def.routing.r#type == "external_mcp" {
+            if let Some(server) = leg_def.routing.config.get("server_name").and_then(|v| v.as_str()) {
+                if let Err(limited) = self.rate_limiter.check_server(server) {
+                    return Ok(Self::rate_limited(tool_call, limited));
+                }
+            }
+        }
+
+        let retry_policy = match RetryPolicy::from_routing(&leg_def.routing.config)? {
+            Some(_) if !tool_def.is_idempotent() => {
+                debug!("Not retrying tool '{}': it is not annotated idempotent or read-only", tool_call.name);
+                None
+            }
+            policy => policy,
+        };
+
+        let started = Instant::now();
+        let mut result = match self.agent_router.parse_routing_config(&leg_def.routing) {
+            Ok(mut agent) => {
+                let timeout = self.timeouts.read().ok().and_then(|timeouts| timeouts.apply(&tool_call.name, &mut agent));
+                let (result, attempts) = self.execute_agent(tool_call, &agent, retry_policy.as_ref()).await;
+                let result = match timeout {
+                    Some(timeout) => result.map(|result| with_metadata(result, "timeout", json!(timeout))),
+                    None => result,
+                };
+                match attempts {
+                    1 => result,
+                    attempts => result.map(|result| with_metadata(result, "retry_attempts", attempts.into())),
+                }
+            }
+            Err(e) => Err(e),
+        };
+        if let Some(transform) = &transform {
+            result = result.and_then(|mut result| {
+                if let (true, Some(data)) = (result.success, &result.data) {
+                    result.data = Some(transform.apply_response(data)?);
+                }
+                Ok(result)
+            });
+        }
+        match (&balancing, endpoint) {
+            (Some(balancing), Some(index)) => {
+                let success = matches!(&result, Ok(result) if result.success);
+                self.load_balancer.record(balancer_key, balancing, index, success, started.elapsed());
+                result.map(|result| with_metadata(result, "endpoint_index", index.into()))
+            }
+            _ => result,
+        }
+    }
+
+    /// Execute the agent, retrying per the routing's policy, inside the
+    /// configured middleware; returns the result and the number of attempts
+    async fn execute_agent(&self, tool_call: &ToolCall, agent: &AgentType, retry_policy: Option<&RetryPolicy>) -> (Result<AgentResult>, u32) {
+        let middleware = self.middleware.read().map(|chain| Arc::clone(&chain)).unwrap_or_default();
+        // The context holds guards such as circuit breaker permits until the call is over
+        let context = MiddlewareContext::new(tool_call.clone(), agent.clone());
+        if let Err(e) = middleware.before_execution(&context).await {
+            return (Err(e), 0);
+        }
+
+        let (result, attempts) = match retry_policy {
+            Some(policy) => {
+                retry::retry_with_policy(policy, &tool_call.name, || self.agent_router.execute_with_agent(tool_call, agent)).await
+            }
+            None => (self.agent_router.execute_with_agent(tool_call, agent).await, 1),
+        };
+        let finished = match &result {
+            Ok(result) => middleware.after_execution(&context, result).await,
+            Err(e) => middleware.on_error(&context, e).await,
+        };
+        if let Err(e) = finished {
+            warn!("Middleware failed after tool call '{}': {}", tool_call.name, e);
+        }
+        (result, attempts)
+    }
+
+    /// Whether a call to the tool is previewed instead of dispatched: the
+    /// call asked for it, or the tool is destructive and policy requires it
+    fn is_dry_run(&self, tool_def: &ToolDefinition) -> bool {
+        dry_run::is_dry_run()
+            || (tool_def.is_destructive() && self.dry_run.read().map_or(false, |config| config.destructive_tools))
+    }
+
+    /// Preview the tool's routing and its canary, one entry per load-balanced endpoint
+    ///
+    /// Fallback routings are left out: they only run when the routing fails.
+    fn route_dry_run(&self, tool_call: &ToolCall, tool_def: &ToolDefinition) -> Result<AgentResult> {
+        debug!("Dry run of tool call: {}", tool_call.name);
+        let routings = std::iter::once((Variant::Primary, &tool_def.routing))
+            .chain(tool_def.routing.canary.as_ref().map(|canary| (Variant::Canary, canary.routing.as_ref())));
+
+        let mut would_execute = Vec::new();
+        for (variant, routing) in routings {
+            let endpoints = match LoadBalancingConfig::from_routing(&routing.config)? {
+                Some(balancing) => (0..balancing.endpoints.len())
+                    .map(|index| (Some(index), balancing.endpoint_config(&routing.config, index)))
+                    .collect(),
+                None => vec![(None, routing.config.clone())],
+            };
+            for (endpoint, config) in endpoints {
+                let (config, arguments) = match TransformConfig::from_routing(&config)? {
+                    Some(transform) => (transform::without_transform(&config), transform.apply_request(&tool_call.arguments)?),
+                    None => (config, tool_call.arguments.clone()),
+                };
+                let call = ToolCall::new(tool_call.name.clone(), arguments);
+                let mut agent = self.agent_router.parse_routing_config(&RoutingConfig::new(routing.r#type.clone(), config))?;
+                let timeout = self.timeouts.read().ok().and_then(|timeouts| timeouts.apply(&tool_call.name, &mut agent));
+
+                let mut preview = dry_run::preview(&call, &agent)?;
+                if let Some(timeout) = timeout {
+                    preview["timeout"] = json!(timeout);
+                }
+                if let Some(index) = endpoint {
+                    preview["endpoint_index"] = index.into();
+                }
+                if tool_def.routing.canary.is_some() {
+                    preview["canary_variant"] = json!(variant);
+                }
+                would_execute.push(preview);
+            }
+        }
+        Ok(dry_run::dry_run_result(tool_call, would_execute))
+    }
+
+    /// Serve the tool's last cached result, even if expired, for a `cache` fallback leg
+    fn route_to_cache(&self, tool_call: &ToolCall, subject: Option<&str>, routing: &RoutingConfig) -> Result<AgentResult> {
+        let max_age = routing.config.get("max_age_seconds").and_then(|v| v.as_u64()).map(Duration::from_secs);
+        match self.cache.get_stale(&tool_call.name, subject, &tool_call.arguments) {
+            Some((result, age)) if max_age.map_or(true, |max_age| age <= max_age) => {
+                Ok(with_metadata(result, "cached_age_seconds", age.as_secs().into()))
+            }
+            _ => Err(ProxyError::routing(format!("No cached result for tool '{}'", tool_call.name))),
+        }
+    }
+
+    /// How results of a tool are cached: its `cache` section, or for tools
+    /// with only a `cache` fallback leg, long enough to serve that leg
+    fn cache_config(&self, tool_def: &ToolDefinition) -> Option<ToolCacheConfig> {
+        if let Some(cache_config) = &tool_def.cache {
+            return Some(cache_config.clone());
+        }
+        let leg = tool_def.routing.fallback.iter().find(|leg| leg.r#type == cache::FALLBACK_ROUTING_TYPE)?;
+        Some(ToolCacheConfig {
+            ttl_seconds: leg.config.get("max_age_seconds").and_then(|v| v.as_u64()).unwrap_or(cache::STALE_RESULT_RETENTION_SECS),
+            max_entries: DEFAULT_CACHE_MAX_ENTRIES,
+        })
+    }
+
+    fn rate_limited(tool_call: &ToolCall, limited: RateLimited) -> AgentResult {
+        warn!("Rate limited call to tool '{}' by {:?} limit '{}'", tool_call.name, limited.scope, limited.key);
+        limited.into_agent_result()
+    }
+
+    /// Rate limits applied to tool calls
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    fn budget_exceeded(tool_call: &ToolCall, exceeded: BudgetExceeded) -> AgentResult {
+        warn!("Refused call to tool '{}': {:?} '{}' has spent its budget", tool_call.name, exceeded.scope, exceeded.key);
+        exceeded.into_agent_result()
+    }
+
+    fn throttled(tool_call: &ToolCall, throttled: Throttled) -> AgentResult {
+        warn!("Refused call to tool '{}': {:?} '{}' is throttled after anomalous activity", tool_call.name, throttled.scope, throttled.key);
+        throttled.into_agent_result()
+    }
+
+    fn quota_exceeded(tool_call: &ToolCall, exceeded: QuotaExceeded) -> AgentResult {
+        warn!("Refused call to tool '{}': {:?} '{}' has used up its quota", tool_call.name, exceeded.scope, exceeded.key);
+        exceeded.into_agent_result()
+    }
+
+    /// Calls waiting for approval and the rules deciding which calls need it
+    pub fn approvals(&self) -> &ApprovalManager {
+        &self.approvals
+    }
+
+    /// Spend of tool calls and the budgets it is checked against
+    pub fn cost_tracker(&self) -> &CostTracker {
+        &self.costs
+    }
+
+    /// Calls and cost per API key, user and role, and the quotas they are checked against
+    pub fn quota_tracker(&self) -> &QuotaTracker {
+        &self.quotas
+    }
+
+    /// Baseline behavior per API key and user, and the anomalies detected against it
+    pub fn threat_detector(&self) -> &ThreatDetector {
+        &self.threats
+    }
+
+    /// Replace the timeout hierarchy applied to tool calls
+    pub fn configure_timeouts(&self, config: TimeoutConfig) {
+        if let Ok(mut timeouts) = self.timeouts.write() {
+            *timeouts = config;
+        }
+    }
+
+    /// Replace the policy deciding which tools are always dry-run
+    pub fn configure_dry_run(&self, config: DryRunConfig) {
+        if let Ok(mut dry_run) = self.dry_run.write() {
+            *dry_run = config;
+        }
+    }
+
+    /// Replace the time windows tools may be called in
+    pub fn configure_access_windows(&self, windows: Option<AccessWindows>) {
+        if let Ok(mut access_windows) = self.access_windows.write() {
+            *access_windows = windows.map(Arc::new);
+        }
+    }
+
+    /// Replace the policies authorizing tool calls
+    pub fn configure_policy(&self, engine: Option<PolicyEngine>) {
+        if let Ok(mut policy) = self.policy.write() {
+            *policy = engine.map(Arc::new);
+        }
+    }
+
+    /// Replace the redaction of secrets in tool arguments and results
+    pub fn configure_secret_scanning(&self, scanner: Option<SecretScanner>) {
+        if let Ok(mut secret_scanner) = self.secret_scanner.write() {
+            *secret_scanner = scanner.map(Arc::new);
+        }
+    }
+
+    /// Replace the handling of personal data in tool arguments and results
+    pub fn configure_pii(&self, masker: Option<PiiMasker>) {
+        if let Ok(mut pii_masker) = self.pii_masker.write() {
+            *pii_masker = masker.map(Arc::new);
+        }
+    }
+
+    /// Replace the middleware run around each agent execution
+    pub fn configure_middleware(&self, chain: MiddlewareChain) {
+        if let Ok(mut middleware) = self.middleware.write() {
+            *middleware = Arc::new(chain);
+        }
+    }
+
+    /// A copy of `value` fit for logs: with its secrets redacted and personal
+    /// data masked, when those are configured
+    pub fn redact_for_logging(&self, value: &serde_json::Value) -> serde_json::Value {
+        let value = match self.secret_scanner.read().ok().and_then(|scanner| scanner.clone()) {
+            Some(scanner) => scanner.redacted(value),
+            None => value.clone(),
+        };
+        match self.pii_masker.read().ok().and_then(|masker| masker.clone()) {
+            Some(masker) => masker.masked(&value),
+            None => value,
+        }
+    }
+
+    /// Endpoint health and selection state of load-balanced tools
+    pub fn load_balancer(&self) -> &LoadBalancer {
+        &self.load_balancer
+    }
+
+    /// Per-variant metrics of a tool routed with a canary
+    pub fn canary_metrics(&self, tool_name: &str) -> Option<CanaryMetrics> {
+        self.canary.metrics(tool_name)
+    }
+
+    /// Per-variant metrics of every tool routed with a canary
+    pub fn all_canary_metrics(&self) -> std::collections::HashMap<String, CanaryMetrics> {
+        self.canary.all_metrics()
+    }
+
+    /// Drop the cached results of a tool, e.g. after its definition changed
+    pub fn invalidate_cache(&self, tool_name: &str) {
+        self.cache.invalidate(tool_name);
     }
 
     /// Create a new router with enhanced features (logging and metrics middleware)
@@ -97,6 +763,21 @@ impl Router {
 
         Self {
             agent_router: Arc::new(enhanced_router),
+            cache: Arc::default(),
+            rate_limiter: Arc::default(),
+            load_balancer: Arc::default(),
+            canary: Arc::default(),
+            timeouts: Arc::default(),
+            dry_run: Arc::default(),
+            costs: Arc::default(),
+            quotas: Arc::default(),
+            threats: Arc::default(),
+            access_windows: Arc::default(),
+            policy: Arc::default(),
+            secret_scanner: Arc::default(),
+            pii_masker: Arc::default(),
+            approvals: Arc::default(),
+            middleware: Arc::default(),
         }
     }
 
@@ -116,6 +797,21 @@ impl Router {
 
         Self {
             agent_router: Arc::new(enhanced_router),
+            cache: Arc::default(),
+            rate_limiter: Arc::default(),
+            load_balancer: Arc::default(),
+            canary: Arc::default(),
+            timeouts: Arc::default(),
+            dry_run: Arc::default(),
+            costs: Arc::default(),
+            quotas: Arc::default(),
+            threats: Arc::default(),
+            access_windows: Arc::default(),
+            policy: Arc::default(),
+            secret_scanner: Arc::default(),
+            pii_masker: Arc::default(),
+            approvals: Arc::default(),
+            middleware: Arc::default(),
         }
     }
 
@@ -134,3 +830,14 @@ impl Default for Router {
         Self::new()
     }
 }
+
+/// Add a field to a result's metadata
+fn with_metadata(mut result: AgentResult, key: &str, value: serde_json::Value) -> AgentResult {
+    match result.metadata {
+        Some(serde_json::Value::Object(ref mut metadata)) => {
+            metadata.insert(key.to_string(), value);
+        }
+        _ => result.metadata = Some(json!({ key: value })),
+    }
+    result
+}
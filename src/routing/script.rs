@@ -0,0 +1,304 @@
+//! Embedded Rhai and Lua scripts for `script`-routed tools
+//!
+//! Small glue tools can be written inline in the capability file instead of
+//! shipping a separate executable:
+//!
+//! ```yaml
+//! routing:
+//!   type: script
+//!   config:
+//!     language: rhai        # or lua
+//!     script: |
+//!       let words = args.text.split(" ");
+//!       #{ count: words.len() }
+//!     max_operations: 1000000
+//!     timeout: 5
+//! ```
+//!
+//! The tool arguments are available as `args` and the script's value (Rhai's
+//! last expression, Lua's `return`) becomes the result. Scripts get no
+//! filesystem, process, network or module access; `print` output is collected.
+//!
+//! Lua needs the `lua` feature (on by default), which builds a vendored Lua.
+
+use crate::error::{ProxyError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+#[cfg(feature = "lua")]
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Lua instructions between deadline and operation-limit checks
+#[cfg(feature = "lua")]
+const LUA_HOOK_INTERVAL: u32 = 1000;
+
+/// Rhai limit on string, array and map sizes
+const RHAI_MAX_COLLECTION_SIZE: usize = 1024 * 1024;
+
+/// Rhai limit on function call nesting
+const RHAI_MAX_CALL_LEVELS: usize = 64;
+
+/// Script language
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptLanguage {
+    #[default]
+    Rhai,
+    Lua,
+}
+
+fn default_max_operations() -> u64 {
+    10_000_000
+}
+
+fn default_max_memory_mb() -> u64 {
+    64
+}
+
+/// Script source and limits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptConfig {
+    #[serde(default)]
+    pub language: ScriptLanguage,
+    /// Script source
+    pub script: String,
+    /// Budget of script operations (Rhai) or VM instructions (Lua)
+    #[serde(default = "default_max_operations")]
+    pub max_operations: u64,
+    /// Heap limit for Lua; Rhai instead caps string, array and map sizes
+    #[serde(default = "default_max_memory_mb")]
+    pub max_memory_mb: u64,
+}
+
+/// Result of a script run
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptOutput {
+    /// The script's value, or the error it failed with
+    pub result: std::result::Result<Value, String>,
+    /// Lines written with `print`
+    pub output: Vec<String>,
+}
+
+impl ScriptConfig {
+    /// Check that the script compiles
+    pub fn validate(&self) -> Result<()> {
+        match self.language {
+            ScriptLanguage::Rhai => {
+                let engine = rhai_engine(self, None, Arc::new(Mutex::new(Vec::new())));
+                engine.compile(&self.script)
+                    .map(|_| ())
+                    .map_err(|e| ProxyError::routing(format!("Invalid Rhai script: {}", e)))
+            }
+            #[cfg(feature = "lua")]
+            ScriptLanguage::Lua => {
+                let lua = mlua::Lua::new();
+                lua.load(self.script.as_str()).into_function()
+                    .map(|_| ())
+                    .map_err(|e| ProxyError::routing(format!("Invalid Lua script: {}", e)))
+            }
+            #[cfg(not(feature = "lua"))]
+            ScriptLanguage::Lua => Err(lua_disabled()),
+        }
+    }
+
+    /// Run the script with `arguments` bound to `args`
+    ///
+    /// Blocks the calling thread; run it on a blocking task. Script failures,
+    /// including hitting a limit or the deadline, are reported in
+    /// [`ScriptOutput::result`].
+    pub fn run(&self, arguments: &Value, timeout: Duration) -> Result<ScriptOutput> {
+        let deadline = Instant::now() + timeout;
+        match self.language {
+            ScriptLanguage::Rhai => Ok(self.run_rhai(arguments, deadline)),
+            #[cfg(feature = "lua")]
+            ScriptLanguage::Lua => self.run_lua(arguments, deadline),
+            #[cfg(not(feature = "lua"))]
+            ScriptLanguage::Lua => Err(lua_disabled()),
+        }
+    }
+
+    fn run_rhai(&self, arguments: &Value, deadline: Instant) -> ScriptOutput {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let engine = rhai_engine(self, Some(deadline), output.clone());
+
+        let result = rhai::serde::to_dynamic(arguments)
+            .and_then(|args| {
+                let mut scope = rhai::Scope::new();
+                scope.push_constant("args", args);
+                engine.eval_with_scope::<rhai::Dynamic>(&mut scope, &self.script)
+            })
+            .and_then(|value| rhai::serde::from_dynamic::<Value>(&value))
+            .map_err(|e| match *e {
+                rhai::EvalAltResult::ErrorTerminated(..) => "Script timed out".to_string(),
+                rhai::EvalAltResult::ErrorTooManyOperations(..) => "Script exceeded its operation limit".to_string(),
+                ref e => format!("Script failed: {}", e),
+            });
+
+        ScriptOutput { result, output: take_lines(&output) }
+    }
+
+    #[cfg(feature = "lua")]
+    fn run_lua(&self, arguments: &Value, deadline: Instant) -> Result<ScriptOutput> {
+        use mlua::LuaSerdeExt;
+
+        let setup_error = |e: mlua::Error| ProxyError::routing(format!("Failed to set up Lua: {}", e));
+        let lua = mlua::Lua::new_with(
+            mlua::StdLib::TABLE | mlua::StdLib::STRING | mlua::StdLib::MATH | mlua::StdLib::UTF8 | mlua::StdLib::COROUTINE,
+            mlua::LuaOptions::new(),
+        ).map_err(setup_error)?;
+        lua.set_memory_limit(usize::try_from(self.max_memory_mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX))
+            .map_err(setup_error)?;
+
+        // The base library is always loaded; drop the parts that reach the filesystem or load code
+        let globals = lua.globals();
+        for name in ["dofile", "loadfile", "load", "require"] {
+            globals.set(name, mlua::Value::Nil).map_err(setup_error)?;
+        }
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let print_output = output.clone();
+        let print = lua.create_function(move |_, values: mlua::Variadic<mlua::Value>| {
+            let line = values.iter()
+                .map(|value| value.to_string())
+                .collect::<mlua::Result<Vec<_>>>()?
+                .join("\t");
+            if let Ok(mut lines) = print_output.lock() {
+                lines.push(line);
+            }
+            Ok(())
+        }).map_err(setup_error)?;
+        globals.set("print", print).map_err(setup_error)?;
+        globals.set("args", lua.to_value(arguments).map_err(setup_error)?).map_err(setup_error)?;
+
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let out_of_operations = Arc::new(AtomicBool::new(false));
+        let operations = AtomicU64::new(0);
+        let max_operations = self.max_operations;
+        let (hook_timed_out, hook_out_of_operations) = (timed_out.clone(), out_of_operations.clone());
+        lua.set_hook(mlua::HookTriggers::new().every_nth_instruction(LUA_HOOK_INTERVAL), move |_, _| {
+            let used = operations.fetch_add(u64::from(LUA_HOOK_INTERVAL), Ordering::Relaxed) + u64::from(LUA_HOOK_INTERVAL);
+            if used > max_operations {
+                hook_out_of_operations.store(true, Ordering::Relaxed);
+                return Err(mlua::Error::RuntimeError("operation limit exceeded".to_string()));
+            }
+            if Instant::now() >= deadline {
+                hook_timed_out.store(true, Ordering::Relaxed);
+                return Err(mlua::Error::RuntimeError("timed out".to_string()));
+            }
+            Ok(())
+        });
+
+        let result = lua.load(self.script.as_str()).set_name("script").eval::<mlua::Value>()
+            .and_then(|value| lua.from_value::<Value>(value))
+            .map_err(|e| {
+                if timed_out.load(Ordering::Relaxed) {
+                    "Script timed out".to_string()
+                } else if out_of_operations.load(Ordering::Relaxed) {
+                    "Script exceeded its operation limit".to_string()
+                } else {
+                    format!("Script failed: {}", e)
+                }
+            });
+
+        Ok(ScriptOutput { result, output: take_lines(&output) })
+    }
+}
+
+#[cfg(not(feature = "lua"))]
+fn lua_disabled() -> ProxyError {
+    ProxyError::routing("Lua scripts need the `lua` feature, which this build was compiled without".to_string())
+}
+
+/// Rhai engine with the standard packages, no module loading and the configured limits
+fn rhai_engine(config: &ScriptConfig, deadline: Option<Instant>, output: Arc<Mutex<Vec<String>>>) -> rhai::Engine {
+    let mut engine = rhai::Engine::new();
+    engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_operations(config.max_operations);
+    engine.set_max_call_levels(RHAI_MAX_CALL_LEVELS);
+    engine.set_max_string_size(RHAI_MAX_COLLECTION_SIZE);
+    engine.set_max_array_size(RHAI_MAX_COLLECTION_SIZE);
+    engine.set_max_map_size(RHAI_MAX_COLLECTION_SIZE);
+    if let Some(deadline) = deadline {
+        engine.on_progress(move |_| (Instant::now() >= deadline).then_some(rhai::Dynamic::UNIT));
+    }
+    let debug_output = output.clone();
+    engine.on_print(move |text| {
+        if let Ok(mut lines) = output.lock() {
+            lines.push(text.to_string());
+        }
+    });
+    engine.on_debug(move |text, _, _| {
+        if let Ok(mut lines) = debug_output.lock() {
+            lines.push(text.to_string());
+        }
+    });
+    engine
+}
+
+fn take_lines(output: &Mutex<Vec<String>>) -> Vec<String> {
+    output.lock().map(|mut lines| std::mem::take(&mut *lines)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(language: &str, script: &str) -> ScriptConfig {
+        serde_json::from_value(json!({"language": language, "script": script})).unwrap()
+    }
+
+    #[test]
+    fn test_rhai_script() {
+        let script = config("rhai", r#"print("counting"); #{ count: args.text.split(" ").len() }"#);
+        script.validate().unwrap();
+
+        let output = script.run(&json!({"text": "a b c"}), Duration::from_secs(5)).unwrap();
+        assert_eq!(output.result, Ok(json!({"count": 3})));
+        assert_eq!(output.output, vec!["counting"]);
+    }
+
+    #[cfg(feature = "lua")]
+    #[test]
+    fn test_lua_script() {
+        let script = config("lua", r#"print("counting", #args.items); return { total = args.items[1] + args.items[2] }"#);
+        script.validate().unwrap();
+
+        let output = script.run(&json!({"items": [2, 3]}), Duration::from_secs(5)).unwrap();
+        assert_eq!(output.result, Ok(json!({"total": 5})));
+        assert_eq!(output.output, vec!["counting\t2"]);
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert!(config("rhai", "let x = ;").validate().is_err());
+        assert!(config("lua", "return (").validate().is_err());
+    }
+
+    #[cfg(feature = "lua")]
+    #[test]
+    fn test_limits() {
+        for language in ["rhai", "lua"] {
+            let mut script = config(language, if language == "rhai" { "loop {}" } else { "while true do end" });
+            let output = script.run(&json!({}), Duration::from_millis(50)).unwrap();
+            assert_eq!(output.result, Err("Script timed out".to_string()), "{}", language);
+
+            script.max_operations = 10_000;
+            let output = script.run(&json!({}), Duration::from_secs(5)).unwrap();
+            assert_eq!(output.result, Err("Script exceeded its operation limit".to_string()), "{}", language);
+        }
+    }
+
+    #[cfg(feature = "lua")]
+    #[test]
+    fn test_sandbox() {
+        let output = config("rhai", r#"import "os" as os; 1"#).run(&json!({}), Duration::from_secs(5)).unwrap();
+        assert!(output.result.is_err());
+
+        let output = config("lua", r#"return io == nil and os == nil and dofile == nil and require == nil"#)
+            .run(&json!({}), Duration::from_secs(5)).unwrap();
+        assert_eq!(output.result, Ok(json!(true)));
+    }
+}
@@ -0,0 +1,166 @@
+//! Remote command execution for `ssh`-routed tools
+//!
+//! Commands run through the system OpenSSH client, so key files, ssh-agent
+//! and `~/.ssh/config` work as they do from a shell:
+//!
+//! ```yaml
+//! routing:
+//!   type: ssh
+//!   config:
+//!     host: build-1.internal
+//!     user: deploy
+//!     port: 22
+//!     command: "df -h {{path}}"              # arguments are shell-quoted
+//!     identity_file: ~/.ssh/id_ed25519       # optional; otherwise ssh-agent / default keys
+//!     known_hosts_file: /etc/magictunnel/known_hosts
+//!     connect_timeout: 10
+//!     timeout: 60
+//!     max_output_bytes: 1048576
+//! ```
+//!
+//! Host keys are always verified (`StrictHostKeyChecking=yes`) and ssh never
+//! prompts (`BatchMode=yes`), so unknown hosts and missing keys fail the call.
+
+use crate::error::{ProxyError, Result};
+use crate::routing::process::CapturedOutput;
+use serde::{Deserialize, Serialize};
+
+/// Exit status ssh uses for its own errors (connection, authentication, host key)
+pub const SSH_ERROR_EXIT_CODE: i32 = 255;
+
+/// How to reach the remote host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SshConnection {
+    /// Host name or address; may be a `{{param}}` template
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Private key file; when unset ssh uses the agent and its default keys
+    #[serde(default)]
+    pub identity_file: Option<String>,
+    /// Offer keys from ssh-agent
+    #[serde(default = "default_use_agent")]
+    pub use_agent: bool,
+    /// known_hosts file the host key is verified against (defaults to ssh's own)
+    #[serde(default)]
+    pub known_hosts_file: Option<String>,
+    /// Seconds to wait for the connection to be established
+    #[serde(default)]
+    pub connect_timeout: Option<u64>,
+    /// ssh client executable
+    #[serde(default = "default_ssh_binary")]
+    pub ssh_binary: String,
+}
+
+fn default_use_agent() -> bool {
+    true
+}
+
+fn default_ssh_binary() -> String {
+    "ssh".to_string()
+}
+
+impl SshConnection {
+    /// Arguments for running `remote_command` on `host`
+    pub fn ssh_args(&self, host: &str, remote_command: &str) -> Result<Vec<String>> {
+        validate_name("host", host)?;
+        let mut args = vec![
+            "-T".to_string(),
+            "-o".to_string(), "BatchMode=yes".to_string(),
+            "-o".to_string(), "StrictHostKeyChecking=yes".to_string(),
+        ];
+        if let Some(known_hosts) = &self.known_hosts_file {
+            args.extend(["-o".to_string(), format!("UserKnownHostsFile={}", shellexpand::tilde(known_hosts))]);
+        }
+        if let Some(identity_file) = &self.identity_file {
+            args.extend([
+                "-i".to_string(), shellexpand::tilde(identity_file).into_owned(),
+                "-o".to_string(), "IdentitiesOnly=yes".to_string(),
+            ]);
+        }
+        if !self.use_agent {
+            args.extend(["-o".to_string(), "IdentityAgent=none".to_string()]);
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            args.extend(["-o".to_string(), format!("ConnectTimeout={}", connect_timeout)]);
+        }
+        if let Some(port) = self.port {
+            args.extend(["-p".to_string(), port.to_string()]);
+        }
+        if let Some(user) = &self.user {
+            validate_name("user", user)?;
+            args.extend(["-l".to_string(), user.clone()]);
+        }
+        args.extend(["--".to_string(), host.to_string(), remote_command.to_string()]);
+        Ok(args)
+    }
+}
+
+/// Reject values ssh could mistake for options or that aren't host/user names
+fn validate_name(field: &str, value: &str) -> Result<()> {
+    let valid = !value.is_empty()
+        && !value.starts_with('-')
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':' | '[' | ']'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ProxyError::routing(format!("Invalid SSH {}: '{}'", field, value)))
+    }
+}
+
+/// Error message for a failed ssh run
+pub fn error_message(output: &CapturedOutput) -> Option<String> {
+    match output.exit_code {
+        Some(0) => None,
+        Some(SSH_ERROR_EXIT_CODE) => Some(format!("SSH connection failed: {}", output.stderr.trim())),
+        Some(code) => Some(format!("Remote command failed with exit code: {}", code)),
+        None => Some("Remote command was terminated by a signal".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection() -> SshConnection {
+        serde_json::from_value(serde_json::json!({"host": "build-1"})).unwrap()
+    }
+
+    #[test]
+    fn test_ssh_args() {
+        let mut conn = connection();
+        conn.user = Some("deploy".to_string());
+        conn.port = Some(2222);
+        conn.known_hosts_file = Some("/etc/magictunnel/known_hosts".to_string());
+        conn.identity_file = Some("/keys/id_ed25519".to_string());
+        conn.use_agent = false;
+
+        let args = conn.ssh_args("build-1.internal", "uptime").unwrap();
+        assert_eq!(args, vec![
+            "-T", "-o", "BatchMode=yes", "-o", "StrictHostKeyChecking=yes",
+            "-o", "UserKnownHostsFile=/etc/magictunnel/known_hosts",
+            "-i", "/keys/id_ed25519", "-o", "IdentitiesOnly=yes",
+            "-o", "IdentityAgent=none",
+            "-p", "2222", "-l", "deploy",
+            "--", "build-1.internal", "uptime",
+        ]);
+    }
+
+    #[test]
+    fn test_rejects_option_like_hosts() {
+        let conn = connection();
+        assert!(conn.ssh_args("-oProxyCommand=evil", "uptime").is_err());
+        assert!(conn.ssh_args("host name", "uptime").is_err());
+        assert!(conn.ssh_args("[::1]", "uptime").is_ok());
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let output = |code| CapturedOutput { exit_code: code, stdout: String::new(), stderr: "Host key verification failed.\n".to_string(), truncated: false };
+        assert!(error_message(&output(Some(0))).is_none());
+        assert_eq!(error_message(&output(Some(255))).unwrap(), "SSH connection failed: Host key verification failed.");
+        assert_eq!(error_message(&output(Some(2))).unwrap(), "Remote command failed with exit code: 2");
+    }
+}
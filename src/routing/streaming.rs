@@ -0,0 +1,271 @@
+//! Streaming LLM responses as progress notifications
+//!
+//! When the client asked for progress (see [`crate::mcp::progress`]), `llm`
+//! routings request a streaming response and report each piece of generated
+//! text as it arrives: OpenAI-compatible backends as server-sent events
+//! (`data: {...}` lines ending with `data: [DONE]`), Ollama as one JSON object
+//! per line. The final result is the aggregated response, in the same shape as
+//! a non-streaming response, so tools behave the same either way.
+//!
+//! `sse` routings read their upstream's events as they arrive, reporting each
+//! one as progress and returning the collected events once the stream ends or
+//! `max_events` is reached.
+
+use crate::error::{ProxyError, Result};
+use crate::mcp::progress::ProgressReporter;
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+
+/// Wire format of a streaming LLM response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmStreamFormat {
+    /// `/chat/completions` with `stream: true`
+    OpenAi,
+    /// `/api/generate` with `stream: true`
+    Ollama,
+}
+
+/// Text generated so far by a streaming LLM response
+#[derive(Debug)]
+pub struct LlmStream {
+    format: LlmStreamFormat,
+    text: String,
+    last_chunk: Option<Value>,
+    finish_reason: Option<Value>,
+}
+
+impl LlmStream {
+    pub fn new(format: LlmStreamFormat) -> Self {
+        Self { format, text: String::new(), last_chunk: None, finish_reason: None }
+    }
+
+    /// Handle one line of the response, returning the text it adds, if any
+    pub fn push_line(&mut self, line: &str) -> Option<String> {
+        let chunk: Value = match self.format {
+            LlmStreamFormat::OpenAi => {
+                let data = line.strip_prefix("data:")?.trim();
+                if data == "[DONE]" {
+                    return None;
+                }
+                serde_json::from_str(data).ok()?
+            }
+            LlmStreamFormat::Ollama => serde_json::from_str(line.trim()).ok()?,
+        };
+
+        let delta = match self.format {
+            LlmStreamFormat::OpenAi => {
+                let choice = &chunk["choices"][0];
+                if !choice["finish_reason"].is_null() {
+                    self.finish_reason = Some(choice["finish_reason"].clone());
+                }
+                choice["delta"]["content"].as_str().map(String::from)
+            }
+            LlmStreamFormat::Ollama => chunk["response"].as_str().map(String::from),
+        };
+        self.last_chunk = Some(chunk);
+
+        let delta = delta.filter(|delta| !delta.is_empty())?;
+        self.text.push_str(&delta);
+        Some(delta)
+    }
+
+    /// The response a non-streaming request would have returned
+    pub fn into_response(self) -> Value {
+        let last_chunk = self.last_chunk.unwrap_or_else(|| json!({}));
+        match self.format {
+            LlmStreamFormat::OpenAi => {
+                let mut response = json!({
+                    "id": last_chunk["id"],
+                    "object": "chat.completion",
+                    "created": last_chunk["created"],
+                    "model": last_chunk["model"],
+                    "choices": [{
+                        "index": 0,
+                        "message": {"role": "assistant", "content": self.text},
+                        "finish_reason": self.finish_reason,
+                    }],
+                });
+                if let Some(usage) = last_chunk.get("usage").filter(|usage| !usage.is_null()) {
+                    response["usage"] = usage.clone();
+                }
+                response
+            }
+            LlmStreamFormat::Ollama => {
+                // The final chunk carries the timings and token counts
+                let mut response = last_chunk;
+                response["response"] = json!(self.text);
+                response
+            }
+        }
+    }
+}
+
+/// Read a streaming LLM response, reporting generated text as progress
+pub async fn read_llm_stream(response: reqwest::Response, format: LlmStreamFormat, progress: &ProgressReporter) -> Result<Value> {
+    let mut stream = LlmStream::new(format);
+    let mut body = response.bytes_stream();
+    let mut pending = Vec::new();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| ProxyError::routing(format!("Failed to read LLM stream: {}", e)))?;
+        pending.extend_from_slice(&chunk);
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            if let Some(delta) = stream.push_line(&String::from_utf8_lossy(&line)) {
+                progress.report(delta);
+            }
+        }
+    }
+    if let Some(delta) = stream.push_line(&String::from_utf8_lossy(&pending)) {
+        progress.report(delta);
+    }
+    Ok(stream.into_response())
+}
+
+/// Events collected by an `sse` routing when it sets no `max_events`
+pub const DEFAULT_MAX_SSE_EVENTS: u32 = 100;
+
+/// Parser of a server-sent event stream, one line at a time
+#[derive(Debug, Default)]
+pub struct SseEventStream {
+    id: Option<String>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseEventStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle one line of the stream, returning the event it completes, if any
+    ///
+    /// Events are `{"id", "event", "data"}`, with `data` parsed as JSON when it is JSON.
+    pub fn push_line(&mut self, line: &str) -> Option<Value> {
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            // Comment, often a keep-alive
+            return None;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "id" => self.id = Some(value.to_string()),
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+
+    /// The event in progress at the end of the stream, if any
+    pub fn finish(&mut self) -> Option<Value> {
+        self.dispatch()
+    }
+
+    fn dispatch(&mut self) -> Option<Value> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data).join("\n");
+        let data = serde_json::from_str(&data).unwrap_or(Value::String(data));
+        Some(json!({
+            "id": self.id,
+            "event": event.as_deref().unwrap_or("message"),
+            "data": data,
+        }))
+    }
+}
+
+/// Read server-sent events until the stream ends or `max_events` events of
+/// type `event_filter` (any type if unset) have arrived, reporting each as progress
+pub async fn read_sse_events(response: reqwest::Response, max_events: u32, event_filter: Option<&str>, progress: Option<&ProgressReporter>) -> Result<Vec<Value>> {
+    let mut stream = SseEventStream::new();
+    let mut events = Vec::new();
+    let keep = |event: Value, events: &mut Vec<Value>| {
+        if event_filter.is_some_and(|filter| event["event"] != filter) {
+            return;
+        }
+        if let Some(progress) = progress {
+            progress.report(event["data"].as_str().map(String::from).unwrap_or_else(|| event["data"].to_string()));
+        }
+        events.push(event);
+    };
+
+    let mut body = response.bytes_stream();
+    let mut pending = Vec::new();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| ProxyError::routing(format!("Failed to read SSE stream: {}", e)))?;
+        pending.extend_from_slice(&chunk);
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            if let Some(event) = stream.push_line(&String::from_utf8_lossy(&line)) {
+                keep(event, &mut events);
+                if events.len() >= max_events as usize {
+                    // Dropping the body closes the connection
+                    return Ok(events);
+                }
+            }
+        }
+    }
+    stream.push_line(&String::from_utf8_lossy(&pending));
+    if let Some(event) = stream.finish() {
+        keep(event, &mut events);
+    }
+    events.truncate(max_events as usize);
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_event_stream() {
+        let mut stream = SseEventStream::new();
+        let lines = [": keep-alive", "id: 1", "event: update", "data: {\"n\": 1}", "", "data: first", "data:second", "", "", "data: tail"];
+        let events: Vec<Value> = lines.iter().filter_map(|line| stream.push_line(line)).collect();
+        assert_eq!(events, vec![
+            json!({"id": "1", "event": "update", "data": {"n": 1}}),
+            json!({"id": "1", "event": "message", "data": "first\nsecond"}),
+        ]);
+        assert_eq!(stream.finish().unwrap()["data"], "tail");
+        assert!(stream.finish().is_none());
+    }
+
+    #[test]
+    fn test_openai_stream() {
+        let mut stream = LlmStream::new(LlmStreamFormat::OpenAi);
+        let lines = [
+            r#"data: {"id":"c1","created":1,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant"},"finish_reason":null}]}"#,
+            "",
+            r#"data: {"id":"c1","created":1,"model":"gpt-4o","choices":[{"index":0,"delta":{"content":"Hel"},"finish_reason":null}]}"#,
+            r#"data: {"id":"c1","created":1,"model":"gpt-4o","choices":[{"index":0,"delta":{"content":"lo"},"finish_reason":null}]}"#,
+            r#"data: {"id":"c1","created":1,"model":"gpt-4o","choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#,
+            "data: [DONE]",
+        ];
+        let deltas: Vec<String> = lines.iter().filter_map(|line| stream.push_line(line)).collect();
+        assert_eq!(deltas, ["Hel", "lo"]);
+
+        let response = stream.into_response();
+        assert_eq!(response["id"], "c1");
+        assert_eq!(response["choices"][0]["message"]["content"], "Hello");
+        assert_eq!(response["choices"][0]["finish_reason"], "stop");
+    }
+
+    #[test]
+    fn test_ollama_stream() {
+        let mut stream = LlmStream::new(LlmStreamFormat::Ollama);
+        assert_eq!(stream.push_line(r#"{"model":"llama3","response":"Hi","done":false}"#).as_deref(), Some("Hi"));
+        assert_eq!(stream.push_line(r#"{"model":"llama3","response":" there","done":false}"#).as_deref(), Some(" there"));
+        assert!(stream.push_line(r#"{"model":"llama3","response":"","done":true,"eval_count":2}"#).is_none());
+
+        let response = stream.into_response();
+        assert_eq!(response["response"], "Hi there");
+        assert_eq!(response["done"], true);
+        assert_eq!(response["eval_count"], 2);
+    }
+}
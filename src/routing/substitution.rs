@@ -1,58 +1,228 @@
 //! Parameter substitution system for routing configurations
+//!
+//! Templates use `{{parameter}}` or `{parameter}` placeholders, plus a small
+//! Handlebars-style language inside double braces:
+//!
+//! ```text
+//! {{limit | default: 10}}                      default for a missing or null parameter
+//! {{query | url}}  {{path | shell}}  {{filter | json}}  {{tags | join: ","}}
+//! {{#if verbose}}-v{{else}}-q{{/if}}           also {{#unless ...}}
+//! {{#each tags}}&tag={{this | url}}{{/each}}   with {{@index}}, {{@key}}, {{@first}}, {{@last}}
+//! ```
+//!
+//! Placeholders whose parameter is missing are left as they are.
 
 use crate::error::{ProxyError, Result};
+use crate::routing::types::{HttpParams, OptionalArg};
 use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::OnceLock;
 use tracing::debug;
 
 /// Substitute parameters in a vector of strings
+///
+/// An argument made of a single `{{#each}}` block becomes one argument per
+/// item, and an argument made of a single block that renders nothing is
+/// dropped, so flags can be built from array and boolean parameters.
 pub fn substitute_parameters(args: &[String], parameters: &Value) -> Result<Vec<String>> {
-    args.iter()
-        .map(|arg| substitute_parameter_string(arg, parameters))
-        .collect()
+    let mut substituted = Vec::with_capacity(args.len());
+    for arg in args {
+        let nodes = parse_template(arg)?;
+        let mut context = Context::new(parameters);
+        match nodes.as_slice() {
+            [Node::Block { kind: BlockKind::Each, path, body, otherwise }] => {
+                let items = context.render_each(path, body, otherwise)?;
+                substituted.extend(items.into_iter().filter(|item| !item.is_empty()));
+            }
+            [Node::Block { .. }] => {
+                let rendered = context.render(&nodes)?;
+                if !rendered.is_empty() {
+                    substituted.push(rendered);
+                }
+            }
+            _ => substituted.push(context.render(&nodes)?),
+        }
+    }
+    Ok(substituted)
 }
 
-/// Substitute parameters in a single string
-pub fn substitute_parameter_string(template: &str, parameters: &Value) -> Result<String> {
-    let mut result = template.to_string();
-
-    // Handle both {{parameter}} and {parameter} syntax for flexibility
-    if let Some(obj) = parameters.as_object() {
-        for (key, value) in obj {
-            // Double brace syntax: {{parameter}}
-            let double_placeholder = format!("{{{{{}}}}}", key);
-            // Single brace syntax: {parameter}
-            let single_placeholder = format!("{{{}}}", key);
-            
-            let replacement = value_to_string(value)?;
-            
-            result = result.replace(&double_placeholder, &replacement);
-            result = result.replace(&single_placeholder, &replacement);
-            
-            // Handle array indexing: {parameter[0]}, {parameter[1]}, etc.
-            if let Some(array) = value.as_array() {
-                for (index, item) in array.iter().enumerate() {
-                    let array_double_placeholder = format!("{{{{{}[{}]}}}}", key, index);
-                    let array_single_placeholder = format!("{{{}[{}]}}", key, index);
-                    
-                    let array_replacement = value_to_string(item)?;
-                    
-                    result = result.replace(&array_double_placeholder, &array_replacement);
-                    result = result.replace(&array_single_placeholder, &array_replacement);
+/// Expand the optional argument groups whose parameter is supplied, in order
+///
+/// Option groups come first. Values substituted into their own argument
+/// (`["--output", "{{output}}"]`) must not look like an option, since the
+/// command would parse them as one. Positional groups follow a `--`
+/// separator, where leading dashes are harmless.
+pub fn expand_optional_args(optional_args: &[OptionalArg], parameters: &Value) -> Result<Vec<String>> {
+    let mut options = Vec::new();
+    let mut positionals = Vec::new();
+
+    for group in optional_args {
+        let value = match parameters.get(&group.param) {
+            None | Some(Value::Null) | Some(Value::Bool(false)) => continue,
+            Some(value) => value,
+        };
+
+        let expanded = if group.positional { &mut positionals } else { &mut options };
+        let pure_placeholder = format!("{{{{{}}}}}", group.param);
+        for arg in &group.args {
+            let values = match value.as_array() {
+                Some(items) if *arg == pure_placeholder => items.iter().map(value_to_string).collect::<Result<Vec<_>>>()?,
+                _ => vec![substitute_parameter_string(arg, parameters)?],
+            };
+            for value in values {
+                if !group.positional && !arg.starts_with('-') && looks_like_option(&value) {
+                    return Err(ProxyError::validation(format!(
+                        "Value '{}' of parameter '{}' must not start with '-'",
+                        value, group.param
+                    )));
                 }
+                expanded.push(value);
             }
         }
     }
 
+    if !positionals.is_empty() {
+        options.push("--".to_string());
+        options.extend(positionals);
+    }
+    Ok(options)
+}
+
+/// Whether a command would take the argument for an option (negative numbers aren't)
+fn looks_like_option(value: &str) -> bool {
+    value.starts_with('-') && value.parse::<f64>().is_err()
+}
+
+/// Substitute parameters in a single string
+///
+/// Substitution is done in a single pass, so substituted values are never
+/// scanned for further placeholders.
+pub fn substitute_parameter_string(template: &str, parameters: &Value) -> Result<String> {
+    let result = Context::new(parameters).render(&parse_template(template)?)?;
+
     debug!("Parameter substitution: '{}' -> '{}'", template, result);
     Ok(result)
 }
 
+/// Substitute parameters into a shell command line, quoting each value
+///
+/// Values are single-quoted for POSIX shells, so arguments can't inject extra
+/// commands; placeholders belong unquoted in the template (`ls -l {{path}}`).
+/// Substitution is done in a single pass so substituted values are never
+/// scanned for further placeholders.
+pub fn substitute_shell_command(template: &str, parameters: &Value) -> Result<String> {
+    let placeholder = regex::Regex::new(r"\{\{([A-Za-z0-9_.-]+)(?:\[(\d+)\])?\}\}|\{([A-Za-z0-9_.-]+)(?:\[(\d+)\])?\}")
+        .expect("valid placeholder pattern");
+
+    let mut result = String::with_capacity(template.len());
+    let mut last = 0;
+    for caps in placeholder.captures_iter(template) {
+        let whole = caps.get(0).expect("match");
+        let name = caps.get(1).or_else(|| caps.get(3)).map(|m| m.as_str()).unwrap_or_default();
+        let index = caps.get(2).or_else(|| caps.get(4)).and_then(|m| m.as_str().parse::<usize>().ok());
+
+        let value = parameters.get(name).and_then(|value| match index {
+            Some(index) => value.as_array().and_then(|items| items.get(index)),
+            None => Some(value),
+        });
+        result.push_str(&template[last..whole.start()]);
+        match value {
+            Some(value) => result.push_str(&shell_quote(&value_to_string(value)?)),
+            // Unknown placeholders are left as they are, like substitute_parameter_string
+            None => result.push_str(whole.as_str()),
+        }
+        last = whole.end();
+    }
+    result.push_str(&template[last..]);
+
+    debug!("Shell command substitution: '{}' -> '{}'", template, result);
+    Ok(result)
+}
+
+/// Quote a string as a single POSIX shell word
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
 /// Substitute parameters in a URL with query parameters
 pub fn substitute_url_parameters(url: &str, parameters: &Value) -> Result<String> {
     substitute_parameter_string(url, parameters)
 }
 
+/// Substitute parameters into an HTTP agent's URL
+///
+/// `params.path` arguments are percent-encoded before they are substituted, so
+/// they stay within their path segment; `params.query` arguments are appended
+/// to the query string.
+pub fn substitute_http_url(url: &str, params: &HttpParams, parameters: &Value) -> Result<String> {
+    let mut url_parameters = Cow::Borrowed(parameters);
+    if !params.path.is_empty() {
+        if let Some(arguments) = url_parameters.to_mut().as_object_mut() {
+            for name in &params.path {
+                if let Some(value) = arguments.get_mut(name) {
+                    *value = Value::String(urlencoding::encode(&value_to_string(value)?).into_owned());
+                }
+            }
+        }
+    }
+    let substituted = substitute_parameter_string(url, &url_parameters)?;
+
+    let mut pairs = Vec::new();
+    for (key, name) in &params.query {
+        match parameters.get(name) {
+            None | Some(Value::Null) => {}
+            Some(Value::Array(items)) => {
+                for item in items {
+                    pairs.push((key, value_to_string(item)?));
+                }
+            }
+            Some(value) => pairs.push((key, value_to_string(value)?)),
+        }
+    }
+    if pairs.is_empty() {
+        return Ok(substituted);
+    }
+    let mut parsed = url::Url::parse(&substituted)
+        .map_err(|e| ProxyError::routing(format!("Invalid URL '{}': {}", substituted, e)))?;
+    parsed.query_pairs_mut().extend_pairs(pairs);
+    Ok(parsed.into())
+}
+
+/// Encode the body arguments of an HTTP agent call: as a form for
+/// `application/x-www-form-urlencoded`, as JSON otherwise
+pub fn http_request_body(params: &HttpParams, parameters: &Value, content_type: &str) -> Result<Vec<u8>> {
+    let serialize = |body: &Value| serde_json::to_vec(body)
+        .map_err(|e| ProxyError::validation(format!("Failed to serialize request body: {}", e)));
+    let Some(arguments) = parameters.as_object() else {
+        return serialize(parameters);
+    };
+    let fields = arguments.iter().filter(|(name, _)| params.in_body(name));
+
+    if !is_form(content_type) {
+        return serialize(&Value::Object(fields.map(|(name, value)| (name.clone(), value.clone())).collect()));
+    }
+    let mut form = url::form_urlencoded::Serializer::new(String::new());
+    for (name, value) in fields {
+        match value {
+            Value::Null => {}
+            Value::Array(items) => {
+                for item in items {
+                    form.append_pair(name, &value_to_string(item)?);
+                }
+            }
+            value => {
+                form.append_pair(name, &value_to_string(value)?);
+            }
+        }
+    }
+    Ok(form.finish().into_bytes())
+}
+
+fn is_form(content_type: &str) -> bool {
+    content_type.trim().to_ascii_lowercase().starts_with("application/x-www-form-urlencoded")
+}
+
 /// Substitute parameters in a JSON value recursively
 pub fn substitute_json_value(value: &Value, parameters: &Value) -> Result<Value> {
     match value {
@@ -130,19 +300,13 @@ pub fn substitute_env_vars(
 }
 
 /// Extract a pure placeholder value if the string contains only one placeholder
+///
+/// The value keeps its JSON type, so `"{{limit | default: 10}}"` can become a number.
 fn extract_pure_placeholder(template: &str, parameters: &Value) -> Result<Option<Value>> {
-    // Check if the template is exactly "{{key}}" or "{key}"
-    if let Some(obj) = parameters.as_object() {
-        for (key, value) in obj {
-            let double_placeholder = format!("{{{{{}}}}}", key);
-            let single_placeholder = format!("{{{}}}", key);
-
-            if template == double_placeholder || template == single_placeholder {
-                return Ok(Some(value.clone()));
-            }
-        }
+    match parse_template(template)?.as_slice() {
+        [Node::Value { path, filters, .. }] => Context::new(parameters).evaluate(path, filters),
+        _ => Ok(None),
     }
-    Ok(None)
 }
 
 /// Convert a JSON value to a string for parameter substitution
@@ -160,6 +324,334 @@ fn value_to_string(value: &Value) -> Result<String> {
     }
 }
 
+/// A parsed piece of a template
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    /// A placeholder, rendered as `raw` when its parameter is missing
+    Value { raw: String, path: String, filters: Vec<Filter> },
+    Block { kind: BlockKind, path: String, body: Vec<Node>, otherwise: Vec<Node> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    If,
+    Unless,
+    Each,
+}
+
+impl BlockKind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "if" => Some(Self::If),
+            "unless" => Some(Self::Unless),
+            "each" => Some(Self::Each),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::If => "if",
+            Self::Unless => "unless",
+            Self::Each => "each",
+        }
+    }
+}
+
+/// A `| name: argument` filter applied to a placeholder's value
+#[derive(Debug)]
+struct Filter {
+    name: String,
+    argument: Option<Value>,
+}
+
+impl Filter {
+    fn parse(filter: &str) -> Result<Self> {
+        let (name, argument) = match filter.split_once(':') {
+            Some((name, argument)) => (name.trim(), Some(parse_filter_argument(argument.trim()))),
+            None => (filter.trim(), None),
+        };
+        if !matches!(name, "default" | "url" | "shell" | "json" | "join") {
+            return Err(ProxyError::validation(format!(
+                "Unknown template filter '{}' (expected default, url, shell, json or join)", name
+            )));
+        }
+        Ok(Self { name: name.to_string(), argument })
+    }
+
+    /// Apply to a value; `None` is a missing parameter, which only `default` fills in
+    fn apply(&self, value: Option<Value>) -> Result<Option<Value>> {
+        if self.name == "default" {
+            return Ok(match value {
+                None | Some(Value::Null) => self.argument.clone(),
+                value => value,
+            });
+        }
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        let filtered = match self.name.as_str() {
+            "url" => urlencoding::encode(&value_to_string(&value)?).into_owned(),
+            "shell" => shell_quote(&value_to_string(&value)?),
+            "json" => serde_json::to_string(&value)
+                .map_err(|e| ProxyError::validation(format!("Failed to serialize parameter value: {}", e)))?,
+            _ => {
+                let separator = match &self.argument {
+                    Some(separator) => value_to_string(separator)?,
+                    None => ",".to_string(),
+                };
+                match &value {
+                    Value::Array(items) => items.iter().map(value_to_string).collect::<Result<Vec<_>>>()?.join(&separator),
+                    value => value_to_string(value)?,
+                }
+            }
+        };
+        Ok(Some(Value::String(filtered)))
+    }
+}
+
+/// A filter argument: a JSON literal, a single-quoted string or a bare word
+fn parse_filter_argument(argument: &str) -> Value {
+    if let Ok(value) = serde_json::from_str(argument) {
+        return value;
+    }
+    let unquoted = argument.strip_prefix('\'').and_then(|a| a.strip_suffix('\'')).unwrap_or(argument);
+    Value::String(unquoted.to_string())
+}
+
+/// Split on `|` outside of quotes
+fn split_filters(expression: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in expression.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '|') => {
+                parts.push(&expression[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&expression[start..]);
+    parts
+}
+
+fn tag_pattern() -> &'static regex::Regex {
+    static PATTERN: OnceLock<regex::Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        regex::Regex::new(r"\{\{(.*?)\}\}|\{([A-Za-z0-9_.-]+(?:\[\d+\])*)\}").expect("valid template pattern")
+    })
+}
+
+/// Parse a template into text, placeholders and blocks
+fn parse_template(template: &str) -> Result<Vec<Node>> {
+    struct OpenBlock {
+        kind: BlockKind,
+        path: String,
+        body: Vec<Node>,
+        otherwise: Option<Vec<Node>>,
+    }
+
+    fn push(root: &mut Vec<Node>, open: &mut [OpenBlock], node: Node) {
+        match open.last_mut() {
+            Some(OpenBlock { otherwise: Some(otherwise), .. }) => otherwise.push(node),
+            Some(block) => block.body.push(node),
+            None => root.push(node),
+        }
+    }
+
+    let mut root = Vec::new();
+    let mut open: Vec<OpenBlock> = Vec::new();
+    let mut last = 0;
+    for caps in tag_pattern().captures_iter(template) {
+        let whole = caps.get(0).expect("match");
+        if whole.start() > last {
+            push(&mut root, &mut open, Node::Text(template[last..whole.start()].to_string()));
+        }
+        last = whole.end();
+
+        if let Some(path) = caps.get(2) {
+            let node = Node::Value { raw: whole.as_str().to_string(), path: path.as_str().to_string(), filters: Vec::new() };
+            push(&mut root, &mut open, node);
+            continue;
+        }
+
+        let tag = caps[1].trim();
+        if let Some(block) = tag.strip_prefix('#') {
+            let (name, path) = block.split_once(char::is_whitespace).unwrap_or((block, ""));
+            let kind = BlockKind::from_name(name)
+                .ok_or_else(|| ProxyError::validation(format!("Unknown template block '{{{{#{}}}}}'", name)))?;
+            if path.trim().is_empty() {
+                return Err(ProxyError::validation(format!("Template block '{{{{#{}}}}}' needs a parameter", name)));
+            }
+            open.push(OpenBlock { kind, path: path.trim().to_string(), body: Vec::new(), otherwise: None });
+        } else if let Some(name) = tag.strip_prefix('/') {
+            let block = open.pop()
+                .filter(|block| block.kind.name() == name.trim())
+                .ok_or_else(|| ProxyError::validation(format!("Unexpected '{{{{/{}}}}}' in template", name.trim())))?;
+            let node = Node::Block { kind: block.kind, path: block.path, body: block.body, otherwise: block.otherwise.unwrap_or_default() };
+            push(&mut root, &mut open, node);
+        } else if tag == "else" {
+            match open.last_mut() {
+                Some(block) if block.otherwise.is_none() => block.otherwise = Some(Vec::new()),
+                _ => return Err(ProxyError::validation("Unexpected '{{else}}' in template")),
+            }
+        } else {
+            let mut parts = split_filters(tag).into_iter();
+            let path = parts.next().unwrap_or_default().trim().to_string();
+            let filters = parts.map(Filter::parse).collect::<Result<Vec<_>>>()?;
+            push(&mut root, &mut open, Node::Value { raw: whole.as_str().to_string(), path, filters });
+        }
+    }
+    if let Some(block) = open.last() {
+        return Err(ProxyError::validation(format!("Unclosed '{{{{#{}}}}}' in template", block.kind.name())));
+    }
+    if last < template.len() {
+        root.push(Node::Text(template[last..].to_string()));
+    }
+    Ok(root)
+}
+
+/// The item an `{{#each}}` block is rendering
+struct Scope<'a> {
+    this: &'a Value,
+    index: usize,
+    key: Option<&'a str>,
+    last: bool,
+}
+
+/// Parameters and enclosing `{{#each}}` items a template is rendered with
+struct Context<'a> {
+    parameters: &'a Value,
+    scopes: Vec<Scope<'a>>,
+}
+
+impl<'a> Context<'a> {
+    fn new(parameters: &'a Value) -> Self {
+        Self { parameters, scopes: Vec::new() }
+    }
+
+    fn render(&mut self, nodes: &[Node]) -> Result<String> {
+        let mut output = String::new();
+        for node in nodes {
+            match node {
+                Node::Text(text) => output.push_str(text),
+                Node::Value { raw, path, filters } => match self.evaluate(path, filters)? {
+                    Some(value) => output.push_str(&value_to_string(&value)?),
+                    None => output.push_str(raw),
+                },
+                Node::Block { kind: BlockKind::Each, path, body, otherwise } => {
+                    output.push_str(&self.render_each(path, body, otherwise)?.concat());
+                }
+                Node::Block { kind, path, body, otherwise } => {
+                    let truthy = is_truthy(self.lookup(path).as_deref());
+                    let branch = if truthy == (*kind == BlockKind::If) { body } else { otherwise };
+                    output.push_str(&self.render(branch)?);
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Render `body` once per item of an array or entry of an object, or
+    /// `otherwise` once if there are none
+    fn render_each(&mut self, path: &str, body: &[Node], otherwise: &[Node]) -> Result<Vec<String>> {
+        let items: Vec<(Option<&'a str>, &'a Value)> = match self.lookup(path) {
+            Some(Cow::Borrowed(Value::Array(items))) => items.iter().map(|item| (None, item)).collect(),
+            Some(Cow::Borrowed(Value::Object(entries))) => {
+                entries.iter().map(|(key, item)| (Some(key.as_str()), item)).collect()
+            }
+            _ => Vec::new(),
+        };
+        if items.is_empty() {
+            return Ok(vec![self.render(otherwise)?]);
+        }
+
+        let count = items.len();
+        let mut rendered = Vec::with_capacity(count);
+        for (index, (key, this)) in items.into_iter().enumerate() {
+            self.scopes.push(Scope { this, index, key, last: index + 1 == count });
+            let item = self.render(body);
+            self.scopes.pop();
+            rendered.push(item?);
+        }
+        Ok(rendered)
+    }
+
+    /// Value of a placeholder after its filters; `None` if its parameter is missing
+    fn evaluate(&self, path: &str, filters: &[Filter]) -> Result<Option<Value>> {
+        let mut value = self.lookup(path).map(Cow::into_owned);
+        for filter in filters {
+            value = filter.apply(value)?;
+        }
+        Ok(value)
+    }
+
+    /// Resolve `name`, `name.field`, `name[0]`, `this`, `this.field` or an `@` variable
+    fn lookup(&self, path: &str) -> Option<Cow<'a, Value>> {
+        let scope = self.scopes.last();
+        match path {
+            "" => return None,
+            "@index" => return scope.map(|s| Cow::Owned(Value::from(s.index))),
+            "@key" => return scope.and_then(|s| s.key).map(|key| Cow::Owned(Value::from(key))),
+            "@first" => return scope.map(|s| Cow::Owned(Value::Bool(s.index == 0))),
+            "@last" => return scope.map(|s| Cow::Owned(Value::Bool(s.last))),
+            _ => {}
+        }
+
+        if let Some(rest) = path.strip_prefix("this") {
+            if rest.is_empty() || rest.starts_with(['.', '[']) {
+                let this = scope.map_or(self.parameters, |s| s.this);
+                return navigate(this, rest.trim_start_matches('.')).map(Cow::Borrowed);
+            }
+        }
+        // Fields of the enclosing items shadow parameters of the same name
+        let name = path.split(['.', '[']).next().unwrap_or_default();
+        if let Some(this) = self.scopes.iter().rev().map(|s| s.this).find(|this| this.get(name).is_some()) {
+            return navigate(this, path).map(Cow::Borrowed);
+        }
+        if let Some(value) = self.parameters.get(path) {
+            return Some(Cow::Borrowed(value));
+        }
+        navigate(self.parameters, path).map(Cow::Borrowed)
+    }
+}
+
+/// Follow a `field.field[0]` path into a value
+fn navigate<'v>(mut value: &'v Value, path: &str) -> Option<&'v Value> {
+    if path.is_empty() {
+        return Some(value);
+    }
+    for segment in path.split('.') {
+        let (name, indexes) = segment.split_once('[').unwrap_or((segment, ""));
+        if !name.is_empty() {
+            value = value.get(name)?;
+        }
+        if !indexes.is_empty() {
+            for index in indexes.trim_end_matches(']').split("][") {
+                value = value.get(index.parse::<usize>().ok()?)?;
+            }
+        }
+    }
+    Some(value)
+}
+
+/// Whether a value selects the `{{#if}}` branch: present, and not false, zero or empty
+fn is_truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_f64() != Some(0.0),
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Array(items)) => !items.is_empty(),
+        Some(Value::Object(entries)) => !entries.is_empty(),
+    }
+}
+
 /// Advanced parameter substitution with type conversion and validation
 pub struct ParameterSubstitution {
     /// Whether to allow missing parameters (default: false)
@@ -288,6 +780,26 @@ mod tests {
         assert_eq!(result, "echo hello world");
     }
 
+    #[test]
+    fn test_shell_command_substitution() {
+        let params = json!({
+            "path": "/var/log/app's logs",
+            "evil": "{path}; rm -rf ~",
+            "files": ["a b", "c"],
+            "lines": 20
+        });
+
+        let result = substitute_shell_command("tail -n {{lines}} {{path}}", &params).unwrap();
+        assert_eq!(result, r#"tail -n '20' '/var/log/app'\''s logs'"#);
+
+        // Substituted values are not scanned again
+        let result = substitute_shell_command("echo {evil} {missing}", &params).unwrap();
+        assert_eq!(result, "echo '{path}; rm -rf ~' {missing}");
+
+        let result = substitute_shell_command("cat {files[0]} {{files[1]}}", &params).unwrap();
+        assert_eq!(result, "cat 'a b' 'c'");
+    }
+
     #[test]
     fn test_double_brace_substitution() {
         let template = "echo {{message}}";
@@ -336,4 +848,120 @@ mod tests {
         let result = substitute_parameter_string(template, &params).unwrap();
         assert_eq!(result, "verbose=true");
     }
+
+    #[test]
+    fn test_filters_and_defaults() {
+        let params = json!({"query": "rust & go", "path": "it's", "filter": {"a": 1}, "tags": ["x", "y"], "empty": null});
+
+        let result = substitute_parameter_string(
+            "q={{query | url}} p={{path | shell}} f={{filter | json}} t={{tags | join: ';'}}",
+            &params,
+        ).unwrap();
+        assert_eq!(result, r#"q=rust%20%26%20go p='it'\''s' f={"a":1} t=x;y"#);
+
+        let result = substitute_parameter_string("{{limit | default: 10}} {{empty | default: \"none\"}} {{missing}}", &params).unwrap();
+        assert_eq!(result, "10 none {{missing}}");
+
+        // A lone placeholder keeps the JSON type of its value
+        assert_eq!(substitute_json_value(&json!("{{limit | default: 10}}"), &params).unwrap(), json!(10));
+        assert!(substitute_parameter_string("{{query | upper}}", &params).is_err());
+    }
+
+    #[test]
+    fn test_conditionals() {
+        let params = json!({"verbose": true, "count": 0, "name": "app"});
+        let template = "{{#if verbose}}-v{{else}}-q{{/if}} {{#if count}}-n {{count}}{{/if}}{{#unless count}}all{{/unless}}";
+        assert_eq!(substitute_parameter_string(template, &params).unwrap(), "-v all");
+        assert_eq!(substitute_parameter_string("{{#if missing}}yes{{else}}{{name}}{{/if}}", &params).unwrap(), "app");
+
+        assert!(substitute_parameter_string("{{#if verbose}}-v", &params).is_err());
+        assert!(substitute_parameter_string("{{#if verbose}}-v{{/each}}", &params).is_err());
+        assert!(substitute_parameter_string("{{#switch verbose}}{{/switch}}", &params).is_err());
+    }
+
+    #[test]
+    fn test_each() {
+        let params = json!({
+            "ids": [3, 5],
+            "labels": {"env": "prod"},
+            "users": [{"name": "ann"}, {"name": "bo"}],
+            "sep": ","
+        });
+        let template = "{{#each ids}}{{#if @first}}?{{else}}&{{/if}}id={{this}}{{/each}}";
+        assert_eq!(substitute_parameter_string(template, &params).unwrap(), "?id=3&id=5");
+        assert_eq!(
+            substitute_parameter_string("{{#each users}}{{name}}{{#unless @last}}{{sep}}{{/unless}}{{/each}}", &params).unwrap(),
+            "ann,bo"
+        );
+        assert_eq!(substitute_parameter_string("{{#each labels}}{{@key}}={{this}}{{/each}}", &params).unwrap(), "env=prod");
+        assert_eq!(substitute_parameter_string("{{#each none}}x{{else}}empty{{/each}}", &params).unwrap(), "empty");
+    }
+
+    #[test]
+    fn test_block_arguments() {
+        let args = vec![
+            "grep".to_string(),
+            "{{#each patterns}}--regexp={{this}}{{/each}}".to_string(),
+            "{{#if ignore_case}}-i{{/if}}".to_string(),
+            "{{#if count}}--count{{/if}}".to_string(),
+            "{{file}}".to_string(),
+        ];
+        let params = json!({"patterns": ["a b", "c"], "ignore_case": false, "count": true, "file": "log.txt"});
+        let result = substitute_parameters(&args, &params).unwrap();
+        assert_eq!(result, vec!["grep", "--regexp=a b", "--regexp=c", "--count", "log.txt"]);
+    }
+
+    #[test]
+    fn test_substituted_values_are_not_rescanned() {
+        let params = json!({"a": "{b}", "b": "x"});
+        assert_eq!(substitute_parameter_string("{a} {b}", &params).unwrap(), "{b} x");
+    }
+
+    #[test]
+    fn test_expand_optional_args() {
+        let group = |param: &str, args: &[&str], positional: bool| OptionalArg {
+            param: param.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            positional,
+        };
+        let groups = vec![
+            group("files", &["{{files}}"], true),
+            group("verbose", &["--verbose"], false),
+            group("quiet", &["--quiet"], false),
+            group("depth", &["--depth", "{{depth}}"], false),
+            group("missing", &["--missing"], false),
+            group("output", &["--output={{output}}"], false),
+        ];
+        let params = json!({"verbose": true, "quiet": false, "depth": -2, "files": ["a.txt", "-rf"], "output": "-"});
+
+        let args = expand_optional_args(&groups, &params).unwrap();
+        assert_eq!(args, vec!["--verbose", "--depth", "-2", "--output=-", "--", "a.txt", "-rf"]);
+
+        // An option value that is itself an option is refused
+        let injected = json!({"depth": "--output=/etc/x"});
+        assert!(expand_optional_args(&groups, &injected).is_err());
+    }
+
+    #[test]
+    fn test_http_params() {
+        let params = HttpParams {
+            path: vec!["key".to_string()],
+            query: vec![("Limit".to_string(), "limit".to_string()), ("tag".to_string(), "tags".to_string())],
+            body: None,
+        };
+        let args = json!({"key": "a/b c", "limit": 10, "tags": ["x", "y"], "name": "n", "note": null});
+
+        let url = substitute_http_url("https://api.example.com/items/{key}?v=1", &params, &args).unwrap();
+        assert_eq!(url, "https://api.example.com/items/a%2Fb%20c?v=1&Limit=10&tag=x&tag=y");
+        assert_eq!(substitute_http_url("https://api.example.com/{key}", &params, &json!({"key": "k"})).unwrap(), "https://api.example.com/k");
+
+        let body: Value = serde_json::from_slice(&http_request_body(&params, &args, "application/json").unwrap()).unwrap();
+        assert_eq!(body, json!({"name": "n", "note": null}));
+        let form = http_request_body(&params, &args, "application/x-www-form-urlencoded; charset=UTF-8").unwrap();
+        assert_eq!(String::from_utf8(form).unwrap(), "name=n");
+
+        // Without bindings every argument is the body
+        let body: Value = serde_json::from_slice(&http_request_body(&HttpParams::default(), &args, "application/json").unwrap()).unwrap();
+        assert_eq!(body, args);
+    }
 }
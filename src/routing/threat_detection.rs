@@ -0,0 +1,556 @@
+//! Behavioral anomaly detection per API key and user
+//!
+//! Each API key and user gets a baseline of its usual behavior: the tools it
+//! calls, its calls per minute, the size of its arguments and the hours it
+//! is active in. Once `learning_calls` calls have been seen, calls deviating
+//! from the baseline are scored from 0 to 100:
+//!
+//! | Deviation | Score |
+//! |-----------|-------|
+//! | Tool never called before | 30 |
+//! | Hour with under 1% of the calls | 15 |
+//! | Arguments over `argument_size_multiplier` times the usual size | 25 |
+//! | Calls this minute over `rate_multiplier` times the usual rate | 40, or 60 at twice that |
+//! | ... of a read-only tool, as in mass data reads | +20 |
+//!
+//! ```yaml
+//! threat_detection:
+//!   learning_calls: 100      # default
+//!   alert_score: 50          # default; audit event for scores at or above
+//!   throttle_score: 80       # optional; refuse calls for throttle_seconds
+//!   throttle_seconds: 300    # default
+//! ```
+//!
+//! Calls scoring at or above `alert_score` raise an `anomaly_detected` audit
+//! event and don't update the baseline. With `throttle_score`, a call
+//! scoring at least that is refused, and so is every call by the same API
+//! key or user until `throttle_seconds` have passed.
+
+use crate::error::{ProxyError, Result};
+use crate::mcp::types::ToolCall;
+use crate::registry::types::ToolDefinition;
+use crate::routing::quota::{QuotaScope, QuotaSubjects};
+use crate::routing::types::AgentResult;
+use chrono::{DateTime, Duration, DurationRound, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, RwLock};
+use tracing::warn;
+
+/// Anomalies kept for the dashboard
+const RECENT_ANOMALIES: usize = 100;
+/// Calls per minute never considered a burst
+const MIN_BURST: f64 = 10.0;
+/// Argument size, in bytes, never considered unusual
+const MIN_ARGUMENT_SIZE: f64 = 1024.0;
+/// Share of calls below which an hour is unusual
+const UNUSUAL_HOUR_SHARE: f64 = 0.01;
+
+fn default_learning_calls() -> u64 {
+    100
+}
+
+fn default_alert_score() -> u32 {
+    50
+}
+
+fn default_throttle_seconds() -> u64 {
+    300
+}
+
+fn default_rate_multiplier() -> f64 {
+    5.0
+}
+
+fn default_argument_size_multiplier() -> f64 {
+    10.0
+}
+
+/// Anomaly detection applied by the router
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThreatDetectionConfig {
+    /// Calls seen before a baseline is used
+    #[serde(default = "default_learning_calls")]
+    pub learning_calls: u64,
+    /// Score from which calls raise an audit event
+    #[serde(default = "default_alert_score")]
+    pub alert_score: u32,
+    /// Score from which callers are throttled; never when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throttle_score: Option<u32>,
+    /// How long a throttled caller's calls are refused
+    #[serde(default = "default_throttle_seconds")]
+    pub throttle_seconds: u64,
+    /// Calls per minute, relative to the usual rate, counting as a burst
+    #[serde(default = "default_rate_multiplier")]
+    pub rate_multiplier: f64,
+    /// Argument size, relative to the usual size, counting as unusual
+    #[serde(default = "default_argument_size_multiplier")]
+    pub argument_size_multiplier: f64,
+}
+
+impl Default for ThreatDetectionConfig {
+    fn default() -> Self {
+        Self {
+            learning_calls: default_learning_calls(),
+            alert_score: default_alert_score(),
+            throttle_score: None,
+            throttle_seconds: default_throttle_seconds(),
+            rate_multiplier: default_rate_multiplier(),
+            argument_size_multiplier: default_argument_size_multiplier(),
+        }
+    }
+}
+
+impl ThreatDetectionConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.alert_score > 100 || self.throttle_score.is_some_and(|score| score > 100) {
+            return Err(ProxyError::config("threat_detection scores range from 0 to 100"));
+        }
+        if !(self.rate_multiplier > 1.0 && self.argument_size_multiplier > 1.0) {
+            return Err(ProxyError::config("threat_detection.rate_multiplier and argument_size_multiplier must be over 1"));
+        }
+        if self.throttle_score.is_some() && self.throttle_seconds == 0 {
+            return Err(ProxyError::config("threat_detection.throttle_seconds must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+/// How alarming an anomaly is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    fn of(score: u32) -> Self {
+        if score >= 80 {
+            Severity::High
+        } else if score >= 50 {
+            Severity::Medium
+        } else {
+            Severity::Low
+        }
+    }
+}
+
+/// A call that deviated from its caller's baseline
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Anomaly {
+    pub timestamp: DateTime<Utc>,
+    pub scope: QuotaScope,
+    /// API key or user
+    pub key: String,
+    pub tool: String,
+    pub score: u32,
+    pub severity: Severity,
+    pub reasons: Vec<String>,
+    /// Whether the caller was throttled because of it
+    pub throttled: bool,
+}
+
+/// A call refused because its caller is throttled
+#[derive(Debug, Clone, PartialEq)]
+pub struct Throttled {
+    pub scope: QuotaScope,
+    pub key: String,
+    pub until: DateTime<Utc>,
+    /// The anomaly that started the throttling, if this call caused it
+    pub anomaly: Option<Anomaly>,
+}
+
+impl Throttled {
+    /// Tool error describing the throttling
+    pub fn into_agent_result(self) -> AgentResult {
+        let who = match self.scope {
+            QuotaScope::ApiKey => "API key",
+            QuotaScope::User => "user",
+            QuotaScope::Role => "role",
+        };
+        AgentResult {
+            success: false,
+            data: None,
+            error: Some(format!(
+                "Calls by {} '{}' are throttled until {} after anomalous activity",
+                who, self.key, self.until.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            )),
+            metadata: Some(json!({
+                "error_category": "anomaly_throttled",
+                "throttle": {
+                    "scope": self.scope,
+                    "key": self.key,
+                    "until": self.until,
+                    "anomaly": self.anomaly,
+                },
+            })),
+        }
+    }
+}
+
+/// Running mean and variance (Welford's algorithm)
+#[derive(Debug, Clone, Copy, Default)]
+struct Stats {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Stats {
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn std_dev(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            (self.m2 / (self.count - 1) as f64).sqrt()
+        }
+    }
+
+    /// Whether `value` is over `multiplier` times the mean, four standard
+    /// deviations above it, and `floor`
+    fn exceeded_by(&self, value: f64, multiplier: f64, floor: f64) -> bool {
+        self.count > 0 && value > floor && value > self.mean * multiplier && value > self.mean + 4.0 * self.std_dev()
+    }
+}
+
+/// Usual behavior of one API key or user
+#[derive(Debug, Clone, Default)]
+struct Baseline {
+    /// Calls learned from
+    calls: u64,
+    tools: HashMap<String, u64>,
+    hours: [u64; 24],
+    argument_size: Stats,
+    /// Calls per minute, over the minutes with calls
+    rate: Stats,
+    minute: Option<DateTime<Utc>>,
+    calls_this_minute: u64,
+    /// Whether a burst was flagged this minute, keeping it out of `rate`
+    burst_this_minute: bool,
+    throttled_until: Option<DateTime<Utc>>,
+    anomalies: u64,
+    rejected: u64,
+}
+
+impl Baseline {
+    /// Count a call in the current minute, folding the previous minute into the rate
+    fn count_call(&mut self, now: DateTime<Utc>) {
+        let minute = now.duration_trunc(Duration::minutes(1)).unwrap_or(now);
+        if self.minute != Some(minute) {
+            if self.minute.is_some() && !self.burst_this_minute {
+                self.rate.add(self.calls_this_minute as f64);
+            }
+            self.minute = Some(minute);
+            self.calls_this_minute = 0;
+            self.burst_this_minute = false;
+        }
+        self.calls_this_minute += 1;
+    }
+
+    /// Score a call, with the reasons for it
+    fn score(&mut self, config: &ThreatDetectionConfig, tool_call: &ToolCall, read_only: bool, size: f64, hour: usize) -> (u32, Vec<String>) {
+        let mut score = 0;
+        let mut reasons = Vec::new();
+        if !self.tools.contains_key(&tool_call.name) {
+            score += 30;
+            reasons.push(format!("first call to tool '{}'", tool_call.name));
+        }
+        if (self.hours[hour] as f64) < self.calls as f64 * UNUSUAL_HOUR_SHARE {
+            score += 15;
+            reasons.push(format!("unusual hour ({:02}:00 UTC)", hour));
+        }
+        if self.argument_size.exceeded_by(size, config.argument_size_multiplier, MIN_ARGUMENT_SIZE) {
+            score += 25;
+            reasons.push(format!("{} byte arguments, usually {:.0}", size, self.argument_size.mean));
+        }
+        let rate = self.calls_this_minute as f64;
+        if self.rate.exceeded_by(rate, config.rate_multiplier, MIN_BURST) {
+            self.burst_this_minute = true;
+            let sharp = self.rate.exceeded_by(rate, config.rate_multiplier * 2.0, MIN_BURST * 2.0);
+            score += if sharp { 60 } else { 40 };
+            reasons.push(format!("{} calls this minute, usually {:.1}", rate, self.rate.mean));
+            if read_only {
+                score += 20;
+                reasons.push("burst of reads".to_string());
+            }
+        }
+        (score.min(100), reasons)
+    }
+
+    fn learn(&mut self, tool: &str, size: f64, hour: usize) {
+        self.calls += 1;
+        *self.tools.entry(tool.to_string()).or_default() += 1;
+        self.hours[hour] += 1;
+        self.argument_size.add(size);
+    }
+}
+
+/// Baseline of one API key or user, as reported by the dashboard
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BaselineReport {
+    pub scope: QuotaScope,
+    pub key: String,
+    /// Calls the baseline was learned from
+    pub calls: u64,
+    /// Whether enough calls were seen for the baseline to be used
+    pub learned: bool,
+    /// Tools called, most called first
+    pub tools: Vec<(String, u64)>,
+    /// Calls per UTC hour of the day
+    pub hours: Vec<u64>,
+    pub average_argument_size: f64,
+    pub average_calls_per_minute: f64,
+    pub anomalies: u64,
+    /// Calls refused while throttled
+    pub rejected: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttled_until: Option<DateTime<Utc>>,
+}
+
+/// Learns each caller's baseline and scores calls against it
+#[derive(Default)]
+pub struct ThreatDetector {
+    config: RwLock<Option<ThreatDetectionConfig>>,
+    baselines: Mutex<HashMap<(QuotaScope, String), Baseline>>,
+    recent: Mutex<VecDeque<Anomaly>>,
+}
+
+impl std::fmt::Debug for ThreatDetector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ThreatDetector").finish_non_exhaustive()
+    }
+}
+
+impl ThreatDetector {
+    pub fn new(config: ThreatDetectionConfig) -> Self {
+        Self { config: RwLock::new(Some(config)), ..Default::default() }
+    }
+
+    /// Replace the detection settings, or turn detection off; baselines are kept
+    pub fn configure(&self, config: Option<ThreatDetectionConfig>) {
+        if let Ok(mut current) = self.config.write() {
+            *current = config;
+        }
+    }
+
+    /// Score a call by `subjects`, refusing it if they are or get throttled
+    pub fn inspect(&self, tool_call: &ToolCall, tool_def: &ToolDefinition, subjects: &QuotaSubjects) -> std::result::Result<(), Throttled> {
+        self.inspect_at(tool_call, tool_def.is_read_only(), subjects, Utc::now())
+    }
+
+    fn inspect_at(&self, tool_call: &ToolCall, read_only: bool, subjects: &QuotaSubjects, now: DateTime<Utc>) -> std::result::Result<(), Throttled> {
+        let Some(config) = self.config.read().ok().and_then(|config| config.clone()) else {
+            return Ok(());
+        };
+        let Ok(mut baselines) = self.baselines.lock() else {
+            return Ok(());
+        };
+        let keys: Vec<(QuotaScope, &str)> = subjects.api_key.iter().map(|key| (QuotaScope::ApiKey, key.as_str()))
+            .chain(subjects.user.iter().map(|user| (QuotaScope::User, user.as_str())))
+            .collect();
+
+        for &(scope, key) in &keys {
+            let Some(baseline) = baselines.get_mut(&(scope, key.to_string())) else { continue };
+            if let Some(until) = baseline.throttled_until.filter(|until| *until > now) {
+                baseline.rejected += 1;
+                return Err(Throttled { scope, key: key.to_string(), until, anomaly: None });
+            }
+        }
+
+        let size = serde_json::to_string(&tool_call.arguments).map_or(0, |arguments| arguments.len()) as f64;
+        let hour = now.hour() as usize;
+        let mut throttled = None;
+        for (scope, key) in keys {
+            let baseline = baselines.entry((scope, key.to_string())).or_default();
+            baseline.count_call(now);
+            if baseline.calls < config.learning_calls {
+                baseline.learn(&tool_call.name, size, hour);
+                continue;
+            }
+            let (score, reasons) = baseline.score(&config, tool_call, read_only, size, hour);
+            if score < config.alert_score {
+                baseline.learn(&tool_call.name, size, hour);
+                continue;
+            }
+
+            baseline.anomalies += 1;
+            let throttle = config.throttle_score.is_some_and(|throttle_score| score >= throttle_score);
+            let anomaly = Anomaly {
+                timestamp: now,
+                scope,
+                key: key.to_string(),
+                tool: tool_call.name.clone(),
+                score,
+                severity: Severity::of(score),
+                reasons,
+                throttled: throttle,
+            };
+            warn!(
+                target: "audit",
+                event_type = "anomaly_detected",
+                scope = ?scope,
+                key = %key,
+                tool = %tool_call.name,
+                score = score,
+                severity = ?anomaly.severity,
+                reasons = ?anomaly.reasons,
+                throttled = throttle,
+                "Anomalous tool call"
+            );
+            if let Ok(mut recent) = self.recent.lock() {
+                recent.push_back(anomaly.clone());
+                while recent.len() > RECENT_ANOMALIES {
+                    recent.pop_front();
+                }
+            }
+            if throttle && throttled.is_none() {
+                let until = now + Duration::seconds(config.throttle_seconds as i64);
+                baseline.throttled_until = Some(until);
+                throttled = Some(Throttled { scope, key: key.to_string(), until, anomaly: Some(anomaly) });
+            }
+        }
+        throttled.map_or(Ok(()), Err)
+    }
+
+    /// Let a throttled API key or user call tools again
+    pub fn release(&self, scope: QuotaScope, key: &str) -> bool {
+        let Ok(mut baselines) = self.baselines.lock() else {
+            return false;
+        };
+        baselines.get_mut(&(scope, key.to_string()))
+            .and_then(|baseline| baseline.throttled_until.take())
+            .is_some()
+    }
+
+    /// Anomalies detected most recently, newest first
+    pub fn recent_anomalies(&self) -> Vec<Anomaly> {
+        self.recent.lock().map(|recent| recent.iter().rev().cloned().collect()).unwrap_or_default()
+    }
+
+    /// Baseline of every API key and user that made calls
+    pub fn report(&self) -> Vec<BaselineReport> {
+        let learning_calls = self.config.read().ok()
+            .and_then(|config| config.as_ref().map(|config| config.learning_calls))
+            .unwrap_or_else(default_learning_calls);
+        let Ok(baselines) = self.baselines.lock() else {
+            return Vec::new();
+        };
+        let now = Utc::now();
+        let mut report: Vec<BaselineReport> = baselines.iter().map(|((scope, key), baseline)| {
+            let mut tools: Vec<(String, u64)> = baseline.tools.iter().map(|(tool, calls)| (tool.clone(), *calls)).collect();
+            tools.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            BaselineReport {
+                scope: *scope,
+                key: key.clone(),
+                calls: baseline.calls,
+                learned: baseline.calls >= learning_calls,
+                tools,
+                hours: baseline.hours.to_vec(),
+                average_argument_size: baseline.argument_size.mean.round(),
+                average_calls_per_minute: (baseline.rate.mean * 100.0).round() / 100.0,
+                anomalies: baseline.anomalies,
+                rejected: baseline.rejected,
+                throttled_until: baseline.throttled_until.filter(|until| *until > now),
+            }
+        }).collect();
+        report.sort_by(|a, b| (a.scope, &a.key).cmp(&(b.scope, &b.key)));
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn detector(throttle_score: Option<u32>) -> ThreatDetector {
+        ThreatDetector::new(ThreatDetectionConfig { learning_calls: 20, throttle_score, ..Default::default() })
+    }
+
+    fn alice() -> QuotaSubjects {
+        QuotaSubjects { user: Some("alice".to_string()), ..Default::default() }
+    }
+
+    fn call(tool: &str) -> ToolCall {
+        ToolCall::new(tool.to_string(), json!({ "id": 42 }))
+    }
+
+    /// Two calls a minute to `read_record` during office hours
+    fn learn(detector: &ThreatDetector) -> DateTime<Utc> {
+        let mut now = Utc.with_ymd_and_hms(2026, 10, 15, 9, 0, 0).unwrap();
+        for _ in 0..15 {
+            for _ in 0..2 {
+                detector.inspect_at(&call("read_record"), true, &alice(), now).unwrap();
+            }
+            now += Duration::minutes(1);
+        }
+        now
+    }
+
+    #[test]
+    fn test_usual_calls_are_not_anomalies() {
+        let detector = detector(Some(80));
+        let now = learn(&detector);
+        assert!(detector.inspect_at(&call("read_record"), true, &alice(), now).is_ok());
+        assert!(detector.recent_anomalies().is_empty());
+        let report = detector.report();
+        assert!(report[0].learned);
+        assert_eq!(report[0].average_calls_per_minute, 2.0);
+    }
+
+    #[test]
+    fn test_mass_reads_are_throttled() {
+        let detector = detector(Some(80));
+        let now = learn(&detector);
+        let mut outcome = Ok(());
+        for _ in 0..25 {
+            outcome = detector.inspect_at(&call("read_record"), true, &alice(), now);
+            if outcome.is_err() {
+                break;
+            }
+        }
+        let throttled = outcome.unwrap_err();
+        let anomaly = throttled.anomaly.unwrap();
+        assert_eq!(anomaly.severity, Severity::High);
+        assert!(anomaly.reasons.contains(&"burst of reads".to_string()));
+
+        // Every call is refused until the throttling ends or is released
+        let refused = detector.inspect_at(&call("read_record"), true, &alice(), now + Duration::minutes(1));
+        assert!(refused.unwrap_err().anomaly.is_none());
+        assert!(detector.release(QuotaScope::User, "alice"));
+        assert!(detector.inspect_at(&call("read_record"), true, &alice(), now + Duration::minutes(1)).is_ok());
+    }
+
+    #[test]
+    fn test_unusual_tool_hour_and_arguments_are_scored() {
+        let detector = detector(None);
+        learn(&detector);
+        let night = Utc.with_ymd_and_hms(2026, 10, 16, 3, 0, 0).unwrap();
+        let dump = ToolCall::new("export_table".to_string(), json!({ "query": "x".repeat(5000) }));
+        assert!(detector.inspect_at(&dump, false, &alice(), night).is_ok());
+
+        let anomalies = detector.recent_anomalies();
+        assert_eq!(anomalies[0].score, 70);
+        assert_eq!(anomalies[0].severity, Severity::Medium);
+        assert!(!anomalies[0].throttled);
+        assert_eq!(anomalies[0].reasons.len(), 3);
+    }
+
+    #[test]
+    fn test_disabled_detection() {
+        let detector = ThreatDetector::default();
+        let now = learn(&detector);
+        assert!(detector.inspect_at(&call("export_table"), false, &alice(), now).is_ok());
+        assert!(detector.report().is_empty());
+    }
+}
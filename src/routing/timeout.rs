@@ -1,20 +1,81 @@
 //! Centralized timeout configuration for agent execution
+//!
+//! The timeout of a call is resolved from the most specific level that sets
+//! one: the call itself (`_meta.timeoutSeconds`), the tool, its agent type,
+//! then the global default:
+//!
+//! ```yaml
+//! timeouts:
+//!   default_timeout_secs: 30
+//!   max_timeout_secs: 300          # cap on per-call overrides
+//!   per_agent_type:
+//!     llm: 90
+//!   per_tool:
+//!     generate_report: 240
+//! ```
 
-use serde::{Deserialize, Serialize};
+use crate::routing::types::AgentType;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
+use std::future::Future;
 use std::time::Duration;
 
+tokio::task_local! {
+    static CALL_TIMEOUT: u64;
+}
+
+/// Run `future` with its tool calls overriding their timeout with `timeout_secs`
+pub async fn with_call_timeout<F: Future>(timeout_secs: Option<u64>, future: F) -> F::Output {
+    match timeout_secs {
+        Some(timeout_secs) => CALL_TIMEOUT.scope(timeout_secs, future).await,
+        None => future.await,
+    }
+}
+
+/// Timeout the current call asked for, in seconds
+pub fn call_timeout() -> Option<u64> {
+    CALL_TIMEOUT.try_with(|timeout| *timeout).ok()
+}
+
+/// Level of the timeout hierarchy a timeout was resolved from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutSource {
+    Default,
+    AgentType,
+    Tool,
+    Call,
+}
+
+/// The timeout a call runs with, and where it came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct EffectiveTimeout {
+    pub seconds: u64,
+    pub source: TimeoutSource,
+}
+
 /// Timeout configuration for different agent types
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TimeoutConfig {
     /// Default timeout for all agent types (in seconds)
     pub default_timeout_secs: u64,
-    /// Per-agent-type timeout configuration
+    /// Per-agent-type timeout configuration; entries given in a config file
+    /// are added to the built-in ones
+    #[serde(deserialize_with = "deserialize_per_agent_type")]
     pub per_agent_type: HashMap<String, u64>,
+    /// Per-tool timeouts, taking precedence over a `timeout` in the tool's routing config
+    pub per_tool: HashMap<String, u64>,
     /// Global maximum timeout (safety limit)
     pub max_timeout_secs: u64,
 }
 
+fn deserialize_per_agent_type<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<String, u64>, D::Error> {
+    let mut per_agent_type = TimeoutConfig::default().per_agent_type;
+    per_agent_type.extend(HashMap::<String, u64>::deserialize(deserializer)?);
+    Ok(per_agent_type)
+}
+
 impl Default for TimeoutConfig {
     fn default() -> Self {
         let mut per_agent_type = HashMap::new();
@@ -25,10 +86,20 @@ impl Default for TimeoutConfig {
         per_agent_type.insert("llm".to_string(), 60);         // 60 seconds for LLM calls (can be slow)
         per_agent_type.insert("websocket".to_string(), 30);   // 30 seconds for WebSocket operations
         per_agent_type.insert("database".to_string(), 30);    // 30 seconds for database queries
+        per_agent_type.insert("lambda".to_string(), 60);      // 60 seconds for synchronous Lambda invocations
+        per_agent_type.insert("kafka".to_string(), 30);       // 30 seconds for Kafka replies
+        per_agent_type.insert("amqp".to_string(), 30);        // 30 seconds for AMQP RPC replies
+        per_agent_type.insert("ssh".to_string(), 60);         // 60 seconds for remote commands
+        per_agent_type.insert("docker".to_string(), 120);     // 120 seconds for containers (image pulls, startup)
+        per_agent_type.insert("kubernetes_job".to_string(), 600); // 10 minutes for Kubernetes Jobs (scheduling, batch work)
+        per_agent_type.insert("webhook".to_string(), 600);    // 10 minutes for webhook jobs to call back
+        per_agent_type.insert("wasm".to_string(), 10);        // 10 seconds for sandboxed WebAssembly modules
+        per_agent_type.insert("script".to_string(), 5);       // 5 seconds for embedded Rhai/Lua scripts
         
         Self {
             default_timeout_secs: 30,
             per_agent_type,
+            per_tool: HashMap::new(),
             max_timeout_secs: 300, // 5 minutes maximum
         }
     }
@@ -40,6 +111,7 @@ impl TimeoutConfig {
         Self {
             default_timeout_secs,
             per_agent_type: HashMap::new(),
+            per_tool: HashMap::new(),
             max_timeout_secs,
         }
     }
@@ -67,6 +139,36 @@ impl TimeoutConfig {
         Duration::from_secs(timeout_secs)
     }
 
+    /// Resolve the timeout of a call to `tool_name`
+    ///
+    /// Priority: call override > per-tool config > tool's routing config >
+    /// agent type > default. Only call overrides, which come from clients,
+    /// are capped at `max_timeout_secs`.
+    pub fn resolve(&self, agent_type: &str, tool_name: &str, tool_timeout: Option<u64>, call_timeout: Option<u64>) -> EffectiveTimeout {
+        let (seconds, source) = if let Some(call_timeout) = call_timeout {
+            (call_timeout.min(self.max_timeout_secs), TimeoutSource::Call)
+        } else if let Some(tool_timeout) = self.per_tool.get(tool_name).copied().or(tool_timeout) {
+            (tool_timeout, TimeoutSource::Tool)
+        } else if let Some(&agent_timeout) = self.per_agent_type.get(agent_type) {
+            (agent_timeout, TimeoutSource::AgentType)
+        } else {
+            (self.default_timeout_secs, TimeoutSource::Default)
+        };
+        EffectiveTimeout { seconds, source }
+    }
+
+    /// Set an agent's timeout to the one resolved for the current call to `tool_name`
+    ///
+    /// The agent's own timeout is taken as the tool level. Agents without a
+    /// timeout are left as they are.
+    pub fn apply(&self, tool_name: &str, agent: &mut AgentType) -> Option<EffectiveTimeout> {
+        let agent_type = agent.type_name();
+        let timeout = agent.timeout_mut()?;
+        let effective = self.resolve(agent_type, tool_name, *timeout, call_timeout());
+        *timeout = Some(effective.seconds);
+        Some(effective)
+    }
+
     /// Get timeout in seconds (for backward compatibility)
     pub fn get_timeout_secs(&self, agent_type: &str, tool_override: Option<u64>) -> u64 {
         self.get_timeout(agent_type, tool_override).as_secs()
@@ -86,16 +188,16 @@ impl TimeoutConfig {
             return Err("Default timeout cannot exceed maximum timeout".to_string());
         }
         
+        // Agent types may exceed the maximum: the built-in kubernetes_job timeout does
         for (agent_type, &timeout) in &self.per_agent_type {
             if timeout == 0 {
                 return Err(format!("Timeout for agent type '{}' cannot be zero", agent_type));
             }
-            
-            if timeout > self.max_timeout_secs {
-                return Err(format!(
-                    "Timeout for agent type '{}' ({}) exceeds maximum timeout ({})",
-                    agent_type, timeout, self.max_timeout_secs
-                ));
+        }
+
+        for (tool, &timeout) in &self.per_tool {
+            if timeout == 0 {
+                return Err(format!("Timeout for tool '{}' cannot be zero", tool));
             }
         }
         
@@ -121,6 +223,7 @@ impl TimeoutConfigBuilder {
             config: TimeoutConfig {
                 default_timeout_secs: 30,
                 per_agent_type: HashMap::new(), // Start with empty map
+                per_tool: HashMap::new(),
                 max_timeout_secs: 300,
             },
         }
@@ -266,4 +369,43 @@ mod tests {
             .build();
         assert!(config.is_err());
     }
+
+    #[test]
+    fn test_timeout_hierarchy() {
+        let mut config = TimeoutConfig::default();
+        config.per_tool.insert("report".to_string(), 240);
+
+        let resolved = |tool: &str, tool_timeout, call_timeout| config.resolve("llm", tool, tool_timeout, call_timeout);
+        assert_eq!(resolved("chat", None, None), EffectiveTimeout { seconds: 60, source: TimeoutSource::AgentType });
+        assert_eq!(resolved("chat", Some(45), None), EffectiveTimeout { seconds: 45, source: TimeoutSource::Tool });
+        assert_eq!(resolved("report", Some(45), None), EffectiveTimeout { seconds: 240, source: TimeoutSource::Tool });
+        assert_eq!(resolved("report", None, Some(10)), EffectiveTimeout { seconds: 10, source: TimeoutSource::Call });
+        assert_eq!(resolved("chat", None, Some(900)), EffectiveTimeout { seconds: 300, source: TimeoutSource::Call });
+        assert_eq!(config.resolve("custom", "chat", None, None).source, TimeoutSource::Default);
+    }
+
+    #[tokio::test]
+    async fn test_apply_call_timeout() {
+        let config = TimeoutConfig::default();
+        let mut agent = AgentType::Sse { url: "http://events".to_string(), headers: None, timeout: None, max_events: None, event_filter: None };
+
+        let effective = with_call_timeout(Some(5), async { config.apply("events", &mut agent) }).await;
+        assert_eq!(effective, Some(EffectiveTimeout { seconds: 5, source: TimeoutSource::Call }));
+        assert_eq!(agent.timeout_mut().copied(), Some(Some(5)));
+
+        let mut agent = AgentType::SmartDiscovery { enabled: true, operation: Default::default() };
+        assert_eq!(config.apply("smart_tool_discovery", &mut agent), None);
+    }
+
+    #[test]
+    fn test_deserialize_adds_to_builtin_agent_types() {
+        let config: TimeoutConfig = serde_json::from_value(serde_json::json!({
+            "per_agent_type": {"llm": 90},
+            "per_tool": {"report": 240}
+        })).unwrap();
+        assert_eq!(config.get_timeout_secs("llm", None), 90);
+        assert_eq!(config.get_timeout_secs("kubernetes_job", None), 600);
+        assert_eq!(config.max_timeout_secs, 300);
+        assert!(config.validate().is_ok());
+    }
 }
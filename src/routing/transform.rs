@@ -0,0 +1,143 @@
+//! Declarative request and response transformations
+//!
+//! A routing config with a `transform` section reshapes the tool arguments
+//! before they are sent to the agent, and the agent's result data before it
+//! is returned, using JMESPath expressions:
+//!
+//! ```yaml
+//! routing:
+//!   type: http
+//!   config:
+//!     method: POST
+//!     url: "https://search.internal/v2/query"
+//!     transform:
+//!       request: "{q: query, size: limit || `10`, filters: {lang: language}}"
+//!       response: "{results: body.hits[].{id: _id, title: _source.title}, total: body.total}"
+//! ```
+//!
+//! Either expression may be left out. The request expression must produce an
+//! object; the response expression is applied to successful results only.
+
+use crate::error::{ProxyError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Key of the transform section in a routing config
+pub const CONFIG_KEY: &str = "transform";
+
+/// The `transform` section of a routing config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransformConfig {
+    /// JMESPath expression over the tool arguments
+    #[serde(default)]
+    pub request: Option<String>,
+    /// JMESPath expression over the result data
+    #[serde(default)]
+    pub response: Option<String>,
+}
+
+impl TransformConfig {
+    /// The transform section of a routing config, if it has one
+    pub fn from_routing(config: &Value) -> Result<Option<Self>> {
+        let Some(section) = config.get(CONFIG_KEY) else {
+            return Ok(None);
+        };
+        let transform: Self = serde_json::from_value(section.clone())
+            .map_err(|e| ProxyError::validation(format!("Invalid transform config: {}", e)))?;
+        transform.validate()?;
+        Ok(Some(transform))
+    }
+
+    /// Check that both expressions compile
+    pub fn validate(&self) -> Result<()> {
+        for (name, expression) in [("request", &self.request), ("response", &self.response)] {
+            if let Some(expression) = expression {
+                jmespath::compile(expression).map_err(|e| {
+                    ProxyError::validation(format!("Invalid {} transform '{}': {}", name, expression, e))
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Arguments to send to the agent
+    pub fn apply_request(&self, arguments: &Value) -> Result<Value> {
+        let Some(expression) = &self.request else {
+            return Ok(arguments.clone());
+        };
+        let transformed = search(expression, arguments)
+            .map_err(|e| ProxyError::routing(format!("Request transform failed: {}", e)))?;
+        if !transformed.is_object() {
+            return Err(ProxyError::routing(format!(
+                "Request transform must produce an object, got: {}", transformed
+            )));
+        }
+        Ok(transformed)
+    }
+
+    /// Result data to return from the agent's data
+    pub fn apply_response(&self, data: &Value) -> Result<Value> {
+        match &self.response {
+            Some(expression) => search(expression, data)
+                .map_err(|e| ProxyError::routing(format!("Response transform failed: {}", e))),
+            None => Ok(data.clone()),
+        }
+    }
+}
+
+/// Routing config without its transform section, as sent to the agent
+pub fn without_transform(config: &Value) -> Value {
+    let mut config = config.clone();
+    if let Some(config) = config.as_object_mut() {
+        config.remove(CONFIG_KEY);
+    }
+    config
+}
+
+fn search(expression: &str, data: &Value) -> std::result::Result<Value, String> {
+    let expression = jmespath::compile(expression).map_err(|e| e.to_string())?;
+    let result = expression.search(data).map_err(|e| e.to_string())?;
+    serde_json::to_value(&*result).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn transform(request: Option<&str>, response: Option<&str>) -> TransformConfig {
+        TransformConfig { request: request.map(String::from), response: response.map(String::from) }
+    }
+
+    #[test]
+    fn test_request_transform() {
+        let transform = transform(Some("{q: query, size: limit || `10`, filters: {lang: language}}"), None);
+        assert_eq!(
+            transform.apply_request(&json!({"query": "rust", "language": "en"})).unwrap(),
+            json!({"q": "rust", "size": 10, "filters": {"lang": "en"}})
+        );
+        assert!(self::transform(Some("query"), None).apply_request(&json!({"query": "rust"})).is_err());
+    }
+
+    #[test]
+    fn test_response_transform() {
+        let transform = transform(None, Some("{results: hits[].{id: _id, title: _source.title}, total: total}"));
+        let data = json!({"total": 1, "hits": [{"_id": "a1", "_source": {"title": "Rust", "body": "..."}}]});
+        assert_eq!(
+            transform.apply_response(&data).unwrap(),
+            json!({"results": [{"id": "a1", "title": "Rust"}], "total": 1})
+        );
+        assert_eq!(transform.apply_request(&json!({"x": 1})).unwrap(), json!({"x": 1}));
+    }
+
+    #[test]
+    fn test_from_routing() {
+        let config = json!({"url": "http://a", "transform": {"response": "items[0]"}});
+        let transform = TransformConfig::from_routing(&config).unwrap().unwrap();
+        assert_eq!(transform.response.as_deref(), Some("items[0]"));
+        assert_eq!(without_transform(&config), json!({"url": "http://a"}));
+
+        assert!(TransformConfig::from_routing(&json!({"url": "http://a"})).unwrap().is_none());
+        assert!(TransformConfig::from_routing(&json!({"transform": {"request": "{a: "}})).is_err());
+    }
+}
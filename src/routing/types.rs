@@ -3,6 +3,54 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// A group of subprocess arguments that is only passed when a tool parameter is present.
+///
+/// Boolean parameters include the group when `true`; other values when present and
+/// not null. An arg that is exactly `{{param}}` expands to one arg per item for arrays.
+/// Positional groups are passed last, after a `--` separator, so their values
+/// are never parsed as options.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptionalArg {
+    /// Tool parameter controlling this group
+    pub param: String,
+    /// Argument templates, e.g. `["--output", "{{output}}"]`
+    pub args: Vec<String>,
+    /// Operands rather than options, e.g. the files of `grep PATTERN FILE...`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub positional: bool,
+}
+
+/// Where an HTTP agent puts tool arguments other than the request body
+///
+/// With nothing set, every argument is sent in the body, as before.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HttpParams {
+    /// Arguments substituted, percent-encoded, into `{name}` placeholders of the URL
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<String>,
+    /// Query string parameters and the argument each is taken from; arguments
+    /// that are omitted or null are left out, arrays repeat the parameter
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query: Vec<(String, String)>,
+    /// Arguments sent in the body; by default every argument not bound to the path or query
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Vec<String>>,
+}
+
+impl HttpParams {
+    pub fn is_empty(&self) -> bool {
+        self.path.is_empty() && self.query.is_empty() && self.body.is_none()
+    }
+
+    /// Whether argument `name` goes in the request body
+    pub fn in_body(&self, name: &str) -> bool {
+        match &self.body {
+            Some(body) => body.iter().any(|param| param == name),
+            None => !self.path.iter().any(|param| param == name) && !self.query.iter().any(|(_, param)| param == name),
+        }
+    }
+}
+
 /// Agent types supported by the router
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -14,6 +62,9 @@ pub enum AgentType {
         args: Vec<String>,
         timeout: Option<u64>,
         env: Option<std::collections::HashMap<String, String>>,
+        /// Arguments appended after `args` only when their parameter is supplied
+        #[serde(default)]
+        optional_args: Vec<OptionalArg>,
     },
     
     /// HTTP agent (make HTTP requests)
@@ -23,8 +74,35 @@ pub enum AgentType {
         url: String,
         headers: Option<std::collections::HashMap<String, String>>,
         timeout: Option<u64>,
+        /// Sign requests with AWS Signature Version 4
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        aws_sigv4: Option<crate::routing::aws_sigv4::AwsSigV4Config>,
+        /// Send the caller's token, exchanged for one issued to this service
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token_exchange: Option<crate::auth::TokenExchangeConfig>,
+        /// Arguments bound to the URL path and query string instead of the body
+        #[serde(default, skip_serializing_if = "HttpParams::is_empty")]
+        params: HttpParams,
     },
     
+    /// AWS Lambda agent (invoke functions through the Lambda Invoke API)
+    #[serde(rename = "lambda")]
+    Lambda {
+        function_name: String,
+        /// Version or alias to invoke
+        qualifier: Option<String>,
+        #[serde(default)]
+        invocation_type: crate::routing::aws_lambda::LambdaInvocationType,
+        /// Event template; `{{param}}` placeholders are replaced with tool arguments.
+        /// When unset, the tool arguments are the event.
+        payload: Option<Value>,
+        /// Invoke API endpoint override (e.g. a local emulator)
+        endpoint: Option<String>,
+        timeout: Option<u64>,
+        /// Region and credentials the request is signed with
+        aws: crate::routing::aws_sigv4::AwsSigV4Config,
+    },
+
     /// LLM agent (call language models)
     #[serde(rename = "llm")]
     Llm {
@@ -49,6 +127,121 @@ pub enum AgentType {
         connection_string: String,
         query: String,
         timeout: Option<u64>,
+        /// Argument names bound, in order, to the query's positional placeholders
+        /// (`$1..` for PostgreSQL, `?` for MySQL, `?1..` for SQLite). When set,
+        /// arguments are never substituted into the query text. `name?` binds
+        /// whether the argument was given at all.
+        #[serde(default)]
+        params: Vec<String>,
+        /// Reject anything but read queries and run them in a read-only session
+        #[serde(default)]
+        read_only: bool,
+        /// Rows returned at most; further rows are dropped and `truncated` is set
+        #[serde(default = "default_max_rows")]
+        max_rows: usize,
+    },
+
+    /// Kafka agent (publish tool calls to a topic, optionally awaiting a reply)
+    #[serde(rename = "kafka")]
+    Kafka {
+        brokers: Vec<String>,
+        topic: String,
+        #[serde(default)]
+        partition: i32,
+        /// Record key template
+        key: Option<String>,
+        /// Record value template; `{{param}}` placeholders are replaced with tool arguments.
+        /// When unset, the tool arguments are the value.
+        payload: Option<Value>,
+        #[serde(default)]
+        headers: std::collections::HashMap<String, String>,
+        /// Topic the correlated reply is read from; without it the call is fire-and-forget
+        reply: Option<crate::routing::kafka::KafkaReplyConfig>,
+        timeout: Option<u64>,
+    },
+
+    /// AMQP agent (RabbitMQ RPC: publish with reply-to and await the correlated reply)
+    #[serde(rename = "amqp")]
+    Amqp {
+        /// Broker URL (`amqp://` or `amqps://`)
+        url: String,
+        #[serde(default)]
+        exchange: String,
+        /// Routing key template
+        routing_key: String,
+        /// Message body template; `{{param}}` placeholders are replaced with tool arguments.
+        /// When unset, the tool arguments are the body.
+        payload: Option<Value>,
+        #[serde(default)]
+        headers: std::collections::HashMap<String, String>,
+        /// Wait for a reply; otherwise the message is only published
+        #[serde(default = "default_await_reply")]
+        await_reply: bool,
+        /// PEM CA certificate for `amqps://` brokers with a private CA
+        ca_cert: Option<String>,
+        timeout: Option<u64>,
+    },
+
+    /// SSH agent (run a command on a remote host)
+    #[serde(rename = "ssh")]
+    Ssh {
+        /// Remote command template; arguments are shell-quoted
+        command: String,
+        timeout: Option<u64>,
+        /// Cap on captured stdout and stderr (each)
+        #[serde(default = "default_max_output_bytes")]
+        max_output_bytes: usize,
+        /// Host, user, keys and host key verification
+        connection: crate::routing::ssh::SshConnection,
+    },
+
+    /// Docker agent (run a command in a new or existing container)
+    #[serde(rename = "docker")]
+    Docker {
+        timeout: Option<u64>,
+        /// Cap on captured stdout and stderr (each)
+        #[serde(default = "default_max_output_bytes")]
+        max_output_bytes: usize,
+        /// Image or container, command and isolation settings
+        config: crate::routing::docker::DockerConfig,
+    },
+
+    /// Kubernetes Job agent (run a templated pod to completion)
+    #[serde(rename = "kubernetes_job")]
+    KubernetesJob {
+        timeout: Option<u64>,
+        /// Cap on collected logs
+        #[serde(default = "default_max_output_bytes")]
+        max_output_bytes: usize,
+        /// Pod spec template, Job settings and kubectl options
+        config: crate::routing::kubernetes::KubernetesJobConfig,
+    },
+
+    /// Webhook agent (submit a job and wait for the backend to call back)
+    #[serde(rename = "webhook")]
+    Webhook {
+        timeout: Option<u64>,
+        /// Submission request, callback URL and shared secret
+        config: crate::routing::webhook::WebhookConfig,
+    },
+
+    /// WebAssembly agent (run a WASI module in an in-process sandbox)
+    #[serde(rename = "wasm")]
+    Wasm {
+        timeout: Option<u64>,
+        /// Cap on captured stdout and stderr (each)
+        #[serde(default = "default_max_output_bytes")]
+        max_output_bytes: usize,
+        /// Module, input and resource limits
+        config: crate::routing::wasm::WasmConfig,
+    },
+
+    /// Script agent (run an embedded Rhai or Lua script)
+    #[serde(rename = "script")]
+    Script {
+        timeout: Option<u64>,
+        /// Language, source and limits
+        config: crate::routing::script::ScriptConfig,
     },
 
     /// gRPC agent (call gRPC services)
@@ -81,6 +274,9 @@ pub enum AgentType {
         headers: Option<std::collections::HashMap<String, String>>,
         timeout: Option<u64>,
         operation_name: Option<String>,
+        /// Limits for subscription operations, which run over WebSocket
+        #[serde(default)]
+        subscription: Option<crate::routing::graphql_ws::SubscriptionConfig>,
     },
 
     /// External MCP agent (route to external MCP servers via external MCP integration)
@@ -96,9 +292,93 @@ pub enum AgentType {
     #[serde(rename = "smart_discovery")]
     SmartDiscovery {
         enabled: bool,
+        /// What the tool does with the request
+        #[serde(default)]
+        operation: SmartDiscoveryOperation,
     },
 }
 
+/// What a `smart_discovery`-routed tool does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmartDiscoveryOperation {
+    /// Find the best tool for the request and run it
+    #[default]
+    Discover,
+    /// Propose an ordered chain of tool calls for a multi-step request, without running it
+    Plan,
+    /// Record feedback on a discovery result
+    Feedback,
+    /// List the tool categories derived from the embeddings, or the tools of one
+    Categories,
+}
+
+impl AgentType {
+    /// Routing type name of the agent, as used in routing configs
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            AgentType::Subprocess { .. } => "subprocess",
+            AgentType::Http { .. } => "http",
+            AgentType::Lambda { .. } => "lambda",
+            AgentType::Llm { .. } => "llm",
+            AgentType::WebSocket { .. } => "websocket",
+            AgentType::Database { .. } => "database",
+            AgentType::Kafka { .. } => "kafka",
+            AgentType::Amqp { .. } => "amqp",
+            AgentType::Ssh { .. } => "ssh",
+            AgentType::Docker { .. } => "docker",
+            AgentType::KubernetesJob { .. } => "kubernetes_job",
+            AgentType::Webhook { .. } => "webhook",
+            AgentType::Wasm { .. } => "wasm",
+            AgentType::Script { .. } => "script",
+            AgentType::Grpc { .. } => "grpc",
+            AgentType::Sse { .. } => "sse",
+            AgentType::GraphQL { .. } => "graphql",
+            AgentType::ExternalMcp { .. } => "external_mcp",
+            AgentType::SmartDiscovery { .. } => "smart_discovery",
+        }
+    }
+
+    /// The agent's timeout in seconds, for agents that have one
+    pub fn timeout_mut(&mut self) -> Option<&mut Option<u64>> {
+        match self {
+            AgentType::Subprocess { timeout, .. }
+            | AgentType::Http { timeout, .. }
+            | AgentType::Lambda { timeout, .. }
+            | AgentType::Llm { timeout, .. }
+            | AgentType::Database { timeout, .. }
+            | AgentType::Kafka { timeout, .. }
+            | AgentType::Amqp { timeout, .. }
+            | AgentType::Ssh { timeout, .. }
+            | AgentType::Docker { timeout, .. }
+            | AgentType::KubernetesJob { timeout, .. }
+            | AgentType::Webhook { timeout, .. }
+            | AgentType::Wasm { timeout, .. }
+            | AgentType::Script { timeout, .. }
+            | AgentType::Grpc { timeout, .. }
+            | AgentType::Sse { timeout, .. }
+            | AgentType::GraphQL { timeout, .. }
+            | AgentType::ExternalMcp { timeout, .. } => Some(timeout),
+            AgentType::WebSocket { .. } | AgentType::SmartDiscovery { .. } => None,
+        }
+    }
+}
+
+fn default_await_reply() -> bool {
+    true
+}
+
+/// Default cap on rows returned by database agents
+pub const DEFAULT_MAX_ROWS: usize = 1000;
+
+fn default_max_rows() -> usize {
+    DEFAULT_MAX_ROWS
+}
+
+fn default_max_output_bytes() -> usize {
+    crate::routing::process::DEFAULT_MAX_OUTPUT_BYTES
+}
+
 /// Smart Discovery LLM configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SmartDiscoveryLlmConfig {
@@ -0,0 +1,334 @@
+//! Sandboxed WebAssembly (WASI) execution for `wasm`-routed tools
+//!
+//! A WASI command module runs in-process with the tool arguments on stdin
+//! (or as argv), and with no preopened directories, sockets or inherited
+//! environment, so it can only compute on its input:
+//!
+//! ```yaml
+//! routing:
+//!   type: wasm
+//!   config:
+//!     module: ./tools/wasm/slugify.wasm
+//!     args: ["--separator", "{{separator}}"]  # optional argv after the module name
+//!     stdin: "{{text}}"                        # optional; defaults to the tool arguments as JSON
+//!     max_memory_mb: 64
+//!     fuel: 1000000000                          # instruction budget
+//!     timeout: 10
+//! ```
+//!
+//! Compiled modules are cached per path and recompiled when the file changes.
+//!
+//! The runtime is built with the `wasm` feature (on by default); without it,
+//! `wasm` routings fail when called.
+
+#[cfg(feature = "wasm")]
+use crate::error::{ProxyError, Result};
+#[cfg(feature = "wasm")]
+use crate::routing::process::CapturedOutput;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "wasm")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "wasm")]
+use std::sync::{Mutex, OnceLock};
+#[cfg(feature = "wasm")]
+use std::time::{Duration, SystemTime};
+#[cfg(feature = "wasm")]
+use wasi_common::pipe::{ReadPipe, WritePipe};
+#[cfg(feature = "wasm")]
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+#[cfg(feature = "wasm")]
+use wasmtime_wasi::sync::WasiCtxBuilder;
+#[cfg(feature = "wasm")]
+use wasmtime_wasi::WasiCtx;
+
+/// Interval of the epoch ticker that enforces wall-clock timeouts
+#[cfg(feature = "wasm")]
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+fn default_max_memory_mb() -> u64 {
+    64
+}
+
+fn default_fuel() -> u64 {
+    1_000_000_000
+}
+
+/// Module, input and limits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WasmConfig {
+    /// Path of the WASI command module (`.wasm`)
+    pub module: String,
+    /// Arguments after the program name; `{{param}}` placeholders are substituted
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// stdin template; when unset, the tool arguments are passed as JSON
+    #[serde(default)]
+    pub stdin: Option<String>,
+    /// Environment variables visible to the module; nothing is inherited
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Linear memory limit
+    #[serde(default = "default_max_memory_mb")]
+    pub max_memory_mb: u64,
+    /// Instruction budget; the module traps when it runs out
+    #[serde(default = "default_fuel")]
+    pub fuel: u64,
+}
+
+#[cfg(feature = "wasm")]
+struct SandboxState {
+    wasi: WasiCtx,
+    limits: StoreLimits,
+}
+
+/// Shared engine and compiled-module cache
+#[cfg(feature = "wasm")]
+pub struct WasmRuntime {
+    engine: Engine,
+    modules: Mutex<HashMap<PathBuf, (SystemTime, Module)>>,
+}
+
+#[cfg(feature = "wasm")]
+impl std::fmt::Debug for WasmRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmRuntime").finish_non_exhaustive()
+    }
+}
+
+/// Process-wide runtime, created on first use
+///
+/// One engine is shared so compiled modules can be reused; a background
+/// thread advances its epoch, against which each call sets its own deadline.
+#[cfg(feature = "wasm")]
+pub fn runtime() -> Result<&'static WasmRuntime> {
+    static RUNTIME: OnceLock<std::result::Result<WasmRuntime, String>> = OnceLock::new();
+    RUNTIME
+        .get_or_init(|| {
+            let mut config = Config::new();
+            config.consume_fuel(true);
+            config.epoch_interruption(true);
+            let engine = Engine::new(&config).map_err(|e| e.to_string())?;
+
+            let ticker = engine.clone();
+            std::thread::Builder::new()
+                .name("wasm-epoch".to_string())
+                .spawn(move || loop {
+                    std::thread::sleep(EPOCH_TICK);
+                    ticker.increment_epoch();
+                })
+                .map_err(|e| e.to_string())?;
+
+            Ok(WasmRuntime { engine, modules: Mutex::new(HashMap::new()) })
+        })
+        .as_ref()
+        .map_err(|e| ProxyError::routing(format!("Failed to initialize WebAssembly runtime: {}", e)))
+}
+
+#[cfg(feature = "wasm")]
+impl WasmRuntime {
+    /// Compiled module for `path`, compiling it if it is new or has changed
+    pub fn module(&self, path: &Path) -> Result<Module> {
+        let modified = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map_err(|e| ProxyError::routing(format!("Cannot read WebAssembly module '{}': {}", path.display(), e)))?;
+
+        let mut modules = self.modules.lock()
+            .map_err(|_| ProxyError::routing("WebAssembly module cache lock poisoned".to_string()))?;
+        if let Some((cached_at, module)) = modules.get(path) {
+            if *cached_at == modified {
+                return Ok(module.clone());
+            }
+        }
+
+        let module = Module::from_file(&self.engine, path)
+            .map_err(|e| ProxyError::routing(format!("Invalid WebAssembly module '{}': {}", path.display(), e)))?;
+        modules.insert(path.to_path_buf(), (modified, module.clone()));
+        Ok(module)
+    }
+
+    /// Run a module's `_start` to completion
+    ///
+    /// Blocks the calling thread; run it on a blocking task. Output beyond
+    /// `max_output_bytes` is dropped.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run(
+        &self,
+        module: &Module,
+        argv: &[String],
+        env: &[(String, String)],
+        stdin: Vec<u8>,
+        config: &WasmConfig,
+        timeout: Duration,
+        max_output_bytes: usize,
+    ) -> Result<WasmOutput> {
+        let stdout = WritePipe::new_in_memory();
+        let stderr = WritePipe::new_in_memory();
+
+        let mut builder = WasiCtxBuilder::new();
+        builder
+            .stdin(Box::new(ReadPipe::from(stdin)))
+            .stdout(Box::new(stdout.clone()))
+            .stderr(Box::new(stderr.clone()));
+        builder.args(argv)
+            .map_err(|e| ProxyError::routing(format!("Invalid WebAssembly arguments: {}", e)))?;
+        builder.envs(env)
+            .map_err(|e| ProxyError::routing(format!("Invalid WebAssembly environment: {}", e)))?;
+
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(usize::try_from(config.max_memory_mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX))
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, SandboxState { wasi: builder.build(), limits });
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(config.fuel)
+            .map_err(|e| ProxyError::routing(format!("Failed to set WebAssembly fuel: {}", e)))?;
+        let ticks = (timeout.as_millis() / EPOCH_TICK.as_millis()).max(1);
+        store.set_epoch_deadline(u64::try_from(ticks).unwrap_or(u64::MAX));
+
+        let mut linker: Linker<SandboxState> = Linker::new(&self.engine);
+        wasmtime_wasi::sync::add_to_linker(&mut linker, |state| &mut state.wasi)
+            .map_err(|e| ProxyError::routing(format!("Failed to link WASI: {}", e)))?;
+
+        let outcome = linker.instantiate(&mut store, module)
+            .and_then(|instance| instance.get_typed_func::<(), ()>(&mut store, "_start"))
+            .and_then(|start| start.call(&mut store, ()));
+        let fuel_used = config.fuel.saturating_sub(store.get_fuel().unwrap_or(0));
+        drop(store);
+
+        let termination = match outcome {
+            Ok(()) => Termination::Exited(0),
+            Err(e) => match e.downcast_ref::<wasi_common::I32Exit>() {
+                Some(exit) => Termination::Exited(exit.0),
+                None => match e.downcast_ref::<Trap>() {
+                    Some(Trap::OutOfFuel) => Termination::OutOfFuel,
+                    Some(Trap::Interrupt) => Termination::TimedOut,
+                    _ => Termination::Trapped(format!("{:#}", e)),
+                },
+            },
+        };
+
+        Ok(WasmOutput {
+            termination,
+            output: captured_output(pipe_contents(stdout), pipe_contents(stderr), max_output_bytes),
+            fuel_used,
+        })
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn pipe_contents(pipe: WritePipe<std::io::Cursor<Vec<u8>>>) -> Vec<u8> {
+    pipe.try_into_inner().map(|cursor| cursor.into_inner()).unwrap_or_default()
+}
+
+/// How a module run ended
+#[cfg(feature = "wasm")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Termination {
+    /// `_start` returned (0) or the module called `proc_exit`
+    Exited(i32),
+    OutOfFuel,
+    TimedOut,
+    /// Any other trap (e.g. out of memory, unreachable, missing import)
+    Trapped(String),
+}
+
+/// Result of a module run
+#[cfg(feature = "wasm")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct WasmOutput {
+    pub termination: Termination,
+    pub output: CapturedOutput,
+    pub fuel_used: u64,
+}
+
+#[cfg(feature = "wasm")]
+impl WasmOutput {
+    pub fn is_success(&self) -> bool {
+        self.termination == Termination::Exited(0)
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        match self.termination {
+            Termination::Exited(code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Error message for a failed run
+    pub fn error_message(&self) -> Option<String> {
+        match &self.termination {
+            Termination::Exited(0) => None,
+            Termination::Exited(code) => Some(format!("WebAssembly module exited with code: {}", code)),
+            Termination::OutOfFuel => Some("WebAssembly module exceeded its fuel limit".to_string()),
+            Termination::TimedOut => Some("WebAssembly module timed out".to_string()),
+            Termination::Trapped(message) => Some(format!("WebAssembly module trapped: {}", message)),
+        }
+    }
+}
+
+/// Module output with stdout and stderr each cut at `max_bytes`
+///
+/// The exit code lives in [`WasmOutput::termination`].
+#[cfg(feature = "wasm")]
+fn captured_output(stdout: Vec<u8>, stderr: Vec<u8>, max_bytes: usize) -> CapturedOutput {
+    CapturedOutput {
+        exit_code: None,
+        truncated: stdout.len() > max_bytes || stderr.len() > max_bytes,
+        stdout: String::from_utf8_lossy(&stdout[..stdout.len().min(max_bytes)]).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr[..stderr.len().min(max_bytes)]).into_owned(),
+    }
+}
+
+#[cfg(all(test, feature = "wasm"))]
+mod tests {
+    use super::*;
+
+    /// Echoes stdin to stdout, then exits with the code given as its only argument
+    const ECHO_WAT: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+          (memory (export "memory") 1)
+          (func (export "_start")
+            (i32.store (i32.const 0) (i32.const 64))
+            (i32.store (i32.const 4) (i32.const 1024))
+            (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8)))
+            (i32.store (i32.const 4) (i32.load (i32.const 8)))
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+            (call $proc_exit (i32.const 3))))
+    "#;
+
+    const SPIN_WAT: &str = r#"(module (func (export "_start") (loop (br 0))))"#;
+
+    fn config(fuel: u64) -> WasmConfig {
+        serde_json::from_value(serde_json::json!({"module": "unused.wasm", "fuel": fuel})).unwrap()
+    }
+
+    fn run(wat: &str, fuel: u64, timeout: Duration) -> WasmOutput {
+        let runtime = runtime().unwrap();
+        let module = Module::new(&runtime.engine, wat).unwrap();
+        runtime.run(&module, &["tool".to_string()], &[], b"hello".to_vec(), &config(fuel), timeout, 1024).unwrap()
+    }
+
+    #[test]
+    fn test_stdin_stdout_and_exit_code() {
+        let output = run(ECHO_WAT, 1_000_000, Duration::from_secs(5));
+        assert_eq!(output.output.stdout, "hello");
+        assert_eq!(output.exit_code(), Some(3));
+        assert_eq!(output.error_message().unwrap(), "WebAssembly module exited with code: 3");
+    }
+
+    #[test]
+    fn test_fuel_limit() {
+        let output = run(SPIN_WAT, 10_000, Duration::from_secs(5));
+        assert_eq!(output.termination, Termination::OutOfFuel);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let output = run(SPIN_WAT, u64::MAX, Duration::from_millis(50));
+        assert_eq!(output.termination, Termination::TimedOut);
+    }
+}
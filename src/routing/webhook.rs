@@ -0,0 +1,301 @@
+//! Webhook-callback execution for long-running async APIs
+//!
+//! A `webhook`-routed tool submits a job to an HTTP backend and waits until
+//! the backend calls back, so an asynchronous API looks like an ordinary
+//! synchronous tool call:
+//!
+//! ```yaml
+//! routing:
+//!   type: webhook
+//!   config:
+//!     url: "https://transcoder.internal/jobs"
+//!     method: POST                              # default
+//!     payload:                                  # defaults to the tool arguments
+//!       source: "{{video_url}}"
+//!       notify: "{{callback_url}}"
+//!     callback_base_url: "https://tunnel.example.com"
+//!     secret: "${TRANSCODER_WEBHOOK_SECRET}"
+//!     timeout: 1800
+//! ```
+//!
+//! Each call gets a correlation ID and a callback URL,
+//! `<callback_base_url>/webhooks/callbacks/<correlation ID>`, available to
+//! templates as `{{correlation_id}}` and `{{callback_url}}` and sent in the
+//! `X-MagicTunnel-Correlation-Id` and `X-MagicTunnel-Callback-Url` headers.
+//! Requests in both directions are signed with the shared secret: the
+//! `X-MagicTunnel-Signature` header is `sha256=` followed by the hex
+//! HMAC-SHA256 of `<timestamp>.<body>`, where the timestamp (Unix seconds) is
+//! sent in `X-MagicTunnel-Timestamp`. Callbacks with a missing, wrong or stale
+//! signature are rejected.
+//!
+//! The backend posts any number of progress updates, forwarded to the client
+//! as progress notifications, then the outcome:
+//!
+//! ```json
+//! {"status": "progress", "message": "Encoding: 40%"}
+//! {"status": "completed", "result": {"url": "https://cdn.example.com/v.mp4"}}
+//! {"status": "failed", "error": "Unsupported codec"}
+//! ```
+
+use crate::error::{ProxyError, Result};
+use crate::mcp::progress::ProgressReporter;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::oneshot;
+
+/// Header carrying the request signature
+pub const SIGNATURE_HEADER: &str = "X-MagicTunnel-Signature";
+/// Header carrying the Unix time the signature was made at
+pub const TIMESTAMP_HEADER: &str = "X-MagicTunnel-Timestamp";
+/// Header carrying the job's correlation ID
+pub const CORRELATION_ID_HEADER: &str = "X-MagicTunnel-Correlation-Id";
+/// Header carrying the URL the backend calls back
+pub const CALLBACK_URL_HEADER: &str = "X-MagicTunnel-Callback-Url";
+/// Path callbacks are posted to, followed by the correlation ID
+pub const CALLBACK_PATH: &str = "/webhooks/callbacks";
+/// Oldest signature accepted, either way from now
+pub const MAX_SIGNATURE_AGE_SECS: i64 = 300;
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+/// Submission request, callback URL and shared secret of a `webhook` routing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// Job submission URL template
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// Header templates
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Body template; the tool arguments when unset
+    #[serde(default)]
+    pub payload: Option<Value>,
+    /// Public base URL of this server, as reachable by the backend
+    pub callback_base_url: String,
+    /// Key both sides sign their requests with
+    pub secret: String,
+}
+
+impl WebhookConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !matches!(self.method.to_uppercase().as_str(), "POST" | "PUT" | "PATCH") {
+            return Err(ProxyError::validation(format!("Webhook method must be POST, PUT or PATCH, got '{}'", self.method)));
+        }
+        if self.secret.is_empty() {
+            return Err(ProxyError::validation("Webhook routing requires a non-empty 'secret'"));
+        }
+        url::Url::parse(&self.callback_base_url)
+            .map_err(|e| ProxyError::validation(format!("Invalid webhook callback_base_url '{}': {}", self.callback_base_url, e)))?;
+        Ok(())
+    }
+
+    /// URL the backend posts the job's callbacks to
+    pub fn callback_url(&self, correlation_id: &str) -> String {
+        format!("{}{}/{}", self.callback_base_url.trim_end_matches('/'), CALLBACK_PATH, correlation_id)
+    }
+
+    /// Template parameters of a call: its arguments plus `correlation_id` and `callback_url`
+    pub fn template_parameters(&self, arguments: &Value, correlation_id: &str) -> Value {
+        let mut parameters = arguments.as_object().cloned().unwrap_or_default();
+        parameters.insert("correlation_id".to_string(), Value::from(correlation_id));
+        parameters.insert("callback_url".to_string(), Value::from(self.callback_url(correlation_id)));
+        Value::Object(parameters)
+    }
+}
+
+/// Signature header value of `body` sent at `timestamp`
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!("sha256={}", hex::encode(mac(secret, timestamp, body).finalize().into_bytes()))
+}
+
+/// Whether a signature is valid for `body` and recent enough at `now`
+pub fn verify(secret: &str, timestamp: Option<&str>, signature: Option<&str>, body: &[u8], now: i64) -> bool {
+    let (Some(timestamp), Some(signature)) = (timestamp.and_then(|t| t.parse::<i64>().ok()), signature) else {
+        return false;
+    };
+    if (now - timestamp).abs() > MAX_SIGNATURE_AGE_SECS {
+        return false;
+    }
+    match signature.strip_prefix("sha256=").and_then(|hex_digest| hex::decode(hex_digest).ok()) {
+        Some(digest) => mac(secret, timestamp, body).verify_slice(&digest).is_ok(),
+        None => false,
+    }
+}
+
+fn mac(secret: &str, timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Body of a callback
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CallbackEvent {
+    Progress { message: String },
+    Completed {
+        #[serde(default)]
+        result: Value,
+    },
+    Failed { error: String },
+}
+
+/// How a job ended
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobOutcome {
+    Completed(Value),
+    Failed(String),
+}
+
+/// Why a callback was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallbackError {
+    /// No job is waiting for the correlation ID; it finished, timed out or never existed
+    UnknownJob,
+    InvalidSignature,
+    InvalidBody(String),
+}
+
+impl std::fmt::Display for CallbackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallbackError::UnknownJob => write!(f, "No job is waiting for this correlation ID"),
+            CallbackError::InvalidSignature => write!(f, "Missing, invalid or expired signature"),
+            CallbackError::InvalidBody(e) => write!(f, "Invalid callback body: {}", e),
+        }
+    }
+}
+
+struct PendingJob {
+    secret: String,
+    progress: Option<ProgressReporter>,
+    done: oneshot::Sender<JobOutcome>,
+}
+
+/// Jobs waiting for their backend to call back, by correlation ID
+#[derive(Default)]
+pub struct PendingJobs {
+    jobs: Mutex<HashMap<String, PendingJob>>,
+}
+
+impl PendingJobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait for callbacks signed with `secret` to `correlation_id`, forwarding progress to `progress`
+    pub fn register(&self, correlation_id: &str, secret: &str, progress: Option<ProgressReporter>) -> oneshot::Receiver<JobOutcome> {
+        let (done, receiver) = oneshot::channel();
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.insert(correlation_id.to_string(), PendingJob { secret: secret.to_string(), progress, done });
+        }
+        receiver
+    }
+
+    /// Stop waiting for a job, e.g. after its call timed out
+    pub fn remove(&self, correlation_id: &str) {
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.remove(correlation_id);
+        }
+    }
+
+    /// Whether a job is waiting for `correlation_id`
+    pub fn is_pending(&self, correlation_id: &str) -> bool {
+        self.jobs.lock().map_or(false, |jobs| jobs.contains_key(correlation_id))
+    }
+
+    /// Handle a callback: report progress, or complete the job
+    pub fn deliver(&self, correlation_id: &str, timestamp: Option<&str>, signature: Option<&str>, body: &[u8]) -> std::result::Result<(), CallbackError> {
+        let mut jobs = self.jobs.lock().map_err(|_| CallbackError::UnknownJob)?;
+        let job = jobs.get(correlation_id).ok_or(CallbackError::UnknownJob)?;
+        if !verify(&job.secret, timestamp, signature, body, chrono::Utc::now().timestamp()) {
+            return Err(CallbackError::InvalidSignature);
+        }
+
+        let event: CallbackEvent = serde_json::from_slice(body).map_err(|e| CallbackError::InvalidBody(e.to_string()))?;
+        let outcome = match event {
+            CallbackEvent::Progress { message } => {
+                if let Some(progress) = &job.progress {
+                    progress.report(message);
+                }
+                return Ok(());
+            }
+            CallbackEvent::Completed { result } => JobOutcome::Completed(result),
+            CallbackEvent::Failed { error } => JobOutcome::Failed(error),
+        };
+        if let Some(job) = jobs.remove(correlation_id) {
+            // The call may have given up in the meantime
+            let _ = job.done.send(outcome);
+        }
+        Ok(())
+    }
+}
+
+/// Process-wide jobs, shared by the agent that submits them and the callback endpoint
+pub fn pending_jobs() -> &'static PendingJobs {
+    static JOBS: OnceLock<PendingJobs> = OnceLock::new();
+    JOBS.get_or_init(PendingJobs::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn signed(secret: &str, body: &Value) -> (String, String, Vec<u8>) {
+        let body = serde_json::to_vec(body).unwrap();
+        let timestamp = chrono::Utc::now().timestamp();
+        (timestamp.to_string(), sign(secret, timestamp, &body), body)
+    }
+
+    #[test]
+    fn test_signature() {
+        let signature = sign("s3cret", 1_700_000_000, b"{}");
+        assert!(verify("s3cret", Some("1700000000"), Some(&signature), b"{}", 1_700_000_100));
+        assert!(!verify("other", Some("1700000000"), Some(&signature), b"{}", 1_700_000_100));
+        assert!(!verify("s3cret", Some("1700000000"), Some(&signature), b"{ }", 1_700_000_100));
+        assert!(!verify("s3cret", Some("1700000000"), Some(&signature), b"{}", 1_700_001_000));
+        assert!(!verify("s3cret", None, Some(&signature), b"{}", 1_700_000_100));
+    }
+
+    #[tokio::test]
+    async fn test_deliver_completes_job() {
+        let jobs = PendingJobs::new();
+        let mut completion = jobs.register("job-1", "s3cret", None);
+
+        let (timestamp, signature, body) = signed("s3cret", &json!({"status": "progress", "message": "halfway"}));
+        assert_eq!(jobs.deliver("job-1", Some(&timestamp), Some(&signature), &body), Ok(()));
+        assert!(completion.try_recv().is_err());
+
+        let (timestamp, _, body) = signed("s3cret", &json!({"status": "completed", "result": {"ok": true}}));
+        let forged = sign("guess", timestamp.parse().unwrap(), &body);
+        assert_eq!(jobs.deliver("job-1", Some(&timestamp), Some(&forged), &body), Err(CallbackError::InvalidSignature));
+
+        let signature = sign("s3cret", timestamp.parse().unwrap(), &body);
+        assert_eq!(jobs.deliver("job-1", Some(&timestamp), Some(&signature), &body), Ok(()));
+        assert_eq!(completion.await.unwrap(), JobOutcome::Completed(json!({"ok": true})));
+        assert_eq!(jobs.deliver("job-1", Some(&timestamp), Some(&signature), &body), Err(CallbackError::UnknownJob));
+    }
+
+    #[test]
+    fn test_template_parameters() {
+        let config: WebhookConfig = serde_json::from_value(json!({
+            "url": "https://jobs.internal/run",
+            "callback_base_url": "https://tunnel.example.com/",
+            "secret": "s3cret"
+        })).unwrap();
+        assert!(config.validate().is_ok());
+        let parameters = config.template_parameters(&json!({"input": "a"}), "abc");
+        assert_eq!(parameters["input"], "a");
+        assert_eq!(parameters["callback_url"], "https://tunnel.example.com/webhooks/callbacks/abc");
+    }
+}
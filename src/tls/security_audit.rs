@@ -234,8 +234,8 @@ impl SecurityAuditLogger {
         Ok(())
     }
     
-    /// Log authentication attempt
-    pub fn log_auth_attempt(&self, req: &HttpRequest, success: bool, user_id: Option<&str>) -> Result<()> {
+    /// Log authentication attempt, with the method that accepted or rejected it
+    pub fn log_auth_attempt(&self, req: &HttpRequest, success: bool, user_id: Option<&str>, auth_method: Option<&str>) -> Result<()> {
         if !self.config.log_auth_events {
             return Ok(());
         }
@@ -260,7 +260,7 @@ impl SecurityAuditLogger {
             json!({
                 "success": success,
                 "user_id": user_id,
-                "auth_method": "api_key" // TODO: Make this configurable
+                "auth_method": auth_method
             }),
         )?;
         
@@ -526,7 +526,7 @@ mod tests {
         let req = TestRequest::default().to_http_request();
         
         // Test authentication logging
-        assert!(logger.log_auth_attempt(&req, false, Some("test_user")).is_ok());
+        assert!(logger.log_auth_attempt(&req, false, Some("test_user"), Some("api_key")).is_ok());
         
         // Test rate limit logging
         assert!(logger.log_rate_limit_exceeded(&req, "per_ip").is_ok());
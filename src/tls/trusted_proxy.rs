@@ -12,7 +12,7 @@ pub struct TrustedProxyValidator {
 
 /// Represents a CIDR range for IP validation
 #[derive(Debug, Clone)]
-struct CidrRange {
+pub(crate) struct CidrRange {
     network: IpAddr,
     prefix_len: u8,
 }
@@ -66,7 +66,7 @@ impl TrustedProxyValidator {
 
 impl CidrRange {
     /// Parse a CIDR range from string
-    fn from_str(cidr: &str) -> Result<Self> {
+    pub(crate) fn from_str(cidr: &str) -> Result<Self> {
         if let Some((ip_str, prefix_str)) = cidr.split_once('/') {
             let network = IpAddr::from_str(ip_str)
                 .map_err(|e| ProxyError::config(format!("Invalid IP address in CIDR: {}", e)))?;
@@ -103,7 +103,7 @@ impl CidrRange {
     }
     
     /// Check if an IP address is within this CIDR range
-    fn contains(&self, ip: &IpAddr) -> bool {
+    pub(crate) fn contains(&self, ip: &IpAddr) -> bool {
         match (self.network, ip) {
             (IpAddr::V4(net), IpAddr::V4(addr)) => {
                 self.ipv4_contains(&net, addr)
@@ -4,12 +4,14 @@ use std::sync::Arc;
 use std::time::Instant;
 use tracing::{debug, info, error, warn};
 use crate::registry::RegistryService;
+use crate::registry::tool_state::ToolState;
 use crate::mcp::{McpServer, types::ToolCall};
 use crate::mcp::resources::{ResourceManager, ResourceProvider};
 use crate::mcp::prompts::{PromptManager, PromptProvider};
 use crate::mcp::types::{Resource, ResourceContent, PromptTemplate, PromptGetResponse};
 use crate::supervisor::{SupervisorClient, types::{CustomCommand, CommandType}};
 use crate::error::ProxyError;
+use crate::config::secrets::{redact_config_text, restore_redacted_config_text};
 use crate::openai::OpenApiGenerator;
 use serde::{Deserialize, Serialize};
 use std::process::Stdio;
@@ -495,6 +497,177 @@ impl DashboardApi {
         })))
     }
 
+    /// GET /dashboard/api/registry/diff - Compare two capability files, directories or snapshots.
+    /// Without `new` the old path is compared against the live registry.
+    /// Both paths must be within the registry's configured paths.
+    pub async fn get_registry_diff(&self, query: web::Query<RegistryDiffQuery>) -> Result<HttpResponse> {
+        use crate::registry::commands::CapabilityDiffer;
+
+        let differ = CapabilityDiffer::new();
+
+        let old_tools = match self.load_diff_tools(&query.old).await {
+            Ok(tools) => tools,
+            Err(response) => return Ok(response),
+        };
+        let new_tools = match query.new {
+            Some(ref new) => match self.load_diff_tools(new).await {
+                Ok(tools) => tools,
+                Err(response) => return Ok(response),
+            },
+            None => self.registry.get_all_tools_including_hidden().into_iter().collect(),
+        };
+
+        let diff = differ.diff(&old_tools, &new_tools);
+        let mut response = json!({
+            "old": query.old,
+            "new": query.new.as_deref().unwrap_or("live registry"),
+            "breaking": diff.has_breaking_changes(),
+            "diff": diff
+        });
+        // Live tools have their `${VAR}` templates resolved; don't show secrets
+        self.registry.redact_resolved_secrets(&mut response);
+        Ok(HttpResponse::Ok().json(response))
+    }
+
+    /// Load the tools at `path` for a registry diff, refusing paths outside the
+    /// registry's configured paths
+    async fn load_diff_tools(&self, path: &str) -> std::result::Result<
+        std::collections::BTreeMap<String, crate::registry::types::ToolDefinition>,
+        HttpResponse,
+    > {
+        // Missing and disallowed paths get the same answer, so the endpoint
+        // can't be used to probe for files elsewhere
+        let mut allowed = None;
+        if let Ok(resolved) = tokio::fs::canonicalize(path).await {
+            for root in self.registry.capability_roots() {
+                if let Ok(root) = tokio::fs::canonicalize(&root).await {
+                    if resolved.starts_with(&root) {
+                        allowed = Some(resolved);
+                        break;
+                    }
+                }
+            }
+        }
+        let Some(resolved) = allowed else {
+            warn!("Refusing registry diff of '{}': missing or outside the registry paths", path);
+            return Err(HttpResponse::Forbidden().json(json!({
+                "error": format!("'{}' does not exist or is not within the registry paths", path)
+            })));
+        };
+
+        crate::registry::commands::CapabilityDiffer::load_tools_async(&resolved).await.map_err(|e| {
+            HttpResponse::BadRequest().json(json!({
+                "error": format!("Failed to load '{}': {}", path, e)
+            }))
+        })
+    }
+
+    /// GET /dashboard/api/registry/snapshot - Export the effective registry as a snapshot
+    pub async fn export_registry_snapshot(&self) -> Result<HttpResponse> {
+        let snapshot = self.registry.export_snapshot();
+        info!("📦 [DASHBOARD] Exporting registry snapshot: {} files, {} tools", snapshot.files.len(), snapshot.tool_count());
+
+        let filename = format!("magictunnel-registry-{}.json", snapshot.created_at.format("%Y%m%d%H%M%S"));
+        Ok(HttpResponse::Ok()
+            .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+            .json(snapshot))
+    }
+
+    /// POST /dashboard/api/registry/snapshot/import - Import a registry snapshot and reload
+    pub async fn import_registry_snapshot(&self, body: web::Json<RegistrySnapshotImportRequest>) -> Result<HttpResponse> {
+        let request = body.into_inner();
+        let target_dir = request.target_dir.unwrap_or_else(|| ".".to_string());
+
+        if let Err(e) = request.snapshot.validate() {
+            return Ok(HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "error": e.to_string()
+            })));
+        }
+
+        match self.registry.import_snapshot(&request.snapshot, std::path::Path::new(&target_dir), request.overwrite).await {
+            Ok(report) => Ok(HttpResponse::Ok().json(json!({
+                "success": true,
+                "target_dir": target_dir,
+                "report": report,
+                "tool_count": self.registry.list_all_tools().len()
+            }))),
+            Err(e) => {
+                error!("Failed to import registry snapshot: {}", e);
+                Ok(HttpResponse::InternalServerError().json(json!({
+                    "success": false,
+                    "error": e.to_string()
+                })))
+            }
+        }
+    }
+
+    /// GET /dashboard/api/tool-state - Runtime enable/disable and hide/show overrides
+    pub async fn get_tool_state(&self) -> Result<HttpResponse> {
+        let (total, visible, hidden, enabled, disabled, active) = self.registry.tool_stats();
+        Ok(HttpResponse::Ok().json(json!({
+            "overrides": self.registry.tool_state_overrides(),
+            "stats": {
+                "total": total,
+                "visible": visible,
+                "hidden": hidden,
+                "enabled": enabled,
+                "disabled": disabled,
+                "active": active
+            }
+        })))
+    }
+
+    /// GET /dashboard/api/registry/files - Loaded capability files and their tools
+    pub async fn get_registry_files(&self) -> Result<HttpResponse> {
+        let files: Vec<_> = self.registry.list_capability_files().into_iter()
+            .map(|(path, tools)| json!({ "path": path, "tools": tools }))
+            .collect();
+        Ok(HttpResponse::Ok().json(json!({ "files": files, "total": files.len() })))
+    }
+
+    /// PUT /dashboard/api/tools/{name}/state - Enable/disable or hide/show a tool
+    pub async fn set_tool_state(&self, path: web::Path<String>, body: web::Json<ToolState>) -> Result<HttpResponse> {
+        let tool_name = path.into_inner();
+        let state = body.into_inner();
+        if state.is_empty() {
+            return Ok(HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "error": "Set 'enabled' and/or 'hidden'"
+            })));
+        }
+
+        info!("🔧 [DASHBOARD] Setting state of tool '{}': {:?}", tool_name, state);
+        Ok(tool_state_response(self.registry.set_tool_state(&tool_name, state), json!({ "tool": tool_name })))
+    }
+
+    /// DELETE /dashboard/api/tools/{name}/state - Restore a tool's flags from its capability file
+    pub async fn reset_tool_state(&self, path: web::Path<String>) -> Result<HttpResponse> {
+        let tool_name = path.into_inner();
+        info!("🔧 [DASHBOARD] Resetting state of tool '{}'", tool_name);
+        Ok(tool_state_response(self.registry.reset_tool_state(&tool_name), json!({ "tool": tool_name })))
+    }
+
+    /// PUT /dashboard/api/registry/files/state - Enable/disable or hide/show every tool in a capability file
+    pub async fn set_file_state(&self, body: web::Json<FileStateRequest>) -> Result<HttpResponse> {
+        let request = body.into_inner();
+        if request.state.is_empty() {
+            return Ok(HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "error": "Set 'enabled' and/or 'hidden'"
+            })));
+        }
+
+        info!("🔧 [DASHBOARD] Setting state of capability file '{}': {:?}", request.path, request.state);
+        Ok(tool_state_response(self.registry.set_file_state(&request.path, request.state), json!({ "file": request.path })))
+    }
+
+    /// DELETE /dashboard/api/registry/files/state?path=... - Remove a capability file's override
+    pub async fn reset_file_state(&self, query: web::Query<FileStateQuery>) -> Result<HttpResponse> {
+        info!("🔧 [DASHBOARD] Resetting state of capability file '{}'", query.path);
+        Ok(tool_state_response(self.registry.reset_file_state(&query.path), json!({ "file": query.path })))
+    }
+
     /// POST /dashboard/api/tools/{name}/execute - Execute tool for testing
     pub async fn execute_tool(&self, path: web::Path<String>, body: web::Json<serde_json::Value>) -> Result<HttpResponse> {
         let tool_name = path.into_inner();
@@ -574,7 +747,13 @@ impl DashboardApi {
                         if let Some(agent_metadata) = metadata_clone.get("agent_metadata") {
                             result_json["result"]["agent_metadata"] = agent_metadata.clone();
                         }
-                        // Include tool candidates for debugging and analysis
+                        // Include the score breakdown and tool candidates for debugging and analysis
+                        if let Some(score_breakdown) = metadata_clone.get("score_breakdown") {
+                            result_json["result"]["score_breakdown"] = score_breakdown.clone();
+                        }
+                        if let Some(resolved_request) = metadata_clone.get("resolved_request") {
+                            result_json["result"]["resolved_request"] = resolved_request.clone();
+                        }
                         if let Some(tool_candidates) = metadata_clone.get("tool_candidates") {
                             result_json["result"]["tool_candidates"] = tool_candidates.clone();
                         }
@@ -814,6 +993,57 @@ impl DashboardApi {
         }
     }
 
+    /// GET /dashboard/api/services/quarantine - Get capabilities withheld for possible prompt injection
+    pub async fn get_quarantined_capabilities(&self) -> Result<HttpResponse> {
+        info!("🛡️ [DASHBOARD] Getting quarantined capabilities");
+
+        let Some(external_mcp) = &self.external_mcp else {
+            return Ok(Self::external_mcp_unavailable());
+        };
+        let integration = external_mcp.read().await;
+        let Some(manager) = integration.get_manager() else {
+            return Ok(Self::external_mcp_unavailable());
+        };
+        let quarantined = manager.quarantine().quarantined();
+        Ok(HttpResponse::Ok().json(json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "total_quarantined": quarantined.len(),
+            "quarantined": quarantined,
+            "approvals": manager.quarantine().approvals()
+        })))
+    }
+
+    /// POST /dashboard/api/services/quarantine/approve - Approve a quarantined capability and expose it
+    pub async fn approve_quarantined_capability(&self, request: web::Json<CapabilityApprovalRequest>) -> Result<HttpResponse> {
+        let request = request.into_inner();
+        info!("🛡️ [DASHBOARD] Approving quarantined {:?} '{}' of server '{}'", request.kind, request.name, request.server);
+
+        let Some(external_mcp) = &self.external_mcp else {
+            return Ok(Self::external_mcp_unavailable());
+        };
+        let integration = external_mcp.read().await;
+        let Some(manager) = integration.get_manager() else {
+            return Ok(Self::external_mcp_unavailable());
+        };
+        match manager.approve_capability(&request.server, request.kind, &request.name, request.approved_by).await {
+            Ok(approval) => Ok(HttpResponse::Ok().json(json!({
+                "success": true,
+                "approval": approval
+            }))),
+            Err(e) => Ok(HttpResponse::NotFound().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        }
+    }
+
+    fn external_mcp_unavailable() -> HttpResponse {
+        HttpResponse::ServiceUnavailable().json(json!({
+            "success": false,
+            "error": "External MCP integration is not enabled or available"
+        }))
+    }
+
     /// POST /dashboard/api/system/restart - Restart MagicTunnel via supervisor
     pub async fn restart_magictunnel(&self) -> Result<HttpResponse> {
         info!("🔄 [DASHBOARD] MagicTunnel restart requested via supervisor");
@@ -1092,7 +1322,7 @@ impl DashboardApi {
                 "external_mcp": {
                     "enabled": true,
                     "config_file": "external-mcp-servers.yaml",
-                    "config_content": self.load_file_content("external-mcp-servers.yaml").await,
+                    "config_content": redact_config_text(&self.load_file_content("external-mcp-servers.yaml").await),
                     "capabilities_output_dir": "./capabilities/external-mcp",
                     "refresh_interval_minutes": 60
                 },
@@ -1135,7 +1365,7 @@ impl DashboardApi {
     /// Load information about configuration files
     async fn load_configuration_files(&self) -> serde_json::Value {
         // Load actual file contents for each configuration file
-        let active_config_content = self.load_file_content("magictunnel-config.yaml").await;
+        let active_config_content = redact_config_text(&self.load_file_content("magictunnel-config.yaml").await);
         let main_config_template_content = self.load_file_content("config.yaml.template").await;
         let external_mcp_template_content = self.load_file_content("external-mcp-servers.yaml.template").await;
         
@@ -1255,7 +1485,7 @@ impl DashboardApi {
         // Load actual file contents for examples
         let main_config_content = self.load_file_content("config.yaml.template").await;
         let external_mcp_content = self.load_file_content("external-mcp-servers.yaml.template").await;
-        let external_mcp_example = self.load_file_content("external-mcp-servers.yaml").await;
+        let external_mcp_example = redact_config_text(&self.load_file_content("external-mcp-servers.yaml").await);
         
         // Load example files
         let auth_config_content = self.load_file_content("examples/auth_config.yaml").await;
@@ -1674,7 +1904,19 @@ impl DashboardApi {
     /// POST /dashboard/api/config/save - Save configuration to file
     pub async fn save_config(&self, body: web::Json<ConfigSaveRequest>) -> Result<HttpResponse> {
         let config_path = body.config_path.as_deref().unwrap_or("magictunnel-config.yaml");
-        let content = &body.content;
+
+        // The editor shows redacted text; keep the stored secrets the user didn't change
+        let original = tokio::fs::read_to_string(config_path).await.unwrap_or_default();
+        let content = match restore_redacted_config_text(&body.content, &original) {
+            Ok(content) => content,
+            Err(e) => {
+                return Ok(HttpResponse::BadRequest().json(json!({
+                    "error": "Configuration contains redacted secrets",
+                    "details": e.to_string()
+                })));
+            }
+        };
+        let content = &content;
         
         // Validate the configuration before saving
         match serde_yaml::from_str::<serde_yaml::Value>(content) {
@@ -2061,7 +2303,8 @@ impl DashboardApi {
             return Ok(Vec::new());
         }
 
-        let parsed: serde_yaml::Value = serde_yaml::from_str(&config_content)
+        // Server entries are returned to the dashboard, so mask literal credentials (env, auth)
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&redact_config_text(&config_content))
             .map_err(|e| format!("Failed to parse external MCP config: {}", e))?;
 
         let mut servers = Vec::new();
@@ -3795,6 +4038,106 @@ tools:
         Ok(HttpResponse::Ok().json(all_tool_metrics))
     }
     
+    /// GET /dashboard/api/tool-metrics/canary - Get per-variant metrics of tools routed with a canary
+    pub async fn get_canary_metrics(&self) -> Result<HttpResponse> {
+        info!("🔧 [DASHBOARD] Getting canary metrics");
+
+        let canary_metrics = self.mcp_server.router().all_canary_metrics();
+        Ok(HttpResponse::Ok().json(json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "total_tools": canary_metrics.len(),
+            "canary_metrics": canary_metrics
+        })))
+    }
+
+    /// GET /dashboard/api/tool-metrics/costs - Get spend per tool, API key, user and session
+    pub async fn get_cost_metrics(&self) -> Result<HttpResponse> {
+        info!("🔧 [DASHBOARD] Getting cost metrics");
+
+        let spend = self.mcp_server.router().cost_tracker().report();
+        let total: f64 = spend.iter()
+            .filter(|spend| spend.scope == crate::routing::cost::SpendScope::Tool)
+            .map(|spend| spend.spent)
+            .sum();
+        Ok(HttpResponse::Ok().json(json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "total_spent": total,
+            "over_budget": spend.iter().filter(|spend| spend.budget.map_or(false, |budget| spend.spent >= budget)).count(),
+            "spend": spend
+        })))
+    }
+
+    /// GET /dashboard/api/tool-metrics/quotas - Get calls and cost per API key, user and role against their quotas
+    pub async fn get_quota_metrics(&self, query: web::Query<QuotaQuery>) -> Result<HttpResponse> {
+        info!("🔧 [DASHBOARD] Getting quota metrics");
+
+        let quotas: Vec<_> = self.mcp_server.router().quota_tracker().report().into_iter()
+            .filter(|quota| query.scope.map_or(true, |scope| quota.scope == scope))
+            .filter(|quota| query.key.as_ref().map_or(true, |key| quota.key == *key))
+            .collect();
+        Ok(HttpResponse::Ok().json(json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "exceeded": quotas.iter().filter(|quota| quota.exceeded).count(),
+            "rejected": quotas.iter().map(|quota| quota.rejected).sum::<u64>(),
+            "quotas": quotas
+        })))
+    }
+
+    /// GET /dashboard/api/tool-metrics/anomalies - Get behavior baselines per API key and user and recent anomalies
+    pub async fn get_anomaly_metrics(&self, query: web::Query<QuotaQuery>) -> Result<HttpResponse> {
+        info!("🔧 [DASHBOARD] Getting anomaly metrics");
+
+        let detector = self.mcp_server.router().threat_detector();
+        let matches = |scope: crate::routing::quota::QuotaScope, key: &str| {
+            query.scope.map_or(true, |wanted| scope == wanted) && query.key.as_ref().map_or(true, |wanted| key == wanted.as_str())
+        };
+        let baselines: Vec<_> = detector.report().into_iter()
+            .filter(|baseline| matches(baseline.scope, &baseline.key))
+            .collect();
+        let anomalies: Vec<_> = detector.recent_anomalies().into_iter()
+            .filter(|anomaly| matches(anomaly.scope, &anomaly.key))
+            .collect();
+        Ok(HttpResponse::Ok().json(json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "throttled": baselines.iter().filter(|baseline| baseline.throttled_until.is_some()).count(),
+            "baselines": baselines,
+            "anomalies": anomalies
+        })))
+    }
+
+    /// DELETE /dashboard/api/tool-metrics/anomalies/throttle - Let a throttled API key or user call tools again
+    pub async fn release_throttle(&self, query: web::Query<QuotaQuery>) -> Result<HttpResponse> {
+        let (Some(scope), Some(key)) = (query.scope, query.key.as_ref()) else {
+            return Ok(HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "error": "Both scope and key are required"
+            })));
+        };
+        info!("🔧 [DASHBOARD] Releasing throttled {:?} '{}'", scope, key);
+
+        if self.mcp_server.router().threat_detector().release(scope, key) {
+            Ok(HttpResponse::Ok().json(json!({ "success": true })))
+        } else {
+            Ok(HttpResponse::NotFound().json(json!({
+                "success": false,
+                "error": format!("{:?} '{}' is not throttled", scope, key)
+            })))
+        }
+    }
+
+    /// GET /dashboard/api/tool-metrics/schema-drift - Get per-tool mismatches between outputs and output schemas
+    pub async fn get_schema_drift_metrics(&self) -> Result<HttpResponse> {
+        info!("🔧 [DASHBOARD] Getting schema drift metrics");
+
+        let drift_metrics = self.mcp_server.schema_drift().all_metrics();
+        Ok(HttpResponse::Ok().json(json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "total_tools": drift_metrics.len(),
+            "drifting_tools": drift_metrics.values().filter(|metrics| metrics.mismatches > 0).count(),
+            "schema_drift": drift_metrics
+        })))
+    }
+
     /// GET /dashboard/api/tool-metrics/{tool_name} - Get metrics for a specific tool
     pub async fn get_tool_metrics(&self, tool_name: &str) -> Result<HttpResponse> {
         info!("🔧 [DASHBOARD] Getting metrics for tool: {}", tool_name);
@@ -3900,6 +4243,248 @@ tools:
         Ok(HttpResponse::Ok().json(recent_executions))
     }
 
+    /// POST /dashboard/api/discovery/feedback - Report whether a discovered tool solved the request
+    pub async fn record_discovery_feedback(&self, body: web::Json<crate::discovery::DiscoveryFeedback>) -> Result<HttpResponse> {
+        let feedback = body.into_inner();
+        info!("👍 [DASHBOARD] Recording discovery feedback for tool: {}", feedback.tool_name);
+
+        let Some(ref discovery) = self.discovery else {
+            return Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": "Smart discovery service is not available"
+            })));
+        };
+
+        match discovery.record_feedback(feedback).await {
+            Ok(stats) => Ok(HttpResponse::Ok().json(json!({
+                "success": true,
+                "tool_stats": stats
+            }))),
+            Err(e @ ProxyError::Validation { .. }) => Ok(HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+            Err(e) => Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        }
+    }
+
+    /// POST /dashboard/api/discovery/explain - Rank the candidate tools for a request with score breakdowns
+    pub async fn explain_discovery(&self, body: web::Json<crate::discovery::SmartDiscoveryRequest>) -> Result<HttpResponse> {
+        let request = body.into_inner();
+        info!("🔍 [DASHBOARD] Explaining discovery for request: {}", request.request);
+
+        let Some(ref discovery) = self.discovery else {
+            return Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": "Smart discovery service is not available"
+            })));
+        };
+
+        match discovery.explain_discovery(&request).await {
+            Ok(explanation) => Ok(HttpResponse::Ok().json(json!({
+                "success": true,
+                "explanation": explanation
+            }))),
+            Err(e @ ProxyError::Validation { .. }) => Ok(HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+            Err(e) => Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        }
+    }
+
+    /// POST /dashboard/api/discovery/plan - Plan the chain of tools a multi-step request needs
+    pub async fn plan_tool_chain(&self, body: web::Json<crate::discovery::SmartDiscoveryRequest>) -> Result<HttpResponse> {
+        let request = body.into_inner();
+        info!("🔗 [DASHBOARD] Planning tool chain for request: {}", request.request);
+
+        let Some(ref discovery) = self.discovery else {
+            return Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": "Smart discovery service is not available"
+            })));
+        };
+
+        match discovery.plan_tool_chain(&request).await {
+            Ok(plan) => Ok(HttpResponse::Ok().json(json!({
+                "success": true,
+                "plan": plan
+            }))),
+            Err(e @ ProxyError::Validation { .. }) => Ok(HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+            Err(e) => Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        }
+    }
+
+    /// GET /dashboard/api/discovery/embeddings/model - Embedding model in use and progress of the latest swap
+    pub async fn get_embedding_model(&self) -> Result<HttpResponse> {
+        let Some(ref discovery) = self.discovery else {
+            return Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": "Smart discovery service is not available"
+            })));
+        };
+
+        Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "status": discovery.embedding_model_status()
+        })))
+    }
+
+    /// POST /dashboard/api/discovery/embeddings/model - Start switching to another embedding model
+    pub async fn swap_embedding_model(&self, body: web::Json<crate::discovery::EmbeddingModelChange>) -> Result<HttpResponse> {
+        let change = body.into_inner();
+        info!("🧠 [DASHBOARD] Swapping embedding model to: {}", change.model_name);
+
+        let Some(ref discovery) = self.discovery else {
+            return Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": "Smart discovery service is not available"
+            })));
+        };
+        if discovery.embedding_model_status().state == crate::discovery::ModelSwapState::Building {
+            return Ok(HttpResponse::Conflict().json(json!({
+                "success": false,
+                "error": "An embedding model swap is already in progress"
+            })));
+        }
+
+        match discovery.start_embedding_model_swap(change) {
+            Ok(status) => Ok(HttpResponse::Accepted().json(json!({
+                "success": true,
+                "status": status
+            }))),
+            Err(e @ (ProxyError::Validation { .. } | ProxyError::Config { .. })) => Ok(HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+            Err(e) => Ok(HttpResponse::InternalServerError().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        }
+    }
+
+    /// POST /dashboard/api/discovery/embeddings/model/rollback - Go back to the embedding model the latest swap replaced
+    pub async fn rollback_embedding_model(&self) -> Result<HttpResponse> {
+        info!("🧠 [DASHBOARD] Rolling back embedding model");
+
+        let Some(ref discovery) = self.discovery else {
+            return Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": "Smart discovery service is not available"
+            })));
+        };
+
+        match discovery.rollback_embedding_model().await {
+            Ok(status) => Ok(HttpResponse::Ok().json(json!({
+                "success": true,
+                "status": status
+            }))),
+            Err(e @ ProxyError::Validation { .. }) => Ok(HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+            Err(e) => Ok(HttpResponse::InternalServerError().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        }
+    }
+
+    /// GET /dashboard/api/discovery/categories - List the tool categories, or the tools of `?category=`
+    pub async fn get_tool_categories(&self, category: Option<String>) -> Result<HttpResponse> {
+        info!("🗂️ [DASHBOARD] Getting tool categories");
+
+        let Some(ref discovery) = self.discovery else {
+            return Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": "Smart discovery service is not available"
+            })));
+        };
+
+        match discovery.browse_categories(category.as_deref()).await {
+            Ok(categories) => Ok(HttpResponse::Ok().json(json!({
+                "success": true,
+                "categories": categories
+            }))),
+            Err(e) => Ok(HttpResponse::NotFound().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        }
+    }
+
+    /// POST /dashboard/api/discovery/categories/refresh - Recompute the tool categories now
+    pub async fn refresh_tool_categories(&self) -> Result<HttpResponse> {
+        info!("🗂️ [DASHBOARD] Refreshing tool categories");
+
+        let Some(ref discovery) = self.discovery else {
+            return Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": "Smart discovery service is not available"
+            })));
+        };
+
+        match discovery.refresh_categories().await {
+            Ok(categories) => Ok(HttpResponse::Ok().json(json!({
+                "success": true,
+                "category_count": categories.as_ref().map_or(0, |categories| categories.categories.len()),
+                "categories": categories
+            }))),
+            Err(e @ ProxyError::Config { .. }) => Ok(HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+            Err(e) => Ok(HttpResponse::InternalServerError().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        }
+    }
+
+    /// GET /dashboard/api/discovery/feedback - Get feedback per tool, mis-matched tools and recent reports
+    pub async fn get_discovery_feedback(&self, limit: Option<usize>) -> Result<HttpResponse> {
+        info!("👍 [DASHBOARD] Getting discovery feedback");
+
+        let limit = limit.unwrap_or(100).min(1000);
+
+        let feedback = match self.discovery.as_ref().and_then(|discovery| discovery.feedback()) {
+            Some(store) => {
+                let stats = store.stats().await;
+                let boosts = store.boosts().await;
+                json!({
+                    "timestamp": chrono::Utc::now().to_rfc3339(),
+                    "total_tools": stats.len(),
+                    "tools": stats,
+                    "boosts": boosts,
+                    "mismatched_tools": store.mismatched_tools().await,
+                    "recent": store.recent(limit).await
+                })
+            }
+            None => json!({
+                "error": "Discovery feedback not enabled",
+                "total_tools": 0,
+                "tools": [],
+                "mismatched_tools": [],
+                "recent": []
+            }),
+        };
+
+        Ok(HttpResponse::Ok().json(feedback))
+    }
+
     /// GET /dashboard/api/observability/alerts - Get system alerts and warnings
     pub async fn get_system_alerts(&self) -> Result<HttpResponse> {
         info!("🚨 [DASHBOARD] Getting system alerts and warnings");
@@ -3964,6 +4549,39 @@ tools:
             }
         }
         
+        // API keys nearing or past their expiry
+        if let Some(auth) = self.mcp_server.auth_middleware() {
+            for key in auth.api_keys().key_lifecycle() {
+                let expires_at = key.expires_at.map(|expires_at| expires_at.to_rfc3339()).unwrap_or_default();
+                let (severity, title, description) = match key.status {
+                    "expiring" => ("warning", format!("API Key {} Expiring", key.name), format!("API key '{}' expires at {}; rotate it before then", key.name, expires_at)),
+                    "expired" if key.active => ("error", format!("API Key {} Expired", key.name), format!("API key '{}' expired at {}", key.name, expires_at)),
+                    _ => continue,
+                };
+                alerts.push(json!({
+                    "id": format!("api_key_{}_{}", key.status, key.name),
+                    "severity": severity,
+                    "title": title,
+                    "description": description,
+                    "timestamp": chrono::Utc::now().to_rfc3339(),
+                    "category": "auth"
+                }));
+            }
+
+            // OAuth clients waiting in the registration approval queue
+            let pending = auth.client_registry().map_or(0, |registry| registry.pending_count());
+            if pending > 0 {
+                alerts.push(json!({
+                    "id": "oauth_clients_pending",
+                    "severity": "warning",
+                    "title": "OAuth Clients Awaiting Approval",
+                    "description": format!("{} OAuth client registration(s) awaiting approval", pending),
+                    "timestamp": chrono::Utc::now().to_rfc3339(),
+                    "category": "auth"
+                }));
+            }
+        }
+        
         let response = json!({
             "timestamp": chrono::Utc::now().to_rfc3339(),
             "alerts": alerts,
@@ -3976,6 +4594,216 @@ tools:
         Ok(HttpResponse::Ok().json(response))
     }
 
+    /// GET /dashboard/api/audit/events - Search stored audit events by user, tool, outcome, event type and time
+    pub async fn get_audit_events(&self, query: web::Query<crate::audit::AuditQuery>) -> Result<HttpResponse> {
+        info!("📜 [DASHBOARD] Searching audit events");
+
+        match crate::audit::query(&query).await {
+            Ok(events) => Ok(HttpResponse::Ok().json(json!({
+                "success": true,
+                "count": events.len(),
+                "events": events
+            }))),
+            Err(ProxyError::Config { message }) => Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": message
+            }))),
+            Err(e) => Ok(HttpResponse::InternalServerError().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        }
+    }
+
+    /// GET /dashboard/api/audit/search - Full-text search of stored audit events with field filters, time range and
+    /// pagination; `format=csv` or `format=ndjson` exports the page as a file
+    pub async fn search_audit_events(&self, params: web::Query<Vec<(String, String)>>) -> Result<HttpResponse> {
+        info!("📜 [DASHBOARD] Searching audit events");
+
+        let format = params.iter()
+            .find(|(name, _)| name == "format")
+            .map_or("json", |(_, value)| value.as_str());
+        let criteria: Vec<(&str, &str)> = params.iter()
+            .filter(|(name, _)| name != "format")
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        let query = match crate::audit::AuditQuery::from_params(criteria) {
+            Ok(query) => query,
+            Err(e) => return Ok(HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        };
+        if !matches!(format, "json" | "csv" | "ndjson") {
+            return Ok(HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "error": format!("Unknown format '{}'; expected json, csv or ndjson", format)
+            })));
+        }
+
+        let found = match crate::audit::query(&query).await {
+            Ok(events) if format == "json" => crate::audit::count(&query).await.map(|total| (events, total)),
+            Ok(events) => Ok((events, 0)),
+            Err(e) => Err(e),
+        };
+        let (events, total) = match found {
+            Ok(found) => found,
+            Err(ProxyError::Config { message }) => return Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": message
+            }))),
+            Err(e) => return Ok(HttpResponse::InternalServerError().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        };
+
+        let filename = format!("audit-events-{}.{}", chrono::Utc::now().format("%Y%m%dT%H%M%SZ"), format);
+        match format {
+            "csv" => Ok(HttpResponse::Ok()
+                .content_type("text/csv; charset=utf-8")
+                .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+                .body(crate::audit::to_csv(&events))),
+            "ndjson" => Ok(HttpResponse::Ok()
+                .content_type("application/x-ndjson")
+                .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+                .body(events.iter().filter_map(|event| serde_json::to_string(event).ok()).map(|line| line + "\n").collect::<String>())),
+            _ => {
+                let offset = query.offset.unwrap_or(0);
+                Ok(HttpResponse::Ok().json(json!({
+                    "success": true,
+                    "total": total,
+                    "offset": offset,
+                    "count": events.len(),
+                    "has_more": offset + events.len() < total as usize,
+                    "events": events
+                })))
+            }
+        }
+    }
+
+    /// GET /dashboard/api/audit/verify - Check stored audit events for modifications and gaps in their hash chains
+    pub async fn verify_audit_events(&self, query: web::Query<crate::audit::AuditQuery>) -> Result<HttpResponse> {
+        info!("📜 [DASHBOARD] Verifying audit events");
+
+        match crate::audit::integrity::verify_installed(&query).await {
+            Ok(report) => Ok(HttpResponse::Ok().json(json!({
+                "success": true,
+                "intact": report.is_intact(),
+                "report": report
+            }))),
+            Err(ProxyError::Config { message }) => Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": message
+            }))),
+            Err(e) => Ok(HttpResponse::InternalServerError().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        }
+    }
+
+    /// GET /dashboard/api/auth/keys - Expiry and rotation status of the API keys, with recent audit events
+    pub async fn get_api_keys(&self) -> Result<HttpResponse> {
+        info!("🔑 [DASHBOARD] Getting API key lifecycle");
+
+        let Some(auth) = self.mcp_server.auth_middleware() else {
+            return Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": "Authentication is not enabled"
+            })));
+        };
+
+        let tenants: serde_json::Map<String, serde_json::Value> = auth.tenants()
+            .map(|(name, tenant)| (name.to_string(), json!({
+                "keys": tenant.api_keys().key_lifecycle(),
+                "events": tenant.api_keys().key_events(50)
+            })))
+            .collect();
+
+        Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "keys": auth.api_keys().key_lifecycle(),
+            "events": auth.api_keys().key_events(50),
+            "tenants": tenants
+        })))
+    }
+
+    /// GET /dashboard/api/auth/clients - Dynamically registered OAuth clients, including the approval queue
+    pub async fn get_oauth_clients(&self) -> Result<HttpResponse> {
+        info!("🔑 [DASHBOARD] Getting registered OAuth clients");
+
+        let Some(registry) = self.mcp_server.auth_middleware().as_ref().and_then(|auth| auth.client_registry()) else {
+            return Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": "OAuth client registration is not enabled"
+            })));
+        };
+
+        Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "clients": registry.clients(),
+            "pending": registry.pending_count()
+        })))
+    }
+
+    /// POST /dashboard/api/auth/clients/{client_id}/approve|reject - Review a registered OAuth client
+    pub async fn review_oauth_client(&self, client_id: &str, approve: bool) -> Result<HttpResponse> {
+        info!("🔑 [DASHBOARD] {} OAuth client: {}", if approve { "Approving" } else { "Rejecting" }, client_id);
+
+        let Some(registry) = self.mcp_server.auth_middleware().as_ref().and_then(|auth| auth.client_registry()) else {
+            return Ok(HttpResponse::ServiceUnavailable().json(json!({
+                "success": false,
+                "error": "OAuth client registration is not enabled"
+            })));
+        };
+
+        match registry.review(client_id, approve) {
+            Ok(client) => Ok(HttpResponse::Ok().json(json!({
+                "success": true,
+                "client": client
+            }))),
+            Err(e @ ProxyError::Validation { .. }) => Ok(HttpResponse::NotFound().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+            Err(e) => Ok(HttpResponse::InternalServerError().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        }
+    }
+
+    /// GET /dashboard/api/approvals - Get the tool calls waiting for approval
+    pub async fn get_pending_approvals(&self) -> Result<HttpResponse> {
+        let pending = self.mcp_server.router().approvals().pending();
+        Ok(HttpResponse::Ok().json(json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "total_pending": pending.len(),
+            "approvals": pending
+        })))
+    }
+
+    /// POST /dashboard/api/approvals/{id}/approve|deny - Decide on a tool call waiting for approval
+    pub async fn decide_approval(&self, id: &str, approved: bool, decision: Option<web::Json<crate::routing::approval::ApprovalDecision>>) -> Result<HttpResponse> {
+        info!("🔐 [DASHBOARD] {} tool call: {}", if approved { "Approving" } else { "Denying" }, id);
+
+        let decision = crate::routing::approval::ApprovalDecision {
+            approved,
+            ..decision.map(web::Json::into_inner).unwrap_or_default()
+        };
+        match self.mcp_server.router().approvals().decide(id, decision) {
+            Ok(request) => Ok(HttpResponse::Ok().json(json!({
+                "success": true,
+                "approval": request
+            }))),
+            Err(e) => Ok(HttpResponse::NotFound().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))),
+        }
+    }
+
     /// Helper method to get CPU usage
     async fn get_cpu_usage(&self) -> f64 {
         // Use a simple method to get CPU usage
@@ -4073,6 +4901,19 @@ tools:
     }
 }
 
+/// Quarantined capability to approve
+#[derive(serde::Deserialize)]
+pub struct CapabilityApprovalRequest {
+    /// External MCP server listing the capability
+    pub server: String,
+    pub kind: crate::mcp::injection_scan::CapabilityKind,
+    /// Tool or prompt name, or resource URI
+    pub name: String,
+    /// Who approved
+    #[serde(default)]
+    pub approved_by: Option<String>,
+}
+
 #[derive(serde::Deserialize)]
 pub struct CustomRestartRequest {
     pub pre_commands: Option<Vec<CustomCommandSpec>>,
@@ -4139,12 +4980,45 @@ pub fn configure_dashboard_api(
                 .route("/capabilities", web::get().to(|api: web::Data<DashboardApi>| async move {
                     api.get_capabilities_catalog().await
                 }))
+                .route("/registry/diff", web::get().to(|api: web::Data<DashboardApi>, query: web::Query<RegistryDiffQuery>| async move {
+                    api.get_registry_diff(query).await
+                }))
+                .route("/registry/snapshot", web::get().to(|api: web::Data<DashboardApi>| async move {
+                    api.export_registry_snapshot().await
+                }))
+                .route("/registry/snapshot/import", web::post().to(|api: web::Data<DashboardApi>, body: web::Json<RegistrySnapshotImportRequest>| async move {
+                    api.import_registry_snapshot(body).await
+                }))
+                .route("/registry/files", web::get().to(|api: web::Data<DashboardApi>| async move {
+                    api.get_registry_files().await
+                }))
+                .route("/registry/files/state", web::put().to(|api: web::Data<DashboardApi>, body: web::Json<FileStateRequest>| async move {
+                    api.set_file_state(body).await
+                }))
+                .route("/registry/files/state", web::delete().to(|api: web::Data<DashboardApi>, query: web::Query<FileStateQuery>| async move {
+                    api.reset_file_state(query).await
+                }))
+                .route("/tool-state", web::get().to(|api: web::Data<DashboardApi>| async move {
+                    api.get_tool_state().await
+                }))
+                .route("/tools/{name}/state", web::put().to(|api: web::Data<DashboardApi>, path: web::Path<String>, body: web::Json<ToolState>| async move {
+                    api.set_tool_state(path, body).await
+                }))
+                .route("/tools/{name}/state", web::delete().to(|api: web::Data<DashboardApi>, path: web::Path<String>| async move {
+                    api.reset_tool_state(path).await
+                }))
                 .route("/tools/{name}/execute", web::post().to(|api: web::Data<DashboardApi>, path: web::Path<String>, body: web::Json<serde_json::Value>| async move {
                     api.execute_tool(path, body).await
                 }))
                 .route("/services", web::get().to(|api: web::Data<DashboardApi>| async move {
                     api.get_services_status().await
                 }))
+                .route("/services/quarantine", web::get().to(|api: web::Data<DashboardApi>| async move {
+                    api.get_quarantined_capabilities().await
+                }))
+                .route("/services/quarantine/approve", web::post().to(|api: web::Data<DashboardApi>, body: web::Json<CapabilityApprovalRequest>| async move {
+                    api.approve_quarantined_capability(body).await
+                }))
                 .route("/services/{name}/restart", web::post().to(|api: web::Data<DashboardApi>, path: web::Path<String>| async move {
                     api.restart_service(path).await
                 }))
@@ -4247,6 +5121,37 @@ pub fn configure_dashboard_api(
                 .route("/observability/alerts", web::get().to(|api: web::Data<DashboardApi>| async move {
                     api.get_system_alerts().await
                 }))
+                .route("/audit/events", web::get().to(|api: web::Data<DashboardApi>, query: web::Query<crate::audit::AuditQuery>| async move {
+                    api.get_audit_events(query).await
+                }))
+                .route("/audit/search", web::get().to(|api: web::Data<DashboardApi>, params: web::Query<Vec<(String, String)>>| async move {
+                    api.search_audit_events(params).await
+                }))
+                .route("/audit/verify", web::get().to(|api: web::Data<DashboardApi>, query: web::Query<crate::audit::AuditQuery>| async move {
+                    api.verify_audit_events(query).await
+                }))
+                .route("/auth/keys", web::get().to(|api: web::Data<DashboardApi>| async move {
+                    api.get_api_keys().await
+                }))
+                .route("/auth/clients", web::get().to(|api: web::Data<DashboardApi>| async move {
+                    api.get_oauth_clients().await
+                }))
+                .route("/auth/clients/{client_id}/approve", web::post().to(|api: web::Data<DashboardApi>, path: web::Path<String>| async move {
+                    api.review_oauth_client(&path.into_inner(), true).await
+                }))
+                .route("/auth/clients/{client_id}/reject", web::post().to(|api: web::Data<DashboardApi>, path: web::Path<String>| async move {
+                    api.review_oauth_client(&path.into_inner(), false).await
+                }))
+                // Human approval of high-risk tool calls
+                .route("/approvals", web::get().to(|api: web::Data<DashboardApi>| async move {
+                    api.get_pending_approvals().await
+                }))
+                .route("/approvals/{id}/approve", web::post().to(|api: web::Data<DashboardApi>, path: web::Path<String>, body: Option<web::Json<crate::routing::approval::ApprovalDecision>>| async move {
+                    api.decide_approval(&path.into_inner(), true, body).await
+                }))
+                .route("/approvals/{id}/deny", web::post().to(|api: web::Data<DashboardApi>, path: web::Path<String>, body: Option<web::Json<crate::routing::approval::ApprovalDecision>>| async move {
+                    api.decide_approval(&path.into_inner(), false, body).await
+                }))
                 // Tool Metrics endpoints
                 .route("/tool-metrics/summary", web::get().to(|api: web::Data<DashboardApi>| async move {
                     api.get_tool_metrics_summary().await
@@ -4254,6 +5159,24 @@ pub fn configure_dashboard_api(
                 .route("/tool-metrics/all", web::get().to(|api: web::Data<DashboardApi>| async move {
                     api.get_all_tool_metrics().await
                 }))
+                .route("/tool-metrics/canary", web::get().to(|api: web::Data<DashboardApi>| async move {
+                    api.get_canary_metrics().await
+                }))
+                .route("/tool-metrics/schema-drift", web::get().to(|api: web::Data<DashboardApi>| async move {
+                    api.get_schema_drift_metrics().await
+                }))
+                .route("/tool-metrics/costs", web::get().to(|api: web::Data<DashboardApi>| async move {
+                    api.get_cost_metrics().await
+                }))
+                .route("/tool-metrics/quotas", web::get().to(|api: web::Data<DashboardApi>, query: web::Query<QuotaQuery>| async move {
+                    api.get_quota_metrics(query).await
+                }))
+                .route("/tool-metrics/anomalies", web::get().to(|api: web::Data<DashboardApi>, query: web::Query<QuotaQuery>| async move {
+                    api.get_anomaly_metrics(query).await
+                }))
+                .route("/tool-metrics/anomalies/throttle", web::delete().to(|api: web::Data<DashboardApi>, query: web::Query<QuotaQuery>| async move {
+                    api.release_throttle(query).await
+                }))
                 .route("/tool-metrics/{tool_name}", web::get().to(|api: web::Data<DashboardApi>, path: web::Path<String>| async move {
                     let tool_name = path.into_inner();
                     api.get_tool_metrics(&tool_name).await
@@ -4265,9 +5188,58 @@ pub fn configure_dashboard_api(
                 .route("/tool-metrics/executions/recent", web::get().to(|api: web::Data<DashboardApi>, query: web::Query<RecentExecutionsQuery>| async move {
                     api.get_recent_tool_executions(query.limit).await
                 }))
+                // Discovery feedback endpoints
+                .route("/discovery/feedback", web::get().to(|api: web::Data<DashboardApi>, query: web::Query<RecentExecutionsQuery>| async move {
+                    api.get_discovery_feedback(query.limit).await
+                }))
+                .route("/discovery/feedback", web::post().to(|api: web::Data<DashboardApi>, body: web::Json<crate::discovery::DiscoveryFeedback>| async move {
+                    api.record_discovery_feedback(body).await
+                }))
+                // Discovery score breakdown (debugging) endpoint
+                .route("/discovery/explain", web::post().to(|api: web::Data<DashboardApi>, body: web::Json<crate::discovery::SmartDiscoveryRequest>| async move {
+                    api.explain_discovery(body).await
+                }))
+                // Tool chain planning endpoint
+                .route("/discovery/plan", web::post().to(|api: web::Data<DashboardApi>, body: web::Json<crate::discovery::SmartDiscoveryRequest>| async move {
+                    api.plan_tool_chain(body).await
+                }))
+                .route("/discovery/embeddings/model", web::get().to(|api: web::Data<DashboardApi>| async move {
+                    api.get_embedding_model().await
+                }))
+                .route("/discovery/embeddings/model", web::post().to(|api: web::Data<DashboardApi>, body: web::Json<crate::discovery::EmbeddingModelChange>| async move {
+                    api.swap_embedding_model(body).await
+                }))
+                .route("/discovery/embeddings/model/rollback", web::post().to(|api: web::Data<DashboardApi>| async move {
+                    api.rollback_embedding_model().await
+                }))
+                // Tool categories derived from the embeddings
+                .route("/discovery/categories", web::get().to(|api: web::Data<DashboardApi>, query: web::Query<ToolCategoriesQuery>| async move {
+                    api.get_tool_categories(query.into_inner().category).await
+                }))
+                .route("/discovery/categories/refresh", web::post().to(|api: web::Data<DashboardApi>| async move {
+                    api.refresh_tool_categories().await
+                }))
         );
 }
 
+/// Response to a tool state change; unknown tools and files are 404s
+fn tool_state_response(result: crate::error::Result<()>, target: serde_json::Value) -> HttpResponse {
+    match result {
+        Ok(()) => HttpResponse::Ok().json(json!({ "success": true, "target": target })),
+        Err(e @ ProxyError::Validation { .. }) => HttpResponse::NotFound().json(json!({
+            "success": false,
+            "error": e.to_string()
+        })),
+        Err(e) => {
+            error!("Failed to update tool state: {}", e);
+            HttpResponse::InternalServerError().json(json!({
+                "success": false,
+                "error": e.to_string()
+            }))
+        }
+    }
+}
+
 // ============================================================================
 // Environment Variable Management API Types
 // ============================================================================
@@ -4303,6 +5275,44 @@ pub struct GetEnvVarsRequest {
         pub env_file: Option<String>,
     }
 
+    /// Registry diff query parameters
+    #[derive(Debug, Deserialize)]
+    pub struct RegistryDiffQuery {
+        /// Old capability file, directory or registry snapshot
+        pub old: String,
+        /// New capability file, directory or registry snapshot (defaults to the live registry)
+        pub new: Option<String>,
+    }
+
+    /// Registry snapshot import request
+    #[derive(Debug, Deserialize)]
+    pub struct RegistrySnapshotImportRequest {
+        /// Snapshot to import
+        pub snapshot: crate::registry::snapshot::RegistrySnapshot,
+        /// Directory the capability files are written under (default: current directory)
+        pub target_dir: Option<String>,
+        /// Whether to replace existing files
+        #[serde(default)]
+        pub overwrite: bool,
+    }
+
+    /// Capability file state change request
+    #[derive(Debug, Deserialize)]
+    pub struct FileStateRequest {
+        /// Capability file path, as listed by `/registry/files`
+        pub path: String,
+        /// Flags to override
+        #[serde(flatten)]
+        pub state: ToolState,
+    }
+
+    /// Capability file state query parameters
+    #[derive(Debug, Deserialize)]
+    pub struct FileStateQuery {
+        /// Capability file path, as listed by `/registry/files`
+        pub path: String,
+    }
+
     /// MCP Resources query parameters
     #[derive(Debug, Deserialize)]
     pub struct ResourceListQuery {
@@ -4347,6 +5357,22 @@ pub struct GetEnvVarsRequest {
         pub limit: Option<usize>,
     }
 
+    /// Quota query parameters
+    #[derive(Debug, Deserialize)]
+    pub struct QuotaQuery {
+        /// Only quotas of API keys (`api_key`), users (`user`) or roles (`role`)
+        pub scope: Option<crate::routing::quota::QuotaScope>,
+        /// Only the quota of this API key, user or role
+        pub key: Option<String>,
+    }
+
+    /// Tool category query parameters
+    #[derive(Debug, Deserialize)]
+    pub struct ToolCategoriesQuery {
+        /// Category (name or tag) to list the tools of; all categories when omitted
+        pub category: Option<String>,
+    }
+
     /// Environment variable information
     #[derive(Debug, Serialize)]
     pub struct EnvVarInfo {
@@ -4402,4 +5428,29 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         assert!(resp.status().is_success());
     }
+
+    #[actix_web::test]
+    async fn test_config_save_keeps_redacted_secrets() {
+        let config = crate::config::RegistryConfig::default();
+        let registry = Arc::new(RegistryService::new(config.clone()).await.unwrap());
+        let mcp_server = Arc::new(McpServer::new(config).await.unwrap());
+        let app = test::init_service(
+            App::new().configure(|cfg| configure_dashboard_api(cfg, registry, mcp_server, None, Arc::new(ResourceManager::new()), Arc::new(PromptManager::new()), None))
+        ).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("magictunnel-config.yaml");
+        let stored = "server:\n  port: 3001\nauth:\n  type: bearer\n  token: \"s3cret-token\"\n";
+        std::fs::write(&path, stored).unwrap();
+
+        // What the config editor loads, edited and sent back
+        let edited = redact_config_text(stored).replace("port: 3001", "port: 3002");
+        let req = test::TestRequest::post()
+            .uri("/dashboard/api/config/save")
+            .set_json(json!({ "content": edited, "config_path": path.to_string_lossy() }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), stored.replace("port: 3001", "port: 3002"));
+    }
 }
@@ -0,0 +1,63 @@
+//! Tests for time windows restricting tool calls
+
+use async_trait::async_trait;
+use magictunnel::error::Result;
+use magictunnel::mcp::{Tool, ToolCall};
+use magictunnel::registry::{RoutingConfig, ToolDefinition};
+use magictunnel::routing::access_window::AccessWindows;
+use magictunnel::routing::types::{AgentResult, AgentType};
+use magictunnel::routing::{AgentRouter, DefaultAgentRouter, Router};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Agent router that parses routings like the default one and succeeds every call
+struct OkAgentRouter;
+
+#[async_trait]
+impl AgentRouter for OkAgentRouter {
+    fn parse_routing_config(&self, routing: &RoutingConfig) -> Result<AgentType> {
+        DefaultAgentRouter::new().parse_routing_config(routing)
+    }
+
+    async fn execute_with_agent(&self, _tool_call: &ToolCall, _agent: &AgentType) -> Result<AgentResult> {
+        Ok(AgentResult { success: true, data: Some(json!({"ok": true})), error: None, metadata: None })
+    }
+}
+
+fn tool(name: &str) -> ToolDefinition {
+    let tool = Tool::new(name.to_string(), "A tool".to_string(), json!({"type": "object"})).unwrap();
+    ToolDefinition::new(tool, RoutingConfig::new("http".to_string(), json!({
+        "method": "POST",
+        "url": "https://deploy.internal/run"
+    }))).unwrap()
+}
+
+fn call(name: &str) -> ToolCall {
+    ToolCall { name: name.to_string(), arguments: json!({}) }
+}
+
+#[tokio::test]
+async fn test_calls_outside_their_window_are_refused() {
+    let router = Router::with_agent_router(Arc::new(OkAgentRouter));
+    router.configure_access_windows(Some(AccessWindows::new(serde_yaml::from_str(r#"
+windows:
+  every_day: { days: [mon, tue, wed, thu, fri, sat, sun] }
+  last_century: { until: "2000-01-01T00:00:00Z" }
+rules:
+  - tools: ["deploy_*"]
+    allowed: [last_century]
+  - tools: ["migrate_db"]
+    denied: [every_day]
+"#).unwrap()).unwrap()));
+
+    let result = router.route(&call("deploy_app"), &tool("deploy_app")).await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.error.as_deref(), Some("Tool 'deploy_app' is outside its allowed window (last_century)"));
+    assert_eq!(result.metadata.unwrap()["error_category"], "outside_allowed_window");
+
+    let result = router.route(&call("migrate_db"), &tool("migrate_db")).await.unwrap();
+    assert!(result.error.unwrap().starts_with("Tool 'migrate_db' cannot be called during every_day"));
+
+    let result = router.route(&call("list_apps"), &tool("list_apps")).await.unwrap();
+    assert!(result.success);
+}
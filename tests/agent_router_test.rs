@@ -301,6 +301,8 @@ async fn test_database_routing_config_parsing() {
             "query": "SELECT 1 as test_value",
             "timeout": 30
         }),
+        fallback: Vec::new(),
+        canary: None,
     };
 
     let _tool_call = ToolCall {
@@ -313,7 +315,7 @@ async fn test_database_routing_config_parsing() {
     assert!(agent_type.is_ok());
 
     match agent_type.unwrap() {
-        AgentType::Database { db_type, connection_string, query, timeout } => {
+        AgentType::Database { db_type, connection_string, query, timeout, .. } => {
             assert_eq!(db_type, "sqlite");
             assert_eq!(connection_string, ":memory:");
             assert_eq!(query, "SELECT 1 as test_value");
@@ -335,6 +337,8 @@ async fn test_database_sqlite_execution() {
             "query": "SELECT {{value}} as result",
             "timeout": 10
         }),
+        fallback: Vec::new(),
+        canary: None,
     };
 
     let tool_call = ToolCall {
@@ -366,3 +370,159 @@ async fn test_database_sqlite_execution() {
     let result_value = first_row.get("result").unwrap().as_i64().unwrap();
     assert_eq!(result_value, 42);
 }
+
+#[tokio::test]
+async fn test_database_sqlite_bound_parameters() {
+    let router = DefaultAgentRouter::new();
+
+    let routing = RoutingConfig {
+        r#type: "database".to_string(),
+        config: json!({
+            "db_type": "sqlite",
+            "connection_string": ":memory:",
+            "query": "SELECT ?1 AS name, ?2 AS missing",
+            "params": ["name", "missing"],
+            "read_only": true
+        }),
+        fallback: Vec::new(),
+        canary: None,
+    };
+
+    // The value is bound, not substituted, so SQL in it is returned verbatim
+    let tool_call = create_test_tool_call("test_sqlite_params", json!({
+        "name": "x'; DROP TABLE users; --"
+    }));
+
+    let agent_type = router.parse_routing_config(&routing).unwrap();
+    let agent_result = router.execute_with_agent(&tool_call, &agent_type).await.unwrap();
+    assert!(agent_result.success);
+
+    let data = agent_result.data.unwrap();
+    let row = &data["rows"][0];
+    assert_eq!(row["name"], "x'; DROP TABLE users; --");
+    assert!(row["missing"].is_null());
+}
+
+#[tokio::test]
+async fn test_database_read_only_rejects_writes() {
+    let router = DefaultAgentRouter::new();
+
+    let routing = RoutingConfig {
+        r#type: "database".to_string(),
+        config: json!({
+            "db_type": "sqlite",
+            "connection_string": ":memory:",
+            "query": "CREATE TABLE notes (id INTEGER)",
+            "read_only": true
+        }),
+        fallback: Vec::new(),
+        canary: None,
+    };
+
+    let tool_call = create_test_tool_call("test_sqlite_read_only", json!({}));
+    let agent_type = router.parse_routing_config(&routing).unwrap();
+    let agent_result = router.execute_with_agent(&tool_call, &agent_type).await.unwrap();
+
+    assert!(!agent_result.success);
+}
+
+#[tokio::test]
+async fn test_database_row_limit() {
+    let router = DefaultAgentRouter::new();
+
+    let routing = RoutingConfig {
+        r#type: "database".to_string(),
+        config: json!({
+            "db_type": "sqlite",
+            "connection_string": ":memory:",
+            "query": "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 50) SELECT i FROM n",
+            "max_rows": 10
+        }),
+        fallback: Vec::new(),
+        canary: None,
+    };
+
+    let tool_call = create_test_tool_call("test_sqlite_row_limit", json!({}));
+    let agent_type = router.parse_routing_config(&routing).unwrap();
+    let agent_result = router.execute_with_agent(&tool_call, &agent_type).await.unwrap();
+    assert!(agent_result.success);
+
+    let data = agent_result.data.unwrap();
+    assert_eq!(data["row_count"], 10);
+    assert_eq!(data["truncated"], true);
+    assert_eq!(data["rows"][9]["i"], 10);
+}
+
+#[tokio::test]
+async fn test_database_mysql_routing_config_parsing() {
+    let router = DefaultAgentRouter::new();
+
+    let routing = RoutingConfig {
+        r#type: "database".to_string(),
+        config: json!({
+            "db_type": "mysql",
+            "connection_string": "mysql://reporting@localhost:3306/shop",
+            "query": "SELECT id FROM orders WHERE customer_id = ?",
+            "params": ["customer_id"],
+            "read_only": true
+        }),
+        fallback: Vec::new(),
+        canary: None,
+    };
+    assert!(routing.validate().is_ok());
+
+    match router.parse_routing_config(&routing).unwrap() {
+        AgentType::Database { db_type, params, read_only, max_rows, .. } => {
+            assert_eq!(db_type, "mysql");
+            assert_eq!(params, vec!["customer_id"]);
+            assert!(read_only);
+            assert_eq!(max_rows, 1000);
+        }
+        _ => panic!("Expected Database agent type"),
+    }
+
+    let unsupported = RoutingConfig {
+        r#type: "database".to_string(),
+        config: json!({"db_type": "oracle", "query": "SELECT 1 FROM dual"}),
+        fallback: Vec::new(),
+        canary: None,
+    };
+    assert!(unsupported.validate().is_err());
+}
+
+#[tokio::test]
+async fn test_subprocess_optional_args() {
+    let router = DefaultAgentRouter::new();
+
+    let routing = RoutingConfig {
+        r#type: "subprocess".to_string(),
+        config: json!({
+            "command": "echo",
+            "args": ["hello"],
+            "optional_args": [
+                {"param": "newline", "args": ["-n"]},
+                {"param": "name", "args": ["{{name}}"]},
+                {"param": "extra", "args": ["--extra", "{{extra}}"]}
+            ]
+        }),
+        fallback: Vec::new(),
+        canary: None,
+    };
+
+    let tool_call = create_test_tool_call("test_optional_args", json!({"name": "world", "newline": false}));
+    let agent_type = router.parse_routing_config(&routing).unwrap();
+    let agent_result = router.execute_with_agent(&tool_call, &agent_type).await.unwrap();
+
+    assert!(agent_result.success);
+    assert_eq!(agent_result.data.unwrap()["stdout"].as_str().unwrap().trim(), "hello world");
+}
+
+#[test]
+fn test_subprocess_invalid_optional_args_are_rejected() {
+    let router = DefaultAgentRouter::new();
+    let routing = RoutingConfig::new(
+        "subprocess".to_string(),
+        json!({"command": "echo", "optional_args": [{"param": "name", "args": "{{name}}"}]}),
+    );
+    assert!(router.parse_routing_config(&routing).is_err());
+}
@@ -0,0 +1,73 @@
+use magictunnel::discovery::{AnnIndexConfig, SemanticSearchConfig, SemanticSearchService, ToolMetadata, VectorFilter};
+use tempfile::TempDir;
+
+fn config(temp_dir: &TempDir) -> SemanticSearchConfig {
+    let mut config = SemanticSearchConfig::default();
+    config.storage.embeddings_file = temp_dir.path().join("embeddings.json");
+    config.storage.metadata_file = temp_dir.path().join("metadata.json");
+    config.storage.hash_file = temp_dir.path().join("hashes.json");
+    config.storage.auto_backup = false;
+    config.model.cache_dir = temp_dir.path().join("models");
+    config.similarity_threshold = 0.0;
+    config.max_results = 3;
+    config.ann_index = Some(AnnIndexConfig {
+        path: temp_dir.path().join("ann"),
+        min_tools: 0,
+        ..AnnIndexConfig::default()
+    });
+    config
+}
+
+fn metadata(name: &str, tags: &[&str]) -> ToolMetadata {
+    ToolMetadata {
+        name: name.to_string(),
+        description: String::new(),
+        enabled: true,
+        hidden: false,
+        content_hash: String::new(),
+        last_updated: 0,
+        embedding_dims: 384,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        server: None,
+    }
+}
+
+#[tokio::test]
+async fn test_semantic_search_uses_ann_index() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = SemanticSearchService::new(config(&temp_dir));
+    service.initialize().await.unwrap();
+
+    let tools = [
+        ("read_file", "Read the contents of a file from disk", &["files"][..]),
+        ("write_file", "Write contents to a file on disk", &["files"][..]),
+        ("http_get", "Send an HTTP GET request to a URL", &["network"][..]),
+        ("ping_host", "Ping a network host to check connectivity", &["network"][..]),
+    ];
+    for (name, description, tags) in tools {
+        let embedding = service.generate_embedding(&format!("{}: {}", name, description)).await.unwrap();
+        service.index_tool_embedding(embedding, metadata(name, tags)).await.unwrap();
+    }
+
+    let query = "read_file: Read the contents of a file from disk";
+    let matches = service.search_similar_tools(query).await.unwrap();
+    assert_eq!(matches.len(), 3);
+    assert_eq!(matches[0].tool_name, "read_file");
+
+    let filter = VectorFilter { tags: vec!["network".to_string()], server: None };
+    let matches = service.search_similar_tools_filtered(query, &filter).await.unwrap();
+    let mut names: Vec<_> = matches.iter().map(|m| m.tool_name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["http_get", "ping_host"]);
+
+    service.remove_tool_embedding("read_file").await.unwrap();
+    service.save_embeddings().await.unwrap();
+    assert!(temp_dir.path().join("ann").join("graph.bin").exists());
+
+    // A restarted service picks the persisted index back up
+    let restarted = SemanticSearchService::new(config(&temp_dir));
+    restarted.initialize().await.unwrap();
+    let matches = restarted.search_similar_tools(query).await.unwrap();
+    assert!(matches.iter().all(|m| m.tool_name != "read_file"));
+    assert_eq!(matches.len(), 3);
+}
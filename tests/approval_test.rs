@@ -0,0 +1,103 @@
+//! Tests for human approval of high-risk tool calls
+
+use async_trait::async_trait;
+use magictunnel::error::Result;
+use magictunnel::mcp::progress::ProgressReporter;
+use magictunnel::mcp::{Tool, ToolCall};
+use magictunnel::registry::{RoutingConfig, ToolDefinition};
+use magictunnel::routing::approval::{ApprovalConfig, ApprovalDecision};
+use magictunnel::routing::types::{AgentResult, AgentType};
+use magictunnel::routing::{AgentRouter, DefaultAgentRouter, Router};
+use serde_json::json;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// Agent router that parses routings like the default one and counts dispatched calls
+#[derive(Default)]
+struct CountingAgentRouter {
+    calls: AtomicU32,
+}
+
+#[async_trait]
+impl AgentRouter for CountingAgentRouter {
+    fn parse_routing_config(&self, routing: &RoutingConfig) -> Result<AgentType> {
+        DefaultAgentRouter::new().parse_routing_config(routing)
+    }
+
+    async fn execute_with_agent(&self, _tool_call: &ToolCall, _agent: &AgentType) -> Result<AgentResult> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(AgentResult { success: true, data: Some(json!({"transferred": true})), error: None, metadata: None })
+    }
+}
+
+fn tool() -> ToolDefinition {
+    let tool = Tool::new("transfer_funds".to_string(), "Transfer funds".to_string(), json!({"type": "object"})).unwrap();
+    ToolDefinition::new(tool, RoutingConfig::new("http".to_string(), json!({
+        "method": "POST",
+        "url": "https://payments.internal/transfers"
+    }))).unwrap()
+}
+
+fn call() -> ToolCall {
+    ToolCall { name: "transfer_funds".to_string(), arguments: json!({"amount": 250}) }
+}
+
+fn router(agents: Arc<CountingAgentRouter>) -> Arc<Router> {
+    let router = Arc::new(Router::with_agent_router(agents));
+    router.approvals().configure(ApprovalConfig { tools: vec!["transfer_funds".to_string()], ..Default::default() });
+    router
+}
+
+/// Wait until a call is parked and return its approval ID
+async fn parked(router: &Router) -> String {
+    loop {
+        if let Some(request) = router.approvals().pending().first() {
+            return request.id.clone();
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_approved_call_runs_with_progress() {
+    let agents = Arc::new(CountingAgentRouter::default());
+    let router = router(agents.clone());
+    let (sender, mut progress) = tokio::sync::mpsc::unbounded_channel();
+    let reporter = ProgressReporter::new(json!("tok-1"), sender);
+
+    let waiting = tokio::spawn({
+        let router = router.clone();
+        async move { reporter.scope(router.route(&call(), &tool())).await }
+    });
+    let id = parked(&router).await;
+    assert_eq!(router.approvals().pending()[0].arguments, json!({"amount": 250}));
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 0);
+
+    let decision = ApprovalDecision { approved: true, approver: Some("carol".to_string()), comment: None };
+    router.approvals().decide(&id, decision).unwrap();
+    let result = waiting.await.unwrap().unwrap();
+    assert_eq!(result.data.unwrap()["transferred"], true);
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 1);
+
+    let waiting_message = progress.recv().await.unwrap().params.unwrap()["message"].clone();
+    assert!(waiting_message.as_str().unwrap().starts_with("Waiting for approval"));
+    assert_eq!(progress.recv().await.unwrap().params.unwrap()["message"], "Approved by carol");
+}
+
+#[tokio::test]
+async fn test_denied_call_is_not_dispatched() {
+    let agents = Arc::new(CountingAgentRouter::default());
+    let router = router(agents.clone());
+
+    let waiting = tokio::spawn({
+        let router = router.clone();
+        async move { router.route(&call(), &tool()).await }
+    });
+    let id = parked(&router).await;
+    router.approvals().decide(&id, ApprovalDecision::default()).unwrap();
+
+    let result = waiting.await.unwrap().unwrap();
+    assert!(!result.success);
+    assert_eq!(result.metadata.unwrap()["error_category"], "approval_denied");
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,84 @@
+use magictunnel::mcp::types::ToolCall;
+use magictunnel::registry::aws_generator::{AwsCapabilityGenerator, AwsGeneratorConfig};
+use magictunnel::registry::types::ToolDefinition;
+use magictunnel::routing::agent_router::{AgentRouter, DefaultAgentRouter};
+use serde_json::json;
+use wiremock::matchers::{body_json, header, header_exists, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const WIDGETS: &str = r#"{
+    "metadata": {
+        "apiVersion": "2024-01-01",
+        "endpointPrefix": "widgets",
+        "protocol": "rest-json",
+        "serviceId": "Widgets"
+    },
+    "operations": {
+        "UpdateWidget": {
+            "name": "UpdateWidget",
+            "http": {"method": "PUT", "requestUri": "/widgets/{WidgetId}"},
+            "input": {"shape": "UpdateWidgetRequest"}
+        }
+    },
+    "shapes": {
+        "UpdateWidgetRequest": {
+            "type": "structure",
+            "required": ["WidgetId", "Owner"],
+            "members": {
+                "WidgetId": {"shape": "String", "location": "uri", "locationName": "WidgetId"},
+                "DryRun": {"shape": "Boolean", "location": "querystring", "locationName": "dryRun"},
+                "Owner": {"shape": "String", "location": "header", "locationName": "X-Widget-Owner"},
+                "Name": {"shape": "String"},
+                "Size": {"shape": "Integer"}
+            }
+        },
+        "String": {"type": "string"},
+        "Boolean": {"type": "boolean"},
+        "Integer": {"type": "integer"}
+    }
+}"#;
+
+/// Generate the tools of `model` against `server`, signing with static test credentials
+fn generated_tool(model: &str, server: &MockServer, name: &str) -> ToolDefinition {
+    let generator = AwsCapabilityGenerator::new(AwsGeneratorConfig {
+        endpoint_url: Some(server.uri()),
+        ..Default::default()
+    });
+    let file = generator.generate_from_model(model).unwrap();
+    let mut tool = file.tools.into_iter().find(|t| t.name == name).unwrap();
+    tool.routing.config["aws_sigv4"]["access_key_id"] = json!("AKIDEXAMPLE");
+    tool.routing.config["aws_sigv4"]["secret_access_key"] = json!("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY");
+    tool
+}
+
+#[tokio::test]
+async fn test_generated_rest_json_tool_calls_endpoint() {
+    let server = MockServer::start().await;
+    Mock::given(method("PUT"))
+        .and(path("/widgets/w%201"))
+        .and(query_param("dryRun", "true"))
+        .and(header("X-Widget-Owner", "ops"))
+        .and(header_exists("Authorization"))
+        .and(header_exists("X-Amz-Date"))
+        .and(body_json(json!({"Name": "gear", "Size": 3})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"Updated": true})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let tool = generated_tool(WIDGETS, &server, "widgets_update_widget");
+    let call = ToolCall::new(tool.name.clone(), json!({
+        "WidgetId": "w 1",
+        "DryRun": true,
+        "Owner": "ops",
+        "Name": "gear",
+        "Size": 3
+    }));
+    let result = DefaultAgentRouter::new().route(&call, &tool).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+
+    let requests = server.received_requests().await.unwrap();
+    let authorization = requests[0].headers.get("Authorization").unwrap().to_str().unwrap();
+    assert!(authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+    assert!(authorization.contains("/us-east-1/widgets/aws4_request"));
+}
@@ -0,0 +1,115 @@
+//! Tests for canary routing
+
+use async_trait::async_trait;
+use magictunnel::error::{ProxyError, Result};
+use magictunnel::mcp::{Tool, ToolCall};
+use magictunnel::registry::{RoutingConfig, ToolDefinition};
+use magictunnel::routing::canary::CanaryConfig;
+use magictunnel::routing::types::{AgentResult, AgentType};
+use magictunnel::routing::{AgentRouter, Router};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Agent router where `http://v2` returns failed results and other URLs succeed
+struct VersionedAgentRouter;
+
+#[async_trait]
+impl AgentRouter for VersionedAgentRouter {
+    fn parse_routing_config(&self, routing: &RoutingConfig) -> Result<AgentType> {
+        Ok(AgentType::Http {
+            method: "GET".to_string(),
+            url: routing.config["url"].as_str().unwrap_or_default().to_string(),
+            headers: None,
+            timeout: None,
+            aws_sigv4: None,
+            token_exchange: None,
+            params: Default::default(),
+        })
+    }
+
+    async fn execute_with_agent(&self, _tool_call: &ToolCall, agent: &AgentType) -> Result<AgentResult> {
+        let AgentType::Http { url, .. } = agent else {
+            return Err(ProxyError::routing("expected an HTTP agent".to_string()));
+        };
+        let success = url != "http://v2";
+        Ok(AgentResult {
+            success,
+            data: Some(json!({"url": url})),
+            error: (!success).then(|| "HTTP 500".to_string()),
+            metadata: None,
+        })
+    }
+}
+
+fn http(url: &str) -> RoutingConfig {
+    RoutingConfig::new("http".to_string(), json!({"method": "GET", "url": url}))
+}
+
+fn tool_with_canary(percentage: f64) -> ToolDefinition {
+    let mut routing = http("http://v1");
+    routing.canary = Some(CanaryConfig { percentage, routing: Box::new(http("http://v2")) });
+    let tool = Tool::new("search".to_string(), "Search".to_string(), json!({"type": "object"})).unwrap();
+    ToolDefinition::new(tool, routing).unwrap()
+}
+
+fn call() -> ToolCall {
+    ToolCall { name: "search".to_string(), arguments: json!({}) }
+}
+
+#[tokio::test]
+async fn test_canary_share_and_metrics() {
+    let router = Router::with_agent_router(Arc::new(VersionedAgentRouter));
+    let tool = tool_with_canary(20.0);
+
+    let mut canary_calls = 0;
+    for _ in 0..10 {
+        let result = router.route(&call(), &tool).await.unwrap();
+        let metadata = result.metadata.unwrap();
+        if metadata["canary_variant"] == "canary" {
+            canary_calls += 1;
+            assert_eq!(result.data, Some(json!({"url": "http://v2"})));
+        } else {
+            assert_eq!(metadata["canary_variant"], "primary");
+        }
+    }
+    assert_eq!(canary_calls, 2);
+
+    let metrics = router.canary_metrics("search").unwrap();
+    assert_eq!((metrics.primary.calls, metrics.primary.errors), (8, 0));
+    assert_eq!((metrics.canary.calls, metrics.canary.errors), (2, 2));
+    assert_eq!(metrics.canary.error_rate, 1.0);
+    assert!(router.all_canary_metrics().contains_key("search"));
+}
+
+#[tokio::test]
+async fn test_failed_canary_uses_fallback() {
+    let router = Router::with_agent_router(Arc::new(VersionedAgentRouter));
+    let mut tool = tool_with_canary(100.0);
+    tool.routing.fallback = vec![http("http://v1-mirror")];
+
+    let result = router.route(&call(), &tool).await.unwrap();
+    assert_eq!(result.data, Some(json!({"url": "http://v1-mirror"})));
+    let metadata = result.metadata.unwrap();
+    assert_eq!(metadata["canary_variant"], "canary");
+    assert_eq!(metadata["fallback"]["leg"], 1);
+    assert_eq!(router.canary_metrics("search").unwrap().canary.errors, 1);
+}
+
+#[test]
+fn test_canary_config_parsing_and_validation() {
+    let routing: RoutingConfig = serde_yaml::from_str(r#"
+type: http
+config: { method: GET, url: "http://v1" }
+canary:
+  percentage: 5
+  routing:
+    type: http
+    config: { method: GET, url: "http://v2" }
+"#).unwrap();
+    assert_eq!(routing.canary.as_ref().unwrap().percentage, 5.0);
+    assert!(routing.validate().is_ok());
+
+    let mut invalid = routing.clone();
+    invalid.canary.as_mut().unwrap().routing = Box::new(RoutingConfig::new("http".to_string(), json!({"method": "GET"})));
+    assert!(invalid.validate().is_err());
+}
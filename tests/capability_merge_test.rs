@@ -15,6 +15,7 @@ use serde_json::json;
 /// Helper function to create a simple capability file for testing
 fn create_test_capability(name: &str, tools: Vec<ToolDefinition>) -> CapabilityFile {
     CapabilityFile {
+        schema_version: None,
         metadata: Some(FileMetadata {
             name: Some(name.to_string()),
             description: Some(format!("Test capability for {}", name)),
@@ -45,10 +46,20 @@ fn create_test_tool(name: &str, description: &str) -> ToolDefinition {
                 "url": "https://example.com/api",
                 "method": "POST"
             }),
+            fallback: Vec::new(),
+            canary: None,
         },
         annotations: None,
         hidden: false, // Test tools are visible by default
         enabled: true, // Test tools are enabled by default
+        deprecated: false,
+        deprecation_reason: None,
+        sunset_date: None,
+        tags: Vec::new(),
+        output_schema: None,
+        cache: None,
+        background: false,
+        cost: None,
     }
 }
 
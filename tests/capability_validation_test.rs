@@ -23,6 +23,7 @@ fn create_valid_capability() -> CapabilityFile {
     };
 
     CapabilityFile {
+        schema_version: None,
         metadata: Some(metadata),
         tools: vec![tool1, tool2],
     }
@@ -47,10 +48,20 @@ fn create_valid_tool(name: &str, description: &str) -> ToolDefinition {
                 "url": "https://example.com/api",
                 "method": "POST"
             }),
+            fallback: Vec::new(),
+            canary: None,
         },
         annotations: None,
         hidden: false, // Test tools are visible by default
         enabled: true, // Test tools are enabled by default
+        deprecated: false,
+        deprecation_reason: None,
+        sunset_date: None,
+        tags: Vec::new(),
+        output_schema: None,
+        cache: None,
+        background: false,
+        cost: None,
     }
 }
 
@@ -68,10 +79,20 @@ fn create_invalid_tool() -> ToolDefinition {
                 // Missing required 'method' field
                 "url": "https://example.com/api"
             }),
+            fallback: Vec::new(),
+            canary: None,
         },
         annotations: None,
         hidden: false, // Test tools are visible by default
         enabled: true, // Test tools are enabled by default
+        deprecated: false,
+        deprecation_reason: None,
+        sunset_date: None,
+        tags: Vec::new(),
+        output_schema: None,
+        cache: None,
+        background: false,
+        cost: None,
     }
 }
 
@@ -98,6 +119,7 @@ fn test_validate_invalid_capability_missing_metadata() -> Result<()> {
     // Create a capability without metadata
     let tool = create_valid_tool("tool1", "Test tool");
     let capability = CapabilityFile {
+        schema_version: None,
         metadata: None,
         tools: vec![tool],
     };
@@ -128,6 +150,7 @@ fn test_validate_invalid_capability_empty_tools() -> Result<()> {
     };
     
     let capability = CapabilityFile {
+        schema_version: None,
         metadata: Some(metadata),
         tools: vec![],
     };
@@ -161,6 +184,7 @@ fn test_validate_invalid_tool_definition() -> Result<()> {
     };
     
     let capability = CapabilityFile {
+        schema_version: None,
         metadata: Some(metadata),
         tools: vec![valid_tool, invalid_tool],
     };
@@ -189,6 +213,7 @@ fn test_validate_with_different_levels() -> Result<()> {
     };
     
     let capability = CapabilityFile {
+        schema_version: None,
         metadata: Some(metadata),
         tools: vec![tool],
     };
@@ -223,6 +248,7 @@ fn test_validate_duplicate_tool_names() -> Result<()> {
     };
     
     let capability = CapabilityFile {
+        schema_version: None,
         metadata: Some(metadata),
         tools: vec![tool1, tool2],
     };
@@ -252,6 +278,7 @@ fn test_validate_empty_description() -> Result<()> {
     };
     
     let capability = CapabilityFile {
+        schema_version: None,
         metadata: Some(metadata),
         tools: vec![tool],
     };
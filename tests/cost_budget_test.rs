@@ -0,0 +1,93 @@
+//! Tests for tool costs and spend budgets in the Router
+
+use async_trait::async_trait;
+use magictunnel::error::Result;
+use magictunnel::mcp::{Tool, ToolCall};
+use magictunnel::registry::{RoutingConfig, ToolCostConfig, ToolDefinition};
+use magictunnel::routing::cost::{self, Budgets, CostsConfig, SpendScope};
+use magictunnel::routing::rate_limit;
+use magictunnel::routing::types::{AgentResult, AgentType};
+use magictunnel::routing::{AgentRouter, Router};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Agent router that counts executions and reports LLM-style token usage
+#[derive(Default)]
+struct CountingAgentRouter {
+    calls: AtomicUsize,
+}
+
+#[async_trait]
+impl AgentRouter for CountingAgentRouter {
+    fn parse_routing_config(&self, _routing: &RoutingConfig) -> Result<AgentType> {
+        Ok(AgentType::Subprocess { command: "true".to_string(), args: vec![], timeout: None, env: None, optional_args: vec![] })
+    }
+
+    async fn execute_with_agent(&self, _tool_call: &ToolCall, _agent: &AgentType) -> Result<AgentResult> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(AgentResult { success: true, data: Some(json!({"usage": {"total_tokens": 100}})), error: None, metadata: None })
+    }
+}
+
+fn tool(name: &str, cost: Option<ToolCostConfig>) -> ToolDefinition {
+    let tool = Tool::new(name.to_string(), "Test tool".to_string(), json!({"type": "object"})).unwrap();
+    let mut tool = ToolDefinition::new(tool, RoutingConfig::new("subprocess".to_string(), json!({"command": "true"}))).unwrap();
+    tool.cost = cost;
+    tool
+}
+
+async fn as_ci<F: std::future::Future>(future: F) -> F::Output {
+    rate_limit::as_client(Some("ci".to_string()), future).await
+}
+
+fn call(name: &str) -> ToolCall {
+    ToolCall { name: name.to_string(), arguments: json!({}) }
+}
+
+#[tokio::test]
+async fn test_calls_are_charged_until_budget_is_spent() {
+    let agent = Arc::new(CountingAgentRouter::default());
+    let router = Router::with_agent_router(agent.clone());
+    router.cost_tracker().configure(CostsConfig {
+        budgets: Budgets { api_keys: HashMap::from([("ci".to_string(), 1.0)]), ..Default::default() },
+        window_hours: None,
+    });
+    let summarize = tool("summarize", Some(ToolCostConfig { per_call: 0.2, per_token: 0.003 }));
+    let echo = tool("echo", None);
+
+    let result = as_ci(router.route(&call("summarize"), &summarize)).await.unwrap();
+    assert!(result.success);
+    assert_eq!(result.metadata.unwrap()["cost"], json!({"amount": 0.5, "tokens": 100}));
+    assert!(as_ci(router.route(&call("summarize"), &summarize)).await.unwrap().success);
+
+    let refused = as_ci(router.route(&call("summarize"), &summarize)).await.unwrap();
+    assert!(!refused.success);
+    let metadata = refused.metadata.unwrap();
+    assert_eq!(metadata["error_category"], "budget_exceeded");
+    assert_eq!(metadata["budget"]["scope"], "api_key");
+    assert_eq!(agent.calls.load(Ordering::SeqCst), 2);
+
+    // Free tools and other callers are unaffected
+    assert!(as_ci(router.route(&call("echo"), &echo)).await.unwrap().success);
+    assert!(router.route(&call("summarize"), &summarize).await.unwrap().success);
+}
+
+#[tokio::test]
+async fn test_spend_is_reported_per_user_and_session() {
+    let router = Router::with_agent_router(Arc::new(CountingAgentRouter::default()));
+    let summarize = tool("summarize", Some(ToolCostConfig { per_call: 1.0, per_token: 0.0 }));
+
+    for session in ["s1", "s1", "s2"] {
+        let routed = router.route(&call("summarize"), &summarize);
+        cost::as_user(Some("alice".to_string()), cost::in_session(Some(session.to_string()), routed)).await.unwrap();
+    }
+
+    let report = router.cost_tracker().report();
+    let spent = |scope: SpendScope, key: &str| report.iter().find(|r| r.scope == scope && r.key == key).map(|r| r.spent);
+    assert_eq!(spent(SpendScope::Tool, "summarize"), Some(3.0));
+    assert_eq!(spent(SpendScope::User, "alice"), Some(3.0));
+    assert_eq!(spent(SpendScope::Session, "s1"), Some(2.0));
+    assert_eq!(spent(SpendScope::Session, "s2"), Some(1.0));
+}
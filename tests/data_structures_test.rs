@@ -351,6 +351,35 @@ mod tool_definition_tests {
         let tool_def = ToolDefinition::new(tool, routing).unwrap();
         assert!(!tool_def.is_safe());
     }
+
+    #[test]
+    fn test_tool_definition_deprecation() {
+        let schema = json!({"type": "object"});
+        let tool = Tool::new("old_tool".to_string(), "Old tool".to_string(), schema).unwrap();
+        let routing = RoutingConfig::new("subprocess".to_string(), json!({"command": "echo"}));
+        let mut tool_def = ToolDefinition::new(tool, routing).unwrap();
+
+        assert!(!tool_def.is_deprecated());
+        assert!(tool_def.deprecation_warning().is_none());
+        assert!(tool_def.to_mcp_tool().annotations.is_none());
+
+        tool_def.deprecated = true;
+        tool_def.deprecation_reason = Some("use new_tool instead".to_string());
+        tool_def.sunset_date = Some("2026-01-31".to_string());
+        assert!(tool_def.validate().is_ok());
+        assert_eq!(
+            tool_def.deprecation_warning().unwrap(),
+            "Tool 'old_tool' is deprecated and will be removed after 2026-01-31: use new_tool instead"
+        );
+
+        let annotations = serde_json::to_value(tool_def.to_mcp_tool().annotations.unwrap()).unwrap();
+        assert_eq!(annotations["deprecated"], true);
+        assert_eq!(annotations["deprecationReason"], "use new_tool instead");
+        assert_eq!(annotations["sunsetDate"], "2026-01-31");
+
+        tool_def.sunset_date = Some("next year".to_string());
+        assert!(tool_def.validate().is_err());
+    }
 }
 
 #[cfg(test)]
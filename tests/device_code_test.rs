@@ -0,0 +1,97 @@
+use magictunnel::auth::DeviceCodeFlow;
+use magictunnel::config::OAuthConfig;
+use serde_json::json;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn oauth_config(server: &MockServer) -> OAuthConfig {
+    OAuthConfig {
+        provider: "github".to_string(),
+        client_id: "cli-client".to_string(),
+        client_secret: String::new(),
+        auth_url: format!("{}/login/oauth/authorize", server.uri()),
+        token_url: format!("{}/login/oauth/access_token", server.uri()),
+        device_auth_url: Some(format!("{}/login/device/code", server.uri())),
+    }
+}
+
+async fn mount_device_code(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/login/device/code"))
+        .and(body_string_contains("client_id=cli-client"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "device_code": "device-123",
+            "user_code": "WDJB-MJHT",
+            "verification_uri": "https://example.com/device",
+            "expires_in": 900,
+            "interval": 1
+        })))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_device_flow_polls_until_approved() {
+    let idp = MockServer::start().await;
+    mount_device_code(&idp).await;
+    // GitHub reports a pending authorization with a 200 status
+    Mock::given(method("POST"))
+        .and(path("/login/oauth/access_token"))
+        .and(body_string_contains("device_code=device-123"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"error": "authorization_pending"})))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&idp)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/login/oauth/access_token"))
+        .and(body_string_contains("grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Adevice_code"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "user-token",
+            "token_type": "bearer",
+            "refresh_token": "refresh-token",
+            "expires_in": 28800
+        })))
+        .mount(&idp)
+        .await;
+
+    let flow = DeviceCodeFlow::new(oauth_config(&idp)).unwrap();
+    let authorization = flow.start().await.unwrap();
+    assert_eq!(authorization.user_code, "WDJB-MJHT");
+
+    let token = flow.poll(&authorization).await.unwrap();
+    assert_eq!(token.access_token, "user-token");
+    assert_eq!(token.refresh_token.as_deref(), Some("refresh-token"));
+}
+
+#[tokio::test]
+async fn test_device_flow_denied() {
+    let idp = MockServer::start().await;
+    mount_device_code(&idp).await;
+    Mock::given(method("POST"))
+        .and(path("/login/oauth/access_token"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": "access_denied",
+            "error_description": "The user denied the request"
+        })))
+        .mount(&idp)
+        .await;
+
+    let flow = DeviceCodeFlow::new(oauth_config(&idp)).unwrap();
+    let authorization = flow.start().await.unwrap();
+    let error = flow.poll(&authorization).await.unwrap_err();
+    assert!(error.to_string().contains("access_denied"), "{}", error);
+}
+
+#[test]
+fn test_device_flow_requires_device_auth_url() {
+    let config = OAuthConfig {
+        provider: "github".to_string(),
+        client_id: "cli-client".to_string(),
+        client_secret: String::new(),
+        auth_url: "https://github.com/login/oauth/authorize".to_string(),
+        token_url: "https://github.com/login/oauth/access_token".to_string(),
+        device_auth_url: None,
+    };
+    assert!(DeviceCodeFlow::new(config).is_err());
+}
@@ -0,0 +1,107 @@
+use magictunnel::mcp::types::ToolCall;
+use magictunnel::registry::RoutingConfig;
+use magictunnel::routing::agent_router::{AgentRouter, DefaultAgentRouter};
+use magictunnel::routing::docker::DockerCommand;
+use magictunnel::routing::types::AgentType;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_docker_agent_parsing() {
+    let routing_config = RoutingConfig::new("docker".to_string(), json!({
+        "image": "python:3.12-slim",
+        "command": ["python", "-c", "{{code}}"],
+        "env": {"LOG_LEVEL": "{{log_level}}"},
+        "mounts": ["/srv/data:/data:ro"],
+        "cpus": 0.5,
+        "timeout": 30,
+        "max_output_bytes": 2048
+    }));
+
+    let router = DefaultAgentRouter::new();
+    match router.parse_routing_config(&routing_config).unwrap() {
+        AgentType::Docker { timeout, max_output_bytes, config } => {
+            assert_eq!(timeout, Some(30));
+            assert_eq!(max_output_bytes, 2048);
+            assert_eq!(config.image.as_deref(), Some("python:3.12-slim"));
+            assert_eq!(config.command, DockerCommand::Args(vec!["python".into(), "-c".into(), "{{code}}".into()]));
+            assert_eq!(config.env["LOG_LEVEL"], "{{log_level}}");
+            assert_eq!(config.mounts, vec!["/srv/data:/data:ro"]);
+            assert_eq!(config.cpus, Some(0.5));
+            assert!(config.network.is_none());
+            assert_eq!(config.docker_binary, "docker");
+        }
+        _ => panic!("Expected Docker agent type"),
+    }
+}
+
+#[tokio::test]
+async fn test_docker_agent_parsing_errors() {
+    let router = DefaultAgentRouter::new();
+
+    let result = router.parse_routing_config(&RoutingConfig::new("docker".to_string(), json!({"command": ["ls"]})));
+    assert!(result.unwrap_err().to_string().contains("image or container"));
+
+    let result = router.parse_routing_config(&RoutingConfig::new("docker".to_string(), json!({
+        "container": "sandbox",
+        "command": ["ls"],
+        "mounts": ["/:/host"]
+    })));
+    assert!(result.is_err());
+}
+
+/// Fake docker client that prints each argument on its own line
+#[cfg(unix)]
+fn fake_docker(dir: &std::path::Path, body: &str) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join("docker");
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path.display().to_string()
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_docker_agent_runs_new_container() {
+    let dir = tempfile::tempdir().unwrap();
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("docker".to_string(), json!({
+        "image": "alpine:3",
+        "command": "wc -c {{path}}",
+        "env": {"MODE": "{{mode}}"},
+        "docker_binary": fake_docker(dir.path(), r#"for arg in "$@"; do echo "$arg"; done"#)
+    }))).unwrap();
+    let tool_call = ToolCall {
+        name: "count_bytes".to_string(),
+        arguments: json!({"path": "/etc/hosts; id", "mode": "fast"}),
+    };
+
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    let data = result.data.unwrap();
+    let args: Vec<&str> = data["stdout"].as_str().unwrap().lines().collect();
+    assert_eq!(args[..2], ["run", "--rm"]);
+    assert!(args.windows(2).any(|w| w == ["--network", "none"]));
+    assert!(args.windows(2).any(|w| w == ["--env", "MODE=fast"]));
+    assert_eq!(args[args.len() - 4..], ["alpine:3", "sh", "-c", "wc -c '/etc/hosts; id'"]);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_docker_agent_reports_daemon_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("docker".to_string(), json!({
+        "image": "missing:latest",
+        "command": ["true"],
+        "docker_binary": fake_docker(dir.path(), "echo 'Unable to find image' >&2; exit 125")
+    }))).unwrap();
+    let tool_call = ToolCall {
+        name: "noop".to_string(),
+        arguments: json!({}),
+    };
+
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.error.as_deref(), Some("Docker failed: Unable to find image"));
+}
@@ -0,0 +1,103 @@
+//! Tests for dry-run tool calls
+
+use async_trait::async_trait;
+use magictunnel::error::Result;
+use magictunnel::mcp::{Tool, ToolCall};
+use magictunnel::registry::{RoutingConfig, ToolDefinition};
+use magictunnel::routing::dry_run::{self, DryRunConfig};
+use magictunnel::routing::types::{AgentResult, AgentType};
+use magictunnel::routing::{AgentRouter, DefaultAgentRouter, Router};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// Agent router that parses routings like the default one and counts dispatched calls
+#[derive(Default)]
+struct CountingAgentRouter {
+    calls: AtomicU32,
+}
+
+#[async_trait]
+impl AgentRouter for CountingAgentRouter {
+    fn parse_routing_config(&self, routing: &RoutingConfig) -> Result<AgentType> {
+        DefaultAgentRouter::new().parse_routing_config(routing)
+    }
+
+    async fn execute_with_agent(&self, _tool_call: &ToolCall, _agent: &AgentType) -> Result<AgentResult> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(AgentResult { success: true, data: Some(json!({"deleted": true})), error: None, metadata: None })
+    }
+}
+
+fn tool(destructive: bool) -> ToolDefinition {
+    let tool = Tool::new("delete_user".to_string(), "Delete a user".to_string(), json!({"type": "object"})).unwrap();
+    let mut tool = ToolDefinition::new(tool, RoutingConfig::new("http".to_string(), json!({
+        "method": "DELETE",
+        "url": "https://users.internal/users/{{user_id}}",
+        "headers": {"Authorization": "Bearer s3cr3t"}
+    }))).unwrap();
+    let annotations: HashMap<String, String> = [("destructiveHint".to_string(), destructive.to_string())].into_iter().collect();
+    tool.annotations = Some(annotations);
+    tool
+}
+
+fn call() -> ToolCall {
+    ToolCall { name: "delete_user".to_string(), arguments: json!({"user_id": "42"}) }
+}
+
+#[tokio::test]
+async fn test_requested_dry_run_is_not_dispatched() {
+    let agents = Arc::new(CountingAgentRouter::default());
+    let router = Router::with_agent_router(agents.clone());
+
+    let result = dry_run::dry_run(router.route(&call(), &tool(false))).await.unwrap();
+    assert!(result.success);
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 0);
+
+    let data = result.data.unwrap();
+    assert_eq!(data["dry_run"], true);
+    let preview = &data["would_execute"][0];
+    assert_eq!(preview["type"], "http");
+    assert_eq!(preview["method"], "DELETE");
+    assert_eq!(preview["url"], "https://users.internal/users/42");
+    assert_ne!(preview["headers"]["Authorization"], "Bearer s3cr3t");
+    assert_eq!(result.metadata.unwrap()["dry_run"], true);
+}
+
+#[tokio::test]
+async fn test_policy_dry_runs_destructive_tools() {
+    let agents = Arc::new(CountingAgentRouter::default());
+    let router = Router::with_agent_router(agents.clone());
+    router.configure_dry_run(DryRunConfig { destructive_tools: true });
+
+    let result = router.route(&call(), &tool(true)).await.unwrap();
+    assert_eq!(result.data.unwrap()["dry_run"], true);
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 0);
+
+    let result = router.route(&call(), &tool(false)).await.unwrap();
+    assert_eq!(result.data.unwrap()["deleted"], true);
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_dry_run_previews_each_endpoint() {
+    let agents = Arc::new(CountingAgentRouter::default());
+    let router = Router::with_agent_router(agents.clone());
+    let mut tool = tool(false);
+    tool.routing.config["load_balancing"] = json!({
+        "strategy": "round_robin",
+        "endpoints": [
+            {"url": "https://a.internal/users/{{user_id}}"},
+            {"url": "https://b.internal/users/{{user_id}}"}
+        ]
+    });
+
+    let result = dry_run::dry_run(router.route(&call(), &tool)).await.unwrap();
+    let data = result.data.unwrap();
+    let previews = data["would_execute"].as_array().unwrap();
+    assert_eq!(previews.len(), 2);
+    assert_eq!(previews[1]["url"], "https://b.internal/users/42");
+    assert_eq!(previews[1]["endpoint_index"], 1);
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,149 @@
+use magictunnel::discovery::{
+    build_provider, EmbeddingInput, EmbeddingProvider, EmbeddingProviderConfig, SemanticSearchConfig, SemanticSearchService, ToolMetadata,
+};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use wiremock::matchers::{body_partial_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn provider_config(server: &MockServer, r#type: &str, model: &str, batch_size: usize) -> EmbeddingProviderConfig {
+    serde_json::from_value(json!({
+        "type": r#type,
+        "model": model,
+        "api_key": "test-key",
+        "base_url": server.uri(),
+        "batch_size": batch_size,
+    }))
+    .unwrap()
+}
+
+/// OpenAI/Voyage style response, listed in reverse index order
+fn data_response(embeddings: &[Vec<f32>]) -> Value {
+    let data: Vec<Value> = embeddings.iter().enumerate().rev()
+        .map(|(index, embedding)| json!({"index": index, "embedding": embedding}))
+        .collect();
+    json!({"data": data})
+}
+
+fn texts(texts: &[&str]) -> Vec<String> {
+    texts.iter().map(|text| text.to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_openai_provider_batches_requests() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/embeddings"))
+        .and(header("authorization", "Bearer test-key"))
+        .and(body_partial_json(json!({"model": "text-embedding-3-small", "input": ["a", "b"]})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(data_response(&[vec![1.0, 0.0], vec![0.0, 1.0]])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/embeddings"))
+        .and(body_partial_json(json!({"input": ["c"]})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(data_response(&[vec![0.5, 0.5]])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = build_provider(&provider_config(&server, "openai", "text-embedding-3-small", 2)).unwrap();
+    assert_eq!(provider.dimensions(), Some(1536));
+    let embeddings = provider.embed(&texts(&["a", "b", "c"]), EmbeddingInput::Document).await.unwrap();
+    assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.5, 0.5]]);
+}
+
+#[tokio::test]
+async fn test_provider_retries_rate_limited_requests() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/embeddings"))
+        .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+        .up_to_n_times(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/embeddings"))
+        .and(body_partial_json(json!({"input_type": "query"})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(data_response(&[vec![0.1, 0.2, 0.3]])))
+        .mount(&server)
+        .await;
+
+    let provider = build_provider(&provider_config(&server, "voyage", "voyage-3-lite", 8)).unwrap();
+    let embeddings = provider.embed(&texts(&["find files"]), EmbeddingInput::Query).await.unwrap();
+    assert_eq!(embeddings, vec![vec![0.1, 0.2, 0.3]]);
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_provider_gives_up_after_max_retries() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/embed"))
+        .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+        .mount(&server)
+        .await;
+
+    let mut config = provider_config(&server, "cohere", "embed-english-v3.0", 8);
+    config.max_retries = 1;
+    let error = build_provider(&config).unwrap().embed(&texts(&["a"]), EmbeddingInput::Document).await.unwrap_err();
+    assert!(error.to_string().contains("429"));
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_cohere_provider() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/embed"))
+        .and(body_partial_json(json!({"texts": ["a", "b"], "input_type": "search_document", "embedding_types": ["float"]})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "embeddings": {"float": [[1.0, 2.0], [3.0, 4.0]]}
+        })))
+        .mount(&server)
+        .await;
+
+    let provider = build_provider(&provider_config(&server, "cohere", "embed-english-v3.0", 8)).unwrap();
+    let embeddings = provider.embed(&texts(&["a", "b"]), EmbeddingInput::Document).await.unwrap();
+    assert_eq!(embeddings, vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+}
+
+#[tokio::test]
+async fn test_semantic_search_detects_dimension_mismatch() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/embeddings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(data_response(&[vec![0.6, 0.8]])))
+        .mount(&server)
+        .await;
+
+    let temp_dir = TempDir::new().unwrap();
+    let mut config = SemanticSearchConfig::default();
+    config.storage.embeddings_file = temp_dir.path().join("embeddings.json");
+    config.storage.metadata_file = temp_dir.path().join("metadata.json");
+    config.storage.hash_file = temp_dir.path().join("hashes.json");
+    config.model.cache_dir = temp_dir.path().join("models");
+    config.similarity_threshold = 0.0;
+    config.provider = Some(provider_config(&server, "openai", "my-embedding-model", 8));
+    let service = SemanticSearchService::new(config);
+    service.initialize().await.unwrap();
+
+    let metadata = |name: &str, dims: usize| ToolMetadata {
+        name: name.to_string(),
+        description: String::new(),
+        enabled: true,
+        hidden: false,
+        content_hash: String::new(),
+        last_updated: 0,
+        embedding_dims: dims,
+        tags: Vec::new(),
+        server: None,
+    };
+    service.storage.write().await.add_tool_embedding("fresh".to_string(), vec![0.6, 0.8], metadata("fresh", 2));
+    assert_eq!(service.search_similar_tools("anything").await.unwrap().len(), 1);
+
+    service.storage.write().await.add_tool_embedding("stale".to_string(), vec![0.1; 384], metadata("stale", 384));
+    let error = service.search_similar_tools("anything").await.unwrap_err();
+    assert!(error.to_string().contains("--pregenerate-embeddings"));
+}
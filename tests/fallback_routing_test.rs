@@ -0,0 +1,135 @@
+//! Tests for fallback routing chains
+
+use async_trait::async_trait;
+use magictunnel::error::{ProxyError, Result};
+use magictunnel::mcp::{Tool, ToolCall};
+use magictunnel::registry::{RoutingConfig, ToolDefinition};
+use magictunnel::routing::types::{AgentResult, AgentType};
+use magictunnel::routing::{AgentRouter, Router};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// Agent router where URLs in `down` fail and `http://broken` returns a failed result
+#[derive(Default)]
+struct FlakyAgentRouter {
+    down: Mutex<HashSet<String>>,
+}
+
+#[async_trait]
+impl AgentRouter for FlakyAgentRouter {
+    fn parse_routing_config(&self, routing: &RoutingConfig) -> Result<AgentType> {
+        Ok(AgentType::Http {
+            method: "GET".to_string(),
+            url: routing.config["url"].as_str().unwrap_or_default().to_string(),
+            headers: None,
+            timeout: None,
+            aws_sigv4: None,
+            token_exchange: None,
+            params: Default::default(),
+        })
+    }
+
+    async fn execute_with_agent(&self, _tool_call: &ToolCall, agent: &AgentType) -> Result<AgentResult> {
+        let AgentType::Http { url, .. } = agent else {
+            return Err(ProxyError::routing("expected an HTTP agent".to_string()));
+        };
+        if self.down.lock().unwrap().contains(url) {
+            return Err(ProxyError::routing(format!("{} timed out", url)));
+        }
+        if url == "http://broken" {
+            return Ok(AgentResult { success: false, data: None, error: Some("HTTP 500".to_string()), metadata: None });
+        }
+        Ok(AgentResult { success: true, data: Some(json!({"url": url})), error: None, metadata: None })
+    }
+}
+
+fn http(url: &str) -> RoutingConfig {
+    RoutingConfig::new("http".to_string(), json!({"method": "GET", "url": url}))
+}
+
+fn tool(routing: RoutingConfig) -> ToolDefinition {
+    let tool = Tool::new("rates".to_string(), "Rates".to_string(), json!({"type": "object"})).unwrap();
+    ToolDefinition::new(tool, routing).unwrap()
+}
+
+fn call() -> ToolCall {
+    ToolCall { name: "rates".to_string(), arguments: json!({"currency": "EUR"}) }
+}
+
+#[tokio::test]
+async fn test_falls_back_to_mirror() {
+    let agent = Arc::new(FlakyAgentRouter::default());
+    agent.down.lock().unwrap().insert("http://primary".to_string());
+    let router = Router::with_agent_router(agent.clone());
+    let tool = tool(http("http://primary").with_fallback(vec![http("http://broken"), http("http://mirror")]));
+
+    let result = router.route(&call(), &tool).await.unwrap();
+    assert!(result.success);
+    assert_eq!(result.data, Some(json!({"url": "http://mirror"})));
+    let fallback = &result.metadata.unwrap()["fallback"];
+    assert_eq!(fallback["leg"], 2);
+    assert_eq!(fallback["failures"][0]["error"], "Routing error: http://primary timed out");
+    assert_eq!(fallback["failures"][1]["error"], "HTTP 500");
+}
+
+#[tokio::test]
+async fn test_primary_success_and_last_failure() {
+    let agent = Arc::new(FlakyAgentRouter::default());
+    let router = Router::with_agent_router(agent.clone());
+
+    let tool_with_fallback = tool(http("http://primary").with_fallback(vec![http("http://mirror")]));
+    let result = router.route(&call(), &tool_with_fallback).await.unwrap();
+    assert_eq!(result.metadata.unwrap()["fallback"], json!({"leg": 0, "type": "http", "failures": []}));
+
+    agent.down.lock().unwrap().extend(["http://primary".to_string(), "http://mirror".to_string()]);
+    let error = router.route(&call(), &tool_with_fallback).await.unwrap_err();
+    assert!(error.to_string().contains("http://mirror timed out"));
+
+    // Tools without fallbacks are routed as before
+    let result = router.route(&call(), &tool(http("http://broken"))).await.unwrap();
+    assert!(!result.success);
+    assert!(result.metadata.unwrap().get("fallback").is_none());
+}
+
+#[tokio::test]
+async fn test_cache_fallback_serves_last_result() {
+    let agent = Arc::new(FlakyAgentRouter::default());
+    let router = Router::with_agent_router(agent.clone());
+    let cache_leg = RoutingConfig::new("cache".to_string(), json!({"max_age_seconds": 3600}));
+    let tool = tool(http("http://primary").with_fallback(vec![cache_leg]));
+
+    // Nothing cached yet
+    agent.down.lock().unwrap().insert("http://primary".to_string());
+    assert!(router.route(&call(), &tool).await.is_err());
+
+    agent.down.lock().unwrap().clear();
+    router.route(&call(), &tool).await.unwrap();
+    agent.down.lock().unwrap().insert("http://primary".to_string());
+    let result = router.route(&call(), &tool).await.unwrap();
+    assert_eq!(result.data, Some(json!({"url": "http://primary"})));
+    let metadata = result.metadata.unwrap();
+    assert_eq!(metadata["fallback"]["type"], "cache");
+    assert_eq!(metadata["cached_age_seconds"], 0);
+}
+
+#[test]
+fn test_fallback_validation() {
+    let tool_def = Tool::new("rates".to_string(), "Rates".to_string(), json!({"type": "object"})).unwrap();
+    let missing_url = http("http://primary")
+        .with_fallback(vec![RoutingConfig::new("http".to_string(), json!({"method": "GET"}))]);
+    assert!(ToolDefinition::new(tool_def.clone(), missing_url).is_err());
+
+    let nested = http("http://primary").with_fallback(vec![http("http://mirror").with_fallback(vec![http("http://b")])]);
+    assert!(ToolDefinition::new(tool_def.clone(), nested).is_err());
+
+    let parsed: RoutingConfig = serde_yaml::from_str(r#"
+type: http
+config: { method: GET, url: "http://primary" }
+fallback:
+  - type: cache
+    config: {}
+"#).unwrap();
+    assert_eq!(parsed.fallback[0].r#type, "cache");
+    assert!(ToolDefinition::new(tool_def, parsed).is_ok());
+}
@@ -55,6 +55,8 @@ fn create_graphql_routing_config(
     RoutingConfig {
         r#type: "graphql".to_string(),
         config: config.into(),
+        fallback: Vec::new(),
+        canary: None,
     }
 }
 
@@ -75,7 +77,7 @@ async fn test_parse_graphql_routing_config() {
     let agent = router.parse_routing_config(&routing_config).unwrap();
     
     match agent {
-        AgentType::GraphQL { endpoint, query, variables, headers, timeout, operation_name } => {
+        AgentType::GraphQL { endpoint, query, variables, headers, timeout, operation_name, .. } => {
             assert_eq!(endpoint, "https://api.example.com/graphql");
             assert_eq!(query, Some("query { user { id name } }".to_string()));
             assert_eq!(variables, Some(json!({"userId": "123"})));
@@ -104,7 +106,7 @@ async fn test_parse_graphql_routing_config_minimal() {
     let agent = router.parse_routing_config(&routing_config).unwrap();
     
     match agent {
-        AgentType::GraphQL { endpoint, query, variables, headers, timeout, operation_name } => {
+        AgentType::GraphQL { endpoint, query, variables, headers, timeout, operation_name, .. } => {
             assert_eq!(endpoint, "https://api.example.com/graphql");
             assert_eq!(query, None);
             assert_eq!(variables, None);
@@ -127,6 +129,8 @@ async fn test_parse_graphql_routing_config_missing_endpoint() {
     let routing_config = RoutingConfig {
         r#type: "graphql".to_string(),
         config: config.into(),
+        fallback: Vec::new(),
+        canary: None,
     };
 
     let result = router.parse_routing_config(&routing_config);
@@ -156,6 +160,7 @@ async fn test_execute_graphql_agent_with_config_query() {
         headers: Some([("Authorization".to_string(), "Bearer token".to_string())].into()),
         timeout: Some(30),
         operation_name: Some("GetUser".to_string()),
+        subscription: None,
     };
 
     let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
@@ -189,6 +194,7 @@ async fn test_execute_graphql_agent_with_runtime_query() {
         headers: Some([("Content-Type".to_string(), "application/json".to_string())].into()),
         timeout: Some(45),
         operation_name: None,
+        subscription: None,
     };
 
     let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
@@ -223,6 +229,7 @@ async fn test_execute_graphql_agent_parameter_substitution() {
         headers: Some([("Authorization".to_string(), "Bearer {{api_token}}".to_string())].into()),
         timeout: Some(30),
         operation_name: Some("GetUser".to_string()),
+        subscription: None,
     };
 
     let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
@@ -273,6 +280,7 @@ async fn test_execute_graphql_agent_complex_variables() {
         headers: None,
         timeout: Some(60),
         operation_name: Some("CreatePost".to_string()),
+        subscription: None,
     };
 
     let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
@@ -307,6 +315,7 @@ async fn test_graphql_agent_timeout_configuration() {
         headers: None,
         timeout: Some(5), // Short timeout
         operation_name: None,
+        subscription: None,
     };
 
     let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
@@ -315,3 +324,100 @@ async fn test_graphql_agent_timeout_configuration() {
     // this would test timeout behavior
     assert!(result.success || !result.success); // Either outcome is valid for mock
 }
+
+/// Minimal graphql-transport-ws server that answers a subscription with `events` events, then completes
+async fn spawn_subscription_server(events: usize) -> String {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_hdr_async(stream, |_: &Request, mut response: Response| {
+            response.headers_mut().insert("Sec-WebSocket-Protocol", "graphql-transport-ws".parse().unwrap());
+            Ok(response)
+        }).await.unwrap();
+
+        while let Some(Ok(Message::Text(text))) = ws.next().await {
+            let message: Value = serde_json::from_str(&text).unwrap();
+            match message["type"].as_str().unwrap() {
+                "connection_init" => {
+                    ws.send(Message::Text(json!({"type": "connection_ack"}).to_string())).await.unwrap();
+                }
+                "subscribe" => {
+                    let channel = message["payload"]["variables"]["channel"].clone();
+                    for i in 0..events {
+                        let next = json!({"id": "1", "type": "next", "payload": {"data": {"messageAdded": {"n": i, "channel": channel}}}});
+                        if ws.send(Message::Text(next.to_string())).await.is_err() {
+                            return;
+                        }
+                    }
+                    let _ = ws.send(Message::Text(json!({"id": "1", "type": "complete"}).to_string())).await;
+                }
+                _ => {}
+            }
+        }
+    });
+    format!("http://{}/graphql", addr)
+}
+
+#[tokio::test]
+async fn test_graphql_subscription_until_complete() {
+    let router = DefaultAgentRouter::new();
+    let endpoint = spawn_subscription_server(2).await;
+    let routing_config = create_graphql_routing_config(
+        &endpoint,
+        Some("subscription OnMessage($channel: ID!) { messageAdded(channel: $channel) { n } }"),
+        None,
+        None,
+        None,
+        None,
+    );
+    let agent = router.parse_routing_config(&routing_config).unwrap();
+    let tool_call = create_test_tool_call("on_message", json!({"channel": "general"}));
+
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    let data = result.data.unwrap();
+    assert_eq!(data["end_reason"], "completed");
+    assert_eq!(data["event_count"], 2);
+    assert_eq!(data["events"][1]["data"]["messageAdded"], json!({"n": 1, "channel": "general"}));
+    assert_eq!(result.metadata.unwrap()["operation_type"], "subscription");
+}
+
+#[tokio::test]
+async fn test_graphql_subscription_event_limit_and_progress() {
+    use magictunnel::mcp::progress::ProgressReporter;
+
+    let router = DefaultAgentRouter::new();
+    let endpoint = spawn_subscription_server(100).await;
+    let mut config = create_graphql_routing_config(
+        &endpoint,
+        Some("subscription { messageAdded(channel: \"general\") { n } }"),
+        None,
+        None,
+        None,
+        None,
+    );
+    config.config["subscription"] = json!({"max_events": 3, "max_duration_secs": 10});
+    let agent = router.parse_routing_config(&config).unwrap();
+    let tool_call = create_test_tool_call("on_message", json!({}));
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let reporter = ProgressReporter::new(json!("sub-1"), sender);
+    let result = reporter.scope(router.execute_with_agent(&tool_call, &agent)).await.unwrap();
+
+    assert!(result.success, "{:?}", result.error);
+    let data = result.data.unwrap();
+    assert_eq!(data["end_reason"], "max_events");
+    assert_eq!(data["event_count"], 3);
+
+    let mut notifications = 0;
+    while let Ok(notification) = receiver.try_recv() {
+        assert_eq!(notification.method, "notifications/progress");
+        notifications += 1;
+    }
+    assert_eq!(notifications, 3);
+}
@@ -24,6 +24,8 @@ async fn test_grpc_agent_parsing() {
             },
             "request_body": "{\"user_id\": \"{{user_id}}\"}"
         }),
+        fallback: Vec::new(),
+        canary: None,
     };
 
     let agent = router.parse_routing_config(&routing_config).unwrap();
@@ -55,6 +57,8 @@ async fn test_grpc_agent_parsing_minimal() {
             "service": "MinimalService",
             "method": "SimpleCall"
         }),
+        fallback: Vec::new(),
+        canary: None,
     };
 
     let agent = router.parse_routing_config(&routing_config).unwrap();
@@ -83,6 +87,8 @@ async fn test_grpc_agent_parsing_missing_required_fields() {
             "service": "UserService",
             "method": "GetUser"
         }),
+        fallback: Vec::new(),
+        canary: None,
     };
     
     let result = router.parse_routing_config(&routing_config);
@@ -96,6 +102,8 @@ async fn test_grpc_agent_parsing_missing_required_fields() {
             "endpoint": "https://api.example.com:443",
             "method": "GetUser"
         }),
+        fallback: Vec::new(),
+        canary: None,
     };
     
     let result = router.parse_routing_config(&routing_config);
@@ -109,6 +117,8 @@ async fn test_grpc_agent_parsing_missing_required_fields() {
             "endpoint": "https://api.example.com:443",
             "service": "UserService"
         }),
+        fallback: Vec::new(),
+        canary: None,
     };
     
     let result = router.parse_routing_config(&routing_config);
@@ -22,7 +22,14 @@ mod grpc_tests {
             validation: ValidationConfig {
                 strict: false,
                 allow_unknown_fields: true,
+                output: Default::default(),
             },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: Default::default(),
         };
 
         let registry = Arc::new(RegistryService::new(config).await.expect("Failed to create registry"));
@@ -80,7 +87,14 @@ mod grpc_tests {
             validation: ValidationConfig {
                 strict: false,
                 allow_unknown_fields: true,
+                output: Default::default(),
             },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: Default::default(),
         };
 
         let registry = Arc::new(RegistryService::new(config).await.expect("Failed to create registry"));
@@ -0,0 +1,69 @@
+use magictunnel::mcp::types::ToolCall;
+use magictunnel::registry::har_generator::{HarCapabilityGenerator, HarGeneratorConfig};
+use magictunnel::registry::types::CapabilityFile;
+use magictunnel::routing::agent_router::{AgentRouter, DefaultAgentRouter};
+use serde_json::json;
+use wiremock::matchers::{body_string, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const HAR: &str = r#"{
+    "log": {
+        "version": "1.2",
+        "entries": [
+            {"request": {"method": "GET", "url": "https://api.example.com/users/42?expand=true&page=1"},
+             "response": {"content": {"mimeType": "application/json"}}},
+            {"request": {"method": "POST", "url": "https://api.example.com/users/7/notes",
+                         "postData": {"mimeType": "application/x-www-form-urlencoded", "text": "title=Hello&body=a+b"}},
+             "response": {"content": {"mimeType": "application/json"}}}
+        ]
+    }
+}"#;
+
+async fn generated_tools(server: &MockServer) -> CapabilityFile {
+    let generator = HarCapabilityGenerator::new(HarGeneratorConfig {
+        base_url: Some(server.uri()),
+        ..Default::default()
+    });
+    generator.generate_from_har(HAR).unwrap()
+}
+
+#[tokio::test]
+async fn test_generated_get_sends_path_and_query_parameters() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/users/a%20b"))
+        .and(query_param("page", "3"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let file = generated_tools(&server).await;
+    let tool = file.tools.iter().find(|t| t.name == "get_users_user_id").unwrap();
+    let call = ToolCall::new(tool.name.clone(), json!({"user_id": "a b", "page": 3}));
+    let result = DefaultAgentRouter::new().route(&call, tool).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+
+    // `expand` was not given, so it is left out of the query string
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests[0].url.query(), Some("page=3"));
+}
+
+#[tokio::test]
+async fn test_generated_form_post_sends_form_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/users/7/notes"))
+        .and(header("Content-Type", "application/x-www-form-urlencoded"))
+        .and(body_string("body=a+%26+b&title=Hi"))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let file = generated_tools(&server).await;
+    let tool = file.tools.iter().find(|t| t.name == "post_users_user_id_notes").unwrap();
+    let call = ToolCall::new(tool.name.clone(), json!({"user_id": 7, "title": "Hi", "body": "a & b"}));
+    let result = DefaultAgentRouter::new().route(&call, tool).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+}
@@ -0,0 +1,91 @@
+use magictunnel::mcp::types::ToolCall;
+use magictunnel::registry::RoutingConfig;
+use magictunnel::routing::agent_router::{AgentRouter, DefaultAgentRouter};
+use magictunnel::routing::kafka::KafkaReplyConfig;
+use magictunnel::routing::types::AgentType;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_kafka_agent_parsing() {
+    let routing_config = RoutingConfig::new("kafka".to_string(), json!({
+        "brokers": "kafka-1:9092, kafka-2:9092",
+        "topic": "orders.requests",
+        "partition": 2,
+        "key": "{{order_id}}",
+        "payload": {"order": "{{order_id}}"},
+        "headers": {"source": "magictunnel"},
+        "response_topic": "orders.replies",
+        "response_partition": 1,
+        "timeout": 15
+    }));
+
+    let router = DefaultAgentRouter::new();
+    match router.parse_routing_config(&routing_config).unwrap() {
+        AgentType::Kafka { brokers, topic, partition, key, payload, headers, reply, timeout } => {
+            assert_eq!(brokers, vec!["kafka-1:9092", "kafka-2:9092"]);
+            assert_eq!(topic, "orders.requests");
+            assert_eq!(partition, 2);
+            assert_eq!(key.as_deref(), Some("{{order_id}}"));
+            assert_eq!(payload, Some(json!({"order": "{{order_id}}"})));
+            assert_eq!(headers.get("source").map(String::as_str), Some("magictunnel"));
+            assert_eq!(reply, Some(KafkaReplyConfig { topic: "orders.replies".to_string(), partition: 1 }));
+            assert_eq!(timeout, Some(15));
+        }
+        _ => panic!("Expected Kafka agent type"),
+    }
+}
+
+#[tokio::test]
+async fn test_kafka_agent_fire_and_forget_parsing() {
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("kafka".to_string(), json!({
+        "brokers": ["localhost:9092"],
+        "topic": "audit"
+    }))).unwrap();
+
+    match agent {
+        AgentType::Kafka { partition, reply, headers, .. } => {
+            assert_eq!(partition, 0);
+            assert!(reply.is_none());
+            assert!(headers.is_empty());
+        }
+        _ => panic!("Expected Kafka agent type"),
+    }
+}
+
+#[tokio::test]
+async fn test_kafka_agent_parsing_errors() {
+    let router = DefaultAgentRouter::new();
+
+    let result = router.parse_routing_config(&RoutingConfig::new("kafka".to_string(), json!({"topic": "audit"})));
+    assert!(result.unwrap_err().to_string().contains("brokers"));
+
+    let result = router.parse_routing_config(&RoutingConfig::new("kafka".to_string(), json!({"brokers": ["localhost:9092"]})));
+    assert!(result.unwrap_err().to_string().contains("topic"));
+
+    let result = router.parse_routing_config(&RoutingConfig::new("kafka".to_string(), json!({
+        "brokers": ["localhost:9092"],
+        "topic": "audit",
+        "partition": 4294967296u64
+    })));
+    assert!(result.is_err());
+}
+
+#[cfg(feature = "kafka")]
+#[tokio::test]
+async fn test_kafka_agent_unreachable_broker() {
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("kafka".to_string(), json!({
+        "brokers": ["127.0.0.1:1"],
+        "topic": "audit",
+        "timeout": 5
+    }))).unwrap();
+    let tool_call = ToolCall {
+        name: "audit_event".to_string(),
+        arguments: json!({"event": "login"}),
+    };
+
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.metadata.unwrap()["execution_type"], "kafka");
+}
@@ -0,0 +1,99 @@
+use magictunnel::mcp::progress::ProgressReporter;
+use magictunnel::mcp::types::ToolCall;
+use magictunnel::registry::RoutingConfig;
+use magictunnel::routing::agent_router::{AgentRouter, DefaultAgentRouter};
+use magictunnel::routing::types::AgentType;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_kubernetes_job_agent_parsing() {
+    let routing_config = RoutingConfig::new("kubernetes_job".to_string(), json!({
+        "namespace": "tools",
+        "name_prefix": "report",
+        "pod_spec": {"containers": [{"name": "main", "image": "alpine:3", "args": ["{{month}}"]}]},
+        "ttl_seconds_after_finished": 600,
+        "timeout": 300
+    }));
+
+    let router = DefaultAgentRouter::new();
+    match router.parse_routing_config(&routing_config).unwrap() {
+        AgentType::KubernetesJob { timeout, max_output_bytes, config } => {
+            assert_eq!(timeout, Some(300));
+            assert_eq!(max_output_bytes, 1024 * 1024);
+            assert_eq!(config.namespace.as_deref(), Some("tools"));
+            assert_eq!(config.name_prefix, "report");
+            assert_eq!(config.backoff_limit, 0);
+            assert_eq!(config.ttl_seconds_after_finished, Some(600));
+            assert!(!config.keep_job);
+            assert_eq!(config.kubectl_binary, "kubectl");
+        }
+        _ => panic!("Expected Kubernetes Job agent type"),
+    }
+}
+
+#[tokio::test]
+async fn test_kubernetes_job_agent_parsing_errors() {
+    let router = DefaultAgentRouter::new();
+
+    assert!(router.parse_routing_config(&RoutingConfig::new("kubernetes_job".to_string(), json!({"namespace": "tools"}))).is_err());
+    let result = router.parse_routing_config(&RoutingConfig::new("kubernetes_job".to_string(), json!({"pod_spec": {"image": "alpine:3"}})));
+    assert!(result.unwrap_err().to_string().contains("containers"));
+}
+
+/// Fake kubectl that records the manifest and reports a completed Job
+#[cfg(unix)]
+fn fake_kubectl(dir: &std::path::Path) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join("kubectl");
+    let script = format!(r#"#!/bin/sh
+case "$*" in
+  *"create -f -"*) cat > "{dir}/manifest.json" ;;
+  *"get job"*) echo '{{"status":{{"conditions":[{{"type":"Complete","status":"True"}}]}}}}' ;;
+  *"get pods"*) echo '{{"items":[{{"metadata":{{"creationTimestamp":"2024-01-01T00:00:00Z"}},"status":{{"containerStatuses":[{{"state":{{"terminated":{{"exitCode":0}}}}}}]}}}}]}}' ;;
+  *"logs"*) echo "loading data"; echo "report written" ;;
+  *"delete"*) echo "$*" > "{dir}/deleted" ;;
+esac
+"#, dir = dir.display());
+    std::fs::write(&path, script).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path.display().to_string()
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_kubernetes_job_agent_runs_job() {
+    let dir = tempfile::tempdir().unwrap();
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("kubernetes_job".to_string(), json!({
+        "namespace": "tools",
+        "name_prefix": "report",
+        "pod_spec": {"containers": [{"name": "main", "image": "alpine:3", "args": ["--month", "{{month}}"]}]},
+        "kubectl_binary": fake_kubectl(dir.path())
+    }))).unwrap();
+    let tool_call = ToolCall {
+        name: "monthly_report".to_string(),
+        arguments: json!({"month": "2024-05"}),
+    };
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let reporter = ProgressReporter::new(json!("tok"), sender);
+    let result = reporter.scope(router.execute_with_agent(&tool_call, &agent)).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+
+    let data = result.data.unwrap();
+    assert_eq!(data["status"], "succeeded");
+    assert_eq!(data["exit_code"], 0);
+    assert_eq!(data["logs"], "loading data\nreport written\n");
+    let job = data["job"].as_str().unwrap();
+    assert!(job.starts_with("report-"));
+
+    let manifest: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.path().join("manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["metadata"]["name"], job);
+    assert_eq!(manifest["spec"]["template"]["spec"]["containers"][0]["args"], json!(["--month", "2024-05"]));
+    assert!(std::fs::read_to_string(dir.path().join("deleted")).unwrap().contains(&format!("job/{}", job)));
+
+    let progress = receiver.recv().await.unwrap();
+    assert_eq!(progress.method, "notifications/progress");
+    assert_eq!(progress.params.unwrap()["message"], "loading data");
+}
@@ -0,0 +1,118 @@
+use magictunnel::mcp::types::ToolCall;
+use magictunnel::registry::RoutingConfig;
+use magictunnel::routing::agent_router::{AgentRouter, DefaultAgentRouter};
+use magictunnel::routing::aws_lambda::LambdaInvocationType;
+use magictunnel::routing::types::AgentType;
+use serde_json::json;
+use wiremock::matchers::{body_json, header, header_exists, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn test_lambda_agent_parsing() {
+    let routing_config = RoutingConfig::new("lambda".to_string(), json!({
+        "function_name": "image-resizer",
+        "region": "eu-west-1",
+        "qualifier": "prod",
+        "invocation_type": "Event",
+        "payload": {"source": "{{url}}"},
+        "access_key_id": "${env:RESIZER_KEY_ID}",
+        "timeout": 120
+    }));
+
+    let router = DefaultAgentRouter::new();
+    match router.parse_routing_config(&routing_config).unwrap() {
+        AgentType::Lambda { function_name, qualifier, invocation_type, payload, endpoint, timeout, aws } => {
+            assert_eq!(function_name, "image-resizer");
+            assert_eq!(qualifier.as_deref(), Some("prod"));
+            assert_eq!(invocation_type, LambdaInvocationType::Event);
+            assert_eq!(payload, Some(json!({"source": "{{url}}"})));
+            assert!(endpoint.is_none());
+            assert_eq!(timeout, Some(120));
+            assert_eq!(aws.service, "lambda");
+            assert_eq!(aws.region, "eu-west-1");
+            assert_eq!(aws.access_key_id, "${env:RESIZER_KEY_ID}");
+            assert_eq!(aws.secret_access_key, "${env:AWS_SECRET_ACCESS_KEY}");
+        }
+        _ => panic!("Expected Lambda agent type"),
+    }
+}
+
+#[tokio::test]
+async fn test_lambda_agent_parsing_errors() {
+    let router = DefaultAgentRouter::new();
+
+    let result = router.parse_routing_config(&RoutingConfig::new("lambda".to_string(), json!({"region": "us-east-1"})));
+    assert!(result.unwrap_err().to_string().contains("function_name"));
+
+    let result = router.parse_routing_config(&RoutingConfig::new("lambda".to_string(), json!({
+        "function_name": "f",
+        "region": "us-east-1",
+        "invocation_type": "DryRun"
+    })));
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_lambda_agent_invocation() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/2015-03-31/functions/image-resizer/invocations"))
+        .and(query_param("Qualifier", "prod"))
+        .and(header("X-Amz-Invocation-Type", "RequestResponse"))
+        .and(header_exists("Authorization"))
+        .and(body_json(json!({"source": "https://example.com/cat.png", "width": 100})))
+        .respond_with(ResponseTemplate::new(200)
+            .insert_header("X-Amz-Executed-Version", "7")
+            .set_body_json(json!({"url": "https://example.com/cat-100.png"})))
+        .mount(&server)
+        .await;
+
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("lambda".to_string(), json!({
+        "function_name": "image-resizer",
+        "region": "us-east-1",
+        "qualifier": "prod",
+        "endpoint": server.uri(),
+        "access_key_id": "AKIDEXAMPLE",
+        "secret_access_key": "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+        "payload": {"source": "{{url}}", "width": "{{width}}"}
+    }))).unwrap();
+    let tool_call = ToolCall {
+        name: "resize_image".to_string(),
+        arguments: json!({"url": "https://example.com/cat.png", "width": 100}),
+    };
+
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data, Some(json!({"url": "https://example.com/cat-100.png"})));
+    assert_eq!(result.metadata.unwrap()["executed_version"], "7");
+}
+
+#[tokio::test]
+async fn test_lambda_agent_function_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/2015-03-31/functions/image-resizer/invocations"))
+        .respond_with(ResponseTemplate::new(200)
+            .insert_header("X-Amz-Function-Error", "Unhandled")
+            .set_body_json(json!({"errorType": "ValueError", "errorMessage": "width must be positive"})))
+        .mount(&server)
+        .await;
+
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("lambda".to_string(), json!({
+        "function_name": "image-resizer",
+        "region": "us-east-1",
+        "endpoint": server.uri(),
+        "access_key_id": "AKIDEXAMPLE",
+        "secret_access_key": "secret"
+    }))).unwrap();
+    let tool_call = ToolCall {
+        name: "resize_image".to_string(),
+        arguments: json!({"width": -1}),
+    };
+
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.error.as_deref(), Some("Lambda function error (Unhandled, ValueError): width must be positive"));
+}
@@ -0,0 +1,99 @@
+//! Tests for load balancing tool calls across endpoints
+
+use async_trait::async_trait;
+use magictunnel::error::{ProxyError, Result};
+use magictunnel::mcp::{Tool, ToolCall};
+use magictunnel::registry::{RoutingConfig, ToolDefinition};
+use magictunnel::routing::types::{AgentResult, AgentType};
+use magictunnel::routing::{AgentRouter, Router};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+/// Agent router that records the URL of each call and fails calls to `http://down`
+#[derive(Default)]
+struct RecordingAgentRouter {
+    urls: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl AgentRouter for RecordingAgentRouter {
+    fn parse_routing_config(&self, routing: &RoutingConfig) -> Result<AgentType> {
+        Ok(AgentType::Http {
+            method: "GET".to_string(),
+            url: routing.config["url"].as_str().unwrap_or_default().to_string(),
+            headers: None,
+            timeout: None,
+            aws_sigv4: None,
+            token_exchange: None,
+            params: Default::default(),
+        })
+    }
+
+    async fn execute_with_agent(&self, _tool_call: &ToolCall, agent: &AgentType) -> Result<AgentResult> {
+        let AgentType::Http { url, .. } = agent else {
+            return Err(ProxyError::routing("expected an HTTP agent".to_string()));
+        };
+        self.urls.lock().unwrap().push(url.clone());
+        if url == "http://down" {
+            return Err(ProxyError::routing("connection refused".to_string()));
+        }
+        Ok(AgentResult { success: true, data: Some(json!({"url": url})), error: None, metadata: None })
+    }
+}
+
+fn balanced_tool(load_balancing: serde_json::Value) -> ToolDefinition {
+    let tool = Tool::new("search".to_string(), "Search".to_string(), json!({"type": "object"})).unwrap();
+    let routing = RoutingConfig::new("http".to_string(), json!({"method": "GET", "load_balancing": load_balancing}));
+    ToolDefinition::new(tool, routing).unwrap()
+}
+
+fn call() -> ToolCall {
+    ToolCall { name: "search".to_string(), arguments: json!({}) }
+}
+
+#[tokio::test]
+async fn test_round_robin_across_endpoints() {
+    let agent = Arc::new(RecordingAgentRouter::default());
+    let router = Router::with_agent_router(agent.clone());
+    let tool = balanced_tool(json!({"endpoints": [{"url": "http://a"}, {"url": "http://b"}]}));
+
+    for expected in [0, 1, 0] {
+        let result = router.route(&call(), &tool).await.unwrap();
+        assert_eq!(result.metadata.unwrap()["endpoint_index"], expected);
+    }
+    assert_eq!(*agent.urls.lock().unwrap(), vec!["http://a", "http://b", "http://a"]);
+}
+
+#[tokio::test]
+async fn test_failing_endpoint_is_excluded() {
+    let agent = Arc::new(RecordingAgentRouter::default());
+    let router = Router::with_agent_router(agent.clone());
+    let tool = balanced_tool(json!({
+        "endpoints": [{"url": "http://down"}, {"url": "http://up"}],
+        "failure_threshold": 1,
+        "cooldown_secs": 60
+    }));
+
+    assert!(router.route(&call(), &tool).await.is_err());
+    assert!(router.load_balancer().is_excluded("search", 0));
+    for _ in 0..3 {
+        assert!(router.route(&call(), &tool).await.unwrap().success);
+    }
+    assert_eq!(*agent.urls.lock().unwrap(), vec!["http://down", "http://up", "http://up", "http://up"]);
+}
+
+#[test]
+fn test_endpoints_are_validated() {
+    let tool = Tool::new("search".to_string(), "Search".to_string(), json!({"type": "object"})).unwrap();
+    let missing_url = RoutingConfig::new("http".to_string(), json!({
+        "method": "GET",
+        "load_balancing": {"endpoints": [{"url": "http://a"}, {"timeout": 5}]}
+    }));
+    assert!(ToolDefinition::new(tool.clone(), missing_url).is_err());
+
+    let unknown_strategy = RoutingConfig::new("http".to_string(), json!({
+        "method": "GET",
+        "load_balancing": {"strategy": "random", "endpoints": [{"url": "http://a"}]}
+    }));
+    assert!(ToolDefinition::new(tool, unknown_strategy).is_err());
+}
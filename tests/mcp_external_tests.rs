@@ -33,6 +33,7 @@ mod tests {
             args: vec!["test".to_string()],
             env: None,
             cwd: None,
+            ssh: None,
         };
 
         let client_config = create_test_client_config();
@@ -77,6 +78,9 @@ mod tests {
                 network_mode: Some("bridge".to_string()),
                 run_args: vec!["--rm".to_string(), "-i".to_string()],
             }),
+            circuit_breaker: Default::default(),
+            startup_concurrency: 4,
+            injection_scan: Default::default(),
         };
 
         let client_config = create_test_client_config();
@@ -98,6 +102,7 @@ mod tests {
             args: vec!["test".to_string()],
             env: None,
             cwd: None,
+            ssh: None,
         };
         assert!(utils::validate_server_config(&valid_config).is_ok());
 
@@ -107,6 +112,7 @@ mod tests {
             args: vec!["test".to_string()],
             env: None,
             cwd: None,
+            ssh: None,
         };
         assert!(utils::validate_server_config(&invalid_config).is_err());
 
@@ -116,6 +122,7 @@ mod tests {
             args: vec![],
             env: None,
             cwd: None,
+            ssh: None,
         };
         assert!(utils::validate_server_config(&invalid_config).is_err());
     }
@@ -138,6 +145,7 @@ mod tests {
                 env
             }),
             cwd: Some("${TEST_PATH}".to_string()),
+            ssh: None,
         };
 
         utils::expand_config_env_vars(&mut config);
@@ -174,6 +182,7 @@ mod tests {
             args: vec!["Hello, External MCP!".to_string()],
             env: None,
             cwd: None,
+            ssh: None,
         };
 
         let client_config = create_test_client_config();
@@ -209,6 +218,9 @@ mod tests {
             capabilities_output_dir: "./test-capabilities".to_string(),
             refresh_interval_minutes: 1, // Short interval for testing
             containers: None,
+            circuit_breaker: Default::default(),
+            startup_concurrency: 4,
+            injection_scan: Default::default(),
         };
 
         let client_config = create_test_client_config();
@@ -248,6 +260,9 @@ mod tests {
             capabilities_output_dir: "./test-capabilities".to_string(),
             refresh_interval_minutes: 60,
             containers: None,
+            circuit_breaker: Default::default(),
+            startup_concurrency: 4,
+            injection_scan: Default::default(),
         };
 
         let client_config = create_test_client_config();
@@ -272,6 +287,9 @@ mod tests {
             capabilities_output_dir: "./test-capabilities".to_string(),
             refresh_interval_minutes: 60,
             containers: None,
+            circuit_breaker: Default::default(),
+            startup_concurrency: 4,
+            injection_scan: Default::default(),
         };
 
         let client_config = create_test_client_config();
@@ -285,4 +303,42 @@ mod tests {
         let _ = tokio::fs::remove_file("./test-restart-config.yaml").await;
         let _ = tokio::fs::remove_dir_all("./test-capabilities").await;
     }
+
+    /// Test that parallel startup reports readiness for every configured server
+    #[tokio::test]
+    async fn test_parallel_startup_readiness_report() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("servers.yaml");
+        tokio::fs::write(&config_path, r#"
+mcpServers:
+  missing-a:
+    command: "/nonexistent/mcp-server-a"
+    args: []
+  missing-b:
+    command: "/nonexistent/mcp-server-b"
+    args: []
+"#).await.unwrap();
+
+        let config = ExternalMcpConfig {
+            enabled: true,
+            config_file: config_path.to_string_lossy().to_string(),
+            capabilities_output_dir: temp_dir.path().join("capabilities").to_string_lossy().to_string(),
+            refresh_interval_minutes: 0,
+            containers: None,
+            circuit_breaker: Default::default(),
+            startup_concurrency: 2,
+            injection_scan: Default::default(),
+        };
+
+        let manager = ExternalMcpManager::new(config, create_test_client_config());
+
+        // No server can start, so startup fails, but each server is reported individually
+        assert!(manager.start().await.is_err());
+
+        let report = manager.get_startup_report().await;
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].server_name, "missing-a");
+        assert_eq!(report[1].server_name, "missing-b");
+        assert!(report.iter().all(|entry| !entry.ready && entry.error.is_some()));
+    }
 }
@@ -355,4 +355,85 @@ mod tests {
         assert_eq!(notification.method, "notifications/resources/updated");
         assert!(notification.params.is_some());
     }
+
+    #[tokio::test]
+    async fn test_notification_jsonrpc_format() {
+        let message = McpNotification::resource_updated("file:///test.txt".to_string()).to_jsonrpc();
+        assert_eq!(message["jsonrpc"], "2.0");
+        assert_eq!(message["method"], "notifications/resources/updated");
+        assert_eq!(message["params"]["uri"], "file:///test.txt");
+        assert!(message.get("id").is_none());
+
+        let message = McpNotification::tools_list_changed().to_jsonrpc();
+        assert!(message.get("params").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resource_subscription_via_mcp_request() {
+        use magictunnel::config::{RegistryConfig, ValidationConfig};
+        use magictunnel::mcp::server::McpServer;
+        use magictunnel::registry::RegistryService;
+        use magictunnel::mcp::upstream_notifications::remap_resource_uri;
+
+        let registry = RegistryService::new(RegistryConfig {
+            r#type: "file".to_string(),
+            paths: vec![],
+            hot_reload: false,
+            validation: ValidationConfig { strict: false, allow_unknown_fields: true },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: Default::default(),
+        }).await.unwrap();
+        let server = McpServer::with_registry(std::sync::Arc::new(registry));
+
+        let uri = remap_resource_uri("filesystem", "file:///tmp/notes.txt");
+        let request: McpRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "resources/subscribe",
+            "params": {"uri": uri}
+        })).unwrap();
+        let response = server.handle_mcp_request(request).await.unwrap().unwrap();
+        assert!(response.contains("\"result\""));
+
+        let mut receiver = server.notification_manager().subscribe();
+        server.notification_manager().notify_resource_updated(uri.clone()).unwrap();
+        let notification = timeout(Duration::from_millis(100), receiver.recv()).await
+            .expect("Should receive notification")
+            .expect("Should not have error");
+        assert_eq!(notification.params.unwrap()["uri"], uri);
+
+        let request: McpRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "resources/subscribe",
+            "params": {}
+        })).unwrap();
+        let response = server.handle_mcp_request(request).await.unwrap().unwrap();
+        assert!(response.contains("uri"));
+        assert!(response.contains("\"error\""));
+    }
+
+    #[tokio::test]
+    async fn test_resource_updates_reach_only_subscribed_sessions() {
+        let manager = McpNotificationManager::new();
+        let uri = "external-mcp://filesystem/file:///tmp/notes.txt".to_string();
+        manager.subscribe_session_to_resource("alice", uri.clone()).unwrap();
+        manager.subscribe_session_to_resource("bob", uri.clone()).unwrap();
+
+        let updated = McpNotification::resource_updated(uri.clone());
+        assert!(manager.is_for_session("alice", &updated));
+        assert!(!manager.is_for_session("carol", &updated));
+        assert!(manager.is_for_session("carol", &McpNotification::resources_list_changed()));
+
+        // The resource stays subscribed until its last session lets go of it
+        manager.unsubscribe_session_from_resource("alice", &uri).unwrap();
+        assert!(!manager.is_for_session("alice", &updated));
+        assert_eq!(manager.get_resource_subscriptions().unwrap(), vec![uri.clone()]);
+        manager.forget_session("bob");
+        assert!(manager.get_resource_subscriptions().unwrap().is_empty());
+    }
 }
@@ -1,7 +1,7 @@
 //! Unit tests for MCP server components and message handling
 
 use magictunnel::mcp::server::McpServer;
-use magictunnel::config::{RegistryConfig, ValidationConfig};
+use magictunnel::config::{RegistryConfig, ToolsetDefinition, ValidationConfig};
 use serde_json::{json, Value};
 
 #[tokio::test]
@@ -146,8 +146,15 @@ async fn test_mcp_server_creation() {
         validation: ValidationConfig {
             strict: false,
             allow_unknown_fields: true,
+            output: Default::default(),
         },
         hot_reload: false,
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
 
     let server = McpServer::new(registry_config).await;
@@ -348,8 +355,15 @@ async fn test_tool_execution_routing() {
         validation: ValidationConfig {
             strict: false,
             allow_unknown_fields: true,
+            output: Default::default(),
         },
         hot_reload: false,
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
 
     // Create MCP server
@@ -371,3 +385,332 @@ async fn test_tool_execution_routing() {
     assert_eq!(tool_call.arguments["param1"], "value1");
     assert_eq!(tool_call.arguments["param2"], 42);
 }
+
+#[tokio::test]
+async fn test_tools_list_tag_filtering_and_toolsets() {
+    let dir = tempfile::tempdir().unwrap();
+    let tool_yaml = |name: &str, tags: &str| format!(
+        "  - name: {}\n    description: {} tool\n    tags: [{}]\n    inputSchema: {{type: object}}\n    routing: {{type: http, config: {{url: 'http://localhost', method: GET}}}}\n",
+        name, name, tags
+    );
+    std::fs::write(
+        dir.path().join("tools.yaml"),
+        format!(
+            "tools:\n{}{}{}",
+            tool_yaml("git_log", "git, read"),
+            tool_yaml("git_push", "git, destructive"),
+            tool_yaml("http_get", "http, read")
+        ),
+    ).unwrap();
+
+    let registry_config = RegistryConfig {
+        r#type: "file".to_string(),
+        paths: vec![dir.path().to_string_lossy().to_string()],
+        validation: ValidationConfig {
+            strict: false,
+            allow_unknown_fields: true,
+            output: Default::default(),
+        },
+        hot_reload: false,
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: [("safe_git".to_string(), "git and not destructive".into())].into_iter().collect(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
+    };
+    let server = McpServer::new(registry_config).await.unwrap();
+
+    let list = |params: Value| serde_json::from_value::<magictunnel::mcp::types::McpRequest>(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/list",
+        "params": params
+    })).unwrap();
+    let tool_names = |response: Option<String>| -> Vec<String> {
+        let response: Value = serde_json::from_str(&response.unwrap()).unwrap();
+        let mut names: Vec<String> = response["result"]["tools"].as_array().unwrap().iter()
+            .map(|tool| tool["name"].as_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    };
+
+    let response = server.handle_mcp_request(list(json!({"tags": ["read"]}))).await.unwrap();
+    assert_eq!(tool_names(response), vec!["git_log", "http_get"]);
+
+    let response = server.handle_mcp_request(list(json!({"toolset": "safe_git"}))).await.unwrap();
+    assert_eq!(tool_names(response), vec!["git_log"]);
+
+    // A bound toolset can be narrowed but not widened by request params
+    let response = server.handle_mcp_request_with_toolset(list(json!({"tags": ["http"]})), Some("safe_git")).await.unwrap();
+    assert!(tool_names(response).is_empty());
+    assert!(!server.is_tool_in_toolset("git_push", Some("safe_git")));
+    assert!(server.is_tool_in_toolset("git_log", Some("safe_git")));
+}
+
+#[tokio::test]
+async fn test_api_key_scopes() {
+    use magictunnel::registry::toolset::ToolScope;
+
+    let dir = tempfile::tempdir().unwrap();
+    let tool_yaml = |name: &str| format!(
+        "  - name: {}\n    description: {} tool\n    inputSchema: {{type: object}}\n    routing: {{type: http, config: {{url: 'http://localhost', method: GET}}}}\n",
+        name, name
+    );
+    std::fs::write(
+        dir.path().join("github.yaml"),
+        format!("tools:\n{}{}", tool_yaml("github_list_issues"), tool_yaml("github_merge")),
+    ).unwrap();
+    std::fs::write(
+        dir.path().join("invoices.yaml"),
+        format!("metadata:\n  name: billing\ntools:\n{}{}", tool_yaml("create_invoice"), tool_yaml("refund")),
+    ).unwrap();
+
+    let registry_config = RegistryConfig {
+        r#type: "file".to_string(),
+        paths: vec![dir.path().to_string_lossy().to_string()],
+        validation: ValidationConfig {
+            strict: false,
+            allow_unknown_fields: true,
+            output: Default::default(),
+        },
+        hot_reload: false,
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
+    };
+    let server = McpServer::new(registry_config).await.unwrap();
+
+    let request = |method: &str, params: Value| serde_json::from_value::<magictunnel::mcp::types::McpRequest>(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params
+    })).unwrap();
+    let scope = ToolScope {
+        patterns: vec!["github_list_*".to_string(), "capability:billing".to_string()],
+        ..ToolScope::default()
+    };
+
+    // tools/list only shows the tools matching a scope pattern
+    let response = server.handle_mcp_request_with_scope(request("tools/list", json!({})), &scope).await.unwrap();
+    let response: Value = serde_json::from_str(&response.unwrap()).unwrap();
+    let mut names: Vec<&str> = response["result"]["tools"].as_array().unwrap().iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["create_invoice", "github_list_issues", "refund"]);
+
+    // tools/call refuses the rest
+    assert!(server.is_tool_in_scope("refund", &scope));
+    assert!(!server.is_tool_in_scope("github_merge", &scope));
+    let response = server.handle_mcp_request_with_scope(request("tools/call", json!({"name": "github_merge", "arguments": {}})), &scope).await.unwrap();
+    let response: Value = serde_json::from_str(&response.unwrap()).unwrap();
+    assert!(response["error"]["message"].as_str().unwrap().contains("not within the scopes"));
+}
+
+#[tokio::test]
+async fn test_session_toolset_activation() {
+    let dir = tempfile::tempdir().unwrap();
+    let tool_yaml = |name: &str, tags: &str| format!(
+        "  - name: {}\n    description: {} tool\n    tags: [{}]\n    inputSchema: {{type: object}}\n    routing: {{type: http, config: {{url: 'http://localhost', method: GET}}}}\n",
+        name, name, tags
+    );
+    std::fs::write(
+        dir.path().join("tools.yaml"),
+        format!(
+            "tools:\n{}{}{}",
+            tool_yaml("get_pull_request", "github"),
+            tool_yaml("run_linter", "lint"),
+            tool_yaml("deploy", "ops")
+        ),
+    ).unwrap();
+
+    let review = ToolsetDefinition::Bundle {
+        description: Some("Code review".to_string()),
+        tools: vec!["get_pull_request".to_string()],
+        tags: Some("lint".to_string()),
+    };
+    let registry_config = RegistryConfig {
+        r#type: "file".to_string(),
+        paths: vec![dir.path().to_string_lossy().to_string()],
+        validation: ValidationConfig {
+            strict: false,
+            allow_unknown_fields: true,
+            output: Default::default(),
+        },
+        hot_reload: false,
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: [
+            ("review".to_string(), review),
+            ("ops".to_string(), "ops".into()),
+        ].into_iter().collect(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
+    };
+    let server = McpServer::new(registry_config).await.unwrap();
+    let session_id = server.session_manager().create_session().unwrap();
+
+    let tool_names = |response: Value| -> Vec<String> {
+        let mut names: Vec<String> = response["result"]["tools"].as_array().unwrap().iter()
+            .map(|tool| tool["name"].as_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    };
+
+    assert_eq!(tool_names(session_request(&server, &session_id, "tools/list", json!({})).await).len(), 3);
+
+    let response = session_request(&server, &session_id, "toolsets/activate", json!({"toolset": "review"})).await;
+    assert_eq!(response["result"]["active"], json!(["review"]));
+    assert_eq!(tool_names(session_request(&server, &session_id, "tools/list", json!({})).await), vec!["get_pull_request", "run_linter"]);
+    let response = session_request(&server, &session_id, "tools/call", json!({"name": "deploy", "arguments": {}})).await;
+    assert!(response["error"]["message"].as_str().unwrap().contains("not available"));
+
+    session_request(&server, &session_id, "toolsets/activate", json!({"toolsets": ["ops"]})).await;
+    assert_eq!(tool_names(session_request(&server, &session_id, "tools/list", json!({})).await).len(), 3);
+
+    let listed = session_request(&server, &session_id, "toolsets/list", json!({})).await;
+    let review = listed["result"]["toolsets"].as_array().unwrap().iter()
+        .find(|toolset| toolset["name"] == "review").unwrap().clone();
+    assert_eq!(review["toolCount"], 2);
+    assert_eq!(review["active"], true);
+
+    session_request(&server, &session_id, "toolsets/deactivate", json!({"toolset": "review"})).await;
+    assert_eq!(tool_names(session_request(&server, &session_id, "tools/list", json!({})).await), vec!["deploy"]);
+
+    let response = session_request(&server, &session_id, "toolsets/activate", json!({"toolset": "missing"})).await;
+    assert!(response["error"].is_object());
+
+    // Stateless callers can list toolsets but not switch them
+    let response = server.handle_mcp_request(mcp_request("toolsets/activate", json!({"toolset": "ops"}))).await.unwrap();
+    assert!(response.unwrap().contains("within a session"));
+}
+
+fn mcp_request(method: &str, params: Value) -> magictunnel::mcp::types::McpRequest {
+    serde_json::from_value(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params
+    })).unwrap()
+}
+
+async fn session_request(server: &McpServer, session_id: &str, method: &str, params: Value) -> Value {
+    let response = server.handle_session_request(mcp_request(method, params), session_id).await.unwrap();
+    serde_json::from_str(&response.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_tools_call_batch() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("tools.yaml"),
+        "tools:\n  - name: say_hello\n    description: say hello\n    tags: [greeting]\n    inputSchema: {type: object}\n    routing: {type: subprocess, config: {command: echo, args: [hello]}}\n",
+    ).unwrap();
+
+    let registry_config = RegistryConfig {
+        r#type: "file".to_string(),
+        paths: vec![dir.path().to_string_lossy().to_string()],
+        validation: ValidationConfig {
+            strict: false,
+            allow_unknown_fields: true,
+            output: Default::default(),
+        },
+        hot_reload: false,
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: [("other".to_string(), "other".into())].into_iter().collect(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
+    };
+    let server = McpServer::new(registry_config).await.unwrap();
+
+    let batch = json!({
+        "calls": [
+            {"name": "say_hello", "arguments": {}},
+            {"arguments": {}},
+            {"name": "say_hello", "arguments": {}}
+        ],
+        "maxConcurrency": 2
+    });
+    let response = server.handle_mcp_request(mcp_request("tools/call_batch", batch.clone())).await.unwrap();
+    let response: Value = serde_json::from_str(&response.unwrap()).unwrap();
+    let results = response["result"]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0]["index"], 0);
+    assert!(results[0]["result"].is_object());
+    assert_eq!(results[1]["error"]["code"], -32602);
+    assert!(results[2]["result"].is_object());
+
+    // Calls outside the caller's toolset fail one by one
+    let response = server.handle_mcp_request_with_toolset(mcp_request("tools/call_batch", batch), Some("other")).await.unwrap();
+    let response: Value = serde_json::from_str(&response.unwrap()).unwrap();
+    assert!(response["result"]["results"][0]["error"]["message"].as_str().unwrap().contains("not available"));
+
+    let response = server.handle_mcp_request(mcp_request("tools/call_batch", json!({"calls": []}))).await.unwrap();
+    assert!(response.unwrap().contains("no calls"));
+}
+
+#[tokio::test]
+async fn test_background_tool_runs_as_job() {
+    use magictunnel::mcp::types::ToolCall;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("tools.yaml"),
+        "tools:\n  - name: build\n    description: build\n    background: true\n    inputSchema: {type: object}\n    routing: {type: subprocess, config: {command: echo, args: [built]}}\n  - name: deploy\n    description: deploy\n    background: true\n    inputSchema: {type: object}\n    routing: {type: subprocess, config: {command: sleep, args: ['30']}}\n",
+    ).unwrap();
+
+    let registry_config = RegistryConfig {
+        r#type: "file".to_string(),
+        paths: vec![dir.path().to_string_lossy().to_string()],
+        validation: ValidationConfig {
+            strict: false,
+            allow_unknown_fields: true,
+            output: Default::default(),
+        },
+        hot_reload: false,
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
+    };
+    let server = McpServer::new(registry_config).await.unwrap();
+
+    let tools = server.list_tools().await.unwrap();
+    for name in ["job_status", "job_result", "job_cancel"] {
+        assert!(tools.iter().any(|tool| tool.name == name));
+    }
+
+    let started = server.call_tool(ToolCall::new("build".to_string(), json!({}))).await.unwrap();
+    let job_id = started.data.unwrap()["job_id"].as_str().unwrap().to_string();
+    let mut result = None;
+    for _ in 0..100 {
+        let status = server.call_tool(ToolCall::new("job_status".to_string(), json!({"job_id": job_id}))).await.unwrap();
+        if status.data.unwrap()["state"] == "completed" {
+            result = Some(server.call_tool(ToolCall::new("job_result".to_string(), json!({"job_id": job_id}))).await.unwrap());
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let result = result.expect("job never completed");
+    assert!(!result.is_error);
+    assert!(result.data.unwrap().to_string().contains("built"));
+
+    let started = server.call_tool(ToolCall::new("deploy".to_string(), json!({}))).await.unwrap();
+    let job_id = started.data.unwrap()["job_id"].as_str().unwrap().to_string();
+    let pending = server.call_tool(ToolCall::new("job_result".to_string(), json!({"job_id": job_id}))).await.unwrap();
+    assert_eq!(pending.metadata.unwrap()["error_category"], "job_not_finished");
+    let cancelled = server.call_tool(ToolCall::new("job_cancel".to_string(), json!({"job_id": job_id}))).await.unwrap();
+    assert_eq!(cancelled.data.unwrap()["state"], "cancelled");
+}
@@ -3,7 +3,7 @@
 //! Comprehensive tests for MCP session lifecycle management, message validation,
 //! and protocol version negotiation functionality.
 
-use magictunnel::mcp::session::{McpSessionManager, SessionConfig};
+use magictunnel::mcp::session::{McpSessionManager, SessionConfig, SessionExpiry, SessionsConfig};
 use magictunnel::mcp::validation::{McpMessageValidator, ValidationConfig};
 use magictunnel::mcp::types::McpRequest;
 use serde_json::{json, Value};
@@ -87,6 +87,42 @@ mod session_management_tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_session_expiry() {
+        let sessions: SessionsConfig = serde_yaml::from_str(
+            "idle_timeout_seconds: 3600\nauth_methods:\n  guest: { idle_timeout_seconds: 1 }\n  api_key: { max_lifetime_seconds: 1 }\n"
+        ).expect("Should parse sessions config");
+        sessions.validate().expect("Sessions config should be valid");
+        let manager = McpSessionManager::with_config(SessionConfig::from(&sessions));
+
+        let plain = manager.create_session().expect("Should create session");
+        let guest = manager.create_authenticated_session(Some("guest")).expect("Should create guest session");
+        let keyed = manager.create_authenticated_session(Some("api_key")).expect("Should create API key session");
+        let connected = manager.create_authenticated_session(Some("guest")).expect("Should create guest session");
+        manager.mark_connected(&connected).expect("Should mark session connected");
+        assert!(manager.session_expiry(&guest).is_none());
+
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        // Activity keeps a session alive past its idle timeout, but not past its lifetime
+        manager.update_activity(&keyed).expect("Should update activity");
+
+        assert_eq!(manager.session_expiry(&plain), None);
+        assert_eq!(manager.session_expiry(&guest), Some(SessionExpiry::Idle));
+        assert_eq!(manager.session_expiry(&keyed), Some(SessionExpiry::Lifetime));
+        assert_eq!(SessionExpiry::Lifetime.reason(), "max_lifetime");
+
+        // The connection owning a session ends it itself, telling its client why
+        assert_eq!(manager.session_expiry(&connected), Some(SessionExpiry::Idle));
+        assert_eq!(manager.cleanup_expired_sessions(), 2);
+        assert!(manager.get_session(&connected).is_some());
+        assert!(manager.get_session(&plain).is_some());
+        assert!(manager.get_session(&guest).is_none());
+        assert!(manager.get_session(&keyed).is_none());
+
+        let invalid: SessionsConfig = serde_yaml::from_str("auth_methods:\n  sso: { idle_timeout_seconds: 60 }\n").unwrap();
+        assert!(invalid.validate().is_err());
+    }
+
     #[test]
     fn test_protocol_version_negotiation() {
         let manager = McpSessionManager::new();
@@ -3,7 +3,7 @@
 use magictunnel::mcp::ToolCall;
 use magictunnel::registry::{RoutingConfig, ToolDefinition};
 use magictunnel::routing::{
-    AgentRouter, DefaultAgentRouter, EnhancedRouterBuilder,
+    AgentRouter, CircuitBreakerMiddleware, DefaultAgentRouter, EnhancedRouterBuilder,
     LoggingMiddleware, MetricsMiddleware, MiddlewareChain, MiddlewareContext, RouterMiddleware
 };
 use serde_json::json;
@@ -41,9 +41,19 @@ fn create_test_tool_definition() -> ToolDefinition {
                 "args": ["{{param1}}", "{{param2}}"],
                 "timeout": 10
             }),
+            fallback: Vec::new(),
+            canary: None,
         },
         hidden: false, // Test tools are visible by default
         enabled: true, // Test tools are enabled by default
+        deprecated: false,
+        deprecation_reason: None,
+        sunset_date: None,
+        tags: Vec::new(),
+        output_schema: None,
+        cache: None,
+        background: false,
+        cost: None,
     }
 }
 
@@ -78,6 +88,7 @@ async fn test_middleware_context_creation() {
         args: vec!["test".to_string()],
         timeout: Some(10),
         env: None,
+        optional_args: Vec::new(),
     };
 
     let context = MiddlewareContext::new(tool_call.clone(), agent_type);
@@ -343,3 +354,265 @@ async fn test_middleware_performance_impact() {
     // This is more of a performance observation than a strict test
     assert!(enhanced_duration < plain_duration * 5); // Allow up to 5x overhead for test environment
 }
+
+/// Agent router whose calls fail while `failing` is set
+struct SwitchableAgentRouter {
+    failing: std::sync::atomic::AtomicBool,
+    calls: std::sync::atomic::AtomicU32,
+}
+
+#[async_trait::async_trait]
+impl AgentRouter for SwitchableAgentRouter {
+    fn parse_routing_config(&self, routing: &RoutingConfig) -> magictunnel::error::Result<magictunnel::routing::types::AgentType> {
+        DefaultAgentRouter::new().parse_routing_config(routing)
+    }
+
+    async fn execute_with_agent(
+        &self,
+        _tool_call: &ToolCall,
+        _agent: &magictunnel::routing::types::AgentType,
+    ) -> magictunnel::error::Result<magictunnel::routing::types::AgentResult> {
+        use std::sync::atomic::Ordering;
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let success = !self.failing.load(Ordering::SeqCst);
+        Ok(magictunnel::routing::types::AgentResult {
+            success,
+            data: None,
+            error: (!success).then(|| "backend unavailable".to_string()),
+            metadata: None,
+        })
+    }
+}
+
+fn circuit_breaker_config(open_duration_secs: u64) -> magictunnel::config::CircuitBreakerConfig {
+    magictunnel::config::CircuitBreakerConfig {
+        failure_threshold: 2,
+        open_duration_secs,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_rejects_failing_tool() {
+    use std::sync::atomic::Ordering;
+
+    let agents = Arc::new(SwitchableAgentRouter { failing: true.into(), calls: 0.into() });
+    let breaker = Arc::new(CircuitBreakerMiddleware::with_config(circuit_breaker_config(60)));
+    let router = EnhancedRouterBuilder::new()
+        .with_agent_router(agents.clone())
+        .add_middleware(breaker.clone())
+        .build();
+
+    let tool_call = create_test_tool_call();
+    let tool_def = create_test_tool_definition();
+    for _ in 0..2 {
+        assert!(!router.route(&tool_call, &tool_def).await.unwrap().success);
+    }
+
+    let error = router.route(&tool_call, &tool_def).await.unwrap_err().to_string();
+    assert!(error.contains("temporarily disabled"), "{}", error);
+    assert!(error.contains("retry in 60 seconds"), "{}", error);
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 2);
+    assert_eq!(breaker.status("test_tool").unwrap().times_opened, 1);
+
+    // Other tools have their own breaker
+    let mut other_call = create_test_tool_call();
+    other_call.name = "other_tool".to_string();
+    assert!(router.route(&other_call, &tool_def).await.is_ok());
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_half_open_probe_recovers() {
+    use magictunnel::mcp::CircuitState;
+    use std::sync::atomic::Ordering;
+
+    let agents = Arc::new(SwitchableAgentRouter { failing: true.into(), calls: 0.into() });
+    let breaker = Arc::new(
+        CircuitBreakerMiddleware::with_config(circuit_breaker_config(60))
+            .with_tool_config("test_tool", circuit_breaker_config(0)),
+    );
+    let router = EnhancedRouterBuilder::new()
+        .with_agent_router(agents.clone())
+        .add_middleware(breaker.clone())
+        .build();
+
+    let tool_call = create_test_tool_call();
+    let tool_def = create_test_tool_definition();
+    for _ in 0..2 {
+        let _ = router.route(&tool_call, &tool_def).await;
+    }
+    assert_eq!(breaker.status("test_tool").unwrap().state, CircuitState::Open);
+
+    // The cool-down has expired, so the next call is a probe
+    agents.failing.store(false, Ordering::SeqCst);
+    assert!(router.route(&tool_call, &tool_def).await.unwrap().success);
+    assert_eq!(breaker.status("test_tool").unwrap().state, CircuitState::Closed);
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 3);
+}
+
+/// Middleware rejecting every call while `rejecting` is set
+struct RejectingMiddleware {
+    rejecting: std::sync::atomic::AtomicBool,
+}
+
+#[async_trait::async_trait]
+impl RouterMiddleware for RejectingMiddleware {
+    fn priority(&self) -> i32 {
+        10
+    }
+
+    async fn before_execution(&self, _context: &MiddlewareContext) -> magictunnel::error::Result<()> {
+        if self.rejecting.load(std::sync::atomic::Ordering::SeqCst) {
+            return Err(magictunnel::error::ProxyError::auth("rejected"));
+        }
+        Ok(())
+    }
+
+    async fn after_execution(&self, _context: &MiddlewareContext, _result: &magictunnel::routing::types::AgentResult) -> magictunnel::error::Result<()> {
+        Ok(())
+    }
+
+    async fn on_error(&self, _context: &MiddlewareContext, _error: &magictunnel::error::ProxyError) -> magictunnel::error::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_probe_rejected_by_later_middleware_is_released() {
+    use magictunnel::mcp::CircuitState;
+    use std::sync::atomic::Ordering;
+
+    let agents = Arc::new(SwitchableAgentRouter { failing: true.into(), calls: 0.into() });
+    let breaker = Arc::new(CircuitBreakerMiddleware::with_config(circuit_breaker_config(0)));
+    let rejecting = Arc::new(RejectingMiddleware { rejecting: false.into() });
+    let router = EnhancedRouterBuilder::new()
+        .with_agent_router(agents.clone())
+        .add_middleware(breaker.clone())
+        .add_middleware(rejecting.clone())
+        .build();
+
+    let tool_call = create_test_tool_call();
+    let tool_def = create_test_tool_definition();
+    for _ in 0..2 {
+        let _ = router.route(&tool_call, &tool_def).await;
+    }
+    assert_eq!(breaker.status("test_tool").unwrap().state, CircuitState::Open);
+
+    // The breaker lets a probe through, but the next middleware rejects it
+    rejecting.rejecting.store(true, Ordering::SeqCst);
+    assert!(router.route(&tool_call, &tool_def).await.unwrap_err().to_string().contains("rejected"));
+    assert_eq!(breaker.status("test_tool").unwrap().state, CircuitState::HalfOpen);
+
+    // Its probe slot was given back, so another probe can still close the circuit
+    rejecting.rejecting.store(false, Ordering::SeqCst);
+    agents.failing.store(false, Ordering::SeqCst);
+    assert!(router.route(&tool_call, &tool_def).await.unwrap().success);
+    assert_eq!(breaker.status("test_tool").unwrap().state, CircuitState::Closed);
+}
+
+#[tokio::test]
+async fn test_router_installs_configured_circuit_breakers() {
+    use magictunnel::routing::{Router, ToolCircuitBreakerConfig};
+    use std::sync::atomic::Ordering;
+
+    let config: ToolCircuitBreakerConfig = serde_yaml::from_str(
+        "open_duration_secs: 60\ntools:\n  test_tool:\n    failure_threshold: 2\n    open_duration_secs: 60\n",
+    ).unwrap();
+    config.validate().unwrap();
+    assert_eq!(config.defaults.failure_threshold, 5);
+
+    let agents = Arc::new(SwitchableAgentRouter { failing: true.into(), calls: 0.into() });
+    let router = Router::with_agent_router(agents.clone());
+    router.configure_middleware(
+        MiddlewareChain::new().add_middleware(Arc::new(CircuitBreakerMiddleware::from_config(&config))),
+    );
+
+    let tool_call = create_test_tool_call();
+    let tool_def = create_test_tool_definition();
+    for _ in 0..2 {
+        assert!(!router.route(&tool_call, &tool_def).await.unwrap().success);
+    }
+    let error = router.route(&tool_call, &tool_def).await.unwrap_err().to_string();
+    assert!(error.contains("temporarily disabled"), "{}", error);
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 2);
+}
+
+/// Middleware recording its calls under a label, at a fixed priority
+struct RecordingMiddleware {
+    label: String,
+    priority: i32,
+    calls: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[async_trait::async_trait]
+impl RouterMiddleware for RecordingMiddleware {
+    fn name(&self) -> &str {
+        &self.label
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    async fn before_execution(&self, _context: &MiddlewareContext) -> magictunnel::error::Result<()> {
+        self.calls.lock().unwrap().push(format!("{}_before", self.label));
+        Ok(())
+    }
+
+    async fn after_execution(&self, _context: &MiddlewareContext, _result: &magictunnel::routing::types::AgentResult) -> magictunnel::error::Result<()> {
+        self.calls.lock().unwrap().push(format!("{}_after", self.label));
+        Ok(())
+    }
+
+    async fn on_error(&self, _context: &MiddlewareContext, _error: &magictunnel::error::ProxyError) -> magictunnel::error::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_middleware_priority_ordering() {
+    let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recording = |label: &str, priority: i32| Arc::new(RecordingMiddleware { label: label.to_string(), priority, calls: calls.clone() });
+
+    let chain = MiddlewareChain::new()
+        .add_middleware(recording("telemetry", 10))
+        .add_middleware(recording("policy", -10))
+        .add_middleware(recording("audit", 0))
+        .add_middleware_with_priority(recording("tracing", 0), 10);
+    assert_eq!(chain.names(), vec!["policy", "audit", "telemetry", "tracing"]);
+    assert_eq!(chain.remove_middleware("audit").names(), vec!["policy", "telemetry", "tracing"]);
+    assert_eq!(LoggingMiddleware::new().name(), "LoggingMiddleware");
+
+    let router = EnhancedRouterBuilder::new()
+        .add_middleware(recording("telemetry", 10))
+        .add_middleware(recording("policy", -10))
+        .build();
+    let _ = router.route(&create_test_tool_call(), &create_test_tool_definition()).await;
+    assert_eq!(*calls.lock().unwrap(), vec!["policy_before", "telemetry_before", "telemetry_after", "policy_after"]);
+}
+
+#[tokio::test]
+async fn test_middleware_plugin_registration() {
+    use magictunnel::routing::middleware::{middleware_plugins, register_middleware_plugin};
+
+    let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let plugin_calls = calls.clone();
+    register_middleware_plugin("acme_audit", move |config| {
+        let label = config["label"].as_str()
+            .ok_or_else(|| magictunnel::error::ProxyError::config("acme_audit requires a label"))?;
+        Ok(Arc::new(RecordingMiddleware { label: label.to_string(), priority: 0, calls: plugin_calls.clone() }) as Arc<dyn RouterMiddleware>)
+    });
+    assert!(middleware_plugins().contains(&"acme_audit".to_string()));
+
+    let router = EnhancedRouterBuilder::new()
+        .with_plugin("acme_audit", &json!({"label": "audit"}))
+        .unwrap()
+        .build();
+    assert_eq!(router.middleware().names(), vec!["audit"]);
+    let _ = router.route(&create_test_tool_call(), &create_test_tool_definition()).await;
+    assert_eq!(calls.lock().unwrap()[0], "audit_before");
+
+    assert!(EnhancedRouterBuilder::new().with_plugin("acme_audit", &json!({})).is_err());
+    assert!(EnhancedRouterBuilder::new().with_plugin("unknown", &json!({})).is_err());
+}
@@ -15,6 +15,7 @@ fn create_test_oauth_config() -> AuthConfig {
         client_secret: "test_client_secret".to_string(),
         auth_url: "https://github.com/login/oauth/authorize".to_string(),
         token_url: "https://github.com/login/oauth/access_token".to_string(),
+        device_auth_url: None,
     });
     config
 }
@@ -29,6 +30,7 @@ fn create_test_oauth_config_google() -> AuthConfig {
         client_secret: "test_google_client_secret".to_string(),
         auth_url: "https://accounts.google.com/o/oauth2/auth".to_string(),
         token_url: "https://oauth2.googleapis.com/token".to_string(),
+        device_auth_url: None,
     });
     config
 }
@@ -215,6 +217,7 @@ mod oauth_middleware_tests {
             user_info,
             expires_at: Some(1234567890),
             scopes: vec!["read".to_string(), "write".to_string()],
+            roles: Vec::new(),
         };
 
         let auth_result = AuthenticationResult::OAuth(oauth_result);
@@ -246,6 +249,7 @@ mod oauth_middleware_tests {
             user_info,
             expires_at: Some(1234567890),
             scopes: vec!["read".to_string(), "write".to_string()],
+            roles: Vec::new(),
         };
 
         let auth_result = AuthenticationResult::OAuth(oauth_result);
@@ -24,7 +24,14 @@ fn create_test_registry_config() -> RegistryConfig {
         validation: magictunnel::config::ValidationConfig {
             strict: false,
             allow_unknown_fields: true,
+            output: Default::default(),
         },
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     }
 }
 
@@ -372,6 +379,7 @@ async fn test_smart_discovery_large_registry_performance() {
             llm_response_ttl: Duration::from_secs(3600),
             max_registry_entries: 100,
             registry_ttl: Duration::from_secs(300),
+            ..magictunnel::discovery::DiscoveryCacheConfig::default()
         },
         ..SmartDiscoveryConfig::default()
     };
@@ -418,6 +426,8 @@ async fn test_smart_discovery_large_registry_performance() {
                 confidence_threshold: Some(0.3),
                 include_error_details: None,
                 sequential_mode: None,
+                conversation: None,
+                exclusions: None,
             };
             
             let response = smart_discovery.discover_and_execute(request).await.unwrap();
@@ -506,6 +516,7 @@ async fn test_smart_discovery_concurrent_large_registry_performance() {
             llm_response_ttl: Duration::from_secs(1800),
             max_registry_entries: 75,
             registry_ttl: Duration::from_secs(300),
+            ..magictunnel::discovery::DiscoveryCacheConfig::default()
         },
         ..SmartDiscoveryConfig::default()
     };
@@ -544,6 +555,8 @@ async fn test_smart_discovery_concurrent_large_registry_performance() {
                 confidence_threshold: Some(0.3),
                 include_error_details: None,
                 sequential_mode: None,
+                conversation: None,
+                exclusions: None,
             };
             
             let response = smart_discovery_clone.discover_and_execute(request).await.unwrap();
@@ -652,6 +665,8 @@ async fn test_smart_discovery_memory_stability() {
             confidence_threshold: Some(0.4),
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = smart_discovery.discover_and_execute(request).await.unwrap();
@@ -690,6 +705,8 @@ async fn test_smart_discovery_memory_stability() {
         confidence_threshold: None,
         include_error_details: None,
         sequential_mode: None,
+        conversation: None,
+        exclusions: None,
     };
     
     let final_response = smart_discovery.discover_and_execute(final_request).await.unwrap();
@@ -207,7 +207,14 @@ mod phase_2b_tests {
             validation: ValidationConfig {
                 strict: false,
                 allow_unknown_fields: true,
+                output: Default::default(),
             },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: Default::default(),
         };
 
         // Create registry service
@@ -0,0 +1,129 @@
+//! Tests for tool call rate limiting in the Router
+
+use async_trait::async_trait;
+use magictunnel::error::Result;
+use magictunnel::mcp::{Tool, ToolCall};
+use magictunnel::registry::{RoutingConfig, ToolCacheConfig, ToolDefinition};
+use magictunnel::routing::rate_limit::{self, RateLimit, RateLimitingConfig};
+use magictunnel::routing::types::{AgentResult, AgentType};
+use magictunnel::routing::{AgentRouter, Router};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Agent router that counts executions
+#[derive(Default)]
+struct CountingAgentRouter {
+    calls: AtomicUsize,
+}
+
+#[async_trait]
+impl AgentRouter for CountingAgentRouter {
+    fn parse_routing_config(&self, _routing: &RoutingConfig) -> Result<AgentType> {
+        Ok(AgentType::Subprocess { command: "true".to_string(), args: vec![], timeout: None, env: None, optional_args: vec![] })
+    }
+
+    async fn execute_with_agent(&self, _tool_call: &ToolCall, _agent: &AgentType) -> Result<AgentResult> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(AgentResult { success: true, data: Some(json!({})), error: None, metadata: None })
+    }
+}
+
+fn tool(name: &str, routing: RoutingConfig) -> ToolDefinition {
+    let tool = Tool::new(name.to_string(), "Test tool".to_string(), json!({"type": "object"})).unwrap();
+    ToolDefinition::new(tool, routing).unwrap()
+}
+
+fn call(name: &str) -> ToolCall {
+    ToolCall { name: name.to_string(), arguments: json!({}) }
+}
+
+fn limit(requests_per_minute: u32, burst: u32) -> RateLimit {
+    RateLimit { requests_per_minute, burst: Some(burst) }
+}
+
+#[tokio::test]
+async fn test_tool_limit_returns_retry_after_error() {
+    let agent = Arc::new(CountingAgentRouter::default());
+    let router = Router::with_agent_router(agent.clone());
+    router.rate_limiter().configure(RateLimitingConfig {
+        tools: HashMap::from([("search".to_string(), limit(6, 1))]),
+        ..Default::default()
+    });
+    let search = tool("search", RoutingConfig::new("subprocess".to_string(), json!({"command": "true"})));
+
+    assert!(router.route(&call("search"), &search).await.unwrap().success);
+    let limited = router.route(&call("search"), &search).await.unwrap();
+    assert!(!limited.success);
+    assert!(limited.error.unwrap().contains("retry after 10s"));
+    let metadata = limited.metadata.unwrap();
+    assert_eq!(metadata["error_category"], "rate_limited");
+    assert_eq!(metadata["retry_after_seconds"], 10);
+    assert_eq!(metadata["rate_limit"]["scope"], "tool");
+    assert_eq!(agent.calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_api_key_and_server_limits() {
+    let agent = Arc::new(CountingAgentRouter::default());
+    let router = Router::with_agent_router(agent.clone());
+    router.rate_limiter().configure(RateLimitingConfig {
+        api_keys: HashMap::from([("ci".to_string(), limit(60, 1))]),
+        servers: HashMap::from([("github".to_string(), limit(60, 2))]),
+        ..Default::default()
+    });
+    let issues = tool("issues", RoutingConfig::new(
+        "external_mcp".to_string(),
+        json!({"server_name": "github", "tool_name": "list_issues"}),
+    ));
+
+    let as_ci = |router: &Router| {
+        let router = router.clone();
+        let issues = issues.clone();
+        rate_limit::as_client(Some("ci".to_string()), async move { router.route(&call("issues"), &issues).await.unwrap() })
+    };
+    assert!(as_ci(&router).await.success);
+    assert_eq!(as_ci(&router).await.metadata.unwrap()["rate_limit"]["scope"], "api_key");
+
+    // Calls without a key only count against the server
+    assert!(router.route(&call("issues"), &issues).await.unwrap().success);
+    let limited = router.route(&call("issues"), &issues).await.unwrap();
+    assert_eq!(limited.metadata.unwrap()["rate_limit"]["key"], "github");
+    assert_eq!(agent.calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_cache_hits_are_not_limited() {
+    let agent = Arc::new(CountingAgentRouter::default());
+    let router = Router::with_agent_router(agent.clone());
+    router.rate_limiter().configure(RateLimitingConfig {
+        tools: HashMap::from([("weather".to_string(), limit(60, 1))]),
+        ..Default::default()
+    });
+    let mut weather = tool("weather", RoutingConfig::new("subprocess".to_string(), json!({"command": "true"})));
+    weather.cache = Some(ToolCacheConfig { ttl_seconds: 60, max_entries: 10 });
+
+    for _ in 0..3 {
+        assert!(router.route(&call("weather"), &weather).await.unwrap().success);
+    }
+    assert_eq!(agent.calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_rate_limiting_config() {
+    let rate_limiting: RateLimitingConfig = serde_yaml::from_str(r#"
+tools:
+  search: { requests_per_minute: 30, burst: 5 }
+default_api_key: { requests_per_minute: 600 }
+"#).unwrap();
+    assert_eq!(rate_limiting.tools["search"], limit(30, 5));
+    assert_eq!(rate_limiting.default_api_key, Some(RateLimit { requests_per_minute: 600, burst: None }));
+    assert!(rate_limiting.validate().is_ok());
+
+    let invalid = RateLimitingConfig {
+        servers: HashMap::from([("github".to_string(), limit(0, 1))]),
+        ..Default::default()
+    };
+    assert!(invalid.validate().is_err());
+}
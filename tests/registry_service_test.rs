@@ -1,7 +1,9 @@
 //! Integration tests for the high-performance registry service
 
-use magictunnel::config::{RegistryConfig, ValidationConfig};
+use magictunnel::config::{RegistryConfig, ToolStateConfig, ToolVersioningConfig, ValidationConfig};
 use magictunnel::registry::service::RegistryService;
+use magictunnel::registry::tool_state::ToolState;
+use std::collections::HashMap;
 
 #[cfg(test)]
 mod registry_service_tests {
@@ -21,7 +23,14 @@ mod registry_service_tests {
             validation: ValidationConfig {
                 strict: true,
                 allow_unknown_fields: false,
+                output: Default::default(),
             },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: Default::default(),
         };
 
         let result = RegistryService::new(config).await;
@@ -51,7 +60,14 @@ mod registry_service_tests {
             validation: ValidationConfig {
                 strict: false, // Allow for test flexibility
                 allow_unknown_fields: true,
+                output: Default::default(),
             },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: Default::default(),
         };
 
         let result = RegistryService::new(config).await;
@@ -101,7 +117,14 @@ mod registry_service_tests {
             validation: ValidationConfig {
                 strict: false,
                 allow_unknown_fields: true,
+                output: Default::default(),
             },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: Default::default(),
         };
 
         let result = RegistryService::new(config).await;
@@ -133,7 +156,14 @@ mod registry_service_tests {
             validation: ValidationConfig {
                 strict: false,
                 allow_unknown_fields: true,
+                output: Default::default(),
             },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: Default::default(),
         };
 
         let result = RegistryService::new(config).await;
@@ -165,4 +195,278 @@ mod registry_service_tests {
         println!("✓ Registry reloaded successfully with {} files and {} tools",
                  updated_metadata.file_count, updated_metadata.tool_count);
     }
+
+    /// Test the SQLite storage backend picks up added and removed files
+    #[tokio::test]
+    async fn test_sqlite_registry_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let caps_dir = dir.path().join("capabilities");
+        std::fs::create_dir_all(&caps_dir).unwrap();
+
+        let tool_yaml = |name: &str| format!(
+            "tools:\n  - name: {}\n    description: {} tool\n    inputSchema: {{type: object}}\n    routing: {{type: http, config: {{url: 'http://localhost', method: GET}}}}\n",
+            name, name
+        );
+        std::fs::write(caps_dir.join("a.yaml"), tool_yaml("tool_a")).unwrap();
+
+        let config = RegistryConfig {
+            r#type: "sqlite".to_string(),
+            paths: vec![caps_dir.to_string_lossy().to_string()],
+            hot_reload: false,
+            validation: ValidationConfig {
+                strict: false,
+                allow_unknown_fields: true,
+                output: Default::default(),
+            },
+            remote: Default::default(),
+            sqlite: magictunnel::config::SqliteRegistryConfig {
+                path: dir.path().join("registry.db").to_string_lossy().to_string(),
+            },
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: Default::default(),
+        };
+
+        let service = RegistryService::new(config).await.expect("SQLite registry should initialize");
+        assert!(service.get_tool("tool_a").is_some());
+        assert_eq!(service.get_stored_tool("tool_a").unwrap().unwrap().name(), "tool_a");
+
+        std::fs::write(caps_dir.join("b.yaml"), tool_yaml("tool_b")).unwrap();
+        std::fs::remove_file(caps_dir.join("a.yaml")).unwrap();
+        service.reload_registry().await.unwrap();
+
+        assert!(service.get_tool("tool_a").is_none());
+        assert!(service.get_tool("tool_b").is_some());
+        assert!(service.get_stored_tool("tool_a").unwrap().is_none());
+    }
+
+    /// Test a snapshot of one registry reproduces its tools in another
+    #[tokio::test]
+    async fn test_registry_snapshot_export_import() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::write(
+            source.path().join("tools.yaml"),
+            "tools:\n  - name: snapshot_tool\n    description: Snapshot tool\n    inputSchema: {type: object}\n    enabled: false\n    routing: {type: http, config: {url: 'http://localhost', method: GET}}\n",
+        ).unwrap();
+
+        let config = |dir: &std::path::Path| RegistryConfig {
+            r#type: "file".to_string(),
+            paths: vec![dir.to_string_lossy().to_string()],
+            hot_reload: false,
+            validation: ValidationConfig {
+                strict: false,
+                allow_unknown_fields: true,
+                output: Default::default(),
+            },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: Default::default(),
+        };
+
+        let snapshot = RegistryService::new(config(source.path())).await.unwrap().export_snapshot();
+        assert_eq!(snapshot.files.len(), 1);
+        assert!(snapshot.files[0].path.ends_with("tools.yaml"));
+
+        let target = tempfile::tempdir().unwrap();
+        let service = RegistryService::new(config(target.path())).await.unwrap();
+        assert!(service.get_tool("snapshot_tool").is_none());
+
+        let report = service.import_snapshot(&snapshot, target.path(), false).await.unwrap();
+        assert_eq!(report.tool_count, 1);
+        assert_eq!(service.is_tool_enabled("snapshot_tool"), Some(false));
+    }
+
+    /// Test runtime enable/hide overrides are applied, persisted and reloaded
+    #[tokio::test]
+    async fn test_tool_state_overrides_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("tools.yaml"),
+            "tools:\n  - name: state_tool\n    description: State tool\n    inputSchema: {type: object}\n    routing: {type: http, config: {url: 'http://localhost', method: GET}}\n  - name: other_tool\n    description: Other tool\n    inputSchema: {type: object}\n    routing: {type: http, config: {url: 'http://localhost', method: GET}}\n",
+        ).unwrap();
+        let state_path = dir.path().join("state/tool-state.json");
+
+        let config = RegistryConfig {
+            r#type: "file".to_string(),
+            paths: vec![dir.path().join("tools.yaml").to_string_lossy().to_string()],
+            hot_reload: false,
+            validation: ValidationConfig {
+                strict: false,
+                allow_unknown_fields: true,
+                output: Default::default(),
+            },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: ToolStateConfig {
+                path: state_path.to_string_lossy().to_string(),
+            },
+        };
+
+        let service = RegistryService::new(config.clone()).await.unwrap();
+        service.set_tool_enabled("state_tool", false).await.unwrap();
+        assert_eq!(service.is_tool_enabled("state_tool"), Some(false));
+        assert!(service.set_tool_enabled("missing_tool", false).await.is_err());
+
+        let (file_path, tools) = service.list_capability_files().remove(0);
+        assert_eq!(tools.len(), 2);
+        service.set_file_state(&file_path, ToolState { enabled: None, hidden: Some(true) }).unwrap();
+        assert_eq!(service.is_tool_hidden("other_tool"), Some(true));
+
+        // The capability file is untouched; the overrides survive a restart
+        let yaml = std::fs::read_to_string(dir.path().join("tools.yaml")).unwrap();
+        assert!(!yaml.contains("enabled"));
+        let restarted = RegistryService::new(config).await.unwrap();
+        assert_eq!(restarted.is_tool_enabled("state_tool"), Some(false));
+        assert_eq!(restarted.is_tool_hidden("state_tool"), Some(true));
+
+        restarted.reset_tool_state("state_tool").unwrap();
+        assert_eq!(restarted.is_tool_enabled("state_tool"), Some(true));
+    }
+
+    /// Test bare names resolve to the default version of a versioned tool
+    #[tokio::test]
+    async fn test_versioned_tool_resolution() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool_yaml = |name: &str| format!(
+            "  - name: {}\n    description: {} tool\n    inputSchema: {{type: object}}\n    routing: {{type: http, config: {{url: 'http://localhost', method: GET}}}}\n",
+            name, name
+        );
+        std::fs::write(
+            dir.path().join("search.yaml"),
+            format!("tools:\n{}{}", tool_yaml("search_code@v1"), tool_yaml("search_code@v2")),
+        ).unwrap();
+
+        let config = |versioning: ToolVersioningConfig| RegistryConfig {
+            r#type: "file".to_string(),
+            paths: vec![dir.path().to_string_lossy().to_string()],
+            hot_reload: false,
+            validation: ValidationConfig {
+                strict: false,
+                allow_unknown_fields: true,
+                output: Default::default(),
+            },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning,
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: Default::default(),
+        };
+
+        let service = RegistryService::new(config(Default::default())).await.unwrap();
+        assert_eq!(service.get_tool("search_code").unwrap().name(), "search_code@v2");
+        assert_eq!(service.get_tool("search_code@v1").unwrap().name(), "search_code@v1");
+        assert_eq!(service.list_tool_versions("search_code"), vec!["v1", "v2"]);
+
+        let pinned = ToolVersioningConfig {
+            default_policy: Default::default(),
+            defaults: [("search_code".to_string(), "v1".to_string())].into_iter().collect(),
+        };
+        let service = RegistryService::new(config(pinned)).await.unwrap();
+        assert_eq!(service.resolve_tool_name("search_code"), "search_code@v1");
+    }
+
+    /// Test reloads publish the tools they added, changed or removed
+    #[tokio::test]
+    async fn test_reload_publishes_changed_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool_yaml = |name: &str, description: &str| format!(
+            "  - name: {}\n    description: {}\n    inputSchema: {{type: object}}\n    routing: {{type: http, config: {{url: 'http://localhost', method: GET}}}}\n",
+            name, description
+        );
+        let path = dir.path().join("tools.yaml");
+        std::fs::write(&path, format!("tools:\n{}{}", tool_yaml("kept_tool", "Kept"), tool_yaml("edited_tool", "Before"))).unwrap();
+
+        let config = RegistryConfig {
+            r#type: "file".to_string(),
+            paths: vec![dir.path().to_string_lossy().to_string()],
+            hot_reload: false,
+            validation: ValidationConfig {
+                strict: false,
+                allow_unknown_fields: true,
+                output: Default::default(),
+            },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: ToolStateConfig {
+                path: dir.path().join("tool-state.json").to_string_lossy().to_string(),
+            },
+        };
+
+        let service = RegistryService::new(config).await.unwrap();
+        let mut changes = service.subscribe_changes();
+
+        std::fs::write(&path, format!("tools:\n{}{}", tool_yaml("kept_tool", "Kept"), tool_yaml("edited_tool", "After"))).unwrap();
+        std::fs::write(dir.path().join("new.yaml"), format!("tools:\n{}", tool_yaml("new_tool", "New"))).unwrap();
+        service.reload_registry().await.unwrap();
+        let change = changes.try_recv().unwrap();
+        assert_eq!(change.changed, vec!["edited_tool", "new_tool"]);
+        assert!(change.removed.is_empty());
+
+        std::fs::remove_file(dir.path().join("new.yaml")).unwrap();
+        service.reload_registry().await.unwrap();
+        let change = changes.try_recv().unwrap();
+        assert!(change.changed.is_empty());
+        assert_eq!(change.removed, vec!["new_tool"]);
+
+        // Reloads that change nothing aren't published
+        service.reload_registry().await.unwrap();
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_generated_tags_survive_reloads() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("tools.yaml"),
+            "tools:\n  - name: read_file\n    description: Read a file\n    tags: [files]\n    inputSchema: {type: object}\n    routing: {type: http, config: {url: 'http://localhost', method: GET}}\n",
+        ).unwrap();
+
+        let config = RegistryConfig {
+            r#type: "file".to_string(),
+            paths: vec![dir.path().to_string_lossy().to_string()],
+            hot_reload: false,
+            validation: ValidationConfig {
+                strict: false,
+                allow_unknown_fields: true,
+                output: Default::default(),
+            },
+            remote: Default::default(),
+            sqlite: Default::default(),
+            versioning: Default::default(),
+            toolsets: Default::default(),
+            env_templating: Default::default(),
+            tool_state: ToolStateConfig {
+                path: dir.path().join("tool-state.json").to_string_lossy().to_string(),
+            },
+        };
+
+        let service = RegistryService::new(config).await.unwrap();
+        let mut changes = service.subscribe_changes();
+        let tags = HashMap::from([("read_file".to_string(), vec!["category:file".to_string(), "files".to_string()])]);
+        service.set_generated_tags(tags.clone()).unwrap();
+        assert_eq!(service.get_tool("read_file").unwrap().tags, vec!["files", "category:file"]);
+        assert_eq!(changes.try_recv().unwrap().changed, vec!["read_file"]);
+
+        // Setting the same tags again changes nothing
+        service.set_generated_tags(tags).unwrap();
+        assert!(changes.try_recv().is_err());
+
+        service.reload_registry().await.unwrap();
+        assert_eq!(service.get_tool("read_file").unwrap().tags, vec!["files", "category:file"]);
+        assert!(service.export_snapshot().files.iter().all(|file| file.capability_file.tools[0].tags == vec!["files"]));
+
+        service.set_generated_tags(HashMap::new()).unwrap();
+        assert_eq!(service.get_tool("read_file").unwrap().tags, vec!["files"]);
+    }
 }
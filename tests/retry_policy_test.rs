@@ -0,0 +1,119 @@
+//! Tests for per-tool retry policies
+
+use async_trait::async_trait;
+use magictunnel::error::{ProxyError, Result};
+use magictunnel::mcp::{Tool, ToolCall};
+use magictunnel::registry::{RoutingConfig, ToolDefinition};
+use magictunnel::routing::types::{AgentResult, AgentType};
+use magictunnel::routing::{AgentRouter, Router};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// Agent router whose first `failures` calls fail with a 503
+struct FlakyAgentRouter {
+    failures: u32,
+    calls: AtomicU32,
+}
+
+impl FlakyAgentRouter {
+    fn new(failures: u32) -> Arc<Self> {
+        Arc::new(Self { failures, calls: AtomicU32::new(0) })
+    }
+}
+
+#[async_trait]
+impl AgentRouter for FlakyAgentRouter {
+    fn parse_routing_config(&self, _routing: &RoutingConfig) -> Result<AgentType> {
+        Ok(AgentType::Subprocess {
+            command: "echo".to_string(),
+            args: vec![],
+            optional_args: vec![],
+            timeout: None,
+            env: None,
+        })
+    }
+
+    async fn execute_with_agent(&self, _tool_call: &ToolCall, _agent: &AgentType) -> Result<AgentResult> {
+        if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+            return Err(ProxyError::routing("HTTP request failed with status 503"));
+        }
+        Ok(AgentResult { success: true, data: Some(json!({"rate": 1.08})), error: None, metadata: None })
+    }
+}
+
+fn tool(annotations: &[(&str, &str)]) -> ToolDefinition {
+    let tool = Tool::new("get_rates".to_string(), "Get rates".to_string(), json!({"type": "object"})).unwrap();
+    let mut tool = ToolDefinition::new(tool, RoutingConfig::new("http".to_string(), json!({
+        "method": "GET",
+        "url": "http://rates",
+        "retry": {"max_attempts": 3, "initial_delay_ms": 1, "use_jitter": false}
+    }))).unwrap();
+    tool.annotations = Some(annotations.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>());
+    tool
+}
+
+fn call() -> ToolCall {
+    ToolCall { name: "get_rates".to_string(), arguments: json!({}) }
+}
+
+#[tokio::test]
+async fn test_idempotent_tool_is_retried() {
+    let agents = FlakyAgentRouter::new(2);
+    let router = Router::with_agent_router(agents.clone());
+
+    let result = router.route(&call(), &tool(&[("idempotentHint", "true")])).await.unwrap();
+    assert!(result.success);
+    assert_eq!(result.metadata.unwrap()["retry_attempts"], json!(3));
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_read_only_tool_gives_up_after_max_attempts() {
+    let agents = FlakyAgentRouter::new(5);
+    let router = Router::with_agent_router(agents.clone());
+
+    assert!(router.route(&call(), &tool(&[("readOnlyHint", "true")])).await.is_err());
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_non_idempotent_tool_is_not_retried() {
+    let agents = FlakyAgentRouter::new(1);
+    let router = Router::with_agent_router(agents.clone());
+
+    let tool = tool(&[("destructiveHint", "true"), ("idempotentHint", "false")]);
+    assert!(router.route(&call(), &tool).await.is_err());
+    assert_eq!(agents.calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_invalid_retry_policy_rejected() {
+    let routing = RoutingConfig::new("http".to_string(), json!({
+        "method": "GET",
+        "url": "http://rates",
+        "retry": {"max_attempts": 0}
+    }));
+    assert!(routing.validate().is_err());
+
+    let routing = RoutingConfig::new("http".to_string(), json!({
+        "method": "GET",
+        "url": "http://rates",
+        "retry": {"backoff_multiplier": "fast"}
+    }));
+    assert!(routing.validate().is_err());
+    assert!(RoutingConfig::new("http".to_string(), json!({"method": "GET", "url": "http://rates"})).validate().is_ok());
+}
+
+#[test]
+fn test_mcp_annotations_are_kept() {
+    let mut tool = Tool::new("get_rates".to_string(), "Get rates".to_string(), json!({"type": "object"})).unwrap();
+    tool.annotations = Some(serde_json::from_value(json!({"readOnlyHint": true, "openWorldHint": false})).unwrap());
+    let definition = ToolDefinition::new(tool, RoutingConfig::new("http".to_string(), json!({"method": "GET", "url": "http://rates"}))).unwrap();
+
+    let annotations: &HashMap<String, String> = definition.annotations.as_ref().unwrap();
+    assert_eq!(annotations.get("readOnlyHint").map(String::as_str), Some("true"));
+    assert_eq!(annotations.get("openWorldHint").map(String::as_str), Some("false"));
+    assert!(definition.is_idempotent());
+}
@@ -0,0 +1,81 @@
+use magictunnel::mcp::types::ToolCall;
+use magictunnel::registry::RoutingConfig;
+use magictunnel::routing::agent_router::{AgentRouter, DefaultAgentRouter};
+use magictunnel::routing::script::ScriptLanguage;
+use magictunnel::routing::types::AgentType;
+use serde_json::json;
+
+#[cfg(feature = "lua")]
+#[tokio::test]
+async fn test_script_agent_parsing() {
+    let routing_config = RoutingConfig::new("script".to_string(), json!({
+        "language": "lua",
+        "script": "return args.a + args.b",
+        "max_operations": 5000,
+        "timeout": 2
+    }));
+
+    let router = DefaultAgentRouter::new();
+    match router.parse_routing_config(&routing_config).unwrap() {
+        AgentType::Script { timeout, config } => {
+            assert_eq!(timeout, Some(2));
+            assert_eq!(config.language, ScriptLanguage::Lua);
+            assert_eq!(config.script, "return args.a + args.b");
+            assert_eq!(config.max_operations, 5000);
+            assert_eq!(config.max_memory_mb, 64);
+        }
+        _ => panic!("Expected Script agent type"),
+    }
+}
+
+#[tokio::test]
+async fn test_script_agent_parsing_errors() {
+    let router = DefaultAgentRouter::new();
+
+    let result = router.parse_routing_config(&RoutingConfig::new("script".to_string(), json!({"language": "rhai"})));
+    assert!(result.unwrap_err().to_string().contains("Invalid script config"));
+
+    let result = router.parse_routing_config(&RoutingConfig::new("script".to_string(), json!({"language": "python", "script": "1"})));
+    assert!(result.is_err());
+
+    let result = router.parse_routing_config(&RoutingConfig::new("script".to_string(), json!({"script": "let x = ;"})));
+    assert!(result.unwrap_err().to_string().contains("Invalid Rhai script"));
+}
+
+#[tokio::test]
+async fn test_script_agent_execution() {
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("script".to_string(), json!({
+        "script": r#"print("slugifying"); #{ slug: args.title.to_lower().replace(" ", "-") }"#
+    }))).unwrap();
+    let tool_call = ToolCall {
+        name: "slugify".to_string(),
+        arguments: json!({"title": "Hello Script World"}),
+    };
+
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data.unwrap(), json!({"slug": "hello-script-world"}));
+    let metadata = result.metadata.unwrap();
+    assert_eq!(metadata["execution_type"], "script");
+    assert_eq!(metadata["language"], "rhai");
+    assert_eq!(metadata["output"], json!(["slugifying"]));
+}
+
+#[cfg(feature = "lua")]
+#[tokio::test]
+async fn test_script_agent_reports_script_errors() {
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("script".to_string(), json!({
+        "language": "lua",
+        "script": "error('bad input: ' .. args.value)"
+    }))).unwrap();
+    let tool_call = ToolCall {
+        name: "validate".to_string(),
+        arguments: json!({"value": "x"}),
+    };
+
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    assert!(!result.success);
+    assert!(result.error.unwrap().contains("bad input: x"));
+}
@@ -218,7 +218,14 @@ mod security_tests {
                 validation: ValidationConfig {
                     strict: true,
                     allow_unknown_fields: false,
+                    output: Default::default(),
                 },
+                remote: Default::default(),
+                sqlite: Default::default(),
+                versioning: Default::default(),
+                toolsets: Default::default(),
+                env_templating: Default::default(),
+                tool_state: Default::default(),
             },
             auth: None,
             logging: None,
@@ -227,6 +234,22 @@ mod security_tests {
             conflict_resolution: None,
             visibility: None,
             smart_discovery: None,
+            rate_limiting: None,
+            timeouts: None,
+            dry_run: None,
+            jobs: None,
+            costs: None,
+            sessions: None,
+            policy: None,
+            secret_scanning: None,
+            pii: None,
+            approvals: None,
+            access_windows: None,
+            quotas: None,
+            audit: None,
+            threat_detection: None,
+            network_policy: None,
+            circuit_breakers: None,
         };
 
         let result = config.validate();
@@ -246,14 +269,25 @@ mod security_tests {
             let auth_config = AuthConfig {
                 enabled: true,
                 r#type: magictunnel::config::AuthType::ApiKey,
+                methods: Vec::new(),
                 api_keys: Some(magictunnel::config::ApiKeyConfig {
                     keys: vec![magictunnel::config::ApiKeyEntry::new(key.to_string(), "Test Key".to_string())],
                     require_header: true,
                     header_name: "Authorization".to_string(),
                     header_format: "Bearer {key}".to_string(),
+                    rotation: Default::default(),
                 }),
                 oauth: None,
                 jwt: None,
+                client_cert: None,
+                hmac: None,
+                resource_server: None,
+                client_registration: None,
+                roles: Default::default(),
+                role_mappings: vec![],
+                guest: None,
+                tenants: vec![],
+                token_encryption: None,
             };
 
             let result = auth_config.validate();
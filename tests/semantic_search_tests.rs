@@ -8,7 +8,8 @@
 use magictunnel::discovery::{
     SemanticSearchService, SemanticSearchConfig,
     EmbeddingManager, EmbeddingManagerConfig,
-    SmartDiscoveryService, SmartDiscoveryConfig, SmartDiscoveryRequest
+    SmartDiscoveryService, SmartDiscoveryConfig, SmartDiscoveryRequest, HybridWeights,
+    EmbeddingModelChange, ModelSwapState
 };
 use magictunnel::registry::RegistryService;
 use magictunnel::config::RegistryConfig;
@@ -46,10 +47,20 @@ fn create_test_tools() -> Vec<ToolDefinition> {
             routing: RoutingConfig {
                 r#type: "http".to_string(),
                 config: json!({"url": "http://example.com/ping"}),
+                fallback: Vec::new(),
+                canary: None,
             },
             annotations: None,
             enabled: true,
             hidden: false,
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
         },
         ToolDefinition {
             name: "search_files".to_string(),
@@ -65,10 +76,20 @@ fn create_test_tools() -> Vec<ToolDefinition> {
             routing: RoutingConfig {
                 r#type: "filesystem".to_string(),
                 config: json!({"type": "search"}),
+                fallback: Vec::new(),
+                canary: None,
             },
             annotations: None,
             enabled: true,
             hidden: false,
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
         },
         ToolDefinition {
             name: "database_query".to_string(),
@@ -83,10 +104,20 @@ fn create_test_tools() -> Vec<ToolDefinition> {
             routing: RoutingConfig {
                 r#type: "database".to_string(),
                 config: json!({"connection": "default"}),
+                fallback: Vec::new(),
+                canary: None,
             },
             annotations: None,
             enabled: false, // Disabled tool
             hidden: false,
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
         },
         ToolDefinition {
             name: "api_request".to_string(),
@@ -102,10 +133,20 @@ fn create_test_tools() -> Vec<ToolDefinition> {
             routing: RoutingConfig {
                 r#type: "http".to_string(),
                 config: json!({"type": "api"}),
+                fallback: Vec::new(),
+                canary: None,
             },
             annotations: None,
             enabled: true,
             hidden: true, // Hidden tool
+            deprecated: false,
+            deprecation_reason: None,
+            sunset_date: None,
+            tags: Vec::new(),
+            output_schema: None,
+            cache: None,
+            background: false,
+            cost: None,
         },
     ]
 }
@@ -186,6 +227,8 @@ async fn test_semantic_search_functionality() {
                     .unwrap()
                     .as_secs(),
                 embedding_dims: embedding.len(),
+                tags: Vec::new(),
+                server: None,
             };
             
             storage.add_tool_embedding(tool.name.clone(), embedding, metadata);
@@ -234,7 +277,14 @@ async fn test_embedding_manager_lifecycle() {
         validation: magictunnel::config::ValidationConfig {
             strict: false,
             allow_unknown_fields: true,
+            output: Default::default(),
         },
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
     
     let registry = Arc::new(RegistryService::new(registry_config).await.unwrap());
@@ -289,7 +339,14 @@ async fn test_hybrid_search_strategy() {
         validation: magictunnel::config::ValidationConfig {
             strict: false,
             allow_unknown_fields: true,
+            output: Default::default(),
         },
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
     
     let registry = Arc::new(RegistryService::new(registry_config).await.unwrap());
@@ -316,6 +373,8 @@ async fn test_hybrid_search_strategy() {
         confidence_threshold: Some(0.6),
         include_error_details: Some(true),
         sequential_mode: Some(true),
+        conversation: None,
+        exclusions: None,
     };
     
     let response = discovery_service.discover_and_execute(request).await;
@@ -340,7 +399,14 @@ async fn test_search_mode_selection() {
         validation: magictunnel::config::ValidationConfig {
             strict: false,
             allow_unknown_fields: true,
+            output: Default::default(),
         },
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
     
     let registry = Arc::new(RegistryService::new(registry_config).await.unwrap());
@@ -399,6 +465,8 @@ async fn test_embedding_persistence() {
                         .unwrap()
                         .as_secs(),
                     embedding_dims: embedding.len(),
+                    tags: Vec::new(),
+                    server: None,
                 };
                 
                 storage.add_tool_embedding(tool.name.clone(), embedding, metadata);
@@ -516,6 +584,8 @@ async fn test_performance_benchmarks() {
                 .unwrap()
                 .as_secs(),
             embedding_dims: 384,
+            tags: Vec::new(),
+            server: None,
         };
         storage.add_tool_embedding(tool_name, embedding, metadata);
     }
@@ -538,4 +608,140 @@ async fn test_performance_benchmarks() {
     assert_eq!(total, 50, "Should have 50 tools in storage");
     assert_eq!(enabled, 50, "All test tools should be enabled");
     assert_eq!(hidden, 0, "No test tools should be hidden");
-}
\ No newline at end of file
+}
+/// Test hybrid ranking weight validation
+#[test]
+fn test_hybrid_weights_validation() {
+    let mut weights = HybridWeights::default();
+    assert!(weights.validate().is_ok());
+    assert!((weights.semantic + weights.keyword + weights.rule_based + weights.llm - 1.0).abs() < 1e-9);
+
+    weights.keyword = -0.1;
+    assert!(weights.validate().is_err());
+
+    let weights = HybridWeights { semantic: 0.0, keyword: 0.0, rule_based: 0.0, llm: 0.0 };
+    assert!(weights.validate().is_err());
+
+    // Omitted weights keep their defaults
+    let weights: HybridWeights = serde_json::from_value(json!({"keyword": 0.4})).unwrap();
+    assert_eq!(weights.keyword, 0.4);
+    assert_eq!(weights.semantic, HybridWeights::default().semantic);
+}
+
+/// Test swapping the embedding model at runtime and rolling it back
+#[tokio::test]
+async fn test_embedding_model_swap_and_rollback() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let caps_dir = temp_dir.path().join("capabilities");
+    std::fs::create_dir_all(&caps_dir).unwrap();
+    std::fs::write(
+        caps_dir.join("network.yaml"),
+        "tools:\n  - name: ping_host\n    description: Test network connectivity by pinging a host\n    inputSchema: {type: object}\n    routing: {type: http, config: {url: 'http://localhost', method: GET}}\n",
+    ).unwrap();
+
+    let registry_config = RegistryConfig {
+        r#type: "file".to_string(),
+        paths: vec![caps_dir.to_string_lossy().to_string()],
+        hot_reload: false,
+        validation: magictunnel::config::ValidationConfig {
+            strict: false,
+            allow_unknown_fields: true,
+            output: Default::default(),
+        },
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
+    };
+    let registry = Arc::new(RegistryService::new(registry_config).await.unwrap());
+
+    let mut discovery_config = SmartDiscoveryConfig::default();
+    discovery_config.semantic_search = create_test_semantic_config(&temp_dir);
+    let discovery_service = SmartDiscoveryService::new(registry, discovery_config).await.unwrap();
+    discovery_service.initialize().await.unwrap();
+    assert_eq!(discovery_service.embedding_model_status().active_model.as_deref(), Some("all-MiniLM-L6-v2"));
+
+    let report = discovery_service.swap_embedding_model(EmbeddingModelChange {
+        model_name: "all-mpnet-base-v2".to_string(),
+        provider: None,
+    }).await.expect("Swap failed");
+    assert_eq!(report.previous_model, "all-MiniLM-L6-v2");
+    assert_eq!(report.dimensions, 768);
+    assert_eq!(report.tools_indexed, 1);
+
+    let status = discovery_service.embedding_model_status();
+    assert_eq!(status.state, ModelSwapState::Completed);
+    assert_eq!(status.active_model.as_deref(), Some("all-mpnet-base-v2"));
+    assert_eq!(status.rollback_model.as_deref(), Some("all-MiniLM-L6-v2"));
+    assert!(temp_dir.path().join("models/all-mpnet-base-v2/embeddings.json").exists());
+
+    let status = discovery_service.rollback_embedding_model().await.expect("Rollback failed");
+    assert_eq!(status.state, ModelSwapState::RolledBack);
+    assert_eq!(status.active_model.as_deref(), Some("all-MiniLM-L6-v2"));
+    assert_eq!(status.rollback_model.as_deref(), Some("all-mpnet-base-v2"));
+
+    let invalid = EmbeddingModelChange { model_name: " ".to_string(), provider: None };
+    assert!(discovery_service.swap_embedding_model(invalid).await.is_err());
+}
+
+/// Test deriving tool categories from the embeddings and tagging the tools
+#[tokio::test]
+async fn test_tool_categories_tag_registry() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let caps_dir = temp_dir.path().join("capabilities");
+    std::fs::create_dir_all(&caps_dir).unwrap();
+    let tool_yaml = |name: &str, description: &str| format!(
+        "  - name: {}\n    description: {}\n    inputSchema: {{type: object}}\n    routing: {{type: http, config: {{url: 'http://localhost', method: GET}}}}\n",
+        name, description
+    );
+    std::fs::write(caps_dir.join("tools.yaml"), format!(
+        "tools:\n{}{}{}{}",
+        tool_yaml("read_file", "Read a file from a directory"),
+        tool_yaml("write_file", "Write a file to a directory"),
+        tool_yaml("http_get", "Send an HTTP GET request to a URL"),
+        tool_yaml("http_post", "Send an HTTP POST request to a URL"),
+    )).unwrap();
+
+    let registry_config = RegistryConfig {
+        r#type: "file".to_string(),
+        paths: vec![caps_dir.to_string_lossy().to_string()],
+        hot_reload: false,
+        validation: magictunnel::config::ValidationConfig {
+            strict: false,
+            allow_unknown_fields: true,
+            output: Default::default(),
+        },
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
+    };
+    let registry = Arc::new(RegistryService::new(registry_config).await.unwrap());
+
+    let mut discovery_config = SmartDiscoveryConfig::default();
+    discovery_config.semantic_search = create_test_semantic_config(&temp_dir);
+    discovery_config.categories.min_tools = 2;
+    discovery_config.categories.tools_per_category = 2;
+    let discovery_service = SmartDiscoveryService::new(Arc::clone(&registry), discovery_config).await.unwrap();
+    discovery_service.initialize().await.unwrap();
+    assert!(discovery_service.browse_categories(None).await.is_err(), "No categories before the first refresh");
+
+    let categories = discovery_service.refresh_categories().await.unwrap().expect("Should derive categories");
+    assert_eq!(categories.categories.iter().map(|c| c.tools.len()).sum::<usize>(), 4);
+    for category in &categories.categories {
+        for tool in &category.tools {
+            assert!(registry.get_tool(tool).unwrap().tags.contains(&category.tag));
+        }
+    }
+
+    let overview = discovery_service.browse_categories(None).await.unwrap();
+    assert_eq!(overview["categories"].as_array().unwrap().len(), categories.categories.len());
+    let first = &categories.categories[0];
+    let listing = discovery_service.browse_categories(Some(&first.tag)).await.unwrap();
+    assert_eq!(listing["tools"].as_array().unwrap().len(), first.tools.len());
+    assert!(discovery_service.browse_categories(Some("no-such-category")).await.is_err());
+}
@@ -125,6 +125,8 @@ smart_discovery:
         confidence_threshold: None,
         include_error_details: None,
         sequential_mode: None,
+        conversation: None,
+        exclusions: None,
     };
     
     let file_response = smart_discovery.discover_and_execute(file_request).await.unwrap();
@@ -142,6 +144,8 @@ smart_discovery:
         confidence_threshold: Some(0.5),
         include_error_details: None,
         sequential_mode: None,
+        conversation: None,
+        exclusions: None,
     };
     
     let http_response = smart_discovery.discover_and_execute(http_request).await.unwrap();
@@ -158,6 +162,8 @@ smart_discovery:
         confidence_threshold: None,
         include_error_details: None,
         sequential_mode: None,
+        conversation: None,
+        exclusions: None,
     };
     
     let db_response = smart_discovery.discover_and_execute(db_request).await.unwrap();
@@ -328,6 +334,8 @@ async fn test_smart_discovery_error_handling_integration() {
         confidence_threshold: None,
         include_error_details: Some(true),
         sequential_mode: Some(true),
+        conversation: None,
+        exclusions: None,
     };
     
     let unknown_response = smart_discovery.discover_and_execute(unknown_request).await.unwrap();
@@ -345,6 +353,8 @@ async fn test_smart_discovery_error_handling_integration() {
         confidence_threshold: None,
         include_error_details: Some(true),
         sequential_mode: Some(true),
+        conversation: None,
+        exclusions: None,
     };
     
     let ambiguous_response = smart_discovery.discover_and_execute(ambiguous_request).await.unwrap();
@@ -360,6 +370,8 @@ async fn test_smart_discovery_error_handling_integration() {
         confidence_threshold: None,
         include_error_details: Some(true),
         sequential_mode: Some(true),
+        conversation: None,
+        exclusions: None,
     };
     
     let incomplete_response = smart_discovery.discover_and_execute(incomplete_request).await.unwrap();
@@ -390,6 +402,8 @@ async fn test_smart_discovery_concurrent_load() {
                 confidence_threshold: None,
                 include_error_details: None,
                 sequential_mode: None,
+                conversation: None,
+                exclusions: None,
             };
             
             let response = smart_discovery_clone.discover_and_execute(request).await.unwrap();
@@ -450,6 +464,8 @@ async fn test_smart_discovery_with_capability_categories() {
             confidence_threshold: Some(0.3), // Lower threshold for broader matching
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = smart_discovery.discover_and_execute(request).await.unwrap();
@@ -511,6 +527,8 @@ async fn test_smart_discovery_configuration_variations() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = smart_discovery.discover_and_execute(request).await.unwrap();
@@ -569,6 +587,7 @@ tools:
         validation: magictunnel::config::ValidationConfig {
             strict: true,
             allow_unknown_fields: false,
+            output: Default::default(),
         },
         ..magictunnel::config::RegistryConfig::default()
     };
@@ -585,6 +604,8 @@ tools:
         confidence_threshold: None,
         include_error_details: None,
         sequential_mode: None,
+        conversation: None,
+        exclusions: None,
     };
     
     let response = smart_discovery.discover_and_execute(request).await.unwrap();
@@ -622,6 +643,8 @@ async fn test_smart_discovery_statistics_monitoring() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let _response = smart_discovery.discover_and_execute(request).await.unwrap();
@@ -688,6 +711,8 @@ async fn test_smart_discovery_tool_accuracy() {
             confidence_threshold: Some(0.3),
             include_error_details: Some(true),
             sequential_mode: Some(true),
+            conversation: None,
+            exclusions: None,
         };
         
         let response = smart_discovery.discover_and_execute(request).await.unwrap();
@@ -739,6 +764,8 @@ async fn test_smart_discovery_realistic_workflows() {
             confidence_threshold: Some(0.4),
             include_error_details: Some(true),
             sequential_mode: Some(true),
+            conversation: None,
+            exclusions: None,
         };
         
         let response = smart_discovery.discover_and_execute(request).await.unwrap();
@@ -763,6 +790,8 @@ async fn test_smart_discovery_realistic_workflows() {
             confidence_threshold: Some(0.4),
             include_error_details: Some(true),
             sequential_mode: Some(true),
+            conversation: None,
+            exclusions: None,
         };
         
         let response = smart_discovery.discover_and_execute(request).await.unwrap();
@@ -787,6 +816,8 @@ async fn test_smart_discovery_realistic_workflows() {
             confidence_threshold: Some(0.4),
             include_error_details: Some(true),
             sequential_mode: Some(true),
+            conversation: None,
+            exclusions: None,
         };
         
         let response = smart_discovery.discover_and_execute(request).await.unwrap();
@@ -837,6 +868,8 @@ async fn test_smart_discovery_error_recovery() {
             confidence_threshold: None,
             include_error_details: Some(true),
             sequential_mode: Some(true),
+            conversation: None,
+            exclusions: None,
         };
         
         let response = smart_discovery.discover_and_execute(request).await.unwrap();
@@ -880,6 +913,7 @@ async fn test_smart_discovery_large_registry_simulation() {
             llm_response_ttl: Duration::from_secs(600),
             max_registry_entries: 50,
             registry_ttl: Duration::from_secs(60),
+            ..DiscoveryCacheConfig::default()
         },
         ..SmartDiscoveryConfig::default()
     };
@@ -920,7 +954,9 @@ async fn test_smart_discovery_large_registry_simulation() {
             preferred_tools: None,
             confidence_threshold: Some(0.3),
             include_error_details: None,
-            sequential_mode: None
+            sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = smart_discovery.discover_and_execute(request).await.unwrap();
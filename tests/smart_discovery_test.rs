@@ -34,6 +34,8 @@ async fn test_service_creation_and_basic_properties() {
             timeout: 30,
             max_retries: 3,
             enabled: false, // Disable LLM for testing
+            api_version: None,
+            fallback: Vec::new(),
         },
         cache: DiscoveryCacheConfig::default(),
         ..SmartDiscoveryConfig::default()
@@ -78,6 +80,8 @@ async fn test_basic_discovery_requests() {
             timeout: 30,
             max_retries: 3,
             enabled: false,
+            api_version: None,
+            fallback: Vec::new(),
         },
         cache: DiscoveryCacheConfig::default(),
         ..SmartDiscoveryConfig::default()
@@ -94,6 +98,8 @@ async fn test_basic_discovery_requests() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         },
         SmartDiscoveryRequest {
             request: "request with context".to_string(),
@@ -102,6 +108,8 @@ async fn test_basic_discovery_requests() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         },
         SmartDiscoveryRequest {
             request: "request with preferences".to_string(),
@@ -110,6 +118,8 @@ async fn test_basic_discovery_requests() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         },
         SmartDiscoveryRequest {
             request: "request with custom threshold".to_string(),
@@ -118,6 +128,8 @@ async fn test_basic_discovery_requests() {
             confidence_threshold: Some(0.8),
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         },
     ];
     
@@ -154,6 +166,8 @@ async fn test_llm_parameter_mapping_comprehensive() {
             timeout: 30,
             max_retries: 3,
             enabled: false, // Use mock for testing
+            api_version: None,
+            fallback: Vec::new(),
         },
         cache: DiscoveryCacheConfig::default(),
         ..SmartDiscoveryConfig::default()
@@ -178,6 +192,8 @@ async fn test_llm_parameter_mapping_comprehensive() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = service.discover_and_execute(request).await.unwrap();
@@ -218,6 +234,8 @@ async fn test_fallback_strategies_comprehensive() {
             timeout: 30,
             max_retries: 3,
             enabled: false,
+            api_version: None,
+            fallback: Vec::new(),
         },
         cache: DiscoveryCacheConfig::default(),
         ..SmartDiscoveryConfig::default()
@@ -242,6 +260,8 @@ async fn test_fallback_strategies_comprehensive() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = service.discover_and_execute(request).await.unwrap();
@@ -277,6 +297,8 @@ async fn test_caching_comprehensive() {
             timeout: 30,
             max_retries: 3,
             enabled: false,
+            api_version: None,
+            fallback: Vec::new(),
         },
         cache: DiscoveryCacheConfig {
             enabled: true,
@@ -286,6 +308,7 @@ async fn test_caching_comprehensive() {
             llm_response_ttl: Duration::from_secs(600),
             max_registry_entries: 10,
             registry_ttl: Duration::from_secs(60),
+            ..DiscoveryCacheConfig::default()
         },
         ..SmartDiscoveryConfig::default()
     };
@@ -311,6 +334,8 @@ async fn test_caching_comprehensive() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = service.discover_and_execute(request).await.unwrap();
@@ -346,6 +371,8 @@ async fn test_caching_comprehensive() {
             confidence_threshold: Some(confidence),
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = service.discover_and_execute(request).await.unwrap();
@@ -374,6 +401,8 @@ async fn test_performance_and_concurrency() {
             timeout: 30,
             max_retries: 3,
             enabled: false,
+            api_version: None,
+            fallback: Vec::new(),
         },
         cache: DiscoveryCacheConfig::default(),
         ..SmartDiscoveryConfig::default()
@@ -395,6 +424,8 @@ async fn test_performance_and_concurrency() {
                 confidence_threshold: None,
                 include_error_details: None,
                 sequential_mode: None,
+                conversation: None,
+                exclusions: None,
             };
             
             let response = service_clone.discover_and_execute(request).await.unwrap();
@@ -447,6 +478,8 @@ async fn test_error_handling_comprehensive() {
             timeout: 30,
             max_retries: 3,
             enabled: false,
+            api_version: None,
+            fallback: Vec::new(),
         },
         cache: DiscoveryCacheConfig::default(),
         ..SmartDiscoveryConfig::default()
@@ -473,6 +506,8 @@ async fn test_error_handling_comprehensive() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = service.discover_and_execute(request).await.unwrap();
@@ -497,6 +532,8 @@ async fn test_error_handling_comprehensive() {
             confidence_threshold: Some(threshold),
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = service.discover_and_execute(request).await.unwrap();
@@ -526,6 +563,8 @@ async fn test_preferred_tools_comprehensive() {
             timeout: 30,
             max_retries: 3,
             enabled: false,
+            api_version: None,
+            fallback: Vec::new(),
         },
         cache: DiscoveryCacheConfig::default(),
         ..SmartDiscoveryConfig::default()
@@ -549,6 +588,8 @@ async fn test_preferred_tools_comprehensive() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = service.discover_and_execute(request).await.unwrap();
@@ -584,6 +625,8 @@ async fn test_context_usage_comprehensive() {
             timeout: 30,
             max_retries: 3,
             enabled: false,
+            api_version: None,
+            fallback: Vec::new(),
         },
         cache: DiscoveryCacheConfig::default(),
         ..SmartDiscoveryConfig::default()
@@ -609,6 +652,8 @@ async fn test_context_usage_comprehensive() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = service.discover_and_execute(request).await.unwrap();
@@ -656,6 +701,8 @@ async fn test_configuration_validation() {
             timeout: 60,
             max_retries: 5,
             enabled: false,
+            api_version: None,
+            fallback: Vec::new(),
         },
         cache: DiscoveryCacheConfig {
             enabled: true,
@@ -665,6 +712,7 @@ async fn test_configuration_validation() {
             llm_response_ttl: Duration::from_secs(300),
             max_registry_entries: 10,
             registry_ttl: Duration::from_secs(120),
+            ..DiscoveryCacheConfig::default()
         },
         ..SmartDiscoveryConfig::default()
     };
@@ -699,6 +747,8 @@ async fn test_service_monitoring_comprehensive() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let _response = service.discover_and_execute(request).await.unwrap();
@@ -765,6 +815,8 @@ async fn test_disabled_service_comprehensive() {
             timeout: 30,
             max_retries: 3,
             enabled: false,
+            api_version: None,
+            fallback: Vec::new(),
         },
         cache: DiscoveryCacheConfig::default(),
         ..SmartDiscoveryConfig::default()
@@ -791,6 +843,8 @@ async fn test_disabled_service_comprehensive() {
             confidence_threshold: None,
             include_error_details: None,
             sequential_mode: None,
+            conversation: None,
+            exclusions: None,
         };
         
         let response = service.discover_and_execute(request).await.unwrap();
@@ -809,4 +863,277 @@ async fn test_disabled_service_comprehensive() {
     // Cache should still work
     let cache_stats = service.get_cache_stats().await;
     assert!(cache_stats.get("enabled").is_some());
-}
\ No newline at end of file
+}
+/// Test recording feedback on discovery results
+#[test]
+async fn test_discovery_feedback() {
+    let config = Config::default();
+    let registry = Arc::new(RegistryService::new(config.registry.clone()).await.unwrap());
+    let tool_name = registry.list_tools().into_iter().next().expect("registry has tools");
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let discovery_config = SmartDiscoveryConfig {
+        feedback: DiscoveryFeedbackConfig {
+            storage_file: temp_dir.path().join("feedback.json"),
+            min_reports: 1,
+            ..DiscoveryFeedbackConfig::default()
+        },
+        ..SmartDiscoveryConfig::default()
+    };
+    let service = SmartDiscoveryService::new(registry, discovery_config).await.unwrap();
+
+    let feedback = |tool_name: &str, solved: bool| DiscoveryFeedback {
+        tool_name: tool_name.to_string(),
+        solved,
+        request: Some("do the thing".to_string()),
+        expected_tool: None,
+        comment: None,
+    };
+
+    let stats = service.record_feedback(feedback(&tool_name, false)).await.unwrap();
+    assert_eq!(stats.unsolved, 1);
+
+    let store = service.feedback().unwrap();
+    assert!(store.boosts().await[&tool_name] < 0.0);
+    assert_eq!(store.mismatched_tools().await[0].tool_name, tool_name);
+    assert!(temp_dir.path().join("feedback.json").exists());
+
+    // Feedback must name a registered tool
+    let error = service.record_feedback(feedback("no_such_tool", true)).await.unwrap_err();
+    assert!(error.to_string().contains("no_such_tool"));
+}
+
+/// Test planning a multi-step request into a chain of tool calls
+#[test]
+async fn test_plan_tool_chain() {
+    let config = Config::default();
+    let registry = Arc::new(RegistryService::new(config.registry.clone()).await.unwrap());
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let discovery_config = SmartDiscoveryConfig {
+        llm_mapper: LlmMapperConfig {
+            enabled: false,
+            ..LlmMapperConfig::default()
+        },
+        feedback: DiscoveryFeedbackConfig {
+            storage_file: temp_dir.path().join("feedback.json"),
+            ..DiscoveryFeedbackConfig::default()
+        },
+        max_chain_steps: 2,
+        ..SmartDiscoveryConfig::default()
+    };
+    let service = SmartDiscoveryService::new(registry, discovery_config).await.unwrap();
+
+    let request = SmartDiscoveryRequest {
+        request: "Find the pull request for issue 42 and then post a summary to Slack".to_string(),
+        context: None,
+        preferred_tools: None,
+        confidence_threshold: None,
+        include_error_details: None,
+        sequential_mode: None,
+        conversation: None,
+        exclusions: None,
+    };
+    let plan = service.plan_tool_chain(&request).await.unwrap();
+    assert_eq!(plan.steps.len(), 2);
+    assert_eq!(plan.steps[0].step, 1);
+    assert_eq!(plan.steps[0].request, "Find the pull request for issue 42");
+    assert_eq!(plan.steps[1].request, "post a summary to Slack");
+
+    // Steps beyond max_chain_steps are dropped
+    let request = SmartDiscoveryRequest {
+        request: "read config.yaml, then validate it, then upload it".to_string(),
+        ..request
+    };
+    assert_eq!(service.plan_tool_chain(&request).await.unwrap().steps.len(), 2);
+
+    // A single action is a one-step chain
+    let request = SmartDiscoveryRequest {
+        request: "read and parse the config file".to_string(),
+        ..request
+    };
+    assert_eq!(service.plan_tool_chain(&request).await.unwrap().steps.len(), 1);
+}
+
+/// Test the score breakdown returned for each discovery candidate
+#[test]
+async fn test_explain_discovery() {
+    let config = Config::default();
+    let registry = Arc::new(RegistryService::new(config.registry.clone()).await.unwrap());
+    let (tool_name, tool_def) = registry.get_enabled_tools().into_iter()
+        .find(|(name, tool_def)| !name.starts_with("smart_") && !tool_def.description.is_empty())
+        .expect("registry has tools");
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let discovery_config = SmartDiscoveryConfig {
+        llm_mapper: LlmMapperConfig {
+            enabled: false,
+            ..LlmMapperConfig::default()
+        },
+        feedback: DiscoveryFeedbackConfig {
+            storage_file: temp_dir.path().join("feedback.json"),
+            min_reports: 1,
+            ..DiscoveryFeedbackConfig::default()
+        },
+        default_confidence_threshold: 0.0,
+        ..SmartDiscoveryConfig::default()
+    };
+    let service = SmartDiscoveryService::new(registry, discovery_config).await.unwrap();
+
+    let request = SmartDiscoveryRequest {
+        request: tool_def.description.clone(),
+        context: None,
+        preferred_tools: None,
+        confidence_threshold: None,
+        include_error_details: None,
+        sequential_mode: None,
+        conversation: None,
+        exclusions: None,
+    };
+    let explanation = service.explain_discovery(&request).await.unwrap();
+    assert_eq!(explanation.tool_selection_mode, "rule_based");
+    assert!(explanation.selected_tool.is_some());
+    assert!(explanation.candidates.iter().all(|candidate| candidate.breakdown.rule_based.is_some()));
+    let candidate = explanation.candidates.iter().find(|candidate| candidate.tool_name == tool_name).unwrap();
+    assert!(candidate.breakdown.matched_metadata.iter().any(|m| m.field == "description"));
+
+    // Negative feedback shows up as a penalty
+    service.record_feedback(DiscoveryFeedback {
+        tool_name: tool_name.clone(),
+        solved: false,
+        request: None,
+        expected_tool: None,
+        comment: None,
+    }).await.unwrap();
+    let explanation = service.explain_discovery(&request).await.unwrap();
+    let candidate = explanation.candidates.iter().find(|candidate| candidate.tool_name == tool_name).unwrap();
+    assert!(candidate.breakdown.feedback_boost.unwrap() < 0.0);
+    assert!(!candidate.breakdown.penalties.is_empty());
+}
+
+/// Test that follow-up requests are resolved against the conversation
+#[test]
+async fn test_conversation_follow_up() {
+    let config = Config::default();
+    let registry = Arc::new(RegistryService::new(config.registry.clone()).await.unwrap());
+    let (tool_name, tool_def) = registry.get_enabled_tools().into_iter()
+        .find(|(name, tool_def)| !name.starts_with("smart_") && !tool_def.description.is_empty())
+        .expect("registry has tools");
+
+    let discovery_config = SmartDiscoveryConfig {
+        llm_mapper: LlmMapperConfig {
+            enabled: false,
+            ..LlmMapperConfig::default()
+        },
+        default_confidence_threshold: 0.0,
+        ..SmartDiscoveryConfig::default()
+    };
+    let service = SmartDiscoveryService::new(registry, discovery_config).await.unwrap();
+
+    let request: SmartDiscoveryRequest = serde_json::from_value(json!({
+        "request": "now do the same again",
+        "conversation": {
+            "messages": [{"role": "user", "content": tool_def.description}],
+            "tool_calls": [{"tool_name": tool_name, "arguments": {}, "request": tool_def.description}]
+        }
+    })).unwrap();
+    assert_eq!(request.conversation.as_ref().unwrap().tool_calls.len(), 1);
+
+    // The tool called last is considered, and its call is in the context scoring sees
+    let explanation = service.explain_discovery(&request).await.unwrap();
+    assert_eq!(explanation.request, "now do the same again");
+    assert!(explanation.candidates.iter().any(|candidate| candidate.tool_name == tool_name));
+}
+
+/// Test that excluded tools are never ranked, however well they match
+#[test]
+async fn test_exclusions_filter_before_ranking() {
+    let config = Config::default();
+    let registry = Arc::new(RegistryService::new(config.registry.clone()).await.unwrap());
+    let (tool_name, tool_def) = registry.get_enabled_tools().into_iter()
+        .find(|(name, tool_def)| !name.starts_with("smart_") && !tool_def.description.is_empty())
+        .expect("registry has tools");
+
+    let discovery_config = SmartDiscoveryConfig {
+        llm_mapper: LlmMapperConfig {
+            enabled: false,
+            ..LlmMapperConfig::default()
+        },
+        default_confidence_threshold: 0.0,
+        ..SmartDiscoveryConfig::default()
+    };
+    let service = SmartDiscoveryService::new(Arc::clone(&registry), discovery_config).await.unwrap();
+
+    let request: SmartDiscoveryRequest = serde_json::from_value(json!({"request": tool_def.description})).unwrap();
+    let explanation = service.explain_discovery(&request).await.unwrap();
+    assert!(explanation.candidates.iter().any(|candidate| candidate.tool_name == tool_name));
+
+    // Excluded by name, the best match for its own description is gone
+    let request: SmartDiscoveryRequest = serde_json::from_value(json!({
+        "request": tool_def.description,
+        "exclusions": {"exclude_tools": [tool_name]}
+    })).unwrap();
+    let explanation = service.explain_discovery(&request).await.unwrap();
+    assert!(explanation.candidates.iter().all(|candidate| candidate.tool_name != tool_name));
+
+    // Read-only requests only get tools annotated readOnlyHint
+    let request: SmartDiscoveryRequest = serde_json::from_value(json!({
+        "request": format!("{}, read-only", tool_def.description)
+    })).unwrap();
+    let explanation = service.explain_discovery(&request).await.unwrap();
+    assert!(explanation.candidates.iter().all(|candidate| {
+        registry.get_tool(&candidate.tool_name).map_or(false, |tool_def| tool_def.is_read_only())
+    }));
+}
+
+/// Test that low-confidence requests get a clarifying question instead of a guess
+#[test]
+async fn test_low_confidence_clarification() {
+    let config = Config::default();
+    let registry = Arc::new(RegistryService::new(config.registry.clone()).await.unwrap());
+    let (_, tool_def) = registry.get_enabled_tools().into_iter()
+        .find(|(name, tool_def)| !name.starts_with("smart_") && !tool_def.description.is_empty())
+        .expect("registry has tools");
+
+    let discovery_config = SmartDiscoveryConfig {
+        llm_mapper: LlmMapperConfig {
+            enabled: false,
+            ..LlmMapperConfig::default()
+        },
+        ..SmartDiscoveryConfig::default()
+    };
+    let service = SmartDiscoveryService::new(registry, discovery_config).await.unwrap();
+
+    let mut request = SmartDiscoveryRequest {
+        request: tool_def.description.clone(),
+        context: None,
+        preferred_tools: None,
+        confidence_threshold: Some(0.0),
+        include_error_details: None,
+        sequential_mode: Some(false),
+        conversation: None,
+        exclusions: None,
+    };
+    let best_score = service.explain_discovery(&request).await.unwrap().candidates.iter()
+        .map(|candidate| candidate.confidence_score)
+        .fold(0.0, f64::max);
+    if best_score >= 1.0 {
+        return;
+    }
+
+    // Nothing reaches the threshold, so discovery asks which tool was meant
+    request.confidence_threshold = Some((best_score + 1.0) / 2.0);
+    let response = service.discover_and_execute(request.clone()).await.unwrap();
+    assert!(!response.success);
+    assert!(response.metadata.original_tool.is_none());
+    let clarification = response.clarification.expect("clarifying question");
+    assert!(!clarification.candidates.is_empty());
+    assert_eq!(clarification.elicitation["requestedSchema"]["required"], json!(["tool"]));
+
+    // Answering with a preferred tool selects it despite the threshold
+    let chosen = clarification.candidates[0].tool_name.clone();
+    request.preferred_tools = Some(vec![chosen.clone()]);
+    let response = service.discover_and_execute(request).await.unwrap();
+    assert!(response.clarification.is_none());
+    assert_eq!(response.metadata.original_tool, Some(chosen));
+}
@@ -3,6 +3,19 @@ use magictunnel::routing::types::AgentType;
 use magictunnel::registry::RoutingConfig;
 use magictunnel::mcp::types::{ToolCall};
 use serde_json::json;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Serve `body` as an event stream at `/events/notifications`
+async fn mount_events(server: &MockServer, body: &str) {
+    Mock::given(method("GET"))
+        .and(path("/events/notifications"))
+        .and(header("Authorization", "Bearer secret123"))
+        .and(header("X-User-ID", "user456"))
+        .respond_with(ResponseTemplate::new(200).insert_header("Content-Type", "text/event-stream").set_body_string(body))
+        .mount(server)
+        .await;
+}
 
 #[tokio::test]
 async fn test_sse_agent_parsing() {
@@ -18,6 +31,8 @@ async fn test_sse_agent_parsing() {
             "max_events": 10,
             "event_filter": "message"
         }),
+        fallback: Vec::new(),
+        canary: None,
     };
 
     let router = DefaultAgentRouter::new();
@@ -45,6 +60,8 @@ async fn test_sse_agent_parsing_minimal() {
         config: json!({
             "url": "https://api.example.com/events"
         }),
+        fallback: Vec::new(),
+        canary: None,
     };
 
     let router = DefaultAgentRouter::new();
@@ -71,6 +88,8 @@ async fn test_sse_agent_parsing_missing_required_fields() {
                 "Authorization": "Bearer token123"
             }
         }),
+        fallback: Vec::new(),
+        canary: None,
     };
 
     let router = DefaultAgentRouter::new();
@@ -83,19 +102,19 @@ async fn test_sse_agent_parsing_missing_required_fields() {
 
 #[tokio::test]
 async fn test_sse_agent_parameter_substitution() {
-    let routing_config = RoutingConfig {
-        r#type: "sse".to_string(),
-        config: json!({
-            "url": "https://api.example.com/events/{{channel}}",
-            "headers": {
-                "Authorization": "Bearer {{token}}",
-                "X-User-ID": "{{user_id}}"
-            },
-            "timeout": 45,
-            "max_events": 20,
-            "event_filter": "{{event_type}}"
-        }),
-    };
+    let server = MockServer::start().await;
+    mount_events(&server, "event: update\ndata: {\"n\": 1}\n\n").await;
+
+    let routing_config = RoutingConfig::new("sse".to_string(), json!({
+        "url": format!("{}/events/{{{{channel}}}}", server.uri()),
+        "headers": {
+            "Authorization": "Bearer {{token}}",
+            "X-User-ID": "{{user_id}}"
+        },
+        "timeout": 45,
+        "max_events": 20,
+        "event_filter": "{{event_type}}"
+    }));
 
     let tool_call = ToolCall {
         name: "sse_subscribe".to_string(),
@@ -111,36 +130,41 @@ async fn test_sse_agent_parameter_substitution() {
     let agent_type = router.parse_routing_config(&routing_config).unwrap();
     let result = router.execute_with_agent(&tool_call, &agent_type).await.unwrap();
 
-    assert!(result.success);
-    assert!(result.data.is_some());
-    
+    assert!(result.success, "{:?}", result.error);
+    let url = format!("{}/events/notifications", server.uri());
     let data = result.data.unwrap();
-    assert_eq!(data["status"], "success");
-    assert_eq!(data["url"], "https://api.example.com/events/notifications");
-    assert_eq!(data["event_filter"], "update");
-    
+    assert_eq!(data["url"], url);
+    assert_eq!(data["events"], json!([{"id": null, "event": "update", "data": {"n": 1}}]));
+
     // Check metadata
     let metadata = result.metadata.unwrap();
     assert_eq!(metadata["execution_type"], "sse");
-    assert_eq!(metadata["url"], "https://api.example.com/events/notifications");
+    assert_eq!(metadata["url"], url);
     assert_eq!(metadata["max_events"], 20);
     assert_eq!(metadata["event_filter"], "update");
 }
 
 #[tokio::test]
-async fn test_sse_agent_execution_mock() {
-    let routing_config = RoutingConfig {
-        r#type: "sse".to_string(),
-        config: json!({
-            "url": "https://api.example.com/events",
-            "headers": {
-                "Authorization": "Bearer token123"
-            },
-            "timeout": 30,
-            "max_events": 5,
-            "event_filter": "message"
-        }),
-    };
+async fn test_sse_agent_collects_filtered_events_up_to_max() {
+    let server = MockServer::start().await;
+    mount_events(&server, concat!(
+        ": connected\n\n",
+        "id: 1\nevent: message\ndata: first\n\n",
+        "id: 2\nevent: heartbeat\ndata: ping\n\n",
+        "id: 3\nevent: message\ndata: second\n\n",
+        "id: 4\nevent: message\ndata: third\n\n",
+    )).await;
+
+    let routing_config = RoutingConfig::new("sse".to_string(), json!({
+        "url": format!("{}/events/notifications", server.uri()),
+        "headers": {
+            "Authorization": "Bearer secret123",
+            "X-User-ID": "user456"
+        },
+        "timeout": 30,
+        "max_events": 2,
+        "event_filter": "message"
+    }));
 
     let tool_call = ToolCall {
         name: "sse_subscribe".to_string(),
@@ -151,22 +175,33 @@ async fn test_sse_agent_execution_mock() {
     let agent_type = router.parse_routing_config(&routing_config).unwrap();
     let result = router.execute_with_agent(&tool_call, &agent_type).await.unwrap();
 
-    assert!(result.success);
-    assert!(result.data.is_some());
-    
+    assert!(result.success, "{:?}", result.error);
     let data = result.data.unwrap();
-    assert_eq!(data["status"], "success");
-    assert_eq!(data["url"], "https://api.example.com/events");
-    assert!(data["events"].is_array());
-    assert_eq!(data["event_count"], 2); // Mock returns 2 events
-    assert_eq!(data["max_events"], 5);
-    assert_eq!(data["event_filter"], "message");
-    
-    // Verify mock events structure
-    let events = data["events"].as_array().unwrap();
-    assert_eq!(events.len(), 2);
-    assert!(events[0]["id"].is_string());
-    assert!(events[0]["event"].is_string());
-    assert!(events[0]["data"].is_string());
-    assert!(events[0]["timestamp"].is_string());
+    assert_eq!(data["event_count"], 2);
+    assert_eq!(data["events"], json!([
+        {"id": "1", "event": "message", "data": "first"},
+        {"id": "3", "event": "message", "data": "second"},
+    ]));
+}
+
+#[tokio::test]
+async fn test_sse_agent_reports_http_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+
+    let routing_config = RoutingConfig::new("sse".to_string(), json!({"url": format!("{}/events", server.uri())}));
+    let tool_call = ToolCall {
+        name: "sse_subscribe".to_string(),
+        arguments: json!({}),
+    };
+
+    let router = DefaultAgentRouter::new();
+    let agent_type = router.parse_routing_config(&routing_config).unwrap();
+    let result = router.execute_with_agent(&tool_call, &agent_type).await.unwrap();
+
+    assert!(!result.success);
+    assert!(result.error.unwrap().contains("HTTP 503"));
 }
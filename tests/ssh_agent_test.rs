@@ -0,0 +1,126 @@
+use magictunnel::mcp::types::ToolCall;
+use magictunnel::registry::RoutingConfig;
+use magictunnel::routing::agent_router::{AgentRouter, DefaultAgentRouter};
+use magictunnel::routing::types::AgentType;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_ssh_agent_parsing() {
+    let routing_config = RoutingConfig::new("ssh".to_string(), json!({
+        "host": "{{host}}",
+        "user": "deploy",
+        "port": 2222,
+        "command": "df -h {{path}}",
+        "identity_file": "~/.ssh/id_ed25519",
+        "known_hosts_file": "/etc/magictunnel/known_hosts",
+        "connect_timeout": 5,
+        "timeout": 20,
+        "max_output_bytes": 4096
+    }));
+
+    let router = DefaultAgentRouter::new();
+    match router.parse_routing_config(&routing_config).unwrap() {
+        AgentType::Ssh { command, timeout, max_output_bytes, connection } => {
+            assert_eq!(command, "df -h {{path}}");
+            assert_eq!(timeout, Some(20));
+            assert_eq!(max_output_bytes, 4096);
+            assert_eq!(connection.host, "{{host}}");
+            assert_eq!(connection.user.as_deref(), Some("deploy"));
+            assert_eq!(connection.port, Some(2222));
+            assert_eq!(connection.known_hosts_file.as_deref(), Some("/etc/magictunnel/known_hosts"));
+            assert_eq!(connection.connect_timeout, Some(5));
+            assert!(connection.use_agent);
+            assert_eq!(connection.ssh_binary, "ssh");
+        }
+        _ => panic!("Expected SSH agent type"),
+    }
+}
+
+#[tokio::test]
+async fn test_ssh_agent_parsing_errors() {
+    let router = DefaultAgentRouter::new();
+
+    let result = router.parse_routing_config(&RoutingConfig::new("ssh".to_string(), json!({"host": "build-1"})));
+    assert!(result.unwrap_err().to_string().contains("command"));
+
+    let result = router.parse_routing_config(&RoutingConfig::new("ssh".to_string(), json!({"command": "uptime"})));
+    assert!(result.is_err());
+}
+
+/// Fake ssh client that prints each argument on its own line
+#[cfg(unix)]
+fn fake_ssh(dir: &std::path::Path, body: &str) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join("ssh");
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path.display().to_string()
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_ssh_agent_quotes_arguments() {
+    let dir = tempfile::tempdir().unwrap();
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("ssh".to_string(), json!({
+        "host": "{{host}}",
+        "user": "deploy",
+        "command": "tail -n {{lines}} {{path}}",
+        "ssh_binary": fake_ssh(dir.path(), r#"for arg in "$@"; do echo "$arg"; done"#)
+    }))).unwrap();
+    let tool_call = ToolCall {
+        name: "tail_log".to_string(),
+        arguments: json!({"host": "app-1", "lines": 5, "path": "/var/log/app.log; reboot"}),
+    };
+
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    let data = result.data.unwrap();
+    let args: Vec<&str> = data["stdout"].as_str().unwrap().lines().collect();
+    assert!(args.contains(&"StrictHostKeyChecking=yes"));
+    assert_eq!(args[args.len() - 3..], ["--", "app-1", "tail -n '5' '/var/log/app.log; reboot'"]);
+    assert_eq!(data["truncated"], false);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_ssh_agent_limits_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("ssh".to_string(), json!({
+        "host": "app-1",
+        "command": "yes",
+        "max_output_bytes": 16,
+        "ssh_binary": fake_ssh(dir.path(), "head -c 100000 /dev/zero | tr '\\0' x")
+    }))).unwrap();
+    let tool_call = ToolCall {
+        name: "spam".to_string(),
+        arguments: json!({}),
+    };
+
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    let data = result.data.unwrap();
+    assert_eq!(data["stdout"], "x".repeat(16));
+    assert_eq!(data["truncated"], true);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_ssh_agent_reports_connection_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("ssh".to_string(), json!({
+        "host": "app-1",
+        "command": "uptime",
+        "ssh_binary": fake_ssh(dir.path(), "echo 'Host key verification failed.' >&2; exit 255")
+    }))).unwrap();
+    let tool_call = ToolCall {
+        name: "uptime".to_string(),
+        arguments: json!({}),
+    };
+
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.error.as_deref(), Some("SSH connection failed: Host key verification failed."));
+}
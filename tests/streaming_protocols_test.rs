@@ -25,7 +25,14 @@ fn create_test_registry_config() -> RegistryConfig {
         validation: magictunnel::config::ValidationConfig {
             strict: false,
             allow_unknown_fields: true,
+            output: Default::default(),
         },
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     }
 }
 
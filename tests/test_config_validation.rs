@@ -74,6 +74,12 @@ fn test_registry_config_validation() {
         paths: vec![temp_path.clone()],
         hot_reload: true,
         validation: ValidationConfig::default(),
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
     assert!(valid_config.validate().is_ok());
 
@@ -83,6 +89,12 @@ fn test_registry_config_validation() {
         paths: vec![],
         hot_reload: true,
         validation: ValidationConfig::default(),
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
     assert!(invalid_config.validate().is_err());
 
@@ -92,6 +104,12 @@ fn test_registry_config_validation() {
         paths: vec![temp_path],
         hot_reload: true,
         validation: ValidationConfig::default(),
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
     assert!(invalid_config.validate().is_err());
 
@@ -101,6 +119,12 @@ fn test_registry_config_validation() {
         paths: vec!["../dangerous/path".to_string()],
         hot_reload: true,
         validation: ValidationConfig::default(),
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
     assert!(invalid_config.validate().is_err());
 }
@@ -111,6 +135,7 @@ fn test_auth_config_validation() {
     let valid_config = AuthConfig {
         enabled: true,
         r#type: magictunnel::config::AuthType::ApiKey,
+        methods: Vec::new(),
         api_keys: Some(magictunnel::config::ApiKeyConfig {
             keys: vec![magictunnel::config::ApiKeyEntry::new(
                 "valid_api_key_123456".to_string(),
@@ -119,9 +144,19 @@ fn test_auth_config_validation() {
             require_header: true,
             header_name: "Authorization".to_string(),
             header_format: "Bearer {key}".to_string(),
+            rotation: Default::default(),
         }),
         oauth: None,
         jwt: None,
+        client_cert: None,
+        hmac: None,
+        resource_server: None,
+        client_registration: None,
+        roles: Default::default(),
+        role_mappings: vec![],
+        guest: None,
+        tenants: vec![],
+        token_encryption: None,
     };
     assert!(valid_config.validate().is_ok());
 
@@ -129,9 +164,19 @@ fn test_auth_config_validation() {
     let invalid_config = AuthConfig {
         enabled: true,
         r#type: magictunnel::config::AuthType::ApiKey,
+        methods: Vec::new(),
         api_keys: None,
         oauth: None,
         jwt: None,
+        client_cert: None,
+        hmac: None,
+        resource_server: None,
+        client_registration: None,
+        roles: Default::default(),
+        role_mappings: vec![],
+        guest: None,
+        tenants: vec![],
+        token_encryption: None,
     };
     assert!(invalid_config.validate().is_err());
 
@@ -139,6 +184,7 @@ fn test_auth_config_validation() {
     let invalid_config = AuthConfig {
         enabled: true,
         r#type: magictunnel::config::AuthType::ApiKey,
+        methods: Vec::new(),
         api_keys: Some(magictunnel::config::ApiKeyConfig {
             keys: vec![magictunnel::config::ApiKeyEntry::new(
                 "short".to_string(),
@@ -147,19 +193,53 @@ fn test_auth_config_validation() {
             require_header: true,
             header_name: "Authorization".to_string(),
             header_format: "Bearer {key}".to_string(),
+            rotation: Default::default(),
         }),
         oauth: None,
         jwt: None,
+        client_cert: None,
+        hmac: None,
+        resource_server: None,
+        client_registration: None,
+        roles: Default::default(),
+        role_mappings: vec![],
+        guest: None,
+        tenants: vec![],
+        token_encryption: None,
     };
     assert!(invalid_config.validate().is_err());
 
+    // Test API key scopes
+    let mut scoped_key = magictunnel::config::ApiKeyEntry::new(
+        "valid_api_key_123456".to_string(),
+        "Scoped Key".to_string()
+    );
+    scoped_key.scopes = vec!["tool:github_*".to_string(), "capability:billing".to_string()];
+    let mut scoped_config = valid_config.clone();
+    scoped_config.api_keys.as_mut().unwrap().keys = vec![scoped_key.clone()];
+    assert!(scoped_config.validate().is_ok());
+
+    scoped_key.scopes = vec!["server:github".to_string()];
+    scoped_config.api_keys.as_mut().unwrap().keys = vec![scoped_key];
+    assert!(scoped_config.validate().is_err());
+
     // Test OAuth auth without config
     let invalid_config = AuthConfig {
         enabled: true,
         r#type: magictunnel::config::AuthType::OAuth,
+        methods: Vec::new(),
         api_keys: None,
         oauth: None,
         jwt: None,
+        client_cert: None,
+        hmac: None,
+        resource_server: None,
+        client_registration: None,
+        roles: Default::default(),
+        role_mappings: vec![],
+        guest: None,
+        tenants: vec![],
+        token_encryption: None,
     };
     assert!(invalid_config.validate().is_err());
 
@@ -170,15 +250,236 @@ fn test_auth_config_validation() {
         client_secret: "secret123".to_string(),
         auth_url: "https://accounts.google.com/oauth/authorize".to_string(),
         token_url: "https://oauth2.googleapis.com/token".to_string(),
+        device_auth_url: None,
     };
     let valid_config = AuthConfig {
         enabled: true,
         r#type: magictunnel::config::AuthType::OAuth,
+        methods: Vec::new(),
         api_keys: None,
         oauth: Some(oauth_config),
         jwt: None,
+        client_cert: None,
+        hmac: None,
+        resource_server: None,
+        client_registration: None,
+        roles: Default::default(),
+        role_mappings: vec![],
+        guest: None,
+        tenants: vec![],
+        token_encryption: None,
     };
     assert!(valid_config.validate().is_ok());
+
+    // Test client certificate auth without config
+    let invalid_config = AuthConfig {
+        enabled: true,
+        r#type: magictunnel::config::AuthType::ClientCert,
+        methods: Vec::new(),
+        api_keys: None,
+        oauth: None,
+        jwt: None,
+        client_cert: None,
+        hmac: None,
+        resource_server: None,
+        client_registration: None,
+        roles: Default::default(),
+        role_mappings: vec![],
+        guest: None,
+        tenants: vec![],
+        token_encryption: None,
+    };
+    assert!(invalid_config.validate().is_err());
+
+    // Test valid client certificate config
+    let mapping = magictunnel::config::ClientCertMapping {
+        name: "Billing workers".to_string(),
+        subject: Some("billing-*".to_string()),
+        san: None,
+        permissions: vec!["read".to_string()],
+        toolset: None,
+    };
+    let mut valid_config = AuthConfig {
+        enabled: true,
+        r#type: magictunnel::config::AuthType::ClientCert,
+        methods: Vec::new(),
+        api_keys: None,
+        oauth: None,
+        jwt: None,
+        client_cert: Some(magictunnel::config::ClientCertConfig {
+            forwarded_header: "X-Client-Cert".to_string(),
+            mappings: vec![mapping.clone()],
+        }),
+        hmac: None,
+        resource_server: None,
+        client_registration: None,
+        roles: Default::default(),
+        role_mappings: vec![],
+        guest: None,
+        tenants: vec![],
+        token_encryption: None,
+    };
+    assert!(valid_config.validate().is_ok());
+
+    // Test client certificate mapping without a pattern
+    valid_config.client_cert.as_mut().unwrap().mappings = vec![magictunnel::config::ClientCertMapping {
+        subject: None,
+        ..mapping
+    }];
+    assert!(valid_config.validate().is_err());
+}
+
+#[test]
+fn test_resource_server_config_validation() {
+    let mut config = AuthConfig::default();
+    config.enabled = true;
+    config.resource_server = Some(magictunnel::config::ResourceServerConfig {
+        resource: Some("https://mcp.example.com/mcp".to_string()),
+        authorization_servers: vec!["https://login.example.com".to_string()],
+        ..Default::default()
+    });
+    assert!(config.validate().is_ok());
+
+    // Resource URIs carry no fragment
+    config.resource_server.as_mut().unwrap().resource = Some("https://mcp.example.com/mcp#tools".to_string());
+    assert!(config.validate().is_err());
+
+    config.resource_server.as_mut().unwrap().resource = Some("mcp.example.com".to_string());
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_tenant_auth_config_validation() {
+    let mut config: AuthConfig = serde_yaml::from_str(r#"
+enabled: false
+type: none
+tenants:
+  - name: team-a
+    hosts: ["team-a.example.com"]
+    enabled: true
+    type: api_key
+    api_keys:
+      keys:
+        - key: "team_a_key_1234567890"
+          name: "Team A"
+          permissions: ["read"]
+          active: true
+      require_header: true
+      header_name: "Authorization"
+      header_format: "Bearer {key}"
+      rotation:
+        state_path: "./.magictunnel/team-a-keys.json"
+    roles:
+      analyst: ["read"]
+"#).unwrap();
+    assert!(config.validate().is_ok());
+    assert!(config.any_enabled());
+    assert!(config.tenants[0].matches("Team-A.example.com:8080", "/mcp"));
+    assert!(!config.tenants[0].matches("team-b.example.com", "/mcp"));
+
+    // Errors in a tenant's configuration name the tenant
+    config.tenants[0].auth.api_keys.as_mut().unwrap().keys.clear();
+    let error = config.validate().unwrap_err().to_string();
+    assert!(error.contains("Tenant 'team-a'"), "{}", error);
+
+    // Tenants need a host or path prefix, and their own key state
+    let mut tenant = config.tenants[0].clone();
+    tenant.auth.api_keys.as_mut().unwrap().keys.push(magictunnel::config::ApiKeyEntry::new(
+        "team_b_key_1234567890".to_string(),
+        "Team B".to_string(),
+    ));
+    tenant.hosts.clear();
+    config.tenants = vec![tenant.clone()];
+    assert!(config.validate().is_err());
+
+    tenant.path_prefix = Some("/team-b".to_string());
+    config.tenants = vec![tenant.clone()];
+    assert!(config.validate().is_ok());
+    assert!(tenant.matches("any.example.com", "/team-b/mcp"));
+    assert!(!tenant.matches("any.example.com", "/team-bc/mcp"));
+
+    config.tenants = vec![tenant.clone(), magictunnel::config::TenantAuthConfig { name: "team-c".to_string(), ..tenant }];
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_guest_access_config_validation() {
+    let mut config = AuthConfig::default();
+    config.enabled = true;
+    config.guest = Some(magictunnel::config::GuestAccessConfig {
+        enabled: true,
+        toolset: Some("docs".to_string()),
+        scopes: vec![],
+        permissions: vec!["read".to_string()],
+    });
+    assert!(config.validate().is_ok());
+
+    // Guests are always restricted, and never administrators
+    config.guest.as_mut().unwrap().toolset = None;
+    assert!(config.validate().is_err());
+    let guest = config.guest.as_mut().unwrap();
+    guest.scopes = vec!["docs_*".to_string()];
+    guest.permissions.push("admin".to_string());
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_role_mapping_validation() {
+    let mut config = AuthConfig::default();
+    config.enabled = true;
+    config.roles.insert("operator".to_string(), vec!["read".to_string(), "write".to_string()]);
+    config.role_mappings = vec![magictunnel::config::RoleMappingConfig {
+        roles: vec!["operator".to_string()],
+        scopes: vec![],
+        groups: vec!["platform-team".to_string()],
+        orgs: vec![],
+        email_domains: vec![],
+    }];
+    assert!(config.validate().is_ok());
+
+    // Mappings grant defined roles, and only to users matching some criterion
+    config.role_mappings[0].roles.push("undefined".to_string());
+    assert!(config.validate().is_err());
+    config.role_mappings[0].roles.pop();
+    config.role_mappings[0].groups.clear();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_auth_method_chain_validation() {
+    use magictunnel::config::{AuthType, HmacClientConfig, HmacConfig};
+
+    let mut config = AuthConfig::default();
+    config.enabled = true;
+    config.r#type = AuthType::ApiKey;
+    config.methods = vec![AuthType::Hmac, AuthType::ApiKey];
+    config.api_keys = Some(magictunnel::config::ApiKeyConfig {
+        keys: vec![magictunnel::config::ApiKeyEntry::new(
+            "chain_test_key_123456789".to_string(),
+            "Chain Test".to_string(),
+        )],
+        ..Default::default()
+    });
+    // Every method in the chain must be configured
+    assert!(config.validate().is_err());
+
+    config.hmac = Some(HmacConfig {
+        clients: vec![HmacClientConfig {
+            name: "webhooks".to_string(),
+            secret: "webhooks_secret_at_least_32_characters".to_string(),
+            permissions: vec!["read".to_string()],
+            toolset: None,
+        }],
+        max_age_seconds: 300,
+    });
+    assert!(config.validate().is_ok());
+    assert!(config.uses(&AuthType::Hmac));
+    assert!(!config.uses(&AuthType::Jwt));
+
+    config.methods.push(AuthType::Hmac);
+    assert!(config.validate().is_err());
+    config.methods = vec![AuthType::None];
+    assert!(config.validate().is_err());
 }
 
 #[test]
@@ -267,6 +568,22 @@ fn test_cross_dependency_validation() {
         conflict_resolution: None,
         visibility: None,
         smart_discovery: None,
+        rate_limiting: None,
+        timeouts: None,
+        dry_run: None,
+        jobs: None,
+        costs: None,
+        sessions: None,
+        policy: None,
+        secret_scanning: None,
+        pii: None,
+        approvals: None,
+        access_windows: None,
+        quotas: None,
+        audit: None,
+        threat_detection: None,
+        network_policy: None,
+        circuit_breakers: None,
     };
     assert!(invalid_config.validate().is_err());
 
@@ -277,14 +594,25 @@ fn test_cross_dependency_validation() {
         auth: Some(AuthConfig {
             enabled: true,
             r#type: magictunnel::config::AuthType::ApiKey,
+            methods: Vec::new(),
             api_keys: Some(magictunnel::config::ApiKeyConfig {
                 keys: vec![], // Empty keys
                 require_header: true,
                 header_name: "Authorization".to_string(),
                 header_format: "Bearer {key}".to_string(),
+                rotation: Default::default(),
             }),
             oauth: None,
             jwt: None,
+            client_cert: None,
+            hmac: None,
+            resource_server: None,
+            client_registration: None,
+            roles: Default::default(),
+            role_mappings: vec![],
+            guest: None,
+            tenants: vec![],
+            token_encryption: None,
         }),
         logging: None,
         external_mcp: None,
@@ -292,6 +620,22 @@ fn test_cross_dependency_validation() {
         conflict_resolution: None,
         visibility: None,
         smart_discovery: None,
+        rate_limiting: None,
+        timeouts: None,
+        dry_run: None,
+        jobs: None,
+        costs: None,
+        sessions: None,
+        policy: None,
+        secret_scanning: None,
+        pii: None,
+        approvals: None,
+        access_windows: None,
+        quotas: None,
+        audit: None,
+        threat_detection: None,
+        network_policy: None,
+        circuit_breakers: None,
     };
     assert!(invalid_config.validate().is_err());
 }
@@ -311,6 +655,12 @@ fn test_file_structure_validation() {
         paths: vec![yaml_file.to_string_lossy().to_string()],
         hot_reload: true,
         validation: ValidationConfig::default(),
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
     assert!(config.validate().is_ok());
 
@@ -323,6 +673,12 @@ fn test_file_structure_validation() {
         paths: vec![txt_file.to_string_lossy().to_string()],
         hot_reload: true,
         validation: ValidationConfig::default(),
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
     assert!(config.validate().is_err());
 
@@ -332,6 +688,12 @@ fn test_file_structure_validation() {
         paths: vec![format!("{}/*.yaml", temp_path.to_string_lossy())],
         hot_reload: true,
         validation: ValidationConfig::default(),
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
     assert!(config.validate().is_ok());
 }
@@ -345,6 +707,9 @@ fn test_external_mcp_config_validation() {
         capabilities_output_dir: "./capabilities".to_string(),
         refresh_interval_minutes: 60,
         containers: None,
+        circuit_breaker: Default::default(),
+        startup_concurrency: 4,
+        injection_scan: Default::default(),
     };
     // Note: ExternalMcpConfig doesn't have a validate method in the current implementation
     // Validation is done at the overall Config level
@@ -362,6 +727,9 @@ fn test_external_mcp_config_validation() {
             network_mode: Some("bridge".to_string()),
             run_args: vec!["--rm".to_string()],
         }),
+        circuit_breaker: Default::default(),
+        startup_concurrency: 4,
+        injection_scan: Default::default(),
     };
     // This should be valid
 
@@ -372,6 +740,9 @@ fn test_external_mcp_config_validation() {
         capabilities_output_dir: "./capabilities".to_string(),
         refresh_interval_minutes: 60,
         containers: None,
+        circuit_breaker: Default::default(),
+        startup_concurrency: 4,
+        injection_scan: Default::default(),
     };
     // This should be valid even when disabled
 }
@@ -384,6 +755,7 @@ fn test_mcp_server_config_validation() {
         args: vec!["-y".to_string(), "@modelcontextprotocol/server-filesystem".to_string(), "/tmp".to_string()],
         env: None,
         cwd: None,
+        ssh: None,
     };
     // Note: McpServerConfig doesn't have a validate method in the new format
     // It's validated as part of the overall configuration
@@ -394,6 +766,7 @@ fn test_mcp_server_config_validation() {
         args: vec!["arg1".to_string()],
         env: None,
         cwd: None,
+        ssh: None,
     };
     // This would be caught during configuration validation
 
@@ -406,6 +779,7 @@ fn test_mcp_server_config_validation() {
         args: vec!["server.js".to_string()],
         env: Some(env_vars),
         cwd: Some("/app".to_string()),
+        ssh: None,
     };
     // This should be valid
 
@@ -415,6 +789,7 @@ fn test_mcp_server_config_validation() {
         args: vec!["-m", "mcp_server"].iter().map(|s| s.to_string()).collect(),
         env: None,
         cwd: Some("/home/user/mcp".to_string()),
+        ssh: None,
     };
     // This should be valid
 }
@@ -0,0 +1,67 @@
+//! Tests for the timeout hierarchy applied by the Router
+
+use async_trait::async_trait;
+use magictunnel::error::Result;
+use magictunnel::mcp::{Tool, ToolCall};
+use magictunnel::registry::{RoutingConfig, ToolDefinition};
+use magictunnel::routing::timeout::{self, TimeoutConfig};
+use magictunnel::routing::types::{AgentResult, AgentType};
+use magictunnel::routing::{AgentRouter, Router};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Agent router that returns the timeout its agent was given
+struct TimeoutEchoAgentRouter;
+
+#[async_trait]
+impl AgentRouter for TimeoutEchoAgentRouter {
+    fn parse_routing_config(&self, routing: &RoutingConfig) -> Result<AgentType> {
+        Ok(AgentType::Llm {
+            provider: "openai".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            api_key: None,
+            base_url: None,
+            timeout: routing.config.get("timeout").and_then(|v| v.as_u64()),
+        })
+    }
+
+    async fn execute_with_agent(&self, _tool_call: &ToolCall, agent: &AgentType) -> Result<AgentResult> {
+        let AgentType::Llm { timeout, .. } = agent else {
+            unreachable!();
+        };
+        Ok(AgentResult { success: true, data: Some(json!({"timeout": timeout})), error: None, metadata: None })
+    }
+}
+
+fn tool(name: &str, config: Value) -> ToolDefinition {
+    let tool = Tool::new(name.to_string(), "Test tool".to_string(), json!({"type": "object"})).unwrap();
+    ToolDefinition::new(tool, RoutingConfig::new("llm".to_string(), config)).unwrap()
+}
+
+async fn timeout_of(router: &Router, tool: &ToolDefinition) -> (Value, Value) {
+    let call = ToolCall { name: tool.name.clone(), arguments: json!({}) };
+    let result = router.route(&call, tool).await.unwrap();
+    (result.data.unwrap()["timeout"].clone(), result.metadata.unwrap()["timeout"].clone())
+}
+
+#[tokio::test]
+async fn test_timeout_levels() {
+    let router = Router::with_agent_router(Arc::new(TimeoutEchoAgentRouter));
+    let mut config = TimeoutConfig::default();
+    config.per_agent_type.insert("llm".to_string(), 90);
+    config.per_tool.insert("report".to_string(), 240);
+    router.configure_timeouts(config);
+
+    let chat = tool("chat", json!({"provider": "openai", "model": "gpt-4o-mini"}));
+    assert_eq!(timeout_of(&router, &chat).await, (json!(90), json!({"seconds": 90, "source": "agent_type"})));
+
+    let summarize = tool("summarize", json!({"provider": "openai", "model": "gpt-4o-mini", "timeout": 45}));
+    assert_eq!(timeout_of(&router, &summarize).await.1, json!({"seconds": 45, "source": "tool"}));
+
+    let report = tool("report", json!({"provider": "openai", "model": "gpt-4o-mini", "timeout": 45}));
+    assert_eq!(timeout_of(&router, &report).await.0, json!(240));
+
+    let (timeout, metadata) = timeout::with_call_timeout(Some(900), timeout_of(&router, &report)).await;
+    assert_eq!(timeout, json!(300));
+    assert_eq!(metadata, json!({"seconds": 300, "source": "call"}));
+}
@@ -0,0 +1,103 @@
+use magictunnel::auth::{on_behalf_of, TokenExchangeConfig};
+use magictunnel::mcp::types::ToolCall;
+use magictunnel::registry::RoutingConfig;
+use magictunnel::routing::agent_router::{AgentRouter, DefaultAgentRouter};
+use serde_json::json;
+use wiremock::matchers::{body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Identity provider exchanging `subject` for `issued`
+async fn mount_exchange(server: &MockServer, subject: &str, audience: &str, issued: &str) {
+    Mock::given(method("POST"))
+        .and(path("/oauth/token"))
+        .and(body_string_contains("grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Atoken-exchange"))
+        .and(body_string_contains(format!("subject_token={}", subject)))
+        .and(body_string_contains(format!("audience={}", audience)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": issued,
+            "issued_token_type": "urn:ietf:params:oauth:token-type:access_token",
+            "token_type": "Bearer",
+            "expires_in": 3600
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+fn exchange_config(server: &MockServer, audience: &str) -> TokenExchangeConfig {
+    serde_json::from_value(json!({
+        "token_url": format!("{}/oauth/token", server.uri()),
+        "client_id": "magictunnel",
+        "client_secret": "exchange-secret",
+        "audience": audience
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_token_exchange_is_cached_per_user() {
+    let idp = MockServer::start().await;
+    mount_exchange(&idp, "alice-token", "crm", "alice-crm-token").await;
+    mount_exchange(&idp, "bob-token", "crm", "bob-crm-token").await;
+
+    let config = exchange_config(&idp, "crm");
+    for _ in 0..2 {
+        let token = on_behalf_of(Some("alice-token".to_string()), config.access_token()).await.unwrap();
+        assert_eq!(token, "alice-crm-token");
+    }
+    let token = on_behalf_of(Some("bob-token".to_string()), config.access_token()).await.unwrap();
+    assert_eq!(token, "bob-crm-token");
+}
+
+#[tokio::test]
+async fn test_token_exchange_rejected() {
+    let idp = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/oauth/token"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": "invalid_grant",
+            "error_description": "subject token expired"
+        })))
+        .mount(&idp)
+        .await;
+
+    let config = exchange_config(&idp, "crm");
+    let error = on_behalf_of(Some("expired-token".to_string()), config.access_token()).await.unwrap_err();
+    assert!(error.to_string().contains("invalid_grant: subject token expired"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_http_agent_calls_on_behalf_of_user() {
+    let idp = MockServer::start().await;
+    mount_exchange(&idp, "alice-token", "billing", "alice-billing-token").await;
+
+    let upstream = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/invoices"))
+        .and(header("Authorization", "Bearer alice-billing-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"invoices": []})))
+        .mount(&upstream)
+        .await;
+
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig {
+        r#type: "http".to_string(),
+        config: json!({
+            "method": "GET",
+            "url": format!("{}/invoices", upstream.uri()),
+            "token_exchange": exchange_config(&idp, "billing")
+        }),
+        fallback: Vec::new(),
+        canary: None,
+    }).unwrap();
+    let tool_call = ToolCall {
+        name: "list_invoices".to_string(),
+        arguments: json!({}),
+    };
+
+    let result = on_behalf_of(Some("alice-token".to_string()), router.execute_with_agent(&tool_call, &agent)).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+
+    // Without a user token and no fallback token, the call is not made
+    assert!(router.execute_with_agent(&tool_call, &agent).await.is_err());
+}
@@ -0,0 +1,147 @@
+//! Tests for per-tool result caching in the Router
+
+use async_trait::async_trait;
+use magictunnel::auth::on_behalf_of;
+use magictunnel::error::Result;
+use magictunnel::mcp::{Tool, ToolCall};
+use magictunnel::registry::{RoutingConfig, ToolCacheConfig, ToolDefinition};
+use magictunnel::routing::{cache, cost};
+use magictunnel::routing::types::{AgentResult, AgentType};
+use magictunnel::routing::{AgentRouter, Router};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Agent router that counts executions and fails calls with `"fail": true`
+#[derive(Default)]
+struct CountingAgentRouter {
+    calls: AtomicUsize,
+}
+
+#[async_trait]
+impl AgentRouter for CountingAgentRouter {
+    fn parse_routing_config(&self, _routing: &RoutingConfig) -> Result<AgentType> {
+        Ok(AgentType::Subprocess { command: "true".to_string(), args: vec![], timeout: None, env: None, optional_args: vec![] })
+    }
+
+    async fn execute_with_agent(&self, tool_call: &ToolCall, _agent: &AgentType) -> Result<AgentResult> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        let failed = tool_call.arguments.get("fail").and_then(|v| v.as_bool()).unwrap_or(false);
+        Ok(AgentResult {
+            success: !failed,
+            data: Some(json!({"call": call})),
+            error: failed.then(|| "failed".to_string()),
+            metadata: None,
+        })
+    }
+}
+
+fn tool(cache: Option<ToolCacheConfig>) -> ToolDefinition {
+    let tool = Tool::new("weather".to_string(), "Weather".to_string(), json!({"type": "object"})).unwrap();
+    let mut definition = ToolDefinition::new(tool, RoutingConfig::new("subprocess".to_string(), json!({"command": "true"}))).unwrap();
+    definition.cache = cache;
+    definition
+}
+
+fn call(arguments: serde_json::Value) -> ToolCall {
+    ToolCall { name: "weather".to_string(), arguments }
+}
+
+fn cache_status(result: &AgentResult) -> Option<&str> {
+    result.metadata.as_ref()?.get("cache")?.as_str()
+}
+
+#[tokio::test]
+async fn test_cached_results_are_reused() {
+    let agent = Arc::new(CountingAgentRouter::default());
+    let router = Router::with_agent_router(agent.clone());
+    let tool = tool(Some(ToolCacheConfig { ttl_seconds: 60, max_entries: 10 }));
+
+    let first = router.route(&call(json!({"city": "Oslo", "units": "metric"})), &tool).await.unwrap();
+    assert_eq!(cache_status(&first), Some("miss"));
+
+    let second = router.route(&call(json!({"units": "metric", "city": "Oslo"})), &tool).await.unwrap();
+    assert_eq!(cache_status(&second), Some("hit"));
+    assert_eq!(second.data, Some(json!({"call": 1})));
+
+    let other = router.route(&call(json!({"city": "Lima"})), &tool).await.unwrap();
+    assert_eq!(other.data, Some(json!({"call": 2})));
+    assert_eq!(agent.calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_bypass_refreshes_entry() {
+    let agent = Arc::new(CountingAgentRouter::default());
+    let router = Router::with_agent_router(agent.clone());
+    let tool = tool(Some(ToolCacheConfig { ttl_seconds: 60, max_entries: 10 }));
+
+    router.route(&call(json!({})), &tool).await.unwrap();
+    let bypassed = cache::bypass(router.route(&call(json!({})), &tool)).await.unwrap();
+    assert_eq!(cache_status(&bypassed), Some("bypass"));
+    assert_eq!(bypassed.data, Some(json!({"call": 2})));
+
+    let cached = router.route(&call(json!({})), &tool).await.unwrap();
+    assert_eq!(cached.data, Some(json!({"call": 2})));
+    assert_eq!(agent.calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_failures_and_uncached_tools_are_not_cached() {
+    let agent = Arc::new(CountingAgentRouter::default());
+    let router = Router::with_agent_router(agent.clone());
+    let cached_tool = tool(Some(ToolCacheConfig { ttl_seconds: 60, max_entries: 10 }));
+
+    router.route(&call(json!({"fail": true})), &cached_tool).await.unwrap();
+    let retried = router.route(&call(json!({"fail": true})), &cached_tool).await.unwrap();
+    assert_eq!(cache_status(&retried), Some("miss"));
+
+    let plain_tool = tool(None);
+    router.route(&call(json!({})), &plain_tool).await.unwrap();
+    let result = router.route(&call(json!({})), &plain_tool).await.unwrap();
+    assert_eq!(cache_status(&result), None);
+    assert_eq!(agent.calls.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn test_delegated_results_are_cached_per_user() {
+    let agent = Arc::new(CountingAgentRouter::default());
+    let router = Router::with_agent_router(agent.clone());
+    let mut delegated = tool(Some(ToolCacheConfig { ttl_seconds: 60, max_entries: 10 }));
+    delegated.routing = RoutingConfig::new(
+        "http".to_string(),
+        json!({"url": "https://api.example.com/weather", "token_exchange": {"token_url": "https://idp.example.com/token"}}),
+    );
+    let (router, tool) = (&router, &delegated);
+    let as_user = move |user: &str, token: &str| {
+        let (user, token) = (Some(user.to_string()), Some(token.to_string()));
+        async move { on_behalf_of(token, cost::as_user(user, router.route(&call(json!({"city": "Oslo"})), tool))).await.unwrap() }
+    };
+
+    let alice = as_user("alice", "token-a").await;
+    assert_eq!(cache_status(&alice), Some("miss"));
+    // Bob's call is made with his own credentials, not served Alice's result
+    let bob = as_user("bob", "token-b").await;
+    assert_eq!(cache_status(&bob), Some("miss"));
+    assert_eq!(bob.data, Some(json!({"call": 2})));
+
+    let alice_again = as_user("alice", "token-a").await;
+    assert_eq!(cache_status(&alice_again), Some("hit"));
+    assert_eq!(alice_again.data, Some(json!({"call": 1})));
+    assert_eq!(agent.calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_cache_config_from_yaml() {
+    let definition: ToolDefinition = serde_yaml::from_str(r#"
+name: weather
+description: Weather
+inputSchema: { type: object }
+routing: { type: subprocess, config: { command: "true" } }
+cache: { ttl_seconds: 300 }
+"#).unwrap();
+    assert_eq!(definition.cache, Some(ToolCacheConfig { ttl_seconds: 300, max_entries: 1000 }));
+
+    let mut invalid = definition.clone();
+    invalid.cache = Some(ToolCacheConfig { ttl_seconds: 0, max_entries: 10 });
+    assert!(invalid.validate().is_err());
+}
@@ -0,0 +1,85 @@
+//! Tests for request/response transforms in routing configs
+
+use async_trait::async_trait;
+use magictunnel::error::Result;
+use magictunnel::mcp::{Tool, ToolCall};
+use magictunnel::registry::{RoutingConfig, ToolDefinition};
+use magictunnel::routing::types::{AgentResult, AgentType};
+use magictunnel::routing::{AgentRouter, Router};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Agent router that echoes the arguments and routing config it was given
+struct EchoAgentRouter;
+
+#[async_trait]
+impl AgentRouter for EchoAgentRouter {
+    fn parse_routing_config(&self, routing: &RoutingConfig) -> Result<AgentType> {
+        Ok(AgentType::Subprocess {
+            command: routing.config.to_string(),
+            args: vec![],
+            optional_args: vec![],
+            timeout: None,
+            env: None,
+        })
+    }
+
+    async fn execute_with_agent(&self, tool_call: &ToolCall, agent: &AgentType) -> Result<AgentResult> {
+        let AgentType::Subprocess { command, .. } = agent else {
+            unreachable!();
+        };
+        let config: Value = serde_json::from_str(command).unwrap();
+        Ok(AgentResult {
+            success: true,
+            data: Some(json!({"body": {"received": tool_call.arguments, "config": config}})),
+            error: None,
+            metadata: None,
+        })
+    }
+}
+
+fn tool(config: Value) -> ToolDefinition {
+    let tool = Tool::new("search".to_string(), "Search".to_string(), json!({"type": "object"})).unwrap();
+    ToolDefinition::new(tool, RoutingConfig::new("http".to_string(), config)).unwrap()
+}
+
+fn call(arguments: Value) -> ToolCall {
+    ToolCall { name: "search".to_string(), arguments }
+}
+
+#[tokio::test]
+async fn test_request_and_response_transforms() {
+    let router = Router::with_agent_router(Arc::new(EchoAgentRouter));
+    let tool = tool(json!({
+        "method": "POST",
+        "url": "http://search",
+        "transform": {
+            "request": "{q: query, size: limit || `10`}",
+            "response": "{sent: body.received, url: body.config.url, has_transform: body.config.transform != null}"
+        }
+    }));
+
+    let result = router.route(&call(json!({"query": "rust"})), &tool).await.unwrap();
+    assert_eq!(result.data, Some(json!({
+        "sent": {"q": "rust", "size": 10},
+        "url": "http://search",
+        "has_transform": false
+    })));
+}
+
+#[tokio::test]
+async fn test_failed_request_transform() {
+    let router = Router::with_agent_router(Arc::new(EchoAgentRouter));
+    let tool = tool(json!({"method": "POST", "url": "http://search", "transform": {"request": "query"}}));
+    assert!(router.route(&call(json!({"query": "rust"})), &tool).await.is_err());
+}
+
+#[test]
+fn test_invalid_expression_rejected() {
+    let routing = RoutingConfig::new("http".to_string(), json!({
+        "method": "POST",
+        "url": "http://search",
+        "transform": {"response": "body.[unclosed"}
+    }));
+    assert!(routing.validate().is_err());
+}
@@ -0,0 +1,136 @@
+use magictunnel::discovery::{QdrantConfig, QdrantStore, ToolMetadata, VectorFilter, VectorPoint, VectorStore};
+use serde_json::json;
+use wiremock::matchers::{body_partial_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn store(server: &MockServer) -> QdrantStore {
+    QdrantStore::new(QdrantConfig {
+        url: server.uri(),
+        collection: "tools".to_string(),
+        api_key: Some("qdrant-key".to_string()),
+        api_key_env: None,
+        timeout: 5,
+    })
+    .unwrap()
+}
+
+fn metadata(name: &str, tags: &[&str], server: Option<&str>) -> ToolMetadata {
+    ToolMetadata {
+        name: name.to_string(),
+        description: format!("{} tool", name),
+        enabled: true,
+        hidden: false,
+        content_hash: "hash".to_string(),
+        last_updated: 0,
+        embedding_dims: 3,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        server: server.map(str::to_string),
+    }
+}
+
+async fn existing_collection(server: &MockServer, size: usize) {
+    Mock::given(method("GET"))
+        .and(path("/collections/tools"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"config": {"params": {"vectors": {"size": size, "distance": "Cosine"}}}}
+        })))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_upsert_creates_collection() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/collections/tools"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/collections/tools"))
+        .and(header("api-key", "qdrant-key"))
+        .and(body_partial_json(json!({"vectors": {"size": 3, "distance": "Cosine"}})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"result": true})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/collections/tools/index"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"result": {}})))
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/collections/tools/points"))
+        .and(body_partial_json(json!({"points": [{"vector": [0.1, 0.2, 0.3], "payload": {"name": "read_file", "tags": ["files"]}}]})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"result": {"status": "completed"}})))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let store = store(&server);
+    let point = VectorPoint { embedding: vec![0.1, 0.2, 0.3], metadata: metadata("read_file", &["files"], None) };
+    store.upsert(std::slice::from_ref(&point)).await.unwrap();
+    // The collection is only created once
+    store.upsert(&[point]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_collection_dimension_mismatch() {
+    let server = MockServer::start().await;
+    existing_collection(&server, 1536).await;
+
+    let point = VectorPoint { embedding: vec![0.1, 0.2, 0.3], metadata: metadata("read_file", &[], None) };
+    let error = store(&server).upsert(&[point]).await.unwrap_err();
+    assert!(error.to_string().contains("1536"));
+}
+
+#[tokio::test]
+async fn test_filtered_search() {
+    let server = MockServer::start().await;
+    existing_collection(&server, 3).await;
+    Mock::given(method("POST"))
+        .and(path("/collections/tools/points/search"))
+        .and(body_partial_json(json!({
+            "limit": 5,
+            "filter": {"must": [
+                {"key": "tags", "match": {"any": ["files"]}},
+                {"key": "server", "match": {"value": "filesystem"}}
+            ]}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": [
+                {"id": "a", "score": 0.91, "payload": metadata("read_file", &["files"], Some("filesystem"))},
+                {"id": "b", "score": 0.72, "payload": metadata("write_file", &["files"], Some("filesystem"))}
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    let filter = VectorFilter { tags: vec!["files".to_string()], server: Some("filesystem".to_string()) };
+    let matches = store(&server).search(&[0.1, 0.2, 0.3], 5, 0.5, &filter).await.unwrap();
+    let names: Vec<_> = matches.iter().map(|(metadata, score)| (metadata.name.as_str(), *score)).collect();
+    assert_eq!(names, vec![("read_file", 0.91), ("write_file", 0.72)]);
+}
+
+#[tokio::test]
+async fn test_get_shared_embeddings() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/collections/tools/points"))
+        .and(body_partial_json(json!({"with_vector": true})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": [{"id": "a", "payload": metadata("read_file", &[], None), "vector": [0.5, 0.5, 0.0]}]
+        })))
+        .mount(&server)
+        .await;
+
+    let points = store(&server).get(&["read_file".to_string(), "missing".to_string()]).await.unwrap();
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].metadata.name, "read_file");
+    assert_eq!(points[0].embedding, vec![0.5, 0.5, 0.0]);
+
+    // Nothing is stored before the collection exists
+    let empty = MockServer::start().await;
+    assert!(store(&empty).get(&["read_file".to_string()]).await.unwrap().is_empty());
+}
@@ -15,6 +15,8 @@ async fn test_tool_definition_hidden_field() {
     let routing = RoutingConfig {
         r#type: "subprocess".to_string(),
         config: json!({"command": "echo", "args": ["test"]}),
+        fallback: Vec::new(),
+        canary: None,
     };
 
     // Test default hidden value (false)
@@ -56,6 +58,8 @@ async fn test_capability_file_visibility_methods() {
     let routing = RoutingConfig {
         r#type: "subprocess".to_string(),
         config: json!({"command": "echo", "args": ["test"]}),
+        fallback: Vec::new(),
+        canary: None,
     };
 
     let visible_tool = ToolDefinition::new_with_hidden(
@@ -77,6 +81,7 @@ async fn test_capability_file_visibility_methods() {
     ).unwrap();
 
     let mut capability_file = CapabilityFile {
+        schema_version: None,
         metadata: None,
         tools: vec![visible_tool, hidden_tool],
     };
@@ -167,6 +172,12 @@ tools:
         paths: vec![temp_path.to_string()],
         hot_reload: false,
         validation: ValidationConfig::default(),
+        remote: Default::default(),
+        sqlite: Default::default(),
+        versioning: Default::default(),
+        toolsets: Default::default(),
+        env_templating: Default::default(),
+        tool_state: Default::default(),
     };
 
     let config = Config {
@@ -223,6 +234,8 @@ async fn test_serde_hidden_field() {
     let routing = RoutingConfig {
         r#type: "subprocess".to_string(),
         config: json!({"command": "echo", "args": ["test"]}),
+        fallback: Vec::new(),
+        canary: None,
     };
 
     let tool = ToolDefinition::new_with_hidden(
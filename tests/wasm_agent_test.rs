@@ -0,0 +1,99 @@
+#![cfg(feature = "wasm")]
+
+use magictunnel::mcp::types::ToolCall;
+use magictunnel::registry::RoutingConfig;
+use magictunnel::routing::agent_router::{AgentRouter, DefaultAgentRouter};
+use magictunnel::routing::types::AgentType;
+use serde_json::json;
+
+/// Echoes up to 1 KiB of stdin to stdout
+const ECHO_WAT: &str = r#"
+    (module
+      (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+      (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+      (memory (export "memory") 1)
+      (func (export "_start")
+        (i32.store (i32.const 0) (i32.const 64))
+        (i32.store (i32.const 4) (i32.const 1024))
+        (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8)))
+        (i32.store (i32.const 4) (i32.load (i32.const 8)))
+        (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+"#;
+
+/// Module files may be WebAssembly text; they are compiled on load
+fn write_module(dir: &std::path::Path, wat: &str) -> String {
+    let path = dir.join("module.wat");
+    std::fs::write(&path, wat).unwrap();
+    path.display().to_string()
+}
+
+#[tokio::test]
+async fn test_wasm_agent_parsing() {
+    let routing_config = RoutingConfig::new("wasm".to_string(), json!({
+        "module": "./tools/wasm/slugify.wasm",
+        "args": ["--separator", "{{separator}}"],
+        "stdin": "{{text}}",
+        "env": {"LOCALE": "{{locale}}"},
+        "max_memory_mb": 16,
+        "timeout": 5,
+        "max_output_bytes": 4096
+    }));
+
+    let router = DefaultAgentRouter::new();
+    match router.parse_routing_config(&routing_config).unwrap() {
+        AgentType::Wasm { timeout, max_output_bytes, config } => {
+            assert_eq!(timeout, Some(5));
+            assert_eq!(max_output_bytes, 4096);
+            assert_eq!(config.module, "./tools/wasm/slugify.wasm");
+            assert_eq!(config.args, vec!["--separator", "{{separator}}"]);
+            assert_eq!(config.stdin.as_deref(), Some("{{text}}"));
+            assert_eq!(config.env["LOCALE"], "{{locale}}");
+            assert_eq!(config.max_memory_mb, 16);
+            assert_eq!(config.fuel, 1_000_000_000);
+        }
+        _ => panic!("Expected Wasm agent type"),
+    }
+}
+
+#[tokio::test]
+async fn test_wasm_agent_parsing_errors() {
+    let router = DefaultAgentRouter::new();
+    let result = router.parse_routing_config(&RoutingConfig::new("wasm".to_string(), json!({"args": ["x"]})));
+    assert!(result.unwrap_err().to_string().contains("Invalid WebAssembly config"));
+}
+
+#[tokio::test]
+async fn test_wasm_agent_passes_arguments_on_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    let router = DefaultAgentRouter::new();
+    let module = write_module(dir.path(), ECHO_WAT);
+    let tool_call = ToolCall {
+        name: "echo".to_string(),
+        arguments: json!({"text": "hello wasm"}),
+    };
+
+    let agent = router.parse_routing_config(&RoutingConfig::new("wasm".to_string(), json!({"module": module}))).unwrap();
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    let data = result.data.unwrap();
+    assert_eq!(data["stdout"], r#"{"text":"hello wasm"}"#);
+    assert_eq!(data["exit_code"], 0);
+
+    let agent = router.parse_routing_config(&RoutingConfig::new("wasm".to_string(), json!({"module": module, "stdin": "{{text}}"}))).unwrap();
+    let result = router.execute_with_agent(&tool_call, &agent).await.unwrap();
+    assert_eq!(result.data.unwrap()["stdout"], "hello wasm");
+    assert_eq!(result.metadata.unwrap()["execution_type"], "wasm");
+}
+
+#[tokio::test]
+async fn test_wasm_agent_missing_module() {
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&RoutingConfig::new("wasm".to_string(), json!({"module": "/nonexistent/tool.wasm"}))).unwrap();
+    let tool_call = ToolCall {
+        name: "missing".to_string(),
+        arguments: json!({}),
+    };
+
+    let result = router.execute_with_agent(&tool_call, &agent).await;
+    assert!(result.unwrap_err().to_string().contains("Cannot read WebAssembly module"));
+}
@@ -0,0 +1,124 @@
+use magictunnel::mcp::types::ToolCall;
+use magictunnel::registry::RoutingConfig;
+use magictunnel::routing::agent_router::{AgentRouter, DefaultAgentRouter};
+use magictunnel::routing::types::AgentType;
+use magictunnel::routing::webhook::{self, CallbackError};
+use serde_json::{json, Value};
+use std::time::Duration;
+use wiremock::matchers::{header_exists, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const SECRET: &str = "s3cret";
+
+async fn backend() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/jobs"))
+        .and(header_exists(webhook::SIGNATURE_HEADER))
+        .and(header_exists(webhook::CORRELATION_ID_HEADER))
+        .respond_with(ResponseTemplate::new(202))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn routing(server: &MockServer, timeout: u64) -> RoutingConfig {
+    RoutingConfig::new("webhook".to_string(), json!({
+        "url": format!("{}/jobs", server.uri()),
+        "payload": {"source": "{{video_url}}", "notify": "{{callback_url}}"},
+        "callback_base_url": "https://tunnel.example.com",
+        "secret": SECRET,
+        "timeout": timeout
+    }))
+}
+
+/// Correlation ID and body of the job submitted to `server`, once it arrives
+async fn submitted_job(server: &MockServer) -> (String, Value) {
+    for _ in 0..100 {
+        if let Some(request) = server.received_requests().await.unwrap_or_default().first() {
+            let correlation_id = request.headers.get(webhook::CORRELATION_ID_HEADER).unwrap().to_str().unwrap().to_string();
+            let timestamp = request.headers.get(webhook::TIMESTAMP_HEADER).unwrap().to_str().unwrap();
+            let signature = request.headers.get(webhook::SIGNATURE_HEADER).unwrap().to_str().unwrap();
+            let now = chrono::Utc::now().timestamp();
+            assert!(webhook::verify(SECRET, Some(timestamp), Some(signature), &request.body, now));
+            return (correlation_id, serde_json::from_slice(&request.body).unwrap());
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("no job was submitted");
+}
+
+fn call_back(correlation_id: &str, event: Value) -> Result<(), CallbackError> {
+    let body = serde_json::to_vec(&event).unwrap();
+    let timestamp = chrono::Utc::now().timestamp();
+    let signature = webhook::sign(SECRET, timestamp, &body);
+    webhook::pending_jobs().deliver(correlation_id, Some(&timestamp.to_string()), Some(&signature), &body)
+}
+
+#[tokio::test]
+async fn test_webhook_agent_parsing() {
+    let router = DefaultAgentRouter::new();
+    match router.parse_routing_config(&RoutingConfig::new("webhook".to_string(), json!({
+        "url": "https://jobs.internal/run",
+        "callback_base_url": "https://tunnel.example.com",
+        "secret": SECRET
+    }))).unwrap() {
+        AgentType::Webhook { timeout, config } => {
+            assert_eq!(timeout, None);
+            assert_eq!(config.method, "POST");
+            assert!(config.payload.is_none());
+        }
+        _ => panic!("Expected webhook agent type"),
+    }
+
+    assert!(router.parse_routing_config(&RoutingConfig::new("webhook".to_string(), json!({"url": "https://jobs.internal/run"}))).is_err());
+    let result = router.parse_routing_config(&RoutingConfig::new("webhook".to_string(), json!({
+        "url": "https://jobs.internal/run",
+        "method": "GET",
+        "callback_base_url": "https://tunnel.example.com",
+        "secret": SECRET
+    })));
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_webhook_agent_completes_on_callback() {
+    let server = backend().await;
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&routing(&server, 10)).unwrap();
+    let call = ToolCall::new("transcode".to_string(), json!({"video_url": "https://cdn.example.com/in.mov"}));
+
+    let execution = tokio::spawn(async move { router.execute_with_agent(&call, &agent).await });
+    let (correlation_id, body) = submitted_job(&server).await;
+    assert_eq!(body["source"], "https://cdn.example.com/in.mov");
+    assert_eq!(body["notify"], format!("https://tunnel.example.com/webhooks/callbacks/{}", correlation_id));
+
+    for _ in 0..100 {
+        if webhook::pending_jobs().is_pending(&correlation_id) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    call_back(&correlation_id, json!({"status": "progress", "message": "Encoding: 40%"})).unwrap();
+    call_back(&correlation_id, json!({"status": "completed", "result": {"url": "https://cdn.example.com/out.mp4"}})).unwrap();
+
+    let result = execution.await.unwrap().unwrap();
+    assert!(result.success);
+    assert_eq!(result.data.unwrap()["url"], "https://cdn.example.com/out.mp4");
+    assert_eq!(result.metadata.unwrap()["correlation_id"], correlation_id);
+    assert_eq!(call_back(&correlation_id, json!({"status": "failed", "error": "late"})), Err(CallbackError::UnknownJob));
+}
+
+#[tokio::test]
+async fn test_webhook_agent_times_out_without_callback() {
+    let server = backend().await;
+    let router = DefaultAgentRouter::new();
+    let agent = router.parse_routing_config(&routing(&server, 1)).unwrap();
+    let call = ToolCall::new("transcode".to_string(), json!({"video_url": "https://cdn.example.com/in.mov"}));
+
+    let result = router.execute_with_agent(&call, &agent).await.unwrap();
+    assert!(!result.success);
+    assert!(result.error.unwrap().contains("did not call back"));
+    let correlation_id = result.metadata.unwrap()["correlation_id"].as_str().unwrap().to_string();
+    assert!(!webhook::pending_jobs().is_pending(&correlation_id));
+}